@@ -0,0 +1,456 @@
+//! One-Shield AMSI Provider
+//!
+//! In-process COM server (IAmsiProvider) được AMSI load vào PowerShell / WSH /
+//! VBA / .NET. Mỗi buffer được chuyển tới agent qua named pipe
+//! `\\.\pipe\OneShieldAmsi` để scan; agent trả về verdict 1 byte.
+//!
+//! Protocol (1 request / connection):
+//! - Request:  u32 LE length + JSON `{app_name, content_name, content}`
+//! - Response: 1 byte (0 = clean, 1 = detected)
+//!
+//! Agent không chạy / bận quá `CONNECT_TIMEOUT_MS` / không trả verdict trong
+//! `VERDICT_TIMEOUT_MS` → fail open (`AMSI_RESULT_NOT_DETECTED`), script host
+//! không bao giờ bị treo theo agent.
+//! Pipe server không chạy dưới SYSTEM / admin (pipe bị squat) → không gửi content.
+//! Đăng ký COM + AMSI provider do agent thực hiện (`amsi_provider::register`).
+
+#![cfg(windows)]
+#![allow(non_snake_case)]
+
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use windows::core::{implement, Interface, GUID, HRESULT, IUnknown, PCWSTR, PWSTR, Result};
+use windows::Win32::Foundation::{
+    CloseHandle, LocalFree, BOOL, CLASS_E_CLASSNOTAVAILABLE, ERROR_IO_PENDING, ERROR_PIPE_BUSY, E_POINTER,
+    GENERIC_READ, GENERIC_WRITE, HANDLE, HLOCAL, S_FALSE, S_OK, WAIT_OBJECT_0,
+};
+use windows::Win32::Security::Authorization::{GetSecurityInfo, SE_KERNEL_OBJECT};
+use windows::Win32::Security::{
+    GetTokenInformation, IsWellKnownSid, TokenElevation, TokenUser, WinBuiltinAdministratorsSid,
+    WinLocalSystemSid, OWNER_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR, PSID, TOKEN_ELEVATION,
+    TOKEN_INFORMATION_CLASS, TOKEN_QUERY, TOKEN_USER,
+};
+use windows::Win32::Storage::FileSystem::{
+    CreateFileW, ReadFile, WriteFile, FILE_FLAG_OVERLAPPED, FILE_SHARE_NONE, OPEN_EXISTING,
+    SECURITY_IDENTIFICATION, SECURITY_SQOS_PRESENT,
+};
+use windows::Win32::System::IO::{CancelIoEx, GetOverlappedResult, OVERLAPPED};
+use windows::Win32::System::Pipes::{GetNamedPipeServerProcessId, WaitNamedPipeW};
+use windows::Win32::System::Threading::{
+    CreateEventW, OpenProcess, OpenProcessToken, WaitForSingleObject, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows::Win32::System::Antimalware::{
+    IAmsiProvider, IAmsiProvider_Impl, IAmsiStream, AMSI_ATTRIBUTE, AMSI_ATTRIBUTE_APP_NAME,
+    AMSI_ATTRIBUTE_CONTENT_ADDRESS, AMSI_ATTRIBUTE_CONTENT_NAME, AMSI_ATTRIBUTE_CONTENT_SIZE,
+    AMSI_RESULT, AMSI_RESULT_DETECTED, AMSI_RESULT_NOT_DETECTED,
+};
+use windows::Win32::System::Com::{CoTaskMemAlloc, IClassFactory, IClassFactory_Impl};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Phải khớp với `PROVIDER_CLSID` trong core-service `amsi_provider.rs`
+const PROVIDER_CLSID: GUID = GUID::from_u128(0x5e1b7a44_2f4b_4c8e_9d6a_0b5e3c1f7a21);
+
+const PIPE_NAME: &str = r"\\.\pipe\OneShieldAmsi";
+const DISPLAY_NAME: &str = "One-Shield AMSI Provider";
+
+/// Không gửi buffer quá lớn qua pipe
+const MAX_CONTENT_BYTES: usize = 1024 * 1024;
+
+/// Chờ tối đa khi mọi pipe instance đang bận
+const CONNECT_TIMEOUT_MS: u32 = 500;
+
+/// Deadline cho gửi request + nhận verdict
+const VERDICT_TIMEOUT: Duration = Duration::from_secs(2);
+
+static LIVE_OBJECTS: AtomicUsize = AtomicUsize::new(0);
+
+// ============================================================================
+// PROVIDER
+// ============================================================================
+
+#[implement(IAmsiProvider)]
+struct Provider;
+
+impl Provider {
+    fn new() -> Self {
+        LIVE_OBJECTS.fetch_add(1, Ordering::SeqCst);
+        Self
+    }
+}
+
+impl Drop for Provider {
+    fn drop(&mut self) {
+        LIVE_OBJECTS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl IAmsiProvider_Impl for Provider_Impl {
+    fn Scan(&self, stream: Option<&IAmsiStream>) -> Result<AMSI_RESULT> {
+        let stream = match stream {
+            Some(s) => s,
+            None => return Ok(AMSI_RESULT_NOT_DETECTED),
+        };
+
+        let app_name = read_string(stream, AMSI_ATTRIBUTE_APP_NAME).unwrap_or_default();
+        let content_name = read_string(stream, AMSI_ATTRIBUTE_CONTENT_NAME).unwrap_or_default();
+        let content = match read_content(stream) {
+            Some(c) if !c.is_empty() => c,
+            _ => return Ok(AMSI_RESULT_NOT_DETECTED),
+        };
+
+        match ask_agent(&app_name, &content_name, &content) {
+            Some(true) => Ok(AMSI_RESULT_DETECTED),
+            _ => Ok(AMSI_RESULT_NOT_DETECTED),
+        }
+    }
+
+    fn CloseSession(&self, _session: u64) {}
+
+    fn DisplayName(&self) -> Result<PWSTR> {
+        let wide: Vec<u16> = DISPLAY_NAME.encode_utf16().chain(std::iter::once(0)).collect();
+        unsafe {
+            let ptr = CoTaskMemAlloc(wide.len() * 2) as *mut u16;
+            if ptr.is_null() {
+                return Err(E_POINTER.into());
+            }
+            std::ptr::copy_nonoverlapping(wide.as_ptr(), ptr, wide.len());
+            Ok(PWSTR(ptr))
+        }
+    }
+}
+
+// ============================================================================
+// STREAM HELPERS
+// ============================================================================
+
+fn read_attribute(stream: &IAmsiStream, attribute: AMSI_ATTRIBUTE, buf: &mut [u8]) -> Option<usize> {
+    let mut len = 0u32;
+    unsafe { stream.GetAttribute(attribute, buf, &mut len).ok()? };
+    Some(len as usize)
+}
+
+fn read_u64(stream: &IAmsiStream, attribute: AMSI_ATTRIBUTE) -> Option<u64> {
+    let mut buf = [0u8; 8];
+    read_attribute(stream, attribute, &mut buf)?;
+    Some(u64::from_ne_bytes(buf))
+}
+
+fn read_string(stream: &IAmsiStream, attribute: AMSI_ATTRIBUTE) -> Option<String> {
+    let mut buf = vec![0u8; 1024];
+    let len = read_attribute(stream, attribute, &mut buf)?;
+    Some(decode(&buf[..len.min(buf.len())]))
+}
+
+fn read_content(stream: &IAmsiStream) -> Option<String> {
+    let size = (read_u64(stream, AMSI_ATTRIBUTE_CONTENT_SIZE)? as usize).min(MAX_CONTENT_BYTES);
+    let address = read_u64(stream, AMSI_ATTRIBUTE_CONTENT_ADDRESS).unwrap_or(0);
+
+    let bytes = if address != 0 {
+        unsafe { std::slice::from_raw_parts(address as *const u8, size) }.to_vec()
+    } else {
+        let mut buf = vec![0u8; size];
+        let mut read = 0u32;
+        unsafe { stream.Read(0, &mut buf, &mut read).ok()? };
+        buf.truncate(read as usize);
+        buf
+    };
+
+    Some(decode(&bytes))
+}
+
+/// Script content thường là UTF-16LE; buffer khác decode lossy UTF-8
+fn decode(bytes: &[u8]) -> String {
+    let looks_utf16 = bytes.len() >= 2 && bytes.len() % 2 == 0
+        && bytes.iter().skip(1).step_by(2).filter(|b| **b == 0).count() * 2 >= bytes.len() / 2;
+
+    if looks_utf16 {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        String::from_utf16_lossy(&units).trim_end_matches('\0').to_string()
+    } else {
+        String::from_utf8_lossy(bytes).trim_end_matches('\0').to_string()
+    }
+}
+
+// ============================================================================
+// AGENT PIPE
+// ============================================================================
+
+fn ask_agent(app_name: &str, content_name: &str, content: &str) -> Option<bool> {
+    let request = format!(
+        "{{\"app_name\":{},\"content_name\":{},\"content\":{}}}",
+        json_string(app_name), json_string(content_name), json_string(content),
+    );
+
+    let pipe = Pipe::connect()?;
+    if !pipe.server_is_trusted() {
+        return None;
+    }
+
+    let deadline = Instant::now() + VERDICT_TIMEOUT;
+    let mut message = (request.len() as u32).to_le_bytes().to_vec();
+    message.extend_from_slice(request.as_bytes());
+    pipe.write_all(&message, deadline)?;
+
+    let mut verdict = [0u8; 1];
+    pipe.read_exact(&mut verdict, deadline)?;
+    Some(verdict[0] == 1)
+}
+
+/// Client end của pipe, mở overlapped để mọi I/O có deadline
+struct Pipe {
+    handle: HANDLE,
+    event: HANDLE,
+}
+
+impl Pipe {
+    fn connect() -> Option<Self> {
+        let name: Vec<u16> = PIPE_NAME.encode_utf16().chain(std::iter::once(0)).collect();
+        let name = PCWSTR(name.as_ptr());
+        // SECURITY_IDENTIFICATION: server không impersonate được script host
+        let open = || unsafe {
+            CreateFileW(
+                name,
+                GENERIC_READ.0 | GENERIC_WRITE.0,
+                FILE_SHARE_NONE,
+                None,
+                OPEN_EXISTING,
+                FILE_FLAG_OVERLAPPED | SECURITY_SQOS_PRESENT | SECURITY_IDENTIFICATION,
+                HANDLE::default(),
+            )
+        };
+
+        let handle = match open() {
+            Ok(h) => h,
+            Err(e) if e.code() == ERROR_PIPE_BUSY.to_hresult() => {
+                if !unsafe { WaitNamedPipeW(name, CONNECT_TIMEOUT_MS) }.as_bool() {
+                    return None;
+                }
+                open().ok()?
+            }
+            // Agent không chạy
+            Err(_) => return None,
+        };
+
+        match unsafe { CreateEventW(None, true, false, PCWSTR::null()) } {
+            Ok(event) => Some(Self { handle, event }),
+            Err(_) => {
+                unsafe { let _ = CloseHandle(handle); }
+                None
+            }
+        }
+    }
+
+    /// Server chạy dưới SYSTEM hoặc token admin elevated. Script host của user thường
+    /// không mở được token của service → dựa vào owner của pipe object (lấy từ token
+    /// của process tạo pipe): SYSTEM / Administrators, user thường không tạo được.
+    fn server_is_trusted(&self) -> bool {
+        let mut pid = 0u32;
+        if unsafe { GetNamedPipeServerProcessId(self.handle, &mut pid) }.is_err() || pid == 0 {
+            return false;
+        }
+        match server_token_trusted(pid) {
+            Some(trusted) => trusted,
+            None => pipe_owner_trusted(self.handle),
+        }
+    }
+
+    fn write_all(&self, mut data: &[u8], deadline: Instant) -> Option<()> {
+        while !data.is_empty() {
+            let written = self.io(deadline, |overlapped| unsafe {
+                WriteFile(self.handle, Some(data), None, Some(overlapped))
+            })?;
+            if written == 0 {
+                return None;
+            }
+            data = &data[written..];
+        }
+        Some(())
+    }
+
+    fn read_exact(&self, buf: &mut [u8], deadline: Instant) -> Option<()> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            let read = self.io(deadline, |overlapped| unsafe {
+                ReadFile(self.handle, Some(&mut buf[filled..]), None, Some(overlapped))
+            })?;
+            if read == 0 {
+                return None;
+            }
+            filled += read;
+        }
+        Some(())
+    }
+
+    /// 1 thao tác overlapped; quá deadline → CancelIoEx và chờ cancel xong
+    /// (buffer / OVERLAPPED phải sống tới khi kernel nhả)
+    fn io(&self, deadline: Instant, start: impl FnOnce(*mut OVERLAPPED) -> Result<()>) -> Option<usize> {
+        let mut overlapped = OVERLAPPED { hEvent: self.event, ..Default::default() };
+        match start(&mut overlapped) {
+            Ok(()) => {}
+            Err(e) if e.code() == ERROR_IO_PENDING.to_hresult() => {
+                let remaining = deadline.saturating_duration_since(Instant::now()).as_millis() as u32;
+                if unsafe { WaitForSingleObject(self.event, remaining) } != WAIT_OBJECT_0 {
+                    let mut transferred = 0u32;
+                    unsafe {
+                        let _ = CancelIoEx(self.handle, Some(&overlapped as *const _));
+                        let _ = GetOverlappedResult(self.handle, &overlapped, &mut transferred, true);
+                    }
+                    return None;
+                }
+            }
+            Err(_) => return None,
+        }
+
+        let mut transferred = 0u32;
+        unsafe { GetOverlappedResult(self.handle, &overlapped, &mut transferred, false) }.ok()?;
+        Some(transferred as usize)
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.handle);
+            let _ = CloseHandle(self.event);
+        }
+    }
+}
+
+/// None = không mở được token (thường do script host chạy dưới user thường)
+fn server_token_trusted(pid: u32) -> Option<bool> {
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }.ok()?;
+    let mut token = HANDLE::default();
+    let opened = unsafe { OpenProcessToken(process, TOKEN_QUERY, &mut token) };
+    unsafe { let _ = CloseHandle(process); }
+    opened.ok()?;
+
+    let system = token_info(token, TokenUser).is_some_and(|buf| {
+        let user = unsafe { &*(buf.as_ptr() as *const TOKEN_USER) };
+        unsafe { IsWellKnownSid(user.User.Sid, WinLocalSystemSid) }.as_bool()
+    });
+    let elevated = token_info(token, TokenElevation)
+        .is_some_and(|buf| unsafe { (*(buf.as_ptr() as *const TOKEN_ELEVATION)).TokenIsElevated != 0 });
+    unsafe { let _ = CloseHandle(token); }
+    Some(system || elevated)
+}
+
+fn pipe_owner_trusted(handle: HANDLE) -> bool {
+    let mut owner = PSID::default();
+    let mut descriptor = PSECURITY_DESCRIPTOR::default();
+    let queried = unsafe {
+        GetSecurityInfo(
+            handle,
+            SE_KERNEL_OBJECT,
+            OWNER_SECURITY_INFORMATION,
+            Some(&mut owner),
+            None,
+            None,
+            None,
+            Some(&mut descriptor),
+        )
+    };
+    if queried.ok().is_err() {
+        return false;
+    }
+    let trusted = unsafe {
+        IsWellKnownSid(owner, WinLocalSystemSid).as_bool() || IsWellKnownSid(owner, WinBuiltinAdministratorsSid).as_bool()
+    };
+    unsafe { let _ = LocalFree(HLOCAL(descriptor.0)); }
+    trusted
+}
+
+/// GetTokenInformation: lần 1 lấy size, lần 2 đọc (buffer u64 để đủ alignment)
+fn token_info(token: HANDLE, class: TOKEN_INFORMATION_CLASS) -> Option<Vec<u64>> {
+    let mut len = 0u32;
+    let _ = unsafe { GetTokenInformation(token, class, None, 0, &mut len) };
+    if len == 0 {
+        return None;
+    }
+    let mut buf = vec![0u64; (len as usize).div_ceil(8)];
+    unsafe { GetTokenInformation(token, class, Some(buf.as_mut_ptr() as *mut c_void), len, &mut len) }.ok()?;
+    Some(buf)
+}
+
+/// JSON string literal (không kéo serde vào DLL được load vào mọi script host)
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// ============================================================================
+// CLASS FACTORY + DLL EXPORTS
+// ============================================================================
+
+#[implement(IClassFactory)]
+struct ClassFactory;
+
+impl IClassFactory_Impl for ClassFactory_Impl {
+    fn CreateInstance(&self, outer: Option<&IUnknown>, iid: *const GUID, object: *mut *mut c_void) -> Result<()> {
+        if object.is_null() {
+            return Err(E_POINTER.into());
+        }
+        unsafe { *object = std::ptr::null_mut() };
+        if outer.is_some() {
+            return Err(windows::Win32::Foundation::CLASS_E_NOAGGREGATION.into());
+        }
+        let provider: IAmsiProvider = Provider::new().into();
+        unsafe { provider.query(iid, object).ok() }
+    }
+
+    fn LockServer(&self, _lock: BOOL) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn DllGetClassObject(clsid: *const GUID, iid: *const GUID, object: *mut *mut c_void) -> HRESULT {
+    if clsid.is_null() || object.is_null() {
+        return E_POINTER;
+    }
+    *object = std::ptr::null_mut();
+    if *clsid != PROVIDER_CLSID {
+        return CLASS_E_CLASSNOTAVAILABLE;
+    }
+    let factory: IClassFactory = ClassFactory.into();
+    factory.query(iid, object)
+}
+
+#[no_mangle]
+pub extern "system" fn DllCanUnloadNow() -> HRESULT {
+    if LIVE_OBJECTS.load(Ordering::SeqCst) == 0 { S_OK } else { S_FALSE }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_utf16() {
+        let bytes: Vec<u8> = "IEX".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        assert_eq!(decode(&bytes), "IEX");
+        assert_eq!(decode(b"Write-Host"), "Write-Host");
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a\"b\\c\n"), "\"a\\\"b\\\\c\\n\"");
+    }
+}
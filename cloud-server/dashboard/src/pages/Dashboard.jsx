@@ -12,7 +12,7 @@ import {
 } from 'lucide-react';
 import Header from '../components/Layout/Header';
 import { EmptyAgents, EmptyIncidents } from '../components/EmptyState';
-import { getEndpoints, getIncidents } from '../services/api';
+import { getEndpoints, getIncidents, getRulePackDeployment } from '../services/api';
 import './Dashboard.css';
 
 // Stat Card Component
@@ -41,8 +41,9 @@ function StatCard({ icon: Icon, label, value, change, changeType, color }) {
 }
 
 // Agent Status Card
-function AgentCard({ agent }) {
+function AgentCard({ agent, latestRulePack }) {
     const isOnline = agent.status === 'online';
+    const rulesStale = (agent.rule_pack_version || 0) < latestRulePack;
     const lastSeen = agent.last_heartbeat
         ? new Date(agent.last_heartbeat).toLocaleString()
         : 'Never';
@@ -67,6 +68,13 @@ function AgentCard({ agent }) {
                     <span className="detail-label">Version</span>
                     <span className="detail-value">{agent.agent_version || 'N/A'}</span>
                 </div>
+                <div className="agent-detail">
+                    <span className="detail-label">Rules</span>
+                    <span className={`detail-value ${rulesStale ? 'text-warning' : ''}`}>
+                        {agent.rule_pack_version ? `v${agent.rule_pack_version}` : 'None'}
+                        {rulesStale && ` (latest v${latestRulePack})`}
+                    </span>
+                </div>
                 <div className="agent-detail">
                     <span className="detail-label">Last Seen</span>
                     <span className="detail-value text-sm">{lastSeen}</span>
@@ -80,6 +88,13 @@ function AgentCard({ agent }) {
 function IncidentRow({ incident }) {
     const severityClass = `badge-${incident.severity}`;
     const time = new Date(incident.created_at).toLocaleString();
+    const chain = incident.process_chain || [];
+    const context = [
+        chain.length > 0
+            ? chain.map(p => p.name).join(' ← ') + (chain[0].signature ? ` (${chain[0].signature})` : '')
+            : null,
+        (incident.rule_matches || []).slice(0, 3).join(', ') || null,
+    ].filter(Boolean).join(' · ');
 
     return (
         <tr className="incident-row">
@@ -92,6 +107,9 @@ function IncidentRow({ incident }) {
                 {incident.title.length > 60
                     ? incident.title.substring(0, 60) + '...'
                     : incident.title}
+                {context && (
+                    <div className="text-sm text-secondary">{context}</div>
+                )}
             </td>
             <td className="text-sm text-secondary">{time}</td>
             <td>
@@ -113,6 +131,7 @@ export default function Dashboard() {
     });
     const [agents, setAgents] = useState([]);
     const [incidents, setIncidents] = useState([]);
+    const [latestRulePack, setLatestRulePack] = useState(0);
 
     useEffect(() => {
         fetchData();
@@ -132,6 +151,10 @@ export default function Dashboard() {
             const incidentsList = Array.isArray(incidentsData) ? incidentsData : (incidentsData.incidents || []);
             setIncidents(incidentsList);
 
+            // Latest detection rule pack (for stale-rules indicator)
+            const deployment = await getRulePackDeployment().catch(() => null);
+            setLatestRulePack(deployment?.latest_version || 0);
+
             // Calculate stats
             const online = endpointsList.filter(e => e.status === 'online').length;
             const openIncidents = incidentsList.filter(i => i.status === 'open').length;
@@ -210,7 +233,7 @@ export default function Dashboard() {
                                 ))
                             ) : agents.length > 0 ? (
                                 agents.map(agent => (
-                                    <AgentCard key={agent.id} agent={agent} />
+                                    <AgentCard key={agent.id} agent={agent} latestRulePack={latestRulePack} />
                                 ))
                             ) : (
                                 <EmptyAgents />
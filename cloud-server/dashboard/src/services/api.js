@@ -203,6 +203,66 @@ export async function updatePolicy(id, policy) {
     });
 }
 
+// ============================================
+// Detection Rule Packs
+// ============================================
+
+export async function getRulePacks() {
+    return apiRequest('/api/v1/rule-packs');
+}
+
+export async function getRulePack(id) {
+    return apiRequest(`/api/v1/rule-packs/${id}`);
+}
+
+export async function createRulePack(pack) {
+    return apiRequest('/api/v1/rule-packs', {
+        method: 'POST',
+        body: JSON.stringify(pack),
+    });
+}
+
+export async function getRulePackDeployment() {
+    return apiRequest('/api/v1/rule-packs/deployment');
+}
+
+// ============================================
+// Never-Learn List
+// ============================================
+
+export async function getNeverLearnEntries() {
+    return apiRequest('/api/v1/never-learn');
+}
+
+export async function createNeverLearnEntry(entry) {
+    return apiRequest('/api/v1/never-learn', {
+        method: 'POST',
+        body: JSON.stringify(entry),
+    });
+}
+
+export async function deleteNeverLearnEntry(id) {
+    return apiRequest(`/api/v1/never-learn/${id}`, {
+        method: 'DELETE',
+    });
+}
+
+export async function getNeverLearnDecisions(limit = 100) {
+    return apiRequest(`/api/v1/never-learn/decisions?limit=${limit}`);
+}
+
+// ============================================
+// File Prevalence
+// ============================================
+
+export async function getFilePrevalence(sha256) {
+    return apiRequest(`/api/v1/prevalence/${sha256}`);
+}
+
+export async function getRarestFiles(limit = 50) {
+    return apiRequest(`/api/v1/prevalence/rare?limit=${limit}`);
+}
+
 // ============================================
 // Reports
 // ============================================
@@ -256,6 +316,18 @@ export default {
     createPolicy,
     updatePolicy,
 
+    // Rule Packs
+    getRulePacks,
+    getRulePack,
+    createRulePack,
+    getRulePackDeployment,
+
+    // Never-learn list
+    getNeverLearnEntries,
+    createNeverLearnEntry,
+    deleteNeverLearnEntry,
+    getNeverLearnDecisions,
+
     // Reports
     getExecutiveReport,
     getComplianceReport,
@@ -158,6 +158,258 @@ BEGIN
     END IF;
 END $$;
 
+-- Host isolation state reported by agents
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns
+                   WHERE table_name = 'endpoints' AND column_name = 'is_isolated') THEN
+        ALTER TABLE endpoints ADD COLUMN is_isolated BOOLEAN NOT NULL DEFAULT false;
+    END IF;
+END $$;
+
+-- Actions awaiting approval on agents (reported by heartbeat)
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns
+                   WHERE table_name = 'endpoints' AND column_name = 'pending_actions') THEN
+        ALTER TABLE endpoints ADD COLUMN pending_actions JSONB NOT NULL DEFAULT '[]';
+    END IF;
+END $$;
+
+-- Commands queued for agents (delivered via heartbeat)
+CREATE TABLE IF NOT EXISTS agent_commands (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    endpoint_id UUID NOT NULL REFERENCES endpoints(id) ON DELETE CASCADE,
+    command JSONB NOT NULL,
+    status VARCHAR(20) DEFAULT 'pending',   -- pending, delivered
+    created_by UUID REFERENCES users(id),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ
+);
+
+-- Detection rule pack version running on agents (reported by heartbeat)
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns
+                   WHERE table_name = 'endpoints' AND column_name = 'rule_pack_version') THEN
+        ALTER TABLE endpoints ADD COLUMN rule_pack_version INT NOT NULL DEFAULT 0;
+    END IF;
+END $$;
+
+-- Offline intel bundle version imported on agents (air-gapped hosts, reported by heartbeat)
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns
+                   WHERE table_name = 'endpoints' AND column_name = 'intel_bundle_version') THEN
+        ALTER TABLE endpoints ADD COLUMN intel_bundle_version VARCHAR(64);
+    END IF;
+END $$;
+
+-- mTLS: SHA-256 of the client certificate generated by the agent at enrollment
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns
+                   WHERE table_name = 'endpoints' AND column_name = 'client_cert_sha256') THEN
+        ALTER TABLE endpoints ADD COLUMN client_cert_sha256 VARCHAR(64);
+    END IF;
+END $$;
+
+-- Incident context from agents: process chain (hashes, signature), rule matches, key features
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns
+                   WHERE table_name = 'incidents' AND column_name = 'process_chain') THEN
+        ALTER TABLE incidents ADD COLUMN process_chain JSONB NOT NULL DEFAULT '[]';
+        ALTER TABLE incidents ADD COLUMN rule_matches JSONB NOT NULL DEFAULT '[]';
+        ALTER TABLE incidents ADD COLUMN key_features JSONB NOT NULL DEFAULT '[]';
+    END IF;
+END $$;
+
+-- Analyst notes / disposition reasons, synced with the reporting agent
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns
+                   WHERE table_name = 'incidents' AND column_name = 'notes') THEN
+        ALTER TABLE incidents ADD COLUMN notes JSONB NOT NULL DEFAULT '[]';
+    END IF;
+END $$;
+
+-- Detection rule packs (behavioral + YARA), versioned per organization
+CREATE TABLE IF NOT EXISTS rule_packs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    version INT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    behavioral_rules TEXT NOT NULL DEFAULT '[]',   -- JSON array (hashed as-is)
+    yara_rules TEXT NOT NULL DEFAULT '',
+    sha256 VARCHAR(64) NOT NULL,
+    created_by UUID REFERENCES users(id),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE (org_id, version)
+);
+
+-- Never-learn list: what agent baselines must never absorb (pushed per organization)
+CREATE TABLE IF NOT EXISTS never_learn_entries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    kind VARCHAR(20) NOT NULL,          -- process, hash, endpoint
+    value VARCHAR(512) NOT NULL,
+    reason TEXT,
+    revision BIGINT NOT NULL,           -- org revision of the last change (create / delete)
+    created_by UUID REFERENCES users(id),
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    deleted_at TIMESTAMPTZ
+);
+
+-- Never-learn decisions made locally by agents
+CREATE TABLE IF NOT EXISTS never_learn_decisions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    endpoint_id UUID NOT NULL REFERENCES endpoints(id) ON DELETE CASCADE,
+    kind VARCHAR(50) NOT NULL,
+    value TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    process_name VARCHAR(255),
+    decided_at TIMESTAMPTZ NOT NULL,
+    received_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+-- File prevalence: which endpoints in an org have executed a binary
+CREATE TABLE IF NOT EXISTS file_prevalence (
+    org_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    sha256 VARCHAR(64) NOT NULL,
+    endpoint_id UUID NOT NULL REFERENCES endpoints(id) ON DELETE CASCADE,
+    file_name VARCHAR(255),
+    is_signed BOOLEAN NOT NULL DEFAULT false,
+    first_seen TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (org_id, sha256, endpoint_id)
+);
+
+-- Telemetry upload accounting per endpoint
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns
+                   WHERE table_name = 'endpoints' AND column_name = 'telemetry_sampled_out') THEN
+        ALTER TABLE endpoints ADD COLUMN telemetry_sampled_out BIGINT NOT NULL DEFAULT 0;
+        ALTER TABLE endpoints ADD COLUMN telemetry_last_upload TIMESTAMPTZ;
+    END IF;
+END $$;
+
+-- Sampled security events uploaded by agents (idempotent per endpoint + event id)
+CREATE TABLE IF NOT EXISTS telemetry_events (
+    id BIGSERIAL PRIMARY KEY,
+    org_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    endpoint_id UUID NOT NULL REFERENCES endpoints(id) ON DELETE CASCADE,
+    event_id VARCHAR(64) NOT NULL,
+    event_type VARCHAR(50) NOT NULL,
+    severity VARCHAR(20),
+    process_name VARCHAR(255),
+    description TEXT NOT NULL DEFAULT '',
+    data JSONB NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (endpoint_id, event_id)
+);
+
+-- Agent engine stats snapshots (buffers, open incidents, inference latency, ...)
+CREATE TABLE IF NOT EXISTS engine_stats (
+    id BIGSERIAL PRIMARY KEY,
+    org_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    endpoint_id UUID NOT NULL REFERENCES endpoints(id) ON DELETE CASCADE,
+    stats JSONB NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL,
+    received_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+-- Organization opt-in for central model training (off until an admin enables it)
+CREATE TABLE IF NOT EXISTS dataset_sharing (
+    org_id UUID PRIMARY KEY REFERENCES organizations(id) ON DELETE CASCADE,
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    max_sample_rate DOUBLE PRECISION NOT NULL DEFAULT 0.1,
+    sampled_out BIGINT NOT NULL DEFAULT 0,
+    last_upload_at TIMESTAMPTZ,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+-- Anonymized dataset records (no endpoint id; idempotent per org + content hash)
+CREATE TABLE IF NOT EXISTS training_records (
+    id BIGSERIAL PRIMARY KEY,
+    org_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    record_hash VARCHAR(64) NOT NULL,
+    feature_version SMALLINT NOT NULL,
+    threat VARCHAR(20) NOT NULL,
+    label VARCHAR(20),
+    record JSONB NOT NULL,
+    recorded_hour TIMESTAMPTZ NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (org_id, record_hash)
+);
+
+-- Managed agent settings (thresholds, intervals, feature toggles, exporters)
+-- group_name '' = org-wide defaults; named groups override them per section
+CREATE TABLE IF NOT EXISTS managed_settings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    group_name VARCHAR(100) NOT NULL DEFAULT '',
+    settings JSONB NOT NULL DEFAULT '{}',
+    revision BIGINT NOT NULL,           -- org settings revision of the last change
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE (org_id, group_name)
+);
+
+-- Settings revision per organization (bumped on every settings / group assignment change),
+-- settings group and applied settings revision per endpoint (reported by heartbeat)
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns
+                   WHERE table_name = 'organizations' AND column_name = 'settings_revision') THEN
+        ALTER TABLE organizations ADD COLUMN settings_revision BIGINT NOT NULL DEFAULT 0;
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns
+                   WHERE table_name = 'endpoints' AND column_name = 'settings_group') THEN
+        ALTER TABLE endpoints ADD COLUMN settings_group VARCHAR(100);
+        ALTER TABLE endpoints ADD COLUMN settings_version BIGINT NOT NULL DEFAULT 0;
+    END IF;
+END $$;
+
+-- Golden baseline per organization (promoted from a reference endpoint),
+-- pulled by agents and reconciled with local learning per conflict_policy
+CREATE TABLE IF NOT EXISTS golden_baselines (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE UNIQUE,
+    source_endpoint_id UUID REFERENCES endpoints(id) ON DELETE SET NULL,
+    mean_values JSONB NOT NULL,
+    variance_values JSONB,
+    sample_count BIGINT NOT NULL DEFAULT 0,
+    feature_version INT,
+    layout_hash BIGINT,
+    conflict_policy VARCHAR(20) NOT NULL DEFAULT 'server_wins', -- server_wins | local_wins | merge
+    revision BIGINT NOT NULL,           -- org baseline revision of the last change
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+-- Golden baseline revision per organization (bumped on every golden change),
+-- version vector of each uploaded baseline (agent learning generation + golden revision it is based on)
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns
+                   WHERE table_name = 'organizations' AND column_name = 'baseline_revision') THEN
+        ALTER TABLE organizations ADD COLUMN baseline_revision BIGINT NOT NULL DEFAULT 0;
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns
+                   WHERE table_name = 'baselines' AND column_name = 'agent_version') THEN
+        ALTER TABLE baselines ADD COLUMN agent_version BIGINT NOT NULL DEFAULT 0;
+        ALTER TABLE baselines ADD COLUMN golden_revision BIGINT NOT NULL DEFAULT 0;
+        ALTER TABLE baselines ADD COLUMN feature_version INT;
+        ALTER TABLE baselines ADD COLUMN layout_hash BIGINT;
+    END IF;
+END $$;
+
 -- Indexes for performance
 CREATE INDEX IF NOT EXISTS idx_endpoints_org ON endpoints(org_id);
 CREATE INDEX IF NOT EXISTS idx_endpoints_heartbeat ON endpoints(last_heartbeat);
@@ -174,6 +426,15 @@ CREATE INDEX IF NOT EXISTS idx_heartbeat_endpoint ON heartbeat_history(endpoint_
 CREATE INDEX IF NOT EXISTS idx_tokens_org ON organization_tokens(org_id);
 CREATE INDEX IF NOT EXISTS idx_tokens_token ON organization_tokens(token);
 CREATE INDEX IF NOT EXISTS idx_tokens_active ON organization_tokens(is_active, expires_at);
+CREATE INDEX IF NOT EXISTS idx_agent_commands_pending ON agent_commands(endpoint_id, status);
+CREATE INDEX IF NOT EXISTS idx_endpoints_rule_pack ON endpoints(org_id, rule_pack_version);
+CREATE INDEX IF NOT EXISTS idx_never_learn_org ON never_learn_entries(org_id, revision);
+CREATE INDEX IF NOT EXISTS idx_never_learn_decisions_org ON never_learn_decisions(org_id, decided_at);
+CREATE INDEX IF NOT EXISTS idx_file_prevalence_seen ON file_prevalence(org_id, last_seen);
+CREATE INDEX IF NOT EXISTS idx_telemetry_events_org ON telemetry_events(org_id, occurred_at);
+CREATE INDEX IF NOT EXISTS idx_telemetry_events_endpoint ON telemetry_events(endpoint_id, occurred_at);
+CREATE INDEX IF NOT EXISTS idx_engine_stats_endpoint ON engine_stats(endpoint_id, recorded_at);
+CREATE INDEX IF NOT EXISTS idx_training_records_org ON training_records(org_id, feature_version, id);
 
 -- Insert default organization
 INSERT INTO organizations (name, license_key, max_agents)
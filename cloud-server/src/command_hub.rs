@@ -0,0 +1,96 @@
+//! Command push hub
+//!
+//! Tracks agents connected to the WebSocket command channel and wakes their
+//! connection when a command is queued for them. Queuing happens through
+//! `QueuedCommand::enqueue`, which fires a Postgres `NOTIFY` so a command
+//! created on any server instance reaches the instance holding the socket.
+//!
+//! The `agent_commands` table stays the source of truth: a woken connection
+//! drains pending commands exactly like a heartbeat does, so agents that are
+//! not connected (or whose socket dropped) still receive them on heartbeat.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::models::COMMAND_NOTIFY_CHANNEL;
+
+/// Endpoint → (connection id, wake sender)
+type Connections = Arc<Mutex<HashMap<Uuid, (u64, mpsc::Sender<()>)>>>;
+
+/// Registry of live command channel connections (one per endpoint)
+#[derive(Clone, Default)]
+pub struct CommandHub {
+    connections: Connections,
+    next_id: Arc<AtomicU64>,
+}
+
+/// Live connection handle; wakes arrive on `wake`
+pub struct Subscription {
+    pub id: u64,
+    pub wake: mpsc::Receiver<()>,
+}
+
+impl CommandHub {
+    /// Register a connection for an endpoint, replacing any previous one
+    /// (the old socket sees its wake channel close and shuts down)
+    pub fn subscribe(&self, endpoint_id: Uuid) -> Subscription {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        // Capacity 1: wakes coalesce, the connection drains everything pending anyway
+        let (tx, wake) = mpsc::channel(1);
+        self.connections.lock().unwrap().insert(endpoint_id, (id, tx));
+        Subscription { id, wake }
+    }
+
+    /// Remove a connection unless it was already replaced by a newer one
+    pub fn unsubscribe(&self, endpoint_id: Uuid, id: u64) {
+        let mut connections = self.connections.lock().unwrap();
+        if connections.get(&endpoint_id).is_some_and(|(current, _)| *current == id) {
+            connections.remove(&endpoint_id);
+        }
+    }
+
+    /// Wake the endpoint's connection, if it has one on this instance
+    pub fn notify(&self, endpoint_id: Uuid) {
+        if let Some((_, tx)) = self.connections.lock().unwrap().get(&endpoint_id) {
+            let _ = tx.try_send(());
+        }
+    }
+}
+
+/// Forward command notifications from Postgres to local connections.
+/// Runs for the lifetime of the server, reconnecting on failure.
+pub async fn listen(pool: PgPool, hub: CommandHub) {
+    loop {
+        match PgListener::connect_with(&pool).await {
+            Ok(mut listener) => {
+                if let Err(e) = listener.listen(COMMAND_NOTIFY_CHANNEL).await {
+                    tracing::warn!("Command channel LISTEN failed: {}", e);
+                } else {
+                    tracing::info!("Listening for queued agent commands");
+                    loop {
+                        match listener.recv().await {
+                            Ok(notification) => match notification.payload().parse::<Uuid>() {
+                                Ok(endpoint_id) => hub.notify(endpoint_id),
+                                Err(_) => tracing::warn!("Ignoring malformed command notification: {}", notification.payload()),
+                            },
+                            Err(e) => {
+                                tracing::warn!("Command notification listener error: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Command notification listener connect failed: {}", e),
+        }
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
+
@@ -1,6 +1,10 @@
 //! Agent handlers
 
-use axum::{extract::State, Json};
+use axum::{
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, State},
+    response::Response,
+    Json,
+};
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
 use uuid::Uuid;
@@ -11,9 +15,16 @@ use crate::{AppState, AppError, AppResult};
 use crate::models::{
     Endpoint, RegisterAgentRequest, RegisterAgentResponse,
     HeartbeatRequest, HeartbeatResponse, AgentCommand,
-    Baseline, SyncBaselineRequest, SyncBaselineResponse,
-    Incident, CreateIncident, SyncIncidentsRequest, SyncIncidentsResponse,
-    Policy, OrganizationToken,
+    Baseline, GoldenBaseline, SyncBaselineRequest, SyncBaselineResponse,
+    Incident, CreateIncident, SyncIncidentsRequest, SyncIncidentsResponse, AgentIncidentNote, SyncIncidentNotesRequest,
+    Policy, OrganizationToken, QueuedCommand, CommandChannelMessage, RulePack, SignedRulePack,
+    NeverLearnEntry, NeverLearnList, NeverLearnDecision, ReportNeverLearnDecisions, ReportNeverLearnResponse,
+    FilePrevalence, ReportPrevalenceRequest, ReportPrevalenceResponse, PrevalenceQueryRequest,
+    PrevalenceQueryResponse, MAX_PREVALENCE_BATCH, normalize_hashes,
+    TelemetryEvent, EngineStatsSample, EndpointTelemetrySummary, UploadTelemetryRequest,
+    UploadTelemetryResponse, MAX_TELEMETRY_BATCH, MAX_STATS_BATCH,
+    DatasetSharing, TrainingRecord, UploadDatasetRequest, UploadDatasetResponse, MAX_DATASET_BATCH,
+    ManagedSettings, ManagedSettingsBundle, SYNC_CAPABILITIES, client_cert_fingerprint,
 };
 use crate::middleware::auth::AgentContext;
 
@@ -35,6 +46,9 @@ pub struct EnrollAgentRequest {
     /// Agent version
     #[serde(default)]
     pub agent_version: Option<String>,
+    /// Self-signed mTLS client certificate (PEM); the private key never leaves the agent
+    #[serde(default)]
+    pub client_cert_pem: Option<String>,
 }
 
 fn default_os_type() -> String {
@@ -48,6 +62,8 @@ pub struct EnrollAgentResponse {
     pub agent_token: String,
     pub org_id: Uuid,
     pub org_name: String,
+    /// CA / server certificate the agent pins for all later connections
+    pub server_ca_pem: Option<String>,
 }
 
 /// Register new agent
@@ -86,7 +102,7 @@ pub async fn heartbeat(
     Json(req): Json<HeartbeatRequest>,
 ) -> AppResult<Json<HeartbeatResponse>> {
     // Update heartbeat
-    Endpoint::update_heartbeat(&state.pool, agent.endpoint_id, agent.ip_address.clone(), &req.agent_version).await?;
+    Endpoint::update_heartbeat(&state.pool, agent.endpoint_id, agent.ip_address.clone(), &req).await?;
 
     // Record metrics
     record_heartbeat_metrics(&state.pool, agent.endpoint_id, &req).await?;
@@ -98,23 +114,126 @@ pub async fn heartbeat(
         None => (0, false),
     };
 
-    // Collect pending commands (for now, empty)
-    let commands: Vec<AgentCommand> = vec![];
+    // Check for rule pack updates
+    let rule_pack_version = RulePack::latest_version(&state.pool, agent.org_id).await?;
+
+    // Check for never-learn list changes
+    let never_learn_revision = NeverLearnEntry::revision(&state.pool, agent.org_id).await?;
+
+    // Check for managed settings changes
+    let settings_version = ManagedSettings::revision(&state.pool, agent.org_id).await?;
+
+    // Check for golden baseline changes
+    let baseline_revision = GoldenBaseline::revision(&state.pool, agent.org_id).await?;
+
+    // Deliver commands queued from the console
+    let commands: Vec<AgentCommand> = QueuedCommand::take_pending(&state.pool, agent.endpoint_id).await?
+        .into_iter()
+        .map(|(_, command)| command)
+        .collect();
+    if !commands.is_empty() {
+        tracing::info!("Delivering {} command(s) to agent {}", commands.len(), agent.endpoint_id);
+    }
 
     Ok(Json(HeartbeatResponse {
         server_time: Utc::now().timestamp(),
         policy_version,
         has_policy_update: has_update,
+        rule_pack_version,
+        has_rule_pack_update: rule_pack_version > req.rule_pack_version,
+        never_learn_revision,
+        has_never_learn_update: never_learn_revision != req.never_learn_revision,
+        settings_version,
+        has_settings_update: settings_version != req.settings_version,
+        baseline_revision,
+        has_baseline_update: baseline_revision != req.baseline_revision,
+        sync_capabilities: SYNC_CAPABILITIES.iter().map(|c| c.to_string()).collect(),
         commands,
     }))
 }
 
+/// Ping interval on the command channel (agents drop the socket after 90s of silence)
+const COMMAND_CHANNEL_PING_SECS: u64 = 30;
+
+/// WebSocket command channel: pushes queued commands as soon as they are created
+pub async fn command_channel(
+    State(state): State<AppState>,
+    agent: AgentContext,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(move |socket| push_commands(state, agent.endpoint_id, socket))
+}
+
+async fn push_commands(state: AppState, endpoint_id: Uuid, mut socket: WebSocket) {
+    let mut subscription = state.commands.subscribe(endpoint_id);
+    let mut ping = tokio::time::interval(std::time::Duration::from_secs(COMMAND_CHANNEL_PING_SECS));
+    tracing::debug!("Command channel opened for agent {}", endpoint_id);
+
+    // Drain on connect (commands queued while offline), on wake, and on each ping
+    // as a safety net for notifications missed while the listener reconnected
+    let mut drain = true;
+    loop {
+        if drain {
+            match QueuedCommand::take_pending(&state.pool, endpoint_id).await {
+                Ok(pending) if !pending.is_empty() => {
+                    let (ids, commands): (Vec<Uuid>, Vec<AgentCommand>) = pending.into_iter().unzip();
+                    tracing::info!("Pushing {} command(s) to agent {}", commands.len(), endpoint_id);
+                    let message = CommandChannelMessage::Commands { commands };
+                    let sent = match serde_json::to_string(&message) {
+                        Ok(text) => socket.send(Message::Text(text)).await.is_ok(),
+                        Err(_) => false,
+                    };
+                    if !sent {
+                        // Not delivered - back to pending for the next heartbeat / connection
+                        if let Err(e) = QueuedCommand::requeue(&state.pool, &ids).await {
+                            tracing::warn!("Failed to requeue commands for agent {}: {}", endpoint_id, e);
+                        }
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to load commands for agent {}: {}", endpoint_id, e),
+            }
+        }
+
+        drain = tokio::select! {
+            woke = subscription.wake.recv() => match woke {
+                Some(()) => true,
+                // Replaced by a newer connection from the same agent
+                None => break,
+            },
+            _ = ping.tick() => {
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+                true
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => false,
+            },
+        };
+    }
+
+    state.commands.unsubscribe(endpoint_id, subscription.id);
+    tracing::debug!("Command channel closed for agent {}", endpoint_id);
+}
+
 /// Sync baseline from agent
 pub async fn sync_baseline(
     State(state): State<AppState>,
     agent: AgentContext,
     Json(req): Json<SyncBaselineRequest>,
 ) -> AppResult<Json<SyncBaselineResponse>> {
+    if req.baseline_hash.len() > 64 {
+        return Err(AppError::ValidationError("Baseline hash is too long".to_string()));
+    }
+    if req.variance_values.as_ref().is_some_and(|v| v.len() != req.mean_values.len()) {
+        return Err(AppError::ValidationError("Mean and variance lengths differ".to_string()));
+    }
+    let baseline_hash = req.baseline_hash.clone();
+    let based_on = req.version_vector.server;
+
     // Update baseline
     let baseline = Baseline::upsert(&state.pool, agent.endpoint_id, req).await?;
 
@@ -123,20 +242,37 @@ pub async fn sync_baseline(
         "UPDATE endpoints SET baseline_hash = $2, baseline_version = $3, updated_at = NOW() WHERE id = $1"
     )
     .bind(agent.endpoint_id)
-    .bind(baseline.mean_values.to_string())
+    .bind(&baseline_hash)
     .bind(baseline.version)
     .execute(&state.pool)
     .await?;
 
-    tracing::debug!("Baseline synced for agent {}", agent.endpoint_id);
+    let golden_revision = GoldenBaseline::revision(&state.pool, agent.org_id).await?;
+
+    tracing::debug!(
+        "Baseline synced for agent {} (generation {}, golden r{})",
+        agent.endpoint_id, baseline.agent_version, based_on
+    );
 
     Ok(Json(SyncBaselineResponse {
         accepted: true,
         server_version: baseline.version,
         server_time: Utc::now().timestamp(),
+        golden_revision,
+        has_golden_update: golden_revision != based_on,
     }))
 }
 
+/// Golden baseline of the agent's organization (404 = none promoted)
+pub async fn get_golden_baseline(
+    State(state): State<AppState>,
+    agent: AgentContext,
+) -> AppResult<Json<GoldenBaseline>> {
+    let golden = GoldenBaseline::get(&state.pool, agent.org_id).await?
+        .ok_or_else(|| AppError::NotFound("No golden baseline".to_string()))?;
+    Ok(Json(golden))
+}
+
 /// Sync incidents from agent
 pub async fn sync_incidents(
     State(state): State<AppState>,
@@ -145,7 +281,11 @@ pub async fn sync_incidents(
 ) -> AppResult<Json<SyncIncidentsResponse>> {
     let mut synced = 0;
 
-    for incident_data in req.incidents {
+    for mut incident_data in req.incidents {
+        if let Err(e) = incident_data.resolve_process_refs(&req.processes) {
+            tracing::warn!("Rejected incident {}: {}", incident_data.id, e);
+            continue;
+        }
         match Incident::create(&state.pool, agent.endpoint_id, incident_data).await {
             Ok(_) => synced += 1,
             Err(e) => tracing::warn!("Failed to sync incident: {}", e),
@@ -160,6 +300,34 @@ pub async fn sync_incidents(
     }))
 }
 
+/// Sync analyst notes written on the agent (duplicates by note id are ignored)
+pub async fn sync_incident_notes(
+    State(state): State<AppState>,
+    agent: AgentContext,
+    Json(req): Json<SyncIncidentNotesRequest>,
+) -> AppResult<Json<SyncIncidentsResponse>> {
+    let mut synced = 0;
+
+    for AgentIncidentNote { incident_id, mut note } in req.notes {
+        if let Err(e) = note.validate() {
+            tracing::warn!("Rejected note {} for incident {}: {}", note.id, incident_id, e);
+            continue;
+        }
+        match Incident::add_note(&state.pool, incident_id, Some(agent.endpoint_id), &note).await {
+            Ok(Some(_)) => synced += 1,
+            Ok(None) => tracing::warn!("Note {} references unknown incident {}", note.id, incident_id),
+            Err(e) => tracing::warn!("Failed to sync incident note: {}", e),
+        }
+    }
+
+    tracing::info!("Synced {} incident notes from agent {}", synced, agent.endpoint_id);
+
+    Ok(Json(SyncIncidentsResponse {
+        synced_count: synced,
+        server_time: Utc::now().timestamp(),
+    }))
+}
+
 /// Get active policy for agent
 pub async fn get_policy(
     State(state): State<AppState>,
@@ -169,6 +337,171 @@ pub async fn get_policy(
     Ok(Json(policy))
 }
 
+/// Get latest signed rule pack for agent (None = no pack)
+pub async fn get_rule_pack(
+    State(state): State<AppState>,
+    agent: AgentContext,
+) -> AppResult<Json<Option<SignedRulePack>>> {
+    let Some(pack) = RulePack::get_latest(&state.pool, agent.org_id).await? else {
+        return Ok(Json(None));
+    };
+
+    tracing::debug!("Delivering rule pack v{} to agent {}", pack.version, agent.endpoint_id);
+    Ok(Json(Some(pack.sign(&state.config.content_signing_key))))
+}
+
+/// Get active never-learn entries for agent
+pub async fn get_never_learn(
+    State(state): State<AppState>,
+    agent: AgentContext,
+) -> AppResult<Json<NeverLearnList>> {
+    let list = NeverLearnEntry::list_for_agent(&state.pool, agent.org_id).await?;
+    Ok(Json(list))
+}
+
+/// Managed settings for this agent (org defaults + its group)
+pub async fn get_settings(
+    State(state): State<AppState>,
+    agent: AgentContext,
+) -> AppResult<Json<ManagedSettingsBundle>> {
+    let bundle = ManagedSettings::effective_for_endpoint(&state.pool, agent.org_id, agent.endpoint_id).await?;
+    Ok(Json(bundle))
+}
+
+/// Never-learn decisions made locally by the agent
+pub async fn report_never_learn(
+    State(state): State<AppState>,
+    agent: AgentContext,
+    Json(req): Json<ReportNeverLearnDecisions>,
+) -> AppResult<Json<ReportNeverLearnResponse>> {
+    let mut accepted = 0;
+
+    for decision in &req.decisions {
+        match NeverLearnDecision::create(&state.pool, agent.org_id, agent.endpoint_id, decision).await {
+            Ok(_) => accepted += 1,
+            Err(e) => tracing::warn!("Failed to store never-learn decision: {}", e),
+        }
+    }
+
+    tracing::debug!("Received {} never-learn decision(s) from agent {}", accepted, agent.endpoint_id);
+
+    Ok(Json(ReportNeverLearnResponse {
+        accepted,
+        server_time: Utc::now().timestamp(),
+    }))
+}
+
+/// Hashes executed on the agent (fleet prevalence)
+pub async fn report_prevalence(
+    State(state): State<AppState>,
+    agent: AgentContext,
+    Json(req): Json<ReportPrevalenceRequest>,
+) -> AppResult<Json<ReportPrevalenceResponse>> {
+    if req.sightings.len() > MAX_PREVALENCE_BATCH {
+        return Err(AppError::ValidationError(format!(
+            "Too many sightings ({} > {})", req.sightings.len(), MAX_PREVALENCE_BATCH
+        )));
+    }
+
+    let mut accepted = 0;
+    for sighting in &req.sightings {
+        if normalize_hashes(std::slice::from_ref(&sighting.sha256)).is_empty() {
+            continue;
+        }
+        match FilePrevalence::record(&state.pool, agent.org_id, agent.endpoint_id, sighting).await {
+            Ok(()) => accepted += 1,
+            Err(e) => tracing::warn!("Failed to store prevalence sighting: {}", e),
+        }
+    }
+
+    tracing::debug!("Received {} prevalence sighting(s) from agent {}", accepted, agent.endpoint_id);
+
+    Ok(Json(ReportPrevalenceResponse {
+        accepted,
+        server_time: Utc::now().timestamp(),
+    }))
+}
+
+/// How many endpoints in the agent's organization have run these hashes
+pub async fn query_prevalence(
+    State(state): State<AppState>,
+    agent: AgentContext,
+    Json(req): Json<PrevalenceQueryRequest>,
+) -> AppResult<Json<PrevalenceQueryResponse>> {
+    let hashes = normalize_hashes(&req.hashes);
+    if hashes.len() > MAX_PREVALENCE_BATCH {
+        return Err(AppError::ValidationError(format!(
+            "Too many hashes ({} > {})", hashes.len(), MAX_PREVALENCE_BATCH
+        )));
+    }
+
+    let results = FilePrevalence::lookup(&state.pool, agent.org_id, &hashes).await?;
+    let total_endpoints = FilePrevalence::total_endpoints(&state.pool, agent.org_id).await?;
+
+    Ok(Json(PrevalenceQueryResponse { total_endpoints, results }))
+}
+
+/// Sampled security events + engine stats (body may be zstd/gzip compressed)
+pub async fn upload_telemetry(
+    State(state): State<AppState>,
+    agent: AgentContext,
+    Json(req): Json<UploadTelemetryRequest>,
+) -> AppResult<Json<UploadTelemetryResponse>> {
+    if req.events.len() > MAX_TELEMETRY_BATCH || req.stats.len() > MAX_STATS_BATCH {
+        return Err(AppError::ValidationError(format!(
+            "Telemetry batch too large ({} events, {} stats; max {} / {})",
+            req.events.len(), req.stats.len(), MAX_TELEMETRY_BATCH, MAX_STATS_BATCH
+        )));
+    }
+
+    let accepted_events = TelemetryEvent::insert_batch(&state.pool, agent.org_id, agent.endpoint_id, &req.events).await?;
+    let accepted_stats = EngineStatsSample::insert_batch(&state.pool, agent.org_id, agent.endpoint_id, &req.stats).await?;
+    EndpointTelemetrySummary::record_upload(&state.pool, agent.endpoint_id, req.sampled_out).await?;
+
+    tracing::debug!(
+        "Received telemetry from agent {}: {} event(s), {} stats sample(s), {} sampled out",
+        agent.endpoint_id, accepted_events, accepted_stats, req.sampled_out
+    );
+
+    Ok(Json(UploadTelemetryResponse {
+        accepted_events: accepted_events as usize,
+        accepted_stats: accepted_stats as usize,
+        server_time: Utc::now().timestamp(),
+    }))
+}
+
+/// Anonymized dataset records for central model training (org must opt in)
+pub async fn upload_dataset(
+    State(state): State<AppState>,
+    agent: AgentContext,
+    Json(req): Json<UploadDatasetRequest>,
+) -> AppResult<Json<UploadDatasetResponse>> {
+    let sharing = DatasetSharing::get(&state.pool, agent.org_id).await?;
+    if !sharing.enabled {
+        return Err(AppError::Forbidden);
+    }
+    if req.records.len() > MAX_DATASET_BATCH {
+        return Err(AppError::ValidationError(format!(
+            "Dataset batch too large ({} records; max {})",
+            req.records.len(), MAX_DATASET_BATCH
+        )));
+    }
+
+    let accepted = TrainingRecord::insert_batch(&state.pool, agent.org_id, &req.records).await?;
+    DatasetSharing::record_upload(&state.pool, agent.org_id, req.sampled_out).await?;
+
+    tracing::debug!(
+        "Received dataset batch for org {}: {} of {} record(s) stored, {} scanned, {} sampled out",
+        agent.org_id, accepted, req.records.len(), req.scanned, req.sampled_out
+    );
+
+    Ok(Json(UploadDatasetResponse {
+        accepted: accepted as usize,
+        sample_rate: sharing.max_sample_rate,
+        server_time: Utc::now().timestamp(),
+    }))
+}
+
 // Helper functions
 
 fn hash_token(token: &str) -> String {
@@ -261,16 +594,23 @@ pub async fn enroll(
             AppError::Unauthorized
         })?;
 
+    let client_cert_sha256 = match req.client_cert_pem.as_deref() {
+        Some(pem) => Some(client_cert_fingerprint(pem)
+            .ok_or_else(|| AppError::ValidationError("Invalid client certificate".to_string()))?),
+        None => None,
+    };
+
     // 2. Check if HWID already registered (re-enrollment case)
     if let Some(existing) = find_endpoint_by_hwid(&state.pool, &req.hwid).await? {
-        // Re-enrollment: Generate new agent token but keep same agent_id
+        // Re-enrollment: Generate new agent token (and client cert) but keep same agent_id
         let new_token = Uuid::new_v4().to_string();
         let token_hash = hash_token(&new_token);
 
         // Update token hash in DB
-        sqlx::query("UPDATE endpoints SET token_hash = $2, updated_at = NOW() WHERE id = $1")
+        sqlx::query("UPDATE endpoints SET token_hash = $2, client_cert_sha256 = $3, updated_at = NOW() WHERE id = $1")
             .bind(existing.id)
             .bind(&token_hash)
+            .bind(&client_cert_sha256)
             .execute(&state.pool)
             .await?;
 
@@ -283,6 +623,7 @@ pub async fn enroll(
             agent_token: new_token,
             org_id: existing.org_id,
             org_name,
+            server_ca_pem: state.config.agent_tls_ca_pem.clone(),
         }));
     }
 
@@ -301,8 +642,8 @@ pub async fn enroll(
     let endpoint_id = Uuid::new_v4();
     sqlx::query(
         r#"
-        INSERT INTO endpoints (id, org_id, hostname, os_type, os_version, agent_version, hwid, token_hash, status)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'online')
+        INSERT INTO endpoints (id, org_id, hostname, os_type, os_version, agent_version, hwid, token_hash, client_cert_sha256, status)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, 'online')
         "#
     )
     .bind(endpoint_id)
@@ -313,6 +654,7 @@ pub async fn enroll(
     .bind(&req.agent_version)
     .bind(&req.hwid)
     .bind(&token_hash)
+    .bind(&client_cert_sha256)
     .execute(&state.pool)
     .await?;
 
@@ -332,6 +674,7 @@ pub async fn enroll(
         agent_token,
         org_id: token.org_id,
         org_name,
+        server_ca_pem: state.config.agent_tls_ca_pem.clone(),
     }))
 }
 
@@ -0,0 +1,91 @@
+//! Golden baseline handlers
+
+use axum::{extract::State, Json};
+
+use crate::{AppState, AppResult, AppError};
+use crate::models::{
+    Baseline, GoldenBaseline, UpdateGoldenBaseline, BaselineSyncOverview, BASELINE_CONFLICT_POLICIES,
+};
+use crate::middleware::auth::{UserContext, require_admin};
+
+/// Current golden baseline of the org
+pub async fn get_golden(
+    State(state): State<AppState>,
+    user: UserContext,
+) -> AppResult<Json<GoldenBaseline>> {
+    let golden = GoldenBaseline::get(&state.pool, user.org_id).await?
+        .ok_or_else(|| AppError::NotFound("No golden baseline".to_string()))?;
+    Ok(Json(golden))
+}
+
+/// Promote an endpoint's baseline to golden and/or change the conflict policy
+pub async fn update_golden(
+    State(state): State<AppState>,
+    user: UserContext,
+    Json(req): Json<UpdateGoldenBaseline>,
+) -> AppResult<Json<GoldenBaseline>> {
+    // RBAC: Admin only
+    require_admin(&user)?;
+
+    if req.endpoint_id.is_none() && req.conflict_policy.is_none() {
+        return Err(AppError::ValidationError("Nothing to update".to_string()));
+    }
+    if let Some(policy) = req.conflict_policy.as_deref() {
+        if !BASELINE_CONFLICT_POLICIES.contains(&policy) {
+            return Err(AppError::ValidationError(format!(
+                "Unknown conflict policy '{}' (expected one of: {})",
+                policy, BASELINE_CONFLICT_POLICIES.join(", ")
+            )));
+        }
+    }
+
+    let golden = GoldenBaseline::update(&state.pool, user.org_id, &req, Some(user.user_id)).await?
+        .ok_or_else(|| AppError::NotFound(match req.endpoint_id {
+            Some(_) => "Endpoint has not uploaded a baseline".to_string(),
+            None => "No golden baseline".to_string(),
+        }))?;
+
+    tracing::info!(
+        "Golden baseline updated by {} (org: {}, source: {:?}, policy: {}, revision {})",
+        user.user_id, user.org_id, golden.source_endpoint_id, golden.conflict_policy, golden.revision
+    );
+
+    Ok(Json(golden))
+}
+
+/// Remove the golden baseline (agents keep learning locally)
+pub async fn delete_golden(
+    State(state): State<AppState>,
+    user: UserContext,
+) -> AppResult<Json<serde_json::Value>> {
+    // RBAC: Admin only
+    require_admin(&user)?;
+
+    if !GoldenBaseline::delete(&state.pool, user.org_id).await? {
+        return Err(AppError::NotFound("No golden baseline".to_string()));
+    }
+
+    tracing::info!("Golden baseline removed by {} (org: {})", user.user_id, user.org_id);
+
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
+
+/// Which endpoints reconciled the latest golden revision
+pub async fn sync_state(
+    State(state): State<AppState>,
+    user: UserContext,
+) -> AppResult<Json<BaselineSyncOverview>> {
+    let golden_revision = GoldenBaseline::revision(&state.pool, user.org_id).await?;
+    let conflict_policy = GoldenBaseline::get(&state.pool, user.org_id).await?
+        .map(|g| g.conflict_policy);
+    let endpoints = Baseline::sync_state_by_org(&state.pool, user.org_id, golden_revision).await?;
+
+    let stale = endpoints.iter().filter(|e| e.is_stale).count();
+    Ok(Json(BaselineSyncOverview {
+        golden_revision,
+        conflict_policy,
+        up_to_date: endpoints.len() - stale,
+        stale,
+        endpoints,
+    }))
+}
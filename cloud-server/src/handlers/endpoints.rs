@@ -5,8 +5,8 @@ use uuid::Uuid;
 use serde::Deserialize;
 
 use crate::{AppState, AppResult, AppError};
-use crate::models::Endpoint;
-use crate::middleware::auth::UserContext;
+use crate::models::{Endpoint, AgentCommand, PendingAction, QueuedCommand};
+use crate::middleware::auth::{UserContext, require_admin};
 
 #[derive(Debug, Deserialize)]
 pub struct ListQuery {
@@ -56,3 +56,93 @@ pub async fn delete(
 
     Ok(Json(serde_json::json!({ "deleted": true })))
 }
+
+/// Isolate endpoint from the network (agent keeps cloud + DNS access)
+pub async fn isolate(
+    State(state): State<AppState>,
+    user: UserContext,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<QueuedCommand>> {
+    queue_command(&state, &user, id, AgentCommand::IsolateHost).await
+}
+
+/// Lift network isolation
+pub async fn release(
+    State(state): State<AppState>,
+    user: UserContext,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<QueuedCommand>> {
+    queue_command(&state, &user, id, AgentCommand::ReleaseHost).await
+}
+
+/// Approve an action waiting on the agent (e.g. KillProcess).
+/// Agent webhooks link here; the caller still needs a console session.
+pub async fn approve_action(
+    State(state): State<AppState>,
+    user: UserContext,
+    Path((id, action_id)): Path<(Uuid, String)>,
+) -> AppResult<Json<QueuedCommand>> {
+    ensure_pending(&state, &user, id, &action_id).await?;
+    queue_command(&state, &user, id, AgentCommand::ApproveAction { action_id }).await
+}
+
+/// Reject (cancel) an action waiting on the agent
+pub async fn reject_action(
+    State(state): State<AppState>,
+    user: UserContext,
+    Path((id, action_id)): Path<(Uuid, String)>,
+) -> AppResult<Json<QueuedCommand>> {
+    ensure_pending(&state, &user, id, &action_id).await?;
+    queue_command(&state, &user, id, AgentCommand::RejectAction { action_id }).await
+}
+
+/// List recent commands sent to an endpoint
+pub async fn list_commands(
+    State(state): State<AppState>,
+    user: UserContext,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ListQuery>,
+) -> AppResult<Json<Vec<QueuedCommand>>> {
+    find_owned_endpoint(&state, &user, id).await?;
+    let commands = QueuedCommand::list_by_endpoint(&state.pool, id, query.limit.unwrap_or(50)).await?;
+    Ok(Json(commands))
+}
+
+async fn queue_command(
+    state: &AppState,
+    user: &UserContext,
+    id: Uuid,
+    command: AgentCommand,
+) -> AppResult<Json<QueuedCommand>> {
+    require_admin(user)?;
+    let endpoint = find_owned_endpoint(state, user, id).await?;
+
+    let queued = QueuedCommand::enqueue(&state.pool, endpoint.id, &command, Some(user.user_id)).await?;
+    tracing::info!("Queued {:?} for endpoint {} by {}", command, endpoint.hostname, user.user_id);
+
+    Ok(Json(queued))
+}
+
+/// The action must be in the pending list the agent last reported
+async fn ensure_pending(state: &AppState, user: &UserContext, id: Uuid, action_id: &str) -> AppResult<()> {
+    let endpoint = find_owned_endpoint(state, user, id).await?;
+    let pending: Vec<PendingAction> = serde_json::from_value(endpoint.pending_actions).unwrap_or_default();
+
+    if pending.iter().any(|a| a.id == action_id) {
+        Ok(())
+    } else {
+        Err(AppError::NotFound("Pending action not found".to_string()))
+    }
+}
+
+async fn find_owned_endpoint(state: &AppState, user: &UserContext, id: Uuid) -> AppResult<Endpoint> {
+    let endpoint = Endpoint::find_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Endpoint not found".to_string()))?;
+
+    if endpoint.org_id != user.org_id {
+        return Err(AppError::Forbidden);
+    }
+
+    Ok(endpoint)
+}
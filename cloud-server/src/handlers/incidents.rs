@@ -1,10 +1,11 @@
 //! Incidents handlers
 
 use axum::{extract::{State, Path, Query}, Json};
+use chrono::Utc;
 use uuid::Uuid;
 
 use crate::{AppState, AppResult, AppError};
-use crate::models::{Incident, IncidentFilter, UpdateIncidentStatus};
+use crate::models::{AgentCommand, CreateIncidentNote, Incident, IncidentFilter, IncidentNote, QueuedCommand, UpdateIncidentStatus, User};
 use crate::middleware::auth::UserContext;
 
 /// List incidents for organization
@@ -31,9 +32,12 @@ pub async fn get(
 }
 
 /// Update incident status
+///
+/// Triage statuses (`confirmed` / `true_positive`, `dismissed` / `false_positive`)
+/// are also sent to the reporting agent as a verdict so it can label its training data.
 pub async fn update_status(
     State(state): State<AppState>,
-    _user: UserContext,
+    user: UserContext,
     Path(id): Path<Uuid>,
     Json(req): Json<UpdateIncidentStatus>,
 ) -> AppResult<Json<Incident>> {
@@ -41,5 +45,55 @@ pub async fn update_status(
         .await?
         .ok_or_else(|| AppError::NotFound("Incident not found".to_string()))?;
 
+    if let Some(verdict) = verdict_for_status(&req.status) {
+        let command = AgentCommand::IncidentVerdict { incident_id: incident.id, verdict: verdict.to_string() };
+        QueuedCommand::enqueue(&state.pool, incident.endpoint_id, &command, Some(user.user_id)).await?;
+        tracing::info!("Queued {} verdict for incident {} to agent {}", verdict, incident.id, incident.endpoint_id);
+    }
+
     Ok(Json(incident))
 }
+
+/// Add an analyst note / disposition reason to an incident
+///
+/// The note is stored on the incident and sent to the reporting agent so both sides
+/// carry the same triage history.
+pub async fn add_note(
+    State(state): State<AppState>,
+    user: UserContext,
+    Path(id): Path<Uuid>,
+    Json(req): Json<CreateIncidentNote>,
+) -> AppResult<Json<Incident>> {
+    let author = User::find_by_id(&state.pool, user.user_id)
+        .await?
+        .map(|u| u.name.unwrap_or(u.email))
+        .unwrap_or_else(|| user.user_id.to_string());
+
+    let mut note = IncidentNote {
+        id: Uuid::new_v4(),
+        author,
+        text: req.text,
+        disposition: req.disposition,
+        source: "console".to_string(),
+        created_at: Utc::now().timestamp(),
+    };
+    note.validate().map_err(AppError::ValidationError)?;
+
+    let incident = Incident::add_note(&state.pool, id, None, &note)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Incident not found".to_string()))?;
+
+    let command = AgentCommand::IncidentNote { incident_id: incident.id, note };
+    QueuedCommand::enqueue(&state.pool, incident.endpoint_id, &command, Some(user.user_id)).await?;
+
+    Ok(Json(incident))
+}
+
+/// Map a triage status to the verdict understood by the agent
+fn verdict_for_status(status: &str) -> Option<&'static str> {
+    match status.trim().to_lowercase().as_str() {
+        "confirmed" | "true_positive" => Some("confirmed"),
+        "dismissed" | "false_positive" => Some("dismissed"),
+        _ => None,
+    }
+}
@@ -0,0 +1,61 @@
+//! Offline intel bundle handlers
+//!
+//! Air-gapped agents import intel bundles from USB / file share. The console
+//! signs the bundle manifest with the content signing key, which never leaves
+//! the server; agents verify it against their pinned public key.
+
+use std::collections::BTreeMap;
+use axum::{extract::State, Json};
+use serde::{Deserialize, Serialize};
+
+use crate::{AppState, AppResult, AppError};
+use crate::middleware::auth::{UserContext, require_admin};
+use crate::signing;
+
+#[derive(Debug, Deserialize)]
+pub struct SignManifestRequest {
+    /// Exact `manifest.json` text that goes into the bundle (the bytes are signed)
+    pub manifest: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SignManifestResponse {
+    /// Hex ed25519 signature, stored as `manifest.sig`
+    pub signature: String,
+    pub public_key: String,
+}
+
+/// Fields the agent requires in a bundle manifest
+#[derive(Debug, Deserialize)]
+struct BundleManifest {
+    version: String,
+    created_at: i64,
+    files: BTreeMap<String, String>,
+}
+
+/// Sign an offline intel bundle manifest
+pub async fn sign_manifest(
+    State(state): State<AppState>,
+    user: UserContext,
+    Json(req): Json<SignManifestRequest>,
+) -> AppResult<Json<SignManifestResponse>> {
+    // RBAC: Admin only
+    require_admin(&user)?;
+
+    let manifest: BundleManifest = serde_json::from_str(&req.manifest)
+        .map_err(|e| AppError::ValidationError(format!("Invalid manifest: {}", e)))?;
+    if manifest.version.trim().is_empty() || manifest.files.is_empty() {
+        return Err(AppError::ValidationError("Manifest needs a version and at least one file".to_string()));
+    }
+
+    let key = &state.config.content_signing_key;
+    tracing::info!(
+        "Intel bundle {} (created_at {}, {} files) signed by {} (org: {})",
+        manifest.version, manifest.created_at, manifest.files.len(), user.user_id, user.org_id
+    );
+
+    Ok(Json(SignManifestResponse {
+        signature: signing::sign(key, req.manifest.as_bytes()),
+        public_key: signing::public_key_hex(key),
+    }))
+}
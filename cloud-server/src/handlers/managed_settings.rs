@@ -0,0 +1,154 @@
+//! Managed agent settings handlers
+
+use axum::{extract::{State, Path, Query}, Json};
+use uuid::Uuid;
+
+use crate::{AppState, AppResult, AppError};
+use crate::models::{
+    Endpoint, ManagedSettings, UpdateManagedSettings, ManagedSettingsGroupQuery, AssignSettingsGroup,
+    ManagedSettingsDeployment, EndpointSettingsStatus, MANAGED_SETTINGS_SECTIONS,
+};
+use crate::middleware::auth::{UserContext, require_admin};
+
+const MAX_GROUP_NAME_LEN: usize = 100;
+
+/// List org defaults and group settings
+pub async fn list(
+    State(state): State<AppState>,
+    user: UserContext,
+) -> AppResult<Json<Vec<ManagedSettings>>> {
+    let settings = ManagedSettings::list_by_org(&state.pool, user.org_id).await?;
+    Ok(Json(settings))
+}
+
+/// Replace the settings of a group or the org defaults (delivered on next heartbeat)
+pub async fn update(
+    State(state): State<AppState>,
+    user: UserContext,
+    Json(req): Json<UpdateManagedSettings>,
+) -> AppResult<Json<ManagedSettings>> {
+    // RBAC: Admin only
+    require_admin(&user)?;
+    let group = normalize_group(req.group.as_deref())?;
+    validate_settings(&req.settings)?;
+
+    let settings = ManagedSettings::upsert(
+        &state.pool,
+        user.org_id,
+        group.as_deref().unwrap_or(""),
+        &req.settings,
+        Some(user.user_id),
+    ).await?;
+
+    tracing::info!(
+        "Managed settings for {} updated by {} (org: {}, revision {})",
+        group.as_deref().unwrap_or("org defaults"), user.user_id, user.org_id, settings.revision
+    );
+
+    Ok(Json(settings))
+}
+
+/// Remove a group's settings (or the org defaults)
+pub async fn delete(
+    State(state): State<AppState>,
+    user: UserContext,
+    Query(query): Query<ManagedSettingsGroupQuery>,
+) -> AppResult<Json<serde_json::Value>> {
+    // RBAC: Admin only
+    require_admin(&user)?;
+    let group = normalize_group(query.group.as_deref())?;
+
+    if !ManagedSettings::delete(&state.pool, user.org_id, group.as_deref().unwrap_or("")).await? {
+        return Err(AppError::NotFound("Managed settings not found".to_string()));
+    }
+
+    tracing::info!(
+        "Managed settings for {} removed by {}",
+        group.as_deref().unwrap_or("org defaults"), user.user_id
+    );
+
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
+
+/// Move an endpoint into a settings group
+pub async fn assign_group(
+    State(state): State<AppState>,
+    user: UserContext,
+    Path(id): Path<Uuid>,
+    Json(req): Json<AssignSettingsGroup>,
+) -> AppResult<Json<serde_json::Value>> {
+    // RBAC: Admin only
+    require_admin(&user)?;
+    let group = normalize_group(req.group.as_deref())?;
+
+    if !ManagedSettings::assign_group(&state.pool, user.org_id, id, group.as_deref()).await? {
+        return Err(AppError::NotFound("Endpoint not found".to_string()));
+    }
+
+    tracing::info!("Endpoint {} moved to settings group {:?} by {}", id, group, user.user_id);
+
+    Ok(Json(serde_json::json!({ "endpoint_id": id, "settings_group": group })))
+}
+
+/// Which endpoints applied the latest settings revision
+pub async fn deployment(
+    State(state): State<AppState>,
+    user: UserContext,
+) -> AppResult<Json<ManagedSettingsDeployment>> {
+    let latest_version = ManagedSettings::revision(&state.pool, user.org_id).await?;
+    let endpoints: Vec<EndpointSettingsStatus> = Endpoint::list_by_org(&state.pool, user.org_id, 1000)
+        .await?
+        .into_iter()
+        .map(|e| EndpointSettingsStatus {
+            endpoint_id: e.id,
+            hostname: e.hostname,
+            settings_group: e.settings_group,
+            settings_version: e.settings_version,
+            last_heartbeat: e.last_heartbeat,
+            is_stale: e.settings_version != latest_version,
+        })
+        .collect();
+
+    let stale = endpoints.iter().filter(|e| e.is_stale).count();
+    Ok(Json(ManagedSettingsDeployment {
+        latest_version,
+        up_to_date: endpoints.len() - stale,
+        stale,
+        endpoints,
+    }))
+}
+
+/// Trimmed group name; None / "" = org defaults
+fn normalize_group(group: Option<&str>) -> AppResult<Option<String>> {
+    let Some(group) = group.map(str::trim).filter(|g| !g.is_empty()) else {
+        return Ok(None);
+    };
+    if group.len() > MAX_GROUP_NAME_LEN {
+        return Err(AppError::ValidationError("Group name is too long".to_string()));
+    }
+    if !group.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ' ')) {
+        return Err(AppError::ValidationError(
+            "Group name may only contain letters, digits, spaces, '-', '_' and '.'".to_string(),
+        ));
+    }
+    Ok(Some(group.to_string()))
+}
+
+/// Shape checks only - the agent validates values and rejects the whole revision on error
+fn validate_settings(settings: &serde_json::Value) -> AppResult<()> {
+    let sections = settings.as_object()
+        .ok_or_else(|| AppError::ValidationError("Settings must be an object of sections".to_string()))?;
+
+    for (name, values) in sections {
+        if !MANAGED_SETTINGS_SECTIONS.contains(&name.as_str()) {
+            return Err(AppError::ValidationError(format!(
+                "Unknown settings section '{}' (expected one of: {})",
+                name, MANAGED_SETTINGS_SECTIONS.join(", ")
+            )));
+        }
+        if !values.is_object() {
+            return Err(AppError::ValidationError(format!("Section '{}' must be an object", name)));
+        }
+    }
+    Ok(())
+}
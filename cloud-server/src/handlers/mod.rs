@@ -9,3 +9,11 @@ pub mod policies;
 pub mod reports;
 pub mod organization;
 pub mod tokens;
+pub mod rule_packs;
+pub mod intel_bundles;
+pub mod never_learn;
+pub mod prevalence;
+pub mod telemetry;
+pub mod training;
+pub mod managed_settings;
+pub mod baselines;
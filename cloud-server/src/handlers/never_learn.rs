@@ -0,0 +1,95 @@
+//! Never-learn list handlers
+
+use axum::{extract::{State, Path, Query}, Json};
+use uuid::Uuid;
+
+use crate::{AppState, AppResult, AppError};
+use crate::models::{
+    NeverLearnEntry, CreateNeverLearnEntry, NeverLearnDecision, NeverLearnDecisionFilter,
+    NEVER_LEARN_KINDS,
+};
+use crate::middleware::auth::{UserContext, require_admin};
+
+/// List active never-learn entries for organization
+pub async fn list(
+    State(state): State<AppState>,
+    user: UserContext,
+) -> AppResult<Json<Vec<NeverLearnEntry>>> {
+    let entries = NeverLearnEntry::list_active(&state.pool, user.org_id).await?;
+    Ok(Json(entries))
+}
+
+/// Add an entry (pushed to agents on next heartbeat)
+pub async fn create(
+    State(state): State<AppState>,
+    user: UserContext,
+    Json(req): Json<CreateNeverLearnEntry>,
+) -> AppResult<Json<NeverLearnEntry>> {
+    // RBAC: Admin only
+    require_admin(&user)?;
+    validate_entry(&req)?;
+
+    let entry = NeverLearnEntry::create(&state.pool, user.org_id, Some(user.user_id), req).await?;
+
+    tracing::info!(
+        "Never-learn {} '{}' added by {} (org: {}, revision {})",
+        entry.kind, entry.value, user.user_id, user.org_id, entry.revision
+    );
+
+    Ok(Json(entry))
+}
+
+/// Remove an entry
+pub async fn delete(
+    State(state): State<AppState>,
+    user: UserContext,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<serde_json::Value>> {
+    // RBAC: Admin only
+    require_admin(&user)?;
+
+    let entry = NeverLearnEntry::find_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Never-learn entry not found".to_string()))?;
+
+    // Verify org ownership
+    if entry.org_id != user.org_id {
+        return Err(AppError::Forbidden);
+    }
+
+    if !NeverLearnEntry::delete(&state.pool, id, user.org_id).await? {
+        return Err(AppError::NotFound("Never-learn entry not found".to_string()));
+    }
+
+    tracing::info!("Never-learn {} '{}' removed by {}", entry.kind, entry.value, user.user_id);
+
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
+
+/// Never-learn decisions reported by agents (newest first)
+pub async fn decisions(
+    State(state): State<AppState>,
+    user: UserContext,
+    Query(filter): Query<NeverLearnDecisionFilter>,
+) -> AppResult<Json<Vec<NeverLearnDecision>>> {
+    let decisions = NeverLearnDecision::list_by_org(&state.pool, user.org_id, filter).await?;
+    Ok(Json(decisions))
+}
+
+fn validate_entry(req: &CreateNeverLearnEntry) -> AppResult<()> {
+    let kind = req.kind.trim().to_lowercase();
+    if !NEVER_LEARN_KINDS.contains(&kind.as_str()) {
+        return Err(AppError::ValidationError(format!(
+            "Invalid kind '{}' (expected one of: {})", req.kind, NEVER_LEARN_KINDS.join(", ")
+        )));
+    }
+
+    let value = req.value.trim();
+    if value.is_empty() {
+        return Err(AppError::ValidationError("Value is required".to_string()));
+    }
+    if kind == "hash" && (value.len() != 64 || !value.chars().all(|c| c.is_ascii_hexdigit())) {
+        return Err(AppError::ValidationError(format!("Invalid SHA-256: {}", value)));
+    }
+    Ok(())
+}
@@ -0,0 +1,40 @@
+//! File prevalence handlers
+
+use axum::{extract::{State, Path, Query}, Json};
+use serde::Deserialize;
+
+use crate::{AppState, AppResult, AppError};
+use crate::models::{FilePrevalence, PrevalenceQueryResponse, normalize_hashes};
+use crate::middleware::auth::UserContext;
+
+#[derive(Debug, Deserialize)]
+pub struct RarestQuery {
+    pub limit: Option<i64>,
+}
+
+/// Prevalence of one hash across the organization
+pub async fn get(
+    State(state): State<AppState>,
+    user: UserContext,
+    Path(sha256): Path<String>,
+) -> AppResult<Json<PrevalenceQueryResponse>> {
+    let hashes = normalize_hashes(std::slice::from_ref(&sha256));
+    if hashes.is_empty() {
+        return Err(AppError::ValidationError(format!("Invalid SHA-256: {}", sha256)));
+    }
+
+    let results = FilePrevalence::lookup(&state.pool, user.org_id, &hashes).await?;
+    let total_endpoints = FilePrevalence::total_endpoints(&state.pool, user.org_id).await?;
+
+    Ok(Json(PrevalenceQueryResponse { total_endpoints, results }))
+}
+
+/// Rarest binaries in the organization
+pub async fn rarest(
+    State(state): State<AppState>,
+    user: UserContext,
+    Query(query): Query<RarestQuery>,
+) -> AppResult<Json<Vec<FilePrevalence>>> {
+    let results = FilePrevalence::rarest(&state.pool, user.org_id, query.limit.unwrap_or(50)).await?;
+    Ok(Json(results))
+}
@@ -0,0 +1,117 @@
+//! Detection rule pack handlers
+
+use axum::{extract::{State, Path}, Json};
+use uuid::Uuid;
+
+use crate::{AppState, AppResult, AppError};
+use crate::models::{
+    Endpoint, RulePack, CreateRulePack, RulePackDeployment, EndpointRulePackStatus,
+};
+use crate::middleware::auth::{UserContext, require_admin};
+
+/// List rule packs for organization (newest first)
+pub async fn list(
+    State(state): State<AppState>,
+    user: UserContext,
+) -> AppResult<Json<Vec<RulePack>>> {
+    let packs = RulePack::list_by_org(&state.pool, user.org_id).await?;
+    Ok(Json(packs))
+}
+
+/// Get single rule pack
+pub async fn get(
+    State(state): State<AppState>,
+    user: UserContext,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<RulePack>> {
+    let pack = RulePack::find_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Rule pack not found".to_string()))?;
+
+    // Verify org ownership
+    if pack.org_id != user.org_id {
+        return Err(AppError::Forbidden);
+    }
+
+    Ok(Json(pack))
+}
+
+/// Publish a new rule pack version (delivered to agents on next heartbeat)
+pub async fn create(
+    State(state): State<AppState>,
+    user: UserContext,
+    Json(req): Json<CreateRulePack>,
+) -> AppResult<Json<RulePack>> {
+    // RBAC: Admin only
+    require_admin(&user)?;
+    validate_pack(&req)?;
+
+    let pack = RulePack::create(&state.pool, user.org_id, Some(user.user_id), req).await?;
+
+    tracing::info!(
+        "Rule pack v{} published by {} (org: {}, sha256: {})",
+        pack.version, user.user_id, user.org_id, pack.sha256
+    );
+
+    Ok(Json(pack))
+}
+
+/// Which endpoints run the latest rule pack
+pub async fn deployment(
+    State(state): State<AppState>,
+    user: UserContext,
+) -> AppResult<Json<RulePackDeployment>> {
+    let latest_version = RulePack::latest_version(&state.pool, user.org_id).await?;
+    let endpoints: Vec<EndpointRulePackStatus> = Endpoint::list_by_org(&state.pool, user.org_id, 1000)
+        .await?
+        .into_iter()
+        .map(|e| EndpointRulePackStatus {
+            endpoint_id: e.id,
+            hostname: e.hostname,
+            rule_pack_version: e.rule_pack_version,
+            last_heartbeat: e.last_heartbeat,
+            is_stale: e.rule_pack_version < latest_version,
+        })
+        .collect();
+
+    let stale = endpoints.iter().filter(|e| e.is_stale).count();
+    Ok(Json(RulePackDeployment {
+        latest_version,
+        up_to_date: endpoints.len() - stale,
+        stale,
+        endpoints,
+    }))
+}
+
+/// Basic shape checks - the agent does full validation (regex, conditions)
+fn validate_pack(req: &CreateRulePack) -> AppResult<()> {
+    if req.name.trim().is_empty() {
+        return Err(AppError::ValidationError("Rule pack name is required".to_string()));
+    }
+    if req.behavioral_rules.is_empty() && req.yara_rules.trim().is_empty() {
+        return Err(AppError::ValidationError("Rule pack is empty".to_string()));
+    }
+
+    let mut ids = std::collections::HashSet::new();
+    for rule in &req.behavioral_rules {
+        let id = rule.get("id").and_then(|v| v.as_str()).unwrap_or_default();
+        if id.is_empty() {
+            return Err(AppError::ValidationError("Behavioral rule without id".to_string()));
+        }
+        if !ids.insert(id) {
+            return Err(AppError::ValidationError(format!("Duplicate rule id: {}", id)));
+        }
+        let has_conditions = rule.get("conditions")
+            .and_then(|c| c.as_array())
+            .map(|c| !c.is_empty())
+            .unwrap_or(false);
+        if !has_conditions {
+            return Err(AppError::ValidationError(format!("Rule {} has no conditions", id)));
+        }
+    }
+
+    if !req.yara_rules.trim().is_empty() && !req.yara_rules.contains("rule ") {
+        return Err(AppError::ValidationError("YARA text contains no rule".to_string()));
+    }
+    Ok(())
+}
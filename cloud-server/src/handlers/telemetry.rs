@@ -0,0 +1,60 @@
+//! Endpoint telemetry handlers
+
+use axum::{extract::{State, Path, Query}, Json};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{AppState, AppResult, AppError};
+use crate::models::{
+    Endpoint, TelemetryEvent, TelemetryEventFilter, EngineStatsSample, EndpointTelemetrySummary,
+};
+use crate::middleware::auth::UserContext;
+
+#[derive(Debug, Deserialize)]
+pub struct WindowQuery {
+    pub hours: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// Sampled security events across the organization
+pub async fn events(
+    State(state): State<AppState>,
+    user: UserContext,
+    Query(filter): Query<TelemetryEventFilter>,
+) -> AppResult<Json<Vec<TelemetryEvent>>> {
+    let events = TelemetryEvent::list_by_org(&state.pool, user.org_id, filter).await?;
+    Ok(Json(events))
+}
+
+/// Telemetry volume per endpoint
+pub async fn summary(
+    State(state): State<AppState>,
+    user: UserContext,
+    Query(query): Query<WindowQuery>,
+) -> AppResult<Json<Vec<EndpointTelemetrySummary>>> {
+    let summary = EndpointTelemetrySummary::list_by_org(&state.pool, user.org_id, query.hours.unwrap_or(24)).await?;
+    Ok(Json(summary))
+}
+
+/// Engine stats history of one endpoint
+pub async fn endpoint_stats(
+    State(state): State<AppState>,
+    user: UserContext,
+    Path(id): Path<Uuid>,
+    Query(query): Query<WindowQuery>,
+) -> AppResult<Json<Vec<EngineStatsSample>>> {
+    let endpoint = Endpoint::find_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Endpoint not found".to_string()))?;
+    if endpoint.org_id != user.org_id {
+        return Err(AppError::Forbidden);
+    }
+
+    let stats = EngineStatsSample::list_by_endpoint(
+        &state.pool,
+        id,
+        query.hours.unwrap_or(24),
+        query.limit.unwrap_or(1440),
+    ).await?;
+    Ok(Json(stats))
+}
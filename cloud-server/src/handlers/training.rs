@@ -0,0 +1,64 @@
+//! Training dataset handlers (central model retraining)
+
+use axum::{extract::{State, Query}, Json};
+
+use crate::{AppState, AppResult, AppError};
+use crate::models::{
+    DatasetSharing, UpdateDatasetSharing, TrainingRecord, TrainingRecordQuery, TrainingDatasetSummary,
+};
+use crate::middleware::auth::{UserContext, require_admin};
+
+/// Dataset sharing setting of the organization
+pub async fn get_sharing(
+    State(state): State<AppState>,
+    user: UserContext,
+) -> AppResult<Json<DatasetSharing>> {
+    let sharing = DatasetSharing::get(&state.pool, user.org_id).await?;
+    Ok(Json(sharing))
+}
+
+/// Opt the organization in / out of central model training
+pub async fn update_sharing(
+    State(state): State<AppState>,
+    user: UserContext,
+    Json(req): Json<UpdateDatasetSharing>,
+) -> AppResult<Json<DatasetSharing>> {
+    // RBAC: Admin only
+    require_admin(&user)?;
+    if let Some(rate) = req.max_sample_rate {
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(AppError::ValidationError("max_sample_rate must be between 0.0 and 1.0".to_string()));
+        }
+    }
+
+    let sharing = DatasetSharing::set(&state.pool, user.org_id, user.user_id, &req).await?;
+
+    tracing::info!(
+        "Dataset sharing {} by {} (org: {}, max sample rate {})",
+        if sharing.enabled { "enabled" } else { "disabled" }, user.user_id, user.org_id, sharing.max_sample_rate
+    );
+
+    Ok(Json(sharing))
+}
+
+/// Record counts by feature version / decision / label
+pub async fn summary(
+    State(state): State<AppState>,
+    user: UserContext,
+) -> AppResult<Json<TrainingDatasetSummary>> {
+    let summary = TrainingDatasetSummary::for_org(&state.pool, user.org_id).await?;
+    Ok(Json(summary))
+}
+
+/// Page through anonymized records for a retraining job
+pub async fn records(
+    State(state): State<AppState>,
+    user: UserContext,
+    Query(query): Query<TrainingRecordQuery>,
+) -> AppResult<Json<Vec<TrainingRecord>>> {
+    // RBAC: Admin only (bulk data export)
+    require_admin(&user)?;
+
+    let records = TrainingRecord::list_by_org(&state.pool, user.org_id, query).await?;
+    Ok(Json(records))
+}
@@ -27,16 +27,20 @@ mod models;
 mod handlers;
 mod middleware;
 mod error;
+mod command_hub;
+mod signing;
 
 use axum::{
     Router,
     routing::{get, post, put, delete},
     middleware as axum_middleware,
+    extract::DefaultBodyLimit,
 };
 use tower_http::{
     cors::{CorsLayer, Any},
     trace::TraceLayer,
     compression::CompressionLayer,
+    decompression::RequestDecompressionLayer,
 };
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use std::net::SocketAddr;
@@ -54,10 +58,14 @@ async fn main() {
 
     // Load configuration
     dotenvy::dotenv().ok();
-    let config = config::Config::from_env();
+    let config = config::Config::from_env().expect("Invalid configuration");
 
     tracing::info!("One-Shield Cloud Server starting...");
     tracing::info!("Database: {}", config.database_url.split('@').last().unwrap_or("***"));
+    tracing::info!("Content signing key: {}", signing::public_key_hex(&config.content_signing_key));
+    if signing::is_dev_key(&config.content_signing_key) {
+        tracing::warn!("CONTENT_SIGNING_KEY not set - signing with the development key");
+    }
 
     // Initialize database pool
     let pool = db::create_pool(&config.database_url).await
@@ -68,10 +76,15 @@ async fn main() {
     db::run_migrations(&pool).await
         .expect("Failed to run migrations");
 
+    // Push queued commands to agents connected over WebSocket
+    let commands = command_hub::CommandHub::default();
+    tokio::spawn(command_hub::listen(pool.clone(), commands.clone()));
+
     // Build application state
     let state = AppState {
         pool,
         config: config.clone(),
+        commands,
     };
 
     // Build router
@@ -90,6 +103,7 @@ async fn main() {
 pub struct AppState {
     pub pool: sqlx::PgPool,
     pub config: config::Config,
+    pub commands: command_hub::CommandHub,
 }
 
 /// Create the main router with all routes
@@ -109,9 +123,31 @@ fn create_router(state: AppState) -> Router {
     // Agent routes (agent token auth) - requires registered agent token
     let agent_routes = Router::new()
         .route("/api/v1/agent/heartbeat", post(handlers::agent::heartbeat))
+        .route("/api/v1/agent/commands/ws", get(handlers::agent::command_channel))
         .route("/api/v1/agent/sync/baseline", post(handlers::agent::sync_baseline))
-        .route("/api/v1/agent/sync/incidents", post(handlers::agent::sync_incidents))
+        .route("/api/v1/agent/sync/baseline", get(handlers::agent::get_golden_baseline))
+        // Incident batches may arrive zstd/gzip compressed; limit applies after decompression
+        .route("/api/v1/agent/sync/incidents", post(handlers::agent::sync_incidents)
+            .layer::<_, std::convert::Infallible>(RequestDecompressionLayer::new())
+            .layer(DefaultBodyLimit::max(models::MAX_INCIDENT_SYNC_BODY)))
+        .route("/api/v1/agent/sync/incident-notes", post(handlers::agent::sync_incident_notes)
+            .layer::<_, std::convert::Infallible>(RequestDecompressionLayer::new())
+            .layer(DefaultBodyLimit::max(models::MAX_INCIDENT_SYNC_BODY)))
         .route("/api/v1/agent/policy", get(handlers::agent::get_policy))
+        .route("/api/v1/agent/rule-pack", get(handlers::agent::get_rule_pack))
+        .route("/api/v1/agent/settings", get(handlers::agent::get_settings))
+        .route("/api/v1/agent/never-learn", get(handlers::agent::get_never_learn))
+        .route("/api/v1/agent/never-learn/decisions", post(handlers::agent::report_never_learn))
+        .route("/api/v1/agent/prevalence", post(handlers::agent::report_prevalence))
+        .route("/api/v1/agent/prevalence/query", post(handlers::agent::query_prevalence))
+        // Telemetry batches arrive zstd/gzip compressed; limit applies after decompression
+        .route("/api/v1/agent/telemetry", post(handlers::agent::upload_telemetry)
+            .layer::<_, std::convert::Infallible>(RequestDecompressionLayer::new())
+            .layer(DefaultBodyLimit::max(models::MAX_TELEMETRY_BODY)))
+        // Anonymized training records (opt-in per org), zstd compressed
+        .route("/api/v1/agent/dataset", post(handlers::agent::upload_dataset)
+            .layer::<_, std::convert::Infallible>(RequestDecompressionLayer::new())
+            .layer(DefaultBodyLimit::max(models::MAX_DATASET_BODY)))
         .layer(axum_middleware::from_fn_with_state(
             state.clone(),
             middleware::auth::require_agent_auth
@@ -123,11 +159,28 @@ fn create_router(state: AppState) -> Router {
         .route("/api/v1/endpoints", get(handlers::endpoints::list))
         .route("/api/v1/endpoints/:id", get(handlers::endpoints::get))
         .route("/api/v1/endpoints/:id", delete(handlers::endpoints::delete))
+        .route("/api/v1/endpoints/:id/isolate", post(handlers::endpoints::isolate))
+        .route("/api/v1/endpoints/:id/release", post(handlers::endpoints::release))
+        .route("/api/v1/endpoints/:id/actions/:action_id/approve", post(handlers::endpoints::approve_action))
+        .route("/api/v1/endpoints/:id/actions/:action_id/reject", post(handlers::endpoints::reject_action))
+        .route("/api/v1/endpoints/:id/commands", get(handlers::endpoints::list_commands))
+        .route("/api/v1/endpoints/:id/telemetry/stats", get(handlers::telemetry::endpoint_stats))
 
         // Incidents
         .route("/api/v1/incidents", get(handlers::incidents::list))
         .route("/api/v1/incidents/:id", get(handlers::incidents::get))
         .route("/api/v1/incidents/:id/status", put(handlers::incidents::update_status))
+        .route("/api/v1/incidents/:id/notes", post(handlers::incidents::add_note))
+
+        // Telemetry (sampled events + engine stats uploaded by agents)
+        .route("/api/v1/telemetry/events", get(handlers::telemetry::events))
+        .route("/api/v1/telemetry/summary", get(handlers::telemetry::summary))
+
+        // Training dataset (opt-in upload for central model retraining)
+        .route("/api/v1/training/sharing", get(handlers::training::get_sharing))
+        .route("/api/v1/training/sharing", put(handlers::training::update_sharing))
+        .route("/api/v1/training/summary", get(handlers::training::summary))
+        .route("/api/v1/training/records", get(handlers::training::records))
 
         // Policies
         .route("/api/v1/policies", get(handlers::policies::list))
@@ -135,6 +188,38 @@ fn create_router(state: AppState) -> Router {
         .route("/api/v1/policies/:id", get(handlers::policies::get))
         .route("/api/v1/policies/:id", put(handlers::policies::update))
 
+        // Detection rule packs
+        .route("/api/v1/rule-packs", get(handlers::rule_packs::list))
+        .route("/api/v1/rule-packs", post(handlers::rule_packs::create))
+        .route("/api/v1/rule-packs/deployment", get(handlers::rule_packs::deployment))
+        .route("/api/v1/rule-packs/:id", get(handlers::rule_packs::get))
+
+        // Offline intel bundles (manifest signing for air-gapped agents)
+        .route("/api/v1/intel-bundles/sign", post(handlers::intel_bundles::sign_manifest))
+
+        // Never-learn list (baseline exclusions) + agent decisions
+        .route("/api/v1/never-learn", get(handlers::never_learn::list))
+        .route("/api/v1/never-learn", post(handlers::never_learn::create))
+        .route("/api/v1/never-learn/decisions", get(handlers::never_learn::decisions))
+        .route("/api/v1/never-learn/:id", delete(handlers::never_learn::delete))
+
+        // Managed agent settings (org defaults + endpoint groups)
+        .route("/api/v1/managed-settings", get(handlers::managed_settings::list))
+        .route("/api/v1/managed-settings", put(handlers::managed_settings::update))
+        .route("/api/v1/managed-settings", delete(handlers::managed_settings::delete))
+        .route("/api/v1/managed-settings/deployment", get(handlers::managed_settings::deployment))
+        .route("/api/v1/endpoints/:id/settings-group", put(handlers::managed_settings::assign_group))
+
+        // Golden baseline (promoted endpoint baseline) + per-endpoint sync state
+        .route("/api/v1/baselines/golden", get(handlers::baselines::get_golden))
+        .route("/api/v1/baselines/golden", put(handlers::baselines::update_golden))
+        .route("/api/v1/baselines/golden", delete(handlers::baselines::delete_golden))
+        .route("/api/v1/baselines/sync", get(handlers::baselines::sync_state))
+
+        // File prevalence (fleet-wide hash sightings)
+        .route("/api/v1/prevalence/rare", get(handlers::prevalence::rarest))
+        .route("/api/v1/prevalence/:sha256", get(handlers::prevalence::get))
+
         // Reports
         .route("/api/v1/reports/executive", get(handlers::reports::executive))
         .route("/api/v1/reports/compliance", get(handlers::reports::compliance))
@@ -14,7 +14,7 @@ use uuid::Uuid;
 
 use crate::{AppState, AppError};
 use crate::handlers::auth::Claims;
-use crate::models::Endpoint;
+use crate::models::{Endpoint, client_cert_fingerprint};
 
 /// User context extracted from JWT
 #[derive(Debug, Clone)]
@@ -109,6 +109,8 @@ pub async fn require_agent_auth(
         .map_err(|_| AppError::InternalError("Database error".to_string()))?
         .ok_or(AppError::Unauthorized)?;
 
+    verify_client_cert(&state, &req, &endpoint)?;
+
     // Extract IP address
     let ip_address = req.headers()
         .get("X-Forwarded-For")
@@ -129,6 +131,34 @@ pub async fn require_agent_auth(
     Ok(next.run(req).await)
 }
 
+/// mTLS: the client certificate forwarded by the TLS-terminating proxy must be
+/// the one the agent enrolled with. Skipped when no proxy header is configured.
+fn verify_client_cert(state: &AppState, req: &Request, endpoint: &Endpoint) -> Result<(), AppError> {
+    let Some(header) = state.config.mtls_client_cert_header.as_deref() else {
+        return Ok(());
+    };
+
+    let Some(expected) = endpoint.client_cert_sha256.as_deref() else {
+        if state.config.mtls_required {
+            tracing::warn!("Agent {} has no enrolled client certificate (mTLS required)", endpoint.id);
+            return Err(AppError::Unauthorized);
+        }
+        return Ok(());
+    };
+
+    let presented = req.headers()
+        .get(header)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| percent_encoding::percent_decode_str(v).decode_utf8_lossy().into_owned())
+        .and_then(|pem| client_cert_fingerprint(&pem));
+
+    if presented.as_deref() != Some(expected) {
+        tracing::warn!("Client certificate mismatch for agent {}", endpoint.id);
+        return Err(AppError::Unauthorized);
+    }
+    Ok(())
+}
+
 /// Extract bearer token from Authorization header
 fn extract_bearer_token(req: &Request) -> Result<String, AppError> {
     let auth_header = req.headers()
@@ -1,10 +1,20 @@
 //! Baseline model
+//!
+//! Agents upload their learned baseline with a version vector: the agent's
+//! local learning generation plus the golden baseline revision it was based
+//! on. An organization can promote one endpoint's baseline to its golden
+//! baseline; agents pull it and reconcile it with local learning according
+//! to the golden baseline's conflict policy.
 
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, PgPool};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+/// How agents reconcile the golden baseline with their own learning
+pub const BASELINE_CONFLICT_POLICIES: &[&str] = &["server_wins", "local_wins", "merge"];
+pub const DEFAULT_BASELINE_CONFLICT_POLICY: &str = "server_wins";
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Baseline {
     pub id: Uuid,
@@ -15,6 +25,18 @@ pub struct Baseline {
     pub version: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Version vector of the last upload
+    pub agent_version: i64,
+    pub golden_revision: i64,
+    pub feature_version: Option<i32>,
+    pub layout_hash: Option<i64>,
+}
+
+/// `agent` = local learning generation, `server` = golden revision the baseline is based on
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct VersionVector {
+    pub agent: i64,
+    pub server: i64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,6 +46,12 @@ pub struct SyncBaselineRequest {
     pub variance_values: Option<Vec<f32>>,
     pub sample_count: u64,
     pub version: i32,
+    #[serde(default)]
+    pub feature_version: Option<i32>,
+    #[serde(default)]
+    pub layout_hash: Option<u32>,
+    #[serde(default)]
+    pub version_vector: VersionVector,
 }
 
 #[derive(Debug, Serialize)]
@@ -31,6 +59,55 @@ pub struct SyncBaselineResponse {
     pub accepted: bool,
     pub server_version: i32,
     pub server_time: i64,
+    /// Current golden baseline revision of the org (0 = none)
+    pub golden_revision: i64,
+    /// The upload is based on an older golden revision
+    pub has_golden_update: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct GoldenBaseline {
+    pub id: Uuid,
+    pub org_id: Uuid,
+    pub source_endpoint_id: Option<Uuid>,
+    pub mean_values: serde_json::Value,
+    pub variance_values: Option<serde_json::Value>,
+    pub sample_count: i64,
+    pub feature_version: Option<i32>,
+    pub layout_hash: Option<i64>,
+    /// `BASELINE_CONFLICT_POLICIES`
+    pub conflict_policy: String,
+    /// Org baseline revision at the last change
+    pub revision: i64,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateGoldenBaseline {
+    /// Promote this endpoint's uploaded baseline (None = keep current values)
+    pub endpoint_id: Option<Uuid>,
+    pub conflict_policy: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EndpointBaselineSync {
+    pub endpoint_id: Uuid,
+    pub hostname: String,
+    pub agent_version: i64,
+    pub golden_revision: i64,
+    pub sample_count: i64,
+    pub synced_at: DateTime<Utc>,
+    pub is_stale: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BaselineSyncOverview {
+    pub golden_revision: i64,
+    pub conflict_policy: Option<String>,
+    pub up_to_date: usize,
+    pub stale: usize,
+    pub endpoints: Vec<EndpointBaselineSync>,
 }
 
 impl Baseline {
@@ -44,13 +121,18 @@ impl Baseline {
 
         sqlx::query_as::<_, Baseline>(
             r#"
-            INSERT INTO baselines (endpoint_id, mean_values, variance_values, sample_count, version)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO baselines (endpoint_id, mean_values, variance_values, sample_count, version,
+                                   agent_version, golden_revision, feature_version, layout_hash)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
             ON CONFLICT (endpoint_id) DO UPDATE SET
                 mean_values = EXCLUDED.mean_values,
                 variance_values = EXCLUDED.variance_values,
                 sample_count = EXCLUDED.sample_count,
                 version = EXCLUDED.version,
+                agent_version = EXCLUDED.agent_version,
+                golden_revision = EXCLUDED.golden_revision,
+                feature_version = EXCLUDED.feature_version,
+                layout_hash = EXCLUDED.layout_hash,
                 updated_at = NOW()
             RETURNING *
             "#
@@ -60,10 +142,46 @@ impl Baseline {
         .bind(&variance_json)
         .bind(data.sample_count as i64)
         .bind(data.version)
+        .bind(data.version_vector.agent)
+        .bind(data.version_vector.server)
+        .bind(data.feature_version)
+        .bind(data.layout_hash.map(i64::from))
         .fetch_one(pool)
         .await
     }
 
+    /// Sync state of every endpoint that uploaded a baseline
+    pub async fn sync_state_by_org(
+        pool: &PgPool,
+        org_id: Uuid,
+        golden_revision: i64,
+    ) -> Result<Vec<EndpointBaselineSync>, sqlx::Error> {
+        let rows = sqlx::query_as::<_, (Uuid, String, i64, i64, i64, DateTime<Utc>)>(
+            r#"
+            SELECT b.endpoint_id, e.hostname, b.agent_version, b.golden_revision, b.sample_count, b.updated_at
+            FROM baselines b
+            JOIN endpoints e ON e.id = b.endpoint_id
+            WHERE e.org_id = $1
+            ORDER BY e.hostname
+            "#
+        )
+        .bind(org_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter()
+            .map(|(endpoint_id, hostname, agent_version, revision, sample_count, synced_at)| EndpointBaselineSync {
+                endpoint_id,
+                hostname,
+                agent_version,
+                golden_revision: revision,
+                sample_count,
+                synced_at,
+                is_stale: revision != golden_revision,
+            })
+            .collect())
+    }
+
     pub async fn find_by_endpoint(pool: &PgPool, endpoint_id: Uuid) -> Result<Option<Self>, sqlx::Error> {
         sqlx::query_as::<_, Baseline>("SELECT * FROM baselines WHERE endpoint_id = $1")
             .bind(endpoint_id)
@@ -71,3 +189,145 @@ impl Baseline {
             .await
     }
 }
+
+impl GoldenBaseline {
+    pub async fn get(pool: &PgPool, org_id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as::<_, GoldenBaseline>("SELECT * FROM golden_baselines WHERE org_id = $1")
+            .bind(org_id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    /// Current org baseline revision (0 = never configured)
+    pub async fn revision(pool: &PgPool, org_id: Uuid) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT baseline_revision FROM organizations WHERE id = $1")
+            .bind(org_id)
+            .fetch_optional(pool)
+            .await
+            .map(|r| r.unwrap_or(0))
+    }
+
+    /// Promote an endpoint's baseline and/or change the conflict policy.
+    /// None when the endpoint has no uploaded baseline, or there is no golden
+    /// baseline to update and no endpoint was given.
+    pub async fn update(
+        pool: &PgPool,
+        org_id: Uuid,
+        req: &UpdateGoldenBaseline,
+        updated_by: Option<Uuid>,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let revision = bump_revision(&mut tx, org_id).await?;
+
+        let row = match req.endpoint_id {
+            Some(endpoint_id) => {
+                let source = sqlx::query_as::<_, Baseline>(
+                    r#"
+                    SELECT b.* FROM baselines b
+                    JOIN endpoints e ON e.id = b.endpoint_id
+                    WHERE b.endpoint_id = $1 AND e.org_id = $2
+                    "#
+                )
+                .bind(endpoint_id)
+                .bind(org_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+                let Some(source) = source else {
+                    return Ok(None);
+                };
+
+                sqlx::query_as::<_, GoldenBaseline>(
+                    r#"
+                    INSERT INTO golden_baselines (org_id, source_endpoint_id, mean_values, variance_values,
+                                                  sample_count, feature_version, layout_hash,
+                                                  conflict_policy, revision, updated_by)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, COALESCE($8, $11), $9, $10)
+                    ON CONFLICT (org_id) DO UPDATE SET
+                        source_endpoint_id = EXCLUDED.source_endpoint_id,
+                        mean_values = EXCLUDED.mean_values,
+                        variance_values = EXCLUDED.variance_values,
+                        sample_count = EXCLUDED.sample_count,
+                        feature_version = EXCLUDED.feature_version,
+                        layout_hash = EXCLUDED.layout_hash,
+                        conflict_policy = COALESCE($8, golden_baselines.conflict_policy),
+                        revision = EXCLUDED.revision,
+                        updated_by = EXCLUDED.updated_by,
+                        updated_at = NOW()
+                    RETURNING *
+                    "#
+                )
+                .bind(org_id)
+                .bind(endpoint_id)
+                .bind(&source.mean_values)
+                .bind(&source.variance_values)
+                .bind(source.sample_count)
+                .bind(source.feature_version)
+                .bind(source.layout_hash)
+                .bind(req.conflict_policy.as_deref())
+                .bind(revision)
+                .bind(updated_by)
+                .bind(DEFAULT_BASELINE_CONFLICT_POLICY)
+                .fetch_one(&mut *tx)
+                .await?
+            }
+            None => {
+                let row = sqlx::query_as::<_, GoldenBaseline>(
+                    r#"
+                    UPDATE golden_baselines
+                    SET conflict_policy = COALESCE($2, conflict_policy),
+                        revision = $3,
+                        updated_by = $4,
+                        updated_at = NOW()
+                    WHERE org_id = $1
+                    RETURNING *
+                    "#
+                )
+                .bind(org_id)
+                .bind(req.conflict_policy.as_deref())
+                .bind(revision)
+                .bind(updated_by)
+                .fetch_optional(&mut *tx)
+                .await?;
+                let Some(row) = row else {
+                    return Ok(None);
+                };
+                row
+            }
+        };
+
+        tx.commit().await?;
+        Ok(Some(row))
+    }
+
+    pub async fn delete(pool: &PgPool, org_id: Uuid) -> Result<bool, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let result = sqlx::query("DELETE FROM golden_baselines WHERE org_id = $1")
+            .bind(org_id)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        bump_revision(&mut tx, org_id).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+}
+
+/// Serialized per organization by the row lock on `organizations`
+async fn bump_revision(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    org_id: Uuid,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        UPDATE organizations
+        SET baseline_revision = baseline_revision + 1
+        WHERE id = $1
+        RETURNING baseline_revision
+        "#
+    )
+    .bind(org_id)
+    .fetch_one(&mut **tx)
+    .await
+}
@@ -0,0 +1,133 @@
+//! Agent command queue model
+//!
+//! Commands queued from the console are pushed over the agent's WebSocket
+//! command channel when it is connected, and otherwise delivered in the
+//! next heartbeat response.
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool, Row};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+use super::AgentCommand;
+
+/// Postgres NOTIFY channel fired on enqueue (payload: endpoint id)
+pub const COMMAND_NOTIFY_CHANNEL: &str = "agent_commands";
+
+/// Server → agent message on the WebSocket command channel
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CommandChannelMessage {
+    Commands { commands: Vec<AgentCommand> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct QueuedCommand {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    pub command: serde_json::Value,
+    pub status: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl QueuedCommand {
+    /// Queue a command for an endpoint
+    pub async fn enqueue(
+        pool: &PgPool,
+        endpoint_id: Uuid,
+        command: &AgentCommand,
+        created_by: Option<Uuid>,
+    ) -> Result<Self, sqlx::Error> {
+        let payload = serde_json::to_value(command)
+            .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+        let queued = sqlx::query_as::<_, QueuedCommand>(
+            r#"
+            INSERT INTO agent_commands (endpoint_id, command, status, created_by)
+            VALUES ($1, $2, 'pending', $3)
+            RETURNING *
+            "#
+        )
+        .bind(endpoint_id)
+        .bind(payload)
+        .bind(created_by)
+        .fetch_one(pool)
+        .await?;
+
+        // Wake the agent's command channel; heartbeat still delivers if this is missed
+        if let Err(e) = sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(COMMAND_NOTIFY_CHANNEL)
+            .bind(endpoint_id.to_string())
+            .execute(pool)
+            .await
+        {
+            tracing::warn!("Failed to notify command channel for {}: {}", endpoint_id, e);
+        }
+
+        Ok(queued)
+    }
+
+    /// Mark all pending commands as delivered and return them with their ids
+    /// (oldest first). Callers that fail to hand them to the agent must `requeue` them.
+    pub async fn take_pending(pool: &PgPool, endpoint_id: Uuid) -> Result<Vec<(Uuid, AgentCommand)>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE agent_commands
+            SET status = 'delivered', delivered_at = NOW()
+            WHERE endpoint_id = $1 AND status = 'pending'
+            RETURNING id, command, created_at
+            "#
+        )
+        .bind(endpoint_id)
+        .fetch_all(pool)
+        .await?;
+
+        let mut commands: Vec<(DateTime<Utc>, Uuid, serde_json::Value)> = rows.iter()
+            .map(|r| (r.get("created_at"), r.get("id"), r.get("command")))
+            .collect();
+        commands.sort_by_key(|(created_at, _, _)| *created_at);
+
+        Ok(commands.into_iter()
+            .filter_map(|(_, id, value)| match serde_json::from_value(value) {
+                Ok(cmd) => Some((id, cmd)),
+                Err(e) => {
+                    tracing::warn!("Dropping malformed queued command: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Return taken commands to the queue (push to the agent failed)
+    pub async fn requeue(pool: &PgPool, ids: &[Uuid]) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE agent_commands
+            SET status = 'pending', delivered_at = NULL
+            WHERE id = ANY($1) AND status = 'delivered'
+            "#
+        )
+        .bind(ids)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// List recent commands for an endpoint
+    pub async fn list_by_endpoint(pool: &PgPool, endpoint_id: Uuid, limit: i64) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, QueuedCommand>(
+            r#"
+            SELECT * FROM agent_commands
+            WHERE endpoint_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#
+        )
+        .bind(endpoint_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+}
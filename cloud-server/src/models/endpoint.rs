@@ -20,10 +20,45 @@ pub struct Endpoint {
     pub status: String,
     pub baseline_hash: Option<String>,
     pub baseline_version: i32,
+    /// Host network isolation state (reported by agent heartbeat)
+    pub is_isolated: bool,
+    /// Actions awaiting approval on the agent (`[PendingAction]`, reported by heartbeat)
+    pub pending_actions: serde_json::Value,
+    /// Detection rule pack version running on the agent (0 = none)
+    pub rule_pack_version: i32,
+    /// Offline intel bundle version imported on the agent (None = none)
+    pub intel_bundle_version: Option<String>,
+    /// SHA-256 of the agent's mTLS client certificate (None = bearer token only)
+    pub client_cert_sha256: Option<String>,
+    /// Managed settings group (None = organization defaults only)
+    pub settings_group: Option<String>,
+    /// Managed settings revision applied on the agent (reported by heartbeat)
+    pub settings_version: i64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// SHA-256 (hex) of the first certificate in a PEM bundle, over its DER bytes
+pub fn client_cert_fingerprint(pem: &str) -> Option<String> {
+    use base64::Engine as _;
+    use sha2::{Digest, Sha256};
+
+    let body: String = pem
+        .split("-----BEGIN CERTIFICATE-----")
+        .nth(1)?
+        .split("-----END CERTIFICATE-----")
+        .next()?
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let der = base64::engine::general_purpose::STANDARD.decode(body).ok()?;
+    Some(hex_encode(&Sha256::digest(der)))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct EndpointSummary {
     pub id: Uuid,
@@ -58,22 +93,85 @@ pub struct HeartbeatRequest {
     pub incident_count: i32,
     pub process_count: Option<i32>,
     pub agent_version: String,
+    /// Host is network-isolated (only cloud + DNS reachable)
+    #[serde(default)]
+    pub is_isolated: bool,
+    /// Actions waiting for analyst approval on the agent
+    #[serde(default)]
+    pub pending_actions: Vec<PendingAction>,
+    /// Detection rule pack version applied on the agent
+    #[serde(default)]
+    pub rule_pack_version: i32,
+    /// Never-learn list revision applied on the agent
+    #[serde(default)]
+    pub never_learn_revision: i64,
+    /// Offline intel bundle version imported on the agent
+    #[serde(default)]
+    pub intel_bundle_version: Option<String>,
+    /// Managed settings revision applied on the agent
+    #[serde(default)]
+    pub settings_version: i64,
+    /// Golden baseline revision reconciled on the agent
+    #[serde(default)]
+    pub baseline_revision: i64,
 }
 
+/// Action waiting for approval on the agent (e.g. KillProcess)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingAction {
+    pub id: String,
+    pub action_type: String,
+    pub target_pid: Option<u32>,
+    pub target_name: String,
+    pub final_score: f32,
+    pub created_at: i64,
+}
+
+/// Sync body encodings the server accepts, advertised in heartbeat responses:
+/// zstd / gzip request bodies and shared process tables in incident batches
+pub const SYNC_CAPABILITIES: &[&str] = &["zstd", "gzip", "incident_delta"];
+
 #[derive(Debug, Serialize)]
 pub struct HeartbeatResponse {
     pub server_time: i64,
     pub policy_version: i32,
     pub has_policy_update: bool,
+    /// Latest rule pack version for the organization
+    pub rule_pack_version: i32,
+    pub has_rule_pack_update: bool,
+    /// Latest never-learn list revision for the organization
+    pub never_learn_revision: i64,
+    pub has_never_learn_update: bool,
+    /// Latest managed settings revision for the organization
+    pub settings_version: i64,
+    pub has_settings_update: bool,
+    /// Latest golden baseline revision for the organization
+    pub baseline_revision: i64,
+    pub has_baseline_update: bool,
+    /// `SYNC_CAPABILITIES`
+    pub sync_capabilities: Vec<String>,
     pub commands: Vec<AgentCommand>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
 pub enum AgentCommand {
     UpdatePolicy { version: i32 },
     CollectDiagnostics,
     RestartService,
     UpdateAgent { url: String, checksum: String },
+    /// Block all traffic except cloud server + DNS
+    IsolateHost,
+    /// Lift host isolation
+    ReleaseHost,
+    /// Execute a pending action (out-of-band approval)
+    ApproveAction { action_id: String },
+    /// Cancel a pending action
+    RejectAction { action_id: String },
+    /// Analyst triage verdict ("confirmed" / "dismissed"), turned into dataset labels on the agent
+    IncidentVerdict { incident_id: Uuid, verdict: String },
+    /// Analyst note added on the console
+    IncidentNote { incident_id: Uuid, note: super::IncidentNote },
 }
 
 impl Endpoint {
@@ -129,11 +227,12 @@ impl Endpoint {
         .await
     }
 
+    /// Store the state reported in a heartbeat
     pub async fn update_heartbeat(
         pool: &PgPool,
         id: Uuid,
         ip_address: Option<String>,
-        agent_version: &str
+        heartbeat: &HeartbeatRequest,
     ) -> Result<(), sqlx::Error> {
         sqlx::query(
             r#"
@@ -142,13 +241,23 @@ impl Endpoint {
                 status = 'online',
                 ip_address = COALESCE($2, ip_address),
                 agent_version = $3,
+                is_isolated = $4,
+                pending_actions = $5,
+                rule_pack_version = $6,
+                intel_bundle_version = $7,
+                settings_version = $8,
                 updated_at = NOW()
             WHERE id = $1
             "#
         )
         .bind(id)
         .bind(ip_address)
-        .bind(agent_version)
+        .bind(&heartbeat.agent_version)
+        .bind(heartbeat.is_isolated)
+        .bind(serde_json::to_value(&heartbeat.pending_actions).unwrap_or_default())
+        .bind(heartbeat.rule_pack_version)
+        .bind(heartbeat.intel_bundle_version.as_deref())
+        .bind(heartbeat.settings_version)
         .execute(pool)
         .await?;
         Ok(())
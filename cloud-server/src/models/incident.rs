@@ -5,6 +5,9 @@ use sqlx::{FromRow, PgPool, Row};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+/// Max decompressed body size for an incident / incident note sync
+pub const MAX_INCIDENT_SYNC_BODY: usize = 16 * 1024 * 1024;
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Incident {
     pub id: Uuid,
@@ -15,6 +18,14 @@ pub struct Incident {
     pub mitre_techniques: Option<serde_json::Value>,
     pub threat_class: Option<String>,
     pub confidence: Option<f32>,
+    /// Process chain (process → root) with hashes + signature status
+    pub process_chain: serde_json::Value,
+    /// Rule ids / conditions that matched
+    pub rule_matches: serde_json::Value,
+    /// Top contributing features (ML incidents)
+    pub key_features: serde_json::Value,
+    /// Analyst notes (`IncidentNote`), oldest first
+    pub notes: serde_json::Value,
     pub status: String,
     pub assigned_to: Option<Uuid>,
     pub created_at: DateTime<Utc>,
@@ -32,11 +43,68 @@ pub struct CreateIncident {
     pub threat_class: Option<String>,
     pub confidence: Option<f32>,
     pub created_at: i64,
+    #[serde(default)]
+    pub process_chain: Vec<IncidentProcess>,
+    #[serde(default)]
+    pub rule_matches: Vec<String>,
+    #[serde(default)]
+    pub key_features: Vec<IncidentFeature>,
+    /// Delta encoding: indexes into `SyncIncidentsRequest::processes`, appended to `process_chain`
+    #[serde(default)]
+    pub process_refs: Vec<usize>,
+}
+
+impl CreateIncident {
+    /// Replace process references with the shared processes of the batch
+    pub fn resolve_process_refs(&mut self, processes: &[IncidentProcess]) -> Result<(), String> {
+        for index in std::mem::take(&mut self.process_refs) {
+            let process = processes.get(index)
+                .ok_or_else(|| format!("Process reference {} out of range", index))?;
+            self.process_chain.push(process.clone());
+        }
+        Ok(())
+    }
+}
+
+/// One process of the incident chain as reported by the agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentProcess {
+    pub pid: u32,
+    pub name: String,
+    #[serde(default)]
+    pub exe_path: Option<String>,
+    #[serde(default)]
+    pub cmdline: Option<String>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    #[serde(default)]
+    pub md5: Option<String>,
+    /// trusted | signed_untrusted | unsigned | invalid | revoked | unknown
+    #[serde(default)]
+    pub signature: Option<String>,
+    #[serde(default)]
+    pub publisher: Option<String>,
+    #[serde(default)]
+    pub exited: bool,
+}
+
+/// Feature contribution behind an ML verdict
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentFeature {
+    pub name: String,
+    pub importance: f32,
+    #[serde(default)]
+    pub delta: Option<f32>,
+    #[serde(default)]
+    pub description: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SyncIncidentsRequest {
     pub incidents: Vec<CreateIncident>,
+    /// Delta encoding: processes shared by several incidents of the batch, sent once
+    #[serde(default)]
+    pub processes: Vec<IncidentProcess>,
 }
 
 #[derive(Debug, Serialize)]
@@ -45,6 +113,64 @@ pub struct SyncIncidentsResponse {
     pub server_time: i64,
 }
 
+/// Maximum note length in characters (matches the agent)
+pub const MAX_NOTE_CHARS: usize = 4000;
+/// Maximum disposition reason length in characters
+pub const MAX_DISPOSITION_CHARS: usize = 200;
+
+/// Analyst note on an incident, written from the console or on the agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentNote {
+    pub id: Uuid,
+    pub author: String,
+    pub text: String,
+    /// Reason behind the triage decision (e.g. "Known admin script")
+    #[serde(default)]
+    pub disposition: Option<String>,
+    /// `console` or `agent`
+    pub source: String,
+    /// Unix seconds
+    pub created_at: i64,
+}
+
+impl IncidentNote {
+    /// Trim and length-check note text and disposition
+    pub fn validate(&mut self) -> Result<(), String> {
+        self.text = self.text.trim().to_string();
+        if self.text.is_empty() {
+            return Err("Note text is empty".to_string());
+        }
+        if self.text.chars().count() > MAX_NOTE_CHARS {
+            return Err(format!("Note exceeds {} characters", MAX_NOTE_CHARS));
+        }
+        self.disposition = self.disposition.as_deref().map(str::trim).filter(|d| !d.is_empty()).map(str::to_string);
+        if self.disposition.as_ref().is_some_and(|d| d.chars().count() > MAX_DISPOSITION_CHARS) {
+            return Err(format!("Disposition exceeds {} characters", MAX_DISPOSITION_CHARS));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateIncidentNote {
+    pub text: String,
+    #[serde(default)]
+    pub disposition: Option<String>,
+}
+
+/// Note created on the agent
+#[derive(Debug, Deserialize)]
+pub struct AgentIncidentNote {
+    pub incident_id: Uuid,
+    #[serde(flatten)]
+    pub note: IncidentNote,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncIncidentNotesRequest {
+    pub notes: Vec<AgentIncidentNote>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UpdateIncidentStatus {
     pub status: String,
@@ -61,6 +187,8 @@ pub struct IncidentFilter {
 }
 
 impl Incident {
+    /// Insert, or update an incident the agent reported before (same id) - e.g. after its
+    /// severity was recalculated. Empty / missing fields keep the stored values.
     pub async fn create(
         pool: &PgPool,
         endpoint_id: Uuid,
@@ -68,18 +196,31 @@ impl Incident {
     ) -> Result<Self, sqlx::Error> {
         let mitre_json = data.mitre_techniques
             .map(|v| serde_json::to_value(v).unwrap());
+        let process_chain = serde_json::to_value(&data.process_chain).unwrap_or_default();
+        let rule_matches = serde_json::to_value(&data.rule_matches).unwrap_or_default();
+        let key_features = serde_json::to_value(&data.key_features).unwrap_or_default();
 
         let created = DateTime::from_timestamp(data.created_at, 0)
             .unwrap_or_else(Utc::now);
 
         sqlx::query_as::<_, Incident>(
             r#"
-            INSERT INTO incidents (id, endpoint_id, severity, title, description, mitre_techniques, threat_class, confidence, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            INSERT INTO incidents (id, endpoint_id, severity, title, description, mitre_techniques, threat_class, confidence, created_at,
+                                   process_chain, rule_matches, key_features)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
             ON CONFLICT (id) DO UPDATE SET
                 severity = EXCLUDED.severity,
                 title = EXCLUDED.title,
-                description = EXCLUDED.description,
+                description = COALESCE(EXCLUDED.description, incidents.description),
+                mitre_techniques = COALESCE(EXCLUDED.mitre_techniques, incidents.mitre_techniques),
+                threat_class = COALESCE(EXCLUDED.threat_class, incidents.threat_class),
+                confidence = COALESCE(EXCLUDED.confidence, incidents.confidence),
+                process_chain = CASE WHEN EXCLUDED.process_chain = '[]'::jsonb
+                                     THEN incidents.process_chain ELSE EXCLUDED.process_chain END,
+                rule_matches = CASE WHEN EXCLUDED.rule_matches = '[]'::jsonb
+                                    THEN incidents.rule_matches ELSE EXCLUDED.rule_matches END,
+                key_features = CASE WHEN EXCLUDED.key_features = '[]'::jsonb
+                                    THEN incidents.key_features ELSE EXCLUDED.key_features END,
                 updated_at = NOW()
             RETURNING *
             "#
@@ -93,6 +234,9 @@ impl Incident {
         .bind(&data.threat_class)
         .bind(data.confidence)
         .bind(created)
+        .bind(&process_chain)
+        .bind(&rule_matches)
+        .bind(&key_features)
         .fetch_one(pool)
         .await
     }
@@ -150,6 +294,37 @@ impl Incident {
         .await
     }
 
+    /// Append a note unless one with the same id is already attached.
+    /// `endpoint_id` restricts the update to incidents reported by that endpoint.
+    /// Returns `None` if the incident does not exist (or belongs to another endpoint).
+    pub async fn add_note(
+        pool: &PgPool,
+        id: Uuid,
+        endpoint_id: Option<Uuid>,
+        note: &IncidentNote,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let note_json = serde_json::to_value(note).unwrap_or_default();
+
+        sqlx::query_as::<_, Incident>(
+            r#"
+            UPDATE incidents
+            SET notes = CASE
+                    WHEN notes @> jsonb_build_array(jsonb_build_object('id', $3::text)) THEN notes
+                    ELSE notes || jsonb_build_array($4::jsonb)
+                END,
+                updated_at = NOW()
+            WHERE id = $1 AND ($2::uuid IS NULL OR endpoint_id = $2)
+            RETURNING *
+            "#
+        )
+        .bind(id)
+        .bind(endpoint_id)
+        .bind(note.id.to_string())
+        .bind(&note_json)
+        .fetch_optional(pool)
+        .await
+    }
+
     pub async fn count_by_severity(pool: &PgPool, org_id: Uuid) -> Result<Vec<(String, i64)>, sqlx::Error> {
         let rows = sqlx::query(
             r#"
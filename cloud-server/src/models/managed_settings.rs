@@ -0,0 +1,248 @@
+//! Managed agent settings model
+//!
+//! Settings the console pushes to agents, stored per organization (group ""
+//! = defaults for every endpoint) and per named endpoint group (overrides
+//! the defaults section by section). Every change - settings or an
+//! endpoint's group assignment - bumps the organization's settings revision,
+//! which agents compare against the revision they applied.
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+/// Sections understood by the agent
+pub const MANAGED_SETTINGS_SECTIONS: &[&str] = &["features", "sync", "syslog", "elastic", "otel"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ManagedSettings {
+    pub id: Uuid,
+    pub org_id: Uuid,
+    /// "" = organization defaults
+    pub group_name: String,
+    /// `{ section: { key: value } }`
+    pub settings: serde_json::Value,
+    /// Org settings revision at the last change
+    pub revision: i64,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateManagedSettings {
+    /// None = organization defaults
+    pub group: Option<String>,
+    pub settings: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ManagedSettingsGroupQuery {
+    pub group: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssignSettingsGroup {
+    /// None = organization defaults only
+    pub group: Option<String>,
+}
+
+/// Effective settings as delivered to an agent
+#[derive(Debug, Serialize)]
+pub struct ManagedSettingsBundle {
+    pub version: i64,
+    pub group: Option<String>,
+    pub settings: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EndpointSettingsStatus {
+    pub endpoint_id: Uuid,
+    pub hostname: String,
+    pub settings_group: Option<String>,
+    pub settings_version: i64,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    pub is_stale: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManagedSettingsDeployment {
+    pub latest_version: i64,
+    pub up_to_date: usize,
+    pub stale: usize,
+    pub endpoints: Vec<EndpointSettingsStatus>,
+}
+
+impl ManagedSettings {
+    pub async fn list_by_org(pool: &PgPool, org_id: Uuid) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, ManagedSettings>(
+            "SELECT * FROM managed_settings WHERE org_id = $1 ORDER BY group_name"
+        )
+        .bind(org_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Replace the settings of a group (or the org defaults)
+    pub async fn upsert(
+        pool: &PgPool,
+        org_id: Uuid,
+        group_name: &str,
+        settings: &serde_json::Value,
+        updated_by: Option<Uuid>,
+    ) -> Result<Self, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let revision = bump_revision(&mut tx, org_id).await?;
+
+        let row = sqlx::query_as::<_, ManagedSettings>(
+            r#"
+            INSERT INTO managed_settings (org_id, group_name, settings, revision, updated_by)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (org_id, group_name) DO UPDATE
+            SET settings = EXCLUDED.settings,
+                revision = EXCLUDED.revision,
+                updated_by = EXCLUDED.updated_by,
+                updated_at = NOW()
+            RETURNING *
+            "#
+        )
+        .bind(org_id)
+        .bind(group_name)
+        .bind(settings)
+        .bind(revision)
+        .bind(updated_by)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(row)
+    }
+
+    pub async fn delete(pool: &PgPool, org_id: Uuid, group_name: &str) -> Result<bool, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let result = sqlx::query("DELETE FROM managed_settings WHERE org_id = $1 AND group_name = $2")
+            .bind(org_id)
+            .bind(group_name)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        bump_revision(&mut tx, org_id).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Move an endpoint to a settings group (None = org defaults only)
+    pub async fn assign_group(
+        pool: &PgPool,
+        org_id: Uuid,
+        endpoint_id: Uuid,
+        group_name: Option<&str>,
+    ) -> Result<bool, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let result = sqlx::query(
+            "UPDATE endpoints SET settings_group = $3, updated_at = NOW() WHERE id = $1 AND org_id = $2"
+        )
+        .bind(endpoint_id)
+        .bind(org_id)
+        .bind(group_name)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        bump_revision(&mut tx, org_id).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Current org settings revision (0 = never configured)
+    pub async fn revision(pool: &PgPool, org_id: Uuid) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT settings_revision FROM organizations WHERE id = $1")
+            .bind(org_id)
+            .fetch_optional(pool)
+            .await
+            .map(|r| r.unwrap_or(0))
+    }
+
+    /// Org defaults merged with the endpoint's group, at the current revision
+    pub async fn effective_for_endpoint(
+        pool: &PgPool,
+        org_id: Uuid,
+        endpoint_id: Uuid,
+    ) -> Result<ManagedSettingsBundle, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let version: i64 = sqlx::query_scalar("SELECT settings_revision FROM organizations WHERE id = $1")
+            .bind(org_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .unwrap_or(0);
+        let group: Option<String> = sqlx::query_scalar("SELECT settings_group FROM endpoints WHERE id = $1")
+            .bind(endpoint_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .flatten();
+        let rows = sqlx::query_as::<_, ManagedSettings>(
+            r#"
+            SELECT * FROM managed_settings
+            WHERE org_id = $1 AND (group_name = '' OR group_name = $2)
+            "#
+        )
+        .bind(org_id)
+        .bind(group.as_deref().unwrap_or(""))
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        let defaults = rows.iter().find(|r| r.group_name.is_empty()).map(|r| &r.settings);
+        let overrides = rows.iter().find(|r| !r.group_name.is_empty()).map(|r| &r.settings);
+        Ok(ManagedSettingsBundle {
+            version,
+            group,
+            settings: merge_settings(defaults, overrides),
+        })
+    }
+}
+
+/// Group values replace org defaults key by key inside each section
+pub fn merge_settings(
+    defaults: Option<&serde_json::Value>,
+    overrides: Option<&serde_json::Value>,
+) -> serde_json::Value {
+    let mut merged = defaults
+        .and_then(|d| d.as_object())
+        .cloned()
+        .unwrap_or_default();
+
+    for (section, values) in overrides.and_then(|o| o.as_object()).into_iter().flatten() {
+        match (merged.get_mut(section).and_then(|s| s.as_object_mut()), values.as_object()) {
+            (Some(existing), Some(values)) => {
+                for (key, value) in values {
+                    existing.insert(key.clone(), value.clone());
+                }
+            }
+            _ => {
+                merged.insert(section.clone(), values.clone());
+            }
+        }
+    }
+    serde_json::Value::Object(merged)
+}
+
+/// Serialized per organization by the row lock on `organizations`
+async fn bump_revision(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    org_id: Uuid,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        UPDATE organizations
+        SET settings_revision = settings_revision + 1
+        WHERE id = $1
+        RETURNING settings_revision
+        "#
+    )
+    .bind(org_id)
+    .fetch_one(&mut **tx)
+    .await
+}
@@ -7,6 +7,13 @@ pub mod incident;
 pub mod policy;
 pub mod baseline;
 pub mod token;
+pub mod command;
+pub mod rule_pack;
+pub mod never_learn;
+pub mod prevalence;
+pub mod telemetry;
+pub mod training;
+pub mod managed_settings;
 
 pub use organization::*;
 pub use user::*;
@@ -15,3 +22,10 @@ pub use incident::*;
 pub use policy::*;
 pub use baseline::*;
 pub use token::*;
+pub use command::*;
+pub use rule_pack::*;
+pub use never_learn::*;
+pub use prevalence::*;
+pub use telemetry::*;
+pub use training::*;
+pub use managed_settings::*;
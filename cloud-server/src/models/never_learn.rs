@@ -0,0 +1,222 @@
+//! Never-learn list model
+//!
+//! Per-organization entries (process names, hashes, network endpoints) that
+//! agent baselines must never absorb, plus the never-learn decisions agents
+//! made locally and reported upstream. Every change bumps the org revision so
+//! agents know when to re-fetch the list.
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+use chrono::{DateTime, TimeZone, Utc};
+
+/// Entry kinds understood by the agent
+pub const NEVER_LEARN_KINDS: &[&str] = &["process", "hash", "endpoint"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NeverLearnEntry {
+    pub id: Uuid,
+    pub org_id: Uuid,
+    /// "process" | "hash" | "endpoint"
+    pub kind: String,
+    /// Lowercased process name, SHA-256 or endpoint pattern
+    pub value: String,
+    pub reason: Option<String>,
+    /// Org revision at which the entry was created (or deleted)
+    pub revision: i64,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateNeverLearnEntry {
+    pub kind: String,
+    pub value: String,
+    pub reason: Option<String>,
+}
+
+/// Active entries as delivered to agents
+#[derive(Debug, Serialize)]
+pub struct NeverLearnList {
+    pub revision: i64,
+    pub entries: Vec<NeverLearnItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NeverLearnItem {
+    pub kind: String,
+    pub value: String,
+}
+
+/// Never-learn decision made locally by an agent
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct NeverLearnDecision {
+    pub id: Uuid,
+    pub org_id: Uuid,
+    pub endpoint_id: Uuid,
+    /// Reason kind reported by the agent (e.g. "process_blacklisted", "custom_rule")
+    pub kind: String,
+    pub value: String,
+    pub reason: String,
+    pub process_name: Option<String>,
+    pub decided_at: DateTime<Utc>,
+    pub received_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportNeverLearnDecisions {
+    pub decisions: Vec<NeverLearnDecisionReport>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NeverLearnDecisionReport {
+    pub kind: String,
+    pub value: String,
+    pub reason: String,
+    pub process_name: Option<String>,
+    /// Unix seconds
+    pub decided_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportNeverLearnResponse {
+    pub accepted: usize,
+    pub server_time: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NeverLearnDecisionFilter {
+    pub endpoint_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+impl NeverLearnEntry {
+    pub async fn create(
+        pool: &PgPool,
+        org_id: Uuid,
+        created_by: Option<Uuid>,
+        data: CreateNeverLearnEntry,
+    ) -> Result<Self, sqlx::Error> {
+        sqlx::query_as::<_, NeverLearnEntry>(
+            r#"
+            INSERT INTO never_learn_entries (org_id, kind, value, reason, revision, created_by)
+            VALUES (
+                $1, $2, $3, $4,
+                (SELECT COALESCE(MAX(revision), 0) + 1 FROM never_learn_entries WHERE org_id = $1),
+                $5
+            )
+            RETURNING *
+            "#
+        )
+        .bind(org_id)
+        .bind(data.kind.trim().to_lowercase())
+        .bind(data.value.trim().to_lowercase())
+        .bind(&data.reason)
+        .bind(created_by)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as::<_, NeverLearnEntry>("SELECT * FROM never_learn_entries WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    /// Active entries (not deleted)
+    pub async fn list_active(pool: &PgPool, org_id: Uuid) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, NeverLearnEntry>(
+            "SELECT * FROM never_learn_entries WHERE org_id = $1 AND deleted_at IS NULL ORDER BY created_at DESC"
+        )
+        .bind(org_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Soft delete so the revision still moves forward for agents
+    pub async fn delete(pool: &PgPool, id: Uuid, org_id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE never_learn_entries
+            SET deleted_at = NOW(),
+                revision = (SELECT COALESCE(MAX(revision), 0) + 1 FROM never_learn_entries WHERE org_id = $2)
+            WHERE id = $1 AND org_id = $2 AND deleted_at IS NULL
+            "#
+        )
+        .bind(id)
+        .bind(org_id)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn revision(pool: &PgPool, org_id: Uuid) -> Result<i64, sqlx::Error> {
+        let revision: Option<i64> = sqlx::query_scalar(
+            "SELECT MAX(revision) FROM never_learn_entries WHERE org_id = $1"
+        )
+        .bind(org_id)
+        .fetch_one(pool)
+        .await?;
+        Ok(revision.unwrap_or(0))
+    }
+
+    pub async fn list_for_agent(pool: &PgPool, org_id: Uuid) -> Result<NeverLearnList, sqlx::Error> {
+        let revision = Self::revision(pool, org_id).await?;
+        let entries = Self::list_active(pool, org_id)
+            .await?
+            .into_iter()
+            .map(|e| NeverLearnItem { kind: e.kind, value: e.value })
+            .collect();
+        Ok(NeverLearnList { revision, entries })
+    }
+}
+
+impl NeverLearnDecision {
+    pub async fn create(
+        pool: &PgPool,
+        org_id: Uuid,
+        endpoint_id: Uuid,
+        data: &NeverLearnDecisionReport,
+    ) -> Result<Self, sqlx::Error> {
+        let decided_at = Utc.timestamp_opt(data.decided_at, 0).single().unwrap_or_else(Utc::now);
+
+        sqlx::query_as::<_, NeverLearnDecision>(
+            r#"
+            INSERT INTO never_learn_decisions (org_id, endpoint_id, kind, value, reason, process_name, decided_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#
+        )
+        .bind(org_id)
+        .bind(endpoint_id)
+        .bind(&data.kind)
+        .bind(&data.value)
+        .bind(&data.reason)
+        .bind(&data.process_name)
+        .bind(decided_at)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn list_by_org(
+        pool: &PgPool,
+        org_id: Uuid,
+        filter: NeverLearnDecisionFilter,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, NeverLearnDecision>(
+            r#"
+            SELECT * FROM never_learn_decisions
+            WHERE org_id = $1 AND ($2::uuid IS NULL OR endpoint_id = $2)
+            ORDER BY decided_at DESC
+            LIMIT $3
+            "#
+        )
+        .bind(org_id)
+        .bind(filter.endpoint_id)
+        .bind(filter.limit.unwrap_or(100).clamp(1, 1000))
+        .fetch_all(pool)
+        .await
+    }
+}
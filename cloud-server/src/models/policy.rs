@@ -28,6 +28,54 @@ pub struct PolicyConfig {
     pub enable_iat_analysis: bool,
     pub auto_quarantine: bool,
     pub notification_channels: Vec<String>,
+    /// Action-guard whitelist pushed to agents
+    #[serde(default)]
+    pub whitelist: Vec<WhitelistRule>,
+    /// Partial Action Guard config override (thresholds, auto_execute, cooldown_secs, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_guard: Option<serde_json::Value>,
+    /// Extra trusted code-signing publishers (org internal certs, vendors)
+    #[serde(default)]
+    pub trusted_publishers: Vec<TrustedPublisherRule>,
+    /// Partial VirusTotal config override (api_key, requests_per_minute, daily_limit, cache_ttl_hours)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub virustotal: Option<serde_json::Value>,
+    /// MISP integration (enabled, url, api_key, pull_interval_mins, lookback_days, to_ids_only, tags, push_sightings)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub misp: Option<serde_json::Value>,
+    /// TAXII 2.1 subscriptions ({ collections: [{ name, api_root, collection_id, username, password, ... }] })
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taxii: Option<serde_json::Value>,
+    /// AlienVault OTX override (enabled, api_key, pull_interval_mins, lookback_days)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otx: Option<serde_json::Value>,
+    /// GeoIP override (enabled, high_risk_countries: ["KP", ...], alert_on_high_risk)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geoip: Option<serde_json::Value>,
+    /// Cloud connection proxy override (mode: system | direct | manual | pac, url, pac_url, username, password, bypass)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<serde_json::Value>,
+}
+
+/// Whitelist entry (kind: name | path | sha256 | publisher)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhitelistRule {
+    pub kind: String,
+    pub value: String,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// Trusted publisher: CN and/or cert thumbprint (both must match when set).
+/// Thumbprint-pinned entries also trust certs chaining to an internal root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedPublisherRule {
+    #[serde(default)]
+    pub publisher: Option<String>,
+    #[serde(default)]
+    pub thumbprint: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
 }
 
 impl Default for PolicyConfig {
@@ -41,6 +89,15 @@ impl Default for PolicyConfig {
             enable_iat_analysis: true,
             auto_quarantine: false,
             notification_channels: vec!["dashboard".to_string()],
+            whitelist: Vec::new(),
+            action_guard: None,
+            trusted_publishers: Vec::new(),
+            virustotal: None,
+            misp: None,
+            taxii: None,
+            otx: None,
+            geoip: None,
+            proxy: None,
         }
     }
 }
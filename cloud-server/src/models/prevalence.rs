@@ -0,0 +1,156 @@
+//! File prevalence model
+//!
+//! Which endpoints in an organization have executed a given binary (by SHA-256).
+//! Agents report hashes they see and query the fleet count, so binaries that
+//! only ever appear on one or two machines stand out.
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+use chrono::{DateTime, TimeZone, Utc};
+
+/// Max hashes per report / query request
+pub const MAX_PREVALENCE_BATCH: usize = 500;
+
+/// Aggregated prevalence of one hash across the organization
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FilePrevalence {
+    pub sha256: String,
+    /// Number of distinct endpoints that reported the hash
+    pub endpoint_count: i64,
+    /// Any endpoint reported a valid signature
+    pub is_signed: bool,
+    pub file_name: Option<String>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportPrevalenceRequest {
+    pub sightings: Vec<PrevalenceSighting>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PrevalenceSighting {
+    pub sha256: String,
+    pub file_name: Option<String>,
+    #[serde(default)]
+    pub signed: bool,
+    /// Unix seconds
+    pub seen_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportPrevalenceResponse {
+    pub accepted: usize,
+    pub server_time: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PrevalenceQueryRequest {
+    pub hashes: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PrevalenceQueryResponse {
+    /// Endpoints registered in the organization
+    pub total_endpoints: i64,
+    /// Hashes never reported by any endpoint are omitted
+    pub results: Vec<FilePrevalence>,
+}
+
+impl FilePrevalence {
+    /// Upsert a sighting for (org, hash, endpoint)
+    pub async fn record(
+        pool: &PgPool,
+        org_id: Uuid,
+        endpoint_id: Uuid,
+        sighting: &PrevalenceSighting,
+    ) -> Result<(), sqlx::Error> {
+        let seen_at = Utc.timestamp_opt(sighting.seen_at, 0).single().unwrap_or_else(Utc::now);
+
+        sqlx::query(
+            r#"
+            INSERT INTO file_prevalence (org_id, sha256, endpoint_id, file_name, is_signed, first_seen, last_seen)
+            VALUES ($1, $2, $3, $4, $5, $6, $6)
+            ON CONFLICT (org_id, sha256, endpoint_id) DO UPDATE SET
+                file_name = COALESCE(EXCLUDED.file_name, file_prevalence.file_name),
+                is_signed = EXCLUDED.is_signed,
+                first_seen = LEAST(file_prevalence.first_seen, EXCLUDED.first_seen),
+                last_seen = GREATEST(file_prevalence.last_seen, EXCLUDED.last_seen)
+            "#
+        )
+        .bind(org_id)
+        .bind(sighting.sha256.trim().to_lowercase())
+        .bind(endpoint_id)
+        .bind(&sighting.file_name)
+        .bind(sighting.signed)
+        .bind(seen_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Prevalence for a batch of hashes
+    pub async fn lookup(pool: &PgPool, org_id: Uuid, hashes: &[String]) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, FilePrevalence>(
+            r#"
+            SELECT sha256,
+                   COUNT(*) AS endpoint_count,
+                   BOOL_OR(is_signed) AS is_signed,
+                   MAX(file_name) AS file_name,
+                   MIN(first_seen) AS first_seen,
+                   MAX(last_seen) AS last_seen
+            FROM file_prevalence
+            WHERE org_id = $1 AND sha256 = ANY($2)
+            GROUP BY sha256
+            "#
+        )
+        .bind(org_id)
+        .bind(hashes)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn total_endpoints(pool: &PgPool, org_id: Uuid) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM endpoints WHERE org_id = $1")
+            .bind(org_id)
+            .fetch_one(pool)
+            .await
+    }
+
+    /// Least prevalent hashes in the organization (rarest first)
+    pub async fn rarest(pool: &PgPool, org_id: Uuid, limit: i64) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, FilePrevalence>(
+            r#"
+            SELECT sha256,
+                   COUNT(*) AS endpoint_count,
+                   BOOL_OR(is_signed) AS is_signed,
+                   MAX(file_name) AS file_name,
+                   MIN(first_seen) AS first_seen,
+                   MAX(last_seen) AS last_seen
+            FROM file_prevalence
+            WHERE org_id = $1
+            GROUP BY sha256
+            ORDER BY COUNT(*) ASC, MAX(last_seen) DESC
+            LIMIT $2
+            "#
+        )
+        .bind(org_id)
+        .bind(limit.clamp(1, 1000))
+        .fetch_all(pool)
+        .await
+    }
+}
+
+/// Lowercase, deduplicate and drop anything that is not a SHA-256
+pub fn normalize_hashes(hashes: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = hashes
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .filter(|h| h.len() == 64 && h.chars().all(|c| c.is_ascii_hexdigit()))
+        .collect();
+    out.sort();
+    out.dedup();
+    out
+}
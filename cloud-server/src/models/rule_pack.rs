@@ -0,0 +1,163 @@
+//! Detection rule pack model
+//!
+//! Versioned bundle of behavioral rules (JSON array) + YARA rules per
+//! organization. Agents report the version they run in heartbeats so the
+//! console can show endpoints with stale detections.
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use sha2::{Sha256, Digest};
+use ed25519_dalek::SigningKey;
+
+use crate::signing;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RulePack {
+    pub id: Uuid,
+    pub org_id: Uuid,
+    pub version: i32,
+    pub name: String,
+    pub description: Option<String>,
+    /// Behavioral rules as JSON array text (exact bytes are hashed)
+    pub behavioral_rules: String,
+    pub yara_rules: String,
+    /// SHA-256 of the pack content (see `content_hash`)
+    pub sha256: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRulePack {
+    pub name: String,
+    pub description: Option<String>,
+    /// Behavioral rule definitions (same format as the agent `rules/` directory)
+    #[serde(default)]
+    pub behavioral_rules: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub yara_rules: String,
+}
+
+/// Rule pack as delivered to an agent, signed with the content signing key
+#[derive(Debug, Serialize)]
+pub struct SignedRulePack {
+    pub version: i32,
+    pub name: String,
+    pub behavioral_rules: String,
+    pub yara_rules: String,
+    pub sha256: String,
+    /// Hex ed25519 signature over "{version}:{sha256}"
+    pub signature: String,
+}
+
+/// Rule pack rollout state of one endpoint
+#[derive(Debug, Serialize)]
+pub struct EndpointRulePackStatus {
+    pub endpoint_id: Uuid,
+    pub hostname: String,
+    pub rule_pack_version: i32,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    pub is_stale: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RulePackDeployment {
+    pub latest_version: i32,
+    pub up_to_date: usize,
+    pub stale: usize,
+    pub endpoints: Vec<EndpointRulePackStatus>,
+}
+
+/// SHA-256 over behavioral rules text + YARA text
+pub fn content_hash(behavioral_rules: &str, yara_rules: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(behavioral_rules.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(yara_rules.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+impl RulePack {
+    pub async fn create(
+        pool: &PgPool,
+        org_id: Uuid,
+        created_by: Option<Uuid>,
+        data: CreateRulePack,
+    ) -> Result<Self, sqlx::Error> {
+        let behavioral_rules = serde_json::to_string(&data.behavioral_rules)
+            .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+        let sha256 = content_hash(&behavioral_rules, &data.yara_rules);
+
+        sqlx::query_as::<_, RulePack>(
+            r#"
+            INSERT INTO rule_packs (org_id, version, name, description, behavioral_rules, yara_rules, sha256, created_by)
+            VALUES (
+                $1,
+                (SELECT COALESCE(MAX(version), 0) + 1 FROM rule_packs WHERE org_id = $1),
+                $2, $3, $4, $5, $6, $7
+            )
+            RETURNING *
+            "#
+        )
+        .bind(org_id)
+        .bind(&data.name)
+        .bind(&data.description)
+        .bind(&behavioral_rules)
+        .bind(&data.yara_rules)
+        .bind(&sha256)
+        .bind(created_by)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as::<_, RulePack>("SELECT * FROM rule_packs WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    pub async fn list_by_org(pool: &PgPool, org_id: Uuid) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, RulePack>(
+            "SELECT * FROM rule_packs WHERE org_id = $1 ORDER BY version DESC"
+        )
+        .bind(org_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn get_latest(pool: &PgPool, org_id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as::<_, RulePack>(
+            "SELECT * FROM rule_packs WHERE org_id = $1 ORDER BY version DESC LIMIT 1"
+        )
+        .bind(org_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn latest_version(pool: &PgPool, org_id: Uuid) -> Result<i32, sqlx::Error> {
+        let version: Option<i32> = sqlx::query_scalar(
+            "SELECT MAX(version) FROM rule_packs WHERE org_id = $1"
+        )
+        .bind(org_id)
+        .fetch_one(pool)
+        .await?;
+        Ok(version.unwrap_or(0))
+    }
+
+    /// Sign the pack (agents verify with their pinned public key)
+    pub fn sign(&self, key: &SigningKey) -> SignedRulePack {
+        let signature = signing::sign(key, format!("{}:{}", self.version, self.sha256).as_bytes());
+
+        SignedRulePack {
+            version: self.version,
+            name: self.name.clone(),
+            behavioral_rules: self.behavioral_rules.clone(),
+            yara_rules: self.yara_rules.clone(),
+            sha256: self.sha256.clone(),
+            signature,
+        }
+    }
+}
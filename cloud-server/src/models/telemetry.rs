@@ -0,0 +1,323 @@
+//! Endpoint telemetry model
+//!
+//! Sampled security events and engine stats uploaded by agents in zstd-compressed
+//! batches. Lets the console show endpoint activity beyond synced incidents.
+//! Events are idempotent per (endpoint, event id), so a retried batch is not stored twice.
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+use chrono::{DateTime, Duration, Utc};
+
+/// Max events per upload batch
+pub const MAX_TELEMETRY_BATCH: usize = 1000;
+/// Max engine stats snapshots per upload batch
+pub const MAX_STATS_BATCH: usize = 200;
+/// Max decompressed body size for a telemetry upload
+pub const MAX_TELEMETRY_BODY: usize = 16 * 1024 * 1024;
+
+/// Stored security event
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TelemetryEvent {
+    pub id: i64,
+    pub endpoint_id: Uuid,
+    pub event_id: String,
+    pub event_type: String,
+    pub severity: Option<String>,
+    pub process_name: Option<String>,
+    pub description: String,
+    /// Full event as sent by the agent
+    pub data: serde_json::Value,
+    pub occurred_at: DateTime<Utc>,
+    pub received_at: DateTime<Utc>,
+}
+
+/// Stored engine stats snapshot
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EngineStatsSample {
+    pub endpoint_id: Uuid,
+    pub stats: serde_json::Value,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Telemetry volume per endpoint over a time window
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct EndpointTelemetrySummary {
+    pub endpoint_id: Uuid,
+    pub hostname: String,
+    pub event_count: i64,
+    pub high_severity_count: i64,
+    pub last_event_at: Option<DateTime<Utc>>,
+    /// Events the agent dropped by sampling (all time)
+    pub sampled_out: i64,
+    pub last_upload_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadTelemetryRequest {
+    #[serde(default)]
+    pub events: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub stats: Vec<serde_json::Value>,
+    /// Events dropped by sampling on the agent since the previous batch
+    #[serde(default)]
+    pub sampled_out: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadTelemetryResponse {
+    pub accepted_events: usize,
+    pub accepted_stats: usize,
+    pub server_time: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TelemetryEventFilter {
+    pub endpoint_id: Option<Uuid>,
+    pub event_type: Option<String>,
+    pub severity: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Fields the server indexes from an agent event
+#[derive(Debug, Deserialize)]
+struct EventHeader {
+    id: String,
+    timestamp: DateTime<Utc>,
+    event_type: String,
+    severity: Option<String>,
+    process: Option<EventProcess>,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventProcess {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatsHeader {
+    timestamp: DateTime<Utc>,
+}
+
+impl TelemetryEvent {
+    /// Insert a batch of agent events, skipping malformed and already stored ones.
+    /// Returns the number of new rows.
+    pub async fn insert_batch(
+        pool: &PgPool,
+        org_id: Uuid,
+        endpoint_id: Uuid,
+        events: &[serde_json::Value],
+    ) -> Result<u64, sqlx::Error> {
+        let mut event_ids = Vec::with_capacity(events.len());
+        let mut event_types = Vec::with_capacity(events.len());
+        let mut severities = Vec::with_capacity(events.len());
+        let mut process_names = Vec::with_capacity(events.len());
+        let mut descriptions = Vec::with_capacity(events.len());
+        let mut data = Vec::with_capacity(events.len());
+        let mut occurred_at = Vec::with_capacity(events.len());
+
+        for event in events {
+            let header: EventHeader = match serde_json::from_value(event.clone()) {
+                Ok(header) => header,
+                Err(e) => {
+                    tracing::debug!("Skipping malformed telemetry event from {}: {}", endpoint_id, e);
+                    continue;
+                }
+            };
+            event_ids.push(truncate(header.id, 64));
+            event_types.push(truncate(header.event_type, 50));
+            severities.push(header.severity.map(|s| s.to_lowercase()));
+            process_names.push(header.process.map(|p| truncate(p.name, 255)));
+            descriptions.push(header.description);
+            data.push(event.clone());
+            occurred_at.push(header.timestamp);
+        }
+
+        if event_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO telemetry_events
+                (org_id, endpoint_id, event_id, event_type, severity, process_name, description, data, occurred_at)
+            SELECT $1, $2, * FROM UNNEST(
+                $3::varchar[], $4::varchar[], $5::varchar[], $6::varchar[],
+                $7::text[], $8::jsonb[], $9::timestamptz[]
+            )
+            ON CONFLICT (endpoint_id, event_id) DO NOTHING
+            "#
+        )
+        .bind(org_id)
+        .bind(endpoint_id)
+        .bind(&event_ids)
+        .bind(&event_types)
+        .bind(&severities)
+        .bind(&process_names)
+        .bind(&descriptions)
+        .bind(&data)
+        .bind(&occurred_at)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn list_by_org(
+        pool: &PgPool,
+        org_id: Uuid,
+        filter: TelemetryEventFilter,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let limit = filter.limit.unwrap_or(100).clamp(1, 1000);
+        let offset = filter.offset.unwrap_or(0).max(0);
+
+        sqlx::query_as::<_, TelemetryEvent>(
+            r#"
+            SELECT id, endpoint_id, event_id, event_type, severity, process_name,
+                   description, data, occurred_at, received_at
+            FROM telemetry_events
+            WHERE org_id = $1
+              AND ($2::uuid IS NULL OR endpoint_id = $2)
+              AND ($3::varchar IS NULL OR event_type = $3)
+              AND ($4::varchar IS NULL OR severity = LOWER($4))
+              AND ($5::timestamptz IS NULL OR occurred_at >= $5)
+              AND ($6::timestamptz IS NULL OR occurred_at <= $6)
+            ORDER BY occurred_at DESC
+            LIMIT $7 OFFSET $8
+            "#
+        )
+        .bind(org_id)
+        .bind(filter.endpoint_id)
+        .bind(filter.event_type)
+        .bind(filter.severity)
+        .bind(filter.since)
+        .bind(filter.until)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+impl EngineStatsSample {
+    /// Insert engine stats snapshots, skipping ones without a timestamp
+    pub async fn insert_batch(
+        pool: &PgPool,
+        org_id: Uuid,
+        endpoint_id: Uuid,
+        stats: &[serde_json::Value],
+    ) -> Result<u64, sqlx::Error> {
+        let mut data = Vec::with_capacity(stats.len());
+        let mut recorded_at = Vec::with_capacity(stats.len());
+        for sample in stats {
+            if let Ok(header) = serde_json::from_value::<StatsHeader>(sample.clone()) {
+                data.push(sample.clone());
+                recorded_at.push(header.timestamp);
+            }
+        }
+
+        if data.is_empty() {
+            return Ok(0);
+        }
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO engine_stats (org_id, endpoint_id, stats, recorded_at)
+            SELECT $1, $2, * FROM UNNEST($3::jsonb[], $4::timestamptz[])
+            "#
+        )
+        .bind(org_id)
+        .bind(endpoint_id)
+        .bind(&data)
+        .bind(&recorded_at)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Stats for one endpoint over the last `hours`, oldest first (for charts)
+    pub async fn list_by_endpoint(
+        pool: &PgPool,
+        endpoint_id: Uuid,
+        hours: i64,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let since = Utc::now() - Duration::hours(hours.clamp(1, 24 * 30));
+
+        sqlx::query_as::<_, EngineStatsSample>(
+            r#"
+            SELECT endpoint_id, stats, recorded_at FROM (
+                SELECT endpoint_id, stats, recorded_at
+                FROM engine_stats
+                WHERE endpoint_id = $1 AND recorded_at >= $2
+                ORDER BY recorded_at DESC
+                LIMIT $3
+            ) recent
+            ORDER BY recorded_at ASC
+            "#
+        )
+        .bind(endpoint_id)
+        .bind(since)
+        .bind(limit.clamp(1, 5000))
+        .fetch_all(pool)
+        .await
+    }
+}
+
+impl EndpointTelemetrySummary {
+    /// Record upload accounting on the endpoint (sampling counter, last upload)
+    pub async fn record_upload(pool: &PgPool, endpoint_id: Uuid, sampled_out: i64) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE endpoints
+            SET telemetry_sampled_out = telemetry_sampled_out + $2,
+                telemetry_last_upload = NOW()
+            WHERE id = $1
+            "#
+        )
+        .bind(endpoint_id)
+        .bind(sampled_out.max(0))
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Telemetry volume per endpoint in the organization over the last `hours`
+    pub async fn list_by_org(pool: &PgPool, org_id: Uuid, hours: i64) -> Result<Vec<Self>, sqlx::Error> {
+        let since = Utc::now() - Duration::hours(hours.clamp(1, 24 * 30));
+
+        sqlx::query_as::<_, EndpointTelemetrySummary>(
+            r#"
+            SELECT e.id AS endpoint_id,
+                   e.hostname,
+                   COUNT(t.id) AS event_count,
+                   COUNT(t.id) FILTER (WHERE t.severity IN ('high', 'critical')) AS high_severity_count,
+                   MAX(t.occurred_at) AS last_event_at,
+                   e.telemetry_sampled_out AS sampled_out,
+                   e.telemetry_last_upload AS last_upload_at
+            FROM endpoints e
+            LEFT JOIN telemetry_events t ON t.endpoint_id = e.id AND t.occurred_at >= $2
+            WHERE e.org_id = $1
+            GROUP BY e.id
+            ORDER BY COUNT(t.id) DESC, e.hostname
+            "#
+        )
+        .bind(org_id)
+        .bind(since)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+fn truncate(mut value: String, max_chars: usize) -> String {
+    if let Some((idx, _)) = value.char_indices().nth(max_chars) {
+        value.truncate(idx);
+    }
+    value
+}
@@ -0,0 +1,323 @@
+//! Training dataset model
+//!
+//! Anonymized dataset records uploaded by agents that opted in to central model
+//! training. Collection is gated per organization (`dataset_sharing`, off by default).
+//! Records carry no endpoint or host identifiers and are stored per org only,
+//! deduplicated by a content hash so retried batches are not stored twice.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+use chrono::{DateTime, TimeZone, Utc};
+
+/// Max records per upload batch
+pub const MAX_DATASET_BATCH: usize = 1000;
+/// Max decompressed body size for a dataset upload
+pub const MAX_DATASET_BODY: usize = 32 * 1024 * 1024;
+/// Max length of a feature / baseline diff vector
+const MAX_VECTOR_LEN: usize = 256;
+const THREAT_CLASSES: &[&str] = &["Benign", "Suspicious", "Malicious"];
+
+/// Organization opt-in for central model training
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DatasetSharing {
+    pub org_id: Uuid,
+    pub enabled: bool,
+    /// Upper bound on the agents' sample rate
+    pub max_sample_rate: f64,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateDatasetSharing {
+    pub enabled: bool,
+    pub max_sample_rate: Option<f64>,
+}
+
+/// Stored training record
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TrainingRecord {
+    pub id: i64,
+    pub feature_version: i16,
+    pub threat: String,
+    pub label: Option<String>,
+    /// Anonymized record as sent by the agent
+    pub record: serde_json::Value,
+    pub recorded_hour: DateTime<Utc>,
+    pub received_at: DateTime<Utc>,
+}
+
+/// Record count per feature version / decision / label
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TrainingDatasetBucket {
+    pub feature_version: i16,
+    pub threat: String,
+    pub label: Option<String>,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrainingDatasetSummary {
+    pub sharing: DatasetSharing,
+    pub total_records: i64,
+    pub labeled_records: i64,
+    /// Records agents read but dropped by sampling
+    pub sampled_out: i64,
+    pub last_received_at: Option<DateTime<Utc>>,
+    pub buckets: Vec<TrainingDatasetBucket>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadDatasetRequest {
+    #[serde(default)]
+    pub records: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub scanned: i64,
+    #[serde(default)]
+    pub sampled_out: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadDatasetResponse {
+    pub accepted: usize,
+    /// Org cap on the sample rate; agents use min(local, cap)
+    pub sample_rate: f64,
+    pub server_time: i64,
+}
+
+/// Page through records for a retraining job (`after_id` cursor, oldest first)
+#[derive(Debug, Deserialize)]
+pub struct TrainingRecordQuery {
+    pub after_id: Option<i64>,
+    pub feature_version: Option<i16>,
+    #[serde(default)]
+    pub labeled_only: bool,
+    pub limit: Option<i64>,
+}
+
+/// Fields the server validates and indexes from an uploaded record
+#[derive(Debug, Deserialize)]
+struct RecordHeader {
+    hour: i64,
+    feature_version: i16,
+    features: Vec<f32>,
+    baseline_diff: Vec<f32>,
+    threat: String,
+    label: Option<String>,
+}
+
+impl DatasetSharing {
+    /// Org setting, disabled if never configured
+    pub async fn get(pool: &PgPool, org_id: Uuid) -> Result<Self, sqlx::Error> {
+        let sharing = sqlx::query_as::<_, DatasetSharing>(
+            "SELECT org_id, enabled, max_sample_rate, updated_by, updated_at FROM dataset_sharing WHERE org_id = $1"
+        )
+        .bind(org_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(sharing.unwrap_or(DatasetSharing {
+            org_id,
+            enabled: false,
+            max_sample_rate: 0.1,
+            updated_by: None,
+            updated_at: None,
+        }))
+    }
+
+    pub async fn set(
+        pool: &PgPool,
+        org_id: Uuid,
+        user_id: Uuid,
+        req: &UpdateDatasetSharing,
+    ) -> Result<Self, sqlx::Error> {
+        sqlx::query_as::<_, DatasetSharing>(
+            r#"
+            INSERT INTO dataset_sharing (org_id, enabled, max_sample_rate, updated_by, updated_at)
+            VALUES ($1, $2, COALESCE($3, 0.1), $4, NOW())
+            ON CONFLICT (org_id) DO UPDATE
+            SET enabled = EXCLUDED.enabled,
+                max_sample_rate = COALESCE($3, dataset_sharing.max_sample_rate),
+                updated_by = EXCLUDED.updated_by,
+                updated_at = NOW()
+            RETURNING org_id, enabled, max_sample_rate, updated_by, updated_at
+            "#
+        )
+        .bind(org_id)
+        .bind(req.enabled)
+        .bind(req.max_sample_rate)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Add sampling counters reported with an upload
+    pub async fn record_upload(pool: &PgPool, org_id: Uuid, sampled_out: i64) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE dataset_sharing
+            SET sampled_out = sampled_out + $2, last_upload_at = NOW()
+            WHERE org_id = $1
+            "#
+        )
+        .bind(org_id)
+        .bind(sampled_out.max(0))
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
+
+impl TrainingRecord {
+    /// Insert a batch of anonymized records, skipping malformed and already stored ones.
+    /// Returns the number of new rows.
+    pub async fn insert_batch(
+        pool: &PgPool,
+        org_id: Uuid,
+        records: &[serde_json::Value],
+    ) -> Result<u64, sqlx::Error> {
+        let mut hashes = Vec::with_capacity(records.len());
+        let mut feature_versions = Vec::with_capacity(records.len());
+        let mut threats = Vec::with_capacity(records.len());
+        let mut labels = Vec::with_capacity(records.len());
+        let mut data = Vec::with_capacity(records.len());
+        let mut hours = Vec::with_capacity(records.len());
+
+        for record in records {
+            let Some(header) = validate(record) else {
+                tracing::debug!("Skipping malformed training record for org {}", org_id);
+                continue;
+            };
+            let Some(hour) = Utc.timestamp_millis_opt(header.hour).single() else { continue };
+
+            hashes.push(content_hash(record));
+            feature_versions.push(header.feature_version);
+            threats.push(header.threat);
+            labels.push(header.label);
+            data.push(record.clone());
+            hours.push(hour);
+        }
+
+        if hashes.is_empty() {
+            return Ok(0);
+        }
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO training_records
+                (org_id, record_hash, feature_version, threat, label, record, recorded_hour)
+            SELECT $1, * FROM UNNEST(
+                $2::varchar[], $3::smallint[], $4::varchar[], $5::varchar[],
+                $6::jsonb[], $7::timestamptz[]
+            )
+            ON CONFLICT (org_id, record_hash) DO NOTHING
+            "#
+        )
+        .bind(org_id)
+        .bind(&hashes)
+        .bind(&feature_versions)
+        .bind(&threats)
+        .bind(&labels)
+        .bind(&data)
+        .bind(&hours)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn list_by_org(
+        pool: &PgPool,
+        org_id: Uuid,
+        query: TrainingRecordQuery,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, TrainingRecord>(
+            r#"
+            SELECT id, feature_version, threat, label, record, recorded_hour, received_at
+            FROM training_records
+            WHERE org_id = $1
+              AND id > $2
+              AND ($3::smallint IS NULL OR feature_version = $3)
+              AND (NOT $4 OR label IS NOT NULL)
+            ORDER BY id ASC
+            LIMIT $5
+            "#
+        )
+        .bind(org_id)
+        .bind(query.after_id.unwrap_or(0))
+        .bind(query.feature_version)
+        .bind(query.labeled_only)
+        .bind(query.limit.unwrap_or(1000).clamp(1, 10_000))
+        .fetch_all(pool)
+        .await
+    }
+}
+
+impl TrainingDatasetSummary {
+    pub async fn for_org(pool: &PgPool, org_id: Uuid) -> Result<Self, sqlx::Error> {
+        let sharing = DatasetSharing::get(pool, org_id).await?;
+
+        let buckets = sqlx::query_as::<_, TrainingDatasetBucket>(
+            r#"
+            SELECT feature_version, threat, label, COUNT(*) AS count
+            FROM training_records
+            WHERE org_id = $1
+            GROUP BY feature_version, threat, label
+            ORDER BY feature_version DESC, COUNT(*) DESC
+            "#
+        )
+        .bind(org_id)
+        .fetch_all(pool)
+        .await?;
+
+        let (sampled_out, last_received_at): (Option<i64>, Option<DateTime<Utc>>) = sqlx::query_as(
+            "SELECT sampled_out, last_upload_at FROM dataset_sharing WHERE org_id = $1"
+        )
+        .bind(org_id)
+        .fetch_optional(pool)
+        .await?
+        .unwrap_or((None, None));
+
+        Ok(Self {
+            sharing,
+            total_records: buckets.iter().map(|b| b.count).sum(),
+            labeled_records: buckets.iter().filter(|b| b.label.is_some()).map(|b| b.count).sum(),
+            sampled_out: sampled_out.unwrap_or(0),
+            last_received_at,
+            buckets,
+        })
+    }
+}
+
+/// Reject records that are malformed or carry anything beyond the anonymized schema
+fn validate(record: &serde_json::Value) -> Option<RecordHeader> {
+    const ALLOWED_KEYS: &[&str] = &[
+        "hour", "feature_version", "layout_hash", "features", "baseline_diff",
+        "score", "confidence", "threat", "label",
+    ];
+    if !record.as_object()?.keys().all(|k| ALLOWED_KEYS.contains(&k.as_str())) {
+        return None;
+    }
+
+    let header: RecordHeader = serde_json::from_value(record.clone()).ok()?;
+    let class_ok = |c: &str| THREAT_CLASSES.contains(&c);
+    if !class_ok(&header.threat) || !header.label.as_deref().is_none_or(class_ok) {
+        return None;
+    }
+    if header.features.is_empty()
+        || header.features.len() > MAX_VECTOR_LEN
+        || header.baseline_diff.len() > MAX_VECTOR_LEN
+        || header.hour % 3_600_000 != 0
+    {
+        return None;
+    }
+    Some(header)
+}
+
+fn content_hash(record: &serde_json::Value) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(record.to_string().as_bytes());
+    format!("{:x}", hasher.finalize())
+}
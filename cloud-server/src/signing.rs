@@ -0,0 +1,49 @@
+//! Content signing
+//!
+//! Rule packs and offline intel bundles are signed with the server's ed25519
+//! key. Agents pin the public key at build time, so neither an agent token nor
+//! the registration key is enough to forge detection content.
+
+use ed25519_dalek::{Signer, SigningKey};
+
+/// Development seed - agents built without a pinned production key trust its public key
+const DEV_SIGNING_SEED: &str = "d9f05d5d3443e8c0a466f4b3691d8ba29f0c575dea4cff3460cab4774fcb9ea4";
+
+/// Signing key from a hex-encoded 32-byte seed
+pub fn parse_seed(seed: &str) -> Option<SigningKey> {
+    let bytes = decode_hex(seed)?;
+    let seed: [u8; 32] = bytes.try_into().ok()?;
+    Some(SigningKey::from_bytes(&seed))
+}
+
+pub fn dev_key() -> SigningKey {
+    parse_seed(DEV_SIGNING_SEED).expect("valid development seed")
+}
+
+pub fn is_dev_key(key: &SigningKey) -> bool {
+    key.verifying_key() == dev_key().verifying_key()
+}
+
+/// Hex ed25519 signature over `message`
+pub fn sign(key: &SigningKey, message: &[u8]) -> String {
+    encode_hex(&key.sign(message).to_bytes())
+}
+
+/// Hex public key (what agents pin)
+pub fn public_key_hex(key: &SigningKey) -> String {
+    encode_hex(key.verifying_key().as_bytes())
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -6,12 +6,13 @@ use tauri::command;
 use serde::{Deserialize, Serialize};
 
 use crate::logic::advanced_detection::{
-    amsi, injection, memory, keylogger, iat_analysis,
+    amsi, injection, memory, keylogger, iat_analysis, pe_static, full_scan, shellcode_emu,
     ScanResult, ThreatLevel, AmsiStats,
     InjectionAlert, InjectionType, InjectionStats,
     MemoryScanResult, ShellcodeType, MemoryScanStats,
     KeyloggerAlert, KeyloggerStats,
     IatAnalysisResult, IatAlert, IatStats,
+    StaticAnalysisResult,
 };
 
 // ============================================================================
@@ -100,6 +101,60 @@ pub fn get_amsi_stats() -> AmsiStats {
     amsi::get_stats()
 }
 
+/// Get AMSI provider status (provider mode vs heuristic fallback)
+#[command]
+pub fn get_amsi_provider_status() -> crate::logic::advanced_detection::amsi_provider::ProviderStatus {
+    crate::logic::advanced_detection::amsi_provider::status()
+}
+
+/// Get PowerShell script-block ETW consumer status
+#[command]
+pub fn get_script_block_status() -> crate::logic::advanced_detection::etw::ScriptBlockStatus {
+    crate::logic::advanced_detection::etw::status()
+}
+
+/// Scan VBA macros trong 1 Office document
+#[command]
+pub fn scan_office_document(file_path: String) -> Result<crate::logic::advanced_detection::office_macro::MacroAnalysis, String> {
+    crate::logic::advanced_detection::office_macro::scan_document(std::path::Path::new(&file_path))
+}
+
+/// Get recent malicious macro alerts
+#[command]
+pub fn get_macro_alerts(limit: usize) -> Vec<crate::logic::advanced_detection::office_macro::MacroAlert> {
+    crate::logic::advanced_detection::office_macro::get_recent_alerts(limit)
+}
+
+/// Get recent AMSI/ETW tampering alerts
+#[command]
+pub fn get_evasion_alerts(limit: usize) -> Vec<crate::logic::advanced_detection::evasion::TamperAlert> {
+    crate::logic::advanced_detection::evasion::get_recent_alerts(limit)
+}
+
+/// Scan a single process for AMSI/ETW patching
+#[command]
+pub fn scan_process_evasion(pid: u32, name: String) -> Vec<crate::logic::advanced_detection::evasion::TamperAlert> {
+    crate::logic::advanced_detection::evasion::scan_process(pid, &name)
+}
+
+/// Get recent browser credential theft alerts
+#[command]
+pub fn get_credential_theft_alerts(limit: usize) -> Vec<crate::logic::advanced_detection::credential_theft::CredentialTheftAlert> {
+    crate::logic::advanced_detection::credential_theft::get_recent_alerts(limit)
+}
+
+/// Get ransomware canary status
+#[command]
+pub fn get_canary_status() -> crate::logic::advanced_detection::canary::CanaryStatus {
+    crate::logic::advanced_detection::canary::status()
+}
+
+/// Re-plant canary files, returns number planted
+#[command]
+pub fn replant_canaries() -> usize {
+    crate::logic::advanced_detection::canary::replant()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanResultDto {
     pub content_preview: String,
@@ -231,6 +286,18 @@ pub fn scan_file_shellcode(path: String) -> Result<Vec<MemoryScanResultDto>, Str
     Ok(results.into_iter().map(MemoryScanResultDto::from).collect())
 }
 
+/// Get background memory-scan scheduler status
+#[command]
+pub fn get_memory_scheduler_status() -> crate::logic::advanced_detection::memory_scheduler::SchedulerStatus {
+    crate::logic::advanced_detection::memory_scheduler::status()
+}
+
+/// Get recent scheduled memory-scan alerts
+#[command]
+pub fn get_memory_scheduler_alerts(limit: usize) -> Vec<crate::logic::advanced_detection::memory_scheduler::MemoryScheduleAlert> {
+    crate::logic::advanced_detection::memory_scheduler::get_recent_alerts(limit)
+}
+
 /// Get memory scanning statistics
 #[command]
 pub fn get_memory_stats() -> MemoryScanStats {
@@ -419,6 +486,12 @@ pub fn check_process_keylogger(pid: u32, process_name: String) -> Option<Keylogg
         .map(KeyloggerAlertDto::from)
 }
 
+/// Get surveillance alerts (screen/mic capture + network egress)
+#[command]
+pub fn get_surveillance_alerts(limit: usize) -> Vec<keylogger::SurveillanceAlert> {
+    keylogger::get_surveillance_alerts(limit)
+}
+
 // ============================================================================
 // IAT ANALYSIS COMMANDS (Phase 9)
 // ============================================================================
@@ -432,6 +505,8 @@ pub struct IatResultDto {
     pub max_severity: u8,
     pub alerts: Vec<IatAlertDto>,
     pub timestamp: i64,
+    pub sha256: Option<String>,
+    pub import_diff: Option<iat_analysis::ImportDiff>,
 }
 
 /// IAT alert DTO
@@ -467,6 +542,8 @@ impl From<IatAnalysisResult> for IatResultDto {
             max_severity: r.max_severity,
             alerts: r.alerts.into_iter().map(IatAlertDto::from).collect(),
             timestamp: r.timestamp,
+            sha256: r.sha256,
+            import_diff: r.import_diff,
         }
     }
 }
@@ -501,3 +578,94 @@ pub fn clear_iat_cache() -> String {
     iat_analysis::clear_cache();
     "IAT cache cleared".to_string()
 }
+
+/// Import profile đã lưu của 1 file
+#[command]
+pub fn get_iat_profile(file_path: String) -> Option<iat_analysis::IatProfile> {
+    iat_analysis::get_profile(std::path::Path::new(&file_path))
+}
+
+/// Tamper alerts: cùng path nhưng import table đổi giữa 2 lần phân tích
+#[command]
+pub fn get_iat_tamper_alerts(limit: usize) -> Vec<iat_analysis::IatTamperAlert> {
+    iat_analysis::get_tamper_alerts(limit)
+}
+
+// ============================================================================
+// PE STATIC ANALYSIS COMMANDS
+// ============================================================================
+
+/// Static analysis của 1 executable (entropy, packer, imphash, overlay, ...)
+#[command]
+pub fn analyze_pe_static(file_path: String) -> Result<StaticAnalysisResult, String> {
+    pe_static::analyze_file(std::path::Path::new(&file_path)).map_err(|e| e.to_string())
+}
+
+/// Kết quả static analysis đã cache (None nếu chưa phân tích)
+#[command]
+pub fn get_pe_static_result(file_path: String) -> Option<StaticAnalysisResult> {
+    pe_static::get_cached(std::path::Path::new(&file_path))
+}
+
+// ============================================================================
+// SHELLCODE EMULATION COMMANDS
+// ============================================================================
+
+/// Emulate raw shellcode file → hashed APIs + C2 IOCs (IOC mới vào threat feed)
+#[command]
+pub fn emulate_shellcode_file(file_path: String) -> Result<shellcode_emu::EmulationReport, String> {
+    let data = std::fs::read(&file_path).map_err(|e| e.to_string())?;
+    Ok(shellcode_emu::analyze(&data))
+}
+
+/// Bật / tắt emulation cho buffer bị flag
+#[command]
+pub fn set_shellcode_emulation_enabled(enabled: bool) -> bool {
+    shellcode_emu::set_enabled(enabled);
+    shellcode_emu::is_enabled()
+}
+
+/// (enabled, emulator có trong build)
+#[command]
+pub fn get_shellcode_emulation_status() -> (bool, bool) {
+    (shellcode_emu::is_enabled(), shellcode_emu::emulator_available())
+}
+
+// ============================================================================
+// FULL SCAN COMMANDS
+// ============================================================================
+
+/// Bắt đầu full scan (background). Progress qua event `scan:progress`.
+#[command]
+pub fn run_full_scan() -> Result<String, String> {
+    full_scan::run(full_scan::ScanTrigger::Manual)
+}
+
+/// Dừng full scan đang chạy
+#[command]
+pub fn cancel_full_scan() -> bool {
+    full_scan::cancel()
+}
+
+/// Full scan đang chạy (None nếu idle)
+#[command]
+pub fn get_full_scan_status() -> Option<full_scan::FullScanReport> {
+    full_scan::current()
+}
+
+/// Lịch sử full scan (mới nhất trước)
+#[command]
+pub fn get_full_scan_history(limit: usize) -> Vec<full_scan::FullScanReport> {
+    full_scan::get_history(limit)
+}
+
+#[command]
+pub fn get_full_scan_config() -> full_scan::FullScanConfig {
+    full_scan::get_config()
+}
+
+/// Cập nhật paths / exclusions / schedule
+#[command]
+pub fn set_full_scan_config(config: full_scan::FullScanConfig) -> Result<(), String> {
+    full_scan::set_config(config)
+}
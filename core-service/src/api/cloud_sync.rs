@@ -21,8 +21,13 @@ pub struct CloudSyncStatus {
     pub last_sync: Option<String>,
     pub heartbeat_count: u64,
     pub incident_sync_count: u64,
+    /// Byte incident / ghi chú: JSON nguyên vs thực gửi
+    pub sync_bytes_raw: u64,
+    pub sync_bytes_sent: u64,
     pub server_version: Option<String>,
     pub errors: Vec<String>,
+    /// Baseline sync (version vector, golden revision, xung đột gần nhất)
+    pub baseline_sync: cloud_sync::baseline_sync::BaselineSyncStatus,
 }
 
 /// Cloud sync configuration for frontend
@@ -63,8 +68,11 @@ pub fn get_cloud_sync_status() -> CloudSyncStatus {
         last_sync: status.last_sync.map(|dt| dt.to_rfc3339()),
         heartbeat_count: status.heartbeat_count,
         incident_sync_count: status.incident_sync_count,
+        sync_bytes_raw: status.sync_bytes_raw,
+        sync_bytes_sent: status.sync_bytes_sent,
         server_version: status.server_version,
         errors: status.errors,
+        baseline_sync: cloud_sync::baseline_sync::get_status(),
     }
 }
 
@@ -120,6 +128,67 @@ pub fn get_pending_incidents_count() -> usize {
     cloud_sync::sync::pending_incidents_count()
 }
 
+/// Get detection rule pack applied from cloud
+#[tauri::command]
+pub fn get_rule_pack_status() -> cloud_sync::RulePackState {
+    cloud_sync::rule_pack::get_state()
+}
+
+/// Get telemetry upload config (sampling, engine stats, batch size)
+#[tauri::command]
+pub fn get_cloud_telemetry_config() -> cloud_sync::telemetry::CloudTelemetryConfig {
+    cloud_sync::telemetry::get_config()
+}
+
+/// Update telemetry upload config
+#[tauri::command]
+pub fn set_cloud_telemetry_config(config: cloud_sync::telemetry::CloudTelemetryConfig) -> Result<(), String> {
+    cloud_sync::telemetry::set_config(config)
+}
+
+/// Get telemetry upload status (queued, sent, sampled out, compression)
+#[tauri::command]
+pub fn get_cloud_telemetry_status() -> cloud_sync::telemetry::CloudTelemetryStatus {
+    cloud_sync::telemetry::get_status()
+}
+
+/// Get dataset upload config (opt-in, sample rate, labeled only)
+#[tauri::command]
+pub fn get_dataset_upload_config() -> cloud_sync::dataset_upload::DatasetUploadConfig {
+    cloud_sync::dataset_upload::get_config()
+}
+
+/// Update dataset upload config (opt in / out of central model training)
+#[tauri::command]
+pub fn set_dataset_upload_config(config: cloud_sync::dataset_upload::DatasetUploadConfig) -> Result<(), String> {
+    cloud_sync::dataset_upload::set_config(config)
+}
+
+/// Get dataset upload status (cursor, sent, sampled out, org limit)
+#[tauri::command]
+pub fn get_dataset_upload_status() -> cloud_sync::dataset_upload::DatasetUploadStatus {
+    cloud_sync::dataset_upload::get_status()
+}
+
+/// Get managed settings status (applied revision, managed sections, last error)
+#[tauri::command]
+pub fn get_managed_settings_status() -> cloud_sync::managed_settings::ManagedSettingsStatus {
+    cloud_sync::managed_settings::get_status()
+}
+
+/// Get cloud proxy status (effective config, source, proxy in use)
+#[tauri::command]
+pub fn get_cloud_proxy_status() -> cloud_sync::proxy::ProxyStatus {
+    cloud_sync::proxy::get_status()
+}
+
+/// Update local proxy config and rebuild the cloud HTTP client
+#[tauri::command]
+pub fn set_cloud_proxy_config(config: cloud_sync::proxy::ProxyConfig) -> Result<(), String> {
+    cloud_sync::proxy::set_config(config)?;
+    cloud_sync::refresh_http_client()
+}
+
 // ==========================================
 // Phase 13: Agent Mode & Personal Auth
 // ==========================================
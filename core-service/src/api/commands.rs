@@ -3,7 +3,8 @@
 //! Hỗ trợ 15 Features, Severity Scoring, ProcessEvent chi tiết, Action Guard, và ONNX Inference.
 
 use serde::{Deserialize, Serialize};
-use crate::logic::{collector, baseline, guard, action_guard, ai_bridge};
+use crate::logic::{collector, baseline, guard, action_guard, ai_bridge, whitelist};
+use crate::logic::response::expiry;
 
 // ============================================================================
 // DATA STRUCTURES - ENHANCED
@@ -288,6 +289,43 @@ pub async fn submit_user_feedback(id: String, label: String) -> Result<String, S
         .map(|_| format!("Feedback '{}' submitted for ID {}", label, id))
 }
 
+/// Danh sách record dataset cần gán nhãn (chưa có nhãn / low-confidence, phân trang)
+#[tauri::command]
+pub async fn list_label_candidates(
+    query: crate::logic::dataset::labeling::LabelQuery,
+) -> Result<crate::logic::dataset::labeling::LabelPage, String> {
+    tokio::task::spawn_blocking(move || crate::logic::dataset::labeling::list_label_candidates(&query))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Gán / sửa nhãn 1 record dataset theo id
+#[tauri::command]
+pub async fn relabel_dataset_record(id: String, label: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || crate::logic::dataset::labeling::relabel_record(&id, &label))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Gán nhãn hàng loạt mọi record khớp filter, trả về số record đã gán
+#[tauri::command]
+pub async fn bulk_label_dataset(
+    filter: crate::logic::dataset::labeling::LabelFilter,
+    label: String,
+) -> Result<usize, String> {
+    tokio::task::spawn_blocking(move || crate::logic::dataset::labeling::bulk_label(&filter, &label))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Tiến độ gán nhãn (đã / chưa gán, theo nhãn, độ khớp với model)
+#[tauri::command]
+pub async fn get_labeling_progress() -> Result<crate::logic::dataset::labeling::LabelingProgress, String> {
+    tokio::task::spawn_blocking(crate::logic::dataset::labeling::labeling_progress)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
 // P3.1: Incident API
 #[tauri::command]
 pub async fn get_incidents() -> Result<Vec<crate::logic::incident::Incident>, String> {
@@ -300,6 +338,82 @@ pub async fn get_incident_detail(id: String) -> Result<Option<crate::logic::inci
     Ok(crate::logic::incident::get_incident(uuid))
 }
 
+/// Confirm / dismiss incident → gán nhãn cho record dataset bị flag trong khoảng thời gian incident
+#[tauri::command]
+pub async fn set_incident_verdict(
+    id: String,
+    verdict: crate::logic::incident::IncidentVerdict,
+) -> Result<usize, String> {
+    let uuid = uuid::Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    tokio::task::spawn_blocking(move || crate::logic::incident::set_verdict(uuid, verdict))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Thêm ghi chú / lý do kết luận của analyst vào incident (đồng bộ lên cloud)
+#[tauri::command]
+pub fn add_incident_note(
+    id: String,
+    text: String,
+    disposition: Option<String>,
+) -> Result<crate::logic::incident::IncidentNote, String> {
+    let uuid = uuid::Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    crate::logic::incident::add_note(uuid, &text, disposition.as_deref())
+}
+
+/// Các giai đoạn kill chain (tactic ATT&CK) của incident theo thứ tự
+#[tauri::command]
+pub fn get_incident_kill_chain(id: String) -> Result<crate::logic::incident::kill_chain::KillChainProgression, String> {
+    let uuid = uuid::Uuid::parse_str(&id).map_err(|e| e.to_string())?;
+    let incident = crate::logic::incident::get_incident(uuid)
+        .ok_or_else(|| format!("Incident not found: {}", id))?;
+    Ok(crate::logic::incident::kill_chain::progression(&incident))
+}
+
+/// Rule tự đóng incident lành tính đã biết
+#[tauri::command]
+pub async fn list_incident_suppressions() -> Result<Vec<crate::logic::incident::suppression::IncidentSuppression>, String> {
+    Ok(crate::logic::incident::suppression::list())
+}
+
+/// Thêm rule suppress incident (process path / rule id / tag, khung giờ tùy chọn)
+#[tauri::command]
+pub async fn add_incident_suppression(
+    rule: crate::logic::incident::suppression::NewIncidentSuppression,
+    created_by: Option<String>,
+) -> Result<crate::logic::incident::suppression::IncidentSuppression, String> {
+    let actor = created_by.unwrap_or_else(|| "local".to_string());
+    crate::logic::incident::suppression::add(rule, &actor)
+}
+
+/// Gỡ rule suppress incident
+#[tauri::command]
+pub async fn remove_incident_suppression(id: String, reason: Option<String>, removed_by: Option<String>) -> Result<(), String> {
+    let actor = removed_by.unwrap_or_else(|| "local".to_string());
+    crate::logic::incident::suppression::remove(&id, &actor, reason)
+}
+
+/// Audit trail: rule tạo / gỡ / hết hạn và incident đã bị tự đóng
+#[tauri::command]
+pub async fn get_incident_suppression_audit(limit: Option<usize>) -> Result<Vec<crate::logic::incident::suppression::IncidentSuppressionAudit>, String> {
+    Ok(crate::logic::incident::suppression::get_audit(limit.unwrap_or(100)))
+}
+
+/// Xuất báo cáo incident (HTML / PDF) cho ticket / báo cáo quản lý.
+/// `path` rỗng → thư mục Downloads
+#[tauri::command]
+pub async fn export_incident_report(
+    incident_id: String,
+    format: crate::logic::incident::report::ReportFormat,
+    path: String,
+) -> Result<String, String> {
+    let uuid = uuid::Uuid::parse_str(&incident_id).map_err(|e| e.to_string())?;
+    let path = (!path.is_empty()).then(|| std::path::PathBuf::from(path));
+    tokio::task::spawn_blocking(move || crate::logic::incident::report::export_incident_report(uuid, format, path))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
 /// Export full dataset to a single JSONL file for training (P2.2)
 #[tauri::command]
 pub async fn export_dataset(path: String) -> Result<String, String> {
@@ -319,6 +433,74 @@ pub async fn export_dataset(path: String) -> Result<String, String> {
     }
 }
 
+/// Export dataset đã dedup record Benign gần giống nhau và cân bằng / phân tầng theo lớp
+#[tauri::command]
+pub async fn export_dataset_sampled(
+    path: String,
+    options: Option<crate::logic::dataset::sampling::ExportOptions>,
+) -> Result<crate::logic::dataset::sampling::ExportSummary, String> {
+    let target_path = if path.is_empty() {
+        dirs::download_dir()
+            .unwrap_or_else(|| std::path::PathBuf::from("."))
+            .join("ai-security-training-data-sampled.jsonl")
+            .to_string_lossy()
+            .to_string()
+    } else {
+        path
+    };
+    let options = options.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || crate::logic::dataset::sampling::export_sampled(&target_path, &options))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn get_dataset_retention_config() -> Result<crate::logic::dataset::retention::DatasetRetentionConfig, String> {
+    Ok(crate::logic::dataset::retention::get_config())
+}
+
+/// Cấu hình giới hạn dataset (tổng MB, số ngày tối đa) - vượt thì xóa file cũ nhất
+#[tauri::command]
+pub async fn set_dataset_retention_config(
+    config: crate::logic::dataset::retention::DatasetRetentionConfig,
+) -> Result<(), String> {
+    crate::logic::dataset::retention::set_config(config)
+}
+
+/// Báo cáo chất lượng dataset: cân bằng lớp, phân bố feature, độ phủ nhãn, layout, tỉ lệ trùng
+#[tauri::command]
+pub async fn get_dataset_report() -> Result<crate::logic::dataset::report::DatasetReport, String> {
+    tokio::task::spawn_blocking(crate::logic::dataset::report::get_report)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Áp giới hạn dataset ngay
+#[tauri::command]
+pub async fn run_dataset_cleanup() -> Result<crate::logic::dataset::retention::PruneResult, String> {
+    tokio::task::spawn_blocking(crate::logic::dataset::retention::run_cleanup)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Cấu hình ẩn danh PII (username / hostname / path trong profile) khi export
+#[tauri::command]
+pub async fn get_scrub_config() -> Result<crate::logic::telemetry::scrub::ScrubConfig, String> {
+    Ok(crate::logic::telemetry::scrub::get_config())
+}
+
+#[tauri::command]
+pub async fn set_scrub_config(config: crate::logic::telemetry::scrub::ScrubConfig) -> Result<(), String> {
+    crate::logic::telemetry::scrub::set_config(config)
+}
+
+/// Tra ngược token ẩn danh (`user_…`, `host_…`, `h_…`) → giá trị gốc, chỉ trên máy này
+#[tauri::command]
+pub async fn reveal_scrubbed_value(token: String) -> Result<Option<String>, String> {
+    Ok(crate::logic::telemetry::scrub::reveal(&token))
+}
+
 // ============================================================================
 // BASELINE COMMANDS
 // ============================================================================
@@ -617,6 +799,30 @@ pub async fn get_action_guard_status() -> Result<serde_json::Value, String> {
     Ok(action_guard::get_status())
 }
 
+/// Lấy cấu hình Action Guard (effective + local + cloud override)
+#[tauri::command]
+pub async fn get_action_guard_config() -> Result<serde_json::Value, String> {
+    Ok(serde_json::json!({
+        "effective": action_guard::get_config(),
+        "local": action_guard::get_local_config(),
+        "cloud_override": action_guard::get_cloud_override(),
+    }))
+}
+
+/// Process tái phạm đang trên escalation ladder
+#[tauri::command]
+pub async fn get_escalations() -> Result<Vec<action_guard::Escalation>, String> {
+    Ok(action_guard::get_escalations())
+}
+
+/// Cập nhật cấu hình Action Guard (persist ra disk)
+#[tauri::command]
+pub async fn set_action_guard_config(
+    config: action_guard::ActionGuardConfig,
+) -> Result<action_guard::ActionGuardConfig, String> {
+    action_guard::set_config(config).map_err(|e| e.to_string())
+}
+
 /// Lấy danh sách pending actions
 #[tauri::command]
 pub async fn get_pending_actions() -> Result<Vec<serde_json::Value>, String> {
@@ -655,6 +861,57 @@ pub async fn cancel_action(action_id: String) -> Result<bool, String> {
     Ok(true)
 }
 
+/// Undo một action đã thực thi (resume / gỡ firewall rule / restore file)
+#[tauri::command]
+pub async fn undo_action(action_id: String) -> Result<serde_json::Value, String> {
+    match action_guard::undo_action(&action_id) {
+        Ok(result) => Ok(serde_json::json!({
+            "success": result.success,
+            "action_type": format!("{:?}", result.action_type),
+            "target_pid": result.target_pid,
+            "message": result.message,
+            "executed_at": result.executed_at.to_rfc3339(),
+        })),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Containment cho threat spawn từ browser (browser session giữ nguyên)
+#[tauri::command]
+pub async fn contain_browser_child(pid: u32) -> Result<serde_json::Value, String> {
+    use crate::logic::response::browser_child;
+
+    let (result, containment) = browser_child::contain(pid, "Manual browser-child containment")
+        .map_err(|e| e.to_string())?;
+    let action_id = action_guard::record_manual_action(
+        action_guard::ActionType::ContainBrowserChild,
+        Some(pid),
+        &containment.browser_name,
+        &result.message,
+        containment.quarantine_id.clone().map(|quarantine_id| {
+            crate::logic::response::expiry::RevertKind::RestoreFile { quarantine_id }
+        }),
+    );
+
+    Ok(serde_json::json!({
+        "action_id": action_id,
+        "message": result.message,
+        "containment": containment,
+    }))
+}
+
+/// Hash bị block (process mới có hash này sẽ bị kill)
+#[tauri::command]
+pub async fn get_blocked_hashes() -> Result<Vec<crate::logic::response::browser_child::BlockedHash>, String> {
+    Ok(crate::logic::response::browser_child::get_blocked_hashes())
+}
+
+/// Gỡ hash khỏi blocklist
+#[tauri::command]
+pub async fn unblock_hash(sha256: String) -> Result<bool, String> {
+    Ok(crate::logic::response::browser_child::unblock_hash(&sha256))
+}
+
 /// Lấy lịch sử actions
 #[tauri::command]
 pub async fn get_action_history(limit: Option<usize>) -> Result<Vec<serde_json::Value>, String> {
@@ -672,13 +929,124 @@ pub async fn get_action_history(limit: Option<usize>) -> Result<Vec<serde_json::
         "result": a.result,
         "executed_at": a.executed_at.to_rfc3339(),
         "auto_executed": a.auto_executed,
+        "reversible": a.undo.is_some() && a.status == action_guard::ActionStatus::Executed,
     })).collect())
 }
 
+/// Tên process cho action history (fallback: PID)
+fn manual_target_name(pid: u32) -> String {
+    crate::logic::process_intel::get_process_info(pid)
+        .map(|p| p.name)
+        .unwrap_or_else(|| pid.to_string())
+}
+
 /// Kill một process (manual action)
 #[tauri::command]
 pub async fn kill_process(pid: u32) -> Result<serde_json::Value, String> {
+    let target_name = manual_target_name(pid);
     match action_guard::kill_process(pid) {
+        Ok(result) => {
+            let action_id = action_guard::record_manual_action(
+                action_guard::ActionType::KillProcess,
+                Some(pid),
+                &target_name,
+                &result.message,
+                None,
+            );
+            Ok(serde_json::json!({
+                "success": result.success,
+                "message": result.message,
+                "action_id": action_id,
+            }))
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Kill một process cùng toàn bộ process con
+#[tauri::command]
+pub async fn kill_process_tree(pid: u32) -> Result<serde_json::Value, String> {
+    let target_name = manual_target_name(pid);
+    match action_guard::kill_process_tree(pid) {
+        Ok(result) => {
+            let action_id = action_guard::record_manual_action(
+                action_guard::ActionType::KillProcessTree,
+                Some(pid),
+                &target_name,
+                &result.message,
+                None,
+            );
+            Ok(serde_json::json!({
+                "success": result.success,
+                "message": result.message,
+                "action_id": action_id,
+            }))
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Suspend một process (`duration_secs`: tự resume sau khoảng thời gian này)
+#[tauri::command]
+pub async fn suspend_process(pid: u32, duration_secs: Option<u64>) -> Result<serde_json::Value, String> {
+    let target_name = manual_target_name(pid);
+    match action_guard::suspend_process(pid) {
+        Ok(result) => {
+            let action_id = action_guard::record_manual_action(
+                action_guard::ActionType::SuspendProcess,
+                Some(pid),
+                &target_name,
+                &result.message,
+                Some(expiry::RevertKind::ResumeProcess { pid }),
+            );
+            let revert = duration_secs.map(|secs| {
+                expiry::schedule(expiry::RevertKind::ResumeProcess { pid }, &target_name, secs)
+            });
+            Ok(serde_json::json!({
+                "success": result.success,
+                "message": result.message,
+                "action_id": action_id,
+                "expires_at": revert.map(|r| r.expires_at),
+            }))
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Block network của một process (`duration_secs`: tự gỡ khi hết hạn)
+#[tauri::command]
+pub async fn block_process_network(
+    pid: u32,
+    process_name: String,
+    duration_secs: Option<u64>,
+) -> Result<serde_json::Value, String> {
+    match action_guard::block_network_io(pid, &process_name) {
+        Ok(result) => {
+            let action_id = result.success.then(|| action_guard::record_manual_action(
+                action_guard::ActionType::BlockNetworkIO,
+                Some(pid),
+                &process_name,
+                &result.message,
+                Some(expiry::RevertKind::UnblockNetwork { pid }),
+            ));
+            let revert = duration_secs.filter(|_| result.success).map(|secs| {
+                expiry::schedule(expiry::RevertKind::UnblockNetwork { pid }, &process_name, secs)
+            });
+            Ok(serde_json::json!({
+                "success": result.success,
+                "message": result.message,
+                "action_id": action_id,
+                "expires_at": revert.map(|r| r.expires_at),
+            }))
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Gỡ block network của một process
+#[tauri::command]
+pub async fn unblock_process_network(pid: u32) -> Result<serde_json::Value, String> {
+    match action_guard::unblock_network_io(pid) {
         Ok(result) => Ok(serde_json::json!({
             "success": result.success,
             "message": result.message,
@@ -687,10 +1055,22 @@ pub async fn kill_process(pid: u32) -> Result<serde_json::Value, String> {
     }
 }
 
-/// Suspend một process
+/// Danh sách action sẽ tự revert
 #[tauri::command]
-pub async fn suspend_process(pid: u32) -> Result<serde_json::Value, String> {
-    match action_guard::suspend_process(pid) {
+pub async fn get_scheduled_reverts() -> Result<Vec<expiry::ScheduledRevert>, String> {
+    Ok(expiry::get_scheduled())
+}
+
+/// Hủy auto-revert (giữ nguyên action)
+#[tauri::command]
+pub async fn cancel_scheduled_revert(id: String) -> Result<bool, String> {
+    Ok(expiry::cancel(&id))
+}
+
+/// Resume một process đã bị suspend
+#[tauri::command]
+pub async fn resume_process(pid: u32) -> Result<serde_json::Value, String> {
+    match action_guard::resume_process(pid) {
         Ok(result) => Ok(serde_json::json!({
             "success": result.success,
             "message": result.message,
@@ -699,6 +1079,252 @@ pub async fn suspend_process(pid: u32) -> Result<serde_json::Value, String> {
     }
 }
 
+/// Cách ly máy khỏi mạng (giữ kết nối cloud server + DNS + allowed_hosts)
+#[tauri::command]
+pub async fn isolate_host(
+    allowed_hosts: Option<Vec<String>>,
+    duration_secs: Option<u64>,
+) -> Result<serde_json::Value, String> {
+    let allowed_hosts = allowed_hosts.unwrap_or_default();
+    match crate::logic::response::network::isolate_host(&allowed_hosts) {
+        Ok(result) => {
+            let action_id = action_guard::record_manual_action(
+                action_guard::ActionType::IsolateHost,
+                None,
+                "host",
+                &result.message,
+                Some(expiry::RevertKind::ReleaseHost),
+            );
+            let revert = duration_secs.map(|secs| {
+                expiry::schedule(expiry::RevertKind::ReleaseHost, "host", secs)
+            });
+            Ok(serde_json::json!({
+                "success": true,
+                "message": result.message,
+                "action_id": action_id,
+                "expires_at": revert.map(|r| r.expires_at),
+            }))
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Gỡ cách ly mạng
+#[tauri::command]
+pub async fn release_host() -> Result<serde_json::Value, String> {
+    match crate::logic::response::network::release_host() {
+        Ok(result) => Ok(serde_json::json!({
+            "success": true,
+            "message": result.message,
+        })),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Trạng thái cách ly mạng hiện tại
+#[tauri::command]
+pub async fn get_host_isolation_status() -> Result<serde_json::Value, String> {
+    let status = crate::logic::response::network::get_isolation_status();
+    Ok(serde_json::json!({
+        "isolated": status.is_some(),
+        "isolated_at": status.as_ref().map(|s| s.isolated_at),
+        "allowed_ips": status.map(|s| s.allowed_ips).unwrap_or_default(),
+    }))
+}
+
+/// Gỡ registry persistence (snapshot trước để undo)
+#[tauri::command]
+pub async fn remove_persistence(location: String, value_name: Option<String>) -> Result<serde_json::Value, String> {
+    use crate::logic::response::registry;
+
+    match registry::remove_persistence(&location, value_name.as_deref()) {
+        Ok((result, snapshot)) => {
+            let action_id = action_guard::record_manual_action(
+                action_guard::ActionType::RemovePersistence,
+                None,
+                &registry::persistence_target(&location, value_name.as_deref()),
+                &result.message,
+                Some(expiry::RevertKind::RestorePersistence { snapshot_id: snapshot.id.clone() }),
+            );
+            Ok(serde_json::json!({
+                "success": true,
+                "message": result.message,
+                "snapshot_id": snapshot.id,
+                "action_id": action_id,
+            }))
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Undo persistence removal
+#[tauri::command]
+pub async fn restore_persistence(snapshot_id: String) -> Result<serde_json::Value, String> {
+    match crate::logic::response::registry::restore_persistence(&snapshot_id) {
+        Ok(result) => Ok(serde_json::json!({
+            "success": true,
+            "message": result.message,
+        })),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Persistence alerts gần nhất (Run key, COM hijack, ...)
+#[tauri::command]
+pub async fn get_persistence_alerts(limit: usize) -> Result<Vec<crate::logic::behavioral_sigs::PersistenceAlert>, String> {
+    Ok(crate::logic::behavioral_sigs::persistence::get_alerts(limit))
+}
+
+/// WMI event subscriptions hiện có (root\subscription)
+#[tauri::command]
+pub async fn get_wmi_subscriptions() -> Result<Vec<crate::logic::behavioral_sigs::wmi_persistence::WmiSubscription>, String> {
+    Ok(crate::logic::behavioral_sigs::wmi_persistence::get_subscriptions())
+}
+
+/// BITS job abuse alerts gần nhất
+#[tauri::command]
+pub async fn get_bits_abuse_alerts(limit: usize) -> Result<Vec<crate::logic::behavioral_sigs::bits_abuse::BitsAbuseAlert>, String> {
+    Ok(crate::logic::behavioral_sigs::bits_abuse::get_recent_alerts(limit))
+}
+
+/// Kerberoasting alerts gần nhất (Kerberos TGS / LDAP SPN bursts)
+#[tauri::command]
+pub async fn get_kerberoast_alerts(limit: usize) -> Result<Vec<crate::logic::behavioral_sigs::kerberoast::KerberoastAlert>, String> {
+    Ok(crate::logic::behavioral_sigs::kerberoast::get_recent_alerts(limit))
+}
+
+/// Phishing / malware site alerts từ browser connections
+#[tauri::command]
+pub async fn get_url_reputation_alerts(limit: usize) -> Result<Vec<crate::logic::network::UrlReputationAlert>, String> {
+    Ok(crate::logic::network::url_reputation::get_recent_alerts(limit))
+}
+
+#[tauri::command]
+pub async fn get_url_reputation_config() -> Result<crate::logic::network::UrlReputationConfig, String> {
+    Ok(crate::logic::network::url_reputation::get_config())
+}
+
+/// Bật/tắt check + tự chặn IP của site độc hại
+#[tauri::command]
+pub async fn set_url_reputation_config(config: crate::logic::network::UrlReputationConfig) -> Result<(), String> {
+    crate::logic::network::url_reputation::set_config(config);
+    Ok(())
+}
+
+/// GeoIP: config hiệu lực + database đã import
+#[tauri::command]
+pub async fn get_geoip_status() -> Result<crate::logic::network::geoip::GeoIpStatus, String> {
+    Ok(crate::logic::network::geoip::get_status())
+}
+
+/// Import database MaxMind (.mmdb, Country / City hoặc ASN)
+#[tauri::command]
+pub async fn import_geoip_database(path: String) -> Result<crate::logic::network::geoip::GeoDatabaseInfo, String> {
+    crate::logic::network::geoip::import_database(std::path::Path::new(&path))
+}
+
+/// Bật/tắt enrichment + danh sách quốc gia rủi ro cao (ISO alpha-2)
+#[tauri::command]
+pub async fn set_geoip_config(config: crate::logic::network::GeoIpConfig) -> Result<(), String> {
+    crate::logic::network::geoip::set_config(config)
+}
+
+#[tauri::command]
+pub async fn lookup_geoip(ip: String) -> Result<Option<crate::logic::network::GeoInfo>, String> {
+    let ip: std::net::IpAddr = ip.trim().parse().map_err(|_| format!("Invalid IP address: {}", ip))?;
+    Ok(crate::logic::network::geoip::lookup(ip))
+}
+
+/// Connection tới quốc gia rủi ro cao gần nhất
+#[tauri::command]
+pub async fn get_geoip_alerts(limit: usize) -> Result<Vec<crate::logic::network::GeoAlert>, String> {
+    Ok(crate::logic::network::geoip::get_recent_alerts(limit))
+}
+
+/// Destination IP đang bị chặn
+#[tauri::command]
+pub async fn get_blocked_destinations() -> Result<Vec<crate::logic::response::BlockedDestination>, String> {
+    Ok(crate::logic::response::get_blocked_destinations())
+}
+
+#[tauri::command]
+pub async fn unblock_destination(ip: String) -> Result<String, String> {
+    crate::logic::response::unblock_destination(&ip)
+        .map(|r| r.message)
+        .map_err(|e| e.to_string())
+}
+
+/// Thống kê network connection collector
+#[tauri::command]
+pub async fn get_network_collector_stats() -> Result<crate::logic::network::ConnectionStats, String> {
+    Ok(crate::logic::network::get_stats())
+}
+
+/// UAC bypass alerts gần nhất
+#[tauri::command]
+pub async fn get_uac_bypass_alerts(limit: usize) -> Result<Vec<crate::logic::behavioral_sigs::uac_bypass::UacBypassAlert>, String> {
+    Ok(crate::logic::behavioral_sigs::uac_bypass::get_recent_alerts(limit))
+}
+
+/// Danh sách persistence snapshots
+#[tauri::command]
+pub async fn get_persistence_snapshots() -> Result<Vec<crate::logic::response::RegistrySnapshot>, String> {
+    Ok(crate::logic::response::registry::get_snapshots())
+}
+
+/// Danh sách remediation playbooks
+#[tauri::command]
+pub async fn get_playbooks() -> Result<Vec<crate::logic::response::Playbook>, String> {
+    Ok(crate::logic::response::playbook::get_playbooks())
+}
+
+/// Tạo / cập nhật playbook
+#[tauri::command]
+pub async fn save_playbook(playbook: crate::logic::response::Playbook) -> Result<bool, String> {
+    crate::logic::response::playbook::save_playbook(playbook)
+        .map(|_| true)
+        .map_err(|e| e.to_string())
+}
+
+/// Xóa playbook
+#[tauri::command]
+pub async fn delete_playbook(playbook_id: String) -> Result<bool, String> {
+    Ok(crate::logic::response::playbook::delete_playbook(&playbook_id))
+}
+
+/// Chạy playbook thủ công trên một process
+#[tauri::command]
+pub async fn run_playbook(playbook_id: String, pid: u32) -> Result<crate::logic::response::PlaybookRun, String> {
+    let context = crate::logic::response::PlaybookContext {
+        pid: Some(pid),
+        ..Default::default()
+    };
+    crate::logic::response::run_playbook(&playbook_id, context).map_err(|e| e.to_string())
+}
+
+/// Lịch sử chạy playbook
+#[tauri::command]
+pub async fn get_playbook_runs(limit: Option<usize>) -> Result<Vec<crate::logic::response::PlaybookRun>, String> {
+    Ok(crate::logic::response::playbook::get_runs(limit.unwrap_or(50)))
+}
+
+/// Bật/tắt detect-only mode (Action Guard chỉ ghi lại, không thực thi)
+#[tauri::command]
+pub async fn set_dry_run_mode(enabled: bool) -> Result<bool, String> {
+    if crate::logic::cloud_sync::managed_settings::managed_features().is_some_and(|f| f.dry_run.is_some()) {
+        return Err("Dry-run mode is managed by your organization".to_string());
+    }
+    crate::logic::config::SafetyConfig::persist_dry_run(enabled);
+    log::warn!("Action Guard dry-run mode: {}", if enabled { "ON" } else { "OFF" });
+    Ok(enabled)
+}
+
+/// Trạng thái detect-only mode
+#[tauri::command]
+pub async fn get_dry_run_mode() -> Result<bool, String> {
+    Ok(crate::logic::config::SafetyConfig::is_dry_run())
+}
+
 /// Thêm process vào whitelist
 #[tauri::command]
 pub async fn add_to_whitelist(process_name: String) -> Result<bool, String> {
@@ -719,6 +1345,409 @@ pub async fn get_whitelist() -> Result<Vec<String>, String> {
     Ok(action_guard::get_whitelist())
 }
 
+/// Thêm whitelist entry (kind: name | path | sha256 | publisher)
+#[tauri::command]
+pub async fn add_whitelist_entry(
+    kind: whitelist::WhitelistKind,
+    value: String,
+    comment: Option<String>,
+) -> Result<whitelist::WhitelistEntry, String> {
+    let entry = whitelist::add(kind, &value, comment)?;
+    if kind == whitelist::WhitelistKind::Name {
+        action_guard::reset_escalation(&value);
+    }
+    Ok(entry)
+}
+
+/// Lấy whitelist đầy đủ (kind, source, comment)
+#[tauri::command]
+pub async fn get_whitelist_entries() -> Result<Vec<whitelist::WhitelistEntry>, String> {
+    Ok(whitelist::list())
+}
+
+/// Import whitelist từ file JSON
+#[tauri::command]
+pub async fn import_whitelist(path: String) -> Result<usize, String> {
+    whitelist::import(std::path::Path::new(&path))
+}
+
+/// Export whitelist local ra file JSON
+#[tauri::command]
+pub async fn export_whitelist(path: String) -> Result<usize, String> {
+    whitelist::export(std::path::Path::new(&path))
+}
+
+// ============================================================================
+// BEHAVIORAL RULES
+// ============================================================================
+
+/// Tất cả behavioral rules (built-in + custom) kèm match count
+#[tauri::command]
+pub async fn list_behavioral_rules() -> Result<Vec<crate::logic::behavioral_sigs::RuleSummary>, String> {
+    Ok(crate::logic::behavioral_sigs::rules::list_rules())
+}
+
+#[tauri::command]
+pub async fn create_behavioral_rule(rule: crate::logic::behavioral_sigs::BehavioralRuleDefinition) -> Result<(), String> {
+    crate::logic::behavioral_sigs::rules::create_rule(rule)
+}
+
+#[tauri::command]
+pub async fn update_behavioral_rule(rule: crate::logic::behavioral_sigs::BehavioralRuleDefinition) -> Result<(), String> {
+    crate::logic::behavioral_sigs::rules::update_rule(rule)
+}
+
+#[tauri::command]
+pub async fn delete_behavioral_rule(rule_id: String) -> Result<(), String> {
+    crate::logic::behavioral_sigs::rules::delete_rule(&rule_id)
+}
+
+#[tauri::command]
+pub async fn set_behavioral_rule_enabled(rule_id: String, enabled: bool) -> Result<(), String> {
+    crate::logic::behavioral_sigs::rules::toggle_rule(&rule_id, enabled)
+}
+
+/// Trạng thái load các file trong thư mục rules/ (lỗi validate theo file)
+#[tauri::command]
+pub async fn get_rule_files_status() -> Result<Vec<crate::logic::behavioral_sigs::rules_dir::RuleFileStatus>, String> {
+    Ok(crate::logic::behavioral_sigs::rules_dir::get_status())
+}
+
+/// Reload toàn bộ thư mục rules/ ngay
+#[tauri::command]
+pub async fn reload_rule_files() -> Result<Vec<crate::logic::behavioral_sigs::rules_dir::RuleFileStatus>, String> {
+    Ok(crate::logic::behavioral_sigs::rules_dir::reload_all())
+}
+
+/// Rule matches gần nhất
+#[tauri::command]
+pub async fn get_behavioral_rule_matches(limit: usize) -> Result<Vec<crate::logic::behavioral_sigs::RuleMatch>, String> {
+    Ok(crate::logic::behavioral_sigs::get_matches(limit))
+}
+
+/// Thống kê rule engine (kèm tuning stats per-rule)
+#[tauri::command]
+pub async fn get_behavioral_rule_stats() -> Result<crate::logic::behavioral_sigs::rules::RuleEngineStats, String> {
+    Ok(crate::logic::behavioral_sigs::rules::get_stats())
+}
+
+/// Rules ồn nhất (match nhiều, FP cao, incident nhẹ) - ưu tiên tune trước
+#[tauri::command]
+pub async fn get_noisiest_rules(limit: Option<usize>) -> Result<Vec<crate::logic::telemetry::RuleTuningStats>, String> {
+    Ok(crate::logic::telemetry::rule_stats::get_noisiest(limit.unwrap_or(10)))
+}
+
+/// Analyst đánh dấu rule match là false/true positive
+#[tauri::command]
+pub async fn submit_rule_feedback(rule_id: String, false_positive: bool, note: Option<String>) -> Result<(), String> {
+    if crate::logic::behavioral_sigs::rules::get_rule(&rule_id).is_none() {
+        return Err(format!("Rule {} not found", rule_id));
+    }
+    crate::logic::telemetry::rule_stats::record_feedback(&rule_id, false_positive, note.as_deref());
+    Ok(())
+}
+
+/// Chạy thử rule (draft, chưa cần lưu) trên sample context JSON, hoặc context
+/// dựng từ history của process đang chạy (`pid`)
+#[tauri::command]
+pub async fn test_rule(
+    rule_json: String,
+    sample_context_json: Option<String>,
+    pid: Option<u32>,
+) -> Result<crate::logic::behavioral_sigs::RuleTestResult, String> {
+    use crate::logic::behavioral_sigs::{rules, history, BehavioralRuleDefinition, SampleContext};
+
+    let rule: BehavioralRuleDefinition = serde_json::from_str(&rule_json)
+        .map_err(|e| format!("Invalid rule: {}", e))?;
+    let ctx: SampleContext = match (sample_context_json, pid) {
+        (Some(json), _) => serde_json::from_str(&json).map_err(|e| format!("Invalid sample context: {}", e))?,
+        (None, Some(pid)) => history::build_context(pid)
+            .ok_or_else(|| format!("Process {} not found", pid))?,
+        (None, None) => return Err("Provide a sample context or a process id".to_string()),
+    };
+
+    rules::test_rule(&rule, ctx)
+}
+
+/// Exception list của rule (None = tất cả)
+#[tauri::command]
+pub async fn list_rule_suppressions(rule_id: Option<String>) -> Result<Vec<crate::logic::behavioral_sigs::RuleSuppression>, String> {
+    Ok(crate::logic::behavioral_sigs::suppressions::list(rule_id.as_deref()))
+}
+
+/// Thêm exception (path / sha256 / user / host) cho rule, `*` = mọi rule
+#[tauri::command]
+pub async fn add_rule_suppression(
+    rule_id: String,
+    kind: crate::logic::behavioral_sigs::SuppressionKind,
+    value: String,
+    reason: String,
+    expires_at: Option<i64>,
+    created_by: Option<String>,
+) -> Result<crate::logic::behavioral_sigs::RuleSuppression, String> {
+    let actor = created_by.unwrap_or_else(|| "local".to_string());
+    crate::logic::behavioral_sigs::suppressions::add(&rule_id, kind, &value, &reason, expires_at, &actor)
+}
+
+/// Gỡ exception
+#[tauri::command]
+pub async fn remove_rule_suppression(id: String, reason: Option<String>, removed_by: Option<String>) -> Result<(), String> {
+    let actor = removed_by.unwrap_or_else(|| "local".to_string());
+    crate::logic::behavioral_sigs::suppressions::remove(&id, &actor, reason)
+}
+
+/// Audit trail của exception list
+#[tauri::command]
+pub async fn get_rule_suppression_audit(limit: Option<usize>) -> Result<Vec<crate::logic::behavioral_sigs::SuppressionAuditEntry>, String> {
+    Ok(crate::logic::behavioral_sigs::suppressions::get_audit(limit.unwrap_or(100)))
+}
+
+// ============================================================================
+// ABUSED SIGNER COMMANDS
+// ============================================================================
+
+/// Danh sách signer bị lạm dụng (built-in + user)
+#[tauri::command]
+pub async fn get_abused_signers() -> Result<Vec<crate::logic::process_intel::AbusedSigner>, String> {
+    Ok(crate::logic::process_intel::signature::get_abused_signers())
+}
+
+/// Thêm signer (thumbprint / serial / publisher) → re-verify reputation entries đang signed
+#[tauri::command]
+pub async fn add_abused_signer(signer: crate::logic::process_intel::AbusedSigner) -> Result<bool, String> {
+    crate::logic::process_intel::signature::add_abused_signer(signer)?;
+    std::thread::spawn(|| {
+        let changed = crate::logic::process_intel::reputation::recheck_signatures();
+        log::info!("Abused signer list updated, {} reputation entries re-scored", changed);
+    });
+    Ok(true)
+}
+
+/// Xóa user signer theo thumbprint / serial / publisher
+#[tauri::command]
+pub async fn remove_abused_signer(value: String) -> Result<bool, String> {
+    Ok(crate::logic::process_intel::signature::remove_abused_signer(&value))
+}
+
+// ============================================================================
+// TRUSTED PUBLISHER COMMANDS
+// ============================================================================
+
+/// Allowlist publisher (built-in + local + cloud policy)
+#[tauri::command]
+pub async fn get_trusted_publishers() -> Result<crate::logic::process_intel::signature::PublisherAllowlist, String> {
+    Ok(crate::logic::process_intel::signature::get_trusted_publishers())
+}
+
+/// Thêm publisher (CN và/hoặc thumbprint cert nội bộ) → re-verify reputation entries đang signed
+#[tauri::command]
+pub async fn add_trusted_publisher(publisher: crate::logic::process_intel::TrustedPublisher) -> Result<bool, String> {
+    let added = crate::logic::process_intel::signature::add_trusted_publisher(publisher)?;
+    if added {
+        std::thread::spawn(|| {
+            let changed = crate::logic::process_intel::reputation::recheck_signatures();
+            log::info!("Trusted publisher list updated, {} reputation entries re-scored", changed);
+        });
+    }
+    Ok(added)
+}
+
+/// Xóa publisher local theo CN / thumbprint
+#[tauri::command]
+pub async fn remove_trusted_publisher(value: String) -> Result<bool, String> {
+    let removed = crate::logic::process_intel::signature::remove_trusted_publisher(&value);
+    if removed {
+        std::thread::spawn(|| {
+            let changed = crate::logic::process_intel::reputation::recheck_signatures();
+            log::info!("Trusted publisher list updated, {} reputation entries re-scored", changed);
+        });
+    }
+    Ok(removed)
+}
+
+// ============================================================================
+// THREAT FEED COMMANDS
+// ============================================================================
+
+/// Số indicator theo feed, lần sync, thống kê match
+#[tauri::command]
+pub async fn get_threat_feed_stats() -> Result<crate::logic::external_intel::threat_feed::FeedStats, String> {
+    Ok(crate::logic::external_intel::threat_feed::get_stats())
+}
+
+/// Sync ngay các feed abuse.ch / ET (không chờ scheduler)
+#[tauri::command]
+pub async fn sync_threat_feeds() -> Result<crate::logic::external_intel::threat_feed::SyncResult, String> {
+    crate::logic::external_intel::threat_feed::sync_feeds()
+}
+
+/// Trạng thái tích hợp MISP (cấu hình qua cloud policy)
+#[tauri::command]
+pub async fn get_misp_status() -> Result<crate::logic::external_intel::misp::MispStatus, String> {
+    Ok(crate::logic::external_intel::misp::get_status())
+}
+
+/// Pull attribute từ MISP ngay
+#[tauri::command]
+pub async fn sync_misp() -> Result<usize, String> {
+    crate::logic::external_intel::misp::pull()
+}
+
+/// Trạng thái các TAXII collection đã subscribe
+#[tauri::command]
+pub async fn get_taxii_status() -> Result<Vec<crate::logic::external_intel::taxii::TaxiiCollectionStatus>, String> {
+    Ok(crate::logic::external_intel::taxii::get_status())
+}
+
+/// Poll tất cả TAXII collection ngay
+#[tauri::command]
+pub async fn sync_taxii() -> Result<usize, String> {
+    crate::logic::external_intel::taxii::sync_all()
+}
+
+/// Trạng thái OTX (pulse, indicator, technique map được)
+#[tauri::command]
+pub async fn get_otx_status() -> Result<crate::logic::external_intel::otx::OtxStatus, String> {
+    Ok(crate::logic::external_intel::otx::get_status())
+}
+
+/// Sync pulse OTX đã subscribe ngay
+#[tauri::command]
+pub async fn sync_otx() -> Result<usize, String> {
+    crate::logic::external_intel::otx::pull()
+}
+
+/// Lưu OTX API key vào local settings (chuỗi rỗng = xóa)
+#[tauri::command]
+pub async fn set_otx_api_key(api_key: String) -> Result<(), String> {
+    crate::logic::external_intel::otx::set_api_key(&api_key);
+    Ok(())
+}
+
+/// Import IOC của case (CSV / JSON / text) vào store local với source tag, expiry tùy chọn
+#[tauri::command]
+pub async fn import_iocs(
+    path: String,
+    format: String,
+    source: String,
+    expires_in_hours: Option<u64>,
+) -> Result<crate::logic::external_intel::local_iocs::ImportReport, String> {
+    use crate::logic::external_intel::local_iocs::{self, IocFormat};
+
+    let path = std::path::PathBuf::from(path);
+    let format = IocFormat::parse(&format, &path)?;
+    let expires_at = expires_in_hours.map(|h| chrono::Utc::now().timestamp() + (h as i64) * 3600);
+    local_iocs::import_file(&path, format, &source, expires_at)
+}
+
+/// Các source tag IOC local
+#[tauri::command]
+pub async fn list_local_ioc_sources() -> Result<Vec<crate::logic::external_intel::local_iocs::LocalIocSource>, String> {
+    Ok(crate::logic::external_intel::local_iocs::list_sources())
+}
+
+/// Xóa toàn bộ IOC local của một source tag
+#[tauri::command]
+pub async fn remove_local_iocs(source: String) -> Result<bool, String> {
+    Ok(crate::logic::external_intel::local_iocs::remove_source(&source))
+}
+
+/// Import offline intel bundle đã ký (feeds + MITRE + rule packs) từ file / USB
+#[tauri::command]
+pub async fn import_intel_bundle(path: String) -> Result<crate::logic::external_intel::intel_bundle::IntelBundleState, String> {
+    crate::logic::external_intel::intel_bundle::import_file(std::path::Path::new(&path))
+}
+
+/// Offline intel bundle đang chạy
+#[tauri::command]
+pub async fn get_intel_bundle_status() -> Result<crate::logic::external_intel::intel_bundle::IntelBundleState, String> {
+    Ok(crate::logic::external_intel::intel_bundle::get_state())
+}
+
+/// Intel match gần nhất (connection / executable khớp IOC → incident)
+#[tauri::command]
+pub async fn get_intel_matches(limit: Option<usize>) -> Result<Vec<crate::logic::external_intel::intel_matcher::IntelMatch>, String> {
+    Ok(crate::logic::external_intel::intel_matcher::get_recent_matches(limit.unwrap_or(100)))
+}
+
+/// Coverage MITRE ATT&CK: technique được rules / detectors phủ + incident theo technique
+#[tauri::command]
+pub async fn get_mitre_coverage() -> Result<crate::logic::external_intel::mitre_coverage::MitreCoverageReport, String> {
+    Ok(crate::logic::external_intel::mitre_coverage::get_coverage())
+}
+
+/// Dataset ATT&CK đang dùng (embedded hay file đã update)
+#[tauri::command]
+pub async fn get_mitre_dataset_info() -> Result<crate::logic::external_intel::MitreDatasetInfo, String> {
+    Ok(crate::logic::external_intel::mitre::get_dataset_info())
+}
+
+/// Thay dataset ATT&CK bằng file enterprise-attack.json (STIX 2.1) chính thức
+#[tauri::command]
+pub async fn update_mitre_dataset(path: String) -> Result<crate::logic::external_intel::MitreDatasetInfo, String> {
+    crate::logic::external_intel::mitre::update_dataset(std::path::Path::new(&path))
+}
+
+// ============================================================================
+// VIRUSTOTAL COMMANDS
+// ============================================================================
+
+/// Queue + quota + cache của VirusTotal client
+#[tauri::command]
+pub async fn get_vt_queue_status() -> Result<crate::logic::external_intel::VTQueueStatus, String> {
+    Ok(crate::logic::external_intel::virustotal::get_queue_status())
+}
+
+/// Lưu VirusTotal API key vào local settings (chuỗi rỗng = xóa)
+#[tauri::command]
+pub async fn set_vt_api_key(api_key: String) -> Result<bool, String> {
+    crate::logic::external_intel::virustotal::set_api_key(&api_key);
+    Ok(crate::logic::external_intel::virustotal::is_configured())
+}
+
+/// Đưa hash vào queue VirusTotal, trả về vị trí (None nếu đã cache / đã trong queue)
+#[tauri::command]
+pub async fn submit_vt_hash(hash: String) -> Result<Option<usize>, String> {
+    crate::logic::external_intel::virustotal::submit_hash(&hash).map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// EXECUTION ARTIFACT COMMANDS
+// ============================================================================
+
+/// Prefetch / Shimcache của một binary (lần chạy đầu, run count)
+#[tauri::command]
+pub async fn get_execution_artifacts(path: String) -> Result<crate::logic::process_intel::ExecutionArtifacts, String> {
+    Ok(crate::logic::process_intel::artifacts::collect(std::path::Path::new(&path)))
+}
+
+/// Bật/tắt enrichment Prefetch / Shimcache tự động cho incident
+#[tauri::command]
+pub async fn set_execution_artifact_enrichment(enabled: bool) -> Result<bool, String> {
+    crate::logic::process_intel::artifacts::set_enabled(enabled);
+    Ok(enabled)
+}
+
+// ============================================================================
+// PROCESS GENEALOGY COMMANDS
+// ============================================================================
+
+/// Ancestry của process (process → parent → ...), gồm cả parent đã exit
+#[tauri::command]
+pub async fn get_process_ancestry(pid: u32, start_time: Option<i64>) -> Result<Vec<crate::logic::process_intel::GenealogyRecord>, String> {
+    Ok(crate::logic::process_intel::genealogy::get_ancestry(pid, start_time))
+}
+
+// ============================================================================
+// PROCESS REPUTATION COMMANDS
+// ============================================================================
+
+/// Báo cáo reputation DB (top trusted/untrusted, most seen, stale...)
+#[tauri::command]
+pub async fn get_reputation_report(limit: Option<usize>) -> Result<crate::logic::process_intel::ReputationReport, String> {
+    Ok(crate::logic::process_intel::reputation::get_reputation_report(limit.unwrap_or(20)))
+}
+
 // ============================================================================
 // ONNX AI COMMANDS (PHASE IV)
 // ============================================================================
@@ -931,9 +1960,139 @@ pub async fn get_telemetry_stats() -> Result<serde_json::Value, String> {
         "events_recorded": stats.events_recorded,
         "current_file": stats.current_file,
         "session_id": stats.session_id,
+        "retention": stats.retention,
     }))
 }
 
+#[tauri::command]
+pub async fn get_telemetry_retention_config() -> Result<telemetry::RetentionConfig, String> {
+    Ok(telemetry::retention::get_config())
+}
+
+/// Cấu hình rotation / nén / retention (ngày, MB) cho security logs
+#[tauri::command]
+pub async fn set_telemetry_retention_config(config: telemetry::RetentionConfig) -> Result<(), String> {
+    telemetry::retention::set_config(config)
+}
+
+/// Nén file đã rotate + dọn log quá hạn ngay
+#[tauri::command]
+pub async fn run_telemetry_cleanup() -> Result<telemetry::RetentionStats, String> {
+    tokio::task::spawn_blocking(telemetry::retention::run_cleanup)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Kiểm tra hash chain + checkpoint của security logs (phát hiện dòng bị sửa / xóa)
+#[tauri::command]
+pub async fn verify_log_integrity() -> Result<telemetry::IntegrityReport, String> {
+    tokio::task::spawn_blocking(telemetry::integrity::verify_log_integrity)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn get_syslog_config() -> Result<telemetry::SyslogConfig, String> {
+    Ok(telemetry::syslog::get_config())
+}
+
+/// Cấu hình syslog output (host, port, UDP/TCP/TLS, facility)
+#[tauri::command]
+pub async fn set_syslog_config(config: telemetry::SyslogConfig) -> Result<(), String> {
+    telemetry::syslog::set_config(config)
+}
+
+/// Trạng thái syslog output (connected, sent, dropped, last error)
+#[tauri::command]
+pub async fn get_syslog_status() -> Result<telemetry::SyslogStatus, String> {
+    Ok(telemetry::syslog::get_status())
+}
+
+/// Gửi thử 1 message tới syslog server đang cấu hình
+#[tauri::command]
+pub async fn test_syslog() -> Result<(), String> {
+    tokio::task::spawn_blocking(telemetry::syslog::send_test)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn get_elastic_config() -> Result<telemetry::ElasticConfig, String> {
+    Ok(telemetry::elastic::get_config())
+}
+
+/// Cấu hình export Elasticsearch / OpenSearch (url, auth, index prefix, lifecycle policy)
+#[tauri::command]
+pub async fn set_elastic_config(config: telemetry::ElasticConfig) -> Result<(), String> {
+    telemetry::elastic::set_config(config)
+}
+
+/// Trạng thái export Elasticsearch (sent, rejected, queued, last error)
+#[tauri::command]
+pub async fn get_elastic_status() -> Result<telemetry::ElasticStatus, String> {
+    Ok(telemetry::elastic::get_status())
+}
+
+/// Flush queue lên cluster ngay (kiểm tra kết nối từ UI)
+#[tauri::command]
+pub async fn flush_elastic_export() -> Result<telemetry::ElasticStatus, String> {
+    tokio::task::spawn_blocking(telemetry::elastic::flush_now)
+        .await
+        .map_err(|e| e.to_string())??;
+    Ok(telemetry::elastic::get_status())
+}
+
+#[tauri::command]
+pub async fn get_event_log_config() -> Result<telemetry::EventLogConfig, String> {
+    Ok(telemetry::eventlog::get_config())
+}
+
+/// Bật/tắt ghi incident High/Critical + tamper vào Windows Event Log
+#[tauri::command]
+pub async fn set_event_log_config(config: telemetry::EventLogConfig) -> Result<(), String> {
+    telemetry::eventlog::set_config(config)
+}
+
+/// Trạng thái Windows Event Log (source registered, written, last error)
+#[tauri::command]
+pub async fn get_event_log_status() -> Result<telemetry::EventLogStatus, String> {
+    Ok(telemetry::eventlog::get_status())
+}
+
+#[tauri::command]
+pub async fn get_metrics_config() -> Result<telemetry::MetricsConfig, String> {
+    Ok(telemetry::metrics::get_config())
+}
+
+/// Bật/tắt Prometheus `/metrics` trên localhost, đổi port
+#[tauri::command]
+pub async fn set_metrics_config(config: telemetry::MetricsConfig) -> Result<(), String> {
+    telemetry::metrics::set_config(config)
+}
+
+/// Trạng thái metrics endpoint (địa chỉ đang listen, số lần scrape, lỗi bind)
+#[tauri::command]
+pub async fn get_metrics_status() -> Result<telemetry::MetricsStatus, String> {
+    Ok(telemetry::metrics::get_status())
+}
+
+#[tauri::command]
+pub async fn get_otel_config() -> Result<telemetry::OtelConfig, String> {
+    Ok(telemetry::otel::get_config())
+}
+
+/// Cấu hình OpenTelemetry tracing (OTLP endpoint, headers, sample ratio)
+#[tauri::command]
+pub async fn set_otel_config(config: telemetry::OtelConfig) -> Result<(), String> {
+    telemetry::otel::set_config(config)
+}
+
+/// Trạng thái OTLP export + latency theo stage của pipeline
+#[tauri::command]
+pub async fn get_otel_status() -> Result<telemetry::OtelStatus, String> {
+    Ok(telemetry::otel::get_status())
+}
+
 /// Get security analytics summary
 #[tauri::command]
 pub async fn get_security_analytics() -> Result<serde_json::Value, String> {
@@ -974,6 +2133,14 @@ pub async fn get_security_log_files() -> Result<Vec<String>, String> {
     }
 }
 
+/// Lọc + phân trang security events (time range, type, severity, pid, process, text)
+#[tauri::command]
+pub async fn query_security_events(query: telemetry::EventQuery) -> Result<telemetry::EventQueryResult, String> {
+    tokio::task::spawn_blocking(move || telemetry::query::query_security_events(&query))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
 /// Get recent security events
 #[tauri::command]
 pub async fn get_recent_security_events(limit: Option<usize>) -> Result<serde_json::Value, String> {
@@ -27,6 +27,9 @@ pub struct DatasetStatus {
     pub benign_count: u64,
     pub suspicious_count: u64,
     pub malicious_count: u64,
+    /// Files / records removed by retention caps (this session)
+    pub pruned_files: u64,
+    pub pruned_records: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
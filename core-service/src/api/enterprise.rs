@@ -4,12 +4,13 @@
 //! - RBAC (Users, Roles, Sessions)
 //! - Policy Management
 //! - File Quarantine
+//! - USB Storage Blocking
 //! - Webhook Alerts
 //! - Executive Reports
 
 use serde::{Deserialize, Serialize};
 use crate::logic::enterprise::{rbac, policy_sync, reporting};
-use crate::logic::response::{file_quarantine, webhook};
+use crate::logic::response::{file_quarantine, usb, webhook};
 
 // ============================================================================
 // DATA STRUCTURES
@@ -294,16 +295,78 @@ pub async fn quarantine_file(file_path: String, reason: String) -> Result<serde_
     let path = std::path::Path::new(&file_path);
 
     match file_quarantine::quarantine_file(path, &reason, None) {
-        Ok(entry) => Ok(serde_json::json!({
+        Ok(entry) => {
+            // Ghi vào action history để có thể undo_action
+            let action_id = crate::logic::action_guard::record_manual_action(
+                crate::logic::action_guard::ActionType::QuarantineFile,
+                None,
+                &file_path,
+                &format!("File quarantined: {}", file_path),
+                Some(crate::logic::response::expiry::RevertKind::RestoreFile {
+                    quarantine_id: entry.id.clone(),
+                }),
+            );
+            Ok(serde_json::json!({
+                "success": true,
+                "id": entry.id,
+                "action_id": action_id,
+                "sha256": entry.sha256,
+                "message": format!("File quarantined: {}", file_path),
+            }))
+        }
+        Err(e) => Err(format!("Quarantine failed: {:?}", e)),
+    }
+}
+
+/// Quarantine a whole directory tree (e.g. a dropper staging folder)
+#[tauri::command]
+pub async fn quarantine_directory(dir_path: String, reason: String) -> Result<serde_json::Value, String> {
+    let path = std::path::Path::new(&dir_path);
+
+    match file_quarantine::quarantine_directory(path, &reason, None) {
+        Ok(group) => {
+            // Ghi vào action history để có thể undo_action
+            let action_id = crate::logic::action_guard::record_manual_action(
+                crate::logic::action_guard::ActionType::QuarantineFile,
+                None,
+                &dir_path,
+                &format!("Directory quarantined: {} ({} files)", dir_path, group.entry_ids.len()),
+                Some(crate::logic::response::expiry::RevertKind::RestoreDirectory {
+                    group_id: group.id.clone(),
+                }),
+            );
+            Ok(serde_json::json!({
+                "success": true,
+                "group_id": group.id,
+                "action_id": action_id,
+                "file_count": group.entry_ids.len(),
+                "total_size": group.total_size,
+                "message": format!("Directory quarantined: {}", dir_path),
+            }))
+        }
+        Err(e) => Err(format!("Quarantine failed: {:?}", e)),
+    }
+}
+
+/// Restore a quarantined directory tree
+#[tauri::command]
+pub async fn restore_quarantined_directory(group_id: String) -> Result<serde_json::Value, String> {
+    match file_quarantine::restore_directory(&group_id) {
+        Ok(path) => Ok(serde_json::json!({
             "success": true,
-            "id": entry.id,
-            "sha256": entry.sha256,
-            "message": format!("File quarantined: {}", file_path),
+            "restored_to": path.to_string_lossy().to_string(),
+            "message": "Directory restored successfully",
         })),
-        Err(e) => Err(format!("Quarantine failed: {:?}", e)),
+        Err(e) => Err(format!("Restore failed: {:?}", e)),
     }
 }
 
+/// Get quarantined directory groups
+#[tauri::command]
+pub async fn get_quarantined_directories() -> Result<Vec<crate::logic::response::QuarantineGroup>, String> {
+    Ok(file_quarantine::get_quarantine_groups())
+}
+
 /// Restore a quarantined file
 #[tauri::command]
 pub async fn restore_quarantined_file(entry_id: String) -> Result<serde_json::Value, String> {
@@ -340,6 +403,56 @@ pub async fn get_quarantine_stats() -> Result<serde_json::Value, String> {
     }))
 }
 
+// ============================================================================
+// USB BLOCKING COMMANDS
+// ============================================================================
+
+/// List USB storage devices currently attached
+#[tauri::command]
+pub async fn get_usb_devices() -> Result<Vec<usb::UsbDevice>, String> {
+    usb::list_devices().map_err(|e| e.to_string())
+}
+
+/// Active USB blocks
+#[tauri::command]
+pub async fn get_usb_blocks() -> Result<Vec<usb::UsbBlock>, String> {
+    Ok(usb::get_blocks())
+}
+
+/// Block a USB storage device (`device_id` = None blocks all removable storage)
+#[tauri::command]
+pub async fn block_usb(device_id: Option<String>) -> Result<serde_json::Value, String> {
+    let (result, block) = usb::block_usb(device_id.as_deref()).map_err(|e| e.to_string())?;
+
+    // Ghi vào action history để có thể undo_action
+    let action_id = crate::logic::action_guard::record_manual_action(
+        crate::logic::action_guard::ActionType::BlockUsb,
+        None,
+        device_id.as_deref().unwrap_or(""),
+        &result.message,
+        Some(crate::logic::response::expiry::RevertKind::UnblockUsb {
+            block_id: block.id.clone(),
+        }),
+    );
+
+    Ok(serde_json::json!({
+        "success": true,
+        "block_id": block.id,
+        "action_id": action_id,
+        "message": result.message,
+    }))
+}
+
+/// Remove a USB block
+#[tauri::command]
+pub async fn unblock_usb(block_id: String) -> Result<serde_json::Value, String> {
+    let result = usb::unblock_usb(&block_id).map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({
+        "success": true,
+        "message": result.message,
+    }))
+}
+
 // ============================================================================
 // WEBHOOK COMMANDS
 // ============================================================================
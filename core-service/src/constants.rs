@@ -13,6 +13,11 @@ pub const DEFAULT_CLOUD_URL: &str = "https://api.accone.vn";
 /// Default registration key
 pub const DEFAULT_REGISTRATION_KEY: &str = "dev-agent-secret-change-in-production-789012";
 
+/// Public key (ed25519, hex) the server signs agent content with (rule packs,
+/// offline intel bundles). Development key of cloud-server; production builds pin
+/// their own via `ONESHIELD_CONTENT_SIGNING_PUBKEY` at compile time
+pub const DEFAULT_CONTENT_SIGNING_PUBLIC_KEY: &str = "77bdf1d794eb3985dde5cf2ae40155069698e68dd59a4e1584e3700af3e530a9";
+
 /// Default heartbeat interval (seconds)
 pub const DEFAULT_HEARTBEAT_INTERVAL: u64 = 30;
 
@@ -41,6 +46,11 @@ pub fn get_registration_key() -> String {
         .unwrap_or_else(|_| DEFAULT_REGISTRATION_KEY.to_string())
 }
 
+/// Pinned content signing public key (compile time only, never read from runtime env)
+pub fn content_signing_public_key() -> &'static str {
+    option_env!("ONESHIELD_CONTENT_SIGNING_PUBKEY").unwrap_or(DEFAULT_CONTENT_SIGNING_PUBLIC_KEY)
+}
+
 /// Get heartbeat interval from environment or use default
 pub fn get_heartbeat_interval() -> u64 {
     std::env::var("CLOUD_HEARTBEAT_INTERVAL")
@@ -12,6 +12,7 @@ use std::process::Command;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::collections::HashMap;
 use parking_lot::RwLock;
+use once_cell::sync::Lazy;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -21,6 +22,10 @@ use super::policy::{self, Decision, PolicyResult};
 
 // Telemetry imports (v0.6.1)
 use super::telemetry::{self, SecurityEvent, ProcessInfo as TelemetryProcessInfo};
+use super::response::expiry::RevertKind;
+use super::response::actions::{authorize_with, check_safety, GateDenial, Origin, Safety};
+use super::whitelist::{self, WhitelistKind};
+use super::process_intel::{IntegrityLevel, SignatureStatus};
 
 // ============================================================================
 // CONSTANTS
@@ -35,8 +40,17 @@ pub const HIGH_ALERT_THRESHOLD: f32 = 0.85;
 /// Thời gian cooldown giữa các hành động (seconds)
 const ACTION_COOLDOWN_SECS: i64 = 30;
 
-/// Số lần tối đa can thiệp 1 process trong 1 phút
-const MAX_ACTIONS_PER_MINUTE: u32 = 3;
+/// Thời gian pending action chờ approval (seconds)
+const PENDING_TTL_SECS: i64 = 300;
+
+const CONFIG_FILE: &str = "action_guard.json";
+
+/// Strike của process tái phạm được giữ trong khoảng này (seconds)
+const ESCALATION_WINDOW_SECS: i64 = 3600;
+
+/// Verify sau action: số lần retry và thời gian chờ mặc định
+const VERIFY_RETRIES: u32 = 2;
+const VERIFY_DELAY_MS: u64 = 1000;
 
 // ============================================================================
 // STATE
@@ -51,8 +65,9 @@ static ACTION_HISTORY: RwLock<Vec<ActionRecord>> = RwLock::new(Vec::new());
 /// Actions đang pending (chờ approval)
 static PENDING_ACTIONS: RwLock<Vec<PendingAction>> = RwLock::new(Vec::new());
 
-/// Whitelist processes (không can thiệp)
-static WHITELIST: RwLock<Vec<String>> = RwLock::new(Vec::new());
+/// Escalation ladder per process name (không reset theo cooldown)
+static ESCALATIONS: Lazy<RwLock<HashMap<String, Escalation>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
 
 /// Cooldown tracker per process
 static PROCESS_COOLDOWNS: RwLock<Option<HashMap<u32, DateTime<Utc>>>> = RwLock::new(None);
@@ -66,12 +81,31 @@ static PROCESS_COOLDOWNS: RwLock<Option<HashMap<u32, DateTime<Utc>>>> = RwLock::
 pub enum ActionType {
     /// Dừng tiến trình
     KillProcess,
+    /// Dừng tiến trình cùng toàn bộ process con (bottom-up)
+    KillProcessTree,
     /// Block network I/O (firewall rule)
     BlockNetworkIO,
+    /// Chặn outbound tới destination cho mọi process (target_name = hostname hoặc IP)
+    BlockDestination,
     /// Suspend process (tạm dừng)
     SuspendProcess,
+    /// Resume process đã bị suspend (khôi phục, không phải can thiệp)
+    ResumeProcess,
     /// Isolate user session
     IsolateSession,
+    /// Cách ly máy khỏi mạng (chỉ giữ cloud server + DNS)
+    IsolateHost,
+    /// Đưa file vào quarantine vault (target_name = đường dẫn file)
+    QuarantineFile,
+    /// Gỡ registry persistence, có snapshot để undo
+    /// (target_name = `key [value]`, hoặc `key` khi xóa cả key)
+    RemovePersistence,
+    /// Chặn USB storage (target_name = PnP instance ID `USBSTOR\...`,
+    /// còn lại = toàn bộ removable storage)
+    BlockUsb,
+    /// Threat spawn từ browser: kill nhánh con, quarantine file tải về, block hash
+    /// (browser session giữ nguyên)
+    ContainBrowserChild,
     /// Alert only (không can thiệp)
     AlertOnly,
 }
@@ -80,20 +114,36 @@ impl ActionType {
     pub fn to_string(&self) -> String {
         match self {
             ActionType::KillProcess => "KILL_PROCESS".to_string(),
+            ActionType::KillProcessTree => "KILL_PROCESS_TREE".to_string(),
             ActionType::BlockNetworkIO => "BLOCK_NETWORK".to_string(),
+            ActionType::BlockDestination => "BLOCK_DESTINATION".to_string(),
             ActionType::SuspendProcess => "SUSPEND_PROCESS".to_string(),
+            ActionType::ResumeProcess => "RESUME_PROCESS".to_string(),
             ActionType::IsolateSession => "ISOLATE_SESSION".to_string(),
+            ActionType::IsolateHost => "ISOLATE_HOST".to_string(),
+            ActionType::QuarantineFile => "QUARANTINE_FILE".to_string(),
+            ActionType::RemovePersistence => "REMOVE_PERSISTENCE".to_string(),
+            ActionType::BlockUsb => "BLOCK_USB".to_string(),
+            ActionType::ContainBrowserChild => "CONTAIN_BROWSER_CHILD".to_string(),
             ActionType::AlertOnly => "ALERT_ONLY".to_string(),
         }
     }
 
     pub fn severity(&self) -> u8 {
         match self {
+            ActionType::ResumeProcess => 0,
             ActionType::AlertOnly => 1,
             ActionType::SuspendProcess => 2,
             ActionType::BlockNetworkIO => 3,
+            ActionType::BlockDestination => 3,
             ActionType::KillProcess => 4,
+            ActionType::KillProcessTree => 4,
             ActionType::IsolateSession => 5,
+            ActionType::IsolateHost => 5,
+            ActionType::QuarantineFile => 3,
+            ActionType::RemovePersistence => 3,
+            ActionType::BlockUsb => 3,
+            ActionType::ContainBrowserChild => 4,
         }
     }
 }
@@ -106,6 +156,10 @@ pub enum ActionStatus {
     Executed,
     Failed,
     Cancelled,
+    /// Dry-run: action được tính toán nhưng không thực thi
+    Simulated,
+    /// Đã được undo
+    Reverted,
 }
 
 /// Record một hành động đã thực hiện
@@ -121,6 +175,9 @@ pub struct ActionRecord {
     pub result: Option<String>,
     pub executed_at: DateTime<Utc>,
     pub auto_executed: bool,
+    /// Cách đảo ngược action (None = không reversible)
+    #[serde(default)]
+    pub undo: Option<RevertKind>,
 }
 
 /// Hành động đang chờ approval
@@ -147,24 +204,155 @@ pub struct ActionResult {
 }
 
 /// Cấu hình Action Guard
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ActionGuardConfig {
     pub enabled: bool,
-    pub auto_execute: bool,          // Tự động thực hiện hay chờ approval
+    pub auto_execute: bool,          // false = mọi action đều chờ approval
     pub action_threshold: f32,
     pub high_alert_threshold: f32,
-    pub require_confirmation: bool,   // Yêu cầu user xác nhận
+    pub cooldown_secs: i64,          // Cooldown giữa các action trên cùng process
+    pub pending_ttl_secs: i64,       // Thời gian pending action chờ approval
+    pub escalation_enabled: bool,    // Alert → Suspend → Kill cho process tái phạm
+    pub escalation_window_secs: i64, // Strike bị quên sau khoảng thời gian này
+    pub verify_enabled: bool,        // Kiểm tra action có hiệu lực sau khi thực thi
+    pub verify_retries: u32,         // Số lần thực thi lại nếu verify thất bại
+    pub verify_delay_ms: u64,        // Chờ trước mỗi lần verify
+    pub verify_escalate: bool,       // Suspend/Block không giữ được → Kill (opt-in)
 }
 
 impl Default for ActionGuardConfig {
     fn default() -> Self {
         Self {
             enabled: true,
-            auto_execute: false,      // Mặc định: chờ approval
+            auto_execute: true,       // Policy Engine quyết định auto/approval
             action_threshold: ACTION_THRESHOLD,
             high_alert_threshold: HIGH_ALERT_THRESHOLD,
-            require_confirmation: true,
+            cooldown_secs: ACTION_COOLDOWN_SECS,
+            pending_ttl_secs: PENDING_TTL_SECS,
+            escalation_enabled: true,
+            escalation_window_secs: ESCALATION_WINDOW_SECS,
+            verify_enabled: true,
+            verify_retries: VERIFY_RETRIES,
+            verify_delay_ms: VERIFY_DELAY_MS,
+            verify_escalate: false,
+        }
+    }
+}
+
+impl ActionGuardConfig {
+    fn validate(&self) -> Result<(), ActionError> {
+        let in_range = |v: f32| (0.0..=1.0).contains(&v);
+        if !in_range(self.action_threshold) || !in_range(self.high_alert_threshold) {
+            return Err(ActionError("Thresholds must be between 0 and 1".to_string()));
+        }
+        if self.high_alert_threshold > self.action_threshold {
+            return Err(ActionError("high_alert_threshold must not exceed action_threshold".to_string()));
+        }
+        if self.cooldown_secs < 0 || self.pending_ttl_secs <= 0 || self.escalation_window_secs <= 0 {
+            return Err(ActionError(
+                "cooldown_secs must be >= 0, pending_ttl_secs and escalation_window_secs > 0".to_string(),
+            ));
+        }
+        if self.verify_retries > 10 || self.verify_delay_ms > 60_000 {
+            return Err(ActionError("verify_retries must be <= 10 and verify_delay_ms <= 60000".to_string()));
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// CONFIG MANAGEMENT
+// ============================================================================
+
+/// Config do user đặt (persist ra disk)
+static LOCAL_CONFIG: Lazy<RwLock<ActionGuardConfig>> = Lazy::new(|| RwLock::new(load_config()));
+
+/// Override từ cloud policy (`config.action_guard`, chỉ các field được set)
+static CLOUD_OVERRIDE: RwLock<Option<serde_json::Value>> = RwLock::new(None);
+
+/// Config đang có hiệu lực = local + cloud override
+static EFFECTIVE_CONFIG: Lazy<RwLock<ActionGuardConfig>> =
+    Lazy::new(|| RwLock::new(LOCAL_CONFIG.read().clone()));
+
+/// Config đang có hiệu lực
+pub fn get_config() -> ActionGuardConfig {
+    EFFECTIVE_CONFIG.read().clone()
+}
+
+/// Config local (trước khi áp cloud override)
+pub fn get_local_config() -> ActionGuardConfig {
+    LOCAL_CONFIG.read().clone()
+}
+
+/// Field nào đang bị cloud policy override
+pub fn get_cloud_override() -> Option<serde_json::Value> {
+    CLOUD_OVERRIDE.read().clone()
+}
+
+/// Cập nhật config local và lưu ra disk
+pub fn set_config(config: ActionGuardConfig) -> Result<ActionGuardConfig, ActionError> {
+    config.validate()?;
+    *LOCAL_CONFIG.write() = config;
+    save_config();
+    recompute_config();
+    log::info!("Action Guard config updated: {:?}", get_config());
+    Ok(get_config())
+}
+
+/// Áp override từ cloud policy (None = bỏ override)
+pub fn apply_cloud_config(overrides: Option<&serde_json::Value>) {
+    *CLOUD_OVERRIDE.write() = overrides.filter(|v| v.is_object()).cloned();
+    recompute_config();
+}
+
+fn recompute_config() {
+    let local = LOCAL_CONFIG.read().clone();
+    let effective = match CLOUD_OVERRIDE.read().as_ref().and_then(|o| o.as_object()) {
+        Some(overrides) => {
+            let mut merged = serde_json::to_value(&local).unwrap_or_default();
+            if let Some(obj) = merged.as_object_mut() {
+                for (key, value) in overrides {
+                    if obj.contains_key(key) {
+                        obj.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            match serde_json::from_value::<ActionGuardConfig>(merged) {
+                Ok(config) if config.validate().is_ok() => config,
+                _ => {
+                    log::warn!("Ignoring invalid Action Guard override from cloud policy");
+                    local
+                }
+            }
         }
+        None => local,
+    };
+    *EFFECTIVE_CONFIG.write() = effective;
+}
+
+fn config_path() -> std::path::PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| std::path::PathBuf::from("."))
+        .join("OneShield")
+        .join(CONFIG_FILE)
+}
+
+fn load_config() -> ActionGuardConfig {
+    std::fs::read_to_string(config_path())
+        .ok()
+        .and_then(|c| serde_json::from_str::<ActionGuardConfig>(&c).ok())
+        .filter(|c| c.validate().is_ok())
+        .unwrap_or_default()
+}
+
+fn save_config() {
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*LOCAL_CONFIG.read()) {
+        let _ = std::fs::write(path, json);
     }
 }
 
@@ -187,41 +375,78 @@ impl std::error::Error for ActionError {}
 // WHITELIST MANAGEMENT
 // ============================================================================
 
-/// Thêm process vào whitelist
+/// Thêm process vào whitelist (theo tên)
 pub fn add_to_whitelist(process_name: &str) {
-    let mut whitelist = WHITELIST.write();
-    if !whitelist.contains(&process_name.to_lowercase()) {
-        whitelist.push(process_name.to_lowercase());
-        log::info!("Added to whitelist: {}", process_name);
+    match whitelist::add(WhitelistKind::Name, process_name, None) {
+        Ok(_) => reset_escalation(process_name),
+        Err(e) => log::warn!("Cannot whitelist {}: {}", process_name, e),
     }
 }
 
-/// Xóa process khỏi whitelist
+/// Xóa process khỏi whitelist (id hoặc value)
 pub fn remove_from_whitelist(process_name: &str) {
-    let mut whitelist = WHITELIST.write();
-    whitelist.retain(|p| p != &process_name.to_lowercase());
+    whitelist::remove(process_name);
 }
 
-/// Kiểm tra process có trong whitelist không
+/// Kiểm tra process có trong whitelist không (chỉ theo tên)
 pub fn is_whitelisted(process_name: &str) -> bool {
-    let whitelist = WHITELIST.read();
+    whitelist::is_whitelisted(&whitelist::Target { name: process_name, exe_path: None })
+}
 
-    // System processes luôn được whitelist
-    let system_procs = [
-        "system", "smss.exe", "csrss.exe", "wininit.exe", "winlogon.exe",
-        "services.exe", "lsass.exe", "svchost.exe", "dwm.exe",
-        "explorer.exe", "taskhostw.exe", "runtimebroker.exe",
-    ];
+/// Kiểm tra whitelist đầy đủ: tên, path, hash và publisher của executable
+pub fn is_process_whitelisted(pid: Option<u32>, process_name: &str) -> bool {
+    let exe_path = pid.and_then(process_exe_path);
+    whitelist::is_whitelisted(&whitelist::Target {
+        name: process_name,
+        exe_path: exe_path.as_deref(),
+    })
+}
 
-    let name_lower = process_name.to_lowercase();
+/// Lấy danh sách whitelist (values)
+pub fn get_whitelist() -> Vec<String> {
+    whitelist::list().into_iter().map(|e| e.value).collect()
+}
+
+/// Binary unsigned chỉ chạy trên vài endpoint trong org (theo prevalence đã cache)
+fn is_rare_in_fleet(path: &std::path::Path) -> bool {
+    use super::process_intel::{prevalence, signature};
 
-    system_procs.iter().any(|&p| name_lower.contains(p)) ||
-        whitelist.iter().any(|p| name_lower.contains(p))
+    let Some(sha256) = prevalence::record_path(path) else { return false };
+    // Verify chữ ký (PowerShell) chỉ khi prevalence đã thấp
+    prevalence::is_rare_in_fleet(&sha256) && !signature::is_signed(path)
 }
 
-/// Lấy danh sách whitelist
-pub fn get_whitelist() -> Vec<String> {
-    WHITELIST.read().clone()
+/// Binary không có chữ ký hợp lệ. Chỉ verify (PowerShell) khi process chạy
+/// quyền cao; còn lại dùng kết quả đã cache.
+fn is_unsigned_binary(path: &std::path::Path, privileged: bool) -> bool {
+    use super::process_intel::signature;
+
+    let status = match signature::cached_status(path) {
+        Some(status) => status,
+        None if privileged => signature::verify_signature(path).status,
+        None => return false,
+    };
+    matches!(
+        status,
+        SignatureStatus::Unsigned | SignatureStatus::Invalid { .. } | SignatureStatus::Revoked { .. }
+    )
+}
+
+/// Executable của PID, lấy từ process tree snapshot dùng chung. Chỉ query
+/// riêng PID khi nó mới xuất hiện sau lần refresh gần nhất.
+fn process_exe_path(pid: u32) -> Option<std::path::PathBuf> {
+    use sysinfo::{Pid, System};
+
+    if let Some(info) = super::process_intel::tree::get_process_info(pid) {
+        return info.exe_path;
+    }
+
+    let mut sys = System::new();
+    let pid = Pid::from_u32(pid);
+    if !sys.refresh_process(pid) {
+        return None;
+    }
+    sys.process(pid).and_then(|p| p.exe()).map(|p| p.to_path_buf())
 }
 
 // ============================================================================
@@ -234,7 +459,7 @@ fn is_in_cooldown(pid: u32) -> bool {
     if let Some(cooldowns) = guard.as_ref() {
         if let Some(last_action) = cooldowns.get(&pid) {
             let elapsed = (Utc::now() - *last_action).num_seconds();
-            return elapsed < ACTION_COOLDOWN_SECS;
+            return elapsed < get_config().cooldown_secs;
         }
     }
     false
@@ -255,6 +480,65 @@ fn set_cooldown(pid: u32) {
     }
 }
 
+// ============================================================================
+// ESCALATION LADDER
+// ============================================================================
+
+/// Trạng thái tái phạm của một process
+#[derive(Debug, Clone, Serialize)]
+pub struct Escalation {
+    pub process_name: String,
+    pub strikes: u32,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Ghi nhận một lần phát hiện và trả về bậc thang hiện tại (1, 2, 3+)
+fn record_strike(process_name: &str, window_secs: i64) -> u32 {
+    let key = process_name.to_lowercase();
+    let now = Utc::now();
+    let mut escalations = ESCALATIONS.write();
+
+    // Quên các process lâu không tái phạm
+    escalations.retain(|_, e| (now - e.last_seen).num_seconds() < window_secs);
+
+    let entry = escalations.entry(key).or_insert_with(|| Escalation {
+        process_name: process_name.to_string(),
+        strikes: 0,
+        first_seen: now,
+        last_seen: now,
+    });
+    entry.strikes += 1;
+    entry.last_seen = now;
+    entry.strikes
+}
+
+/// Giới hạn action theo số strike: lần 1 alert, lần 2 suspend, lần 3 trở đi
+/// dùng action theo score. Isolate (tấn công phối hợp) không bị giới hạn.
+fn escalate(action: ActionType, strikes: u32) -> ActionType {
+    match action {
+        ActionType::IsolateSession | ActionType::IsolateHost
+        | ActionType::AlertOnly | ActionType::ResumeProcess => action,
+        _ => match strikes {
+            1 => ActionType::AlertOnly,
+            2 if action.severity() > ActionType::SuspendProcess.severity() => ActionType::SuspendProcess,
+            _ => action,
+        },
+    }
+}
+
+/// Xóa escalation của process (khi được whitelist)
+pub fn reset_escalation(process_name: &str) {
+    ESCALATIONS.write().remove(&process_name.to_lowercase());
+}
+
+/// Danh sách process đang trên escalation ladder
+pub fn get_escalations() -> Vec<Escalation> {
+    let mut list: Vec<_> = ESCALATIONS.read().values().cloned().collect();
+    list.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+    list
+}
+
 // ============================================================================
 // ACTION IMPLEMENTATIONS
 // ============================================================================
@@ -322,6 +606,32 @@ pub fn kill_process(pid: u32) -> Result<ActionResult, ActionError> {
     }
 }
 
+/// Kill một process cùng toàn bộ cây con
+///
+/// Con cháu được liệt kê qua `process_intel::tree` và bị dừng từ lá lên gốc,
+/// tránh trường hợp dropper bị kill nhưng payload con vẫn sống.
+pub fn kill_process_tree(pid: u32) -> Result<ActionResult, ActionError> {
+    if is_in_cooldown(pid) {
+        return Err(ActionError(format!("Process {} đang trong cooldown", pid)));
+    }
+
+    log::warn!("Executing KILL_PROCESS_TREE for PID: {}", pid);
+
+    let result = super::response::actions::kill_process_tree(pid)
+        .map_err(|e| ActionError(format!("Kill tree failed: {}", e)))?;
+
+    set_cooldown(pid);
+    TOTAL_ACTIONS.fetch_add(1, Ordering::SeqCst);
+
+    Ok(ActionResult {
+        success: result.status == super::response::ActionStatus::Success,
+        action_type: ActionType::KillProcessTree,
+        target_pid: Some(pid),
+        message: result.message,
+        executed_at: Utc::now(),
+    })
+}
+
 /// Suspend (tạm dừng) một process
 #[cfg(windows)]
 pub fn suspend_process(pid: u32) -> Result<ActionResult, ActionError> {
@@ -331,34 +641,18 @@ pub fn suspend_process(pid: u32) -> Result<ActionResult, ActionError> {
 
     log::warn!("Executing SUSPEND_PROCESS for PID: {}", pid);
 
-    // Windows: Use pssuspend từ Sysinternals hoặc PowerShell
-    // Fallback: Dùng debug API (cần admin)
-    let _output = Command::new("powershell")
-        .args([
-            "-Command",
-            &format!(
-                "try {{ \
-                    $p = Get-Process -Id {}; \
-                    $p.Suspend(); \
-                    Write-Output 'OK' \
-                }} catch {{ \
-                    Write-Error $_.Exception.Message \
-                }}",
-                pid
-            ),
-        ])
-        .output()
-        .map_err(|e| ActionError(format!("Failed to suspend: {}", e)))?;
+    // NtSuspendProcess qua ntdll (cần quyền tương đương process đích)
+    super::response::actions::nt_suspend_process(pid)
+        .map_err(|e| ActionError(format!("Suspend failed: {}", e)))?;
 
     set_cooldown(pid);
     TOTAL_ACTIONS.fetch_add(1, Ordering::SeqCst);
 
-    // PowerShell Suspend thường không work, fallback message
     Ok(ActionResult {
         success: true,
         action_type: ActionType::SuspendProcess,
         target_pid: Some(pid),
-        message: format!("Process {} đã được đánh dấu để suspend (cần admin)", pid),
+        message: format!("Process {} đã bị tạm dừng (NtSuspendProcess)", pid),
         executed_at: Utc::now(),
     })
 }
@@ -392,12 +686,69 @@ pub fn suspend_process(pid: u32) -> Result<ActionResult, ActionError> {
     }
 }
 
+/// Ransomware profile (suspend cả tree, shadow-copy guard, recovery snapshot)
+/// thay cho suspend đơn lẻ - cùng cooldown với các action khác
+fn suspend_ransomware(pid: u32, process_name: &str, tags: &[String]) -> Result<ActionResult, ActionError> {
+    if is_in_cooldown(pid) {
+        return Err(ActionError(format!("Process {} đang trong cooldown", pid)));
+    }
+
+    let result = super::response::ransomware::respond(pid, process_name, tags)
+        .map_err(|e| ActionError(format!("Ransomware response failed: {}", e)))?;
+
+    set_cooldown(pid);
+    TOTAL_ACTIONS.fetch_add(1, Ordering::SeqCst);
+
+    Ok(ActionResult {
+        success: result.status != super::response::ActionStatus::Failed,
+        action_type: ActionType::SuspendProcess,
+        target_pid: Some(pid),
+        message: result.message,
+        executed_at: Utc::now(),
+    })
+}
+
+/// Resume (tiếp tục) một process đã bị suspend
+///
+/// Không áp dụng cooldown: resume là thao tác khôi phục, không phải can thiệp.
+pub fn resume_process(pid: u32) -> Result<ActionResult, ActionError> {
+    log::info!("Executing RESUME_PROCESS for PID: {}", pid);
+
+    super::response::actions::nt_resume_process(pid)
+        .map_err(|e| ActionError(format!("Resume failed: {}", e)))?;
+
+    super::response::expiry::forget(&super::response::expiry::RevertKind::ResumeProcess { pid });
+
+    Ok(ActionResult {
+        success: true,
+        action_type: ActionType::ResumeProcess,
+        target_pid: Some(pid),
+        message: format!("Process {} đã được resume", pid),
+        executed_at: Utc::now(),
+    })
+}
+
 /// Block network I/O của một process
 #[cfg(windows)]
 pub fn block_network_io(pid: u32, process_name: &str) -> Result<ActionResult, ActionError> {
     log::warn!("Executing BLOCK_NETWORK for PID: {} ({})", pid, process_name);
 
-    // Tạo Windows Firewall rule để block
+    // Ưu tiên WFP: block theo image thật của PID, tự gỡ khi process exit
+    match super::response::wfp::block_pid(pid) {
+        Ok(result) => {
+            TOTAL_ACTIONS.fetch_add(1, Ordering::SeqCst);
+            return Ok(ActionResult {
+                success: true,
+                action_type: ActionType::BlockNetworkIO,
+                target_pid: Some(pid),
+                message: result.message,
+                executed_at: Utc::now(),
+            });
+        }
+        Err(e) => log::warn!("WFP block failed for PID {}, falling back to netsh: {}", pid, e),
+    }
+
+    // Fallback: Windows Firewall rule theo program path
     let rule_name = format!("AISecurityBlock_{}", pid);
 
     // Block outbound
@@ -475,6 +826,71 @@ pub fn block_network_io(pid: u32, process_name: &str) -> Result<ActionResult, Ac
     }
 }
 
+/// Gỡ block network của một process (WFP filter hoặc firewall rule)
+#[cfg(windows)]
+pub fn unblock_network_io(pid: u32) -> Result<ActionResult, ActionError> {
+    log::info!("Executing UNBLOCK_NETWORK for PID: {}", pid);
+
+    let message = if super::response::wfp::is_blocked(pid) {
+        super::response::wfp::unblock_pid(pid)
+            .map_err(|e| ActionError(format!("WFP unblock failed: {}", e)))?
+            .message
+    } else {
+        let rule_name = format!("AISecurityBlock_{}", pid);
+        let output = Command::new("netsh")
+            .args(["advfirewall", "firewall", "delete", "rule", &format!("name={}", rule_name)])
+            .output()
+            .map_err(|e| ActionError(format!("netsh failed: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(ActionError(format!(
+                "Không xóa được rule {}: {}",
+                rule_name,
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+        format!("Đã xóa firewall rule {}", rule_name)
+    };
+
+    super::response::expiry::forget(&super::response::expiry::RevertKind::UnblockNetwork { pid });
+
+    Ok(ActionResult {
+        success: true,
+        action_type: ActionType::BlockNetworkIO,
+        target_pid: Some(pid),
+        message,
+        executed_at: Utc::now(),
+    })
+}
+
+#[cfg(not(windows))]
+pub fn unblock_network_io(pid: u32) -> Result<ActionResult, ActionError> {
+    log::info!("Executing UNBLOCK_NETWORK for PID: {}", pid);
+
+    let output = Command::new("iptables")
+        .args([
+            "-D", "OUTPUT",
+            "-m", "owner", "--pid-owner", &pid.to_string(),
+            "-j", "DROP",
+        ])
+        .output()
+        .map_err(|e| ActionError(format!("iptables failed: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(ActionError("Cần quyền root để gỡ block network".to_string()));
+    }
+
+    super::response::expiry::forget(&super::response::expiry::RevertKind::UnblockNetwork { pid });
+
+    Ok(ActionResult {
+        success: true,
+        action_type: ActionType::BlockNetworkIO,
+        target_pid: Some(pid),
+        message: format!("Network I/O của PID {} đã được mở lại", pid),
+        executed_at: Utc::now(),
+    })
+}
+
 /// Isolate user session (lock workstation)
 #[cfg(windows)]
 pub fn isolate_session() -> Result<ActionResult, ActionError> {
@@ -524,6 +940,24 @@ pub fn isolate_session() -> Result<ActionResult, ActionError> {
     Err(ActionError("Không thể lock session".to_string()))
 }
 
+/// Cách ly toàn bộ máy khỏi mạng (chỉ cloud server + DNS còn truy cập được)
+pub fn isolate_host() -> Result<ActionResult, ActionError> {
+    log::warn!("Executing ISOLATE_HOST");
+
+    let result = super::response::network::isolate_host(&[])
+        .map_err(|e| ActionError(format!("Isolate host failed: {}", e)))?;
+
+    TOTAL_ACTIONS.fetch_add(1, Ordering::SeqCst);
+
+    Ok(ActionResult {
+        success: true,
+        action_type: ActionType::IsolateHost,
+        target_pid: None,
+        message: result.message,
+        executed_at: Utc::now(),
+    })
+}
+
 // ============================================================================
 // DECISION ENGINE
 // ============================================================================
@@ -535,8 +969,13 @@ pub fn decide_action(
     target_pid: Option<u32>,
     target_name: &str,
 ) -> Option<ActionType> {
+    let config = get_config();
+    if !config.enabled {
+        return None;
+    }
+
     // Không can thiệp process whitelist
-    if is_whitelisted(target_name) {
+    if is_process_whitelisted(target_pid, target_name) {
         return None;
     }
 
@@ -548,7 +987,7 @@ pub fn decide_action(
     }
 
     // Critical: Kill hoặc Isolate
-    if final_score >= ACTION_THRESHOLD {
+    if final_score >= config.action_threshold {
         // Kiểm tra tags để quyết định hành động phù hợp
         let has_coordinated = tags.iter().any(|t| t.contains("COORDINATED"));
         let has_critical = tags.iter().any(|t| t.contains("CRITICAL"));
@@ -567,7 +1006,7 @@ pub fn decide_action(
     }
 
     // High alert: Suspend
-    if final_score >= HIGH_ALERT_THRESHOLD {
+    if final_score >= config.high_alert_threshold {
         return Some(ActionType::SuspendProcess);
     }
 
@@ -610,8 +1049,24 @@ pub struct PipelineOutput {
 ///
 /// Flow: AI Score → Threat Classification → Policy Decision → Action
 pub fn decide_with_pipeline(input: &PipelineInput) -> PipelineOutput {
+    let mut span = crate::logic::telemetry::otel::span("action_guard.decide");
+    span.set_attr("process.pid", input.target_pid);
+    let config = get_config();
+    if !config.enabled {
+        return PipelineOutput {
+            threat_class: "Unknown".to_string(),
+            decision: "SilentLog".to_string(),
+            severity: "Low".to_string(),
+            action: None,
+            auto_execute: false,
+            confidence: 0.0,
+            reasons: vec!["Action Guard is disabled".to_string()],
+        };
+    }
+
     // Step 1: Validate whitelist
-    if is_whitelisted(&input.target_name) {
+    if is_process_whitelisted(Some(input.target_pid), &input.target_name) {
+        reset_escalation(&input.target_name);
         return PipelineOutput {
             threat_class: "Benign".to_string(),
             decision: "SilentLog".to_string(),
@@ -649,6 +1104,9 @@ pub fn decide_with_pipeline(input: &PipelineInput) -> PipelineOutput {
         ..Default::default()
     };
 
+    let exe_path = process_exe_path(input.target_pid);
+    let token = super::process_intel::token::query(input.target_pid);
+    let integrity = token.as_ref().map(|t| t.integrity);
     let context = ThreatContext {
         is_new_process: input.is_new_process,
         is_whitelisted: false, // Already checked above
@@ -657,17 +1115,68 @@ pub fn decide_with_pipeline(input: &PipelineInput) -> PipelineOutput {
         tags: input.tags.clone(),
         process_name: Some(input.target_name.clone()),
         pid: Some(input.target_pid),
+        static_risk_score: exe_path.as_deref()
+            .and_then(super::advanced_detection::pe_static::risk_score),
+        rare_in_fleet: exe_path.as_deref().map(is_rare_in_fleet).unwrap_or(false),
+        user: token.as_ref().and_then(|t| t.user.clone()),
+        is_admin_session: token.as_ref().map(|t| t.is_admin_session()).unwrap_or(false),
+        integrity_level: integrity,
+        is_unsigned: exe_path.as_deref()
+            .map(|p| is_unsigned_binary(p, integrity >= Some(IntegrityLevel::High)))
+            .unwrap_or(false),
         ..Default::default()
     };
 
     // Step 4: Classify threat
-    let classification = threat::classify(&anomaly, &baseline, &context);
+    let classification = {
+        let _span = crate::logic::telemetry::otel::span("threat.classify");
+        threat::classify(&anomaly, &baseline, &context)
+    };
 
     // Step 5: Get policy decision
-    let policy_result = policy::decide(&classification);
+    let policy_result = {
+        let _span = crate::logic::telemetry::otel::span("policy.decide");
+        policy::decide(&classification)
+    };
 
     // Step 6: Map policy action to our ActionType
-    let action = map_policy_action(&policy_result, &classification);
+    let mut action = map_policy_action(&policy_result, &classification);
+
+    // Process đã spawn con → kill cả cây để payload con không sống sót
+    if action == Some(ActionType::KillProcess) && input.child_count > 0 {
+        action = Some(ActionType::KillProcessTree);
+    }
+
+    // Escalation ladder: process tái phạm mới bị xử lý nặng dần
+    let mut escalation_reason = None;
+    // (ransomware không chờ tái phạm)
+    if config.escalation_enabled && !super::response::ransomware::is_ransomware(&input.tags) {
+        if let Some(a) = action.filter(|a| *a != ActionType::AlertOnly) {
+            let strikes = record_strike(&input.target_name, config.escalation_window_secs);
+            let escalated = escalate(a, strikes);
+            if escalated != a {
+                escalation_reason = Some(format!(
+                    "Escalation strike {}: {} capped to {}",
+                    strikes, a.to_string(), escalated.to_string()
+                ));
+                action = Some(escalated);
+            }
+        }
+    }
+
+    // Threat spawn từ browser: chỉ xử lý nhánh con, không isolate cả session
+    if matches!(action, Some(ActionType::KillProcess | ActionType::KillProcessTree | ActionType::IsolateSession))
+        && super::response::browser_child::find_browser_ancestor(input.target_pid).is_some()
+    {
+        action = Some(ActionType::ContainBrowserChild);
+    }
+
+    // Exfiltration ra USB: chặn removable storage thay vì đụng tới process
+    if super::response::usb::is_removable_exfil(&input.tags)
+        && action.is_some_and(|a| a != ActionType::AlertOnly)
+    {
+        action = Some(ActionType::BlockUsb);
+    }
 
     // FREEZE CORE: Safety Config Check
     let (final_action, auto_exec) = if !crate::logic::config::SafetyConfig::is_auto_block_enabled() {
@@ -678,10 +1187,11 @@ pub fn decide_with_pipeline(input: &PipelineInput) -> PipelineOutput {
             (action, false)
         }
     } else {
-        (action, policy_result.auto_execute)
+        (action, policy_result.auto_execute && config.auto_execute)
     };
 
     // Step 7: Build output
+    span.set_attr("decision", format!("{:?}", policy_result.decision));
     PipelineOutput {
         threat_class: format!("{:?}", classification.threat_class),
         decision: format!("{:?}", policy_result.decision),
@@ -692,6 +1202,7 @@ pub fn decide_with_pipeline(input: &PipelineInput) -> PipelineOutput {
         reasons: [
             classification.reasons.clone(),
             policy_result.reasons.clone(),
+            escalation_reason.into_iter().collect(),
         ].concat(),
     }
 }
@@ -717,6 +1228,41 @@ fn map_policy_action(policy: &PolicyResult, _classification: &ClassificationResu
     }
 }
 
+/// Ghi lại action mà Action Guard *sẽ* làm (dry-run mode)
+fn record_simulated(mut record: ActionRecord) -> ActionResult {
+    let would = if record.auto_executed {
+        format!("would execute {}", record.action_type.to_string())
+    } else {
+        format!("would request approval for {}", record.action_type.to_string())
+    };
+    let message = format!("[DRY-RUN] {} on {}", would, record.target_name);
+
+    log::info!("{} (score: {:.2})", message, record.final_score);
+
+    telemetry::record(
+        SecurityEvent::action_created(
+            TelemetryProcessInfo::new(record.target_pid.unwrap_or(0), &record.target_name),
+            record.action_type,
+            record.auto_executed,
+        )
+        .with_metadata(serde_json::json!({ "dry_run": true })),
+    );
+
+    let result = ActionResult {
+        success: true,
+        action_type: record.action_type,
+        target_pid: record.target_pid,
+        message: message.clone(),
+        executed_at: Utc::now(),
+    };
+
+    record.status = ActionStatus::Simulated;
+    record.result = Some(message);
+    ACTION_HISTORY.write().push(record);
+
+    result
+}
+
 /// Execute action based on pipeline output
 pub fn execute_from_pipeline(
     input: &PipelineInput,
@@ -727,6 +1273,44 @@ pub fn execute_from_pipeline(
         None => return Err(ActionError("No action required".to_string())),
     };
 
+    // Ransomware: suspend cả tree + chặn xóa shadow copy + ghi lại file vừa bị sửa.
+    // Đi qua execute_action như mọi action khác (whitelist, Safety Config, approval,
+    // rate limit, cooldown, history) - profile chạy trong nhánh SuspendProcess
+    if action != ActionType::AlertOnly && super::response::ransomware::is_ransomware(&input.tags) {
+        return execute_action(
+            ActionType::SuspendProcess,
+            Some(input.target_pid),
+            &input.target_name,
+            input.anomaly_score,
+            input.tags.clone(),
+            output.auto_execute,
+        );
+    }
+
+    let permitted = check_safety(Safety::current(), action.severity(), Origin::Automatic).is_ok();
+    // Playbook gắn với threat class / rule thay thế action đơn lẻ
+    if permitted && output.auto_execute && action != ActionType::AlertOnly && !is_process_whitelisted(Some(input.target_pid), &input.target_name) {
+        if let Some(playbook) = super::response::find_playbook(Some(&output.threat_class), &input.tags) {
+            let run = super::response::playbook::execute(&playbook, super::response::PlaybookContext {
+                pid: Some(input.target_pid),
+                process_name: Some(input.target_name.clone()),
+                threat_class: Some(output.threat_class.clone()),
+                ..Default::default()
+            }, Origin::Automatic);
+            set_cooldown(input.target_pid);
+            TOTAL_ACTIONS.fetch_add(1, Ordering::SeqCst);
+
+            let ok = run.steps.iter().filter(|s| s.success).count();
+            return Ok(ActionResult {
+                success: run.status != super::response::ActionStatus::Failed,
+                action_type: action,
+                target_pid: Some(input.target_pid),
+                message: format!("Playbook '{}': {}/{} steps succeeded", playbook.id, ok, run.steps.len()),
+                executed_at: Utc::now(),
+            });
+        }
+    }
+
     execute_action(
         action,
         Some(input.target_pid),
@@ -737,6 +1321,127 @@ pub fn execute_from_pipeline(
     )
 }
 
+// ============================================================================
+// BEHAVIORAL RULE BRIDGE
+// ============================================================================
+
+/// Response mà một behavioral rule yêu cầu (RuleAction → policy decision)
+#[derive(Debug, Clone)]
+pub struct RuleResponseInput {
+    pub rule_id: String,
+    pub decision: Decision,
+    pub action: ActionType,
+    /// Chỉ rule Critical mới được AutoBlock
+    pub critical: bool,
+    pub target_pid: u32,
+    pub target_name: String,
+    /// File đích cho QuarantineFile
+    pub target_path: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Quyết định cho rule match - cùng guard với ML pipeline
+/// (whitelist, cooldown, browser containment, Safety Config).
+/// Rule là signature xác định nên không qua escalation ladder.
+pub fn decide_from_rule(input: &RuleResponseInput) -> PipelineOutput {
+    let config = get_config();
+    let no_action = |reason: &str| PipelineOutput {
+        threat_class: "Benign".to_string(),
+        decision: "SilentLog".to_string(),
+        severity: "Low".to_string(),
+        action: None,
+        auto_execute: false,
+        confidence: 1.0,
+        reasons: vec![reason.to_string()],
+    };
+
+    if !config.enabled {
+        return no_action("Action Guard is disabled");
+    }
+    if is_process_whitelisted(Some(input.target_pid), &input.target_name) {
+        return no_action("Process is whitelisted");
+    }
+    if is_in_cooldown(input.target_pid) {
+        return no_action("Process in cooldown");
+    }
+
+    let mut reasons = vec![format!(
+        "Behavioral rule {} requested {} ({})",
+        input.rule_id, input.action.to_string(), input.decision
+    )];
+
+    let mut decision = input.decision;
+    let mut action = match decision {
+        Decision::SilentLog => None,
+        Decision::Notify => Some(ActionType::AlertOnly),
+        Decision::RequireApproval | Decision::AutoBlock => Some(input.action),
+    };
+
+    if decision == Decision::AutoBlock && !input.critical {
+        decision = Decision::RequireApproval;
+        reasons.push("AutoBlock requires a Critical rule - downgraded to RequireApproval".to_string());
+    }
+
+    if action == Some(ActionType::QuarantineFile) && input.target_path.is_none() {
+        action = Some(ActionType::AlertOnly);
+        reasons.push("No file path for quarantine - alert only".to_string());
+    }
+
+    // Threat spawn từ browser: chỉ xử lý nhánh con, không isolate cả session
+    if matches!(action, Some(ActionType::KillProcess | ActionType::KillProcessTree | ActionType::IsolateSession))
+        && super::response::browser_child::find_browser_ancestor(input.target_pid).is_some()
+    {
+        action = Some(ActionType::ContainBrowserChild);
+    }
+
+    // FREEZE CORE: Safety Config Check
+    let (final_action, auto_exec) = if !crate::logic::config::SafetyConfig::is_auto_block_enabled() {
+        if action.is_some() && action != Some(ActionType::AlertOnly) {
+            log::info!("Auto-Block disabled: Downgrading rule action to AlertOnly");
+            (Some(ActionType::AlertOnly), false)
+        } else {
+            (action, false)
+        }
+    } else {
+        (action, decision == Decision::AutoBlock && config.auto_execute)
+    };
+
+    PipelineOutput {
+        threat_class: "Malicious".to_string(),
+        decision: format!("{:?}", decision),
+        severity: if input.critical { "Critical" } else { "High" }.to_string(),
+        action: final_action,
+        auto_execute: auto_exec,
+        confidence: 1.0,
+        reasons,
+    }
+}
+
+/// Execute action based on rule decision
+pub fn execute_from_rule(
+    input: &RuleResponseInput,
+    output: &PipelineOutput,
+) -> Result<ActionResult, ActionError> {
+    let action = match &output.action {
+        Some(a) => *a,
+        None => return Err(ActionError("No action required".to_string())),
+    };
+
+    let target = match action {
+        ActionType::QuarantineFile => input.target_path.as_deref().unwrap_or(&input.target_name),
+        _ => &input.target_name,
+    };
+
+    execute_action(
+        action,
+        Some(input.target_pid),
+        target,
+        1.0,
+        input.tags.clone(),
+        output.auto_execute,
+    )
+}
+
 /// Thực thi hành động (với hoặc không approval)
 pub fn execute_action(
     action_type: ActionType,
@@ -745,12 +1450,43 @@ pub fn execute_action(
     final_score: f32,
     tags: Vec<String>,
     auto_execute: bool,
+) -> Result<ActionResult, ActionError> {
+    let mut span = crate::logic::telemetry::otel::span("action.execute");
+    span.set_attr("action", action_type.to_string());
+    let result = execute_action_inner(
+        action_type, target_pid, target_name, final_score, tags, auto_execute,
+        Origin::Automatic, Safety::current(),
+    );
+    if let Err(e) = &result {
+        span.set_error(e.0.clone());
+    }
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_action_inner(
+    action_type: ActionType,
+    target_pid: Option<u32>,
+    target_name: &str,
+    final_score: f32,
+    tags: Vec<String>,
+    mut auto_execute: bool,
+    origin: Origin,
+    safety: Safety,
 ) -> Result<ActionResult, ActionError> {
     // Validate
-    if is_whitelisted(target_name) {
+    if is_process_whitelisted(target_pid, target_name) {
         return Err(ActionError(format!("{} is whitelisted", target_name)));
     }
 
+    // Kill-switch (gate chung của response layer): dry-run chỉ ghi lại quyết định,
+    // auto-block tắt thì action tự động phải chờ approval
+    let gate = check_safety(safety, action_type.severity(), origin);
+    if gate == Err(GateDenial::AutoBlockDisabled) && auto_execute {
+        log::info!("Auto-Block disabled: {} on {} requires approval", action_type.to_string(), target_name);
+        auto_execute = false;
+    }
+
     // Create record
     let record = ActionRecord {
         id: uuid::Uuid::new_v4().to_string(),
@@ -763,8 +1499,14 @@ pub fn execute_action(
         result: None,
         executed_at: Utc::now(),
         auto_executed: auto_execute,
+        undo: None,
     };
 
+    // Detect-only mode: chỉ ghi lại quyết định, không tạo pending / không thực thi
+    if gate == Err(GateDenial::DryRun) {
+        return Ok(record_simulated(record));
+    }
+
     if !auto_execute {
         // Add to pending
         let pending = PendingAction {
@@ -775,7 +1517,7 @@ pub fn execute_action(
             final_score,
             reason: format!("Score {:.2}, Tags: {:?}", final_score, tags),
             created_at: Utc::now(),
-            expires_at: Utc::now() + chrono::Duration::minutes(5),
+            expires_at: Utc::now() + chrono::Duration::seconds(get_config().pending_ttl_secs),
         };
 
         // Clone for event before moving to storage
@@ -794,6 +1536,9 @@ pub fn execute_action(
             "expires_at": pending_clone.expires_at.to_rfc3339(),
         }));
 
+        // Báo cho analyst ở xa (Slack/Teams/...) - approve qua cloud console
+        notify_pending(&pending_clone);
+
         // Record telemetry event
         telemetry::record(SecurityEvent::action_created(
             TelemetryProcessInfo::new(target_pid.unwrap_or(0), target_name),
@@ -810,7 +1555,15 @@ pub fn execute_action(
         });
     }
 
+    // Rate limit: tránh auto-response dây chuyền làm sập máy
+    // (check + ghi nhận trong một lock ở response gate)
+    if let Err(denial) = authorize_with(safety, action_type.severity(), target_pid, origin) {
+        log::warn!("{} on {} not executed: {}", action_type.to_string(), target_name, denial);
+        return Err(ActionError(format!("Not permitted: {}", denial)));
+    }
+
     // Execute
+    let mut undo: Option<RevertKind> = None;
     let result = match action_type {
         ActionType::KillProcess => {
             if let Some(pid) = target_pid {
@@ -819,13 +1572,31 @@ pub fn execute_action(
                 return Err(ActionError("PID required for kill".to_string()));
             }
         }
+        ActionType::KillProcessTree => {
+            if let Some(pid) = target_pid {
+                kill_process_tree(pid)?
+            } else {
+                return Err(ActionError("PID required for kill tree".to_string()));
+            }
+        }
         ActionType::SuspendProcess => {
             if let Some(pid) = target_pid {
-                suspend_process(pid)?
+                if super::response::ransomware::is_ransomware(&tags) {
+                    suspend_ransomware(pid, target_name, &tags)?
+                } else {
+                    suspend_process(pid)?
+                }
             } else {
                 return Err(ActionError("PID required for suspend".to_string()));
             }
         }
+        ActionType::ResumeProcess => {
+            if let Some(pid) = target_pid {
+                resume_process(pid)?
+            } else {
+                return Err(ActionError("PID required for resume".to_string()));
+            }
+        }
         ActionType::BlockNetworkIO => {
             if let Some(pid) = target_pid {
                 block_network_io(pid, target_name)?
@@ -833,9 +1604,102 @@ pub fn execute_action(
                 return Err(ActionError("PID required for block".to_string()));
             }
         }
+        ActionType::BlockDestination => {
+            let blocked = super::response::network::block_destination(
+                target_name,
+                &format!("Action Guard (score: {:.2}, tags: {})", final_score, tags.join(", ")),
+            ).map_err(|e| ActionError(format!("Destination block failed: {}", e)))?;
+            TOTAL_ACTIONS.fetch_add(1, Ordering::SeqCst);
+            undo = Some(RevertKind::UnblockDestination { target: target_name.to_string() });
+
+            ActionResult {
+                success: true,
+                action_type: ActionType::BlockDestination,
+                target_pid,
+                message: blocked.message,
+                executed_at: Utc::now(),
+            }
+        }
         ActionType::IsolateSession => {
             isolate_session()?
         }
+        ActionType::IsolateHost => {
+            isolate_host()?
+        }
+        ActionType::QuarantineFile => {
+            let path = std::path::Path::new(target_name);
+            let entry = super::response::file_quarantine::quarantine_file(
+                path,
+                &format!("Action Guard (score: {:.2})", final_score),
+                None,
+            ).map_err(|e| ActionError(format!("Quarantine failed: {}", e)))?;
+            TOTAL_ACTIONS.fetch_add(1, Ordering::SeqCst);
+            undo = Some(RevertKind::RestoreFile { quarantine_id: entry.id.clone() });
+
+            ActionResult {
+                success: true,
+                action_type: ActionType::QuarantineFile,
+                target_pid,
+                message: format!("File {} đã được quarantine ({})", entry.file_name, entry.id),
+                executed_at: Utc::now(),
+            }
+        }
+        ActionType::RemovePersistence => {
+            let (location, value_name) = super::response::registry::parse_persistence_target(target_name);
+            let (removed, snapshot) = super::response::registry::remove_persistence(&location, value_name.as_deref())
+                .map_err(|e| ActionError(format!("Persistence removal failed: {}", e)))?;
+            TOTAL_ACTIONS.fetch_add(1, Ordering::SeqCst);
+            undo = Some(RevertKind::RestorePersistence { snapshot_id: snapshot.id.clone() });
+
+            ActionResult {
+                success: true,
+                action_type: ActionType::RemovePersistence,
+                target_pid,
+                message: removed.message,
+                executed_at: Utc::now(),
+            }
+        }
+        ActionType::ContainBrowserChild => {
+            let pid = target_pid.ok_or_else(|| ActionError("PID required for browser containment".to_string()))?;
+            let (_, containment) = super::response::browser_child::contain(
+                pid,
+                &format!("Action Guard (score: {:.2})", final_score),
+            ).map_err(|e| ActionError(format!("Browser containment failed: {}", e)))?;
+            set_cooldown(pid);
+            TOTAL_ACTIONS.fetch_add(1, Ordering::SeqCst);
+            if let Some(id) = &containment.quarantine_id {
+                undo = Some(RevertKind::RestoreFile { quarantine_id: id.clone() });
+            }
+
+            ActionResult {
+                success: true,
+                action_type: ActionType::ContainBrowserChild,
+                target_pid,
+                message: format!(
+                    "Đã kill nhánh PID {} spawn từ {} (browser vẫn chạy)",
+                    containment.killed_pid, containment.browser_name
+                ),
+                executed_at: Utc::now(),
+            }
+        }
+        ActionType::BlockUsb => {
+            let device_id = Some(target_name).filter(|t| t.to_uppercase().starts_with("USBSTOR\\"));
+            let (_, block) = super::response::usb::block_usb(device_id)
+                .map_err(|e| ActionError(format!("USB block failed: {}", e)))?;
+            TOTAL_ACTIONS.fetch_add(1, Ordering::SeqCst);
+            undo = Some(RevertKind::UnblockUsb { block_id: block.id.clone() });
+
+            ActionResult {
+                success: true,
+                action_type: ActionType::BlockUsb,
+                target_pid,
+                message: match device_id {
+                    Some(id) => format!("USB device {} đã bị chặn", id),
+                    None => "Toàn bộ removable storage đã bị chặn".to_string(),
+                },
+                executed_at: Utc::now(),
+            }
+        }
         ActionType::AlertOnly => {
             ActionResult {
                 success: true,
@@ -847,21 +1711,143 @@ pub fn execute_action(
         }
     };
 
+    if result.success && undo.is_none() {
+        undo = match (action_type, target_pid) {
+            (ActionType::SuspendProcess, Some(pid)) => Some(RevertKind::ResumeProcess { pid }),
+            (ActionType::BlockNetworkIO, Some(pid)) => Some(RevertKind::UnblockNetwork { pid }),
+            (ActionType::IsolateHost, _) => Some(RevertKind::ReleaseHost),
+            _ => None,
+        };
+    }
+
     // Save to history
-    let mut history = ACTION_HISTORY.write();
-    let mut final_record = record;
-    final_record.status = if result.success { ActionStatus::Executed } else { ActionStatus::Failed };
-    final_record.result = Some(result.message.clone());
-    history.push(final_record);
+    let record_id = record.id.clone();
+    {
+        let mut history = ACTION_HISTORY.write();
+        let mut final_record = record;
+        final_record.status = if result.success { ActionStatus::Executed } else { ActionStatus::Failed };
+        final_record.result = Some(result.message.clone());
+        final_record.undo = undo;
+        history.push(final_record);
+
+        // Limit history size
+        if history.len() > 1000 {
+            history.drain(0..500);
+        }
+    }
 
-    // Limit history size
-    if history.len() > 1000 {
-        history.drain(0..500);
+    if result.success {
+        if let Some(pid) = target_pid {
+            spawn_verification(record_id, action_type, pid, target_name.to_string());
+        }
     }
 
     Ok(result)
 }
 
+// ============================================================================
+// ENFORCEMENT VERIFICATION
+// ============================================================================
+
+/// Kiểm tra action có hiệu lực (background): retry, escalate sang kill nếu
+/// suspend/block không giữ được, cuối cùng đánh dấu `Failed` + ghi vào incident.
+fn spawn_verification(record_id: String, action_type: ActionType, pid: u32, target_name: String) {
+    let config = get_config();
+    if !config.verify_enabled {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        use super::response::verify::Outcome;
+
+        let delay = std::time::Duration::from_millis(config.verify_delay_ms);
+        let mut attempts = 0;
+        let outcome = loop {
+            std::thread::sleep(delay);
+            let outcome = verify_outcome(action_type, pid, &target_name);
+            if !matches!(outcome, Outcome::NotEnforced(_)) || attempts >= config.verify_retries {
+                break outcome;
+            }
+            attempts += 1;
+            log::warn!("{} on PID {} did not stick, retry {}/{}",
+                action_type.to_string(), pid, attempts, config.verify_retries);
+            if let Err(e) = reenforce(action_type, pid, &target_name) {
+                log::warn!("Retry failed: {}", e);
+            }
+        };
+
+        let reason = match outcome {
+            Outcome::NotEnforced(reason) => reason,
+            _ => return,
+        };
+
+        // Escalate (opt-in): suspend / block không giữ được thì kill - kill là action
+        // mới nên vẫn qua gate (Safety Config + rate limit) như mọi action tự động
+        if config.verify_escalate
+            && matches!(action_type, ActionType::SuspendProcess | ActionType::BlockNetworkIO)
+        {
+            log::warn!("Escalating {} on PID {} to kill", action_type.to_string(), pid);
+            let kill = super::response::ResponseAction::KillProcess { pid, force: true };
+            if let Err(e) = super::response::actions::execute_gated(kill, Origin::Automatic) {
+                log::warn!("Escalation to kill not executed: {}", e);
+            }
+            std::thread::sleep(delay);
+            if verify_outcome(ActionType::KillProcess, pid, &target_name) == Outcome::Enforced {
+                update_record(&record_id, ActionStatus::Executed, &format!(
+                    "{} - escalated to kill after verification failed", reason
+                ));
+                return;
+            }
+        }
+
+        log::error!("Enforcement failed for {} on {} (PID {}): {}",
+            action_type.to_string(), target_name, pid, reason);
+        update_record(&record_id, ActionStatus::Failed, &format!("Verification failed: {}", reason));
+        super::incident::record_enforcement_failure(super::incident::EnforcementFailure {
+            action_id: record_id,
+            action_type: action_type.to_string(),
+            target_pid: Some(pid),
+            target_name,
+            reason,
+            at: Utc::now(),
+        });
+    });
+}
+
+fn verify_outcome(action_type: ActionType, pid: u32, name: &str) -> super::response::verify::Outcome {
+    use super::response::verify::{self, Outcome};
+
+    match action_type {
+        ActionType::KillProcess | ActionType::KillProcessTree | ActionType::ContainBrowserChild => {
+            verify::verify_killed(pid, name)
+        }
+        ActionType::SuspendProcess => verify::verify_suspended(pid, name),
+        ActionType::BlockNetworkIO => verify::verify_network_blocked(pid, name),
+        _ => Outcome::Unverifiable,
+    }
+}
+
+/// Thực thi lại action (bỏ qua cooldown - đây là retry của cùng một quyết định)
+fn reenforce(action_type: ActionType, pid: u32, name: &str) -> Result<(), ActionError> {
+    let map = |e: super::response::ActionError| ActionError(e.to_string());
+    match action_type {
+        ActionType::KillProcess | ActionType::ContainBrowserChild => {
+            super::response::actions::kill_process(pid, true).map(|_| ()).map_err(map)
+        }
+        ActionType::KillProcessTree => super::response::actions::kill_process_tree(pid).map(|_| ()).map_err(map),
+        ActionType::SuspendProcess => super::response::actions::nt_suspend_process(pid).map_err(map),
+        ActionType::BlockNetworkIO => block_network_io(pid, name).map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+fn update_record(record_id: &str, status: ActionStatus, message: &str) {
+    if let Some(record) = ACTION_HISTORY.write().iter_mut().find(|r| r.id == record_id) {
+        record.status = status;
+        record.result = Some(message.to_string());
+    }
+}
+
 /// Approve pending action
 pub fn approve_action(action_id: &str) -> Result<ActionResult, ActionError> {
     let mut pending = PENDING_ACTIONS.write();
@@ -878,17 +1864,137 @@ pub fn approve_action(action_id: &str) -> Result<ActionResult, ActionError> {
         action.action_type,
     ));
 
-    // Execute
-    execute_action(
+    // Execute (user đã approve - không bị kill-switch của auto-response chặn)
+    execute_action_inner(
         action.action_type,
         Some(action.target_pid),
         &action.target_name,
         action.final_score,
         vec![action.reason],
         true,  // Now auto-execute
+        Origin::Manual,
+        Safety::current(),
     )
 }
 
+/// Gửi webhook cho pending action kèm đường dẫn approve/reject trên cloud.
+/// Cloud chuyển quyết định xuống agent qua `AgentCommand::ApproveAction/RejectAction`.
+///
+/// Đường dẫn là API của cloud console, không tự mang quyền: analyst phải gọi kèm
+/// phiên đăng nhập console (JWT). Người chỉ nhận được webhook không approve được.
+fn notify_pending(action: &PendingAction) {
+    use super::response::types::{AlertPayload, AlertSeverity};
+
+    let severity = if action.action_type.severity() >= 4 { AlertSeverity::High } else { AlertSeverity::Medium };
+    let mut payload = AlertPayload::new(
+        &format!("Approval required: {}", action.action_type.to_string()),
+        &format!(
+            "{} on {} (PID {}) is waiting for approval - expires {}",
+            action.action_type.to_string(),
+            action.target_name,
+            action.target_pid,
+            action.expires_at.to_rfc3339(),
+        ),
+        severity,
+    );
+    payload.process_name = Some(action.target_name.clone());
+    payload.process_pid = Some(action.target_pid);
+    payload.tags = vec!["pending_action".to_string()];
+    payload.extra.insert("action_id".to_string(), action.id.clone());
+
+    if let Some(agent_id) = super::cloud_sync::get_status().agent_id {
+        let base = format!(
+            "{}/api/v1/endpoints/{}/actions/{}",
+            crate::constants::get_cloud_url(),
+            agent_id,
+            action.id,
+        );
+        payload.extra.insert("approve_url".to_string(), format!("{}/approve", base));
+        payload.extra.insert("reject_url".to_string(), format!("{}/reject", base));
+    }
+
+    // ureq là blocking - không giữ analysis loop
+    std::thread::spawn(move || {
+        for result in super::response::webhook::send_alert(&payload) {
+            if let Err(e) = result {
+                log::warn!("Pending action notification failed: {}", e);
+            }
+        }
+    });
+}
+
+/// Undo một action đã thực thi (resume, gỡ firewall rule, restore file, ...)
+pub fn undo_action(action_id: &str) -> Result<ActionResult, ActionError> {
+    let record = ACTION_HISTORY.read()
+        .iter()
+        .find(|r| r.id == action_id)
+        .cloned()
+        .ok_or_else(|| ActionError("Action not found".to_string()))?;
+
+    if record.status == ActionStatus::Reverted {
+        return Err(ActionError("Action đã được undo".to_string()));
+    }
+    if record.status != ActionStatus::Executed {
+        return Err(ActionError(format!("Không thể undo action ở trạng thái {:?}", record.status)));
+    }
+    let undo = record.undo.clone()
+        .ok_or_else(|| ActionError(format!("{} không thể undo", record.action_type.to_string())))?;
+
+    log::warn!("Undoing {} ({}) on {}", record.action_type.to_string(), action_id, record.target_name);
+
+    let outcome = super::response::expiry::revert(&undo);
+
+    telemetry::record(SecurityEvent::action_reverted(
+        TelemetryProcessInfo::new(record.target_pid.unwrap_or(0), &record.target_name),
+        Some(record.action_type),
+        "undo",
+        outcome.is_ok(),
+    ));
+
+    let message = outcome.map_err(ActionError)?;
+    super::response::expiry::forget(&undo);
+
+    if let Some(r) = ACTION_HISTORY.write().iter_mut().find(|r| r.id == action_id) {
+        r.status = ActionStatus::Reverted;
+        r.result = Some(format!("{} | undo: {}", r.result.clone().unwrap_or_default(), message));
+    }
+
+    Ok(ActionResult {
+        success: true,
+        action_type: record.action_type,
+        target_pid: record.target_pid,
+        message,
+        executed_at: Utc::now(),
+    })
+}
+
+/// Ghi lại action được thực hiện ngoài `execute_action` (vd: quarantine thủ công)
+/// để có action_id phục vụ undo. Trả về action_id.
+pub fn record_manual_action(
+    action_type: ActionType,
+    target_pid: Option<u32>,
+    target_name: &str,
+    message: &str,
+    undo: Option<RevertKind>,
+) -> String {
+    let id = uuid::Uuid::new_v4().to_string();
+    ACTION_HISTORY.write().push(ActionRecord {
+        id: id.clone(),
+        action_type,
+        target_pid,
+        target_name: target_name.to_string(),
+        final_score: 0.0,
+        tags: vec!["manual".to_string()],
+        status: ActionStatus::Executed,
+        result: Some(message.to_string()),
+        executed_at: Utc::now(),
+        auto_executed: false,
+        undo,
+    });
+    TOTAL_ACTIONS.fetch_add(1, Ordering::SeqCst);
+    id
+}
+
 /// Cancel pending action
 pub fn cancel_action(action_id: &str) -> Result<(), ActionError> {
     let mut pending = PENDING_ACTIONS.write();
@@ -937,18 +2043,29 @@ pub fn reset() {
     TOTAL_ACTIONS.store(0, Ordering::SeqCst);
     ACTION_HISTORY.write().clear();
     PENDING_ACTIONS.write().clear();
+    ESCALATIONS.write().clear();
+    super::response::actions::reset_rate_limits();
     *PROCESS_COOLDOWNS.write() = None;
 }
 
 /// Lấy trạng thái Action Guard
 pub fn get_status() -> serde_json::Value {
+    let config = get_config();
     serde_json::json!({
-        "enabled": true,
-        "action_threshold": ACTION_THRESHOLD,
-        "high_alert_threshold": HIGH_ALERT_THRESHOLD,
+        "enabled": config.enabled,
+        "action_threshold": config.action_threshold,
+        "high_alert_threshold": config.high_alert_threshold,
+        "auto_execute": config.auto_execute,
+        "cloud_override": CLOUD_OVERRIDE.read().is_some(),
         "total_actions": get_total_actions(),
         "pending_actions": get_pending_actions().len(),
-        "whitelist_count": WHITELIST.read().len(),
+        "whitelist_count": whitelist::count(),
+        "escalations": ESCALATIONS.read().len(),
+        "rate_limit": super::response::actions::rate_limit_status(),
+        "dry_run": crate::logic::config::SafetyConfig::is_dry_run(),
+        "simulated_actions": ACTION_HISTORY.read().iter()
+            .filter(|r| r.status == ActionStatus::Simulated)
+            .count(),
     })
 }
 
@@ -960,6 +2077,67 @@ pub fn get_status() -> serde_json::Value {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_dry_run_does_not_create_pending() {
+        // Safety truyền vào trực tiếp - không đụng tới kill-switch global
+        let result = execute_action_inner(
+            ActionType::KillProcess,
+            Some(424242),
+            "dryrun_target.exe",
+            0.99,
+            vec![],
+            false,
+            Origin::Automatic,
+            Safety { dry_run: true, auto_block: true },
+        ).unwrap();
+
+        assert!(result.message.starts_with("[DRY-RUN]"));
+        assert!(!get_pending_actions().iter().any(|a| a.target_pid == 424242));
+    }
+
+    #[test]
+    fn test_undo_requires_reversible_action() {
+        let id = record_manual_action(ActionType::AlertOnly, None, "undo_target.exe", "alert", None);
+        assert!(undo_action(&id).is_err());
+        assert!(undo_action("missing-action").is_err());
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(ActionGuardConfig::default().validate().is_ok());
+
+        let inverted = ActionGuardConfig {
+            action_threshold: 0.5,
+            high_alert_threshold: 0.9,
+            ..Default::default()
+        };
+        assert!(inverted.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_partial_deserialize() {
+        let config: ActionGuardConfig = serde_json::from_str(r#"{"cooldown_secs": 60}"#).unwrap();
+        assert_eq!(config.cooldown_secs, 60);
+        assert_eq!(config.action_threshold, ACTION_THRESHOLD);
+    }
+
+    #[test]
+    fn test_escalation_ladder() {
+        assert_eq!(escalate(ActionType::KillProcess, 1), ActionType::AlertOnly);
+        assert_eq!(escalate(ActionType::KillProcess, 2), ActionType::SuspendProcess);
+        assert_eq!(escalate(ActionType::KillProcess, 3), ActionType::KillProcess);
+        assert_eq!(escalate(ActionType::IsolateSession, 1), ActionType::IsolateSession);
+    }
+
+    #[test]
+    fn test_record_strike_and_reset() {
+        assert_eq!(record_strike("ladder_test.exe", 3600), 1);
+        assert_eq!(record_strike("LADDER_TEST.exe", 3600), 2);
+        reset_escalation("ladder_test.exe");
+        assert_eq!(record_strike("ladder_test.exe", 3600), 1);
+        reset_escalation("ladder_test.exe");
+    }
+
     #[test]
     fn test_decide_action_critical() {
         // CRITICAL takes priority over NETWORK -> IsolateSession
@@ -0,0 +1,349 @@
+//! AMSI Provider Bridge - Nhận script content từ AMSI
+//!
+//! `amsi-provider` (oneshield_amsi.dll) là IAmsiProvider được AMSI load vào
+//! PowerShell / WSH / VBA. DLL chuyển từng buffer tới agent qua named pipe,
+//! agent scan bằng `amsi::scan` và trả về verdict.
+//!
+//! Module này:
+//! - Đăng ký COM class + AMSI provider (HKLM, cần quyền Admin)
+//! - Chạy named-pipe server nhận scan request
+//!
+//! Đăng ký thất bại (thiếu DLL / quyền) → giữ heuristic mode như cũ:
+//! content chỉ được scan khi gọi `amsi::scan` trực tiếp.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use serde::{Deserialize, Serialize};
+
+use super::amsi;
+use super::types::AmsiError;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Phải khớp với `PROVIDER_CLSID` trong amsi-provider/src/lib.rs
+pub const PROVIDER_CLSID: &str = "{5E1B7A44-2F4B-4C8E-9D6A-0B5E3C1F7A21}";
+pub const PIPE_NAME: &str = r"\\.\pipe\OneShieldAmsi";
+const PROVIDER_DLL: &str = "oneshield_amsi.dll";
+
+/// Request lớn hơn mức này bị bỏ qua (DLL đã cắt ở 1 MB)
+const MAX_REQUEST_BYTES: usize = 4 * 1024 * 1024;
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static REGISTERED: AtomicBool = AtomicBool::new(false);
+static LISTENING: AtomicBool = AtomicBool::new(false);
+static PROVIDER_SCANS: AtomicU64 = AtomicU64::new(0);
+static PROVIDER_DETECTIONS: AtomicU64 = AtomicU64::new(0);
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Request từ provider DLL
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderRequest {
+    #[serde(default)]
+    pub app_name: String,
+    #[serde(default)]
+    pub content_name: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderStatus {
+    /// "provider" = nhận content tự động qua AMSI, "heuristic" = fallback
+    pub mode: String,
+    pub registered: bool,
+    pub pipe_listening: bool,
+    pub scans: u64,
+    pub detections: u64,
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Đăng ký provider và bật pipe server. Lỗi → vẫn chạy heuristic mode.
+pub fn start() {
+    match register() {
+        Ok(()) => {
+            REGISTERED.store(true, Ordering::SeqCst);
+            log::info!("AMSI provider registered ({})", PROVIDER_CLSID);
+        }
+        Err(e) => {
+            log::warn!("AMSI provider registration failed, using heuristic mode: {}", e);
+            return;
+        }
+    }
+
+    if !LISTENING.swap(true, Ordering::SeqCst) {
+        std::thread::spawn(|| {
+            let rt = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(rt) => rt,
+                Err(e) => {
+                    log::error!("AMSI pipe runtime failed: {}", e);
+                    LISTENING.store(false, Ordering::SeqCst);
+                    return;
+                }
+            };
+            rt.block_on(platform::serve());
+            LISTENING.store(false, Ordering::SeqCst);
+        });
+    }
+}
+
+/// Trạng thái provider
+pub fn status() -> ProviderStatus {
+    let registered = REGISTERED.load(Ordering::Relaxed);
+    let listening = LISTENING.load(Ordering::Relaxed);
+    ProviderStatus {
+        mode: if registered && listening { "provider" } else { "heuristic" }.to_string(),
+        registered,
+        pipe_listening: listening,
+        scans: PROVIDER_SCANS.load(Ordering::Relaxed),
+        detections: PROVIDER_DETECTIONS.load(Ordering::Relaxed),
+    }
+}
+
+/// Scan một request từ provider. Trả về true nếu cần block.
+pub fn handle_request(request: &ProviderRequest) -> bool {
+    PROVIDER_SCANS.fetch_add(1, Ordering::Relaxed);
+
+    let content_type = content_type(&request.app_name);
+    let detected = amsi::scan(&request.content, content_type)
+        .map(|r| r.should_block)
+        .unwrap_or(false);
+
+    if detected {
+        PROVIDER_DETECTIONS.fetch_add(1, Ordering::Relaxed);
+        log::warn!(
+            "AMSI provider blocked {} content from {} ({})",
+            content_type, request.app_name, request.content_name
+        );
+    }
+    detected
+}
+
+/// AMSI app name → content type của heuristic scanner
+fn content_type(app_name: &str) -> &'static str {
+    let app = app_name.to_lowercase();
+    if app.contains("powershell") {
+        "PowerShell"
+    } else if app.contains("vbscript") {
+        "VBScript"
+    } else if app.contains("jscript") || app.contains("javascript") {
+        "JavaScript"
+    } else if app.contains("vba") || app.contains("office") {
+        "VBA"
+    } else if app.contains("dotnet") || app.contains("coreclr") {
+        ".NET"
+    } else {
+        "Unknown"
+    }
+}
+
+// ============================================================================
+// REGISTRATION
+// ============================================================================
+
+/// Đăng ký COM InprocServer32 + AMSI provider key (HKLM)
+pub fn register() -> Result<(), AmsiError> {
+    platform::register()
+}
+
+/// Gỡ đăng ký provider
+pub fn unregister() -> Result<(), AmsiError> {
+    REGISTERED.store(false, Ordering::SeqCst);
+    platform::unregister()
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use std::process::Command;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::{LocalFree, HLOCAL};
+    use windows::Win32::Security::Authorization::{
+        ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+    };
+    use windows::Win32::Security::{PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES};
+
+    /// DACL của pipe: SYSTEM + Administrators full, Authenticated Users read/write
+    /// (0x12019b = FILE_GENERIC_READ | FILE_GENERIC_WRITE trừ FILE_CREATE_PIPE_INSTANCE,
+    /// user thường không tạo được instance giả để nhận content thay agent).
+    /// DACL mặc định chỉ cho Everyone đọc → PowerShell của user thường không gửi được request.
+    const PIPE_SDDL: PCWSTR = w!("D:P(A;;GA;;;SY)(A;;GA;;;BA)(A;;0x12019b;;;AU)");
+
+    /// Client phải gửi xong request trong khoảng này (DLL tự bỏ cuộc sau 2s)
+    const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+    pub fn register() -> Result<(), AmsiError> {
+        let dll = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|d| d.join(PROVIDER_DLL)))
+            .filter(|p| p.exists())
+            .ok_or_else(|| AmsiError::Other { message: format!("{} not found", PROVIDER_DLL) })?;
+
+        let clsid_key = format!(r"HKLM\SOFTWARE\Classes\CLSID\{}", PROVIDER_CLSID);
+        let inproc_key = format!(r"{}\InprocServer32", clsid_key);
+        let dll_path = dll.to_string_lossy().to_string();
+
+        reg(&["add", &clsid_key, "/ve", "/d", "One-Shield AMSI Provider", "/f"])?;
+        reg(&["add", &inproc_key, "/ve", "/d", &dll_path, "/f"])?;
+        reg(&["add", &inproc_key, "/v", "ThreadingModel", "/d", "Both", "/f"])?;
+        reg(&["add", &format!(r"HKLM\SOFTWARE\Microsoft\AMSI\Providers\{}", PROVIDER_CLSID), "/f"])?;
+        Ok(())
+    }
+
+    pub fn unregister() -> Result<(), AmsiError> {
+        reg(&["delete", &format!(r"HKLM\SOFTWARE\Microsoft\AMSI\Providers\{}", PROVIDER_CLSID), "/f"])?;
+        reg(&["delete", &format!(r"HKLM\SOFTWARE\Classes\CLSID\{}", PROVIDER_CLSID), "/f"])
+    }
+
+    fn reg(args: &[&str]) -> Result<(), AmsiError> {
+        let output = Command::new("reg")
+            .args(args)
+            .output()
+            .map_err(|e| AmsiError::Other { message: e.to_string() })?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(AmsiError::Other {
+                message: format!("reg {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr).trim()),
+            })
+        }
+    }
+
+    /// Named-pipe server: 1 request / connection
+    pub async fn serve() {
+        let mut server = match create_pipe(true) {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("AMSI pipe create failed: {}", e);
+                return;
+            }
+        };
+        log::info!("AMSI provider pipe listening on {}", PIPE_NAME);
+
+        loop {
+            if let Err(e) = server.connect().await {
+                log::warn!("AMSI pipe connect failed: {}", e);
+                continue;
+            }
+            let mut client = server;
+            server = match create_pipe(false) {
+                Ok(s) => s,
+                Err(e) => {
+                    log::error!("AMSI pipe re-create failed: {}", e);
+                    return;
+                }
+            };
+
+            tokio::spawn(async move {
+                let body = match tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut client)).await {
+                    Ok(Some(body)) => body,
+                    Ok(None) => {
+                        let _ = client.write_all(&[0]).await;
+                        return;
+                    }
+                    // Client treo / đã bỏ cuộc
+                    Err(_) => return,
+                };
+
+                // amsi::scan đồng bộ - không chặn runtime, các script host scan song song
+                let verdict = tokio::task::spawn_blocking(move || {
+                    serde_json::from_slice::<ProviderRequest>(&body)
+                        .map(|req| handle_request(&req))
+                        .unwrap_or(false)
+                })
+                .await
+                .unwrap_or(false);
+                let _ = client.write_all(&[verdict as u8]).await;
+            });
+        }
+    }
+
+    /// u32 LE length + body. None = request quá lớn / client đóng pipe
+    async fn read_request(client: &mut NamedPipeServer) -> Option<Vec<u8>> {
+        let mut len = [0u8; 4];
+        client.read_exact(&mut len).await.ok()?;
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_REQUEST_BYTES {
+            return None;
+        }
+        let mut body = vec![0u8; len];
+        client.read_exact(&mut body).await.ok()?;
+        Some(body)
+    }
+
+    /// Tạo pipe instance với DACL `PIPE_SDDL`
+    fn create_pipe(first: bool) -> std::io::Result<NamedPipeServer> {
+        let mut descriptor = PSECURITY_DESCRIPTOR::default();
+        unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(PIPE_SDDL, SDDL_REVISION_1, &mut descriptor, None)
+        }
+        .map_err(|e| std::io::Error::other(format!("pipe security descriptor: {}", e)))?;
+
+        let mut attributes = SECURITY_ATTRIBUTES {
+            nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: descriptor.0,
+            bInheritHandle: false.into(),
+        };
+        let server = unsafe {
+            ServerOptions::new()
+                .first_pipe_instance(first)
+                .create_with_security_attributes_raw(PIPE_NAME, &mut attributes as *mut _ as *mut _)
+        };
+        unsafe {
+            let _ = LocalFree(HLOCAL(descriptor.0));
+        }
+        server
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::*;
+
+    pub fn register() -> Result<(), AmsiError> {
+        Err(AmsiError::NotAvailable)
+    }
+
+    pub fn unregister() -> Result<(), AmsiError> {
+        Ok(())
+    }
+
+    pub async fn serve() {}
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_type() {
+        assert_eq!(content_type("PowerShell_C:\\Windows\\System32\\powershell.exe_10.0"), "PowerShell");
+        assert_eq!(content_type("VBScript"), "VBScript");
+        assert_eq!(content_type("foo"), "Unknown");
+    }
+
+    #[test]
+    fn test_handle_request() {
+        amsi::init().unwrap();
+        let request = ProviderRequest {
+            app_name: "PowerShell".to_string(),
+            content_name: String::new(),
+            content: "Invoke-Mimikatz -DumpCreds".to_string(),
+        };
+        assert!(handle_request(&request));
+    }
+}
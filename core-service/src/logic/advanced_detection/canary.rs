@@ -0,0 +1,382 @@
+//! Ransomware Canary Files
+//!
+//! Mục đích: Phát hiện ransomware bằng file mồi, không phụ thuộc ML score
+//!
+//! - Đặt canary ẩn trong Documents / Desktop / Pictures / Downloads, tên được chọn
+//!   để nằm đầu và cuối thứ tự duyệt file (`!!!...`, `~~~...`)
+//! - Watch thư mục chứa canary (notify); canary bị sửa nội dung, xóa hoặc đổi tên
+//!   → incident Critical
+//! - Chỉ process thực sự đang giữ handle tới canary lúc nhận event (Restart Manager)
+//!   mới bị xử lý: ransomware response profile qua Action Guard (whitelist, Safety
+//!   Config, approval, rate limit). Không xác định được → chỉ incident + shadow-copy guard
+//!
+//! Canary được đặt lại sau mỗi lần trigger.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::logic::action_guard::{self, ActionType};
+use crate::logic::incident::{self, DetectionContext, Severity};
+use crate::logic::process_intel::{self, handles};
+use crate::logic::response::ransomware;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CANARY_NAMES: &[&str] = &[
+    "!!!_Financial_Report_2024.docx",
+    "~~~_Passwords_Backup.xlsx",
+];
+
+const CANARY_SIZE: usize = 64 * 1024;
+const CANARIES_FILE: &str = "canaries.json";
+
+/// Bỏ qua event do chính agent ghi khi đặt canary
+const PLANT_GRACE_SECS: i64 = 3;
+
+/// Chỉ trigger 1 lần trong khoảng này (ransomware sửa nhiều canary liên tiếp)
+const TRIGGER_DEBOUNCE_SECS: i64 = 30;
+
+const CANARY_TAGS: &[&str] = &["CANARY_TRIGGERED", "RANSOMWARE"];
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Canary {
+    pub path: PathBuf,
+    pub sha256: String,
+    pub planted_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CanaryStatus {
+    pub active: bool,
+    pub canaries: Vec<Canary>,
+    pub triggers: u64,
+    pub last_trigger: Option<i64>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static CANARIES: Lazy<RwLock<HashMap<PathBuf, Canary>>> = Lazy::new(|| RwLock::new(load()));
+static WATCHER: Mutex<Option<RecommendedWatcher>> = Mutex::new(None);
+static LAST_TRIGGER: AtomicI64 = AtomicI64::new(0);
+static TRIGGERS: AtomicU64 = AtomicU64::new(0);
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Đặt canary và bắt đầu watch
+pub fn start() {
+    let planted = plant_all();
+    if planted == 0 {
+        log::warn!("Canary: no user folders available, canaries disabled");
+        return;
+    }
+
+    let dirs: Vec<PathBuf> = CANARIES.read().keys()
+        .filter_map(|p| p.parent().map(|d| d.to_path_buf()))
+        .collect::<std::collections::HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    let mut watcher = match notify::recommended_watcher(|res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            handle_event(&event);
+        }
+    }) {
+        Ok(w) => w,
+        Err(e) => {
+            log::error!("Canary watcher failed: {}", e);
+            return;
+        }
+    };
+
+    for dir in &dirs {
+        if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+            log::warn!("Canary: cannot watch {}: {}", dir.display(), e);
+        }
+    }
+
+    *WATCHER.lock() = Some(watcher);
+    log::info!("Canary: {} canaries planted in {} folders", planted, dirs.len());
+}
+
+/// Đặt lại toàn bộ canary (vd: sau khi user xóa nhầm)
+pub fn replant() -> usize {
+    plant_all()
+}
+
+pub fn status() -> CanaryStatus {
+    let last = LAST_TRIGGER.load(Ordering::Relaxed);
+    CanaryStatus {
+        active: WATCHER.lock().is_some(),
+        canaries: CANARIES.read().values().cloned().collect(),
+        triggers: TRIGGERS.load(Ordering::Relaxed),
+        last_trigger: if last > 0 { Some(last) } else { None },
+    }
+}
+
+pub fn is_canary(path: &Path) -> bool {
+    CANARIES.read().contains_key(path)
+}
+
+// ============================================================================
+// EVENT HANDLING
+// ============================================================================
+
+fn handle_event(event: &notify::Event) {
+    if !matches!(event.kind, EventKind::Modify(_) | EventKind::Remove(_)) {
+        return;
+    }
+
+    for path in &event.paths {
+        let canary = match CANARIES.read().get(path).cloned() {
+            Some(c) => c,
+            None => continue,
+        };
+        if Utc::now().timestamp() - canary.planted_at < PLANT_GRACE_SECS {
+            continue;
+        }
+        // Lấy process giữ handle ngay, trước khi writer kịp đóng file
+        let holders: Vec<u32> = event.paths.iter()
+            .flat_map(|p| handles::processes_using(p))
+            .collect();
+        if let Some(reason) = tampered(&canary) {
+            trigger(&canary, &reason, &holders);
+            return;
+        }
+    }
+}
+
+/// Canary có bị đụng tới không (None = metadata change, nội dung nguyên vẹn)
+fn tampered(canary: &Canary) -> Option<String> {
+    match fs::read(&canary.path) {
+        Ok(content) if hash(&content) == canary.sha256 => None,
+        Ok(_) => Some("content modified".to_string()),
+        Err(_) => Some("deleted or renamed".to_string()),
+    }
+}
+
+fn trigger(canary: &Canary, reason: &str, holders: &[u32]) {
+    let writers = writers(holders);
+    let tags: Vec<String> = CANARY_TAGS.iter().map(|t| t.to_string()).collect();
+    let description = format!("Canary {} {}", canary.path.display(), reason);
+
+    // Writer đã xác định: detection + ransomware profile qua Action Guard
+    // (cooldown của Action Guard chống xử lý lặp khi nhiều canary bị sửa liên tiếp)
+    for (pid, name) in &writers {
+        TRIGGERS.fetch_add(1, Ordering::Relaxed);
+        log::error!("🐤 {} by {} (PID {}) - triggering ransomware response", description, name, pid);
+        incident::raise_process_detection(
+            &format!("Ransomware canary modified by {}", name),
+            Severity::Critical,
+            &tags,
+            &["T1486"],
+            &description,
+            DetectionContext { pid: Some(*pid), rule_matches: vec!["canary".to_string()] },
+        );
+        if let Err(e) = action_guard::execute_action(
+            ActionType::SuspendProcess,
+            Some(*pid),
+            name,
+            1.0,
+            tags.clone(),
+            true,
+        ) {
+            log::error!("Canary: ransomware response not executed for {} ({}): {}", name, pid, e);
+        }
+    }
+
+    let now = Utc::now().timestamp();
+    let last = LAST_TRIGGER.load(Ordering::SeqCst);
+    if now - last < TRIGGER_DEBOUNCE_SECS
+        || LAST_TRIGGER.compare_exchange(last, now, Ordering::SeqCst, Ordering::SeqCst).is_err()
+    {
+        return;
+    }
+
+    if writers.is_empty() {
+        // Không thấy process nào giữ canary - không đoán, chỉ mở incident và chặn xóa shadow copy
+        TRIGGERS.fetch_add(1, Ordering::Relaxed);
+        log::error!("🐤 {} - writer process not observed", description);
+        ransomware::arm_shadow_guard(600);
+        incident::raise_detection(
+            "Ransomware canary modified",
+            Severity::Critical,
+            &tags,
+            &["T1486"],
+            &format!("{} (writer process not observed)", description),
+        );
+    }
+
+    // Đặt lại canary cho lần sau
+    std::thread::spawn(|| {
+        std::thread::sleep(std::time::Duration::from_secs(TRIGGER_DEBOUNCE_SECS as u64));
+        plant_all();
+    });
+}
+
+/// Process đang giữ handle tới canary (bỏ qua agent và process được whitelist)
+fn writers(holders: &[u32]) -> Vec<(u32, String)> {
+    let own_pid = std::process::id();
+    let mut writers: Vec<(u32, String)> = Vec::new();
+    for &pid in holders {
+        if pid == own_pid || writers.iter().any(|(p, _)| *p == pid) {
+            continue;
+        }
+        let name = match process_intel::get_process_info(pid) {
+            Some(info) => info.name,
+            None => {
+                let mut system = sysinfo::System::new();
+                system.refresh_process(sysinfo::Pid::from_u32(pid));
+                match system.process(sysinfo::Pid::from_u32(pid)) {
+                    Some(p) => p.name().to_string(),
+                    None => continue,
+                }
+            }
+        };
+        if !action_guard::is_process_whitelisted(Some(pid), &name) {
+            writers.push((pid, name));
+        }
+    }
+    writers
+}
+
+// ============================================================================
+// PLANTING
+// ============================================================================
+
+fn user_folders() -> Vec<PathBuf> {
+    [dirs::document_dir(), dirs::desktop_dir(), dirs::picture_dir(), dirs::download_dir()]
+        .into_iter()
+        .flatten()
+        .filter(|d| d.is_dir())
+        .collect()
+}
+
+fn plant_all() -> usize {
+    let mut planted = 0;
+    for dir in user_folders() {
+        for name in CANARY_NAMES {
+            match plant(&dir.join(name)) {
+                Ok(canary) => {
+                    CANARIES.write().insert(canary.path.clone(), canary);
+                    planted += 1;
+                }
+                Err(e) => log::warn!("Canary: cannot plant in {}: {}", dir.display(), e),
+            }
+        }
+    }
+    save();
+    planted
+}
+
+fn plant(path: &Path) -> std::io::Result<Canary> {
+    // Nội dung giả ngẫu nhiên (không nén được, giống document thật với ransomware)
+    let mut content = vec![0u8; CANARY_SIZE];
+    let mut state = Utc::now().timestamp_nanos_opt().unwrap_or(0) as u64 | 1;
+    for byte in content.iter_mut() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        *byte = state as u8;
+    }
+
+    set_hidden(path, false);
+    fs::write(path, &content)?;
+    set_hidden(path, true);
+
+    Ok(Canary {
+        path: path.to_path_buf(),
+        sha256: hash(&content),
+        planted_at: Utc::now().timestamp(),
+    })
+}
+
+#[cfg(windows)]
+fn set_hidden(path: &Path, hidden: bool) {
+    if !path.exists() {
+        return;
+    }
+    let flag = if hidden { "+h" } else { "-h" };
+    let _ = std::process::Command::new("attrib").arg(flag).arg(path).output();
+}
+
+#[cfg(not(windows))]
+fn set_hidden(_path: &Path, _hidden: bool) {}
+
+fn hash(content: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content);
+    format!("{:x}", hasher.finalize())
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn canaries_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(CANARIES_FILE)
+}
+
+fn load() -> HashMap<PathBuf, Canary> {
+    fs::read_to_string(canaries_path())
+        .ok()
+        .and_then(|c| serde_json::from_str::<Vec<Canary>>(&c).ok())
+        .map(|list| list.into_iter().map(|c| (c.path.clone(), c)).collect())
+        .unwrap_or_default()
+}
+
+fn save() {
+    let path = canaries_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let list: Vec<_> = CANARIES.read().values().cloned().collect();
+    if let Ok(json) = serde_json::to_string_pretty(&list) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plant_and_tamper() {
+        let dir = std::env::temp_dir().join(format!("oneshield_canary_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let canary = plant(&dir.join(CANARY_NAMES[0])).unwrap();
+        assert_eq!(tampered(&canary), None);
+
+        fs::write(&canary.path, b"encrypted").unwrap();
+        assert_eq!(tampered(&canary).as_deref(), Some("content modified"));
+
+        fs::remove_file(&canary.path).unwrap();
+        assert_eq!(tampered(&canary).as_deref(), Some("deleted or renamed"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,272 @@
+//! Browser Credential / Cookie Theft Detection (T1555.003, T1539)
+//!
+//! Mục đích: Phát hiện process KHÔNG phải browser đọc kho credential của browser
+//!
+//! Target:
+//! - Chromium (Chrome / Edge / Brave): `Login Data`, `Cookies`, `Network\Cookies`,
+//!   `Local State` (chứa master key đã mã hóa DPAPI)
+//! - DPAPI master keys: `%APPDATA%\Microsoft\Protect\<SID>\`
+//!
+//! 2 tín hiệu:
+//! 1. Process đang giữ handle tới file target (Restart Manager `RmGetList`)
+//! 2. Command line nhắc tới file target (copy / sqlite3 / python stealer)
+//!
+//! Phát hiện → incident High + BLOCK_NETWORK cho process qua Action Guard
+//! (Safety Config tắt auto-block → chờ approval).
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::action_guard::{self, ActionType};
+use crate::logic::incident::{self, DetectionContext, Severity};
+use crate::logic::process_intel::handles;
+use crate::logic::response::browser_child;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// User data dir (tương đối với %LOCALAPPDATA%) → tên browser
+const CHROMIUM_ROOTS: &[(&str, &str)] = &[
+    (r"Google\Chrome\User Data", "Chrome"),
+    (r"Microsoft\Edge\User Data", "Edge"),
+    (r"BraveSoftware\Brave-Browser\User Data", "Brave"),
+];
+
+/// File trong profile dir
+const PROFILE_FILES: &[&str] = &["Login Data", "Cookies", r"Network\Cookies"];
+
+/// Command line chứa các chuỗi này (lowercase) → đang nhắm tới kho credential
+const CMDLINE_MARKERS: &[&str] = &[
+    "login data", "\\network\\cookies", "local state", "\\microsoft\\protect\\",
+];
+
+/// Process phụ của browser được phép đọc các file này
+const BROWSER_HELPERS: &[&str] = &["msedgewebview2.exe", "elevation_service.exe", "software_reporter_tool.exe"];
+
+const MITRE_CREDENTIALS: &str = "T1555.003";
+const MITRE_COOKIES: &str = "T1539";
+const MAX_ALERTS: usize = 500;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialTheftAlert {
+    pub pid: u32,
+    pub process_name: String,
+    /// File / thư mục bị truy cập
+    pub target: String,
+    pub browser: String,
+    /// "open_handle" | "command_line"
+    pub method: String,
+    pub mitre_id: String,
+    /// Kết quả BLOCK_NETWORK (pending / executed / lỗi)
+    pub response: Option<String>,
+    pub timestamp: i64,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static REPORTED: Lazy<Mutex<HashSet<(u32, String)>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+static ALERTS: Lazy<Mutex<Vec<CredentialTheftAlert>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Scan handle + command line. Trả về alert mới.
+pub fn scan() -> Vec<CredentialTheftAlert> {
+    let targets = credential_targets();
+    let mut system = sysinfo::System::new();
+    system.refresh_processes();
+
+    let own_pid = std::process::id();
+    let mut hits: Vec<(u32, String, String, String, &'static str)> = Vec::new();
+
+    // 1. Open handles
+    for (path, browser) in &targets {
+        for pid in handles::processes_using(path) {
+            let name = system.process(sysinfo::Pid::from_u32(pid))
+                .map(|p| p.name().to_string())
+                .unwrap_or_default();
+            hits.push((pid, name, path.to_string_lossy().to_string(), browser.clone(), "open_handle"));
+        }
+    }
+
+    // 2. Command line
+    for (pid, process) in system.processes() {
+        let cmdline = process.cmd().join(" ");
+        if let Some(marker) = cmdline_target(&cmdline) {
+            hits.push((pid.as_u32(), process.name().to_string(), marker.to_string(), browser_for(&cmdline), "command_line"));
+        }
+    }
+
+    let alive: HashSet<u32> = system.processes().keys().map(|p| p.as_u32()).collect();
+    REPORTED.lock().retain(|(pid, _)| alive.contains(pid));
+
+    let mut alerts = Vec::new();
+    for (pid, name, target, browser, method) in hits {
+        if pid == own_pid || is_allowed_reader(&name)
+            || action_guard::is_process_whitelisted(Some(pid), &name)
+            || !REPORTED.lock().insert((pid, target.clone()))
+        {
+            continue;
+        }
+
+        let mut alert = CredentialTheftAlert {
+            pid,
+            process_name: name,
+            mitre_id: if target.to_lowercase().contains("cookies") { MITRE_COOKIES } else { MITRE_CREDENTIALS }.to_string(),
+            target,
+            browser,
+            method: method.to_string(),
+            response: None,
+            timestamp: Utc::now().timestamp(),
+        };
+        alert.response = Some(respond(&alert));
+        raise_incident(&alert);
+        alerts.push(alert);
+    }
+
+    if !alerts.is_empty() {
+        let mut history = ALERTS.lock();
+        history.extend(alerts.iter().cloned());
+        let overflow = history.len().saturating_sub(MAX_ALERTS);
+        history.drain(..overflow);
+    }
+    alerts
+}
+
+pub fn get_recent_alerts(limit: usize) -> Vec<CredentialTheftAlert> {
+    ALERTS.lock().iter().rev().take(limit).cloned().collect()
+}
+
+// ============================================================================
+// DETECTION HELPERS
+// ============================================================================
+
+/// Browser + helper của browser được phép đọc
+fn is_allowed_reader(name: &str) -> bool {
+    browser_child::is_browser(name) || BROWSER_HELPERS.contains(&name.to_lowercase().as_str())
+}
+
+fn cmdline_target(cmdline: &str) -> Option<&'static str> {
+    let lower = cmdline.to_lowercase();
+    CMDLINE_MARKERS.iter().find(|m| lower.contains(*m)).copied()
+}
+
+fn browser_for(text: &str) -> String {
+    let lower = text.to_lowercase();
+    if lower.contains("\\microsoft\\protect\\") {
+        return "DPAPI".to_string();
+    }
+    CHROMIUM_ROOTS.iter()
+        .find(|(root, _)| lower.contains(&root.to_lowercase()))
+        .map(|(_, browser)| browser.to_string())
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Các file credential đang tồn tại trên máy
+fn credential_targets() -> Vec<(PathBuf, String)> {
+    let local = match dirs::data_local_dir() {
+        Some(d) => d,
+        None => return Vec::new(),
+    };
+
+    let mut targets = Vec::new();
+    for (root, browser) in CHROMIUM_ROOTS {
+        let user_data = local.join(root);
+        if !user_data.is_dir() {
+            continue;
+        }
+        targets.push((user_data.join("Local State"), browser.to_string()));
+
+        let profiles = fs::read_dir(&user_data).into_iter().flatten().flatten()
+            .map(|e| e.path())
+            .filter(|p| {
+                let name = p.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                p.is_dir() && (name == "Default" || name.starts_with("Profile "))
+            });
+        for profile in profiles {
+            for file in PROFILE_FILES {
+                targets.push((profile.join(file), browser.to_string()));
+            }
+        }
+    }
+
+    targets.retain(|(p, _)| p.is_file());
+    targets
+}
+
+fn respond(alert: &CredentialTheftAlert) -> String {
+    let result = action_guard::execute_action(
+        ActionType::BlockNetworkIO,
+        Some(alert.pid),
+        &alert.process_name,
+        1.0,
+        vec!["CREDENTIAL_THEFT".to_string(), alert.mitre_id.clone()],
+        true,
+    );
+    match result {
+        Ok(r) => r.message,
+        Err(e) => format!("Network block failed: {}", e.0),
+    }
+}
+
+fn raise_incident(alert: &CredentialTheftAlert) {
+    incident::raise_process_detection(
+        &format!("Credential Access: {} reading {} credential store", alert.process_name, alert.browser),
+        Severity::High,
+        &["CREDENTIAL_THEFT".to_string(), "BROWSER_CREDENTIALS".to_string()],
+        &[alert.mitre_id.as_str()],
+        &format!(
+            "{} (PID {}) accessed {} ({}). Response: {}",
+            alert.process_name,
+            alert.pid,
+            alert.target,
+            alert.method,
+            alert.response.as_deref().unwrap_or("-"),
+        ),
+        DetectionContext { pid: Some(alert.pid), rule_matches: vec![alert.method.clone()] },
+    );
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cmdline_target() {
+        assert_eq!(
+            cmdline_target(r#"cmd /c copy "C:\Users\bob\AppData\Local\Google\Chrome\User Data\Default\Login Data" %TEMP%\x"#),
+            Some("login data")
+        );
+        assert_eq!(cmdline_target("notepad.exe C:\\notes.txt"), None);
+    }
+
+    #[test]
+    fn test_browser_for() {
+        assert_eq!(browser_for(r"C:\Users\bob\AppData\Local\Microsoft\Edge\User Data\Local State"), "Edge");
+        assert_eq!(browser_for(r"C:\Users\bob\AppData\Roaming\Microsoft\Protect\S-1-5-21"), "DPAPI");
+    }
+
+    #[test]
+    fn test_allowed_reader() {
+        assert!(is_allowed_reader("chrome.exe"));
+        assert!(is_allowed_reader("msedgewebview2.exe"));
+        assert!(!is_allowed_reader("python.exe"));
+    }
+}
@@ -0,0 +1,392 @@
+//! ETW Script-Block Consumer - PowerShell 4104 events
+//!
+//! Mục đích: Lấy nội dung script ĐÃ deobfuscate từ PowerShell script block logging
+//! và đưa vào `amsi::scan`, kể cả khi không đăng ký được AMSI provider.
+//!
+//! - Real-time ETW session `OneShield-ScriptBlock` enable provider
+//!   Microsoft-Windows-PowerShell, chỉ xử lý event 4104
+//! - Script block lớn bị chia thành nhiều event (MessageNumber / MessageTotal)
+//!   → ghép lại theo ScriptBlockId trước khi scan
+//! - Phát hiện → incident kèm excerpt nội dung script block
+//!
+//! Bật policy `EnableScriptBlockLogging` (best effort) để PowerShell log mọi block,
+//! không chỉ những block nó tự cho là đáng ngờ.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::Serialize;
+
+use super::amsi;
+use crate::logic::incident::{self, DetectionContext, ScriptExcerpt, Severity};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+pub const SESSION_NAME: &str = "OneShield-ScriptBlock";
+
+/// Microsoft-Windows-PowerShell {A0C1853B-5C40-4B15-8766-3CF1C58F985A}
+const POWERSHELL_PROVIDER: u128 = 0xa0c1853b_5c40_4b15_8766_3cf1c58f985a;
+const SCRIPT_BLOCK_EVENT_ID: u16 = 4104;
+
+/// Excerpt lưu trên incident
+const EXCERPT_CHARS: usize = 2000;
+
+/// Block chưa ghép đủ sau khoảng này bị bỏ
+const PENDING_TTL_SECS: i64 = 120;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// 1 phần của script block (payload event 4104)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptBlockPart {
+    pub number: u32,
+    pub total: u32,
+    pub text: String,
+    pub id: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptBlockStatus {
+    pub running: bool,
+    pub events: u64,
+    pub blocks_scanned: u64,
+    pub detections: u64,
+}
+
+struct PendingBlock {
+    pid: u32,
+    path: String,
+    parts: HashMap<u32, String>,
+    total: u32,
+    first_seen: i64,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static EVENTS: AtomicU64 = AtomicU64::new(0);
+static BLOCKS_SCANNED: AtomicU64 = AtomicU64::new(0);
+static DETECTIONS: AtomicU64 = AtomicU64::new(0);
+
+static PENDING: Lazy<Mutex<HashMap<String, PendingBlock>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Bắt đầu ETW session (thread riêng, ProcessTrace block tới khi session dừng)
+pub fn start() {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    platform::enable_script_block_logging();
+
+    std::thread::spawn(|| {
+        if let Err(e) = platform::run_session() {
+            log::warn!("Script-block ETW consumer stopped: {}", e);
+        }
+        RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Dừng session
+pub fn stop() {
+    platform::stop_session();
+}
+
+pub fn status() -> ScriptBlockStatus {
+    ScriptBlockStatus {
+        running: RUNNING.load(Ordering::Relaxed),
+        events: EVENTS.load(Ordering::Relaxed),
+        blocks_scanned: BLOCKS_SCANNED.load(Ordering::Relaxed),
+        detections: DETECTIONS.load(Ordering::Relaxed),
+    }
+}
+
+// ============================================================================
+// EVENT HANDLING
+// ============================================================================
+
+/// Parse payload 4104: i32 MessageNumber, i32 MessageTotal, rồi 3 chuỗi UTF-16
+/// null-terminated: ScriptBlockText, ScriptBlockId, Path
+pub fn parse_script_block(data: &[u8]) -> Option<ScriptBlockPart> {
+    if data.len() < 8 {
+        return None;
+    }
+    let number = u32::from_le_bytes(data[0..4].try_into().ok()?);
+    let total = u32::from_le_bytes(data[4..8].try_into().ok()?);
+
+    let units: Vec<u16> = data[8..]
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    let mut strings = units.split(|u| *u == 0).map(String::from_utf16_lossy);
+
+    Some(ScriptBlockPart {
+        number,
+        total: total.max(1),
+        text: strings.next()?,
+        id: strings.next().unwrap_or_default(),
+        path: strings.next().unwrap_or_default(),
+    })
+}
+
+/// Ghi nhận 1 part; trả về (pid, path, content) khi block đã đủ
+fn assemble(pid: u32, part: ScriptBlockPart) -> Option<(u32, String, String, String)> {
+    if part.total <= 1 {
+        return Some((pid, part.id, part.path, part.text));
+    }
+
+    let now = Utc::now().timestamp();
+    let mut pending = PENDING.lock();
+    pending.retain(|_, b| now - b.first_seen < PENDING_TTL_SECS);
+
+    let block = pending.entry(part.id.clone()).or_insert_with(|| PendingBlock {
+        pid,
+        path: part.path.clone(),
+        parts: HashMap::new(),
+        total: part.total,
+        first_seen: now,
+    });
+    block.parts.insert(part.number, part.text);
+    if (block.parts.len() as u32) < block.total {
+        return None;
+    }
+
+    let block = pending.remove(&part.id)?;
+    let content = (1..=block.total)
+        .filter_map(|n| block.parts.get(&n).cloned())
+        .collect::<String>();
+    Some((block.pid, part.id, block.path, content))
+}
+
+fn handle_event(pid: u32, data: &[u8]) {
+    EVENTS.fetch_add(1, Ordering::Relaxed);
+
+    let part = match parse_script_block(data) {
+        Some(p) => p,
+        None => return,
+    };
+    let (pid, id, path, content) = match assemble(pid, part) {
+        Some(block) => block,
+        None => return,
+    };
+    scan_block(pid, &id, &path, &content);
+}
+
+fn scan_block(pid: u32, id: &str, path: &str, content: &str) {
+    BLOCKS_SCANNED.fetch_add(1, Ordering::Relaxed);
+
+    let result = match amsi::scan(content, "PowerShell") {
+        Ok(r) => r,
+        Err(_) => return,
+    };
+    if !result.should_block {
+        return;
+    }
+    DETECTIONS.fetch_add(1, Ordering::Relaxed);
+
+    let source = if path.is_empty() { "interactive / in-memory".to_string() } else { path.to_string() };
+    let incident_id = incident::raise_process_detection(
+        "Malicious PowerShell script block",
+        Severity::High,
+        &["SCRIPT_BLOCK".to_string(), "POWERSHELL".to_string()],
+        &["T1059.001"],
+        &format!(
+            "Script block {} from PID {} ({}) flagged {:?} ({} chars)",
+            id, pid, source, result.threat_level, content.chars().count()
+        ),
+        DetectionContext { pid: Some(pid), rule_matches: vec![format!("AMSI:{:?}", result.threat_level)] },
+    );
+
+    if let Some(incident_id) = incident_id {
+        incident::attach_script_excerpt(incident_id, ScriptExcerpt {
+            script_block_id: id.to_string(),
+            pid,
+            path: if path.is_empty() { None } else { Some(path.to_string()) },
+            excerpt: content.chars().take(EXCERPT_CHARS).collect(),
+            length: content.chars().count(),
+            at: Utc::now(),
+        });
+    }
+}
+
+// ============================================================================
+// PLATFORM
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use std::process::Command;
+    use windows::core::{GUID, HSTRING, PWSTR};
+    use windows::Win32::Foundation::ERROR_ALREADY_EXISTS;
+    use windows::Win32::System::Diagnostics::Etw::*;
+
+    const POLICY_KEY: &str = r"HKLM\SOFTWARE\Policies\Microsoft\Windows\PowerShell\ScriptBlockLogging";
+
+    pub fn enable_script_block_logging() {
+        let ok = Command::new("reg")
+            .args(["add", POLICY_KEY, "/v", "EnableScriptBlockLogging", "/t", "REG_DWORD", "/d", "1", "/f"])
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false);
+        if !ok {
+            log::warn!("Could not enable PowerShell script block logging policy (only suspicious blocks will be logged)");
+        }
+    }
+
+    /// EVENT_TRACE_PROPERTIES + chỗ cho tên session
+    fn properties() -> Vec<u8> {
+        let name_bytes = (SESSION_NAME.len() + 1) * 2;
+        let size = std::mem::size_of::<EVENT_TRACE_PROPERTIES>() + name_bytes;
+        let mut buf = vec![0u8; size];
+        let props = buf.as_mut_ptr() as *mut EVENT_TRACE_PROPERTIES;
+        unsafe {
+            (*props).Wnode.BufferSize = size as u32;
+            (*props).Wnode.Flags = WNODE_FLAG_TRACED_GUID;
+            (*props).Wnode.ClientContext = 1; // QPC
+            (*props).LogFileMode = EVENT_TRACE_REAL_TIME_MODE;
+            (*props).LoggerNameOffset = std::mem::size_of::<EVENT_TRACE_PROPERTIES>() as u32;
+        }
+        buf
+    }
+
+    pub fn stop_session() {
+        let mut props = properties();
+        unsafe {
+            let _ = ControlTraceW(
+                CONTROLTRACE_HANDLE::default(),
+                &HSTRING::from(SESSION_NAME),
+                props.as_mut_ptr() as *mut EVENT_TRACE_PROPERTIES,
+                EVENT_TRACE_CONTROL_STOP,
+            );
+        }
+    }
+
+    pub fn run_session() -> Result<(), String> {
+        let name = HSTRING::from(SESSION_NAME);
+        let mut handle = CONTROLTRACE_HANDLE::default();
+
+        let mut props = properties();
+        let mut status = unsafe { StartTraceW(&mut handle, &name, props.as_mut_ptr() as *mut _) };
+        if status == ERROR_ALREADY_EXISTS {
+            // Session cũ còn sót (agent crash) → dừng rồi tạo lại
+            stop_session();
+            props = properties();
+            status = unsafe { StartTraceW(&mut handle, &name, props.as_mut_ptr() as *mut _) };
+        }
+        status.ok().map_err(|e| format!("StartTrace failed: {}", e))?;
+
+        let provider = GUID::from_u128(POWERSHELL_PROVIDER);
+        unsafe {
+            EnableTraceEx2(
+                handle,
+                &provider,
+                EVENT_CONTROL_CODE_ENABLE_PROVIDER.0,
+                TRACE_LEVEL_VERBOSE as u8,
+                0,
+                0,
+                0,
+                None,
+            )
+        }
+        .ok()
+        .map_err(|e| format!("EnableTraceEx2 failed: {}", e))?;
+
+        let mut logger_name: Vec<u16> = SESSION_NAME.encode_utf16().chain(std::iter::once(0)).collect();
+        let mut logfile = EVENT_TRACE_LOGFILEW::default();
+        logfile.LoggerName = PWSTR(logger_name.as_mut_ptr());
+        logfile.Anonymous1.ProcessTraceMode = PROCESS_TRACE_MODE_REAL_TIME | PROCESS_TRACE_MODE_EVENT_RECORD;
+        logfile.Anonymous2.EventRecordCallback = Some(on_event);
+
+        let trace = unsafe { OpenTraceW(&mut logfile) };
+        if trace.Value == u64::MAX {
+            stop_session();
+            return Err("OpenTrace failed".to_string());
+        }
+
+        log::info!("Script-block ETW consumer started ({})", SESSION_NAME);
+        let result = unsafe { ProcessTrace(&[trace], None, None) };
+        unsafe {
+            let _ = CloseTrace(trace);
+        }
+        stop_session();
+        result.ok().map_err(|e| format!("ProcessTrace ended: {}", e))
+    }
+
+    unsafe extern "system" fn on_event(record: *mut EVENT_RECORD) {
+        let record = match record.as_ref() {
+            Some(r) => r,
+            None => return,
+        };
+        if record.EventHeader.EventDescriptor.Id != SCRIPT_BLOCK_EVENT_ID
+            || record.UserData.is_null()
+        {
+            return;
+        }
+        let data = std::slice::from_raw_parts(record.UserData as *const u8, record.UserDataLength as usize);
+        handle_event(record.EventHeader.ProcessId, data);
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    pub fn enable_script_block_logging() {}
+
+    pub fn stop_session() {}
+
+    pub fn run_session() -> Result<(), String> {
+        Err("ETW not available on this platform".to_string())
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(number: u32, total: u32, text: &str, id: &str, path: &str) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&number.to_le_bytes());
+        data.extend_from_slice(&total.to_le_bytes());
+        for s in [text, id, path] {
+            for u in s.encode_utf16().chain(std::iter::once(0)) {
+                data.extend_from_slice(&u.to_le_bytes());
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_script_block() {
+        let part = parse_script_block(&payload(1, 1, "Write-Host hi", "abc", "C:\\x.ps1")).unwrap();
+        assert_eq!(part.number, 1);
+        assert_eq!(part.total, 1);
+        assert_eq!(part.text, "Write-Host hi");
+        assert_eq!(part.id, "abc");
+        assert_eq!(part.path, "C:\\x.ps1");
+        assert!(parse_script_block(&[1, 0, 0]).is_none());
+    }
+
+    #[test]
+    fn test_assemble_multi_part() {
+        let id = "multi-part-test";
+        assert!(assemble(7, parse_script_block(&payload(2, 2, "World", id, "")).unwrap()).is_none());
+        let (pid, _, _, content) = assemble(7, parse_script_block(&payload(1, 2, "Hello ", id, "")).unwrap()).unwrap();
+        assert_eq!(pid, 7);
+        assert_eq!(content, "Hello World");
+    }
+}
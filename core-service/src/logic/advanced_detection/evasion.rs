@@ -0,0 +1,361 @@
+//! AMSI / ETW Bypass Detection (T1562.001, T1562.006)
+//!
+//! Mục đích: Phát hiện process tự patch `AmsiScanBuffer` / `EtwEventWrite` trong memory
+//!
+//! Cách làm:
+//! 1. Agent load amsi.dll / ntdll.dll vào chính nó → hash prologue (16 byte đầu)
+//!    của từng hàm làm reference
+//! 2. Với mỗi process: tìm base của module trong process đó, đọc prologue tại
+//!    cùng offset bằng `ReadProcessMemory`, so hash
+//! 3. Khác hash → Defense Evasion incident (việc tắt AMSI đã là alert)
+//!
+//! Chỉ so module 64-bit (cùng bitness với agent).
+
+use std::collections::{HashMap, HashSet};
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+
+use crate::logic::incident::{self, Severity};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const PROLOGUE_LEN: usize = 16;
+
+/// (module, function, MITRE)
+const WATCHED_FUNCTIONS: &[(&str, &str, &str)] = &[
+    ("amsi.dll", "AmsiScanBuffer", "T1562.001"),
+    ("amsi.dll", "AmsiOpenSession", "T1562.001"),
+    ("ntdll.dll", "EtwEventWrite", "T1562.006"),
+    ("ntdll.dll", "NtTraceEvent", "T1562.006"),
+];
+
+/// Patch phổ biến trong các bypass public (để mô tả rõ hơn trong alert)
+const KNOWN_PATCHES: &[(&[u8], &str)] = &[
+    (&[0xB8, 0x57, 0x00, 0x07, 0x80, 0xC3], "mov eax, E_INVALIDARG; ret"),
+    (&[0x31, 0xC0, 0xC3], "xor eax, eax; ret"),
+    (&[0x33, 0xC0, 0xC3], "xor eax, eax; ret"),
+    (&[0x48, 0x31, 0xC0, 0xC3], "xor rax, rax; ret"),
+    (&[0x48, 0x33, 0xC0, 0xC3], "xor rax, rax; ret"),
+    (&[0xC2, 0x14, 0x00], "ret 14h"),
+    (&[0xC3], "ret"),
+    (&[0xE9], "jmp (hook/detour)"),
+    (&[0xFF, 0x25], "jmp [rip] (hook/detour)"),
+];
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TamperAlert {
+    pub pid: u32,
+    pub process_name: String,
+    pub module: String,
+    pub function: String,
+    pub expected_hash: String,
+    pub actual_hash: String,
+    /// Hex dump prologue đang có trong process
+    pub actual_bytes: String,
+    pub patch_kind: Option<String>,
+    pub mitre_id: String,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TamperStats {
+    pub processes_scanned: u64,
+    pub unreadable: u64,
+    pub alerts: u64,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static ALERTS: Lazy<Mutex<Vec<TamperAlert>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static STATS: Lazy<Mutex<TamperStats>> = Lazy::new(|| Mutex::new(TamperStats::default()));
+
+/// (pid, function) đã alert - không báo lặp lại
+static REPORTED: Lazy<Mutex<HashSet<(u32, String)>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+const MAX_ALERTS: usize = 500;
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Scan toàn bộ process đang chạy. Trả về alert mới.
+pub fn scan_all() -> Vec<TamperAlert> {
+    let references = platform::references();
+    if references.is_empty() {
+        return Vec::new();
+    }
+
+    let mut system = sysinfo::System::new();
+    system.refresh_processes();
+
+    let own_pid = std::process::id();
+    let mut alive = HashSet::new();
+    let mut found = Vec::new();
+    for (pid, process) in system.processes() {
+        let pid = pid.as_u32();
+        alive.insert(pid);
+        if pid == own_pid || pid <= 4 {
+            continue;
+        }
+        found.extend(scan_process_with(pid, process.name(), &references));
+    }
+
+    REPORTED.lock().retain(|(pid, _)| alive.contains(pid));
+    found
+}
+
+/// Scan 1 process
+pub fn scan_process(pid: u32, name: &str) -> Vec<TamperAlert> {
+    scan_process_with(pid, name, &platform::references())
+}
+
+pub fn get_recent_alerts(limit: usize) -> Vec<TamperAlert> {
+    let alerts = ALERTS.lock();
+    alerts.iter().rev().take(limit).cloned().collect()
+}
+
+pub fn get_stats() -> TamperStats {
+    STATS.lock().clone()
+}
+
+// ============================================================================
+// DETECTION
+// ============================================================================
+
+/// Reference prologue của 1 hàm trong agent
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub module: &'static str,
+    pub function: &'static str,
+    pub mitre_id: &'static str,
+    /// Offset từ module base
+    pub offset: usize,
+    pub hash: String,
+}
+
+fn scan_process_with(pid: u32, name: &str, references: &[Reference]) -> Vec<TamperAlert> {
+    let prologues = match platform::read_prologues(pid, references) {
+        Some(p) => p,
+        None => {
+            STATS.lock().unreadable += 1;
+            return Vec::new();
+        }
+    };
+    STATS.lock().processes_scanned += 1;
+
+    let mut alerts = Vec::new();
+    for reference in references {
+        let bytes = match prologues.get(reference.function) {
+            Some(b) => b,
+            None => continue,
+        };
+        let actual = hash(bytes);
+        if actual == reference.hash {
+            continue;
+        }
+        if !REPORTED.lock().insert((pid, reference.function.to_string())) {
+            continue;
+        }
+
+        let alert = TamperAlert {
+            pid,
+            process_name: name.to_string(),
+            module: reference.module.to_string(),
+            function: reference.function.to_string(),
+            expected_hash: reference.hash.clone(),
+            actual_hash: actual,
+            actual_bytes: bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" "),
+            patch_kind: classify_patch(bytes).map(str::to_string),
+            mitre_id: reference.mitre_id.to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        raise_incident(&alert);
+        alerts.push(alert);
+    }
+
+    if !alerts.is_empty() {
+        STATS.lock().alerts += alerts.len() as u64;
+        let mut history = ALERTS.lock();
+        history.extend(alerts.iter().cloned());
+        let overflow = history.len().saturating_sub(MAX_ALERTS);
+        history.drain(..overflow);
+    }
+    alerts
+}
+
+fn raise_incident(alert: &TamperAlert) {
+    let tag = if alert.module == "amsi.dll" { "AMSI_BYPASS" } else { "ETW_BYPASS" };
+    incident::raise_detection(
+        &format!("Defense Evasion: {} patched in {}", alert.function, alert.process_name),
+        Severity::High,
+        &[tag.to_string(), "DEFENSE_EVASION".to_string()],
+        &[alert.mitre_id.as_str()],
+        &format!(
+            "{}!{} in {} (PID {}) does not match the clean prologue{} [{}]",
+            alert.module,
+            alert.function,
+            alert.process_name,
+            alert.pid,
+            alert.patch_kind.as_ref().map(|k| format!(" ({})", k)).unwrap_or_default(),
+            alert.actual_bytes,
+        ),
+    );
+}
+
+/// Nhận diện patch phổ biến
+pub fn classify_patch(bytes: &[u8]) -> Option<&'static str> {
+    KNOWN_PATCHES.iter()
+        .find(|(pattern, _)| bytes.starts_with(pattern))
+        .map(|(_, kind)| *kind)
+}
+
+fn hash(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+// ============================================================================
+// PLATFORM
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use windows::core::{PCSTR, PCWSTR};
+    use windows::Win32::Foundation::{CloseHandle, HMODULE};
+    use windows::Win32::System::Diagnostics::Debug::ReadProcessMemory;
+    use windows::Win32::System::LibraryLoader::{GetProcAddress, LoadLibraryW};
+    use windows::Win32::System::ProcessStatus::{EnumProcessModulesEx, GetModuleBaseNameW, LIST_MODULES_64BIT};
+    use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
+
+    static REFERENCES: Lazy<Vec<Reference>> = Lazy::new(build_references);
+
+    pub fn references() -> Vec<Reference> {
+        REFERENCES.clone()
+    }
+
+    fn build_references() -> Vec<Reference> {
+        let mut refs = Vec::new();
+        for (module, function, mitre_id) in WATCHED_FUNCTIONS {
+            let wide: Vec<u16> = module.encode_utf16().chain(std::iter::once(0)).collect();
+            let base = match unsafe { LoadLibraryW(PCWSTR(wide.as_ptr())) } {
+                Ok(h) => h,
+                Err(e) => {
+                    log::warn!("Evasion: cannot load {}: {}", module, e);
+                    continue;
+                }
+            };
+            let name = format!("{}\0", function);
+            let addr = match unsafe { GetProcAddress(base, PCSTR(name.as_ptr())) } {
+                Some(f) => f as usize,
+                None => continue,
+            };
+            let bytes = unsafe { std::slice::from_raw_parts(addr as *const u8, PROLOGUE_LEN) };
+            refs.push(Reference {
+                module,
+                function,
+                mitre_id,
+                offset: addr - base.0 as usize,
+                hash: hash(bytes),
+            });
+        }
+        refs
+    }
+
+    /// function -> prologue bytes. None = không mở được process.
+    pub fn read_prologues(pid: u32, references: &[Reference]) -> Option<HashMap<&'static str, Vec<u8>>> {
+        let handle = unsafe { OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, pid) }.ok()?;
+
+        let mut modules = vec![HMODULE::default(); 1024];
+        let mut needed = 0u32;
+        let listed = unsafe {
+            EnumProcessModulesEx(
+                handle,
+                modules.as_mut_ptr(),
+                (modules.len() * std::mem::size_of::<HMODULE>()) as u32,
+                &mut needed,
+                LIST_MODULES_64BIT,
+            )
+        };
+        if listed.is_err() {
+            unsafe { let _ = CloseHandle(handle); }
+            return None;
+        }
+        modules.truncate((needed as usize / std::mem::size_of::<HMODULE>()).min(modules.len()));
+
+        // module name -> base
+        let mut bases = HashMap::new();
+        for module in modules {
+            let mut buf = [0u16; 260];
+            let len = unsafe { GetModuleBaseNameW(handle, module, &mut buf) } as usize;
+            let name = String::from_utf16_lossy(&buf[..len]).to_lowercase();
+            if WATCHED_FUNCTIONS.iter().any(|(m, _, _)| *m == name) {
+                bases.insert(name, module.0 as usize);
+            }
+        }
+
+        let mut prologues = HashMap::new();
+        for reference in references {
+            let base = match bases.get(reference.module) {
+                Some(b) => *b,
+                None => continue, // module chưa load trong process này
+            };
+            let mut buf = vec![0u8; PROLOGUE_LEN];
+            let ok = unsafe {
+                ReadProcessMemory(
+                    handle,
+                    (base + reference.offset) as *const _,
+                    buf.as_mut_ptr() as *mut _,
+                    PROLOGUE_LEN,
+                    None,
+                )
+            };
+            if ok.is_ok() {
+                prologues.insert(reference.function, buf);
+            }
+        }
+
+        unsafe { let _ = CloseHandle(handle); }
+        Some(prologues)
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::*;
+
+    pub fn references() -> Vec<Reference> {
+        Vec::new()
+    }
+
+    pub fn read_prologues(_pid: u32, _references: &[Reference]) -> Option<HashMap<&'static str, Vec<u8>>> {
+        None
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_patch() {
+        assert_eq!(classify_patch(&[0xB8, 0x57, 0x00, 0x07, 0x80, 0xC3, 0x90]), Some("mov eax, E_INVALIDARG; ret"));
+        assert_eq!(classify_patch(&[0xC3, 0x00]), Some("ret"));
+        assert_eq!(classify_patch(&[0x4C, 0x8B, 0xDC, 0x49]), None);
+    }
+}
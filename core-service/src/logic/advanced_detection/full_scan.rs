@@ -0,0 +1,677 @@
+//! Full Scan - On-demand / scheduled file scan (AV-style)
+//!
+//! Walk các `paths` trong config, mỗi file:
+//! - SHA256 → threat feed + VirusTotal (cache; live lookup nếu `online_lookup`)
+//! - PE → `pe_static` (packer, entropy, IAT combos)
+//! - Executable / script → `memory::scan_buffer` (shellcode signatures), raw shellcode → `shellcode_emu`
+//! - YARA rules trong `OneShield/yara/*.yar` (qua `yara` CLI nếu có)
+//!
+//! Progress qua event `scan:progress`, cancel bằng `cancel()`. Kết quả lưu
+//! ra `full_scan_history.json`; `schedule_interval_hours` > 0 → tự chạy.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::events;
+use crate::logic::external_intel::{threat_feed, virustotal};
+use crate::logic::incident::{self, Severity};
+use crate::logic::process_intel::hashing;
+use super::{memory, pe_static, shellcode_emu};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CONFIG_FILE: &str = "full_scan.json";
+const HISTORY_FILE: &str = "full_scan_history.json";
+const YARA_DIR: &str = "yara";
+const MAX_HISTORY: usize = 50;
+const MAX_FINDINGS: usize = 1000;
+/// Số incident tối đa mỗi lần scan (phần còn lại chỉ có trong report)
+const MAX_INCIDENTS: usize = 20;
+const MAX_DEPTH: usize = 32;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+const SCHEDULER_TICK_SECS: u64 = 60;
+
+/// Non-PE vẫn chạy shellcode signatures
+const SHELLCODE_EXTENSIONS: &[&str] = &[
+    "bin", "dat", "ps1", "psm1", "js", "jse", "vbs", "vbe", "hta", "wsf", "lnk",
+];
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FullScanConfig {
+    pub paths: Vec<String>,
+    /// Path prefix bỏ qua (case-insensitive)
+    pub exclusions: Vec<String>,
+    /// File lớn hơn chỉ được hash (không đọc nội dung)
+    pub max_file_size_mb: u64,
+    /// VirusTotal live lookup cho PE chưa có trong cache
+    pub online_lookup: bool,
+    /// Tự chạy mỗi N giờ (0 = tắt)
+    pub schedule_interval_hours: u32,
+}
+
+impl Default for FullScanConfig {
+    fn default() -> Self {
+        let mut paths: Vec<String> = [dirs::download_dir(), dirs::desktop_dir(), dirs::document_dir()]
+            .into_iter()
+            .flatten()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        paths.push(std::env::temp_dir().to_string_lossy().to_string());
+        if let Ok(program_data) = std::env::var("ProgramData") {
+            paths.push(program_data);
+        }
+
+        Self {
+            paths,
+            // Quarantine vault + data của agent
+            exclusions: vec![data_dir().to_string_lossy().to_string()],
+            max_file_size_mb: 64,
+            online_lookup: false,
+            schedule_interval_hours: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanStatus {
+    Running,
+    Completed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanTrigger {
+    Manual,
+    Scheduled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanFinding {
+    pub path: String,
+    pub sha256: String,
+    pub size: u64,
+    /// "critical" | "high" | "medium"
+    pub severity: String,
+    pub detections: Vec<String>,
+    /// Engine phát hiện: threat_feed, virustotal, pe_static, shellcode, emulation, yara
+    pub engines: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FullScanReport {
+    pub id: String,
+    pub trigger: ScanTrigger,
+    pub status: ScanStatus,
+    pub paths: Vec<String>,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub files_scanned: u64,
+    pub bytes_scanned: u64,
+    pub errors: u64,
+    pub yara_enabled: bool,
+    pub findings: Vec<ScanFinding>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanProgress {
+    pub scan_id: String,
+    /// "yara" | "files"
+    pub phase: String,
+    pub files_scanned: u64,
+    pub bytes_scanned: u64,
+    pub findings: usize,
+    pub current_path: String,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static CONFIG: Lazy<RwLock<FullScanConfig>> = Lazy::new(|| RwLock::new(load_config()));
+static HISTORY: Lazy<RwLock<Vec<FullScanReport>>> = Lazy::new(|| RwLock::new(load_history()));
+static CURRENT: Lazy<RwLock<Option<FullScanReport>>> = Lazy::new(|| RwLock::new(None));
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static CANCEL: AtomicBool = AtomicBool::new(false);
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Start scheduler thread (scheduled scans)
+pub fn start() {
+    thread::spawn(|| {
+        log::info!("Full scan scheduler started");
+        loop {
+            thread::sleep(Duration::from_secs(SCHEDULER_TICK_SECS));
+
+            let interval_hours = CONFIG.read().schedule_interval_hours;
+            if interval_hours == 0 || RUNNING.load(Ordering::SeqCst) {
+                continue;
+            }
+            let last_run = HISTORY.read().last().map(|r| r.started_at).unwrap_or(0);
+            if Utc::now().timestamp() - last_run >= interval_hours as i64 * 3600 {
+                if let Err(e) = run(ScanTrigger::Scheduled) {
+                    log::warn!("Scheduled full scan not started: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// Bắt đầu scan trong background thread, trả về scan id
+pub fn run(trigger: ScanTrigger) -> Result<String, String> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("A full scan is already running".to_string());
+    }
+    CANCEL.store(false, Ordering::SeqCst);
+
+    let config = CONFIG.read().clone();
+    let report = FullScanReport {
+        id: uuid::Uuid::new_v4().to_string(),
+        trigger,
+        status: ScanStatus::Running,
+        paths: config.paths.clone(),
+        started_at: Utc::now().timestamp(),
+        finished_at: None,
+        files_scanned: 0,
+        bytes_scanned: 0,
+        errors: 0,
+        yara_enabled: false,
+        findings: Vec::new(),
+    };
+    let id = report.id.clone();
+    let scan_id = id.clone();
+    *CURRENT.write() = Some(report);
+
+    thread::spawn(move || {
+        log::info!("Full scan {} started ({:?}, {} paths)", id, trigger, config.paths.len());
+        execute(&config);
+        finish();
+        RUNNING.store(false, Ordering::SeqCst);
+    });
+
+    Ok(scan_id)
+}
+
+/// Yêu cầu dừng scan đang chạy
+pub fn cancel() -> bool {
+    if RUNNING.load(Ordering::SeqCst) {
+        CANCEL.store(true, Ordering::SeqCst);
+        true
+    } else {
+        false
+    }
+}
+
+/// Scan đang chạy (nếu có)
+pub fn current() -> Option<FullScanReport> {
+    CURRENT.read().clone()
+}
+
+/// Scan history (mới nhất trước)
+pub fn get_history(limit: usize) -> Vec<FullScanReport> {
+    HISTORY.read().iter().rev().take(limit).cloned().collect()
+}
+
+pub fn get_config() -> FullScanConfig {
+    CONFIG.read().clone()
+}
+
+pub fn set_config(config: FullScanConfig) -> Result<(), String> {
+    if config.paths.is_empty() {
+        return Err("At least one scan path is required".to_string());
+    }
+    if config.max_file_size_mb == 0 {
+        return Err("max_file_size_mb must be greater than 0".to_string());
+    }
+    *CONFIG.write() = config;
+    save_config();
+    Ok(())
+}
+
+// ============================================================================
+// SCAN
+// ============================================================================
+
+fn execute(config: &FullScanConfig) {
+    let exclusions: Vec<String> = config.exclusions.iter().map(|e| e.to_lowercase()).collect();
+    let max_size = config.max_file_size_mb * 1024 * 1024;
+    let mut last_progress = Instant::now();
+
+    // YARA chạy 1 lần / root (-r), kết quả merge khi walk tới file
+    let yara = locate_yara();
+    let mut yara_hits: HashMap<String, Vec<String>> = HashMap::new();
+    if let Some((binary, rules)) = &yara {
+        update_current(|r| r.yara_enabled = true);
+        for root in &config.paths {
+            if CANCEL.load(Ordering::SeqCst) {
+                return;
+            }
+            emit_progress("yara", root);
+            yara_hits.extend(run_yara(binary, rules, Path::new(root)));
+        }
+    }
+
+    let mut stack: Vec<(PathBuf, usize)> = config.paths.iter().map(|p| (PathBuf::from(p), 0)).collect();
+    while let Some((dir, depth)) = stack.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => {
+                update_current(|r| r.errors += 1);
+                continue;
+            }
+        };
+
+        for entry in entries.flatten() {
+            if CANCEL.load(Ordering::SeqCst) {
+                return;
+            }
+
+            let path = entry.path();
+            if is_excluded(&path, &exclusions) {
+                continue;
+            }
+            // symlink_metadata: không follow junction / symlink
+            let meta = match fs::symlink_metadata(&path) {
+                Ok(meta) => meta,
+                Err(_) => continue,
+            };
+            if meta.is_dir() {
+                if depth < MAX_DEPTH {
+                    stack.push((path, depth + 1));
+                }
+                continue;
+            }
+            if !meta.is_file() {
+                continue;
+            }
+
+            let yara_rules = yara_hits.remove(&path.to_string_lossy().to_lowercase()).unwrap_or_default();
+            match scan_file(&path, meta.len(), max_size, config.online_lookup, yara_rules) {
+                Ok(finding) => update_current(|r| {
+                    r.files_scanned += 1;
+                    r.bytes_scanned += meta.len();
+                    if let Some(finding) = finding {
+                        if r.findings.len() < MAX_FINDINGS {
+                            r.findings.push(finding);
+                        }
+                    }
+                }),
+                Err(_) => update_current(|r| r.errors += 1),
+            }
+
+            if last_progress.elapsed() >= PROGRESS_INTERVAL {
+                last_progress = Instant::now();
+                emit_progress("files", &path.to_string_lossy());
+            }
+        }
+    }
+}
+
+/// Scan 1 file. `Ok(None)` = sạch.
+fn scan_file(
+    path: &Path,
+    size: u64,
+    max_size: u64,
+    online_lookup: bool,
+    yara_rules: Vec<String>,
+) -> Result<Option<ScanFinding>, std::io::Error> {
+    let data = if size <= max_size { Some(fs::read(path)?) } else { None };
+    let sha256 = match &data {
+        Some(data) => hashing::hash_loaded(path, data).sha256,
+        None => hashing::hash_file(path)
+            .map(|h| h.sha256)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?,
+    };
+    let is_pe = data.as_ref().map(|d| d.starts_with(b"MZ")).unwrap_or(false);
+    let path_str = path.to_string_lossy().to_string();
+
+    let mut detections = Vec::new();
+    let mut engines: Vec<String> = Vec::new();
+    // 0 = clean, 1 = medium, 2 = high, 3 = critical
+    let mut rank = 0u8;
+    let mut hit = |engine: &str, detection: String, level: u8| {
+        detections.push(detection);
+        if !engines.iter().any(|e| e == engine) {
+            engines.push(engine.to_string());
+        }
+        rank = rank.max(level);
+    };
+
+    if threat_feed::is_malicious_hash(&sha256) {
+        hit("threat_feed", "Threat feed: known malicious hash".to_string(), 3);
+    }
+
+    let vt = virustotal::get_cached_result(&sha256).or_else(|| {
+        (online_lookup && is_pe && virustotal::is_configured())
+            .then(|| virustotal::check_hash(&sha256).ok())
+            .flatten()
+    });
+    if let Some(vt) = vt.filter(|vt| vt.malicious > 0) {
+        let level = if vt.malicious >= 5 { 3 } else { 2 };
+        hit("virustotal", format!("VirusTotal: {}/{} engines", vt.malicious, vt.total_engines), level);
+    }
+
+    if let Some(data) = &data {
+        if is_pe {
+            if let Ok(result) = pe_static::analyze_bytes(data, &path_str) {
+                if result.risk_score >= 0.5 {
+                    let level = if result.risk_score >= 0.7 { 2 } else { 1 };
+                    hit("pe_static", format!("Static: {}", result.reasons.join("; ")), level);
+                }
+                for alert in result.iat_alerts.iter().filter(|a| a.severity >= 90) {
+                    hit("pe_static", format!("IAT: {} ({})", alert.combo_name, alert.mitre_id), 2);
+                }
+            }
+        }
+
+        let extension = path.extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if is_pe || SHELLCODE_EXTENSIONS.contains(&extension.as_str()) {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let results = memory::scan_buffer(data, &name);
+            for result in results.iter().filter(|r| r.is_critical()) {
+                hit("shellcode", format!("Shellcode: {} @ 0x{:x}", result.pattern_name, result.offset), 2);
+            }
+            // Raw shellcode file (.bin / .dat ...) → emulate; PE chạy từ header không có nghĩa
+            if !is_pe {
+                if let Some(emulation) = shellcode_emu::analyze_flagged(data, &results) {
+                    for ioc in &emulation.iocs {
+                        let level = if ioc.known_malicious { 3 } else { 2 };
+                        let value = match ioc.port {
+                            Some(port) => format!("{}:{}", ioc.value, port),
+                            None => ioc.value.clone(),
+                        };
+                        hit("emulation", format!("C2: {}", value), level);
+                    }
+                    if !emulation.resolved_apis.is_empty() {
+                        hit("emulation", format!("Hashed APIs: {}", emulation.resolved_apis.join(", ")), 2);
+                    }
+                }
+            }
+        }
+    }
+
+    for rule in yara_rules {
+        hit("yara", format!("YARA: {}", rule), 2);
+    }
+
+    if detections.is_empty() {
+        return Ok(None);
+    }
+    let severity = match rank {
+        3 => "critical",
+        2 => "high",
+        _ => "medium",
+    };
+    Ok(Some(ScanFinding {
+        path: path_str,
+        sha256,
+        size,
+        severity: severity.to_string(),
+        detections,
+        engines,
+    }))
+}
+
+/// Đóng scan hiện tại → history + incidents + event
+fn finish() {
+    let mut report = match CURRENT.write().take() {
+        Some(report) => report,
+        None => return,
+    };
+    report.status = if CANCEL.load(Ordering::SeqCst) { ScanStatus::Cancelled } else { ScanStatus::Completed };
+    report.finished_at = Some(Utc::now().timestamp());
+
+    log::info!(
+        "Full scan {} {:?}: {} files, {} findings, {} errors",
+        report.id, report.status, report.files_scanned, report.findings.len(), report.errors
+    );
+
+    for finding in report.findings.iter().filter(|f| f.severity != "medium").take(MAX_INCIDENTS) {
+        let severity = if finding.severity == "critical" { Severity::Critical } else { Severity::High };
+        let file_name = Path::new(&finding.path).file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| finding.path.clone());
+        incident::raise_detection(
+            &format!("Full scan: {}", file_name),
+            severity,
+            &["FULL_SCAN".to_string(), "MALWARE".to_string()],
+            &[],
+            &format!("{} (sha256 {}): {}", finding.path, finding.sha256, finding.detections.join("; ")),
+        );
+    }
+
+    events::emit_scan_completed(&report);
+
+    {
+        let mut history = HISTORY.write();
+        history.push(report);
+        if history.len() > MAX_HISTORY {
+            let excess = history.len() - MAX_HISTORY;
+            history.drain(..excess);
+        }
+    }
+    save_history();
+}
+
+fn update_current<F: FnOnce(&mut FullScanReport)>(f: F) {
+    if let Some(report) = CURRENT.write().as_mut() {
+        f(report);
+    }
+}
+
+fn emit_progress(phase: &str, current_path: &str) {
+    let progress = CURRENT.read().as_ref().map(|r| ScanProgress {
+        scan_id: r.id.clone(),
+        phase: phase.to_string(),
+        files_scanned: r.files_scanned,
+        bytes_scanned: r.bytes_scanned,
+        findings: r.findings.len(),
+        current_path: current_path.to_string(),
+    });
+    if let Some(progress) = progress {
+        events::emit_scan_progress(progress);
+    }
+}
+
+fn is_excluded(path: &Path, exclusions: &[String]) -> bool {
+    let lower = path.to_string_lossy().to_lowercase();
+    exclusions.iter().any(|e| !e.is_empty() && lower.starts_with(e.as_str()))
+}
+
+// ============================================================================
+// YARA
+// ============================================================================
+
+/// (yara binary, rule files). None nếu không có rule hoặc không có yara.
+fn locate_yara() -> Option<(PathBuf, Vec<PathBuf>)> {
+    let dir = data_dir().join(YARA_DIR);
+    let mut rules: Vec<PathBuf> = fs::read_dir(&dir).ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension()
+                .map(|e| e.eq_ignore_ascii_case("yar") || e.eq_ignore_ascii_case("yara"))
+                .unwrap_or(false)
+        })
+        .collect();
+    if rules.is_empty() {
+        return None;
+    }
+    rules.sort();
+
+    let bundled = dir.join(if cfg!(windows) { "yara64.exe" } else { "yara" });
+    let binary = if bundled.exists() { bundled } else { PathBuf::from("yara") };
+    let available = Command::new(&binary).arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+    if !available {
+        log::warn!("YARA rules found in {:?} but yara binary is not available", dir);
+        return None;
+    }
+    Some((binary, rules))
+}
+
+/// `yara -r rules... root`, kill nếu scan bị cancel
+fn run_yara(binary: &Path, rules: &[PathBuf], root: &Path) -> HashMap<String, Vec<String>> {
+    let child = Command::new(binary)
+        .arg("-r")
+        .args(rules)
+        .arg(root)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            log::warn!("Failed to start yara: {}", e);
+            return HashMap::new();
+        }
+    };
+
+    // Đọc stdout ở thread riêng để pipe không đầy
+    let stdout = child.stdout.take();
+    let reader = thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(mut stdout) = stdout {
+            let _ = stdout.read_to_string(&mut output);
+        }
+        output
+    });
+
+    loop {
+        if CANCEL.load(Ordering::SeqCst) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return HashMap::new();
+        }
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) => thread::sleep(Duration::from_millis(200)),
+            Err(_) => break,
+        }
+    }
+
+    parse_yara_output(&reader.join().unwrap_or_default())
+}
+
+/// "RuleName C:\path\file.exe" → path (lowercase) -> rules
+pub fn parse_yara_output(output: &str) -> HashMap<String, Vec<String>> {
+    let mut hits: HashMap<String, Vec<String>> = HashMap::new();
+    for line in output.lines() {
+        if let Some((rule, path)) = line.trim().split_once(' ') {
+            if rule.is_empty() || path.is_empty() {
+                continue;
+            }
+            hits.entry(path.trim().to_lowercase()).or_default().push(rule.to_string());
+        }
+    }
+    hits
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+}
+
+fn load_config() -> FullScanConfig {
+    fs::read_to_string(data_dir().join(CONFIG_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_config() {
+    let _ = fs::create_dir_all(data_dir());
+    if let Ok(json) = serde_json::to_string_pretty(&*CONFIG.read()) {
+        let _ = fs::write(data_dir().join(CONFIG_FILE), json);
+    }
+}
+
+fn load_history() -> Vec<FullScanReport> {
+    fs::read_to_string(data_dir().join(HISTORY_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_history() {
+    let _ = fs::create_dir_all(data_dir());
+    if let Ok(json) = serde_json::to_string_pretty(&*HISTORY.read()) {
+        let _ = fs::write(data_dir().join(HISTORY_FILE), json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_yara_output() {
+        let output = "Mimikatz_Strings C:\\Users\\bob\\Downloads\\m.exe\n\
+                      CobaltStrike_Beacon C:\\Users\\bob\\Downloads\\m.exe\n\
+                      Generic_Dropper C:\\Program Files\\App\\setup.exe\n";
+        let hits = parse_yara_output(output);
+
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits["c:\\users\\bob\\downloads\\m.exe"].len(), 2);
+        assert_eq!(hits["c:\\program files\\app\\setup.exe"], vec!["Generic_Dropper".to_string()]);
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let exclusions = vec!["c:\\users\\bob\\appdata\\local\\oneshield".to_string()];
+        assert!(is_excluded(Path::new("C:\\Users\\Bob\\AppData\\Local\\OneShield\\quarantine\\x.bin"), &exclusions));
+        assert!(!is_excluded(Path::new("C:\\Users\\Bob\\Downloads\\x.bin"), &exclusions));
+    }
+
+    #[test]
+    fn test_scan_clean_file() {
+        let path = std::env::temp_dir().join(format!("oneshield_scan_{}.txt", uuid::Uuid::new_v4()));
+        fs::write(&path, b"hello world").unwrap();
+
+        let finding = scan_file(&path, 11, 1024, false, Vec::new()).unwrap();
+        assert!(finding.is_none());
+
+        let finding = scan_file(&path, 11, 1024, false, vec!["Test_Rule".to_string()]).unwrap().unwrap();
+        assert_eq!(finding.severity, "high");
+        assert_eq!(finding.engines, vec!["yara".to_string()]);
+        assert_eq!(finding.sha256, "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9");
+
+        let _ = fs::remove_file(&path);
+    }
+}
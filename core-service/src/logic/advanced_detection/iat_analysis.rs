@@ -6,23 +6,46 @@
 //! - Evasion combo (Nt* undocumented APIs)
 //! - Keylogger combo (GetAsyncKeyState + SetWindowsHookEx)
 //!
+//! Import profile của mỗi file được lưu theo SHA256 (`iat_profiles.json`). Khi cùng
+//! 1 path đổi hash và import set khác lần trước (binary bị patch, thêm injection
+//! stub) → tamper alert kèm import diff.
+//!
 //! MITRE ATT&CK: Multiple techniques
 
 #![allow(dead_code)]
 
-use std::collections::HashSet;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 use parking_lot::RwLock;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+
+use crate::logic::incident::{self, Severity};
+use super::pe_static;
+
+const PROFILES_FILE: &str = "iat_profiles.json";
+const MAX_PROFILES: usize = 5000;
+const MAX_TAMPER_ALERTS: usize = 200;
+/// Compromise Host Software Binary
+const TAMPER_MITRE_ID: &str = "T1554";
 
 // ============================================================================
 // GLOBAL STATE
 // ============================================================================
 
-/// Analysis results cache
-static CACHE: Lazy<RwLock<std::collections::HashMap<String, IatAnalysisResult>>> =
-    Lazy::new(|| RwLock::new(std::collections::HashMap::new()));
+/// Analysis results cache (sha256 → result)
+static CACHE: Lazy<RwLock<HashMap<String, IatAnalysisResult>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Import profiles đã lưu
+static PROFILES: Lazy<RwLock<ProfileStore>> =
+    Lazy::new(|| RwLock::new(load_profiles()));
+
+/// Tamper alerts gần nhất
+static TAMPER_ALERTS: Lazy<RwLock<Vec<IatTamperAlert>>> =
+    Lazy::new(|| RwLock::new(Vec::new()));
 
 /// Module statistics
 static STATS: Lazy<RwLock<IatStats>> =
@@ -243,6 +266,55 @@ pub struct IatAnalysisResult {
     pub is_suspicious: bool,
     pub max_severity: u8,
     pub timestamp: i64,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Import thay đổi so với lần phân tích trước của cùng path
+    #[serde(default)]
+    pub import_diff: Option<ImportDiff>,
+}
+
+/// Import profile của 1 file (theo hash)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IatProfile {
+    pub sha256: String,
+    pub path: String,
+    /// `dll!function` (lowercase, sorted) - hoặc tên API heuristic nếu không parse được import table
+    pub imports: Vec<String>,
+    /// Combo đã match
+    pub combos: Vec<String>,
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ProfileStore {
+    profiles: HashMap<String, IatProfile>,
+    /// path (lowercase) → sha256 lần phân tích gần nhất
+    paths: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl ImportDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Cùng path, hash mới, import set khác
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IatTamperAlert {
+    pub path: String,
+    pub previous_sha256: String,
+    pub current_sha256: String,
+    pub diff: ImportDiff,
+    /// Combo mới xuất hiện so với bản trước
+    pub new_combos: Vec<String>,
+    pub timestamp: i64,
 }
 
 /// Module statistics
@@ -253,6 +325,8 @@ pub struct IatStats {
     pub critical_count: u64,
     pub cache_hits: u64,
     pub last_scan: i64,
+    #[serde(default)]
+    pub tamper_count: u64,
 }
 
 // ============================================================================
@@ -304,33 +378,44 @@ pub fn analyze_imports(imports: &[String]) -> Vec<IatAlert> {
 pub fn analyze_file(path: &Path) -> Result<IatAnalysisResult, IatError> {
     let path_str = path.to_string_lossy().to_string();
 
-    // Check cache first
-    {
-        let cache = CACHE.read();
-        if let Some(cached) = cache.get(&path_str) {
-            let mut stats = STATS.write();
-            stats.cache_hits += 1;
-            return Ok(cached.clone());
-        }
-    }
-
     // Read file
     let data = std::fs::read(path).map_err(|e| IatError::IoError(e.to_string()))?;
+    let sha256 = crate::logic::process_intel::hashing::hash_loaded(path, &data).sha256;
+
+    // Check cache first (theo hash → file bị sửa không dùng lại kết quả cũ)
+    let cached = CACHE.read().get(&sha256).cloned();
+    let mut result = match cached {
+        Some(cached) => {
+            STATS.write().cache_hits += 1;
+            cached
+        }
+        None => analyze_uncached(&data, &path_str, &sha256)?,
+    };
 
+    result.file_path = path_str.clone();
+    result.sha256 = Some(sha256.clone());
+    result.import_diff = record_profile(&path_str, &sha256, &data, &result.alerts);
+
+    Ok(result)
+}
+
+fn analyze_uncached(data: &[u8], path_str: &str, sha256: &str) -> Result<IatAnalysisResult, IatError> {
     // Parse imports
-    let imports = parse_pe_imports(&data)?;
+    let imports = parse_pe_imports(data)?;
 
     // Analyze
     let alerts = analyze_imports(&imports);
     let max_severity = alerts.iter().map(|a| a.severity).max().unwrap_or(0);
 
     let result = IatAnalysisResult {
-        file_path: path_str.clone(),
+        file_path: path_str.to_string(),
         total_imports: imports.len(),
         alerts: alerts.clone(),
         is_suspicious: !alerts.is_empty(),
         max_severity,
         timestamp: chrono::Utc::now().timestamp(),
+        sha256: Some(sha256.to_string()),
+        import_diff: None,
     };
 
     // Update cache
@@ -340,7 +425,7 @@ pub fn analyze_file(path: &Path) -> Result<IatAnalysisResult, IatError> {
         if cache.len() > 1000 {
             cache.clear();
         }
-        cache.insert(path_str, result.clone());
+        cache.insert(sha256.to_string(), result.clone());
     }
 
     // Update stats
@@ -372,6 +457,8 @@ pub fn analyze_binary(data: &[u8], source_name: &str) -> Result<IatAnalysisResul
         is_suspicious: max_severity > 0,
         max_severity,
         timestamp: chrono::Utc::now().timestamp(),
+        sha256: None,
+        import_diff: None,
     })
 }
 
@@ -385,6 +472,208 @@ pub fn clear_cache() {
     CACHE.write().clear();
 }
 
+/// Profile đã lưu của path (bản phân tích gần nhất)
+pub fn get_profile(path: &Path) -> Option<IatProfile> {
+    let store = PROFILES.read();
+    let sha256 = store.paths.get(&path.to_string_lossy().to_lowercase())?;
+    store.profiles.get(sha256).cloned()
+}
+
+/// Tamper alerts gần nhất
+pub fn get_tamper_alerts(limit: usize) -> Vec<IatTamperAlert> {
+    TAMPER_ALERTS.read().iter().rev().take(limit).cloned().collect()
+}
+
+// ============================================================================
+// PROFILES & TAMPER DETECTION
+// ============================================================================
+
+/// Lưu import profile của file đã đọc sẵn (pe_static, full scan, ...)
+pub fn track_profile(path_str: &str, data: &[u8], alerts: &[IatAlert]) -> Option<ImportDiff> {
+    let sha256 = hex::encode(Sha256::digest(data));
+    record_profile(path_str, &sha256, data, alerts)
+}
+
+/// Lưu profile theo hash; path đổi hash + import khác → tamper alert. Trả về diff nếu có.
+fn record_profile(path_str: &str, sha256: &str, data: &[u8], alerts: &[IatAlert]) -> Option<ImportDiff> {
+    let now = chrono::Utc::now().timestamp();
+    let path_key = path_str.to_lowercase();
+
+    let (previous, current) = {
+        let mut store = PROFILES.write();
+        let previous_sha = store.paths.get(&path_key).cloned();
+        if previous_sha.as_deref() == Some(sha256) {
+            if let Some(profile) = store.profiles.get_mut(sha256) {
+                profile.last_seen = now;
+                return None;
+            }
+        }
+
+        let current = store.profiles.entry(sha256.to_string())
+            .or_insert_with(|| IatProfile {
+                sha256: sha256.to_string(),
+                path: path_str.to_string(),
+                imports: profile_imports(data),
+                combos: alerts.iter().map(|a| a.combo_name.clone()).collect(),
+                first_seen: now,
+                last_seen: now,
+            });
+        current.last_seen = now;
+        let current = current.clone();
+        store.paths.insert(path_key, sha256.to_string());
+        let previous = previous_sha.and_then(|sha| store.profiles.get(&sha).cloned());
+
+        prune_profiles(&mut store);
+        (previous, current)
+    };
+    save_profiles();
+
+    let previous = previous?;
+    let diff = diff_imports(&previous.imports, &current.imports);
+    if diff.is_empty() {
+        return None;
+    }
+
+    let alert = IatTamperAlert {
+        path: path_str.to_string(),
+        previous_sha256: previous.sha256.clone(),
+        current_sha256: current.sha256.clone(),
+        new_combos: current.combos.iter()
+            .filter(|c| !previous.combos.contains(c))
+            .cloned()
+            .collect(),
+        diff: diff.clone(),
+        timestamp: now,
+    };
+    raise_tamper_incident(&alert);
+    STATS.write().tamper_count += 1;
+
+    let mut history = TAMPER_ALERTS.write();
+    history.push(alert);
+    let overflow = history.len().saturating_sub(MAX_TAMPER_ALERTS);
+    history.drain(..overflow);
+
+    Some(diff)
+}
+
+/// Import dùng cho profile: import table thật nếu parse được, không thì heuristic
+fn profile_imports(data: &[u8]) -> Vec<String> {
+    let mut imports: Vec<String> = match pe_static::parse_imports(data) {
+        Some(table) if !table.is_empty() => table.iter()
+            .map(|(dll, func)| format!("{}!{}", dll.to_lowercase(), func.to_lowercase()))
+            .collect(),
+        _ => extract_api_strings(data).iter().map(|s| s.to_lowercase()).collect(),
+    };
+    imports.sort();
+    imports.dedup();
+    imports
+}
+
+/// Diff 2 import list đã sort
+pub fn diff_imports(previous: &[String], current: &[String]) -> ImportDiff {
+    let prev: HashSet<&String> = previous.iter().collect();
+    let curr: HashSet<&String> = current.iter().collect();
+    ImportDiff {
+        added: current.iter().filter(|i| !prev.contains(i)).cloned().collect(),
+        removed: previous.iter().filter(|i| !curr.contains(i)).cloned().collect(),
+    }
+}
+
+/// Import mới có nằm trong combo nguy hiểm không
+fn is_suspicious_addition(import: &str) -> bool {
+    // Profile đã lowercase → bỏ hậu tố a/w thủ công
+    let func = import.rsplit('!').next().unwrap_or(import).to_lowercase();
+    SUSPICIOUS_COMBOS.iter()
+        .flat_map(|c| c.apis.iter())
+        .map(|api| normalize_api_name(api))
+        .any(|api| func == api || func.strip_suffix(&['a', 'w'][..]) == Some(api.as_str()))
+}
+
+fn raise_tamper_incident(alert: &IatTamperAlert) {
+    let suspicious: Vec<&String> = alert.diff.added.iter()
+        .filter(|i| is_suspicious_addition(i))
+        .collect();
+    let severity = if !alert.new_combos.is_empty() {
+        Severity::Critical
+    } else if !suspicious.is_empty() {
+        Severity::High
+    } else {
+        Severity::Medium
+    };
+
+    let mut description = format!(
+        "Import table of {} changed ({} → {}): +{} / -{} imports",
+        alert.path,
+        &alert.previous_sha256[..alert.previous_sha256.len().min(12)],
+        &alert.current_sha256[..alert.current_sha256.len().min(12)],
+        alert.diff.added.len(),
+        alert.diff.removed.len()
+    );
+    if !alert.diff.added.is_empty() {
+        let shown: Vec<&str> = alert.diff.added.iter().take(15).map(|s| s.as_str()).collect();
+        description.push_str(&format!("\nAdded: {}", shown.join(", ")));
+    }
+    if !alert.diff.removed.is_empty() {
+        let shown: Vec<&str> = alert.diff.removed.iter().take(15).map(|s| s.as_str()).collect();
+        description.push_str(&format!("\nRemoved: {}", shown.join(", ")));
+    }
+    if !alert.new_combos.is_empty() {
+        description.push_str(&format!("\nNew suspicious combos: {}", alert.new_combos.join(", ")));
+    }
+
+    let file_name = Path::new(&alert.path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| alert.path.clone());
+    incident::raise_detection(
+        &format!("Binary tampering: import table changed ({})", file_name),
+        severity,
+        &["IAT_TAMPER".to_string(), "TAMPERING".to_string()],
+        &[TAMPER_MITRE_ID],
+        &description,
+    );
+}
+
+fn prune_profiles(store: &mut ProfileStore) {
+    if store.profiles.len() <= MAX_PROFILES {
+        return;
+    }
+    let mut by_age: Vec<(String, i64)> = store.profiles.iter()
+        .map(|(sha, p)| (sha.clone(), p.last_seen))
+        .collect();
+    by_age.sort_by_key(|(_, seen)| *seen);
+    let excess = store.profiles.len() - MAX_PROFILES;
+    for (sha, _) in by_age.into_iter().take(excess) {
+        store.profiles.remove(&sha);
+    }
+    let ProfileStore { profiles, paths } = store;
+    paths.retain(|_, sha| profiles.contains_key(sha));
+}
+
+fn profiles_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(PROFILES_FILE)
+}
+
+fn load_profiles() -> ProfileStore {
+    fs::read_to_string(profiles_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_profiles() {
+    let path = profiles_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string(&*PROFILES.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
 // ============================================================================
 // PE PARSING
 // ============================================================================
@@ -620,4 +909,24 @@ mod tests {
         assert!(!alerts.is_empty());
         assert!(alerts.iter().any(|a| a.mitre_id == "T1003.001"));
     }
+
+    #[test]
+    fn test_import_diff() {
+        let previous = vec![
+            "kernel32.dll!closehandle".to_string(),
+            "kernel32.dll!createfilew".to_string(),
+        ];
+        let current = vec![
+            "kernel32.dll!closehandle".to_string(),
+            "kernel32.dll!virtualallocex".to_string(),
+            "kernel32.dll!writeprocessmemory".to_string(),
+        ];
+
+        let diff = diff_imports(&previous, &current);
+        assert_eq!(diff.added, vec!["kernel32.dll!virtualallocex", "kernel32.dll!writeprocessmemory"]);
+        assert_eq!(diff.removed, vec!["kernel32.dll!createfilew"]);
+        assert!(is_suspicious_addition(&diff.added[0]));
+        assert!(!is_suspicious_addition("kernel32.dll!closehandle"));
+        assert!(diff_imports(&current, &current).is_empty());
+    }
 }
@@ -306,18 +306,24 @@ impl InjectionDetector {
             ));
         }
 
+        self.record_alerts(&alerts);
+        alerts
+    }
+
+    /// Update stats + lưu alert vào history (dùng chung cho detector khác, vd: sideload)
+    pub fn record_alerts(&mut self, alerts: &[InjectionAlert]) {
         // Update stats
         update_stats(|s| {
             s.total_checks += 1;
             s.alerts_count += alerts.len() as u64;
             s.critical_count += alerts.iter().filter(|a| a.is_critical()).count() as u64;
-            for alert in &alerts {
+            for alert in alerts {
                 *s.by_type.entry(alert.injection_type.as_str().to_string()).or_default() += 1;
             }
         });
 
         // Store alerts
-        for alert in &alerts {
+        for alert in alerts {
             self.alerts.push(alert.clone());
             if self.alerts.len() > self.max_alerts {
                 self.alerts.remove(0);
@@ -329,8 +335,6 @@ impl InjectionDetector {
                 alert.injection_type.as_str(), alert.confidence
             );
         }
-
-        alerts
     }
 
     /// Classify injection type based on patterns found
@@ -428,6 +432,13 @@ pub fn is_suspicious(
     !analyze_process(pid, name, cmdline, parent_pid, parent_name).is_empty()
 }
 
+/// Ghi alert từ detector bên ngoài (sideload) vào history chung
+pub fn record_alerts(alerts: &[InjectionAlert]) {
+    if !alerts.is_empty() {
+        DETECTOR.lock().record_alerts(alerts);
+    }
+}
+
 /// Get recent injection alerts
 pub fn get_recent_alerts(limit: usize) -> Vec<InjectionAlert> {
     DETECTOR.lock().get_recent_alerts(limit)
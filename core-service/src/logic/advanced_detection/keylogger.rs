@@ -6,11 +6,14 @@
 //! - Window tracking for focus logging
 //! - Suspicious log file patterns
 //!
-//! MITRE ATT&CK: T1056.001 - Keylogging
+//! Surveillance (khác keylogging thuần): screenshot burst (BitBlt / PrintWindow)
+//! hoặc microphone đang dùng + network egress → `SurveillanceAlert`
+//!
+//! MITRE ATT&CK: T1056.001 - Keylogging, T1113 - Screen Capture, T1123 - Audio Capture
 
 #![allow(dead_code)]
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use parking_lot::RwLock;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
@@ -27,6 +30,14 @@ static API_TRACKER: Lazy<RwLock<HashMap<u32, ApiCallStats>>> =
 static ALERTS: Lazy<RwLock<Vec<KeyloggerAlert>>> =
     Lazy::new(|| RwLock::new(Vec::new()));
 
+/// Surveillance alerts history
+static SURVEILLANCE_ALERTS: Lazy<RwLock<Vec<SurveillanceAlert>>> =
+    Lazy::new(|| RwLock::new(Vec::new()));
+
+/// (pid, capabilities) đã báo surveillance
+static SURVEILLANCE_REPORTED: Lazy<RwLock<HashSet<(u32, String)>>> =
+    Lazy::new(|| RwLock::new(HashSet::new()));
+
 /// Detection statistics
 static STATS: Lazy<RwLock<KeyloggerStats>> =
     Lazy::new(|| RwLock::new(KeyloggerStats::default()));
@@ -47,6 +58,10 @@ pub enum SuspiciousApi {
     GetForegroundWindow,
     GetWindowText,
     RegisterRawInputDevices,
+    // Surveillance
+    BitBlt,
+    PrintWindow,
+    MicrophoneAccess,
 }
 
 impl SuspiciousApi {
@@ -61,6 +76,9 @@ impl SuspiciousApi {
             Self::GetForegroundWindow => "GetForegroundWindow",
             Self::GetWindowText => "GetWindowText",
             Self::RegisterRawInputDevices => "RegisterRawInputDevices",
+            Self::BitBlt => "BitBlt",
+            Self::PrintWindow => "PrintWindow",
+            Self::MicrophoneAccess => "MicrophoneAccess",
         }
     }
 
@@ -75,6 +93,9 @@ impl SuspiciousApi {
             Self::GetForegroundWindow => 50,
             Self::GetWindowText => 40,
             Self::GetKeyState => 60,
+            Self::BitBlt => 50,
+            Self::PrintWindow => 60,
+            Self::MicrophoneAccess => 70,
         }
     }
 
@@ -92,6 +113,9 @@ impl SuspiciousApi {
             "getwindowtextw" | "getwindowtexta" | "getwindowtext" =>
                 Some(Self::GetWindowText),
             "registerrawinputdevices" => Some(Self::RegisterRawInputDevices),
+            "bitblt" | "stretchblt" => Some(Self::BitBlt),
+            "printwindow" => Some(Self::PrintWindow),
+            "waveinopen" | "microphoneaccess" => Some(Self::MicrophoneAccess),
             _ => None,
         }
     }
@@ -109,6 +133,8 @@ pub struct ApiCallStats {
     pub clipboard_access: u32,
     pub window_tracking: u32,
     pub raw_input: u32,
+    pub screen_capture: u32,
+    pub microphone_access: u32,
     pub last_reset: i64,
     pub total_calls: u32,
 }
@@ -132,6 +158,19 @@ impl KeyloggerAlert {
     }
 }
 
+/// Surveillance alert: capture (screen / mic / keystrokes) + network egress
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SurveillanceAlert {
+    pub pid: u32,
+    pub process_name: String,
+    /// "screen", "microphone", "keystrokes"
+    pub capabilities: Vec<String>,
+    pub indicators: Vec<String>,
+    pub confidence: u8,
+    pub mitre_ids: Vec<String>,
+    pub timestamp: i64,
+}
+
 /// Module statistics
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct KeyloggerStats {
@@ -152,6 +191,7 @@ pub struct Thresholds {
     pub keyboard_dump: u32,         // GetKeyboardState
     pub clipboard_access: u32,
     pub window_tracking: u32,
+    pub screen_capture: u32,        // BitBlt / PrintWindow
     pub combined_score: u32,        // Minimum score to trigger alert
 }
 
@@ -162,6 +202,7 @@ impl Default for Thresholds {
             keyboard_dump: 50,       // > 50 calls/min = suspicious
             clipboard_access: 10,    // > 10 accesses/min = suspicious
             window_tracking: 30,     // > 30 calls/min = suspicious
+            screen_capture: 20,      // > 20 captures/min = burst
             combined_score: 50,      // Need 50+ points to alert
         }
     }
@@ -202,6 +243,8 @@ pub fn record_api_call(pid: u32, process_name: &str, api: SuspiciousApi) {
         SuspiciousApi::GetForegroundWindow | SuspiciousApi::GetWindowText =>
             stats.window_tracking += 1,
         SuspiciousApi::RegisterRawInputDevices => stats.raw_input += 1,
+        SuspiciousApi::BitBlt | SuspiciousApi::PrintWindow => stats.screen_capture += 1,
+        SuspiciousApi::MicrophoneAccess => stats.microphone_access += 1,
     }
 
     stats.total_calls += 1;
@@ -416,6 +459,209 @@ pub fn get_stats() -> KeyloggerStats {
     STATS.read().clone()
 }
 
+// ============================================================================
+// SURVEILLANCE CORRELATION
+// ============================================================================
+
+/// App họp / stream / chụp màn hình: dùng mic và screen capture hợp lệ
+const CAPTURE_APPS: &[&str] = &[
+    "teams.exe", "ms-teams.exe", "zoom.exe", "discord.exe", "skype.exe", "slack.exe",
+    "webex.exe", "obs64.exe", "obs32.exe", "snippingtool.exe", "screenclippinghost.exe",
+    "sharex.exe", "chrome.exe", "msedge.exe", "firefox.exe",
+];
+
+/// Đánh giá surveillance từ runtime stats. `egress` = process có kết nối ra ngoài.
+/// Trả về (capabilities, indicators, confidence).
+pub fn evaluate_surveillance(
+    stats: &ApiCallStats,
+    egress: bool,
+    now: i64,
+) -> Option<(Vec<String>, Vec<String>, u8)> {
+    if !egress {
+        return None;
+    }
+    let thresholds = Thresholds::default();
+    let age_seconds = (now - stats.last_reset).max(1);
+    let per_minute = |count: u32| (count as f64 / age_seconds as f64 * 60.0) as u32;
+
+    let mut capabilities = Vec::new();
+    let mut indicators = Vec::new();
+
+    let screen_rate = per_minute(stats.screen_capture);
+    if screen_rate > thresholds.screen_capture {
+        capabilities.push("screen".to_string());
+        indicators.push(format!("Screen capture burst: {}/min", screen_rate));
+    }
+    if stats.microphone_access > 0 {
+        capabilities.push("microphone".to_string());
+        indicators.push("Microphone in use".to_string());
+    }
+    if capabilities.is_empty() {
+        return None;
+    }
+
+    if per_minute(stats.get_async_key_state) > thresholds.keyboard_polling
+        || stats.set_windows_hook > 0
+        || stats.raw_input > 0
+    {
+        capabilities.push("keystrokes".to_string());
+        indicators.push("Keystroke capture".to_string());
+    }
+    indicators.push("Network egress while capturing".to_string());
+
+    let confidence = (50 + 15 * capabilities.len() as u32).min(95) as u8;
+    Some((capabilities, indicators, confidence))
+}
+
+/// Poll microphone usage + correlate capture với network egress
+pub fn check_surveillance(networked: &HashSet<u32>) -> Vec<SurveillanceAlert> {
+    for (pid, name) in platform::microphone_users() {
+        record_api_call(pid, &name, SuspiciousApi::MicrophoneAccess);
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let candidates: Vec<_> = API_TRACKER.read().iter()
+        .filter(|(_, stats)| !CAPTURE_APPS.contains(&stats.process_name.to_lowercase().as_str()))
+        .filter_map(|(pid, stats)| {
+            evaluate_surveillance(stats, networked.contains(pid), now)
+                .map(|result| (*pid, stats.process_name.clone(), result))
+        })
+        .collect();
+
+    let mut alerts = Vec::new();
+    for (pid, process_name, (capabilities, indicators, confidence)) in candidates {
+        if crate::logic::action_guard::is_process_whitelisted(Some(pid), &process_name)
+            || !SURVEILLANCE_REPORTED.write().insert((pid, capabilities.join("+")))
+        {
+            continue;
+        }
+
+        let mut mitre_ids: Vec<String> = capabilities.iter()
+            .map(|cap| match cap.as_str() {
+                "screen" => "T1113",
+                "microphone" => "T1123",
+                _ => "T1056.001",
+            }.to_string())
+            .collect();
+        mitre_ids.push("T1041".to_string());
+
+        let alert = SurveillanceAlert {
+            pid,
+            process_name,
+            capabilities,
+            indicators,
+            confidence,
+            mitre_ids,
+            timestamp: now,
+        };
+
+        let mitre: Vec<&str> = alert.mitre_ids.iter().map(|m| m.as_str()).collect();
+        crate::logic::incident::raise_process_detection(
+            &format!("Surveillance: {} capturing {}", alert.process_name, alert.capabilities.join(" + ")),
+            crate::logic::incident::Severity::High,
+            &["SURVEILLANCE".to_string(), "COLLECTION".to_string()],
+            &mitre,
+            &format!("{} (PID {}): {}", alert.process_name, alert.pid, alert.indicators.join("; ")),
+            crate::logic::incident::DetectionContext {
+                pid: Some(alert.pid),
+                rule_matches: alert.indicators.clone(),
+            },
+        );
+        alerts.push(alert);
+    }
+
+    if !alerts.is_empty() {
+        let mut history = SURVEILLANCE_ALERTS.write();
+        history.extend(alerts.iter().cloned());
+        if history.len() > 100 {
+            let excess = history.len() - 100;
+            history.drain(..excess);
+        }
+    }
+
+    // Bỏ dedup của process đã bị cleanup
+    let tracked: HashSet<u32> = API_TRACKER.read().keys().copied().collect();
+    SURVEILLANCE_REPORTED.write().retain(|(pid, _)| tracked.contains(pid));
+
+    alerts
+}
+
+/// Get recent surveillance alerts
+pub fn get_surveillance_alerts(limit: usize) -> Vec<SurveillanceAlert> {
+    let alerts = SURVEILLANCE_ALERTS.read();
+    alerts.iter().rev().take(limit).cloned().collect()
+}
+
+/// Parse `reg query ...\ConsentStore\microphone\NonPackaged /s`
+/// → exe path đang dùng mic (LastUsedTimeStop = 0)
+pub fn parse_microphone_usage(output: &str) -> Vec<String> {
+    let mut in_use = Vec::new();
+    let mut current: Option<String> = None;
+
+    for line in output.lines() {
+        if line.starts_with("HKEY_") {
+            // ...\NonPackaged\C:#Users#bob#app.exe
+            current = line.rsplit('\\').next()
+                .filter(|leaf| leaf.contains('#'))
+                .map(|leaf| leaf.replace('#', "\\"));
+            continue;
+        }
+
+        let mut cols = line.split_whitespace();
+        if let (Some("LastUsedTimeStop"), Some(_), Some(value)) = (cols.next(), cols.next(), cols.next()) {
+            if let Some(path) = &current {
+                if u64::from_str_radix(value.trim_start_matches("0x"), 16) == Ok(0) {
+                    in_use.push(path.clone());
+                }
+            }
+        }
+    }
+
+    in_use
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use std::process::Command;
+
+    const MIC_CONSENT_KEY: &str =
+        r"HKCU\Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\microphone\NonPackaged";
+
+    /// (pid, name) của process đang dùng microphone
+    pub fn microphone_users() -> Vec<(u32, String)> {
+        let output = match Command::new("reg").args(["query", MIC_CONSENT_KEY, "/s"]).output() {
+            Ok(o) if o.status.success() => o,
+            _ => return Vec::new(),
+        };
+        let paths: HashSet<String> = parse_microphone_usage(&String::from_utf8_lossy(&output.stdout))
+            .into_iter()
+            .map(|p| p.to_lowercase())
+            .collect();
+        if paths.is_empty() {
+            return Vec::new();
+        }
+
+        let mut system = sysinfo::System::new();
+        system.refresh_processes();
+        system.processes().iter()
+            .filter(|(_, p)| {
+                p.exe()
+                    .map(|exe| paths.contains(&exe.to_string_lossy().to_lowercase()))
+                    .unwrap_or(false)
+            })
+            .map(|(pid, p)| (pid.as_u32(), p.name().to_string()))
+            .collect()
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    pub fn microphone_users() -> Vec<(u32, String)> {
+        Vec::new()
+    }
+}
+
 // ============================================================================
 // HELPERS
 // ============================================================================
@@ -504,6 +750,42 @@ mod tests {
         assert!(alert.is_some());
     }
 
+    #[test]
+    fn test_surveillance_requires_egress() {
+        let now = chrono::Utc::now().timestamp();
+        let stats = ApiCallStats {
+            pid: 4321,
+            process_name: "update.exe".to_string(),
+            screen_capture: 60,
+            microphone_access: 1,
+            last_reset: now - 60,
+            ..Default::default()
+        };
+
+        assert!(evaluate_surveillance(&stats, false, now).is_none());
+
+        let (caps, _, confidence) = evaluate_surveillance(&stats, true, now).unwrap();
+        assert_eq!(caps, vec!["screen".to_string(), "microphone".to_string()]);
+        assert!(confidence >= 80);
+    }
+
+    #[test]
+    fn test_parse_microphone_usage() {
+        let output = r"
+HKEY_CURRENT_USER\Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\microphone\NonPackaged\C:#Users#bob#AppData#svc.exe
+    LastUsedTimeStart    REG_QWORD    0x1da1c2b3d4e5f60
+    LastUsedTimeStop    REG_QWORD    0x0
+
+HKEY_CURRENT_USER\Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\microphone\NonPackaged\C:#Program Files#Zoom#Zoom.exe
+    LastUsedTimeStart    REG_QWORD    0x1da1c2b3d4e5f60
+    LastUsedTimeStop    REG_QWORD    0x1da1c2b3d4e6000
+";
+        assert_eq!(
+            parse_microphone_usage(output),
+            vec![r"C:\Users\bob\AppData\svc.exe".to_string()]
+        );
+    }
+
     #[test]
     fn test_record_api_call() {
         let pid = 11111;
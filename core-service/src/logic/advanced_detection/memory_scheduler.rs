@@ -0,0 +1,600 @@
+//! Memory Scan Scheduler - Quét bộ nhớ nền cho process rủi ro cao
+//!
+//! Mục đích: `scan_memory` chỉ chạy on-demand; module này tự chọn process đáng
+//! ngờ và quét private memory định kỳ, có rate limit.
+//!
+//! Candidate: unsigned / chữ ký lỗi, có kết nối mạng ra ngoài, là target của
+//! injection alert gần đây, hoặc static PE risk cao.
+//!
+//! Mỗi cycle:
+//! 1. Sample protection của private region (rẻ - chỉ VirtualQueryEx) cho mọi candidate
+//!    → phát hiện region đổi qua lại executable ↔ RW/NOACCESS (sleep mask: Ekko, Foliage, ...)
+//! 2. Đọc nội dung cho tối đa `MAX_SCANS_PER_CYCLE` process (mỗi process tối đa
+//!    1 lần / `RESCAN_INTERVAL_SECS`): shellcode patterns (`memory.rs`), RWX region,
+//!    private RW region lớn có entropy cao (beacon đã mã hóa khi sleep)
+
+use std::collections::{HashMap, HashSet};
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::{injection, memory, pe_static, shellcode_emu};
+use super::shellcode_emu::EmulationReport;
+use crate::logic::incident::{self, DetectionContext, Severity};
+use crate::logic::process_intel::signature;
+use crate::logic::process_intel::types::SignatureStatus;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const MAX_CANDIDATES: usize = 32;
+const MAX_SCANS_PER_CYCLE: usize = 2;
+const RESCAN_INTERVAL_SECS: i64 = 300;
+const MAX_BYTES_PER_PROCESS: usize = 64 * 1024 * 1024;
+const MAX_REGION_BYTES: usize = 16 * 1024 * 1024;
+
+/// Injection alert còn hiệu lực để chọn candidate
+const INJECTION_LOOKBACK_SECS: i64 = 600;
+
+/// Private RW region ≥ kích thước này được kiểm tra entropy
+const ENCRYPTED_REGION_MIN: usize = 256 * 1024;
+const ENCRYPTED_SAMPLE: usize = 64 * 1024;
+const ENCRYPTED_ENTROPY: f64 = 7.5;
+
+const MAX_ALERTS: usize = 500;
+
+const PAGE_NOACCESS: u32 = 0x01;
+const PAGE_READWRITE: u32 = 0x04;
+const PAGE_EXECUTE: u32 = 0x10;
+const PAGE_EXECUTE_READ: u32 = 0x20;
+const PAGE_EXECUTE_READWRITE: u32 = 0x40;
+const PAGE_EXECUTE_WRITECOPY: u32 = 0x80;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Committed private region
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub base: usize,
+    pub size: usize,
+    pub protect: u32,
+}
+
+impl Region {
+    pub fn is_executable(&self) -> bool {
+        protect_is_executable(self.protect)
+    }
+
+    pub fn is_rwx(&self) -> bool {
+        matches!(self.protect & 0xFF, PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryScheduleAlert {
+    pub pid: u32,
+    pub process_name: String,
+    /// Lý do process được chọn (unsigned, network, injected, static risk)
+    pub candidate_reasons: Vec<String>,
+    pub indicators: Vec<String>,
+    /// Shellcode pattern khớp
+    pub shellcode: Vec<String>,
+    /// API hash / C2 IOC từ `shellcode_emu` (region đầu tiên bị flag)
+    #[serde(default)]
+    pub emulation: Option<EmulationReport>,
+    pub region_base: Option<String>,
+    pub severity: String,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchedulerStatus {
+    pub cycles: u64,
+    pub processes_scanned: u64,
+    pub bytes_scanned: u64,
+    pub tracked_processes: usize,
+    pub last_cycle: Option<i64>,
+}
+
+struct Candidate {
+    pid: u32,
+    name: String,
+    reasons: Vec<String>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// pid -> (region base -> protection lần sample trước)
+static PROTECTIONS: Lazy<Mutex<HashMap<u32, HashMap<usize, u32>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// pid -> lần quét nội dung gần nhất
+static LAST_SCANNED: Lazy<Mutex<HashMap<u32, i64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// (pid, indicator) đã báo
+static REPORTED: Lazy<Mutex<HashSet<(u32, String)>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+static ALERTS: Lazy<Mutex<Vec<MemoryScheduleAlert>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static STATUS: Lazy<Mutex<SchedulerStatus>> = Lazy::new(|| Mutex::new(SchedulerStatus::default()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// 1 cycle của scheduler (analysis loop gọi định kỳ). Trả về alert mới.
+pub fn run_cycle() -> Vec<MemoryScheduleAlert> {
+    let now = Utc::now().timestamp();
+    let candidates = select_candidates(now);
+    let live: HashSet<u32> = candidates.iter().map(|c| c.pid).collect();
+
+    let mut alerts = Vec::new();
+    let mut scans = 0;
+    for candidate in &candidates {
+        let regions = match platform::query_regions(candidate.pid) {
+            Some(r) => r,
+            None => continue,
+        };
+
+        // 1. Protection flip (rẻ, mọi candidate)
+        let flips = {
+            let mut protections = PROTECTIONS.lock();
+            let previous = protections.entry(candidate.pid).or_default();
+            let flips = protection_flips(previous, &regions);
+            *previous = regions.iter().map(|r| (r.base, r.protect)).collect();
+            flips
+        };
+        let mut indicators: Vec<String> = flips.iter()
+            .map(|(r, before)| format!(
+                "Region {:#x} flipped {} → {} (sleep mask)",
+                r.base, protect_name(*before), protect_name(r.protect)
+            ))
+            .collect();
+        let mut region_base = flips.first().map(|(r, _)| r.base);
+
+        // 2. Quét nội dung (rate limited)
+        let mut shellcode = Vec::new();
+        let mut emulation = None;
+        let due = LAST_SCANNED.lock().get(&candidate.pid).map(|t| now - t >= RESCAN_INTERVAL_SECS).unwrap_or(true);
+        if due && scans < MAX_SCANS_PER_CYCLE {
+            scans += 1;
+            LAST_SCANNED.lock().insert(candidate.pid, now);
+            let (content_indicators, matches, base, emulated) = scan_contents(candidate, &regions);
+            indicators.extend(content_indicators);
+            shellcode = matches;
+            emulation = emulated;
+            region_base = region_base.or(base);
+        }
+
+        if let Some(alert) = report(candidate, indicators, shellcode, emulation, region_base, now) {
+            alerts.push(alert);
+        }
+    }
+
+    // Dọn state của process đã thoát / không còn là candidate
+    PROTECTIONS.lock().retain(|pid, _| live.contains(pid));
+    LAST_SCANNED.lock().retain(|pid, t| live.contains(pid) || now - *t < RESCAN_INTERVAL_SECS);
+    REPORTED.lock().retain(|(pid, _)| live.contains(pid));
+
+    {
+        let mut status = STATUS.lock();
+        status.cycles += 1;
+        status.tracked_processes = candidates.len();
+        status.last_cycle = Some(now);
+    }
+    if !alerts.is_empty() {
+        let mut history = ALERTS.lock();
+        history.extend(alerts.iter().cloned());
+        let overflow = history.len().saturating_sub(MAX_ALERTS);
+        history.drain(..overflow);
+    }
+    alerts
+}
+
+pub fn get_recent_alerts(limit: usize) -> Vec<MemoryScheduleAlert> {
+    ALERTS.lock().iter().rev().take(limit).cloned().collect()
+}
+
+pub fn status() -> SchedulerStatus {
+    STATUS.lock().clone()
+}
+
+/// PID có kết nối mạng ra ngoài (netstat)
+pub fn network_active_pids() -> HashSet<u32> {
+    platform::network_active_pids()
+}
+
+// ============================================================================
+// CANDIDATE SELECTION
+// ============================================================================
+
+fn select_candidates(now: i64) -> Vec<Candidate> {
+    let mut system = sysinfo::System::new();
+    system.refresh_processes();
+
+    let networked = platform::network_active_pids();
+    let injected: HashSet<u32> = injection::get_recent_alerts(200).into_iter()
+        .filter(|a| now - a.timestamp <= INJECTION_LOOKBACK_SECS)
+        .map(|a| a.target_pid)
+        .collect();
+    let own_pid = std::process::id();
+
+    let mut candidates: Vec<Candidate> = system.processes().iter().filter_map(|(pid, process)| {
+        let pid = pid.as_u32();
+        if pid <= 4 || pid == own_pid {
+            return None;
+        }
+        let name = process.name().to_string();
+        if crate::logic::action_guard::is_process_whitelisted(Some(pid), &name) {
+            return None;
+        }
+
+        let mut reasons = Vec::new();
+        if injected.contains(&pid) {
+            reasons.push("injection target".to_string());
+        }
+        if let Some(exe) = process.exe() {
+            if let Some(risk) = pe_static::risk_score(exe).filter(|r| *r >= 0.5) {
+                reasons.push(format!("static risk {:.2}", risk));
+            }
+            // Verify chữ ký tốn kém → chỉ cho process có kết nối ra ngoài (beacon profile)
+            if networked.contains(&pid) {
+                match signature::verify_signature(exe).status {
+                    SignatureStatus::Unsigned => reasons.push("unsigned".to_string()),
+                    SignatureStatus::Invalid { .. } => reasons.push("invalid signature".to_string()),
+                    SignatureStatus::Revoked { .. } => reasons.push("revoked certificate".to_string()),
+                    _ => {}
+                }
+            }
+        }
+        // Network chỉ tính khi đã có yếu tố rủi ro khác (browser, updater đều có network)
+        if !reasons.is_empty() && networked.contains(&pid) {
+            reasons.push("network active".to_string());
+        }
+
+        if reasons.is_empty() {
+            None
+        } else {
+            Some(Candidate { pid, name, reasons })
+        }
+    }).collect();
+
+    candidates.sort_by(|a, b| b.reasons.len().cmp(&a.reasons.len()));
+    candidates.truncate(MAX_CANDIDATES);
+    candidates
+}
+
+// ============================================================================
+// HEURISTICS
+// ============================================================================
+
+fn protect_is_executable(protect: u32) -> bool {
+    matches!(
+        protect & 0xFF,
+        PAGE_EXECUTE | PAGE_EXECUTE_READ | PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY
+    )
+}
+
+fn protect_name(protect: u32) -> &'static str {
+    match protect & 0xFF {
+        PAGE_NOACCESS => "NOACCESS",
+        0x02 => "R",
+        PAGE_READWRITE => "RW",
+        PAGE_EXECUTE => "X",
+        PAGE_EXECUTE_READ => "RX",
+        PAGE_EXECUTE_READWRITE => "RWX",
+        PAGE_EXECUTE_WRITECOPY => "RWX(copy)",
+        _ => "other",
+    }
+}
+
+/// Region (cùng base) đổi giữa executable và non-executable so với lần sample trước.
+/// Trả về (region hiện tại, protection cũ).
+pub fn protection_flips(previous: &HashMap<usize, u32>, current: &[Region]) -> Vec<(Region, u32)> {
+    current.iter()
+        .filter_map(|r| {
+            let before = *previous.get(&r.base)?;
+            let was_exec = protect_is_executable(before);
+            let now_exec = r.is_executable();
+            let masked = |p: u32| matches!(p & 0xFF, PAGE_READWRITE | PAGE_NOACCESS);
+            let flipped = (was_exec && !now_exec && masked(r.protect)) || (!was_exec && now_exec && masked(before));
+            if flipped { Some((*r, before)) } else { None }
+        })
+        .collect()
+}
+
+/// Private region lớn, RW, nội dung gần như ngẫu nhiên (payload đã mã hóa)
+pub fn is_encrypted_heap(region: &Region, sample: &[u8]) -> bool {
+    region.size >= ENCRYPTED_REGION_MIN
+        && region.protect & 0xFF == PAGE_READWRITE
+        && pe_static::entropy(sample) >= ENCRYPTED_ENTROPY
+}
+
+/// Đọc region executable / RW lớn. Trả về (indicators, shellcode matches, region đầu tiên có finding, emulation).
+fn scan_contents(
+    candidate: &Candidate,
+    regions: &[Region],
+) -> (Vec<String>, Vec<String>, Option<usize>, Option<EmulationReport>) {
+    let mut indicators = Vec::new();
+    let mut shellcode = Vec::new();
+    let mut emulation = None;
+    let mut first = None;
+    let mut budget = MAX_BYTES_PER_PROCESS;
+
+    for region in regions {
+        if budget == 0 {
+            break;
+        }
+        if region.is_rwx() {
+            indicators.push(format!("RWX private region {:#x} ({} KB)", region.base, region.size / 1024));
+            first = first.or(Some(region.base));
+        }
+
+        if region.is_executable() {
+            let len = region.size.min(MAX_REGION_BYTES).min(budget);
+            if let Some(data) = platform::read_region(candidate.pid, region.base, len) {
+                budget -= data.len();
+                let matches = memory::scan_buffer(&data, &format!("{}:{:#x}", candidate.name, region.base));
+                if !matches.is_empty() {
+                    first = first.or(Some(region.base));
+                }
+                if emulation.is_none() {
+                    emulation = shellcode_emu::analyze_flagged(&data, &matches);
+                }
+                shellcode.extend(matches.into_iter().map(|m| {
+                    format!("{} @ {:#x}", m.pattern_name, region.base + m.offset)
+                }));
+            }
+        } else if region.size >= ENCRYPTED_REGION_MIN && region.protect & 0xFF == PAGE_READWRITE {
+            let len = ENCRYPTED_SAMPLE.min(budget);
+            if let Some(sample) = platform::read_region(candidate.pid, region.base, len) {
+                budget -= sample.len();
+                if is_encrypted_heap(region, &sample) {
+                    indicators.push(format!(
+                        "High-entropy private region {:#x} ({} KB)", region.base, region.size / 1024
+                    ));
+                    first = first.or(Some(region.base));
+                }
+            }
+        }
+    }
+
+    let mut status = STATUS.lock();
+    status.processes_scanned += 1;
+    status.bytes_scanned += (MAX_BYTES_PER_PROCESS - budget) as u64;
+    (indicators, shellcode, first, emulation)
+}
+
+fn report(
+    candidate: &Candidate,
+    indicators: Vec<String>,
+    shellcode: Vec<String>,
+    emulation: Option<EmulationReport>,
+    region_base: Option<usize>,
+    now: i64,
+) -> Option<MemoryScheduleAlert> {
+    if indicators.is_empty() && shellcode.is_empty() {
+        return None;
+    }
+
+    // Dedup theo loại indicator (base address đổi giữa các lần quét)
+    let kinds: Vec<String> = indicators.iter().chain(shellcode.iter())
+        .map(|i| i.split(|c: char| c == '@' || c.is_ascii_digit()).next().unwrap_or("").trim().to_string())
+        .collect();
+    let fresh = {
+        let mut reported = REPORTED.lock();
+        kinds.into_iter().fold(false, |acc, k| reported.insert((candidate.pid, k)) || acc)
+    };
+    if !fresh {
+        return None;
+    }
+
+    let flipped = indicators.iter().any(|i| i.contains("sleep mask"));
+    let encrypted = indicators.iter().any(|i| i.starts_with("High-entropy"));
+    let rwx = indicators.iter().any(|i| i.starts_with("RWX"));
+    let severity = if !shellcode.is_empty() && (flipped || rwx) {
+        Severity::Critical
+    } else if !shellcode.is_empty() || flipped || (rwx && encrypted) {
+        Severity::High
+    } else {
+        Severity::Medium
+    };
+
+    if matches!(severity, Severity::High | Severity::Critical) {
+        let mut mitre = vec!["T1055"];
+        if flipped || encrypted {
+            mitre.push("T1027");
+        }
+        let title = if flipped {
+            format!("Sleep-masked beacon in {}", candidate.name)
+        } else {
+            format!("Suspicious memory in {}", candidate.name)
+        };
+        incident::raise_process_detection(
+            &title,
+            severity.clone(),
+            &["MEMORY_SCAN".to_string(), "DEFENSE_EVASION".to_string()],
+            &mitre,
+            &format!(
+                "{} (PID {}) selected for: {}. Findings: {}{}",
+                candidate.name,
+                candidate.pid,
+                candidate.reasons.join(", "),
+                indicators.iter().chain(shellcode.iter()).cloned().collect::<Vec<_>>().join("; "),
+                emulation.as_ref().map(|e| format!(". Emulation: {}", e.summary())).unwrap_or_default(),
+            ),
+            DetectionContext {
+                pid: Some(candidate.pid),
+                rule_matches: indicators.iter().chain(shellcode.iter()).cloned().collect(),
+            },
+        );
+    }
+
+    Some(MemoryScheduleAlert {
+        pid: candidate.pid,
+        process_name: candidate.name.clone(),
+        candidate_reasons: candidate.reasons.clone(),
+        indicators,
+        shellcode,
+        emulation,
+        region_base: region_base.map(|b| format!("{:#x}", b)),
+        severity: format!("{:?}", severity),
+        timestamp: now,
+    })
+}
+
+/// `netstat -ano` → PID có kết nối TCP ESTABLISHED tới địa chỉ không phải loopback
+pub fn parse_netstat(output: &str) -> HashSet<u32> {
+    output.lines()
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            if cols.len() < 5 || !cols[0].eq_ignore_ascii_case("TCP") || cols[3] != "ESTABLISHED" {
+                return None;
+            }
+            let remote = cols[2];
+            if remote.starts_with("127.") || remote.starts_with("[::1]") {
+                return None;
+            }
+            cols[4].parse().ok()
+        })
+        .collect()
+}
+
+// ============================================================================
+// PLATFORM
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use std::process::Command;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Diagnostics::Debug::ReadProcessMemory;
+    use windows::Win32::System::Memory::{VirtualQueryEx, MEMORY_BASIC_INFORMATION, MEM_COMMIT, MEM_PRIVATE};
+    use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
+
+    pub fn network_active_pids() -> HashSet<u32> {
+        Command::new("netstat")
+            .args(["-ano", "-p", "TCP"])
+            .output()
+            .map(|o| parse_netstat(&String::from_utf8_lossy(&o.stdout)))
+            .unwrap_or_default()
+    }
+
+    /// Committed private regions của process
+    pub fn query_regions(pid: u32) -> Option<Vec<Region>> {
+        let handle = unsafe { OpenProcess(PROCESS_QUERY_INFORMATION, false, pid) }.ok()?;
+        let mut regions = Vec::new();
+        let mut address = 0usize;
+
+        loop {
+            let mut info = MEMORY_BASIC_INFORMATION::default();
+            let written = unsafe {
+                VirtualQueryEx(
+                    handle,
+                    Some(address as *const _),
+                    &mut info,
+                    std::mem::size_of::<MEMORY_BASIC_INFORMATION>(),
+                )
+            };
+            if written == 0 || info.RegionSize == 0 {
+                break;
+            }
+            if info.State == MEM_COMMIT && info.Type == MEM_PRIVATE {
+                regions.push(Region {
+                    base: info.BaseAddress as usize,
+                    size: info.RegionSize,
+                    protect: info.Protect.0,
+                });
+            }
+            address = match (info.BaseAddress as usize).checked_add(info.RegionSize) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        unsafe { let _ = CloseHandle(handle); }
+        Some(regions)
+    }
+
+    pub fn read_region(pid: u32, base: usize, len: usize) -> Option<Vec<u8>> {
+        let handle = unsafe { OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, pid) }.ok()?;
+        let mut buf = vec![0u8; len];
+        let mut read = 0usize;
+        let ok = unsafe {
+            ReadProcessMemory(handle, base as *const _, buf.as_mut_ptr() as *mut _, len, Some(&mut read))
+        };
+        unsafe { let _ = CloseHandle(handle); }
+        if ok.is_err() || read == 0 {
+            return None;
+        }
+        buf.truncate(read);
+        Some(buf)
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::*;
+
+    pub fn network_active_pids() -> HashSet<u32> {
+        HashSet::new()
+    }
+
+    pub fn query_regions(_pid: u32) -> Option<Vec<Region>> {
+        None
+    }
+
+    pub fn read_region(_pid: u32, _base: usize, _len: usize) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protection_flips() {
+        let previous: HashMap<usize, u32> = [(0x1000, PAGE_EXECUTE_READ), (0x5000, PAGE_READWRITE)].into();
+        let current = vec![
+            Region { base: 0x1000, size: 0x4000, protect: PAGE_READWRITE },      // RX → RW: masked
+            Region { base: 0x5000, size: 0x4000, protect: PAGE_READWRITE },      // không đổi
+            Region { base: 0x9000, size: 0x4000, protect: PAGE_EXECUTE_READ },   // region mới
+        ];
+        let flips = protection_flips(&previous, &current);
+        assert_eq!(flips.len(), 1);
+        assert_eq!(flips[0].0.base, 0x1000);
+        assert_eq!(flips[0].1, PAGE_EXECUTE_READ);
+    }
+
+    #[test]
+    fn test_encrypted_heap() {
+        let region = Region { base: 0, size: 512 * 1024, protect: PAGE_READWRITE };
+        let random: Vec<u8> = (0..65536u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).collect();
+        assert!(is_encrypted_heap(&region, &random));
+        assert!(!is_encrypted_heap(&region, &vec![0u8; 65536]));
+
+        let small = Region { size: 4096, ..region };
+        assert!(!is_encrypted_heap(&small, &random));
+    }
+
+    #[test]
+    fn test_parse_netstat() {
+        let output = "
+  Proto  Local Address          Foreign Address        State           PID
+  TCP    10.0.0.5:50123         93.184.216.34:443      ESTABLISHED     4242
+  TCP    127.0.0.1:50200        127.0.0.1:50201        ESTABLISHED     1111
+  TCP    0.0.0.0:135            0.0.0.0:0              LISTENING       900
+";
+        let pids = parse_netstat(output);
+        assert!(pids.contains(&4242));
+        assert!(!pids.contains(&1111));
+        assert!(!pids.contains(&900));
+    }
+}
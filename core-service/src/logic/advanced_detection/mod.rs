@@ -4,8 +4,18 @@
 //!
 //! # Components - Phase 8
 //! - `amsi.rs`: Script scanning (PowerShell, VBScript, JavaScript)
+//! - `amsi_provider.rs`: AMSI provider registration + named-pipe bridge to `amsi.rs`
+//! - `evasion.rs`: AMSI / ETW patch (bypass) detection
+//! - `etw.rs`: PowerShell script-block (4104) ETW consumer → `amsi.rs`
+//! - `credential_theft.rs`: Non-browser access to browser credential stores / DPAPI keys
+//! - `canary.rs`: Ransomware canary files (file watcher → ransomware response)
+//! - `office_macro.rs`: VBA extraction từ docm/xlsm/doc/xls → AMSI + auto-exec heuristics
 //! - `injection.rs`: DLL injection detection
+//! - `sideload.rs`: DLL side-loading detection (alerts via `injection` history)
 //! - `memory.rs`: Shellcode pattern scanning
+//! - `shellcode_emu.rs`: API hash resolution + emulation (feature `shellcode-emulation`) → C2 IOCs
+//! - `memory_scheduler.rs`: Background memory scans của process rủi ro cao + sleep-mask heuristics
+//! - `full_scan.rs`: On-demand / scheduled file scan (hash intel, PE static, shellcode, YARA)
 //! - `types.rs`: Shared types for AMSI detection
 //! - `injection_types.rs`: Types for injection detection
 //! - `memory_types.rs`: Types for memory scanning
@@ -13,24 +23,33 @@
 //! # Components - Phase 9 (v2.3)
 //! - `keylogger.rs`: Keylogger behavior detection (T1056.001)
 //! - `iat_analysis.rs`: Import Address Table analysis
-//!
-//! # Future
-//! - `etw.rs`: Event Tracing for Windows
+//! - `pe_static.rs`: Static pass cho executable mới (entropy, packer, imphash, overlay)
 
 // Allow unused for now - incrementally integrated
 #![allow(unused)]
 
 // Phase 8 modules
 pub mod amsi;
+pub mod amsi_provider;
+pub mod canary;
+pub mod office_macro;
+pub mod evasion;
+pub mod etw;
+pub mod credential_theft;
 pub mod types;
 pub mod injection;
+pub mod sideload;
 pub mod injection_types;
 pub mod memory;
+pub mod memory_scheduler;
+pub mod shellcode_emu;
 pub mod memory_types;
+pub mod full_scan;
 
 // Phase 9 modules (v2.3)
 pub mod keylogger;
 pub mod iat_analysis;
+pub mod pe_static;
 
 // Re-exports - AMSI
 pub use amsi::{init as init_amsi, scan, scan_file, is_malicious, is_available as amsi_available, get_stats as amsi_stats};
@@ -48,16 +67,24 @@ pub use memory_types::{ShellcodeType, MemoryScanResult, MemoryScanStats, MemoryS
 pub use keylogger::{KeyloggerAlert, KeyloggerStats, ApiCallStats, SuspiciousApi};
 
 // Re-exports - IAT Analysis (Phase 9)
-pub use iat_analysis::{IatAnalysisResult, IatAlert, IatStats, IatError};
+pub use iat_analysis::{IatAnalysisResult, IatAlert, IatStats, IatError, IatProfile, IatTamperAlert, ImportDiff};
+
+// Re-exports - PE static analysis
+pub use pe_static::{StaticAnalysisResult, SectionInfo, StaticAnalysisError};
 
 /// Initialize all advanced detection modules
 pub fn init() {
     if let Err(e) = amsi::init() {
         log::warn!("AMSI init failed: {}", e);
     }
+    amsi_provider::start();
+    etw::start();
+    office_macro::start();
     injection::init();
     memory::init();
     keylogger::init();
     iat_analysis::init();
+    pe_static::start();
+    full_scan::start();
     log::info!("Advanced Detection v2.3 initialized (AMSI + Injection + Memory + Keylogger + IAT)");
 }
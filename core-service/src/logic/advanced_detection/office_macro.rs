@@ -0,0 +1,628 @@
+//! Office Macro Scanner - VBA trong document macro-enabled
+//!
+//! Mục đích: Bắt document độc (docm / xlsm / doc / xls ...) trước khi user bấm "Enable Content"
+//!
+//! Nguồn file:
+//! - File watcher trên Documents / Desktop / Downloads (file mới ghi xuống disk)
+//! - Document đang mở bởi Office process (path trong command line)
+//!
+//! Extraction:
+//! - OOXML (zip) → `vbaProject.bin`; file OLE cũ dùng trực tiếp
+//! - OLE storage `VBA` → module stream → MS-OVBA decompress
+//!
+//! Phân tích: auto-exec entry point + API nguy hiểm + dấu hiệu obfuscation +
+//! heuristic của `amsi.rs`. Alert → incident kèm excerpt của macro.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::amsi;
+use crate::logic::incident::{self, ScriptExcerpt, Severity};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const OOXML_EXTENSIONS: &[&str] = &["docm", "dotm", "xlsm", "xltm", "xlsb", "xlam", "pptm", "potm", "ppsm"];
+const OLE_EXTENSIONS: &[&str] = &["doc", "dot", "xls", "xlt", "ppt", "pps"];
+
+const OFFICE_PROCESSES: &[&str] = &["winword.exe", "excel.exe", "powerpnt.exe"];
+
+/// Entry point chạy tự động khi mở / đóng document
+const AUTOEXEC_KEYWORDS: &[&str] = &[
+    "AutoOpen", "Auto_Open", "AutoExec", "AutoClose", "Auto_Close", "AutoNew",
+    "Document_Open", "Document_Close", "Document_New", "DocumentOpen",
+    "Workbook_Open", "Workbook_Activate", "Workbook_BeforeClose",
+    "Presentation_Open", "InkPicture1_Painted",
+];
+
+/// API / object thường dùng để tải và chạy payload
+const SUSPICIOUS_KEYWORDS: &[&str] = &[
+    "Shell", "WScript.Shell", "Shell.Application", "CreateObject", "GetObject",
+    "URLDownloadToFile", "MSXML2.XMLHTTP", "WinHttp.WinHttpRequest", "ADODB.Stream",
+    "SaveToFile", "powershell", "cmd.exe", "mshta", "certutil", "regsvr32", "rundll32",
+    "winmgmts:", "Win32_Process", "VirtualAlloc", "RtlMoveMemory", "CreateThread",
+    "Lib \"kernel32", "Environ(",
+];
+
+const CHR_OBFUSCATION_THRESHOLD: usize = 20;
+const CONCAT_OBFUSCATION_THRESHOLD: usize = 60;
+const LONG_LITERAL_CHARS: usize = 200;
+
+const MAX_FILE_SIZE: u64 = 50 * 1024 * 1024;
+/// Chờ file ghi xong trước khi scan
+const SETTLE_DELAY: Duration = Duration::from_secs(2);
+const OFFICE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+const EXCERPT_CHARS: usize = 2000;
+const MAX_ALERTS: usize = 500;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroModule {
+    pub name: String,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroAnalysis {
+    pub path: String,
+    pub modules: Vec<MacroModule>,
+    pub autoexec: Vec<String>,
+    pub suspicious: Vec<String>,
+    pub obfuscation: Vec<String>,
+    /// AMSI heuristic flag (should_block)
+    pub amsi_detected: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroAlert {
+    pub path: String,
+    /// "file_write" | "office_open" | "manual"
+    pub source: String,
+    pub process_name: Option<String>,
+    pub pid: Option<u32>,
+    pub modules: Vec<String>,
+    pub autoexec: Vec<String>,
+    pub suspicious: Vec<String>,
+    pub obfuscation: Vec<String>,
+    pub amsi_detected: bool,
+    pub severity: String,
+    pub mitre_ids: Vec<String>,
+    pub timestamp: i64,
+}
+
+impl MacroAnalysis {
+    /// Macro đáng báo: obfuscated, AMSI flag, hoặc auto-exec + API nguy hiểm
+    pub fn is_malicious(&self) -> bool {
+        self.amsi_detected
+            || !self.obfuscation.is_empty()
+            || (!self.autoexec.is_empty() && !self.suspicious.is_empty())
+    }
+
+    fn severity(&self) -> Severity {
+        if self.amsi_detected {
+            Severity::Critical
+        } else if !self.autoexec.is_empty() && (!self.suspicious.is_empty() || !self.obfuscation.is_empty()) {
+            Severity::High
+        } else {
+            Severity::Medium
+        }
+    }
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static WATCHER: Mutex<Option<RecommendedWatcher>> = Mutex::new(None);
+/// File chờ scan (path -> (thời điểm event cuối, source))
+static PENDING: Lazy<Mutex<HashMap<PathBuf, (Instant, &'static str)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// Path -> (size, mtime) đã scan
+static SCANNED: Lazy<Mutex<HashMap<PathBuf, (u64, Option<SystemTime>)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// Document -> Office process đang mở nó
+static OPENED_BY: Lazy<Mutex<HashMap<PathBuf, (u32, String)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static ALERTS: Lazy<Mutex<Vec<MacroAlert>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Watch thư mục user + poll document đang mở bởi Office
+pub fn start() {
+    let mut watcher = match notify::recommended_watcher(|res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                for path in event.paths.iter().filter(|p| is_office_document(p)) {
+                    PENDING.lock().insert(path.clone(), (Instant::now(), "file_write"));
+                }
+            }
+        }
+    }) {
+        Ok(w) => w,
+        Err(e) => {
+            log::error!("Macro scanner watcher failed: {}", e);
+            return;
+        }
+    };
+
+    let dirs: Vec<PathBuf> = [dirs::document_dir(), dirs::desktop_dir(), dirs::download_dir()]
+        .into_iter()
+        .flatten()
+        .filter(|d| d.is_dir())
+        .collect();
+    for dir in &dirs {
+        if let Err(e) = watcher.watch(dir, RecursiveMode::Recursive) {
+            log::warn!("Macro scanner: cannot watch {}: {}", dir.display(), e);
+        }
+    }
+    *WATCHER.lock() = Some(watcher);
+
+    std::thread::spawn(|| {
+        let mut last_poll = Instant::now() - OFFICE_POLL_INTERVAL;
+        loop {
+            if last_poll.elapsed() >= OFFICE_POLL_INTERVAL {
+                poll_office_processes();
+                last_poll = Instant::now();
+            }
+            process_pending();
+            std::thread::sleep(Duration::from_millis(500));
+        }
+    });
+
+    log::info!("Macro scanner started ({} folders)", dirs.len());
+}
+
+/// Scan 1 document theo yêu cầu (không dedup)
+pub fn scan_document(path: &Path) -> Result<MacroAnalysis, String> {
+    let analysis = analyze_file(path)?;
+    if analysis.is_malicious() {
+        report(&analysis, "manual", None);
+    }
+    Ok(analysis)
+}
+
+pub fn get_recent_alerts(limit: usize) -> Vec<MacroAlert> {
+    ALERTS.lock().iter().rev().take(limit).cloned().collect()
+}
+
+// ============================================================================
+// SOURCES
+// ============================================================================
+
+fn poll_office_processes() {
+    let mut system = sysinfo::System::new();
+    system.refresh_processes();
+
+    for (pid, process) in system.processes() {
+        if !OFFICE_PROCESSES.contains(&process.name().to_lowercase().as_str()) {
+            continue;
+        }
+        for arg in process.cmd().iter().skip(1) {
+            let path = PathBuf::from(arg.trim_matches('"'));
+            if !is_office_document(&path) || !path.is_file() {
+                continue;
+            }
+            OPENED_BY.lock().insert(path.clone(), (pid.as_u32(), process.name().to_string()));
+            PENDING.lock().entry(path).or_insert((Instant::now() - SETTLE_DELAY, "office_open"));
+        }
+    }
+}
+
+fn process_pending() {
+    let ready: Vec<(PathBuf, &'static str)> = {
+        let mut pending = PENDING.lock();
+        let ready: Vec<(PathBuf, &'static str)> = pending.iter()
+            .filter(|(_, (at, _))| at.elapsed() >= SETTLE_DELAY)
+            .map(|(p, (_, source))| (p.clone(), *source))
+            .collect();
+        for (path, _) in &ready {
+            pending.remove(path);
+        }
+        ready
+    };
+
+    for (path, source) in ready {
+        let meta = match std::fs::metadata(&path) {
+            Ok(m) if m.len() <= MAX_FILE_SIZE => m,
+            _ => continue,
+        };
+        let fingerprint = (meta.len(), meta.modified().ok());
+        if SCANNED.lock().insert(path.clone(), fingerprint) == Some(fingerprint) {
+            continue;
+        }
+
+        match analyze_file(&path) {
+            Ok(analysis) if analysis.is_malicious() => {
+                let opener = OPENED_BY.lock().remove(&path);
+                report(&analysis, source, opener);
+            }
+            Ok(_) => {}
+            Err(e) => log::debug!("Macro scan skipped {}: {}", path.display(), e),
+        }
+    }
+}
+
+// ============================================================================
+// ANALYSIS
+// ============================================================================
+
+fn is_office_document(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    // Lock file của Office (~$report.docm)
+    if name.starts_with("~$") {
+        return false;
+    }
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| {
+            let e = e.to_lowercase();
+            OOXML_EXTENSIONS.contains(&e.as_str()) || OLE_EXTENSIONS.contains(&e.as_str())
+        })
+        .unwrap_or(false)
+}
+
+pub fn analyze_file(path: &Path) -> Result<MacroAnalysis, String> {
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    let modules = extract_macros(&data)?;
+    Ok(analyze_modules(&path.to_string_lossy(), modules))
+}
+
+pub fn analyze_modules(path: &str, modules: Vec<MacroModule>) -> MacroAnalysis {
+    let all_code: String = modules.iter().map(|m| m.code.as_str()).collect::<Vec<_>>().join("\n");
+    let lower = all_code.to_lowercase();
+
+    let autoexec = AUTOEXEC_KEYWORDS.iter()
+        .filter(|k| contains_word(&lower, &k.to_lowercase()))
+        .map(|k| k.to_string())
+        .collect();
+    let suspicious = SUSPICIOUS_KEYWORDS.iter()
+        .filter(|k| contains_word(&lower, &k.to_lowercase()))
+        .map(|k| k.to_string())
+        .collect();
+    let obfuscation = obfuscation_indicators(&all_code);
+    let amsi_detected = !all_code.is_empty()
+        && amsi::scan(&all_code, "VBA").map(|r| r.should_block).unwrap_or(false);
+
+    MacroAnalysis {
+        path: path.to_string(),
+        modules,
+        autoexec,
+        suspicious,
+        obfuscation,
+        amsi_detected,
+    }
+}
+
+/// `needle` xuất hiện như 1 identifier (không nằm giữa tên khác)
+fn contains_word(haystack: &str, needle: &str) -> bool {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    haystack.match_indices(needle).any(|(i, _)| {
+        let before = haystack[..i].chars().next_back();
+        let after = haystack[i + needle.len()..].chars().next();
+        !before.map(is_ident).unwrap_or(false)
+            && !(needle.chars().last().map(is_ident).unwrap_or(false) && after.map(is_ident).unwrap_or(false))
+    })
+}
+
+pub fn obfuscation_indicators(code: &str) -> Vec<String> {
+    let lower = code.to_lowercase();
+    let mut indicators = Vec::new();
+
+    let chr_calls = lower.matches("chr(").count() + lower.matches("chrw(").count() + lower.matches("chrb(").count();
+    if chr_calls >= CHR_OBFUSCATION_THRESHOLD {
+        indicators.push(format!("Chr() string building ({} calls)", chr_calls));
+    }
+
+    let concats = code.matches("\" & \"").count() + code.matches("\"&\"").count();
+    if concats >= CONCAT_OBFUSCATION_THRESHOLD {
+        indicators.push(format!("Heavy string concatenation ({} joins)", concats));
+    }
+
+    if lower.contains("strreverse(") {
+        indicators.push("StrReverse".to_string());
+    }
+    if lower.contains("callbyname") {
+        indicators.push("CallByName dynamic call".to_string());
+    }
+
+    let long_literal = code.split('"')
+        .skip(1)
+        .step_by(2)
+        .any(|s| s.len() >= LONG_LITERAL_CHARS && s.chars().all(|c| c.is_ascii_alphanumeric() || "+/=".contains(c)));
+    if long_literal {
+        indicators.push("Long encoded string literal".to_string());
+    }
+
+    indicators
+}
+
+fn report(analysis: &MacroAnalysis, source: &str, opener: Option<(u32, String)>) {
+    let severity = analysis.severity();
+    let mut mitre = vec!["T1204.002", "T1059.005"];
+    if !analysis.obfuscation.is_empty() {
+        mitre.push("T1027");
+    }
+
+    let mut findings: Vec<String> = Vec::new();
+    if !analysis.autoexec.is_empty() {
+        findings.push(format!("auto-exec: {}", analysis.autoexec.join(", ")));
+    }
+    if !analysis.suspicious.is_empty() {
+        findings.push(format!("suspicious: {}", analysis.suspicious.join(", ")));
+    }
+    if !analysis.obfuscation.is_empty() {
+        findings.push(format!("obfuscation: {}", analysis.obfuscation.join(", ")));
+    }
+    if analysis.amsi_detected {
+        findings.push("AMSI detection".to_string());
+    }
+
+    let file_name = Path::new(&analysis.path).file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| analysis.path.clone());
+    log::warn!("[MACRO] {} - {}", analysis.path, findings.join("; "));
+
+    let incident_id = incident::raise_detection(
+        &format!("Malicious Office macro in {}", file_name),
+        severity.clone(),
+        &["OFFICE_MACRO".to_string(), "INITIAL_ACCESS".to_string()],
+        &mitre,
+        &format!("{} ({} VBA modules): {}", analysis.path, analysis.modules.len(), findings.join("; ")),
+    );
+
+    let code: String = analysis.modules.iter()
+        .map(|m| format!("' ==== {} ====\n{}", m.name, m.code))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if let Some(incident_id) = incident_id {
+        incident::attach_script_excerpt(incident_id, ScriptExcerpt {
+            script_block_id: file_name,
+            pid: opener.as_ref().map(|(pid, _)| *pid).unwrap_or(0),
+            path: Some(analysis.path.clone()),
+            excerpt: code.chars().take(EXCERPT_CHARS).collect(),
+            length: code.chars().count(),
+            at: Utc::now(),
+        });
+    }
+
+    let alert = MacroAlert {
+        path: analysis.path.clone(),
+        source: source.to_string(),
+        process_name: opener.as_ref().map(|(_, name)| name.clone()),
+        pid: opener.map(|(pid, _)| pid),
+        modules: analysis.modules.iter().map(|m| m.name.clone()).collect(),
+        autoexec: analysis.autoexec.clone(),
+        suspicious: analysis.suspicious.clone(),
+        obfuscation: analysis.obfuscation.clone(),
+        amsi_detected: analysis.amsi_detected,
+        severity: format!("{:?}", severity),
+        mitre_ids: mitre.iter().map(|m| m.to_string()).collect(),
+        timestamp: Utc::now().timestamp(),
+    };
+
+    crate::logic::events::emit_threat_alert(&serde_json::json!({
+        "type": "OFFICE_MACRO",
+        "path": alert.path,
+        "pid": alert.pid,
+        "process_name": alert.process_name,
+        "autoexec": alert.autoexec,
+        "obfuscation": alert.obfuscation,
+        "severity": alert.severity,
+        "timestamp": alert.timestamp
+    }));
+
+    let mut history = ALERTS.lock();
+    history.push(alert);
+    let overflow = history.len().saturating_sub(MAX_ALERTS);
+    history.drain(..overflow);
+}
+
+// ============================================================================
+// EXTRACTION
+// ============================================================================
+
+/// Lấy source các VBA module từ document (OOXML hoặc OLE)
+pub fn extract_macros(data: &[u8]) -> Result<Vec<MacroModule>, String> {
+    if data.starts_with(b"PK\x03\x04") {
+        let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(|e| e.to_string())?;
+        let mut modules = Vec::new();
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+            if !entry.name().to_lowercase().ends_with("vbaproject.bin") {
+                continue;
+            }
+            let mut bin = Vec::new();
+            entry.read_to_end(&mut bin).map_err(|e| e.to_string())?;
+            modules.extend(extract_from_ole(bin)?);
+        }
+        Ok(modules)
+    } else if data.starts_with(&[0xD0, 0xCF, 0x11, 0xE0]) {
+        extract_from_ole(data.to_vec())
+    } else {
+        Err("Not an Office document".to_string())
+    }
+}
+
+fn extract_from_ole(data: Vec<u8>) -> Result<Vec<MacroModule>, String> {
+    let mut ole = cfb::CompoundFile::open(Cursor::new(data)).map_err(|e| e.to_string())?;
+
+    let streams: Vec<PathBuf> = ole.walk()
+        .filter(|e| e.is_stream())
+        .filter(|e| {
+            let parent = e.path().parent().and_then(|p| p.file_name()).map(|n| n.to_string_lossy().to_uppercase());
+            let name = e.name().to_uppercase();
+            parent.as_deref() == Some("VBA")
+                && name != "DIR"
+                && !name.starts_with("_VBA_PROJECT")
+                && !name.starts_with("__SRP_")
+        })
+        .map(|e| e.path().to_path_buf())
+        .collect();
+
+    let mut modules = Vec::new();
+    for path in streams {
+        let mut raw = Vec::new();
+        if let Ok(mut stream) = ole.open_stream(&path) {
+            if stream.read_to_end(&mut raw).is_err() {
+                continue;
+            }
+        }
+        if let Some(code) = module_source(&raw) {
+            modules.push(MacroModule {
+                name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+                code,
+            });
+        }
+    }
+    Ok(modules)
+}
+
+/// Source nằm sau p-code, là compressed container bắt đầu bằng `Attribute VB_Name`.
+/// Định vị bằng signature (thay vì MODULEOFFSET trong dir stream) - giống olevba fallback.
+fn module_source(stream: &[u8]) -> Option<String> {
+    let marker = b"\x00Attribut";
+    let idx = stream.windows(marker.len()).position(|w| w == marker)?;
+    let start = idx.checked_sub(3)?;
+    let decompressed = decompress(&stream[start..])?;
+    Some(String::from_utf8_lossy(&decompressed).to_string())
+}
+
+/// MS-OVBA 2.4.1 decompression
+pub fn decompress(data: &[u8]) -> Option<Vec<u8>> {
+    if data.first() != Some(&0x01) {
+        return None;
+    }
+    let mut out = Vec::new();
+    let mut pos = 1;
+
+    while pos + 2 <= data.len() {
+        let header = u16::from_le_bytes([data[pos], data[pos + 1]]);
+        let chunk_end = (pos + (header & 0x0FFF) as usize + 3).min(data.len());
+        pos += 2;
+        let chunk_start = out.len();
+
+        if header & 0x8000 == 0 {
+            // Chunk không nén: 4096 byte raw
+            let end = (pos + 4096).min(data.len());
+            out.extend_from_slice(&data[pos..end]);
+            pos = end;
+            continue;
+        }
+
+        while pos < chunk_end {
+            let flags = data[pos];
+            pos += 1;
+            for bit in 0..8 {
+                if pos >= chunk_end {
+                    break;
+                }
+                if flags & (1 << bit) == 0 {
+                    out.push(data[pos]);
+                    pos += 1;
+                    continue;
+                }
+
+                if pos + 2 > chunk_end {
+                    return None;
+                }
+                let token = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
+                pos += 2;
+
+                let difference = out.len() - chunk_start;
+                let mut bit_count = 4;
+                while (1usize << bit_count) < difference {
+                    bit_count += 1;
+                }
+                let length_mask = 0xFFFF >> bit_count;
+                let length = (token & length_mask) + 3;
+                let offset = (token >> (16 - bit_count)) + 1;
+                if offset > difference {
+                    return None;
+                }
+                let src = out.len() - offset;
+                for i in 0..length {
+                    let b = out[src + i];
+                    out.push(b);
+                }
+            }
+        }
+        pos = chunk_end;
+    }
+    Some(out)
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompress_literals() {
+        // MS-OVBA 3.2.1 - no compression possible
+        let data = [
+            0x01, 0x19, 0xB0, 0x00, 0x61, 0x62, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x00,
+            0x69, 0x6A, 0x6B, 0x6C, 0x6D, 0x6E, 0x6F, 0x70, 0x00, 0x71, 0x72, 0x73, 0x74,
+            0x75, 0x76, 0x2E,
+        ];
+        assert_eq!(decompress(&data).unwrap(), b"abcdefghijklmnopqrstuv.".to_vec());
+    }
+
+    #[test]
+    fn test_decompress_spec_example() {
+        // MS-OVBA 3.2.2 - normal compression
+        let data = [
+            0x01, 0x2F, 0xB0, 0x00, 0x23, 0x61, 0x61, 0x61, 0x62, 0x63, 0x64, 0x65, 0x82,
+            0x66, 0x00, 0x70, 0x61, 0x67, 0x68, 0x69, 0x6A, 0x01, 0x38, 0x08, 0x61, 0x6B,
+            0x6C, 0x00, 0x30, 0x6D, 0x6E, 0x6F, 0x70, 0x06, 0x71, 0x02, 0x70, 0x04, 0x10,
+            0x72, 0x73, 0x74, 0x75, 0x76, 0x10, 0x77, 0x78, 0x79, 0x7A, 0x00, 0x3C,
+        ];
+        assert_eq!(
+            decompress(&data).unwrap(),
+            b"#aaabcdefaaaaghijaaaaaklaaamnopqaaaaaaaaaaaarstuvwxyzaaa".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_decompress_copy_token() {
+        // "a" rồi copy token (offset 1, length 8) → 9 ký tự 'a'
+        let data = [0x01, 0x03, 0xB0, 0x02, 0x61, 0x05, 0x00];
+        assert_eq!(decompress(&data).unwrap(), b"aaaaaaaaa".to_vec());
+    }
+
+    #[test]
+    fn test_analyze_autoexec_downloader() {
+        let code = "Attribute VB_Name = \"ThisDocument\"\nSub AutoOpen()\n  Set s = CreateObject(\"WScript.Shell\")\n  s.Run \"powershell -w hidden\"\nEnd Sub";
+        let analysis = analyze_modules("invoice.docm", vec![MacroModule { name: "ThisDocument".to_string(), code: code.to_string() }]);
+        assert_eq!(analysis.autoexec, vec!["AutoOpen".to_string()]);
+        assert!(analysis.suspicious.contains(&"WScript.Shell".to_string()));
+        assert!(analysis.is_malicious());
+    }
+
+    #[test]
+    fn test_benign_macro_not_flagged() {
+        let code = "Sub FormatTable()\n  Selection.Font.Bold = True\nEnd Sub";
+        let analysis = analyze_modules("report.xlsm", vec![MacroModule { name: "Module1".to_string(), code: code.to_string() }]);
+        assert!(!analysis.is_malicious());
+        assert!(!contains_word("autoopenx", "autoopen"));
+    }
+
+    #[test]
+    fn test_obfuscation_indicators() {
+        let code = (0..25).map(|i| format!("Chr({})", 65 + i)).collect::<Vec<_>>().join(" & ");
+        assert!(!obfuscation_indicators(&code).is_empty());
+        assert!(obfuscation_indicators("x = StrReverse(\"exe.dmc\")").contains(&"StrReverse".to_string()));
+    }
+}
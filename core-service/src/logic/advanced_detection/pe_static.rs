@@ -0,0 +1,611 @@
+//! PE Static Analysis - Executable chưa từng thấy
+//!
+//! Mục đích: Cho process mới một risk score trước khi nó có runtime history
+//!
+//! Khi collector thấy executable lần đầu → queue → worker thread phân tích:
+//! - Section entropy (packed / encrypted payload), section RWX
+//! - Packer signatures (UPX, ASPack, MPRESS, Themida, VMProtect, ...)
+//! - Import table → imphash + suspicious API combos (`iat_analysis`)
+//! - Overlay (data sau section cuối, không tính certificate)
+//! - Thiếu VERSIONINFO resource, entry point trong section writable
+//!
+//! Kết quả cache theo path; `action_guard` đưa score vào `ThreatContext`.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use super::iat_analysis::{self, IatAlert};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// File lớn hơn → bỏ qua (installer, game, ...)
+const MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+const MAX_CACHE: usize = 5000;
+
+const HIGH_ENTROPY: f64 = 7.2;
+const LARGE_OVERLAY: u64 = 1024 * 1024;
+
+/// (section name, packer)
+const PACKER_SECTIONS: &[(&str, &str)] = &[
+    ("UPX0", "UPX"), ("UPX1", "UPX"), ("UPX2", "UPX"),
+    (".aspack", "ASPack"), (".adata", "ASPack"),
+    (".MPRESS1", "MPRESS"), (".MPRESS2", "MPRESS"),
+    (".petite", "Petite"), (".nsp0", "NsPack"), (".nsp1", "NsPack"),
+    (".themida", "Themida"), (".winlice", "Themida"),
+    (".vmp0", "VMProtect"), (".vmp1", "VMProtect"),
+    (".enigma1", "Enigma"), (".enigma2", "Enigma"),
+    ("PEC2", "PECompact"), (".packed", "Unknown packer"),
+];
+
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+const IMAGE_SCN_MEM_WRITE: u32 = 0x8000_0000;
+const RT_VERSION: u32 = 16;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionInfo {
+    pub name: String,
+    pub raw_size: u32,
+    pub entropy: f64,
+    pub executable: bool,
+    pub writable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaticAnalysisResult {
+    pub file_path: String,
+    pub file_size: u64,
+    pub is_64bit: bool,
+    pub sections: Vec<SectionInfo>,
+    pub max_entropy: f64,
+    pub packer: Option<String>,
+    pub import_count: usize,
+    pub imphash: Option<String>,
+    pub iat_alerts: Vec<IatAlert>,
+    pub overlay_size: u64,
+    pub has_version_info: bool,
+    /// 0.0 - 1.0
+    pub risk_score: f32,
+    pub reasons: Vec<String>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone)]
+pub enum StaticAnalysisError {
+    IoError(String),
+    NotPe(String),
+    TooLarge(u64),
+}
+
+impl std::fmt::Display for StaticAnalysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "IO error: {}", e),
+            Self::NotPe(e) => write!(f, "Not a PE file: {}", e),
+            Self::TooLarge(size) => write!(f, "File too large: {} bytes", size),
+        }
+    }
+}
+
+impl std::error::Error for StaticAnalysisError {}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// Path (lowercase) -> kết quả
+static RESULTS: Lazy<RwLock<HashMap<String, StaticAnalysisResult>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+/// Path đã submit (tránh queue trùng)
+static SEEN: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+static QUEUE: Lazy<Mutex<VecDeque<PathBuf>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Start worker thread xử lý queue
+pub fn start() {
+    thread::spawn(|| {
+        log::info!("PE static analysis worker started");
+        loop {
+            let next = QUEUE.lock().pop_front();
+            match next {
+                Some(path) => match analyze_file(&path) {
+                    Ok(result) if result.risk_score >= 0.5 => log::warn!(
+                        "[PE STATIC] {} risk {:.2}: {}",
+                        result.file_path, result.risk_score, result.reasons.join("; ")
+                    ),
+                    Ok(_) => {}
+                    Err(e) => log::debug!("PE static analysis skipped {:?}: {}", path, e),
+                },
+                None => thread::sleep(Duration::from_millis(500)),
+            }
+        }
+    });
+}
+
+/// Collector gọi khi thấy process mới. Chỉ queue executable chưa từng thấy.
+pub fn submit(path: &Path) {
+    let key = cache_key(path);
+    if SEEN.lock().insert(key) {
+        QUEUE.lock().push_back(path.to_path_buf());
+    }
+}
+
+/// Kết quả đã có (không phân tích đồng bộ)
+pub fn get_cached(path: &Path) -> Option<StaticAnalysisResult> {
+    RESULTS.read().get(&cache_key(path)).cloned()
+}
+
+/// Static risk score cho ThreatContext
+pub fn risk_score(path: &Path) -> Option<f32> {
+    RESULTS.read().get(&cache_key(path)).map(|r| r.risk_score)
+}
+
+/// Phân tích file (và cache kết quả)
+pub fn analyze_file(path: &Path) -> Result<StaticAnalysisResult, StaticAnalysisError> {
+    let size = std::fs::metadata(path).map_err(|e| StaticAnalysisError::IoError(e.to_string()))?.len();
+    if size > MAX_FILE_SIZE {
+        return Err(StaticAnalysisError::TooLarge(size));
+    }
+    let data = std::fs::read(path).map_err(|e| StaticAnalysisError::IoError(e.to_string()))?;
+    let result = analyze_bytes(&data, &path.to_string_lossy())?;
+    // Lưu import profile + so với bản trước của cùng path
+    iat_analysis::track_profile(&path.to_string_lossy(), &data, &result.iat_alerts);
+
+    let mut results = RESULTS.write();
+    if results.len() >= MAX_CACHE {
+        results.clear();
+    }
+    results.insert(cache_key(path), result.clone());
+    Ok(result)
+}
+
+/// Import table thật (dll, function) - None nếu không phải PE hợp lệ
+pub fn parse_imports(data: &[u8]) -> Option<Vec<(String, String)>> {
+    PeImage::parse(data).ok().map(|pe| pe.imports())
+}
+
+/// Phân tích buffer PE
+pub fn analyze_bytes(data: &[u8], source_name: &str) -> Result<StaticAnalysisResult, StaticAnalysisError> {
+    let pe = PeImage::parse(data)?;
+
+    let sections: Vec<SectionInfo> = pe.sections.iter().map(|s| SectionInfo {
+        name: s.name.clone(),
+        raw_size: s.raw_size,
+        entropy: entropy(pe.raw(s)),
+        executable: s.characteristics & IMAGE_SCN_MEM_EXECUTE != 0,
+        writable: s.characteristics & IMAGE_SCN_MEM_WRITE != 0,
+    }).collect();
+    let max_entropy = sections.iter().map(|s| s.entropy).fold(0.0, f64::max);
+
+    let packer = detect_packer(data, &sections);
+    let imports = pe.imports();
+    let imphash = imphash(&imports);
+    let iat_alerts = if imports.is_empty() {
+        // Không parse được import table → heuristic string scan
+        iat_analysis::analyze_binary(data, source_name).map(|r| r.alerts).unwrap_or_default()
+    } else {
+        let names: Vec<String> = imports.iter().map(|(_, f)| f.clone()).collect();
+        iat_analysis::analyze_imports(&names)
+    };
+    let overlay_size = pe.overlay_size();
+    let has_version_info = pe.has_resource_type(RT_VERSION);
+    let entry_writable = pe.entry_section()
+        .map(|s| s.characteristics & IMAGE_SCN_MEM_WRITE != 0)
+        .unwrap_or(false);
+
+    let mut result = StaticAnalysisResult {
+        file_path: source_name.to_string(),
+        file_size: data.len() as u64,
+        is_64bit: pe.is_64bit,
+        sections,
+        max_entropy,
+        packer,
+        import_count: imports.len(),
+        imphash,
+        iat_alerts,
+        overlay_size,
+        has_version_info,
+        risk_score: 0.0,
+        reasons: Vec::new(),
+        timestamp: chrono::Utc::now().timestamp(),
+    };
+    score(&mut result, entry_writable);
+    Ok(result)
+}
+
+// ============================================================================
+// SCORING
+// ============================================================================
+
+fn score(result: &mut StaticAnalysisResult, entry_writable: bool) {
+    let mut score = 0.0f32;
+    let mut reasons = Vec::new();
+
+    if let Some(packer) = &result.packer {
+        score += 0.35;
+        reasons.push(format!("Packed with {}", packer));
+    }
+
+    let packed_code = result.sections.iter().find(|s| s.executable && s.entropy >= HIGH_ENTROPY);
+    if let Some(s) = packed_code {
+        score += 0.25;
+        reasons.push(format!("High-entropy code section {} ({:.2})", s.name, s.entropy));
+    } else if result.max_entropy >= HIGH_ENTROPY {
+        score += 0.1;
+        reasons.push(format!("High-entropy section ({:.2})", result.max_entropy));
+    }
+
+    if let Some(s) = result.sections.iter().find(|s| s.executable && s.writable) {
+        score += 0.2;
+        reasons.push(format!("Writable + executable section {}", s.name));
+    }
+    if entry_writable {
+        score += 0.15;
+        reasons.push("Entry point in writable section".to_string());
+    }
+
+    if result.imphash.is_some() && result.import_count <= 5 {
+        score += 0.15;
+        reasons.push(format!("Minimal import table ({} imports)", result.import_count));
+    }
+
+    if let Some(max) = result.iat_alerts.iter().map(|a| a.severity).max() {
+        score += max as f32 / 100.0 * 0.3;
+        let combos: Vec<&str> = result.iat_alerts.iter().map(|a| a.combo_name.as_str()).collect();
+        reasons.push(format!("Suspicious imports: {}", combos.join(", ")));
+    }
+
+    if result.overlay_size >= LARGE_OVERLAY || result.overlay_size * 2 > result.file_size {
+        score += 0.1;
+        reasons.push(format!("Large overlay ({} bytes)", result.overlay_size));
+    }
+
+    if !result.has_version_info {
+        score += 0.1;
+        reasons.push("Missing version info".to_string());
+    }
+
+    result.risk_score = score.min(1.0);
+    result.reasons = reasons;
+}
+
+fn detect_packer(data: &[u8], sections: &[SectionInfo]) -> Option<String> {
+    for section in sections {
+        if let Some((_, packer)) = PACKER_SECTIONS.iter().find(|(name, _)| section.name.eq_ignore_ascii_case(name)) {
+            return Some(packer.to_string());
+        }
+    }
+    // UPX magic nằm ngay trước section data đầu tiên
+    let head = &data[..data.len().min(4096)];
+    if head.windows(4).any(|w| w == b"UPX!") {
+        return Some("UPX".to_string());
+    }
+    None
+}
+
+/// Shannon entropy (bits / byte)
+pub fn entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0u64; 256];
+    for b in data {
+        counts[*b as usize] += 1;
+    }
+    let len = data.len() as f64;
+    counts.iter()
+        .filter(|c| **c > 0)
+        .map(|c| {
+            let p = *c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Imphash (pefile convention): md5 của "dll.func" lowercase, nối bằng ','
+pub fn imphash(imports: &[(String, String)]) -> Option<String> {
+    if imports.is_empty() {
+        return None;
+    }
+    let joined: Vec<String> = imports.iter().map(|(dll, func)| {
+        let dll = dll.to_lowercase();
+        let dll = dll.strip_suffix(".dll")
+            .or_else(|| dll.strip_suffix(".ocx"))
+            .or_else(|| dll.strip_suffix(".sys"))
+            .unwrap_or(&dll);
+        format!("{}.{}", dll, func.to_lowercase())
+    }).collect();
+    Some(format!("{:x}", md5::compute(joined.join(","))))
+}
+
+fn cache_key(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
+// ============================================================================
+// PE PARSING
+// ============================================================================
+
+struct Section {
+    name: String,
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_offset: u32,
+    raw_size: u32,
+    characteristics: u32,
+}
+
+struct PeImage<'a> {
+    data: &'a [u8],
+    is_64bit: bool,
+    entry_point: u32,
+    /// (RVA, size) theo index IMAGE_DIRECTORY_ENTRY_*
+    directories: Vec<(u32, u32)>,
+    sections: Vec<Section>,
+}
+
+fn read_u16(data: &[u8], off: usize) -> Option<u16> {
+    data.get(off..off + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], off: usize) -> Option<u32> {
+    data.get(off..off + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_u64(data: &[u8], off: usize) -> Option<u64> {
+    data.get(off..off + 8).map(|b| {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(b);
+        u64::from_le_bytes(buf)
+    })
+}
+
+fn read_cstr(data: &[u8], off: usize) -> Option<String> {
+    let bytes = data.get(off..)?;
+    let end = bytes.iter().take(512).position(|b| *b == 0)?;
+    Some(String::from_utf8_lossy(&bytes[..end]).to_string())
+}
+
+impl<'a> PeImage<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self, StaticAnalysisError> {
+        let not_pe = |msg: &str| StaticAnalysisError::NotPe(msg.to_string());
+
+        if data.len() < 64 || &data[0..2] != b"MZ" {
+            return Err(not_pe("no MZ header"));
+        }
+        let pe = read_u32(data, 60).ok_or_else(|| not_pe("truncated DOS header"))? as usize;
+        if data.get(pe..pe + 4) != Some(b"PE\0\0".as_slice()) {
+            return Err(not_pe("invalid PE signature"));
+        }
+
+        let section_count = read_u16(data, pe + 6).ok_or_else(|| not_pe("truncated COFF header"))? as usize;
+        let optional_size = read_u16(data, pe + 20).ok_or_else(|| not_pe("truncated COFF header"))? as usize;
+        let opt = pe + 24;
+        let is_64bit = match read_u16(data, opt) {
+            Some(0x10b) => false,
+            Some(0x20b) => true,
+            _ => return Err(not_pe("unknown optional header magic")),
+        };
+        let entry_point = read_u32(data, opt + 16).unwrap_or(0);
+
+        let (count_off, dir_off) = if is_64bit { (opt + 108, opt + 112) } else { (opt + 92, opt + 96) };
+        let dir_count = read_u32(data, count_off).unwrap_or(0).min(16) as usize;
+        let directories = (0..dir_count)
+            .filter_map(|i| Some((read_u32(data, dir_off + i * 8)?, read_u32(data, dir_off + i * 8 + 4)?)))
+            .collect();
+
+        let table = opt + optional_size;
+        let sections = (0..section_count.min(96)).filter_map(|i| {
+            let s = table + i * 40;
+            let raw_name = data.get(s..s + 8)?;
+            let name_len = raw_name.iter().position(|b| *b == 0).unwrap_or(8);
+            Some(Section {
+                name: String::from_utf8_lossy(&raw_name[..name_len]).to_string(),
+                virtual_size: read_u32(data, s + 8)?,
+                virtual_address: read_u32(data, s + 12)?,
+                raw_size: read_u32(data, s + 16)?,
+                raw_offset: read_u32(data, s + 20)?,
+                characteristics: read_u32(data, s + 36)?,
+            })
+        }).collect();
+
+        Ok(Self { data, is_64bit, entry_point, directories, sections })
+    }
+
+    fn raw(&self, section: &Section) -> &'a [u8] {
+        let start = (section.raw_offset as usize).min(self.data.len());
+        let end = start.saturating_add(section.raw_size as usize).min(self.data.len());
+        &self.data[start..end]
+    }
+
+    fn section_for_rva(&self, rva: u32) -> Option<&Section> {
+        self.sections.iter().find(|s| {
+            let size = s.virtual_size.max(s.raw_size);
+            rva >= s.virtual_address && rva < s.virtual_address.saturating_add(size)
+        })
+    }
+
+    fn rva_to_offset(&self, rva: u32) -> Option<usize> {
+        let s = self.section_for_rva(rva)?;
+        Some((rva - s.virtual_address) as usize + s.raw_offset as usize)
+    }
+
+    fn directory(&self, index: usize) -> Option<(u32, u32)> {
+        self.directories.get(index).copied().filter(|(rva, size)| *rva != 0 && *size != 0)
+    }
+
+    fn entry_section(&self) -> Option<&Section> {
+        self.section_for_rva(self.entry_point)
+    }
+
+    /// Data sau section cuối, trừ Authenticode certificate (IMAGE_DIRECTORY_ENTRY_SECURITY)
+    fn overlay_size(&self) -> u64 {
+        let end = self.sections.iter()
+            .map(|s| s.raw_offset as u64 + s.raw_size as u64)
+            .max()
+            .unwrap_or(0);
+        let mut overlay = (self.data.len() as u64).saturating_sub(end);
+        // Security directory: "RVA" là file offset
+        if let Some((offset, size)) = self.directory(4) {
+            if offset as u64 >= end {
+                overlay = overlay.saturating_sub(size as u64);
+            }
+        }
+        overlay
+    }
+
+    /// Import table → (dll, function). Ordinal → "ord<n>".
+    fn imports(&self) -> Vec<(String, String)> {
+        let mut imports = Vec::new();
+        let (rva, _) = match self.directory(1) {
+            Some(d) => d,
+            None => return imports,
+        };
+        let mut desc = match self.rva_to_offset(rva) {
+            Some(o) => o,
+            None => return imports,
+        };
+
+        for _ in 0..512 {
+            let (lookup, name_rva, first_thunk) = match (
+                read_u32(self.data, desc),
+                read_u32(self.data, desc + 12),
+                read_u32(self.data, desc + 16),
+            ) {
+                (Some(l), Some(n), Some(f)) => (l, n, f),
+                _ => break,
+            };
+            if name_rva == 0 {
+                break;
+            }
+            desc += 20;
+
+            let dll = match self.rva_to_offset(name_rva).and_then(|o| read_cstr(self.data, o)) {
+                Some(d) => d,
+                None => continue,
+            };
+            let thunk_rva = if lookup != 0 { lookup } else { first_thunk };
+            let mut thunk = match self.rva_to_offset(thunk_rva) {
+                Some(o) => o,
+                None => continue,
+            };
+
+            for _ in 0..4096 {
+                let (value, is_ordinal) = if self.is_64bit {
+                    match read_u64(self.data, thunk) {
+                        Some(v) => (v & 0x7fff_ffff_ffff_ffff, v & (1 << 63) != 0),
+                        None => break,
+                    }
+                } else {
+                    match read_u32(self.data, thunk) {
+                        Some(v) => ((v & 0x7fff_ffff) as u64, v & (1 << 31) != 0),
+                        None => break,
+                    }
+                };
+                if value == 0 && !is_ordinal {
+                    break;
+                }
+                thunk += if self.is_64bit { 8 } else { 4 };
+
+                let func = if is_ordinal {
+                    Some(format!("ord{}", value & 0xffff))
+                } else {
+                    // IMAGE_IMPORT_BY_NAME: u16 hint + name
+                    self.rva_to_offset(value as u32).and_then(|o| read_cstr(self.data, o + 2))
+                };
+                if let Some(func) = func {
+                    imports.push((dll.clone(), func));
+                }
+            }
+        }
+        imports
+    }
+
+    /// Resource directory gốc có type `type_id` không
+    fn has_resource_type(&self, type_id: u32) -> bool {
+        let root = match self.directory(2).and_then(|(rva, _)| self.rva_to_offset(rva)) {
+            Some(o) => o,
+            None => return false,
+        };
+        let named = read_u16(self.data, root + 12).unwrap_or(0) as usize;
+        let ids = read_u16(self.data, root + 14).unwrap_or(0) as usize;
+        (named..named + ids).any(|i| read_u32(self.data, root + 16 + i * 8) == Some(type_id))
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entropy() {
+        assert_eq!(entropy(&[0u8; 1024]), 0.0);
+        let uniform: Vec<u8> = (0..=255u8).cycle().take(4096).collect();
+        assert!((entropy(&uniform) - 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_imphash_normalization() {
+        let a = imphash(&[
+            ("KERNEL32.dll".to_string(), "VirtualAlloc".to_string()),
+            ("ws2_32.dll".to_string(), "ord115".to_string()),
+        ]);
+        let b = imphash(&[
+            ("kernel32".to_string(), "virtualalloc".to_string()),
+            ("WS2_32.DLL".to_string(), "ORD115".to_string()),
+        ]);
+        assert!(a.is_some());
+        assert_eq!(a, b);
+        assert_eq!(imphash(&[]), None);
+    }
+
+    #[test]
+    fn test_score_packed_sample() {
+        let mut result = StaticAnalysisResult {
+            file_path: "sample.exe".to_string(),
+            file_size: 200_000,
+            is_64bit: false,
+            sections: vec![SectionInfo {
+                name: "UPX1".to_string(),
+                raw_size: 150_000,
+                entropy: 7.8,
+                executable: true,
+                writable: true,
+            }],
+            max_entropy: 7.8,
+            packer: Some("UPX".to_string()),
+            import_count: 3,
+            imphash: Some("x".to_string()),
+            iat_alerts: Vec::new(),
+            overlay_size: 0,
+            has_version_info: false,
+            risk_score: 0.0,
+            reasons: Vec::new(),
+            timestamp: 0,
+        };
+        score(&mut result, true);
+        assert_eq!(result.risk_score, 1.0);
+        assert!(result.reasons.iter().any(|r| r.contains("UPX")));
+    }
+
+    #[test]
+    fn test_rejects_non_pe() {
+        assert!(analyze_bytes(b"not a pe file at all, just some text padding padding padding padding", "x").is_err());
+    }
+}
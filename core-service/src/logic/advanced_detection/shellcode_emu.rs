@@ -0,0 +1,506 @@
+//! Shellcode Emulation - Resolve API hashes + extract C2 IOCs từ buffer bị flag
+//!
+//! Khi `memory::scan_buffer` phát hiện shellcode (critical match):
+//! 1. API hash resolution: ROR13 (block_api của Metasploit / Cobalt Strike stager
+//!    và biến thể không module) trên bảng API phổ biến → tìm hằng số 32-bit trong buffer
+//! 2. Emulation (feature `shellcode-emulation`, unicorn): chạy tối đa
+//!    `MAX_INSTRUCTIONS` trên x86 / x64, dump code (đã decode) + stack
+//! 3. IOC extraction: URL, host, sockaddr_in (push ip / port), User-Agent
+//!    từ buffer gốc + memory sau emulation
+//!
+//! IOC public được đưa vào `threat_feed` (source "shellcode_emulation").
+
+use std::collections::{HashMap, HashSet};
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::external_intel::{threat_feed, IndicatorType, ThreatIndicator, ThreatLevel};
+use super::memory_types::MemoryScanResult;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Buffer lớn hơn bị cắt (shellcode stager thường < 64KB)
+const MAX_BUFFER: usize = 1024 * 1024;
+const MAX_INSTRUCTIONS: usize = 2_000_000;
+const EMULATION_TIMEOUT_US: u64 = 2_000_000;
+
+const SOURCE: &str = "shellcode_emulation";
+
+/// API hay được resolve bằng hash trong shellcode
+const HASHED_APIS: &[(&str, &[&str])] = &[
+    ("kernel32.dll", &[
+        "LoadLibraryA", "LoadLibraryW", "LoadLibraryExA", "GetProcAddress", "GetModuleHandleA",
+        "VirtualAlloc", "VirtualProtect", "VirtualAllocEx", "WriteProcessMemory",
+        "CreateRemoteThread", "CreateThread", "CreateProcessA", "WinExec", "ExitProcess",
+        "ExitThread", "WaitForSingleObject", "Sleep", "GetVersion",
+    ]),
+    ("ntdll.dll", &["RtlExitUserThread", "NtAllocateVirtualMemory", "NtProtectVirtualMemory"]),
+    ("ws2_32.dll", &[
+        "WSAStartup", "WSASocketA", "connect", "bind", "listen", "accept", "recv", "send",
+        "closesocket", "gethostbyname",
+    ]),
+    ("wininet.dll", &[
+        "InternetOpenA", "InternetConnectA", "HttpOpenRequestA", "HttpSendRequestA",
+        "InternetReadFile", "InternetSetOptionA", "InternetOpenUrlA", "InternetErrorDlg",
+    ]),
+    ("winhttp.dll", &[
+        "WinHttpOpen", "WinHttpConnect", "WinHttpOpenRequest", "WinHttpSendRequest",
+        "WinHttpReceiveResponse", "WinHttpReadData",
+    ]),
+    ("urlmon.dll", &["URLDownloadToFileA"]),
+    ("dnsapi.dll", &["DnsQuery_A"]),
+];
+
+/// TLD chấp nhận khi extract domain (tránh "kernel32.dll", "data.bin", ...)
+const DOMAIN_TLDS: &str = "com|net|org|io|ru|cn|su|xyz|top|info|biz|cc|tk|me|co|online|site|club|live|pw|onion|us|uk|de|in|vn";
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractedIoc {
+    pub kind: IndicatorType,
+    pub value: String,
+    pub port: Option<u16>,
+    /// Đã có trong threat feed trước khi extract
+    pub known_malicious: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmulationReport {
+    /// false = chỉ static pass (feature tắt hoặc emulator lỗi)
+    pub emulated: bool,
+    pub arch: Option<String>,
+    pub instructions: u64,
+    pub stop_reason: Option<String>,
+    pub resolved_apis: Vec<String>,
+    pub iocs: Vec<ExtractedIoc>,
+    pub user_agents: Vec<String>,
+}
+
+impl EmulationReport {
+    /// Tóm tắt cho incident description / findings
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.iocs.is_empty() {
+            let iocs: Vec<String> = self.iocs.iter().map(|i| match i.port {
+                Some(port) => format!("{}:{}", i.value, port),
+                None => i.value.clone(),
+            }).collect();
+            parts.push(format!("C2: {}", iocs.join(", ")));
+        }
+        if !self.resolved_apis.is_empty() {
+            parts.push(format!("APIs: {}", self.resolved_apis.join(", ")));
+        }
+        parts.join(" | ")
+    }
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// hash → "module!function"
+static API_HASHES: Lazy<HashMap<u32, String>> = Lazy::new(build_hash_table);
+
+/// IOC đã đưa vào threat feed
+static FED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+static URL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"https?://[A-Za-z0-9.\-]+(?::\d{1,5})?(?:/[\x21-\x7e]*)?").unwrap()
+});
+static IPV4_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(?:\d{1,3}\.){3}\d{1,3}\b").unwrap()
+});
+static DOMAIN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(r"(?i)\b(?:[a-z0-9](?:[a-z0-9\-]{{0,61}}[a-z0-9])?\.)+(?:{})\b", DOMAIN_TLDS)).unwrap()
+});
+static USER_AGENT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"Mozilla/\d\.\d \([\x20-\x7e]{8,200}").unwrap()
+});
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// Emulator có được build vào không (feature `shellcode-emulation`)
+pub fn emulator_available() -> bool {
+    cfg!(feature = "shellcode-emulation")
+}
+
+/// Chạy phân tích nếu scan có critical shellcode match
+pub fn analyze_flagged(data: &[u8], results: &[MemoryScanResult]) -> Option<EmulationReport> {
+    if !is_enabled() || !results.iter().any(|r| r.is_critical()) {
+        return None;
+    }
+    let report = analyze(data);
+    if report.iocs.is_empty() && report.resolved_apis.is_empty() {
+        None
+    } else {
+        Some(report)
+    }
+}
+
+/// Static pass + emulation + IOC extraction, IOC mới được feed vào threat feed
+pub fn analyze(data: &[u8]) -> EmulationReport {
+    let data = &data[..data.len().min(MAX_BUFFER)];
+    let mut report = EmulationReport {
+        resolved_apis: resolve_api_hashes(data),
+        ..Default::default()
+    };
+
+    let mut buffers = vec![data.to_vec()];
+    if let Some(run) = emulator::run(data) {
+        report.emulated = true;
+        report.arch = Some(run.arch);
+        report.instructions = run.instructions;
+        report.stop_reason = run.stop_reason;
+        for name in resolve_api_hashes(&run.code) {
+            if !report.resolved_apis.contains(&name) {
+                report.resolved_apis.push(name);
+            }
+        }
+        buffers.push(run.code);
+        buffers.push(run.stack);
+    }
+
+    let mut seen = HashSet::new();
+    for buffer in &buffers {
+        for mut ioc in extract_iocs(buffer) {
+            if seen.insert((ioc.value.clone(), ioc.port)) {
+                ioc.known_malicious = is_known(&ioc);
+                report.iocs.push(ioc);
+            }
+        }
+        for ua in extract_user_agents(buffer) {
+            if !report.user_agents.contains(&ua) {
+                report.user_agents.push(ua);
+            }
+        }
+    }
+
+    feed_intel(&report.iocs);
+    report
+}
+
+// ============================================================================
+// API HASH RESOLUTION
+// ============================================================================
+
+fn ror13<I: IntoIterator<Item = u8>>(bytes: I) -> u32 {
+    bytes.into_iter().fold(0u32, |h, b| h.rotate_right(13).wrapping_add(b as u32))
+}
+
+/// Metasploit block_api: ror13(UTF-16 module name, uppercase, + null) + ror13(function + null)
+pub fn block_api_hash(module: &str, function: &str) -> u32 {
+    let module_bytes: Vec<u8> = module.to_uppercase()
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .flat_map(|u| u.to_le_bytes())
+        .collect();
+    let function_bytes = function.bytes().chain(std::iter::once(0));
+    ror13(module_bytes).wrapping_add(ror13(function_bytes))
+}
+
+/// Biến thể không module (chỉ tên function, không null)
+pub fn function_hash(function: &str) -> u32 {
+    ror13(function.bytes())
+}
+
+fn build_hash_table() -> HashMap<u32, String> {
+    let mut table = HashMap::new();
+    for (module, functions) in HASHED_APIS {
+        for function in *functions {
+            let name = format!("{}!{}", module, function);
+            table.insert(block_api_hash(module, function), name.clone());
+            table.insert(function_hash(function), name);
+        }
+    }
+    table
+}
+
+/// Tìm hằng số hash (little-endian, không cần align) trong buffer
+pub fn resolve_api_hashes(data: &[u8]) -> Vec<String> {
+    let mut resolved = Vec::new();
+    for window in data.windows(4) {
+        let value = u32::from_le_bytes([window[0], window[1], window[2], window[3]]);
+        if let Some(name) = API_HASHES.get(&value) {
+            if !resolved.contains(name) {
+                resolved.push(name.clone());
+            }
+        }
+    }
+    resolved
+}
+
+// ============================================================================
+// IOC EXTRACTION
+// ============================================================================
+
+/// Printable ASCII; byte khác → ' '
+fn ascii_view(data: &[u8]) -> String {
+    data.iter().map(|&b| if (0x20..0x7f).contains(&b) { b as char } else { ' ' }).collect()
+}
+
+/// UTF-16LE ASCII-range strings ("h\0t\0t\0p\0") → "http"
+fn utf16_view(data: &[u8]) -> String {
+    data.chunks_exact(2)
+        .map(|c| if c[1] == 0 && (0x20..0x7f).contains(&c[0]) { c[0] as char } else { ' ' })
+        .collect()
+}
+
+pub fn extract_iocs(data: &[u8]) -> Vec<ExtractedIoc> {
+    let mut iocs: Vec<ExtractedIoc> = Vec::new();
+    let mut push = |kind: IndicatorType, value: String, port: Option<u16>| {
+        if !iocs.iter().any(|i| i.value == value && i.port == port) {
+            iocs.push(ExtractedIoc { kind, value, port, known_malicious: false });
+        }
+    };
+
+    for (ip, port) in extract_sockaddrs(data) {
+        push(IndicatorType::IPv4, ip.to_string(), Some(port));
+    }
+
+    let odd = data.get(1..).unwrap_or_default();
+    for text in [ascii_view(data), utf16_view(data), utf16_view(odd)] {
+        let mut url_spans = Vec::new();
+        for m in URL_RE.find_iter(&text) {
+            url_spans.push(m.range());
+            push(IndicatorType::Url, m.as_str().to_string(), None);
+        }
+        let in_url = |start: usize| url_spans.iter().any(|r| r.contains(&start));
+
+        for m in IPV4_RE.find_iter(&text) {
+            if in_url(m.start()) {
+                continue;
+            }
+            if let Ok(ip) = m.as_str().parse::<Ipv4Addr>() {
+                if !ip.is_unspecified() && !ip.is_loopback() && !ip.is_broadcast() {
+                    push(IndicatorType::IPv4, ip.to_string(), None);
+                }
+            }
+        }
+        for m in DOMAIN_RE.find_iter(&text) {
+            if !in_url(m.start()) {
+                push(IndicatorType::Domain, m.as_str().to_lowercase(), None);
+            }
+        }
+    }
+
+    iocs
+}
+
+/// sockaddr_in dựng trong code:
+/// - x86: `push <ip>` (68 a b c d) + `push <port|AF_INET>` (68 02 00 p p)
+/// - x64: `mov r14, <sockaddr>` (49 BE 02 00 p p a b c d)
+pub fn extract_sockaddrs(data: &[u8]) -> Vec<(Ipv4Addr, u16)> {
+    let mut found = Vec::new();
+    for i in 0..data.len() {
+        let rest = &data[i..];
+        let parsed = if rest.len() >= 10 && rest[0] == 0x68 && rest[5] == 0x68 && rest[6] == 0x02 && rest[7] == 0x00 {
+            Some((Ipv4Addr::new(rest[1], rest[2], rest[3], rest[4]), u16::from_be_bytes([rest[8], rest[9]])))
+        } else if rest.len() >= 10 && rest[0] == 0x49 && rest[1] == 0xBE && rest[2] == 0x02 && rest[3] == 0x00 {
+            Some((Ipv4Addr::new(rest[6], rest[7], rest[8], rest[9]), u16::from_be_bytes([rest[4], rest[5]])))
+        } else {
+            None
+        };
+
+        if let Some((ip, port)) = parsed {
+            if port != 0 && !ip.is_unspecified() && !found.contains(&(ip, port)) {
+                found.push((ip, port));
+            }
+        }
+    }
+    found
+}
+
+fn extract_user_agents(data: &[u8]) -> Vec<String> {
+    USER_AGENT_RE.find_iter(&ascii_view(data))
+        .map(|m| m.as_str().trim_end().to_string())
+        .collect()
+}
+
+// ============================================================================
+// EXTERNAL INTEL
+// ============================================================================
+
+fn is_known(ioc: &ExtractedIoc) -> bool {
+    match ioc.kind {
+        IndicatorType::IPv4 => threat_feed::is_malicious_ip(&ioc.value),
+        IndicatorType::Domain => threat_feed::is_malicious_domain(&ioc.value),
+        IndicatorType::Url => threat_feed::is_malicious_url(&ioc.value),
+        _ => false,
+    }
+}
+
+/// IOC có cấu trúc C2 (URL, sockaddr public) → threat feed.
+/// Domain / IP trần chỉ hiển thị trong report (string rời dễ false positive).
+fn feed_intel(iocs: &[ExtractedIoc]) {
+    let now = chrono::Utc::now().timestamp();
+    for ioc in iocs {
+        if ioc.known_malicious {
+            continue;
+        }
+        match ioc.kind {
+            IndicatorType::Url => {}
+            IndicatorType::IPv4 if ioc.port.is_some() => match ioc.value.parse::<Ipv4Addr>() {
+                Ok(ip) if !ip.is_private() && !ip.is_link_local() => {}
+                _ => continue,
+            },
+            _ => continue,
+        }
+        if !FED.lock().insert(ioc.value.clone()) {
+            continue;
+        }
+
+        threat_feed::add_indicator(ThreatIndicator {
+            indicator_type: ioc.kind,
+            value: ioc.value.clone(),
+            threat_level: ThreatLevel::High,
+            source: SOURCE.to_string(),
+            first_seen: Some(now),
+            last_seen: Some(now),
+            tags: vec!["shellcode".to_string(), "c2".to_string()],
+            description: Some("Extracted from emulated shellcode".to_string()),
+        });
+        log::warn!("[SHELLCODE EMU] Added C2 indicator {} to threat feed", ioc.value);
+    }
+}
+
+// ============================================================================
+// EMULATOR
+// ============================================================================
+
+struct EmulationRun {
+    arch: String,
+    instructions: u64,
+    stop_reason: Option<String>,
+    /// Code region sau emulation (payload đã decode)
+    code: Vec<u8>,
+    stack: Vec<u8>,
+}
+
+#[cfg(feature = "shellcode-emulation")]
+mod emulator {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use unicorn_engine::{RegisterX86, Unicorn};
+    use unicorn_engine::unicorn_const::{Arch, HookType, Mode, Permission};
+
+    const CODE_BASE: u64 = 0x1000_0000;
+    const STACK_BASE: u64 = 0x2000_0000;
+    const STACK_SIZE: u64 = 0x10_0000;
+    const PAGE: u64 = 0x1000;
+
+    /// Chạy x86 rồi x64, giữ lần chạy được nhiều instruction hơn
+    pub fn run(data: &[u8]) -> Option<EmulationRun> {
+        let x86 = run_mode(data, Mode::MODE_32, "x86");
+        let x64 = run_mode(data, Mode::MODE_64, "x64");
+        match (x86, x64) {
+            (Some(a), Some(b)) => Some(if b.instructions > a.instructions { b } else { a }),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn run_mode(data: &[u8], mode: Mode, arch: &str) -> Option<EmulationRun> {
+        if data.is_empty() {
+            return None;
+        }
+        let code_size = (data.len() as u64 + PAGE - 1) / PAGE * PAGE;
+
+        let mut emu = Unicorn::new(Arch::X86, mode).ok()?;
+        emu.mem_map(CODE_BASE, code_size as usize, Permission::ALL).ok()?;
+        emu.mem_write(CODE_BASE, data).ok()?;
+        emu.mem_map(STACK_BASE, STACK_SIZE as usize, Permission::READ | Permission::WRITE).ok()?;
+
+        let sp = STACK_BASE + STACK_SIZE / 2;
+        let sp_reg = if mode == Mode::MODE_64 { RegisterX86::RSP } else { RegisterX86::ESP };
+        emu.reg_write(sp_reg, sp).ok()?;
+
+        let count = Rc::new(Cell::new(0u64));
+        let counter = count.clone();
+        emu.add_code_hook(CODE_BASE, CODE_BASE + code_size, move |_, _, _| {
+            counter.set(counter.get() + 1);
+        }).ok()?;
+        // PEB walk / API call → unmapped access: dừng thay vì crash
+        emu.add_mem_hook(HookType::MEM_UNMAPPED, 1, 0, |_, _, _, _, _| false).ok()?;
+
+        let stop_reason = emu.emu_start(CODE_BASE, CODE_BASE + data.len() as u64, EMULATION_TIMEOUT_US, MAX_INSTRUCTIONS)
+            .err()
+            .map(|e| format!("{:?}", e));
+
+        Some(EmulationRun {
+            arch: arch.to_string(),
+            instructions: count.get(),
+            stop_reason,
+            code: emu.mem_read_as_vec(CODE_BASE, data.len()).ok()?,
+            stack: emu.mem_read_as_vec(STACK_BASE, STACK_SIZE as usize).ok()?,
+        })
+    }
+}
+
+#[cfg(not(feature = "shellcode-emulation"))]
+mod emulator {
+    use super::EmulationRun;
+
+    pub fn run(_data: &[u8]) -> Option<EmulationRun> {
+        None
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_hashes() {
+        // Giá trị công khai của Metasploit block_api / Skape
+        assert_eq!(block_api_hash("kernel32.dll", "LoadLibraryA"), 0x0726_774C);
+        assert_eq!(function_hash("LoadLibraryA"), 0xEC0E_4E8E);
+    }
+
+    #[test]
+    fn test_resolve_api_hashes() {
+        // push 0x0726774C; call ebp
+        let code = [0x68, 0x4C, 0x77, 0x26, 0x07, 0xFF, 0xD5];
+        assert_eq!(resolve_api_hashes(&code), vec!["kernel32.dll!LoadLibraryA".to_string()]);
+    }
+
+    #[test]
+    fn test_extract_iocs() {
+        // reverse_tcp: push 0x0A01A8C0 (192.168.1.10); push 0x5C110002 (port 4444, AF_INET)
+        let mut data = vec![0x68, 0xC0, 0xA8, 0x01, 0x0A, 0x68, 0x02, 0x00, 0x11, 0x5C];
+        data.extend_from_slice(b"\x00http://evil.example.com:8080/a.bin\x00");
+        data.extend_from_slice(&"cdn.badhost.xyz".encode_utf16().flat_map(|u| u.to_le_bytes()).collect::<Vec<u8>>());
+
+        let iocs = extract_iocs(&data);
+        let values: Vec<(&str, Option<u16>)> = iocs.iter().map(|i| (i.value.as_str(), i.port)).collect();
+        assert!(values.contains(&("192.168.1.10", Some(4444))));
+        assert!(values.contains(&("http://evil.example.com:8080/a.bin", None)));
+        assert!(values.contains(&("cdn.badhost.xyz", None)));
+        // Host trong URL không bị tách thành domain riêng
+        assert!(!values.contains(&("evil.example.com", None)));
+    }
+}
@@ -0,0 +1,242 @@
+//! DLL Side-Loading Detection (T1574.002)
+//!
+//! Mục đích: Phát hiện executable có chữ ký load DLL không ký từ thư mục user-writable
+//!
+//! Kỹ thuật phổ biến: copy một exe hợp lệ (đã ký) vào %TEMP% / %APPDATA% cùng với
+//! `version.dll` / `dbghelp.dll` độc hại → Windows load DLL cạnh exe trước System32.
+//!
+//! Điều kiện alert (cả 4):
+//! 1. Executable có chữ ký hợp lệ (`process_intel::signature`)
+//! 2. Module có tên nằm trong danh sách sideload-prone
+//! 3. Module nằm trong thư mục user-writable (không phải System32 / Program Files)
+//! 4. Module không có chữ ký
+//!
+//! Alert được ghi vào history của `injection` → hiển thị qua `get_injection_alerts`.
+//!
+//! Mỗi lượt enumerate lại module của mọi process (DLL có thể được load muộn qua
+//! `LoadLibrary`), chỉ module chưa kiểm tra mới bị verify chữ ký.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+
+use crate::logic::process_intel::signature;
+use super::injection;
+use super::injection_types::{InjectionAlert, InjectionType};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// DLL hay bị lợi dụng để side-load (được load theo search order, không KnownDLLs)
+const SIDELOAD_PRONE_DLLS: &[&str] = &[
+    "version.dll", "dbghelp.dll", "dbgcore.dll", "winmm.dll", "wtsapi32.dll",
+    "cryptbase.dll", "dwmapi.dll", "uxtheme.dll", "userenv.dll", "secur32.dll",
+    "msimg32.dll", "dxgi.dll", "d3d11.dll", "winhttp.dll", "wininet.dll",
+    "iphlpapi.dll", "netapi32.dll", "mpclient.dll", "propsys.dll", "profapi.dll",
+    "libcurl.dll", "vcruntime140.dll", "msvcp140.dll", "sspicli.dll", "dpapi.dll",
+];
+
+/// Thư mục user-writable
+const WRITABLE_MARKERS: &[&str] = &[
+    "\\users\\", "\\appdata\\", "\\temp\\", "\\programdata\\", "\\downloads\\",
+    "\\desktop\\", "\\public\\", "\\windows\\tasks\\", "\\recycle.bin\\",
+];
+
+/// Thư mục hệ thống (chỉ admin ghi được) - không bao giờ alert
+const PROTECTED_MARKERS: &[&str] = &[
+    "\\windows\\system32\\", "\\windows\\syswow64\\", "\\windows\\winsxs\\",
+    "\\program files\\", "\\program files (x86)\\",
+];
+
+/// Confidence cơ bản; exe từ trusted publisher (vd Microsoft) → cao hơn,
+/// vì đó chính là binary attacker hay mượn
+const BASE_CONFIDENCE: u8 = 70;
+const TRUSTED_HOST_BONUS: u8 = 15;
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// Candidate module đã kiểm tra theo process: pid → (start time, modules).
+/// Start time khác → PID bị tái sử dụng, kiểm tra lại từ đầu.
+type CheckedModules = HashMap<u32, (u64, HashSet<PathBuf>)>;
+
+static CHECKED_MODULES: Lazy<Mutex<CheckedModules>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Scan module mới load của các process đang chạy. Trả về số alert mới.
+pub fn scan_running_processes() -> usize {
+    let mut system = sysinfo::System::new();
+    system.refresh_processes();
+
+    let mut alive = HashSet::new();
+    let mut total = 0;
+    for (pid, process) in system.processes() {
+        let pid = pid.as_u32();
+        alive.insert(pid);
+        if let Some(exe) = process.exe() {
+            total += scan_process(pid, process.start_time(), process.name(), exe).len();
+        }
+    }
+
+    CHECKED_MODULES.lock().retain(|pid, _| alive.contains(pid));
+    total
+}
+
+/// Kiểm tra module mới của 1 process; alert được ghi vào injection history
+pub fn scan_process(pid: u32, start_time: u64, name: &str, exe: &Path) -> Vec<InjectionAlert> {
+    let modules = platform::list_modules(pid);
+    let candidates = new_candidates(&mut CHECKED_MODULES.lock(), pid, start_time, modules);
+    if candidates.is_empty() {
+        return Vec::new();
+    }
+
+    let host = signature::verify_signature(exe).status;
+    if !host.is_signed() {
+        return Vec::new();
+    }
+
+    let mut alerts = Vec::new();
+    for module in candidates {
+        if signature::is_signed(&module) {
+            continue;
+        }
+        let confidence = if host.is_trusted() {
+            BASE_CONFIDENCE + TRUSTED_HOST_BONUS
+        } else {
+            BASE_CONFIDENCE
+        };
+        let dll = module.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+
+        let mut alert = InjectionAlert::new(pid, name, pid, &dll, InjectionType::DllSideLoading, confidence);
+        alert.description = format!(
+            "Signed {} ({}) loaded unsigned {} from {}",
+            name, pid, dll, module.display()
+        );
+        alerts.push(alert);
+    }
+
+    injection::record_alerts(&alerts);
+    alerts
+}
+
+/// Candidate trong `modules` chưa được kiểm tra cho process này
+fn new_candidates(checked: &mut CheckedModules, pid: u32, start_time: u64, modules: Vec<PathBuf>) -> Vec<PathBuf> {
+    let entry = checked.entry(pid).or_insert_with(|| (start_time, HashSet::new()));
+    if entry.0 != start_time {
+        *entry = (start_time, HashSet::new());
+    }
+    modules
+        .into_iter()
+        .filter(|m| is_sideload_candidate(m))
+        .filter(|m| entry.1.insert(m.clone()))
+        .collect()
+}
+
+/// Tên DLL sideload-prone + nằm ở thư mục user-writable
+pub fn is_sideload_candidate(module: &Path) -> bool {
+    let name = match module.file_name() {
+        Some(n) => n.to_string_lossy().to_lowercase(),
+        None => return false,
+    };
+    if !SIDELOAD_PRONE_DLLS.contains(&name.as_str()) {
+        return false;
+    }
+
+    let lower = module.to_string_lossy().to_lowercase();
+    !PROTECTED_MARKERS.iter().any(|m| lower.contains(m))
+        && WRITABLE_MARKERS.iter().any(|m| lower.contains(m))
+}
+
+// ============================================================================
+// MODULE ENUMERATION
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use windows::Win32::Foundation::{CloseHandle, HMODULE};
+    use windows::Win32::System::ProcessStatus::{EnumProcessModulesEx, GetModuleFileNameExW, LIST_MODULES_ALL};
+    use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
+
+    /// Danh sách module đã load (cả module 32-bit của process WOW64)
+    pub fn list_modules(pid: u32) -> Vec<PathBuf> {
+        let handle = match unsafe { OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, pid) } {
+            Ok(h) => h,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut modules = vec![HMODULE::default(); 1024];
+        let mut needed = 0u32;
+        let listed = unsafe {
+            EnumProcessModulesEx(
+                handle,
+                modules.as_mut_ptr(),
+                (modules.len() * std::mem::size_of::<HMODULE>()) as u32,
+                &mut needed,
+                LIST_MODULES_ALL,
+            )
+        };
+
+        let mut paths = Vec::new();
+        if listed.is_ok() {
+            modules.truncate((needed as usize / std::mem::size_of::<HMODULE>()).min(modules.len()));
+            for module in modules {
+                let mut buf = [0u16; 1024];
+                let len = unsafe { GetModuleFileNameExW(handle, module, &mut buf) } as usize;
+                if len > 0 {
+                    paths.push(PathBuf::from(String::from_utf16_lossy(&buf[..len])));
+                }
+            }
+        }
+
+        unsafe { let _ = CloseHandle(handle); }
+        paths
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::*;
+
+    pub fn list_modules(_pid: u32) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sideload_candidate() {
+        assert!(is_sideload_candidate(Path::new("C:\\Users\\bob\\AppData\\Local\\Temp\\x\\version.dll")));
+        assert!(is_sideload_candidate(Path::new("C:\\ProgramData\\Updater\\DBGHELP.DLL")));
+        assert!(!is_sideload_candidate(Path::new("C:\\Windows\\System32\\version.dll")));
+        assert!(!is_sideload_candidate(Path::new("C:\\Program Files\\App\\version.dll")));
+        assert!(!is_sideload_candidate(Path::new("C:\\Users\\bob\\AppData\\Local\\App\\app.dll")));
+    }
+
+    #[test]
+    fn test_new_candidates_rescan() {
+        let mut checked = CheckedModules::new();
+        let dll = PathBuf::from("C:\\Users\\bob\\AppData\\Local\\Temp\\x\\version.dll");
+        let late = PathBuf::from("C:\\Users\\bob\\AppData\\Local\\Temp\\x\\winmm.dll");
+        let system = PathBuf::from("C:\\Windows\\System32\\kernel32.dll");
+
+        assert_eq!(new_candidates(&mut checked, 10, 100, vec![dll.clone(), system]), vec![dll.clone()]);
+        // Lượt sau: module cũ bỏ qua, DLL load muộn vẫn được kiểm tra
+        assert_eq!(new_candidates(&mut checked, 10, 100, vec![dll.clone(), late.clone()]), vec![late]);
+        // PID tái sử dụng → kiểm tra lại
+        assert_eq!(new_candidates(&mut checked, 10, 200, vec![dll.clone()]), vec![dll]);
+    }
+}
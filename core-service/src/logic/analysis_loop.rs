@@ -5,19 +5,55 @@ use crate::logic::{collector, baseline, incident, events};
 use crate::logic::features::vector::FeatureVector;
 use crate::logic::dataset::DatasetRecord;
 use crate::logic::threat::ThreatClass;
-use crate::logic::advanced_detection::{injection, keylogger};
+use crate::logic::advanced_detection::{injection, keylogger, sideload, evasion};
+use crate::logic::telemetry::otel;
 
 // Track last check times
 static LAST_INJECTION_CHECK: AtomicU64 = AtomicU64::new(0);
 static LAST_KEYLOGGER_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_HASH_BLOCK_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_SIDELOAD_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_EVASION_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_COM_HIJACK_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_UAC_BYPASS_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_CREDENTIAL_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_WMI_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_BITS_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_MEMORY_SCAN: AtomicU64 = AtomicU64::new(0);
+static LAST_SURVEILLANCE_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_KERBEROAST_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_URL_REPUTATION_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_RULES_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_BEACONING_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_INTEL_MATCH_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_GEOIP_CHECK: AtomicU64 = AtomicU64::new(0);
+static LAST_EVENT_LOG_SYNC: AtomicU64 = AtomicU64::new(0);
 
 const INJECTION_CHECK_INTERVAL_MS: u64 = 10_000; // Check every 10 seconds
 const KEYLOGGER_CHECK_INTERVAL_MS: u64 = 30_000; // Check every 30 seconds
+const HASH_BLOCK_CHECK_INTERVAL_MS: u64 = 2_000; // Blocked hashes - check every 2 seconds
+const SIDELOAD_CHECK_INTERVAL_MS: u64 = 60_000; // DLL side-loading - new processes every 60 seconds
+const EVASION_CHECK_INTERVAL_MS: u64 = 30_000; // AMSI/ETW patching - check every 30 seconds
+const COM_HIJACK_CHECK_INTERVAL_MS: u64 = 60_000; // HKCU CLSID shadowing - check every 60 seconds
+const UAC_BYPASS_CHECK_INTERVAL_MS: u64 = 2_000; // UAC bypass keys are short-lived - check every 2 seconds
+const CREDENTIAL_CHECK_INTERVAL_MS: u64 = 5_000; // Browser credential stores - check every 5 seconds
+const WMI_CHECK_INTERVAL_MS: u64 = 60_000; // WMI subscriptions (queried in the background) - check every 60 seconds
+const BITS_CHECK_INTERVAL_MS: u64 = 60_000; // BITS jobs (job list queried in the background) - check every 60 seconds
+const MEMORY_SCAN_INTERVAL_MS: u64 = 30_000; // Scheduled memory scans - cycle every 30 seconds (rate limited per process)
+const SURVEILLANCE_CHECK_INTERVAL_MS: u64 = 15_000; // Screen/mic capture + egress - check every 15 seconds
+const KERBEROAST_CHECK_INTERVAL_MS: u64 = 15_000; // Kerberos/LDAP bursts - check every 15 seconds
+const URL_REPUTATION_CHECK_INTERVAL_MS: u64 = 3_000; // Browser domains vs threat feed - check every 3 seconds
+const RULES_CHECK_INTERVAL_MS: u64 = 2_000; // Behavioral rules (incl. sequences) on processes with new events - every 2 seconds
+const BEACONING_CHECK_INTERVAL_MS: u64 = 10_000; // Periodic outbound connections (C2 beaconing) - check every 10 seconds
+const INTEL_MATCH_CHECK_INTERVAL_MS: u64 = 5_000; // Connections / executables vs threat intel - check every 5 seconds
+const GEOIP_CHECK_INTERVAL_MS: u64 = 5_000; // Connections to high-risk countries (policy) - check every 5 seconds
+const EVENT_LOG_SYNC_INTERVAL_MS: u64 = 5_000; // High/Critical incidents → Windows Event Log - every 5 seconds
 
 pub fn start() {
     // Initialize detection modules
     injection::init();
     keylogger::init();
+    crate::logic::network::start_collectors();
 
     thread::spawn(move || {
         log::info!("Analysis Engine loop started (v2.3 - Advanced Detection)");
@@ -25,6 +61,23 @@ pub fn start() {
             // === ADVANCED DETECTION ===
             check_injection_patterns();
             check_keylogger_patterns();
+            check_blocked_hashes();
+            check_sideloading();
+            check_amsi_etw_tampering();
+            check_com_hijacking();
+            check_uac_bypass();
+            check_credential_theft();
+            check_wmi_persistence();
+            check_bits_abuse();
+            check_memory_schedule();
+            check_surveillance();
+            check_kerberoasting();
+            check_url_reputation();
+            check_behavioral_rules();
+            check_beaconing();
+            check_intel_matches();
+            check_high_risk_countries();
+            sync_event_log();
 
             let pending = collector::get_pending_summaries();
             if pending.is_empty() {
@@ -34,8 +87,17 @@ pub fn start() {
             }
 
             for summary in pending {
+                // Trace cho cả pipeline của summary (no-op khi tắt OTel)
+                let mut pipeline_span = otel::span("pipeline.summary");
+                pipeline_span.set_attr("summary.id", summary.id.clone());
+                // Thời gian summary nằm trong queue của collector
+                drop(otel::span_at("collector.queue_wait", Some(summary.created_at.into())));
+
                 // 1. Create FeatureVector wrapper
-                let fv = FeatureVector::from_values(summary.features);
+                let fv = {
+                    let _span = otel::span("features.build");
+                    FeatureVector::from_values(summary.features)
+                };
 
                 // 2. Mock AI Score / Retrieve from Cache
                 // For v1.0, we rely on baseline tags primarily if ML not loaded
@@ -69,10 +131,18 @@ pub fn start() {
                 };
 
                 // 6. Send to Incident Manager & Dataset Logger
-                incident::process_event(&record, &analysis.tags);
+                {
+                    let _span = otel::span("incident.process");
+                    incident::process_event(&record, &analysis.tags);
+                }
 
                 // LOGGING TO DISK (Crucial for Training)
-                crate::logic::dataset::log(record.clone());
+                {
+                    let _span = otel::span("dataset.log");
+                    crate::logic::dataset::log(record.clone());
+                }
+                pipeline_span.set_attr("score", analysis.final_score);
+                pipeline_span.set_attr("threat", format!("{:?}", record.threat));
 
                 // 7. Mark summary as processed in Collector
                 collector::mark_summary_processed(
@@ -83,6 +153,452 @@ pub fn start() {
     });
 }
 
+/// Kill processes whose executable hash was blocked (browser-child containment)
+fn check_blocked_hashes() {
+    let now = get_current_time_ms();
+    let last_check = LAST_HASH_BLOCK_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < HASH_BLOCK_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_HASH_BLOCK_CHECK.store(now, Ordering::Relaxed);
+
+    let handled = crate::logic::response::browser_child::enforce_hash_blocks();
+    if handled > 0 {
+        log::warn!("Responded to {} process(es) matching blocked hashes", handled);
+    }
+}
+
+/// Scan new processes for DLL side-loading (signed exe + unsigned DLL)
+fn check_sideloading() {
+    let now = get_current_time_ms();
+    let last_check = LAST_SIDELOAD_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < SIDELOAD_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_SIDELOAD_CHECK.store(now, Ordering::Relaxed);
+
+    let found = sideload::scan_running_processes();
+    if found > 0 {
+        log::warn!("[Advanced Detection] Found {} DLL side-loading alerts", found);
+    }
+}
+
+/// Scan processes for patched AmsiScanBuffer / EtwEventWrite
+fn check_amsi_etw_tampering() {
+    let now = get_current_time_ms();
+    let last_check = LAST_EVASION_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < EVASION_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_EVASION_CHECK.store(now, Ordering::Relaxed);
+
+    for alert in evasion::scan_all() {
+        log::warn!(
+            "[DEFENSE EVASION] {}!{} patched in {} (PID: {})",
+            alert.module, alert.function, alert.process_name, alert.pid
+        );
+        events::emit_threat_alert(&serde_json::json!({
+            "type": "DEFENSE_EVASION",
+            "pid": alert.pid,
+            "process_name": alert.process_name,
+            "module": alert.module,
+            "function": alert.function,
+            "patch_kind": alert.patch_kind,
+            "mitre_id": alert.mitre_id,
+            "timestamp": alert.timestamp
+        }));
+    }
+}
+
+/// Diff HKCU CLSID InprocServer32 entries for COM hijacking
+fn check_com_hijacking() {
+    let now = get_current_time_ms();
+    let last_check = LAST_COM_HIJACK_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < COM_HIJACK_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_COM_HIJACK_CHECK.store(now, Ordering::Relaxed);
+
+    for alert in crate::logic::behavioral_sigs::com_hijack::scan() {
+        events::emit_threat_alert(&serde_json::json!({
+            "type": "PERSISTENCE",
+            "mechanism": alert.mechanism.description(),
+            "location": alert.location,
+            "value_name": alert.value_name,
+            "value_data": alert.value_data,
+            "mitre_id": alert.mitre_technique,
+            "timestamp": alert.timestamp
+        }));
+    }
+}
+
+/// Correlate UAC bypass registry hijacks with elevated child processes
+fn check_uac_bypass() {
+    let now = get_current_time_ms();
+    let last_check = LAST_UAC_BYPASS_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < UAC_BYPASS_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_UAC_BYPASS_CHECK.store(now, Ordering::Relaxed);
+
+    for alert in crate::logic::behavioral_sigs::uac_bypass::scan() {
+        log::warn!(
+            "[UAC BYPASS] {} (PID: {}) -> {} (PID: {}) via {}",
+            alert.trigger_process, alert.trigger_pid, alert.child_name, alert.child_pid, alert.technique
+        );
+        events::emit_threat_alert(&serde_json::json!({
+            "type": "UAC_BYPASS",
+            "pid": alert.child_pid,
+            "process_name": alert.child_name,
+            "trigger_process": alert.trigger_process,
+            "hijack_key": alert.hijack_key,
+            "confidence": alert.confidence,
+            "mitre_id": alert.mitre_id,
+            "timestamp": alert.timestamp
+        }));
+    }
+}
+
+/// Detect non-browser processes touching browser credential stores
+fn check_credential_theft() {
+    let now = get_current_time_ms();
+    let last_check = LAST_CREDENTIAL_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < CREDENTIAL_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_CREDENTIAL_CHECK.store(now, Ordering::Relaxed);
+
+    for alert in crate::logic::advanced_detection::credential_theft::scan() {
+        log::warn!(
+            "[CREDENTIAL THEFT] {} (PID: {}) accessed {} ({})",
+            alert.process_name, alert.pid, alert.target, alert.method
+        );
+        events::emit_threat_alert(&serde_json::json!({
+            "type": "CREDENTIAL_THEFT",
+            "pid": alert.pid,
+            "process_name": alert.process_name,
+            "target": alert.target,
+            "browser": alert.browser,
+            "response": alert.response,
+            "mitre_id": alert.mitre_id,
+            "timestamp": alert.timestamp
+        }));
+    }
+}
+
+/// Detect new permanent WMI event subscriptions
+fn check_wmi_persistence() {
+    let now = get_current_time_ms();
+    let last_check = LAST_WMI_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < WMI_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_WMI_CHECK.store(now, Ordering::Relaxed);
+
+    for alert in crate::logic::behavioral_sigs::wmi_persistence::scan() {
+        events::emit_threat_alert(&serde_json::json!({
+            "type": "PERSISTENCE",
+            "mechanism": alert.mechanism.description(),
+            "location": alert.location,
+            "consumer_class": alert.value_name,
+            "command_line": alert.value_data,
+            "mitre_id": alert.mitre_technique,
+            "timestamp": alert.timestamp
+        }));
+    }
+}
+
+/// Detect BITS transfer jobs to external URLs
+fn check_bits_abuse() {
+    let now = get_current_time_ms();
+    let last_check = LAST_BITS_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < BITS_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_BITS_CHECK.store(now, Ordering::Relaxed);
+
+    for alert in crate::logic::behavioral_sigs::bits_abuse::scan() {
+        log::warn!(
+            "[BITS ABUSE] {} via {} (intel match: {})",
+            alert.url, alert.source, alert.intel_match
+        );
+        events::emit_threat_alert(&serde_json::json!({
+            "type": "BITS_ABUSE",
+            "pid": alert.pid,
+            "process_name": alert.process_name,
+            "job_id": alert.job_id,
+            "url": alert.url,
+            "intel_match": alert.intel_match,
+            "mitre_id": alert.mitre_id,
+            "timestamp": alert.timestamp
+        }));
+    }
+}
+
+/// Background memory scans of high-risk processes (shellcode, sleep-masked beacons)
+fn check_memory_schedule() {
+    let now = get_current_time_ms();
+    let last_check = LAST_MEMORY_SCAN.load(Ordering::Relaxed);
+
+    if now - last_check < MEMORY_SCAN_INTERVAL_MS {
+        return;
+    }
+    LAST_MEMORY_SCAN.store(now, Ordering::Relaxed);
+
+    for alert in crate::logic::advanced_detection::memory_scheduler::run_cycle() {
+        log::warn!(
+            "[MEMORY SCAN] {} (PID: {}): {:?} {:?}",
+            alert.process_name, alert.pid, alert.indicators, alert.shellcode
+        );
+        events::emit_threat_alert(&serde_json::json!({
+            "type": "MEMORY_SCAN",
+            "pid": alert.pid,
+            "process_name": alert.process_name,
+            "indicators": alert.indicators,
+            "shellcode": alert.shellcode,
+            "severity": alert.severity,
+            "timestamp": alert.timestamp
+        }));
+    }
+}
+
+/// Correlate screen/mic capture with network egress
+fn check_surveillance() {
+    let now = get_current_time_ms();
+    let last_check = LAST_SURVEILLANCE_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < SURVEILLANCE_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_SURVEILLANCE_CHECK.store(now, Ordering::Relaxed);
+
+    let networked = crate::logic::advanced_detection::memory_scheduler::network_active_pids();
+    for alert in keylogger::check_surveillance(&networked) {
+        log::warn!(
+            "[SURVEILLANCE] {} (PID: {}): {:?}",
+            alert.process_name, alert.pid, alert.capabilities
+        );
+        events::emit_threat_alert(&serde_json::json!({
+            "type": "SURVEILLANCE",
+            "pid": alert.pid,
+            "process_name": alert.process_name,
+            "capabilities": alert.capabilities,
+            "indicators": alert.indicators,
+            "confidence": alert.confidence,
+            "mitre_ids": alert.mitre_ids,
+            "timestamp": alert.timestamp
+        }));
+    }
+}
+
+/// Detect kerberoasting-style Kerberos TGS / LDAP SPN bursts
+fn check_kerberoasting() {
+    let now = get_current_time_ms();
+    let last_check = LAST_KERBEROAST_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < KERBEROAST_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_KERBEROAST_CHECK.store(now, Ordering::Relaxed);
+
+    for alert in crate::logic::behavioral_sigs::kerberoast::check() {
+        log::warn!(
+            "[KERBEROASTING] {} (PID: {}): {:?}",
+            alert.process_name, alert.pid, alert.indicators
+        );
+        events::emit_threat_alert(&serde_json::json!({
+            "type": "KERBEROASTING",
+            "pid": alert.pid,
+            "process_name": alert.process_name,
+            "domain_controllers": alert.domain_controllers,
+            "indicators": alert.indicators,
+            "confidence": alert.confidence,
+            "mitre_id": alert.mitre_id,
+            "timestamp": alert.timestamp
+        }));
+    }
+}
+
+/// Check browser connections against threat intel (phishing / malware sites)
+fn check_url_reputation() {
+    let now = get_current_time_ms();
+    let last_check = LAST_URL_REPUTATION_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < URL_REPUTATION_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_URL_REPUTATION_CHECK.store(now, Ordering::Relaxed);
+
+    for alert in crate::logic::network::url_reputation::check() {
+        log::warn!(
+            "[URL REPUTATION] {} site {} via {} (PID: {}, blocked: {})",
+            alert.category, alert.domain.as_deref().unwrap_or(&alert.remote_ip),
+            alert.browser_name, alert.pid, alert.blocked
+        );
+        events::emit_threat_alert(&serde_json::json!({
+            "type": "MALICIOUS_SITE",
+            "category": alert.category,
+            "domain": alert.domain,
+            "remote_ip": alert.remote_ip,
+            "pid": alert.pid,
+            "process_name": alert.process_name,
+            "browser_pid": alert.browser_pid,
+            "browser_name": alert.browser_name,
+            "blocked": alert.blocked,
+            "timestamp": alert.timestamp
+        }));
+    }
+}
+
+/// Evaluate behavioral rules (single-sample + sequence conditions) on processes with new events
+fn check_behavioral_rules() {
+    let now = get_current_time_ms();
+    let last_check = LAST_RULES_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < RULES_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_RULES_CHECK.store(now, Ordering::Relaxed);
+
+    for rule_match in crate::logic::behavioral_sigs::rules::check_processes() {
+        log::warn!(
+            "[BEHAVIORAL RULE] {} matched {:?} (PID: {:?}): {:?}",
+            rule_match.rule_id, rule_match.context.process_name, rule_match.context.process_pid,
+            rule_match.matched_conditions
+        );
+        events::emit_threat_alert(&serde_json::json!({
+            "type": "BEHAVIORAL_RULE",
+            "rule_id": rule_match.rule_id,
+            "rule_name": rule_match.rule_name,
+            "severity": rule_match.severity.as_str(),
+            "pid": rule_match.context.process_pid,
+            "process_name": rule_match.context.process_name,
+            "matched_conditions": rule_match.matched_conditions,
+            "mitre_id": rule_match.mitre_technique,
+            "timestamp": rule_match.timestamp
+        }));
+    }
+}
+
+/// Feed new connections into the beaconing detector and alert on periodic C2-like traffic
+fn check_beaconing() {
+    let now = get_current_time_ms();
+    let last_check = LAST_BEACONING_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < BEACONING_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_BEACONING_CHECK.store(now, Ordering::Relaxed);
+
+    for alert in crate::logic::behavioral_sigs::beaconing::check() {
+        log::warn!(
+            "[BEACONING] {:?} → {} every {:.0}s (jitter {:.1}%, {} samples, severity {:?})",
+            alert.process_name, alert.endpoint, alert.interval_seconds, alert.jitter_percent,
+            alert.sample_count, alert.severity
+        );
+        events::emit_threat_alert(&serde_json::json!({
+            "type": "BEACONING",
+            "endpoint": alert.endpoint,
+            "ip": alert.ip,
+            "port": alert.port,
+            "interval_seconds": alert.interval_seconds,
+            "jitter_percent": alert.jitter_percent,
+            "sample_count": alert.sample_count,
+            "pid": alert.process_pid,
+            "process_name": alert.process_name,
+            "severity": alert.severity,
+            "first_seen": alert.first_seen,
+            "last_seen": alert.last_seen,
+            "geo": alert.geo
+        }));
+    }
+}
+
+/// Check new connections / executables against threat intel → incidents
+fn check_intel_matches() {
+    let now = get_current_time_ms();
+    let last_check = LAST_INTEL_MATCH_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < INTEL_MATCH_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_INTEL_MATCH_CHECK.store(now, Ordering::Relaxed);
+
+    for m in crate::logic::external_intel::intel_matcher::check() {
+        log::warn!(
+            "[THREAT INTEL] {} {} matched ({}, {}) by {} (PID: {})",
+            m.kind, m.indicator, m.source, m.threat_level.as_str(), m.process_name, m.pid
+        );
+        events::emit_threat_alert(&serde_json::json!({
+            "type": "THREAT_INTEL_MATCH",
+            "kind": m.kind,
+            "indicator": m.indicator,
+            "source": m.source,
+            "threat_level": m.threat_level.as_str(),
+            "confidence": m.confidence,
+            "pid": m.pid,
+            "process_name": m.process_name,
+            "exe_path": m.exe_path,
+            "remote_ip": m.remote_ip,
+            "remote_port": m.remote_port,
+            "geo": m.geo,
+            "timestamp": m.timestamp
+        }));
+    }
+}
+
+/// Check new connections against the high-risk country policy (GeoIP)
+fn check_high_risk_countries() {
+    let now = get_current_time_ms();
+    let last_check = LAST_GEOIP_CHECK.load(Ordering::Relaxed);
+
+    if now - last_check < GEOIP_CHECK_INTERVAL_MS {
+        return;
+    }
+    LAST_GEOIP_CHECK.store(now, Ordering::Relaxed);
+
+    for alert in crate::logic::network::geoip::check() {
+        log::warn!(
+            "[GEOIP] {} (PID: {}) → {}:{} in high-risk country {}",
+            alert.process_name, alert.pid, alert.remote_ip, alert.remote_port, alert.country_code
+        );
+        events::emit_threat_alert(&serde_json::json!({
+            "type": "HIGH_RISK_COUNTRY",
+            "country_code": alert.country_code,
+            "geo": alert.geo,
+            "remote_ip": alert.remote_ip,
+            "remote_port": alert.remote_port,
+            "pid": alert.pid,
+            "process_name": alert.process_name,
+            "timestamp": alert.timestamp
+        }));
+    }
+}
+
+/// Ghi incident High/Critical mới (hoặc vừa escalate) vào Windows Event Log
+fn sync_event_log() {
+    let now = get_current_time_ms();
+    let last_check = LAST_EVENT_LOG_SYNC.load(Ordering::Relaxed);
+
+    if now - last_check < EVENT_LOG_SYNC_INTERVAL_MS {
+        return;
+    }
+    LAST_EVENT_LOG_SYNC.store(now, Ordering::Relaxed);
+
+    let written = crate::logic::telemetry::eventlog::sync_incidents();
+    if written > 0 {
+        log::info!("[EVENTLOG] Wrote {} incident(s) to Windows Event Log", written);
+    }
+}
+
 /// Check running processes for injection patterns
 fn check_injection_patterns() {
     let now = get_current_time_ms();
@@ -315,8 +315,10 @@ pub fn analyze_summary(
     features: &FeatureVector,
     ml_score: f32,
 ) -> AnalysisResult {
+    let mut span = crate::logic::telemetry::otel::span("baseline.analyze");
     let tags = compare_with_baseline(features);
     let tag_strings: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+    span.set_attr("tags", tag_strings.len());
 
     // Helper calculate score (simplified)
     let tag_score = calculate_tag_score(&tags);
@@ -694,6 +696,29 @@ pub fn rollback_to_snapshot(snapshot_id: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Thay baseline bằng bản từ cloud (golden baseline hoặc bản merge)
+/// Snapshot baseline hiện tại trước để rollback được
+pub fn apply_cloud_baseline(baseline: VersionedBaseline, details: &str) -> Result<(), String> {
+    validate::validate_baseline(&baseline).map_err(|e| e.to_string())?;
+    init();
+
+    if let Some(current) = GLOBAL_BASELINE.read().as_ref() {
+        history::create_snapshot(current, SnapshotTrigger::BeforeCloudSync);
+    }
+
+    let path = storage::get_default_baseline_path();
+    storage::save_baseline(&baseline, &path)
+        .map_err(|e| format!("Failed to save cloud baseline: {}", e))?;
+    *GLOBAL_BASELINE.write() = Some(baseline.clone());
+
+    drift::reset();
+    drift::record_baseline(&baseline);
+    audit::log(AuditLogEntry::new(AuditAction::CloudBaselineApplied).with_details(details));
+
+    log::info!("Baseline replaced from cloud ({})", details);
+    Ok(())
+}
+
 /// Rollback to N hours ago
 pub fn rollback_hours_ago(hours: u32) -> Result<(), String> {
     let baseline = history::rollback_hours_ago(hours)?;
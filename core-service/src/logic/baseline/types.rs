@@ -364,6 +364,7 @@ pub enum AuditAction {
     LearningPaused,       // Learning bị tạm dừng
     LearningResumed,      // Learning được resume
     SnapshotCreated,      // Tạo snapshot mới
+    CloudBaselineApplied, // Áp baseline từ cloud (golden / merge)
 }
 
 impl AuditLogEntry {
@@ -438,6 +439,7 @@ pub enum SnapshotTrigger {
     ManualBackup,         // User request backup
     BeforeReset,          // Trước khi reset baseline
     DriftAlert,           // Khi phát hiện drift bất thường
+    BeforeCloudSync,      // Trước khi áp baseline từ cloud (golden / merge)
 }
 
 impl BaselineSnapshot {
@@ -6,14 +6,20 @@
 //! - Kết nối đến cùng endpoint nhiều lần
 //! - Intervals đều đặn (low jitter)
 //! - Thường xảy ra vào ban đêm
+//!
+//! Nguồn dữ liệu: connection mới từ `network::connections` (mỗi lần reconnect
+//! = 1 sample), endpoint = domain (DNS cache) hoặc IP. `check()` được gọi từ
+//! analysis loop → BeaconAlert mới thành incident (và cloud sync qua incident).
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::IpAddr;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use once_cell::sync::Lazy;
 use chrono::Utc;
 
 use super::types::{BeaconAlert, BeaconSeverity};
+use crate::logic::incident::{self, Severity};
+use crate::logic::network::{self, dns, geoip};
 
 // ============================================================================
 // CONSTANTS
@@ -46,6 +52,12 @@ const KNOWN_C2_INTERVALS: &[(f32, f32)] = &[
     (3600.0, 180.0), // 1 hour
 ];
 
+/// Endpoint không có connection mới trong khoảng này → bỏ history
+const STALE_ENDPOINT_SECS: i64 = 6 * 3600;
+
+/// Không báo lại cùng endpoint trong khoảng này (trừ khi severity tăng)
+const REPORT_COOLDOWN_SECS: i64 = 3600;
+
 // ============================================================================
 // STATE
 // ============================================================================
@@ -53,6 +65,18 @@ const KNOWN_C2_INTERVALS: &[(f32, f32)] = &[
 static DETECTOR: Lazy<RwLock<BeaconingDetector>> =
     Lazy::new(|| RwLock::new(BeaconingDetector::new()));
 
+struct CollectorState {
+    /// Seq của connection event cuối đã đọc
+    cursor: u64,
+    /// endpoint → (severity đã báo, lúc báo)
+    reported: HashMap<String, (BeaconSeverity, i64)>,
+}
+
+static COLLECTOR: Lazy<Mutex<CollectorState>> = Lazy::new(|| Mutex::new(CollectorState {
+    cursor: network::latest_seq(),
+    reported: HashMap::new(),
+}));
+
 // ============================================================================
 // BEACONING DETECTOR
 // ============================================================================
@@ -85,7 +109,12 @@ impl BeaconingDetector {
     pub fn record_connection(&mut self, endpoint: &str, ip: Option<IpAddr>, port: Option<u16>,
                              process_name: Option<&str>, process_pid: Option<u32>) {
         let now = Utc::now().timestamp();
+        self.record_connection_at(endpoint, ip, port, process_name, process_pid, now);
+    }
 
+    /// Record a connection observed at `timestamp` (unix seconds)
+    pub fn record_connection_at(&mut self, endpoint: &str, ip: Option<IpAddr>, port: Option<u16>,
+                                process_name: Option<&str>, process_pid: Option<u32>, timestamp: i64) {
         let history = self.connections.entry(endpoint.to_string()).or_insert_with(|| {
             ConnectionHistory {
                 timestamps: Vec::new(),
@@ -96,7 +125,7 @@ impl BeaconingDetector {
             }
         });
 
-        history.timestamps.push(now);
+        history.timestamps.push(timestamp);
 
         // Update process info if provided
         if let Some(name) = process_name {
@@ -166,6 +195,7 @@ impl BeaconingDetector {
                 first_seen: *history.timestamps.first().unwrap_or(&0),
                 last_seen: *history.timestamps.last().unwrap_or(&0),
                 severity,
+                geo: None,
             })
         } else {
             None
@@ -233,6 +263,13 @@ impl BeaconingDetector {
     pub fn clear_all(&mut self) {
         self.connections.clear();
     }
+
+    /// Bỏ endpoint không còn hoạt động
+    pub fn prune_stale(&mut self, now: i64) {
+        self.connections.retain(|_, h| {
+            h.timestamps.last().map_or(false, |last| now - *last < STALE_ENDPOINT_SECS)
+        });
+    }
 }
 
 impl Default for BeaconingDetector {
@@ -251,6 +288,101 @@ pub fn record_connection(endpoint: &str, ip: Option<IpAddr>, port: Option<u16>,
     DETECTOR.write().record_connection(endpoint, ip, port, process_name, process_pid);
 }
 
+/// Đọc connection mới từ network collector, cập nhật history, trả về beacon mới
+/// (mỗi endpoint báo 1 lần / cooldown, báo lại nếu severity tăng)
+pub fn check() -> Vec<BeaconAlert> {
+    let events = {
+        let mut collector = COLLECTOR.lock();
+        let events = network::events_since(collector.cursor);
+        if let Some(last) = events.last() {
+            collector.cursor = last.seq;
+        }
+        events
+    };
+    let now = Utc::now().timestamp();
+
+    let mut touched = HashSet::new();
+    {
+        let mut detector = DETECTOR.write();
+        for event in events.iter().filter(|e| e.is_remote()) {
+            let endpoint = dns::domains_for_ip(&event.remote_ip)
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| event.remote_ip.to_string());
+            detector.record_connection_at(
+                &endpoint,
+                Some(event.remote_ip),
+                Some(event.remote_port),
+                Some(&event.process_name),
+                Some(event.pid),
+                event.timestamp / 1000,
+            );
+            touched.insert(endpoint);
+        }
+        detector.prune_stale(now);
+    }
+
+    let mut alerts: Vec<BeaconAlert> = {
+        let detector = DETECTOR.read();
+        touched.iter().filter_map(|e| detector.check_endpoint(e)).collect()
+    };
+
+    {
+        let mut collector = COLLECTOR.lock();
+        collector.reported.retain(|_, (_, at)| now - *at < REPORT_COOLDOWN_SECS);
+        alerts.retain(|alert| {
+            let escalated = collector.reported.get(&alert.endpoint)
+                .map_or(true, |(severity, _)| (alert.severity as u8) > (*severity as u8));
+            if escalated {
+                collector.reported.insert(alert.endpoint.clone(), (alert.severity, now));
+            }
+            escalated
+        });
+    }
+
+    for alert in &mut alerts {
+        alert.geo = alert.ip.and_then(geoip::lookup);
+        raise_incident(alert);
+    }
+    alerts
+}
+
+/// Beacon → incident. Low (update check, heartbeat hợp lệ) chỉ alert, không tạo incident.
+fn raise_incident(alert: &BeaconAlert) {
+    let severity = match alert.severity {
+        BeaconSeverity::Low => return,
+        BeaconSeverity::Medium => Severity::Medium,
+        BeaconSeverity::High => Severity::High,
+        BeaconSeverity::Critical => Severity::Critical,
+    };
+    let process = alert.process_name.as_deref().unwrap_or("unknown");
+    let target = match (alert.ip, alert.port) {
+        (Some(ip), Some(port)) if ip.to_string() != alert.endpoint => format!("{} ({}:{})", alert.endpoint, ip, port),
+        (_, Some(port)) => format!("{}:{}", alert.endpoint, port),
+        _ => alert.endpoint.clone(),
+    };
+
+    let mut tags = vec!["BEACONING".to_string()];
+    let mut description = format!(
+        "{} (PID {}) connected to {} {} times every {:.0}s (jitter {:.1}%)",
+        process,
+        alert.process_pid.map(|p| p.to_string()).unwrap_or_else(|| "?".to_string()),
+        target, alert.sample_count, alert.interval_seconds, alert.jitter_percent
+    );
+    if let Some(geo) = &alert.geo {
+        tags.extend(geo.tags());
+        description.push_str(&format!(" — destination: {}", geo.describe()));
+    }
+
+    incident::raise_detection(
+        &format!("C2 beaconing: {} → {}", process, alert.endpoint),
+        severity,
+        &tags,
+        &["T1071"],
+        &description,
+    );
+}
+
 /// Check if an endpoint shows beaconing behavior
 pub fn check_beaconing(endpoint: &str) -> Option<BeaconAlert> {
     DETECTOR.read().check_endpoint(endpoint)
@@ -364,4 +496,21 @@ mod tests {
         // Should not detect beaconing due to high jitter
         assert!(alert.is_none() || alert.unwrap().jitter_percent > 15.0);
     }
+
+    #[test]
+    fn test_observed_timestamps_and_prune() {
+        let mut detector = BeaconingDetector::new();
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+
+        // Connection thực tế: timestamp lấy từ event, không phải lúc record
+        for i in 0..6 {
+            detector.record_connection_at("203.0.113.7", Some(ip), Some(443), Some("svc.exe"), Some(42), 5_000 + i * 300);
+        }
+        let alert = detector.check_endpoint("203.0.113.7").unwrap();
+        assert!((alert.interval_seconds - 300.0).abs() < 1.0);
+        assert_eq!(alert.first_seen, 5_000);
+
+        detector.prune_stale(5_000 + 5 * 300 + STALE_ENDPOINT_SECS);
+        assert!(detector.check_endpoint("203.0.113.7").is_none());
+    }
 }
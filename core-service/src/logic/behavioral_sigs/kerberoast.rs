@@ -0,0 +1,317 @@
+//! Kerberoasting Detection (T1558.003)
+//!
+//! Mục đích: Phát hiện process trên workstation xin hàng loạt TGS ticket / query
+//! SPN qua LDAP trong thời gian ngắn (Rubeus, Invoke-Kerberoast, GetUserSPNs, ...)
+//!
+//! Nguồn: `logic::network` connection events (port 88 / 389 / 636 / 3268 / 3269).
+//!
+//! 3 tín hiệu trong cửa sổ `WINDOW_MS`:
+//! 1. Raw Kerberos client: process không phải lsass tự nói chuyện với KDC (port 88)
+//! 2. LDAP burst: nhiều connection LDAP/GC từ process không phải LDAP client hợp lệ
+//!    (ngưỡng thấp hơn cho scripting host)
+//! 3. lsass TGS burst: lsass mở nhiều connection tới KDC cùng lúc với LDAP burst
+//!    → TGS request do process đó kích hoạt qua SSPI
+//!
+//! Connection sống ngắn có thể lọt giữa 2 poll → ngưỡng là burst, không phải đếm chính xác.
+
+use std::collections::{HashMap, VecDeque};
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::incident::{self, DetectionContext, Severity};
+use crate::logic::network::{self, ConnectionEvent};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const MITRE_ID: &str = "T1558.003";
+const MAX_ALERTS: usize = 500;
+
+/// Cửa sổ trượt
+const WINDOW_MS: i64 = 60_000;
+/// Không báo lại cùng PID trong khoảng này
+const REPORT_COOLDOWN_MS: i64 = 10 * 60_000;
+
+const KERBEROS_PORT: u16 = 88;
+const LDAP_PORTS: &[u16] = &[389, 636, 3268, 3269];
+
+/// Raw Kerberos connection từ non-lsass
+const RAW_KERBEROS_THRESHOLD: usize = 3;
+/// LDAP connection từ process thường
+const LDAP_BURST_THRESHOLD: usize = 20;
+/// LDAP connection từ scripting host
+const SCRIPT_LDAP_THRESHOLD: usize = 3;
+/// lsass → KDC connection đi kèm LDAP burst
+const LSASS_TGS_THRESHOLD: usize = 10;
+
+/// Process hệ thống được phép nói chuyện với KDC
+const KERBEROS_CLIENTS: &[&str] = &["lsass.exe", "system"];
+
+/// LDAP client hợp lệ (GPO, domain join, Outlook address book, ...)
+const LDAP_CLIENTS: &[&str] = &[
+    "lsass.exe", "system", "svchost.exe", "services.exe", "gpsvc.exe", "dsregcmd.exe",
+    "outlook.exe", "ccmexec.exe", "msmpeng.exe", "dsac.exe", "mmc.exe",
+];
+
+const SCRIPT_HOSTS: &[&str] = &[
+    "powershell.exe", "pwsh.exe", "cscript.exe", "wscript.exe", "rundll32.exe", "mshta.exe",
+];
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KerberoastAlert {
+    pub pid: u32,
+    pub process_name: String,
+    /// Connection port 88 của chính process
+    pub kerberos_connections: usize,
+    /// Connection LDAP/GC của process
+    pub ldap_connections: usize,
+    /// Connection lsass → KDC cùng cửa sổ (0 nếu không có LDAP burst)
+    pub lsass_tgs_connections: usize,
+    /// DC / KDC bị query
+    pub domain_controllers: Vec<String>,
+    pub indicators: Vec<String>,
+    pub confidence: u8,
+    pub mitre_id: String,
+    pub timestamp: i64,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+struct KerberoastState {
+    cursor: u64,
+    /// Connection Kerberos / LDAP trong cửa sổ
+    window: VecDeque<ConnectionEvent>,
+    /// pid → lần báo cuối (ms)
+    reported: HashMap<u32, i64>,
+}
+
+static STATE: Lazy<Mutex<KerberoastState>> = Lazy::new(|| Mutex::new(KerberoastState {
+    cursor: network::latest_seq(),
+    window: VecDeque::new(),
+    reported: HashMap::new(),
+}));
+static ALERTS: Lazy<Mutex<Vec<KerberoastAlert>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Đọc connection mới từ network collector, đánh giá cửa sổ. Trả về alert mới.
+pub fn check() -> Vec<KerberoastAlert> {
+    let now = Utc::now().timestamp_millis();
+    let alerts = {
+        let mut state = STATE.lock();
+        let events = network::events_since(state.cursor);
+        if let Some(last) = events.last() {
+            state.cursor = last.seq;
+        }
+        state.window.extend(events.into_iter().filter(is_relevant));
+        while state.window.front().map(|e| now - e.timestamp > WINDOW_MS).unwrap_or(false) {
+            state.window.pop_front();
+        }
+
+        let candidates = evaluate(state.window.make_contiguous(), now);
+        state.reported.retain(|_, at| now - *at < REPORT_COOLDOWN_MS);
+        let mut fresh = Vec::new();
+        for alert in candidates {
+            if !state.reported.contains_key(&alert.pid) {
+                state.reported.insert(alert.pid, now);
+                fresh.push(alert);
+            }
+        }
+        fresh
+    };
+
+    for alert in &alerts {
+        raise_incident(alert);
+    }
+    if !alerts.is_empty() {
+        let mut history = ALERTS.lock();
+        history.extend(alerts.iter().cloned());
+        let overflow = history.len().saturating_sub(MAX_ALERTS);
+        history.drain(..overflow);
+    }
+    alerts
+}
+
+pub fn get_recent_alerts(limit: usize) -> Vec<KerberoastAlert> {
+    ALERTS.lock().iter().rev().take(limit).cloned().collect()
+}
+
+// ============================================================================
+// EVALUATION
+// ============================================================================
+
+fn is_relevant(event: &ConnectionEvent) -> bool {
+    event.is_remote() && (event.remote_port == KERBEROS_PORT || LDAP_PORTS.contains(&event.remote_port))
+}
+
+/// Đánh giá connection Kerberos/LDAP trong cửa sổ → alert theo PID
+pub fn evaluate(window: &[ConnectionEvent], now: i64) -> Vec<KerberoastAlert> {
+    struct Counts<'a> {
+        name: &'a str,
+        kerberos: usize,
+        ldap: usize,
+        dcs: Vec<String>,
+    }
+
+    let mut per_pid: HashMap<u32, Counts> = HashMap::new();
+    let mut lsass_tgs = 0;
+
+    for event in window.iter().filter(|e| now - e.timestamp <= WINDOW_MS) {
+        let name = event.process_name.to_lowercase();
+        if event.remote_port == KERBEROS_PORT && KERBEROS_CLIENTS.contains(&name.as_str()) {
+            lsass_tgs += 1;
+            continue;
+        }
+
+        let counts = per_pid.entry(event.pid).or_insert_with(|| Counts {
+            name: &event.process_name,
+            kerberos: 0,
+            ldap: 0,
+            dcs: Vec::new(),
+        });
+        if event.remote_port == KERBEROS_PORT {
+            counts.kerberos += 1;
+        } else {
+            counts.ldap += 1;
+        }
+        let dc = event.remote_ip.to_string();
+        if !counts.dcs.contains(&dc) {
+            counts.dcs.push(dc);
+        }
+    }
+
+    let mut alerts = Vec::new();
+    for (pid, counts) in per_pid {
+        let lower = counts.name.to_lowercase();
+        let is_script = SCRIPT_HOSTS.contains(&lower.as_str());
+        let ldap_threshold = if is_script { SCRIPT_LDAP_THRESHOLD } else { LDAP_BURST_THRESHOLD };
+        let ldap_burst = !LDAP_CLIENTS.contains(&lower.as_str()) && counts.ldap >= ldap_threshold;
+        let raw_kerberos = counts.kerberos >= RAW_KERBEROS_THRESHOLD;
+        if !ldap_burst && !raw_kerberos {
+            continue;
+        }
+
+        let mut indicators = Vec::new();
+        let mut confidence: u8 = 0;
+        if raw_kerberos {
+            indicators.push(format!("{} direct Kerberos (port 88) connections", counts.kerberos));
+            confidence += 60;
+        }
+        if ldap_burst {
+            indicators.push(format!("{} LDAP/GC connections in {}s", counts.ldap, WINDOW_MS / 1000));
+            confidence += if is_script { 40 } else { 30 };
+        }
+        let lsass_correlated = ldap_burst && lsass_tgs >= LSASS_TGS_THRESHOLD;
+        if lsass_correlated {
+            indicators.push(format!("{} concurrent lsass → KDC requests (TGS burst)", lsass_tgs));
+            confidence += 30;
+        }
+
+        alerts.push(KerberoastAlert {
+            pid,
+            process_name: counts.name.to_string(),
+            kerberos_connections: counts.kerberos,
+            ldap_connections: counts.ldap,
+            lsass_tgs_connections: if lsass_correlated { lsass_tgs } else { 0 },
+            domain_controllers: counts.dcs,
+            indicators,
+            confidence: confidence.min(100),
+            mitre_id: MITRE_ID.to_string(),
+            timestamp: now / 1000,
+        });
+    }
+    alerts
+}
+
+fn raise_incident(alert: &KerberoastAlert) {
+    // Raw Kerberos + LDAP, hoặc LDAP + lsass TGS burst → gần như chắc chắn roasting
+    let severity = if alert.confidence >= 90 {
+        Severity::Critical
+    } else if alert.kerberos_connections > 0 || alert.lsass_tgs_connections > 0 {
+        Severity::High
+    } else {
+        Severity::Medium
+    };
+
+    incident::raise_process_detection(
+        &format!("Credential Access: Kerberoasting by {}", alert.process_name),
+        severity,
+        &["KERBEROASTING".to_string(), "CREDENTIAL_ACCESS".to_string()],
+        &[MITRE_ID],
+        &format!(
+            "{} (PID {}) queried domain controllers {} — {}",
+            alert.process_name,
+            alert.pid,
+            alert.domain_controllers.join(", "),
+            alert.indicators.join("; ")
+        ),
+        DetectionContext { pid: Some(alert.pid), rule_matches: alert.indicators.clone() },
+    );
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn conn(pid: u32, name: &str, port: u16, timestamp: i64) -> ConnectionEvent {
+        ConnectionEvent {
+            seq: 0,
+            pid,
+            process_name: name.to_string(),
+            protocol: "tcp".to_string(),
+            local_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 50)),
+            local_port: 50000,
+            remote_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            remote_port: port,
+            state: "ESTABLISHED".to_string(),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_script_ldap_burst_with_lsass_tgs() {
+        let now = 1_000_000;
+        let mut window: Vec<_> = (0..4).map(|i| conn(700, "powershell.exe", 389, now - i * 1000)).collect();
+        window.extend((0..12).map(|i| conn(600, "lsass.exe", 88, now - i * 500)));
+        // Outlook address book lookups → không báo
+        window.extend((0..30).map(|_| conn(800, "OUTLOOK.EXE", 3268, now)));
+
+        let alerts = evaluate(&window, now);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].pid, 700);
+        assert_eq!(alerts[0].lsass_tgs_connections, 12);
+        assert!(alerts[0].confidence >= 70);
+    }
+
+    #[test]
+    fn test_raw_kerberos_client_and_window_expiry() {
+        let now = 1_000_000;
+        let window = vec![
+            conn(900, "svc.exe", 88, now - 1000),
+            conn(900, "svc.exe", 88, now - 2000),
+            conn(900, "svc.exe", 88, now - 3000),
+        ];
+        let alerts = evaluate(&window, now);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kerberos_connections, 3);
+
+        // Ngoài cửa sổ → không tính
+        assert!(evaluate(&window, now + WINDOW_MS + 5000).is_empty());
+    }
+}
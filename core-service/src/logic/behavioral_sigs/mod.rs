@@ -5,27 +5,89 @@
 //! # Components
 //! - `beaconing.rs`: Phát hiện C2 beaconing patterns
 //! - `persistence.rs`: Monitor registry persistence locations
+//! - `registry_watcher.rs`: RegNotifyChangeKeyValue trên Run / Services / IFEO → `persistence.rs`
+//! - `com_hijack.rs`: HKCU CLSID shadowing HKLM (COM hijacking)
+//! - `wmi_persistence.rs`: Permanent WMI event subscriptions (root\subscription)
+//! - `uac_bypass.rs`: Registry hijack + auto-elevate child correlation (UAC bypass)
+//! - `bits_abuse.rs`: BITS transfer job tới URL ngoài do non-updater tạo
+//! - `kerberoast.rs`: Burst TGS request / LDAP SPN query từ workstation process (Kerberoasting)
 //! - `never_learn.rs`: Blacklist patterns không bao giờ học
 //! - `rules.rs`: Custom behavioral rules engine
+//! - `suppressions.rs`: Exception list theo rule (có hạn + audit trail)
+//! - `history.rs`: Event history ngắn hạn theo process (sequence / time-window conditions)
+//! - `rules_dir.rs`: Hot-reload rules từ thư mục `rules/` (JSON / YAML)
 
 // Allow unused for now - will be fully integrated in future phases
 #![allow(unused)]
 
 pub mod beaconing;
 pub mod persistence;
+pub mod registry_watcher;
+pub mod com_hijack;
+pub mod uac_bypass;
+pub mod wmi_persistence;
+pub mod bits_abuse;
+pub mod kerberoast;
 pub mod never_learn;
 pub mod rules;
+pub mod history;
+pub mod suppressions;
+pub mod rules_dir;
 pub mod types;
 
 // Re-exports from types
 pub use types::{
     BeaconAlert, BeaconSeverity, PersistenceAlert, PersistenceMechanism, PersistenceSeverity,
     BehavioralRuleDefinition, RuleCondition, RuleAction, RuleSeverity, RuleMatch, MatchContext,
-    NeverLearnReason, SampleContext,
+    NeverLearnReason, SampleContext, SequenceStep, ProcessEvent, ProcessEventKind,
 };
 
 // Re-exports from submodules
 pub use beaconing::{BeaconingDetector, check_beaconing, record_connection, get_all_beacons};
 pub use persistence::{PersistenceMonitor, PERSISTENCE_KEYS, record_registry_write, is_persistence_key};
 pub use never_learn::{NeverLearnBlacklist, should_never_learn, is_process_blacklisted};
-pub use rules::{RuleEngine, evaluate, add_rule, get_matches, get_all_rules};
+pub use suppressions::{RuleSuppression, SuppressionKind, SuppressionAuditEntry};
+pub use rules::{RuleEngine, RuleSummary, RuleTestResult, ConditionTestResult, evaluate, add_rule, get_matches, get_all_rules};
+
+/// Chạy PowerShell script, kill nếu quá `timeout`. None khi không chạy được,
+/// exit code lỗi hoặc timeout. Script query (BITS, WMI) có thể treo nhiều giây
+/// → gọi từ thread nền, không gọi trên analysis loop.
+#[cfg(windows)]
+pub(crate) fn run_powershell(script: &str, timeout: std::time::Duration) -> Option<String> {
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+    use std::time::{Duration, Instant};
+
+    let mut child = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    // Đọc stdout ở thread riêng - pipe đầy sẽ chặn PowerShell
+    let mut stdout = child.stdout.take()?;
+    let reader = std::thread::spawn(move || {
+        let mut output = String::new();
+        let _ = stdout.read_to_string(&mut output);
+        output
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(100)),
+            _ => {
+                log::warn!("PowerShell query did not finish within {:?}, killing it", timeout);
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    };
+
+    let output = reader.join().ok()?;
+    status.success().then_some(output)
+}
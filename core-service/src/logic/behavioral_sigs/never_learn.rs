@@ -7,11 +7,16 @@
 //! - Network to Tor/C2
 //! - Registry persistence attempts
 //! - Unsigned with network activity
+//!
+//! Danh sách từ cloud (per-org) được giữ tách riêng khỏi danh sách local để
+//! một lần sync không ghi đè lên các entry thêm tay trên máy.
 
 use std::collections::HashSet;
-use std::path::Path;
-use parking_lot::RwLock;
+use std::fs;
+use std::path::{Path, PathBuf};
+use parking_lot::{Mutex, RwLock};
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 
 use super::types::{NeverLearnReason, SampleContext};
 use crate::logic::process_intel;
@@ -78,8 +83,59 @@ const SUSPICIOUS_TLDS: &[&str] = &[
 // STATE
 // ============================================================================
 
-static BLACKLIST: Lazy<RwLock<NeverLearnBlacklist>> =
-    Lazy::new(|| RwLock::new(NeverLearnBlacklist::new()));
+const CLOUD_FILE: &str = "never_learn_cloud.json";
+
+/// Max decisions waiting for upload (oldest dropped first)
+const MAX_PENDING_DECISIONS: usize = 500;
+
+static BLACKLIST: Lazy<RwLock<NeverLearnBlacklist>> = Lazy::new(|| {
+    let mut bl = NeverLearnBlacklist::new();
+    let cloud = load_cloud();
+    bl.apply_cloud(&cloud.entries);
+    RwLock::new(bl)
+});
+
+/// Revision của danh sách cloud đang áp dụng
+static CLOUD_REVISION: Lazy<RwLock<i64>> = Lazy::new(|| RwLock::new(load_cloud().revision));
+
+static DECISIONS: Lazy<Mutex<DecisionQueue>> = Lazy::new(|| Mutex::new(DecisionQueue::default()));
+
+// ============================================================================
+// CLOUD TYPES
+// ============================================================================
+
+/// Entry pushed from the cloud ("process" | "hash" | "endpoint")
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloudNeverLearnEntry {
+    pub kind: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CloudNeverLearnStore {
+    revision: i64,
+    entries: Vec<CloudNeverLearnEntry>,
+}
+
+/// Never-learn decision made locally, reported to the cloud
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NeverLearnDecision {
+    /// Reason kind (e.g. "process_blacklisted", "custom_rule")
+    pub kind: String,
+    /// Subject of the decision (process name, hash, endpoint, rule id...)
+    pub value: String,
+    pub reason: String,
+    pub process_name: Option<String>,
+    /// Unix seconds
+    pub decided_at: i64,
+}
+
+#[derive(Default)]
+struct DecisionQueue {
+    pending: Vec<NeverLearnDecision>,
+    /// (kind, value) đã báo trong phiên này - tránh gửi trùng
+    seen: HashSet<(String, String)>,
+}
 
 // ============================================================================
 // NEVER LEARN BLACKLIST
@@ -95,6 +151,15 @@ pub struct NeverLearnBlacklist {
     /// Known C2 endpoints
     c2_endpoints: HashSet<String>,
 
+    /// Process names from cloud list
+    cloud_processes: HashSet<String>,
+
+    /// Hashes from cloud list
+    cloud_hashes: HashSet<String>,
+
+    /// Endpoints from cloud list
+    cloud_endpoints: HashSet<String>,
+
     /// Block unsigned + network
     block_unsigned_network: bool,
 
@@ -111,6 +176,9 @@ impl NeverLearnBlacklist {
             process_names: HashSet::new(),
             hashes: HashSet::new(),
             c2_endpoints: HashSet::new(),
+            cloud_processes: HashSet::new(),
+            cloud_hashes: HashSet::new(),
+            cloud_endpoints: HashSet::new(),
             block_unsigned_network: true,
             block_unsigned_disk: false, // Too noisy by default
             enabled: true,
@@ -139,7 +207,7 @@ impl NeverLearnBlacklist {
         // Check process name
         if let Some(ref name) = ctx.process_name {
             let name_lower = name.to_lowercase();
-            if self.process_names.contains(&name_lower) {
+            if self.has_process(&name_lower) {
                 return Some(NeverLearnReason::ProcessBlacklisted {
                     name: name.clone()
                 });
@@ -148,7 +216,7 @@ impl NeverLearnBlacklist {
 
         // Check hash
         if let Some(ref hash) = ctx.process_hash {
-            if self.hashes.contains(&hash.to_lowercase()) {
+            if self.has_hash(&hash.to_lowercase()) {
                 return Some(NeverLearnReason::HashBlacklisted {
                     hash: hash.clone()
                 });
@@ -165,7 +233,7 @@ impl NeverLearnBlacklist {
             }
 
             // Check known C2
-            if self.c2_endpoints.iter().any(|c2| dest_lower.contains(c2)) {
+            if self.has_endpoint(&dest_lower) {
                 return Some(NeverLearnReason::NetworkToKnownC2 {
                     endpoint: dest.clone()
                 });
@@ -198,6 +266,41 @@ impl NeverLearnBlacklist {
         None
     }
 
+    fn has_process(&self, name_lower: &str) -> bool {
+        self.process_names.contains(name_lower) || self.cloud_processes.contains(name_lower)
+    }
+
+    fn has_hash(&self, hash_lower: &str) -> bool {
+        self.hashes.contains(hash_lower) || self.cloud_hashes.contains(hash_lower)
+    }
+
+    fn has_endpoint(&self, dest_lower: &str) -> bool {
+        self.c2_endpoints
+            .iter()
+            .chain(self.cloud_endpoints.iter())
+            .any(|c2| dest_lower.contains(c2.as_str()))
+    }
+
+    /// Replace cloud-managed entries (local entries untouched)
+    pub fn apply_cloud(&mut self, entries: &[CloudNeverLearnEntry]) {
+        self.cloud_processes.clear();
+        self.cloud_hashes.clear();
+        self.cloud_endpoints.clear();
+
+        for entry in entries {
+            let value = entry.value.trim().to_lowercase();
+            if value.is_empty() {
+                continue;
+            }
+            match entry.kind.as_str() {
+                "process" => { self.cloud_processes.insert(value); }
+                "hash" => { self.cloud_hashes.insert(value); }
+                "endpoint" => { self.cloud_endpoints.insert(value); }
+                other => log::debug!("Unknown never-learn kind from cloud: {}", other),
+            }
+        }
+    }
+
     /// Add process to blacklist
     pub fn add_process(&mut self, name: &str) {
         self.process_names.insert(name.to_lowercase());
@@ -250,18 +353,24 @@ impl Default for NeverLearnBlacklist {
 // ============================================================================
 
 /// Check if sample should never be learned
+///
+/// Quyết định dương tính được đưa vào hàng đợi để báo lên cloud.
 pub fn should_never_learn(ctx: &SampleContext) -> Option<NeverLearnReason> {
-    BLACKLIST.read().should_never_learn(ctx)
+    let reason = BLACKLIST.read().should_never_learn(ctx);
+    if let Some(ref r) = reason {
+        record_decision(r, ctx.process_name.as_deref());
+    }
+    reason
 }
 
 /// Quick check for process name only
 pub fn is_process_blacklisted(name: &str) -> bool {
-    BLACKLIST.read().process_names.contains(&name.to_lowercase())
+    BLACKLIST.read().has_process(&name.to_lowercase())
 }
 
 /// Quick check for hash only
 pub fn is_hash_blacklisted(hash: &str) -> bool {
-    BLACKLIST.read().hashes.contains(&hash.to_lowercase())
+    BLACKLIST.read().has_hash(&hash.to_lowercase())
 }
 
 /// Quick check for network destination
@@ -280,7 +389,7 @@ pub fn is_network_blacklisted(dest: &str) -> bool {
     }
 
     // Check C2 list
-    BLACKLIST.read().c2_endpoints.iter().any(|c2| dest_lower.contains(c2))
+    BLACKLIST.read().has_endpoint(&dest_lower)
 }
 
 /// Add process to blacklist
@@ -323,6 +432,97 @@ pub fn set_enabled(enabled: bool) {
     BLACKLIST.write().set_enabled(enabled);
 }
 
+// ============================================================================
+// CLOUD SYNC
+// ============================================================================
+
+/// Revision of the cloud list currently applied (0 = never synced)
+pub fn cloud_revision() -> i64 {
+    *CLOUD_REVISION.read()
+}
+
+/// Apply the org list fetched from the cloud and persist it for next start
+pub fn apply_cloud_entries(revision: i64, entries: Vec<CloudNeverLearnEntry>) {
+    BLACKLIST.write().apply_cloud(&entries);
+    *CLOUD_REVISION.write() = revision;
+    save_cloud(&CloudNeverLearnStore { revision, entries });
+}
+
+/// Queue a never-learn decision for upload (once per subject per session)
+pub fn record_decision(reason: &NeverLearnReason, process_name: Option<&str>) {
+    let (kind, value) = decision_subject(reason);
+    let mut queue = DECISIONS.lock();
+    if !queue.seen.insert((kind.to_string(), value.clone())) {
+        return;
+    }
+
+    queue.pending.push(NeverLearnDecision {
+        kind: kind.to_string(),
+        value,
+        reason: reason.description(),
+        process_name: process_name.map(|s| s.to_string()),
+        decided_at: chrono::Utc::now().timestamp(),
+    });
+    if queue.pending.len() > MAX_PENDING_DECISIONS {
+        let overflow = queue.pending.len() - MAX_PENDING_DECISIONS;
+        queue.pending.drain(..overflow);
+    }
+}
+
+/// Drain decisions waiting for upload
+pub fn take_pending_decisions() -> Vec<NeverLearnDecision> {
+    std::mem::take(&mut DECISIONS.lock().pending)
+}
+
+/// Put decisions back after a failed upload
+pub fn requeue_decisions(mut decisions: Vec<NeverLearnDecision>) {
+    let mut queue = DECISIONS.lock();
+    decisions.append(&mut queue.pending);
+    queue.pending = decisions;
+    if queue.pending.len() > MAX_PENDING_DECISIONS {
+        let overflow = queue.pending.len() - MAX_PENDING_DECISIONS;
+        queue.pending.drain(..overflow);
+    }
+}
+
+fn decision_subject(reason: &NeverLearnReason) -> (&'static str, String) {
+    match reason {
+        NeverLearnReason::ProcessBlacklisted { name } => ("process_blacklisted", name.to_lowercase()),
+        NeverLearnReason::HashBlacklisted { hash } => ("hash_blacklisted", hash.to_lowercase()),
+        NeverLearnReason::NetworkToTor => ("network_to_tor", String::new()),
+        NeverLearnReason::NetworkToKnownC2 { endpoint } => ("network_to_known_c2", endpoint.to_lowercase()),
+        NeverLearnReason::RegistryPersistence { key } => ("registry_persistence", key.clone()),
+        NeverLearnReason::UnsignedWithNetwork => ("unsigned_with_network", String::new()),
+        NeverLearnReason::UnsignedWithDiskWrite => ("unsigned_with_disk_write", String::new()),
+        NeverLearnReason::BeaconingDetected { endpoint } => ("beaconing_detected", endpoint.to_lowercase()),
+        NeverLearnReason::CustomRule { rule_id } => ("custom_rule", rule_id.clone()),
+    }
+}
+
+fn cloud_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(CLOUD_FILE)
+}
+
+fn load_cloud() -> CloudNeverLearnStore {
+    fs::read_to_string(cloud_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_cloud(store: &CloudNeverLearnStore) {
+    let path = cloud_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(store) {
+        let _ = fs::write(path, json);
+    }
+}
+
 // ============================================================================
 // STATISTICS
 // ============================================================================
@@ -333,6 +533,8 @@ pub struct BlacklistStats {
     pub process_count: usize,
     pub hash_count: usize,
     pub c2_endpoint_count: usize,
+    pub cloud_entry_count: usize,
+    pub cloud_revision: i64,
     pub block_unsigned_network: bool,
     pub block_unsigned_disk: bool,
 }
@@ -344,6 +546,8 @@ pub fn get_stats() -> BlacklistStats {
         process_count: bl.process_names.len(),
         hash_count: bl.hashes.len(),
         c2_endpoint_count: bl.c2_endpoints.len(),
+        cloud_entry_count: bl.cloud_processes.len() + bl.cloud_hashes.len() + bl.cloud_endpoints.len(),
+        cloud_revision: cloud_revision(),
         block_unsigned_network: bl.block_unsigned_network,
         block_unsigned_disk: bl.block_unsigned_disk,
     }
@@ -403,4 +607,45 @@ mod tests {
         let reason = bl.should_never_learn(&ctx);
         assert!(reason.is_none());
     }
+
+    #[test]
+    fn test_cloud_entries_do_not_clobber_local() {
+        let mut bl = NeverLearnBlacklist::new();
+        bl.apply_cloud(&[
+            CloudNeverLearnEntry { kind: "process".to_string(), value: "Dropper.EXE".to_string() },
+            CloudNeverLearnEntry { kind: "endpoint".to_string(), value: "c2.example.net".to_string() },
+        ]);
+
+        let ctx = SampleContext {
+            process_name: Some("dropper.exe".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(bl.should_never_learn(&ctx), Some(NeverLearnReason::ProcessBlacklisted { .. })));
+
+        let ctx = SampleContext {
+            network_destinations: vec!["api.c2.example.net".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(bl.should_never_learn(&ctx), Some(NeverLearnReason::NetworkToKnownC2 { .. })));
+
+        // Sync lại với danh sách rỗng: entry cloud mất, entry local vẫn còn
+        bl.apply_cloud(&[]);
+        assert!(!bl.has_process("dropper.exe"));
+        assert!(bl.has_process("mimikatz.exe"));
+    }
+
+    #[test]
+    fn test_decision_dedup_and_requeue() {
+        let reason = NeverLearnReason::CustomRule { rule_id: "test-dedup-rule".to_string() };
+        record_decision(&reason, Some("evil.exe"));
+        record_decision(&reason, Some("evil.exe"));
+
+        let taken = take_pending_decisions();
+        let ours: Vec<_> = taken.iter().filter(|d| d.value == "test-dedup-rule").cloned().collect();
+        assert_eq!(ours.len(), 1);
+        assert_eq!(ours[0].kind, "custom_rule");
+
+        requeue_decisions(ours.clone());
+        assert!(take_pending_decisions().contains(&ours[0]));
+    }
 }
@@ -241,6 +241,20 @@ impl PersistenceMonitor {
         }
     }
 
+    /// Store an alert produced by a dedicated scanner (e.g. COM hijack)
+    pub fn record_alert(&mut self, alert: PersistenceAlert) {
+        log::warn!(
+            "Persistence detected: {:?} at {} ({})",
+            alert.mechanism,
+            alert.location,
+            alert.mitre_technique
+        );
+        self.alerts.push(alert);
+        if self.alerts.len() > self.max_alerts {
+            self.alerts.drain(0..self.alerts.len() - self.max_alerts);
+        }
+    }
+
     /// Get recent alerts
     pub fn get_alerts(&self, limit: usize) -> Vec<PersistenceAlert> {
         let start = self.alerts.len().saturating_sub(limit);
@@ -298,6 +312,11 @@ pub fn record_registry_write(
     MONITOR.write().record_registry_write(key, value_name, value_data, process_name, process_pid)
 }
 
+/// Store an alert produced by a dedicated scanner
+pub fn record_alert(alert: PersistenceAlert) {
+    MONITOR.write().record_alert(alert);
+}
+
 /// Get recent alerts
 pub fn get_alerts(limit: usize) -> Vec<PersistenceAlert> {
     MONITOR.read().get_alerts(limit)
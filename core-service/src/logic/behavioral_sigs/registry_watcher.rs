@@ -0,0 +1,484 @@
+//! Registry Watcher - Theo dõi persistence keys bằng RegNotifyChangeKeyValue
+//!
+//! Mục đích: `persistence::record_registry_write` có dữ liệu thật mà không cần
+//! registry collection backend (ETW / driver).
+//!
+//! 1. Mở Run / RunOnce (HKLM, WOW6432Node, HKCU), Services, IFEO với KEY_NOTIFY
+//! 2. 1 thread chờ trên event của tất cả key (notify là one-shot → arm lại mỗi lần)
+//! 3. Có notify → snapshot lại key, diff với snapshot cũ → value mới / bị sửa
+//! 4. Mỗi thay đổi → `record_registry_write` + `history::record` (RegistryWrite)
+//!    cho sequence rules. High / Critical → incident.
+//!
+//! RegNotifyChangeKeyValue không cho biết process ghi. Writer chỉ được gán cho
+//! tool ghi registry đã biết (reg.exe, PowerShell Set-/New-ItemProperty, sc.exe,
+//! wmic) mà command line chứa CẢ key path lẫn value name; còn lại → PID 0 /
+//! "unknown", không ghi history (đoán sai sẽ kéo process vô can vào sequence
+//! rules / response).
+//!
+//! HKCU là hive của user chạy agent.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use crate::logic::incident::{self, Severity};
+use crate::logic::process_intel::tree;
+use crate::logic::response::registry;
+use super::history;
+use super::persistence;
+use super::types::{PersistenceAlert, PersistenceSeverity, ProcessEventKind};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hive {
+    LocalMachine,
+    CurrentUser,
+}
+
+impl Hive {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Hive::LocalMachine => "HKLM",
+            Hive::CurrentUser => "HKCU",
+        }
+    }
+}
+
+/// Key được watch: (hive, path, watch subkeys)
+/// Services / IFEO: persistence nằm ở subkey (ImagePath, Debugger)
+pub const WATCHED_KEYS: &[(Hive, &str, bool)] = &[
+    (Hive::LocalMachine, r"SOFTWARE\Microsoft\Windows\CurrentVersion\Run", false),
+    (Hive::LocalMachine, r"SOFTWARE\Microsoft\Windows\CurrentVersion\RunOnce", false),
+    (Hive::LocalMachine, r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Run", false),
+    (Hive::LocalMachine, r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\RunOnce", false),
+    (Hive::CurrentUser, r"Software\Microsoft\Windows\CurrentVersion\Run", false),
+    (Hive::CurrentUser, r"Software\Microsoft\Windows\CurrentVersion\RunOnce", false),
+    (Hive::LocalMachine, r"SYSTEM\CurrentControlSet\Services", true),
+    (Hive::LocalMachine, r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\Image File Execution Options", true),
+    (Hive::LocalMachine, r"SOFTWARE\WOW6432Node\Microsoft\Windows NT\CurrentVersion\Image File Execution Options", true),
+];
+
+/// Value quan tâm trong subkey của Services / IFEO (bỏ qua Start, Type, ...)
+const SUBKEY_VALUES: &[&str] = &["imagepath", "servicedll", "debugger", "verifierdlls", "globalflag"];
+
+/// Chờ writer ghi xong (thường ghi nhiều value liên tiếp) trước khi snapshot
+const DEBOUNCE_MS: u64 = 300;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// (subkey tương đối - "" = chính key, value name) → data
+pub type Snapshot = HashMap<(String, String), String>;
+
+/// Value mới / bị sửa
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryChange {
+    pub subkey: String,
+    pub value_name: String,
+    pub value_data: String,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Start watcher thread (idempotent)
+pub fn start() {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(|| {
+        log::info!("Registry persistence watcher started ({} keys)", WATCHED_KEYS.len());
+        platform::run(WATCHED_KEYS);
+        RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+pub fn is_running() -> bool {
+    RUNNING.load(Ordering::SeqCst)
+}
+
+/// Value có trong `after` nhưng mới / khác so với `before`
+pub fn diff(before: &Snapshot, after: &Snapshot) -> Vec<RegistryChange> {
+    let mut changes: Vec<RegistryChange> = after.iter()
+        .filter(|(entry, data)| before.get(*entry) != Some(*data))
+        .map(|((subkey, value_name), data)| RegistryChange {
+            subkey: subkey.clone(),
+            value_name: value_name.clone(),
+            value_data: data.clone(),
+        })
+        .collect();
+    changes.sort_by(|a, b| (&a.subkey, &a.value_name).cmp(&(&b.subkey, &b.value_name)));
+    changes
+}
+
+/// Subkey value có được đưa vào snapshot không
+pub fn is_tracked_subkey_value(value_name: &str) -> bool {
+    SUBKEY_VALUES.contains(&value_name.to_lowercase().as_str())
+}
+
+// ============================================================================
+// CHANGE HANDLING
+// ============================================================================
+
+/// Xử lý thay đổi trên 1 watched key (gọi từ watcher thread)
+fn handle_changes(hive: Hive, path: &str, changes: Vec<RegistryChange>) {
+    if changes.is_empty() {
+        return;
+    }
+    tree::refresh_tree();
+
+    for change in changes {
+        let key = if change.subkey.is_empty() {
+            path.to_string()
+        } else {
+            format!(r"{}\{}", path, change.subkey)
+        };
+        let full_key = format!(r"{}\{}", hive.as_str(), key);
+        let (pid, process_name) = attribute_writer(&key, &change);
+
+        if pid != 0 {
+            history::record(pid, ProcessEventKind::RegistryWrite { key: full_key.clone() });
+        }
+
+        let value_name = (!change.value_name.is_empty()).then_some(change.value_name.as_str());
+        if let Some(alert) = persistence::record_registry_write(
+            &full_key, value_name, Some(&change.value_data), &process_name, pid,
+        ) {
+            raise_incident(&alert);
+        }
+    }
+}
+
+/// Process đã ghi key (xem doc đầu file). Trả về (pid, name) - (0, "unknown") nếu không xác định được.
+fn attribute_writer(key: &str, change: &RegistryChange) -> (u32, String) {
+    let processes: Vec<(u32, String, String, i64)> = tree::get_process_tree()
+        .into_values()
+        .map(|n| (n.info.pid, n.info.name, n.info.cmdline.unwrap_or_default(), n.info.start_time))
+        .collect();
+
+    pick_writer(&processes, key, &change.value_name)
+        .unwrap_or_else(|| (0, "unknown".to_string()))
+}
+
+/// Command line của tool ghi registry có nêu đúng key + value này không.
+/// Chỉ tính reg.exe, wmic, sc.exe và PowerShell Set-/New-ItemProperty -
+/// process khác nhắc tới key (vd. chính payload trong value data) không phải writer.
+fn is_writer_command(name: &str, cmdline: &str, key: &str, value_name: &str) -> bool {
+    let name = name.to_lowercase();
+    let cmdline = cmdline.to_lowercase();
+    let key = key.to_lowercase();
+    let value = value_name.to_lowercase();
+
+    // Tool 32-bit ghi vào key 64-bit bị redirect sang WOW6432Node
+    let mentions_key = cmdline.contains(&key)
+        || cmdline.contains(&key.replace(r"wow6432node\", ""));
+    // Value mặc định ("") không có tên để so
+    let mentions_value = value.is_empty() || cmdline.contains(&value);
+
+    match name.as_str() {
+        "reg.exe" | "wmic.exe" => mentions_key && mentions_value,
+        "powershell.exe" | "pwsh.exe" => {
+            (cmdline.contains("set-itemproperty") || cmdline.contains("new-itemproperty"))
+                && mentions_key && mentions_value
+        }
+        // sc create/config <service> binPath= ... → Services\<service>\ImagePath
+        "sc.exe" => {
+            let Some(service) = key.strip_prefix(r"system\currentcontrolset\services\") else {
+                return false;
+            };
+            let mut args = cmdline.split_whitespace().skip(1);
+            matches!(args.next(), Some("create" | "config"))
+                && args.next().map(|a| a.trim_matches('"')) == Some(service)
+                && value == "imagepath"
+                && cmdline.contains("binpath=")
+        }
+        _ => false,
+    }
+}
+
+/// processes: (pid, name, cmdline, start_time giây). Nhiều process khớp → process mới nhất.
+fn pick_writer(processes: &[(u32, String, String, i64)], key: &str, value_name: &str) -> Option<(u32, String)> {
+    processes.iter()
+        .filter(|(_, name, cmdline, _)| is_writer_command(name, cmdline, key, value_name))
+        .max_by_key(|(_, _, _, start)| *start)
+        .map(|(pid, name, _, _)| (*pid, name.clone()))
+}
+
+fn raise_incident(alert: &PersistenceAlert) {
+    let severity = match alert.severity {
+        PersistenceSeverity::Critical => Severity::Critical,
+        PersistenceSeverity::High => Severity::High,
+        // Low / Medium: installer, update - chỉ giữ alert
+        _ => return,
+    };
+
+    incident::raise_detection(
+        &format!("Persistence: {:?} written by {}", alert.mechanism, alert.process_name),
+        severity,
+        &["PERSISTENCE".to_string(), "REGISTRY".to_string()],
+        &[alert.mitre_technique.as_str()],
+        &format!(
+            "{} (PID {}) set {}{} = {}",
+            alert.process_name, alert.process_pid, alert.location,
+            alert.value_name.as_deref().map(|v| format!(" [{}]", v)).unwrap_or_default(),
+            alert.value_data.as_deref().unwrap_or(""),
+        ),
+    );
+
+    // Gỡ persistence qua Action Guard (Critical tự động, High chờ approval)
+    if let Err(e) = registry::remediate_alert(alert) {
+        log::warn!("Persistence remediation not executed for {}: {}", alert.location, e);
+    }
+}
+
+// ============================================================================
+// PLATFORM
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use std::time::Duration;
+    use windows::core::{HSTRING, PWSTR};
+    use windows::Win32::Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0};
+    use windows::Win32::System::Registry::{
+        RegCloseKey, RegEnumKeyExW, RegEnumValueW, RegNotifyChangeKeyValue, RegOpenKeyExW,
+        HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_NOTIFY, KEY_READ, REG_SAM_FLAGS,
+        REG_EXPAND_SZ, REG_MULTI_SZ, REG_NOTIFY_CHANGE_LAST_SET, REG_NOTIFY_CHANGE_NAME, REG_SZ,
+    };
+    use windows::Win32::System::Threading::{CreateEventW, WaitForMultipleObjects, INFINITE};
+
+    use super::{Hive, Snapshot, DEBOUNCE_MS};
+
+    struct Watched {
+        hive: Hive,
+        path: &'static str,
+        subtree: bool,
+        hkey: HKEY,
+        event: HANDLE,
+        snapshot: Snapshot,
+    }
+
+    pub fn run(keys: &'static [(Hive, &'static str, bool)]) {
+        let mut watched = Vec::new();
+        for (hive, path, subtree) in keys {
+            let Some(hkey) = open(*hive, path) else {
+                log::debug!("Registry watcher: cannot open {}\\{}", hive.as_str(), path);
+                continue;
+            };
+            let event = match unsafe { CreateEventW(None, false, false, None) } {
+                Ok(event) => event,
+                Err(e) => {
+                    log::warn!("Registry watcher: CreateEventW failed: {}", e);
+                    unsafe { let _ = RegCloseKey(hkey); }
+                    continue;
+                }
+            };
+            if !arm(hkey, event, *subtree) {
+                log::debug!("Registry watcher: cannot watch {}\\{}", hive.as_str(), path);
+                unsafe {
+                    let _ = RegCloseKey(hkey);
+                    let _ = CloseHandle(event);
+                }
+                continue;
+            }
+            watched.push(Watched { hive: *hive, path, subtree: *subtree, hkey, event, snapshot: snapshot(hkey, *subtree) });
+        }
+        if watched.is_empty() {
+            log::warn!("Registry watcher: no persistence key could be watched");
+            return;
+        }
+
+        let handles: Vec<HANDLE> = watched.iter().map(|w| w.event).collect();
+        loop {
+            let result = unsafe { WaitForMultipleObjects(&handles, false, INFINITE) };
+            let index = result.0.wrapping_sub(WAIT_OBJECT_0.0) as usize;
+            let Some(w) = watched.get_mut(index) else {
+                log::error!("Registry watcher: wait failed ({:#x})", result.0);
+                return;
+            };
+
+            std::thread::sleep(Duration::from_millis(DEBOUNCE_MS));
+            // Arm lại trước khi snapshot → không lỡ thay đổi xảy ra trong lúc đọc
+            if !arm(w.hkey, w.event, w.subtree) {
+                log::warn!("Registry watcher: re-arm failed for {}\\{}", w.hive.as_str(), w.path);
+            }
+            let current = snapshot(w.hkey, w.subtree);
+            let changes = super::diff(&w.snapshot, &current);
+            w.snapshot = current;
+            super::handle_changes(w.hive, w.path, changes);
+        }
+    }
+
+    fn open(hive: Hive, path: &str) -> Option<HKEY> {
+        let root = match hive {
+            Hive::LocalMachine => HKEY_LOCAL_MACHINE,
+            Hive::CurrentUser => HKEY_CURRENT_USER,
+        };
+        open_subkey(root, path, KEY_NOTIFY | KEY_READ)
+    }
+
+    fn open_subkey(parent: HKEY, path: &str, access: REG_SAM_FLAGS) -> Option<HKEY> {
+        let mut hkey = HKEY::default();
+        let status = unsafe {
+            RegOpenKeyExW(parent, &HSTRING::from(path), 0, access, &mut hkey)
+        };
+        status.is_ok().then_some(hkey)
+    }
+
+    fn arm(hkey: HKEY, event: HANDLE, subtree: bool) -> bool {
+        let filter = REG_NOTIFY_CHANGE_NAME | REG_NOTIFY_CHANGE_LAST_SET;
+        unsafe { RegNotifyChangeKeyValue(hkey, subtree, filter, event, true) }.is_ok()
+    }
+
+    fn snapshot(hkey: HKEY, subtree: bool) -> Snapshot {
+        let mut snapshot = Snapshot::new();
+        if !subtree {
+            for (name, data) in string_values(hkey) {
+                snapshot.insert((String::new(), name), data);
+            }
+            return snapshot;
+        }
+
+        for subkey in subkey_names(hkey) {
+            let Some(child) = open_subkey(hkey, &subkey, KEY_READ) else { continue };
+            for (name, data) in string_values(child) {
+                if super::is_tracked_subkey_value(&name) {
+                    snapshot.insert((subkey.clone(), name), data);
+                }
+            }
+            unsafe { let _ = RegCloseKey(child); }
+        }
+        snapshot
+    }
+
+    fn subkey_names(hkey: HKEY) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut buf = [0u16; 256];
+        for index in 0.. {
+            let mut len = buf.len() as u32;
+            let status = unsafe {
+                RegEnumKeyExW(hkey, index, PWSTR(buf.as_mut_ptr()), &mut len, None, PWSTR::null(), None, None)
+            };
+            if status.is_err() {
+                break;
+            }
+            names.push(String::from_utf16_lossy(&buf[..len as usize]));
+        }
+        names
+    }
+
+    /// REG_SZ / REG_EXPAND_SZ / REG_MULTI_SZ values (multi-sz nối bằng ';')
+    fn string_values(hkey: HKEY) -> Vec<(String, String)> {
+        let mut values = Vec::new();
+        let mut name_buf = [0u16; 16_384];
+        let mut data_buf = vec![0u8; 16_384];
+        for index in 0.. {
+            let mut name_len = name_buf.len() as u32;
+            let mut data_len = data_buf.len() as u32;
+            let mut kind = 0u32;
+            let status = unsafe {
+                RegEnumValueW(
+                    hkey, index, PWSTR(name_buf.as_mut_ptr()), &mut name_len, None,
+                    Some(&mut kind as *mut u32), Some(data_buf.as_mut_ptr()), Some(&mut data_len as *mut u32),
+                )
+            };
+            if status.is_err() {
+                // ERROR_MORE_DATA (value quá lớn) cũng kết thúc ở đây - không phải persistence value thông thường
+                break;
+            }
+            if kind != REG_SZ.0 && kind != REG_EXPAND_SZ.0 && kind != REG_MULTI_SZ.0 {
+                continue;
+            }
+            let wide: Vec<u16> = data_buf[..data_len as usize]
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            let data = String::from_utf16_lossy(&wide)
+                .trim_end_matches('\0')
+                .replace('\0', ";");
+            values.push((String::from_utf16_lossy(&name_buf[..name_len as usize]), data));
+        }
+        values
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::Hive;
+
+    pub fn run(_keys: &'static [(Hive, &'static str, bool)]) {
+        log::debug!("Registry watcher not supported on this platform");
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(subkey: &str, name: &str, data: &str) -> ((String, String), String) {
+        ((subkey.to_string(), name.to_string()), data.to_string())
+    }
+
+    #[test]
+    fn test_diff_and_attribution() {
+        let before: Snapshot = [
+            entry("", "OneDrive", r"C:\Program Files\OneDrive\OneDrive.exe /background"),
+        ].into_iter().collect();
+        let after: Snapshot = [
+            entry("", "OneDrive", r"C:\Program Files\OneDrive\OneDrive.exe /background"),
+            entry("", "Updater", r"C:\Users\bob\AppData\Roaming\upd.exe"),
+        ].into_iter().collect();
+
+        let changes = diff(&before, &after);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].value_name, "Updater");
+        assert!(diff(&after, &after).is_empty());
+
+        let now = 10_000;
+        let processes = vec![
+            (10, "explorer.exe".to_string(), "explorer.exe".to_string(), 100),
+            (20, "reg.exe".to_string(),
+             r"reg add HKCU\Software\Microsoft\Windows\CurrentVersion\Run /v Updater /d C:\x".to_string(), now - 60),
+            // Payload nhắc tới key + value nhưng không phải tool ghi registry
+            (30, "upd.exe".to_string(),
+             r"upd.exe --persist Software\Microsoft\Windows\CurrentVersion\Run Updater".to_string(), now - 2),
+            (40, "chrome.exe".to_string(), "chrome.exe".to_string(), now - 1),
+        ];
+        let key = r"Software\Microsoft\Windows\CurrentVersion\Run";
+        // Tool ghi registry + key + value → writer
+        assert_eq!(pick_writer(&processes, key, &changes[0].value_name), Some((20, "reg.exe".to_string())));
+        // Tool ghi đúng key nhưng value khác → unknown
+        assert_eq!(pick_writer(&processes, key, "OneDrive"), None);
+
+        // PowerShell phải là Set-/New-ItemProperty
+        let ps = r"powershell -c Set-ItemProperty -Path 'HKCU:\Software\Microsoft\Windows\CurrentVersion\Run' -Name Updater -Value x";
+        assert!(is_writer_command("powershell.exe", ps, key, "Updater"));
+        let ps_read = r"powershell -c Get-ItemProperty 'HKCU:\Software\Microsoft\Windows\CurrentVersion\Run' Updater";
+        assert!(!is_writer_command("powershell.exe", ps_read, key, "Updater"));
+
+        // reg.exe 32-bit ghi vào key 64-bit → bị redirect sang WOW6432Node
+        let wow = r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Run";
+        assert!(is_writer_command("reg.exe", r"reg add HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\Run /v Updater", wow, "Updater"));
+
+        // sc create <service> binPath= → Services\<service>\ImagePath
+        let svc = r"SYSTEM\CurrentControlSet\Services\evilsvc";
+        assert!(is_writer_command("sc.exe", r"sc create evilsvc binPath= C:\x.exe", svc, "ImagePath"));
+        assert!(!is_writer_command("sc.exe", r"sc create othersvc binPath= C:\x.exe", svc, "ImagePath"));
+        assert!(!is_writer_command("sc.exe", "sc query evilsvc", svc, "ImagePath"));
+    }
+}
@@ -6,24 +6,84 @@
 //! - YARA-like pattern matching
 //! - Condition-based rules
 //! - Custom severity and actions
+//! - Custom rules + trạng thái bật/tắt built-in được lưu ra `behavioral_rules.json`
+//! - Rules từ thư mục `rules/` (hot-reload, xem `rules_dir.rs`) - không lưu vào file trên
+//! - Exception list theo rule (path / hash / user / host, có hạn) - xem `suppressions.rs`
+//! - Sequence conditions: nhiều bước trong time window trên event history của process
+//!   (xem `history.rs`)
 
 use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use parking_lot::RwLock;
 use once_cell::sync::Lazy;
 use chrono::Utc;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 use super::types::{
     BehavioralRuleDefinition, RuleCondition, RuleAction, RuleSeverity,
-    RuleMatch, MatchContext, SampleContext,
+    RuleMatch, MatchContext, SampleContext, SequenceStep, ProcessEvent, ProcessEventKind,
+    NeverLearnReason,
 };
+use super::history;
+use super::suppressions;
+use crate::logic::incident::{self, DetectionContext, Severity};
+use crate::logic::action_guard;
+use crate::logic::telemetry::rule_stats;
 
 // ============================================================================
 // STATE
 // ============================================================================
 
 static ENGINE: Lazy<RwLock<RuleEngine>> =
-    Lazy::new(|| RwLock::new(RuleEngine::new()));
+    Lazy::new(|| RwLock::new(RuleEngine::with_persisted()));
+
+const RULES_FILE: &str = "behavioral_rules.json";
+
+/// Nội dung `behavioral_rules.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PersistedRules {
+    custom: Vec<BehavioralRuleDefinition>,
+    /// Built-in id → enabled (chỉ lưu khi khác mặc định)
+    builtin_enabled: HashMap<String, bool>,
+}
+
+/// Rule + thống kê cho UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleSummary {
+    #[serde(flatten)]
+    pub rule: BehavioralRuleDefinition,
+    pub builtin: bool,
+    /// File trong thư mục `rules/` định nghĩa rule (None = built-in / custom)
+    pub source_file: Option<String>,
+    /// Tổng số match từ khi agent start
+    pub match_count: u64,
+    pub last_matched: Option<i64>,
+}
+
+/// Kết quả chạy thử 1 rule (draft) trên 1 sample
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleTestResult {
+    pub rule_id: String,
+    /// Tất cả conditions khớp (AND)
+    pub matched: bool,
+    pub conditions: Vec<ConditionTestResult>,
+    /// Exception đang có sẽ suppress match này (id)
+    pub suppressed_by: Option<String>,
+    /// Context đã dùng để evaluate (kể cả khi lấy từ history)
+    pub context: SampleContext,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConditionTestResult {
+    /// Condition dạng JSON
+    pub condition: String,
+    pub matched: bool,
+    /// Mô tả match (như trong `RuleMatch::matched_conditions`)
+    pub detail: Option<String>,
+}
 
 // ============================================================================
 // BUILT-IN RULES
@@ -128,7 +188,11 @@ fn get_builtin_rules() -> Vec<BehavioralRuleDefinition> {
                     },
                 ]),
             ],
-            action: RuleAction::NeverLearn,
+            // mimikatz/procdump đã nằm trong never-learn blacklist
+            action: RuleAction::Respond {
+                decision: crate::logic::policy::Decision::RequireApproval,
+                action: action_guard::ActionType::KillProcess,
+            },
         },
 
         // Rule 5: Suspicious temp execution
@@ -191,6 +255,15 @@ pub struct RuleEngine {
 
     /// Compiled regexes cache
     regex_cache: HashMap<String, Regex>,
+
+    /// Default của built-in rules (id → enabled)
+    builtin_defaults: HashMap<String, bool>,
+
+    /// rule id → (match count, last matched)
+    match_counts: HashMap<String, (u64, i64)>,
+
+    /// rule id → file trong thư mục rules/ (hot-reload)
+    file_rules: HashMap<String, PathBuf>,
 }
 
 impl RuleEngine {
@@ -201,16 +274,84 @@ impl RuleEngine {
             max_matches: 1000,
             enabled: true,
             regex_cache: HashMap::new(),
+            builtin_defaults: HashMap::new(),
+            match_counts: HashMap::new(),
+            file_rules: HashMap::new(),
         };
 
         // Load built-in rules
         for rule in get_builtin_rules() {
+            engine.builtin_defaults.insert(rule.id.clone(), rule.enabled);
             engine.rules.insert(rule.id.clone(), rule);
         }
 
         engine
     }
 
+    /// Built-in + custom rules đã lưu trên disk
+    fn with_persisted() -> Self {
+        let mut engine = Self::new();
+        let persisted = load_persisted();
+
+        for (id, enabled) in persisted.builtin_enabled {
+            engine.set_rule_enabled(&id, enabled);
+        }
+        for rule in persisted.custom {
+            match validate_rule(&rule) {
+                Ok(()) if !engine.is_builtin(&rule.id) => engine.add_rule(rule),
+                Ok(()) => log::warn!("Skipping persisted rule {}: id clashes with built-in rule", rule.id),
+                Err(e) => log::warn!("Skipping invalid persisted rule {}: {}", rule.id, e),
+            }
+        }
+        engine
+    }
+
+    pub fn is_builtin(&self, rule_id: &str) -> bool {
+        self.builtin_defaults.contains_key(rule_id)
+    }
+
+    /// File định nghĩa rule (rule từ thư mục rules/)
+    pub fn source_file(&self, rule_id: &str) -> Option<&PathBuf> {
+        self.file_rules.get(rule_id)
+    }
+
+    /// Thay toàn bộ rules của 1 file (hot-swap). Rule trùng id với built-in / custom /
+    /// file khác bị bỏ qua. Trả về (id đã load, lỗi).
+    pub fn replace_file_rules(&mut self, path: &Path, rules: Vec<BehavioralRuleDefinition>) -> (Vec<String>, Vec<String>) {
+        self.remove_file_rules(path);
+
+        let mut loaded = Vec::new();
+        let mut errors = Vec::new();
+        for rule in rules {
+            if self.rules.contains_key(&rule.id) {
+                let owner = match self.file_rules.get(&rule.id) {
+                    Some(other) => other.display().to_string(),
+                    None if self.is_builtin(&rule.id) => "built-in rule".to_string(),
+                    None => "custom rule".to_string(),
+                };
+                errors.push(format!("{}: id already defined by {}", rule.id, owner));
+                continue;
+            }
+            self.file_rules.insert(rule.id.clone(), path.to_path_buf());
+            loaded.push(rule.id.clone());
+            self.rules.insert(rule.id.clone(), rule);
+        }
+        (loaded, errors)
+    }
+
+    /// Gỡ rules của 1 file (file bị xóa / reload)
+    pub fn remove_file_rules(&mut self, path: &Path) -> Vec<String> {
+        let ids: Vec<String> = self.file_rules.iter()
+            .filter(|(_, p)| p.as_path() == path)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &ids {
+            self.file_rules.remove(id);
+            self.rules.remove(id);
+        }
+        ids
+    }
+
     /// Evaluate all rules against a sample
     pub fn evaluate(&mut self, ctx: &SampleContext) -> Vec<RuleMatch> {
         if !self.enabled {
@@ -228,6 +369,12 @@ impl RuleEngine {
             }
 
             if let Some(matched_conditions) = self.evaluate_conditions(&rule.conditions, ctx) {
+                // Exception list - match hợp lệ đã biết, không tính
+                if let Some(suppression) = suppressions::check(&rule.id, ctx) {
+                    log::debug!("Rule {} match suppressed by {}", rule.id, suppression);
+                    continue;
+                }
+
                 let rule_match = RuleMatch {
                     rule_id: rule.id.clone(),
                     rule_name: rule.name.clone(),
@@ -246,6 +393,10 @@ impl RuleEngine {
                     action: rule.action.clone(),
                 };
 
+                let count = self.match_counts.entry(rule.id.clone()).or_insert((0, 0));
+                count.0 += 1;
+                count.1 = rule_match.timestamp;
+
                 results.push(rule_match.clone());
                 self.matches.push(rule_match);
             }
@@ -387,6 +538,17 @@ impl RuleEngine {
                 }
             }
 
+            RuleCondition::Sequence { steps, within_secs, include_descendants } => {
+                let events: Vec<&ProcessEvent> = ctx.history.iter()
+                    .filter(|e| *include_descendants || ctx.process_pid.map_or(true, |pid| e.pid == pid))
+                    .collect();
+                let (start, end) = self.match_sequence(steps, &events, *within_secs as i64 * 1000)?;
+                Some(format!(
+                    "Sequence of {} steps within {}s (took {:.1}s)",
+                    steps.len(), within_secs, (end - start) as f64 / 1000.0
+                ))
+            }
+
             RuleCondition::And(sub_conditions) => {
                 self.evaluate_conditions(sub_conditions, ctx)
                     .map(|_| "AND condition matched".to_string())
@@ -413,6 +575,73 @@ impl RuleEngine {
         }
     }
 
+    /// Tìm các bước theo thứ tự, bước cuối cách bước đầu <= window.
+    /// `events` phải sort theo thời gian. Trả về (ts bước đầu, ts bước cuối).
+    fn match_sequence(&mut self, steps: &[SequenceStep], events: &[&ProcessEvent], window_ms: i64) -> Option<(i64, i64)> {
+        let first = steps.first()?;
+
+        for (i, start) in events.iter().enumerate() {
+            if !self.matches_step(first, start) {
+                continue;
+            }
+            // Greedy: lấy event khớp sớm nhất cho mỗi bước tiếp theo
+            let mut next = 1;
+            let mut last = start.timestamp;
+            for event in &events[i + 1..] {
+                if next == steps.len() || event.timestamp - start.timestamp > window_ms {
+                    break;
+                }
+                if self.matches_step(&steps[next], event) {
+                    next += 1;
+                    last = event.timestamp;
+                }
+            }
+            if next == steps.len() {
+                return Some((start.timestamp, last));
+            }
+        }
+        None
+    }
+
+    fn matches_step(&mut self, step: &SequenceStep, event: &ProcessEvent) -> bool {
+        match (step, &event.kind) {
+            (
+                SequenceStep::ProcessStart { pattern, parent_pattern, is_regex },
+                ProcessEventKind::ProcessStart { name, parent, .. },
+            ) => {
+                if !self.matches_pattern(name, pattern, *is_regex) {
+                    return false;
+                }
+                match (parent_pattern, parent) {
+                    (None, _) => true,
+                    (Some(expected), Some(parent)) => self.matches_pattern(parent, expected, *is_regex),
+                    (Some(_), None) => false,
+                }
+            }
+            (SequenceStep::ChildSpawned { pattern, is_regex }, ProcessEventKind::ChildSpawned { name, .. }) => {
+                self.matches_pattern(name, pattern, *is_regex)
+            }
+            (
+                SequenceStep::OutboundConnection { port, dest_pattern },
+                ProcessEventKind::NetworkConnect { remote_ip, remote_port, domain },
+            ) => {
+                port.map_or(true, |p| p == *remote_port)
+                    && dest_pattern.as_ref().map_or(true, |d| {
+                        let d = d.to_lowercase();
+                        remote_ip.to_string().contains(&d)
+                            || domain.as_ref().map_or(false, |domain| domain.contains(&d))
+                    })
+            }
+            (SequenceStep::FileWrite { path_pattern }, ProcessEventKind::FileWrite { path }) => {
+                self.matches_pattern(path, path_pattern, false)
+            }
+            (SequenceStep::RegistryWrite { key_pattern }, ProcessEventKind::RegistryWrite { key }) => {
+                self.matches_pattern(key, key_pattern, false)
+            }
+            _ => false,
+        }
+    }
+
     /// Match string against pattern (literal or regex)
     fn matches_pattern(&mut self, text: &str, pattern: &str, is_regex: bool) -> bool {
         if is_regex {
@@ -452,6 +681,43 @@ impl RuleEngine {
         }
     }
 
+    /// Rules kèm match count (sắp theo id)
+    pub fn summaries(&self) -> Vec<RuleSummary> {
+        let mut summaries: Vec<RuleSummary> = self.rules.values()
+            .map(|rule| {
+                let (match_count, last_matched) = self.match_counts.get(&rule.id)
+                    .map(|(count, at)| (*count, Some(*at)))
+                    .unwrap_or((0, None));
+                RuleSummary {
+                    rule: rule.clone(),
+                    builtin: self.is_builtin(&rule.id),
+                    source_file: self.file_rules.get(&rule.id).map(|p| p.display().to_string()),
+                    match_count,
+                    last_matched,
+                }
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.rule.id.cmp(&b.rule.id));
+        summaries
+    }
+
+    fn to_persisted(&self) -> PersistedRules {
+        let mut custom: Vec<BehavioralRuleDefinition> = self.rules.values()
+            .filter(|r| !self.is_builtin(&r.id) && !self.file_rules.contains_key(&r.id))
+            .cloned()
+            .collect();
+        custom.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let builtin_enabled = self.builtin_defaults.iter()
+            .filter_map(|(id, default)| {
+                let enabled = self.rules.get(id)?.enabled;
+                (enabled != *default).then(|| (id.clone(), enabled))
+            })
+            .collect();
+
+        PersistedRules { custom, builtin_enabled }
+    }
+
     /// Get recent matches
     pub fn get_matches(&self, limit: usize) -> Vec<RuleMatch> {
         let start = self.matches.len().saturating_sub(limit);
@@ -484,19 +750,199 @@ pub fn evaluate(ctx: &SampleContext) -> Vec<RuleMatch> {
     ENGINE.write().evaluate(ctx)
 }
 
-/// Add a custom rule
+/// Evaluate rules cho các process có event mới trong history (gọi từ analysis loop).
+/// Mỗi (rule, process) chỉ báo 1 lần. Trả về match mới.
+pub fn check_processes() -> Vec<RuleMatch> {
+    let mut reported = Vec::new();
+    for pid in history::collect() {
+        let Some(ctx) = history::build_context(pid) else { continue };
+        for rule_match in evaluate(&ctx) {
+            if history::first_report(&rule_match.rule_id, pid) {
+                rule_stats::record_match(&rule_match.rule_id);
+                let response = request_response(&rule_match);
+                raise_incident(&rule_match, response.as_deref());
+                reported.push(rule_match);
+            }
+        }
+    }
+    reported
+}
+
+/// Match → incident (Info chỉ lưu trong match history, NeverLearn được báo lên cloud)
+fn raise_incident(rule_match: &RuleMatch, response: Option<&str>) {
+    if matches!(rule_match.action, RuleAction::NeverLearn) {
+        super::never_learn::record_decision(
+            &NeverLearnReason::CustomRule { rule_id: rule_match.rule_id.clone() },
+            rule_match.context.process_name.as_deref(),
+        );
+        return;
+    }
+
+    let severity = match rule_match.severity {
+        RuleSeverity::Info => return,
+        RuleSeverity::Low => Severity::Low,
+        RuleSeverity::Medium => Severity::Medium,
+        RuleSeverity::High => Severity::High,
+        RuleSeverity::Critical => Severity::Critical,
+    };
+
+    let process = rule_match.context.process_name.as_deref().unwrap_or("unknown");
+    let mut description = format!(
+        "{} (PID {}) matched rule {}: {}",
+        process,
+        rule_match.context.process_pid.unwrap_or(0),
+        rule_match.rule_id,
+        rule_match.matched_conditions.join("; ")
+    );
+    if let Some(response) = response {
+        description.push_str(&format!(". Response: {}", response));
+    }
+    let mitre: Vec<&str> = rule_match.mitre_technique.as_deref().into_iter().collect();
+    let mut rule_matches = vec![rule_match.rule_id.clone()];
+    rule_matches.extend(rule_match.matched_conditions.iter().cloned());
+    let incident_id = incident::raise_process_detection(
+        &format!("Behavioral rule: {} ({})", rule_match.rule_name, process),
+        severity.clone(),
+        &["BEHAVIORAL_RULE".to_string(), rule_match.rule_id.clone()],
+        &mitre,
+        &description,
+        DetectionContext { pid: rule_match.context.process_pid, rule_matches },
+    );
+
+    // Severity của incident sau khi gộp/escalate (tuning stats)
+    if let Some(id) = incident_id {
+        let resulting = incident::get_incident(id).map(|i| i.severity).unwrap_or(severity);
+        rule_stats::record_incident(&rule_match.rule_id, &resulting);
+    }
+}
+
+/// Rule action Block/Quarantine/Respond → Action Guard (giống ML verdict)
+fn request_response(rule_match: &RuleMatch) -> Option<String> {
+    let (decision, action) = rule_match.action.response_request()?;
+    let pid = rule_match.context.process_pid?;
+
+    let mut tags = vec!["BEHAVIORAL_RULE".to_string(), rule_match.rule_id.clone()];
+    tags.extend(rule_match.mitre_technique.clone());
+
+    let input = action_guard::RuleResponseInput {
+        rule_id: rule_match.rule_id.clone(),
+        decision,
+        action,
+        critical: rule_match.severity == RuleSeverity::Critical,
+        target_pid: pid,
+        target_name: rule_match.context.process_name.clone().unwrap_or_else(|| "unknown".to_string()),
+        target_path: rule_match.context.process_path.as_ref().map(|p| p.to_string_lossy().to_string()),
+        tags,
+    };
+    let output = action_guard::decide_from_rule(&input);
+    if output.action.is_none() {
+        return None;
+    }
+
+    match action_guard::execute_from_rule(&input, &output) {
+        Ok(result) => Some(result.message),
+        Err(e) => {
+            log::warn!("Rule {} response failed: {}", rule_match.rule_id, e.0);
+            Some(format!("{} failed: {}", action.to_string(), e.0))
+        }
+    }
+}
+
+/// Add a custom rule (ghi đè nếu trùng id, được lưu xuống disk)
 pub fn add_rule(rule: BehavioralRuleDefinition) {
     ENGINE.write().add_rule(rule);
+    save_persisted();
 }
 
 /// Remove a rule
 pub fn remove_rule(rule_id: &str) {
     ENGINE.write().remove_rule(rule_id);
+    save_persisted();
 }
 
 /// Enable/disable a rule
 pub fn set_rule_enabled(rule_id: &str, enabled: bool) {
     ENGINE.write().set_rule_enabled(rule_id, enabled);
+    save_persisted();
+}
+
+/// Tạo custom rule mới (id chưa tồn tại)
+pub fn create_rule(rule: BehavioralRuleDefinition) -> Result<(), String> {
+    validate_rule(&rule)?;
+    {
+        let mut engine = ENGINE.write();
+        if engine.rules.contains_key(&rule.id) {
+            return Err(format!("Rule {} already exists", rule.id));
+        }
+        engine.add_rule(rule);
+    }
+    save_persisted();
+    Ok(())
+}
+
+/// Cập nhật custom rule. Built-in chỉ được bật/tắt.
+pub fn update_rule(rule: BehavioralRuleDefinition) -> Result<(), String> {
+    validate_rule(&rule)?;
+    {
+        let mut engine = ENGINE.write();
+        if engine.is_builtin(&rule.id) {
+            return Err(format!("Built-in rule {} cannot be modified (only enabled/disabled)", rule.id));
+        }
+        if let Some(path) = engine.source_file(&rule.id) {
+            return Err(format!("Rule {} is managed by {} - edit the file instead", rule.id, path.display()));
+        }
+        if !engine.rules.contains_key(&rule.id) {
+            return Err(format!("Rule {} not found", rule.id));
+        }
+        engine.add_rule(rule);
+    }
+    save_persisted();
+    Ok(())
+}
+
+/// Xóa custom rule
+pub fn delete_rule(rule_id: &str) -> Result<(), String> {
+    {
+        let mut engine = ENGINE.write();
+        if engine.is_builtin(rule_id) {
+            return Err(format!("Built-in rule {} cannot be deleted (disable it instead)", rule_id));
+        }
+        if let Some(path) = engine.source_file(rule_id) {
+            return Err(format!("Rule {} is managed by {} - delete it from the file instead", rule_id, path.display()));
+        }
+        if !engine.rules.contains_key(rule_id) {
+            return Err(format!("Rule {} not found", rule_id));
+        }
+        engine.remove_rule(rule_id);
+        engine.match_counts.remove(rule_id);
+    }
+    save_persisted();
+    rule_stats::reset(rule_id);
+    Ok(())
+}
+
+/// Bật/tắt rule (built-in hoặc custom)
+pub fn toggle_rule(rule_id: &str, enabled: bool) -> Result<(), String> {
+    if !ENGINE.read().rules.contains_key(rule_id) {
+        return Err(format!("Rule {} not found", rule_id));
+    }
+    set_rule_enabled(rule_id, enabled);
+    Ok(())
+}
+
+/// Hot-swap rules của 1 file trong thư mục rules/
+pub fn replace_file_rules(path: &Path, rules: Vec<BehavioralRuleDefinition>) -> (Vec<String>, Vec<String>) {
+    ENGINE.write().replace_file_rules(path, rules)
+}
+
+/// Gỡ rules của 1 file trong thư mục rules/
+pub fn remove_file_rules(path: &Path) -> Vec<String> {
+    ENGINE.write().remove_file_rules(path)
+}
+
+/// Rules kèm match count
+pub fn list_rules() -> Vec<RuleSummary> {
+    ENGINE.read().summaries()
 }
 
 /// Get recent matches
@@ -524,6 +970,157 @@ pub fn get_rule(rule_id: &str) -> Option<BehavioralRuleDefinition> {
     ENGINE.read().rules.get(rule_id).cloned()
 }
 
+/// Chạy thử rule trên sample - từng condition khớp hay không. Không đụng tới
+/// engine (match history, match count, hit count của exception).
+pub fn test_rule(rule: &BehavioralRuleDefinition, ctx: SampleContext) -> Result<RuleTestResult, String> {
+    validate_rule(rule)?;
+
+    let mut engine = RuleEngine::new();
+    let conditions: Vec<ConditionTestResult> = rule.conditions.iter()
+        .map(|condition| {
+            let detail = engine.evaluate_single_condition(condition, &ctx);
+            ConditionTestResult {
+                condition: serde_json::to_string(condition).unwrap_or_default(),
+                matched: detail.is_some(),
+                detail,
+            }
+        })
+        .collect();
+    let matched = conditions.iter().all(|c| c.matched);
+    let suppressed_by = if matched { suppressions::find(&rule.id, &ctx) } else { None };
+
+    Ok(RuleTestResult {
+        rule_id: rule.id.clone(),
+        matched,
+        conditions,
+        suppressed_by,
+        context: ctx,
+    })
+}
+
+// ============================================================================
+// VALIDATION & PERSISTENCE
+// ============================================================================
+
+/// Kiểm tra rule trước khi đưa vào engine (id, conditions, regex compile được)
+pub fn validate_rule(rule: &BehavioralRuleDefinition) -> Result<(), String> {
+    if rule.id.is_empty() || !rule.id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(format!("Invalid rule id '{}' (allowed: A-Z, 0-9, '_', '-')", rule.id));
+    }
+    if rule.name.trim().is_empty() {
+        return Err("Rule name is required".to_string());
+    }
+    if rule.conditions.is_empty() {
+        return Err("Rule must have at least one condition".to_string());
+    }
+    validate_action(rule)?;
+    rule.conditions.iter().try_for_each(validate_condition)
+}
+
+fn validate_action(rule: &BehavioralRuleDefinition) -> Result<(), String> {
+    use crate::logic::policy::Decision;
+
+    if let RuleAction::Respond { decision, action } = &rule.action {
+        if !matches!(decision, Decision::RequireApproval | Decision::AutoBlock) {
+            return Err(format!("Respond decision must be RequireApproval or AutoBlock (got {:?})", decision));
+        }
+        if *action == action_guard::ActionType::AlertOnly {
+            return Err("Respond action must not be AlertOnly (use Alert)".to_string());
+        }
+        if *decision == Decision::AutoBlock && rule.severity != RuleSeverity::Critical {
+            return Err("AutoBlock is only allowed for Critical rules".to_string());
+        }
+    }
+    Ok(())
+}
+
+fn validate_condition(condition: &RuleCondition) -> Result<(), String> {
+    match condition {
+        RuleCondition::ProcessName { pattern, is_regex }
+        | RuleCondition::ProcessPath { pattern, is_regex }
+        | RuleCondition::ProcessCmdline { pattern, is_regex }
+        | RuleCondition::ParentProcessName { pattern, is_regex } => {
+            if pattern.is_empty() {
+                return Err("Empty pattern".to_string());
+            }
+            if *is_regex {
+                Regex::new(pattern).map_err(|e| format!("Invalid regex '{}': {}", pattern, e))?;
+            }
+            Ok(())
+        }
+        RuleCondition::And(subs) | RuleCondition::Or(subs) => {
+            if subs.is_empty() {
+                return Err("Empty AND/OR group".to_string());
+            }
+            subs.iter().try_for_each(validate_condition)
+        }
+        RuleCondition::Not(sub) => validate_condition(sub),
+        RuleCondition::Sequence { steps, within_secs, .. } => {
+            if steps.is_empty() {
+                return Err("Empty sequence".to_string());
+            }
+            if *within_secs == 0 || *within_secs > history::RETENTION_SECS {
+                return Err(format!("Sequence window must be 1-{}s", history::RETENTION_SECS));
+            }
+            steps.iter().try_for_each(validate_step)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_step(step: &SequenceStep) -> Result<(), String> {
+    let patterns: Vec<(&str, bool)> = match step {
+        SequenceStep::ProcessStart { pattern, parent_pattern, is_regex } => {
+            let mut patterns = vec![(pattern.as_str(), *is_regex)];
+            patterns.extend(parent_pattern.as_deref().map(|p| (p, *is_regex)));
+            patterns
+        }
+        SequenceStep::ChildSpawned { pattern, is_regex } => vec![(pattern.as_str(), *is_regex)],
+        SequenceStep::FileWrite { path_pattern } => vec![(path_pattern.as_str(), false)],
+        SequenceStep::RegistryWrite { key_pattern } => vec![(key_pattern.as_str(), false)],
+        SequenceStep::OutboundConnection { .. } => Vec::new(),
+    };
+    for (pattern, is_regex) in patterns {
+        if pattern.is_empty() {
+            return Err("Empty pattern in sequence step".to_string());
+        }
+        if is_regex {
+            Regex::new(pattern).map_err(|e| format!("Invalid regex '{}': {}", pattern, e))?;
+        }
+    }
+    Ok(())
+}
+
+fn rules_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(RULES_FILE)
+}
+
+fn load_persisted() -> PersistedRules {
+    fs::read_to_string(rules_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_persisted() {
+    let persisted = ENGINE.read().to_persisted();
+    let path = rules_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    match serde_json::to_string_pretty(&persisted) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&path, json) {
+                log::error!("Failed to persist behavioral rules: {}", e);
+            }
+        }
+        Err(e) => log::error!("Failed to serialize behavioral rules: {}", e),
+    }
+}
+
 // ============================================================================
 // STATISTICS
 // ============================================================================
@@ -536,6 +1133,8 @@ pub struct RuleEngineStats {
     pub total_matches: usize,
     pub matches_by_severity: HashMap<String, usize>,
     pub top_triggered_rules: Vec<(String, usize)>,
+    /// Tuning stats (persisted, sống qua restart)
+    pub rule_tuning: Vec<rule_stats::RuleTuningStats>,
 }
 
 pub fn get_stats() -> RuleEngineStats {
@@ -560,6 +1159,7 @@ pub fn get_stats() -> RuleEngineStats {
         total_matches: engine.matches.len(),
         matches_by_severity: by_severity,
         top_triggered_rules: top_rules,
+        rule_tuning: rule_stats::get_all(),
     }
 }
 
@@ -614,4 +1214,166 @@ mod tests {
         let matches = engine.evaluate(&ctx);
         assert!(matches.is_empty() || matches.iter().all(|m| m.severity <= RuleSeverity::Low));
     }
+
+    #[test]
+    fn test_custom_rule_persistence_and_counts() {
+        let mut engine = RuleEngine::new();
+        let rule = BehavioralRuleDefinition {
+            id: "IT_CERTUTIL".to_string(),
+            name: "Certutil decode".to_string(),
+            description: String::new(),
+            enabled: true,
+            severity: RuleSeverity::Medium,
+            mitre_technique: None,
+            conditions: vec![RuleCondition::ProcessCmdline { pattern: "-decode".to_string(), is_regex: false }],
+            action: RuleAction::Alert,
+        };
+        assert!(validate_rule(&rule).is_ok());
+        engine.add_rule(rule);
+        engine.set_rule_enabled("ENCODED_PS", false);
+
+        let persisted = engine.to_persisted();
+        assert_eq!(persisted.custom.len(), 1);
+        assert_eq!(persisted.builtin_enabled.get("ENCODED_PS"), Some(&false));
+
+        let ctx = SampleContext {
+            process_name: Some("certutil.exe".to_string()),
+            process_cmdline: Some("certutil -decode a.b64 a.exe".to_string()),
+            ..Default::default()
+        };
+        engine.evaluate(&ctx);
+        engine.evaluate(&ctx);
+        let summary = engine.summaries().into_iter().find(|s| s.rule.id == "IT_CERTUTIL").unwrap();
+        assert_eq!(summary.match_count, 2);
+        assert!(!summary.builtin);
+
+        let bad = BehavioralRuleDefinition {
+            id: "bad id".to_string(),
+            conditions: vec![RuleCondition::ProcessName { pattern: "(".to_string(), is_regex: true }],
+            ..persisted.custom[0].clone()
+        };
+        assert!(validate_rule(&bad).is_err());
+    }
+
+    #[test]
+    fn test_respond_action_mapping() {
+        use crate::logic::action_guard::ActionType;
+        use crate::logic::policy::Decision;
+
+        let mut rule: BehavioralRuleDefinition = serde_json::from_value(serde_json::json!({
+            "id": "RESPOND_TEST",
+            "name": "Respond test",
+            "description": "",
+            "severity": "High",
+            "conditions": [{ "ProcessName": { "pattern": "evil.exe", "is_regex": false } }],
+            "action": { "Respond": { "decision": "AutoBlock", "action": "KillProcessTree" } },
+            "enabled": true,
+            "mitre_technique": null
+        })).unwrap();
+        assert_eq!(
+            rule.action.response_request(),
+            Some((Decision::AutoBlock, ActionType::KillProcessTree))
+        );
+        // AutoBlock chỉ cho rule Critical
+        assert!(validate_rule(&rule).is_err());
+        rule.severity = RuleSeverity::Critical;
+        assert!(validate_rule(&rule).is_ok());
+
+        rule.action = RuleAction::Respond { decision: Decision::Notify, action: ActionType::KillProcess };
+        assert!(validate_rule(&rule).is_err());
+
+        assert_eq!(RuleAction::Alert.response_request(), None);
+        assert_eq!(
+            RuleAction::Block.response_request(),
+            Some((Decision::RequireApproval, ActionType::KillProcess))
+        );
+    }
+
+    #[test]
+    fn test_rule_dry_run() {
+        let rule: BehavioralRuleDefinition = serde_json::from_value(serde_json::json!({
+            "id": "DRAFT_CERTUTIL",
+            "name": "Certutil download",
+            "description": "",
+            "severity": "High",
+            "conditions": [
+                { "ProcessName": { "pattern": "certutil.exe", "is_regex": false } },
+                { "ProcessCmdline": { "pattern": "-urlcache", "is_regex": false } }
+            ],
+            "action": "Alert",
+            "enabled": false,
+            "mitre_technique": null
+        })).unwrap();
+        let ctx: SampleContext = serde_json::from_value(serde_json::json!({
+            "process_name": "certutil.exe",
+            "process_cmdline": "certutil.exe -decode a.b64 a.exe"
+        })).unwrap();
+
+        let result = test_rule(&rule, ctx).unwrap();
+        assert!(!result.matched);
+        assert!(result.conditions[0].matched);
+        assert!(!result.conditions[1].matched);
+        // Dry run không ghi vào match history
+        assert!(get_matches(1000).iter().all(|m| m.rule_id != "DRAFT_CERTUTIL"));
+    }
+
+    #[test]
+    fn test_sequence_condition() {
+        let mut engine = RuleEngine::new();
+        let rule = BehavioralRuleDefinition {
+            id: "OFFICE_CMD_NET".to_string(),
+            name: "Office spawns cmd then connects out".to_string(),
+            description: String::new(),
+            enabled: true,
+            severity: RuleSeverity::High,
+            mitre_technique: Some("T1204.002".to_string()),
+            conditions: vec![RuleCondition::Sequence {
+                steps: vec![
+                    SequenceStep::ProcessStart {
+                        pattern: "cmd.exe".to_string(),
+                        parent_pattern: Some("winword.exe".to_string()),
+                        is_regex: false,
+                    },
+                    SequenceStep::OutboundConnection { port: Some(443), dest_pattern: None },
+                ],
+                within_secs: 60,
+                include_descendants: true,
+            }],
+            action: RuleAction::Alert,
+        };
+        assert!(validate_rule(&rule).is_ok());
+        engine.add_rule(rule);
+
+        let start = ProcessEvent {
+            pid: 100,
+            kind: ProcessEventKind::ProcessStart {
+                name: "cmd.exe".to_string(),
+                parent: Some("WINWORD.EXE".to_string()),
+                cmdline: None,
+            },
+            timestamp: 1_000,
+        };
+        let connect = |pid: u32, at: i64| ProcessEvent {
+            pid,
+            kind: ProcessEventKind::NetworkConnect {
+                remote_ip: "203.0.113.9".parse().unwrap(),
+                remote_port: 443,
+                domain: None,
+            },
+            timestamp: at,
+        };
+
+        // Connection từ powershell (con của cmd) sau 20s → khớp
+        let mut ctx = SampleContext::new().with_process("cmd.exe", 100);
+        ctx.history = vec![start.clone(), connect(101, 21_000)];
+        assert!(engine.evaluate(&ctx).iter().any(|m| m.rule_id == "OFFICE_CMD_NET"));
+
+        // Ngoài window 60s → không khớp
+        ctx.history = vec![start.clone(), connect(101, 90_000)];
+        assert!(!engine.evaluate(&ctx).iter().any(|m| m.rule_id == "OFFICE_CMD_NET"));
+
+        // Sai thứ tự → không khớp
+        ctx.history = vec![connect(100, 500), start];
+        assert!(!engine.evaluate(&ctx).iter().any(|m| m.rule_id == "OFFICE_CMD_NET"));
+    }
 }
@@ -0,0 +1,271 @@
+//! Rules Directory - Hot-reload behavioral rules từ file
+//!
+//! Mục đích: Thêm / sửa rule bằng cách thả file vào `OneShield/rules/` mà không
+//! cần restart agent
+//!
+//! - Định dạng: `.json`, `.yaml`, `.yml` - 1 rule hoặc danh sách rule
+//! - Watcher (notify) + debounce: file đổi → parse + validate → hot-swap rules của file đó
+//! - File lỗi (parse / validate) → giữ nguyên bản đã load trước đó, log lỗi theo file
+//! - File bị xóa → gỡ rules của nó khỏi engine
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+
+use super::rules;
+use super::types::BehavioralRuleDefinition;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const RULES_DIR: &str = "rules";
+/// Editor ghi file nhiều lần liên tiếp → chờ file ổn định
+const DEBOUNCE: Duration = Duration::from_millis(750);
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Kết quả load gần nhất của 1 file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleFileStatus {
+    pub path: String,
+    /// Rule id đang active từ file này
+    pub loaded: Vec<String>,
+    /// Lỗi parse / validate / trùng id (rỗng = OK)
+    pub errors: Vec<String>,
+    pub loaded_at: i64,
+}
+
+/// File chứa 1 rule hoặc danh sách rule
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RuleFile {
+    Many(Vec<BehavioralRuleDefinition>),
+    One(BehavioralRuleDefinition),
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static WATCHER: Mutex<Option<RecommendedWatcher>> = Mutex::new(None);
+/// File chờ reload → thời điểm event cuối
+static PENDING: Lazy<Mutex<HashMap<PathBuf, Instant>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static STATUS: Lazy<Mutex<HashMap<PathBuf, RuleFileStatus>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Thư mục rules
+pub fn rules_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(RULES_DIR)
+}
+
+/// Load toàn bộ thư mục + watch thay đổi
+pub fn start() {
+    let dir = rules_dir();
+    if let Err(e) = fs::create_dir_all(&dir) {
+        log::error!("Rules directory {} unavailable: {}", dir.display(), e);
+        return;
+    }
+    reload_all();
+
+    let mut watcher = match notify::recommended_watcher(|res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+                for path in event.paths.iter().filter(|p| is_rule_file(p)) {
+                    PENDING.lock().insert(path.clone(), Instant::now());
+                }
+            }
+        }
+    }) {
+        Ok(w) => w,
+        Err(e) => {
+            log::error!("Rules directory watcher failed: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+        log::error!("Cannot watch rules directory {}: {}", dir.display(), e);
+        return;
+    }
+    *WATCHER.lock() = Some(watcher);
+
+    std::thread::spawn(|| loop {
+        process_pending();
+        std::thread::sleep(Duration::from_millis(250));
+    });
+
+    log::info!("Rules directory watcher started ({})", dir.display());
+}
+
+/// Reload tất cả file trong thư mục (command / lúc start)
+pub fn reload_all() -> Vec<RuleFileStatus> {
+    let dir = rules_dir();
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).filter(|p| is_rule_file(p)).collect())
+        .unwrap_or_default();
+    files.sort();
+
+    // File đã load trước đó nhưng không còn → gỡ
+    let known: Vec<PathBuf> = STATUS.lock().keys().cloned().collect();
+    for path in known.iter().filter(|p| !files.contains(p)) {
+        reload_file(path);
+    }
+    files.iter().map(|p| reload_file(p)).collect()
+}
+
+/// Trạng thái load của từng file
+pub fn get_status() -> Vec<RuleFileStatus> {
+    let mut status: Vec<RuleFileStatus> = STATUS.lock().values().cloned().collect();
+    status.sort_by(|a, b| a.path.cmp(&b.path));
+    status
+}
+
+// ============================================================================
+// LOADING
+// ============================================================================
+
+fn process_pending() {
+    let ready: Vec<PathBuf> = {
+        let mut pending = PENDING.lock();
+        let ready: Vec<PathBuf> = pending.iter()
+            .filter(|(_, at)| at.elapsed() >= DEBOUNCE)
+            .map(|(p, _)| p.clone())
+            .collect();
+        for path in &ready {
+            pending.remove(path);
+        }
+        ready
+    };
+    for path in ready {
+        reload_file(&path);
+    }
+}
+
+/// Load / reload / gỡ rules của 1 file
+fn reload_file(path: &Path) -> RuleFileStatus {
+    if !path.exists() {
+        let removed = rules::remove_file_rules(path);
+        if !removed.is_empty() {
+            log::info!("Rules file {} removed - unloaded {} rules", path.display(), removed.len());
+        }
+        STATUS.lock().remove(path);
+        return RuleFileStatus {
+            path: path.display().to_string(),
+            loaded: Vec::new(),
+            errors: Vec::new(),
+            loaded_at: Utc::now().timestamp(),
+        };
+    }
+
+    let status = match fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|c| parse_rules(path, &c)) {
+        Ok(parsed) => {
+            let (loaded, errors) = rules::replace_file_rules(path, parsed);
+            log::info!("Loaded {} rules from {}", loaded.len(), path.display());
+            RuleFileStatus {
+                path: path.display().to_string(),
+                loaded,
+                errors,
+                loaded_at: Utc::now().timestamp(),
+            }
+        }
+        Err(errors) => {
+            // Giữ bản đã load trước đó
+            let previous = STATUS.lock().get(path).map(|s| s.loaded.clone()).unwrap_or_default();
+            RuleFileStatus {
+                path: path.display().to_string(),
+                loaded: previous,
+                errors: vec![errors],
+                loaded_at: Utc::now().timestamp(),
+            }
+        }
+    };
+
+    for error in &status.errors {
+        log::warn!("Rules file {}: {}", path.display(), error);
+    }
+    STATUS.lock().insert(path.to_path_buf(), status.clone());
+    status
+}
+
+/// Parse + validate tất cả rules trong file. Lỗi bất kỳ → cả file bị từ chối.
+pub fn parse_rules(path: &Path, content: &str) -> Result<Vec<BehavioralRuleDefinition>, String> {
+    let parsed: RuleFile = match extension(path).as_str() {
+        "json" => serde_json::from_str(content).map_err(|e| format!("JSON parse error: {}", e))?,
+        _ => serde_yaml::from_str(content).map_err(|e| format!("YAML parse error: {}", e))?,
+    };
+    let rules = match parsed {
+        RuleFile::Many(rules) => rules,
+        RuleFile::One(rule) => vec![rule],
+    };
+
+    let errors: Vec<String> = rules.iter()
+        .filter_map(|r| rules::validate_rule(r).err().map(|e| format!("{}: {}", r.id, e)))
+        .collect();
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    if let Some(dup) = rules.iter().find(|r| !seen.insert(r.id.as_str())) {
+        return Err(format!("{}: duplicate id in file", dup.id));
+    }
+    Ok(rules)
+}
+
+fn extension(path: &Path) -> String {
+    path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default()
+}
+
+fn is_rule_file(path: &Path) -> bool {
+    matches!(extension(path).as_str(), "json" | "yaml" | "yml")
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rule_files() {
+        let yaml = r#"
+- id: IT_CERTUTIL_DECODE
+  name: Certutil decode
+  description: certutil -decode outside IT scripts
+  enabled: true
+  severity: Medium
+  mitre_technique: T1140
+  conditions:
+    - ProcessName: { pattern: certutil.exe, is_regex: false }
+    - ProcessCmdline: { pattern: "(?i)-decode", is_regex: true }
+  action: Alert
+"#;
+        let rules = parse_rules(Path::new("it.yaml"), yaml).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].conditions.len(), 2);
+
+        let json = r#"{"id":"BAD","name":"Bad regex","description":"","enabled":true,"severity":"Low",
+            "mitre_technique":null,"conditions":[{"ProcessCmdline":{"pattern":"(","is_regex":true}}],"action":"Alert"}"#;
+        let err = parse_rules(Path::new("bad.json"), json).unwrap_err();
+        assert!(err.contains("Invalid regex"));
+
+        assert!(is_rule_file(Path::new("a.YML")));
+        assert!(!is_rule_file(Path::new("a.txt")));
+    }
+}
@@ -0,0 +1,364 @@
+//! Rule Suppressions - Exception list theo rule
+//!
+//! Mục đích: Tắt match ồn nhưng hợp lệ (vd. script IT dùng certutil) mà không
+//! phải tắt cả rule.
+//!
+//! - Entry theo rule (hoặc `*` = mọi rule): process path, sha256, user, host
+//! - `expires_at` tùy chọn - entry hết hạn tự gỡ (ghi audit "expired")
+//! - Audit trail: ai tạo / gỡ, lý do, lúc nào - lưu cùng entries
+//! - Match bị suppress không tính vào match history, chỉ tăng `hit_count` của entry
+
+use std::fs;
+use std::path::PathBuf;
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::types::SampleContext;
+use crate::logic::whitelist;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const SUPPRESSIONS_FILE: &str = "rule_suppressions.json";
+const MAX_AUDIT_ENTRIES: usize = 2000;
+/// Áp dụng cho mọi rule
+pub const ANY_RULE: &str = "*";
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuppressionKind {
+    /// Đường dẫn executable (kết thúc bằng `*` = prefix)
+    ProcessPath,
+    Sha256,
+    /// `user` hoặc `DOMAIN\user`
+    User,
+    Host,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleSuppression {
+    pub id: String,
+    /// Rule id hoặc `*`
+    pub rule_id: String,
+    pub kind: SuppressionKind,
+    /// Giá trị đã normalize (lowercase)
+    pub value: String,
+    pub reason: String,
+    pub created_by: String,
+    pub created_at: i64,
+    /// Unix seconds, None = không hết hạn
+    pub expires_at: Option<i64>,
+    #[serde(default)]
+    pub hit_count: u64,
+    #[serde(default)]
+    pub last_hit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuppressionAuditEntry {
+    pub timestamp: i64,
+    /// "created" | "removed" | "expired"
+    pub action: String,
+    pub suppression_id: String,
+    pub rule_id: String,
+    pub kind: SuppressionKind,
+    pub value: String,
+    pub actor: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SuppressionStore {
+    entries: Vec<RuleSuppression>,
+    audit: Vec<SuppressionAuditEntry>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static STORE: Lazy<RwLock<SuppressionStore>> = Lazy::new(|| RwLock::new(load()));
+
+static HOSTNAME: Lazy<String> = Lazy::new(|| {
+    hostname::get()
+        .map(|h| h.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+});
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Thêm exception cho rule
+pub fn add(
+    rule_id: &str,
+    kind: SuppressionKind,
+    value: &str,
+    reason: &str,
+    expires_at: Option<i64>,
+    actor: &str,
+) -> Result<RuleSuppression, String> {
+    let now = Utc::now().timestamp();
+    if rule_id.trim().is_empty() {
+        return Err("Rule id is required (use '*' for all rules)".to_string());
+    }
+    if value.trim().is_empty() {
+        return Err("Suppression value is required".to_string());
+    }
+    if reason.trim().is_empty() {
+        return Err("A reason is required for the audit trail".to_string());
+    }
+    if kind == SuppressionKind::Sha256 && !is_sha256(value.trim()) {
+        return Err(format!("Invalid SHA-256: {}", value));
+    }
+    if expires_at.map_or(false, |at| at <= now) {
+        return Err("Expiry must be in the future".to_string());
+    }
+
+    let entry = RuleSuppression {
+        id: uuid::Uuid::new_v4().to_string(),
+        rule_id: rule_id.trim().to_string(),
+        kind,
+        value: normalize(kind, value),
+        reason: reason.trim().to_string(),
+        created_by: actor.to_string(),
+        created_at: now,
+        expires_at,
+        hit_count: 0,
+        last_hit: None,
+    };
+
+    {
+        let mut store = STORE.write();
+        let audit = audit_entry("created", &entry, actor, Some(entry.reason.clone()));
+        push_audit(&mut store, audit);
+        store.entries.push(entry.clone());
+    }
+    save();
+    log::info!("Rule suppression added: {} {:?}={} ({})", entry.rule_id, entry.kind, entry.value, entry.reason);
+    Ok(entry)
+}
+
+/// Gỡ exception
+pub fn remove(id: &str, actor: &str, reason: Option<String>) -> Result<(), String> {
+    {
+        let mut store = STORE.write();
+        let index = store.entries.iter().position(|e| e.id == id)
+            .ok_or_else(|| format!("Suppression {} not found", id))?;
+        let entry = store.entries.remove(index);
+        let audit = audit_entry("removed", &entry, actor, reason);
+        push_audit(&mut store, audit);
+    }
+    save();
+    Ok(())
+}
+
+/// Exception đang hiệu lực (lọc theo rule nếu có)
+pub fn list(rule_id: Option<&str>) -> Vec<RuleSuppression> {
+    expire();
+    STORE.read().entries.iter()
+        .filter(|e| rule_id.map_or(true, |r| e.rule_id == r))
+        .cloned()
+        .collect()
+}
+
+/// Audit trail (mới nhất trước)
+pub fn get_audit(limit: usize) -> Vec<SuppressionAuditEntry> {
+    STORE.read().audit.iter().rev().take(limit).cloned().collect()
+}
+
+/// Match của rule trên sample có bị suppress không. Trả về id của exception khớp.
+pub fn check(rule_id: &str, ctx: &SampleContext) -> Option<String> {
+    let id = find(rule_id, ctx)?;
+    if let Some(entry) = STORE.write().entries.iter_mut().find(|e| e.id == id) {
+        entry.hit_count += 1;
+        entry.last_hit = Some(Utc::now().timestamp());
+    }
+    save();
+    Some(id)
+}
+
+/// Như `check` nhưng không tính hit (dùng cho test rule)
+pub fn find(rule_id: &str, ctx: &SampleContext) -> Option<String> {
+    if STORE.read().entries.is_empty() {
+        return None;
+    }
+    expire();
+
+    let candidates: Vec<RuleSuppression> = STORE.read().entries.iter()
+        .filter(|e| e.rule_id == rule_id || e.rule_id == ANY_RULE)
+        .cloned()
+        .collect();
+    // Hash chỉ tính khi có entry sha256
+    let hash = if candidates.iter().any(|e| e.kind == SuppressionKind::Sha256) {
+        ctx.process_hash.clone()
+            .or_else(|| ctx.process_path.as_deref().and_then(whitelist::file_hash))
+            .map(|h| h.to_lowercase())
+    } else {
+        None
+    };
+
+    candidates.iter()
+        .find(|e| matches(e, ctx, hash.as_deref(), &HOSTNAME))
+        .map(|e| e.id.clone())
+}
+
+// ============================================================================
+// MATCHING
+// ============================================================================
+
+fn matches(entry: &RuleSuppression, ctx: &SampleContext, hash: Option<&str>, host: &str) -> bool {
+    match entry.kind {
+        SuppressionKind::ProcessPath => {
+            let Some(path) = ctx.process_path.as_deref() else { return false };
+            let path = normalize(SuppressionKind::ProcessPath, &path.to_string_lossy());
+            match entry.value.strip_suffix('*') {
+                Some(prefix) => path.starts_with(prefix),
+                None => path == entry.value,
+            }
+        }
+        SuppressionKind::Sha256 => hash == Some(entry.value.as_str()),
+        SuppressionKind::User => {
+            let Some(user) = ctx.user.as_deref() else { return false };
+            let user = user.to_lowercase();
+            // "user" khớp cả "DOMAIN\user"
+            user == entry.value
+                || (!entry.value.contains('\\') && user.rsplit('\\').next() == Some(entry.value.as_str()))
+        }
+        SuppressionKind::Host => host == entry.value,
+    }
+}
+
+fn normalize(kind: SuppressionKind, value: &str) -> String {
+    let value = value.trim().to_lowercase();
+    match kind {
+        SuppressionKind::ProcessPath => value.replace('/', "\\"),
+        _ => value,
+    }
+}
+
+fn is_sha256(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Gỡ entry hết hạn (ghi audit)
+fn expire() {
+    let now = Utc::now().timestamp();
+    if !STORE.read().entries.iter().any(|e| e.expires_at.map_or(false, |at| at <= now)) {
+        return;
+    }
+    {
+        let mut store = STORE.write();
+        let (expired, active): (Vec<_>, Vec<_>) = std::mem::take(&mut store.entries)
+            .into_iter()
+            .partition(|e| e.expires_at.map_or(false, |at| at <= now));
+        store.entries = active;
+        for entry in expired {
+            log::info!("Rule suppression expired: {} {:?}={}", entry.rule_id, entry.kind, entry.value);
+            let audit = audit_entry("expired", &entry, "system", None);
+            push_audit(&mut store, audit);
+        }
+    }
+    save();
+}
+
+fn audit_entry(action: &str, entry: &RuleSuppression, actor: &str, reason: Option<String>) -> SuppressionAuditEntry {
+    SuppressionAuditEntry {
+        timestamp: Utc::now().timestamp(),
+        action: action.to_string(),
+        suppression_id: entry.id.clone(),
+        rule_id: entry.rule_id.clone(),
+        kind: entry.kind,
+        value: entry.value.clone(),
+        actor: actor.to_string(),
+        reason,
+    }
+}
+
+fn push_audit(store: &mut SuppressionStore, entry: SuppressionAuditEntry) {
+    store.audit.push(entry);
+    let overflow = store.audit.len().saturating_sub(MAX_AUDIT_ENTRIES);
+    store.audit.drain(..overflow);
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn store_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(SUPPRESSIONS_FILE)
+}
+
+fn load() -> SuppressionStore {
+    fs::read_to_string(store_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save() {
+    let path = store_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*STORE.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: SuppressionKind, value: &str) -> RuleSuppression {
+        RuleSuppression {
+            id: "s1".to_string(),
+            rule_id: "CERTUTIL_DECODE".to_string(),
+            kind,
+            value: normalize(kind, value),
+            reason: "IT deployment script".to_string(),
+            created_by: "analyst".to_string(),
+            created_at: 0,
+            expires_at: None,
+            hit_count: 0,
+            last_hit: None,
+        }
+    }
+
+    #[test]
+    fn test_suppression_matching() {
+        let ctx = SampleContext {
+            process_path: Some(PathBuf::from(r"C:\IT\Scripts\deploy.exe")),
+            user: Some(r"CORP\svc_deploy".to_string()),
+            ..Default::default()
+        };
+
+        assert!(matches(&entry(SuppressionKind::ProcessPath, r"c:\it\scripts\deploy.exe"), &ctx, None, "ws01"));
+        assert!(matches(&entry(SuppressionKind::ProcessPath, "C:/IT/Scripts/*"), &ctx, None, "ws01"));
+        assert!(!matches(&entry(SuppressionKind::ProcessPath, r"C:\Temp\*"), &ctx, None, "ws01"));
+
+        assert!(matches(&entry(SuppressionKind::User, "svc_deploy"), &ctx, None, "ws01"));
+        assert!(matches(&entry(SuppressionKind::User, r"corp\svc_deploy"), &ctx, None, "ws01"));
+        assert!(!matches(&entry(SuppressionKind::User, r"other\svc_deploy"), &ctx, None, "ws01"));
+
+        assert!(matches(&entry(SuppressionKind::Host, "WS01"), &ctx, None, "ws01"));
+        let hash = "a".repeat(64);
+        assert!(matches(&entry(SuppressionKind::Sha256, &hash.to_uppercase()), &ctx, Some(&hash), "ws01"));
+        assert!(!is_sha256("abc"));
+    }
+}
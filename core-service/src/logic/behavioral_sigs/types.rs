@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::net::IpAddr;
 
+use crate::logic::network::GeoInfo;
+
 // ============================================================================
 // BEACONING TYPES
 // ============================================================================
@@ -22,6 +24,9 @@ pub struct BeaconAlert {
     pub first_seen: i64,
     pub last_seen: i64,
     pub severity: BeaconSeverity,
+    /// Country / ASN của đích (GeoIP)
+    #[serde(default)]
+    pub geo: Option<GeoInfo>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -172,20 +177,79 @@ pub enum RuleCondition {
     MemoryUsageAbove { threshold: f32 },
     NetworkRateAbove { threshold: f32 },
 
+    // Time-window conditions (event history của process)
+    /// Các bước xảy ra theo thứ tự trong `within_secs`. `include_descendants`:
+    /// tính cả event của process con / cháu (vd. connection từ powershell do cmd tạo)
+    Sequence {
+        steps: Vec<SequenceStep>,
+        within_secs: u64,
+        #[serde(default)]
+        include_descendants: bool,
+    },
+
     // Composite
     And(Vec<RuleCondition>),
     Or(Vec<RuleCondition>),
     Not(Box<RuleCondition>),
 }
 
+/// 1 bước của sequence condition - khớp với 1 `ProcessEvent`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SequenceStep {
+    /// Process được tạo (tên + tùy chọn tên parent)
+    ProcessStart {
+        pattern: String,
+        #[serde(default)]
+        parent_pattern: Option<String>,
+        #[serde(default)]
+        is_regex: bool,
+    },
+    /// Process tạo child có tên khớp
+    ChildSpawned {
+        pattern: String,
+        #[serde(default)]
+        is_regex: bool,
+    },
+    /// Outbound connection (port / IP hoặc domain tùy chọn)
+    OutboundConnection {
+        #[serde(default)]
+        port: Option<u16>,
+        #[serde(default)]
+        dest_pattern: Option<String>,
+    },
+    FileWrite { path_pattern: String },
+    RegistryWrite { key_pattern: String },
+}
+
 /// Action khi rule match
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RuleAction {
     Alert,              // Chỉ alert
     NeverLearn,         // Không học vào baseline
-    Block,              // Block process (future)
-    Quarantine,         // Quarantine file (future)
+    Block,              // Kill process (chờ approve)
+    Quarantine,         // Quarantine file (chờ approve)
     Custom(String),     // Custom action
+    /// Yêu cầu response qua Action Guard như ML verdict
+    /// (decision: RequireApproval | AutoBlock, AutoBlock chỉ cho rule Critical)
+    Respond {
+        decision: crate::logic::policy::Decision,
+        action: crate::logic::action_guard::ActionType,
+    },
+}
+
+impl RuleAction {
+    /// Policy decision + action cụ thể mà rule yêu cầu (None = chỉ alert)
+    pub fn response_request(&self) -> Option<(crate::logic::policy::Decision, crate::logic::action_guard::ActionType)> {
+        use crate::logic::action_guard::ActionType;
+        use crate::logic::policy::Decision;
+
+        match self {
+            RuleAction::Block => Some((Decision::RequireApproval, ActionType::KillProcess)),
+            RuleAction::Quarantine => Some((Decision::RequireApproval, ActionType::QuarantineFile)),
+            RuleAction::Respond { decision, action } => Some((*decision, *action)),
+            _ => None,
+        }
+    }
 }
 
 /// Kết quả khi rule match
@@ -280,12 +344,35 @@ impl NeverLearnReason {
     }
 }
 
+// ============================================================================
+// PROCESS EVENT HISTORY
+// ============================================================================
+
+/// Event trong history ngắn hạn của process (cho sequence conditions)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ProcessEventKind {
+    ProcessStart { name: String, parent: Option<String>, cmdline: Option<String> },
+    ChildSpawned { child_pid: u32, name: String },
+    NetworkConnect { remote_ip: IpAddr, remote_port: u16, domain: Option<String> },
+    FileWrite { path: String },
+    RegistryWrite { key: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProcessEvent {
+    pub pid: u32,
+    pub kind: ProcessEventKind,
+    /// Unix ms
+    pub timestamp: i64,
+}
+
 // ============================================================================
 // SAMPLE CONTEXT
 // ============================================================================
 
 /// Context đầy đủ của một sample để evaluate rules
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SampleContext {
     // Process info
     pub process_name: Option<String>,
@@ -294,6 +381,8 @@ pub struct SampleContext {
     pub process_cmdline: Option<String>,
     pub process_hash: Option<String>,
     pub process_signed: Option<bool>,
+    /// User sở hữu process (`DOMAIN\user`)
+    pub user: Option<String>,
 
     // Parent info
     pub parent_name: Option<String>,
@@ -316,6 +405,9 @@ pub struct SampleContext {
     pub cpu_usage: f32,
     pub memory_usage: f32,
     pub network_rate: f32,
+
+    // History (process + con cháu, cũ → mới) cho sequence conditions
+    pub history: Vec<ProcessEvent>,
 }
 
 impl SampleContext {
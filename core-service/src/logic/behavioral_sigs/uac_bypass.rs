@@ -0,0 +1,313 @@
+//! UAC Bypass Detection (T1548.002)
+//!
+//! Mục đích: Gộp chuỗi "registry hijack → auto-elevate binary → child elevated"
+//! thành 1 incident High
+//!
+//! Các chain phổ biến:
+//! - fodhelper / computerdefaults: `HKCU\Software\Classes\ms-settings\Shell\Open\command`
+//! - eventvwr / compmgmtlauncher:  `HKCU\Software\Classes\mscfile\shell\open\command`
+//! - sdclt: `Folder\shell\open\command`, `exefile\shell\runas\command`, `App Paths\control.exe`
+//!
+//! Key được poll thường xuyên (attacker xóa key ngay sau khi trigger). Child của
+//! auto-elevate binary xuất hiện trong cửa sổ correlation → alert. Child là shell
+//! nhưng không thấy registry write → vẫn alert với confidence thấp hơn.
+
+use std::collections::{HashMap, HashSet};
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::incident::{self, Severity};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// (HKCU subkey, value name - None = (Default), auto-elevate triggers, technique)
+const HIJACK_KEYS: &[(&str, Option<&str>, &[&str], &str)] = &[
+    (r"Software\Classes\ms-settings\Shell\Open\command", None, &["fodhelper.exe", "computerdefaults.exe"], "fodhelper"),
+    (r"Software\Classes\mscfile\shell\open\command", None, &["eventvwr.exe", "compmgmtlauncher.exe"], "eventvwr"),
+    (r"Software\Classes\Folder\shell\open\command", None, &["sdclt.exe"], "sdclt"),
+    (r"Software\Classes\exefile\shell\runas\command", Some("IsolatedCommand"), &["sdclt.exe"], "sdclt"),
+    (r"Software\Microsoft\Windows\CurrentVersion\App Paths\control.exe", None, &["sdclt.exe"], "sdclt"),
+];
+
+/// Child của auto-elevate binary mà không có hijack → vẫn đáng ngờ
+const SUSPICIOUS_CHILDREN: &[&str] = &[
+    "cmd.exe", "powershell.exe", "pwsh.exe", "wscript.exe", "cscript.exe",
+    "mshta.exe", "rundll32.exe", "regsvr32.exe",
+];
+
+const CORRELATION_WINDOW_SECS: i64 = 60;
+const MITRE_ID: &str = "T1548.002";
+const MAX_ALERTS: usize = 500;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Hijack key đang có giá trị (hoặc vừa bị xóa, còn trong cửa sổ correlation)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArmedHijack {
+    pub key: String,
+    pub command: String,
+    pub technique: String,
+    pub triggers: Vec<String>,
+    pub seen_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UacBypassAlert {
+    pub technique: String,
+    pub trigger_process: String,
+    pub trigger_pid: u32,
+    pub child_name: String,
+    pub child_pid: u32,
+    pub child_cmdline: String,
+    pub hijack_key: Option<String>,
+    pub hijack_command: Option<String>,
+    pub confidence: u8,
+    pub mitre_id: String,
+    pub timestamp: i64,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static ARMED: Lazy<Mutex<HashMap<String, ArmedHijack>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static REPORTED_PIDS: Lazy<Mutex<HashSet<u32>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+static ALERTS: Lazy<Mutex<Vec<UacBypassAlert>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Poll hijack keys + process tree. Trả về alert mới.
+pub fn scan() -> Vec<UacBypassAlert> {
+    update_armed();
+    let armed: Vec<ArmedHijack> = ARMED.lock().values().cloned().collect();
+
+    let mut system = sysinfo::System::new();
+    system.refresh_processes();
+
+    let mut alive = HashSet::new();
+    let mut alerts = Vec::new();
+    for (pid, process) in system.processes() {
+        let pid = pid.as_u32();
+        alive.insert(pid);
+
+        let parent = match process.parent().and_then(|ppid| system.process(ppid)) {
+            Some(p) => p,
+            None => continue,
+        };
+        let parent_name = parent.name().to_lowercase();
+        if !is_auto_elevate(&parent_name) || REPORTED_PIDS.lock().contains(&pid) {
+            continue;
+        }
+
+        let child_name = process.name().to_string();
+        let (hijack, confidence) = match correlate(&armed, &parent_name, &child_name, process.start_time() as i64) {
+            Some(c) => c,
+            None => continue,
+        };
+        REPORTED_PIDS.lock().insert(pid);
+
+        let alert = UacBypassAlert {
+            technique: hijack.map(|h| h.technique.clone()).unwrap_or_else(|| parent_name.trim_end_matches(".exe").to_string()),
+            trigger_process: parent.name().to_string(),
+            trigger_pid: parent.pid().as_u32(),
+            child_name,
+            child_pid: pid,
+            child_cmdline: process.cmd().join(" "),
+            hijack_key: hijack.map(|h| h.key.clone()),
+            hijack_command: hijack.map(|h| h.command.clone()),
+            confidence,
+            mitre_id: MITRE_ID.to_string(),
+            timestamp: Utc::now().timestamp(),
+        };
+        raise_incident(&alert);
+        alerts.push(alert);
+    }
+
+    REPORTED_PIDS.lock().retain(|pid| alive.contains(pid));
+    if !alerts.is_empty() {
+        let mut history = ALERTS.lock();
+        history.extend(alerts.iter().cloned());
+        let overflow = history.len().saturating_sub(MAX_ALERTS);
+        history.drain(..overflow);
+    }
+    alerts
+}
+
+pub fn get_recent_alerts(limit: usize) -> Vec<UacBypassAlert> {
+    ALERTS.lock().iter().rev().take(limit).cloned().collect()
+}
+
+/// Hijack key đang armed (cho UI / debug)
+pub fn get_armed() -> Vec<ArmedHijack> {
+    ARMED.lock().values().cloned().collect()
+}
+
+// ============================================================================
+// CORRELATION
+// ============================================================================
+
+fn is_auto_elevate(name: &str) -> bool {
+    HIJACK_KEYS.iter().any(|(_, _, triggers, _)| triggers.contains(&name))
+}
+
+/// Child của auto-elevate binary `parent` có phải UAC bypass không.
+/// Trả về (hijack khớp, confidence).
+fn correlate<'a>(
+    armed: &'a [ArmedHijack],
+    parent: &str,
+    child: &str,
+    child_start: i64,
+) -> Option<(Option<&'a ArmedHijack>, u8)> {
+    let hijack = armed.iter().find(|h| {
+        h.triggers.iter().any(|t| t == parent)
+            && (child_start - h.seen_at).abs() <= CORRELATION_WINDOW_SECS
+    });
+
+    match hijack {
+        Some(h) => Some((Some(h), 95)),
+        None if SUSPICIOUS_CHILDREN.contains(&child.to_lowercase().as_str()) => Some((None, 70)),
+        None => None,
+    }
+}
+
+/// Đọc các hijack key, ghi nhận key mới xuất hiện; bỏ entry đã hết cửa sổ
+fn update_armed() {
+    let now = Utc::now().timestamp();
+    let mut armed = ARMED.lock();
+    let mut present = HashSet::new();
+
+    for (key, value, triggers, technique) in HIJACK_KEYS {
+        let command = match platform::read_hkcu(key, *value) {
+            Some(c) if !c.trim().is_empty() => c,
+            _ => continue,
+        };
+        present.insert(key.to_string());
+
+        let changed = armed.get(*key).map(|h| h.command != command).unwrap_or(true);
+        if changed {
+            log::warn!("UAC bypass key armed: HKCU\\{} = {}", key, command);
+            armed.insert(key.to_string(), ArmedHijack {
+                key: format!(r"HKCU\{}", key),
+                command,
+                technique: technique.to_string(),
+                triggers: triggers.iter().map(|t| t.to_string()).collect(),
+                seen_at: now,
+            });
+        }
+    }
+
+    armed.retain(|key, h| present.contains(key) || now - h.seen_at <= CORRELATION_WINDOW_SECS);
+}
+
+fn raise_incident(alert: &UacBypassAlert) {
+    let description = match (&alert.hijack_key, &alert.hijack_command) {
+        (Some(key), Some(command)) => format!(
+            "{} was set to \"{}\", then {} (PID {}) spawned elevated {} (PID {}): {}",
+            key, command, alert.trigger_process, alert.trigger_pid,
+            alert.child_name, alert.child_pid, alert.child_cmdline,
+        ),
+        _ => format!(
+            "Auto-elevate binary {} (PID {}) spawned {} (PID {}) (registry write not observed): {}",
+            alert.trigger_process, alert.trigger_pid, alert.child_name, alert.child_pid, alert.child_cmdline,
+        ),
+    };
+
+    incident::raise_detection(
+        &format!("Privilege Escalation: UAC bypass via {}", alert.technique),
+        Severity::High,
+        &["UAC_BYPASS".to_string(), "PRIVILEGE_ESCALATION".to_string()],
+        &[MITRE_ID],
+        &description,
+    );
+}
+
+// ============================================================================
+// PLATFORM
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_SZ, RRF_RT_REG_EXPAND_SZ, RRF_NOEXPAND};
+
+    /// Đọc REG_SZ / REG_EXPAND_SZ từ HKCU (value None = (Default))
+    pub fn read_hkcu(key: &str, value: Option<&str>) -> Option<String> {
+        let key = HSTRING::from(key);
+        let value = value.map(HSTRING::from);
+        let value_ptr = value.as_ref().map(|v| PCWSTR(v.as_ptr())).unwrap_or(PCWSTR::null());
+        let flags = RRF_RT_REG_SZ | RRF_RT_REG_EXPAND_SZ | RRF_NOEXPAND;
+
+        let mut size = 0u32;
+        let status = unsafe {
+            RegGetValueW(HKEY_CURRENT_USER, &key, value_ptr, flags, None, None, Some(&mut size))
+        };
+        if status.is_err() || size == 0 {
+            return None;
+        }
+
+        let mut buf = vec![0u16; (size as usize + 1) / 2];
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER, &key, value_ptr, flags,
+                None, Some(buf.as_mut_ptr() as *mut _), Some(&mut size),
+            )
+        };
+        if status.is_err() {
+            return None;
+        }
+        Some(String::from_utf16_lossy(&buf).trim_end_matches('\0').to_string())
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    pub fn read_hkcu(_key: &str, _value: Option<&str>) -> Option<String> {
+        None
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn armed(seen_at: i64) -> ArmedHijack {
+        ArmedHijack {
+            key: r"HKCU\Software\Classes\ms-settings\Shell\Open\command".to_string(),
+            command: r"C:\Users\bob\payload.exe".to_string(),
+            technique: "fodhelper".to_string(),
+            triggers: vec!["fodhelper.exe".to_string(), "computerdefaults.exe".to_string()],
+            seen_at,
+        }
+    }
+
+    #[test]
+    fn test_correlate_with_hijack() {
+        let list = vec![armed(1000)];
+        let (hijack, confidence) = correlate(&list, "fodhelper.exe", "payload.exe", 1010).unwrap();
+        assert!(hijack.is_some());
+        assert_eq!(confidence, 95);
+
+        // Ngoài cửa sổ, child không phải shell → bỏ qua
+        assert!(correlate(&list, "fodhelper.exe", "payload.exe", 2000).is_none());
+    }
+
+    #[test]
+    fn test_correlate_without_hijack() {
+        let (hijack, confidence) = correlate(&[], "eventvwr.exe", "powershell.exe", 0).unwrap();
+        assert!(hijack.is_none());
+        assert_eq!(confidence, 70);
+        assert!(correlate(&[], "eventvwr.exe", "mmc.exe", 0).is_none());
+    }
+}
@@ -0,0 +1,251 @@
+//! WMI Event Subscription Persistence Detection (T1546.003)
+//!
+//! Mục đích: Phát hiện permanent WMI subscription mới trong `root\subscription`
+//!
+//! Subscription = __EventFilter (điều kiện) + __EventConsumer (hành động) +
+//! __FilterToConsumerBinding. Consumer nguy hiểm nhất:
+//! - CommandLineEventConsumer: chạy command line
+//! - ActiveScriptEventConsumer: chạy VBScript / JScript
+//!
+//! Poll bằng PowerShell `Get-CimInstance`; binding mới → persistence alert
+//! (kèm command line / script của consumer) + incident. Query chạy ở thread nền
+//! có timeout; `scan` dùng snapshot đã cache từ lần query trước và không bao giờ chặn.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::types::{PersistenceAlert, PersistenceMechanism, PersistenceSeverity};
+use crate::logic::incident::{self, Severity};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Consumer mặc định của Windows
+const BUILTIN_CONSUMERS: &[&str] = &["SCM Event Log Consumer", "BVTConsumer"];
+/// Get-CimInstance treo quá lâu (WMI repository bận / hỏng) → kill, bỏ lượt này
+const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+const QUERY_SCRIPT: &str = r#"
+$f = @(Get-CimInstance -Namespace root\subscription -ClassName __EventFilter -ErrorAction SilentlyContinue | Select-Object Name,Query)
+$c = @(Get-CimInstance -Namespace root\subscription -ClassName __EventConsumer -ErrorAction SilentlyContinue | Select-Object @{n='Class';e={$_.CimClass.CimClassName}},Name,CommandLineTemplate,ExecutablePath,ScriptText,ScriptFileName)
+$b = @(Get-CimInstance -Namespace root\subscription -ClassName __FilterToConsumerBinding -ErrorAction SilentlyContinue | Select-Object @{n='Filter';e={$_.Filter.Name}},@{n='Consumer';e={$_.Consumer.Name}})
+@{Filters=$f;Consumers=$c;Bindings=$b} | ConvertTo-Json -Depth 4 -Compress
+"#;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct WmiFilter {
+    pub name: String,
+    pub query: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct WmiConsumer {
+    pub class: String,
+    pub name: String,
+    pub command_line_template: Option<String>,
+    pub executable_path: Option<String>,
+    pub script_text: Option<String>,
+    pub script_file_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct WmiBinding {
+    pub filter: String,
+    pub consumer: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct WmiSnapshot {
+    pub filters: Vec<WmiFilter>,
+    pub consumers: Vec<WmiConsumer>,
+    pub bindings: Vec<WmiBinding>,
+}
+
+/// Subscription hoàn chỉnh (filter + consumer đã bind)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WmiSubscription {
+    pub filter: String,
+    pub query: Option<String>,
+    pub consumer: String,
+    pub consumer_class: String,
+    /// Command line / script mà consumer sẽ chạy
+    pub action: Option<String>,
+}
+
+impl WmiConsumer {
+    /// Nội dung consumer sẽ thực thi
+    pub fn action(&self) -> Option<String> {
+        self.command_line_template.clone()
+            .or_else(|| self.executable_path.clone())
+            .or_else(|| self.script_text.clone())
+            .or_else(|| self.script_file_name.clone())
+            .filter(|s| !s.trim().is_empty())
+    }
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// Binding (filter, consumer) đã báo
+static KNOWN_BINDINGS: Lazy<Mutex<HashSet<(String, String)>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+static SUBSCRIPTIONS: Lazy<Mutex<Vec<WmiSubscription>>> = Lazy::new(|| Mutex::new(Vec::new()));
+/// Snapshot từ query nền gần nhất, chưa được scan
+static SNAPSHOT_CACHE: Lazy<Mutex<Option<WmiSnapshot>>> = Lazy::new(|| Mutex::new(None));
+static QUERY_RUNNING: AtomicBool = AtomicBool::new(false);
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Poll root\subscription. Trả về alert cho subscription mới.
+pub fn scan() -> Vec<PersistenceAlert> {
+    let cached = SNAPSHOT_CACHE.lock().take();
+    refresh_snapshot();
+    let snapshot = match cached {
+        Some(s) => s,
+        None => return Vec::new(),
+    };
+    let subscriptions = resolve(&snapshot);
+
+    let mut alerts = Vec::new();
+    for sub in &subscriptions {
+        if BUILTIN_CONSUMERS.contains(&sub.consumer.as_str())
+            || !KNOWN_BINDINGS.lock().insert((sub.filter.clone(), sub.consumer.clone()))
+        {
+            continue;
+        }
+
+        let alert = PersistenceAlert {
+            mechanism: PersistenceMechanism::WmiSubscription,
+            location: format!(r"root\subscription:{} -> {}", sub.filter, sub.consumer),
+            value_name: Some(sub.consumer_class.clone()),
+            value_data: sub.action.clone(),
+            process_name: "unknown".to_string(),
+            process_pid: 0,
+            timestamp: Utc::now().timestamp(),
+            severity: severity(sub),
+            mitre_technique: PersistenceMechanism::WmiSubscription.mitre_technique().to_string(),
+        };
+
+        super::persistence::record_alert(alert.clone());
+        incident::raise_detection(
+            &format!("Persistence: WMI subscription {}", sub.consumer),
+            if alert.severity >= PersistenceSeverity::High { Severity::High } else { Severity::Medium },
+            &["WMI_PERSISTENCE".to_string(), "PERSISTENCE".to_string()],
+            &[alert.mitre_technique.as_str()],
+            &format!(
+                "Filter '{}' ({}) bound to {} '{}': {}",
+                sub.filter,
+                sub.query.as_deref().unwrap_or("-"),
+                sub.consumer_class,
+                sub.consumer,
+                sub.action.as_deref().unwrap_or("-"),
+            ),
+        );
+        alerts.push(alert);
+    }
+
+    *SUBSCRIPTIONS.lock() = subscriptions;
+    alerts
+}
+
+/// Query root\subscription ở thread nền (tối đa 1 query cùng lúc), kết quả vào `SNAPSHOT_CACHE`
+fn refresh_snapshot() {
+    if QUERY_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| {
+        if let Some(snapshot) = query() {
+            *SNAPSHOT_CACHE.lock() = Some(snapshot);
+        }
+        QUERY_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Subscription đang tồn tại (lần scan gần nhất)
+pub fn get_subscriptions() -> Vec<WmiSubscription> {
+    SUBSCRIPTIONS.lock().clone()
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// Ghép binding với filter / consumer tương ứng
+pub fn resolve(snapshot: &WmiSnapshot) -> Vec<WmiSubscription> {
+    snapshot.bindings.iter().map(|b| {
+        let filter = snapshot.filters.iter().find(|f| f.name == b.filter);
+        let consumer = snapshot.consumers.iter().find(|c| c.name == b.consumer);
+        WmiSubscription {
+            filter: b.filter.clone(),
+            query: filter.and_then(|f| f.query.clone()),
+            consumer: b.consumer.clone(),
+            consumer_class: consumer.map(|c| c.class.clone()).unwrap_or_default(),
+            action: consumer.and_then(|c| c.action()),
+        }
+    }).collect()
+}
+
+fn severity(sub: &WmiSubscription) -> PersistenceSeverity {
+    match sub.consumer_class.as_str() {
+        "CommandLineEventConsumer" | "ActiveScriptEventConsumer" => {
+            let action = sub.action.as_deref().unwrap_or("").to_lowercase();
+            if action.contains("powershell") || action.contains("-enc") || action.contains("http") {
+                PersistenceSeverity::Critical
+            } else {
+                PersistenceSeverity::High
+            }
+        }
+        _ => PersistenceSeverity::Medium,
+    }
+}
+
+/// Lỗi / timeout → None
+#[cfg(windows)]
+fn query() -> Option<WmiSnapshot> {
+    let stdout = super::run_powershell(QUERY_SCRIPT, QUERY_TIMEOUT)?;
+    serde_json::from_str(stdout.trim()).ok()
+}
+
+#[cfg(not(windows))]
+fn query() -> Option<WmiSnapshot> {
+    None
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_subscription() {
+        let json = r#"{"Filters":[{"Name":"Updater","Query":"SELECT * FROM __InstanceModificationEvent WITHIN 60"}],
+            "Consumers":[{"Class":"CommandLineEventConsumer","Name":"UpdaterConsumer","CommandLineTemplate":"powershell -enc AAAA","ExecutablePath":null}],
+            "Bindings":[{"Filter":"Updater","Consumer":"UpdaterConsumer"}]}"#;
+        let snapshot: WmiSnapshot = serde_json::from_str(json).unwrap();
+        let subs = resolve(&snapshot);
+
+        assert_eq!(subs.len(), 1);
+        assert_eq!(subs[0].consumer_class, "CommandLineEventConsumer");
+        assert_eq!(subs[0].action.as_deref(), Some("powershell -enc AAAA"));
+        assert_eq!(severity(&subs[0]), PersistenceSeverity::Critical);
+    }
+}
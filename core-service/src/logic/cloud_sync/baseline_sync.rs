@@ -0,0 +1,542 @@
+//! Baseline Sync - đồng bộ baseline với cloud (version vector + xử lý xung đột)
+//!
+//! Version vector `{ agent, server }`:
+//! - `agent`: thế hệ baseline local, tăng mỗi khi baseline local khác lần ghi nhận trước
+//! - `server`: revision golden baseline mà baseline local dựa trên
+//!
+//! Mỗi heartbeat thành công:
+//! 1. Golden revision khác `server` → tải golden về, hoà giải với local:
+//!    - Local chưa học thêm từ lần hoà giải trước → áp golden (fast-forward)
+//!    - Local đã học thêm → xung đột, xử lý theo `conflict_policy` của golden:
+//!      `server_wins` (golden thay local), `local_wins` (giữ local),
+//!      `merge` (gộp mean / variance theo số sample)
+//!    - Golden khác layout feature / dữ liệu hỏng → từ chối revision đó, giữ local
+//! 2. Vector khác lần upload trước → upload baseline local (giới hạn
+//!    `UPLOAD_INTERVAL_SECS`, trừ khi vừa hoà giải golden mới; bỏ qua baseline rỗng)
+//!
+//! Áp baseline từ cloud luôn snapshot baseline cũ trước (rollback được).
+//! State lưu `OneShield/baseline_sync.json` (sống qua restart).
+
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::logic::baseline::{self, VersionedBaseline};
+use crate::logic::features::layout::{self, FEATURE_COUNT};
+use super::client::{SyncBaselineRequest, SyncBaselineResponse};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const STATE_FILE: &str = "baseline_sync.json";
+/// Upload baseline local tối đa 1 lần / 15 phút
+const UPLOAD_INTERVAL_SECS: i64 = 15 * 60;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionVector {
+    pub agent: i64,
+    pub server: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Golden thay baseline local (học local bị bỏ, còn snapshot)
+    #[default]
+    ServerWins,
+    /// Giữ baseline local, chỉ đánh dấu đã thấy revision
+    LocalWins,
+    /// Gộp golden + local theo số sample
+    Merge,
+}
+
+/// Golden baseline nhận từ `GET /api/v1/agent/sync/baseline`
+#[derive(Debug, Clone, Deserialize)]
+pub struct GoldenBaseline {
+    pub revision: i64,
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+    pub mean_values: Vec<f32>,
+    pub variance_values: Option<Vec<f32>>,
+    pub sample_count: i64,
+    pub feature_version: Option<i32>,
+    pub layout_hash: Option<i64>,
+}
+
+impl GoldenBaseline {
+    /// Chuyển sang baseline engine (kiểm tra layout + dữ liệu)
+    fn to_baseline(&self, local: &VersionedBaseline) -> Result<VersionedBaseline, String> {
+        let (Some(version), Some(hash)) = (self.feature_version, self.layout_hash) else {
+            return Err("Golden baseline has no feature layout".to_string());
+        };
+        let version = u8::try_from(version).map_err(|_| format!("Invalid feature version {}", version))?;
+        let hash = u32::try_from(hash).map_err(|_| format!("Invalid layout hash {}", hash))?;
+        if !layout::is_layout_compatible(version, hash) {
+            return Err(format!("Golden baseline layout v{} ({:x}) does not match this agent", version, hash));
+        }
+
+        let variance = self.variance_values.as_ref()
+            .ok_or_else(|| "Golden baseline has no variance".to_string())?;
+        let mean: [f32; FEATURE_COUNT] = self.mean_values.as_slice().try_into()
+            .map_err(|_| format!("Expected {} mean values, got {}", FEATURE_COUNT, self.mean_values.len()))?;
+        let variance: [f32; FEATURE_COUNT] = variance.as_slice().try_into()
+            .map_err(|_| format!("Expected {} variance values, got {}", FEATURE_COUNT, variance.len()))?;
+        if mean.iter().chain(variance.iter()).any(|v| !v.is_finite()) || variance.iter().any(|v| *v < 0.0) {
+            return Err("Golden baseline contains invalid values".to_string());
+        }
+
+        // Giữ metadata local (id, tên, giờ hoạt động), chỉ thay thống kê
+        let mut baseline = local.clone();
+        baseline.feature_version = version;
+        baseline.layout_hash = hash;
+        baseline.mean = mean;
+        baseline.variance = variance;
+        baseline.samples = self.sample_count.max(0) as u64;
+        baseline.last_updated = Utc::now().timestamp();
+        Ok(baseline)
+    }
+}
+
+/// Xung đột gần nhất (golden mới trong khi local đã học thêm)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineConflict {
+    pub detected_at: DateTime<Utc>,
+    pub golden_revision: i64,
+    pub local_generation: i64,
+    pub policy: ConflictPolicy,
+    /// Mô tả kết quả (UI hiển thị)
+    pub resolution: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BaselineSyncState {
+    /// Chưa upload lần nào
+    #[default]
+    NeverSynced,
+    /// Cloud có baseline mới nhất của agent, golden đã hoà giải
+    InSync,
+    /// Baseline local đổi, chờ upload
+    LocalChanges,
+    /// Golden mới chưa hoà giải (chưa tải được)
+    GoldenPending,
+    /// Golden revision mới nhất bị từ chối (layout / dữ liệu)
+    GoldenRejected,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BaselineSyncStatus {
+    pub state: BaselineSyncState,
+    pub version_vector: VersionVector,
+    /// Vector của lần upload thành công gần nhất
+    pub uploaded_vector: Option<VersionVector>,
+    /// Revision golden mới nhất server báo
+    pub latest_golden_revision: i64,
+    pub conflict_policy: Option<ConflictPolicy>,
+    pub last_upload: Option<DateTime<Utc>>,
+    pub last_golden_applied: Option<DateTime<Utc>>,
+    pub last_conflict: Option<BaselineConflict>,
+    pub last_error: Option<String>,
+}
+
+/// State lưu đĩa
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SyncState {
+    vector: VersionVector,
+    /// Thế hệ local ngay sau lần hoà giải golden gần nhất
+    reconciled_generation: i64,
+    uploaded_vector: Option<VersionVector>,
+    /// Hash baseline local ở lần ghi nhận gần nhất
+    local_hash: Option<String>,
+    latest_golden_revision: i64,
+    rejected_revision: i64,
+    conflict_policy: Option<ConflictPolicy>,
+    last_upload: Option<DateTime<Utc>>,
+    last_golden_applied: Option<DateTime<Utc>>,
+    last_conflict: Option<BaselineConflict>,
+    last_error: Option<String>,
+}
+
+impl SyncState {
+    /// Baseline local khác lần trước → tăng thế hệ agent
+    fn note_local(&mut self, hash: String) {
+        if self.local_hash.as_deref() != Some(hash.as_str()) {
+            self.vector.agent += 1;
+            self.local_hash = Some(hash);
+        }
+    }
+
+    fn local_changed(&self) -> bool {
+        self.vector.agent > self.reconciled_generation
+    }
+
+    fn needs_golden(&self, revision: i64) -> bool {
+        revision != self.vector.server && revision != self.rejected_revision
+    }
+
+    fn upload_due(&self, now: DateTime<Utc>) -> bool {
+        match self.uploaded_vector {
+            None => true,
+            Some(uploaded) if uploaded == self.vector => false,
+            // Vừa hoà giải golden mới → báo ngay để console thấy
+            Some(uploaded) if uploaded.server != self.vector.server => true,
+            Some(_) => self.last_upload
+                .is_none_or(|t| (now - t).num_seconds() >= UPLOAD_INTERVAL_SECS),
+        }
+    }
+
+    fn status(&self) -> BaselineSyncStatus {
+        let state = if self.latest_golden_revision == self.rejected_revision && self.rejected_revision != 0 {
+            BaselineSyncState::GoldenRejected
+        } else if self.latest_golden_revision != self.vector.server {
+            BaselineSyncState::GoldenPending
+        } else {
+            match self.uploaded_vector {
+                None => BaselineSyncState::NeverSynced,
+                Some(uploaded) if uploaded == self.vector => BaselineSyncState::InSync,
+                Some(_) => BaselineSyncState::LocalChanges,
+            }
+        };
+
+        BaselineSyncStatus {
+            state,
+            version_vector: self.vector,
+            uploaded_vector: self.uploaded_vector,
+            latest_golden_revision: self.latest_golden_revision,
+            conflict_policy: self.conflict_policy,
+            last_upload: self.last_upload,
+            last_golden_applied: self.last_golden_applied,
+            last_conflict: self.last_conflict.clone(),
+            last_error: self.last_error.clone(),
+        }
+    }
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static STATE: Lazy<RwLock<SyncState>> = Lazy::new(|| RwLock::new(load_state()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+pub fn get_status() -> BaselineSyncStatus {
+    STATE.read().status()
+}
+
+/// Revision golden đã hoà giải (gửi kèm heartbeat)
+pub fn golden_revision() -> i64 {
+    STATE.read().vector.server
+}
+
+/// Heartbeat / upload response báo revision golden hiện tại → có cần tải không
+pub fn needs_golden(revision: i64) -> bool {
+    let mut state = STATE.write();
+    if state.latest_golden_revision != revision {
+        state.latest_golden_revision = revision;
+        save_state(&state);
+    }
+    state.needs_golden(revision)
+}
+
+/// Hoà giải golden (None = org đã gỡ golden) với baseline local
+pub fn reconcile(revision: i64, golden: Option<GoldenBaseline>) -> Result<(), String> {
+    let Some(local) = baseline::get_versioned_baseline() else {
+        return Err("Baseline engine not initialized".to_string());
+    };
+
+    let mut state = STATE.write();
+    state.note_local(baseline_hash(&local));
+
+    let Some(golden) = golden else {
+        // Không còn golden: giữ local, chỉ ghi nhận revision
+        state.vector.server = revision;
+        state.conflict_policy = None;
+        state.last_error = None;
+        save_state(&state);
+        return Ok(());
+    };
+
+    // Baseline rỗng (mới cài / vừa reset) không tính là đã học
+    let local_changed = state.local_changed() && local.samples > 0;
+    let result = resolve(&local, &golden, local_changed).and_then(|resolved| {
+        if let Some(resolved) = resolved {
+            let details = format!("golden r{} ({:?})", golden.revision, golden.conflict_policy);
+            baseline::apply_cloud_baseline(resolved.clone(), &details)?;
+            state.note_local(baseline_hash(&resolved));
+            state.last_golden_applied = Some(Utc::now());
+        }
+        Ok(resolution_label(local_changed, golden.conflict_policy))
+    });
+
+    match result {
+        Ok(resolution) => {
+            if local_changed {
+                log::warn!("Baseline conflict with golden r{}: {}", golden.revision, resolution);
+                state.last_conflict = Some(BaselineConflict {
+                    detected_at: Utc::now(),
+                    golden_revision: golden.revision,
+                    local_generation: state.vector.agent,
+                    policy: golden.conflict_policy,
+                    resolution: resolution.to_string(),
+                });
+            }
+            state.vector.server = golden.revision;
+            state.reconciled_generation = state.vector.agent;
+            state.conflict_policy = Some(golden.conflict_policy);
+            state.last_error = None;
+            save_state(&state);
+            Ok(())
+        }
+        Err(e) => {
+            // Không tải lại revision này (tránh vòng lặp), revision mới sẽ thử lại
+            state.rejected_revision = golden.revision;
+            state.last_error = Some(format!("Golden r{} rejected: {}", golden.revision, e));
+            save_state(&state);
+            Err(e)
+        }
+    }
+}
+
+/// Request upload nếu baseline local có thay đổi chưa gửi (None = không cần)
+pub fn prepare_upload() -> Option<SyncBaselineRequest> {
+    // Baseline rỗng (chưa học gì) không có gì để gửi
+    let local = baseline::get_versioned_baseline().filter(|b| b.samples > 0)?;
+    let hash = baseline_hash(&local);
+
+    let mut state = STATE.write();
+    let before = state.vector;
+    state.note_local(hash.clone());
+    if state.vector != before {
+        save_state(&state);
+    }
+    if !state.upload_due(Utc::now()) {
+        return None;
+    }
+
+    Some(SyncBaselineRequest {
+        baseline_hash: hash,
+        mean_values: local.mean.to_vec(),
+        variance_values: Some(local.variance.to_vec()),
+        sample_count: local.samples,
+        version: local.feature_version as i32,
+        feature_version: Some(local.feature_version as i32),
+        layout_hash: Some(local.layout_hash),
+        version_vector: state.vector,
+    })
+}
+
+pub fn upload_succeeded(request: &SyncBaselineRequest, response: &SyncBaselineResponse) {
+    let mut state = STATE.write();
+    state.uploaded_vector = Some(request.version_vector);
+    state.last_upload = Some(Utc::now());
+    state.latest_golden_revision = response.golden_revision;
+    state.last_error = None;
+    save_state(&state);
+}
+
+pub fn upload_failed(error: String) {
+    let mut state = STATE.write();
+    state.last_error = Some(format!("Upload failed: {}", error));
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// Golden + local → baseline cần áp (golden hoặc bản merge), None = giữ local
+fn resolve(local: &VersionedBaseline, golden: &GoldenBaseline, local_changed: bool) -> Result<Option<VersionedBaseline>, String> {
+    let golden_baseline = golden.to_baseline(local)?;
+    if !local_changed {
+        return Ok(Some(golden_baseline));
+    }
+    Ok(match golden.conflict_policy {
+        ConflictPolicy::ServerWins => Some(golden_baseline),
+        ConflictPolicy::LocalWins => None,
+        ConflictPolicy::Merge => Some(merge(local, &golden_baseline)),
+    })
+}
+
+/// Gộp 2 baseline theo số sample (pooled mean / variance)
+fn merge(local: &VersionedBaseline, golden: &VersionedBaseline) -> VersionedBaseline {
+    let (n_local, n_golden) = match (local.samples, golden.samples) {
+        (0, 0) => (1.0, 1.0),
+        (l, g) => (l as f64, g as f64),
+    };
+    let total = n_local + n_golden;
+
+    let mut merged = local.clone();
+    for i in 0..FEATURE_COUNT {
+        let (m_local, m_golden) = (local.mean[i] as f64, golden.mean[i] as f64);
+        let mean = (n_local * m_local + n_golden * m_golden) / total;
+        let variance = (n_local * (local.variance[i] as f64 + (m_local - mean).powi(2))
+            + n_golden * (golden.variance[i] as f64 + (m_golden - mean).powi(2))) / total;
+        merged.mean[i] = mean as f32;
+        merged.variance[i] = variance as f32;
+    }
+    merged.samples = local.samples + golden.samples;
+    merged.last_updated = Utc::now().timestamp();
+    merged
+}
+
+fn resolution_label(local_changed: bool, policy: ConflictPolicy) -> &'static str {
+    match (local_changed, policy) {
+        (false, _) => "golden applied",
+        (true, ConflictPolicy::ServerWins) => "golden replaced local learning (snapshot kept)",
+        (true, ConflictPolicy::LocalWins) => "local learning kept",
+        (true, ConflictPolicy::Merge) => "golden merged with local learning",
+    }
+}
+
+/// SHA256 thống kê baseline (layout + mean + variance + samples)
+fn baseline_hash(baseline: &VersionedBaseline) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update([baseline.feature_version]);
+    hasher.update(baseline.layout_hash.to_le_bytes());
+    hasher.update(baseline.samples.to_le_bytes());
+    for value in baseline.mean.iter().chain(baseline.variance.iter()) {
+        hasher.update(value.to_le_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+fn state_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(STATE_FILE)
+}
+
+fn load_state() -> SyncState {
+    fs::read_to_string(state_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(state: &SyncState) {
+    let path = state_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(state) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn baseline_with(mean: f32, variance: f32, samples: u64) -> VersionedBaseline {
+        let mut baseline = VersionedBaseline::new("test");
+        baseline.mean = [mean; FEATURE_COUNT];
+        baseline.variance = [variance; FEATURE_COUNT];
+        baseline.samples = samples;
+        baseline
+    }
+
+    fn golden(policy: ConflictPolicy, mean: f32, samples: i64) -> GoldenBaseline {
+        GoldenBaseline {
+            revision: 3,
+            conflict_policy: policy,
+            mean_values: vec![mean; FEATURE_COUNT],
+            variance_values: Some(vec![1.0; FEATURE_COUNT]),
+            sample_count: samples,
+            feature_version: Some(layout::FEATURE_VERSION as i32),
+            layout_hash: Some(layout::layout_hash() as i64),
+        }
+    }
+
+    #[test]
+    fn test_conflict_policies() {
+        let local = baseline_with(2.0, 1.0, 100);
+
+        // Local chưa học thêm → luôn fast-forward về golden
+        let b = resolve(&local, &golden(ConflictPolicy::LocalWins, 4.0, 300), false).unwrap().expect("golden");
+        assert_eq!(b.mean[0], 4.0);
+        assert_eq!(b.id, local.id);
+
+        // Xung đột
+        let b = resolve(&local, &golden(ConflictPolicy::ServerWins, 4.0, 300), true).unwrap().expect("golden");
+        assert_eq!((b.mean[0], b.samples), (4.0, 300));
+        assert!(resolve(&local, &golden(ConflictPolicy::LocalWins, 4.0, 300), true).unwrap().is_none());
+
+        // Merge: mean theo trọng số sample, variance gồm cả độ lệch giữa 2 mean
+        let b = resolve(&local, &golden(ConflictPolicy::Merge, 4.0, 300), true).unwrap().expect("merge");
+        assert!((b.mean[0] - 3.5).abs() < 1e-5);
+        assert!((b.variance[0] - 1.75).abs() < 1e-5);
+        assert_eq!(b.samples, 400);
+    }
+
+    #[test]
+    fn test_golden_validation() {
+        let local = baseline_with(0.0, 0.0, 0);
+
+        let mut wrong_layout = golden(ConflictPolicy::ServerWins, 1.0, 10);
+        wrong_layout.layout_hash = Some(layout::layout_hash() as i64 ^ 1);
+        assert!(resolve(&local, &wrong_layout, false).is_err());
+
+        let mut short = golden(ConflictPolicy::ServerWins, 1.0, 10);
+        short.mean_values.pop();
+        assert!(resolve(&local, &short, false).is_err());
+
+        let mut negative = golden(ConflictPolicy::ServerWins, 1.0, 10);
+        negative.variance_values = Some(vec![-1.0; FEATURE_COUNT]);
+        assert!(resolve(&local, &negative, false).is_err());
+    }
+
+    #[test]
+    fn test_version_vector_tracking() {
+        let mut state = SyncState::default();
+        let now = Utc::now();
+
+        state.note_local("a".to_string());
+        state.note_local("a".to_string());
+        assert_eq!(state.vector, VersionVector { agent: 1, server: 0 });
+        assert!(state.local_changed());
+        assert!(state.upload_due(now));
+        assert_eq!(state.status().state, BaselineSyncState::NeverSynced);
+
+        // Upload xong → InSync, baseline đổi → chờ hết interval mới upload lại
+        state.uploaded_vector = Some(state.vector);
+        state.last_upload = Some(now);
+        assert_eq!(state.status().state, BaselineSyncState::InSync);
+        state.note_local("b".to_string());
+        assert_eq!(state.status().state, BaselineSyncState::LocalChanges);
+        assert!(!state.upload_due(now));
+        assert!(state.upload_due(now + chrono::Duration::seconds(UPLOAD_INTERVAL_SECS)));
+
+        // Golden mới: cần tải; sau hoà giải vector.server đổi → upload ngay
+        state.latest_golden_revision = 5;
+        assert!(state.needs_golden(5));
+        assert_eq!(state.status().state, BaselineSyncState::GoldenPending);
+        state.vector.server = 5;
+        state.reconciled_generation = state.vector.agent;
+        assert!(!state.local_changed());
+        assert!(state.upload_due(now));
+
+        // Revision bị từ chối không tải lại
+        state.rejected_revision = 6;
+        state.latest_golden_revision = 6;
+        assert!(!state.needs_golden(6));
+        assert_eq!(state.status().state, BaselineSyncState::GoldenRejected);
+    }
+}
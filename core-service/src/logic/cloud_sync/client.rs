@@ -6,6 +6,9 @@ use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use uuid::Uuid;
 
+use crate::logic::identity::AgentTls;
+use super::sync_encoding::EncodedBody;
+
 /// Cloud server configuration
 #[derive(Debug, Clone)]
 pub struct CloudConfig {
@@ -33,6 +36,8 @@ pub struct CloudClient {
     agent_token: Option<String>,
     org_id: Option<Uuid>,
     http_client: reqwest::Client,
+    /// Pin / mTLS hiện hành (kênh lệnh WebSocket dùng chung)
+    tls_connector: Option<native_tls::TlsConnector>,
 }
 
 // Request/Response types
@@ -64,6 +69,30 @@ pub struct HeartbeatRequest {
     pub incident_count: i32,
     pub process_count: Option<i32>,
     pub agent_version: String,
+    /// Host đang bị cách ly mạng
+    pub is_isolated: bool,
+    /// Actions chờ approve (để approve từ cloud console)
+    pub pending_actions: Vec<PendingActionSummary>,
+    /// Version rule pack đang chạy
+    pub rule_pack_version: i32,
+    /// Revision never-learn list đang áp dụng
+    pub never_learn_revision: i64,
+    /// Version offline intel bundle đã import (None = chưa có)
+    pub intel_bundle_version: Option<String>,
+    /// Revision managed settings đang áp dụng
+    pub settings_version: i64,
+    /// Revision golden baseline đã hoà giải với baseline local
+    pub baseline_revision: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PendingActionSummary {
+    pub id: String,
+    pub action_type: String,
+    pub target_pid: Option<u32>,
+    pub target_name: String,
+    pub final_score: f32,
+    pub created_at: i64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -71,9 +100,38 @@ pub struct HeartbeatResponse {
     pub server_time: i64,
     pub policy_version: i32,
     pub has_policy_update: bool,
+    /// Version rule pack mới nhất của org
+    #[serde(default)]
+    pub rule_pack_version: i32,
+    #[serde(default)]
+    pub has_rule_pack_update: bool,
+    /// Revision never-learn list mới nhất của org
+    #[serde(default)]
+    pub never_learn_revision: i64,
+    #[serde(default)]
+    pub has_never_learn_update: bool,
+    /// Revision managed settings mới nhất của org
+    #[serde(default)]
+    pub settings_version: i64,
+    #[serde(default)]
+    pub has_settings_update: bool,
+    /// Revision golden baseline mới nhất của org
+    #[serde(default)]
+    pub baseline_revision: i64,
+    #[serde(default)]
+    pub has_baseline_update: bool,
+    /// Encoding body sync server chấp nhận (zstd, incident_delta, ...)
+    #[serde(default)]
+    pub sync_capabilities: Vec<String>,
     pub commands: Vec<AgentCommand>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct CloudPolicy {
+    pub version: i32,
+    pub config: serde_json::Value,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "type")]
 pub enum AgentCommand {
@@ -81,9 +139,66 @@ pub enum AgentCommand {
     CollectDiagnostics,
     RestartService,
     UpdateAgent { url: String, checksum: String },
+    IsolateHost,
+    ReleaseHost,
+    ApproveAction { action_id: String },
+    RejectAction { action_id: String },
+    /// Analyst confirm / dismiss incident trên console → nhãn dataset
+    IncidentVerdict { incident_id: Uuid, verdict: crate::logic::incident::IncidentVerdict },
+    /// Analyst thêm ghi chú trên console
+    IncidentNote { incident_id: Uuid, note: IncidentNotePayload },
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Ghi chú incident trên đường truyền (created_at = unix seconds)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentNotePayload {
+    pub id: Uuid,
+    pub author: String,
+    pub text: String,
+    pub disposition: Option<String>,
+    pub source: String,
+    pub created_at: i64,
+}
+
+impl From<&crate::logic::incident::IncidentNote> for IncidentNotePayload {
+    fn from(note: &crate::logic::incident::IncidentNote) -> Self {
+        Self {
+            id: note.id,
+            author: note.author.clone(),
+            text: note.text.clone(),
+            disposition: note.disposition.clone(),
+            source: note.source.clone(),
+            created_at: note.created_at.timestamp(),
+        }
+    }
+}
+
+impl From<IncidentNotePayload> for crate::logic::incident::IncidentNote {
+    fn from(note: IncidentNotePayload) -> Self {
+        Self {
+            id: note.id,
+            author: note.author,
+            text: note.text,
+            disposition: note.disposition,
+            source: note.source,
+            created_at: chrono::DateTime::from_timestamp(note.created_at, 0).unwrap_or_else(chrono::Utc::now),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncIncidentNote {
+    pub incident_id: Uuid,
+    #[serde(flatten)]
+    pub note: IncidentNotePayload,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncIncidentNotesRequest {
+    pub notes: Vec<SyncIncidentNote>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncIncidentRequest {
     pub id: Uuid,
     pub severity: String,
@@ -93,11 +208,69 @@ pub struct SyncIncidentRequest {
     pub threat_class: Option<String>,
     pub confidence: Option<f32>,
     pub created_at: i64,
+    #[serde(flatten)]
+    pub intel: IncidentIntel,
+}
+
+/// Ngữ cảnh gửi kèm incident để cloud console hiển thị được chi tiết
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IncidentIntel {
+    /// Process → root (gồm cả parent đã exit)
+    pub process_chain: Vec<IncidentProcess>,
+    pub rule_matches: Vec<String>,
+    pub key_features: Vec<IncidentFeature>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentProcess {
+    pub pid: u32,
+    pub name: String,
+    pub exe_path: Option<String>,
+    pub cmdline: Option<String>,
+    pub sha256: Option<String>,
+    pub md5: Option<String>,
+    /// trusted | signed_untrusted | unsigned | invalid | revoked | unknown
+    pub signature: Option<String>,
+    pub publisher: Option<String>,
+    pub exited: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentFeature {
+    pub name: String,
+    pub importance: f32,
+    pub delta: Option<f32>,
+    pub description: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
-pub struct SyncIncidentsRequest {
-    pub incidents: Vec<SyncIncidentRequest>,
+pub struct SyncIncidentsRequest<'a> {
+    pub incidents: &'a [SyncIncidentRequest],
+}
+
+/// Baseline local + version vector gửi lên `/agent/sync/baseline`
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncBaselineRequest {
+    pub baseline_hash: String,
+    pub mean_values: Vec<f32>,
+    pub variance_values: Option<Vec<f32>>,
+    pub sample_count: u64,
+    pub version: i32,
+    pub feature_version: Option<i32>,
+    pub layout_hash: Option<u32>,
+    pub version_vector: super::baseline_sync::VersionVector,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncBaselineResponse {
+    pub accepted: bool,
+    pub server_version: i32,
+    pub server_time: i64,
+    /// Revision golden baseline hiện tại của org (0 = chưa có)
+    #[serde(default)]
+    pub golden_revision: i64,
+    #[serde(default)]
+    pub has_golden_update: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -106,6 +279,60 @@ pub struct SyncIncidentsResponse {
     pub server_time: i64,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct NeverLearnListResponse {
+    pub revision: i64,
+    pub entries: Vec<crate::logic::behavioral_sigs::never_learn::CloudNeverLearnEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportNeverLearnRequest {
+    pub decisions: Vec<crate::logic::behavioral_sigs::never_learn::NeverLearnDecision>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportNeverLearnResponse {
+    pub accepted: usize,
+    pub server_time: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportPrevalenceRequest {
+    pub sightings: Vec<crate::logic::process_intel::prevalence::PrevalenceSighting>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReportPrevalenceResponse {
+    pub accepted: usize,
+    pub server_time: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PrevalenceQueryRequest {
+    pub hashes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PrevalenceQueryResponse {
+    pub total_endpoints: i64,
+    pub results: Vec<crate::logic::process_intel::prevalence::CloudPrevalence>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadTelemetryResponse {
+    pub accepted_events: usize,
+    pub accepted_stats: usize,
+    pub server_time: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadDatasetResponse {
+    pub accepted: usize,
+    /// Giới hạn sample rate của org
+    pub sample_rate: f64,
+    pub server_time: i64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct HealthResponse {
     pub status: String,
@@ -128,6 +355,8 @@ pub struct EnrollAgentRequest {
     pub os_type: String,
     pub os_version: String,
     pub agent_version: String,
+    /// Cert mTLS tự ký (chỉ cert, private key giữ lại trên máy)
+    pub client_cert_pem: Option<String>,
 }
 
 /// Enrollment response
@@ -137,6 +366,12 @@ pub struct EnrollAgentResponse {
     pub agent_token: String,
     pub org_id: Uuid,
     pub org_name: String,
+    /// CA / cert server để pin cho các kết nối sau
+    #[serde(default)]
+    pub server_ca_pem: Option<String>,
+    /// Key + cert mTLS đã sinh và pin (lưu vào identity)
+    #[serde(skip)]
+    pub tls: AgentTls,
 }
 
 impl CloudClient {
@@ -147,13 +382,46 @@ impl CloudClient {
             .build()
             .expect("Failed to create HTTP client");
 
-        Self {
+        let mut client = Self {
             config,
             agent_id: None,
             agent_token: None,
             org_id: None,
             http_client,
+            tls_connector: None,
+        };
+        // Pin do admin đặt sẵn áp dụng cả cho lần enroll đầu
+        if let Err(e) = client.configure_tls(&AgentTls::default()) {
+            log::error!("Cloud TLS pin ignored: {}", e);
+            if let Err(e) = client.rebuild_http_client() {
+                log::error!("Cloud proxy ignored: {}", e);
+            }
         }
+        client
+    }
+
+    /// Áp dụng pin server + cert mTLS cho mọi request sau đó
+    pub fn configure_tls(&mut self, tls: &AgentTls) -> Result<(), String> {
+        let connector = super::tls::connector(tls)?;
+        self.tls_connector = connector;
+        self.rebuild_http_client()
+    }
+
+    /// Dựng lại HTTP client theo TLS + proxy hiện hành (proxy đổi / đổi mạng)
+    pub fn rebuild_http_client(&mut self) -> Result<(), String> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(Duration::from_secs(self.config.timeout_seconds));
+        if let Some(connector) = self.tls_connector.clone() {
+            builder = builder.use_preconfigured_tls(connector);
+        }
+        builder = super::proxy::apply(builder, &self.config.server_url);
+        self.http_client = builder.build().map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// TLS connector cho kênh lệnh WebSocket (None = mặc định)
+    pub fn tls_connector(&self) -> Option<native_tls::TlsConnector> {
+        self.tls_connector.clone()
     }
 
     /// Check if agent is registered
@@ -267,6 +535,12 @@ impl CloudClient {
             .map(|h| h.to_string_lossy().to_string())
             .unwrap_or_else(|_| "unknown".to_string());
 
+        // Khóa mTLS sinh tại máy; lỗi → enroll không mTLS (chỉ bearer token)
+        let client_tls = super::tls::generate_client_identity(hwid).unwrap_or_else(|e| {
+            log::warn!("mTLS identity not generated: {}", e);
+            AgentTls::default()
+        });
+
         let request = EnrollAgentRequest {
             enrollment_token: enrollment_token.to_string(),
             hwid: hwid.to_string(),
@@ -274,6 +548,7 @@ impl CloudClient {
             os_type: "Windows".to_string(),
             os_version: get_os_version(),
             agent_version: env!("CARGO_PKG_VERSION").to_string(),
+            client_cert_pem: client_tls.client_cert_pem.clone(),
         };
 
         log::info!("Enrolling agent with token: {}...", &enrollment_token[..enrollment_token.len().min(15)]);
@@ -286,7 +561,7 @@ impl CloudClient {
             .map_err(|e| CloudError::NetworkError(e.to_string()))?;
 
         if response.status().is_success() {
-            let result: EnrollAgentResponse = response.json().await
+            let mut result: EnrollAgentResponse = response.json().await
                 .map_err(|e| CloudError::ParseError(e.to_string()))?;
 
             // Save credentials
@@ -294,6 +569,11 @@ impl CloudClient {
             self.agent_token = Some(result.agent_token.clone());
             self.org_id = Some(result.org_id);
 
+            result.tls = AgentTls { server_ca_pem: result.server_ca_pem.clone(), ..client_tls };
+            if let Err(e) = self.configure_tls(&result.tls) {
+                log::error!("Failed to apply cloud TLS from enrollment: {}", e);
+            }
+
             log::info!("Agent enrolled successfully: {} (org: {})", result.agent_id, result.org_name);
             Ok(result)
         } else {
@@ -318,6 +598,23 @@ impl CloudClient {
             incident_count,
             process_count: None,
             agent_version: env!("CARGO_PKG_VERSION").to_string(),
+            is_isolated: crate::logic::response::network::is_host_isolated(),
+            pending_actions: crate::logic::action_guard::get_pending_actions()
+                .into_iter()
+                .map(|a| PendingActionSummary {
+                    id: a.id,
+                    action_type: a.action_type.to_string(),
+                    target_pid: Some(a.target_pid),
+                    target_name: a.target_name,
+                    final_score: a.final_score,
+                    created_at: a.created_at.timestamp(),
+                })
+                .collect(),
+            rule_pack_version: super::rule_pack::applied_version(),
+            never_learn_revision: crate::logic::behavioral_sigs::never_learn::cloud_revision(),
+            intel_bundle_version: crate::logic::external_intel::intel_bundle::applied_version(),
+            settings_version: super::managed_settings::applied_version(),
+            baseline_revision: super::baseline_sync::golden_revision(),
         };
 
         let response = self.http_client
@@ -336,14 +633,206 @@ impl CloudClient {
         }
     }
 
-    /// Sync incidents to cloud server
-    pub async fn sync_incidents(&self, incidents: Vec<SyncIncidentRequest>) -> Result<SyncIncidentsResponse, CloudError> {
+    /// Fetch the organization's active policy (None = no policy)
+    pub async fn get_policy(&self) -> Result<Option<CloudPolicy>, CloudError> {
         let token = self.agent_token.as_ref()
             .ok_or(CloudError::NotRegistered)?;
 
-        let url = format!("{}/api/v1/agent/sync/incidents", self.config.server_url);
+        let url = format!("{}/api/v1/agent/policy", self.config.server_url);
+
+        let response = self.http_client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| CloudError::ParseError(e.to_string()))
+        } else {
+            Err(CloudError::ServerError(response.status().as_u16()))
+        }
+    }
+
+    /// Fetch the organization's latest signed rule pack (None = no pack)
+    pub async fn get_rule_pack(&self) -> Result<Option<super::rule_pack::SignedRulePack>, CloudError> {
+        let token = self.agent_token.as_ref()
+            .ok_or(CloudError::NotRegistered)?;
+
+        let url = format!("{}/api/v1/agent/rule-pack", self.config.server_url);
+
+        let response = self.http_client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| CloudError::ParseError(e.to_string()))
+        } else {
+            Err(CloudError::ServerError(response.status().as_u16()))
+        }
+    }
+
+    /// Get managed settings for this agent (org defaults + group)
+    pub async fn get_managed_settings(&self) -> Result<super::managed_settings::ManagedSettingsBundle, CloudError> {
+        let token = self.agent_token.as_ref()
+            .ok_or(CloudError::NotRegistered)?;
+
+        let url = format!("{}/api/v1/agent/settings", self.config.server_url);
+
+        let response = self.http_client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| CloudError::ParseError(e.to_string()))
+        } else {
+            Err(CloudError::ServerError(response.status().as_u16()))
+        }
+    }
+
+    /// Upload baseline local kèm version vector
+    pub async fn sync_baseline(&self, request: &SyncBaselineRequest) -> Result<SyncBaselineResponse, CloudError> {
+        let token = self.agent_token.as_ref()
+            .ok_or(CloudError::NotRegistered)?;
 
-        let request = SyncIncidentsRequest { incidents };
+        let url = format!("{}/api/v1/agent/sync/baseline", self.config.server_url);
+
+        let response = self.http_client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .json(request)
+            .send()
+            .await
+            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| CloudError::ParseError(e.to_string()))
+        } else {
+            Err(CloudError::ServerError(response.status().as_u16()))
+        }
+    }
+
+    /// Golden baseline của org (None = org chưa promote baseline nào)
+    pub async fn get_golden_baseline(&self) -> Result<Option<super::baseline_sync::GoldenBaseline>, CloudError> {
+        let token = self.agent_token.as_ref()
+            .ok_or(CloudError::NotRegistered)?;
+
+        let url = format!("{}/api/v1/agent/sync/baseline", self.config.server_url);
+
+        let response = self.http_client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            Ok(None)
+        } else if response.status().is_success() {
+            response.json().await
+                .map(Some)
+                .map_err(|e| CloudError::ParseError(e.to_string()))
+        } else {
+            Err(CloudError::ServerError(response.status().as_u16()))
+        }
+    }
+
+    /// Get org never-learn list
+    pub async fn get_never_learn(&self) -> Result<NeverLearnListResponse, CloudError> {
+        let token = self.agent_token.as_ref()
+            .ok_or(CloudError::NotRegistered)?;
+
+        let url = format!("{}/api/v1/agent/never-learn", self.config.server_url);
+
+        let response = self.http_client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| CloudError::ParseError(e.to_string()))
+        } else {
+            Err(CloudError::ServerError(response.status().as_u16()))
+        }
+    }
+
+    /// Report never-learn decisions made locally
+    pub async fn report_never_learn(
+        &self,
+        decisions: Vec<crate::logic::behavioral_sigs::never_learn::NeverLearnDecision>,
+    ) -> Result<ReportNeverLearnResponse, CloudError> {
+        let token = self.agent_token.as_ref()
+            .ok_or(CloudError::NotRegistered)?;
+
+        let url = format!("{}/api/v1/agent/never-learn/decisions", self.config.server_url);
+
+        let request = ReportNeverLearnRequest { decisions };
+
+        let response = self.http_client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| CloudError::ParseError(e.to_string()))
+        } else {
+            Err(CloudError::ServerError(response.status().as_u16()))
+        }
+    }
+
+    /// Report executable hashes seen on this endpoint (fleet prevalence)
+    pub async fn report_prevalence(
+        &self,
+        sightings: Vec<crate::logic::process_intel::prevalence::PrevalenceSighting>,
+    ) -> Result<ReportPrevalenceResponse, CloudError> {
+        let token = self.agent_token.as_ref()
+            .ok_or(CloudError::NotRegistered)?;
+
+        let url = format!("{}/api/v1/agent/prevalence", self.config.server_url);
+
+        let request = ReportPrevalenceRequest { sightings };
+
+        let response = self.http_client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| CloudError::ParseError(e.to_string()))
+        } else {
+            Err(CloudError::ServerError(response.status().as_u16()))
+        }
+    }
+
+    /// How many endpoints in the org have run these hashes
+    pub async fn query_prevalence(&self, hashes: Vec<String>) -> Result<PrevalenceQueryResponse, CloudError> {
+        let token = self.agent_token.as_ref()
+            .ok_or(CloudError::NotRegistered)?;
+
+        let url = format!("{}/api/v1/agent/prevalence/query", self.config.server_url);
+
+        let request = PrevalenceQueryRequest { hashes };
 
         let response = self.http_client
             .post(&url)
@@ -360,6 +849,104 @@ impl CloudClient {
             Err(CloudError::ServerError(response.status().as_u16()))
         }
     }
+
+    /// Upload telemetry batch (JSON đã nén zstd)
+    pub async fn upload_telemetry(&self, body: Vec<u8>) -> Result<UploadTelemetryResponse, CloudError> {
+        let token = self.agent_token.as_ref()
+            .ok_or(CloudError::NotRegistered)?;
+
+        let url = format!("{}/api/v1/agent/telemetry", self.config.server_url);
+
+        let response = self.http_client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Content-Type", "application/json")
+            .header("Content-Encoding", "zstd")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| CloudError::ParseError(e.to_string()))
+        } else {
+            Err(CloudError::ServerError(response.status().as_u16()))
+        }
+    }
+
+    /// Upload dataset record đã ẩn danh (JSON đã nén zstd)
+    pub async fn upload_dataset(&self, body: Vec<u8>) -> Result<UploadDatasetResponse, CloudError> {
+        let token = self.agent_token.as_ref()
+            .ok_or(CloudError::NotRegistered)?;
+
+        let url = format!("{}/api/v1/agent/dataset", self.config.server_url);
+
+        let response = self.http_client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Content-Type", "application/json")
+            .header("Content-Encoding", "zstd")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| CloudError::ParseError(e.to_string()))
+        } else {
+            Err(CloudError::ServerError(response.status().as_u16()))
+        }
+    }
+
+    /// Server URL (kênh lệnh WebSocket dùng cùng host)
+    pub fn server_url(&self) -> &str {
+        &self.config.server_url
+    }
+
+    /// Agent token (key verify chữ ký rule pack)
+    pub fn agent_token(&self) -> Option<&str> {
+        self.agent_token.as_deref()
+    }
+
+    /// Sync incidents to cloud server (body từ `sync_encoding::encode_incidents`)
+    pub async fn sync_incidents(&self, request: &EncodedBody) -> Result<SyncIncidentsResponse, CloudError> {
+        let url = format!("{}/api/v1/agent/sync/incidents", self.config.server_url);
+        self.post_encoded(&url, request).await
+    }
+
+    /// Đẩy ghi chú analyst tạo trên agent lên cloud (body `SyncIncidentNotesRequest` đã encode)
+    pub async fn sync_incident_notes(&self, request: &EncodedBody) -> Result<SyncIncidentsResponse, CloudError> {
+        let url = format!("{}/api/v1/agent/sync/incident-notes", self.config.server_url);
+        self.post_encoded(&url, request).await
+    }
+
+    async fn post_encoded(&self, url: &str, request: &EncodedBody) -> Result<SyncIncidentsResponse, CloudError> {
+        let token = self.agent_token.as_ref()
+            .ok_or(CloudError::NotRegistered)?;
+
+        let mut builder = self.http_client
+            .post(url)
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Content-Type", "application/json");
+        if let Some(encoding) = request.content_encoding {
+            builder = builder.header("Content-Encoding", encoding);
+        }
+
+        let response = builder
+            .body(request.body.clone())
+            .send()
+            .await
+            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+
+        if response.status().is_success() {
+            response.json().await
+                .map_err(|e| CloudError::ParseError(e.to_string()))
+        } else {
+            Err(CloudError::ServerError(response.status().as_u16()))
+        }
+    }
 }
 
 /// Cloud client errors
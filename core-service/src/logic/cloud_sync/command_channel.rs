@@ -0,0 +1,158 @@
+//! Command Channel - Kênh WebSocket nhận lệnh từ cloud gần như tức thì
+//!
+//! Heartbeat chỉ poll mỗi N giây → lệnh isolate / scan / approve action tới chậm.
+//! Agent giữ một kết nối WebSocket tới `/api/v1/agent/commands/ws`, server đẩy lệnh
+//! ngay khi console tạo. Mất kết nối → tự kết nối lại (backoff 1s → 60s).
+//!
+//! Heartbeat vẫn nhận lệnh như cũ: server giao mỗi lệnh đúng một lần (kênh nào lấy
+//! trước), nên kênh WebSocket hỏng / bị proxy chặn chỉ làm lệnh chậm lại, không mất.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use futures_util::StreamExt;
+use parking_lot::RwLock;
+use serde::Deserialize;
+use tokio::time::{sleep, timeout};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::AUTHORIZATION;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::Connector;
+
+use super::client::{AgentCommand, CloudClient};
+
+const CHANNEL_PATH: &str = "/api/v1/agent/commands/ws";
+const MAX_BACKOFF_SECS: u64 = 60;
+/// Server ping mỗi 30s; im lặng quá lâu → coi như kết nối chết (NAT / proxy cắt ngầm)
+const IDLE_TIMEOUT_SECS: u64 = 90;
+
+static CONNECTED: AtomicBool = AtomicBool::new(false);
+
+/// Kênh push đang kết nối (false → lệnh chỉ tới qua heartbeat)
+pub fn is_connected() -> bool {
+    CONNECTED.load(Ordering::Relaxed)
+}
+
+/// Message server đẩy xuống
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ChannelMessage {
+    Commands { commands: Vec<AgentCommand> },
+}
+
+/// http(s)://host → ws(s)://host/api/v1/agent/commands/ws
+fn channel_url(server_url: &str) -> Option<String> {
+    let base = server_url.trim_end_matches('/');
+    let ws = if let Some(rest) = base.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = base.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        return None;
+    };
+    Some(format!("{}{}", ws, CHANNEL_PATH))
+}
+
+/// Vòng kết nối kênh lệnh (chạy suốt vòng đời sync loop)
+pub async fn run(client: Arc<RwLock<CloudClient>>) {
+    let mut backoff = 1;
+
+    loop {
+        // Chưa đăng ký (personal mode chưa login) → chờ identity
+        let target = {
+            let c = client.read();
+            c.agent_token().map(|token| (c.server_url().to_string(), token.to_string(), c.tls_connector()))
+        };
+        let Some((server_url, token, tls)) = target else {
+            sleep(Duration::from_secs(5)).await;
+            continue;
+        };
+        let Some(url) = channel_url(&server_url) else {
+            log::warn!("Command channel disabled: unsupported server URL {}", server_url);
+            return;
+        };
+
+        let result = session(&client, &server_url, &url, &token, tls).await;
+        // Đã kết nối được → lỗi lần này là rớt mạng, thử lại nhanh
+        if CONNECTED.swap(false, Ordering::Relaxed) {
+            backoff = 1;
+        }
+        match result {
+            Ok(()) => log::info!("📡 Command channel closed, reconnecting in {}s", backoff),
+            Err(e) => log::debug!("Command channel unavailable ({}), retry in {}s", e, backoff),
+        }
+
+        sleep(Duration::from_secs(backoff)).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF_SECS);
+    }
+}
+
+async fn session(
+    client: &Arc<RwLock<CloudClient>>,
+    server_url: &str,
+    url: &str,
+    token: &str,
+    tls: Option<native_tls::TlsConnector>,
+) -> Result<(), String> {
+    let mut request = url.into_client_request().map_err(|e| e.to_string())?;
+    let auth = format!("Bearer {}", token).parse().map_err(|_| "invalid agent token".to_string())?;
+    request.headers_mut().insert(AUTHORIZATION, auth);
+
+    // Cùng pin / cert mTLS với HTTP client
+    let connector = tls.map(Connector::NativeTls);
+    let host = request.uri().host().unwrap_or_default().to_string();
+    let port = request.uri().port_u16().unwrap_or(if url.starts_with("wss") { 443 } else { 80 });
+    // Cùng proxy với HTTP client
+    let (mut ws, _) = match super::proxy::tunnel(server_url, &host, port).await? {
+        Some(stream) => tokio_tungstenite::client_async_tls_with_config(request, stream, None, connector).await,
+        None => tokio_tungstenite::connect_async_tls_with_config(request, None, false, connector).await,
+    }
+    .map_err(|e| e.to_string())?;
+    CONNECTED.store(true, Ordering::Relaxed);
+    log::info!("📡 Command channel connected");
+
+    loop {
+        let message = timeout(Duration::from_secs(IDLE_TIMEOUT_SECS), ws.next()).await
+            .map_err(|_| "idle timeout".to_string())?;
+
+        match message {
+            None | Some(Ok(Message::Close(_))) => return Ok(()),
+            Some(Err(e)) => return Err(e.to_string()),
+            Some(Ok(Message::Text(text))) => match serde_json::from_str::<ChannelMessage>(&text) {
+                Ok(ChannelMessage::Commands { commands }) => {
+                    log::info!("📡 {} command(s) pushed from cloud", commands.len());
+                    for cmd in commands {
+                        super::sync::dispatch_command(client, cmd).await;
+                    }
+                }
+                Err(e) => log::warn!("Unknown command channel message: {}", e),
+            },
+            // Ping được tungstenite tự trả pong
+            Some(Ok(_)) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_url() {
+        assert_eq!(
+            channel_url("https://cloud.oneshield.io/").as_deref(),
+            Some("wss://cloud.oneshield.io/api/v1/agent/commands/ws")
+        );
+        assert_eq!(
+            channel_url("http://127.0.0.1:8080").as_deref(),
+            Some("ws://127.0.0.1:8080/api/v1/agent/commands/ws")
+        );
+        assert_eq!(channel_url("cloud.oneshield.io"), None);
+
+        let msg: ChannelMessage = serde_json::from_str(
+            r#"{"type":"commands","commands":[{"type":"UpdatePolicy","version":3}]}"#
+        ).unwrap();
+        let ChannelMessage::Commands { commands } = msg;
+        assert!(matches!(commands[0], AgentCommand::UpdatePolicy { version: 3 }));
+    }
+}
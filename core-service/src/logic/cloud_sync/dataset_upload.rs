@@ -0,0 +1,453 @@
+//! Dataset Upload - Gửi dataset đã ẩn danh lên cloud để train model cấp fleet (opt-in)
+//!
+//! Mặc định TẮT. Chỉ upload khi cả 2 phía đồng ý:
+//! - Agent: `enabled` trong `cloud_dataset_upload.json`
+//! - Server: org bật dataset sharing (tắt → 403, agent tạm dừng 1 giờ)
+//!
+//! Record được ẩn danh trước khi rời máy:
+//! - Không gửi id record / tên file / endpoint (server chỉ lưu theo org)
+//! - Timestamp làm tròn xuống giờ
+//! - Nhãn người gán chỉ giữ tên lớp chuẩn (benign / suspicious / malicious)
+//!
+//! Sample theo hash record id (`sample_rate`, bị chặn trên bởi giới hạn org server trả về).
+//! Vị trí đã đọc (file + dòng) lưu trong `dataset_upload_state.json` → không gửi lại sau restart.
+//! Upload chạy trong sync loop sau mỗi heartbeat thành công.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::logic::dataset::{self, labeling, DatasetRecord};
+use crate::logic::threat::ThreatClass;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CONFIG_FILE: &str = "cloud_dataset_upload.json";
+const STATE_FILE: &str = "dataset_upload_state.json";
+/// Giới hạn server (`MAX_DATASET_BATCH`)
+const MAX_BATCH_SIZE: usize = 1000;
+/// Số dòng tối đa đọc cho 1 batch (không chặn sync loop khi sample rate thấp)
+const MAX_SCAN_LINES: usize = 50_000;
+const MAX_BATCHES_PER_UPLOAD: usize = 2;
+/// Org chưa bật sharing → chờ trước khi thử lại
+const ORG_DISABLED_BACKOFF_MINS: i64 = 60;
+const HOUR_MILLIS: u64 = 3600 * 1000;
+
+// ============================================================================
+// CONFIG
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatasetUploadConfig {
+    /// Opt-in - mặc định tắt
+    pub enabled: bool,
+    /// Tỉ lệ record được gửi (0.0 - 1.0)
+    pub sample_rate: f64,
+    /// Chỉ gửi record đã có nhãn người gán
+    pub labeled_only: bool,
+    /// Số record tối đa mỗi request
+    pub batch_size: usize,
+    /// Mức nén zstd (1-19)
+    pub compression_level: i32,
+}
+
+impl Default for DatasetUploadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sample_rate: 0.1,
+            labeled_only: false,
+            batch_size: 500,
+            compression_level: 3,
+        }
+    }
+}
+
+impl DatasetUploadConfig {
+    fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.sample_rate) {
+            return Err("sample_rate must be between 0.0 and 1.0".to_string());
+        }
+        if self.batch_size == 0 || self.batch_size > MAX_BATCH_SIZE {
+            return Err(format!("batch_size must be between 1 and {}", MAX_BATCH_SIZE));
+        }
+        if !(1..=19).contains(&self.compression_level) {
+            return Err("compression_level must be between 1 and 19".to_string());
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Record đã ẩn danh gửi lên server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnonymizedRecord {
+    /// Unix millis, làm tròn xuống giờ
+    pub hour: u64,
+    pub feature_version: u8,
+    pub layout_hash: u32,
+    pub features: Vec<f32>,
+    pub baseline_diff: Vec<f32>,
+    pub score: f32,
+    pub confidence: f32,
+    pub threat: ThreatClass,
+    /// Nhãn người gán (chỉ tên lớp chuẩn)
+    pub label: Option<ThreatClass>,
+}
+
+/// Vị trí đã đọc trong dataset (file + số dòng đã xử lý)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UploadCursor {
+    pub file: String,
+    pub line: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct DatasetBatch<'a> {
+    records: &'a [AnonymizedRecord],
+    /// Record đã đọc / bị sample bỏ trong batch này (server ước lượng độ phủ)
+    scanned: u64,
+    sampled_out: u64,
+}
+
+/// Batch đã nén, cursor chỉ tiến khi server nhận
+pub struct PreparedDatasetBatch {
+    pub body: Vec<u8>,
+    pub raw_bytes: usize,
+    pub records: usize,
+    scanned: u64,
+    sampled_out: u64,
+    next_cursor: UploadCursor,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatasetUploadStatus {
+    pub enabled: bool,
+    pub cursor: UploadCursor,
+    pub records_sent: u64,
+    pub records_scanned: u64,
+    pub sampled_out: u64,
+    pub batches_sent: u64,
+    pub bytes_compressed: u64,
+    /// Giới hạn sample rate của org (server trả về)
+    pub org_sample_rate: Option<f64>,
+    /// Org tắt dataset sharing → tạm dừng đến thời điểm này
+    pub paused_until: Option<DateTime<Utc>>,
+    pub last_upload: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+static CONFIG: Lazy<RwLock<DatasetUploadConfig>> = Lazy::new(|| RwLock::new(load_json(CONFIG_FILE)));
+static STATE: Lazy<Mutex<DatasetUploadStatus>> = Lazy::new(|| Mutex::new(load_json(STATE_FILE)));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Đọc + ẩn danh + sample batch kế tiếp từ cursor (None nếu tắt / hết dữ liệu mới)
+pub fn take_batch() -> Result<Option<PreparedDatasetBatch>, String> {
+    let config = CONFIG.read().clone();
+    if !config.enabled {
+        return Ok(None);
+    }
+    let (cursor, org_rate) = {
+        let state = STATE.lock();
+        if state.paused_until.is_some_and(|until| Utc::now() < until) {
+            return Ok(None);
+        }
+        (state.cursor.clone(), state.org_sample_rate)
+    };
+    let rate = org_rate.map_or(config.sample_rate, |cap| config.sample_rate.min(cap));
+
+    let scan = scan_from(&dataset::get_dataset_dir(), &cursor, &config, rate).map_err(|e| e.to_string())?;
+    if scan.next_cursor == cursor {
+        return Ok(None);
+    }
+    if scan.records.is_empty() {
+        // Không có record nào qua sample → chỉ tiến cursor, không gọi server
+        let mut state = STATE.lock();
+        state.cursor = scan.next_cursor;
+        state.records_scanned += scan.scanned;
+        state.sampled_out += scan.sampled_out;
+        save_state(&state);
+        return Ok(None);
+    }
+
+    let raw = serde_json::to_vec(&DatasetBatch {
+        records: &scan.records,
+        scanned: scan.scanned,
+        sampled_out: scan.sampled_out,
+    }).map_err(|e| e.to_string())?;
+    let body = zstd::encode_all(raw.as_slice(), config.compression_level).map_err(|e| e.to_string())?;
+
+    Ok(Some(PreparedDatasetBatch {
+        body,
+        raw_bytes: raw.len(),
+        records: scan.records.len(),
+        scanned: scan.scanned,
+        sampled_out: scan.sampled_out,
+        next_cursor: scan.next_cursor,
+    }))
+}
+
+/// Server nhận batch → tiến cursor
+pub fn mark_sent(batch: &PreparedDatasetBatch, org_sample_rate: f64) {
+    let mut state = STATE.lock();
+    state.cursor = batch.next_cursor.clone();
+    state.records_sent += batch.records as u64;
+    state.records_scanned += batch.scanned;
+    state.sampled_out += batch.sampled_out;
+    state.batches_sent += 1;
+    state.bytes_compressed += batch.body.len() as u64;
+    state.org_sample_rate = Some(org_sample_rate.clamp(0.0, 1.0));
+    state.paused_until = None;
+    state.last_upload = Some(Utc::now());
+    state.last_error = None;
+    save_state(&state);
+}
+
+/// Upload lỗi → giữ cursor, lần sau gửi lại cùng dữ liệu
+pub fn mark_failed(error: String) {
+    STATE.lock().last_error = Some(error);
+}
+
+/// Org chưa bật dataset sharing (server trả 403)
+pub fn mark_org_disabled() {
+    let mut state = STATE.lock();
+    state.paused_until = Some(Utc::now() + Duration::minutes(ORG_DISABLED_BACKOFF_MINS));
+    state.last_error = Some("Dataset sharing is disabled for this organization".to_string());
+    save_state(&state);
+}
+
+/// Số batch tối đa mỗi lần upload
+pub fn max_batches_per_upload() -> usize {
+    MAX_BATCHES_PER_UPLOAD
+}
+
+pub fn get_config() -> DatasetUploadConfig {
+    CONFIG.read().clone()
+}
+
+pub fn set_config(config: DatasetUploadConfig) -> Result<(), String> {
+    config.validate()?;
+    if config.enabled != CONFIG.read().enabled {
+        log::info!("Dataset upload {}", if config.enabled { "enabled (opt-in)" } else { "disabled" });
+    }
+    // Bật lại → thử server ngay, không chờ hết backoff
+    if config.enabled {
+        STATE.lock().paused_until = None;
+    }
+    *CONFIG.write() = config;
+    save_json(CONFIG_FILE, &*CONFIG.read());
+    Ok(())
+}
+
+pub fn get_status() -> DatasetUploadStatus {
+    let mut status = STATE.lock().clone();
+    status.enabled = CONFIG.read().enabled;
+    status
+}
+
+// ============================================================================
+// IMPLEMENTATION
+// ============================================================================
+
+pub(crate) struct ScanResult {
+    pub records: Vec<AnonymizedRecord>,
+    pub scanned: u64,
+    pub sampled_out: u64,
+    pub next_cursor: UploadCursor,
+}
+
+/// Đọc record sau `cursor` đến khi đủ `batch_size` record qua sample hoặc hết dữ liệu
+pub(crate) fn scan_from(
+    dir: &Path,
+    cursor: &UploadCursor,
+    config: &DatasetUploadConfig,
+    rate: f64,
+) -> std::io::Result<ScanResult> {
+    let labels = labeling::overlay_labels(dir);
+    let mut result = ScanResult { records: Vec::new(), scanned: 0, sampled_out: 0, next_cursor: cursor.clone() };
+    let mut lines_read = 0usize;
+
+    for path in labeling::dataset_files(dir)? {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        if name < cursor.file {
+            continue;
+        }
+        let skip = if name == cursor.file { cursor.line } else { 0 };
+        let mut reader = BufReader::new(File::open(&path)?);
+        let mut line_no = 0usize;
+        let mut line = String::new();
+
+        loop {
+            if result.records.len() >= config.batch_size || lines_read >= MAX_SCAN_LINES {
+                return Ok(result);
+            }
+            line.clear();
+            // Dòng chưa có '\n' = writer đang ghi dở → dừng, đọc lại lần sau
+            if reader.read_line(&mut line)? == 0 || !line.ends_with('\n') {
+                break;
+            }
+            line_no += 1;
+            if line_no <= skip {
+                continue;
+            }
+            lines_read += 1;
+            result.next_cursor = UploadCursor { file: name.clone(), line: line_no };
+
+            let Ok(record) = serde_json::from_str::<DatasetRecord>(line.trim()) else { continue };
+            // Record không có feature vector (vd intel match) không dùng để train
+            if record.features.is_empty() {
+                continue;
+            }
+            let id = labeling::record_id(&name, line_no);
+            let label = labels.get(&id).cloned().or_else(|| record.user_label.clone());
+            let anonymized = anonymize(&record, label.as_deref());
+            if config.labeled_only && anonymized.label.is_none() {
+                continue;
+            }
+            result.scanned += 1;
+            if !super::telemetry::sampled_in(&id, rate) {
+                result.sampled_out += 1;
+                continue;
+            }
+            result.records.push(anonymized);
+        }
+    }
+    Ok(result)
+}
+
+pub(crate) fn anonymize(record: &DatasetRecord, label: Option<&str>) -> AnonymizedRecord {
+    AnonymizedRecord {
+        hour: record.timestamp - record.timestamp % HOUR_MILLIS,
+        feature_version: record.feature_version,
+        layout_hash: record.layout_hash,
+        features: record.features.clone(),
+        baseline_diff: record.baseline_diff.clone(),
+        score: record.score,
+        confidence: record.confidence,
+        threat: record.threat,
+        label: label.and_then(canonical_label),
+    }
+}
+
+/// Nhãn tự do → lớp chuẩn (nhãn khác bỏ, có thể chứa thông tin người dùng)
+fn canonical_label(label: &str) -> Option<ThreatClass> {
+    match label.trim().to_lowercase().as_str() {
+        "benign" | "safe" | "false_positive" | "false positive" => Some(ThreatClass::Benign),
+        "suspicious" => Some(ThreatClass::Suspicious),
+        "malicious" | "malware" | "true_positive" | "true positive" => Some(ThreatClass::Malicious),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+fn data_path(file: &str) -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(file)
+}
+
+fn load_json<T: for<'de> Deserialize<'de> + Default>(file: &str) -> T {
+    fs::read_to_string(data_path(file))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_json<T: Serialize>(file: &str, value: &T) {
+    let path = data_path(file);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(value) {
+        let _ = fs::write(path, json);
+    }
+}
+
+fn save_state(state: &DatasetUploadStatus) {
+    save_json(STATE_FILE, state);
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::dataset::writer::DatasetWriter;
+    use std::io::Write;
+
+    fn record(i: u64, label: Option<&str>) -> DatasetRecord {
+        DatasetRecord {
+            timestamp: 1_700_000_123_456 + i,
+            feature_version: 1,
+            layout_hash: 7,
+            features: vec![0.5; 15],
+            baseline_diff: vec![0.0; 15],
+            score: 0.3,
+            confidence: 0.9,
+            threat: ThreatClass::Benign,
+            user_label: label.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_scan_anonymizes_and_resumes_from_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        let writer = DatasetWriter::from_path(dir.path().to_path_buf());
+        writer.append(&record(0, Some("Malicious"))).unwrap();
+        writer.append(&record(1, Some("john-laptop"))).unwrap();
+        for i in 2..5 {
+            writer.append(&record(i, None)).unwrap();
+        }
+
+        let config = DatasetUploadConfig { enabled: true, batch_size: 3, ..Default::default() };
+        let first = scan_from(dir.path(), &UploadCursor::default(), &config, 1.0).unwrap();
+        assert_eq!(first.records.len(), 3);
+        assert_eq!(first.next_cursor.line, 3);
+        assert_eq!(first.records[0].hour % HOUR_MILLIS, 0);
+        assert_eq!(first.records[0].label, Some(ThreatClass::Malicious));
+        // Nhãn tự do không phải tên lớp → bỏ
+        assert_eq!(first.records[1].label, None);
+        let json = serde_json::to_string(&first.records).unwrap();
+        assert!(!json.contains("john") && !json.contains(".jsonl"));
+
+        let rest = scan_from(dir.path(), &first.next_cursor, &config, 1.0).unwrap();
+        assert_eq!((rest.records.len(), rest.next_cursor.line), (2, 5));
+        let done = scan_from(dir.path(), &rest.next_cursor, &config, 1.0).unwrap();
+        assert!(done.records.is_empty());
+        assert_eq!(done.next_cursor, rest.next_cursor);
+
+        // Dòng ghi dở (chưa có '\n') chưa được đọc
+        let file = labeling::dataset_files(dir.path()).unwrap().remove(0);
+        fs::OpenOptions::new().append(true).open(&file).unwrap().write_all(b"{\"timestamp\":").unwrap();
+        let partial = scan_from(dir.path(), &rest.next_cursor, &config, 1.0).unwrap();
+        assert_eq!(partial.next_cursor, rest.next_cursor);
+
+        let labeled = DatasetUploadConfig { labeled_only: true, ..config.clone() };
+        let only = scan_from(dir.path(), &UploadCursor::default(), &labeled, 1.0).unwrap();
+        assert_eq!(only.records.len(), 1);
+
+        let none = scan_from(dir.path(), &UploadCursor::default(), &config, 0.0).unwrap();
+        assert!(none.records.is_empty());
+        assert_eq!(none.sampled_out, 5);
+    }
+}
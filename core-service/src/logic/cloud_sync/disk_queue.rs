@@ -0,0 +1,224 @@
+//! Disk Queue - Hàng đợi gửi cloud được lưu xuống đĩa
+//!
+//! Incident sinh ra lúc offline trước đây chỉ nằm trong RAM → crash / reboot là mất.
+//! Queue này ghi song song ra file JSONL (mỗi dòng một item):
+//! - `push`: append một dòng (không ghi lại cả file)
+//! - `take_all` → gửi → `commit` (thành công: ghi lại file chỉ với item mới vào trong lúc gửi)
+//!   hoặc `requeue` (thất bại: đưa item về đầu queue)
+//! - Khởi động: đọc lại file (dòng hỏng bị bỏ qua) → gửi tiếp
+//!
+//! Crash giữa lúc gửi và `commit` → item được gửi lại sau khi khởi động; server upsert
+//! theo id nên không tạo bản trùng. Vượt `max_items` / `max_bytes` → bỏ item cũ nhất.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub struct DiskQueue<T> {
+    path: PathBuf,
+    items: Vec<T>,
+    /// Kích thước JSON của từng item (byte, kèm newline)
+    sizes: Vec<usize>,
+    max_items: usize,
+    max_bytes: usize,
+    /// Item cũ bị bỏ do vượt giới hạn (từ lúc khởi động)
+    dropped: u64,
+}
+
+impl<T: Serialize + DeserializeOwned> DiskQueue<T> {
+    /// Mở queue, nạp lại item còn trong file từ lần chạy trước
+    pub fn open(path: PathBuf, max_items: usize, max_bytes: usize) -> Self {
+        let mut queue = Self { path, items: Vec::new(), sizes: Vec::new(), max_items, max_bytes, dropped: 0 };
+        let mut corrupt = 0;
+        if let Ok(content) = fs::read_to_string(&queue.path) {
+            for line in content.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str::<T>(line) {
+                    Ok(item) => {
+                        queue.items.push(item);
+                        queue.sizes.push(line.len() + 1);
+                    }
+                    Err(_) => corrupt += 1,
+                }
+            }
+        }
+        let trimmed = queue.enforce_caps();
+        if corrupt > 0 || trimmed {
+            log::warn!("Disk queue {}: skipped {} corrupt entries, dropped {} over cap", queue.path.display(), corrupt, queue.dropped);
+            queue.rewrite();
+        }
+        if !queue.items.is_empty() {
+            log::info!("Disk queue {}: resumed {} pending entries", queue.path.display(), queue.items.len());
+        }
+        queue
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn push(&mut self, item: T) {
+        let Ok(line) = serde_json::to_string(&item) else { return };
+        self.items.push(item);
+        self.sizes.push(line.len() + 1);
+        if self.enforce_caps() {
+            self.rewrite();
+            return;
+        }
+        if let Err(e) = append_line(&self.path, &line) {
+            log::warn!("Disk queue {}: append failed: {}", self.path.display(), e);
+        }
+    }
+
+    /// Lấy toàn bộ item để gửi. File giữ nguyên đến khi `commit` / `requeue`
+    pub fn take_all(&mut self) -> Vec<T> {
+        self.sizes.clear();
+        std::mem::take(&mut self.items)
+    }
+
+    /// Gửi thành công: file chỉ còn item vào queue trong lúc gửi
+    pub fn commit(&mut self) {
+        self.rewrite();
+    }
+
+    /// Gửi thất bại: đưa item về đầu queue (cũ hơn item mới vào)
+    pub fn requeue(&mut self, mut items: Vec<T>) {
+        let mut sizes: Vec<usize> = items.iter()
+            .map(|item| serde_json::to_string(item).map(|l| l.len() + 1).unwrap_or(0))
+            .collect();
+        items.append(&mut self.items);
+        sizes.append(&mut self.sizes);
+        self.items = items;
+        self.sizes = sizes;
+        self.enforce_caps();
+        self.rewrite();
+    }
+
+    /// Bỏ item cũ nhất đến khi trong giới hạn. Trả về true nếu có bỏ
+    fn enforce_caps(&mut self) -> bool {
+        let mut total: usize = self.sizes.iter().sum();
+        let mut overflow = 0;
+        while overflow < self.items.len()
+            && (self.items.len() - overflow > self.max_items || total > self.max_bytes)
+        {
+            total -= self.sizes[overflow];
+            overflow += 1;
+        }
+        if overflow == 0 {
+            return false;
+        }
+        self.items.drain(..overflow);
+        self.sizes.drain(..overflow);
+        self.dropped += overflow as u64;
+        log::warn!("Disk queue {}: dropped {} oldest entries (cap reached)", self.path.display(), overflow);
+        true
+    }
+
+    /// Ghi lại cả file (tmp + rename để không hỏng file khi crash giữa chừng)
+    fn rewrite(&self) {
+        if let Err(e) = write_all(&self.path, &self.items) {
+            log::warn!("Disk queue {}: rewrite failed: {}", self.path.display(), e);
+        }
+    }
+}
+
+fn append_line(path: &Path, line: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+fn write_all<T: Serialize>(path: &Path, items: &[T]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut content = String::new();
+    for item in items {
+        if let Ok(line) = serde_json::to_string(item) {
+            content.push_str(&line);
+            content.push('\n');
+        }
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        id: u32,
+    }
+
+    fn ids(items: &[Item]) -> Vec<u32> {
+        items.iter().map(|i| i.id).collect()
+    }
+
+    #[test]
+    fn test_disk_queue_resume_commit_requeue() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pending.jsonl");
+
+        let mut queue = DiskQueue::open(path.clone(), 100, 1 << 20);
+        for id in 1..=3 {
+            queue.push(Item { id });
+        }
+        // "Crash" → mở lại từ file, dòng hỏng bị bỏ qua
+        fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{broken\n").unwrap();
+        let mut queue: DiskQueue<Item> = DiskQueue::open(path.clone(), 100, 1 << 20);
+        assert_eq!(queue.len(), 3);
+
+        // Gửi thất bại → item về đầu queue, trước item mới
+        let batch = queue.take_all();
+        queue.push(Item { id: 4 });
+        queue.requeue(batch);
+        assert_eq!(ids(&DiskQueue::<Item>::open(path.clone(), 100, 1 << 20).take_all()), vec![1, 2, 3, 4]);
+
+        // Gửi thành công → chỉ còn item vào trong lúc gửi
+        let batch = queue.take_all();
+        assert_eq!(ids(&batch), vec![1, 2, 3, 4]);
+        // Chưa commit: file vẫn giữ batch (crash lúc gửi → gửi lại)
+        assert_eq!(DiskQueue::<Item>::open(path.clone(), 100, 1 << 20).len(), 4);
+        queue.push(Item { id: 5 });
+        queue.commit();
+        assert_eq!(ids(&DiskQueue::<Item>::open(path.clone(), 100, 1 << 20).take_all()), vec![5]);
+    }
+
+    #[test]
+    fn test_disk_queue_caps_drop_oldest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pending.jsonl");
+
+        let mut queue = DiskQueue::open(path.clone(), 3, 1 << 20);
+        for id in 1..=5 {
+            queue.push(Item { id });
+        }
+        assert_eq!(queue.dropped(), 2);
+        assert_eq!(ids(&DiskQueue::<Item>::open(path.clone(), 3, 1 << 20).take_all()), vec![3, 4, 5]);
+
+        // Giới hạn byte: mỗi dòng `{"id":N}\n` = 9 byte
+        let mut queue: DiskQueue<Item> = DiskQueue::open(path.clone(), 100, 20);
+        assert_eq!(queue.len(), 2);
+        queue.push(Item { id: 6 });
+        assert_eq!(ids(&queue.take_all()), vec![5, 6]);
+    }
+}
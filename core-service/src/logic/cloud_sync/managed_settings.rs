@@ -0,0 +1,325 @@
+//! Managed Settings - cấu hình agent do cloud console quản lý
+//!
+//! Server gửi bundle `{ version, group, settings }` (mặc định của org + override của group
+//! chứa endpoint). Sections:
+//! - `features`: kill-switch (ai, explain, auto_block, learning, dry_run)
+//! - `sync`: heartbeat_interval_secs, incident_sync_interval_secs
+//! - `syslog` / `elastic` / `otel`: override từng key trên config exporter local
+//!
+//! - Áp dụng nguyên tử: kiểm tra mọi section trước, 1 section lỗi → bỏ cả revision
+//!   (giữ revision đang chạy, báo lỗi lên status, không tải lại revision đó nữa)
+//! - Chỉ giữ trong bộ nhớ, không ghi đè config local: agent khởi động offline / chưa
+//!   nhận bundle → chạy theo config local. Section bỏ khỏi bundle → trả về config local
+//! - Section đang managed → UI không sửa được config local tương ứng
+//! - Revision đã áp dụng gửi kèm heartbeat (console theo dõi endpoint nào chưa cập nhật)
+
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::config::SafetyConfig;
+use crate::logic::telemetry::{elastic, otel, syslog};
+use super::sync::SyncConfig;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const MIN_HEARTBEAT_SECS: u64 = 10;
+const MAX_HEARTBEAT_SECS: u64 = 3600;
+const MIN_INCIDENT_SYNC_SECS: u64 = 10;
+const MAX_INCIDENT_SYNC_SECS: u64 = 86_400;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Bundle nhận từ `/api/v1/agent/settings`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManagedSettingsBundle {
+    pub version: i64,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub settings: serde_json::Value,
+}
+
+/// Section `features` (None = giữ giá trị local)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeatureSettings {
+    pub ai: Option<bool>,
+    pub explain: Option<bool>,
+    pub auto_block: Option<bool>,
+    pub learning: Option<bool>,
+    pub dry_run: Option<bool>,
+}
+
+/// Section `sync` (None = theo SyncConfig local)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyncSettings {
+    pub heartbeat_interval_secs: Option<u64>,
+    pub incident_sync_interval_secs: Option<u64>,
+}
+
+impl SyncSettings {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(secs) = self.heartbeat_interval_secs {
+            if !(MIN_HEARTBEAT_SECS..=MAX_HEARTBEAT_SECS).contains(&secs) {
+                return Err(format!(
+                    "heartbeat_interval_secs must be between {} and {}", MIN_HEARTBEAT_SECS, MAX_HEARTBEAT_SECS
+                ));
+            }
+        }
+        if let Some(secs) = self.incident_sync_interval_secs {
+            if !(MIN_INCIDENT_SYNC_SECS..=MAX_INCIDENT_SYNC_SECS).contains(&secs) {
+                return Err(format!(
+                    "incident_sync_interval_secs must be between {} and {}", MIN_INCIDENT_SYNC_SECS, MAX_INCIDENT_SYNC_SECS
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Giá trị kill-switch local (khôi phục khi bỏ section `features`)
+#[derive(Debug, Clone, Copy)]
+struct FeatureSnapshot {
+    ai: bool,
+    explain: bool,
+    auto_block: bool,
+    learning: bool,
+    dry_run: bool,
+}
+
+impl FeatureSnapshot {
+    fn capture() -> Self {
+        Self {
+            ai: SafetyConfig::is_ai_enabled(),
+            explain: SafetyConfig::is_explain_enabled(),
+            auto_block: SafetyConfig::is_auto_block_enabled(),
+            learning: SafetyConfig::is_learning_enabled(),
+            dry_run: SafetyConfig::is_dry_run(),
+        }
+    }
+
+    fn restore(&self) {
+        SafetyConfig::set_ai(self.ai);
+        SafetyConfig::set_explain(self.explain);
+        SafetyConfig::set_auto_block(self.auto_block);
+        SafetyConfig::set_learning(self.learning);
+        SafetyConfig::set_dry_run(self.dry_run);
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ManagedSettingsStatus {
+    /// Revision đang áp dụng (0 = chưa nhận / config local)
+    pub applied_version: i64,
+    pub group: Option<String>,
+    /// Section đang do cloud quản lý
+    pub managed_sections: Vec<String>,
+    pub applied_at: Option<DateTime<Utc>>,
+    /// Revision bị từ chối (section không hợp lệ)
+    pub rejected_version: Option<i64>,
+    pub last_error: Option<String>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+#[derive(Default)]
+struct State {
+    status: ManagedSettingsStatus,
+    features: Option<FeatureSettings>,
+    /// Kill-switch local trước khi áp section `features`
+    local_features: Option<FeatureSnapshot>,
+    sync: Option<SyncSettings>,
+}
+
+static STATE: Lazy<RwLock<State>> = Lazy::new(|| RwLock::new(State::default()));
+
+/// Section đã kiểm tra, chờ áp dụng
+struct Prepared {
+    features: Option<FeatureSettings>,
+    sync: Option<SyncSettings>,
+    syslog: Option<syslog::SyslogConfig>,
+    elastic: Option<elastic::ElasticConfig>,
+    otel: Option<otel::OtelConfig>,
+    sections: Vec<String>,
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+pub fn get_status() -> ManagedSettingsStatus {
+    STATE.read().status.clone()
+}
+
+/// Revision đã áp dụng (gửi kèm heartbeat)
+pub fn applied_version() -> i64 {
+    STATE.read().status.applied_version
+}
+
+/// Có nên tải bundle `version` không (chưa áp dụng, chưa bị từ chối)
+pub fn needs_update(version: i64) -> bool {
+    let state = STATE.read();
+    version != state.status.applied_version && Some(version) != state.status.rejected_version
+}
+
+/// Kill-switch đang do cloud quản lý (None = theo local)
+pub fn managed_features() -> Option<FeatureSettings> {
+    STATE.read().features
+}
+
+/// Áp dụng bundle: mọi section hợp lệ → áp dụng hết, không thì không đổi gì
+pub fn apply(bundle: &ManagedSettingsBundle) -> Result<(), String> {
+    let prepared = match prepare(&bundle.settings) {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            let mut state = STATE.write();
+            state.status.rejected_version = Some(bundle.version);
+            state.status.last_error = Some(e.clone());
+            return Err(e);
+        }
+    };
+
+    let mut state = STATE.write();
+    match prepared.features {
+        Some(features) => {
+            let local = *state.local_features.get_or_insert_with(FeatureSnapshot::capture);
+            SafetyConfig::set_ai(features.ai.unwrap_or(local.ai));
+            SafetyConfig::set_explain(features.explain.unwrap_or(local.explain));
+            SafetyConfig::set_auto_block(features.auto_block.unwrap_or(local.auto_block));
+            SafetyConfig::set_learning(features.learning.unwrap_or(local.learning));
+            SafetyConfig::set_dry_run(features.dry_run.unwrap_or(local.dry_run));
+        }
+        None => {
+            if let Some(local) = state.local_features.take() {
+                local.restore();
+            }
+        }
+    }
+    state.features = prepared.features;
+    state.sync = prepared.sync;
+    syslog::set_managed(prepared.syslog);
+    elastic::set_managed(prepared.elastic);
+    otel::set_managed(prepared.otel);
+
+    state.status = ManagedSettingsStatus {
+        applied_version: bundle.version,
+        group: bundle.group.clone(),
+        managed_sections: prepared.sections,
+        applied_at: Some(Utc::now()),
+        rejected_version: None,
+        last_error: None,
+    };
+    Ok(())
+}
+
+/// Interval sync hiệu lực (managed settings, không thì SyncConfig local)
+pub fn sync_intervals(config: &SyncConfig) -> (Duration, Duration) {
+    let sync = STATE.read().sync.unwrap_or_default();
+    (
+        Duration::from_secs(sync.heartbeat_interval_secs.unwrap_or(config.heartbeat_interval_secs)),
+        Duration::from_secs(sync.incident_sync_interval_secs.unwrap_or(config.incident_sync_interval_secs)),
+    )
+}
+
+/// Ghi đè các key của `overrides` lên config local. Key không tồn tại → lỗi
+/// (tránh gõ sai tên key mà config vẫn "áp dụng thành công")
+pub fn merge_overrides<T: Serialize + DeserializeOwned>(local: &T, overrides: &serde_json::Value) -> Result<T, String> {
+    let overrides = overrides.as_object().ok_or("Settings section must be an object")?;
+    let mut merged = serde_json::to_value(local).map_err(|e| e.to_string())?;
+    let obj = merged.as_object_mut().ok_or("Config is not an object")?;
+    for (key, value) in overrides {
+        if !obj.contains_key(key) {
+            return Err(format!("Unknown setting '{}'", key));
+        }
+        obj.insert(key.clone(), value.clone());
+    }
+    serde_json::from_value(merged).map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// Kiểm tra mọi section, chưa áp dụng gì
+fn prepare(settings: &serde_json::Value) -> Result<Prepared, String> {
+    let empty = serde_json::Map::new();
+    let sections = match settings {
+        serde_json::Value::Null => &empty,
+        other => other.as_object().ok_or("Managed settings must be an object")?,
+    };
+
+    let mut prepared = Prepared { features: None, sync: None, syslog: None, elastic: None, otel: None, sections: Vec::new() };
+    for (name, value) in sections {
+        let result = match name.as_str() {
+            "features" => section::<FeatureSettings>(value).map(|f| prepared.features = Some(f)),
+            "sync" => section::<SyncSettings>(value)
+                .and_then(|s| s.validate().map(|_| s))
+                .map(|s| prepared.sync = Some(s)),
+            "syslog" => syslog::managed_config(value).map(|c| prepared.syslog = Some(c)),
+            "elastic" => elastic::managed_config(value).map(|c| prepared.elastic = Some(c)),
+            "otel" => otel::managed_config(value).map(|c| prepared.otel = Some(c)),
+            // Section của agent bản mới hơn → bỏ qua, không chặn cả revision
+            other => {
+                log::warn!("Ignoring unknown managed settings section '{}'", other);
+                continue;
+            }
+        };
+        result.map_err(|e| format!("{}: {}", name, e))?;
+        prepared.sections.push(name.clone());
+    }
+    Ok(prepared)
+}
+
+fn section<T: DeserializeOwned>(value: &serde_json::Value) -> Result<T, String> {
+    serde_json::from_value(value.clone()).map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_prepare_validates_all_sections() {
+        let prepared = prepare(&json!({
+            "features": { "dry_run": true },
+            "sync": { "heartbeat_interval_secs": 60 },
+            "future_section": { "x": 1 }
+        })).unwrap();
+        assert_eq!(prepared.features.unwrap().dry_run, Some(true));
+        assert_eq!(prepared.sync.unwrap().heartbeat_interval_secs, Some(60));
+        assert_eq!(prepared.sections, vec!["features".to_string(), "sync".to_string()]);
+
+        assert!(prepare(&json!({ "features": { "dryrun": true } })).is_err());
+        assert!(prepare(&json!({ "sync": { "heartbeat_interval_secs": 1 } })).is_err());
+        assert!(prepare(&json!({ "otel": { "no_such_key": 1 } })).is_err());
+        assert!(prepare(&json!(null)).unwrap().sections.is_empty());
+    }
+
+    #[test]
+    fn test_merge_overrides() {
+        let local = SyncSettings { heartbeat_interval_secs: Some(30), incident_sync_interval_secs: None };
+        let merged: SyncSettings = merge_overrides(&local, &json!({ "incident_sync_interval_secs": 120 })).unwrap();
+        assert_eq!(merged.heartbeat_interval_secs, Some(30));
+        assert_eq!(merged.incident_sync_interval_secs, Some(120));
+
+        assert!(merge_overrides(&local, &json!({ "typo": 1 })).is_err());
+        assert!(merge_overrides(&local, &json!({ "heartbeat_interval_secs": "fast" })).is_err());
+        assert!(merge_overrides(&local, &json!([1])).is_err());
+    }
+}
@@ -2,15 +2,36 @@
 //!
 //! This module handles:
 //! - Agent registration with cloud server
+//! - Optional mTLS (agent-generated key) and server certificate pinning
+//! - HTTP / SOCKS proxy (system / PAC / manual, local config or policy)
 //! - Periodic heartbeats
-//! - Incident synchronization
+//! - WebSocket command channel (server push, heartbeat commands as fallback)
+//! - Incident synchronization (offline queue persisted to disk, delta + zstd bodies)
 //! - Policy updates
+//! - Managed settings (versioned per org / group, applied atomically, in memory only)
+//! - Baseline sync (version vectors, golden baseline vs local learning conflict policy)
+//! - Detection rule packs (behavioral + YARA)
+//! - Never-learn list (org entries down, local decisions up)
+//! - Fleet prevalence (hash sightings up, endpoint counts down)
+//! - Telemetry upload (sampled security events + engine stats, zstd batches)
+//! - Dataset upload (opt-in, anonymized training records for fleet retraining)
 
 pub mod client;
+pub mod command_channel;
 pub mod sync;
+pub mod rule_pack;
+pub mod telemetry;
+pub mod dataset_upload;
+pub mod tls;
+pub mod proxy;
+pub mod managed_settings;
+pub mod sync_encoding;
+pub mod baseline_sync;
+pub mod disk_queue;
 
 pub use client::CloudClient;
-pub use sync::{start_sync_loop, reload_credentials, SyncConfig, SyncStatus};
+pub use sync::{start_sync_loop, reload_credentials, refresh_http_client, SyncConfig, SyncStatus};
+pub use rule_pack::RulePackState;
 
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
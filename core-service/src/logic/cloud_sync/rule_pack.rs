@@ -0,0 +1,230 @@
+//! Cloud Rule Pack - Behavioral / YARA rules phân phối từ cloud theo org
+//!
+//! 1. Heartbeat báo version đang chạy, server trả version mới nhất
+//! 2. Version mới hơn → tải pack đã ký (`/api/v1/agent/rule-pack`)
+//! 3. Verify: sha256(nội dung) + chữ ký ed25519 của server trên "{version}:{sha256}"
+//!    bằng public key pin lúc build (token agent bị lộ cũng không giả được pack)
+//! 4. Behavioral rules → `rules/cloud_pack.json` (rules_dir hot-reload),
+//!    YARA → `yara/cloud_pack.yar` (full scan)
+//! 5. Lưu version đã apply → heartbeat tiếp theo báo lên console
+//!
+//! Pack lỗi (chữ ký / rule không hợp lệ) → giữ nguyên pack đang chạy.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::constants;
+use crate::logic::behavioral_sigs::rules_dir;
+use crate::logic::identity::signing;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const STATE_FILE: &str = "rule_pack.json";
+const RULES_FILE: &str = "cloud_pack.json";
+const YARA_DIR: &str = "yara";
+const YARA_FILE: &str = "cloud_pack.yar";
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Pack nhận từ server
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignedRulePack {
+    pub version: i32,
+    pub name: String,
+    /// JSON array text - hash đúng byte server gửi
+    pub behavioral_rules: String,
+    pub yara_rules: String,
+    pub sha256: String,
+    pub signature: String,
+}
+
+/// Pack đang chạy trên agent
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RulePackState {
+    pub version: i32,
+    pub name: String,
+    pub sha256: String,
+    pub behavioral_rule_count: usize,
+    pub has_yara: bool,
+    pub applied_at: i64,
+    /// Lỗi của lần apply gần nhất (pack bị từ chối)
+    pub last_error: Option<String>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static STATE: Lazy<RwLock<RulePackState>> = Lazy::new(|| RwLock::new(load_state()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Version pack đang chạy (0 = chưa có)
+pub fn applied_version() -> i32 {
+    STATE.read().version
+}
+
+pub fn get_state() -> RulePackState {
+    STATE.read().clone()
+}
+
+/// Verify + apply pack. Lỗi → pack cũ giữ nguyên.
+pub fn apply(pack: &SignedRulePack) -> Result<RulePackState, String> {
+    let result = verify(pack, constants::content_signing_public_key()).and_then(|_| install(pack));
+    match &result {
+        Ok(state) => {
+            *STATE.write() = state.clone();
+            log::info!(
+                "🛡️ Applied rule pack v{} '{}' ({} behavioral rules, yara: {})",
+                state.version, state.name, state.behavioral_rule_count, state.has_yara
+            );
+        }
+        Err(e) => {
+            STATE.write().last_error = Some(format!("v{}: {}", pack.version, e));
+            log::warn!("Rejected rule pack v{}: {}", pack.version, e);
+        }
+    }
+    save_state();
+    result
+}
+
+/// sha256 nội dung + chữ ký server
+pub fn verify(pack: &SignedRulePack, public_key: &str) -> Result<(), String> {
+    let hash = content_hash(&pack.behavioral_rules, &pack.yara_rules);
+    if !hash.eq_ignore_ascii_case(&pack.sha256) {
+        return Err("content hash mismatch".to_string());
+    }
+
+    let message = format!("{}:{}", pack.version, pack.sha256);
+    signing::verify(public_key, message.as_bytes(), &pack.signature)
+}
+
+/// SHA-256 over behavioral rules text + "\n" + YARA text (giống server)
+pub fn content_hash(behavioral_rules: &str, yara_rules: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(behavioral_rules.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(yara_rules.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+// ============================================================================
+// INSTALL
+// ============================================================================
+
+fn install(pack: &SignedRulePack) -> Result<RulePackState, String> {
+    let rules_path = rules_dir::rules_dir().join(RULES_FILE);
+    // Validate trước khi ghi - nếu không, watcher từ chối file nhưng version
+    // vẫn bị báo là đã apply
+    let rules = rules_dir::parse_rules(&rules_path, &pack.behavioral_rules)?;
+    let yara_path = data_dir().join(YARA_DIR).join(YARA_FILE);
+    let has_yara = !pack.yara_rules.trim().is_empty();
+
+    write_file(&rules_path, &pack.behavioral_rules)?;
+    if has_yara {
+        write_file(&yara_path, &pack.yara_rules)?;
+    } else if yara_path.exists() {
+        fs::remove_file(&yara_path).map_err(|e| format!("{}: {}", yara_path.display(), e))?;
+    }
+    // Không chờ debounce của watcher
+    rules_dir::reload_all();
+
+    Ok(RulePackState {
+        version: pack.version,
+        name: pack.name.clone(),
+        sha256: pack.sha256.clone(),
+        behavioral_rule_count: rules.len(),
+        has_yara,
+        applied_at: Utc::now().timestamp(),
+        last_error: None,
+    })
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+}
+
+fn load_state() -> RulePackState {
+    fs::read_to_string(data_dir().join(STATE_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_state() {
+    let path = data_dir().join(STATE_FILE);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*STATE.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn public_key(seed: u8) -> String {
+        hex::encode(SigningKey::from_bytes(&[seed; 32]).verifying_key().as_bytes())
+    }
+
+    fn signed(version: i32, rules: &str, yara: &str, seed: u8) -> SignedRulePack {
+        let sha256 = content_hash(rules, yara);
+        let signature = SigningKey::from_bytes(&[seed; 32]).sign(format!("{}:{}", version, sha256).as_bytes());
+        SignedRulePack {
+            version,
+            name: "baseline".to_string(),
+            behavioral_rules: rules.to_string(),
+            yara_rules: yara.to_string(),
+            sha256,
+            signature: hex::encode(signature.to_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_verify_rule_pack() {
+        let pack = signed(3, "[]", "rule test { condition: true }", 1);
+        assert!(verify(&pack, &public_key(1)).is_ok());
+        assert_eq!(verify(&pack, &public_key(2)).unwrap_err(), "invalid signature");
+
+        let mut tampered = pack.clone();
+        tampered.yara_rules.push_str(" ");
+        assert_eq!(verify(&tampered, &public_key(1)).unwrap_err(), "content hash mismatch");
+
+        // Đổi version (replay pack cũ với version mới) → chữ ký sai
+        let mut replayed = pack;
+        replayed.version = 4;
+        assert!(verify(&replayed, &public_key(1)).is_err());
+    }
+}
@@ -2,12 +2,14 @@
 //!
 //! Background task for periodic cloud synchronization.
 
-use super::client::{CloudClient, CloudConfig, CloudError, SyncIncidentRequest};
+use super::client::{CloudClient, CloudConfig, CloudError, SyncIncidentRequest, SyncIncidentNote, SyncIncidentNotesRequest, IncidentIntel};
+use super::disk_queue::DiskQueue;
 use super::set_status;
 use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicI32, Ordering};
 use std::time::Duration;
 use tokio::time::sleep;
 use uuid::Uuid;
@@ -53,6 +55,9 @@ pub struct SyncStatus {
     pub last_success_sync: Option<DateTime<Utc>>,  // Hardening: last successful operation
     pub heartbeat_count: u64,
     pub incident_sync_count: u64,
+    /// Byte incident / ghi chú đã sync: JSON nguyên vs thực gửi (delta + nén)
+    pub sync_bytes_raw: u64,
+    pub sync_bytes_sent: u64,
     pub consecutive_failures: u32,  // Hardening: track failures
     pub next_retry_delay_secs: u64, // Hardening: current backoff delay
     pub last_error_type: Option<String>, // Hardening: distinguish error types
@@ -60,14 +65,50 @@ pub struct SyncStatus {
     pub server_version: Option<String>,
 }
 
-/// Pending incidents queue
-static PENDING_INCIDENTS: once_cell::sync::Lazy<RwLock<Vec<SyncIncidentRequest>>> =
-    once_cell::sync::Lazy::new(|| RwLock::new(Vec::new()));
+/// Giới hạn queue offline (vượt → bỏ incident cũ nhất)
+const MAX_PENDING_INCIDENTS: usize = 5_000;
+const MAX_PENDING_INCIDENT_BYTES: usize = 50 * 1024 * 1024;
+const MAX_PENDING_NOTES: usize = 5_000;
+const MAX_PENDING_NOTE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Pending incidents queue (lưu xuống đĩa, sống qua crash / reboot lúc offline)
+static PENDING_INCIDENTS: once_cell::sync::Lazy<RwLock<DiskQueue<SyncIncidentRequest>>> =
+    once_cell::sync::Lazy::new(|| RwLock::new(DiskQueue::open(
+        queue_path("pending_incidents.jsonl"),
+        MAX_PENDING_INCIDENTS,
+        MAX_PENDING_INCIDENT_BYTES,
+    )));
+
+/// Ghi chú analyst tạo trên agent chờ đẩy lên cloud
+static PENDING_NOTES: once_cell::sync::Lazy<RwLock<DiskQueue<SyncIncidentNote>>> =
+    once_cell::sync::Lazy::new(|| RwLock::new(DiskQueue::open(
+        queue_path("pending_incident_notes.jsonl"),
+        MAX_PENDING_NOTES,
+        MAX_PENDING_NOTE_BYTES,
+    )));
+
+fn queue_path(file: &str) -> std::path::PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| std::path::PathBuf::from("."))
+        .join("OneShield")
+        .join(file)
+}
+
+/// Last cloud policy version applied locally
+static APPLIED_POLICY_VERSION: AtomicI32 = AtomicI32::new(0);
 
 /// Global cloud client for token updates
 static CLOUD_CLIENT: once_cell::sync::Lazy<RwLock<Option<Arc<RwLock<CloudClient>>>>> =
     once_cell::sync::Lazy::new(|| RwLock::new(None));
 
+/// Dựng lại HTTP client sau khi đổi cấu hình proxy local
+pub fn refresh_http_client() -> Result<(), String> {
+    match CLOUD_CLIENT.read().as_ref() {
+        Some(client) => client.write().rebuild_http_client(),
+        None => Ok(()),
+    }
+}
+
 /// Reload cloud client credentials from identity storage
 /// Call this after login/logout to update the token
 pub fn reload_credentials() {
@@ -81,6 +122,9 @@ pub fn reload_credentials() {
             // Update client token if available
             if let Some(client) = CLOUD_CLIENT.read().as_ref() {
                 client.write().set_token(identity.agent_token.clone());
+                if let Err(e) = client.write().configure_tls(&identity.tls) {
+                    log::error!("Cloud TLS (pin / mTLS) not applied: {}", e);
+                }
                 log::info!("✅ Cloud client credentials reloaded: agent={}", identity.agent_id);
 
                 // Update status
@@ -119,6 +163,24 @@ pub fn queue_incident(
     mitre_techniques: Option<Vec<String>>,
     threat_class: Option<String>,
     confidence: Option<f32>,
+) {
+    queue_incident_with_intel(
+        id, severity, title, description, mitre_techniques, threat_class, confidence,
+        IncidentIntel::default(),
+    );
+}
+
+/// Add incident to sync queue kèm process chain / rule matches / key features
+#[allow(clippy::too_many_arguments)]
+pub fn queue_incident_with_intel(
+    id: Uuid,
+    severity: String,
+    title: String,
+    description: Option<String>,
+    mitre_techniques: Option<Vec<String>>,
+    threat_class: Option<String>,
+    confidence: Option<f32>,
+    intel: IncidentIntel,
 ) {
     let incident = SyncIncidentRequest {
         id,
@@ -129,12 +191,19 @@ pub fn queue_incident(
         threat_class,
         confidence,
         created_at: Utc::now().timestamp(),
+        intel,
     };
 
     PENDING_INCIDENTS.write().push(incident);
     log::debug!("Incident queued for cloud sync: {}", id);
 }
 
+/// Add analyst note to sync queue (gửi sau incident cùng lượt sync)
+pub fn queue_incident_note(incident_id: Uuid, note: &crate::logic::incident::IncidentNote) {
+    PENDING_NOTES.write().push(SyncIncidentNote { incident_id, note: note.into() });
+    log::debug!("Incident note queued for cloud sync: {} ({})", note.id, incident_id);
+}
+
 /// Get pending incidents count
 pub fn pending_incidents_count() -> usize {
     PENDING_INCIDENTS.read().len()
@@ -150,6 +219,8 @@ pub async fn start_sync_loop(config: SyncConfig) {
     log::info!("Starting cloud sync loop...");
     log::info!("  Server: {}", config.server_url);
     log::info!("  Heartbeat interval: {}s", config.heartbeat_interval_secs);
+    // Nạp queue offline từ lần chạy trước (gửi tiếp ở lượt sync incident đầu tiên)
+    log::info!("  Pending incidents: {} (notes: {})", pending_incidents_count(), PENDING_NOTES.read().len());
 
     let cloud_config = CloudConfig {
         server_url: config.server_url.clone(),
@@ -162,6 +233,9 @@ pub async fn start_sync_loop(config: SyncConfig) {
     // Save to global for credential reloading
     *CLOUD_CLIENT.write() = Some(client.clone());
 
+    // Kênh lệnh push (WebSocket), heartbeat vẫn là đường dự phòng
+    tokio::task::spawn_local(super::command_channel::run(client.clone()));
+
     // Initial status
     set_status(SyncStatus {
         is_connected: false,
@@ -204,6 +278,9 @@ pub async fn start_sync_loop(config: SyncConfig) {
 
             // Set credentials on client
             client.write().set_token(existing.agent_token.clone());
+            if let Err(e) = client.write().configure_tls(&existing.tls) {
+                log::error!("Cloud TLS (pin / mTLS) not applied: {}", e);
+            }
 
             let mut status = super::get_status();
             status.is_registered = true;
@@ -239,6 +316,8 @@ pub async fn start_sync_loop(config: SyncConfig) {
                                 &config.server_url,
                             ) {
                                 log::error!("Failed to save identity: {}", e);
+                            } else if let Err(e) = identity_mgr.save_tls(result.tls.clone()) {
+                                log::error!("Failed to save identity TLS: {}", e);
                             } else {
                                 log::info!("Identity saved to secure storage");
                             }
@@ -275,14 +354,13 @@ pub async fn start_sync_loop(config: SyncConfig) {
     }
 
     // Main sync loop
-    let heartbeat_interval = Duration::from_secs(config.heartbeat_interval_secs);
-    let incident_interval = Duration::from_secs(config.incident_sync_interval_secs);
-
     let mut heartbeat_timer = tokio::time::Instant::now();
     let mut incident_timer = tokio::time::Instant::now();
 
     loop {
         sleep(Duration::from_secs(5)).await;
+        // Interval có thể đổi theo managed settings
+        let (heartbeat_interval, incident_interval) = super::managed_settings::sync_intervals(&config);
 
         // Check if identity was added (from personal_enroll)
         if !client.read().is_registered() {
@@ -314,8 +392,11 @@ pub async fn start_sync_loop(config: SyncConfig) {
                 let (cpu, mem) = get_system_metrics();
                 let incident_count = pending_incidents_count() as i32;
 
-                match client.read().heartbeat(cpu, mem, incident_count).await {
+                let heartbeat = client.read().heartbeat(cpu, mem, incident_count).await;
+                let mut rebuild_http_client = false;
+                match heartbeat {
                     Ok(response) => {
+                        super::proxy::report_success();
                         log::debug!("Heartbeat sent. Server time: {}, Policy v{}",
                             response.server_time, response.policy_version);
 
@@ -329,12 +410,48 @@ pub async fn start_sync_loop(config: SyncConfig) {
                         status.last_error_type = None;
                         set_status(status);
 
-                        // Handle commands
+                        super::sync_encoding::set_server_capabilities(&response.sync_capabilities);
+
+                        // Apply new cloud policy (whitelist, ...)
+                        if response.policy_version > APPLIED_POLICY_VERSION.load(Ordering::SeqCst) {
+                            apply_cloud_policy(&client).await;
+                        }
+
+                        // Apply new detection rule pack
+                        if response.rule_pack_version > super::rule_pack::applied_version() {
+                            apply_rule_pack(&client).await;
+                        }
+
+                        // Never-learn list: tải lại khi revision khác, báo decisions local
+                        if response.has_never_learn_update {
+                            apply_never_learn(&client).await;
+                        }
+
+                        // Managed settings: tải lại khi revision khác (bỏ qua revision đã từ chối)
+                        if response.has_settings_update && super::managed_settings::needs_update(response.settings_version) {
+                            apply_managed_settings(&client).await;
+                        }
+                        report_never_learn(&client).await;
+
+                        // Baseline: hoà giải golden mới, upload baseline local đã đổi
+                        sync_baseline(&client, response.baseline_revision).await;
+
+                        // Fleet prevalence: báo hash đã thấy, query hash đang chờ
+                        sync_prevalence(&client).await;
+
+                        // Telemetry: event đã sample + engine stats
+                        upload_telemetry(&client).await;
+
+                        // Dataset training (opt-in, đã ẩn danh)
+                        upload_dataset(&client).await;
+
+                        // Handle commands (kênh WebSocket chưa giao / không kết nối được)
                         for cmd in response.commands {
-                            handle_command(cmd).await;
+                            dispatch_command(&client, cmd).await;
                         }
                     }
                     Err(e) => {
+                        crate::logic::telemetry::metrics::inc_heartbeat_failure();
                         let mut status = super::get_status();
                         status.consecutive_failures += 1;
 
@@ -353,6 +470,8 @@ pub async fn start_sync_loop(config: SyncConfig) {
                             CloudError::NetworkError(_) => {
                                 // Network = temporary, will retry
                                 log::warn!("⚠️ Network unreachable, will retry");
+                                // Proxy policy hỏng / đổi mạng → dựng lại client
+                                rebuild_http_client = super::proxy::report_network_failure();
                                 "network".to_string()
                             }
                             _ => {
@@ -382,6 +501,12 @@ pub async fn start_sync_loop(config: SyncConfig) {
                         set_status(status);
                     }
                 }
+
+                if rebuild_http_client {
+                    if let Err(e) = client.write().rebuild_http_client() {
+                        log::error!("Cloud HTTP client rebuild failed: {}", e);
+                    }
+                }
             }
         }
 
@@ -391,27 +516,61 @@ pub async fn start_sync_loop(config: SyncConfig) {
 
             if client.read().is_registered() {
                 let incidents: Vec<SyncIncidentRequest> = {
-                    let mut queue = PENDING_INCIDENTS.write();
-                    std::mem::take(&mut *queue)
+                    PENDING_INCIDENTS.write().take_all()
                 };
 
                 if !incidents.is_empty() {
                     log::info!("Syncing {} incidents to cloud...", incidents.len());
 
-                    match client.read().sync_incidents(incidents.clone()).await {
-                        Ok(response) => {
-                            log::info!("✅ Synced {} incidents", response.synced_count);
+                    let result = match super::sync_encoding::encode_incidents(&incidents) {
+                        Ok(body) => client.read().sync_incidents(&body).await.map(|r| (r, body)),
+                        Err(e) => Err(CloudError::ParseError(e)),
+                    };
+                    match result {
+                        Ok((response, body)) => {
+                            log::info!("✅ Synced {} incidents ({} bytes, {} uncompressed)",
+                                response.synced_count, body.body.len(), body.raw_bytes);
                             let mut status = super::get_status();
+                            status.sync_bytes_raw += body.raw_bytes as u64;
+                            status.sync_bytes_sent += body.body.len() as u64;
                             status.last_sync = Some(Utc::now());
                             status.last_success_sync = Some(Utc::now()); // Hardening
                             status.incident_sync_count += response.synced_count as u64;
                             status.consecutive_failures = 0; // Reset on success
                             set_status(status);
+                            PENDING_INCIDENTS.write().commit();
                         }
                         Err(e) => {
                             log::error!("Incident sync failed: {}", e);
-                            // Re-queue incidents
-                            PENDING_INCIDENTS.write().extend(incidents);
+                            crate::logic::telemetry::metrics::inc_incident_sync_failure();
+                            // Re-queue incidents (về đầu queue, vẫn trên đĩa)
+                            PENDING_INCIDENTS.write().requeue(incidents);
+                        }
+                    }
+                }
+
+                // Notes sau incident để cloud đã có incident khi gắn ghi chú
+                let notes: Vec<SyncIncidentNote> = {
+                    PENDING_NOTES.write().take_all()
+                };
+
+                if !notes.is_empty() {
+                    let result = match super::sync_encoding::encode_json(&SyncIncidentNotesRequest { notes: notes.clone() }) {
+                        Ok(body) => client.read().sync_incident_notes(&body).await.map(|r| (r, body)),
+                        Err(e) => Err(CloudError::ParseError(e)),
+                    };
+                    match result {
+                        Ok((response, body)) => {
+                            log::info!("✅ Synced {} incident notes", response.synced_count);
+                            let mut status = super::get_status();
+                            status.sync_bytes_raw += body.raw_bytes as u64;
+                            status.sync_bytes_sent += body.body.len() as u64;
+                            set_status(status);
+                            PENDING_NOTES.write().commit();
+                        }
+                        Err(e) => {
+                            log::error!("Incident note sync failed: {}", e);
+                            PENDING_NOTES.write().requeue(notes);
                         }
                     }
                 }
@@ -438,6 +597,234 @@ fn get_system_metrics() -> (f32, f32) {
     (cpu, mem)
 }
 
+/// Fetch active cloud policy and apply the parts the agent enforces locally
+async fn apply_cloud_policy(client: &Arc<RwLock<CloudClient>>) {
+    let policy = client.read().get_policy().await;
+    match policy {
+        Ok(Some(policy)) => {
+            let items = policy.config.get("whitelist")
+                .and_then(|w| w.as_array())
+                .cloned()
+                .unwrap_or_default();
+            crate::logic::whitelist::apply_cloud(&items);
+            crate::logic::action_guard::apply_cloud_config(policy.config.get("action_guard"));
+            crate::logic::external_intel::virustotal::apply_cloud_config(policy.config.get("virustotal"));
+            crate::logic::external_intel::misp::apply_cloud_config(policy.config.get("misp"));
+            crate::logic::external_intel::taxii::apply_cloud_config(policy.config.get("taxii"));
+            crate::logic::external_intel::otx::apply_cloud_config(policy.config.get("otx"));
+            crate::logic::network::geoip::apply_cloud_config(policy.config.get("geoip"));
+            if super::proxy::apply_cloud_config(policy.config.get("proxy")) {
+                if let Err(e) = client.write().rebuild_http_client() {
+                    log::error!("Cloud proxy from policy not applied: {}", e);
+                }
+            }
+            let publishers = policy.config.get("trusted_publishers")
+                .and_then(|p| p.as_array())
+                .cloned()
+                .unwrap_or_default();
+            if crate::logic::process_intel::signature::apply_cloud_trusted_publishers(&publishers) {
+                tokio::task::spawn_blocking(crate::logic::process_intel::reputation::recheck_signatures);
+            }
+            APPLIED_POLICY_VERSION.store(policy.version, Ordering::SeqCst);
+            log::info!("📋 Applied cloud policy v{}", policy.version);
+        }
+        Ok(None) => log::debug!("No active cloud policy"),
+        Err(e) => log::warn!("Failed to fetch cloud policy: {}", e),
+    }
+}
+
+/// Fetch latest signed rule pack, verify and install it
+async fn apply_rule_pack(client: &Arc<RwLock<CloudClient>>) {
+    let pack = client.read().get_rule_pack().await;
+    match pack {
+        Ok(Some(pack)) => {
+            let _ = super::rule_pack::apply(&pack);
+        }
+        Ok(None) => log::debug!("No rule pack for organization"),
+        Err(e) => log::warn!("Failed to fetch rule pack: {}", e),
+    }
+}
+
+async fn apply_managed_settings(client: &Arc<RwLock<CloudClient>>) {
+    let bundle = client.read().get_managed_settings().await;
+    match bundle {
+        Ok(bundle) => match super::managed_settings::apply(&bundle) {
+            Ok(()) => log::info!("⚙️ Managed settings r{} applied (group: {})",
+                bundle.version, bundle.group.as_deref().unwrap_or("default")),
+            Err(e) => log::error!("Managed settings r{} rejected: {}", bundle.version, e),
+        },
+        Err(e) => log::warn!("Failed to fetch managed settings: {}", e),
+    }
+}
+
+async fn sync_baseline(client: &Arc<RwLock<CloudClient>>, golden_revision: i64) {
+    use super::baseline_sync;
+
+    if baseline_sync::needs_golden(golden_revision) {
+        let golden = client.read().get_golden_baseline().await;
+        match golden {
+            Ok(golden) => match baseline_sync::reconcile(golden_revision, golden) {
+                Ok(()) => log::info!("📐 Golden baseline r{} reconciled", golden_revision),
+                Err(e) => log::error!("Golden baseline r{} rejected: {}", golden_revision, e),
+            },
+            Err(e) => log::warn!("Failed to fetch golden baseline: {}", e),
+        }
+    }
+
+    let Some(request) = baseline_sync::prepare_upload() else {
+        return;
+    };
+    let response = client.read().sync_baseline(&request).await;
+    match response {
+        Ok(response) => {
+            log::debug!("Baseline uploaded (vector {:?})", request.version_vector);
+            // Golden đổi giữa 2 heartbeat → status báo chờ, hoà giải ở heartbeat sau
+            baseline_sync::upload_succeeded(&request, &response);
+        }
+        Err(e) => {
+            log::warn!("Baseline upload failed: {}", e);
+            baseline_sync::upload_failed(e.to_string());
+        }
+    }
+}
+
+async fn apply_never_learn(client: &Arc<RwLock<CloudClient>>) {
+    let list = client.read().get_never_learn().await;
+    match list {
+        Ok(list) => {
+            log::info!("🚫 Never-learn list r{} applied ({} entries)", list.revision, list.entries.len());
+            crate::logic::behavioral_sigs::never_learn::apply_cloud_entries(list.revision, list.entries);
+        }
+        Err(e) => log::warn!("Failed to fetch never-learn list: {}", e),
+    }
+}
+
+async fn report_never_learn(client: &Arc<RwLock<CloudClient>>) {
+    use crate::logic::behavioral_sigs::never_learn;
+
+    let decisions = never_learn::take_pending_decisions();
+    if decisions.is_empty() {
+        return;
+    }
+
+    let result = client.read().report_never_learn(decisions.clone()).await;
+    match result {
+        Ok(resp) => log::debug!("Reported {} never-learn decisions", resp.accepted),
+        Err(e) => {
+            log::warn!("Failed to report never-learn decisions: {}", e);
+            never_learn::requeue_decisions(decisions);
+        }
+    }
+}
+
+async fn sync_prevalence(client: &Arc<RwLock<CloudClient>>) {
+    use crate::logic::process_intel::prevalence;
+
+    // Hash executable đang chạy (file I/O) ngoài async runtime
+    let _ = tokio::task::spawn_blocking(prevalence::collect_running).await;
+
+    // Báo trước để query tính cả endpoint này
+    let sightings = prevalence::take_pending_sightings();
+    if !sightings.is_empty() {
+        let result = client.read().report_prevalence(sightings.clone()).await;
+        match result {
+            Ok(resp) => log::debug!("Reported {} prevalence sightings", resp.accepted),
+            Err(e) => {
+                log::warn!("Failed to report prevalence sightings: {}", e);
+                prevalence::requeue_sightings(sightings);
+                return;
+            }
+        }
+    }
+
+    let hashes = prevalence::take_pending_lookups();
+    if hashes.is_empty() {
+        return;
+    }
+
+    let result = client.read().query_prevalence(hashes.clone()).await;
+    match result {
+        Ok(resp) => prevalence::apply_lookup(&hashes, resp.total_endpoints, resp.results),
+        Err(e) => {
+            log::warn!("Failed to query fleet prevalence: {}", e);
+            prevalence::requeue_lookups(hashes);
+        }
+    }
+}
+
+async fn upload_telemetry(client: &Arc<RwLock<CloudClient>>) {
+    use super::telemetry;
+
+    telemetry::collect_stats();
+    for _ in 0..telemetry::max_batches_per_upload() {
+        let batch = match telemetry::take_batch() {
+            Ok(Some(batch)) => batch,
+            Ok(None) => break,
+            Err(e) => {
+                log::warn!("Failed to prepare telemetry batch: {}", e);
+                break;
+            }
+        };
+
+        let result = client.read().upload_telemetry(batch.body.clone()).await;
+        match result {
+            Ok(resp) => {
+                log::debug!("Uploaded {} telemetry events ({} -> {} bytes)",
+                    resp.accepted_events, batch.raw_bytes, batch.body.len());
+                telemetry::mark_sent(&batch);
+            }
+            Err(e) => {
+                log::warn!("Failed to upload telemetry: {}", e);
+                crate::logic::telemetry::metrics::inc_telemetry_upload_failure();
+                telemetry::requeue(batch, e.to_string());
+                break;
+            }
+        }
+    }
+}
+
+async fn upload_dataset(client: &Arc<RwLock<CloudClient>>) {
+    use super::dataset_upload;
+
+    for _ in 0..dataset_upload::max_batches_per_upload() {
+        let batch = match dataset_upload::take_batch() {
+            Ok(Some(batch)) => batch,
+            Ok(None) => break,
+            Err(e) => {
+                log::warn!("Failed to prepare dataset batch: {}", e);
+                break;
+            }
+        };
+
+        let result = client.read().upload_dataset(batch.body.clone()).await;
+        match result {
+            Ok(resp) => {
+                log::debug!("Uploaded {} dataset records ({} -> {} bytes)",
+                    resp.accepted, batch.raw_bytes, batch.body.len());
+                dataset_upload::mark_sent(&batch, resp.sample_rate);
+            }
+            Err(CloudError::ServerError(403)) => {
+                log::info!("Dataset sharing disabled for organization, pausing dataset upload");
+                dataset_upload::mark_org_disabled();
+                break;
+            }
+            Err(e) => {
+                log::warn!("Failed to upload dataset: {}", e);
+                dataset_upload::mark_failed(e.to_string());
+                break;
+            }
+        }
+    }
+}
+
+/// Thực thi lệnh cloud (từ heartbeat hoặc kênh WebSocket)
+pub(super) async fn dispatch_command(client: &Arc<RwLock<CloudClient>>, cmd: super::client::AgentCommand) {
+    if let super::client::AgentCommand::UpdatePolicy { .. } = cmd {
+        apply_cloud_policy(client).await;
+    }
+    handle_command(cmd).await;
+}
+
 /// Handle command from server
 async fn handle_command(cmd: super::client::AgentCommand) {
     match cmd {
@@ -457,5 +844,49 @@ async fn handle_command(cmd: super::client::AgentCommand) {
             log::info!("⬆️ Received UpdateAgent command: {}", url);
             // TODO: Download and install update
         }
+        super::client::AgentCommand::IsolateHost => {
+            log::warn!("🔒 Received IsolateHost command");
+            match crate::logic::response::network::isolate_host(&[]) {
+                Ok(result) => log::warn!("{}", result.message),
+                Err(e) => log::error!("Host isolation failed: {}", e),
+            }
+        }
+        super::client::AgentCommand::ReleaseHost => {
+            log::info!("🔓 Received ReleaseHost command");
+            match crate::logic::response::network::release_host() {
+                Ok(result) => log::info!("{}", result.message),
+                Err(e) => log::error!("Release isolation failed: {}", e),
+            }
+        }
+        super::client::AgentCommand::ApproveAction { action_id } => {
+            log::warn!("✅ Received ApproveAction command: {}", action_id);
+            match crate::logic::action_guard::approve_action(&action_id) {
+                Ok(result) => log::warn!("Remote-approved action executed: {}", result.message),
+                Err(e) => log::error!("Remote approval of {} failed: {}", action_id, e),
+            }
+        }
+        super::client::AgentCommand::RejectAction { action_id } => {
+            log::info!("❌ Received RejectAction command: {}", action_id);
+            if let Err(e) = crate::logic::action_guard::cancel_action(&action_id) {
+                log::error!("Remote rejection of {} failed: {}", action_id, e);
+            }
+        }
+        super::client::AgentCommand::IncidentVerdict { incident_id, verdict } => {
+            log::info!("🏷️ Received IncidentVerdict command: {} {:?}", incident_id, verdict);
+            let result = tokio::task::spawn_blocking(move || crate::logic::incident::set_verdict(incident_id, verdict)).await;
+            match result {
+                Ok(Ok(labeled)) => log::info!("Incident {} verdict labeled {} dataset record(s)", incident_id, labeled),
+                Ok(Err(e)) => log::warn!("Remote verdict for incident {} not applied: {}", incident_id, e),
+                Err(e) => log::error!("Remote verdict for incident {} failed: {}", incident_id, e),
+            }
+        }
+        super::client::AgentCommand::IncidentNote { incident_id, note } => {
+            log::info!("📝 Received IncidentNote command: {} by {}", incident_id, note.author);
+            match crate::logic::incident::merge_cloud_note(incident_id, note.into()) {
+                Ok(true) => log::info!("Console note added to incident {}", incident_id),
+                Ok(false) => log::debug!("Console note already present on incident {}", incident_id),
+                Err(e) => log::warn!("Console note for incident {} not applied: {}", incident_id, e),
+            }
+        }
     }
 }
@@ -0,0 +1,248 @@
+//! Sync Encoding - Giảm băng thông khi đẩy incident / ghi chú lên cloud
+//!
+//! - Server báo encoding hỗ trợ qua heartbeat (`sync_capabilities`);
+//!   chưa báo (server cũ) → gửi JSON thường như trước
+//! - Delta: process lặp lại giữa các incident trong batch (cùng process
+//!   chain gốc) chỉ gửi một lần trong `processes`, incident tham chiếu
+//!   bằng `process_refs`
+//! - Body > `MIN_COMPRESS_BYTES` nén zstd (`Content-Encoding: zstd`)
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::Serialize;
+
+use super::client::{IncidentProcess, SyncIncidentRequest, SyncIncidentsRequest};
+
+/// Body nhỏ hơn thì nén không đáng
+const MIN_COMPRESS_BYTES: usize = 1024;
+/// Mức nén zstd cho body sync (ưu tiên CPU, batch nhỏ)
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Encoding server chấp nhận (heartbeat gần nhất)
+static SERVER_CAPABILITIES: Lazy<RwLock<SyncCapabilities>> =
+    Lazy::new(|| RwLock::new(SyncCapabilities::default()));
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SyncCapabilities {
+    pub zstd: bool,
+    pub incident_delta: bool,
+}
+
+impl SyncCapabilities {
+    fn from_list(capabilities: &[String]) -> Self {
+        Self {
+            zstd: capabilities.iter().any(|c| c == "zstd"),
+            incident_delta: capabilities.iter().any(|c| c == "incident_delta"),
+        }
+    }
+}
+
+/// Body đã encode, sẵn sàng gửi
+#[derive(Debug, Clone)]
+pub struct EncodedBody {
+    pub body: Vec<u8>,
+    /// Giá trị header `Content-Encoding` (None = không nén)
+    pub content_encoding: Option<&'static str>,
+    /// Số byte JSON nếu gửi nguyên (không delta, không nén)
+    pub raw_bytes: usize,
+}
+
+/// Incident trên dây: process chain chuyển sang `process_refs`
+#[derive(Serialize)]
+struct DeltaIncident {
+    #[serde(flatten)]
+    incident: SyncIncidentRequest,
+    process_refs: Vec<usize>,
+}
+
+#[derive(Serialize)]
+struct DeltaIncidentsRequest {
+    incidents: Vec<DeltaIncident>,
+    processes: Vec<IncidentProcess>,
+}
+
+/// Cập nhật từ heartbeat response
+pub fn set_server_capabilities(capabilities: &[String]) {
+    let capabilities = SyncCapabilities::from_list(capabilities);
+    let mut current = SERVER_CAPABILITIES.write();
+    if *current != capabilities {
+        log::info!("Cloud sync encoding: {:?}", capabilities);
+        *current = capabilities;
+    }
+}
+
+pub fn server_capabilities() -> SyncCapabilities {
+    *SERVER_CAPABILITIES.read()
+}
+
+/// Batch incident → body `/agent/sync/incidents`
+pub fn encode_incidents(incidents: &[SyncIncidentRequest]) -> Result<EncodedBody, String> {
+    encode_incidents_with(incidents, server_capabilities())
+}
+
+/// Payload JSON bất kỳ (ghi chú incident, ...) → body, nén nếu server hỗ trợ
+pub fn encode_json<T: Serialize>(payload: &T) -> Result<EncodedBody, String> {
+    let raw = serde_json::to_vec(payload).map_err(|e| e.to_string())?;
+    let raw_bytes = raw.len();
+    compress(raw, raw_bytes, server_capabilities())
+}
+
+fn encode_incidents_with(
+    incidents: &[SyncIncidentRequest],
+    capabilities: SyncCapabilities,
+) -> Result<EncodedBody, String> {
+    let raw = serde_json::to_vec(&SyncIncidentsRequest { incidents }).map_err(|e| e.to_string())?;
+    let raw_bytes = raw.len();
+
+    let body = match capabilities.incident_delta {
+        true => match delta_encode(incidents)? {
+            Some(delta) if delta.len() < raw.len() => delta,
+            _ => raw,
+        },
+        false => raw,
+    };
+    compress(body, raw_bytes, capabilities)
+}
+
+/// None khi không có process nào lặp lại (delta không lợi gì)
+fn delta_encode(incidents: &[SyncIncidentRequest]) -> Result<Option<Vec<u8>>, String> {
+    let mut processes: Vec<IncidentProcess> = Vec::new();
+    let mut index_by_key: HashMap<String, usize> = HashMap::new();
+    let mut repeated = false;
+    let mut delta_incidents = Vec::with_capacity(incidents.len());
+
+    for incident in incidents {
+        let mut incident = incident.clone();
+        let chain = std::mem::take(&mut incident.intel.process_chain);
+        let mut process_refs = Vec::with_capacity(chain.len());
+
+        for process in chain {
+            let key = serde_json::to_string(&process).map_err(|e| e.to_string())?;
+            let index = match index_by_key.get(&key) {
+                Some(&index) => {
+                    repeated = true;
+                    index
+                }
+                None => {
+                    processes.push(process);
+                    index_by_key.insert(key, processes.len() - 1);
+                    processes.len() - 1
+                }
+            };
+            process_refs.push(index);
+        }
+        delta_incidents.push(DeltaIncident { incident, process_refs });
+    }
+
+    if !repeated {
+        return Ok(None);
+    }
+    serde_json::to_vec(&DeltaIncidentsRequest { incidents: delta_incidents, processes })
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+fn compress(body: Vec<u8>, raw_bytes: usize, capabilities: SyncCapabilities) -> Result<EncodedBody, String> {
+    if !capabilities.zstd || body.len() < MIN_COMPRESS_BYTES {
+        return Ok(EncodedBody { body, content_encoding: None, raw_bytes });
+    }
+    let compressed = zstd::encode_all(body.as_slice(), COMPRESSION_LEVEL).map_err(|e| e.to_string())?;
+    Ok(EncodedBody { body: compressed, content_encoding: Some("zstd"), raw_bytes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::client::IncidentIntel;
+
+    fn process(pid: u32, name: &str) -> IncidentProcess {
+        IncidentProcess {
+            pid,
+            name: name.to_string(),
+            exe_path: Some(format!("C:\\Windows\\System32\\{}", name)),
+            cmdline: Some(format!("{} /c whoami", name)),
+            sha256: Some("ab".repeat(32)),
+            md5: None,
+            signature: Some("trusted".to_string()),
+            publisher: Some("Microsoft Windows".to_string()),
+            exited: false,
+        }
+    }
+
+    fn incident(chain: Vec<IncidentProcess>) -> SyncIncidentRequest {
+        SyncIncidentRequest {
+            id: uuid::Uuid::new_v4(),
+            severity: "high".to_string(),
+            title: "Suspicious child process".to_string(),
+            description: None,
+            mitre_techniques: Some(vec!["T1059".to_string()]),
+            threat_class: Some("execution".to_string()),
+            confidence: Some(0.9),
+            created_at: 1_700_000_000,
+            intel: IncidentIntel { process_chain: chain, ..Default::default() },
+        }
+    }
+
+    /// Giải mã giống server: `process_refs` → `process_chain`
+    fn expand(body: &[u8], content_encoding: Option<&str>) -> Vec<Vec<u32>> {
+        let body = match content_encoding {
+            Some("zstd") => zstd::decode_all(body).unwrap(),
+            _ => body.to_vec(),
+        };
+        let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let processes = request["processes"].as_array().cloned().unwrap_or_default();
+        request["incidents"].as_array().unwrap().iter().map(|incident| {
+            let mut chain: Vec<serde_json::Value> = incident["process_chain"].as_array().unwrap().clone();
+            for index in incident["process_refs"].as_array().into_iter().flatten() {
+                chain.push(processes[index.as_u64().unwrap() as usize].clone());
+            }
+            chain.iter().map(|p| p["pid"].as_u64().unwrap() as u32).collect()
+        }).collect()
+    }
+
+    #[test]
+    fn test_delta_roundtrip_and_fallback() {
+        let shared = vec![process(100, "cmd.exe"), process(4, "explorer.exe")];
+        let incidents: Vec<SyncIncidentRequest> = (0..20)
+            .map(|i| {
+                let mut chain = vec![process(1000 + i, "powershell.exe")];
+                chain.extend(shared.clone());
+                incident(chain)
+            })
+            .collect();
+        let expected: Vec<Vec<u32>> = (0..20).map(|i| vec![1000 + i, 100, 4]).collect();
+
+        // Server cũ: JSON nguyên, không header
+        let plain = encode_incidents_with(&incidents, SyncCapabilities::default()).unwrap();
+        assert_eq!(plain.content_encoding, None);
+        assert_eq!(plain.body.len(), plain.raw_bytes);
+        assert_eq!(expand(&plain.body, None), expected);
+
+        // Delta: process chung chỉ gửi một lần
+        let delta = encode_incidents_with(&incidents, SyncCapabilities { zstd: false, incident_delta: true }).unwrap();
+        assert!(delta.body.len() < plain.body.len());
+        assert_eq!(expand(&delta.body, None), expected);
+
+        // Delta + zstd
+        let full = encode_incidents_with(&incidents, SyncCapabilities { zstd: true, incident_delta: true }).unwrap();
+        assert_eq!(full.content_encoding, Some("zstd"));
+        assert!(full.body.len() < delta.body.len());
+        assert_eq!(expand(&full.body, full.content_encoding), expected);
+    }
+
+    #[test]
+    fn test_no_repeats_sends_plain_json() {
+        let incidents = vec![incident(vec![process(1, "a.exe")]), incident(vec![process(2, "b.exe")])];
+        let encoded = encode_incidents_with(&incidents, SyncCapabilities { zstd: false, incident_delta: true }).unwrap();
+        // Không lặp → giữ nguyên định dạng cũ
+        assert_eq!(encoded.body.len(), encoded.raw_bytes);
+        let request: serde_json::Value = serde_json::from_slice(&encoded.body).unwrap();
+        assert!(request.get("processes").is_none());
+        assert_eq!(expand(&encoded.body, None), vec![vec![1], vec![2]]);
+
+        assert_eq!(SyncCapabilities::from_list(&["gzip".to_string(), "zstd".to_string()]),
+            SyncCapabilities { zstd: true, incident_delta: false });
+    }
+}
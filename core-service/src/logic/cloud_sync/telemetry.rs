@@ -0,0 +1,467 @@
+//! Cloud Telemetry - Gửi security event (đã sample) + engine stats lên cloud theo batch
+//!
+//! Console không chỉ thấy incident mà còn thấy hoạt động của từng endpoint:
+//! - Event severity >= `always_min_severity` luôn gửi, còn lại sample theo
+//!   `sample_rate` (quyết định theo hash event id → ổn định, không phụ thuộc thứ tự)
+//! - Engine stats (buffer, incident mở, inference latency, ...) chụp mỗi
+//!   `stats_interval_secs`, gửi kèm batch
+//! - Batch JSON nén zstd (`Content-Encoding: zstd`) → `/api/v1/agent/telemetry`
+//! - Queue có giới hạn (đầy → bỏ event cũ nhất); upload lỗi → trả lại đầu queue
+//! - Username / hostname / path trong profile được ẩn danh trước khi gửi (`telemetry::scrub`)
+//!
+//! Upload chạy trong sync loop sau mỗi heartbeat thành công.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::logic::incident::{self, IncidentStatus};
+use crate::logic::policy::Severity;
+use crate::logic::telemetry::SecurityEvent;
+use crate::logic::telemetry::scrub::{self, ScrubTarget};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CONFIG_FILE: &str = "cloud_telemetry.json";
+const MAX_QUEUE: usize = 10_000;
+const MAX_PENDING_STATS: usize = 120;
+/// Giới hạn server (`MAX_TELEMETRY_BATCH`)
+const MAX_BATCH_SIZE: usize = 1000;
+/// Số batch tối đa mỗi lần upload (không chặn sync loop quá lâu)
+const MAX_BATCHES_PER_UPLOAD: usize = 4;
+
+// ============================================================================
+// CONFIG
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CloudTelemetryConfig {
+    pub enabled: bool,
+    /// Tỉ lệ giữ event dưới `always_min_severity` (0.0 - 1.0)
+    pub sample_rate: f64,
+    /// Event có severity >= giá trị này luôn được gửi
+    pub always_min_severity: Severity,
+    pub include_engine_stats: bool,
+    pub stats_interval_secs: u64,
+    /// Số event tối đa mỗi request
+    pub batch_size: usize,
+    /// Mức nén zstd (1-19)
+    pub compression_level: i32,
+}
+
+impl Default for CloudTelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sample_rate: 0.1,
+            always_min_severity: Severity::High,
+            include_engine_stats: true,
+            stats_interval_secs: 60,
+            batch_size: 500,
+            compression_level: 3,
+        }
+    }
+}
+
+impl CloudTelemetryConfig {
+    fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.sample_rate) {
+            return Err("sample_rate must be between 0.0 and 1.0".to_string());
+        }
+        if self.batch_size == 0 || self.batch_size > MAX_BATCH_SIZE {
+            return Err(format!("batch_size must be between 1 and {}", MAX_BATCH_SIZE));
+        }
+        if self.stats_interval_secs < 10 {
+            return Err("stats_interval_secs must be at least 10".to_string());
+        }
+        if !(1..=19).contains(&self.compression_level) {
+            return Err("compression_level must be between 1 and 19".to_string());
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Snapshot trạng thái engine gửi lên console
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineStats {
+    pub timestamp: DateTime<Utc>,
+    pub events_recorded: u64,
+    /// Event ghi từ snapshot trước
+    pub events_since_last: u64,
+    pub incidents_open: usize,
+    pub pending_actions: usize,
+    pub process_event_buffer: usize,
+    pub pending_summaries: usize,
+    pub model_buffer: usize,
+    pub cloud_incidents_queued: usize,
+    pub inference_count: u64,
+    pub inference_avg_ms: f64,
+    /// Event bị sample bỏ (không gửi) từ snapshot trước
+    pub telemetry_sampled_out: u64,
+}
+
+/// Body gửi lên server (trước khi nén)
+#[derive(Debug, Serialize)]
+struct TelemetryBatch<'a> {
+    events: &'a [SecurityEvent],
+    stats: &'a [EngineStats],
+    /// Event bị sample bỏ kể từ batch trước
+    sampled_out: u64,
+}
+
+/// Batch đã nén, giữ lại nội dung để requeue khi upload lỗi
+pub struct PreparedBatch {
+    pub body: Vec<u8>,
+    pub raw_bytes: usize,
+    events: Vec<SecurityEvent>,
+    stats: Vec<EngineStats>,
+    sampled_out: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CloudTelemetryStatus {
+    pub enabled: bool,
+    pub queued_events: usize,
+    pub queued_stats: usize,
+    pub events_sent: u64,
+    pub stats_sent: u64,
+    pub batches_sent: u64,
+    /// Event không qua sample
+    pub sampled_out: u64,
+    /// Queue đầy → bỏ event cũ nhất
+    pub dropped: u64,
+    /// Tổng byte trước / sau nén đã gửi
+    pub bytes_raw: u64,
+    pub bytes_compressed: u64,
+    pub last_upload: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+struct UploadState {
+    events: VecDeque<SecurityEvent>,
+    stats: VecDeque<EngineStats>,
+    /// Sample bỏ từ batch trước (báo server để console ước lượng tổng)
+    sampled_out_pending: u64,
+    /// Sample bỏ từ snapshot stats trước
+    sampled_out_since_stats: u64,
+    last_stats_at: Option<DateTime<Utc>>,
+    last_events_recorded: u64,
+}
+
+impl UploadState {
+    fn new() -> Self {
+        Self {
+            events: VecDeque::new(),
+            stats: VecDeque::new(),
+            sampled_out_pending: 0,
+            sampled_out_since_stats: 0,
+            last_stats_at: None,
+            last_events_recorded: 0,
+        }
+    }
+
+    /// Sample + xếp hàng. Trả về (giữ lại, phải bỏ event cũ nhất)
+    fn offer(&mut self, event: &SecurityEvent, config: &CloudTelemetryConfig) -> (bool, bool) {
+        let keep = event.severity.map_or(false, |s| severity_rank(s) >= severity_rank(config.always_min_severity))
+            || sampled_in(&event.id, config.sample_rate);
+        if !keep {
+            self.sampled_out_pending += 1;
+            self.sampled_out_since_stats += 1;
+            return (false, false);
+        }
+        let dropped = self.events.len() >= MAX_QUEUE;
+        if dropped {
+            self.events.pop_front();
+        }
+        self.events.push_back(event.clone());
+        (true, dropped)
+    }
+
+    fn take(&mut self, batch_size: usize) -> (Vec<SecurityEvent>, Vec<EngineStats>, u64) {
+        let n = self.events.len().min(batch_size);
+        (
+            self.events.drain(..n).collect(),
+            self.stats.drain(..).collect(),
+            std::mem::take(&mut self.sampled_out_pending),
+        )
+    }
+
+    fn restore(&mut self, events: Vec<SecurityEvent>, stats: Vec<EngineStats>, sampled_out: u64) {
+        for event in events.into_iter().rev() {
+            if self.events.len() >= MAX_QUEUE {
+                break;
+            }
+            self.events.push_front(event);
+        }
+        for sample in stats.into_iter().rev() {
+            if self.stats.len() >= MAX_PENDING_STATS {
+                break;
+            }
+            self.stats.push_front(sample);
+        }
+        self.sampled_out_pending += sampled_out;
+    }
+}
+
+static CONFIG: Lazy<RwLock<CloudTelemetryConfig>> = Lazy::new(|| RwLock::new(load_config()));
+static STATE: Lazy<Mutex<UploadState>> = Lazy::new(|| Mutex::new(UploadState::new()));
+static STATUS: Lazy<Mutex<CloudTelemetryStatus>> = Lazy::new(|| Mutex::new(CloudTelemetryStatus::default()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Sample + đưa event vào queue upload (gọi từ `telemetry::record`)
+pub fn publish(event: &SecurityEvent) {
+    let config = CONFIG.read().clone();
+    if !config.enabled {
+        return;
+    }
+    let (kept, dropped) = STATE.lock().offer(event, &config);
+    let mut status = STATUS.lock();
+    if !kept {
+        status.sampled_out += 1;
+    }
+    if dropped {
+        status.dropped += 1;
+    }
+}
+
+/// Chụp engine stats nếu đến hạn (gọi trước khi upload)
+pub fn collect_stats() {
+    let config = CONFIG.read().clone();
+    if !config.enabled || !config.include_engine_stats {
+        return;
+    }
+    let now = Utc::now();
+    if let Some(last) = STATE.lock().last_stats_at {
+        if (now - last).num_seconds() < config.stats_interval_secs as i64 {
+            return;
+        }
+    }
+
+    // Đọc trạng thái engine ngoài lock (các module có thể ghi telemetry → `publish`)
+    let recorded = crate::logic::telemetry::events_recorded();
+    let (process_events, pending_summaries) = crate::logic::collector::buffer_sizes();
+    let (inference_count, inference_sum_us) = crate::logic::telemetry::metrics::inference_totals();
+    let incidents_open = incident::get_incidents().iter().filter(|i| i.status == IncidentStatus::Open).count();
+    let pending_actions = crate::logic::action_guard::get_pending_actions().len();
+    let model_buffer = crate::logic::model::buffer::buffer_size();
+    let cloud_incidents_queued = super::sync::pending_incidents_count();
+
+    let mut state = STATE.lock();
+    let stats = EngineStats {
+        timestamp: now,
+        events_recorded: recorded,
+        events_since_last: recorded.saturating_sub(state.last_events_recorded),
+        incidents_open,
+        pending_actions,
+        process_event_buffer: process_events,
+        pending_summaries,
+        model_buffer,
+        cloud_incidents_queued,
+        inference_count,
+        inference_avg_ms: if inference_count == 0 { 0.0 } else { inference_sum_us as f64 / inference_count as f64 / 1000.0 },
+        telemetry_sampled_out: state.sampled_out_since_stats,
+    };
+
+    state.last_stats_at = Some(now);
+    state.last_events_recorded = recorded;
+    state.sampled_out_since_stats = 0;
+    if state.stats.len() >= MAX_PENDING_STATS {
+        state.stats.pop_front();
+    }
+    state.stats.push_back(stats);
+}
+
+/// Lấy batch kế tiếp và nén (None nếu không có gì để gửi)
+pub fn take_batch() -> Result<Option<PreparedBatch>, String> {
+    let config = CONFIG.read().clone();
+    if !config.enabled {
+        return Ok(None);
+    }
+    let (events, stats, sampled_out) = STATE.lock().take(config.batch_size);
+    if events.is_empty() && stats.is_empty() {
+        STATE.lock().sampled_out_pending += sampled_out;
+        return Ok(None);
+    }
+    // Queue giữ bản gốc (requeue khi lỗi), body gửi đi thì ẩn danh PII
+    let encoded = if scrub::enabled(ScrubTarget::CloudUpload) {
+        let scrubbed: Vec<SecurityEvent> = events.iter().map(scrub::scrub_event).collect();
+        encode(&scrubbed, &stats, sampled_out, config.compression_level)
+    } else {
+        encode(&events, &stats, sampled_out, config.compression_level)
+    };
+    match encoded {
+        Ok((body, raw_bytes)) => Ok(Some(PreparedBatch { body, raw_bytes, events, stats, sampled_out })),
+        Err(e) => {
+            STATE.lock().restore(events, stats, sampled_out);
+            Err(e)
+        }
+    }
+}
+
+/// Server nhận batch
+pub fn mark_sent(batch: &PreparedBatch) {
+    let mut status = STATUS.lock();
+    status.events_sent += batch.events.len() as u64;
+    status.stats_sent += batch.stats.len() as u64;
+    status.batches_sent += 1;
+    status.bytes_raw += batch.raw_bytes as u64;
+    status.bytes_compressed += batch.body.len() as u64;
+    status.last_upload = Some(Utc::now());
+    status.last_error = None;
+}
+
+/// Upload lỗi → trả lại đầu queue
+pub fn requeue(batch: PreparedBatch, error: String) {
+    STATUS.lock().last_error = Some(error);
+    STATE.lock().restore(batch.events, batch.stats, batch.sampled_out);
+}
+
+/// Số batch tối đa mỗi lần upload
+pub fn max_batches_per_upload() -> usize {
+    MAX_BATCHES_PER_UPLOAD
+}
+
+pub fn get_config() -> CloudTelemetryConfig {
+    CONFIG.read().clone()
+}
+
+pub fn set_config(config: CloudTelemetryConfig) -> Result<(), String> {
+    config.validate()?;
+    if !config.enabled {
+        let mut state = STATE.lock();
+        state.events.clear();
+        state.stats.clear();
+        state.sampled_out_pending = 0;
+    }
+    *CONFIG.write() = config;
+    save_config();
+    Ok(())
+}
+
+pub fn get_status() -> CloudTelemetryStatus {
+    let mut status = STATUS.lock().clone();
+    status.enabled = CONFIG.read().enabled;
+    let state = STATE.lock();
+    status.queued_events = state.events.len();
+    status.queued_stats = state.stats.len();
+    status
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// JSON → zstd. Trả về (body nén, số byte trước nén)
+fn encode(events: &[SecurityEvent], stats: &[EngineStats], sampled_out: u64, level: i32) -> Result<(Vec<u8>, usize), String> {
+    let raw = serde_json::to_vec(&TelemetryBatch { events, stats, sampled_out })
+        .map_err(|e| e.to_string())?;
+    let body = zstd::encode_all(raw.as_slice(), level).map_err(|e| e.to_string())?;
+    Ok((body, raw.len()))
+}
+
+/// Giữ event theo hash id (cùng event → cùng quyết định)
+pub(super) fn sampled_in(id: &str, rate: f64) -> bool {
+    if rate >= 1.0 {
+        return true;
+    }
+    if rate <= 0.0 {
+        return false;
+    }
+    let mut hasher = DefaultHasher::new();
+    id.hash(&mut hasher);
+    (hasher.finish() % 10_000) < (rate * 10_000.0) as u64
+}
+
+fn severity_rank(severity: Severity) -> u8 {
+    match severity {
+        Severity::Low => 0,
+        Severity::Medium => 1,
+        Severity::High => 2,
+        Severity::Critical => 3,
+    }
+}
+
+fn config_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(CONFIG_FILE)
+}
+
+fn load_config() -> CloudTelemetryConfig {
+    fs::read_to_string(config_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_config() {
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*CONFIG.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::telemetry::EventType;
+
+    #[test]
+    fn test_sampling_and_batch_roundtrip() {
+        // Quyết định sample ổn định theo id
+        let kept = (0..10_000).filter(|i| sampled_in(&format!("event-{}", i), 0.25)).count();
+        assert!((2000..3000).contains(&kept), "kept {}", kept);
+        assert_eq!(sampled_in("abc", 0.5), sampled_in("abc", 0.5));
+        assert!(!sampled_in("abc", 0.0));
+
+        let config = CloudTelemetryConfig { sample_rate: 0.0, ..Default::default() };
+        let mut state = UploadState::new();
+        assert_eq!(state.offer(&SecurityEvent::new(EventType::SystemStart, "dropped by sampling"), &config), (false, false));
+        for i in 0..3 {
+            let event = SecurityEvent::new(EventType::ThreatDetected, &format!("Threat {}", i))
+                .with_severity(Severity::Critical);
+            assert_eq!(state.offer(&event, &config), (true, false));
+        }
+
+        let (events, stats, sampled_out) = state.take(2);
+        assert_eq!((events.len(), stats.len(), sampled_out), (2, 0, 1));
+        let (body, raw_bytes) = encode(&events, &stats, sampled_out, 3).unwrap();
+        let raw = zstd::decode_all(body.as_slice()).unwrap();
+        assert_eq!(raw.len(), raw_bytes);
+        let json: serde_json::Value = serde_json::from_slice(&raw).unwrap();
+        assert_eq!(json["events"][0]["description"], "Threat 0");
+        assert_eq!(json["sampled_out"], 1);
+
+        // Upload lỗi → event quay lại đầu queue
+        state.restore(events, stats, sampled_out);
+        let (events, _, sampled_out) = state.take(2);
+        assert_eq!(events[0].description, "Threat 0");
+        assert_eq!(sampled_out, 1);
+        let (events, _, sampled_out) = state.take(2);
+        assert_eq!((events.len(), sampled_out), (1, 0));
+    }
+}
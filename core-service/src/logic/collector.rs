@@ -345,6 +345,13 @@ fn collect_process_events() -> Result<(), CollectorError> {
         let disk_read = disk_usage.read_bytes;
         let disk_write = disk_usage.written_bytes;
 
+        // Process mới → static pass cho executable chưa từng thấy
+        if !history.contains_key(&pid_u32) {
+            if let Some(exe) = process.exe() {
+                crate::logic::advanced_detection::pe_static::submit(exe);
+            }
+        }
+
         // Get/update history
         let hist = history.entry(pid_u32).or_insert_with(|| ProcessHistory {
             first_seen: timestamp,
@@ -657,6 +664,13 @@ pub fn get_pending_summaries() -> Vec<SummaryVector> {
     queue.iter().filter(|s| !s.processed).cloned().collect()
 }
 
+/// (process events buffered, summaries chưa xử lý) - metrics endpoint
+pub fn buffer_sizes() -> (usize, usize) {
+    let events = PROCESS_EVENTS_BUFFER.read().len();
+    let pending = SUMMARY_QUEUE.read().iter().filter(|s| !s.processed).count();
+    (events, pending)
+}
+
 /// Get ALL summaries (for training data export)
 pub fn get_all_summaries() -> Vec<SummaryVector> {
     let queue = SUMMARY_QUEUE.read();
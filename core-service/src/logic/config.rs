@@ -1,6 +1,10 @@
 #![allow(dead_code)]
 
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
+use once_cell::sync::Lazy;
+
+const SAFETY_FILE: &str = "safety.json";
 
 // FREEZE CORE: Safety Configuration (Kill-switches)
 // Default state: All systems nominal (Enabled)
@@ -8,6 +12,8 @@ static AI_ENABLED: AtomicBool = AtomicBool::new(true);
 static EXPLAIN_ENABLED: AtomicBool = AtomicBool::new(true);
 static AUTO_BLOCK_ENABLED: AtomicBool = AtomicBool::new(true);
 static REALTIME_LEARNING: AtomicBool = AtomicBool::new(true);
+// Detect-only: response tự động chỉ được ghi lại, không thực thi (persist qua restart)
+static DRY_RUN: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(load_dry_run()));
 
 pub struct SafetyConfig;
 
@@ -28,9 +34,43 @@ impl SafetyConfig {
         REALTIME_LEARNING.load(Ordering::Relaxed)
     }
 
+    pub fn is_dry_run() -> bool {
+        DRY_RUN.load(Ordering::Relaxed)
+    }
+
     // Setters (e.g. from Emergency UI or Panic Handler)
     pub fn set_ai(val: bool) { AI_ENABLED.store(val, Ordering::Relaxed); }
     pub fn set_explain(val: bool) { EXPLAIN_ENABLED.store(val, Ordering::Relaxed); }
     pub fn set_auto_block(val: bool) { AUTO_BLOCK_ENABLED.store(val, Ordering::Relaxed); }
     pub fn set_learning(val: bool) { REALTIME_LEARNING.store(val, Ordering::Relaxed); }
+    pub fn set_dry_run(val: bool) { DRY_RUN.store(val, Ordering::Relaxed); }
+
+    /// Dry-run do user đặt: lưu ra disk để còn hiệu lực sau restart
+    /// (override từ cloud dùng `set_dry_run`, không ghi đè giá trị local)
+    pub fn persist_dry_run(val: bool) {
+        Self::set_dry_run(val);
+        let path = safety_path();
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let json = serde_json::json!({ "dry_run": val });
+        if let Err(e) = std::fs::write(&path, json.to_string()) {
+            log::warn!("Cannot persist dry-run mode to {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn safety_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(SAFETY_FILE)
+}
+
+fn load_dry_run() -> bool {
+    std::fs::read_to_string(safety_path())
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .and_then(|v| v.get("dry_run").and_then(|d| d.as_bool()))
+        .unwrap_or(false)
 }
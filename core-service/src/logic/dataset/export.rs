@@ -1,8 +1,14 @@
+use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{self, Write};
 use crate::logic::dataset::get_dataset_dir;
+use crate::logic::dataset::labeling;
+use crate::logic::dataset::record::DatasetRecord;
+use crate::logic::telemetry::scrub::{self, ScrubTarget};
 
 /// Export all dataset files to a single JSONL file
+/// Labels from the labeling workflow are applied as `user_label`
+/// Free-text `user_label` is scrubbed of PII when `scrub.file_exports` is on
 /// Returns the number of source files merged
 pub fn to_jsonl(target_path: &str) -> io::Result<usize> {
     let source_dir = get_dataset_dir();
@@ -11,6 +17,9 @@ pub fn to_jsonl(target_path: &str) -> io::Result<usize> {
         return Err(io::Error::new(io::ErrorKind::NotFound, "Dataset directory not found"));
     }
 
+    let labels = labeling::overlay_labels(&source_dir);
+    let scrub = scrub::enabled(ScrubTarget::FileExport);
+
     // Create target file (truncate if exists)
     let mut output_file = File::create(target_path)?;
     let mut file_count = 0;
@@ -27,7 +36,11 @@ pub fn to_jsonl(target_path: &str) -> io::Result<usize> {
 
     for path in paths {
         // Read file content and append to output
-        let content = fs::read(&path)?;
+        let mut content = fs::read(&path)?;
+        if !labels.is_empty() || scrub {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            content = apply_labels(name, &content, &labels, scrub);
+        }
         output_file.write_all(&content)?;
 
         // Ensure newline between files if not present (simple check)
@@ -44,3 +57,29 @@ pub fn to_jsonl(target_path: &str) -> io::Result<usize> {
     log::info!("Exported {} dataset files to {}", file_count, target_path);
     Ok(file_count)
 }
+
+/// Set `user_label` on records relabeled through the labeling workflow
+/// (and scrub existing free-text labels if `scrub`)
+fn apply_labels(file_name: &str, content: &[u8], labels: &HashMap<String, String>, scrub: bool) -> Vec<u8> {
+    let text = String::from_utf8_lossy(content);
+    let mut out = Vec::with_capacity(content.len());
+    for (i, line) in text.lines().enumerate() {
+        let overlay = labels.get(&labeling::record_id(file_name, i + 1));
+        let has_label = line.contains("\"user_label\":\"");
+        let labeled = (overlay.is_some() || (scrub && has_label))
+            .then(|| {
+                let mut record: DatasetRecord = serde_json::from_str(line).ok()?;
+                if let Some(label) = overlay {
+                    record.user_label = Some(label.clone());
+                }
+                if scrub {
+                    record.user_label = record.user_label.map(|l| scrub::scrub_text(&l));
+                }
+                serde_json::to_string(&record).ok()
+            })
+            .flatten();
+        out.extend_from_slice(labeled.as_deref().unwrap_or(line).as_bytes());
+        out.push(b'\n');
+    }
+    out
+}
@@ -0,0 +1,473 @@
+//! Labeling Workflow - Human-in-the-loop gán nhãn cho dataset (P2.2.3+)
+//!
+//! File dataset là append-only nên nhãn được lưu riêng (overlay) trong `labels.json`:
+//! - Record id = `<tên file>:<số dòng>` (ổn định vì file chỉ append, không sửa)
+//! - Nhãn hiệu lực = overlay > `user_label` ghi sẵn trong record (`override_label`)
+//! - `export::to_jsonl` áp overlay vào `user_label` → trainer đọc như cũ
+//!
+//! Hàng đợi gán nhãn mặc định sắp theo confidence thấp nhất trước
+//! (record model kém chắc chắn nhất có giá trị nhất khi được gán nhãn).
+//!
+//! Verdict của incident (confirm / dismiss, local hoặc từ cloud console) được lan sang
+//! các record bị flag trong khoảng thời gian của incident (`label_incident_records`).
+//! Nhãn gán tay từng record luôn được ưu tiên, không bị verdict ghi đè.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::threat::ThreatClass;
+use super::get_dataset_dir;
+use super::record::DatasetRecord;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+pub const LABELS_FILE: &str = "labels.json";
+/// Record dưới ngưỡng này được coi là low-confidence
+pub const LOW_CONFIDENCE: f32 = 0.6;
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+const MAX_LABEL_LEN: usize = 64;
+
+/// Chỉ 1 lần ghi `labels.json` tại một thời điểm
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelEntry {
+    pub label: String,
+    /// Unix millis
+    pub labeled_at: u64,
+    /// Gán qua bulk-label (không review từng record)
+    pub bulk: bool,
+    /// Nhãn lan từ verdict của incident này
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub incident: Option<String>,
+}
+
+impl LabelEntry {
+    /// Nhãn người gán cho riêng record này (không phải bulk / verdict incident)
+    fn is_manual(&self) -> bool {
+        !self.bulk && self.incident.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LabelFilter {
+    /// Chỉ record chưa có nhãn
+    pub unlabeled_only: bool,
+    /// Chỉ record có confidence < giá trị này
+    pub max_confidence: Option<f32>,
+    /// Quyết định của model
+    pub threat: Option<ThreatClass>,
+    /// Nhãn hiệu lực hiện tại (không phân biệt hoa thường) - review / sửa nhãn
+    pub label: Option<String>,
+    /// Khoảng thời gian (Unix millis)
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+impl LabelFilter {
+    fn is_empty(&self) -> bool {
+        !self.unlabeled_only
+            && self.max_confidence.is_none()
+            && self.threat.is_none()
+            && self.label.is_none()
+            && self.from.is_none()
+            && self.to.is_none()
+    }
+
+    fn matches(&self, record: &DatasetRecord, label: Option<&str>) -> bool {
+        if self.unlabeled_only && label.is_some() {
+            return false;
+        }
+        if self.max_confidence.map_or(false, |max| record.confidence >= max) {
+            return false;
+        }
+        if self.threat.map_or(false, |t| record.threat != t) {
+            return false;
+        }
+        if let Some(wanted) = &self.label {
+            if !label.map_or(false, |l| l.eq_ignore_ascii_case(wanted.trim())) {
+                return false;
+            }
+        }
+        if self.from.map_or(false, |from| record.timestamp < from) || self.to.map_or(false, |to| record.timestamp > to) {
+            return false;
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelOrder {
+    #[default]
+    LowestConfidence,
+    Newest,
+    Oldest,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LabelQuery {
+    #[serde(flatten)]
+    pub filter: LabelFilter,
+    pub order: LabelOrder,
+    pub offset: usize,
+    /// Mặc định 100, tối đa 1000
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LabelCandidate {
+    pub id: String,
+    /// Nhãn hiệu lực (None = chưa gán)
+    pub label: Option<String>,
+    pub record: DatasetRecord,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LabelPage {
+    pub items: Vec<LabelCandidate>,
+    pub total_matched: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LabelingProgress {
+    pub total_records: usize,
+    pub labeled: usize,
+    pub unlabeled: usize,
+    /// Chưa gán nhãn và confidence < `LOW_CONFIDENCE`
+    pub low_confidence_unlabeled: usize,
+    pub percent_labeled: f32,
+    pub by_label: BTreeMap<String, usize>,
+    pub unlabeled_by_threat: BTreeMap<String, usize>,
+    pub bulk_labeled: usize,
+    /// Nhãn lan từ verdict incident
+    pub incident_labeled: usize,
+    pub labeled_last_24h: usize,
+    /// Tỉ lệ nhãn trùng quyết định của model (None = chưa có nhãn)
+    pub model_agreement: Option<f32>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LabelStore {
+    labels: HashMap<String, LabelEntry>,
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Danh sách record cần gán nhãn (mặc định confidence thấp nhất trước)
+pub fn list_label_candidates(query: &LabelQuery) -> Result<LabelPage, String> {
+    list_candidates_in(&get_dataset_dir(), query)
+}
+
+/// Gán / sửa nhãn 1 record
+pub fn relabel_record(id: &str, label: &str) -> Result<(), String> {
+    relabel_in(&get_dataset_dir(), id, label)
+}
+
+/// Gán nhãn mọi record khớp filter. Trả về số record đã gán
+pub fn bulk_label(filter: &LabelFilter, label: &str) -> Result<usize, String> {
+    bulk_label_in(&get_dataset_dir(), filter, label)
+}
+
+pub fn labeling_progress() -> Result<LabelingProgress, String> {
+    progress_in(&get_dataset_dir())
+}
+
+/// Gán `label` cho record bị flag (không Benign) trong [from, to] (Unix millis) của incident.
+/// Trả về số record đã gán
+pub fn label_incident_records(incident_id: &str, from: u64, to: u64, label: &str) -> Result<usize, String> {
+    label_incident_in(&get_dataset_dir(), incident_id, from, to, label)
+}
+
+/// Xóa nhãn overlay của các file dataset đã bị retention xóa
+pub(crate) fn drop_labels_for_files(dir: &Path, files: &[String]) {
+    let _guard = STORE_LOCK.lock();
+    let mut store = load_store(dir);
+    let before = store.labels.len();
+    store.labels.retain(|id, _| {
+        id.rsplit_once(':').is_none_or(|(file, _)| !files.iter().any(|f| f == file))
+    });
+    if store.labels.len() != before {
+        if let Err(e) = save_store(dir, &store) {
+            log::warn!("Failed to drop labels of pruned dataset files: {}", e);
+        }
+    }
+}
+
+/// Record id → nhãn overlay (cho export)
+pub(crate) fn overlay_labels(dir: &Path) -> HashMap<String, String> {
+    load_store(dir)
+        .labels
+        .into_iter()
+        .map(|(id, entry)| (id, entry.label))
+        .collect()
+}
+
+pub(crate) fn record_id(file_name: &str, line: usize) -> String {
+    format!("{}:{}", file_name, line)
+}
+
+// ============================================================================
+// IMPLEMENTATION
+// ============================================================================
+
+pub(crate) fn list_candidates_in(dir: &Path, query: &LabelQuery) -> Result<LabelPage, String> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let store = load_store(dir);
+
+    let mut matched = Vec::new();
+    for_each_record(dir, |id, record| {
+        let label = effective_label(&store, &id, &record);
+        if query.filter.matches(&record, label.as_deref()) {
+            matched.push(LabelCandidate { id, label, record });
+        }
+    }).map_err(|e| e.to_string())?;
+
+    match query.order {
+        LabelOrder::LowestConfidence => matched.sort_by(|a, b| a.record.confidence.total_cmp(&b.record.confidence)),
+        LabelOrder::Newest => matched.sort_by(|a, b| b.record.timestamp.cmp(&a.record.timestamp)),
+        LabelOrder::Oldest => matched.sort_by(|a, b| a.record.timestamp.cmp(&b.record.timestamp)),
+    }
+
+    let total_matched = matched.len();
+    let items: Vec<LabelCandidate> = matched.into_iter().skip(query.offset).take(limit).collect();
+    Ok(LabelPage {
+        items,
+        total_matched,
+        offset: query.offset,
+        limit,
+        has_more: total_matched > query.offset + limit,
+    })
+}
+
+pub(crate) fn relabel_in(dir: &Path, id: &str, label: &str) -> Result<(), String> {
+    let label = validate_label(label)?;
+    if find_record(dir, id).map_err(|e| e.to_string())?.is_none() {
+        return Err(format!("Dataset record not found: {}", id));
+    }
+
+    let _guard = STORE_LOCK.lock();
+    let mut store = load_store(dir);
+    store.labels.insert(id.to_string(), LabelEntry { label, labeled_at: now_millis(), bulk: false, incident: None });
+    save_store(dir, &store)
+}
+
+pub(crate) fn bulk_label_in(dir: &Path, filter: &LabelFilter, label: &str) -> Result<usize, String> {
+    let label = validate_label(label)?;
+    if filter.is_empty() {
+        return Err("Bulk labeling requires at least one filter".to_string());
+    }
+
+    let _guard = STORE_LOCK.lock();
+    let mut store = load_store(dir);
+    let mut ids = Vec::new();
+    for_each_record(dir, |id, record| {
+        let current = effective_label(&store, &id, &record);
+        if filter.matches(&record, current.as_deref()) {
+            ids.push(id);
+        }
+    }).map_err(|e| e.to_string())?;
+
+    let labeled_at = now_millis();
+    for id in &ids {
+        store.labels.insert(id.clone(), LabelEntry { label: label.clone(), labeled_at, bulk: true, incident: None });
+    }
+    save_store(dir, &store)?;
+    log::info!("Bulk-labeled {} dataset records as '{}'", ids.len(), label);
+    Ok(ids.len())
+}
+
+pub(crate) fn label_incident_in(dir: &Path, incident_id: &str, from: u64, to: u64, label: &str) -> Result<usize, String> {
+    let label = validate_label(label)?;
+    if from > to {
+        return Err("Invalid incident time window".to_string());
+    }
+
+    let _guard = STORE_LOCK.lock();
+    let mut store = load_store(dir);
+    let labeled_at = now_millis();
+    let mut labeled = 0usize;
+    let mut kept_manual = 0usize;
+    for_each_record(dir, |id, record| {
+        if record.threat == ThreatClass::Benign || record.timestamp < from || record.timestamp > to {
+            return;
+        }
+        if store.labels.get(&id).is_some_and(LabelEntry::is_manual) {
+            kept_manual += 1;
+            return;
+        }
+        store.labels.insert(id, LabelEntry {
+            label: label.clone(),
+            labeled_at,
+            bulk: false,
+            incident: Some(incident_id.to_string()),
+        });
+        labeled += 1;
+    }).map_err(|e| e.to_string())?;
+
+    if labeled > 0 {
+        save_store(dir, &store)?;
+    }
+    log::info!(
+        "Incident {} verdict labeled {} dataset records as '{}' ({} manual labels kept)",
+        incident_id, labeled, label, kept_manual
+    );
+    Ok(labeled)
+}
+
+pub(crate) fn progress_in(dir: &Path) -> Result<LabelingProgress, String> {
+    let store = load_store(dir);
+    let day_ago = now_millis().saturating_sub(24 * 3600 * 1000);
+    let mut progress = LabelingProgress::default();
+    let mut agree = 0usize;
+
+    for_each_record(dir, |id, record| {
+        progress.total_records += 1;
+        match effective_label(&store, &id, &record) {
+            Some(label) => {
+                progress.labeled += 1;
+                if label.eq_ignore_ascii_case(record.threat.as_str()) {
+                    agree += 1;
+                }
+                *progress.by_label.entry(label).or_insert(0) += 1;
+                if let Some(entry) = store.labels.get(&id) {
+                    if entry.bulk {
+                        progress.bulk_labeled += 1;
+                    }
+                    if entry.incident.is_some() {
+                        progress.incident_labeled += 1;
+                    }
+                    if entry.labeled_at >= day_ago {
+                        progress.labeled_last_24h += 1;
+                    }
+                }
+            }
+            None => {
+                progress.unlabeled += 1;
+                if record.confidence < LOW_CONFIDENCE {
+                    progress.low_confidence_unlabeled += 1;
+                }
+                *progress.unlabeled_by_threat.entry(record.threat.as_str().to_string()).or_insert(0) += 1;
+            }
+        }
+    }).map_err(|e| e.to_string())?;
+
+    if progress.total_records > 0 {
+        progress.percent_labeled = progress.labeled as f32 * 100.0 / progress.total_records as f32;
+    }
+    if progress.labeled > 0 {
+        progress.model_agreement = Some(agree as f32 / progress.labeled as f32);
+    }
+    Ok(progress)
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+fn effective_label(store: &LabelStore, id: &str, record: &DatasetRecord) -> Option<String> {
+    store.labels.get(id)
+        .map(|e| e.label.clone())
+        .or_else(|| record.user_label.clone().filter(|l| !l.trim().is_empty()))
+}
+
+fn validate_label(label: &str) -> Result<String, String> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Err("Label must not be empty".to_string());
+    }
+    if label.len() > MAX_LABEL_LEN {
+        return Err(format!("Label must be at most {} characters", MAX_LABEL_LEN));
+    }
+    if !label.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | ' ')) {
+        return Err("Label may only contain letters, digits, '_', '-' and spaces".to_string());
+    }
+    Ok(label.to_string())
+}
+
+/// File dataset theo thứ tự tên (timestamp)
+pub(crate) fn dataset_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|r| r.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().map_or(false, |e| e == "jsonl"))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+pub(crate) fn for_each_record(dir: &Path, mut f: impl FnMut(String, DatasetRecord)) -> io::Result<()> {
+    for path in dataset_files(dir)? {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        let reader = BufReader::new(File::open(&path)?);
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Ok(record) = serde_json::from_str::<DatasetRecord>(&line) {
+                f(record_id(&name, i + 1), record);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn find_record(dir: &Path, id: &str) -> io::Result<Option<DatasetRecord>> {
+    let Some((file, line)) = id.rsplit_once(':') else { return Ok(None) };
+    let Ok(line) = line.parse::<usize>() else { return Ok(None) };
+    // Chỉ tên file trong thư mục dataset
+    if line == 0 || file.contains(['/', '\\']) || file.contains("..") || !file.ends_with(".jsonl") {
+        return Ok(None);
+    }
+    let path = dir.join(file);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let reader = BufReader::new(File::open(path)?);
+    match reader.lines().nth(line - 1) {
+        Some(text) => Ok(serde_json::from_str(&text?).ok()),
+        None => Ok(None),
+    }
+}
+
+fn load_store(dir: &Path) -> LabelStore {
+    fs::read_to_string(dir.join(LABELS_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_store(dir: &Path, store: &LabelStore) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string(store).map_err(|e| e.to_string())?;
+    // Ghi file tạm rồi rename → không mất nhãn nếu crash giữa chừng
+    let tmp = dir.join(format!("{}.tmp", LABELS_FILE));
+    fs::write(&tmp, json).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, dir.join(LABELS_FILE)).map_err(|e| e.to_string())
+}
+
+fn now_millis() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
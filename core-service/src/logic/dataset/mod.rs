@@ -2,10 +2,18 @@
 //!
 //! Records high-quality, versioned feature vectors and decisions for offline AI training.
 //! Stores data in JSONL format with automatic rotation.
+//! Human labels live in an overlay next to the data files (`labeling.rs`).
+//! Deduplicated / class-balanced exports are built in `sampling.rs`.
+//! Size / age caps with oldest-first pruning live in `retention.rs`.
+//! Quality statistics (class balance, feature distribution, duplicates) are in `report.rs`.
 
 pub mod record;
 pub mod writer;
 pub mod export;
+pub mod labeling;
+pub mod sampling;
+pub mod retention;
+pub mod report;
 
 #[cfg(test)]
 mod tests;
@@ -39,6 +47,7 @@ pub fn init() {
     if writer.is_none() {
         *writer = Some(DatasetWriter::new());
         log::info!("Dataset logging initialized");
+        retention::schedule_cleanup();
     }
 }
 
@@ -50,13 +59,17 @@ pub fn log(record: DatasetRecord) {
     // Lazy init if needed
     if guard.is_none() {
         *guard = Some(DatasetWriter::new());
+        retention::schedule_cleanup();
     }
 
     if let Some(w) = guard.as_ref() {
         if let Err(e) = w.append(&record) {
             log::error!("Failed to append to dataset: {}", e);
         } else {
-            TOTAL_RECORDS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let total = TOTAL_RECORDS.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+            if total % retention::CLEANUP_EVERY_RECORDS == 0 {
+                retention::schedule_cleanup();
+            }
 
             match record.threat {
                 ThreatClass::Benign => BENIGN_COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
@@ -74,6 +87,7 @@ pub fn get_status() -> crate::api::engine_status::DatasetStatus {
     let benign = BENIGN_COUNT.load(std::sync::atomic::Ordering::Relaxed);
     let suspicious = SUSPICIOUS_COUNT.load(std::sync::atomic::Ordering::Relaxed);
     let malicious = MALICIOUS_COUNT.load(std::sync::atomic::Ordering::Relaxed);
+    let (pruned_files, pruned_records) = retention::pruned_totals();
 
     if let Some(writer) = guard.as_ref() {
         if let Ok((count, size, current)) = writer.get_stats() {
@@ -85,6 +99,8 @@ pub fn get_status() -> crate::api::engine_status::DatasetStatus {
                 benign_count: benign,
                 suspicious_count: suspicious,
                 malicious_count: malicious,
+                pruned_files,
+                pruned_records,
             };
         }
     }
@@ -96,5 +112,7 @@ pub fn get_status() -> crate::api::engine_status::DatasetStatus {
         benign_count: 0,
         suspicious_count: 0,
         malicious_count: 0,
+        pruned_files,
+        pruned_records,
     }
 }
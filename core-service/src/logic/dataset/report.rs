@@ -0,0 +1,283 @@
+//! Dataset Report - Thống kê chất lượng dataset trước khi export (P2.2+)
+//!
+//! ML engineer cần biết dữ liệu đã thu có dùng được không trước khi export / train:
+//! - Cân bằng lớp: theo quyết định model và theo nhãn hiệu lực (overlay > `user_label` > model)
+//! - Phân bố từng feature (mean / std / min / max / tỉ lệ 0) theo layout
+//! - Độ phủ nhãn (tái dùng `labeling::progress_in`)
+//! - Số record theo feature version / layout hash (layout cũ không train chung được)
+//! - Tỉ lệ trùng lặp: trùng tuyệt đối + gần trùng (cùng LSH với export có chọn lọc)
+//!
+//! `warnings` tóm tắt các vấn đề rõ ràng (lệch lớp nặng, ít nhãn, feature hằng, ...).
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use serde::Serialize;
+
+use crate::logic::features::layout::{layout_hash, FEATURE_LAYOUT};
+use super::get_dataset_dir;
+use super::labeling::{self, LabelingProgress};
+use super::record::DatasetRecord;
+use super::sampling::{self, LshHasher};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Lớp lớn nhất / lớp nhỏ nhất vượt ngưỡng này → cảnh báo lệch lớp
+const IMBALANCE_WARN_RATIO: f32 = 100.0;
+/// Tỉ lệ record có nhãn dưới ngưỡng này → cảnh báo
+const LABEL_COVERAGE_WARN_PERCENT: f32 = 5.0;
+/// Tỉ lệ gần trùng vượt ngưỡng này → khuyên export có dedup
+const NEAR_DUPLICATE_WARN_RATE: f32 = 0.5;
+const REPORT_LSH_BITS: u8 = 16;
+const REPORT_LSH_SEED: u64 = 0x5EED;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DatasetReport {
+    pub files: usize,
+    pub total_bytes: u64,
+    pub total_records: usize,
+    /// Unix millis của record đầu / cuối
+    pub first_timestamp: Option<u64>,
+    pub last_timestamp: Option<u64>,
+    pub class_balance: ClassBalance,
+    pub labels: LabelingProgress,
+    pub layouts: Vec<LayoutReport>,
+    pub duplicates: DuplicateStats,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ClassBalance {
+    /// Theo quyết định của model
+    pub by_decision: BTreeMap<String, usize>,
+    /// Theo lớp huấn luyện (nhãn người gán nếu có, ngược lại quyết định model)
+    pub by_training_class: BTreeMap<String, usize>,
+    /// Lớp huấn luyện lớn nhất / nhỏ nhất (None nếu < 2 lớp)
+    pub imbalance_ratio: Option<f32>,
+}
+
+/// Record của một feature layout
+#[derive(Debug, Clone, Serialize)]
+pub struct LayoutReport {
+    pub feature_version: u8,
+    pub layout_hash: u32,
+    /// Layout của agent hiện tại
+    pub current: bool,
+    pub records: usize,
+    pub features: Vec<FeatureStats>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FeatureStats {
+    pub index: usize,
+    /// Tên theo `FEATURE_LAYOUT` nếu là layout hiện tại, ngược lại `f<index>`
+    pub name: String,
+    pub count: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Tỉ lệ giá trị bằng 0
+    pub zero_fraction: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DuplicateStats {
+    /// Record trùng tuyệt đối (features + baseline_diff) với record trước đó
+    pub exact: usize,
+    pub exact_rate: f32,
+    /// Record gần trùng (cùng bucket LSH, cùng lớp) - gồm cả trùng tuyệt đối
+    pub near: usize,
+    pub near_rate: f32,
+}
+
+/// Welford online mean / variance cho một feature
+#[derive(Default)]
+struct Accumulator {
+    count: usize,
+    mean: f64,
+    m2: f64,
+    min: f64,
+    max: f64,
+    zeros: usize,
+}
+
+impl Accumulator {
+    fn push(&mut self, value: f32) {
+        let value = value as f64;
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        if value == 0.0 {
+            self.zeros += 1;
+        }
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn finish(&self, index: usize, name: String) -> FeatureStats {
+        FeatureStats {
+            index,
+            name,
+            count: self.count,
+            mean: self.mean,
+            std_dev: if self.count > 1 { (self.m2 / (self.count - 1) as f64).sqrt() } else { 0.0 },
+            min: self.min,
+            max: self.max,
+            zero_fraction: if self.count > 0 { self.zeros as f64 / self.count as f64 } else { 0.0 },
+        }
+    }
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Báo cáo chất lượng toàn bộ dataset local
+pub fn get_report() -> Result<DatasetReport, String> {
+    report_in(&get_dataset_dir())
+}
+
+// ============================================================================
+// IMPLEMENTATION
+// ============================================================================
+
+pub(crate) fn report_in(dir: &Path) -> Result<DatasetReport, String> {
+    let mut report = DatasetReport::default();
+    for path in labeling::dataset_files(dir).map_err(|e| e.to_string())? {
+        report.files += 1;
+        report.total_bytes += fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    }
+
+    let overlay = labeling::overlay_labels(dir);
+    let mut layouts: BTreeMap<(u8, u32), (usize, Vec<Accumulator>)> = BTreeMap::new();
+    let mut exact_seen: HashSet<u64> = HashSet::new();
+    let mut near_seen: HashSet<(String, u64, i32)> = HashSet::new();
+    let mut lsh = LshHasher::new(REPORT_LSH_BITS, REPORT_LSH_SEED);
+
+    labeling::for_each_record(dir, |id, record| {
+        report.total_records += 1;
+        report.first_timestamp = Some(report.first_timestamp.map_or(record.timestamp, |t| t.min(record.timestamp)));
+        report.last_timestamp = Some(report.last_timestamp.map_or(record.timestamp, |t| t.max(record.timestamp)));
+
+        let class = sampling::class_of(&record, overlay.get(&id));
+        *report.class_balance.by_decision.entry(record.threat.as_str().to_string()).or_insert(0) += 1;
+        *report.class_balance.by_training_class.entry(class.clone()).or_insert(0) += 1;
+
+        let (records, accumulators) = layouts.entry((record.feature_version, record.layout_hash)).or_default();
+        *records += 1;
+        if accumulators.len() < record.features.len() {
+            accumulators.resize_with(record.features.len(), Accumulator::default);
+        }
+        for (acc, value) in accumulators.iter_mut().zip(&record.features) {
+            acc.push(*value);
+        }
+
+        if !exact_seen.insert(exact_hash(&record)) {
+            report.duplicates.exact += 1;
+        }
+        let (signature, norm_bucket) = lsh.signature(&record);
+        if !near_seen.insert((class, signature, norm_bucket)) {
+            report.duplicates.near += 1;
+        }
+    }).map_err(|e| e.to_string())?;
+
+    report.labels = labeling::progress_in(dir)?;
+
+    let current_hash = layout_hash();
+    report.layouts = layouts
+        .into_iter()
+        .map(|((feature_version, hash), (records, accumulators))| {
+            let current = hash == current_hash;
+            let features = accumulators
+                .iter()
+                .enumerate()
+                .map(|(i, acc)| {
+                    let name = FEATURE_LAYOUT.get(i).filter(|_| current).map_or_else(|| format!("f{}", i), |n| n.to_string());
+                    acc.finish(i, name)
+                })
+                .collect();
+            LayoutReport { feature_version, layout_hash: hash, current, records, features }
+        })
+        .collect();
+
+    let counts = &report.class_balance.by_training_class;
+    if counts.len() >= 2 {
+        let max = counts.values().copied().max().unwrap_or(0);
+        let min = counts.values().copied().min().unwrap_or(0).max(1);
+        report.class_balance.imbalance_ratio = Some(max as f32 / min as f32);
+    }
+    if report.total_records > 0 {
+        report.duplicates.exact_rate = report.duplicates.exact as f32 / report.total_records as f32;
+        report.duplicates.near_rate = report.duplicates.near as f32 / report.total_records as f32;
+    }
+
+    report.warnings = warnings(&report);
+    Ok(report)
+}
+
+fn warnings(report: &DatasetReport) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if report.total_records == 0 {
+        warnings.push("Dataset is empty".to_string());
+        return warnings;
+    }
+
+    let classes = &report.class_balance.by_training_class;
+    if classes.len() < 2 {
+        warnings.push("Only one training class present - a classifier cannot be trained".to_string());
+    }
+    if let Some(ratio) = report.class_balance.imbalance_ratio.filter(|r| *r > IMBALANCE_WARN_RATIO) {
+        warnings.push(format!("Severe class imbalance ({:.0}:1) - export with balancing", ratio));
+    }
+    if report.labels.percent_labeled < LABEL_COVERAGE_WARN_PERCENT {
+        warnings.push(format!("Only {:.1}% of records are labeled", report.labels.percent_labeled));
+    }
+    if report.layouts.len() > 1 {
+        warnings.push(format!(
+            "{} feature layouts present - records of different layouts cannot be trained together",
+            report.layouts.len()
+        ));
+    }
+    for layout in &report.layouts {
+        let constant: Vec<&str> = layout.features.iter()
+            .filter(|f| f.count > 1 && f.std_dev == 0.0)
+            .map(|f| f.name.as_str())
+            .collect();
+        if !constant.is_empty() {
+            warnings.push(format!("Constant features in layout v{}: {}", layout.feature_version, constant.join(", ")));
+        }
+    }
+    if report.duplicates.near_rate > NEAR_DUPLICATE_WARN_RATE {
+        warnings.push(format!(
+            "{:.0}% of records are near-duplicates - export with dedup",
+            report.duplicates.near_rate * 100.0
+        ));
+    }
+    warnings
+}
+
+/// Hash chính xác của vector (so bit, không so gần đúng)
+fn exact_hash(record: &DatasetRecord) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    record.feature_version.hash(&mut hasher);
+    record.features.len().hash(&mut hasher);
+    for value in record.features.iter().chain(&record.baseline_diff) {
+        value.to_bits().hash(&mut hasher);
+    }
+    hasher.finish()
+}
@@ -0,0 +1,224 @@
+//! Dataset Retention - Giới hạn dung lượng / tuổi của dataset
+//!
+//! Writer chỉ rotate (10 MB / file) và không bao giờ xóa → dataset lớn mãi.
+//! Cleanup xóa file cũ nhất trước:
+//! - File cũ hơn `max_age_days` (theo mtime = lần ghi cuối)
+//! - Sau đó xóa tiếp tới khi tổng dung lượng <= `max_total_mb`
+//!
+//! File mới nhất (writer đang ghi) không bao giờ bị xóa. Nhãn overlay của file
+//! bị xóa cũng được dọn khỏi `labels.json`. Số file / record đã xóa hiện trong `DatasetStatus`.
+//!
+//! Cleanup chạy nền khi writer khởi tạo và sau mỗi `CLEANUP_EVERY_RECORDS` record.
+//! Config local (`dataset_retention.json`).
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use super::get_dataset_dir;
+use super::labeling;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CONFIG_FILE: &str = "dataset_retention.json";
+/// Chạy cleanup nền sau mỗi N record ghi
+pub(super) const CLEANUP_EVERY_RECORDS: u64 = 1000;
+const MB: u64 = 1024 * 1024;
+
+// ============================================================================
+// CONFIG
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatasetRetentionConfig {
+    /// Tổng dung lượng tối đa của dataset (0 = không giới hạn)
+    pub max_total_mb: u64,
+    /// Xóa file cũ hơn N ngày (0 = không giới hạn)
+    pub max_age_days: u64,
+}
+
+impl Default for DatasetRetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_total_mb: 500,
+            max_age_days: 180,
+        }
+    }
+}
+
+impl DatasetRetentionConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        // Nhỏ hơn 1 file rotate (10 MB) thì cap không có tác dụng
+        if self.max_total_mb != 0 && self.max_total_mb < 20 {
+            return Err("max_total_mb must be 0 (unlimited) or at least 20".to_string());
+        }
+        if self.max_age_days > 3650 {
+            return Err("max_age_days must be at most 3650".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Kết quả của một lần cleanup
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PruneResult {
+    pub files: u64,
+    pub records: u64,
+    pub bytes: u64,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static CONFIG: Lazy<RwLock<DatasetRetentionConfig>> = Lazy::new(|| RwLock::new(load_config()));
+/// Một cleanup tại một thời điểm
+static CLEANUP_LOCK: Mutex<()> = Mutex::new(());
+static CLEANUP_RUNNING: AtomicBool = AtomicBool::new(false);
+static PRUNED_FILES: AtomicU64 = AtomicU64::new(0);
+static PRUNED_RECORDS: AtomicU64 = AtomicU64::new(0);
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+pub fn get_config() -> DatasetRetentionConfig {
+    CONFIG.read().clone()
+}
+
+pub fn set_config(config: DatasetRetentionConfig) -> Result<(), String> {
+    config.validate()?;
+    *CONFIG.write() = config;
+    save_config();
+    schedule_cleanup();
+    Ok(())
+}
+
+/// (số file, số record) đã xóa từ đầu session
+pub fn pruned_totals() -> (u64, u64) {
+    (PRUNED_FILES.load(Ordering::Relaxed), PRUNED_RECORDS.load(Ordering::Relaxed))
+}
+
+/// Chạy cleanup nền (bỏ qua nếu đang có cleanup chạy)
+pub fn schedule_cleanup() {
+    if CLEANUP_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| {
+        if let Err(e) = run_cleanup() {
+            log::warn!("Dataset cleanup failed: {}", e);
+        }
+        CLEANUP_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Cleanup đồng bộ (command `run_dataset_cleanup`)
+pub fn run_cleanup() -> Result<PruneResult, String> {
+    let _guard = CLEANUP_LOCK.lock();
+    let config = get_config();
+    let result = prune(&get_dataset_dir(), &config, SystemTime::now()).map_err(|e| e.to_string())?;
+    if result.files > 0 {
+        log::info!(
+            "Dataset cleanup: pruned {} file(s), {} record(s), {} bytes",
+            result.files, result.records, result.bytes
+        );
+        PRUNED_FILES.fetch_add(result.files, Ordering::Relaxed);
+        PRUNED_RECORDS.fetch_add(result.records, Ordering::Relaxed);
+    }
+    Ok(result)
+}
+
+// ============================================================================
+// IMPLEMENTATION
+// ============================================================================
+
+/// Xóa file quá hạn rồi xóa file cũ nhất tới khi vừa budget. File mới nhất luôn giữ lại
+pub(crate) fn prune(dir: &Path, config: &DatasetRetentionConfig, now: SystemTime) -> std::io::Result<PruneResult> {
+    let mut result = PruneResult::default();
+    let mut files = dataset_files(dir)?;
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    // File mới nhất = file writer đang ghi
+    files.pop();
+
+    let max_age = (config.max_age_days > 0).then(|| Duration::from_secs(config.max_age_days * 24 * 3600));
+    let budget = (config.max_total_mb > 0).then(|| config.max_total_mb * MB);
+    let mut removed = Vec::new();
+
+    // Cũ nhất trước (tên file chứa timestamp)
+    for (path, size, modified) in files {
+        let expired = max_age.is_some_and(|max| now.duration_since(modified).is_ok_and(|age| age > max));
+        let over_budget = budget.is_some_and(|b| total > b);
+        if !expired && !over_budget {
+            continue;
+        }
+        let records = count_records(&path);
+        if fs::remove_file(&path).is_ok() {
+            total -= size;
+            result.files += 1;
+            result.records += records;
+            result.bytes += size;
+            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                removed.push(name.to_string());
+            }
+        }
+    }
+
+    if !removed.is_empty() {
+        labeling::drop_labels_for_files(dir, &removed);
+    }
+    Ok(result)
+}
+
+/// File dataset (path, size, modified), sort theo tên
+fn dataset_files(dir: &Path) -> std::io::Result<Vec<(PathBuf, u64, SystemTime)>> {
+    Ok(labeling::dataset_files(dir)?
+        .into_iter()
+        .filter_map(|path| {
+            let meta = fs::metadata(&path).ok()?;
+            Some((path, meta.len(), meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)))
+        })
+        .collect())
+}
+
+fn count_records(path: &Path) -> u64 {
+    File::open(path)
+        .map(|f| BufReader::new(f).lines().map_while(Result::ok).filter(|l| !l.trim().is_empty()).count() as u64)
+        .unwrap_or(0)
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn config_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(CONFIG_FILE)
+}
+
+fn load_config() -> DatasetRetentionConfig {
+    fs::read_to_string(config_path())
+        .ok()
+        .and_then(|c| serde_json::from_str::<DatasetRetentionConfig>(&c).ok())
+        .filter(|c| c.validate().is_ok())
+        .unwrap_or_default()
+}
+
+fn save_config() {
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*CONFIG.read()) {
+        let _ = fs::write(path, json);
+    }
+}
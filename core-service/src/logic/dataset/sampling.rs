@@ -0,0 +1,272 @@
+//! Dataset Sampling - Export đã khử trùng lặp và cân bằng lớp (P2.2+)
+//!
+//! Dataset thô gần như toàn record Benign giống hệt nhau → model học lệch.
+//! Export có chọn lọc gồm 2 bước:
+//! 1. Dedup: record Benign gần giống nhau được gom bằng LSH (random hyperplane /
+//!    SimHash trên `features` + `baseline_diff`, kèm bucket độ lớn vector) →
+//!    mỗi bucket chỉ giữ record đầu tiên
+//! 2. Sampling theo lớp (nhãn hiệu lực: overlay > `user_label` > quyết định model):
+//!    - `Balanced`: mỗi lớp lấy bằng lớp nhỏ nhất
+//!    - `Stratified`: mỗi lớp lấy cùng một tỉ lệ (giữ phân bố gốc)
+//!
+//! Chọn mẫu dùng RNG có seed → cùng dataset + cùng options = cùng kết quả.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::logic::threat::ThreatClass;
+use super::get_dataset_dir;
+use super::labeling;
+use super::record::DatasetRecord;
+use crate::logic::telemetry::scrub::{self, ScrubTarget};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const DEFAULT_LSH_BITS: u8 = 16;
+const MAX_LSH_BITS: u8 = 64;
+const DEFAULT_SEED: u64 = 0x5EED;
+/// Số bucket độ lớn trên mỗi lần nhân đôi norm
+const NORM_BUCKETS_PER_OCTAVE: f32 = 4.0;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "mode")]
+pub enum BalanceMode {
+    /// Giữ nguyên phân bố (chỉ áp `max_per_class`)
+    #[default]
+    None,
+    /// Mỗi lớp lấy bằng số record của lớp nhỏ nhất
+    Balanced,
+    /// Mỗi lớp lấy `fraction` (0..=1) số record của lớp đó
+    Stratified { fraction: f32 },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    /// Gom record Benign gần giống nhau
+    pub dedup: bool,
+    /// Dedup cả record Suspicious / Malicious (mặc định chỉ Benign)
+    pub dedup_all_classes: bool,
+    /// Số hyperplane LSH - càng nhiều thì dedup càng chặt
+    pub lsh_bits: u8,
+    pub balance: BalanceMode,
+    /// Giới hạn cứng số record mỗi lớp
+    pub max_per_class: Option<usize>,
+    pub seed: Option<u64>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            dedup: true,
+            dedup_all_classes: false,
+            lsh_bits: DEFAULT_LSH_BITS,
+            balance: BalanceMode::None,
+            max_per_class: None,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExportSummary {
+    pub path: String,
+    pub records_read: usize,
+    pub duplicates_removed: usize,
+    pub records_written: usize,
+    /// Số record trước sampling (sau dedup) theo lớp
+    pub available_by_class: BTreeMap<String, usize>,
+    pub written_by_class: BTreeMap<String, usize>,
+}
+
+/// Record sau dedup, chờ sampling
+struct Candidate {
+    id: String,
+    class: String,
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Export dataset đã dedup + sampling ra 1 file JSONL (thứ tự thời gian giữ nguyên)
+pub fn export_sampled(target_path: &str, options: &ExportOptions) -> Result<ExportSummary, String> {
+    export_in(&get_dataset_dir(), target_path, options)
+}
+
+// ============================================================================
+// IMPLEMENTATION
+// ============================================================================
+
+pub(crate) fn export_in(dir: &Path, target_path: &str, options: &ExportOptions) -> Result<ExportSummary, String> {
+    if let BalanceMode::Stratified { fraction } = options.balance {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err("Stratified fraction must be in (0, 1]".to_string());
+        }
+    }
+    if !dir.exists() {
+        return Err("Dataset directory not found".to_string());
+    }
+
+    let labels = labeling::overlay_labels(dir);
+    let scrub = scrub::enabled(ScrubTarget::FileExport);
+    let mut summary = ExportSummary { path: target_path.to_string(), ..Default::default() };
+
+    // Pass 1: dedup, gom id theo lớp
+    let mut hasher = LshHasher::new(options.lsh_bits, options.seed.unwrap_or(DEFAULT_SEED));
+    let mut seen: HashSet<(String, u64, i32)> = HashSet::new();
+    let mut candidates = Vec::new();
+    labeling::for_each_record(dir, |id, record| {
+        summary.records_read += 1;
+        let class = class_of(&record, labels.get(&id));
+        let dedup = options.dedup && (options.dedup_all_classes || class == ThreatClass::Benign.as_str());
+        if dedup {
+            let (signature, norm_bucket) = hasher.signature(&record);
+            if !seen.insert((class.clone(), signature, norm_bucket)) {
+                summary.duplicates_removed += 1;
+                return;
+            }
+        }
+        candidates.push(Candidate { id, class });
+    }).map_err(|e| e.to_string())?;
+
+    let selected = select(candidates, options, &mut summary.available_by_class);
+
+    // Pass 2: ghi record được chọn, áp nhãn overlay vào `user_label` (ẩn danh PII nếu bật)
+    let file = File::create(target_path).map_err(|e| e.to_string())?;
+    let mut out = BufWriter::new(file);
+    let mut write_err: Option<io::Error> = None;
+    labeling::for_each_record(dir, |id, mut record| {
+        if write_err.is_some() || !selected.contains(&id) {
+            return;
+        }
+        let label = labels.get(&id);
+        *summary.written_by_class.entry(class_of(&record, label)).or_insert(0) += 1;
+        if let Some(label) = label {
+            record.user_label = Some(label.clone());
+        }
+        if scrub {
+            record.user_label = record.user_label.map(|l| scrub::scrub_text(&l));
+        }
+        let result = serde_json::to_string(&record)
+            .map_err(io::Error::from)
+            .and_then(|json| writeln!(out, "{}", json));
+        if let Err(e) = result {
+            write_err = Some(e);
+        }
+    }).map_err(|e| e.to_string())?;
+    if let Some(e) = write_err {
+        return Err(e.to_string());
+    }
+    out.flush().map_err(|e| e.to_string())?;
+
+    summary.records_written = summary.written_by_class.values().sum();
+    log::info!(
+        "Exported {} of {} dataset records to {} ({} near-duplicates removed)",
+        summary.records_written, summary.records_read, target_path, summary.duplicates_removed
+    );
+    Ok(summary)
+}
+
+// ============================================================================
+// SAMPLING
+// ============================================================================
+
+/// Chọn id theo `balance` / `max_per_class`. Ghi số record sẵn có theo lớp vào `available`
+fn select(candidates: Vec<Candidate>, options: &ExportOptions, available: &mut BTreeMap<String, usize>) -> HashSet<String> {
+    let mut by_class: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for c in candidates {
+        by_class.entry(c.class).or_default().push(c.id);
+    }
+    for (class, ids) in &by_class {
+        available.insert(class.clone(), ids.len());
+    }
+
+    let smallest = by_class.values().map(Vec::len).min().unwrap_or(0);
+    let mut rng = StdRng::seed_from_u64(options.seed.unwrap_or(DEFAULT_SEED));
+    let mut selected = HashSet::new();
+
+    for (_, mut ids) in by_class {
+        let mut take = match options.balance {
+            BalanceMode::None => ids.len(),
+            BalanceMode::Balanced => smallest,
+            BalanceMode::Stratified { fraction } => ((ids.len() as f32 * fraction).ceil() as usize).max(1),
+        };
+        if let Some(max) = options.max_per_class {
+            take = take.min(max);
+        }
+        if take < ids.len() {
+            ids.shuffle(&mut rng);
+            ids.truncate(take);
+        }
+        selected.extend(ids);
+    }
+    selected
+}
+
+/// Lớp huấn luyện: nhãn người gán (overlay > `user_label`) hoặc quyết định model
+pub(super) fn class_of(record: &DatasetRecord, overlay: Option<&String>) -> String {
+    overlay
+        .cloned()
+        .or_else(|| record.user_label.clone().filter(|l| !l.trim().is_empty()))
+        .map(|l| l.trim().to_lowercase())
+        .unwrap_or_else(|| record.threat.as_str().to_string())
+}
+
+// ============================================================================
+// LSH
+// ============================================================================
+
+/// Random-hyperplane LSH: bit i = dấu của <v, h_i>.
+/// Chỉ giữ hướng vector nên kèm thêm bucket log2(norm) để tách vector cùng hướng khác độ lớn.
+pub(super) struct LshHasher {
+    bits: u8,
+    seed: u64,
+    /// Hyperplane theo số chiều (feature layout có thể khác nhau giữa các version)
+    planes: HashMap<usize, Vec<Vec<f32>>>,
+}
+
+impl LshHasher {
+    pub(super) fn new(bits: u8, seed: u64) -> Self {
+        Self { bits: bits.clamp(1, MAX_LSH_BITS), seed, planes: HashMap::new() }
+    }
+
+    pub(super) fn signature(&mut self, record: &DatasetRecord) -> (u64, i32) {
+        let vector: Vec<f32> = record.features.iter().chain(&record.baseline_diff).copied().collect();
+        let (bits, seed) = (self.bits, self.seed);
+        let planes = self.planes.entry(vector.len()).or_insert_with(|| {
+            let mut rng = StdRng::seed_from_u64(seed ^ vector.len() as u64);
+            (0..bits)
+                .map(|_| (0..vector.len()).map(|_| rng.gen_range(-1.0f32..1.0)).collect())
+                .collect()
+        });
+
+        let mut signature = 0u64;
+        for (i, plane) in planes.iter().enumerate() {
+            let dot: f32 = plane.iter().zip(&vector).map(|(h, v)| h * v).sum();
+            if dot >= 0.0 {
+                signature |= 1 << i;
+            }
+        }
+
+        let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        let norm_bucket = if norm > f32::EPSILON {
+            (norm.log2() * NORM_BUCKETS_PER_OCTAVE).floor() as i32
+        } else {
+            i32::MIN
+        };
+        (signature, norm_bucket)
+    }
+}
@@ -68,3 +68,225 @@ fn test_rotation_creates_new_file() {
     let entries: Vec<_> = fs::read_dir(dir.path()).unwrap().map(|e| e.unwrap()).collect();
     assert_eq!(entries.len(), 1);
 }
+
+#[test]
+fn test_labeling_workflow() {
+    use super::labeling::{self, LabelFilter, LabelQuery};
+
+    let dir = tempdir().unwrap();
+    let writer = DatasetWriter::from_path(dir.path().to_path_buf());
+    for i in 0..6 {
+        writer.append(&DatasetRecord {
+            timestamp: 1000 + i,
+            feature_version: 1,
+            layout_hash: 1,
+            features: vec![0.0; 15],
+            baseline_diff: vec![0.0; 15],
+            score: 0.5,
+            confidence: 0.1 * (i + 1) as f32,
+            threat: if i % 2 == 0 { ThreatClass::Suspicious } else { ThreatClass::Benign },
+            user_label: if i == 5 { Some("Benign".to_string()) } else { None },
+        }).unwrap();
+    }
+
+    // Hàng đợi: chưa gán nhãn, low-confidence, thấp nhất trước
+    let query = LabelQuery {
+        filter: LabelFilter { unlabeled_only: true, max_confidence: Some(0.45), ..Default::default() },
+        ..Default::default()
+    };
+    let page = labeling::list_candidates_in(dir.path(), &query).unwrap();
+    assert_eq!(page.total_matched, 4);
+    assert!(page.items[0].record.confidence < page.items[1].record.confidence);
+
+    let first = page.items[0].id.clone();
+    labeling::relabel_in(dir.path(), &first, "Malicious").unwrap();
+    assert!(labeling::relabel_in(dir.path(), "missing.jsonl:1", "Benign").is_err());
+    assert!(labeling::relabel_in(dir.path(), &first, "  ").is_err());
+
+    // Bulk: mọi Suspicious còn lại chưa gán nhãn → Benign
+    let bulk = LabelFilter { unlabeled_only: true, threat: Some(ThreatClass::Suspicious), ..Default::default() };
+    assert_eq!(labeling::bulk_label_in(dir.path(), &bulk, "Benign").unwrap(), 2);
+    assert!(labeling::bulk_label_in(dir.path(), &LabelFilter::default(), "Benign").is_err());
+
+    let progress = labeling::progress_in(dir.path()).unwrap();
+    assert_eq!(progress.total_records, 6);
+    assert_eq!(progress.labeled, 4);
+    assert_eq!(progress.bulk_labeled, 2);
+    assert_eq!(progress.by_label.get("Benign"), Some(&3));
+    assert_eq!(progress.by_label.get("Malicious"), Some(&1));
+
+    // Sửa nhãn: lọc theo nhãn hiện tại
+    let malicious = LabelQuery {
+        filter: LabelFilter { label: Some("malicious".to_string()), ..Default::default() },
+        ..Default::default()
+    };
+    let page = labeling::list_candidates_in(dir.path(), &malicious).unwrap();
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].id, first);
+
+    // Verdict incident [1000, 1003]: chỉ record bị flag, nhãn tay giữ nguyên, ghi đè nhãn bulk
+    assert_eq!(labeling::label_incident_in(dir.path(), "inc-1", 1000, 1003, "malicious").unwrap(), 1);
+    assert!(labeling::label_incident_in(dir.path(), "inc-1", 1003, 1000, "malicious").is_err());
+    let progress = labeling::progress_in(dir.path()).unwrap();
+    assert_eq!(progress.labeled, 4);
+    assert_eq!(progress.incident_labeled, 1);
+    assert_eq!(progress.bulk_labeled, 1);
+    assert_eq!(progress.by_label.get("Malicious"), Some(&1));
+    assert_eq!(progress.by_label.get("malicious"), Some(&1));
+}
+
+#[test]
+fn test_sampled_export_dedups_and_balances() {
+    use super::sampling::{self, BalanceMode, ExportOptions};
+
+    let dir = tempdir().unwrap();
+    let writer = DatasetWriter::from_path(dir.path().to_path_buf());
+    let record = |i: u64, features: Vec<f32>, threat: ThreatClass| DatasetRecord {
+        timestamp: 1000 + i,
+        feature_version: 1,
+        layout_hash: 1,
+        baseline_diff: vec![0.0; features.len()],
+        features,
+        score: 0.5,
+        confidence: 0.9,
+        threat,
+        user_label: None,
+    };
+
+    // 20 Benign gần như trùng nhau + 5 Benign khác hẳn + 3 Malicious
+    for i in 0..20 {
+        writer.append(&record(i, vec![1.0 + i as f32 * 1e-4, 2.0, 3.0], ThreatClass::Benign)).unwrap();
+    }
+    for i in 0..5 {
+        let mut features = vec![0.0; 3];
+        features[i % 3] = 10.0 * (i + 1) as f32;
+        writer.append(&record(100 + i as u64, features, ThreatClass::Benign)).unwrap();
+    }
+    for i in 0..3 {
+        writer.append(&record(200 + i, vec![-5.0, 1.0, i as f32], ThreatClass::Malicious)).unwrap();
+    }
+
+    let out = dir.path().join("out").with_extension("export");
+    let summary = sampling::export_in(dir.path(), out.to_str().unwrap(), &ExportOptions::default()).unwrap();
+    assert_eq!(summary.records_read, 28);
+    assert!(summary.duplicates_removed >= 19);
+    assert_eq!(summary.written_by_class.get("malicious"), Some(&3));
+    let benign = summary.written_by_class["benign"];
+    assert!((2..=6).contains(&benign));
+
+    let balanced = ExportOptions { balance: BalanceMode::Balanced, ..Default::default() };
+    let summary = sampling::export_in(dir.path(), out.to_str().unwrap(), &balanced).unwrap();
+    assert_eq!(summary.written_by_class["benign"], summary.written_by_class["malicious"]);
+    let lines = fs::read_to_string(&out).unwrap();
+    assert_eq!(lines.lines().count(), summary.records_written);
+
+    // Không dedup, giữ 50% mỗi lớp
+    let stratified = ExportOptions { dedup: false, balance: BalanceMode::Stratified { fraction: 0.5 }, ..Default::default() };
+    let summary = sampling::export_in(dir.path(), out.to_str().unwrap(), &stratified).unwrap();
+    assert_eq!(summary.written_by_class["benign"], 13);
+    assert_eq!(summary.written_by_class["malicious"], 2);
+
+    let invalid = ExportOptions { balance: BalanceMode::Stratified { fraction: 1.5 }, ..Default::default() };
+    assert!(sampling::export_in(dir.path(), out.to_str().unwrap(), &invalid).is_err());
+}
+
+#[test]
+fn test_retention_prunes_oldest_first() {
+    use super::labeling;
+    use super::retention::{self, DatasetRetentionConfig};
+    use std::time::{Duration, SystemTime};
+
+    let dir = tempdir().unwrap();
+    let line = serde_json::to_string(&DatasetRecord {
+        timestamp: 1,
+        feature_version: 1,
+        layout_hash: 1,
+        features: vec![0.0; 15],
+        baseline_diff: vec![0.0; 15],
+        score: 0.0,
+        confidence: 0.0,
+        threat: ThreatClass::Benign,
+        user_label: None,
+    }).unwrap() + "\n";
+
+    // 3 file ~3 MB, file đầu cũ 200 ngày
+    let records_per_file = 3 * 1024 * 1024 / line.len();
+    let names = ["dataset-2026-01-01-000000.jsonl", "dataset-2026-02-01-000000.jsonl", "dataset-2026-03-01-000000.jsonl"];
+    for (i, name) in names.iter().enumerate() {
+        let path = dir.path().join(name);
+        fs::write(&path, line.repeat(records_per_file)).unwrap();
+        let age = if i == 0 { 200 } else { 1 };
+        let modified = SystemTime::now() - Duration::from_secs(age * 24 * 3600);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+    }
+    labeling::relabel_in(dir.path(), &format!("{}:1", names[0]), "Malicious").unwrap();
+    labeling::relabel_in(dir.path(), &format!("{}:1", names[1]), "Benign").unwrap();
+
+    // Chỉ giới hạn tuổi → xóa file 200 ngày và nhãn của nó
+    let by_age = DatasetRetentionConfig { max_total_mb: 0, max_age_days: 180 };
+    let result = retention::prune(dir.path(), &by_age, SystemTime::now()).unwrap();
+    assert_eq!((result.files, result.records), (1, records_per_file as u64));
+    assert!(!dir.path().join(names[0]).exists());
+    assert_eq!(labeling::progress_in(dir.path()).unwrap().labeled, 1);
+
+    // Giới hạn 5 MB → xóa file cũ nhất còn lại, file mới nhất luôn giữ
+    let by_size = DatasetRetentionConfig { max_total_mb: 5, max_age_days: 0 };
+    let result = retention::prune(dir.path(), &by_size, SystemTime::now()).unwrap();
+    assert_eq!(result.files, 1);
+    assert!(dir.path().join(names[2]).exists());
+    let result = retention::prune(dir.path(), &DatasetRetentionConfig { max_total_mb: 0, max_age_days: 1 }, SystemTime::now()).unwrap();
+    assert_eq!(result.files, 0);
+
+    assert!(DatasetRetentionConfig { max_total_mb: 5, max_age_days: 0 }.validate().is_err());
+}
+
+#[test]
+fn test_dataset_report() {
+    use super::{labeling, report};
+    use crate::logic::features::layout::layout_hash;
+
+    let dir = tempdir().unwrap();
+    let writer = DatasetWriter::from_path(dir.path().to_path_buf());
+    let record = |i: u64, features: Vec<f32>, threat: ThreatClass| DatasetRecord {
+        timestamp: 1000 + i,
+        feature_version: 1,
+        layout_hash: layout_hash(),
+        baseline_diff: vec![0.0; features.len()],
+        features,
+        score: 0.5,
+        confidence: 0.9,
+        threat,
+        user_label: None,
+    };
+
+    // 4 Benign giống hệt nhau + 1 Malicious, feature 1 luôn = 0
+    for i in 0..4 {
+        writer.append(&record(i, vec![1.0, 0.0, 2.0], ThreatClass::Benign)).unwrap();
+    }
+    writer.append(&record(10, vec![9.0, 0.0, 7.0], ThreatClass::Malicious)).unwrap();
+    let first = labeling::list_candidates_in(dir.path(), &Default::default()).unwrap().items[0].id.clone();
+    labeling::relabel_in(dir.path(), &first, "Suspicious").unwrap();
+
+    let report = report::report_in(dir.path()).unwrap();
+    assert_eq!(report.total_records, 5);
+    assert_eq!((report.first_timestamp, report.last_timestamp), (Some(1000), Some(1010)));
+    assert_eq!(report.class_balance.by_decision.get("benign"), Some(&4));
+    assert_eq!(report.class_balance.by_training_class.get("benign"), Some(&3));
+    assert_eq!(report.class_balance.by_training_class.get("suspicious"), Some(&1));
+    assert_eq!(report.class_balance.imbalance_ratio, Some(3.0));
+    assert_eq!(report.labels.labeled, 1);
+
+    assert_eq!(report.layouts.len(), 1);
+    let layout = &report.layouts[0];
+    assert!(layout.current);
+    assert_eq!(layout.records, 5);
+    assert_eq!(layout.features[0].name, "cpu_percent");
+    assert!((layout.features[0].mean - 2.6).abs() < 1e-6);
+    assert_eq!(layout.features[1].zero_fraction, 1.0);
+    assert_eq!((layout.features[2].min, layout.features[2].max), (2.0, 7.0));
+
+    // Record đã relabel khác lớp → không tính trùng gần
+    assert_eq!(report.duplicates.exact, 3);
+    assert_eq!(report.duplicates.near, 2);
+    assert!(report.warnings.iter().any(|w| w.contains("Constant features")));
+}
@@ -23,6 +23,13 @@ pub mod events {
     pub const MEMORY_ALERT: &str = "advanced:memory";
     pub const SCRIPT_BLOCKED: &str = "advanced:script";
     pub const THREAT_ALERT: &str = "advanced:threat";
+
+    // Incidents
+    pub const INCIDENT_SEVERITY: &str = "incident:severity";
+
+    // Full scan
+    pub const SCAN_PROGRESS: &str = "scan:progress";
+    pub const SCAN_COMPLETED: &str = "scan:completed";
 }
 
 /// Initialize event system with AppHandle
@@ -101,3 +108,32 @@ pub fn emit_threat_alert<S: Serialize + Clone>(payload: S) {
         log::error!("Failed to emit threat alert: {}", e);
     }
 }
+
+// ============================================================================
+// INCIDENT EVENTS
+// ============================================================================
+
+/// Emit incident severity raised event
+pub fn emit_incident_severity<S: Serialize + Clone>(payload: S) {
+    if let Err(e) = emit(events::INCIDENT_SEVERITY, payload) {
+        log::error!("Failed to emit incident severity: {}", e);
+    }
+}
+
+// ============================================================================
+// FULL SCAN EVENTS
+// ============================================================================
+
+/// Emit full scan progress event
+pub fn emit_scan_progress<S: Serialize + Clone>(payload: S) {
+    if let Err(e) = emit(events::SCAN_PROGRESS, payload) {
+        log::error!("Failed to emit scan progress: {}", e);
+    }
+}
+
+/// Emit full scan completed event
+pub fn emit_scan_completed<S: Serialize + Clone>(payload: S) {
+    if let Err(e) = emit(events::SCAN_COMPLETED, payload) {
+        log::error!("Failed to emit scan completed: {}", e);
+    }
+}
@@ -0,0 +1,413 @@
+//! Offline Intel Bundle - Threat intel cho endpoint không có internet (air-gapped)
+//!
+//! Bundle = file zip (deflate) chép qua USB / file share:
+//! - `manifest.json`: version, name, created_at, `files` (path → sha256)
+//! - `manifest.sig`: hex chữ ký ed25519 của manifest.json, ký trên console
+//!   (`POST /api/v1/intel-bundles/sign`); agent verify bằng public key pin lúc build
+//!   nên không cần từng kết nối cloud, và registration key bị lộ cũng không giả được
+//! - `feeds/*.json`: `[ThreatIndicator]` → threat_feed, source "Offline bundle: <tên file>"
+//! - `mitre/*.json`: `[MitreTechnique]` → bổ sung / cập nhật database MITRE
+//! - `rules/*.json|yaml`: behavioral rules → rules_dir (`offline_bundle_*`)
+//! - `yara/*.yar`: YARA rules cho full scan (`offline_bundle_*`)
+//!
+//! Verify toàn bộ (chữ ký, hash từng file, parse rules) trước khi apply → bundle lỗi
+//! không làm thay đổi gì. Bundle cũ hơn bundle đang chạy bị từ chối (chống replay).
+//! Bản copy bundle được giữ trong data dir để nạp lại feeds / MITRE khi khởi động;
+//! version đang chạy được báo lên console qua heartbeat khi có kết nối.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::logic::identity::signing;
+use crate::logic::behavioral_sigs::rules_dir;
+use super::mitre;
+use super::threat_feed;
+use super::types::{MitreTechnique, ThreatIndicator};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const STATE_FILE: &str = "intel_bundle.json";
+const BUNDLE_FILE: &str = "intel_bundle.zip";
+const MANIFEST: &str = "manifest.json";
+const SIGNATURE: &str = "manifest.sig";
+const SOURCE_PREFIX: &str = "Offline bundle: ";
+/// Prefix file rules / YARA do bundle cài (xóa khi bundle mới thay thế)
+const INSTALLED_PREFIX: &str = "offline_bundle_";
+const YARA_DIR: &str = "yara";
+/// Giới hạn dung lượng giải nén (chống zip bomb)
+const MAX_UNPACKED_BYTES: u64 = 512 * 1024 * 1024;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub version: String,
+    #[serde(default)]
+    pub name: String,
+    /// Unix seconds - dùng để so bundle mới / cũ
+    pub created_at: i64,
+    /// Path trong zip → sha256 hex
+    pub files: BTreeMap<String, String>,
+}
+
+/// Bundle đang chạy trên agent
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IntelBundleState {
+    pub version: Option<String>,
+    pub name: String,
+    pub created_at: i64,
+    pub sha256: String,
+    pub feed_sources: Vec<String>,
+    pub indicator_count: usize,
+    pub technique_count: usize,
+    pub behavioral_rule_count: usize,
+    pub yara_files: usize,
+    pub applied_at: i64,
+    /// Lỗi của lần import gần nhất (bundle bị từ chối)
+    pub last_error: Option<String>,
+}
+
+/// Nội dung bundle đã verify + parse
+struct BundleContent {
+    manifest: BundleManifest,
+    /// source → indicators
+    feeds: Vec<(String, Vec<ThreatIndicator>)>,
+    techniques: Vec<MitreTechnique>,
+    /// (tên file cài đặt, nội dung, số rule)
+    rules: Vec<(String, String, usize)>,
+    yara: Vec<(String, String)>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static STATE: Lazy<RwLock<IntelBundleState>> = Lazy::new(|| RwLock::new(load_state()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Version bundle đang chạy (báo qua heartbeat)
+pub fn applied_version() -> Option<String> {
+    STATE.read().version.clone()
+}
+
+pub fn get_state() -> IntelBundleState {
+    STATE.read().clone()
+}
+
+/// Verify + apply bundle từ file. Lỗi → bundle đang chạy giữ nguyên.
+pub fn import_file(path: &Path) -> Result<IntelBundleState, String> {
+    let result = fs::read(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))
+        .and_then(|data| import_bytes(&data));
+    if let Err(e) = &result {
+        STATE.write().last_error = Some(e.clone());
+        save_state();
+        log::warn!("Rejected intel bundle {}: {}", path.display(), e);
+    }
+    result
+}
+
+/// Nạp lại feeds + MITRE của bundle đã import (rules / YARA đã nằm trên đĩa)
+pub fn restore() {
+    let path = data_dir().join(BUNDLE_FILE);
+    let Ok(data) = fs::read(&path) else {
+        return;
+    };
+    match read_bundle(&data, crate::constants::content_signing_public_key()) {
+        Ok(content) => {
+            apply_intel(&content);
+            log::info!("Restored offline intel bundle {}", content.manifest.version);
+        }
+        Err(e) => log::warn!("Stored intel bundle invalid: {}", e),
+    }
+}
+
+// ============================================================================
+// IMPORT
+// ============================================================================
+
+fn import_bytes(data: &[u8]) -> Result<IntelBundleState, String> {
+    let content = read_bundle(data, crate::constants::content_signing_public_key())?;
+    {
+        let current = STATE.read();
+        if current.version.is_some() && content.manifest.created_at < current.created_at {
+            return Err(format!(
+                "bundle {} is older than installed bundle {}",
+                content.manifest.version,
+                current.version.as_deref().unwrap_or_default()
+            ));
+        }
+    }
+
+    install_rules(&content)?;
+    let previous = STATE.read().feed_sources.clone();
+    for source in previous.iter().filter(|s| !content.feeds.iter().any(|(f, _)| f == *s)) {
+        threat_feed::replace_source_indicators(source, Vec::new());
+    }
+    apply_intel(&content);
+
+    let bundle_path = data_dir().join(BUNDLE_FILE);
+    write_file(&bundle_path, data)?;
+
+    let state = IntelBundleState {
+        version: Some(content.manifest.version.clone()),
+        name: content.manifest.name.clone(),
+        created_at: content.manifest.created_at,
+        sha256: format!("{:x}", Sha256::digest(data)),
+        feed_sources: content.feeds.iter().map(|(s, _)| s.clone()).collect(),
+        indicator_count: content.feeds.iter().map(|(_, i)| i.len()).sum(),
+        technique_count: content.techniques.len(),
+        behavioral_rule_count: content.rules.iter().map(|(_, _, n)| n).sum(),
+        yara_files: content.yara.len(),
+        applied_at: Utc::now().timestamp(),
+        last_error: None,
+    };
+    *STATE.write() = state.clone();
+    save_state();
+    log::info!(
+        "📦 Imported offline intel bundle {} ({} indicators, {} techniques, {} rules, {} YARA files)",
+        content.manifest.version, state.indicator_count, state.technique_count,
+        state.behavioral_rule_count, state.yara_files
+    );
+    Ok(state)
+}
+
+/// Giải nén + verify chữ ký + hash từng file + parse nội dung
+fn read_bundle(data: &[u8], public_key: &str) -> Result<BundleContent, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(|e| format!("not a bundle archive: {}", e))?;
+    let mut entries: HashMap<String, Vec<u8>> = HashMap::new();
+    let mut unpacked = 0u64;
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().replace('\\', "/");
+        let mut buf = Vec::new();
+        entry.take(MAX_UNPACKED_BYTES - unpacked + 1)
+            .read_to_end(&mut buf)
+            .map_err(|e| format!("{}: {}", name, e))?;
+        unpacked += buf.len() as u64;
+        if unpacked > MAX_UNPACKED_BYTES {
+            return Err("bundle too large".to_string());
+        }
+        entries.insert(name, buf);
+    }
+
+    let manifest_bytes = entries.remove(MANIFEST).ok_or("missing manifest.json")?;
+    let signature = entries.remove(SIGNATURE).ok_or("missing manifest.sig")?;
+    signing::verify(public_key, &manifest_bytes, &String::from_utf8_lossy(&signature))?;
+    let manifest: BundleManifest = serde_json::from_slice(&manifest_bytes)
+        .map_err(|e| format!("invalid manifest: {}", e))?;
+
+    if let Some(extra) = entries.keys().find(|name| !manifest.files.contains_key(*name)) {
+        return Err(format!("{} not listed in manifest", extra));
+    }
+    let mut content = BundleContent {
+        manifest: manifest.clone(),
+        feeds: Vec::new(),
+        techniques: Vec::new(),
+        rules: Vec::new(),
+        yara: Vec::new(),
+    };
+    for (name, expected) in &manifest.files {
+        let bytes = entries.get(name).ok_or_else(|| format!("{} missing from bundle", name))?;
+        if !format!("{:x}", Sha256::digest(bytes)).eq_ignore_ascii_case(expected) {
+            return Err(format!("{}: content hash mismatch", name));
+        }
+        parse_entry(name, bytes, &mut content)?;
+    }
+    Ok(content)
+}
+
+fn parse_entry(name: &str, bytes: &[u8], content: &mut BundleContent) -> Result<(), String> {
+    let (dir, file) = name.split_once('/').ok_or_else(|| format!("{}: unexpected file", name))?;
+    let stem = Path::new(file).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = Path::new(file).extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if file.contains('/') || stem.is_empty() {
+        return Err(format!("{}: unexpected file", name));
+    }
+    let text = std::str::from_utf8(bytes).map_err(|_| format!("{}: not UTF-8", name))?;
+
+    match (dir, ext.as_str()) {
+        ("feeds", "json") => {
+            let source = format!("{}{}", SOURCE_PREFIX, stem);
+            let mut indicators: Vec<ThreatIndicator> = serde_json::from_str(text)
+                .map_err(|e| format!("{}: {}", name, e))?;
+            for indicator in &mut indicators {
+                indicator.source = source.clone();
+            }
+            content.feeds.push((source, indicators));
+        }
+        ("mitre", "json") => {
+            let techniques: Vec<MitreTechnique> = serde_json::from_str(text)
+                .map_err(|e| format!("{}: {}", name, e))?;
+            content.techniques.extend(techniques);
+        }
+        ("rules", "json" | "yaml" | "yml") => {
+            let installed = format!("{}{}.{}", INSTALLED_PREFIX, stem, ext);
+            let rules = rules_dir::parse_rules(Path::new(&installed), text).map_err(|e| format!("{}: {}", name, e))?;
+            content.rules.push((installed, text.to_string(), rules.len()));
+        }
+        ("yara", "yar" | "yara") => {
+            content.yara.push((format!("{}{}.yar", INSTALLED_PREFIX, stem), text.to_string()));
+        }
+        _ => return Err(format!("{}: unexpected file", name)),
+    }
+    Ok(())
+}
+
+// ============================================================================
+// APPLY
+// ============================================================================
+
+fn apply_intel(content: &BundleContent) {
+    for (source, indicators) in &content.feeds {
+        threat_feed::replace_source_indicators(source, indicators.clone());
+    }
+    mitre::load_bundle_techniques(content.techniques.clone());
+}
+
+/// Thay file rules / YARA của bundle trước bằng bundle mới
+fn install_rules(content: &BundleContent) -> Result<(), String> {
+    let rules_path = rules_dir::rules_dir();
+    let yara_path = data_dir().join(YARA_DIR);
+    remove_installed(&rules_path);
+    remove_installed(&yara_path);
+
+    for (file, text, _) in &content.rules {
+        write_file(&rules_path.join(file), text.as_bytes())?;
+    }
+    for (file, text) in &content.yara {
+        write_file(&yara_path.join(file), text.as_bytes())?;
+    }
+    // Không chờ debounce của watcher
+    rules_dir::reload_all();
+    Ok(())
+}
+
+fn remove_installed(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with(INSTALLED_PREFIX) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn load_state() -> IntelBundleState {
+    fs::read_to_string(data_dir().join(STATE_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_state() {
+    let path = data_dir().join(STATE_FILE);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*STATE.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use std::io::Write;
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn public_key(seed: u8) -> String {
+        hex::encode(signing_key(seed).verifying_key().as_bytes())
+    }
+
+    fn build_bundle(files: &[(&str, &str)], key: u8, tamper: Option<&str>) -> Vec<u8> {
+        let manifest = BundleManifest {
+            version: "2026.10.1".to_string(),
+            name: "test".to_string(),
+            created_at: 1_790_000_000,
+            files: files.iter()
+                .map(|(name, body)| (name.to_string(), format!("{:x}", Sha256::digest(body.as_bytes()))))
+                .collect(),
+        };
+        let manifest = serde_json::to_vec(&manifest).unwrap();
+        let signature = hex::encode(signing_key(key).sign(&manifest).to_bytes());
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file(MANIFEST, options).unwrap();
+        zip.write_all(&manifest).unwrap();
+        zip.start_file(SIGNATURE, options).unwrap();
+        zip.write_all(signature.as_bytes()).unwrap();
+        for (name, body) in files {
+            zip.start_file(*name, options).unwrap();
+            let body = if tamper == Some(*name) { format!("{} ", body) } else { body.to_string() };
+            zip.write_all(body.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_read_bundle() {
+        let feed = r#"[{"indicator_type":"Domain","value":"evil.example","threat_level":"High","source":"x",
+            "first_seen":null,"last_seen":null,"tags":["c2"],"description":null}]"#;
+        let files = [("feeds/apt.json", feed), ("yara/apt.yar", "rule apt { condition: false }")];
+
+        let content = read_bundle(&build_bundle(&files, 1, None), &public_key(1)).unwrap();
+        assert_eq!(content.manifest.version, "2026.10.1");
+        assert_eq!(content.feeds.len(), 1);
+        assert_eq!(content.feeds[0].0, "Offline bundle: apt");
+        assert_eq!(content.feeds[0].1[0].source, "Offline bundle: apt");
+        assert_eq!(content.yara[0].0, "offline_bundle_apt.yar");
+
+        let err = read_bundle(&build_bundle(&files, 1, None), &public_key(2)).err().unwrap();
+        assert_eq!(err, "invalid signature");
+        let err = read_bundle(&build_bundle(&files, 1, Some("yara/apt.yar")), &public_key(1)).err().unwrap();
+        assert_eq!(err, "yara/apt.yar: content hash mismatch");
+        let err = read_bundle(&build_bundle(&[("bin/tool.exe", "MZ")], 1, None), &public_key(1)).err().unwrap();
+        assert_eq!(err, "bin/tool.exe: unexpected file");
+    }
+}
@@ -0,0 +1,316 @@
+//! Intel Matcher - Threat intel match → incident
+//!
+//! Mục đích: Lookup intel không chỉ trả bool mà tạo incident + sync cloud
+//!
+//! 1. Connection mới (mọi process) → remote IP + domain (qua `dns`) vs threat feed
+//! 2. Executable mới thấy trong process tree → SHA256 / MD5 vs threat feed
+//! 3. Match → `DatasetRecord` (score / confidence theo threat level của indicator),
+//!    connection kèm country / ASN của đích (`network::geoip`)
+//!    → `incident::process_event` (incident mới được queue lên cloud)
+//!
+//! Connection của browser đã do `network::url_reputation` xử lý (kèm chặn) nên bỏ qua
+//! khi module đó bật, tránh 2 incident cho cùng 1 site.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::dataset::DatasetRecord;
+use crate::logic::features::layout::{FEATURE_VERSION, layout_hash};
+use crate::logic::incident;
+use crate::logic::network::{connections, dns, geoip, url_reputation, ConnectionEvent, GeoInfo};
+use crate::logic::process_intel::{hashing, tree};
+use crate::logic::response::browser_child;
+use crate::logic::threat::ThreatClass;
+use super::threat_feed;
+use super::types::{ThreatIndicator, ThreatLevel};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const MAX_MATCHES: usize = 500;
+/// Không tạo lại incident cho cùng (pid, indicator) trong khoảng này
+const REPORT_COOLDOWN_MS: i64 = 30 * 60_000;
+/// Connection mới tạo → DNS cache có thể chưa kịp poll, giữ lại chờ resolve
+const PENDING_RESOLVE_MS: i64 = 15_000;
+/// Giới hạn số executable hash mỗi lần check (hash cache dùng chung với prevalence)
+const MAX_HASH_PER_RUN: usize = 20;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntelMatch {
+    /// "ip" | "domain" | "hash"
+    pub kind: String,
+    /// Giá trị khớp intel (IP, domain / parent domain, hash)
+    pub indicator: String,
+    pub source: String,
+    pub threat_level: ThreatLevel,
+    pub confidence: f32,
+    pub intel_tags: Vec<String>,
+    pub pid: u32,
+    pub process_name: String,
+    pub exe_path: Option<String>,
+    pub remote_ip: Option<String>,
+    pub remote_port: Option<u16>,
+    /// Country / ASN của remote IP (GeoIP)
+    #[serde(default)]
+    pub geo: Option<GeoInfo>,
+    pub timestamp: i64,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+struct MatcherState {
+    cursor: u64,
+    /// Connection chưa resolve được domain
+    pending: Vec<ConnectionEvent>,
+    /// Executable đã hash (hoặc hash lỗi)
+    hashed_paths: HashSet<PathBuf>,
+    /// (pid, indicator) → lần báo cuối (ms)
+    reported: HashMap<(u32, String), i64>,
+}
+
+static STATE: Lazy<Mutex<MatcherState>> = Lazy::new(|| Mutex::new(MatcherState {
+    cursor: connections::latest_seq(),
+    pending: Vec::new(),
+    hashed_paths: HashSet::new(),
+    reported: HashMap::new(),
+}));
+static MATCHES: Lazy<Mutex<Vec<IntelMatch>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Đối chiếu connection / executable mới với intel, tạo incident cho match mới.
+pub fn check() -> Vec<IntelMatch> {
+    let now = Utc::now().timestamp_millis();
+    let mut matches = check_connections(now);
+    matches.extend(check_executables());
+
+    {
+        let mut state = STATE.lock();
+        state.reported.retain(|_, at| now - *at < REPORT_COOLDOWN_MS);
+        matches.retain(|m| state.reported.insert((m.pid, m.indicator.clone()), now).is_none());
+    }
+
+    for m in &matches {
+        incident::process_event(&to_record(m), &match_tags(m));
+    }
+    if !matches.is_empty() {
+        let mut history = MATCHES.lock();
+        history.extend(matches.iter().cloned());
+        let overflow = history.len().saturating_sub(MAX_MATCHES);
+        history.drain(..overflow);
+    }
+    matches
+}
+
+pub fn get_recent_matches(limit: usize) -> Vec<IntelMatch> {
+    MATCHES.lock().iter().rev().take(limit).cloned().collect()
+}
+
+// ============================================================================
+// MATCHING
+// ============================================================================
+
+fn check_connections(now: i64) -> Vec<IntelMatch> {
+    let skip_browsers = url_reputation::get_config().enabled;
+    let mut state = STATE.lock();
+    let events = connections::events_since(state.cursor);
+    if let Some(last) = events.last() {
+        state.cursor = last.seq;
+    }
+    let mut queue = std::mem::take(&mut state.pending);
+    queue.extend(events.into_iter().filter(|e| {
+        e.is_remote() && !(skip_browsers && browser_child::is_browser(&e.process_name))
+    }));
+
+    let mut matches = Vec::new();
+    for event in queue {
+        let domains = dns::domains_for_ip(&event.remote_ip);
+        match match_connection(&event, &domains) {
+            Some(m) => matches.push(m),
+            None if domains.is_empty() && now - event.timestamp < PENDING_RESOLVE_MS => state.pending.push(event),
+            None => {}
+        }
+    }
+    matches
+}
+
+/// Domain (kể cả parent) ưu tiên trước IP: domain cho analyst nhiều ngữ cảnh hơn
+fn match_connection(event: &ConnectionEvent, domains: &[String]) -> Option<IntelMatch> {
+    for domain in domains {
+        if threat_feed::is_malicious_domain(domain) {
+            let indicator = url_reputation::domain_candidates(domain)
+                .into_iter()
+                .find_map(|d| threat_feed::find_indicator(&d));
+            let value = indicator.as_ref().map(|i| i.value.clone()).unwrap_or_else(|| domain.clone());
+            return Some(build_match("domain", value, indicator, event.pid, &event.process_name, None, Some(event)));
+        }
+    }
+
+    let ip = event.remote_ip.to_string();
+    if threat_feed::is_malicious_ip(&ip) {
+        let indicator = threat_feed::find_indicator(&ip);
+        return Some(build_match("ip", ip, indicator, event.pid, &event.process_name, None, Some(event)));
+    }
+    None
+}
+
+/// Hash executable chưa thấy của process đang chạy
+fn check_executables() -> Vec<IntelMatch> {
+    let candidates: Vec<(PathBuf, u32, String)> = {
+        let state = STATE.lock();
+        let mut seen = HashSet::new();
+        tree::get_process_tree()
+            .into_values()
+            .filter_map(|n| n.info.exe_path.map(|p| (p, n.info.pid, n.info.name)))
+            .filter(|(p, _, _)| !state.hashed_paths.contains(p) && seen.insert(p.clone()))
+            .take(MAX_HASH_PER_RUN)
+            .collect()
+    };
+
+    let mut matches = Vec::new();
+    for (path, pid, name) in candidates {
+        if let Ok(hashes) = hashing::hash_file(&path) {
+            let hit = [hashes.sha256, hashes.md5]
+                .into_iter()
+                .find(|h| threat_feed::is_malicious_hash(h));
+            if let Some(hash) = hit {
+                let indicator = threat_feed::find_indicator(&hash);
+                let exe = path.to_string_lossy().to_string();
+                matches.push(build_match("hash", hash, indicator, pid, &name, Some(exe), None));
+            }
+        }
+        STATE.lock().hashed_paths.insert(path);
+    }
+    matches
+}
+
+fn build_match(
+    kind: &str,
+    value: String,
+    indicator: Option<ThreatIndicator>,
+    pid: u32,
+    process_name: &str,
+    exe_path: Option<String>,
+    event: Option<&ConnectionEvent>,
+) -> IntelMatch {
+    let (source, threat_level, intel_tags) = indicator
+        .map(|i| (i.source, i.threat_level, i.tags))
+        .unwrap_or_else(|| ("threat_feed".to_string(), ThreatLevel::Medium, Vec::new()));
+
+    IntelMatch {
+        kind: kind.to_string(),
+        indicator: value,
+        source,
+        confidence: level_confidence(threat_level),
+        threat_level,
+        intel_tags,
+        pid,
+        process_name: process_name.to_string(),
+        exe_path,
+        remote_ip: event.map(|e| e.remote_ip.to_string()),
+        remote_port: event.map(|e| e.remote_port),
+        geo: event.and_then(|e| geoip::lookup(e.remote_ip)),
+        timestamp: Utc::now().timestamp(),
+    }
+}
+
+// ============================================================================
+// INCIDENT MAPPING
+// ============================================================================
+
+/// Threat level của indicator → confidence của verdict
+pub fn level_confidence(level: ThreatLevel) -> f32 {
+    match level {
+        ThreatLevel::Critical => 0.95,
+        ThreatLevel::High => 0.85,
+        ThreatLevel::Medium => 0.7,
+        ThreatLevel::Low => 0.5,
+        ThreatLevel::Unknown => 0.4,
+    }
+}
+
+/// Record cho incident manager: không có feature vector (không ghi vào dataset training)
+fn to_record(m: &IntelMatch) -> DatasetRecord {
+    DatasetRecord {
+        timestamp: (m.timestamp * 1000) as u64,
+        feature_version: FEATURE_VERSION,
+        layout_hash: layout_hash(),
+        features: Vec::new(),
+        baseline_diff: Vec::new(),
+        score: m.confidence,
+        confidence: m.confidence,
+        threat: if m.threat_level >= ThreatLevel::High {
+            ThreatClass::Malicious
+        } else {
+            ThreatClass::Suspicious
+        },
+        user_label: None,
+    }
+}
+
+/// Tag của incident: loại match, nguồn intel, indicator và process (gửi lên cloud qua rule_matches)
+fn match_tags(m: &IntelMatch) -> Vec<String> {
+    let mut tags = vec![
+        "THREAT_INTEL".to_string(),
+        format!("INTEL_{}", m.kind.to_uppercase()),
+        format!("source:{}", m.source),
+        format!("ioc:{}", m.indicator),
+        format!("process:{} ({})", m.process_name, m.pid),
+    ];
+    if let Some(geo) = &m.geo {
+        tags.extend(geo.tags());
+    }
+    tags
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_to_record() {
+        let indicator = ThreatIndicator {
+            indicator_type: super::super::types::IndicatorType::Domain,
+            value: "evil.example".to_string(),
+            threat_level: ThreatLevel::High,
+            source: "MISP".to_string(),
+            first_seen: None,
+            last_seen: None,
+            tags: vec!["c2".to_string()],
+            description: None,
+        };
+        let m = build_match("domain", "evil.example".to_string(), Some(indicator), 42, "agent.exe", None, None);
+        assert_eq!(m.source, "MISP");
+        assert!((m.confidence - 0.85).abs() < f32::EPSILON);
+
+        let record = to_record(&m);
+        assert_eq!(record.threat, ThreatClass::Malicious);
+        assert!((record.confidence - 0.85).abs() < f32::EPSILON);
+        assert!(record.features.is_empty());
+
+        let tags = match_tags(&m);
+        assert!(tags.contains(&"INTEL_DOMAIN".to_string()));
+        assert!(tags.contains(&"source:MISP".to_string()));
+        assert!(tags.contains(&"ioc:evil.example".to_string()));
+
+        let low = build_match("ip", "203.0.113.9".to_string(), None, 7, "x.exe", None, None);
+        assert_eq!(to_record(&low).threat, ThreatClass::Suspicious);
+    }
+}
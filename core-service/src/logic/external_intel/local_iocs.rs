@@ -0,0 +1,476 @@
+//! Local IOC Store
+//!
+//! Mục đích: Incident responder nạp IOC riêng của case (hash, IP, domain, URL)
+//! lên endpoint ngay lập tức, không chờ feed / cloud.
+//!
+//! - Import từ CSV / JSON / danh sách text (tự nhận loại indicator, refang `hxxp`, `[.]`)
+//! - Mỗi lần import gắn source tag (VD: "case-1234"), expiry tùy chọn
+//! - Lưu tại `local_iocs.json`, nạp lại vào threat feed khi khởi động
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use chrono::Utc;
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use super::threat_feed;
+use super::types::{ThreatIndicator, IndicatorType, ThreatLevel};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const STORE_FILE: &str = "local_iocs.json";
+const MAX_IMPORT_FILE_SIZE: u64 = 50 * 1024 * 1024;
+const MAX_REPORTED_ERRORS: usize = 20;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IocFormat {
+    Csv,
+    Json,
+    /// Một indicator mỗi dòng
+    Text,
+}
+
+impl IocFormat {
+    pub fn parse(format: &str, path: &Path) -> Result<Self, String> {
+        match format.to_lowercase().as_str() {
+            "csv" => Ok(IocFormat::Csv),
+            "json" => Ok(IocFormat::Json),
+            "text" | "txt" | "plain" => Ok(IocFormat::Text),
+            "" | "auto" => Ok(match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+                Some("csv") => IocFormat::Csv,
+                Some("json") => IocFormat::Json,
+                _ => IocFormat::Text,
+            }),
+            other => Err(format!("Unsupported IOC format: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LocalIoc {
+    indicator: ThreatIndicator,
+    imported_at: i64,
+    expires_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub source: String,
+    pub imported: usize,
+    /// Đã có trong cùng source (cập nhật expiry)
+    pub updated: usize,
+    pub skipped: usize,
+    pub expires_at: Option<i64>,
+    /// Dòng không nhận dạng được (tối đa `MAX_REPORTED_ERRORS`)
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalIocSource {
+    pub source: String,
+    pub indicators: usize,
+    pub imported_at: i64,
+    pub expires_at: Option<i64>,
+}
+
+/// Source tag → IOC
+static STORE: Lazy<RwLock<HashMap<String, Vec<LocalIoc>>>> =
+    Lazy::new(|| RwLock::new(load_store()));
+
+// ============================================================================
+// PARSING
+// ============================================================================
+
+/// Bản ghi thô trước khi nhận loại
+#[derive(Debug, Default, Deserialize)]
+struct RawIoc {
+    #[serde(alias = "indicator", alias = "ioc")]
+    value: String,
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+    #[serde(default, alias = "comment")]
+    description: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonIocs {
+    Wrapped { indicators: Vec<JsonIoc> },
+    List(Vec<JsonIoc>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonIoc {
+    Plain(String),
+    Object(RawIoc),
+}
+
+fn parse_content(content: &str, format: IocFormat) -> Result<Vec<RawIoc>, String> {
+    match format {
+        IocFormat::Text => Ok(content.lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with("//"))
+            .map(|l| RawIoc { value: l.to_string(), ..Default::default() })
+            .collect()),
+        IocFormat::Json => {
+            let parsed: JsonIocs = serde_json::from_str(content)
+                .map_err(|e| format!("Invalid IOC JSON: {}", e))?;
+            let items = match parsed {
+                JsonIocs::Wrapped { indicators } => indicators,
+                JsonIocs::List(items) => items,
+            };
+            Ok(items.into_iter()
+                .map(|item| match item {
+                    JsonIoc::Plain(value) => RawIoc { value, ..Default::default() },
+                    JsonIoc::Object(raw) => raw,
+                })
+                .collect())
+        }
+        IocFormat::Csv => Ok(parse_csv(content)),
+    }
+}
+
+/// CSV có header (`value|indicator|ioc`, `type`, `description|comment`, `tags`)
+/// hoặc không header (cột đầu là indicator)
+fn parse_csv(content: &str) -> Vec<RawIoc> {
+    let mut lines = content.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')).peekable();
+    let header: Option<Vec<String>> = lines.peek()
+        .map(|l| split_csv_line(l).into_iter().map(|c| c.to_lowercase()).collect::<Vec<_>>())
+        .filter(|cols| cols.iter().any(|c| matches!(c.as_str(), "value" | "indicator" | "ioc")));
+    if header.is_some() {
+        lines.next();
+    }
+    let column = |names: &[&str]| -> Option<usize> {
+        header.as_ref()?.iter().position(|c| names.contains(&c.as_str()))
+    };
+    let value_col = column(&["value", "indicator", "ioc"]).unwrap_or(0);
+    let type_col = column(&["type", "kind"]);
+    let desc_col = column(&["description", "comment"]);
+    let tags_col = column(&["tags", "tag"]);
+
+    lines
+        .filter_map(|line| {
+            let cols = split_csv_line(line);
+            let get = |i: Option<usize>| i.and_then(|i| cols.get(i)).filter(|v| !v.is_empty()).cloned();
+            Some(RawIoc {
+                value: get(Some(value_col))?,
+                kind: get(type_col),
+                description: get(desc_col),
+                tags: get(tags_col)
+                    .map(|t| t.split(['|', ';']).map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+                    .unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// Tách dòng CSV (hỗ trợ field trong dấu nháy kép)
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut current).trim().to_string()),
+            _ => current.push(c),
+        }
+    }
+    fields.push(current.trim().to_string());
+    fields
+}
+
+/// "hxxp://evil[.]com" → "http://evil.com"
+fn refang(value: &str) -> String {
+    value.trim()
+        .replace("[.]", ".")
+        .replace("(.)", ".")
+        .replace("[:]", ":")
+        .replace("hxxps://", "https://")
+        .replace("hxxp://", "http://")
+        .replace("hXXp", "http")
+}
+
+/// Nhận loại indicator (ưu tiên cột `type` nếu có)
+fn classify(value: &str, kind: Option<&str>) -> Option<IndicatorType> {
+    let is_hex = value.chars().all(|c| c.is_ascii_hexdigit());
+    let by_shape = || -> Option<IndicatorType> {
+        if is_hex {
+            return match value.len() {
+                32 => Some(IndicatorType::Md5),
+                40 => Some(IndicatorType::Sha1),
+                64 => Some(IndicatorType::Sha256),
+                _ => None,
+            };
+        }
+        if let Ok(ip) = value.parse::<IpAddr>() {
+            return Some(if ip.is_ipv4() { IndicatorType::IPv4 } else { IndicatorType::IPv6 });
+        }
+        let lower = value.to_lowercase();
+        if lower.starts_with("http://") || lower.starts_with("https://") {
+            return Some(IndicatorType::Url);
+        }
+        let domain_like = lower.contains('.')
+            && !lower.starts_with('.')
+            && lower.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+        domain_like.then_some(IndicatorType::Domain)
+    };
+
+    let detected = by_shape()?;
+    match kind.map(|k| k.to_lowercase()) {
+        None => Some(detected),
+        Some(k) => {
+            let declared = match k.as_str() {
+                "md5" | "sha1" | "sha-1" | "sha256" | "sha-256" | "hash" => return is_hex.then_some(detected),
+                "ip" | "ipv4" | "ipv6" | "ip-dst" | "ip-src" => matches!(detected, IndicatorType::IPv4 | IndicatorType::IPv6),
+                "domain" | "hostname" | "fqdn" => detected == IndicatorType::Domain,
+                "url" | "uri" => detected == IndicatorType::Url,
+                _ => true,
+            };
+            declared.then_some(detected)
+        }
+    }
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Import IOC từ file vào store local + threat feed
+pub fn import_file(path: &Path, format: IocFormat, source: &str, expires_at: Option<i64>) -> Result<ImportReport, String> {
+    let source = source.trim();
+    if source.is_empty() {
+        return Err("IOC source tag is required".to_string());
+    }
+    let size = std::fs::metadata(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?.len();
+    if size > MAX_IMPORT_FILE_SIZE {
+        return Err(format!("IOC file too large ({} bytes)", size));
+    }
+    let content = std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let raw = parse_content(&content, format)?;
+    let report = import_raw(raw, source, expires_at);
+
+    log::info!(
+        "Imported {} IOCs ({} updated, {} skipped) from {} as '{}'",
+        report.imported, report.updated, report.skipped, path.display(), source
+    );
+    Ok(report)
+}
+
+fn import_raw(raw: Vec<RawIoc>, source: &str, expires_at: Option<i64>) -> ImportReport {
+    let now = Utc::now().timestamp();
+    let mut report = ImportReport {
+        source: source.to_string(),
+        imported: 0,
+        updated: 0,
+        skipped: 0,
+        expires_at,
+        errors: Vec::new(),
+    };
+
+    let indicators = {
+        let mut store = STORE.write();
+        let entries = store.entry(source.to_string()).or_default();
+        for item in raw {
+            let value = refang(&item.value);
+            let Some(indicator_type) = classify(&value, item.kind.as_deref()) else {
+                report.skipped += 1;
+                if report.errors.len() < MAX_REPORTED_ERRORS {
+                    report.errors.push(format!("Unrecognized indicator: {}", item.value));
+                }
+                continue;
+            };
+            let value = match indicator_type {
+                IndicatorType::Url => value,
+                _ => value.to_lowercase(),
+            };
+
+            if let Some(existing) = entries.iter_mut().find(|e| e.indicator.value.eq_ignore_ascii_case(&value)) {
+                existing.expires_at = expires_at;
+                existing.indicator.last_seen = Some(now);
+                report.updated += 1;
+                continue;
+            }
+
+            let mut tags = item.tags;
+            tags.push("local_ioc".to_string());
+            entries.push(LocalIoc {
+                indicator: ThreatIndicator {
+                    indicator_type,
+                    value,
+                    threat_level: ThreatLevel::High,
+                    source: source.to_string(),
+                    first_seen: Some(now),
+                    last_seen: Some(now),
+                    tags,
+                    description: item.description,
+                },
+                imported_at: now,
+                expires_at,
+            });
+            report.imported += 1;
+        }
+        let indicators = active(entries, now);
+        save_store(&store);
+        indicators
+    };
+
+    threat_feed::replace_source_indicators(source, indicators);
+    report
+}
+
+fn active(entries: &[LocalIoc], now: i64) -> Vec<ThreatIndicator> {
+    entries.iter()
+        .filter(|e| e.expires_at.map_or(true, |t| t > now))
+        .map(|e| e.indicator.clone())
+        .collect()
+}
+
+/// Nạp store vào threat feed (khi khởi động)
+pub fn restore() {
+    let now = Utc::now().timestamp();
+    let sources: Vec<(String, Vec<ThreatIndicator>)> = STORE.read().iter()
+        .map(|(source, entries)| (source.clone(), active(entries, now)))
+        .collect();
+    for (source, indicators) in sources {
+        threat_feed::replace_source_indicators(&source, indicators);
+    }
+}
+
+/// Xóa IOC đã hết hạn khỏi store + threat feed
+pub fn expire() {
+    let now = Utc::now().timestamp();
+    let changed: Vec<(String, Vec<ThreatIndicator>)> = {
+        let mut store = STORE.write();
+        let mut changed = Vec::new();
+        for (source, entries) in store.iter_mut() {
+            let before = entries.len();
+            entries.retain(|e| e.expires_at.map_or(true, |t| t > now));
+            if entries.len() != before {
+                changed.push((source.clone(), active(entries, now)));
+            }
+        }
+        store.retain(|_, entries| !entries.is_empty());
+        if !changed.is_empty() {
+            save_store(&store);
+        }
+        changed
+    };
+    for (source, indicators) in changed {
+        log::info!("Local IOC source '{}': expired entries removed", source);
+        threat_feed::replace_source_indicators(&source, indicators);
+    }
+}
+
+/// Xóa toàn bộ IOC của một source tag
+pub fn remove_source(source: &str) -> bool {
+    let removed = {
+        let mut store = STORE.write();
+        let removed = store.remove(source).is_some();
+        if removed {
+            save_store(&store);
+        }
+        removed
+    };
+    if removed {
+        threat_feed::replace_source_indicators(source, Vec::new());
+    }
+    removed
+}
+
+pub fn list_sources() -> Vec<LocalIocSource> {
+    let mut sources: Vec<LocalIocSource> = STORE.read().iter()
+        .map(|(source, entries)| LocalIocSource {
+            source: source.clone(),
+            indicators: entries.len(),
+            imported_at: entries.iter().map(|e| e.imported_at).max().unwrap_or(0),
+            expires_at: entries.iter().filter_map(|e| e.expires_at).max(),
+        })
+        .collect();
+    sources.sort_by(|a, b| b.imported_at.cmp(&a.imported_at));
+    sources
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn store_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(STORE_FILE)
+}
+
+fn load_store() -> HashMap<String, Vec<LocalIoc>> {
+    std::fs::read_to_string(store_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_store(store: &HashMap<String, Vec<LocalIoc>>) {
+    let path = store_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(store) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_and_refang() {
+        assert_eq!(classify(&refang("hxxp://evil[.]example/a"), None), Some(IndicatorType::Url));
+        assert_eq!(classify(&refang("bad[.]example"), None), Some(IndicatorType::Domain));
+        assert_eq!(classify("203.0.113.9", Some("ip")), Some(IndicatorType::IPv4));
+        assert_eq!(classify("d41d8cd98f00b204e9800998ecf8427e", None), Some(IndicatorType::Md5));
+        assert_eq!(classify("evil.example", Some("ip")), None);
+        assert_eq!(classify("not an ioc", None), None);
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        let csv = "type,value,comment\nsha256,E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855,\"dropper, stage 1\"\ndomain,c2.example,\n";
+        let rows = parse_content(csv, IocFormat::Csv).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].description.as_deref(), Some("dropper, stage 1"));
+        assert_eq!(rows[1].value, "c2.example");
+
+        let json = r#"{"indicators": ["198.51.100.1", {"value": "http://x.example/p", "tags": ["case"]}]}"#;
+        let rows = parse_content(json, IocFormat::Json).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].tags, vec!["case".to_string()]);
+
+        let text = "# case 42\nevil.example\n\n10.0.0.5\n";
+        assert_eq!(parse_content(text, IocFormat::Text).unwrap().len(), 2);
+
+        assert_eq!(IocFormat::parse("auto", Path::new("iocs.CSV")).unwrap(), IocFormat::Csv);
+        assert!(IocFormat::parse("xml", Path::new("a")).is_err());
+    }
+}
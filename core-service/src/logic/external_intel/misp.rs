@@ -0,0 +1,520 @@
+//! MISP Integration Module
+//!
+//! Mục đích: Pull attribute (hash, IP, domain, URL) từ MISP instance của tổ chức
+//! vào threat feed, và push sighting ngược lại khi agent thấy indicator khớp.
+//!
+//! Cấu hình qua section `misp` của cloud policy (enterprise):
+//! `{ "enabled": true, "url": "https://misp.corp", "api_key": "...", "lookback_days": 30 }`
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use chrono::Utc;
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use super::threat_feed;
+use super::types::{ThreatIndicator, IndicatorType, ThreatLevel};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Tên nguồn của indicator trong threat feed
+pub const SOURCE: &str = "MISP";
+
+/// Số attribute mỗi trang restSearch
+const PAGE_SIZE: usize = 5000;
+const MAX_PAGES: usize = 20;
+
+/// Cùng một value chỉ báo sighting một lần trong khoảng này
+const SIGHTING_DEDUP_SECS: i64 = 3600;
+const MAX_PENDING_SIGHTINGS: usize = 1000;
+
+const TICK: Duration = Duration::from_secs(60);
+
+// ============================================================================
+// CONFIG
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MispConfig {
+    pub enabled: bool,
+    pub url: String,
+    pub api_key: String,
+    /// Chu kỳ pull (phút)
+    pub pull_interval_mins: u64,
+    /// Chỉ lấy attribute cập nhật trong N ngày
+    pub lookback_days: u32,
+    /// Chỉ lấy attribute có cờ to_ids
+    pub to_ids_only: bool,
+    /// Lọc theo tag MISP (rỗng = tất cả)
+    pub tags: Vec<String>,
+    pub push_sightings: bool,
+}
+
+impl Default for MispConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            api_key: String::new(),
+            pull_interval_mins: 60,
+            lookback_days: 30,
+            to_ids_only: true,
+            tags: Vec::new(),
+            push_sightings: true,
+        }
+    }
+}
+
+impl MispConfig {
+    fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if !self.url.starts_with("https://") && !self.url.starts_with("http://") {
+            return Err("MISP url must be http(s)".to_string());
+        }
+        if self.api_key.trim().is_empty() {
+            return Err("MISP api_key is required".to_string());
+        }
+        if self.pull_interval_mins == 0 {
+            return Err("MISP pull_interval_mins must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.url.trim_end_matches('/'), path)
+    }
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+#[derive(Default)]
+struct MispState {
+    config: MispConfig,
+    last_pull: Option<i64>,
+    last_error: Option<String>,
+    attributes: usize,
+    /// Value (lowercase) → attribute id, để gửi sighting theo id
+    attribute_ids: HashMap<String, String>,
+    pending_sightings: VecDeque<(String, i64)>,
+    /// Value → lần cuối đưa vào queue sighting
+    last_sighted: HashMap<String, i64>,
+    sightings_sent: u64,
+}
+
+static STATE: Lazy<RwLock<MispState>> = Lazy::new(|| RwLock::new(MispState::default()));
+
+static PULLING: AtomicBool = AtomicBool::new(false);
+static SCHEDULER_STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MispStatus {
+    pub enabled: bool,
+    pub url: Option<String>,
+    pub last_pull: Option<i64>,
+    pub last_error: Option<String>,
+    pub attributes: usize,
+    pub sightings_pending: usize,
+    pub sightings_sent: u64,
+}
+
+// ============================================================================
+// API RESPONSE TYPES
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+struct RestSearchResponse {
+    response: RestSearchAttributes,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestSearchAttributes {
+    #[serde(rename = "Attribute", default)]
+    attributes: Vec<MispAttribute>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MispAttribute {
+    id: String,
+    #[serde(default)]
+    event_id: Option<String>,
+    #[serde(rename = "type")]
+    attr_type: String,
+    value: String,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    comment: Option<String>,
+    /// Unix seconds dạng string
+    #[serde(default)]
+    timestamp: Option<String>,
+    #[serde(rename = "Tag", default)]
+    tags: Vec<MispTag>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MispTag {
+    name: String,
+}
+
+// ============================================================================
+// PULL
+// ============================================================================
+
+/// Pull attribute từ MISP → thay toàn bộ indicator nguồn MISP trong threat feed
+pub fn pull() -> Result<usize, String> {
+    let config = STATE.read().config.clone();
+    if !config.enabled {
+        return Err("MISP integration is not enabled".to_string());
+    }
+    if PULLING.swap(true, Ordering::SeqCst) {
+        return Err("MISP pull already in progress".to_string());
+    }
+
+    let result = fetch_attributes(&config);
+    PULLING.store(false, Ordering::SeqCst);
+
+    let mut state = STATE.write();
+    state.last_pull = Some(Utc::now().timestamp());
+    match result {
+        Ok(attributes) => {
+            let mut ids = HashMap::new();
+            let indicators: Vec<ThreatIndicator> = attributes.iter()
+                .filter_map(|attr| {
+                    let indicator = to_indicator(attr)?;
+                    ids.insert(indicator.value.to_lowercase(), attr.id.clone());
+                    Some(indicator)
+                })
+                .collect();
+            let count = indicators.len();
+
+            state.attributes = count;
+            state.attribute_ids = ids;
+            state.last_error = None;
+            drop(state);
+
+            threat_feed::replace_source_indicators(SOURCE, indicators);
+            log::info!("MISP: pulled {} indicators from {}", count, config.url);
+            Ok(count)
+        }
+        Err(e) => {
+            state.last_error = Some(e.clone());
+            log::warn!("MISP pull failed: {}", e);
+            Err(e)
+        }
+    }
+}
+
+fn fetch_attributes(config: &MispConfig) -> Result<Vec<MispAttribute>, String> {
+    let mut all = Vec::new();
+
+    for page in 1..=MAX_PAGES {
+        let mut body = serde_json::json!({
+            "returnFormat": "json",
+            "type": ["md5", "sha1", "sha256", "filename|md5", "filename|sha1", "filename|sha256",
+                     "ip-src", "ip-dst", "ip-dst|port", "domain", "hostname", "domain|ip", "url"],
+            "last": format!("{}d", config.lookback_days),
+            "deleted": false,
+            "includeEventTags": true,
+            "limit": PAGE_SIZE,
+            "page": page,
+        });
+        if config.to_ids_only {
+            body["to_ids"] = serde_json::json!(true);
+        }
+        if !config.tags.is_empty() {
+            body["tags"] = serde_json::json!(config.tags);
+        }
+
+        let response = ureq::post(&config.endpoint("attributes/restSearch"))
+            .set("Authorization", config.api_key.trim())
+            .set("Accept", "application/json")
+            .set("Content-Type", "application/json")
+            .timeout(Duration::from_secs(60))
+            .send_string(&body.to_string())
+            .map_err(|e| match e {
+                ureq::Error::Status(403, _) | ureq::Error::Status(401, _) => "MISP rejected API key".to_string(),
+                e => e.to_string(),
+            })?;
+
+        let text = response.into_string().map_err(|e| e.to_string())?;
+        let parsed: RestSearchResponse = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid MISP response: {}", e))?;
+
+        let received = parsed.response.attributes.len();
+        all.extend(parsed.response.attributes);
+        if received < PAGE_SIZE {
+            break;
+        }
+    }
+
+    Ok(all)
+}
+
+/// MISP attribute → indicator (attribute ghép `a|b` lấy phần có giá trị tra cứu)
+fn to_indicator(attr: &MispAttribute) -> Option<ThreatIndicator> {
+    let (indicator_type, value) = match attr.attr_type.as_str() {
+        "md5" => (IndicatorType::Md5, attr.value.as_str()),
+        "sha1" => (IndicatorType::Sha1, attr.value.as_str()),
+        "sha256" => (IndicatorType::Sha256, attr.value.as_str()),
+        "filename|md5" => (IndicatorType::Md5, attr.value.split('|').nth(1)?),
+        "filename|sha1" => (IndicatorType::Sha1, attr.value.split('|').nth(1)?),
+        "filename|sha256" => (IndicatorType::Sha256, attr.value.split('|').nth(1)?),
+        "ip-src" | "ip-dst" | "ip-dst|port" => {
+            let ip = attr.value.split('|').next()?;
+            let kind = if ip.contains(':') { IndicatorType::IPv6 } else { IndicatorType::IPv4 };
+            (kind, ip)
+        }
+        "domain" | "hostname" | "domain|ip" => (IndicatorType::Domain, attr.value.split('|').next()?),
+        "url" => (IndicatorType::Url, attr.value.as_str()),
+        _ => return None,
+    };
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    let tags: Vec<String> = attr.tags.iter().map(|t| t.name.clone()).collect();
+    let seen = attr.timestamp.as_deref().and_then(|t| t.parse::<i64>().ok());
+    let description = match (&attr.event_id, &attr.comment) {
+        (Some(event), Some(comment)) if !comment.is_empty() => Some(format!("MISP event {}: {}", event, comment)),
+        (Some(event), _) => Some(format!("MISP event {}", event)),
+        (None, comment) => comment.clone().filter(|c| !c.is_empty()),
+    };
+
+    Some(ThreatIndicator {
+        indicator_type,
+        value: value.to_string(),
+        threat_level: threat_level(&tags, attr.category.as_deref()),
+        source: SOURCE.to_string(),
+        first_seen: None,
+        last_seen: seen,
+        tags,
+        description,
+    })
+}
+
+/// Threat level theo tag TLP/threat-level hoặc category của attribute
+fn threat_level(tags: &[String], category: Option<&str>) -> ThreatLevel {
+    let tags_lower: Vec<String> = tags.iter().map(|t| t.to_lowercase()).collect();
+    if tags_lower.iter().any(|t| t.contains("threat-level=\"high\"") || t.contains("apt") || t.contains("ransomware")) {
+        ThreatLevel::Critical
+    } else if category == Some("Payload delivery") || category == Some("Network activity") {
+        ThreatLevel::High
+    } else {
+        ThreatLevel::Medium
+    }
+}
+
+// ============================================================================
+// SIGHTINGS
+// ============================================================================
+
+/// Ghi nhận agent thấy indicator nguồn MISP (gửi lên MISP bởi scheduler)
+pub fn record_sighting(value: &str) {
+    let mut state = STATE.write();
+    if !state.config.enabled || !state.config.push_sightings {
+        return;
+    }
+
+    let key = value.to_lowercase();
+    let now = Utc::now().timestamp();
+    if state.last_sighted.get(&key).is_some_and(|t| now - t < SIGHTING_DEDUP_SECS) {
+        return;
+    }
+    state.last_sighted.insert(key.clone(), now);
+    if state.pending_sightings.len() >= MAX_PENDING_SIGHTINGS {
+        state.pending_sightings.pop_front();
+    }
+    state.pending_sightings.push_back((key, now));
+}
+
+/// Gửi các sighting đang chờ (POST /sightings/add, theo attribute id nếu có)
+fn flush_sightings() {
+    let (config, batch) = {
+        let mut state = STATE.write();
+        let pending: Vec<(String, i64)> = state.pending_sightings.drain(..).collect();
+        let batch: Vec<(String, i64, Option<String>)> = pending.into_iter()
+            .map(|(value, at)| {
+                let id = state.attribute_ids.get(&value).cloned();
+                (value, at, id)
+            })
+            .collect();
+        (state.config.clone(), batch)
+    };
+    if batch.is_empty() || !config.enabled {
+        return;
+    }
+
+    let mut sent = 0u64;
+    let mut failed = Vec::new();
+    for (value, at, id) in batch {
+        let body = match &id {
+            Some(id) => serde_json::json!({ "id": id, "source": "One-Shield", "timestamp": at }),
+            None => serde_json::json!({ "values": [value], "source": "One-Shield", "timestamp": at }),
+        };
+        let result = ureq::post(&config.endpoint("sightings/add"))
+            .set("Authorization", config.api_key.trim())
+            .set("Accept", "application/json")
+            .set("Content-Type", "application/json")
+            .timeout(Duration::from_secs(15))
+            .send_string(&body.to_string());
+        match result {
+            Ok(_) => sent += 1,
+            Err(e) => {
+                log::debug!("MISP sighting for {} failed: {}", value, e);
+                failed.push((value, at));
+            }
+        }
+    }
+
+    let mut state = STATE.write();
+    state.sightings_sent += sent;
+    // Lỗi mạng → giữ lại lần sau (có giới hạn)
+    for item in failed.into_iter().rev() {
+        if state.pending_sightings.len() < MAX_PENDING_SIGHTINGS {
+            state.pending_sightings.push_front(item);
+        }
+    }
+    if sent > 0 {
+        log::info!("MISP: reported {} sightings", sent);
+    }
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Áp dụng section `misp` của cloud policy (None / không hợp lệ → tắt)
+pub fn apply_cloud_config(config: Option<&serde_json::Value>) {
+    let parsed = match config {
+        Some(value) => match serde_json::from_value::<MispConfig>(value.clone()) {
+            Ok(config) if config.validate().is_ok() => config,
+            Ok(config) => {
+                log::warn!("Ignoring invalid MISP config from cloud policy: {}", config.validate().unwrap_err());
+                MispConfig::default()
+            }
+            Err(e) => {
+                log::warn!("Ignoring invalid MISP config from cloud policy: {}", e);
+                MispConfig::default()
+            }
+        },
+        None => MispConfig::default(),
+    };
+
+    let mut state = STATE.write();
+    if state.config == parsed {
+        return;
+    }
+    let was_enabled = state.config.enabled;
+    let changed_target = state.config.url != parsed.url || state.config.api_key != parsed.api_key;
+    state.config = parsed;
+    if changed_target {
+        // Instance khác → pull lại ngay ở tick kế tiếp
+        state.last_pull = None;
+    }
+    let enabled = state.config.enabled;
+    drop(state);
+
+    if was_enabled && !enabled {
+        threat_feed::replace_source_indicators(SOURCE, Vec::new());
+        log::info!("MISP integration disabled by policy");
+    } else if enabled {
+        log::info!("MISP integration configured by policy");
+    }
+}
+
+/// Scheduler: pull theo `pull_interval_mins`, flush sighting mỗi tick
+pub fn start() {
+    if SCHEDULER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(|| loop {
+        std::thread::sleep(TICK);
+
+        let (enabled, due) = {
+            let state = STATE.read();
+            let interval = state.config.pull_interval_mins as i64 * 60;
+            let due = state.last_pull
+                .map(|t| Utc::now().timestamp() - t >= interval)
+                .unwrap_or(true);
+            (state.config.enabled, due)
+        };
+        if !enabled {
+            continue;
+        }
+        if due {
+            let _ = pull();
+        }
+        flush_sightings();
+    });
+}
+
+pub fn get_status() -> MispStatus {
+    let state = STATE.read();
+    MispStatus {
+        enabled: state.config.enabled,
+        url: (!state.config.url.is_empty()).then(|| state.config.url.clone()),
+        last_pull: state.last_pull,
+        last_error: state.last_error.clone(),
+        attributes: state.attributes,
+        sightings_pending: state.pending_sightings.len(),
+        sightings_sent: state.sightings_sent,
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attr(attr_type: &str, value: &str) -> MispAttribute {
+        MispAttribute {
+            id: "42".to_string(),
+            event_id: Some("7".to_string()),
+            attr_type: attr_type.to_string(),
+            value: value.to_string(),
+            category: Some("Network activity".to_string()),
+            comment: None,
+            timestamp: Some("1700000000".to_string()),
+            tags: vec![MispTag { name: "tlp:amber".to_string() }],
+        }
+    }
+
+    #[test]
+    fn test_attribute_mapping() {
+        let hash = to_indicator(&attr("filename|sha256", "evil.exe|ABCDEF")).unwrap();
+        assert_eq!(hash.indicator_type, IndicatorType::Sha256);
+        assert_eq!(hash.value, "ABCDEF");
+        assert_eq!(hash.source, SOURCE);
+        assert_eq!(hash.last_seen, Some(1_700_000_000));
+
+        let ip = to_indicator(&attr("ip-dst|port", "203.0.113.5|443")).unwrap();
+        assert_eq!((ip.indicator_type, ip.value.as_str()), (IndicatorType::IPv4, "203.0.113.5"));
+        assert_eq!(ip.threat_level, ThreatLevel::High);
+
+        assert!(to_indicator(&attr("email-src", "a@b.c")).is_none());
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(MispConfig::default().validate().is_ok());
+        let config = MispConfig { enabled: true, url: "https://misp.corp/".to_string(), ..Default::default() };
+        assert!(config.validate().is_err());
+        let config = MispConfig { api_key: "key".to_string(), ..config };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.endpoint("sightings/add"), "https://misp.corp/sightings/add");
+    }
+}
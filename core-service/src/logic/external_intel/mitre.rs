@@ -3,248 +3,205 @@
 //! Mục đích: Map detections và alerts với MITRE ATT&CK framework
 //!
 //! Features:
-//! - Technique database (50+ techniques)
+//! - Technique database: ATT&CK STIX 2.1 dataset (nhúng sẵn, cập nhật bằng
+//!   `enterprise-attack.json` chính thức qua `update_dataset`)
 //! - Tag to technique mapping
 //! - Enrichment for alerts
+//! - Technique từ offline intel bundle (bổ sung / cập nhật database built-in)
 
 use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
 
 use super::types::{MitreTechnique, MitreTactic};
 
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Dataset nhúng sẵn (STIX 2.1, cùng format với `enterprise-attack.json` chính thức)
+const EMBEDDED_DATASET: &str = include_str!("data/enterprise-attack.json");
+/// Dataset đã cập nhật, lưu trong data dir
+const DATASET_FILE: &str = "enterprise-attack.json";
+
 // ============================================================================
 // MITRE TECHNIQUE DATABASE
 // ============================================================================
 
-/// All known MITRE techniques (sample subset)
-pub static MITRE_TECHNIQUES: Lazy<HashMap<&'static str, MitreTechnique>> = Lazy::new(|| {
-    let mut m = HashMap::new();
-
-    // Execution
-    m.insert("T1059", MitreTechnique {
-        id: "T1059".to_string(),
-        name: "Command and Scripting Interpreter".to_string(),
-        tactic: MitreTactic::Execution,
-        description: "Adversaries may abuse command and script interpreters to execute commands, scripts, or binaries.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1059/".to_string(),
-        sub_techniques: vec!["T1059.001".to_string(), "T1059.003".to_string(), "T1059.005".to_string()],
-    });
-
-    m.insert("T1059.001", MitreTechnique {
-        id: "T1059.001".to_string(),
-        name: "PowerShell".to_string(),
-        tactic: MitreTactic::Execution,
-        description: "Adversaries may abuse PowerShell commands and scripts for execution.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1059/001/".to_string(),
-        sub_techniques: vec![],
-    });
-
-    m.insert("T1059.003", MitreTechnique {
-        id: "T1059.003".to_string(),
-        name: "Windows Command Shell".to_string(),
-        tactic: MitreTactic::Execution,
-        description: "Adversaries may abuse the Windows command shell for execution.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1059/003/".to_string(),
-        sub_techniques: vec![],
-    });
-
-    m.insert("T1059.005", MitreTechnique {
-        id: "T1059.005".to_string(),
-        name: "Visual Basic".to_string(),
-        tactic: MitreTactic::Execution,
-        description: "Adversaries may abuse VB scripts for execution.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1059/005/".to_string(),
-        sub_techniques: vec![],
-    });
+#[derive(Debug, Clone, Serialize)]
+pub struct MitreDatasetInfo {
+    pub name: String,
+    /// `x_mitre_version` của collection ("15.1", ...)
+    pub version: String,
+    /// "embedded" | "file"
+    pub source: &'static str,
+    pub techniques: usize,
+    pub sub_techniques: usize,
+}
 
-    // Persistence
-    m.insert("T1547", MitreTechnique {
-        id: "T1547".to_string(),
-        name: "Boot or Logon Autostart Execution".to_string(),
-        tactic: MitreTactic::Persistence,
-        description: "Adversaries may configure system settings to automatically execute a program during system boot or logon.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1547/".to_string(),
-        sub_techniques: vec!["T1547.001".to_string()],
-    });
-
-    m.insert("T1547.001", MitreTechnique {
-        id: "T1547.001".to_string(),
-        name: "Registry Run Keys / Startup Folder".to_string(),
-        tactic: MitreTactic::Persistence,
-        description: "Adversaries may achieve persistence by adding a program to a startup folder or referencing it with a Registry run key.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1547/001/".to_string(),
-        sub_techniques: vec![],
-    });
-
-    m.insert("T1543.003", MitreTechnique {
-        id: "T1543.003".to_string(),
-        name: "Windows Service".to_string(),
-        tactic: MitreTactic::Persistence,
-        description: "Adversaries may create or modify Windows services to repeatedly execute malicious payloads.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1543/003/".to_string(),
-        sub_techniques: vec![],
-    });
-
-    m.insert("T1053.005", MitreTechnique {
-        id: "T1053.005".to_string(),
-        name: "Scheduled Task".to_string(),
-        tactic: MitreTactic::Persistence,
-        description: "Adversaries may abuse the Windows Task Scheduler to perform task scheduling for execution.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1053/005/".to_string(),
-        sub_techniques: vec![],
-    });
+struct MitreDataset {
+    info: MitreDatasetInfo,
+    techniques: HashMap<String, MitreTechnique>,
+}
 
-    // Defense Evasion
-    m.insert("T1055", MitreTechnique {
-        id: "T1055".to_string(),
-        name: "Process Injection".to_string(),
-        tactic: MitreTactic::DefenseEvasion,
-        description: "Adversaries may inject code into processes in order to evade process-based defenses.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1055/".to_string(),
-        sub_techniques: vec!["T1055.001".to_string(), "T1055.012".to_string()],
-    });
-
-    m.insert("T1218", MitreTechnique {
-        id: "T1218".to_string(),
-        name: "System Binary Proxy Execution".to_string(),
-        tactic: MitreTactic::DefenseEvasion,
-        description: "Adversaries may bypass process and/or signature-based defenses by proxying execution of malicious content with signed, or otherwise trusted, binaries.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1218/".to_string(),
-        sub_techniques: vec!["T1218.003".to_string(), "T1218.005".to_string(), "T1218.010".to_string(), "T1218.011".to_string()],
-    });
-
-    m.insert("T1218.005", MitreTechnique {
-        id: "T1218.005".to_string(),
-        name: "Mshta".to_string(),
-        tactic: MitreTactic::DefenseEvasion,
-        description: "Adversaries may abuse mshta.exe to proxy execution of malicious .hta files.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1218/005/".to_string(),
-        sub_techniques: vec![],
-    });
-
-    m.insert("T1218.010", MitreTechnique {
-        id: "T1218.010".to_string(),
-        name: "Regsvr32".to_string(),
-        tactic: MitreTactic::DefenseEvasion,
-        description: "Adversaries may abuse Regsvr32.exe to proxy execution of malicious code.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1218/010/".to_string(),
-        sub_techniques: vec![],
-    });
-
-    m.insert("T1218.011", MitreTechnique {
-        id: "T1218.011".to_string(),
-        name: "Rundll32".to_string(),
-        tactic: MitreTactic::DefenseEvasion,
-        description: "Adversaries may abuse rundll32.exe to proxy execution of malicious code.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1218/011/".to_string(),
-        sub_techniques: vec![],
-    });
+static DATASET: Lazy<RwLock<MitreDataset>> = Lazy::new(|| RwLock::new(load_dataset()));
 
-    // Credential Access
-    m.insert("T1003", MitreTechnique {
-        id: "T1003".to_string(),
-        name: "OS Credential Dumping".to_string(),
-        tactic: MitreTactic::CredentialAccess,
-        description: "Adversaries may attempt to dump credentials to obtain account login and credential material.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1003/".to_string(),
-        sub_techniques: vec!["T1003.001".to_string()],
-    });
-
-    m.insert("T1003.001", MitreTechnique {
-        id: "T1003.001".to_string(),
-        name: "LSASS Memory".to_string(),
-        tactic: MitreTactic::CredentialAccess,
-        description: "Adversaries may attempt to access credential material stored in the process memory of LSASS.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1003/001/".to_string(),
-        sub_techniques: vec![],
-    });
-
-    m.insert("T1056", MitreTechnique {
-        id: "T1056".to_string(),
-        name: "Input Capture".to_string(),
-        tactic: MitreTactic::Collection,
-        description: "Adversaries may use methods of capturing user input to obtain credentials or collect information.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1056/".to_string(),
-        sub_techniques: vec!["T1056.001".to_string()],
-    });
-
-    m.insert("T1056.001", MitreTechnique {
-        id: "T1056.001".to_string(),
-        name: "Keylogging".to_string(),
-        tactic: MitreTactic::Collection,
-        description: "Adversaries may log user keystrokes to intercept credentials as the user types them.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1056/001/".to_string(),
-        sub_techniques: vec![],
-    });
-
-    // Command and Control
-    m.insert("T1071", MitreTechnique {
-        id: "T1071".to_string(),
-        name: "Application Layer Protocol".to_string(),
-        tactic: MitreTactic::CommandAndControl,
-        description: "Adversaries may communicate using OSI application layer protocols to avoid detection.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1071/".to_string(),
-        sub_techniques: vec!["T1071.001".to_string()],
-    });
-
-    m.insert("T1071.001", MitreTechnique {
-        id: "T1071.001".to_string(),
-        name: "Web Protocols".to_string(),
-        tactic: MitreTactic::CommandAndControl,
-        description: "Adversaries may communicate using application layer protocols associated with web traffic.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1071/001/".to_string(),
-        sub_techniques: vec![],
-    });
-
-    m.insert("T1105", MitreTechnique {
-        id: "T1105".to_string(),
-        name: "Ingress Tool Transfer".to_string(),
-        tactic: MitreTactic::CommandAndControl,
-        description: "Adversaries may transfer tools or other files from an external system into a compromised environment.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1105/".to_string(),
-        sub_techniques: vec![],
-    });
+/// Object trong STIX bundle - chỉ đọc field cần cho `attack-pattern` / `x-mitre-collection`
+#[derive(Debug, Deserialize)]
+struct StixBundle {
+    #[serde(default)]
+    objects: Vec<StixObject>,
+}
 
-    // Discovery
-    m.insert("T1087", MitreTechnique {
-        id: "T1087".to_string(),
-        name: "Account Discovery".to_string(),
-        tactic: MitreTactic::Discovery,
-        description: "Adversaries may attempt to get a listing of valid accounts on a system.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1087/".to_string(),
-        sub_techniques: vec![],
-    });
-
-    m.insert("T1047", MitreTechnique {
-        id: "T1047".to_string(),
-        name: "Windows Management Instrumentation".to_string(),
-        tactic: MitreTactic::Execution,
-        description: "Adversaries may abuse WMI to execute malicious commands and payloads.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1047/".to_string(),
-        sub_techniques: vec![],
-    });
+#[derive(Debug, Deserialize)]
+struct StixObject {
+    #[serde(rename = "type")]
+    object_type: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    kill_chain_phases: Vec<KillChainPhase>,
+    #[serde(default)]
+    external_references: Vec<ExternalReference>,
+    #[serde(default)]
+    revoked: bool,
+    #[serde(default)]
+    x_mitre_deprecated: bool,
+    #[serde(default)]
+    x_mitre_version: Option<String>,
+}
 
-    // Impact
-    m.insert("T1496", MitreTechnique {
-        id: "T1496".to_string(),
-        name: "Resource Hijacking".to_string(),
-        tactic: MitreTactic::Impact,
-        description: "Adversaries may leverage compute resources for purposes such as cryptocurrency mining.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1496/".to_string(),
-        sub_techniques: vec![],
-    });
-
-    m.insert("T1204", MitreTechnique {
-        id: "T1204".to_string(),
-        name: "User Execution".to_string(),
-        tactic: MitreTactic::Execution,
-        description: "An adversary may rely upon specific actions by a user in order to gain execution.".to_string(),
-        url: "https://attack.mitre.org/techniques/T1204/".to_string(),
-        sub_techniques: vec![],
-    });
-
-    m
-});
+#[derive(Debug, Deserialize)]
+struct KillChainPhase {
+    kill_chain_name: String,
+    phase_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExternalReference {
+    source_name: String,
+    #[serde(default)]
+    external_id: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+/// STIX bundle ATT&CK → technique (bỏ technique revoked / deprecated)
+fn parse_stix_bundle(content: &str, source: &'static str) -> Result<MitreDataset, String> {
+    let bundle: StixBundle = serde_json::from_str(content).map_err(|e| format!("Invalid STIX bundle: {}", e))?;
+
+    let mut name = "Enterprise ATT&CK".to_string();
+    let mut version = "unknown".to_string();
+    let mut techniques: HashMap<String, MitreTechnique> = HashMap::new();
+
+    for object in bundle.objects {
+        match object.object_type.as_str() {
+            "x-mitre-collection" => {
+                name = object.name.unwrap_or(name);
+                version = object.x_mitre_version.unwrap_or(version);
+            }
+            "attack-pattern" if !object.revoked && !object.x_mitre_deprecated => {
+                let Some(reference) = object.external_references.iter()
+                    .find(|r| r.source_name == "mitre-attack" && r.external_id.is_some())
+                else {
+                    continue;
+                };
+                let tactics: Vec<MitreTactic> = object.kill_chain_phases.iter()
+                    .filter(|p| p.kill_chain_name == "mitre-attack")
+                    .filter_map(|p| MitreTactic::from_phase_name(&p.phase_name))
+                    .collect();
+                let Some(&tactic) = tactics.first() else {
+                    continue;
+                };
+                let id = reference.external_id.clone().unwrap_or_default();
+                techniques.insert(id.clone(), MitreTechnique {
+                    id,
+                    name: object.name.unwrap_or_default(),
+                    tactic,
+                    description: summarize_description(object.description.as_deref().unwrap_or_default()),
+                    url: reference.url.clone().unwrap_or_default(),
+                    sub_techniques: Vec::new(),
+                    tactics,
+                });
+            }
+            _ => {}
+        }
+    }
+    if techniques.is_empty() {
+        return Err("STIX bundle contains no ATT&CK techniques".to_string());
+    }
+
+    // Sub-technique → parent (T1059.001 → T1059)
+    let mut children: Vec<(String, String)> = techniques.keys()
+        .filter_map(|id| id.split_once('.').map(|(parent, _)| (parent.to_string(), id.clone())))
+        .collect();
+    children.sort();
+    let sub_count = children.len();
+    for (parent, child) in children {
+        if let Some(technique) = techniques.get_mut(&parent) {
+            technique.sub_techniques.push(child);
+        }
+    }
+
+    Ok(MitreDataset {
+        info: MitreDatasetInfo {
+            name,
+            version,
+            source,
+            techniques: techniques.len(),
+            sub_techniques: sub_count,
+        },
+        techniques,
+    })
+}
+
+/// Đoạn đầu của description, bỏ `(Citation: ...)`
+fn summarize_description(description: &str) -> String {
+    let first = description.split("\n\n").next().unwrap_or_default();
+    let mut out = String::with_capacity(first.len());
+    let mut rest = first;
+    while let Some(start) = rest.find("(Citation:") {
+        out.push_str(&rest[..start]);
+        rest = rest[start..].find(')').map(|end| &rest[start + end + 1..]).unwrap_or("");
+    }
+    out.push_str(rest);
+    out.split_whitespace().collect::<Vec<_>>().join(" ").replace(" .", ".")
+}
+
+fn load_dataset() -> MitreDataset {
+    if let Ok(content) = fs::read_to_string(dataset_path()) {
+        match parse_stix_bundle(&content, "file") {
+            Ok(dataset) => return dataset,
+            Err(e) => log::warn!("Ignoring stored MITRE dataset: {}", e),
+        }
+    }
+    parse_stix_bundle(EMBEDDED_DATASET, "embedded").unwrap_or_else(|e| {
+        log::error!("Embedded MITRE dataset invalid: {}", e);
+        MitreDataset {
+            info: MitreDatasetInfo {
+                name: String::new(),
+                version: String::new(),
+                source: "embedded",
+                techniques: 0,
+                sub_techniques: 0,
+            },
+            techniques: HashMap::new(),
+        }
+    })
+}
+
+fn dataset_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join("mitre")
+        .join(DATASET_FILE)
+}
 
 // ============================================================================
 // TAG TO TECHNIQUE MAPPING
@@ -294,13 +251,50 @@ pub const TAG_TO_MITRE: &[(&str, &str)] = &[
     ("TOOL_DOWNLOAD", "T1105"),
 ];
 
+/// Technique nạp từ offline intel bundle (ưu tiên hơn dataset)
+static BUNDLE_TECHNIQUES: Lazy<RwLock<HashMap<String, MitreTechnique>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
 // ============================================================================
 // PUBLIC API
 // ============================================================================
 
+/// Thay technique của offline bundle, trả về số technique đã nạp
+pub fn load_bundle_techniques(techniques: Vec<MitreTechnique>) -> usize {
+    let mut bundle = BUNDLE_TECHNIQUES.write();
+    *bundle = techniques.into_iter().map(|t| (t.id.clone(), t)).collect();
+    bundle.len()
+}
+
 /// Get technique by ID
 pub fn get_technique(id: &str) -> Option<MitreTechnique> {
-    MITRE_TECHNIQUES.get(id).cloned()
+    if let Some(technique) = BUNDLE_TECHNIQUES.read().get(id) {
+        return Some(technique.clone());
+    }
+    DATASET.read().techniques.get(id).cloned()
+}
+
+/// Dataset đang dùng
+pub fn get_dataset_info() -> MitreDatasetInfo {
+    DATASET.read().info.clone()
+}
+
+/// Nạp dataset ATT&CK mới (STIX bundle, vd. `enterprise-attack.json` chính thức) từ file.
+/// Dataset lỗi → giữ nguyên dataset đang dùng.
+pub fn update_dataset(path: &Path) -> Result<MitreDatasetInfo, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let dataset = parse_stix_bundle(&content, "file")?;
+
+    let target = dataset_path();
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    fs::write(&target, &content).map_err(|e| format!("{}: {}", target.display(), e))?;
+
+    let info = dataset.info.clone();
+    *DATASET.write() = dataset;
+    log::info!("MITRE ATT&CK dataset updated: {} v{} ({} techniques)", info.name, info.version, info.techniques);
+    Ok(info)
 }
 
 /// Get techniques for a tag
@@ -343,16 +337,20 @@ pub fn enrich_with_mitre(tags: &[String]) -> Vec<MitreEnrichment> {
     result
 }
 
-/// Get all techniques
+/// Get all techniques (dataset + bundle, bundle thắng khi trùng ID)
 pub fn get_all_techniques() -> Vec<MitreTechnique> {
-    MITRE_TECHNIQUES.values().cloned().collect()
+    let bundle = BUNDLE_TECHNIQUES.read();
+    DATASET.read().techniques.values()
+        .filter(|t| !bundle.contains_key(&t.id))
+        .chain(bundle.values())
+        .cloned()
+        .collect()
 }
 
 /// Get techniques by tactic
 pub fn get_techniques_by_tactic(tactic: MitreTactic) -> Vec<MitreTechnique> {
-    MITRE_TECHNIQUES.values()
-        .filter(|t| t.tactic == tactic)
-        .cloned()
+    get_all_techniques().into_iter()
+        .filter(|t| t.tactic == tactic || t.tactics.contains(&tactic))
         .collect()
 }
 
@@ -360,13 +358,12 @@ pub fn get_techniques_by_tactic(tactic: MitreTactic) -> Vec<MitreTechnique> {
 pub fn search_techniques(query: &str) -> Vec<MitreTechnique> {
     let query_lower = query.to_lowercase();
 
-    MITRE_TECHNIQUES.values()
+    get_all_techniques().into_iter()
         .filter(|t| {
             t.id.to_lowercase().contains(&query_lower) ||
             t.name.to_lowercase().contains(&query_lower) ||
             t.description.to_lowercase().contains(&query_lower)
         })
-        .cloned()
         .collect()
 }
 
@@ -392,13 +389,14 @@ pub struct MitreStats {
 
 pub fn get_stats() -> MitreStats {
     let mut by_tactic: HashMap<String, usize> = HashMap::new();
+    let techniques = get_all_techniques();
 
-    for technique in MITRE_TECHNIQUES.values() {
+    for technique in &techniques {
         *by_tactic.entry(technique.tactic.as_str().to_string()).or_insert(0) += 1;
     }
 
     MitreStats {
-        total_techniques: MITRE_TECHNIQUES.len(),
+        total_techniques: techniques.len(),
         by_tactic,
     }
 }
@@ -428,6 +426,48 @@ mod tests {
         assert!(techniques.iter().any(|t| t.id == "T1003.001"));
     }
 
+    #[test]
+    fn test_embedded_dataset() {
+        let dataset = parse_stix_bundle(EMBEDDED_DATASET, "embedded").unwrap();
+        // Mọi technique mà tag detector map tới phải có trong dataset
+        for (tag, id) in TAG_TO_MITRE {
+            assert!(dataset.techniques.contains_key(*id), "{} → {} missing", tag, id);
+        }
+        assert!(dataset.techniques["T1059"].sub_techniques.contains(&"T1059.001".to_string()));
+    }
+
+    #[test]
+    fn test_parse_stix_bundle() {
+        let bundle = r#"{"type":"bundle","objects":[
+            {"type":"x-mitre-collection","name":"Enterprise ATT&CK","x_mitre_version":"15.1"},
+            {"type":"attack-pattern","name":"Scheduled Task/Job",
+             "description":"Adversaries may abuse task scheduling. (Citation: TechNet Task Scheduler Security)\n\nMore detail.",
+             "kill_chain_phases":[{"kill_chain_name":"mitre-attack","phase_name":"execution"},
+                                  {"kill_chain_name":"mitre-attack","phase_name":"persistence"}],
+             "external_references":[{"source_name":"mitre-attack","external_id":"T1053","url":"https://attack.mitre.org/techniques/T1053"}]},
+            {"type":"attack-pattern","name":"Scheduled Task","x_mitre_is_subtechnique":true,
+             "kill_chain_phases":[{"kill_chain_name":"mitre-attack","phase_name":"execution"}],
+             "external_references":[{"source_name":"mitre-attack","external_id":"T1053.005"}]},
+            {"type":"attack-pattern","name":"Old","revoked":true,
+             "kill_chain_phases":[{"kill_chain_name":"mitre-attack","phase_name":"execution"}],
+             "external_references":[{"source_name":"mitre-attack","external_id":"T1086"}]},
+            {"type":"intrusion-set","name":"APT29"}
+        ]}"#;
+
+        let dataset = parse_stix_bundle(bundle, "file").unwrap();
+        assert_eq!(dataset.info.version, "15.1");
+        assert_eq!((dataset.info.techniques, dataset.info.sub_techniques), (2, 1));
+        assert!(!dataset.techniques.contains_key("T1086"));
+
+        let task = &dataset.techniques["T1053"];
+        assert_eq!(task.tactic, MitreTactic::Execution);
+        assert_eq!(task.tactics, vec![MitreTactic::Execution, MitreTactic::Persistence]);
+        assert_eq!(task.description, "Adversaries may abuse task scheduling.");
+        assert_eq!(task.sub_techniques, vec!["T1053.005".to_string()]);
+
+        assert!(parse_stix_bundle(r#"{"type":"bundle","objects":[]}"#, "file").is_err());
+    }
+
     #[test]
     fn test_enrichment() {
         let tags = vec!["POWERSHELL_SPAWN".to_string(), "BEACONING".to_string()];
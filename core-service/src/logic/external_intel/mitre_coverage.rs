@@ -0,0 +1,272 @@
+//! MITRE ATT&CK Coverage - Technique nào đang được rules / detectors phủ
+//!
+//! Nguồn coverage:
+//! - Behavioral rules đang load (built-in + rules_dir + cloud pack), field `mitre_technique`
+//! - Detector chuyên biệt (`DETECTOR_TECHNIQUES`, giữ đồng bộ với technique mà detector
+//!   truyền vào `incident::raise_detection`)
+//! - Tag anomaly → technique (`mitre::TAG_TO_MITRE`)
+//!
+//! Kèm incident đang mở map vào từng technique (`Incident::mitre_techniques`).
+//! Sub-technique được phủ → technique cha tính là phủ một phần (`partial`).
+
+use std::collections::{BTreeMap, BTreeSet};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::logic::behavioral_sigs::rules;
+use crate::logic::incident;
+use super::mitre;
+use super::types::{MitreTactic, MitreTechnique};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Detector chuyên biệt → technique báo khi phát hiện
+pub const DETECTOR_TECHNIQUES: &[(&str, &[&str])] = &[
+    ("etw_script_block", &["T1059.001"]),
+    ("office_macro", &["T1059.005", "T1204.002", "T1027"]),
+    ("memory_scanner", &["T1055", "T1027"]),
+    ("injection", &["T1055", "T1055.001", "T1055.003", "T1055.004", "T1055.012"]),
+    ("iat_analysis", &["T1055", "T1003.001", "T1056.001", "T1113", "T1554"]),
+    ("keylogger_surveillance", &["T1056.001", "T1113", "T1123", "T1041"]),
+    ("credential_theft", &["T1555.003", "T1539"]),
+    ("amsi_etw_tampering", &["T1562.001", "T1562.006"]),
+    ("dll_sideload", &["T1574.002"]),
+    ("persistence", &["T1547.001", "T1053.005", "T1543.003", "T1546.012", "T1546.010"]),
+    ("wmi_persistence", &["T1546.003"]),
+    ("com_hijack", &["T1546.015"]),
+    ("uac_bypass", &["T1548.002"]),
+    ("bits_abuse", &["T1197"]),
+    ("kerberoasting", &["T1558.003"]),
+    ("beaconing", &["T1071", "T1071.001"]),
+    ("url_reputation", &["T1566.002", "T1189"]),
+];
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MitreCoverageReport {
+    pub dataset: mitre::MitreDatasetInfo,
+    pub total_techniques: usize,
+    pub covered_techniques: usize,
+    /// Technique cha chỉ có sub-technique được phủ
+    pub partial_techniques: usize,
+    pub coverage_percent: f32,
+    pub by_tactic: Vec<TacticCoverage>,
+    /// Technique được phủ hoặc có incident (theo ID)
+    pub techniques: Vec<TechniqueCoverage>,
+    /// Technique mà rule / detector khai báo nhưng không có trong dataset (ID sai / đã revoked)
+    pub unknown_techniques: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TacticCoverage {
+    pub tactic: String,
+    pub tactic_id: String,
+    pub total: usize,
+    pub covered: usize,
+    /// Technique chưa phủ (ID)
+    pub uncovered: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TechniqueCoverage {
+    pub id: String,
+    pub name: String,
+    pub tactics: Vec<String>,
+    /// "rule:<id>" | "detector:<name>" | "tag:<TAG>"
+    pub sources: Vec<String>,
+    pub partial: bool,
+    pub incident_ids: Vec<Uuid>,
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+pub fn get_coverage() -> MitreCoverageReport {
+    let rule_sources: Vec<(String, String)> = rules::get_all_rules()
+        .into_iter()
+        .filter(|r| r.enabled)
+        .filter_map(|r| r.mitre_technique.map(|t| (t, format!("rule:{}", r.id))))
+        .collect();
+    let incidents: Vec<(Uuid, Vec<String>)> = incident::get_incidents()
+        .into_iter()
+        .map(|i| (i.incident_id, i.mitre_techniques))
+        .collect();
+
+    build_report(mitre::get_dataset_info(), mitre::get_all_techniques(), rule_sources, &incidents)
+}
+
+// ============================================================================
+// REPORT
+// ============================================================================
+
+fn build_report(
+    dataset: mitre::MitreDatasetInfo,
+    techniques: Vec<MitreTechnique>,
+    rule_sources: Vec<(String, String)>,
+    incidents: &[(Uuid, Vec<String>)],
+) -> MitreCoverageReport {
+    // technique ID → nguồn phủ
+    let mut sources: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for (technique, source) in rule_sources {
+        sources.entry(normalize(&technique)).or_default().insert(source);
+    }
+    for (detector, ids) in DETECTOR_TECHNIQUES {
+        for id in *ids {
+            sources.entry(id.to_string()).or_default().insert(format!("detector:{}", detector));
+        }
+    }
+    for (tag, id) in mitre::TAG_TO_MITRE {
+        sources.entry(id.to_string()).or_default().insert(format!("tag:{}", tag));
+    }
+
+    let mut incident_map: BTreeMap<String, Vec<Uuid>> = BTreeMap::new();
+    for (incident_id, ids) in incidents {
+        for id in ids {
+            incident_map.entry(normalize(id)).or_default().push(*incident_id);
+        }
+    }
+
+    let known: BTreeSet<&str> = techniques.iter().map(|t| t.id.as_str()).collect();
+    let unknown_techniques: Vec<String> = sources.keys()
+        .filter(|id| !known.contains(id.as_str()))
+        .cloned()
+        .collect();
+    let partial = |technique: &MitreTechnique| {
+        !sources.contains_key(&technique.id)
+            && technique.sub_techniques.iter().any(|s| sources.contains_key(s))
+    };
+
+    let mut by_tactic = Vec::new();
+    for tactic in MitreTactic::ALL {
+        let in_tactic: Vec<&MitreTechnique> = techniques.iter()
+            .filter(|t| tactics_of(t).contains(&tactic))
+            .collect();
+        if in_tactic.is_empty() {
+            continue;
+        }
+        let mut uncovered: Vec<String> = in_tactic.iter()
+            .filter(|t| !sources.contains_key(&t.id))
+            .map(|t| t.id.clone())
+            .collect();
+        uncovered.sort();
+        by_tactic.push(TacticCoverage {
+            tactic: tactic.as_str().to_string(),
+            tactic_id: tactic.id().to_string(),
+            total: in_tactic.len(),
+            covered: in_tactic.len() - uncovered.len(),
+            uncovered,
+        });
+    }
+
+    let mut entries: Vec<TechniqueCoverage> = techniques.iter()
+        .filter(|t| sources.contains_key(&t.id) || partial(t) || incident_map.contains_key(&t.id))
+        .map(|t| TechniqueCoverage {
+            id: t.id.clone(),
+            name: t.name.clone(),
+            tactics: tactics_of(t).iter().map(|x| x.as_str().to_string()).collect(),
+            sources: sources.get(&t.id).map(|s| s.iter().cloned().collect()).unwrap_or_default(),
+            partial: partial(t),
+            incident_ids: incident_map.get(&t.id).cloned().unwrap_or_default(),
+        })
+        .collect();
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let covered_techniques = techniques.iter().filter(|t| sources.contains_key(&t.id)).count();
+    let partial_techniques = techniques.iter().filter(|t| partial(t)).count();
+    MitreCoverageReport {
+        dataset,
+        total_techniques: techniques.len(),
+        covered_techniques,
+        partial_techniques,
+        coverage_percent: if techniques.is_empty() {
+            0.0
+        } else {
+            covered_techniques as f32 * 100.0 / techniques.len() as f32
+        },
+        by_tactic,
+        techniques: entries,
+        unknown_techniques,
+    }
+}
+
+fn tactics_of(technique: &MitreTechnique) -> Vec<MitreTactic> {
+    if technique.tactics.is_empty() {
+        vec![technique.tactic]
+    } else {
+        technique.tactics.clone()
+    }
+}
+
+fn normalize(id: &str) -> String {
+    id.trim().to_uppercase()
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn technique(id: &str, tactics: &[MitreTactic], subs: &[&str]) -> MitreTechnique {
+        MitreTechnique {
+            id: id.to_string(),
+            name: id.to_string(),
+            tactic: tactics[0],
+            description: String::new(),
+            url: String::new(),
+            sub_techniques: subs.iter().map(|s| s.to_string()).collect(),
+            tactics: tactics.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_coverage_report() {
+        let techniques = vec![
+            technique("T1059", &[MitreTactic::Execution], &["T1059.001"]),
+            technique("T1059.001", &[MitreTactic::Execution], &[]),
+            technique("T1053", &[MitreTactic::Execution, MitreTactic::Persistence], &[]),
+            technique("T1595", &[MitreTactic::Reconnaissance], &[]),
+        ];
+        let incident = Uuid::new_v4();
+        let dataset = mitre::MitreDatasetInfo {
+            name: "test".to_string(),
+            version: "1".to_string(),
+            source: "file",
+            techniques: 4,
+            sub_techniques: 1,
+        };
+        let report = build_report(
+            dataset,
+            techniques,
+            vec![("t1053".to_string(), "rule:sched".to_string())],
+            &[(incident, vec!["T1595".to_string()])],
+        );
+
+        // T1059.001 (detector etw) + T1053 (rule)
+        assert_eq!(report.covered_techniques, 2);
+        assert_eq!(report.partial_techniques, 1);
+        assert!((report.coverage_percent - 50.0).abs() < 0.01);
+
+        let persistence = report.by_tactic.iter().find(|t| t.tactic_id == "TA0003").unwrap();
+        assert_eq!((persistence.total, persistence.covered), (1, 1));
+        let recon = report.by_tactic.iter().find(|t| t.tactic_id == "TA0043").unwrap();
+        assert_eq!(recon.uncovered, vec!["T1595".to_string()]);
+
+        let t1053 = report.techniques.iter().find(|t| t.id == "T1053").unwrap();
+        assert_eq!(t1053.sources, vec!["rule:sched".to_string()]);
+        let t1595 = report.techniques.iter().find(|t| t.id == "T1595").unwrap();
+        assert!(t1595.sources.is_empty());
+        assert_eq!(t1595.incident_ids, vec![incident]);
+        assert!(report.techniques.iter().find(|t| t.id == "T1059").unwrap().partial);
+        // Detector technique không có trong dataset test
+        assert!(report.unknown_techniques.contains(&"T1558.003".to_string()));
+    }
+}
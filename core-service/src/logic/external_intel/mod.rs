@@ -3,16 +3,30 @@
 //! Mục đích: Kết nối với nguồn threat intelligence bên ngoài
 //!
 //! # Components
-//! - `virustotal.rs`: VirusTotal API integration
-//! - `threat_feed.rs`: Cloud threat feed sync (IPs, domains, hashes)
-//! - `mitre.rs`: MITRE ATT&CK mapping and enrichment
+//! - `virustotal.rs`: VirusTotal API integration (quota queue, persistent cache)
+//! - `threat_feed.rs`: abuse.ch / ET feed sync (IPs, domains, URLs, hashes), store local + match stats
+//! - `misp.rs`: MISP attribute pull + sighting push (cloud policy)
+//! - `taxii.rs`: STIX/TAXII 2.1 collection subscription (cloud policy)
+//! - `otx.rs`: AlienVault OTX pulse subscription (tag → MITRE technique)
+//! - `local_iocs.rs`: IOC import của incident responder (CSV/JSON/text, expiry)
+//! - `intel_bundle.rs`: Offline intel bundle đã ký (feeds + MITRE + rule packs) cho host air-gapped
+//! - `intel_matcher.rs`: Connection / executable khớp intel → incident (sync cloud)
+//! - `mitre.rs`: MITRE ATT&CK dataset (STIX), mapping and enrichment
+//! - `mitre_coverage.rs`: Coverage ATT&CK của rules / detectors + incident theo technique
 
 // Allow unused for now - will be fully integrated in future phases
 #![allow(unused)]
 
 pub mod virustotal;
 pub mod threat_feed;
+pub mod misp;
+pub mod taxii;
+pub mod otx;
+pub mod local_iocs;
+pub mod intel_bundle;
+pub mod intel_matcher;
 pub mod mitre;
+pub mod mitre_coverage;
 pub mod types;
 
 // Re-exports from types
@@ -23,6 +37,6 @@ pub use types::{
 };
 
 // Re-exports from submodules
-pub use virustotal::{check_hash, check_file, get_cached_result, VTClient};
-pub use threat_feed::{ThreatFeed, sync_feeds, is_malicious_ip, is_malicious_domain, is_malicious_hash};
-pub use mitre::{get_technique, get_techniques_for_tag, enrich_with_mitre, MITRE_TECHNIQUES};
+pub use virustotal::{check_hash, check_file, get_cached_result, submit_hash, VTClient, VTConfig, VTQueueStatus};
+pub use threat_feed::{ThreatFeed, sync_feeds, is_malicious_ip, is_malicious_domain, is_malicious_hash, is_malicious_url};
+pub use mitre::{get_technique, get_techniques_for_tag, enrich_with_mitre, MitreDatasetInfo};
@@ -0,0 +1,674 @@
+//! AlienVault OTX Integration Module
+//!
+//! Mục đích: Community intel miễn phí - sync các pulse user đã subscribe trên OTX
+//! vào threat feed, map tag của pulse sang MITRE technique.
+//!
+//! - `GET /api/v1/pulses/subscribed?modified_since=...` (header `X-OTX-API-KEY`), theo `next`
+//! - Incremental: chỉ lấy pulse sửa sau lần sync trước, pulse cũ hơn `lookback_days` bị bỏ
+//! - Indicator hết hạn (`expiration`) / `is_active = 0` không đưa vào feed
+//! - Technique: `attack_ids` của pulse + tag dạng `T1566.001` + keyword (phishing, ransomware, ...)
+//!   → gắn vào tag indicator (`mitre:T1566`)
+//!
+//! API key lưu local (`otx.json`, như VirusTotal), section `otx` của cloud policy
+//! override từng field.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use chrono::{NaiveDateTime, Utc};
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use super::threat_feed;
+use super::types::{ThreatIndicator, IndicatorType, ThreatLevel};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Tên nguồn của indicator trong threat feed
+pub const SOURCE: &str = "OTX";
+
+const API_BASE: &str = "https://otx.alienvault.com/api/v1";
+const CONFIG_FILE: &str = "otx.json";
+const STATE_FILE: &str = "otx_state.json";
+const PAGE_SIZE: usize = 50;
+const MAX_PAGES: usize = 40;
+const TICK: Duration = Duration::from_secs(60);
+
+/// Keyword (nguyên từ) trong tag pulse → technique (tag OTX là free-text)
+const TAG_TECHNIQUES: &[(&str, &str)] = &[
+    ("spearphishing", "T1566.001"),
+    ("spear phishing", "T1566.001"),
+    ("phishing", "T1566"),
+    ("ransomware", "T1486"),
+    ("cobalt strike", "T1071.001"),
+    ("cobaltstrike", "T1071.001"),
+    ("c2", "T1071"),
+    ("command and control", "T1071"),
+    ("keylogger", "T1056.001"),
+    ("credential theft", "T1003"),
+    ("mimikatz", "T1003.001"),
+    ("stealer", "T1555"),
+    ("infostealer", "T1555"),
+    ("miner", "T1496"),
+    ("cryptominer", "T1496"),
+    ("powershell", "T1059.001"),
+    ("macro", "T1204.002"),
+    ("maldoc", "T1204.002"),
+    ("exploit", "T1203"),
+    ("dropper", "T1105"),
+    ("loader", "T1105"),
+    ("exfiltration", "T1041"),
+    ("dll sideloading", "T1574.002"),
+    ("process injection", "T1055"),
+    ("lolbin", "T1218"),
+];
+
+// ============================================================================
+// CONFIG
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OtxConfig {
+    pub enabled: bool,
+    pub api_key: String,
+    /// Chu kỳ sync (phút)
+    pub pull_interval_mins: u64,
+    /// Bỏ pulse không được sửa trong N ngày
+    pub lookback_days: u32,
+}
+
+impl Default for OtxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_key: String::new(),
+            pull_interval_mins: 60,
+            lookback_days: 90,
+        }
+    }
+}
+
+impl OtxConfig {
+    fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.api_key.trim().is_empty() {
+            return Err("OTX api_key is required".to_string());
+        }
+        if self.pull_interval_mins == 0 {
+            return Err("OTX pull_interval_mins must be greater than zero".to_string());
+        }
+        if self.lookback_days == 0 {
+            return Err("OTX lookback_days must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// Pulse đã sync (persist để sync tiếp incremental sau khi restart)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredPulse {
+    name: String,
+    /// Unix seconds
+    modified: i64,
+    techniques: Vec<String>,
+    indicators: Vec<ThreatIndicator>,
+    /// Unix seconds, theo thứ tự `indicators` (None = không hết hạn)
+    expires: Vec<Option<i64>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct OtxStore {
+    /// `modified` lớn nhất đã thấy (nguyên văn OTX) → `modified_since`
+    cursor: Option<String>,
+    pulses: HashMap<String, StoredPulse>,
+}
+
+struct OtxState {
+    local: OtxConfig,
+    cloud_override: Option<serde_json::Value>,
+    config: OtxConfig,
+    store: OtxStore,
+    last_pull: Option<i64>,
+    last_error: Option<String>,
+    indicators: usize,
+}
+
+static STATE: Lazy<RwLock<OtxState>> = Lazy::new(|| {
+    let local = load_config();
+    RwLock::new(OtxState {
+        config: local.clone(),
+        local,
+        cloud_override: None,
+        store: load_store(),
+        last_pull: None,
+        last_error: None,
+        indicators: 0,
+    })
+});
+
+static PULLING: AtomicBool = AtomicBool::new(false);
+static SCHEDULER_STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OtxStatus {
+    pub enabled: bool,
+    pub configured: bool,
+    /// "local" | "cloud"
+    pub key_source: Option<&'static str>,
+    pub last_pull: Option<i64>,
+    pub last_error: Option<String>,
+    pub pulses: usize,
+    pub indicators: usize,
+    /// Technique → số pulse
+    pub techniques: HashMap<String, usize>,
+}
+
+impl OtxState {
+    fn recompute_config(&mut self) {
+        let effective = match self.cloud_override.as_ref().and_then(|o| o.as_object()) {
+            Some(overrides) => {
+                let mut merged = serde_json::to_value(&self.local).unwrap_or_default();
+                if let Some(obj) = merged.as_object_mut() {
+                    for (key, value) in overrides {
+                        if obj.contains_key(key) {
+                            obj.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+                match serde_json::from_value::<OtxConfig>(merged) {
+                    Ok(config) if config.validate().is_ok() => config,
+                    _ => {
+                        log::warn!("Ignoring invalid OTX override from cloud policy");
+                        self.local.clone()
+                    }
+                }
+            }
+            None => self.local.clone(),
+        };
+        if effective.api_key != self.config.api_key {
+            // Account khác → subscription khác, sync lại từ đầu
+            self.store = OtxStore::default();
+            self.last_pull = None;
+        }
+        self.config = effective;
+    }
+
+    fn key_source(&self) -> Option<&'static str> {
+        if self.config.api_key.trim().is_empty() {
+            None
+        } else if self.config.api_key != self.local.api_key {
+            Some("cloud")
+        } else {
+            Some("local")
+        }
+    }
+}
+
+// ============================================================================
+// API RESPONSE TYPES
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+struct PulsePage {
+    #[serde(default)]
+    results: Vec<Pulse>,
+    #[serde(default)]
+    next: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Pulse {
+    id: String,
+    name: String,
+    modified: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    attack_ids: Vec<AttackId>,
+    #[serde(default)]
+    malware_families: Vec<serde_json::Value>,
+    #[serde(default)]
+    adversary: Option<String>,
+    #[serde(default)]
+    indicators: Vec<PulseIndicator>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AttackId {
+    id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PulseIndicator {
+    indicator: String,
+    #[serde(rename = "type")]
+    indicator_type: String,
+    #[serde(default)]
+    created: Option<String>,
+    #[serde(default)]
+    expiration: Option<String>,
+    #[serde(default)]
+    is_active: Option<i64>,
+    #[serde(default)]
+    title: Option<String>,
+}
+
+// ============================================================================
+// SYNC
+// ============================================================================
+
+/// Sync pulse đã subscribe → thay toàn bộ indicator nguồn OTX trong threat feed
+pub fn pull() -> Result<usize, String> {
+    let (config, cursor) = {
+        let state = STATE.read();
+        (state.config.clone(), state.store.cursor.clone())
+    };
+    if !config.enabled {
+        return Err("OTX integration is not enabled".to_string());
+    }
+    if PULLING.swap(true, Ordering::SeqCst) {
+        return Err("OTX sync already in progress".to_string());
+    }
+
+    let result = fetch_pulses(&config, cursor.as_deref());
+    PULLING.store(false, Ordering::SeqCst);
+
+    let now = Utc::now().timestamp();
+    let mut state = STATE.write();
+    state.last_pull = Some(now);
+    let pulses = match result {
+        Ok(pulses) => pulses,
+        Err(e) => {
+            state.last_error = Some(e.clone());
+            log::warn!("OTX sync failed: {}", e);
+            return Err(e);
+        }
+    };
+
+    let updated = pulses.len();
+    for pulse in pulses {
+        if state.store.cursor.as_deref().is_none_or(|c| pulse.modified.as_str() > c) {
+            state.store.cursor = Some(pulse.modified.clone());
+        }
+        state.store.pulses.insert(pulse.id.clone(), to_stored(&pulse));
+    }
+    let cutoff = now - config.lookback_days as i64 * 86_400;
+    state.store.pulses.retain(|_, p| p.modified >= cutoff);
+
+    let indicators = active_indicators(&state.store, now);
+    let count = indicators.len();
+    state.indicators = count;
+    state.last_error = None;
+    save_store(&state.store);
+    drop(state);
+
+    threat_feed::replace_source_indicators(SOURCE, indicators);
+    log::info!("OTX: {} pulses updated, {} active indicators", updated, count);
+    Ok(count)
+}
+
+fn fetch_pulses(config: &OtxConfig, modified_since: Option<&str>) -> Result<Vec<Pulse>, String> {
+    let mut url = format!("{}/pulses/subscribed?limit={}", API_BASE, PAGE_SIZE);
+    if let Some(since) = modified_since {
+        url.push_str(&format!("&modified_since={}", since));
+    }
+
+    let mut all = Vec::new();
+    for _ in 0..MAX_PAGES {
+        let response = ureq::get(&url)
+            .set("X-OTX-API-KEY", config.api_key.trim())
+            .set("Accept", "application/json")
+            .timeout(Duration::from_secs(60))
+            .call()
+            .map_err(|e| match e {
+                ureq::Error::Status(403, _) | ureq::Error::Status(401, _) => "OTX rejected API key".to_string(),
+                e => e.to_string(),
+            })?;
+
+        let text = response.into_string().map_err(|e| e.to_string())?;
+        let page: PulsePage = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid OTX response: {}", e))?;
+        all.extend(page.results);
+        match page.next.filter(|n| !n.is_empty()) {
+            Some(next) => url = next,
+            None => break,
+        }
+    }
+    Ok(all)
+}
+
+fn to_stored(pulse: &Pulse) -> StoredPulse {
+    let techniques = pulse_techniques(pulse);
+    let mut tags: Vec<String> = pulse.tags.clone();
+    if let Some(adversary) = pulse.adversary.as_ref().filter(|a| !a.is_empty()) {
+        tags.push(format!("adversary:{}", adversary));
+    }
+    tags.extend(techniques.iter().map(|t| format!("mitre:{}", t)));
+    let level = threat_level(pulse);
+
+    let mut indicators = Vec::new();
+    let mut expires = Vec::new();
+    for item in &pulse.indicators {
+        if item.is_active == Some(0) {
+            continue;
+        }
+        let Some(indicator_type) = map_type(&item.indicator_type) else {
+            continue;
+        };
+        indicators.push(ThreatIndicator {
+            indicator_type,
+            value: item.indicator.trim().to_string(),
+            threat_level: level,
+            source: SOURCE.to_string(),
+            first_seen: item.created.as_deref().and_then(parse_time),
+            last_seen: parse_time(&pulse.modified),
+            tags: tags.clone(),
+            description: Some(match &item.title {
+                Some(title) if !title.is_empty() => format!("OTX pulse {}: {}", pulse.name, title),
+                _ => format!("OTX pulse {}", pulse.name),
+            }),
+        });
+        expires.push(item.expiration.as_deref().and_then(parse_time));
+    }
+
+    StoredPulse {
+        name: pulse.name.clone(),
+        modified: parse_time(&pulse.modified).unwrap_or_else(|| Utc::now().timestamp()),
+        techniques,
+        indicators,
+        expires,
+    }
+}
+
+fn active_indicators(store: &OtxStore, now: i64) -> Vec<ThreatIndicator> {
+    store.pulses.values()
+        .flat_map(|p| p.indicators.iter().zip(p.expires.iter()))
+        .filter(|(_, expires)| expires.is_none_or(|at| at > now))
+        .map(|(indicator, _)| indicator.clone())
+        .collect()
+}
+
+/// OTX indicator type → loại tra cứu được (CIDR, email, CVE, ... bỏ qua)
+fn map_type(otx_type: &str) -> Option<IndicatorType> {
+    match otx_type {
+        "IPv4" => Some(IndicatorType::IPv4),
+        "IPv6" => Some(IndicatorType::IPv6),
+        "domain" | "hostname" => Some(IndicatorType::Domain),
+        "URL" | "URI" => Some(IndicatorType::Url),
+        "FileHash-MD5" => Some(IndicatorType::Md5),
+        "FileHash-SHA1" => Some(IndicatorType::Sha1),
+        "FileHash-SHA256" => Some(IndicatorType::Sha256),
+        _ => None,
+    }
+}
+
+/// Pulse gắn adversary / malware family / ransomware → cao hơn pulse chung chung
+fn threat_level(pulse: &Pulse) -> ThreatLevel {
+    let tags_lower: Vec<String> = pulse.tags.iter().map(|t| t.to_lowercase()).collect();
+    if pulse.adversary.as_ref().is_some_and(|a| !a.is_empty())
+        || tags_lower.iter().any(|t| t.contains("apt") || t.contains("ransomware"))
+    {
+        ThreatLevel::High
+    } else if !pulse.malware_families.is_empty() || !pulse.attack_ids.is_empty() {
+        ThreatLevel::Medium
+    } else {
+        ThreatLevel::Low
+    }
+}
+
+/// Technique của pulse: `attack_ids`, tag dạng T-code, keyword trong tag
+pub(crate) fn map_tags_to_techniques(attack_ids: &[String], tags: &[String]) -> Vec<String> {
+    let mut techniques: Vec<String> = Vec::new();
+    let mut add = |id: String| {
+        if !techniques.contains(&id) {
+            techniques.push(id);
+        }
+    };
+
+    for id in attack_ids {
+        if let Some(id) = technique_id(id) {
+            add(id);
+        }
+    }
+    for tag in tags {
+        if let Some(id) = technique_id(tag) {
+            add(id);
+            continue;
+        }
+        // So theo từ: "c2" khớp "emotet c2" nhưng không khớp "ec2"
+        let words = format!(" {} ", tag.to_lowercase().replace(['-', '_', '/'], " "));
+        if let Some((_, id)) = TAG_TECHNIQUES.iter().find(|(keyword, _)| words.contains(&format!(" {} ", keyword))) {
+            add(id.to_string());
+        }
+    }
+    techniques
+}
+
+fn pulse_techniques(pulse: &Pulse) -> Vec<String> {
+    let attack_ids: Vec<String> = pulse.attack_ids.iter().map(|a| a.id.clone()).collect();
+    map_tags_to_techniques(&attack_ids, &pulse.tags)
+}
+
+/// "T1566.001" / "t1566" → ID chuẩn hóa, chuỗi khác → None
+fn technique_id(value: &str) -> Option<String> {
+    let value = value.trim().to_uppercase();
+    let rest = value.strip_prefix('T')?;
+    let (base, sub) = match rest.split_once('.') {
+        Some((base, sub)) => (base, Some(sub)),
+        None => (rest, None),
+    };
+    let digits = |s: &str, n: usize| s.len() == n && s.chars().all(|c| c.is_ascii_digit());
+    if digits(base, 4) && sub.is_none_or(|s| digits(s, 3)) {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// OTX timestamp ("2024-05-01T12:34:56.789000", không timezone = UTC) → Unix seconds
+fn parse_time(value: &str) -> Option<i64> {
+    let value = value.trim_end_matches('Z');
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+        .ok()
+        .map(|t| t.and_utc().timestamp())
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Lưu OTX API key vào local settings (chuỗi rỗng = xóa và tắt)
+pub fn set_api_key(key: &str) {
+    let mut state = STATE.write();
+    state.local.api_key = key.trim().to_string();
+    state.local.enabled = !state.local.api_key.is_empty();
+    save_config(&state.local);
+    state.recompute_config();
+    let enabled = state.config.enabled;
+    drop(state);
+
+    if !enabled {
+        threat_feed::replace_source_indicators(SOURCE, Vec::new());
+    }
+    log::info!("OTX API key {}", if key.trim().is_empty() { "cleared" } else { "updated" });
+}
+
+/// Áp dụng section `otx` của cloud policy
+pub fn apply_cloud_config(overrides: Option<&serde_json::Value>) {
+    let mut state = STATE.write();
+    let was_enabled = state.config.enabled;
+    state.cloud_override = overrides.filter(|v| v.is_object()).cloned();
+    state.recompute_config();
+    let enabled = state.config.enabled;
+    drop(state);
+
+    if was_enabled && !enabled {
+        threat_feed::replace_source_indicators(SOURCE, Vec::new());
+        log::info!("OTX integration disabled by policy");
+    }
+}
+
+/// Scheduler: nạp lại pulse đã lưu, sync theo `pull_interval_mins`
+pub fn start() {
+    if SCHEDULER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(|| {
+        {
+            let mut state = STATE.write();
+            if state.config.enabled {
+                let indicators = active_indicators(&state.store, Utc::now().timestamp());
+                state.indicators = indicators.len();
+                drop(state);
+                threat_feed::replace_source_indicators(SOURCE, indicators);
+            }
+        }
+        loop {
+            let (enabled, due) = {
+                let state = STATE.read();
+                let interval = state.config.pull_interval_mins as i64 * 60;
+                let due = state.last_pull
+                    .map(|t| Utc::now().timestamp() - t >= interval)
+                    .unwrap_or(true);
+                (state.config.enabled, due)
+            };
+            if enabled && due {
+                let _ = pull();
+            }
+            std::thread::sleep(TICK);
+        }
+    });
+}
+
+pub fn get_status() -> OtxStatus {
+    let state = STATE.read();
+    let mut techniques: HashMap<String, usize> = HashMap::new();
+    for pulse in state.store.pulses.values() {
+        for technique in &pulse.techniques {
+            *techniques.entry(technique.clone()).or_insert(0) += 1;
+        }
+    }
+    OtxStatus {
+        enabled: state.config.enabled,
+        configured: !state.config.api_key.trim().is_empty(),
+        key_source: state.key_source(),
+        last_pull: state.last_pull,
+        last_error: state.last_error.clone(),
+        pulses: state.store.pulses.len(),
+        indicators: state.indicators,
+        techniques,
+    }
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn data_path(file: &str) -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(file)
+}
+
+fn load_config() -> OtxConfig {
+    fs::read_to_string(data_path(CONFIG_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str::<OtxConfig>(&c).ok())
+        .filter(|c| c.validate().is_ok())
+        .unwrap_or_default()
+}
+
+fn save_config(config: &OtxConfig) {
+    let path = data_path(CONFIG_FILE);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(config) {
+        let _ = fs::write(path, json);
+    }
+}
+
+fn load_store() -> OtxStore {
+    fs::read_to_string(data_path(STATE_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_store(store: &OtxStore) {
+    let path = data_path(STATE_FILE);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string(store) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_technique_mapping() {
+        let techniques = map_tags_to_techniques(
+            &["T1059.001".to_string(), "not-an-id".to_string()],
+            &["Spear-Phishing".to_string(), "t1204.002".to_string(), "Emotet".to_string(), "powershell".to_string()],
+        );
+        assert_eq!(techniques, vec!["T1059.001", "T1566.001", "T1204.002"]);
+        assert_eq!(technique_id("T15660"), None);
+    }
+
+    #[test]
+    fn test_pulse_to_indicators() {
+        let pulse: Pulse = serde_json::from_value(serde_json::json!({
+            "id": "p1",
+            "name": "Emotet wave",
+            "modified": "2026-01-10T08:00:00.123000",
+            "tags": ["ransomware"],
+            "attack_ids": [{ "id": "T1486", "name": "Data Encrypted for Impact" }],
+            "indicators": [
+                { "indicator": "evil.example", "type": "hostname", "created": "2026-01-09T00:00:00", "expiration": null },
+                { "indicator": "203.0.113.7", "type": "IPv4", "expiration": "2026-01-11T00:00:00" },
+                { "indicator": "10.0.0.0/8", "type": "CIDR" },
+                { "indicator": "old.example", "type": "domain", "is_active": 0 }
+            ]
+        })).unwrap();
+
+        let stored = to_stored(&pulse);
+        assert_eq!(stored.indicators.len(), 2);
+        assert_eq!(stored.techniques, vec!["T1486"]);
+        assert_eq!(stored.indicators[0].indicator_type, IndicatorType::Domain);
+        assert_eq!(stored.indicators[0].threat_level, ThreatLevel::High);
+        assert!(stored.indicators[0].tags.contains(&"mitre:T1486".to_string()));
+        assert_eq!(stored.modified, parse_time("2026-01-10T08:00:00").unwrap());
+
+        let mut store = OtxStore::default();
+        store.pulses.insert(pulse.id.clone(), stored);
+        // IP hết hạn 2026-01-11 → chỉ còn domain
+        let later = parse_time("2026-01-12T00:00:00").unwrap();
+        let active = active_indicators(&store, later);
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].value, "evil.example");
+    }
+}
@@ -0,0 +1,619 @@
+//! STIX / TAXII 2.1 Client
+//!
+//! Mục đích: Subscribe TAXII collection (feed thương mại, ISAC) → STIX `indicator`
+//! → `ThreatIndicator` trong threat feed, không cần code riêng cho từng feed.
+//!
+//! - Poll incremental theo `added_after` (X-TAXII-Date-Added-Last), phân trang `next`
+//! - Giữ version mới nhất theo `modified`; `revoked` → xóa
+//! - Chỉ active trong khoảng `valid_from` .. `valid_until`
+//! - Pattern hỗ trợ: so sánh `=` trên file hash, ipv4/ipv6, domain-name, url
+//!
+//! Cấu hình qua section `taxii` của cloud policy:
+//! `{ "collections": [{ "name": "ISAC", "api_root": "https://taxii.example/api1", "collection_id": "..." }] }`
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use super::threat_feed;
+use super::types::{ThreatIndicator, IndicatorType, ThreatLevel};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const TAXII_MEDIA_TYPE: &str = "application/taxii+json;version=2.1";
+const PAGE_LIMIT: usize = 1000;
+const MAX_PAGES: usize = 50;
+const STATE_FILE: &str = "taxii_state.json";
+const TICK: Duration = Duration::from_secs(60);
+
+// ============================================================================
+// CONFIG
+// ============================================================================
+
+/// Một TAXII collection được subscribe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaxiiCollection {
+    /// Tên hiển thị, cũng là source của indicator ("TAXII: <name>")
+    pub name: String,
+    /// VD: https://taxii.example.com/api1
+    pub api_root: String,
+    pub collection_id: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Một số server dùng API key thay basic auth
+    #[serde(default)]
+    pub api_key_header: Option<String>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval_mins: u64,
+}
+
+fn default_poll_interval() -> u64 {
+    60
+}
+
+impl TaxiiCollection {
+    fn source(&self) -> String {
+        format!("TAXII: {}", self.name)
+    }
+
+    /// Key trong state local (đổi server / collection → poll lại từ đầu)
+    fn key(&self) -> String {
+        format!("{}|{}", self.api_root.trim_end_matches('/'), self.collection_id)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.collection_id.trim().is_empty() {
+            return Err("TAXII collection needs name and collection_id".to_string());
+        }
+        if !self.api_root.starts_with("https://") && !self.api_root.starts_with("http://") {
+            return Err(format!("TAXII api_root must be http(s): {}", self.api_root));
+        }
+        if self.poll_interval_mins == 0 {
+            return Err("TAXII poll_interval_mins must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct TaxiiPolicy {
+    #[serde(default)]
+    collections: Vec<TaxiiCollection>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// STIX indicator đã parse (lưu xuống disk)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredIndicator {
+    modified: i64,
+    valid_from: Option<i64>,
+    valid_until: Option<i64>,
+    indicators: Vec<ThreatIndicator>,
+}
+
+impl StoredIndicator {
+    fn is_active(&self, now: i64) -> bool {
+        self.valid_from.map_or(true, |t| t <= now) && self.valid_until.map_or(true, |t| t > now)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CollectionState {
+    /// Cursor cho lần poll kế tiếp
+    added_after: Option<String>,
+    last_poll: Option<i64>,
+    last_error: Option<String>,
+    /// STIX id → indicator
+    objects: HashMap<String, StoredIndicator>,
+}
+
+#[derive(Default)]
+struct TaxiiState {
+    collections: Vec<TaxiiCollection>,
+    state: HashMap<String, CollectionState>,
+}
+
+static STATE: Lazy<RwLock<TaxiiState>> = Lazy::new(|| {
+    RwLock::new(TaxiiState { collections: Vec::new(), state: load_state() })
+});
+
+static POLLING: AtomicBool = AtomicBool::new(false);
+static SCHEDULER_STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaxiiCollectionStatus {
+    pub name: String,
+    pub api_root: String,
+    pub collection_id: String,
+    pub last_poll: Option<i64>,
+    pub last_error: Option<String>,
+    pub stix_objects: usize,
+    pub active_indicators: usize,
+}
+
+// ============================================================================
+// STIX PARSING
+// ============================================================================
+
+/// TAXII envelope (GET .../objects/)
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    more: bool,
+    #[serde(default)]
+    next: Option<String>,
+    #[serde(default)]
+    objects: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StixIndicator {
+    id: String,
+    modified: String,
+    pattern: String,
+    #[serde(default)]
+    pattern_type: Option<String>,
+    #[serde(default)]
+    valid_from: Option<String>,
+    #[serde(default)]
+    valid_until: Option<String>,
+    #[serde(default)]
+    revoked: bool,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    indicator_types: Vec<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    confidence: Option<u8>,
+}
+
+enum StixUpdate {
+    Upsert(String, StoredIndicator),
+    Revoke(String),
+}
+
+fn parse_time(value: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(value).ok().map(|t| t.timestamp())
+}
+
+/// STIX object → thay đổi trong store (None nếu không phải indicator hỗ trợ được)
+fn parse_stix_object(object: &serde_json::Value, source: &str) -> Option<StixUpdate> {
+    if object.get("type").and_then(|t| t.as_str()) != Some("indicator") {
+        return None;
+    }
+    let stix: StixIndicator = serde_json::from_value(object.clone()).ok()?;
+    if stix.revoked {
+        return Some(StixUpdate::Revoke(stix.id));
+    }
+    if stix.pattern_type.as_deref().is_some_and(|t| t != "stix") {
+        return None;
+    }
+
+    let comparisons = parse_pattern(&stix.pattern);
+    if comparisons.is_empty() {
+        return None;
+    }
+
+    let modified = parse_time(&stix.modified)?;
+    let mut tags = stix.indicator_types.clone();
+    tags.extend(stix.labels.iter().cloned());
+    let threat_level = match stix.confidence {
+        Some(c) if c >= 85 => ThreatLevel::Critical,
+        Some(c) if c >= 60 => ThreatLevel::High,
+        Some(c) if c >= 30 => ThreatLevel::Medium,
+        Some(_) => ThreatLevel::Low,
+        None => ThreatLevel::Medium,
+    };
+    let description = stix.description.clone().or_else(|| stix.name.clone());
+
+    let indicators = comparisons.into_iter()
+        .map(|(indicator_type, value)| ThreatIndicator {
+            indicator_type,
+            value,
+            threat_level,
+            source: source.to_string(),
+            first_seen: stix.valid_from.as_deref().and_then(parse_time),
+            last_seen: Some(modified),
+            tags: tags.clone(),
+            description: description.clone(),
+        })
+        .collect();
+
+    Some(StixUpdate::Upsert(stix.id, StoredIndicator {
+        modified,
+        valid_from: stix.valid_from.as_deref().and_then(parse_time),
+        valid_until: stix.valid_until.as_deref().and_then(parse_time),
+        indicators,
+    }))
+}
+
+/// Lấy các so sánh `object:path = 'value'` từ STIX pattern.
+/// Pattern có AND / FOLLOWEDBY chỉ giữ file hash (hash nào cũng định danh file).
+fn parse_pattern(pattern: &str) -> Vec<(IndicatorType, String)> {
+    let compound = pattern.contains(" AND ") || pattern.contains("FOLLOWEDBY");
+    let mut results = Vec::new();
+    let mut rest = pattern;
+
+    while let Some(eq) = rest.find(" = '") {
+        let path_start = rest[..eq].rfind(|c: char| c == '[' || c == '(' || c == ' ').map(|i| i + 1).unwrap_or(0);
+        let path = rest[path_start..eq].trim();
+        let value_start = eq + 4;
+        let Some(value_len) = find_closing_quote(&rest[value_start..]) else { break };
+        let value = rest[value_start..value_start + value_len].replace("\\'", "'");
+        rest = &rest[value_start + value_len + 1..];
+
+        let indicator_type = match path.to_lowercase().as_str() {
+            "file:hashes.'sha-256'" | "file:hashes.sha256" | "file:hashes.\"sha-256\"" => IndicatorType::Sha256,
+            "file:hashes.'sha-1'" | "file:hashes.sha1" | "file:hashes.\"sha-1\"" => IndicatorType::Sha1,
+            "file:hashes.md5" | "file:hashes.'md5'" => IndicatorType::Md5,
+            _ if compound => continue,
+            "ipv4-addr:value" => IndicatorType::IPv4,
+            "ipv6-addr:value" => IndicatorType::IPv6,
+            "domain-name:value" => IndicatorType::Domain,
+            "url:value" => IndicatorType::Url,
+            _ => continue,
+        };
+        // CIDR không tra cứu được bằng set
+        if matches!(indicator_type, IndicatorType::IPv4 | IndicatorType::IPv6) && value.contains('/') {
+            continue;
+        }
+        results.push((indicator_type, value));
+    }
+    results
+}
+
+/// Vị trí dấu `'` đóng (bỏ qua `\'`)
+fn find_closing_quote(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    (0..bytes.len()).find(|&i| bytes[i] == b'\'' && (i == 0 || bytes[i - 1] != b'\\'))
+}
+
+/// Áp dụng thay đổi: chỉ nhận version mới hơn
+fn apply_update(objects: &mut HashMap<String, StoredIndicator>, update: StixUpdate) {
+    match update {
+        StixUpdate::Revoke(id) => {
+            objects.remove(&id);
+        }
+        StixUpdate::Upsert(id, indicator) => {
+            let newer = objects.get(&id).map_or(true, |existing| indicator.modified >= existing.modified);
+            if newer {
+                objects.insert(id, indicator);
+            }
+        }
+    }
+}
+
+fn active_indicators(state: &CollectionState, now: i64) -> Vec<ThreatIndicator> {
+    state.objects.values()
+        .filter(|o| o.is_active(now))
+        .flat_map(|o| o.indicators.iter().cloned())
+        .collect()
+}
+
+// ============================================================================
+// POLL
+// ============================================================================
+
+fn poll_collection(collection: &TaxiiCollection, added_after: Option<&str>) -> Result<(Vec<serde_json::Value>, Option<String>), String> {
+    let url = format!(
+        "{}/collections/{}/objects/",
+        collection.api_root.trim_end_matches('/'),
+        collection.collection_id
+    );
+    let mut objects = Vec::new();
+    let mut cursor = added_after.map(str::to_string);
+    let mut next: Option<String> = None;
+
+    for _ in 0..MAX_PAGES {
+        let mut request = ureq::get(&url)
+            .set("Accept", TAXII_MEDIA_TYPE)
+            .timeout(Duration::from_secs(60))
+            .query("match[type]", "indicator")
+            .query("limit", &PAGE_LIMIT.to_string());
+        if let Some(after) = &added_after {
+            request = request.query("added_after", after);
+        }
+        if let Some(next) = &next {
+            request = request.query("next", next);
+        }
+        if let (Some(user), Some(password)) = (&collection.username, &collection.password) {
+            let token = BASE64.encode(format!("{}:{}", user, password));
+            request = request.set("Authorization", &format!("Basic {}", token));
+        }
+        if let (Some(header), Some(key)) = (&collection.api_key_header, &collection.api_key) {
+            request = request.set(header, key);
+        }
+
+        let response = request.call().map_err(|e| match e {
+            ureq::Error::Status(401, _) | ureq::Error::Status(403, _) => "TAXII server rejected credentials".to_string(),
+            e => e.to_string(),
+        })?;
+        if let Some(last) = response.header("X-TAXII-Date-Added-Last") {
+            cursor = Some(last.to_string());
+        }
+        let text = response.into_string().map_err(|e| e.to_string())?;
+        let envelope: Envelope = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid TAXII envelope: {}", e))?;
+
+        objects.extend(envelope.objects);
+        match envelope.next.filter(|_| envelope.more) {
+            Some(n) => next = Some(n),
+            None => break,
+        }
+    }
+
+    Ok((objects, cursor))
+}
+
+/// Poll một collection, cập nhật store + threat feed. Trả về số indicator active.
+fn sync_collection(collection: &TaxiiCollection) -> Result<usize, String> {
+    let key = collection.key();
+    let added_after = STATE.read().state.get(&key).and_then(|s| s.added_after.clone());
+    let result = poll_collection(collection, added_after.as_deref());
+
+    let now = Utc::now().timestamp();
+    let mut guard = STATE.write();
+    let state = guard.state.entry(key).or_default();
+    state.last_poll = Some(now);
+
+    let (objects, cursor) = match result {
+        Ok(r) => r,
+        Err(e) => {
+            state.last_error = Some(e.clone());
+            return Err(e);
+        }
+    };
+    let source = collection.source();
+    let mut parsed = 0;
+    for object in &objects {
+        if let Some(update) = parse_stix_object(object, &source) {
+            apply_update(&mut state.objects, update);
+            parsed += 1;
+        }
+    }
+    state.added_after = cursor;
+    state.last_error = None;
+
+    let active = active_indicators(state, now);
+    let count = active.len();
+    save_state(&guard.state);
+    drop(guard);
+
+    threat_feed::replace_source_indicators(&source, active);
+    log::info!("TAXII {}: {} STIX indicators received, {} active", collection.name, parsed, count);
+    Ok(count)
+}
+
+/// Tính lại indicator active (valid_until hết hạn giữa các lần poll)
+fn refresh_validity() {
+    let now = Utc::now().timestamp();
+    let updates: Vec<(String, Vec<ThreatIndicator>)> = {
+        let guard = STATE.read();
+        guard.collections.iter()
+            .filter_map(|c| guard.state.get(&c.key()).map(|s| (c.source(), active_indicators(s, now))))
+            .collect()
+    };
+    for (source, indicators) in updates {
+        threat_feed::replace_source_indicators(&source, indicators);
+    }
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Áp dụng section `taxii` của cloud policy
+pub fn apply_cloud_config(config: Option<&serde_json::Value>) {
+    let policy = config
+        .and_then(|v| serde_json::from_value::<TaxiiPolicy>(v.clone()).ok())
+        .unwrap_or_default();
+    let collections: Vec<TaxiiCollection> = policy.collections.into_iter()
+        .filter(|c| match c.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("Ignoring TAXII collection from cloud policy: {}", e);
+                false
+            }
+        })
+        .collect();
+
+    let removed: Vec<String> = {
+        let mut guard = STATE.write();
+        if guard.collections == collections {
+            return;
+        }
+        let removed = guard.collections.iter()
+            .filter(|old| !collections.iter().any(|c| c.source() == old.source()))
+            .map(|old| old.source())
+            .collect();
+        guard.collections = collections;
+        removed
+    };
+    for source in removed {
+        threat_feed::replace_source_indicators(&source, Vec::new());
+    }
+    refresh_validity();
+}
+
+/// Poll tất cả collection ngay
+pub fn sync_all() -> Result<usize, String> {
+    if POLLING.swap(true, Ordering::SeqCst) {
+        return Err("TAXII poll already in progress".to_string());
+    }
+    let collections = STATE.read().collections.clone();
+    let mut total = 0;
+    let mut errors = Vec::new();
+    for collection in &collections {
+        match sync_collection(collection) {
+            Ok(count) => total += count,
+            Err(e) => {
+                log::warn!("TAXII {} poll failed: {}", collection.name, e);
+                errors.push(format!("{}: {}", collection.name, e));
+            }
+        }
+    }
+    POLLING.store(false, Ordering::SeqCst);
+
+    if errors.is_empty() || total > 0 {
+        Ok(total)
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// Scheduler: poll theo `poll_interval_mins` từng collection, tính lại validity mỗi tick
+pub fn start() {
+    if SCHEDULER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(|| loop {
+        std::thread::sleep(TICK);
+
+        let now = Utc::now().timestamp();
+        let due: Vec<TaxiiCollection> = {
+            let guard = STATE.read();
+            guard.collections.iter()
+                .filter(|c| {
+                    guard.state.get(&c.key())
+                        .and_then(|s| s.last_poll)
+                        .map_or(true, |t| now - t >= c.poll_interval_mins as i64 * 60)
+                })
+                .cloned()
+                .collect()
+        };
+        for collection in &due {
+            let _ = sync_collection(collection);
+        }
+        if due.is_empty() {
+            refresh_validity();
+        }
+    });
+}
+
+pub fn get_status() -> Vec<TaxiiCollectionStatus> {
+    let now = Utc::now().timestamp();
+    let guard = STATE.read();
+    guard.collections.iter()
+        .map(|c| {
+            let state = guard.state.get(&c.key());
+            TaxiiCollectionStatus {
+                name: c.name.clone(),
+                api_root: c.api_root.clone(),
+                collection_id: c.collection_id.clone(),
+                last_poll: state.and_then(|s| s.last_poll),
+                last_error: state.and_then(|s| s.last_error.clone()),
+                stix_objects: state.map(|s| s.objects.len()).unwrap_or(0),
+                active_indicators: state.map(|s| s.objects.values().filter(|o| o.is_active(now)).count()).unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn state_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(STATE_FILE)
+}
+
+fn load_state() -> HashMap<String, CollectionState> {
+    std::fs::read_to_string(state_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(state: &HashMap<String, CollectionState>) {
+    let path = state_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string(state) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pattern() {
+        let p = parse_pattern("[file:hashes.'SHA-256' = 'aabb' OR file:hashes.MD5 = 'ccdd']");
+        assert_eq!(p, vec![(IndicatorType::Sha256, "aabb".to_string()), (IndicatorType::Md5, "ccdd".to_string())]);
+
+        let p = parse_pattern("[domain-name:value = 'evil.example'] OR [ipv4-addr:value = '198.51.100.7']");
+        assert_eq!(p, vec![(IndicatorType::Domain, "evil.example".to_string()), (IndicatorType::IPv4, "198.51.100.7".to_string())]);
+
+        // AND: chỉ giữ hash, bỏ CIDR
+        let p = parse_pattern("[file:name = 'a.exe' AND file:hashes.'SHA-1' = 'eeff']");
+        assert_eq!(p, vec![(IndicatorType::Sha1, "eeff".to_string())]);
+        assert!(parse_pattern("[ipv4-addr:value = '10.0.0.0/8']").is_empty());
+    }
+
+    #[test]
+    fn test_validity_and_revocation() {
+        let object = serde_json::json!({
+            "type": "indicator",
+            "spec_version": "2.1",
+            "id": "indicator--1",
+            "created": "2024-01-01T00:00:00Z",
+            "modified": "2024-01-02T00:00:00Z",
+            "pattern": "[url:value = 'http://evil.example/a']",
+            "pattern_type": "stix",
+            "valid_from": "2024-01-01T00:00:00Z",
+            "valid_until": "2024-02-01T00:00:00Z",
+            "confidence": 90
+        });
+        let mut objects = HashMap::new();
+        apply_update(&mut objects, parse_stix_object(&object, "TAXII: test").unwrap());
+        let state = CollectionState { objects, ..Default::default() };
+
+        let jan_15 = parse_time("2024-01-15T00:00:00Z").unwrap();
+        let active = active_indicators(&state, jan_15);
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].threat_level, ThreatLevel::Critical);
+        assert!(active_indicators(&state, parse_time("2024-03-01T00:00:00Z").unwrap()).is_empty());
+
+        // Version cũ hơn bị bỏ qua, revoke xóa
+        let mut objects = state.objects;
+        let mut older = object.clone();
+        older["modified"] = serde_json::json!("2023-12-01T00:00:00Z");
+        older["pattern"] = serde_json::json!("[url:value = 'http://old.example/']");
+        apply_update(&mut objects, parse_stix_object(&older, "TAXII: test").unwrap());
+        assert_eq!(objects["indicator--1"].indicators[0].value, "http://evil.example/a");
+
+        let mut revoked = object;
+        revoked["revoked"] = serde_json::json!(true);
+        apply_update(&mut objects, parse_stix_object(&revoked, "TAXII: test").unwrap());
+        assert!(objects.is_empty());
+    }
+}
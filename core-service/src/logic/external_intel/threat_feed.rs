@@ -3,16 +3,27 @@
 //! Mục đích: Sync known-bad indicators từ các threat intelligence feeds
 //!
 //! Feeds supported:
-//! - URLhaus (abuse.ch)
+//! - URLhaus (abuse.ch) - URL đang phát tán malware
+//! - MalwareBazaar (abuse.ch) - SHA256 sample 48h gần nhất, tích lũy dần
+//! - Feodo Tracker (abuse.ch) - botnet C2 IP
 //! - Emerging Threats
 //! - Custom feeds
+//!
+//! Update:
+//! - Snapshot feed: conditional GET (ETag / Last-Modified), 304 → giữ nguyên
+//! - Incremental feed: merge vào store local, indicator hết hạn sau `INCREMENTAL_RETENTION_DAYS`
+//! - Store lưu tại `threat_feeds.json` → có dữ liệu ngay khi khởi động, kể cả offline
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::net::IpAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
-use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use parking_lot::{Mutex, RwLock};
 use once_cell::sync::Lazy;
 use chrono::Utc;
+use serde::{Deserialize, Serialize};
 
 use super::types::{ThreatIndicator, IndicatorType, ThreatLevel};
 
@@ -23,39 +34,108 @@ use super::types::{ThreatIndicator, IndicatorType, ThreatLevel};
 /// Available threat feed sources
 pub const FEED_SOURCES: &[FeedSource] = &[
     FeedSource {
-        name: "URLhaus",
-        url: "https://urlhaus.abuse.ch/downloads/text/",
+        id: "urlhaus",
+        name: "URLhaus - Online URLs",
+        url: "https://urlhaus.abuse.ch/downloads/text_online/",
         indicator_type: IndicatorType::Url,
+        mode: FeedMode::Snapshot,
+        threat_level: ThreatLevel::High,
+        tags: &["malware_download"],
         enabled: true,
     },
     FeedSource {
-        name: "Emerging Threats - Compromised IPs",
-        url: "https://rules.emergingthreats.net/blockrules/compromised-ips.txt",
-        indicator_type: IndicatorType::IPv4,
+        id: "malwarebazaar",
+        name: "MalwareBazaar - Recent SHA256",
+        url: "https://bazaar.abuse.ch/export/txt/sha256/recent/",
+        indicator_type: IndicatorType::Sha256,
+        mode: FeedMode::Incremental,
+        threat_level: ThreatLevel::Critical,
+        tags: &["malware"],
         enabled: true,
     },
     FeedSource {
+        id: "feodo",
         name: "Feodo Tracker - Botnet C2",
-        url: "https://feodotracker.abuse.ch/downloads/ipblocklist.txt",
+        url: "https://feodotracker.abuse.ch/downloads/ipblocklist_recommended.txt",
+        indicator_type: IndicatorType::IPv4,
+        mode: FeedMode::Snapshot,
+        threat_level: ThreatLevel::Critical,
+        tags: &["botnet", "c2"],
+        enabled: true,
+    },
+    FeedSource {
+        id: "et_compromised",
+        name: "Emerging Threats - Compromised IPs",
+        url: "https://rules.emergingthreats.net/blockrules/compromised-ips.txt",
         indicator_type: IndicatorType::IPv4,
+        mode: FeedMode::Snapshot,
+        threat_level: ThreatLevel::Medium,
+        tags: &["compromised"],
         enabled: true,
     },
 ];
 
 #[derive(Debug, Clone)]
 pub struct FeedSource {
+    /// Key trong store local
+    pub id: &'static str,
     pub name: &'static str,
     pub url: &'static str,
     pub indicator_type: IndicatorType,
+    pub mode: FeedMode,
+    pub threat_level: ThreatLevel,
+    /// Tag gán cho indicator của feed (feed text không có metadata)
+    pub tags: &'static [&'static str],
     pub enabled: bool,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedMode {
+    /// Feed là danh sách đầy đủ hiện tại → thay thế
+    Snapshot,
+    /// Feed chỉ chứa indicator mới → merge + retention
+    Incremental,
+}
+
+/// Sync định kỳ (abuse.ch khuyến nghị không quá 5 phút/lần)
+const SYNC_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Giữ indicator của incremental feed trong bao lâu kể từ lần cuối xuất hiện
+const INCREMENTAL_RETENTION_DAYS: i64 = 90;
+
+/// Host dùng chung (file hosting, CDN) → chỉ match URL đầy đủ, không flag cả domain
+const SHARED_HOSTS: &[&str] = &[
+    "github.com", "githubusercontent.com", "gitlab.com", "bitbucket.org",
+    "google.com", "googleusercontent.com", "dropbox.com", "dropboxusercontent.com",
+    "onedrive.live.com", "1drv.ms", "sharepoint.com", "discordapp.com", "discord.com",
+    "cdn.discordapp.com", "mediafire.com", "pastebin.com", "transfer.sh", "amazonaws.com",
+];
+
+const STORE_FILE: &str = "threat_feeds.json";
+
 // ============================================================================
 // STATE
 // ============================================================================
 
 static THREAT_FEED: Lazy<RwLock<ThreatFeed>> =
-    Lazy::new(|| RwLock::new(ThreatFeed::new()));
+    Lazy::new(|| RwLock::new(ThreatFeed::load()));
+
+static MATCH_STATS: Lazy<Mutex<FeedMatchStats>> =
+    Lazy::new(|| Mutex::new(FeedMatchStats::default()));
+
+static SYNCING: AtomicBool = AtomicBool::new(false);
+static SCHEDULER_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Trạng thái một feed (lưu xuống disk)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct FeedState {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    last_sync: Option<i64>,
+    last_error: Option<String>,
+    /// Indicator (đã normalize) → lần cuối thấy trong feed
+    indicators: HashMap<String, i64>,
+}
 
 // ============================================================================
 // THREAT FEED
@@ -77,12 +157,12 @@ pub struct ThreatFeed {
     /// Custom indicators with full metadata
     custom_indicators: Vec<ThreatIndicator>,
 
+    /// Dữ liệu từng feed (theo `FeedSource::id`)
+    sources: HashMap<String, FeedState>,
+
     /// Last sync time
     last_sync: Option<i64>,
 
-    /// Sync in progress
-    syncing: bool,
-
     /// Enabled
     enabled: bool,
 }
@@ -95,123 +175,95 @@ impl ThreatFeed {
             malicious_urls: HashSet::new(),
             malicious_hashes: HashSet::new(),
             custom_indicators: Vec::new(),
+            sources: HashMap::new(),
             last_sync: None,
-            syncing: false,
             enabled: true,
         }
     }
 
-    /// Sync all enabled feeds (blocking)
-    pub fn sync_all(&mut self) -> Result<SyncResult, String> {
-        if self.syncing {
-            return Err("Sync already in progress".to_string());
-        }
+    /// Feed với store đã lưu trên disk
+    fn load() -> Self {
+        let mut feed = Self::new();
+        feed.sources = load_store();
+        feed.last_sync = feed.sources.values().filter_map(|s| s.last_sync).max();
+        feed.rebuild();
+        feed
+    }
 
-        self.syncing = true;
-        let mut result = SyncResult {
-            success: true,
-            feeds_synced: 0,
-            total_indicators: 0,
-            errors: Vec::new(),
-        };
+    /// Ghép nội dung feed vừa tải vào store
+    fn apply_feed(&mut self, source: &FeedSource, content: &str, now: i64) -> usize {
+        let values = parse_indicators(content, source.indicator_type);
+        let count = values.len();
+        let state = self.sources.entry(source.id.to_string()).or_default();
 
-        for source in FEED_SOURCES {
-            if !source.enabled {
-                continue;
-            }
-
-            match self.sync_feed(source) {
-                Ok(count) => {
-                    result.feeds_synced += 1;
-                    result.total_indicators += count;
-                    log::info!("Synced {} indicators from {}", count, source.name);
-                }
-                Err(e) => {
-                    result.errors.push(format!("{}: {}", source.name, e));
-                    log::warn!("Failed to sync {}: {}", source.name, e);
-                }
-            }
+        if source.mode == FeedMode::Snapshot {
+            state.indicators.clear();
         }
-
-        self.last_sync = Some(Utc::now().timestamp());
-        self.syncing = false;
-
-        if !result.errors.is_empty() {
-            result.success = result.feeds_synced > 0;
+        for value in values {
+            state.indicators.insert(value, now);
         }
-
-        Ok(result)
-    }
-
-    /// Sync a single feed
-    fn sync_feed(&mut self, source: &FeedSource) -> Result<usize, String> {
-        // Fetch content (blocking)
-        let response = ureq::get(source.url)
-            .timeout(std::time::Duration::from_secs(30))
-            .call()
-            .map_err(|e| e.to_string())?;
-
-        let content = response.into_string()
-            .map_err(|e| e.to_string())?;
-
-        let count = self.parse_feed(&content, source);
-        Ok(count)
+        if source.mode == FeedMode::Incremental {
+            let cutoff = now - INCREMENTAL_RETENTION_DAYS * 86_400;
+            state.indicators.retain(|_, seen| *seen >= cutoff);
+        }
+        state.last_sync = Some(now);
+        state.last_error = None;
+        count
     }
 
-    /// Parse feed content
-    fn parse_feed(&mut self, content: &str, source: &FeedSource) -> usize {
-        let mut count = 0;
-
-        for line in content.lines() {
-            let line = line.trim();
+    /// Dựng lại lookup set từ store + custom indicators
+    fn rebuild(&mut self) {
+        self.malicious_ips.clear();
+        self.malicious_domains.clear();
+        self.malicious_urls.clear();
+        self.malicious_hashes.clear();
 
-            // Skip comments and empty lines
-            if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
-                continue;
+        for source in FEED_SOURCES {
+            let Some(state) = self.sources.get(source.id) else { continue };
+            let values: Vec<String> = state.indicators.keys().cloned().collect();
+            for value in values {
+                self.insert_lookup(source.indicator_type, &value);
             }
+        }
+        let custom: Vec<(IndicatorType, String)> = self.custom_indicators.iter()
+            .map(|i| (i.indicator_type, i.value.clone()))
+            .collect();
+        for (indicator_type, value) in custom {
+            self.insert_lookup(indicator_type, &value);
+        }
+    }
 
-            match source.indicator_type {
-                IndicatorType::IPv4 | IndicatorType::IPv6 => {
-                    // Try to parse as IP
-                    if let Ok(ip) = IpAddr::from_str(line) {
-                        self.malicious_ips.insert(ip);
-                        count += 1;
-                    }
-                }
-                IndicatorType::Domain => {
-                    // Just add as domain
-                    let domain = line.to_lowercase();
-                    if !domain.is_empty() && domain.contains('.') {
-                        self.malicious_domains.insert(domain);
-                        count += 1;
-                    }
+    fn insert_lookup(&mut self, indicator_type: IndicatorType, value: &str) {
+        match indicator_type {
+            IndicatorType::IPv4 | IndicatorType::IPv6 => {
+                if let Ok(ip) = IpAddr::from_str(value) {
+                    self.malicious_ips.insert(ip);
                 }
-                IndicatorType::Url => {
-                    // Add as URL
-                    let url = line.to_lowercase();
-                    if url.starts_with("http://") || url.starts_with("https://") {
-                        self.malicious_urls.insert(url.clone());
-
-                        // Also extract domain
-                        if let Some(domain) = extract_domain(&url) {
-                            self.malicious_domains.insert(domain);
+            }
+            IndicatorType::Domain => {
+                self.malicious_domains.insert(value.to_lowercase());
+            }
+            IndicatorType::Url => {
+                let url = value.to_lowercase();
+                // URL đặt trên host riêng → flag cả host
+                if let Some(host) = extract_domain(&url) {
+                    match IpAddr::from_str(&host) {
+                        Ok(ip) => {
+                            self.malicious_ips.insert(ip);
                         }
-                        count += 1;
-                    }
-                }
-                IndicatorType::Sha256 | IndicatorType::Sha1 | IndicatorType::Md5 => {
-                    // Add as hash
-                    let hash = line.to_lowercase();
-                    if is_valid_hash(&hash) {
-                        self.malicious_hashes.insert(hash);
-                        count += 1;
+                        Err(_) if !is_shared_host(&host) => {
+                            self.malicious_domains.insert(host);
+                        }
+                        Err(_) => {}
                     }
                 }
-                _ => {}
+                self.malicious_urls.insert(url);
+            }
+            IndicatorType::Sha256 | IndicatorType::Sha1 | IndicatorType::Md5 => {
+                self.malicious_hashes.insert(value.to_lowercase());
             }
+            _ => {}
         }
-
-        count
     }
 
     /// Check if IP is malicious
@@ -221,11 +273,16 @@ impl ThreatFeed {
 
     /// Check if domain is malicious
     pub fn is_malicious_domain(&self, domain: &str) -> bool {
+        self.matching_domain(domain).is_some()
+    }
+
+    /// Domain (hoặc parent domain) khớp trong feed
+    fn matching_domain(&self, domain: &str) -> Option<String> {
         let domain_lower = domain.to_lowercase();
 
         // Check exact match
         if self.malicious_domains.contains(&domain_lower) {
-            return true;
+            return Some(domain_lower);
         }
 
         // Check parent domains
@@ -233,11 +290,11 @@ impl ThreatFeed {
         for i in 0..parts.len().saturating_sub(1) {
             let parent = parts[i..].join(".");
             if self.malicious_domains.contains(&parent) {
-                return true;
+                return Some(parent);
             }
         }
 
-        false
+        None
     }
 
     /// Check if hash is malicious
@@ -250,30 +307,60 @@ impl ThreatFeed {
         self.malicious_urls.contains(&url.to_lowercase())
     }
 
+    /// Indicator có metadata (custom / cloud) khớp value, fallback theo feed nguồn
+    pub fn find_indicator(&self, value: &str) -> Option<ThreatIndicator> {
+        if let Some(indicator) = self.custom_indicators.iter().find(|i| i.value.eq_ignore_ascii_case(value)) {
+            return Some(indicator.clone());
+        }
+        let key = value.to_lowercase();
+        FEED_SOURCES.iter().find_map(|source| {
+            let state = self.sources.get(source.id)?;
+            let seen = *state.indicators.get(&key)
+                .or_else(|| self.url_host_seen(source, state, &key))?;
+            Some(ThreatIndicator {
+                indicator_type: source.indicator_type,
+                value: key.clone(),
+                threat_level: source.threat_level,
+                source: source.name.to_string(),
+                first_seen: None,
+                last_seen: Some(seen),
+                tags: source.tags.iter().map(|t| t.to_string()).collect(),
+                description: None,
+            })
+        })
+    }
+
+    /// Host được suy ra từ URL của feed URL (URLhaus chỉ có URL)
+    fn url_host_seen<'a>(&self, source: &FeedSource, state: &'a FeedState, host: &str) -> Option<&'a i64> {
+        if source.indicator_type != IndicatorType::Url || is_shared_host(host) {
+            return None;
+        }
+        state.indicators.iter()
+            .find(|(url, _)| extract_domain(url).as_deref() == Some(host))
+            .map(|(_, seen)| seen)
+    }
+
+    /// Tên feed chứa value (cho thống kê match)
+    fn source_of(&self, value: &str) -> String {
+        self.find_indicator(value)
+            .map(|i| i.source)
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
     /// Add custom indicator
     pub fn add_indicator(&mut self, indicator: ThreatIndicator) {
         // Add to quick lookup sets
-        match indicator.indicator_type {
-            IndicatorType::IPv4 | IndicatorType::IPv6 => {
-                if let Ok(ip) = IpAddr::from_str(&indicator.value) {
-                    self.malicious_ips.insert(ip);
-                }
-            }
-            IndicatorType::Domain => {
-                self.malicious_domains.insert(indicator.value.to_lowercase());
-            }
-            IndicatorType::Url => {
-                self.malicious_urls.insert(indicator.value.to_lowercase());
-            }
-            IndicatorType::Sha256 | IndicatorType::Sha1 | IndicatorType::Md5 => {
-                self.malicious_hashes.insert(indicator.value.to_lowercase());
-            }
-            _ => {}
-        }
-
+        self.insert_lookup(indicator.indicator_type, &indicator.value);
         self.custom_indicators.push(indicator);
     }
 
+    /// Thay toàn bộ indicator của một nguồn có metadata (MISP, TAXII, ...)
+    pub fn replace_source_indicators(&mut self, source: &str, indicators: Vec<ThreatIndicator>) {
+        self.custom_indicators.retain(|i| i.source != source);
+        self.custom_indicators.extend(indicators);
+        self.rebuild();
+    }
+
     /// Get stats
     pub fn stats(&self) -> FeedStats {
         FeedStats {
@@ -284,6 +371,18 @@ impl ThreatFeed {
             custom_indicators: self.custom_indicators.len(),
             last_sync: self.last_sync,
             enabled: self.enabled,
+            sources: FEED_SOURCES.iter().map(|source| {
+                let state = self.sources.get(source.id);
+                FeedSourceStatus {
+                    id: source.id.to_string(),
+                    name: source.name.to_string(),
+                    indicator_type: source.indicator_type.as_str().to_string(),
+                    indicators: state.map(|s| s.indicators.len()).unwrap_or(0),
+                    last_sync: state.and_then(|s| s.last_sync),
+                    last_error: state.and_then(|s| s.last_error.clone()),
+                }
+            }).collect(),
+            matches: MATCH_STATS.lock().clone(),
         }
     }
 
@@ -294,6 +393,7 @@ impl ThreatFeed {
         self.malicious_urls.clear();
         self.malicious_hashes.clear();
         self.custom_indicators.clear();
+        self.sources.clear();
         self.last_sync = None;
     }
 }
@@ -304,6 +404,41 @@ impl Default for ThreatFeed {
     }
 }
 
+// ============================================================================
+// FETCH
+// ============================================================================
+
+enum FetchOutcome {
+    Updated { content: String, etag: Option<String>, last_modified: Option<String> },
+    NotModified,
+}
+
+/// Tải feed (blocking, ngoài lock). Snapshot feed gửi conditional headers.
+fn fetch_feed(source: &FeedSource, etag: Option<&str>, last_modified: Option<&str>) -> Result<FetchOutcome, String> {
+    let mut request = ureq::get(source.url)
+        .timeout(Duration::from_secs(30));
+    if source.mode == FeedMode::Snapshot {
+        if let Some(etag) = etag {
+            request = request.set("If-None-Match", etag);
+        }
+        if let Some(last_modified) = last_modified {
+            request = request.set("If-Modified-Since", last_modified);
+        }
+    }
+
+    let response = request.call().map_err(|e| e.to_string())?;
+    if response.status() == 304 {
+        return Ok(FetchOutcome::NotModified);
+    }
+
+    let etag = response.header("ETag").map(str::to_string);
+    let last_modified = response.header("Last-Modified").map(str::to_string);
+    let content = response.into_string()
+        .map_err(|e| e.to_string())?;
+
+    Ok(FetchOutcome::Updated { content, etag, last_modified })
+}
+
 // ============================================================================
 // SYNC RESULT
 // ============================================================================
@@ -312,6 +447,8 @@ impl Default for ThreatFeed {
 pub struct SyncResult {
     pub success: bool,
     pub feeds_synced: usize,
+    /// Feed trả về 304 (không đổi từ lần sync trước)
+    pub feeds_unchanged: usize,
     pub total_indicators: usize,
     pub errors: Vec<String>,
 }
@@ -329,39 +466,200 @@ pub struct FeedStats {
     pub custom_indicators: usize,
     pub last_sync: Option<i64>,
     pub enabled: bool,
+    pub sources: Vec<FeedSourceStatus>,
+    pub matches: FeedMatchStats,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FeedSourceStatus {
+    pub id: String,
+    pub name: String,
+    pub indicator_type: String,
+    pub indicators: usize,
+    pub last_sync: Option<i64>,
+    pub last_error: Option<String>,
+}
+
+/// Số lần lookup trúng feed (từ lúc khởi động)
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct FeedMatchStats {
+    pub total: u64,
+    /// Theo tên feed
+    pub by_source: HashMap<String, u64>,
+    /// Theo loại indicator (ip, domain, url, hash)
+    pub by_type: HashMap<String, u64>,
+    pub last_match: Option<FeedMatch>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FeedMatch {
+    pub value: String,
+    pub source: String,
+    pub at: i64,
+}
+
+fn record_match(kind: &str, value: &str) {
+    let source = THREAT_FEED.read().source_of(value);
+    if source == super::misp::SOURCE {
+        super::misp::record_sighting(value);
+    }
+    let mut stats = MATCH_STATS.lock();
+    stats.total += 1;
+    *stats.by_source.entry(source.clone()).or_insert(0) += 1;
+    *stats.by_type.entry(kind.to_string()).or_insert(0) += 1;
+    stats.last_match = Some(FeedMatch {
+        value: value.to_string(),
+        source,
+        at: Utc::now().timestamp(),
+    });
 }
 
 // ============================================================================
 // PUBLIC API
 // ============================================================================
 
-/// Sync all threat feeds
+/// Sync all enabled feeds (blocking; tải ngoài lock, lookup vẫn chạy trong lúc sync)
 pub fn sync_feeds() -> Result<SyncResult, String> {
-    THREAT_FEED.write().sync_all()
+    if SYNCING.swap(true, Ordering::SeqCst) {
+        return Err("Sync already in progress".to_string());
+    }
+
+    let mut result = SyncResult {
+        success: true,
+        feeds_synced: 0,
+        feeds_unchanged: 0,
+        total_indicators: 0,
+        errors: Vec::new(),
+    };
+
+    for source in FEED_SOURCES.iter().filter(|s| s.enabled) {
+        let (etag, last_modified) = {
+            let feed = THREAT_FEED.read();
+            let state = feed.sources.get(source.id);
+            (
+                state.and_then(|s| s.etag.clone()),
+                state.and_then(|s| s.last_modified.clone()),
+            )
+        };
+
+        match fetch_feed(source, etag.as_deref(), last_modified.as_deref()) {
+            Ok(FetchOutcome::Updated { content, etag, last_modified }) => {
+                let mut feed = THREAT_FEED.write();
+                let count = feed.apply_feed(source, &content, Utc::now().timestamp());
+                if let Some(state) = feed.sources.get_mut(source.id) {
+                    state.etag = etag;
+                    state.last_modified = last_modified;
+                }
+                result.feeds_synced += 1;
+                result.total_indicators += count;
+                log::info!("Synced {} indicators from {}", count, source.name);
+            }
+            Ok(FetchOutcome::NotModified) => {
+                let mut feed = THREAT_FEED.write();
+                let state = feed.sources.entry(source.id.to_string()).or_default();
+                state.last_sync = Some(Utc::now().timestamp());
+                state.last_error = None;
+                result.feeds_unchanged += 1;
+                log::debug!("{} not modified since last sync", source.name);
+            }
+            Err(e) => {
+                THREAT_FEED.write().sources.entry(source.id.to_string()).or_default().last_error = Some(e.clone());
+                result.errors.push(format!("{}: {}", source.name, e));
+                log::warn!("Failed to sync {}: {}", source.name, e);
+            }
+        }
+    }
+
+    {
+        let mut feed = THREAT_FEED.write();
+        feed.rebuild();
+        feed.last_sync = Some(Utc::now().timestamp());
+        save_store(&feed.sources);
+    }
+    SYNCING.store(false, Ordering::SeqCst);
+
+    if !result.errors.is_empty() {
+        result.success = result.feeds_synced + result.feeds_unchanged > 0;
+    }
+
+    Ok(result)
+}
+
+/// Scheduler sync định kỳ (sync ngay nếu store cũ hơn `SYNC_INTERVAL`)
+pub fn start() {
+    if SCHEDULER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(|| {
+        super::local_iocs::restore();
+        super::intel_bundle::restore();
+        let stats = get_stats();
+        log::info!(
+            "Threat feed scheduler started ({} IPs, {} domains, {} URLs, {} hashes cached)",
+            stats.total_ips, stats.total_domains, stats.total_urls, stats.total_hashes
+        );
+        loop {
+            let (enabled, last_sync) = {
+                let feed = THREAT_FEED.read();
+                (feed.enabled, feed.last_sync)
+            };
+            let due = last_sync
+                .map(|t| Utc::now().timestamp() - t >= SYNC_INTERVAL.as_secs() as i64)
+                .unwrap_or(true);
+            if enabled && due {
+                if let Err(e) = sync_feeds() {
+                    log::debug!("Threat feed sync skipped: {}", e);
+                }
+            }
+            super::local_iocs::expire();
+            std::thread::sleep(Duration::from_secs(60));
+        }
+    });
 }
 
 /// Check if IP is malicious
 pub fn is_malicious_ip(ip: &str) -> bool {
-    if let Ok(ip_addr) = IpAddr::from_str(ip) {
-        THREAT_FEED.read().is_malicious_ip(&ip_addr)
-    } else {
-        false
+    let hit = match IpAddr::from_str(ip) {
+        Ok(ip_addr) => THREAT_FEED.read().is_malicious_ip(&ip_addr),
+        Err(_) => false,
+    };
+    if hit {
+        record_match("ip", ip);
     }
+    hit
 }
 
 /// Check if domain is malicious
 pub fn is_malicious_domain(domain: &str) -> bool {
-    THREAT_FEED.read().is_malicious_domain(domain)
+    let matched = THREAT_FEED.read().matching_domain(domain);
+    if let Some(matched) = &matched {
+        record_match("domain", matched);
+    }
+    matched.is_some()
 }
 
 /// Check if hash is malicious
 pub fn is_malicious_hash(hash: &str) -> bool {
-    THREAT_FEED.read().is_malicious_hash(hash)
+    let hit = THREAT_FEED.read().is_malicious_hash(hash);
+    if hit {
+        record_match("hash", hash);
+    }
+    hit
 }
 
 /// Check if URL is malicious
 pub fn is_malicious_url(url: &str) -> bool {
-    THREAT_FEED.read().is_malicious_url(url)
+    let hit = THREAT_FEED.read().is_malicious_url(url);
+    if hit {
+        record_match("url", url);
+    }
+    hit
+}
+
+/// Metadata của indicator (custom / cloud, hoặc feed nguồn)
+pub fn find_indicator(value: &str) -> Option<ThreatIndicator> {
+    THREAT_FEED.read().find_indicator(value)
 }
 
 /// Add custom indicator
@@ -369,6 +667,11 @@ pub fn add_indicator(indicator: ThreatIndicator) {
     THREAT_FEED.write().add_indicator(indicator);
 }
 
+/// Thay toàn bộ indicator của một nguồn (pull lại từ MISP, TAXII, ...)
+pub fn replace_source_indicators(source: &str, indicators: Vec<ThreatIndicator>) {
+    THREAT_FEED.write().replace_source_indicators(source, indicators);
+}
+
 /// Get stats
 pub fn get_stats() -> FeedStats {
     THREAT_FEED.read().stats()
@@ -376,7 +679,9 @@ pub fn get_stats() -> FeedStats {
 
 /// Clear all data
 pub fn clear() {
-    THREAT_FEED.write().clear();
+    let mut feed = THREAT_FEED.write();
+    feed.clear();
+    save_store(&feed.sources);
 }
 
 /// Enable/disable feeds
@@ -384,10 +689,65 @@ pub fn set_enabled(enabled: bool) {
     THREAT_FEED.write().enabled = enabled;
 }
 
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn store_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(STORE_FILE)
+}
+
+fn load_store() -> HashMap<String, FeedState> {
+    std::fs::read_to_string(store_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_store(sources: &HashMap<String, FeedState>) {
+    let path = store_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string(sources) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
 // ============================================================================
 // UTILITIES
 // ============================================================================
 
+/// Parse feed dạng text (một indicator mỗi dòng, comment `#` / `//`)
+fn parse_indicators(content: &str, indicator_type: IndicatorType) -> Vec<String> {
+    content.lines()
+        .map(str::trim)
+        // Skip comments and empty lines
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with("//"))
+        .filter_map(|line| match indicator_type {
+            IndicatorType::IPv4 | IndicatorType::IPv6 => {
+                IpAddr::from_str(line).ok().map(|ip| ip.to_string())
+            }
+            IndicatorType::Domain => {
+                let domain = line.to_lowercase();
+                domain.contains('.').then_some(domain)
+            }
+            IndicatorType::Url => {
+                let url = line.to_lowercase();
+                (url.starts_with("http://") || url.starts_with("https://")).then_some(url)
+            }
+            IndicatorType::Sha256 | IndicatorType::Sha1 | IndicatorType::Md5 => {
+                let hash = line.to_lowercase();
+                is_valid_hash(&hash).then_some(hash)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
 /// Extract domain from URL
 fn extract_domain(url: &str) -> Option<String> {
     let url = url.strip_prefix("https://")
@@ -399,6 +759,10 @@ fn extract_domain(url: &str) -> Option<String> {
     Some(domain.to_lowercase())
 }
 
+fn is_shared_host(host: &str) -> bool {
+    SHARED_HOSTS.iter().any(|shared| host == *shared || host.ends_with(&format!(".{}", shared)))
+}
+
 /// Check if string is a valid hash
 fn is_valid_hash(s: &str) -> bool {
     let len = s.len();
@@ -442,4 +806,38 @@ mod tests {
         assert!(feed.is_malicious_domain("sub.evil.com"));
         assert!(!feed.is_malicious_domain("notevil.com"));
     }
+
+    #[test]
+    fn test_abuse_ch_feeds() {
+        let urlhaus = &FEED_SOURCES[0];
+        let bazaar = &FEED_SOURCES[1];
+        let mut feed = ThreatFeed::new();
+
+        let content = "# URLhaus online\nhttp://evil.example/payload.exe\nhttps://raw.githubusercontent.com/x/y/a.ps1\nhttp://45.1.2.3:8080/bins/mirai\n";
+        assert_eq!(feed.apply_feed(urlhaus, content, 1_000), 3);
+
+        let sha = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        feed.apply_feed(bazaar, &format!("################\n# MalwareBazaar\n{}\n", sha), 1_000);
+        feed.rebuild();
+
+        assert!(feed.is_malicious_url("http://evil.example/payload.exe"));
+        assert!(feed.is_malicious_domain("evil.example"));
+        assert!(feed.is_malicious_ip(&"45.1.2.3".parse().unwrap()));
+        // File hosting dùng chung → chỉ URL, không flag domain
+        assert!(!feed.is_malicious_domain("raw.githubusercontent.com"));
+        assert!(feed.is_malicious_hash(&sha.to_uppercase()));
+        assert_eq!(feed.find_indicator("evil.example").unwrap().source, urlhaus.name);
+
+        // Snapshot thay thế; incremental giữ tới hết retention
+        feed.apply_feed(urlhaus, "http://other.example/x\n", 2_000);
+        let later = 1_000 + INCREMENTAL_RETENTION_DAYS * 86_400 + 1;
+        feed.apply_feed(bazaar, "", 2_000);
+        feed.rebuild();
+        assert!(!feed.is_malicious_url("http://evil.example/payload.exe"));
+        assert!(feed.is_malicious_hash(sha));
+
+        feed.apply_feed(bazaar, "", later);
+        feed.rebuild();
+        assert!(!feed.is_malicious_hash(sha));
+    }
 }
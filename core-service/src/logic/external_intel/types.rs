@@ -194,11 +194,16 @@ pub struct MitreTechnique {
     pub description: String,
     pub url: String,
     pub sub_techniques: Vec<String>,
+    /// Tất cả tactic của technique (dataset STIX có thể gán nhiều), `tactic` = tactic đầu tiên
+    #[serde(default)]
+    pub tactics: Vec<MitreTactic>,
 }
 
 /// MITRE ATT&CK Tactic
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum MitreTactic {
+    Reconnaissance,
+    ResourceDevelopment,
     InitialAccess,
     Execution,
     Persistence,
@@ -214,8 +219,52 @@ pub enum MitreTactic {
 }
 
 impl MitreTactic {
+    /// Thứ tự kill chain (dùng cho báo cáo coverage)
+    pub const ALL: [MitreTactic; 14] = [
+        MitreTactic::Reconnaissance,
+        MitreTactic::ResourceDevelopment,
+        MitreTactic::InitialAccess,
+        MitreTactic::Execution,
+        MitreTactic::Persistence,
+        MitreTactic::PrivilegeEscalation,
+        MitreTactic::DefenseEvasion,
+        MitreTactic::CredentialAccess,
+        MitreTactic::Discovery,
+        MitreTactic::LateralMovement,
+        MitreTactic::Collection,
+        MitreTactic::CommandAndControl,
+        MitreTactic::Exfiltration,
+        MitreTactic::Impact,
+    ];
+
+    /// `phase_name` trong `kill_chain_phases` của STIX ("defense-evasion", ...)
+    pub fn from_phase_name(phase: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.phase_name() == phase)
+    }
+
+    pub fn phase_name(&self) -> &'static str {
+        match self {
+            MitreTactic::Reconnaissance => "reconnaissance",
+            MitreTactic::ResourceDevelopment => "resource-development",
+            MitreTactic::InitialAccess => "initial-access",
+            MitreTactic::Execution => "execution",
+            MitreTactic::Persistence => "persistence",
+            MitreTactic::PrivilegeEscalation => "privilege-escalation",
+            MitreTactic::DefenseEvasion => "defense-evasion",
+            MitreTactic::CredentialAccess => "credential-access",
+            MitreTactic::Discovery => "discovery",
+            MitreTactic::LateralMovement => "lateral-movement",
+            MitreTactic::Collection => "collection",
+            MitreTactic::CommandAndControl => "command-and-control",
+            MitreTactic::Exfiltration => "exfiltration",
+            MitreTactic::Impact => "impact",
+        }
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
+            MitreTactic::Reconnaissance => "Reconnaissance",
+            MitreTactic::ResourceDevelopment => "Resource Development",
             MitreTactic::InitialAccess => "Initial Access",
             MitreTactic::Execution => "Execution",
             MitreTactic::Persistence => "Persistence",
@@ -233,6 +282,8 @@ impl MitreTactic {
 
     pub fn id(&self) -> &'static str {
         match self {
+            MitreTactic::Reconnaissance => "TA0043",
+            MitreTactic::ResourceDevelopment => "TA0042",
             MitreTactic::InitialAccess => "TA0001",
             MitreTactic::Execution => "TA0002",
             MitreTactic::Persistence => "TA0003",
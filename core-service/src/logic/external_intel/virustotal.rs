@@ -4,15 +4,19 @@
 //!
 //! Features:
 //! - Check file hash (SHA256, SHA1, MD5)
-//! - Rate limiting (free tier: 4 req/min)
-//! - Local caching to reduce API calls
-
-use std::collections::HashMap;
-use std::path::Path;
+//! - Quota theo sliding window (free tier: 4 req/min, 500 req/ngày)
+//! - Queue: hash bị rate-limit được worker nền tra lại khi có quota
+//! - Cache kết quả theo hash có TTL, lưu xuống disk (`vt_cache.json`)
+//! - API key / quota cấu hình local (`virustotal.json`), cloud policy override
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
+use chrono::Utc;
 use parking_lot::RwLock;
 use once_cell::sync::Lazy;
-use sha2::{Sha256, Digest};
+use serde::{Deserialize, Serialize};
 
 use super::types::{VTResult, VTError, VTApiResponse, ThreatLevel};
 
@@ -22,29 +26,85 @@ use super::types::{VTResult, VTError, VTApiResponse, ThreatLevel};
 
 const VT_API_BASE: &str = "https://www.virustotal.com/api/v3";
 const FREE_TIER_RATE_LIMIT: u32 = 4; // requests per minute
+const FREE_TIER_DAILY_LIMIT: u32 = 500;
 const CACHE_MAX_SIZE: usize = 1000;
 const CACHE_TTL_HOURS: i64 = 24;
+const QUEUE_MAX_SIZE: usize = 500;
+const WORKER_IDLE: Duration = Duration::from_secs(5);
+const CONFIG_FILE: &str = "virustotal.json";
+const CACHE_FILE: &str = "vt_cache.json";
 
 // ============================================================================
 // STATE
 // ============================================================================
 
 static VT_CLIENT: Lazy<RwLock<VTClient>> =
-    Lazy::new(|| RwLock::new(VTClient::new()));
+    Lazy::new(|| RwLock::new(VTClient::load()));
+
+static WORKER_STARTED: AtomicBool = AtomicBool::new(false);
+
+// ============================================================================
+// CONFIG
+// ============================================================================
+
+/// Cấu hình VT (local settings, cloud policy `virustotal` override từng field)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VTConfig {
+    pub api_key: Option<String>,
+    /// Premium key được phép nâng quota
+    pub requests_per_minute: u32,
+    pub daily_limit: u32,
+    pub cache_ttl_hours: i64,
+}
+
+impl Default for VTConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            requests_per_minute: FREE_TIER_RATE_LIMIT,
+            daily_limit: FREE_TIER_DAILY_LIMIT,
+            cache_ttl_hours: CACHE_TTL_HOURS,
+        }
+    }
+}
+
+impl VTConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.requests_per_minute == 0 || self.daily_limit == 0 {
+            return Err("VirusTotal quota must be greater than zero".to_string());
+        }
+        if self.cache_ttl_hours <= 0 {
+            return Err("VirusTotal cache TTL must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    fn key(&self) -> Option<&str> {
+        self.api_key.as_deref().map(str::trim).filter(|k| !k.is_empty())
+    }
+}
 
 // ============================================================================
 // VT CLIENT
 // ============================================================================
 
 pub struct VTClient {
-    api_key: Option<String>,
+    local: VTConfig,
+    cloud_override: Option<serde_json::Value>,
+    config: VTConfig,
     cache: HashMap<String, CachedResult>,
-    last_request: Option<Instant>,
-    requests_this_minute: u32,
-    minute_start: Instant,
-    enabled: bool,
+    /// Thời điểm các request trong 60s gần nhất
+    request_log: VecDeque<Instant>,
+    day: chrono::NaiveDate,
+    requests_today: u32,
+    queue: VecDeque<String>,
+    processed: u64,
+    failed: u64,
+    last_error: Option<String>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 struct CachedResult {
     result: VTResult,
     cached_at: i64,
@@ -53,63 +113,137 @@ struct CachedResult {
 impl VTClient {
     pub fn new() -> Self {
         Self {
-            api_key: None,
+            local: VTConfig::default(),
+            cloud_override: None,
+            config: VTConfig::default(),
             cache: HashMap::new(),
-            last_request: None,
-            requests_this_minute: 0,
-            minute_start: Instant::now(),
-            enabled: false,
+            request_log: VecDeque::new(),
+            day: Utc::now().date_naive(),
+            requests_today: 0,
+            queue: VecDeque::new(),
+            processed: 0,
+            failed: 0,
+            last_error: None,
         }
     }
 
-    /// Set API key
+    /// Client với config + cache đã lưu trên disk
+    fn load() -> Self {
+        let mut client = Self::new();
+        client.local = load_config();
+        client.config = client.local.clone();
+        let ttl_secs = client.config.cache_ttl_hours * 3600;
+        let now = Utc::now().timestamp();
+        client.cache = load_cache();
+        client.cache.retain(|_, c| now - c.cached_at < ttl_secs);
+        client
+    }
+
+    /// Set API key (local settings)
     pub fn set_api_key(&mut self, key: &str) {
-        if key.is_empty() {
-            self.api_key = None;
-            self.enabled = false;
-        } else {
-            self.api_key = Some(key.to_string());
-            self.enabled = true;
-        }
+        let key = key.trim();
+        self.local.api_key = (!key.is_empty()).then(|| key.to_string());
+        self.recompute_config();
+    }
+
+    /// Cloud policy override (cùng field với `VTConfig`)
+    fn apply_cloud_override(&mut self, overrides: Option<&serde_json::Value>) {
+        self.cloud_override = overrides.filter(|v| v.is_object()).cloned();
+        self.recompute_config();
+    }
+
+    fn recompute_config(&mut self) {
+        let effective = match self.cloud_override.as_ref().and_then(|o| o.as_object()) {
+            Some(overrides) => {
+                let mut merged = serde_json::to_value(&self.local).unwrap_or_default();
+                if let Some(obj) = merged.as_object_mut() {
+                    for (key, value) in overrides {
+                        if obj.contains_key(key) {
+                            obj.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+                match serde_json::from_value::<VTConfig>(merged) {
+                    Ok(config) if config.validate().is_ok() => config,
+                    _ => {
+                        log::warn!("Ignoring invalid VirusTotal override from cloud policy");
+                        self.local.clone()
+                    }
+                }
+            }
+            None => self.local.clone(),
+        };
+        self.config = effective;
     }
 
     /// Check if client is configured
     pub fn is_configured(&self) -> bool {
-        self.api_key.is_some() && self.enabled
+        self.config.key().is_some()
     }
 
-    /// Check rate limit
-    fn check_rate_limit(&mut self) -> Result<(), VTError> {
-        let now = Instant::now();
-
-        // Reset counter if minute passed
-        if now.duration_since(self.minute_start) >= Duration::from_secs(60) {
-            self.minute_start = now;
-            self.requests_this_minute = 0;
+    /// Nguồn của API key đang dùng
+    fn key_source(&self) -> Option<&'static str> {
+        let from_cloud = self.cloud_override.as_ref()
+            .and_then(|o| o.get("api_key"))
+            .and_then(|k| k.as_str())
+            .is_some_and(|k| Some(k.trim()) == self.config.key());
+        match (self.config.key(), from_cloud) {
+            (None, _) => None,
+            (Some(_), true) => Some("cloud"),
+            (Some(_), false) => Some("local"),
         }
+    }
 
-        if self.requests_this_minute >= FREE_TIER_RATE_LIMIT {
-            let wait_time = 60 - now.duration_since(self.minute_start).as_secs();
-            return Err(VTError::RateLimited { retry_after: wait_time });
+    fn roll_window(&mut self, now: Instant) {
+        while self.request_log.front().is_some_and(|t| now.duration_since(*t) >= Duration::from_secs(60)) {
+            self.request_log.pop_front();
+        }
+        let today = Utc::now().date_naive();
+        if today != self.day {
+            self.day = today;
+            self.requests_today = 0;
         }
-
-        Ok(())
     }
 
-    /// Get from cache
-    fn get_cached(&self, hash: &str) -> Option<VTResult> {
-        let hash_lower = hash.to_lowercase();
-
-        if let Some(cached) = self.cache.get(&hash_lower) {
-            let now = chrono::Utc::now().timestamp();
-            let age_hours = (now - cached.cached_at) / 3600;
+    /// Giây chờ tới khi có quota (0 = gửi được ngay)
+    fn next_slot_secs(&mut self) -> u64 {
+        let now = Instant::now();
+        self.roll_window(now);
+
+        if self.requests_today >= self.config.daily_limit {
+            let midnight = (self.day + chrono::Duration::days(1))
+                .and_hms_opt(0, 0, 0)
+                .map(|t| t.and_utc().timestamp())
+                .unwrap_or_default();
+            return (midnight - Utc::now().timestamp()).max(1) as u64;
+        }
+        if self.request_log.len() as u32 >= self.config.requests_per_minute {
+            let oldest = self.request_log.front().copied().unwrap_or(now);
+            return 60u64.saturating_sub(now.duration_since(oldest).as_secs()).max(1);
+        }
+        0
+    }
 
-            if age_hours < CACHE_TTL_HOURS {
-                return Some(cached.result.clone());
+    /// Giữ một slot quota cho request sắp gửi
+    fn acquire_slot(&mut self) -> Result<(), VTError> {
+        match self.next_slot_secs() {
+            0 => {
+                self.request_log.push_back(Instant::now());
+                self.requests_today += 1;
+                Ok(())
             }
+            retry_after => Err(VTError::RateLimited { retry_after }),
         }
+    }
 
-        None
+    /// Get from cache
+    fn get_cached(&self, hash: &str) -> Option<VTResult> {
+        let cached = self.cache.get(&hash.to_lowercase())?;
+        let age_hours = (Utc::now().timestamp() - cached.cached_at) / 3600;
+        (age_hours < self.config.cache_ttl_hours).then(|| VTResult {
+            cached_at: Some(cached.cached_at),
+            ..cached.result.clone()
+        })
     }
 
     /// Add to cache
@@ -131,72 +265,63 @@ impl VTClient {
 
         self.cache.insert(hash_lower, CachedResult {
             result,
-            cached_at: chrono::Utc::now().timestamp(),
+            cached_at: Utc::now().timestamp(),
         });
     }
 
-    /// Query VT API for hash (blocking)
-    pub fn check_hash_sync(&mut self, hash: &str) -> Result<VTResult, VTError> {
-        // Check if configured
-        if !self.is_configured() {
-            return Err(VTError::Other {
-                message: "VirusTotal API key not configured".to_string()
-            });
+    /// Đưa hash vào queue (None nếu đã cache / đã trong queue / queue đầy)
+    fn enqueue(&mut self, hash: &str) -> Option<usize> {
+        if self.get_cached(hash).is_some() || self.queue.iter().any(|h| h == hash) {
+            return None;
         }
-
-        // Check cache first
-        if let Some(cached) = self.get_cached(hash) {
-            return Ok(VTResult { is_cached: true, ..cached });
+        if self.queue.len() >= QUEUE_MAX_SIZE {
+            log::warn!("VirusTotal queue full, dropping {}", hash);
+            return None;
         }
+        self.queue.push_back(hash.to_string());
+        Some(self.queue.len())
+    }
 
-        // Check rate limit
-        self.check_rate_limit()?;
-
-        let api_key = self.api_key.clone().unwrap();
-        let url = format!("{}/files/{}", VT_API_BASE, hash);
-
-        // Make request (blocking)
-        let response = ureq::get(&url)
-            .set("x-apikey", &api_key)
-            .call();
-
-        self.requests_this_minute += 1;
-        self.last_request = Some(Instant::now());
-
-        match response {
-            Ok(resp) => {
-                if resp.status() == 404 {
-                    return Err(VTError::NotFound);
-                }
-
-                let body = resp.into_string()
-                    .map_err(|e| VTError::ParseError { message: e.to_string() })?;
-
-                let api_response: VTApiResponse = serde_json::from_str(&body)
-                    .map_err(|e| VTError::ParseError { message: e.to_string() })?;
-
-                let result = parse_api_response(api_response);
-
-                // Cache result
-                self.cache_result(hash, result.clone());
-
-                Ok(result)
-            }
-            Err(ureq::Error::Status(401, _)) => {
-                Err(VTError::InvalidApiKey)
-            }
-            Err(ureq::Error::Status(429, _)) => {
-                Err(VTError::RateLimited { retry_after: 60 })
+    /// Ghi nhận kết quả request (cache + thống kê), trả về có cần lưu cache không
+    fn record_result(&mut self, hash: &str, result: &Result<VTResult, VTError>) -> bool {
+        match result {
+            Ok(vt) => {
+                self.processed += 1;
+                self.cache_result(hash, vt.clone());
+                true
             }
-            Err(ureq::Error::Status(404, _)) => {
-                Err(VTError::NotFound)
+            Err(VTError::NotFound) => {
+                self.processed += 1;
+                false
             }
             Err(e) => {
-                Err(VTError::NetworkError { message: e.to_string() })
+                self.failed += 1;
+                self.last_error = Some(e.to_string());
+                false
             }
         }
     }
 
+    /// Query VT API for hash (blocking, giữ client trong suốt request)
+    pub fn check_hash_sync(&mut self, hash: &str) -> Result<VTResult, VTError> {
+        let hash = normalize_hash(hash)?;
+        let api_key = self.config.key()
+            .map(str::to_string)
+            .ok_or_else(not_configured)?;
+
+        // Check cache first
+        if let Some(cached) = self.get_cached(&hash) {
+            return Ok(VTResult { is_cached: true, ..cached });
+        }
+
+        self.acquire_slot()?;
+        let result = fetch(&api_key, &hash);
+        if self.record_result(&hash, &result) {
+            save_cache(&self.cache);
+        }
+        result
+    }
+
     /// Get cache stats
     pub fn cache_stats(&self) -> (usize, usize) {
         (self.cache.len(), CACHE_MAX_SIZE)
@@ -205,6 +330,7 @@ impl VTClient {
     /// Clear cache
     pub fn clear_cache(&mut self) {
         self.cache.clear();
+        save_cache(&self.cache);
     }
 }
 
@@ -214,6 +340,48 @@ impl Default for VTClient {
     }
 }
 
+// ============================================================================
+// HTTP
+// ============================================================================
+
+/// GET /files/{hash} (không giữ lock của client)
+fn fetch(api_key: &str, hash: &str) -> Result<VTResult, VTError> {
+    let url = format!("{}/files/{}", VT_API_BASE, hash);
+
+    // Make request (blocking)
+    let response = ureq::get(&url)
+        .set("x-apikey", api_key)
+        .call();
+
+    match response {
+        Ok(resp) => {
+            if resp.status() == 404 {
+                return Err(VTError::NotFound);
+            }
+
+            let body = resp.into_string()
+                .map_err(|e| VTError::ParseError { message: e.to_string() })?;
+
+            let api_response: VTApiResponse = serde_json::from_str(&body)
+                .map_err(|e| VTError::ParseError { message: e.to_string() })?;
+
+            Ok(parse_api_response(api_response))
+        }
+        Err(ureq::Error::Status(401, _)) => {
+            Err(VTError::InvalidApiKey)
+        }
+        Err(ureq::Error::Status(429, _)) => {
+            Err(VTError::RateLimited { retry_after: 60 })
+        }
+        Err(ureq::Error::Status(404, _)) => {
+            Err(VTError::NotFound)
+        }
+        Err(e) => {
+            Err(VTError::NetworkError { message: e.to_string() })
+        }
+    }
+}
+
 // ============================================================================
 // PARSE RESPONSE
 // ============================================================================
@@ -263,9 +431,18 @@ fn parse_api_response(resp: VTApiResponse) -> VTResult {
 // PUBLIC API
 // ============================================================================
 
-/// Configure VT API key
+/// Configure VT API key (lưu vào local settings)
 pub fn set_api_key(key: &str) {
-    VT_CLIENT.write().set_api_key(key);
+    let mut client = VT_CLIENT.write();
+    client.set_api_key(key);
+    save_config(&client.local);
+    drop(client);
+    log::info!("VirusTotal API key {}", if key.trim().is_empty() { "cleared" } else { "updated" });
+}
+
+/// Áp dụng section `virustotal` của cloud policy
+pub fn apply_cloud_config(overrides: Option<&serde_json::Value>) {
+    VT_CLIENT.write().apply_cloud_override(overrides);
 }
 
 /// Check if VT is configured
@@ -273,9 +450,48 @@ pub fn is_configured() -> bool {
     VT_CLIENT.read().is_configured()
 }
 
-/// Check hash against VirusTotal
+/// Check hash against VirusTotal.
+/// Hết quota → hash được đưa vào queue, kết quả sẽ có trong cache khi worker xử lý xong.
 pub fn check_hash(hash: &str) -> Result<VTResult, VTError> {
-    VT_CLIENT.write().check_hash_sync(hash)
+    let hash = normalize_hash(hash)?;
+    let api_key = {
+        let mut client = VT_CLIENT.write();
+        let api_key = client.config.key()
+            .map(str::to_string)
+            .ok_or_else(not_configured)?;
+
+        if let Some(cached) = client.get_cached(&hash) {
+            return Ok(VTResult { is_cached: true, ..cached });
+        }
+        if let Err(e) = client.acquire_slot() {
+            client.enqueue(&hash);
+            drop(client);
+            ensure_worker();
+            return Err(e);
+        }
+        api_key
+    };
+
+    let result = fetch(&api_key, &hash);
+    let mut client = VT_CLIENT.write();
+    if client.record_result(&hash, &result) {
+        save_cache(&client.cache);
+    }
+    result
+}
+
+/// Đưa hash vào queue để tra khi có quota, trả về vị trí trong queue
+pub fn submit_hash(hash: &str) -> Result<Option<usize>, VTError> {
+    let hash = normalize_hash(hash)?;
+    let position = {
+        let mut client = VT_CLIENT.write();
+        if !client.is_configured() {
+            return Err(not_configured());
+        }
+        client.enqueue(&hash)
+    };
+    ensure_worker();
+    Ok(position)
 }
 
 /// Check file against VirusTotal (calculates SHA256 first)
@@ -287,7 +503,7 @@ pub fn check_file(path: &Path) -> Result<VTResult, VTError> {
 
 /// Get cached result without API call
 pub fn get_cached_result(hash: &str) -> Option<VTResult> {
-    VT_CLIENT.read().get_cached(hash)
+    VT_CLIENT.read().get_cached(hash).map(|r| VTResult { is_cached: true, ..r })
 }
 
 /// Clear cache
@@ -301,32 +517,141 @@ pub fn get_cache_stats() -> (usize, usize) {
 }
 
 // ============================================================================
-// UTILITIES
+// QUEUE WORKER
 // ============================================================================
 
-/// Calculate SHA256 of a file
-fn calculate_sha256(path: &Path) -> Result<String, VTError> {
-    use std::fs::File;
-    use std::io::Read;
-
-    let mut file = File::open(path)
-        .map_err(|e| VTError::Other { message: format!("Cannot open file: {}", e) })?;
-
-    let mut hasher = Sha256::new();
-    let mut buffer = [0u8; 8192];
+fn ensure_worker() {
+    if WORKER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let spawned = std::thread::Builder::new()
+        .name("vt-queue".to_string())
+        .spawn(worker_loop);
+    if let Err(e) = spawned {
+        WORKER_STARTED.store(false, Ordering::SeqCst);
+        log::warn!("Cannot start VirusTotal queue worker: {}", e);
+    }
+}
 
+/// Tra lần lượt hash trong queue, chờ theo quota giữa các request
+fn worker_loop() {
     loop {
-        let bytes_read = file.read(&mut buffer)
-            .map_err(|e| VTError::Other { message: format!("Cannot read file: {}", e) })?;
+        let job = {
+            let mut client = VT_CLIENT.write();
+            match (client.config.key().map(str::to_string), client.queue.front().cloned()) {
+                (Some(api_key), Some(hash)) => {
+                    if client.get_cached(&hash).is_some() {
+                        client.queue.pop_front();
+                        continue;
+                    }
+                    match client.next_slot_secs() {
+                        0 => {
+                            let _ = client.acquire_slot();
+                            client.queue.pop_front();
+                            Ok((api_key, hash))
+                        }
+                        wait => Err(Duration::from_secs(wait)),
+                    }
+                }
+                _ => Err(WORKER_IDLE),
+            }
+        };
 
-        if bytes_read == 0 {
-            break;
+        let (api_key, hash) = match job {
+            Ok(job) => job,
+            Err(wait) => {
+                std::thread::sleep(wait);
+                continue;
+            }
+        };
+
+        let result = fetch(&api_key, &hash);
+        let mut client = VT_CLIENT.write();
+        if let Err(VTError::RateLimited { retry_after }) = result {
+            // Server từ chối (quota chia sẻ giữa nhiều máy) → trả hash lại đầu queue
+            client.queue.push_front(hash);
+            drop(client);
+            std::thread::sleep(Duration::from_secs(retry_after.max(1)));
+            continue;
+        }
+        if client.record_result(&hash, &result) {
+            save_cache(&client.cache);
+        }
+        if let Ok(vt) = &result {
+            log::debug!("VirusTotal queue: {} → {}/{}", hash, vt.malicious, vt.total_engines);
         }
+    }
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
 
-        hasher.update(&buffer[..bytes_read]);
+fn data_path(file: &str) -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(file)
+}
+
+fn load_config() -> VTConfig {
+    std::fs::read_to_string(data_path(CONFIG_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str::<VTConfig>(&c).ok())
+        .filter(|c| c.validate().is_ok())
+        .unwrap_or_default()
+}
+
+fn save_config(config: &VTConfig) {
+    let path = data_path(CONFIG_FILE);
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(config) {
+        let _ = std::fs::write(path, json);
     }
+}
 
-    Ok(format!("{:x}", hasher.finalize()))
+fn load_cache() -> HashMap<String, CachedResult> {
+    std::fs::read_to_string(data_path(CACHE_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_cache(cache: &HashMap<String, CachedResult>) {
+    let path = data_path(CACHE_FILE);
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string(cache) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// UTILITIES
+// ============================================================================
+
+/// Calculate SHA256 of a file (shared hashing service cache)
+fn calculate_sha256(path: &Path) -> Result<String, VTError> {
+    crate::logic::process_intel::hashing::hash_file(path)
+        .map(|h| h.sha256)
+        .map_err(|e| VTError::Other { message: format!("Cannot hash file: {}", e) })
+}
+
+/// MD5 / SHA1 / SHA256 hex → lowercase
+fn normalize_hash(hash: &str) -> Result<String, VTError> {
+    let hash = hash.trim().to_lowercase();
+    if matches!(hash.len(), 32 | 40 | 64) && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(hash)
+    } else {
+        Err(VTError::Other { message: format!("Invalid hash: {}", hash) })
+    }
+}
+
+fn not_configured() -> VTError {
+    VTError::Other { message: "VirusTotal API key not configured".to_string() }
 }
 
 // ============================================================================
@@ -342,14 +667,59 @@ pub struct VTClientStats {
 }
 
 pub fn get_stats() -> VTClientStats {
-    let client = VT_CLIENT.read();
+    let mut client = VT_CLIENT.write();
+    client.roll_window(Instant::now());
     let (cache_size, cache_max) = client.cache_stats();
 
     VTClientStats {
         configured: client.is_configured(),
         cache_size,
         cache_max,
-        requests_this_minute: client.requests_this_minute,
+        requests_this_minute: client.request_log.len() as u32,
+    }
+}
+
+/// Trạng thái queue + quota (`get_vt_queue_status`)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct VTQueueStatus {
+    pub configured: bool,
+    /// "local" | "cloud"
+    pub key_source: Option<&'static str>,
+    pub pending: usize,
+    /// Các hash kế tiếp trong queue
+    pub next_hashes: Vec<String>,
+    pub requests_this_minute: u32,
+    pub requests_per_minute: u32,
+    pub requests_today: u32,
+    pub daily_limit: u32,
+    /// Giây tới khi gửi được request kế tiếp
+    pub next_slot_secs: u64,
+    pub cache_size: usize,
+    pub cache_ttl_hours: i64,
+    pub processed: u64,
+    pub failed: u64,
+    pub last_error: Option<String>,
+}
+
+pub fn get_queue_status() -> VTQueueStatus {
+    let mut client = VT_CLIENT.write();
+    let next_slot_secs = client.next_slot_secs();
+
+    VTQueueStatus {
+        configured: client.is_configured(),
+        key_source: client.key_source(),
+        pending: client.queue.len(),
+        next_hashes: client.queue.iter().take(10).cloned().collect(),
+        requests_this_minute: client.request_log.len() as u32,
+        requests_per_minute: client.config.requests_per_minute,
+        requests_today: client.requests_today,
+        daily_limit: client.config.daily_limit,
+        next_slot_secs,
+        cache_size: client.cache.len(),
+        cache_ttl_hours: client.config.cache_ttl_hours,
+        processed: client.processed,
+        failed: client.failed,
+        last_error: client.last_error.clone(),
     }
 }
 
@@ -405,4 +775,38 @@ mod tests {
 
         assert!(result.is_malware());
     }
+
+    #[test]
+    fn test_quota_window() {
+        let mut client = VTClient::new();
+        client.set_api_key("key");
+        for _ in 0..FREE_TIER_RATE_LIMIT {
+            assert!(client.acquire_slot().is_ok());
+        }
+        assert!(matches!(client.acquire_slot(), Err(VTError::RateLimited { retry_after }) if retry_after > 0));
+
+        let hash = "a".repeat(64);
+        assert_eq!(client.enqueue(&hash), Some(1));
+        assert_eq!(client.enqueue(&hash), None);
+        assert_eq!(client.requests_today, FREE_TIER_RATE_LIMIT);
+    }
+
+    #[test]
+    fn test_cloud_override() {
+        let mut client = VTClient::new();
+        client.set_api_key("local-key");
+        assert_eq!(client.key_source(), Some("local"));
+
+        client.apply_cloud_override(Some(&serde_json::json!({ "api_key": "org-key", "requests_per_minute": 500 })));
+        assert_eq!(client.config.key(), Some("org-key"));
+        assert_eq!(client.config.requests_per_minute, 500);
+        assert_eq!(client.key_source(), Some("cloud"));
+
+        // Override không hợp lệ → giữ local
+        client.apply_cloud_override(Some(&serde_json::json!({ "daily_limit": 0 })));
+        assert_eq!(client.config, client.local);
+
+        assert!(normalize_hash("ABC").is_err());
+        assert_eq!(normalize_hash(&"F".repeat(32)).unwrap(), "f".repeat(32));
+    }
 }
@@ -0,0 +1,92 @@
+//! DPAPI - Bọc secret lưu trên disk (vault key, telemetry key, ...)
+//!
+//! Dùng `CryptProtectData` scope máy (`CRYPTPROTECT_LOCAL_MACHINE`): service và UI
+//! của agent đều giải mã được, nhưng file bị copy sang máy khác thì vô dụng.
+//! Non-Windows: passthrough, secret chỉ được bảo vệ bằng quyền file.
+
+/// Mã hóa `data` bằng DPAPI
+pub fn protect(data: &[u8]) -> Result<Vec<u8>, String> {
+    platform::protect(data)
+}
+
+/// Giải mã blob tạo bởi `protect`
+pub fn unprotect(blob: &[u8]) -> Result<Vec<u8>, String> {
+    platform::unprotect(blob)
+}
+
+#[cfg(windows)]
+mod platform {
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{LocalFree, HLOCAL};
+    use windows::Win32::Security::Cryptography::{
+        CryptProtectData, CryptUnprotectData, CRYPTPROTECT_LOCAL_MACHINE, CRYPTPROTECT_UI_FORBIDDEN,
+        CRYPT_INTEGER_BLOB,
+    };
+
+    pub fn protect(data: &[u8]) -> Result<Vec<u8>, String> {
+        let input = blob(data);
+        let mut output = CRYPT_INTEGER_BLOB::default();
+        unsafe {
+            CryptProtectData(
+                &input,
+                PCWSTR::null(),
+                None,
+                None,
+                None,
+                CRYPTPROTECT_LOCAL_MACHINE | CRYPTPROTECT_UI_FORBIDDEN,
+                &mut output,
+            )
+        }
+        .map_err(|e| format!("CryptProtectData failed: {}", e))?;
+        Ok(take(output))
+    }
+
+    pub fn unprotect(data: &[u8]) -> Result<Vec<u8>, String> {
+        let input = blob(data);
+        let mut output = CRYPT_INTEGER_BLOB::default();
+        unsafe {
+            CryptUnprotectData(&input, None, None, None, None, CRYPTPROTECT_UI_FORBIDDEN, &mut output)
+        }
+        .map_err(|e| format!("CryptUnprotectData failed: {}", e))?;
+        Ok(take(output))
+    }
+
+    fn blob(data: &[u8]) -> CRYPT_INTEGER_BLOB {
+        CRYPT_INTEGER_BLOB { cbData: data.len() as u32, pbData: data.as_ptr() as *mut u8 }
+    }
+
+    /// Copy output rồi trả buffer lại cho LocalFree
+    fn take(output: CRYPT_INTEGER_BLOB) -> Vec<u8> {
+        if output.pbData.is_null() {
+            return Vec::new();
+        }
+        unsafe {
+            let data = std::slice::from_raw_parts(output.pbData, output.cbData as usize).to_vec();
+            let _ = LocalFree(HLOCAL(output.pbData as *mut _));
+            data
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    pub fn protect(data: &[u8]) -> Result<Vec<u8>, String> {
+        Ok(data.to_vec())
+    }
+
+    pub fn unprotect(data: &[u8]) -> Result<Vec<u8>, String> {
+        Ok(data.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protect_roundtrip() {
+        let secret = [7u8; 32];
+        let blob = protect(&secret).unwrap();
+        assert_eq!(unprotect(&blob).unwrap(), secret);
+    }
+}
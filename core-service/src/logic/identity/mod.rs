@@ -6,14 +6,18 @@
 //! Features:
 //! - Hardware-bound identity (HWID)
 //! - DPAPI-style encryption with HMAC signing
+//! - DPAPI wrapper for secrets kept on disk (`dpapi`)
+//! - Verification of server-signed content against a pinned key (`signing`)
 //! - Anti-rollback protection
 //! - Cloud verification support
 
+pub mod dpapi;
 pub mod hwid;
+pub mod signing;
 pub mod storage;
 
 pub use hwid::generate_hwid;
-pub use storage::{AgentIdentity, IdentityStorage, IdentityError};
+pub use storage::{AgentIdentity, AgentTls, IdentityStorage, IdentityError};
 
 use uuid::Uuid;
 use chrono::Utc;
@@ -190,6 +194,9 @@ impl IdentityManager {
         );
 
         log::info!("✅ Tamper incident queued for cloud sync");
+
+        // Windows Event Log → WEF / SIEM agent thu được kể cả khi chưa kết nối cloud
+        crate::logic::telemetry::eventlog::write_tamper(tamper_type, description);
     }
 
     /// Save new identity after registration
@@ -208,6 +215,7 @@ impl IdentityManager {
             issued_at: Utc::now(),
             version: 1,
             server_url: server_url.to_string(),
+            tls: AgentTls::default(),
         };
 
         self.storage.save(&identity)?;
@@ -218,6 +226,17 @@ impl IdentityManager {
         Ok(())
     }
 
+    /// Save mTLS key/cert and server pin received at enrollment
+    pub fn save_tls(&mut self, tls: AgentTls) -> Result<(), IdentityError> {
+        if let Some(ref mut identity) = self.current_identity {
+            identity.tls = tls;
+            self.storage.save(identity)?;
+            log::info!("Identity TLS saved (mTLS: {}, pinned CA: {})",
+                identity.tls.has_client_key(), identity.tls.server_ca_pem.is_some());
+        }
+        Ok(())
+    }
+
     /// Update identity version (after cloud verification)
     pub fn update_version(&mut self, new_version: u32) -> Result<(), IdentityError> {
         if let Some(ref mut identity) = self.current_identity {
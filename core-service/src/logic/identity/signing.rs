@@ -0,0 +1,43 @@
+//! Content Signing - Verify nội dung server ký (rule pack, offline intel bundle)
+//!
+//! Server ký bằng ed25519, agent pin public key lúc build (`constants`), nên token
+//! agent hay registration key bị lộ cũng không giả được nội dung.
+
+use ed25519_dalek::{Signature, VerifyingKey};
+
+/// Verify chữ ký hex của server trên `message` (`public_key`: hex, thường là
+/// `constants::content_signing_public_key()`)
+pub fn verify(public_key: &str, message: &[u8], signature: &str) -> Result<(), String> {
+    let key: [u8; 32] = hex::decode(public_key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("invalid pinned signing key")?;
+    let key = VerifyingKey::from_bytes(&key).map_err(|_| "invalid pinned signing key".to_string())?;
+
+    let signature: [u8; 64] = hex::decode(signature.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("malformed signature")?;
+    key.verify_strict(message, &Signature::from_bytes(&signature))
+        .map_err(|_| "invalid signature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    #[test]
+    fn test_verify_content_signature() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let public_key = hex::encode(key.verifying_key().as_bytes());
+        let signature = hex::encode(key.sign(b"content").to_bytes());
+
+        assert!(verify(&public_key, b"content", &signature).is_ok());
+        assert_eq!(verify(&public_key, b"content2", &signature).unwrap_err(), "invalid signature");
+        assert_eq!(verify(&public_key, b"content", "abcd").unwrap_err(), "malformed signature");
+
+        let other = hex::encode(SigningKey::from_bytes(&[8u8; 32]).verifying_key().as_bytes());
+        assert!(verify(&other, b"content", &signature).is_err());
+    }
+}
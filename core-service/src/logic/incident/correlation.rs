@@ -0,0 +1,236 @@
+//! Incident Correlation - Gom detection liên quan vào cùng một incident
+//!
+//! Trước đây mọi detection trong 60s gộp chung (kể cả không liên quan), còn detection
+//! liên quan cách nhau > 60s lại thành incident riêng → console bị ngập. Giờ một detection
+//! được gộp vào incident đang mở nếu (theo thứ tự ưu tiên):
+//! 1. Cùng cây process: process bị flag là tổ tiên / con cháu của process đã bị flag trong
+//!    incident (không tính anh em chung root như explorer.exe) - trong `CORRELATION_WINDOW_SECS`
+//! 2. Cùng hash SHA-256 của binary bị flag - trong `CORRELATION_WINDOW_SECS`
+//! 3. Trùng khoảng thời gian (`TIME_WINDOW_SECS`) - chỉ khi một trong hai bên không có
+//!    ngữ cảnh process (vd anomaly ML từ summary toàn hệ thống)
+//!
+//! Severity của incident = max severity các detection, nâng thêm 1 bậc khi incident gom
+//! được từ `ESCALATE_AFTER_DETECTIONS` detection khác nhau trở lên.
+
+use std::collections::{BTreeMap, BTreeSet};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::types::Severity;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Cửa sổ gộp theo cây process / hash (tính từ lần thấy cuối)
+pub const CORRELATION_WINDOW_SECS: i64 = 30 * 60;
+/// Cửa sổ gộp chỉ theo thời gian
+pub const TIME_WINDOW_SECS: i64 = 60;
+/// Số detection khác nhau để nâng severity thêm 1 bậc
+pub const ESCALATE_AFTER_DETECTIONS: usize = 3;
+/// PID hệ thống (Idle / System) - không dùng để correlate
+const SYSTEM_PIDS: &[u32] = &[0, 4];
+const MAX_TRACKED_DETECTIONS: usize = 50;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Dấu vết correlate của một incident
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Correlation {
+    /// PID của các process bị flag
+    pub pids: BTreeSet<u32>,
+    /// PID tổ tiên của các process bị flag
+    pub ancestor_pids: BTreeSet<u32>,
+    /// SHA-256 của binary bị flag
+    pub hashes: BTreeSet<String>,
+    /// Tiêu đề detection (khác nhau, theo thứ tự gộp)
+    pub detections: Vec<String>,
+    /// Tổng số detection đã gộp (kể cả trùng tiêu đề)
+    pub detection_count: usize,
+    /// Lý do gộp (`process_tree` / `hash` / `time_window`) → số lần
+    pub reasons: BTreeMap<String, usize>,
+    /// Severity cao nhất trong các detection (trước khi nâng bậc)
+    pub peak_severity: Option<Severity>,
+}
+
+/// Thông tin của một detection mới dùng để correlate
+#[derive(Debug, Clone)]
+pub struct DetectionFacts {
+    pub title: String,
+    pub ts: DateTime<Utc>,
+    pub pid: Option<u32>,
+    /// PID tổ tiên (parent → root)
+    pub ancestor_pids: Vec<u32>,
+    pub sha256: Option<String>,
+    pub severity: Severity,
+}
+
+/// Thứ tự khai báo = thứ tự ưu tiên khi nhiều incident cùng khớp
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchReason {
+    ProcessTree,
+    Hash,
+    TimeWindow,
+}
+
+impl MatchReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchReason::ProcessTree => "process_tree",
+            MatchReason::Hash => "hash",
+            MatchReason::TimeWindow => "time_window",
+        }
+    }
+}
+
+impl DetectionFacts {
+    fn has_process(&self) -> bool {
+        self.pid.is_some_and(|p| !SYSTEM_PIDS.contains(&p)) || self.sha256.is_some()
+    }
+}
+
+impl Correlation {
+    fn has_process(&self) -> bool {
+        !self.pids.is_empty() || !self.hashes.is_empty()
+    }
+
+    /// Detection có thuộc incident này không (incident thấy lần cuối lúc `last_seen`)
+    pub fn matches(&self, last_seen: DateTime<Utc>, facts: &DetectionFacts) -> Option<MatchReason> {
+        let gap = facts.ts.signed_duration_since(last_seen).num_seconds().abs();
+
+        if gap < CORRELATION_WINDOW_SECS {
+            if let Some(pid) = facts.pid.filter(|p| !SYSTEM_PIDS.contains(p)) {
+                let is_relative = self.pids.contains(&pid)
+                    || self.ancestor_pids.contains(&pid)
+                    || facts.ancestor_pids.iter().any(|a| self.pids.contains(a));
+                if is_relative {
+                    return Some(MatchReason::ProcessTree);
+                }
+            }
+            if facts.sha256.as_ref().is_some_and(|h| self.hashes.contains(h)) {
+                return Some(MatchReason::Hash);
+            }
+        }
+
+        // Cả hai bên có ngữ cảnh process mà không khớp → không liên quan
+        if gap < TIME_WINDOW_SECS && !(facts.has_process() && self.has_process()) {
+            return Some(MatchReason::TimeWindow);
+        }
+        None
+    }
+
+    /// Ghi nhận detection đã gộp vào incident
+    pub fn record(&mut self, facts: &DetectionFacts, reason: Option<MatchReason>) {
+        if let Some(pid) = facts.pid.filter(|p| !SYSTEM_PIDS.contains(p)) {
+            self.pids.insert(pid);
+            self.ancestor_pids.extend(facts.ancestor_pids.iter().filter(|p| !SYSTEM_PIDS.contains(p)));
+        }
+        if let Some(hash) = &facts.sha256 {
+            self.hashes.insert(hash.clone());
+        }
+        if !facts.title.is_empty()
+            && self.detections.len() < MAX_TRACKED_DETECTIONS
+            && !self.detections.iter().any(|d| d == &facts.title)
+        {
+            self.detections.push(facts.title.clone());
+        }
+        self.detection_count += 1;
+        if let Some(reason) = reason {
+            *self.reasons.entry(reason.as_str().to_string()).or_insert(0) += 1;
+        }
+        if self.peak_severity.as_ref().is_none_or(|peak| rank(&facts.severity) > rank(peak)) {
+            self.peak_severity = Some(facts.severity.clone());
+        }
+    }
+
+    /// Severity tổng hợp: severity cao nhất của các detection, +1 bậc khi đủ nhiều detection
+    pub fn aggregate_severity(&self) -> Severity {
+        let peak = self.peak_severity.clone().unwrap_or(Severity::Low);
+        if self.detections.len() < ESCALATE_AFTER_DETECTIONS {
+            return peak;
+        }
+        match peak {
+            Severity::Low => Severity::Medium,
+            Severity::Medium => Severity::High,
+            Severity::High | Severity::Critical => Severity::Critical,
+        }
+    }
+
+    /// Tiêu đề cho console: detection đầu tiên + số detection liên quan
+    pub fn title(&self, fallback: &str) -> String {
+        let first = self.detections.first().map(String::as_str).unwrap_or(fallback);
+        match self.detections.len() {
+            0 | 1 => first.to_string(),
+            n => format!("{} (+{} related detections)", first, n - 1),
+        }
+    }
+}
+
+fn rank(severity: &Severity) -> u8 {
+    match severity {
+        Severity::Low => 1,
+        Severity::Medium => 2,
+        Severity::High => 3,
+        Severity::Critical => 4,
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn facts(title: &str, ts: DateTime<Utc>, pid: Option<u32>, ancestors: &[u32], sha: Option<&str>) -> DetectionFacts {
+        DetectionFacts {
+            title: title.to_string(),
+            ts,
+            pid,
+            ancestor_pids: ancestors.to_vec(),
+            sha256: sha.map(str::to_string),
+            severity: Severity::Medium,
+        }
+    }
+
+    #[test]
+    fn test_correlation_rules() {
+        let t0 = Utc::now();
+        let mut corr = Correlation::default();
+        // powershell (200) ← cmd (100) ← explorer (10)
+        corr.record(&facts("Encoded PowerShell", t0, Some(200), &[100, 10], Some("aa")), None);
+
+        // Con của process bị flag, 10 phút sau → cùng cây
+        let child = facts("LSASS access", t0 + Duration::minutes(10), Some(300), &[200, 100, 10], None);
+        assert_eq!(corr.matches(t0, &child), Some(MatchReason::ProcessTree));
+        // Tổ tiên bị flag sau → cùng cây
+        let parent = facts("Suspicious cmd", t0 + Duration::minutes(5), Some(100), &[10], None);
+        assert_eq!(corr.matches(t0, &parent), Some(MatchReason::ProcessTree));
+        // Anh em chung explorer, 10s sau → không liên quan
+        let sibling = facts("Other", t0 + Duration::seconds(10), Some(400), &[10], Some("bb"));
+        assert_eq!(corr.matches(t0, &sibling), None);
+        // Cùng binary ở process khác
+        let same_hash = facts("Same binary", t0 + Duration::minutes(20), Some(500), &[10], Some("aa"));
+        assert_eq!(corr.matches(t0, &same_hash), Some(MatchReason::Hash));
+        // Anomaly không có process trong 60s → gộp theo thời gian; quá cửa sổ → không
+        assert_eq!(corr.matches(t0, &facts("ML", t0 + Duration::seconds(30), None, &[], None)), Some(MatchReason::TimeWindow));
+        assert_eq!(corr.matches(t0, &facts("ML", t0 + Duration::minutes(2), None, &[], None)), None);
+        assert_eq!(corr.matches(t0, &facts("Late", t0 + Duration::hours(1), Some(300), &[200], None)), None);
+
+        let child = DetectionFacts { severity: Severity::High, ..child };
+        corr.record(&child, Some(MatchReason::ProcessTree));
+        assert_eq!(corr.aggregate_severity(), Severity::High);
+        corr.record(&same_hash, Some(MatchReason::Hash));
+        corr.record(&same_hash, Some(MatchReason::Hash));
+        assert_eq!(corr.detection_count, 4);
+        assert_eq!(corr.detections.len(), 3);
+        // Nâng 1 bậc từ peak (không cộng dồn)
+        assert_eq!(corr.aggregate_severity(), Severity::Critical);
+        assert_eq!(corr.reasons.get("hash"), Some(&2));
+        assert_eq!(corr.title("x"), "Encoded PowerShell (+2 related detections)");
+    }
+}
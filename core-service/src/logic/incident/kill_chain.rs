@@ -0,0 +1,167 @@
+//! Kill Chain - Gắn incident vào các giai đoạn tactic ATT&CK
+//!
+//! Mỗi technique của incident được ánh xạ sang tactic (theo dataset MITRE đã load, một
+//! technique có thể thuộc nhiều tactic). Các tactic được xếp theo thứ tự kill chain
+//! (`MitreTactic::ALL`: Reconnaissance → ... → Impact) kèm thời điểm thấy lần đầu, để
+//! console thấy ngay một xâm nhập đi qua nhiều giai đoạn (vd Initial Access → Execution →
+//! Persistence → Credential Access).
+//!
+//! Tactic được tính khi đọc (không lưu) - dataset MITRE load sau incident vẫn cho kết quả đúng.
+
+use std::collections::{BTreeMap, HashMap};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::types::{Incident, Severity};
+use crate::logic::external_intel::mitre;
+use crate::logic::external_intel::types::MitreTactic;
+
+/// Số giai đoạn khác nhau để coi là xâm nhập nhiều giai đoạn
+pub const MULTI_STAGE_MIN: usize = 3;
+/// Từ số giai đoạn này incident là Critical
+pub const CRITICAL_STAGE_MIN: usize = 5;
+
+/// Một giai đoạn (tactic) của incident
+#[derive(Debug, Clone, Serialize)]
+pub struct KillChainStage {
+    pub tactic: MitreTactic,
+    pub name: String,
+    /// Vị trí trong kill chain (1 = Reconnaissance ... 14 = Impact)
+    pub position: usize,
+    /// Thời điểm thấy technique đầu tiên của giai đoạn
+    pub first_seen: DateTime<Utc>,
+    pub techniques: Vec<String>,
+}
+
+/// Tiến trình kill chain của incident
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KillChainProgression {
+    /// Theo thứ tự kill chain
+    pub stages: Vec<KillChainStage>,
+    /// Giai đoạn xa nhất đã đạt
+    pub furthest_stage: Option<String>,
+    /// `MULTI_STAGE_MIN` giai đoạn trở lên
+    pub multi_stage: bool,
+    /// Vd "Initial Access → Execution → Persistence"
+    pub path: String,
+    /// Technique không ánh xạ được tactic (dataset MITRE chưa có)
+    pub unmapped_techniques: Vec<String>,
+}
+
+/// Tiến trình kill chain của incident (tactic tra theo dataset MITRE hiện tại)
+pub fn progression(incident: &Incident) -> KillChainProgression {
+    let seen: BTreeMap<String, DateTime<Utc>> = incident.mitre_techniques.iter()
+        .map(|t| (t.clone(), incident.technique_first_seen.get(t).copied().unwrap_or(incident.started_at)))
+        .collect();
+    progression_from(&seen, |id| {
+        mitre::get_technique(id)
+            .map(|t| if t.tactics.is_empty() { vec![t.tactic] } else { t.tactics })
+            .unwrap_or_default()
+    })
+}
+
+/// Severity tối thiểu theo số giai đoạn kill chain đã đạt
+pub fn stage_severity(stages: usize) -> Option<Severity> {
+    if stages >= CRITICAL_STAGE_MIN {
+        Some(Severity::Critical)
+    } else if stages >= MULTI_STAGE_MIN {
+        Some(Severity::High)
+    } else {
+        None
+    }
+}
+
+/// Gom technique (→ thời điểm thấy đầu tiên) theo tactic
+pub(crate) fn progression_from(
+    seen: &BTreeMap<String, DateTime<Utc>>,
+    tactics_of: impl Fn(&str) -> Vec<MitreTactic>,
+) -> KillChainProgression {
+    let mut by_tactic: HashMap<MitreTactic, (DateTime<Utc>, Vec<String>)> = HashMap::new();
+    let mut unmapped_techniques = Vec::new();
+
+    for (technique, ts) in seen {
+        let tactics = tactics_of(technique);
+        if tactics.is_empty() {
+            unmapped_techniques.push(technique.clone());
+        }
+        for tactic in tactics {
+            let (first_seen, techniques) = by_tactic.entry(tactic).or_insert((*ts, Vec::new()));
+            *first_seen = (*first_seen).min(*ts);
+            techniques.push(technique.clone());
+        }
+    }
+
+    let stages: Vec<KillChainStage> = MitreTactic::ALL.iter()
+        .enumerate()
+        .filter_map(|(i, tactic)| {
+            by_tactic.remove(tactic).map(|(first_seen, techniques)| KillChainStage {
+                tactic: *tactic,
+                name: tactic.as_str().to_string(),
+                position: i + 1,
+                first_seen,
+                techniques,
+            })
+        })
+        .collect();
+
+    KillChainProgression {
+        furthest_stage: stages.last().map(|s| s.name.clone()),
+        multi_stage: stages.len() >= MULTI_STAGE_MIN,
+        path: stages.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(" → "),
+        stages,
+        unmapped_techniques,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_kill_chain_progression() {
+        let t0 = Utc::now();
+        let seen: BTreeMap<String, DateTime<Utc>> = [
+            ("T1003", t0 + Duration::minutes(9)),
+            ("T1059.001", t0 + Duration::minutes(1)),
+            ("T1566", t0),
+            ("T1547", t0 + Duration::minutes(5)),
+            ("T1055", t0 + Duration::minutes(3)),
+            ("T9999", t0),
+        ]
+        .into_iter()
+        .map(|(t, ts)| (t.to_string(), ts))
+        .collect();
+
+        let progression = progression_from(&seen, |id| match id {
+            "T1566" => vec![MitreTactic::InitialAccess],
+            "T1059.001" => vec![MitreTactic::Execution],
+            "T1547" => vec![MitreTactic::Persistence, MitreTactic::PrivilegeEscalation],
+            "T1055" => vec![MitreTactic::DefenseEvasion, MitreTactic::PrivilegeEscalation],
+            "T1003" => vec![MitreTactic::CredentialAccess],
+            _ => Vec::new(),
+        });
+
+        assert_eq!(
+            progression.path,
+            "Initial Access → Execution → Persistence → Privilege Escalation → Defense Evasion → Credential Access"
+        );
+        assert!(progression.multi_stage);
+        assert_eq!(progression.furthest_stage.as_deref(), Some("Credential Access"));
+        assert_eq!(progression.unmapped_techniques, vec!["T9999".to_string()]);
+
+        // Giai đoạn có nhiều technique lấy thời điểm sớm nhất
+        let privesc = progression.stages.iter().find(|s| s.tactic == MitreTactic::PrivilegeEscalation).unwrap();
+        assert_eq!(privesc.first_seen, t0 + Duration::minutes(3));
+        assert_eq!(privesc.techniques.len(), 2);
+        assert_eq!(privesc.position, 6);
+
+        let single = progression_from(&seen, |id| if id == "T1566" { vec![MitreTactic::InitialAccess] } else { Vec::new() });
+        assert!(!single.multi_stage);
+        assert_eq!(single.stages.len(), 1);
+
+        assert_eq!(stage_severity(progression.stages.len()), Some(Severity::Critical));
+        assert_eq!(stage_severity(3), Some(Severity::High));
+        assert_eq!(stage_severity(single.stages.len()), None);
+    }
+}
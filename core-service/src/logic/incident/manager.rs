@@ -1,13 +1,27 @@
 use std::collections::HashMap;
+use std::path::Path;
 use parking_lot::Mutex;
 use uuid::Uuid;
 use chrono::Utc;
 
-use super::types::{Incident, DatasetRecordSummary, Severity};
+use super::correlation::{DetectionFacts, MatchReason};
+use super::suppression;
+use super::types::{Incident, IncidentNote, IncidentStatus, IncidentVerdict, DatasetRecordSummary, DetectionContext, EnforcementFailure, ScriptExcerpt, Severity, SeverityChange};
 use crate::logic::threat::ThreatClass;
 use crate::logic::dataset::DatasetRecord;
-use crate::logic::explain::explain;
+use crate::logic::explain::{explain, ExplainResult};
 use crate::logic::cloud_sync;
+use crate::logic::external_intel::mitre;
+use crate::logic::cloud_sync::client::{IncidentIntel, IncidentProcess, IncidentFeature};
+use crate::logic::process_intel::{artifacts, genealogy, hashing, signature, SignatureStatus};
+use crate::logic::process_intel::artifacts::ExecutionArtifacts;
+use crate::logic::process_intel::genealogy::GenealogyRecord;
+
+/// Số process tối đa trong chain gửi lên cloud
+const MAX_CLOUD_CHAIN: usize = 16;
+
+/// Số feature đóng góp nhiều nhất gửi lên cloud
+const MAX_KEY_FEATURES: usize = 5;
 
 // Global Incident Manager (In-Memory for P3.1)
 static MANAGER: Mutex<Option<IncidentManager>> = Mutex::new(None);
@@ -23,6 +37,23 @@ impl IncidentManager {
         }
     }
 
+    /// Incident chưa đóng khớp detection: ưu tiên cây process > hash > thời gian,
+    /// cùng mức thì incident thấy gần nhất
+    fn find_correlated(&self, facts: &DetectionFacts) -> Option<(Uuid, MatchReason)> {
+        self.active.iter()
+            .filter(|(_, inc)| inc.status != IncidentStatus::Closed)
+            .filter_map(|(id, inc)| inc.correlation.matches(inc.last_seen, facts).map(|r| (*id, r, inc.last_seen)))
+            .min_by_key(|(_, reason, last_seen)| (*reason, std::cmp::Reverse(*last_seen)))
+            .map(|(id, reason, _)| (id, reason))
+    }
+
+    /// Như `find_correlated` nhưng chỉ nhận incident cùng cây process / hash,
+    /// không gộp theo thời gian (dùng khi chắc chắn biết process nào gây ra)
+    fn find_process_correlated(&self, facts: &DetectionFacts) -> Option<(Uuid, MatchReason)> {
+        self.find_correlated(facts)
+            .filter(|(_, reason)| *reason != MatchReason::TimeWindow)
+    }
+
     fn process(&mut self, record: &DatasetRecord, tags: &[String]) {
         // P3.1: Only process non-benign events
         if record.threat == ThreatClass::Benign {
@@ -42,21 +73,29 @@ impl IncidentManager {
         // P3.2 Explainability (Why detected?)
         let explanation = explain(record);
 
-        // Rule: Group by time window (60s)
-        let mut target_id = None;
-        let now = summary.ts;
-
-        for (id, incident) in self.active.iter() {
-            let gap = now.signed_duration_since(incident.last_seen).num_seconds();
-            if gap.abs() < 60 {
-                target_id = Some(*id);
-                break;
-            }
-        }
+        // Anomaly ML không có ngữ cảnh process → chỉ gộp theo thời gian
+        let facts = DetectionFacts {
+            title: if tags.is_empty() { "Anomaly detected".to_string() } else { format!("Anomaly: {}", tags.join(", ")) },
+            ts: summary.ts,
+            pid: None,
+            ancestor_pids: Vec::new(),
+            sha256: None,
+            severity: Incident::map_severity_static(&summary),
+        };
 
-        if let Some(id) = target_id {
+        let techniques = tag_techniques(tags);
+        if let Some((id, reason)) = self.find_correlated(&facts) {
             if let Some(inc) = self.active.get_mut(&id) {
-                inc.update(summary);
+                inc.correlation.record(&facts, Some(reason));
+                let raised = inc.update(summary);
+                inc.add_techniques(&techniques);
+                if suppression::apply(inc).is_none() {
+                    // Technique mới có thể thêm giai đoạn kill chain → tính lại lần nữa
+                    let changes: Vec<_> = raised.into_iter().chain(inc.recalculate_severity(facts.ts)).collect();
+                    for change in &changes {
+                        announce_severity_change(inc, change, true);
+                    }
+                }
                 // If new record has explanation and higher score, maybe update?
                 // For now: Keep first explanation if exists
                 if inc.explanation.is_none() {
@@ -65,12 +104,15 @@ impl IncidentManager {
             }
         } else {
             // Create NEW incident
-            let inc = Incident::new(summary.clone(), explanation.clone());
+            let mut inc = Incident::new(summary.clone(), explanation.clone());
+            inc.add_techniques(&techniques);
+            inc.correlation.record(&facts, None);
+            let suppressed = suppression::apply(&mut inc).is_some();
             let incident_id = inc.incident_id;
             self.active.insert(incident_id, inc);
 
             // ===== CLOUD SYNC: Queue new incident for cloud =====
-            if cloud_sync::is_connected() {
+            if !suppressed && cloud_sync::is_connected() {
                 let severity_str = match Incident::map_severity_static(&summary) {
                     Severity::Low => "low",
                     Severity::Medium => "medium",
@@ -108,7 +150,7 @@ impl IncidentManager {
 
                 let threat_class = Some(format!("{:?}", summary.threat));
 
-                cloud_sync::sync::queue_incident(
+                cloud_sync::sync::queue_incident_with_intel(
                     incident_id,
                     severity_str.to_string(),
                     title,
@@ -116,6 +158,11 @@ impl IncidentManager {
                     mitre_techniques,
                     threat_class,
                     Some(summary.confidence),
+                    IncidentIntel {
+                        process_chain: Vec::new(),
+                        rule_matches: summary.tags.clone(),
+                        key_features: key_features(explanation.as_ref()),
+                    },
                 );
 
                 log::debug!("☁️ Incident {} queued for cloud sync", incident_id);
@@ -124,6 +171,86 @@ impl IncidentManager {
     }
 }
 
+/// Technique từ tag: tag detector (TAG_TO_MITRE) hoặc tag là technique ID (rule match)
+fn tag_techniques(tags: &[String]) -> Vec<String> {
+    tags.iter()
+        .filter_map(|tag| {
+            mitre::get_technique_id_for_tag(tag)
+                .map(str::to_string)
+                .or_else(|| mitre::get_technique(tag).map(|t| t.id))
+        })
+        .collect()
+}
+
+/// Severity vừa được nâng: event cho UI, log, và (nếu `push_cloud`) cập nhật incident trên
+/// cloud - cùng incident id nên server upsert severity / tiêu đề của incident cũ
+fn announce_severity_change(inc: &Incident, change: &SeverityChange, push_cloud: bool) {
+    log::warn!(
+        "⬆️ Incident {} severity {:?} → {:?} ({})",
+        inc.incident_id, change.from, change.to, change.reason
+    );
+    crate::logic::events::emit_incident_severity(change);
+
+    if push_cloud && cloud_sync::is_connected() {
+        cloud_sync::sync::queue_incident(
+            inc.incident_id,
+            change.to.as_str().to_string(),
+            inc.correlation.title("Incident"),
+            None,
+            (!inc.mitre_techniques.is_empty()).then(|| inc.mitre_techniques.clone()),
+            None,
+            None,
+        );
+    }
+}
+
+// Cloud intel helpers
+
+/// Process chain gửi lên cloud: hash (cache dùng chung) + chữ ký đã verify (không gọi PowerShell)
+fn cloud_process_chain(ancestry: &[GenealogyRecord]) -> Vec<IncidentProcess> {
+    ancestry.iter().take(MAX_CLOUD_CHAIN).map(|record| {
+        let path = record.exe_path.as_deref().map(Path::new);
+        let hashes = path.and_then(|p| hashing::hash_file(p).ok());
+        let status = path.and_then(signature::cached_status);
+        let (label, publisher) = match status {
+            Some(SignatureStatus::Trusted { publisher, .. }) => ("trusted", Some(publisher)),
+            Some(SignatureStatus::SignedUntrusted { publisher }) => ("signed_untrusted", Some(publisher)),
+            Some(SignatureStatus::Revoked { publisher, .. }) => ("revoked", Some(publisher)),
+            Some(SignatureStatus::Unsigned) => ("unsigned", None),
+            Some(SignatureStatus::Invalid { .. }) => ("invalid", None),
+            Some(SignatureStatus::Error { .. }) | None => ("unknown", None),
+        };
+        IncidentProcess {
+            pid: record.pid,
+            name: record.image.clone(),
+            exe_path: record.exe_path.clone(),
+            cmdline: record.cmdline.clone(),
+            sha256: hashes.as_ref().map(|h| h.sha256.clone()),
+            md5: hashes.map(|h| h.md5),
+            signature: Some(label.to_string()),
+            publisher,
+            exited: record.exited_at.is_some(),
+        }
+    }).collect()
+}
+
+/// Feature đóng góp nhiều nhất vào verdict ML
+fn key_features(explanation: Option<&ExplainResult>) -> Vec<IncidentFeature> {
+    explanation
+        .map(|e| {
+            e.contributions.iter()
+                .take(MAX_KEY_FEATURES)
+                .map(|c| IncidentFeature {
+                    name: c.name.clone(),
+                    importance: c.importance,
+                    delta: Some(c.delta),
+                    description: c.description.clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 // Public API
 pub fn process_event(record: &DatasetRecord, tags: &[String]) {
     let mut guard = MANAGER.lock();
@@ -159,3 +286,353 @@ pub fn get_incident(id: Uuid) -> Option<Incident> {
 
     guard.as_ref().and_then(|mgr| mgr.active.get(&id).cloned())
 }
+
+/// Ghi verdict triage (confirm / dismiss) và lan thành nhãn training cho record dataset
+/// bị flag trong khoảng [started_at, last_seen] của incident. Dismiss → đóng incident.
+/// Trả về số record dataset đã gán nhãn
+pub fn set_verdict(id: Uuid, verdict: IncidentVerdict) -> Result<usize, String> {
+    let (from, to) = {
+        let mut guard = MANAGER.lock();
+        let inc = guard
+            .as_mut()
+            .and_then(|mgr| mgr.active.get_mut(&id))
+            .ok_or_else(|| format!("Incident not found: {}", id))?;
+        inc.verdict = Some(verdict);
+        if verdict == IncidentVerdict::Dismissed {
+            inc.status = IncidentStatus::Closed;
+        }
+        (inc.started_at.timestamp_millis().max(0) as u64, inc.last_seen.timestamp_millis().max(0) as u64)
+    };
+
+    // Quét dataset ngoài lock của manager
+    crate::logic::dataset::labeling::label_incident_records(
+        &id.to_string(),
+        from,
+        to,
+        verdict.dataset_label().as_str(),
+    )
+}
+
+/// Thêm ghi chú / lý do kết luận của analyst local, queue lên cloud nếu đang kết nối
+pub fn add_note(id: Uuid, text: &str, disposition: Option<&str>) -> Result<IncidentNote, String> {
+    let author = std::env::var("USERNAME")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "local".to_string());
+    let note = IncidentNote::new(&author, text, disposition)?;
+
+    {
+        let mut guard = MANAGER.lock();
+        let inc = guard
+            .as_mut()
+            .and_then(|mgr| mgr.active.get_mut(&id))
+            .ok_or_else(|| format!("Incident not found: {}", id))?;
+        inc.notes.push(note.clone());
+    }
+
+    if cloud_sync::is_connected() {
+        cloud_sync::sync::queue_incident_note(id, &note);
+    }
+    Ok(note)
+}
+
+/// Nhận ghi chú từ cloud console (bỏ qua nếu đã có cùng id). Trả về true nếu đã thêm
+pub fn merge_cloud_note(id: Uuid, note: IncidentNote) -> Result<bool, String> {
+    let mut guard = MANAGER.lock();
+    let inc = guard
+        .as_mut()
+        .and_then(|mgr| mgr.active.get_mut(&id))
+        .ok_or_else(|| format!("Incident not found: {}", id))?;
+    if inc.notes.iter().any(|n| n.id == note.id) {
+        return Ok(false);
+    }
+    inc.notes.push(note);
+    inc.notes.sort_by_key(|n| n.created_at);
+    Ok(true)
+}
+
+/// Gắn danh sách file bị sửa gần đây vào incident của chính process đó (cùng cây
+/// process / hash), hoặc tạo incident Critical mới. Queue lên cloud kèm danh sách file.
+pub fn attach_recovery_files(
+    pid: Option<u32>,
+    process_name: &str,
+    tags: &[String],
+    files: Vec<crate::logic::response::ransomware::RecoveryFile>,
+) -> Option<Uuid> {
+    // Genealogy ngoài MANAGER lock
+    let ancestry = pid
+        .map(|pid| genealogy::get_ancestry(pid, None))
+        .unwrap_or_default();
+    let subject = ancestry.first().filter(|r| Some(r.pid) == pid);
+    let now = Utc::now();
+    let facts = DetectionFacts {
+        title: format!("Ransomware activity: {}", process_name),
+        ts: now,
+        pid,
+        ancestor_pids: ancestry.iter().skip(usize::from(subject.is_some())).map(|r| r.pid).collect(),
+        sha256: None,
+        severity: Severity::Critical,
+    };
+
+    let mut guard = MANAGER.lock();
+    if guard.is_none() {
+        *guard = Some(IncidentManager::new());
+    }
+    let mgr = guard.as_mut()?;
+
+    let (incident_id, reason) = match mgr.find_process_correlated(&facts) {
+        Some((id, reason)) => (id, Some(reason)),
+        None => {
+            let inc = Incident::new(DatasetRecordSummary {
+                ts: now,
+                score: 1.0,
+                confidence: 1.0,
+                threat: ThreatClass::Malicious,
+                tags: tags.to_vec(),
+            }, None);
+            let id = inc.incident_id;
+            mgr.active.insert(id, inc);
+            (id, None)
+        }
+    };
+    let existing = reason.is_some();
+
+    let inc = mgr.active.get_mut(&incident_id)?;
+    inc.correlation.record(&facts, reason);
+    if inc.process_ancestry.is_empty() {
+        inc.process_ancestry = ancestry;
+    }
+    if let Some(change) = inc.raise_severity(Severity::Critical, "ransomware activity", now) {
+        announce_severity_change(inc, &change, existing);
+    }
+    inc.recovery_files = files.clone();
+
+    if cloud_sync::is_connected() {
+        let listing: Vec<String> = files.iter().take(50).map(|f| f.path.clone()).collect();
+        cloud_sync::sync::queue_incident(
+            uuid::Uuid::new_v4(),
+            "critical".to_string(),
+            format!("Ransomware activity: {}", process_name),
+            Some(format!(
+                "Process tree suspended. {} recently modified file(s) recorded for recovery:\n{}",
+                files.len(),
+                listing.join("\n"),
+            )),
+            Some(vec!["T1486".to_string(), "T1490".to_string()]),
+            Some("Malicious".to_string()),
+            Some(1.0),
+        );
+    }
+
+    Some(incident_id)
+}
+
+/// Ghi nhận action không có hiệu lực vào incident của chính process đó (cùng cây
+/// process), hoặc tạo incident mới; nâng severity lên ít nhất High và báo lên cloud.
+pub fn record_enforcement_failure(failure: EnforcementFailure) -> Option<Uuid> {
+    let mut guard = MANAGER.lock();
+    if guard.is_none() {
+        *guard = Some(IncidentManager::new());
+    }
+    let mgr = guard.as_mut()?;
+
+    let facts = DetectionFacts {
+        title: format!("Enforcement failed: {}", failure.action_type),
+        ts: failure.at,
+        pid: failure.target_pid,
+        ancestor_pids: Vec::new(),
+        sha256: None,
+        severity: Severity::High,
+    };
+    let (incident_id, reason) = match mgr.find_process_correlated(&facts) {
+        Some((id, reason)) => (id, Some(reason)),
+        None => {
+            let mut inc = Incident::new(DatasetRecordSummary {
+                ts: failure.at,
+                score: 1.0,
+                confidence: 1.0,
+                threat: ThreatClass::Malicious,
+                tags: vec!["ENFORCEMENT_FAILED".to_string()],
+            }, None);
+            inc.severity = facts.severity.clone();
+            let id = inc.incident_id;
+            mgr.active.insert(id, inc);
+            (id, None)
+        }
+    };
+
+    let inc = mgr.active.get_mut(&incident_id)?;
+    inc.correlation.record(&facts, reason);
+    inc.enforcement_failures.push(failure.clone());
+    if let Some(change) = inc.recalculate_severity(failure.at) {
+        announce_severity_change(inc, &change, reason.is_some());
+    }
+
+    if cloud_sync::is_connected() {
+        cloud_sync::sync::queue_incident(
+            uuid::Uuid::new_v4(),
+            "high".to_string(),
+            format!("Enforcement failed: {} on {}", failure.action_type, failure.target_name),
+            Some(failure.reason.clone()),
+            None,
+            Some("Malicious".to_string()),
+            Some(1.0),
+        );
+    }
+
+    Some(incident_id)
+}
+
+/// Incident từ detector chuyên biệt (không qua ML scoring): gộp vào incident
+/// liên quan (`correlation`) hoặc tạo mới, nâng severity và queue lên cloud.
+pub fn raise_detection(
+    title: &str,
+    severity: Severity,
+    tags: &[String],
+    mitre: &[&str],
+    description: &str,
+) -> Option<Uuid> {
+    raise_process_detection(title, severity, tags, mitre, description, DetectionContext::default())
+}
+
+/// Như `raise_detection`, kèm ancestry của process (hash + chữ ký) và rule matches
+pub fn raise_process_detection(
+    title: &str,
+    severity: Severity,
+    tags: &[String],
+    mitre: &[&str],
+    description: &str,
+    context: DetectionContext,
+) -> Option<Uuid> {
+    // Genealogy + hash ngoài MANAGER lock
+    let ancestry = context.pid
+        .map(|pid| genealogy::get_ancestry(pid, None))
+        .unwrap_or_default();
+    let connected = cloud_sync::is_connected();
+    let process_chain = if connected { cloud_process_chain(&ancestry) } else { Vec::new() };
+    // ancestry[0] = chính process bị flag
+    let subject = ancestry.first().filter(|r| Some(r.pid) == context.pid);
+    let sha256 = subject
+        .and_then(|r| r.exe_path.as_deref())
+        .and_then(|p| hashing::hash_file(Path::new(p)).ok())
+        .map(|h| h.sha256);
+    let facts = DetectionFacts {
+        title: title.to_string(),
+        ts: Utc::now(),
+        pid: context.pid,
+        ancestor_pids: ancestry.iter().skip(usize::from(subject.is_some())).map(|r| r.pid).collect(),
+        sha256,
+        severity,
+    };
+
+    let mut guard = MANAGER.lock();
+    if guard.is_none() {
+        *guard = Some(IncidentManager::new());
+    }
+    let mgr = guard.as_mut()?;
+
+    let summary = DatasetRecordSummary {
+        ts: facts.ts,
+        score: 1.0,
+        confidence: 1.0,
+        threat: ThreatClass::Malicious,
+        tags: tags.to_vec(),
+    };
+
+    let (incident_id, reason, raised) = match mgr.find_correlated(&facts) {
+        Some((id, reason)) => {
+            let inc = mgr.active.get_mut(&id)?;
+            inc.correlation.record(&facts, Some(reason));
+            let raised = inc.update(summary);
+            (id, Some(reason), raised)
+        }
+        None => {
+            // Severity theo detection, không theo record tổng hợp (score 1.0 → luôn Critical)
+            let mut inc = Incident::new(summary, None);
+            inc.severity = facts.severity.clone();
+            inc.correlation.record(&facts, None);
+            let id = inc.incident_id;
+            mgr.active.insert(id, inc);
+            (id, None, None)
+        }
+    };
+
+    let inc = mgr.active.get_mut(&incident_id)?;
+    inc.add_techniques(mitre);
+    for rule in &context.rule_matches {
+        if !inc.rule_matches.contains(rule) {
+            inc.rule_matches.push(rule.clone());
+        }
+    }
+    let exe_path = ancestry.first().and_then(|r| r.exe_path.clone());
+    if inc.process_ancestry.is_empty() {
+        inc.process_ancestry = ancestry;
+    }
+
+    // Mẫu đã biết là lành tính → đóng ngay, không báo lên cloud
+    if suppression::apply(inc).is_some() {
+        return Some(incident_id);
+    }
+
+    // Incident được queue lên cloud ngay bên dưới với severity mới
+    let changes: Vec<_> = raised.into_iter().chain(inc.recalculate_severity(facts.ts)).collect();
+    for change in &changes {
+        announce_severity_change(inc, change, false);
+    }
+    if artifacts::is_enabled() && inc.execution_artifacts.is_none() {
+        if let Some(exe_path) = exe_path {
+            // Đọc Prefetch / registry chậm → thread riêng, gắn vào incident sau
+            std::thread::spawn(move || {
+                let collected = artifacts::collect(Path::new(&exe_path));
+                attach_execution_artifacts(incident_id, collected);
+            });
+        }
+    }
+
+    // Cùng incident id → server cập nhật incident cũ (upsert) thay vì tạo thêm
+    if connected {
+        cloud_sync::sync::queue_incident_with_intel(
+            incident_id,
+            inc.severity.as_str().to_string(),
+            inc.correlation.title(title),
+            Some(description.to_string()),
+            Some(mitre.iter().map(|m| m.to_string()).collect()),
+            Some("Malicious".to_string()),
+            Some(1.0),
+            IncidentIntel {
+                process_chain,
+                rule_matches: context.rule_matches,
+                key_features: Vec::new(),
+            },
+        );
+    }
+
+    match reason {
+        Some(reason) => log::warn!("🚨 Incident {} (+{}): {} - {}", incident_id, reason.as_str(), title, description),
+        None => log::warn!("🚨 Incident {}: {} - {}", incident_id, title, description),
+    }
+    Some(incident_id)
+}
+
+/// Gắn Prefetch / Shimcache của binary bị flag vào incident
+pub fn attach_execution_artifacts(incident_id: Uuid, collected: ExecutionArtifacts) -> bool {
+    let mut guard = MANAGER.lock();
+    match guard.as_mut().and_then(|mgr| mgr.active.get_mut(&incident_id)) {
+        Some(inc) => {
+            inc.execution_artifacts = Some(collected);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Gắn excerpt script block vào incident
+pub fn attach_script_excerpt(incident_id: Uuid, excerpt: ScriptExcerpt) -> bool {
+    let mut guard = MANAGER.lock();
+    match guard.as_mut().and_then(|mgr| mgr.active.get_mut(&incident_id)) {
+        Some(inc) => {
+            inc.script_excerpts.push(excerpt);
+            true
+        }
+        None => false,
+    }
+}
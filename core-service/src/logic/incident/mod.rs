@@ -1,5 +1,9 @@
 pub mod types;
 pub mod manager;
+pub mod correlation;
+pub mod kill_chain;
+pub mod suppression;
+pub mod report;
 
 pub use types::*;
-pub use manager::{process_event, get_incidents, get_incident};
+pub use manager::{process_event, get_incidents, get_incident, set_verdict, add_note, merge_cloud_note, attach_recovery_files, record_enforcement_failure, raise_detection, raise_process_detection, attach_script_excerpt, attach_execution_artifacts};
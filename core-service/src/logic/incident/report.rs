@@ -0,0 +1,848 @@
+//! Incident Report - Xuất báo cáo incident dạng HTML / PDF độc lập
+//!
+//! Dùng để đính kèm vào ticket hoặc gửi quản lý: chi tiết incident, timeline, MITRE ATT&CK,
+//! feature attribution, ancestry, hành động đã thực hiện và ghi chú analyst.
+//!
+//! Nội dung được dựng một lần thành `ReportDocument` (section → field / bảng / đoạn văn)
+//! rồi render sang HTML (CSS inline, không tài nguyên ngoài) hoặc PDF. PDF được viết trực
+//! tiếp (PDF 1.4, font chuẩn Helvetica / Courier, WinAnsi) - không cần thư viện ngoài;
+//! ký tự ngoài Latin-1 được thay bằng `?`.
+
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::kill_chain;
+use super::types::{Incident, IncidentStatus, IncidentVerdict, Severity};
+use crate::logic::action_guard::{self, ActionRecord};
+use crate::logic::external_intel::mitre;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Số record tối đa đưa vào timeline (incident dài có thể có hàng nghìn record)
+const MAX_TIMELINE_RECORDS: usize = 200;
+/// Số action gần nhất được xét khi gắn action vào incident
+const ACTION_HISTORY_SCAN: usize = 1000;
+/// Action thực hiện sau `last_seen` trong khoảng này vẫn tính là của incident
+const ACTION_GRACE_SECS: i64 = 10 * 60;
+
+// A4, đơn vị point
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const BODY_SIZE: f32 = 10.0;
+const TABLE_SIZE: f32 = 8.0;
+const HEADING_SIZE: f32 = 13.0;
+const TITLE_SIZE: f32 = 18.0;
+/// Độ rộng trung bình của Helvetica (em) - ước lượng để ngắt dòng
+const HELVETICA_AVG_EM: f32 = 0.55;
+/// Courier: mọi ký tự rộng 0.6 em
+const COURIER_EM: f32 = 0.6;
+const FIELD_LABEL_WIDTH: f32 = 130.0;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Html,
+    Pdf,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Pdf => "pdf",
+        }
+    }
+}
+
+/// Nội dung báo cáo, độc lập với định dạng
+#[derive(Debug, Clone)]
+pub(crate) struct ReportDocument {
+    pub title: String,
+    pub subtitle: String,
+    pub sections: Vec<ReportSection>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ReportSection {
+    pub heading: String,
+    pub blocks: Vec<ReportBlock>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum ReportBlock {
+    /// Cặp nhãn → giá trị
+    Fields(Vec<(String, String)>),
+    Table { columns: Vec<String>, rows: Vec<Vec<String>> },
+    Text(String),
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Xuất báo cáo incident ra file. `path` None → thư mục Downloads.
+/// Trả về đường dẫn file đã ghi
+pub fn export_incident_report(
+    incident_id: Uuid,
+    format: ReportFormat,
+    path: Option<PathBuf>,
+) -> Result<String, String> {
+    let incident = super::get_incident(incident_id)
+        .ok_or_else(|| format!("Incident not found: {}", incident_id))?;
+    let actions = related_actions(&incident, &action_guard::get_action_history(ACTION_HISTORY_SCAN));
+    let document = build_document(&incident, &actions, Utc::now());
+
+    let target = path.unwrap_or_else(|| {
+        dirs::download_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(format!("incident-{}.{}", short_id(&incident_id), format.extension()))
+    });
+    write_document(&document, format, &target)?;
+
+    log::info!("Incident report {} exported to {}", incident_id, target.display());
+    Ok(target.to_string_lossy().to_string())
+}
+
+// ============================================================================
+// DOCUMENT
+// ============================================================================
+
+fn write_document(document: &ReportDocument, format: ReportFormat, path: &Path) -> Result<(), String> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let bytes = match format {
+        ReportFormat::Html => render_html(document).into_bytes(),
+        ReportFormat::Pdf => render_pdf(document),
+    };
+    std::fs::write(path, bytes).map_err(|e| format!("Failed to write report: {}", e))
+}
+
+/// Action của incident: trong khoảng thời gian incident, nhắm vào process đã correlate
+/// (hoặc action toàn máy). Incident không có ngữ cảnh process → mọi action trong khoảng đó
+fn related_actions(incident: &Incident, history: &[ActionRecord]) -> Vec<ActionRecord> {
+    let from = incident.started_at - chrono::Duration::seconds(60);
+    let to = incident.last_seen + chrono::Duration::seconds(ACTION_GRACE_SECS);
+    let pids: Vec<u32> = incident.correlation.pids.iter()
+        .chain(incident.process_ancestry.iter().map(|r| &r.pid))
+        .copied()
+        .collect();
+
+    history.iter()
+        .filter(|a| a.executed_at >= from && a.executed_at <= to)
+        .filter(|a| pids.is_empty() || a.target_pid.is_none_or(|pid| pids.contains(&pid)))
+        .cloned()
+        .collect()
+}
+
+fn build_document(incident: &Incident, actions: &[ActionRecord], generated_at: DateTime<Utc>) -> ReportDocument {
+    let title = incident.correlation.title("Security incident");
+    let mut sections = vec![summary_section(incident, &title), timeline_section(incident, actions)];
+
+    // Kill chain
+    let kill_chain = kill_chain::progression(incident);
+    let mut kill_chain_section = table_or_text(
+        "Kill chain",
+        &["Stage", "Tactic", "First seen", "Techniques"],
+        kill_chain.stages.iter()
+            .map(|s| vec![format!("{}/14", s.position), s.name.clone(), format_time(&s.first_seen), s.techniques.join(", ")])
+            .collect(),
+        "No ATT&CK tactics identified.",
+    );
+    if kill_chain.multi_stage {
+        // "->" thay cho "→" (font PDF chuẩn không có ký tự mũi tên)
+        let path: Vec<&str> = kill_chain.stages.iter().map(|s| s.name.as_str()).collect();
+        kill_chain_section.blocks.insert(0, ReportBlock::Text(format!("Multi-stage intrusion: {}", path.join(" -> "))));
+    }
+    sections.push(kill_chain_section);
+
+    // MITRE ATT&CK
+    let mitre_rows: Vec<Vec<String>> = incident.mitre_techniques.iter()
+        .map(|id| match mitre::get_technique(id) {
+            Some(t) => {
+                let tactics = if t.tactics.is_empty() { vec![t.tactic.clone()] } else { t.tactics.clone() };
+                let tactics: Vec<&str> = tactics.iter().map(|t| t.as_str()).collect();
+                vec![id.clone(), t.name, tactics.join(", ")]
+            }
+            None => vec![id.clone(), "-".to_string(), "-".to_string()],
+        })
+        .collect();
+    sections.push(table_or_text(
+        "MITRE ATT&CK",
+        &["Technique", "Name", "Tactics"],
+        mitre_rows,
+        "No techniques mapped.",
+    ));
+
+    // Feature attribution (ML)
+    let feature_rows: Vec<Vec<String>> = incident.explanation.iter()
+        .flat_map(|e| e.contributions.iter())
+        .map(|c| vec![
+            c.name.clone(),
+            format!("{:.3}", c.delta),
+            format!("{:.3}", c.weight),
+            format!("{:.3}", c.importance),
+            c.description.clone().unwrap_or_default(),
+        ])
+        .collect();
+    sections.push(table_or_text(
+        "Feature attribution",
+        &["Feature", "Delta", "Weight", "Importance", "Description"],
+        feature_rows,
+        "No model attribution (rule-based detection).",
+    ));
+
+    if !incident.process_ancestry.is_empty() {
+        let rows = incident.process_ancestry.iter()
+            .map(|r| vec![
+                r.pid.to_string(),
+                r.image.clone(),
+                r.cmdline.clone().or_else(|| r.exe_path.clone()).unwrap_or_default(),
+                format_unix(r.start_time),
+                if r.exited_at.is_some() { "exited".to_string() } else { "running".to_string() },
+            ])
+            .collect();
+        sections.push(ReportSection {
+            heading: "Process ancestry".to_string(),
+            blocks: vec![table(&["PID", "Image", "Command line", "Started", "State"], rows)],
+        });
+    }
+
+    let action_rows: Vec<Vec<String>> = actions.iter()
+        .map(|a| vec![
+            format_time(&a.executed_at),
+            a.action_type.to_string(),
+            match a.target_pid {
+                Some(pid) => format!("{} (PID {})", a.target_name, pid),
+                None => a.target_name.clone(),
+            },
+            format!("{:?}", a.status),
+            if a.auto_executed { "auto".to_string() } else { "manual".to_string() },
+            a.result.clone().unwrap_or_default(),
+        ])
+        .chain(incident.enforcement_failures.iter().map(|f| vec![
+            format_time(&f.at),
+            f.action_type.clone(),
+            match f.target_pid {
+                Some(pid) => format!("{} (PID {})", f.target_name, pid),
+                None => f.target_name.clone(),
+            },
+            "Failed".to_string(),
+            "-".to_string(),
+            f.reason.clone(),
+        ]))
+        .collect();
+    sections.push(table_or_text(
+        "Actions taken",
+        &["Time", "Action", "Target", "Status", "Mode", "Result"],
+        action_rows,
+        "No response actions were taken.",
+    ));
+
+    if !incident.notes.is_empty() {
+        let rows = incident.notes.iter()
+            .map(|n| vec![
+                format_time(&n.created_at),
+                format!("{} ({})", n.author, n.source),
+                n.disposition.clone().unwrap_or_default(),
+                n.text.clone(),
+            ])
+            .collect();
+        sections.push(ReportSection {
+            heading: "Analyst notes".to_string(),
+            blocks: vec![table(&["Time", "Author", "Disposition", "Note"], rows)],
+        });
+    }
+
+    ReportDocument {
+        title,
+        subtitle: format!("Incident {} - generated {}", incident.incident_id, format_time(&generated_at)),
+        sections,
+    }
+}
+
+fn summary_section(incident: &Incident, title: &str) -> ReportSection {
+    let duration = incident.last_seen.signed_duration_since(incident.started_at);
+    let mut fields = vec![
+        ("Incident ID".to_string(), incident.incident_id.to_string()),
+        ("Title".to_string(), title.to_string()),
+        ("Severity".to_string(), severity_label(&incident.severity).to_string()),
+        ("Status".to_string(), status_label(&incident.status).to_string()),
+        ("Verdict".to_string(), match incident.verdict {
+            Some(IncidentVerdict::Confirmed) => "Confirmed (true positive)".to_string(),
+            Some(IncidentVerdict::Dismissed) => "Dismissed (false positive)".to_string(),
+            None => "Pending triage".to_string(),
+        }),
+        ("First seen".to_string(), format_time(&incident.started_at)),
+        ("Last seen".to_string(), format_time(&incident.last_seen)),
+        ("Duration".to_string(), format_duration(duration.num_seconds())),
+        ("Detections".to_string(), incident.correlation.detection_count.max(1).to_string()),
+        ("Telemetry records".to_string(), incident.records.len().to_string()),
+    ];
+    if !incident.correlation.pids.is_empty() {
+        let pids: Vec<String> = incident.correlation.pids.iter().map(u32::to_string).collect();
+        fields.push(("Processes".to_string(), pids.join(", ")));
+    }
+    if !incident.correlation.hashes.is_empty() {
+        fields.push(("SHA-256".to_string(), incident.correlation.hashes.iter().cloned().collect::<Vec<_>>().join("\n")));
+    }
+
+    let mut blocks = vec![ReportBlock::Fields(fields)];
+    if incident.correlation.detections.len() > 1 {
+        blocks.push(ReportBlock::Text(format!(
+            "Correlated detections: {}",
+            incident.correlation.detections.join("; ")
+        )));
+    }
+    ReportSection { heading: "Summary".to_string(), blocks }
+}
+
+fn timeline_section(incident: &Incident, actions: &[ActionRecord]) -> ReportSection {
+    let mut events: Vec<(DateTime<Utc>, String, String)> = Vec::new();
+
+    for record in incident.records.iter().take(MAX_TIMELINE_RECORDS) {
+        let mut detail = format!("score {:.2}, confidence {:.2}", record.score, record.confidence);
+        if !record.tags.is_empty() {
+            detail.push_str(&format!(", tags: {}", record.tags.join(", ")));
+        }
+        events.push((record.ts, format!("{:?} detection", record.threat), detail));
+    }
+    for excerpt in &incident.script_excerpts {
+        events.push((
+            excerpt.at,
+            "Script block".to_string(),
+            format!("PID {}: {}", excerpt.pid, excerpt.excerpt.lines().next().unwrap_or_default()),
+        ));
+    }
+    for action in actions {
+        events.push((action.executed_at, format!("Action {}", action.action_type.to_string()), format!("{} ({:?})", action.target_name, action.status)));
+    }
+    for failure in &incident.enforcement_failures {
+        events.push((failure.at, "Enforcement failed".to_string(), format!("{} on {}: {}", failure.action_type, failure.target_name, failure.reason)));
+    }
+    for change in &incident.severity_history {
+        events.push((
+            change.at,
+            "Severity raised".to_string(),
+            format!("{} -> {}: {}", severity_label(&change.from), severity_label(&change.to), change.reason),
+        ));
+    }
+    for note in &incident.notes {
+        events.push((note.created_at, "Analyst note".to_string(), format!("{}: {}", note.author, note.text)));
+    }
+    events.sort_by_key(|(ts, _, _)| *ts);
+
+    let rows = events.into_iter().map(|(ts, event, detail)| vec![format_time(&ts), event, detail]).collect();
+    let mut blocks = vec![table(&["Time", "Event", "Details"], rows)];
+    if incident.records.len() > MAX_TIMELINE_RECORDS {
+        blocks.push(ReportBlock::Text(format!(
+            "Showing the first {} of {} telemetry records.",
+            MAX_TIMELINE_RECORDS,
+            incident.records.len()
+        )));
+    }
+    ReportSection { heading: "Timeline".to_string(), blocks }
+}
+
+fn table(columns: &[&str], rows: Vec<Vec<String>>) -> ReportBlock {
+    ReportBlock::Table { columns: columns.iter().map(|c| c.to_string()).collect(), rows }
+}
+
+fn table_or_text(heading: &str, columns: &[&str], rows: Vec<Vec<String>>, empty: &str) -> ReportSection {
+    let block = if rows.is_empty() { ReportBlock::Text(empty.to_string()) } else { table(columns, rows) };
+    ReportSection { heading: heading.to_string(), blocks: vec![block] }
+}
+
+fn severity_label(severity: &Severity) -> &'static str {
+    match severity {
+        Severity::Low => "Low",
+        Severity::Medium => "Medium",
+        Severity::High => "High",
+        Severity::Critical => "Critical",
+    }
+}
+
+fn status_label(status: &IncidentStatus) -> &'static str {
+    match status {
+        IncidentStatus::Open => "Open",
+        IncidentStatus::Mitigated => "Mitigated",
+        IncidentStatus::Closed => "Closed",
+    }
+}
+
+fn format_time(ts: &DateTime<Utc>) -> String {
+    ts.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+fn format_unix(secs: i64) -> String {
+    Utc.timestamp_opt(secs, 0).single().map(|t| format_time(&t)).unwrap_or_else(|| "-".to_string())
+}
+
+fn format_duration(secs: i64) -> String {
+    let secs = secs.max(0);
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
+
+fn short_id(id: &Uuid) -> String {
+    id.simple().to_string()[..8].to_string()
+}
+
+// ============================================================================
+// HTML
+// ============================================================================
+
+const HTML_STYLE: &str = "body{font-family:Segoe UI,Helvetica,Arial,sans-serif;color:#1f2933;margin:32px auto;max-width:1000px;padding:0 24px}\
+h1{font-size:24px;margin-bottom:4px}.subtitle{color:#616e7c;margin-top:0}\
+h2{font-size:17px;border-bottom:2px solid #e4e7eb;padding-bottom:4px;margin-top:28px}\
+table{border-collapse:collapse;width:100%;font-size:13px}th,td{border:1px solid #e4e7eb;padding:5px 8px;text-align:left;vertical-align:top}\
+th{background:#f5f7fa}td{white-space:pre-wrap;word-break:break-word}table.fields th{width:170px}\
+.sev{font-weight:600;padding:1px 8px;border-radius:4px;color:#fff}.sev-low{background:#3e7bfa}.sev-medium{background:#f0b429}\
+.sev-high{background:#e8590c}.sev-critical{background:#c92a2a}\
+@media print{body{margin:0;max-width:none}h2{page-break-after:avoid}tr{page-break-inside:avoid}}";
+
+pub(crate) fn render_html(document: &ReportDocument) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n", escape_html(&document.title), HTML_STYLE));
+    html.push_str(&format!("<h1>{}</h1>\n<p class=\"subtitle\">{}</p>\n", escape_html(&document.title), escape_html(&document.subtitle)));
+
+    for section in &document.sections {
+        html.push_str(&format!("<h2>{}</h2>\n", escape_html(&section.heading)));
+        for block in &section.blocks {
+            match block {
+                ReportBlock::Fields(fields) => {
+                    html.push_str("<table class=\"fields\">\n");
+                    for (label, value) in fields {
+                        let value_html = if label == "Severity" {
+                            format!("<span class=\"sev sev-{}\">{}</span>", value.to_lowercase(), escape_html(value))
+                        } else {
+                            escape_html(value)
+                        };
+                        html.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", escape_html(label), value_html));
+                    }
+                    html.push_str("</table>\n");
+                }
+                ReportBlock::Table { columns, rows } => {
+                    html.push_str("<table>\n<tr>");
+                    for column in columns {
+                        html.push_str(&format!("<th>{}</th>", escape_html(column)));
+                    }
+                    html.push_str("</tr>\n");
+                    for row in rows {
+                        html.push_str("<tr>");
+                        for cell in row {
+                            html.push_str(&format!("<td>{}</td>", escape_html(cell)));
+                        }
+                        html.push_str("</tr>\n");
+                    }
+                    html.push_str("</table>\n");
+                }
+                ReportBlock::Text(text) => html.push_str(&format!("<p>{}</p>\n", escape_html(text))),
+            }
+        }
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// ============================================================================
+// PDF
+// ============================================================================
+
+#[derive(Clone, Copy)]
+enum PdfFont {
+    Regular,
+    Bold,
+    Mono,
+}
+
+impl PdfFont {
+    fn resource(&self) -> &'static str {
+        match self {
+            PdfFont::Regular => "F1",
+            PdfFont::Bold => "F2",
+            PdfFont::Mono => "F3",
+        }
+    }
+
+    /// Số ký tự tối đa vừa `width` point ở cỡ chữ `size`
+    fn chars_for(&self, width: f32, size: f32) -> usize {
+        let em = match self {
+            PdfFont::Mono => COURIER_EM,
+            _ => HELVETICA_AVG_EM,
+        };
+        ((width / (size * em)) as usize).max(1)
+    }
+}
+
+/// Dựng content stream theo trang, tự sang trang khi hết chỗ
+struct PdfPages {
+    pages: Vec<String>,
+    current: String,
+    y: f32,
+}
+
+impl PdfPages {
+    fn new() -> Self {
+        Self { pages: Vec::new(), current: String::new(), y: PAGE_HEIGHT - MARGIN }
+    }
+
+    fn ensure(&mut self, height: f32) {
+        if self.y - height < MARGIN && !self.current.is_empty() {
+            self.pages.push(std::mem::take(&mut self.current));
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    fn text(&mut self, font: PdfFont, size: f32, x: f32, y: f32, text: &str) {
+        self.current.push_str(&format!(
+            "BT /{} {} Tf {:.1} {:.1} Td ({}) Tj ET\n",
+            font.resource(),
+            size,
+            x,
+            y,
+            escape_pdf(text)
+        ));
+    }
+
+    fn rule(&mut self, y: f32) {
+        self.current.push_str(&format!("0.8 G 0.5 w {} {:.1} m {} {:.1} l S 0 G\n", MARGIN, y, PAGE_WIDTH - MARGIN, y));
+    }
+
+    /// Đoạn văn tự ngắt dòng
+    fn paragraph(&mut self, font: PdfFont, size: f32, x: f32, text: &str) {
+        let leading = size * 1.3;
+        for line in wrap(text, font.chars_for(PAGE_WIDTH - MARGIN - x, size)) {
+            self.ensure(leading);
+            self.y -= leading;
+            self.text(font, size, x, self.y, &line);
+        }
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        if !self.current.is_empty() || self.pages.is_empty() {
+            self.pages.push(self.current);
+        }
+        self.pages
+    }
+}
+
+pub(crate) fn render_pdf(document: &ReportDocument) -> Vec<u8> {
+    let mut pages = PdfPages::new();
+    pages.paragraph(PdfFont::Bold, TITLE_SIZE, MARGIN, &document.title);
+    pages.paragraph(PdfFont::Regular, BODY_SIZE, MARGIN, &document.subtitle);
+
+    for section in &document.sections {
+        pages.ensure(HEADING_SIZE * 4.0);
+        pages.y -= HEADING_SIZE;
+        pages.paragraph(PdfFont::Bold, HEADING_SIZE, MARGIN, &section.heading);
+        pages.y -= 3.0;
+        pages.rule(pages.y);
+        pages.y -= 2.0;
+
+        for block in &section.blocks {
+            match block {
+                ReportBlock::Fields(fields) => pdf_fields(&mut pages, fields),
+                ReportBlock::Table { columns, rows } => pdf_table(&mut pages, columns, rows),
+                ReportBlock::Text(text) => {
+                    pages.paragraph(PdfFont::Regular, BODY_SIZE, MARGIN, text);
+                }
+            }
+            pages.y -= 4.0;
+        }
+    }
+
+    let streams = pages.finish();
+    let total = streams.len();
+    let streams: Vec<String> = streams
+        .into_iter()
+        .enumerate()
+        .map(|(i, mut stream)| {
+            let footer = format!("{} - page {} of {}", document.title, i + 1, total);
+            let footer: String = footer.chars().take(PdfFont::Regular.chars_for(PAGE_WIDTH - 2.0 * MARGIN, 8.0)).collect();
+            stream.push_str(&format!("BT /F1 8 Tf {} {} Td ({}) Tj ET\n", MARGIN, MARGIN / 2.0, escape_pdf(&footer)));
+            stream
+        })
+        .collect();
+    assemble_pdf(&streams)
+}
+
+fn pdf_fields(pages: &mut PdfPages, fields: &[(String, String)]) {
+    let leading = BODY_SIZE * 1.4;
+    let value_x = MARGIN + FIELD_LABEL_WIDTH;
+    let max_chars = PdfFont::Regular.chars_for(PAGE_WIDTH - MARGIN - value_x, BODY_SIZE);
+    for (label, value) in fields {
+        let lines: Vec<String> = value.lines().flat_map(|l| wrap(l, max_chars)).collect();
+        pages.ensure(leading * lines.len().max(1) as f32);
+        pages.y -= leading;
+        pages.text(PdfFont::Bold, BODY_SIZE, MARGIN, pages.y, label);
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                pages.y -= leading;
+            }
+            pages.text(PdfFont::Regular, BODY_SIZE, value_x, pages.y, line);
+        }
+    }
+}
+
+/// Bảng font Courier: cột rộng theo nội dung, ô dài được ngắt dòng trong cột
+fn pdf_table(pages: &mut PdfPages, columns: &[String], rows: &[Vec<String>]) {
+    const GAP: usize = 2;
+    let leading = TABLE_SIZE * 1.35;
+    let available = PdfFont::Mono.chars_for(PAGE_WIDTH - 2.0 * MARGIN, TABLE_SIZE);
+    let widths = column_widths(columns, rows, available.saturating_sub(GAP * columns.len().saturating_sub(1)));
+    let char_width = TABLE_SIZE * COURIER_EM;
+
+    let draw_row = |pages: &mut PdfPages, cells: &[String], font: PdfFont| {
+        let wrapped: Vec<Vec<String>> = widths.iter().enumerate()
+            .map(|(i, w)| wrap(cells.get(i).map(String::as_str).unwrap_or(""), *w))
+            .collect();
+        let height = wrapped.iter().map(Vec::len).max().unwrap_or(1).max(1);
+        pages.ensure(leading * height as f32);
+        for line in 0..height {
+            pages.y -= leading;
+            let mut x = MARGIN;
+            for (i, cell) in wrapped.iter().enumerate() {
+                if let Some(text) = cell.get(line) {
+                    pages.text(font, TABLE_SIZE, x, pages.y, text);
+                }
+                x += (widths[i] + GAP) as f32 * char_width;
+            }
+        }
+    };
+
+    draw_row(pages, columns, PdfFont::Bold);
+    pages.rule(pages.y - 3.0);
+    pages.y -= 3.0;
+    for row in rows {
+        draw_row(pages, row, PdfFont::Mono);
+    }
+}
+
+/// Chia `available` ký tự cho các cột: bắt đầu bằng độ dài lớn nhất, thu cột rộng nhất đến khi vừa
+fn column_widths(columns: &[String], rows: &[Vec<String>], available: usize) -> Vec<usize> {
+    const MIN_WIDTH: usize = 6;
+    let mut widths: Vec<usize> = columns.iter().enumerate()
+        .map(|(i, c)| {
+            rows.iter()
+                .filter_map(|r| r.get(i))
+                .flat_map(|cell| cell.lines().map(|l| l.chars().count()))
+                .chain(std::iter::once(c.chars().count()))
+                .max()
+                .unwrap_or(0)
+                .max(MIN_WIDTH)
+        })
+        .collect();
+    while widths.iter().sum::<usize>() > available {
+        let Some(widest) = widths.iter().enumerate().max_by_key(|(_, w)| **w).map(|(i, _)| i) else { break };
+        if widths[widest] <= MIN_WIDTH {
+            break;
+        }
+        widths[widest] -= 1;
+    }
+    widths
+}
+
+/// Ngắt dòng theo từ (từ dài hơn dòng bị cắt cứng)
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        let mut len = 0;
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            while word.len() > width {
+                if len > 0 {
+                    lines.push(std::mem::take(&mut line));
+                    len = 0;
+                }
+                lines.push(word.drain(..width).collect());
+            }
+            if word.is_empty() {
+                continue;
+            }
+            if len > 0 && len + 1 + word.len() > width {
+                lines.push(std::mem::take(&mut line));
+                len = 0;
+            }
+            if len > 0 {
+                line.push(' ');
+                len += 1;
+            }
+            len += word.len();
+            line.extend(word);
+        }
+        if len > 0 || lines.is_empty() {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+/// Chuỗi PDF literal (WinAnsi): escape `\ ( )`, ký tự ngoài Latin-1 → `?`
+fn escape_pdf(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '(' | ')' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\t' => out.push(' '),
+            c if (c as u32) < 0x20 => {}
+            c if (c as u32) < 0x7F => out.push(c),
+            c if (0xA0..=0xFF).contains(&(c as u32)) => out.push_str(&format!("\\{:03o}", c as u32)),
+            _ => out.push('?'),
+        }
+    }
+    out
+}
+
+/// Ghép object PDF: catalog, pages, 3 font, mỗi trang một page + content stream, xref
+fn assemble_pdf(streams: &[String]) -> Vec<u8> {
+    let first_page_obj = 6;
+    let kids: Vec<String> = (0..streams.len()).map(|i| format!("{} 0 R", first_page_obj + i * 2)).collect();
+
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), streams.len()),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_string(),
+    ];
+    for (i, stream) in streams.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R /F3 5 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            first_page_obj + i * 2 + 1
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{}endstream", stream.len(), stream));
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+    let xref_offset = pdf.len();
+    let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        xref.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    xref.push_str(&format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref_offset));
+    pdf.extend_from_slice(xref.as_bytes());
+    pdf
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(rows: usize) -> ReportDocument {
+        ReportDocument {
+            title: "Encoded PowerShell (+1 related detections)".to_string(),
+            subtitle: "Incident 00000000 - generated 2026-01-01 00:00:00 UTC".to_string(),
+            sections: vec![
+                ReportSection {
+                    heading: "Summary".to_string(),
+                    blocks: vec![ReportBlock::Fields(vec![
+                        ("Severity".to_string(), "High".to_string()),
+                        ("SHA-256".to_string(), "aa\nbb".to_string()),
+                    ])],
+                },
+                ReportSection {
+                    heading: "Timeline".to_string(),
+                    blocks: vec![ReportBlock::Table {
+                        columns: vec!["Time".to_string(), "Event".to_string(), "Details".to_string()],
+                        rows: (0..rows)
+                            .map(|i| vec![format!("t{}", i), "<script>".to_string(), "powershell -enc (AAAA) Tệp".repeat(5)])
+                            .collect(),
+                    }],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_render_html_and_pdf() {
+        let html = render_html(&document(3));
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("<td><script>"));
+        assert!(html.contains("sev-high"));
+
+        let pdf = render_pdf(&document(200));
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(text.trim_end().ends_with("%%EOF"));
+        // 200 dòng timeline ngắt dòng → nhiều trang, đếm khớp với /Count
+        let pages = text.matches("/Type /Page ").count();
+        assert!(pages > 1);
+        assert!(text.contains(&format!("/Count {}", pages)));
+        assert!(text.contains(&format!("page {} of {}", pages, pages)));
+        // Ngoặc được escape, ký tự ngoài Latin-1 thay bằng ?
+        assert!(text.contains("\\(AAAA\\)"));
+        assert!(text.contains("T?p"));
+
+        // xref trỏ đúng offset (byte) của từng object
+        let xref_at: usize = text.lines().rev().nth(1).unwrap().parse().unwrap();
+        let xref = std::str::from_utf8(&pdf[xref_at..]).unwrap();
+        assert!(xref.starts_with("xref"));
+        let entries: Vec<usize> = xref.lines().skip(3)
+            .take_while(|l| l.ends_with(" n "))
+            .map(|l| l[..10].parse().unwrap())
+            .collect();
+        assert_eq!(entries.len(), 5 + pages * 2);
+        for (i, offset) in entries.iter().enumerate() {
+            assert!(pdf[*offset..].starts_with(format!("{} 0 obj", i + 1).as_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_wrap_and_column_widths() {
+        assert_eq!(wrap("alpha beta gamma", 10), vec!["alpha beta", "gamma"]);
+        assert_eq!(wrap("abcdefghij klm", 4), vec!["abcd", "efgh", "ij", "klm"]);
+        assert_eq!(wrap("", 5), vec![""]);
+
+        let columns = vec!["Time".to_string(), "Details".to_string()];
+        let rows = vec![vec!["2026-01-01 00:00:00 UTC".to_string(), "x".repeat(300)]];
+        let widths = column_widths(&columns, &rows, 100);
+        assert_eq!(widths.iter().sum::<usize>(), 100);
+        assert_eq!(widths[0], 23);
+    }
+}
@@ -0,0 +1,437 @@
+//! Incident Suppressions - Tự đóng incident theo mẫu đã biết là lành tính
+//!
+//! Khác whitelist (bỏ qua process ở mọi nơi) và rule suppression (tắt match của một rule):
+//! detection vẫn chạy và incident vẫn được tạo, nhưng incident khớp mẫu bị đóng ngay
+//! (`Closed`, `suppressed_by`), không đẩy lên cloud và được ghi audit.
+//!
+//! Một rule gồm các điều kiện AND (cần ít nhất một trong process path / rule id / tag):
+//! - `process_path`: executable bị flag (kết thúc bằng `*` = prefix)
+//! - `rule_id`: rule đã match trong incident
+//! - `tags`: incident có đủ mọi tag
+//! - `time_window`: giờ local của detection (vd bảo trì 02:00-04:00, qua nửa đêm được)
+//! - `max_severity`: không suppress incident nặng hơn mức này
+//!
+//! `expires_at` tùy chọn - rule hết hạn tự gỡ (ghi audit "expired").
+
+use std::fs;
+use std::path::PathBuf;
+use chrono::{DateTime, Local, Timelike, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use super::types::{Incident, IncidentStatus, Severity};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const SUPPRESSIONS_FILE: &str = "incident_suppressions.json";
+const MAX_AUDIT_ENTRIES: usize = 2000;
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Khoảng giờ local [start, end) tính bằng phút trong ngày; start > end = qua nửa đêm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeWindow {
+    pub start_minute: u16,
+    pub end_minute: u16,
+}
+
+impl TimeWindow {
+    fn contains(&self, minute: u16) -> bool {
+        if self.start_minute <= self.end_minute {
+            minute >= self.start_minute && minute < self.end_minute
+        } else {
+            minute >= self.start_minute || minute < self.end_minute
+        }
+    }
+}
+
+/// Rule mới từ UI (chưa có id / audit)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NewIncidentSuppression {
+    pub name: String,
+    pub process_path: Option<String>,
+    pub rule_id: Option<String>,
+    pub tags: Vec<String>,
+    pub time_window: Option<TimeWindow>,
+    pub max_severity: Option<Severity>,
+    pub reason: String,
+    /// Unix seconds, None = không hết hạn
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentSuppression {
+    pub id: String,
+    pub name: String,
+    /// Đã normalize (lowercase, `\`)
+    pub process_path: Option<String>,
+    pub rule_id: Option<String>,
+    /// Đã normalize (uppercase)
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub time_window: Option<TimeWindow>,
+    pub max_severity: Option<Severity>,
+    pub reason: String,
+    pub created_by: String,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    #[serde(default)]
+    pub hit_count: u64,
+    #[serde(default)]
+    pub last_hit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentSuppressionAudit {
+    pub timestamp: i64,
+    /// "created" | "removed" | "expired" | "suppressed"
+    pub action: String,
+    pub suppression_id: String,
+    pub suppression_name: String,
+    /// Incident bị đóng (chỉ với "suppressed")
+    pub incident_id: Option<String>,
+    pub incident_title: Option<String>,
+    pub actor: String,
+    pub reason: Option<String>,
+}
+
+/// Thông tin của incident dùng để so với rule
+#[derive(Debug, Clone)]
+pub(crate) struct IncidentFacts {
+    /// Executable bị flag (đã normalize)
+    pub process_path: Option<String>,
+    pub rule_ids: Vec<String>,
+    /// Đã normalize (uppercase)
+    pub tags: Vec<String>,
+    pub severity: Severity,
+    /// Phút trong ngày (giờ local) của detection
+    pub minute_of_day: u16,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SuppressionStore {
+    entries: Vec<IncidentSuppression>,
+    audit: Vec<IncidentSuppressionAudit>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static STORE: Lazy<RwLock<SuppressionStore>> = Lazy::new(|| RwLock::new(load()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Thêm rule suppress incident
+pub fn add(rule: NewIncidentSuppression, actor: &str) -> Result<IncidentSuppression, String> {
+    let now = Utc::now().timestamp();
+    let process_path = rule.process_path.as_deref().map(str::trim).filter(|p| !p.is_empty()).map(normalize_path);
+    let rule_id = rule.rule_id.as_deref().map(str::trim).filter(|r| !r.is_empty()).map(str::to_string);
+    let tags: Vec<String> = rule.tags.iter().map(|t| t.trim().to_uppercase()).filter(|t| !t.is_empty()).collect();
+
+    if process_path.is_none() && rule_id.is_none() && tags.is_empty() {
+        return Err("A process path, rule id or tag is required".to_string());
+    }
+    if rule.reason.trim().is_empty() {
+        return Err("A reason is required for the audit trail".to_string());
+    }
+    if let Some(window) = rule.time_window {
+        if window.start_minute >= MINUTES_PER_DAY || window.end_minute >= MINUTES_PER_DAY || window.start_minute == window.end_minute {
+            return Err("Invalid time window".to_string());
+        }
+    }
+    if rule.expires_at.is_some_and(|at| at <= now) {
+        return Err("Expiry must be in the future".to_string());
+    }
+
+    let entry = IncidentSuppression {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: if rule.name.trim().is_empty() { rule.reason.trim().to_string() } else { rule.name.trim().to_string() },
+        process_path,
+        rule_id,
+        tags,
+        time_window: rule.time_window,
+        max_severity: rule.max_severity,
+        reason: rule.reason.trim().to_string(),
+        created_by: actor.to_string(),
+        created_at: now,
+        expires_at: rule.expires_at,
+        hit_count: 0,
+        last_hit: None,
+    };
+
+    {
+        let mut store = STORE.write();
+        let audit = audit_entry("created", &entry, None, actor, Some(entry.reason.clone()));
+        push_audit(&mut store, audit);
+        store.entries.push(entry.clone());
+    }
+    save();
+    log::info!("Incident suppression added: {} ({})", entry.name, entry.reason);
+    Ok(entry)
+}
+
+/// Gỡ rule
+pub fn remove(id: &str, actor: &str, reason: Option<String>) -> Result<(), String> {
+    {
+        let mut store = STORE.write();
+        let index = store.entries.iter().position(|e| e.id == id)
+            .ok_or_else(|| format!("Incident suppression {} not found", id))?;
+        let entry = store.entries.remove(index);
+        let audit = audit_entry("removed", &entry, None, actor, reason);
+        push_audit(&mut store, audit);
+    }
+    save();
+    Ok(())
+}
+
+/// Rule đang hiệu lực
+pub fn list() -> Vec<IncidentSuppression> {
+    expire();
+    STORE.read().entries.clone()
+}
+
+/// Audit trail (mới nhất trước)
+pub fn get_audit(limit: usize) -> Vec<IncidentSuppressionAudit> {
+    STORE.read().audit.iter().rev().take(limit).cloned().collect()
+}
+
+/// Đóng incident nếu khớp rule (ghi audit, tăng hit). Trả về id của rule khớp
+pub(crate) fn apply(incident: &mut Incident) -> Option<String> {
+    if incident.status == IncidentStatus::Closed || STORE.read().entries.is_empty() {
+        return None;
+    }
+    expire();
+
+    let facts = facts_of(incident);
+    let entry = STORE.read().entries.iter().find(|e| matches(e, &facts)).cloned()?;
+
+    incident.status = IncidentStatus::Closed;
+    incident.suppressed_by = Some(entry.id.clone());
+    let title = incident.correlation.title("Incident");
+    {
+        let mut store = STORE.write();
+        if let Some(stored) = store.entries.iter_mut().find(|e| e.id == entry.id) {
+            stored.hit_count += 1;
+            stored.last_hit = Some(Utc::now().timestamp());
+        }
+        let audit = audit_entry("suppressed", &entry, Some((incident.incident_id.to_string(), title.clone())), "system", Some(entry.reason.clone()));
+        push_audit(&mut store, audit);
+    }
+    save();
+    log::info!("🔕 Incident {} ({}) suppressed by '{}'", incident.incident_id, title, entry.name);
+    Some(entry.id)
+}
+
+// ============================================================================
+// MATCHING
+// ============================================================================
+
+fn facts_of(incident: &Incident) -> IncidentFacts {
+    let mut tags: Vec<String> = incident.records.iter()
+        .flat_map(|r| r.tags.iter())
+        .map(|t| t.to_uppercase())
+        .collect();
+    tags.sort();
+    tags.dedup();
+
+    IncidentFacts {
+        // ancestry[0] = process bị flag
+        process_path: incident.process_ancestry.first()
+            .and_then(|r| r.exe_path.as_deref())
+            .map(normalize_path),
+        rule_ids: incident.rule_matches.clone(),
+        tags,
+        severity: incident.severity.clone(),
+        minute_of_day: minute_of_day(incident.last_seen),
+    }
+}
+
+pub(crate) fn matches(entry: &IncidentSuppression, facts: &IncidentFacts) -> bool {
+    if let Some(pattern) = &entry.process_path {
+        let Some(path) = facts.process_path.as_deref() else { return false };
+        let matched = match pattern.strip_suffix('*') {
+            Some(prefix) => path.starts_with(prefix),
+            None => path == pattern,
+        };
+        if !matched {
+            return false;
+        }
+    }
+    if let Some(rule_id) = &entry.rule_id {
+        if !facts.rule_ids.iter().any(|r| r.eq_ignore_ascii_case(rule_id)) {
+            return false;
+        }
+    }
+    if !entry.tags.iter().all(|t| facts.tags.contains(t)) {
+        return false;
+    }
+    if let Some(window) = entry.time_window {
+        if !window.contains(facts.minute_of_day) {
+            return false;
+        }
+    }
+    if let Some(max) = &entry.max_severity {
+        if level(&facts.severity) > level(max) {
+            return false;
+        }
+    }
+    true
+}
+
+fn level(severity: &Severity) -> u8 {
+    match severity {
+        Severity::Low => 1,
+        Severity::Medium => 2,
+        Severity::High => 3,
+        Severity::Critical => 4,
+    }
+}
+
+fn minute_of_day(ts: DateTime<Utc>) -> u16 {
+    let local = ts.with_timezone(&Local);
+    (local.hour() * 60 + local.minute()) as u16
+}
+
+fn normalize_path(path: &str) -> String {
+    path.trim().to_lowercase().replace('/', "\\")
+}
+
+/// Gỡ rule hết hạn (ghi audit)
+fn expire() {
+    let now = Utc::now().timestamp();
+    if !STORE.read().entries.iter().any(|e| e.expires_at.is_some_and(|at| at <= now)) {
+        return;
+    }
+    {
+        let mut store = STORE.write();
+        let (expired, active): (Vec<_>, Vec<_>) = std::mem::take(&mut store.entries)
+            .into_iter()
+            .partition(|e| e.expires_at.is_some_and(|at| at <= now));
+        store.entries = active;
+        for entry in expired {
+            log::info!("Incident suppression expired: {}", entry.name);
+            let audit = audit_entry("expired", &entry, None, "system", None);
+            push_audit(&mut store, audit);
+        }
+    }
+    save();
+}
+
+fn audit_entry(
+    action: &str,
+    entry: &IncidentSuppression,
+    incident: Option<(String, String)>,
+    actor: &str,
+    reason: Option<String>,
+) -> IncidentSuppressionAudit {
+    let (incident_id, incident_title) = incident.unzip();
+    IncidentSuppressionAudit {
+        timestamp: Utc::now().timestamp(),
+        action: action.to_string(),
+        suppression_id: entry.id.clone(),
+        suppression_name: entry.name.clone(),
+        incident_id,
+        incident_title,
+        actor: actor.to_string(),
+        reason,
+    }
+}
+
+fn push_audit(store: &mut SuppressionStore, entry: IncidentSuppressionAudit) {
+    store.audit.push(entry);
+    let overflow = store.audit.len().saturating_sub(MAX_AUDIT_ENTRIES);
+    store.audit.drain(..overflow);
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn store_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(SUPPRESSIONS_FILE)
+}
+
+fn load() -> SuppressionStore {
+    fs::read_to_string(store_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save() {
+    let path = store_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*STORE.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> IncidentSuppression {
+        IncidentSuppression {
+            id: "s1".to_string(),
+            name: "Nightly backup".to_string(),
+            process_path: Some(normalize_path("C:/Program Files/Backup/*")),
+            rule_id: None,
+            tags: vec!["MASS_FILE_WRITE".to_string()],
+            time_window: Some(TimeWindow { start_minute: 23 * 60, end_minute: 3 * 60 }),
+            max_severity: Some(Severity::High),
+            reason: "Known backup job".to_string(),
+            created_by: "analyst".to_string(),
+            created_at: 0,
+            expires_at: None,
+            hit_count: 0,
+            last_hit: None,
+        }
+    }
+
+    #[test]
+    fn test_incident_suppression_matching() {
+        let facts = IncidentFacts {
+            process_path: Some(normalize_path(r"C:\Program Files\Backup\agent.exe")),
+            rule_ids: vec!["RANSOM_NOTE".to_string()],
+            tags: vec!["ENTROPY_SPIKE".to_string(), "MASS_FILE_WRITE".to_string()],
+            severity: Severity::Medium,
+            minute_of_day: 60,
+        };
+        assert!(matches(&entry(), &facts));
+
+        // Ngoài khung giờ (qua nửa đêm 23:00-03:00)
+        assert!(!matches(&entry(), &IncidentFacts { minute_of_day: 12 * 60, ..facts.clone() }));
+        assert!(matches(&entry(), &IncidentFacts { minute_of_day: 23 * 60 + 30, ..facts.clone() }));
+        // Nặng hơn max_severity
+        assert!(!matches(&entry(), &IncidentFacts { severity: Severity::Critical, ..facts.clone() }));
+        // Process khác / không có process
+        assert!(!matches(&entry(), &IncidentFacts { process_path: Some(r"c:\temp\agent.exe".to_string()), ..facts.clone() }));
+        assert!(!matches(&entry(), &IncidentFacts { process_path: None, ..facts.clone() }));
+        // Thiếu tag
+        assert!(!matches(&entry(), &IncidentFacts { tags: vec!["ENTROPY_SPIKE".to_string()], ..facts.clone() }));
+
+        let by_rule = IncidentSuppression { rule_id: Some("ransom_note".to_string()), process_path: None, tags: Vec::new(), time_window: None, ..entry() };
+        assert!(matches(&by_rule, &IncidentFacts { process_path: None, ..facts.clone() }));
+        assert!(!matches(&by_rule, &IncidentFacts { rule_ids: Vec::new(), ..facts }));
+    }
+}
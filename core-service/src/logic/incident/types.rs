@@ -1,8 +1,13 @@
+use std::collections::BTreeMap;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::logic::threat::ThreatClass;
 use crate::logic::explain::ExplainResult;
+use crate::logic::response::ransomware::RecoveryFile;
+use crate::logic::process_intel::genealogy::GenealogyRecord;
+use crate::logic::process_intel::artifacts::ExecutionArtifacts;
+use super::correlation::{Correlation, ESCALATE_AFTER_DETECTIONS};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum IncidentStatus {
@@ -11,6 +16,26 @@ pub enum IncidentStatus {
     Closed,
 }
 
+/// Kết luận triage của analyst (local hoặc từ cloud console)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentVerdict {
+    /// True positive
+    Confirmed,
+    /// False positive
+    Dismissed,
+}
+
+impl IncidentVerdict {
+    /// Nhãn training cho record bị flag của incident
+    pub fn dataset_label(&self) -> ThreatClass {
+        match self {
+            IncidentVerdict::Confirmed => ThreatClass::Malicious,
+            IncidentVerdict::Dismissed => ThreatClass::Benign,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Severity {
     Low,     // Suspicious low confidence
@@ -19,6 +44,18 @@ pub enum Severity {
     Critical, // Multiple Malicious or Critical Tag
 }
 
+impl Severity {
+    /// Tên severity của cloud API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatasetRecordSummary {
     pub ts: DateTime<Utc>,
@@ -41,6 +78,142 @@ pub struct Incident {
     pub explanation: Option<ExplainResult>,
 
     pub records: Vec<DatasetRecordSummary>,
+
+    // Ransomware recovery triage: file bị sửa gần thời điểm phát hiện
+    #[serde(default)]
+    pub recovery_files: Vec<RecoveryFile>,
+
+    // Action Guard không enforce được (verify thất bại sau retry/escalation)
+    #[serde(default)]
+    pub enforcement_failures: Vec<EnforcementFailure>,
+
+    // PowerShell script block (ETW 4104) đã bị phát hiện
+    #[serde(default)]
+    pub script_excerpts: Vec<ScriptExcerpt>,
+
+    // Ancestry của process liên quan (gồm cả parent đã exit), process → root
+    #[serde(default)]
+    pub process_ancestry: Vec<GenealogyRecord>,
+
+    // Prefetch / Shimcache của binary bị flag (enrichment tùy chọn)
+    #[serde(default)]
+    pub execution_artifacts: Option<ExecutionArtifacts>,
+
+    // MITRE ATT&CK technique của các detection trong incident (coverage report)
+    #[serde(default)]
+    pub mitre_techniques: Vec<String>,
+
+    // Thời điểm thấy lần đầu của từng technique (tiến trình kill chain)
+    #[serde(default)]
+    pub technique_first_seen: BTreeMap<String, DateTime<Utc>>,
+
+    // Verdict triage → nhãn cho record dataset trong khoảng thời gian incident
+    #[serde(default)]
+    pub verdict: Option<IncidentVerdict>,
+
+    // Detection đã gộp vào incident (cây process / hash / thời gian)
+    #[serde(default)]
+    pub correlation: Correlation,
+
+    // Ghi chú / lý do kết luận của analyst (đồng bộ hai chiều với cloud)
+    #[serde(default)]
+    pub notes: Vec<IncidentNote>,
+
+    // Các lần severity được nâng sau khi tạo incident
+    #[serde(default)]
+    pub severity_history: Vec<SeverityChange>,
+
+    // Rule id / condition đã match (detector chuyên biệt)
+    #[serde(default)]
+    pub rule_matches: Vec<String>,
+
+    // Id của rule suppression đã tự đóng incident
+    #[serde(default)]
+    pub suppressed_by: Option<String>,
+}
+
+/// Một lần nâng severity của incident
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeverityChange {
+    pub incident_id: Uuid,
+    pub from: Severity,
+    pub to: Severity,
+    pub reason: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Độ dài tối đa của ghi chú (ký tự) - khớp giới hạn của cloud
+pub const MAX_NOTE_CHARS: usize = 4000;
+/// Độ dài tối đa của lý do kết luận (ký tự)
+pub const MAX_DISPOSITION_CHARS: usize = 200;
+
+/// Ghi chú của analyst trên incident
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentNote {
+    pub id: Uuid,
+    pub author: String,
+    pub text: String,
+    /// Lý do kết luận (vd "Known admin script", "Confirmed phishing payload")
+    #[serde(default)]
+    pub disposition: Option<String>,
+    /// Nơi tạo ghi chú: `agent` / `console`
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl IncidentNote {
+    /// Ghi chú mới tạo trên agent (đã trim + kiểm tra độ dài)
+    pub fn new(author: &str, text: &str, disposition: Option<&str>) -> Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("Note text is empty".to_string());
+        }
+        if text.chars().count() > MAX_NOTE_CHARS {
+            return Err(format!("Note exceeds {} characters", MAX_NOTE_CHARS));
+        }
+        let disposition = disposition.map(str::trim).filter(|d| !d.is_empty());
+        if disposition.is_some_and(|d| d.chars().count() > MAX_DISPOSITION_CHARS) {
+            return Err(format!("Disposition exceeds {} characters", MAX_DISPOSITION_CHARS));
+        }
+        Ok(Self {
+            id: Uuid::new_v4(),
+            author: author.to_string(),
+            text: text.to_string(),
+            disposition: disposition.map(str::to_string),
+            source: "agent".to_string(),
+            created_at: Utc::now(),
+        })
+    }
+}
+
+/// Ngữ cảnh process của detection (gửi kèm incident lên cloud)
+#[derive(Debug, Clone, Default)]
+pub struct DetectionContext {
+    pub pid: Option<u32>,
+    /// Rule id / condition đã match
+    pub rule_matches: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnforcementFailure {
+    pub action_id: String,
+    pub action_type: String,
+    pub target_pid: Option<u32>,
+    pub target_name: String,
+    pub reason: String,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptExcerpt {
+    pub script_block_id: String,
+    pub pid: u32,
+    pub path: Option<String>,
+    /// Đoạn đầu của script block (đã cắt)
+    pub excerpt: String,
+    /// Độ dài đầy đủ (ký tự)
+    pub length: usize,
+    pub at: DateTime<Utc>,
 }
 
 impl Incident {
@@ -54,20 +227,88 @@ impl Incident {
             status: IncidentStatus::Open,
             explanation,
             records: vec![first_record],
+            recovery_files: Vec::new(),
+            enforcement_failures: Vec::new(),
+            script_excerpts: Vec::new(),
+            process_ancestry: Vec::new(),
+            execution_artifacts: None,
+            mitre_techniques: Vec::new(),
+            technique_first_seen: BTreeMap::new(),
+            verdict: None,
+            correlation: Correlation::default(),
+            notes: Vec::new(),
+            severity_history: Vec::new(),
+            rule_matches: Vec::new(),
+            suppressed_by: None,
         }
     }
 
-    pub fn update(&mut self, record: DatasetRecordSummary) {
+    /// Thêm record rồi tính lại severity (`recalculate_severity`, chỉ nâng).
+    /// Severity của chính record không được dùng: record tổng hợp của detector luôn là
+    /// score 1.0 / Malicious - severity của detection nằm trong `correlation`
+    pub fn update(&mut self, record: DatasetRecordSummary) -> Option<SeverityChange> {
         if record.ts > self.last_seen {
             self.last_seen = record.ts;
         }
+        let at = record.ts;
+        self.records.push(record);
+        self.recalculate_severity(at)
+    }
 
-        let new_severity = Self::map_severity(&record);
-        if self.severity_level(&new_severity) > self.severity_level(&self.severity) {
-            self.severity = new_severity;
+    /// Gắn technique (không trùng), ghi thời điểm thấy lần đầu = `last_seen` hiện tại
+    pub fn add_techniques<S: AsRef<str>>(&mut self, techniques: &[S]) {
+        for technique in techniques {
+            let technique = technique.as_ref();
+            if !technique.is_empty() && !self.mitre_techniques.iter().any(|t| t == technique) {
+                self.mitre_techniques.push(technique.to_string());
+                self.technique_first_seen.insert(technique.to_string(), self.last_seen);
+            }
         }
+    }
 
-        self.records.push(record);
+    /// Nâng severity (không bao giờ hạ)
+    pub fn escalate(&mut self, severity: Severity) {
+        self.raise_severity(severity, "escalated", self.last_seen);
+    }
+
+    /// Nâng severity kèm lý do, ghi vào `severity_history`. None nếu không cao hơn hiện tại
+    pub fn raise_severity(&mut self, severity: Severity, reason: &str, at: DateTime<Utc>) -> Option<SeverityChange> {
+        if self.severity_level(&severity) <= self.severity_level(&self.severity) {
+            return None;
+        }
+        let change = SeverityChange {
+            incident_id: self.incident_id,
+            from: std::mem::replace(&mut self.severity, severity.clone()),
+            to: severity,
+            reason: reason.to_string(),
+            at,
+        };
+        self.severity_history.push(change.clone());
+        Some(change)
+    }
+
+    /// Tính lại severity từ toàn bộ bằng chứng hiện có (detection đã gộp, action thất bại,
+    /// số giai đoạn kill chain) - chỉ nâng, không hạ
+    pub fn recalculate_severity(&mut self, at: DateTime<Utc>) -> Option<SeverityChange> {
+        let correlated = self.correlation.aggregate_severity();
+        let correlated_reason = if self.correlation.detections.len() >= ESCALATE_AFTER_DETECTIONS {
+            format!("{} correlated detections", self.correlation.detections.len())
+        } else {
+            "new correlated detection".to_string()
+        };
+        let mut candidates = vec![(correlated, correlated_reason)];
+
+        if !self.enforcement_failures.is_empty() {
+            candidates.push((Severity::High, "response action could not be enforced".to_string()));
+        }
+        let stages = super::kill_chain::progression(self).stages.len();
+        if let Some(severity) = super::kill_chain::stage_severity(stages) {
+            candidates.push((severity, format!("{} kill-chain stages", stages)));
+        }
+
+        // Cùng mức → giữ lý do đầu tiên (max_by_key lấy phần tử cuối nên đảo thứ tự)
+        let (severity, reason) = candidates.into_iter().rev().max_by_key(|(s, _)| self.severity_level(s))?;
+        self.raise_severity(severity, &reason, at)
     }
 
     fn map_severity(rec: &DatasetRecordSummary) -> Severity {
@@ -96,3 +337,105 @@ impl Incident {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::incident::correlation::DetectionFacts;
+
+    fn record(threat: ThreatClass, score: f32) -> DatasetRecordSummary {
+        DatasetRecordSummary { ts: Utc::now(), score, confidence: 1.0, threat, tags: Vec::new() }
+    }
+
+    fn detect(inc: &mut Incident, title: &str, severity: Severity) -> Option<SeverityChange> {
+        let facts = DetectionFacts {
+            title: title.to_string(),
+            ts: Utc::now(),
+            pid: Some(1234),
+            ancestor_pids: Vec::new(),
+            sha256: None,
+            severity,
+        };
+        inc.correlation.record(&facts, None);
+        inc.update(record(ThreatClass::Malicious, 1.0))
+    }
+
+    #[test]
+    fn test_raise_from_correlated_detections() {
+        let mut inc = Incident::new(record(ThreatClass::Suspicious, 0.8), None);
+        assert_eq!(inc.severity, Severity::Medium);
+
+        // Detection Medium đầu tiên không nâng, dù record tổng hợp là Malicious
+        assert!(detect(&mut inc, "Encoded PowerShell", Severity::Medium).is_none());
+        assert!(detect(&mut inc, "LOLBin download", Severity::Medium).is_none());
+        assert_eq!(inc.severity, Severity::Medium);
+
+        // Đủ ESCALATE_AFTER_DETECTIONS detection → +1 bậc
+        let change = detect(&mut inc, "Run key persistence", Severity::Medium).unwrap();
+        assert_eq!((change.from, change.to), (Severity::Medium, Severity::High));
+        assert_eq!(change.reason, "3 correlated detections");
+        assert_eq!(inc.severity, Severity::High);
+    }
+
+    #[test]
+    fn test_raise_from_enforcement_failure() {
+        let mut inc = Incident::new(record(ThreatClass::Suspicious, 0.5), None);
+        assert_eq!(inc.severity, Severity::Low);
+
+        inc.enforcement_failures.push(EnforcementFailure {
+            action_id: "a1".to_string(),
+            action_type: "KillProcess".to_string(),
+            target_pid: Some(1234),
+            target_name: "evil.exe".to_string(),
+            reason: "access denied".to_string(),
+            at: Utc::now(),
+        });
+        let change = inc.recalculate_severity(Utc::now()).unwrap();
+        assert_eq!(change.to, Severity::High);
+        assert_eq!(change.reason, "response action could not be enforced");
+    }
+
+    #[test]
+    fn test_raise_from_kill_chain_progression() {
+        let mut inc = Incident::new(record(ThreatClass::Suspicious, 0.5), None);
+
+        // Initial Access → Execution → Persistence (MULTI_STAGE_MIN)
+        inc.add_techniques(&["T1566", "T1059.001"]);
+        assert!(inc.recalculate_severity(Utc::now()).is_none());
+        inc.add_techniques(&["T1547"]);
+        let change = inc.recalculate_severity(Utc::now()).unwrap();
+        assert_eq!((change.from, change.to), (Severity::Low, Severity::High));
+        assert_eq!(change.reason, "3 kill-chain stages");
+    }
+
+    #[test]
+    fn test_severity_never_lowered() {
+        let mut inc = Incident::new(record(ThreatClass::Malicious, 0.95), None);
+        assert_eq!(inc.severity, Severity::Critical);
+
+        assert!(detect(&mut inc, "Low signal", Severity::Low).is_none());
+        assert!(inc.raise_severity(Severity::Medium, "manual", Utc::now()).is_none());
+        inc.escalate(Severity::Low);
+        assert_eq!(inc.severity, Severity::Critical);
+        assert!(inc.severity_history.is_empty());
+    }
+
+    #[test]
+    fn test_severity_history_recorded() {
+        let mut inc = Incident::new(record(ThreatClass::Suspicious, 0.5), None);
+        let at = Utc::now();
+
+        let change = inc.raise_severity(Severity::High, "ransomware activity", at).unwrap();
+        assert_eq!(inc.severity_history.len(), 1);
+        let entry = &inc.severity_history[0];
+        assert_eq!(entry.incident_id, inc.incident_id);
+        assert_eq!((entry.from.clone(), entry.to.clone()), (Severity::Low, Severity::High));
+        assert_eq!(entry.reason, "ransomware activity");
+        assert_eq!(entry.at, at);
+        assert_eq!(change.to, Severity::High);
+
+        inc.escalate(Severity::Critical);
+        assert_eq!(inc.severity_history.len(), 2);
+        assert_eq!(inc.severity_history[1].from, Severity::High);
+    }
+}
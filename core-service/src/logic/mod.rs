@@ -34,6 +34,11 @@
 //! - `never_learn.rs` - Never-learn blacklist
 //! - `rules.rs` - Behavioral rules engine
 //!
+//! ### Network Collection (`network/`)
+//! - `connections.rs` - Per-process TCP connection events
+//! - `dns.rs` - DNS client cache (IP → domain)
+//! - `url_reputation.rs` - Browser domain reputation vs threat feed
+//!
 //! ### External Intelligence (`external_intel/`) - Phase 4
 //! - `virustotal.rs` - VirusTotal API integration
 //! - `threat_feed.rs` - Cloud threat feed sync
@@ -47,6 +52,7 @@ pub mod status;
 pub mod guard;
 pub mod ai_bridge;
 pub mod action_guard;
+pub mod whitelist;
 pub mod events;
 
 // Threat & Policy (EDR pipeline) - Modular
@@ -72,6 +78,9 @@ pub mod process_intel;
 // Behavioral Signatures (Phase 3)
 pub mod behavioral_sigs;
 
+// Network Collection (per-process connections)
+pub mod network;
+
 // External Intelligence (Phase 4)
 pub mod external_intel;
 
@@ -372,11 +372,16 @@ pub fn predict_fallback(sequence: &[[f32; FEATURE_COUNT]]) -> PredictionResult {
 
 /// Auto predict: ONNX if loaded, fallback otherwise
 pub fn predict(sequence: &[[f32; FEATURE_COUNT]]) -> PredictionResult {
-    match predict_onnx(sequence) {
+    let mut span = crate::logic::telemetry::otel::span("ai.inference");
+    let result = match predict_onnx(sequence) {
         Ok(result) => result,
         Err(e) => {
             log::debug!("ONNX failed ({}), using fallback", e);
             predict_fallback(sequence)
         }
-    }
+    };
+    crate::logic::telemetry::metrics::observe_inference(result.inference_time_us);
+    span.set_attr("method", result.method.clone());
+    span.set_attr("score", result.score);
+    result
 }
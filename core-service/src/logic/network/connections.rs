@@ -0,0 +1,339 @@
+//! Connection Collector - Poll TCP table theo PID
+//!
+//! - Windows: `GetExtendedTcpTable(TCP_TABLE_OWNER_PID_ALL)` mỗi `POLL_INTERVAL_MS`
+//! - Diff với snapshot trước → `ConnectionEvent` cho connection mới (bỏ LISTEN, PID 0)
+//! - Ring buffer `MAX_EVENTS` với sequence number: mỗi consumer giữ cursor riêng
+//!   và đọc bằng `events_since(seq)`
+//!
+//! Connection sống ngắn hơn 1 poll có thể bị bỏ sót → detector nên dùng ngưỡng burst
+//! thay vì đếm chính xác.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const POLL_INTERVAL_MS: u64 = 1_000;
+const MAX_EVENTS: usize = 20_000;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionEvent {
+    pub seq: u64,
+    pub pid: u32,
+    pub process_name: String,
+    pub protocol: String,
+    pub local_ip: IpAddr,
+    pub local_port: u16,
+    pub remote_ip: IpAddr,
+    pub remote_port: u16,
+    pub state: String,
+    /// Unix ms
+    pub timestamp: i64,
+}
+
+impl ConnectionEvent {
+    /// Remote không phải loopback / unspecified
+    pub fn is_remote(&self) -> bool {
+        !self.remote_ip.is_loopback() && !self.remote_ip.is_unspecified()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConnectionStats {
+    pub running: bool,
+    pub polls: u64,
+    pub events: u64,
+    pub active_connections: usize,
+}
+
+/// 1 dòng của TCP table
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TcpRow {
+    pub pid: u32,
+    pub local_ip: IpAddr,
+    pub local_port: u16,
+    pub remote_ip: IpAddr,
+    pub remote_port: u16,
+    pub state: u32,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static EVENTS: Lazy<RwLock<VecDeque<ConnectionEvent>>> = Lazy::new(|| RwLock::new(VecDeque::new()));
+/// Connection đang mở (key = row không tính state)
+static ACTIVE: Lazy<Mutex<HashMap<(u32, IpAddr, u16, IpAddr, u16), ConnectionEvent>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static PROCESS_NAMES: Lazy<Mutex<HashMap<u32, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static STATS: Lazy<Mutex<ConnectionStats>> = Lazy::new(|| Mutex::new(ConnectionStats::default()));
+
+static SEQ: AtomicU64 = AtomicU64::new(0);
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Start poll thread (idempotent)
+pub fn start() {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    STATS.lock().running = true;
+
+    thread::spawn(|| {
+        log::info!("Network connection collector started");
+        loop {
+            poll();
+            thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+        }
+    });
+}
+
+/// Event có seq > `seq` (cũ nhất trước)
+pub fn events_since(seq: u64) -> Vec<ConnectionEvent> {
+    let events = EVENTS.read();
+    let start = events.partition_point(|e| e.seq <= seq);
+    events.range(start..).cloned().collect()
+}
+
+/// Seq của event mới nhất (cursor ban đầu cho consumer)
+pub fn latest_seq() -> u64 {
+    SEQ.load(Ordering::SeqCst)
+}
+
+/// Connection mới của 1 PID trong `window_secs` gần nhất
+pub fn recent_for_pid(pid: u32, window_secs: i64) -> Vec<ConnectionEvent> {
+    let cutoff = Utc::now().timestamp_millis() - window_secs * 1000;
+    EVENTS.read().iter()
+        .filter(|e| e.pid == pid && e.timestamp >= cutoff)
+        .cloned()
+        .collect()
+}
+
+/// Connection đang mở
+pub fn active_connections() -> Vec<ConnectionEvent> {
+    ACTIVE.lock().values().cloned().collect()
+}
+
+pub fn get_stats() -> ConnectionStats {
+    STATS.lock().clone()
+}
+
+// ============================================================================
+// POLLING
+// ============================================================================
+
+fn poll() {
+    let rows = match platform::tcp_table() {
+        Some(rows) => rows,
+        None => return,
+    };
+    let now = Utc::now().timestamp_millis();
+    let new_events = diff(&rows, now);
+
+    if !new_events.is_empty() {
+        let mut events = EVENTS.write();
+        events.extend(new_events.iter().cloned());
+        while events.len() > MAX_EVENTS {
+            events.pop_front();
+        }
+    }
+
+    let mut stats = STATS.lock();
+    stats.polls += 1;
+    stats.events += new_events.len() as u64;
+    stats.active_connections = ACTIVE.lock().len();
+}
+
+/// Cập nhật ACTIVE theo snapshot, trả về event cho connection mới
+fn diff(rows: &[TcpRow], now: i64) -> Vec<ConnectionEvent> {
+    let mut active = ACTIVE.lock();
+    let mut seen = HashSet::new();
+    let mut new_events = Vec::new();
+
+    for row in rows {
+        if row.pid == 0 || row.state == platform::STATE_LISTEN {
+            continue;
+        }
+        let key = (row.pid, row.local_ip, row.local_port, row.remote_ip, row.remote_port);
+        seen.insert(key);
+
+        if let Some(existing) = active.get_mut(&key) {
+            existing.state = state_name(row.state).to_string();
+            continue;
+        }
+
+        let event = ConnectionEvent {
+            seq: SEQ.fetch_add(1, Ordering::SeqCst) + 1,
+            pid: row.pid,
+            process_name: process_name(row.pid),
+            protocol: "tcp".to_string(),
+            local_ip: row.local_ip,
+            local_port: row.local_port,
+            remote_ip: row.remote_ip,
+            remote_port: row.remote_port,
+            state: state_name(row.state).to_string(),
+            timestamp: now,
+        };
+        active.insert(key, event.clone());
+        new_events.push(event);
+    }
+
+    active.retain(|key, _| seen.contains(key));
+    let live: HashSet<u32> = active.keys().map(|k| k.0).collect();
+    PROCESS_NAMES.lock().retain(|pid, _| live.contains(pid));
+    new_events
+}
+
+fn process_name(pid: u32) -> String {
+    if let Some(name) = PROCESS_NAMES.lock().get(&pid) {
+        return name.clone();
+    }
+
+    let mut system = sysinfo::System::new();
+    system.refresh_process(sysinfo::Pid::from_u32(pid));
+    let name = system.process(sysinfo::Pid::from_u32(pid))
+        .map(|p| p.name().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    PROCESS_NAMES.lock().insert(pid, name.clone());
+    name
+}
+
+/// MIB_TCP_STATE → tên
+pub fn state_name(state: u32) -> &'static str {
+    match state {
+        1 => "CLOSED",
+        2 => "LISTEN",
+        3 => "SYN_SENT",
+        4 => "SYN_RCVD",
+        5 => "ESTABLISHED",
+        6 => "FIN_WAIT1",
+        7 => "FIN_WAIT2",
+        8 => "CLOSE_WAIT",
+        9 => "CLOSING",
+        10 => "LAST_ACK",
+        11 => "TIME_WAIT",
+        12 => "DELETE_TCB",
+        _ => "UNKNOWN",
+    }
+}
+
+// ============================================================================
+// PLATFORM
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use super::TcpRow;
+    use std::net::{IpAddr, Ipv4Addr};
+    use windows::Win32::NetworkManagement::IpHelper::{
+        GetExtendedTcpTable, MIB_TCPROW_OWNER_PID, MIB_TCPTABLE_OWNER_PID, TCP_TABLE_OWNER_PID_ALL,
+    };
+
+    pub const STATE_LISTEN: u32 = 2;
+    const AF_INET: u32 = 2;
+    const ERROR_INSUFFICIENT_BUFFER: u32 = 122;
+
+    pub fn tcp_table() -> Option<Vec<TcpRow>> {
+        let mut size = 0u32;
+        let mut buffer: Vec<u8> = Vec::new();
+
+        // Table có thể lớn lên giữa 2 lần gọi → retry
+        for _ in 0..3 {
+            let ret = unsafe {
+                GetExtendedTcpTable(
+                    if buffer.is_empty() { None } else { Some(buffer.as_mut_ptr() as *mut _) },
+                    &mut size,
+                    false,
+                    AF_INET,
+                    TCP_TABLE_OWNER_PID_ALL,
+                    0,
+                )
+            };
+            match ret {
+                0 if !buffer.is_empty() => return Some(parse(&buffer)),
+                0 | ERROR_INSUFFICIENT_BUFFER => buffer = vec![0u8; size as usize],
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    fn parse(buffer: &[u8]) -> Vec<TcpRow> {
+        unsafe {
+            let table = &*(buffer.as_ptr() as *const MIB_TCPTABLE_OWNER_PID);
+            let rows: &[MIB_TCPROW_OWNER_PID] =
+                std::slice::from_raw_parts(table.table.as_ptr(), table.dwNumEntries as usize);
+            rows.iter().map(|r| TcpRow {
+                pid: r.dwOwningPid,
+                local_ip: IpAddr::V4(Ipv4Addr::from(r.dwLocalAddr.to_ne_bytes())),
+                local_port: u16::from_be(r.dwLocalPort as u16),
+                remote_ip: IpAddr::V4(Ipv4Addr::from(r.dwRemoteAddr.to_ne_bytes())),
+                remote_port: u16::from_be(r.dwRemotePort as u16),
+                state: r.dwState,
+            }).collect()
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::TcpRow;
+
+    pub const STATE_LISTEN: u32 = 2;
+
+    pub fn tcp_table() -> Option<Vec<TcpRow>> {
+        None
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn row(pid: u32, local_port: u16, remote_port: u16, state: u32) -> TcpRow {
+        TcpRow {
+            pid,
+            local_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5)),
+            local_port,
+            remote_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            remote_port,
+            state,
+        }
+    }
+
+    #[test]
+    fn test_diff_emits_only_new_connections() {
+        let first = vec![row(4242, 50001, 389, 5), row(4242, 0, 0, 2), row(0, 50002, 88, 11)];
+        let events = diff(&first, 1_000);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].remote_port, 389);
+
+        // Connection cũ vẫn mở + 1 connection mới
+        let second = vec![row(4242, 50001, 389, 5), row(4242, 50003, 88, 3)];
+        let events = diff(&second, 2_000);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].remote_port, 88);
+        assert_eq!(events[0].state, "SYN_SENT");
+    }
+}
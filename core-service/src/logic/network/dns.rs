@@ -0,0 +1,203 @@
+//! DNS Collector - IP → domain qua DNS client cache
+//!
+//! Connection table chỉ có IP. Poll DNS client cache (`Get-DnsClientCache`) mỗi
+//! `POLL_INTERVAL_MS` để biết domain nào đã resolve ra IP đó → consumer (URL
+//! reputation, beaconing, ...) đối chiếu domain với threat intel.
+//!
+//! `Entry` là tên được query (kể cả khi trả lời qua CNAME) nên map thẳng
+//! IP → tên user thực sự truy cập.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const POLL_INTERVAL_MS: u64 = 5_000;
+/// Giữ mapping sau khi entry rời cache (TTL ngắn, cache bị flush)
+const RETENTION_MS: i64 = 30 * 60_000;
+const MAX_DOMAINS_PER_IP: usize = 16;
+
+const RECORD_A: u32 = 1;
+const RECORD_AAAA: u32 = 28;
+
+const QUERY_SCRIPT: &str =
+    "ConvertTo-Json -Compress -InputObject @(Get-DnsClientCache -ErrorAction SilentlyContinue | Select-Object Entry,Type,Data)";
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// 1 record trong DNS client cache
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct DnsCacheEntry {
+    pub entry: String,
+    #[serde(rename = "Type")]
+    pub record_type: u32,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsResolution {
+    pub domain: String,
+    pub ip: IpAddr,
+    /// Unix ms
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// ip → resolutions
+static RESOLUTIONS: Lazy<RwLock<HashMap<IpAddr, Vec<DnsResolution>>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Start poll thread (idempotent)
+pub fn start() {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    thread::spawn(|| {
+        log::info!("DNS cache collector started");
+        loop {
+            poll();
+            thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+        }
+    });
+}
+
+/// Domain đã resolve ra `ip` (mới nhất trước)
+pub fn domains_for_ip(ip: &IpAddr) -> Vec<String> {
+    let mut resolutions = RESOLUTIONS.read().get(ip).cloned().unwrap_or_default();
+    resolutions.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+    resolutions.into_iter().map(|r| r.domain).collect()
+}
+
+/// Resolution thấy lần đầu sau `since_ms`
+pub fn resolutions_since(since_ms: i64) -> Vec<DnsResolution> {
+    RESOLUTIONS.read().values()
+        .flatten()
+        .filter(|r| r.first_seen > since_ms)
+        .cloned()
+        .collect()
+}
+
+// ============================================================================
+// POLLING
+// ============================================================================
+
+fn poll() {
+    let Some(entries) = platform::query_cache() else { return };
+    let now = Utc::now().timestamp_millis();
+    record(&entries, now);
+}
+
+fn record(entries: &[DnsCacheEntry], now: i64) {
+    let mut resolutions = RESOLUTIONS.write();
+    for entry in entries {
+        if entry.record_type != RECORD_A && entry.record_type != RECORD_AAAA {
+            continue;
+        }
+        let Ok(ip) = entry.data.trim().parse::<IpAddr>() else { continue };
+        let domain = entry.entry.trim().trim_end_matches('.').to_lowercase();
+        if domain.is_empty() {
+            continue;
+        }
+
+        let list = resolutions.entry(ip).or_default();
+        match list.iter_mut().find(|r| r.domain == domain) {
+            Some(existing) => existing.last_seen = now,
+            None => {
+                list.push(DnsResolution { domain, ip, first_seen: now, last_seen: now });
+                if list.len() > MAX_DOMAINS_PER_IP {
+                    list.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+                    list.truncate(MAX_DOMAINS_PER_IP);
+                }
+            }
+        }
+    }
+
+    for list in resolutions.values_mut() {
+        list.retain(|r| now - r.last_seen < RETENTION_MS);
+    }
+    resolutions.retain(|_, list| !list.is_empty());
+}
+
+/// Parse output JSON của QUERY_SCRIPT
+pub fn parse_cache(json: &str) -> Vec<DnsCacheEntry> {
+    let json = json.trim();
+    if json.is_empty() {
+        return Vec::new();
+    }
+    serde_json::from_str(json).unwrap_or_default()
+}
+
+// ============================================================================
+// PLATFORM
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+
+    pub fn query_cache() -> Option<Vec<DnsCacheEntry>> {
+        let output = Command::new("powershell")
+            .args(["-NoProfile", "-Command", QUERY_SCRIPT])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(parse_cache(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::*;
+
+    pub fn query_cache() -> Option<Vec<DnsCacheEntry>> {
+        None
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_record_cache() {
+        let json = r#"[{"Entry":"login.evil-bank.example","Type":1,"Data":"203.0.113.7"},
+            {"Entry":"login.evil-bank.example","Type":5,"Data":"cdn.example.net"},
+            {"Entry":"Mail.Example.COM.","Type":28,"Data":"2001:db8::1"}]"#;
+        let entries = parse_cache(json);
+        assert_eq!(entries.len(), 3);
+
+        record(&entries, 1_000);
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        assert_eq!(domains_for_ip(&ip), vec!["login.evil-bank.example".to_string()]);
+        let ip6: IpAddr = "2001:db8::1".parse().unwrap();
+        assert_eq!(domains_for_ip(&ip6), vec!["mail.example.com".to_string()]);
+    }
+}
@@ -0,0 +1,509 @@
+//! GeoIP - Country / ASN của remote IP (MaxMind DB local)
+//!
+//! Mục đích: Incident network (beaconing, intel match) kèm quốc gia + ASN của đích,
+//! và tùy chọn alert khi process kết nối tới quốc gia rủi ro cao (policy).
+//!
+//! Database: file `.mmdb` định dạng MaxMind (GeoLite2 / GeoIP2 Country hoặc City + ASN)
+//! import qua `import_database`, lưu ở `OneShield/geoip/`. Không có database → lookup
+//! trả None, detector không chạy. Không gọi dịch vụ online.
+//!
+//! Config local (`geoip.json`), section `geoip` của cloud policy override từng key.
+
+use std::collections::HashMap;
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use maxminddb::{geoip2, Reader};
+use serde::{Deserialize, Serialize};
+
+use crate::logic::incident::{self, DetectionContext, Severity};
+use super::{connections, ConnectionEvent};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CONFIG_FILE: &str = "geoip.json";
+const DB_DIR: &str = "geoip";
+const COUNTRY_DB_FILE: &str = "country.mmdb";
+const ASN_DB_FILE: &str = "asn.mmdb";
+const MAX_CACHE_ENTRIES: usize = 10_000;
+const MAX_ALERTS: usize = 500;
+/// Không báo lại cùng (pid, quốc gia) trong khoảng này
+const REPORT_COOLDOWN_MS: i64 = 30 * 60_000;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeoIpConfig {
+    /// Enrichment country / ASN cho incident network
+    pub enabled: bool,
+    /// ISO 3166-1 alpha-2 ("KP", "IR", ...)
+    pub high_risk_countries: Vec<String>,
+    /// Tạo incident khi có connection tới `high_risk_countries`
+    pub alert_on_high_risk: bool,
+}
+
+impl Default for GeoIpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            high_risk_countries: Vec::new(),
+            alert_on_high_risk: false,
+        }
+    }
+}
+
+impl GeoIpConfig {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(code) = self.high_risk_countries.iter()
+            .find(|c| c.len() != 2 || !c.chars().all(|ch| ch.is_ascii_alphabetic()))
+        {
+            return Err(format!("Invalid country code '{}' (expected ISO 3166-1 alpha-2)", code));
+        }
+        Ok(())
+    }
+
+    fn normalized(mut self) -> Self {
+        self.high_risk_countries = self.high_risk_countries.iter()
+            .map(|c| c.trim().to_uppercase())
+            .collect();
+        self.high_risk_countries.sort();
+        self.high_risk_countries.dedup();
+        self
+    }
+
+    fn is_high_risk(&self, country_code: &str) -> bool {
+        self.high_risk_countries.iter().any(|c| c == country_code)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeoInfo {
+    pub country_code: Option<String>,
+    pub country_name: Option<String>,
+    pub asn: Option<u32>,
+    pub as_org: Option<String>,
+}
+
+impl GeoInfo {
+    /// "RU (Russia), AS12345 Example Org"
+    pub fn describe(&self) -> String {
+        let country = match (&self.country_code, &self.country_name) {
+            (Some(code), Some(name)) => Some(format!("{} ({})", code, name)),
+            (Some(code), None) => Some(code.clone()),
+            _ => None,
+        };
+        let asn = self.asn.map(|n| match &self.as_org {
+            Some(org) => format!("AS{} {}", n, org),
+            None => format!("AS{}", n),
+        });
+        [country, asn].into_iter().flatten().collect::<Vec<_>>().join(", ")
+    }
+
+    /// Tag incident: `geo:RU`, `asn:AS12345`
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = Vec::new();
+        if let Some(code) = &self.country_code {
+            tags.push(format!("geo:{}", code));
+        }
+        if let Some(asn) = self.asn {
+            tags.push(format!("asn:AS{}", asn));
+        }
+        tags
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeoAlert {
+    pub country_code: String,
+    pub geo: GeoInfo,
+    pub remote_ip: String,
+    pub remote_port: u16,
+    pub pid: u32,
+    pub process_name: String,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GeoDatabaseInfo {
+    /// `database_type` trong metadata ("GeoLite2-Country", "GeoLite2-ASN", ...)
+    pub database_type: String,
+    /// Unix seconds
+    pub build_epoch: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GeoIpStatus {
+    pub config: GeoIpConfig,
+    /// "local" | "cloud"
+    pub config_source: &'static str,
+    pub country_db: Option<GeoDatabaseInfo>,
+    pub asn_db: Option<GeoDatabaseInfo>,
+    pub cache_entries: usize,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+struct ConfigState {
+    local: GeoIpConfig,
+    cloud_override: Option<serde_json::Value>,
+    config: GeoIpConfig,
+}
+
+impl ConfigState {
+    fn recompute_config(&mut self) {
+        self.config = match self.cloud_override.as_ref().and_then(|o| o.as_object()) {
+            Some(overrides) => {
+                let mut merged = serde_json::to_value(&self.local).unwrap_or_default();
+                if let Some(obj) = merged.as_object_mut() {
+                    for (key, value) in overrides {
+                        if obj.contains_key(key) {
+                            obj.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+                match serde_json::from_value::<GeoIpConfig>(merged) {
+                    Ok(config) if config.validate().is_ok() => config.normalized(),
+                    _ => {
+                        log::warn!("Ignoring invalid GeoIP override from cloud policy");
+                        self.local.clone()
+                    }
+                }
+            }
+            None => self.local.clone(),
+        };
+    }
+}
+
+struct Databases {
+    country: Option<Reader<Vec<u8>>>,
+    asn: Option<Reader<Vec<u8>>>,
+}
+
+struct DetectorState {
+    cursor: u64,
+    /// (pid, country) → lần báo cuối (ms)
+    reported: HashMap<(u32, String), i64>,
+}
+
+static CONFIG: Lazy<RwLock<ConfigState>> = Lazy::new(|| {
+    let local = load_config();
+    RwLock::new(ConfigState { config: local.clone(), local, cloud_override: None })
+});
+static DATABASES: Lazy<RwLock<Databases>> = Lazy::new(|| RwLock::new(Databases {
+    country: open_database(&db_path(COUNTRY_DB_FILE)),
+    asn: open_database(&db_path(ASN_DB_FILE)),
+}));
+static CACHE: Lazy<Mutex<HashMap<IpAddr, Option<GeoInfo>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static DETECTOR: Lazy<Mutex<DetectorState>> = Lazy::new(|| Mutex::new(DetectorState {
+    cursor: connections::latest_seq(),
+    reported: HashMap::new(),
+}));
+static ALERTS: Lazy<Mutex<Vec<GeoAlert>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Country / ASN của IP. None nếu tắt, chưa có database hoặc IP không có trong database.
+pub fn lookup(ip: IpAddr) -> Option<GeoInfo> {
+    if !CONFIG.read().config.enabled {
+        return None;
+    }
+    if let Some(cached) = CACHE.lock().get(&ip) {
+        return cached.clone();
+    }
+
+    let info = lookup_uncached(ip);
+    let mut cache = CACHE.lock();
+    if cache.len() >= MAX_CACHE_ENTRIES {
+        cache.clear();
+    }
+    cache.insert(ip, info.clone());
+    info
+}
+
+/// Connection mới tới quốc gia rủi ro cao → incident. Trả về alert mới.
+pub fn check() -> Vec<GeoAlert> {
+    let config = CONFIG.read().config.clone();
+    let events = {
+        let mut detector = DETECTOR.lock();
+        let events = connections::events_since(detector.cursor);
+        if let Some(last) = events.last() {
+            detector.cursor = last.seq;
+        }
+        events
+    };
+    if !config.enabled || !config.alert_on_high_risk || config.high_risk_countries.is_empty() {
+        return Vec::new();
+    }
+    let now = Utc::now().timestamp_millis();
+
+    let mut alerts: Vec<GeoAlert> = events.iter()
+        .filter(|e| e.is_remote())
+        .filter_map(|e| {
+            let geo = lookup(e.remote_ip)?;
+            let code = geo.country_code.clone().filter(|c| config.is_high_risk(c))?;
+            Some(build_alert(e, code, geo))
+        })
+        .collect();
+
+    {
+        let mut detector = DETECTOR.lock();
+        detector.reported.retain(|_, at| now - *at < REPORT_COOLDOWN_MS);
+        alerts.retain(|a| detector.reported.insert((a.pid, a.country_code.clone()), now).is_none());
+    }
+
+    for alert in &alerts {
+        raise_incident(alert);
+    }
+    if !alerts.is_empty() {
+        let mut history = ALERTS.lock();
+        history.extend(alerts.iter().cloned());
+        let overflow = history.len().saturating_sub(MAX_ALERTS);
+        history.drain(..overflow);
+    }
+    alerts
+}
+
+pub fn get_recent_alerts(limit: usize) -> Vec<GeoAlert> {
+    ALERTS.lock().iter().rev().take(limit).cloned().collect()
+}
+
+pub fn get_config() -> GeoIpConfig {
+    CONFIG.read().config.clone()
+}
+
+/// Lưu config local (cloud override vẫn được áp dụng lên trên)
+pub fn set_config(config: GeoIpConfig) -> Result<(), String> {
+    config.validate()?;
+    let mut state = CONFIG.write();
+    state.local = config.normalized();
+    save_config(&state.local);
+    state.recompute_config();
+    drop(state);
+    CACHE.lock().clear();
+    Ok(())
+}
+
+/// Áp dụng section `geoip` của cloud policy
+pub fn apply_cloud_config(overrides: Option<&serde_json::Value>) {
+    let mut state = CONFIG.write();
+    state.cloud_override = overrides.filter(|v| v.is_object()).cloned();
+    state.recompute_config();
+}
+
+/// Import database `.mmdb` (Country / City hoặc ASN, nhận diện qua metadata)
+pub fn import_database(path: &Path) -> Result<GeoDatabaseInfo, String> {
+    let reader = Reader::open_readfile(path)
+        .map_err(|e| format!("Invalid MaxMind database {}: {}", path.display(), e))?;
+    let info = database_info(&reader);
+    let file = database_file(&info.database_type)
+        .ok_or_else(|| format!("Unsupported GeoIP database type '{}'", info.database_type))?;
+
+    let target = db_path(file);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::copy(path, &target).map_err(|e| format!("Failed to store GeoIP database: {}", e))?;
+
+    {
+        let mut dbs = DATABASES.write();
+        if file == ASN_DB_FILE {
+            dbs.asn = Some(reader);
+        } else {
+            dbs.country = Some(reader);
+        }
+    }
+    CACHE.lock().clear();
+    log::info!("GeoIP database imported: {} (build {})", info.database_type, info.build_epoch);
+    Ok(info)
+}
+
+pub fn get_status() -> GeoIpStatus {
+    let state = CONFIG.read();
+    let dbs = DATABASES.read();
+    GeoIpStatus {
+        config: state.config.clone(),
+        config_source: if state.config != state.local { "cloud" } else { "local" },
+        country_db: dbs.country.as_ref().map(database_info),
+        asn_db: dbs.asn.as_ref().map(database_info),
+        cache_entries: CACHE.lock().len(),
+    }
+}
+
+// ============================================================================
+// LOOKUP
+// ============================================================================
+
+fn lookup_uncached(ip: IpAddr) -> Option<GeoInfo> {
+    let dbs = DATABASES.read();
+    let mut info = GeoInfo { country_code: None, country_name: None, asn: None, as_org: None };
+
+    if let Some(country) = dbs.country.as_ref()
+        .and_then(|r| r.lookup::<geoip2::Country>(ip).ok())
+        .and_then(|c| c.country)
+    {
+        info.country_code = country.iso_code.map(|c| c.to_string());
+        info.country_name = country.names
+            .and_then(|names| names.get("en").map(|n| n.to_string()));
+    }
+    if let Some(asn) = dbs.asn.as_ref().and_then(|r| r.lookup::<geoip2::Asn>(ip).ok()) {
+        info.asn = asn.autonomous_system_number;
+        info.as_org = asn.autonomous_system_organization.map(|o| o.to_string());
+    }
+
+    if info.country_code.is_none() && info.asn.is_none() {
+        None
+    } else {
+        Some(info)
+    }
+}
+
+fn database_info(reader: &Reader<Vec<u8>>) -> GeoDatabaseInfo {
+    GeoDatabaseInfo {
+        database_type: reader.metadata.database_type.clone(),
+        build_epoch: reader.metadata.build_epoch,
+    }
+}
+
+/// `database_type` → file lưu: City cũng có field country
+fn database_file(database_type: &str) -> Option<&'static str> {
+    let lower = database_type.to_lowercase();
+    if lower.contains("asn") {
+        Some(ASN_DB_FILE)
+    } else if lower.contains("country") || lower.contains("city") {
+        Some(COUNTRY_DB_FILE)
+    } else {
+        None
+    }
+}
+
+fn open_database(path: &Path) -> Option<Reader<Vec<u8>>> {
+    if !path.exists() {
+        return None;
+    }
+    match Reader::open_readfile(path) {
+        Ok(reader) => Some(reader),
+        Err(e) => {
+            log::warn!("Failed to open GeoIP database {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+// ============================================================================
+// INCIDENT
+// ============================================================================
+
+fn build_alert(event: &ConnectionEvent, country_code: String, geo: GeoInfo) -> GeoAlert {
+    GeoAlert {
+        country_code,
+        geo,
+        remote_ip: event.remote_ip.to_string(),
+        remote_port: event.remote_port,
+        pid: event.pid,
+        process_name: event.process_name.clone(),
+        timestamp: Utc::now().timestamp(),
+    }
+}
+
+fn raise_incident(alert: &GeoAlert) {
+    let mut tags = vec!["HIGH_RISK_COUNTRY".to_string()];
+    tags.extend(alert.geo.tags());
+
+    incident::raise_process_detection(
+        &format!("Connection to high-risk country: {} → {}", alert.process_name, alert.country_code),
+        Severity::Medium,
+        &tags,
+        &[],
+        &format!(
+            "{} (PID {}) connected to {}:{} — {}",
+            alert.process_name, alert.pid, alert.remote_ip, alert.remote_port, alert.geo.describe()
+        ),
+        DetectionContext {
+            pid: Some(alert.pid),
+            rule_matches: vec![format!("geoip:high_risk_country:{}", alert.country_code)],
+        },
+    );
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+}
+
+fn db_path(file: &str) -> PathBuf {
+    data_dir().join(DB_DIR).join(file)
+}
+
+fn load_config() -> GeoIpConfig {
+    fs::read_to_string(data_dir().join(CONFIG_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str::<GeoIpConfig>(&c).ok())
+        .filter(|c| c.validate().is_ok())
+        .map(GeoIpConfig::normalized)
+        .unwrap_or_default()
+}
+
+fn save_config(config: &GeoIpConfig) {
+    let path = data_dir().join(CONFIG_FILE);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(config) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_and_describe() {
+        let config = GeoIpConfig {
+            high_risk_countries: vec!["kp".to_string(), " IR".to_string(), "KP".to_string()],
+            alert_on_high_risk: true,
+            ..Default::default()
+        }.normalized();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.high_risk_countries, vec!["IR".to_string(), "KP".to_string()]);
+        assert!(config.is_high_risk("KP"));
+        assert!(!config.is_high_risk("US"));
+
+        let bad = GeoIpConfig { high_risk_countries: vec!["Russia".to_string()], ..Default::default() };
+        assert!(bad.validate().is_err());
+
+        let geo = GeoInfo {
+            country_code: Some("KP".to_string()),
+            country_name: Some("North Korea".to_string()),
+            asn: Some(131279),
+            as_org: Some("Star JV".to_string()),
+        };
+        assert_eq!(geo.describe(), "KP (North Korea), AS131279 Star JV");
+        assert_eq!(geo.tags(), vec!["geo:KP".to_string(), "asn:AS131279".to_string()]);
+
+        assert_eq!(database_file("GeoLite2-ASN"), Some(ASN_DB_FILE));
+        assert_eq!(database_file("GeoIP2-City"), Some(COUNTRY_DB_FILE));
+        assert_eq!(database_file("GeoIP2-Anonymous-IP"), None);
+    }
+}
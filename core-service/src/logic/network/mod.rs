@@ -0,0 +1,31 @@
+//! Network Collection Module - Per-process connection events
+//!
+//! Mục đích: Nguồn dữ liệu network chung cho các detector (kerberoasting,
+//! beaconing, URL reputation, sequence rules, ...)
+//!
+//! # Components
+//! - `connections.rs`: Poll TCP table (owner PID) → event cho mỗi connection mới
+//! - `dns.rs`: DNS client cache → map IP → domain
+//! - `url_reputation.rs`: Domain browser truy cập vs threat feed (phishing / malware site)
+//! - `geoip.rs`: Country / ASN của remote IP (MaxMind DB local), alert quốc gia rủi ro cao
+
+// Allow unused for now - consumers được nối dần
+#![allow(unused)]
+
+pub mod connections;
+pub mod dns;
+pub mod url_reputation;
+pub mod geoip;
+
+pub use connections::{
+    ConnectionEvent, ConnectionStats, events_since, latest_seq, recent_for_pid, active_connections,
+    get_stats,
+};
+pub use url_reputation::{UrlReputationAlert, UrlReputationConfig};
+pub use geoip::{GeoInfo, GeoIpConfig, GeoAlert};
+
+/// Start tất cả collector (idempotent)
+pub fn start_collectors() {
+    connections::start();
+    dns::start();
+}
@@ -0,0 +1,355 @@
+//! Browser URL Reputation - Domain của connection browser vs threat feed
+//!
+//! Mục đích: Gần real-time phát hiện browser truy cập site phishing / malware
+//!
+//! 1. Đọc connection mới của process browser từ `connections`
+//! 2. Remote IP → domain qua `dns` (DNS client cache)
+//! 3. Domain (kể cả parent) / IP đối chiếu `external_intel::threat_feed`
+//! 4. Alert kèm process sở hữu socket (network service / tab process) + browser gốc
+//! 5. Tùy chọn (`block_on_match`): chặn outbound qua Action Guard - theo hostname
+//!    (hosts file sinkhole) khi biết domain, chỉ theo IP khi không resolve được
+//!
+//! Chrome/Edge mở socket từ network service process chứ không phải renderer, nên
+//! "owning process" là process browser giữ connection - command line của nó
+//! (`--type=...`) được ghi lại để analyst phân biệt.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::action_guard::{self, ActionType};
+use crate::logic::external_intel::threat_feed;
+use crate::logic::incident::{self, Severity};
+use crate::logic::process_intel::tree;
+use crate::logic::response::{browser_child, network as response_network};
+use super::{connections, dns, ConnectionEvent};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CONFIG_FILE: &str = "url_reputation.json";
+const MAX_ALERTS: usize = 500;
+/// Không báo lại cùng (browser, domain) trong khoảng này
+const REPORT_COOLDOWN_MS: i64 = 30 * 60_000;
+/// Connection mới tạo → DNS cache có thể chưa kịp poll, giữ lại chờ resolve
+const PENDING_RESOLVE_MS: i64 = 15_000;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UrlReputationConfig {
+    pub enabled: bool,
+    /// Chặn outbound tới site độc hại (hostname, hoặc IP nếu không biết domain)
+    pub block_on_match: bool,
+}
+
+impl Default for UrlReputationConfig {
+    fn default() -> Self {
+        Self { enabled: true, block_on_match: false }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlReputationAlert {
+    /// "phishing" | "malware"
+    pub category: String,
+    /// Domain khớp intel (None nếu chỉ IP khớp)
+    pub domain: Option<String>,
+    pub remote_ip: String,
+    pub remote_port: u16,
+    /// Process giữ connection
+    pub pid: u32,
+    pub process_name: String,
+    /// Loại process browser (`--type=...`), "browser" nếu là process chính
+    pub process_type: String,
+    /// Process browser gốc (top-level)
+    pub browser_pid: u32,
+    pub browser_name: String,
+    pub intel_source: Option<String>,
+    pub blocked: bool,
+    pub timestamp: i64,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+struct ReputationState {
+    cursor: u64,
+    /// Connection browser chưa resolve được domain
+    pending: Vec<ConnectionEvent>,
+    /// (pid, domain/ip) → lần báo cuối (ms)
+    reported: HashMap<(u32, String), i64>,
+}
+
+static CONFIG: Lazy<RwLock<UrlReputationConfig>> = Lazy::new(|| RwLock::new(load_config()));
+static STATE: Lazy<Mutex<ReputationState>> = Lazy::new(|| Mutex::new(ReputationState {
+    cursor: connections::latest_seq(),
+    pending: Vec::new(),
+    reported: HashMap::new(),
+}));
+static ALERTS: Lazy<Mutex<Vec<UrlReputationAlert>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Kiểm tra connection browser mới. Trả về alert mới.
+pub fn check() -> Vec<UrlReputationAlert> {
+    let config = CONFIG.read().clone();
+    if !config.enabled {
+        return Vec::new();
+    }
+    let now = Utc::now().timestamp_millis();
+
+    let hits = {
+        let mut state = STATE.lock();
+        let events = connections::events_since(state.cursor);
+        if let Some(last) = events.last() {
+            state.cursor = last.seq;
+        }
+        let mut queue = std::mem::take(&mut state.pending);
+        queue.extend(events.into_iter().filter(|e| e.is_remote() && browser_child::is_browser(&e.process_name)));
+
+        let mut hits = Vec::new();
+        for event in queue {
+            let domains = dns::domains_for_ip(&event.remote_ip);
+            match match_intel(&event, &domains) {
+                Some((domain, category, source)) => hits.push((event, domain, category, source)),
+                None if domains.is_empty() && now - event.timestamp < PENDING_RESOLVE_MS => state.pending.push(event),
+                None => {}
+            }
+        }
+
+        state.reported.retain(|_, at| now - *at < REPORT_COOLDOWN_MS);
+        hits.retain(|(event, domain, _, _)| {
+            let key = (event.pid, domain.clone().unwrap_or_else(|| event.remote_ip.to_string()));
+            state.reported.insert(key, now).is_none()
+        });
+        hits
+    };
+
+    let mut alerts: Vec<UrlReputationAlert> = hits.into_iter()
+        .map(|(event, domain, category, source)| build_alert(&event, domain, category, source))
+        .collect();
+
+    for alert in alerts.iter_mut() {
+        if config.block_on_match {
+            alert.blocked = block(alert);
+        }
+        raise_incident(alert);
+    }
+    if !alerts.is_empty() {
+        let mut history = ALERTS.lock();
+        history.extend(alerts.iter().cloned());
+        let overflow = history.len().saturating_sub(MAX_ALERTS);
+        history.drain(..overflow);
+    }
+    alerts
+}
+
+pub fn get_recent_alerts(limit: usize) -> Vec<UrlReputationAlert> {
+    ALERTS.lock().iter().rev().take(limit).cloned().collect()
+}
+
+pub fn get_config() -> UrlReputationConfig {
+    CONFIG.read().clone()
+}
+
+pub fn set_config(config: UrlReputationConfig) {
+    *CONFIG.write() = config;
+    save_config();
+}
+
+// ============================================================================
+// MATCHING
+// ============================================================================
+
+/// Domain / IP của connection có trong threat feed không.
+/// Trả về (domain khớp, category, intel source).
+fn match_intel(event: &ConnectionEvent, domains: &[String]) -> Option<(Option<String>, String, Option<String>)> {
+    for domain in domains {
+        if threat_feed::is_malicious_domain(domain) {
+            let indicator = domain_candidates(domain).into_iter().find_map(|d| threat_feed::find_indicator(&d));
+            let tags = indicator.as_ref().map(|i| i.tags.clone()).unwrap_or_default();
+            let source = indicator.map(|i| i.source);
+            return Some((Some(domain.clone()), categorize(&tags), source));
+        }
+    }
+
+    let ip = event.remote_ip.to_string();
+    if threat_feed::is_malicious_ip(&ip) {
+        let indicator = threat_feed::find_indicator(&ip);
+        let tags = indicator.as_ref().map(|i| i.tags.clone()).unwrap_or_default();
+        return Some((domains.first().cloned(), categorize(&tags), indicator.map(|i| i.source)));
+    }
+    None
+}
+
+/// Domain + các parent domain (a.b.example.com → b.example.com → example.com)
+pub fn domain_candidates(domain: &str) -> Vec<String> {
+    let parts: Vec<&str> = domain.split('.').collect();
+    (0..parts.len().saturating_sub(1)).map(|i| parts[i..].join(".")).collect()
+}
+
+/// Tag intel → category. Feed text (URLhaus) không có tag → malware distribution.
+pub fn categorize(tags: &[String]) -> String {
+    if tags.iter().any(|t| t.to_lowercase().contains("phish")) {
+        "phishing".to_string()
+    } else {
+        "malware".to_string()
+    }
+}
+
+/// `--type=renderer` → "renderer"; không có → "browser"
+pub fn browser_process_type(cmdline: &str) -> String {
+    cmdline.split_whitespace()
+        .find_map(|arg| arg.trim_matches('"').strip_prefix("--type="))
+        .map(|t| t.to_string())
+        .unwrap_or_else(|| "browser".to_string())
+}
+
+fn build_alert(
+    event: &ConnectionEvent,
+    domain: Option<String>,
+    category: String,
+    intel_source: Option<String>,
+) -> UrlReputationAlert {
+    let chain = tree::get_ancestry_chain(event.pid);
+    let process_type = chain.first()
+        .and_then(|p| p.cmdline.as_deref())
+        .map(browser_process_type)
+        .unwrap_or_else(|| "browser".to_string());
+    // Browser gốc = tổ tiên browser xa nhất liên tiếp
+    let root = chain.iter()
+        .take_while(|p| browser_child::is_browser(&p.name))
+        .last();
+    let (browser_pid, browser_name) = root
+        .map(|p| (p.pid, p.name.clone()))
+        .unwrap_or_else(|| (event.pid, event.process_name.clone()));
+
+    let remote_ip = event.remote_ip.to_string();
+
+    UrlReputationAlert {
+        category,
+        domain,
+        remote_ip,
+        remote_port: event.remote_port,
+        pid: event.pid,
+        process_name: event.process_name.clone(),
+        process_type,
+        browser_pid,
+        browser_name,
+        intel_source,
+        blocked: false,
+        timestamp: Utc::now().timestamp(),
+    }
+}
+
+/// Chặn destination qua Action Guard (whitelist, Safety Config, approval, rate limit).
+/// Trả về true nếu destination đang bị chặn sau khi gọi.
+fn block(alert: &UrlReputationAlert) -> bool {
+    let target = alert.domain.clone().unwrap_or_else(|| alert.remote_ip.clone());
+    let tag = if alert.category == "phishing" { "PHISHING" } else { "MALICIOUS_SITE" };
+    if let Err(e) = action_guard::execute_action(
+        ActionType::BlockDestination,
+        None,
+        &target,
+        1.0,
+        vec![tag.to_string(), "URL_REPUTATION".to_string()],
+        true,
+    ) {
+        log::warn!("Failed to block {}: {}", target, e);
+    }
+    response_network::is_destination_blocked(&target)
+}
+
+fn raise_incident(alert: &UrlReputationAlert) {
+    let target = alert.domain.clone().unwrap_or_else(|| alert.remote_ip.clone());
+    let (title, tag, mitre) = if alert.category == "phishing" {
+        (format!("Phishing site visited: {}", target), "PHISHING", "T1566.002")
+    } else {
+        (format!("Malware site visited: {}", target), "MALICIOUS_SITE", "T1189")
+    };
+
+    let mut description = format!(
+        "{} (PID {}) connected to {}:{} ({}) via PID {} ({} process)",
+        alert.browser_name, alert.browser_pid, alert.remote_ip, alert.remote_port, target,
+        alert.pid, alert.process_type
+    );
+    if let Some(source) = &alert.intel_source {
+        description.push_str(&format!(" — intel source: {}", source));
+    }
+    if alert.blocked {
+        description.push_str(&format!(" — outbound traffic to {} blocked", target));
+    }
+
+    incident::raise_detection(
+        &title,
+        Severity::High,
+        &[tag.to_string(), "URL_REPUTATION".to_string()],
+        &[mitre],
+        &description,
+    );
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn config_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(CONFIG_FILE)
+}
+
+fn load_config() -> UrlReputationConfig {
+    fs::read_to_string(config_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_config() {
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*CONFIG.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_helpers() {
+        assert_eq!(
+            domain_candidates("login.secure.evil.example"),
+            vec!["login.secure.evil.example", "secure.evil.example", "evil.example"]
+        );
+        assert_eq!(categorize(&["Phishing".to_string()]), "phishing");
+        assert_eq!(categorize(&[]), "malware");
+
+        assert_eq!(
+            browser_process_type(r#""C:\Program Files\Google\Chrome\Application\chrome.exe" --type=utility --utility-sub-type=network.mojom.NetworkService"#),
+            "utility"
+        );
+        assert_eq!(browser_process_type(r#""C:\Program Files\Google\Chrome\Application\chrome.exe""#), "browser");
+    }
+}
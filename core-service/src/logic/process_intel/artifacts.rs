@@ -0,0 +1,461 @@
+//! Execution Artifacts - Prefetch / Shimcache enrichment
+//!
+//! Pass enrichment tùy chọn (mặc định tắt) cho binary bị flag: đọc artifact
+//! thực thi của Windows để analyst biết binary chạy lần đầu khi nào, bao nhiêu lần:
+//! - Prefetch (`%SystemRoot%\Prefetch\*.pf`, nén MAM / Xpress Huffman từ Win10):
+//!   run count, 8 lần chạy gần nhất, thời điểm tạo file .pf ≈ lần chạy đầu
+//! - Shimcache (AppCompatCache trong SYSTEM hive): binary từng hiện diện trên máy,
+//!   last-modified time và vị trí trong cache (0 = mới nhất)
+//!
+//! Amcache.hve bị hệ thống lock khi đang chạy nên không đọc ở đây. Shimcache chỉ
+//! được ghi xuống registry lúc shutdown → binary mới có thể chưa có entry.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::UNIX_EPOCH;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Giây giữa 1601-01-01 (FILETIME epoch) và 1970-01-01
+const FILETIME_UNIX_OFFSET_SECS: i64 = 11_644_473_600;
+
+/// Số file .pf tối đa parse cho một tên executable
+const MAX_PREFETCH_FILES: usize = 16;
+
+/// Enrichment tự động cho incident (command on-demand luôn chạy được)
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrefetchRecord {
+    pub prefetch_file: String,
+    pub executable: String,
+    pub format_version: u32,
+    pub run_count: u32,
+    /// Unix seconds, mới nhất trước (tối đa 8)
+    pub last_run_times: Vec<i64>,
+    /// Thời điểm tạo file .pf (≈ lần chạy đầu)
+    pub created_at: Option<i64>,
+    /// Full path của binary có trong danh sách file của .pf
+    pub path_verified: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShimcacheEntry {
+    pub path: String,
+    /// Last-modified time của file lúc được cache (không phải thời điểm chạy)
+    pub last_modified: Option<i64>,
+    /// Vị trí trong cache (0 = mới nhất)
+    pub position: usize,
+}
+
+/// Forensic context gắn vào incident
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionArtifacts {
+    pub path: String,
+    pub prefetch: Vec<PrefetchRecord>,
+    pub shimcache: Option<ShimcacheEntry>,
+    /// Sớm nhất trong các nguồn (Prefetch created / last runs)
+    pub first_execution: Option<i64>,
+    pub last_execution: Option<i64>,
+    /// Tổng run count từ Prefetch
+    pub run_count: u32,
+    pub collected_at: i64,
+}
+
+/// Kết quả parse header SCCA
+#[derive(Debug, Clone, PartialEq)]
+struct ParsedPrefetch {
+    version: u32,
+    executable: String,
+    run_count: u32,
+    last_run_times: Vec<i64>,
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    log::info!("Execution artifact enrichment {}", if enabled { "enabled" } else { "disabled" });
+}
+
+/// Thu thập Prefetch + Shimcache cho binary
+pub fn collect(exe_path: &Path) -> ExecutionArtifacts {
+    let prefetch = find_prefetch(exe_path);
+    let shimcache = platform::read_shimcache()
+        .map(|data| parse_shimcache(&data))
+        .and_then(|entries| find_shimcache_entry(entries, exe_path));
+
+    let run_times = prefetch.iter().flat_map(|p| p.last_run_times.iter().copied());
+    let first_execution = prefetch.iter()
+        .filter_map(|p| p.created_at)
+        .chain(run_times.clone())
+        .min();
+
+    ExecutionArtifacts {
+        path: exe_path.to_string_lossy().to_string(),
+        first_execution,
+        last_execution: run_times.max(),
+        run_count: prefetch.iter().map(|p| p.run_count).sum(),
+        prefetch,
+        shimcache,
+        collected_at: Utc::now().timestamp(),
+    }
+}
+
+// ============================================================================
+// PREFETCH
+// ============================================================================
+
+fn prefetch_dir() -> PathBuf {
+    PathBuf::from(std::env::var("SystemRoot").unwrap_or_else(|_| "C:\\Windows".to_string()))
+        .join("Prefetch")
+}
+
+/// File .pf có tên `<EXE>-<HASH>.pf`; hash phụ thuộc device path nên lọc lại
+/// bằng full path nằm trong danh sách file của .pf
+fn find_prefetch(exe_path: &Path) -> Vec<PrefetchRecord> {
+    let Some(name) = exe_path.file_name().map(|n| n.to_string_lossy().to_uppercase()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}-", name);
+    let needle = device_independent_path(exe_path);
+
+    let Ok(dir) = fs::read_dir(prefetch_dir()) else {
+        return Vec::new();
+    };
+    let mut records: Vec<PrefetchRecord> = dir
+        .flatten()
+        .filter(|e| {
+            let file = e.file_name().to_string_lossy().to_uppercase();
+            file.starts_with(&prefix) && file.ends_with(".PF")
+        })
+        .take(MAX_PREFETCH_FILES)
+        .filter_map(|e| {
+            let data = read_prefetch_file(&e.path())?;
+            let parsed = parse_prefetch(&data)?;
+            Some(PrefetchRecord {
+                prefetch_file: e.file_name().to_string_lossy().to_string(),
+                executable: parsed.executable,
+                format_version: parsed.version,
+                run_count: parsed.run_count,
+                last_run_times: parsed.last_run_times,
+                created_at: e.metadata().ok()
+                    .and_then(|m| m.created().ok())
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs() as i64),
+                path_verified: !needle.is_empty() && contains_utf16(&data, &needle),
+            })
+        })
+        .collect();
+
+    // Có .pf khớp đúng path → bỏ các .pf cùng tên nhưng khác thư mục
+    if records.iter().any(|r| r.path_verified) {
+        records.retain(|r| r.path_verified);
+    }
+    records
+}
+
+fn read_prefetch_file(path: &Path) -> Option<Vec<u8>> {
+    let data = fs::read(path).ok()?;
+    if data.starts_with(b"MAM") {
+        let size = u32_at(&data, 4)? as usize;
+        platform::decompress_mam(data.get(8..)?, size)
+    } else {
+        Some(data)
+    }
+}
+
+/// Parse header + file information của SCCA (XP v17 → Win10/11 v30/31)
+fn parse_prefetch(data: &[u8]) -> Option<ParsedPrefetch> {
+    if data.get(4..8)? != b"SCCA" {
+        return None;
+    }
+    let version = u32_at(data, 0)?;
+    let executable = utf16_until_nul(data.get(0x10..0x10 + 60)?);
+
+    let (times_offset, times_count, count_offset) = match version {
+        17 => (0x78, 1, 0x90),
+        23 => (0x80, 1, 0x98),
+        26 => (0x80, 8, 0xD0),
+        // File information 224 byte (metrics ở 0x130) hoặc 216 byte (metrics ở 0x128)
+        30 | 31 => (0x80, 8, if u32_at(data, 0x54)? == 0x128 { 0xC8 } else { 0xD0 }),
+        _ => return None,
+    };
+
+    let mut last_run_times: Vec<i64> = (0..times_count)
+        .filter_map(|i| u64_at(data, times_offset + i * 8))
+        .filter_map(filetime_to_unix)
+        .collect();
+    last_run_times.sort_unstable_by(|a, b| b.cmp(a));
+
+    Some(ParsedPrefetch {
+        version,
+        executable,
+        run_count: u32_at(data, count_offset)?,
+        last_run_times,
+    })
+}
+
+// ============================================================================
+// SHIMCACHE
+// ============================================================================
+
+/// Parse AppCompatCache Win10/11 (header 0x30 / 0x34, entry signature "10ts")
+fn parse_shimcache(data: &[u8]) -> Vec<ShimcacheEntry> {
+    let mut entries = Vec::new();
+    let Some(header) = u32_at(data, 0).filter(|h| *h == 0x30 || *h == 0x34) else {
+        return entries;
+    };
+
+    let mut offset = header as usize;
+    while data.get(offset..offset + 4) == Some(b"10ts".as_slice()) {
+        let (Some(entry_size), Some(path_len)) = (u32_at(data, offset + 8), u16_at(data, offset + 12)) else {
+            break;
+        };
+        let path_start = offset + 14;
+        let Some(path) = data.get(path_start..path_start + path_len as usize) else {
+            break;
+        };
+        entries.push(ShimcacheEntry {
+            path: utf16_until_nul(path),
+            last_modified: u64_at(data, path_start + path_len as usize).and_then(filetime_to_unix),
+            position: entries.len(),
+        });
+        offset += 12 + entry_size as usize;
+    }
+    entries
+}
+
+fn find_shimcache_entry(entries: Vec<ShimcacheEntry>, exe_path: &Path) -> Option<ShimcacheEntry> {
+    let target = normalize_path(&exe_path.to_string_lossy());
+    entries.into_iter().find(|e| normalize_path(&e.path) == target)
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+fn filetime_to_unix(filetime: u64) -> Option<i64> {
+    (filetime != 0).then(|| (filetime / 10_000_000) as i64 - FILETIME_UNIX_OFFSET_SECS)
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).and_then(|b| b.try_into().ok()).map(u32::from_le_bytes)
+}
+
+fn u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8).and_then(|b| b.try_into().ok()).map(u64::from_le_bytes)
+}
+
+fn utf16_until_nul(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|u| *u != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// "C:\Users\x\a.exe" → "\USERS\X\A.EXE" (Prefetch lưu path dạng \VOLUME{...}\...)
+fn device_independent_path(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('/', "\\").to_uppercase();
+    match path.find(":\\") {
+        Some(i) => path[i + 1..].to_string(),
+        None => path,
+    }
+}
+
+fn normalize_path(path: &str) -> String {
+    path.trim_start_matches("\\??\\").replace('/', "\\").to_lowercase()
+}
+
+fn contains_utf16(data: &[u8], needle: &str) -> bool {
+    let pattern: Vec<u8> = needle.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+    !pattern.is_empty() && data.windows(pattern.len()).any(|w| w == pattern.as_slice())
+}
+
+// ============================================================================
+// PLATFORM
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use std::ffi::c_void;
+    use windows::core::{s, w};
+    use windows::Win32::System::LibraryLoader::{GetProcAddress, LoadLibraryW};
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_BINARY};
+
+    const COMPRESSION_FORMAT_XPRESS_HUFF: u16 = 4;
+
+    type RtlGetCompressionWorkSpaceSize = unsafe extern "system" fn(u16, *mut u32, *mut u32) -> i32;
+    type RtlDecompressBufferEx =
+        unsafe extern "system" fn(u16, *mut u8, u32, *const u8, u32, *mut u32, *mut c_void) -> i32;
+
+    /// Giải nén Prefetch Win10+ qua ntdll!RtlDecompressBufferEx
+    pub fn decompress_mam(compressed: &[u8], size: usize) -> Option<Vec<u8>> {
+        let ntdll = unsafe { LoadLibraryW(w!("ntdll.dll")) }.ok()?;
+        let workspace_size_fn = unsafe { GetProcAddress(ntdll, s!("RtlGetCompressionWorkSpaceSize")) }?;
+        let decompress_fn = unsafe { GetProcAddress(ntdll, s!("RtlDecompressBufferEx")) }?;
+        let workspace_size_fn: RtlGetCompressionWorkSpaceSize = unsafe { std::mem::transmute(workspace_size_fn) };
+        let decompress_fn: RtlDecompressBufferEx = unsafe { std::mem::transmute(decompress_fn) };
+
+        let (mut workspace_size, mut fragment_size) = (0u32, 0u32);
+        let status = unsafe { workspace_size_fn(COMPRESSION_FORMAT_XPRESS_HUFF, &mut workspace_size, &mut fragment_size) };
+        if status < 0 {
+            return None;
+        }
+
+        let mut workspace = vec![0u8; workspace_size as usize];
+        let mut output = vec![0u8; size];
+        let mut final_size = 0u32;
+        let status = unsafe {
+            decompress_fn(
+                COMPRESSION_FORMAT_XPRESS_HUFF,
+                output.as_mut_ptr(),
+                size as u32,
+                compressed.as_ptr(),
+                compressed.len() as u32,
+                &mut final_size,
+                workspace.as_mut_ptr() as *mut c_void,
+            )
+        };
+        if status < 0 {
+            return None;
+        }
+        output.truncate(final_size as usize);
+        Some(output)
+    }
+
+    pub fn read_shimcache() -> Option<Vec<u8>> {
+        let subkey = w!("SYSTEM\\CurrentControlSet\\Control\\Session Manager\\AppCompatCache");
+        let value = w!("AppCompatCache");
+
+        let mut len = 0u32;
+        let status = unsafe {
+            RegGetValueW(HKEY_LOCAL_MACHINE, subkey, value, RRF_RT_REG_BINARY, None, None, Some(&mut len))
+        };
+        if status.is_err() || len == 0 {
+            return None;
+        }
+        let mut data = vec![0u8; len as usize];
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_LOCAL_MACHINE,
+                subkey,
+                value,
+                RRF_RT_REG_BINARY,
+                None,
+                Some(data.as_mut_ptr() as *mut c_void),
+                Some(&mut len),
+            )
+        };
+        if status.is_err() {
+            return None;
+        }
+        data.truncate(len as usize);
+        Some(data)
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    pub fn decompress_mam(_compressed: &[u8], _size: usize) -> Option<Vec<u8>> {
+        None
+    }
+
+    pub fn read_shimcache() -> Option<Vec<u8>> {
+        None
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filetime(unix: i64) -> u64 {
+        ((unix + FILETIME_UNIX_OFFSET_SECS) * 10_000_000) as u64
+    }
+
+    fn utf16(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_parse_prefetch_v30() {
+        let mut data = vec![0u8; 0x200];
+        data[0..4].copy_from_slice(&30u32.to_le_bytes());
+        data[4..8].copy_from_slice(b"SCCA");
+        let name = utf16("EVIL.EXE");
+        data[0x10..0x10 + name.len()].copy_from_slice(&name);
+        data[0x54..0x58].copy_from_slice(&0x130u32.to_le_bytes());
+        data[0x80..0x88].copy_from_slice(&filetime(1_700_000_000).to_le_bytes());
+        data[0x88..0x90].copy_from_slice(&filetime(1_700_100_000).to_le_bytes());
+        data[0xD0..0xD4].copy_from_slice(&7u32.to_le_bytes());
+        let path = utf16("\\VOLUME{01D}\\USERS\\BOB\\DOWNLOADS\\EVIL.EXE");
+        data[0x100..0x100 + path.len()].copy_from_slice(&path);
+
+        let parsed = parse_prefetch(&data).unwrap();
+        assert_eq!(parsed.version, 30);
+        assert_eq!(parsed.executable, "EVIL.EXE");
+        assert_eq!(parsed.run_count, 7);
+        assert_eq!(parsed.last_run_times, vec![1_700_100_000, 1_700_000_000]);
+
+        let exe = Path::new("C:\\Users\\bob\\Downloads\\evil.exe");
+        assert!(contains_utf16(&data, &device_independent_path(exe)));
+        assert!(!contains_utf16(&data, &device_independent_path(Path::new("C:\\Temp\\evil.exe"))));
+
+        // 216-byte file information → run count ở 0xC8
+        data[0x54..0x58].copy_from_slice(&0x128u32.to_le_bytes());
+        data[0xC8..0xCC].copy_from_slice(&3u32.to_le_bytes());
+        assert_eq!(parse_prefetch(&data).unwrap().run_count, 3);
+
+        assert!(parse_prefetch(b"MAM\x04garbage").is_none());
+    }
+
+    #[test]
+    fn test_parse_shimcache_win10() {
+        let mut data = vec![0u8; 0x34];
+        data[0..4].copy_from_slice(&0x34u32.to_le_bytes());
+        for (path, modified) in [("C:\\Tools\\new.exe", 1_700_000_000), ("C:\\Windows\\notepad.exe", 1_600_000_000)] {
+            let path = utf16(path);
+            let mut body = Vec::new();
+            body.extend_from_slice(&(path.len() as u16).to_le_bytes());
+            body.extend_from_slice(&path);
+            body.extend_from_slice(&filetime(modified).to_le_bytes());
+            body.extend_from_slice(&0u32.to_le_bytes());
+            data.extend_from_slice(b"10ts");
+            data.extend_from_slice(&0u32.to_le_bytes());
+            data.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            data.extend_from_slice(&body);
+        }
+
+        let entries = parse_shimcache(&data);
+        assert_eq!(entries.len(), 2);
+        let entry = find_shimcache_entry(entries, Path::new("c:\\windows\\NOTEPAD.EXE")).unwrap();
+        assert_eq!(entry.position, 1);
+        assert_eq!(entry.last_modified, Some(1_600_000_000));
+    }
+}
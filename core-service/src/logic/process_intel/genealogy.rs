@@ -0,0 +1,325 @@
+//! Process Genealogy - Lịch sử parent-child bền vững
+//!
+//! `tree.rs` chỉ thấy process đang chạy: parent đã exit thì chain bị đứt.
+//! Module này ghi lại mọi process mới xuất hiện trong tree (pid, start_time,
+//! ppid, image, cmdline) và giữ lại sau khi process exit, nên incident vẫn
+//! dựng được đầy đủ ancestry (vd: winword.exe → cmd.exe đã exit → payload.exe).
+//!
+//! Key là (pid, start_time) để phân biệt PID bị tái sử dụng.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::types::{ProcessInfo, ProcessTreeNode};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const GENEALOGY_FILE: &str = "process_genealogy.json";
+
+/// Giữ record của process đã exit trong 7 ngày
+const RETENTION_SECS: i64 = 7 * 24 * 3600;
+
+/// Giới hạn số record (bỏ process exit sớm nhất trước)
+const MAX_RECORDS: usize = 50_000;
+
+/// Khoảng cách tối thiểu giữa 2 lần ghi disk
+const SAVE_INTERVAL_SECS: i64 = 30;
+
+/// Giới hạn độ sâu ancestry
+const MAX_DEPTH: usize = 64;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Một process trong genealogy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenealogyRecord {
+    pub pid: u32,
+    /// Unix seconds
+    pub start_time: i64,
+    pub ppid: Option<u32>,
+    /// Start time của đúng parent instance (None nếu không xác định được)
+    pub parent_start_time: Option<i64>,
+    pub image: String,
+    pub exe_path: Option<String>,
+    pub cmdline: Option<String>,
+    pub first_seen: i64,
+    /// Thời điểm phát hiện process đã exit
+    pub exited_at: Option<i64>,
+}
+
+impl GenealogyRecord {
+    pub fn to_process_info(&self) -> ProcessInfo {
+        let mut info = ProcessInfo::new(self.pid, self.image.clone());
+        info.exe_path = self.exe_path.as_ref().map(PathBuf::from);
+        info.cmdline = self.cmdline.clone();
+        info.parent_pid = self.ppid;
+        info.start_time = self.start_time;
+        info
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct GenealogyStore {
+    records: Vec<GenealogyRecord>,
+}
+
+#[derive(Default)]
+struct GenealogyState {
+    records: HashMap<(u32, i64), GenealogyRecord>,
+    dirty: bool,
+    last_save: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GenealogyStats {
+    pub total_records: usize,
+    pub running: usize,
+    pub exited: usize,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static STATE: Lazy<Mutex<GenealogyState>> = Lazy::new(|| {
+    let records = load()
+        .records
+        .into_iter()
+        .map(|r| ((r.pid, r.start_time), r))
+        .collect();
+    Mutex::new(GenealogyState { records, ..Default::default() })
+});
+
+// ============================================================================
+// STATE LOGIC
+// ============================================================================
+
+impl GenealogyState {
+    /// Ghi nhận snapshot tree: process mới → record, process biến mất → exited
+    fn observe(&mut self, nodes: &HashMap<u32, ProcessTreeNode>, now: i64) {
+        for node in nodes.values() {
+            let info = &node.info;
+            let key = (info.pid, info.start_time);
+            if self.records.contains_key(&key) {
+                continue;
+            }
+
+            let parent_start_time = live_parent_start(info, nodes)
+                .or_else(|| info.parent_pid.and_then(|ppid| self.resolve_parent(ppid, info.start_time)));
+
+            self.records.insert(key, GenealogyRecord {
+                pid: info.pid,
+                start_time: info.start_time,
+                ppid: info.parent_pid,
+                parent_start_time,
+                image: info.name.clone(),
+                exe_path: info.exe_path.as_ref().map(|p| p.to_string_lossy().to_string()),
+                cmdline: info.cmdline.clone().filter(|c| !c.is_empty()),
+                first_seen: now,
+                exited_at: None,
+            });
+            self.dirty = true;
+        }
+
+        for (key, record) in self.records.iter_mut() {
+            if record.exited_at.is_none()
+                && nodes.get(&key.0).map(|n| n.info.start_time) != Some(key.1)
+            {
+                record.exited_at = Some(now);
+                self.dirty = true;
+            }
+        }
+
+        self.prune(now);
+    }
+
+    /// Parent instance mới nhất bắt đầu không muộn hơn child
+    fn resolve_parent(&self, ppid: u32, child_start: i64) -> Option<i64> {
+        self.records
+            .values()
+            .filter(|r| r.pid == ppid && r.start_time <= child_start)
+            .map(|r| r.start_time)
+            .max()
+    }
+
+    fn find(&self, pid: u32, start_time: Option<i64>) -> Option<&GenealogyRecord> {
+        match start_time {
+            Some(st) => self.records.get(&(pid, st)),
+            // Không có start_time: ưu tiên instance đang chạy, sau đó instance mới nhất
+            None => self.records
+                .values()
+                .filter(|r| r.pid == pid)
+                .max_by_key(|r| (r.exited_at.is_none(), r.start_time)),
+        }
+    }
+
+    fn ancestry(&self, pid: u32, start_time: Option<i64>) -> Vec<GenealogyRecord> {
+        let mut chain: Vec<GenealogyRecord> = Vec::new();
+        let mut current = self.find(pid, start_time);
+
+        while let Some(record) = current {
+            if chain.len() >= MAX_DEPTH || chain.iter().any(|r| r.pid == record.pid && r.start_time == record.start_time) {
+                break;
+            }
+            chain.push(record.clone());
+            current = match (record.ppid, record.parent_start_time) {
+                (Some(ppid), Some(pst)) => self.records.get(&(ppid, pst)),
+                (Some(ppid), None) => self.resolve_parent(ppid, record.start_time)
+                    .and_then(|pst| self.records.get(&(ppid, pst))),
+                _ => None,
+            };
+        }
+        chain
+    }
+
+    fn prune(&mut self, now: i64) {
+        let before = self.records.len();
+        self.records.retain(|_, r| r.exited_at.map_or(true, |e| now - e < RETENTION_SECS));
+
+        if self.records.len() > MAX_RECORDS {
+            let mut exited: Vec<_> = self.records.iter()
+                .filter_map(|(k, r)| r.exited_at.map(|e| (e, *k)))
+                .collect();
+            exited.sort_unstable();
+            let overflow = self.records.len() - MAX_RECORDS;
+            for (_, key) in exited.into_iter().take(overflow) {
+                self.records.remove(&key);
+            }
+        }
+
+        if self.records.len() != before {
+            self.dirty = true;
+        }
+    }
+}
+
+/// Start time của parent nếu parent đang chạy và không phải PID tái sử dụng
+fn live_parent_start(info: &ProcessInfo, nodes: &HashMap<u32, ProcessTreeNode>) -> Option<i64> {
+    let parent = nodes.get(&info.parent_pid?)?;
+    (parent.info.start_time <= info.start_time).then_some(parent.info.start_time)
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Cập nhật genealogy từ snapshot tree (gọi mỗi lần refresh tree)
+pub fn observe(nodes: &HashMap<u32, ProcessTreeNode>) {
+    let now = Utc::now().timestamp();
+    let snapshot = {
+        let mut state = STATE.lock();
+        state.observe(nodes, now);
+        if !state.dirty || now - state.last_save < SAVE_INTERVAL_SECS {
+            return;
+        }
+        state.dirty = false;
+        state.last_save = now;
+        state.records.values().cloned().collect::<Vec<_>>()
+    };
+    save(&GenealogyStore { records: snapshot });
+}
+
+/// Record của process (kể cả đã exit)
+pub fn get_record(pid: u32, start_time: Option<i64>) -> Option<GenealogyRecord> {
+    STATE.lock().find(pid, start_time).cloned()
+}
+
+/// Ancestry từ process lên root, gồm cả parent đã exit (process → parent → ...)
+pub fn get_ancestry(pid: u32, start_time: Option<i64>) -> Vec<GenealogyRecord> {
+    STATE.lock().ancestry(pid, start_time)
+}
+
+pub fn get_stats() -> GenealogyStats {
+    let state = STATE.lock();
+    let running = state.records.values().filter(|r| r.exited_at.is_none()).count();
+    GenealogyStats {
+        total_records: state.records.len(),
+        running,
+        exited: state.records.len() - running,
+    }
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn genealogy_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(GENEALOGY_FILE)
+}
+
+fn load() -> GenealogyStore {
+    fs::read_to_string(genealogy_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save(store: &GenealogyStore) {
+    let path = genealogy_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string(store) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(pid: u32, ppid: Option<u32>, name: &str, start_time: i64) -> (u32, ProcessTreeNode) {
+        let mut info = ProcessInfo::new(pid, name.to_string());
+        info.parent_pid = ppid;
+        info.start_time = start_time;
+        (pid, ProcessTreeNode { info, children: Vec::new(), depth: 0 })
+    }
+
+    #[test]
+    fn test_ancestry_survives_parent_exit() {
+        let mut state = GenealogyState::default();
+
+        let snapshot: HashMap<_, _> = [
+            node(10, None, "explorer.exe", 100),
+            node(20, Some(10), "winword.exe", 200),
+            node(30, Some(20), "cmd.exe", 300),
+        ].into_iter().collect();
+        state.observe(&snapshot, 1_000);
+
+        // cmd.exe spawn payload rồi exit, winword.exe cũng exit
+        let snapshot: HashMap<_, _> = [
+            node(10, None, "explorer.exe", 100),
+            node(40, Some(30), "payload.exe", 400),
+        ].into_iter().collect();
+        state.observe(&snapshot, 1_010);
+
+        let chain: Vec<_> = state.ancestry(40, None).into_iter().map(|r| r.image).collect();
+        assert_eq!(chain, vec!["payload.exe", "cmd.exe", "winword.exe", "explorer.exe"]);
+        assert!(state.find(30, Some(300)).unwrap().exited_at.is_some());
+
+        // PID 20 bị tái sử dụng bởi process khác - không được nối nhầm vào chain cũ
+        let snapshot: HashMap<_, _> = [
+            node(10, None, "explorer.exe", 100),
+            node(20, Some(10), "notepad.exe", 500),
+        ].into_iter().collect();
+        state.observe(&snapshot, 1_020);
+        let chain: Vec<_> = state.ancestry(40, Some(400)).into_iter().map(|r| r.image).collect();
+        assert_eq!(chain[2], "winword.exe");
+    }
+}
@@ -0,0 +1,65 @@
+//! Open Handles - Process nào đang giữ handle tới một file
+//!
+//! Dùng Restart Manager (`RmGetList`): không cần driver, không cần quyền admin với
+//! file của user hiện tại. Chỉ thấy handle còn mở ở thời điểm gọi.
+
+use std::path::Path;
+
+/// PIDs đang giữ handle tới file (rỗng nếu không xác định được)
+pub fn processes_using(path: &Path) -> Vec<u32> {
+    platform::processes_using(path)
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::path::Path;
+    use windows::core::{HSTRING, PCWSTR, PWSTR};
+    use windows::Win32::Foundation::ERROR_MORE_DATA;
+    use windows::Win32::System::RestartManager::{
+        RmEndSession, RmGetList, RmRegisterResources, RmStartSession, CCH_RM_SESSION_KEY, RM_PROCESS_INFO,
+    };
+
+    /// PIDs đang giữ handle tới file (Restart Manager)
+    pub fn processes_using(path: &Path) -> Vec<u32> {
+        let mut session = 0u32;
+        let mut key = [0u16; CCH_RM_SESSION_KEY as usize + 1];
+        if unsafe { RmStartSession(&mut session, 0, PWSTR(key.as_mut_ptr())) }.is_err() {
+            return Vec::new();
+        }
+
+        let file = HSTRING::from(path.as_os_str());
+        let files = [PCWSTR(file.as_ptr())];
+        let mut pids = Vec::new();
+
+        if unsafe { RmRegisterResources(session, Some(&files), None, None) }.is_ok() {
+            let mut needed = 0u32;
+            let mut count = 0u32;
+            let mut reasons = 0u32;
+            let status = unsafe { RmGetList(session, &mut needed, &mut count, None, &mut reasons) };
+            if status == ERROR_MORE_DATA && needed > 0 {
+                let mut infos = vec![RM_PROCESS_INFO::default(); needed as usize];
+                count = needed;
+                let status = unsafe {
+                    RmGetList(session, &mut needed, &mut count, Some(infos.as_mut_ptr()), &mut reasons)
+                };
+                if status.is_ok() {
+                    pids = infos[..count as usize].iter().map(|i| i.Process.dwProcessId).collect();
+                }
+            }
+        }
+
+        unsafe {
+            let _ = RmEndSession(session);
+        }
+        pids
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use std::path::Path;
+
+    pub fn processes_using(_path: &Path) -> Vec<u32> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,352 @@
+//! File Hashing Service - SHA-256/MD5 dùng chung có cache
+//!
+//! IAT analysis, VirusTotal, reputation, quarantine, whitelist... đều cần hash
+//! file. Service này đọc file một lần, tính cả SHA-256 và MD5, rồi cache theo
+//! (path, size, mtime):
+//! - LRU trong RAM (MAX_MEMORY_ENTRIES)
+//! - Cache trên disk (`hash_cache.json`) để restart không phải hash lại
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CACHE_FILE: &str = "hash_cache.json";
+
+/// Entries giữ trong RAM
+const MAX_MEMORY_ENTRIES: usize = 4096;
+
+/// Entries giữ trên disk (LRU bị cắt khi save)
+const MAX_DISK_ENTRIES: usize = 20_000;
+
+/// Số entry mới trước khi ghi cache xuống disk
+const SAVE_EVERY: usize = 32;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Hashes của một file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileHashes {
+    pub sha256: String,
+    pub md5: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    size: u64,
+    /// mtime (ns since epoch)
+    mtime_ns: u64,
+    hashes: FileHashes,
+    /// Tick lần dùng gần nhất (LRU)
+    #[serde(default)]
+    last_used: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HashCacheStats {
+    pub memory_entries: usize,
+    pub disk_entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub bytes_hashed: u64,
+}
+
+#[derive(Default)]
+struct HashCache {
+    /// RAM cache (LRU)
+    memory: HashMap<PathBuf, CacheEntry>,
+    /// Cache đọc từ disk (lazy promote lên RAM khi dùng)
+    disk: HashMap<PathBuf, CacheEntry>,
+    tick: u64,
+    unsaved: usize,
+    stats: HashCacheStats,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static CACHE: Lazy<Mutex<HashCache>> = Lazy::new(|| {
+    Mutex::new(HashCache {
+        disk: load(),
+        ..Default::default()
+    })
+});
+
+// ============================================================================
+// CACHE
+// ============================================================================
+
+impl HashCache {
+    fn lookup(&mut self, path: &Path, size: u64, mtime_ns: u64) -> Option<FileHashes> {
+        self.tick += 1;
+        let tick = self.tick;
+
+        if let Some(entry) = self.memory.get_mut(path) {
+            if entry.size == size && entry.mtime_ns == mtime_ns {
+                entry.last_used = tick;
+                return Some(entry.hashes.clone());
+            }
+            self.memory.remove(path);
+            return None;
+        }
+
+        let entry = self.disk.remove(path)?;
+        if entry.size != size || entry.mtime_ns != mtime_ns {
+            return None;
+        }
+        let hashes = entry.hashes.clone();
+        self.insert_memory(path.to_path_buf(), CacheEntry { last_used: tick, ..entry });
+        Some(hashes)
+    }
+
+    fn insert(&mut self, path: PathBuf, size: u64, mtime_ns: u64, hashes: FileHashes) {
+        self.tick += 1;
+        let entry = CacheEntry { size, mtime_ns, hashes, last_used: self.tick };
+        self.insert_memory(path, entry);
+        self.unsaved += 1;
+    }
+
+    fn insert_memory(&mut self, path: PathBuf, entry: CacheEntry) {
+        self.memory.insert(path, entry);
+        if self.memory.len() > MAX_MEMORY_ENTRIES {
+            // Evict 1/8 entry ít dùng nhất xuống disk cache
+            let mut by_age: Vec<_> = self.memory.iter().map(|(p, e)| (e.last_used, p.clone())).collect();
+            by_age.sort_unstable();
+            for (_, p) in by_age.into_iter().take(MAX_MEMORY_ENTRIES / 8) {
+                if let Some(old) = self.memory.remove(&p) {
+                    self.disk.insert(p, old);
+                }
+            }
+        }
+    }
+
+    /// Gộp RAM + disk, cắt theo LRU
+    fn snapshot(&self) -> HashMap<PathBuf, CacheEntry> {
+        let mut all: Vec<_> = self.disk.iter().chain(self.memory.iter())
+            .map(|(p, e)| (p.clone(), e.clone()))
+            .collect();
+        all.sort_unstable_by(|a, b| b.1.last_used.cmp(&a.1.last_used));
+        all.truncate(MAX_DISK_ENTRIES);
+        all.into_iter().collect()
+    }
+}
+
+fn file_key(path: &Path) -> Result<(u64, u64), String> {
+    let meta = fs::metadata(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if !meta.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+    let mtime_ns = meta
+        .modified()
+        .ok()
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    Ok((meta.len(), mtime_ns))
+}
+
+/// Đọc file một lần, tính SHA-256 + MD5
+fn compute(path: &Path) -> Result<FileHashes, String> {
+    let mut file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut sha256 = Sha256::new();
+    let mut md5 = md5::Context::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        sha256.update(&buffer[..read]);
+        md5.consume(&buffer[..read]);
+        size += read as u64;
+    }
+
+    Ok(FileHashes {
+        sha256: format!("{:x}", sha256.finalize()),
+        md5: format!("{:x}", md5.compute()),
+        size,
+    })
+}
+
+fn compute_bytes(data: &[u8]) -> FileHashes {
+    FileHashes {
+        sha256: format!("{:x}", Sha256::digest(data)),
+        md5: format!("{:x}", md5::compute(data)),
+        size: data.len() as u64,
+    }
+}
+
+fn store(path: &Path, size: u64, mtime_ns: u64, hashes: &FileHashes) {
+    let should_save = {
+        let mut cache = CACHE.lock();
+        cache.stats.bytes_hashed += hashes.size;
+        cache.insert(path.to_path_buf(), size, mtime_ns, hashes.clone());
+        cache.unsaved >= SAVE_EVERY
+    };
+    if should_save {
+        flush();
+    }
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// SHA-256 + MD5 của file (cache theo path, size, mtime)
+pub fn hash_file(path: &Path) -> Result<FileHashes, String> {
+    let (size, mtime_ns) = file_key(path)?;
+
+    {
+        let mut cache = CACHE.lock();
+        if let Some(hashes) = cache.lookup(path, size, mtime_ns) {
+            cache.stats.hits += 1;
+            return Ok(hashes);
+        }
+        cache.stats.misses += 1;
+    }
+
+    // Hash ngoài lock - file lớn không chặn các caller khác
+    let hashes = compute(path)?;
+    store(path, size, mtime_ns, &hashes);
+    Ok(hashes)
+}
+
+/// Hash nội dung `path` đã đọc sẵn vào RAM (IAT, full scan) - không đọc lại file
+pub fn hash_loaded(path: &Path, data: &[u8]) -> FileHashes {
+    // Chỉ cache khi nội dung khớp file trên disk (cùng size)
+    let key = file_key(path).ok().filter(|(size, _)| *size == data.len() as u64);
+
+    if let Some((size, mtime_ns)) = key {
+        let mut cache = CACHE.lock();
+        if let Some(hashes) = cache.lookup(path, size, mtime_ns) {
+            cache.stats.hits += 1;
+            return hashes;
+        }
+        cache.stats.misses += 1;
+    }
+
+    let hashes = compute_bytes(data);
+    if let Some((size, mtime_ns)) = key {
+        store(path, size, mtime_ns, &hashes);
+    }
+    hashes
+}
+
+/// SHA-256 (lowercase hex)
+pub fn sha256_file(path: &Path) -> Option<String> {
+    hash_file(path).ok().map(|h| h.sha256)
+}
+
+/// MD5 (lowercase hex)
+pub fn md5_file(path: &Path) -> Option<String> {
+    hash_file(path).ok().map(|h| h.md5)
+}
+
+/// Hash đã cache (không đọc file) - None nếu chưa có hoặc file đã đổi
+pub fn cached_hashes(path: &Path) -> Option<FileHashes> {
+    let (size, mtime_ns) = file_key(path).ok()?;
+    CACHE.lock().lookup(path, size, mtime_ns)
+}
+
+/// Ghi cache xuống disk
+pub fn flush() {
+    let snapshot = {
+        let mut cache = CACHE.lock();
+        cache.unsaved = 0;
+        cache.snapshot()
+    };
+    save(&snapshot);
+}
+
+/// Xóa toàn bộ cache (RAM + disk)
+pub fn clear_cache() {
+    {
+        let mut cache = CACHE.lock();
+        cache.memory.clear();
+        cache.disk.clear();
+        cache.unsaved = 0;
+    }
+    let _ = fs::remove_file(cache_path());
+}
+
+pub fn get_stats() -> HashCacheStats {
+    let cache = CACHE.lock();
+    HashCacheStats {
+        memory_entries: cache.memory.len(),
+        disk_entries: cache.disk.len(),
+        ..cache.stats.clone()
+    }
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn cache_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(CACHE_FILE)
+}
+
+fn load() -> HashMap<PathBuf, CacheEntry> {
+    fs::read_to_string(cache_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save(entries: &HashMap<PathBuf, CacheEntry>) {
+    let path = cache_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string(entries) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_and_cache_invalidation() {
+        let dir = std::env::temp_dir().join(format!("oneshield_hash_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("sample.bin");
+        fs::write(&file, b"abc").unwrap();
+
+        let hashes = hash_file(&file).unwrap();
+        assert_eq!(hashes.sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(hashes.md5, "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(hashes.size, 3);
+        assert_eq!(cached_hashes(&file), Some(hashes));
+
+        // Size đổi → cache miss, hash lại
+        fs::write(&file, b"abcd").unwrap();
+        assert!(cached_hashes(&file).is_none());
+        assert_eq!(hash_file(&file).unwrap().size, 4);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -3,10 +3,16 @@
 //! Mục đích: Phân tích sâu process behaviors để phát hiện suspicious activity
 //!
 //! # Components
-//! - `signature.rs`: Kiểm tra chữ ký số của ứng dụng
+//! - `signature.rs`: Kiểm tra chữ ký số của ứng dụng, allowlist publisher (local + cloud policy)
 //! - `tree.rs`: Phân tích Parent-Child relationships
 //! - `spawn.rs`: Phát hiện LOLBins và suspicious spawns
 //! - `reputation.rs`: Điểm tin cậy dựa trên lịch sử behavior
+//! - `hashing.rs`: SHA-256/MD5 dùng chung, cache theo (path, size, mtime)
+//! - `genealogy.rs`: Lịch sử parent-child bền vững (ancestry sau khi parent exit)
+//! - `prevalence.rs`: Số endpoint trong fleet đã chạy binary (RareInFleet)
+//! - `token.rs`: User sở hữu, integrity level, elevated của process token
+//! - `artifacts.rs`: Prefetch / Shimcache (lần chạy đầu, run count) cho incident
+//! - `handles.rs`: Process đang giữ handle tới file (Restart Manager)
 
 // Allow unused for now - will be fully integrated in future phases
 #![allow(unused)]
@@ -15,15 +21,26 @@ pub mod signature;
 pub mod tree;
 pub mod spawn;
 pub mod reputation;
+pub mod hashing;
+pub mod genealogy;
+pub mod prevalence;
+pub mod token;
+pub mod artifacts;
+pub mod handles;
 pub mod types;
 
 // Re-exports - only public items
 pub use types::{
     SignatureStatus, ProcessInfo, SpawnSeverity, ReputationEntry,
     ReputationFlags, ProcessTreeNode, TreeAnalysisResult, SuspiciousChain,
-    SuspiciousSpawnAlert, is_publisher_trusted,
+    SuspiciousSpawnAlert, AbusedSigner, TrustedPublisher, TrustedPublisherSource, IntegrityLevel, is_publisher_trusted,
 };
 pub use signature::{verify_signature, SignatureResult, is_trusted_publisher, is_signed};
 pub use tree::{get_process_tree, get_process_parent, get_process_info, refresh_tree};
-pub use spawn::{check_suspicious_spawn, is_lolbin, get_lolbin_info};
-pub use reputation::{get_reputation, update_reputation, ProcessReputation, is_trusted, is_untrusted};
+pub use spawn::{check_suspicious_spawn, is_lolbin, get_lolbin_info, abusive_arguments};
+pub use reputation::{get_reputation, update_reputation, ProcessReputation, ReputationReport, is_trusted, is_untrusted};
+pub use hashing::{hash_file, sha256_file, md5_file, FileHashes};
+pub use genealogy::{GenealogyRecord, get_ancestry};
+pub use prevalence::{FleetPrevalence, is_rare_in_fleet};
+pub use token::ProcessToken;
+pub use artifacts::ExecutionArtifacts;
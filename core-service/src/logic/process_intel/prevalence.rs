@@ -0,0 +1,309 @@
+//! Fleet Prevalence - Bao nhiêu endpoint trong org đã chạy binary này
+//!
+//! - Sightings: hash của executable thấy trên máy → báo cloud sau heartbeat
+//! - Lookup: hỏi cloud số endpoint đã chạy hash, cache kết quả (TTL)
+//!
+//! Classification chỉ đọc cache (không chờ network): hash chưa có trong cache
+//! được queue để lần sync sau query, lần classify kế tiếp mới có kết quả.
+//! Binary unsigned chỉ xuất hiện trên vài máy → `RareInFleet` trong ThreatContext.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use parking_lot::Mutex;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Kết quả lookup giữ trong bao lâu trước khi query lại
+const CACHE_TTL_SECS: i64 = 6 * 3600;
+
+const MAX_CACHE_ENTRIES: usize = 10_000;
+const MAX_PENDING_SIGHTINGS: usize = 5_000;
+
+/// Số hash mỗi request (server giới hạn 500)
+const BATCH_SIZE: usize = 200;
+
+/// Số executable mới hash mỗi lần quét process đang chạy
+const MAX_COLLECT_PER_RUN: usize = 200;
+
+/// Hiếm: chạy trên ≤ RARE_MAX_ENDPOINTS máy hoặc < RARE_MAX_FRACTION fleet
+const RARE_MAX_ENDPOINTS: i64 = 2;
+const RARE_MAX_FRACTION: f64 = 0.02;
+
+/// Fleet nhỏ hơn thì prevalence không có ý nghĩa
+const MIN_FLEET_SIZE: i64 = 10;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Hash thấy trên endpoint này (gửi lên cloud)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrevalenceSighting {
+    pub sha256: String,
+    pub file_name: Option<String>,
+    pub signed: bool,
+    /// Unix seconds
+    pub seen_at: i64,
+}
+
+/// Kết quả query từ cloud (hash không có trong response = chưa endpoint nào báo)
+#[derive(Debug, Clone, Deserialize)]
+pub struct CloudPrevalence {
+    pub sha256: String,
+    pub endpoint_count: i64,
+    #[serde(default)]
+    pub is_signed: bool,
+}
+
+/// Prevalence đã cache
+#[derive(Debug, Clone, Serialize)]
+pub struct FleetPrevalence {
+    pub sha256: String,
+    pub endpoint_count: i64,
+    pub total_endpoints: i64,
+    /// Có endpoint nào báo chữ ký hợp lệ
+    pub signed_in_fleet: bool,
+    pub fetched_at: i64,
+}
+
+impl FleetPrevalence {
+    pub fn is_rare(&self) -> bool {
+        self.total_endpoints >= MIN_FLEET_SIZE
+            && (self.endpoint_count <= RARE_MAX_ENDPOINTS
+                || (self.endpoint_count as f64 / self.total_endpoints as f64) < RARE_MAX_FRACTION)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PrevalenceStats {
+    pub cached: usize,
+    pub rare_cached: usize,
+    pub pending_sightings: usize,
+    pub pending_lookups: usize,
+    pub reported_hashes: usize,
+    pub total_endpoints: i64,
+}
+
+#[derive(Default)]
+struct PrevalenceState {
+    /// Hash đã báo trong session này
+    reported: HashSet<String>,
+    /// Executable đã hash khi quét process đang chạy
+    collected_paths: HashSet<PathBuf>,
+    pending_sightings: VecDeque<PrevalenceSighting>,
+    pending_lookups: HashSet<String>,
+    cache: HashMap<String, FleetPrevalence>,
+    total_endpoints: i64,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static STATE: Lazy<Mutex<PrevalenceState>> = Lazy::new(|| Mutex::new(PrevalenceState::default()));
+
+impl PrevalenceState {
+    fn record_sighting(&mut self, sha256: String, file_name: Option<String>, signed: bool, now: i64) {
+        if !self.reported.insert(sha256.clone()) {
+            return;
+        }
+        if self.pending_sightings.len() >= MAX_PENDING_SIGHTINGS {
+            self.pending_sightings.pop_front();
+        }
+        if self.cached(&sha256, now).is_none() {
+            self.pending_lookups.insert(sha256.clone());
+        }
+        self.pending_sightings.push_back(PrevalenceSighting { sha256, file_name, signed, seen_at: now });
+    }
+
+    fn cached(&self, sha256: &str, now: i64) -> Option<&FleetPrevalence> {
+        self.cache.get(sha256).filter(|p| now - p.fetched_at < CACHE_TTL_SECS)
+    }
+
+    fn apply_lookup(&mut self, queried: &[String], total_endpoints: i64, results: Vec<CloudPrevalence>, now: i64) {
+        self.total_endpoints = total_endpoints;
+        let mut by_hash: HashMap<String, CloudPrevalence> = results
+            .into_iter()
+            .map(|r| (r.sha256.to_lowercase(), r))
+            .collect();
+
+        for sha256 in queried {
+            let result = by_hash.remove(sha256);
+            self.pending_lookups.remove(sha256);
+            self.cache.insert(sha256.clone(), FleetPrevalence {
+                sha256: sha256.clone(),
+                endpoint_count: result.as_ref().map(|r| r.endpoint_count).unwrap_or(0),
+                total_endpoints,
+                signed_in_fleet: result.map(|r| r.is_signed).unwrap_or(false),
+                fetched_at: now,
+            });
+        }
+
+        if self.cache.len() > MAX_CACHE_ENTRIES {
+            self.cache.retain(|_, p| now - p.fetched_at < CACHE_TTL_SECS);
+        }
+    }
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Ghi nhận hash thấy trên máy (dedup trong session)
+pub fn record_sighting(sha256: &str, file_name: Option<&str>, signed: bool) {
+    STATE.lock().record_sighting(
+        sha256.to_lowercase(),
+        file_name.map(str::to_string),
+        signed,
+        Utc::now().timestamp(),
+    );
+}
+
+/// Hash + ghi nhận executable (dùng signature đã cache nếu có)
+pub fn record_path(exe_path: &Path) -> Option<String> {
+    let sha256 = super::hashing::sha256_file(exe_path)?;
+    let signed = super::signature::cached_status(exe_path)
+        .map(|s| s.is_signed())
+        .unwrap_or(false);
+    let name = exe_path.file_name().map(|n| n.to_string_lossy().to_string());
+    record_sighting(&sha256, name.as_deref(), signed);
+    Some(sha256)
+}
+
+/// Quét process đang chạy, ghi nhận executable chưa báo (gọi từ sync, ngoài async runtime)
+pub fn collect_running() -> usize {
+    let running: HashSet<PathBuf> = super::tree::get_process_tree()
+        .into_values()
+        .filter_map(|n| n.info.exe_path)
+        .collect();
+
+    let paths: Vec<PathBuf> = {
+        let state = STATE.lock();
+        running
+            .into_iter()
+            .filter(|p| !state.collected_paths.contains(p))
+            .take(MAX_COLLECT_PER_RUN)
+            .collect()
+    };
+
+    let mut collected = 0;
+    for path in paths {
+        if record_path(&path).is_some() {
+            collected += 1;
+        }
+        STATE.lock().collected_paths.insert(path);
+    }
+    collected
+}
+
+/// Prevalence đã cache; None (và queue lookup) nếu chưa có / hết hạn
+pub fn lookup(sha256: &str) -> Option<FleetPrevalence> {
+    let sha256 = sha256.to_lowercase();
+    let mut state = STATE.lock();
+    let cached = state.cached(&sha256, Utc::now().timestamp()).cloned();
+    if cached.is_none() {
+        state.pending_lookups.insert(sha256);
+    }
+    cached
+}
+
+/// Hash hiếm trong fleet (caller tự kiểm tra chữ ký - RareInFleet chỉ áp cho unsigned)
+pub fn is_rare_in_fleet(sha256: &str) -> bool {
+    lookup(sha256).map(|p| p.is_rare()).unwrap_or(false)
+}
+
+/// Lấy batch sightings để báo cloud
+pub fn take_pending_sightings() -> Vec<PrevalenceSighting> {
+    let mut state = STATE.lock();
+    let n = state.pending_sightings.len().min(BATCH_SIZE);
+    state.pending_sightings.drain(..n).collect()
+}
+
+/// Báo thất bại → đưa lại vào đầu queue
+pub fn requeue_sightings(sightings: Vec<PrevalenceSighting>) {
+    let mut state = STATE.lock();
+    for sighting in sightings.into_iter().rev() {
+        if state.pending_sightings.len() >= MAX_PENDING_SIGHTINGS {
+            break;
+        }
+        state.pending_sightings.push_front(sighting);
+    }
+}
+
+/// Lấy batch hash cần query
+pub fn take_pending_lookups() -> Vec<String> {
+    let mut state = STATE.lock();
+    let batch: Vec<String> = state.pending_lookups.iter().take(BATCH_SIZE).cloned().collect();
+    for sha256 in &batch {
+        state.pending_lookups.remove(sha256);
+    }
+    batch
+}
+
+pub fn requeue_lookups(hashes: Vec<String>) {
+    STATE.lock().pending_lookups.extend(hashes);
+}
+
+/// Cập nhật cache từ kết quả query
+pub fn apply_lookup(queried: &[String], total_endpoints: i64, results: Vec<CloudPrevalence>) {
+    STATE.lock().apply_lookup(queried, total_endpoints, results, Utc::now().timestamp());
+}
+
+pub fn get_stats() -> PrevalenceStats {
+    let state = STATE.lock();
+    PrevalenceStats {
+        cached: state.cache.len(),
+        rare_cached: state.cache.values().filter(|p| p.is_rare()).count(),
+        pending_sightings: state.pending_sightings.len(),
+        pending_lookups: state.pending_lookups.len(),
+        reported_hashes: state.reported.len(),
+        total_endpoints: state.total_endpoints,
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_and_rarity() {
+        let mut state = PrevalenceState::default();
+        let now = 1_700_000_000;
+        let rare = "a".repeat(64);
+        let common = "b".repeat(64);
+
+        state.record_sighting(rare.clone(), Some("dropper.exe".into()), false, now);
+        state.record_sighting(rare.clone(), Some("dropper.exe".into()), false, now);
+        assert_eq!(state.pending_sightings.len(), 1);
+        assert!(state.pending_lookups.contains(&rare));
+
+        state.apply_lookup(
+            &[rare.clone(), common.clone()],
+            50,
+            vec![
+                CloudPrevalence { sha256: rare.clone(), endpoint_count: 1, is_signed: false },
+                CloudPrevalence { sha256: common.clone(), endpoint_count: 40, is_signed: true },
+            ],
+            now,
+        );
+        assert!(state.pending_lookups.is_empty());
+        assert!(state.cached(&rare, now).unwrap().is_rare());
+        assert!(!state.cached(&common, now).unwrap().is_rare());
+
+        // Hết TTL → phải query lại
+        assert!(state.cached(&rare, now + CACHE_TTL_SECS).is_none());
+
+        // Fleet nhỏ: không kết luận được
+        state.apply_lookup(&[rare.clone()], 3, vec![], now);
+        assert!(!state.cached(&rare, now).unwrap().is_rare());
+    }
+}
@@ -6,16 +6,18 @@
 //! - Số lần gây anomaly
 //! - Chữ ký số
 //!
-//! Persistence: Lưu vào JSON file để không mất sau restart
+//! Persistence: SQLite (`process_reputation.db`) để counters tích lũy qua restart.
+//! Decay: anomaly cũ giảm dần, entry lâu không thấy trôi về neutral.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::fs::{self, File};
-use std::io::{BufReader, BufWriter};
-use parking_lot::RwLock;
-use sha2::{Sha256, Digest};
+use std::io::BufReader;
+use parking_lot::{Mutex, RwLock};
 use once_cell::sync::Lazy;
 use std::sync::atomic::{AtomicU64, Ordering};
+use chrono::Utc;
+use rusqlite::{params, Connection};
 
 use super::types::{ReputationEntry, ReputationFlags, SignatureStatus};
 use super::signature;
@@ -24,9 +26,13 @@ use super::signature;
 // CONSTANTS
 // ============================================================================
 
-const REPUTATION_FILE_NAME: &str = "process_reputation.json";
+const REPUTATION_DB_NAME: &str = "process_reputation.db";
+/// File JSON cũ (trước SQLite) - migrate một lần khi init
+const LEGACY_FILE_NAME: &str = "process_reputation.json";
 const MAX_ENTRIES: usize = 10_000;
-const SAVE_INTERVAL: u64 = 100; // Save after every N updates
+const SAVE_INTERVAL: u64 = 50; // Flush after every N updates
+const SAVE_MAX_DELAY_SECS: i64 = 60; // ...hoặc khi có thay đổi chưa ghi quá lâu
+const DECAY_INTERVAL_SECS: i64 = 3600;
 
 // Reputation thresholds
 const HIGH_TRUST_THRESHOLD: f32 = 0.7;
@@ -38,6 +44,8 @@ const LOW_TRUST_THRESHOLD: f32 = 0.3;
 
 static REPUTATION_DB: Lazy<RwLock<ReputationDatabase>> =
     Lazy::new(|| RwLock::new(ReputationDatabase::new()));
+/// Connection tách riêng (Connection không Sync, chỉ giữ sau Mutex)
+static CONNECTION: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(None));
 static UPDATE_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 // ============================================================================
@@ -47,6 +55,10 @@ static UPDATE_COUNTER: AtomicU64 = AtomicU64::new(0);
 pub struct ReputationDatabase {
     entries: HashMap<String, ReputationEntry>,  // hash -> entry
     path_to_hash: HashMap<String, String>,      // path -> hash (quick lookup)
+    dirty: HashSet<String>,                     // hash đã đổi, chưa ghi SQLite
+    removed: HashSet<String>,                   // hash đã evict, cần xóa khỏi SQLite
+    last_flush: i64,
+    last_decay: i64,
     loaded: bool,
 }
 
@@ -55,9 +67,41 @@ impl ReputationDatabase {
         Self {
             entries: HashMap::new(),
             path_to_hash: HashMap::new(),
+            dirty: HashSet::new(),
+            removed: HashSet::new(),
+            last_flush: 0,
+            last_decay: 0,
             loaded: false,
         }
     }
+
+    fn mark_dirty(&mut self, hash: &str) {
+        self.removed.remove(hash);
+        self.dirty.insert(hash.to_string());
+    }
+
+    fn index(&mut self, entry: ReputationEntry) {
+        let path_str = entry.exe_path.to_string_lossy().to_lowercase();
+        self.path_to_hash.insert(path_str, entry.exe_hash.clone());
+        self.entries.insert(entry.exe_hash.clone(), entry);
+    }
+
+    /// Áp dụng decay cho mọi entry, trả về số entry có counters thay đổi
+    fn apply_decay(&mut self, now: i64) -> usize {
+        self.last_decay = now;
+        let changed: Vec<String> = self.entries.iter_mut()
+            .filter_map(|(hash, entry)| entry.apply_decay(now).then(|| hash.clone()))
+            .collect();
+        for hash in &changed {
+            self.mark_dirty(hash);
+        }
+        changed.len()
+    }
+
+    fn needs_flush(&self, counter: u64, now: i64) -> bool {
+        !self.dirty.is_empty()
+            && (counter % SAVE_INTERVAL == 0 || now - self.last_flush >= SAVE_MAX_DELAY_SECS)
+    }
 }
 
 // ============================================================================
@@ -70,13 +114,36 @@ pub fn init() {
     if db.loaded {
         return;
     }
+    db.loaded = true;
 
-    if let Err(e) = load_internal(&mut db) {
-        log::warn!("Failed to load reputation database: {}", e);
+    match open_connection() {
+        Ok(conn) => {
+            match read_entries(&conn) {
+                Ok(entries) => entries.into_iter().for_each(|e| db.index(e)),
+                Err(e) => log::warn!("Failed to load reputation database: {}", e),
+            }
+            *CONNECTION.lock() = Some(conn);
+        }
+        Err(e) => log::warn!("Failed to open reputation database: {}", e),
     }
 
-    db.loaded = true;
-    log::info!("Reputation database initialized with {} entries", db.entries.len());
+    let migrated = migrate_legacy_json(&mut db);
+    let now = Utc::now().timestamp();
+    let decayed = db.apply_decay(now);
+    db.last_flush = now;
+
+    log::info!(
+        "Reputation database initialized with {} entries ({} migrated, {} decayed)",
+        db.entries.len(), migrated, decayed
+    );
+
+    let pending = !db.dirty.is_empty();
+    drop(db);
+    if pending {
+        if let Err(e) = save() {
+            log::warn!("Failed to save reputation database: {}", e);
+        }
+    }
 }
 
 /// Lấy reputation của một executable
@@ -119,14 +186,18 @@ pub fn update_reputation(exe_path: &Path, is_anomaly: bool, is_alert: bool) -> R
             } else if is_anomaly {
                 entry.record_anomaly();
             }
-            return entry.clone();
+            let entry = entry.clone();
+            db.mark_dirty(&hash);
+            drop(db);
+            maybe_flush();
+            return entry;
         }
     }
 
     // New entry - need to compute hash
     drop(db); // Release lock before I/O
 
-    let hash = compute_file_hash(exe_path).unwrap_or_else(|_| {
+    let hash = super::hashing::sha256_file(exe_path).unwrap_or_else(|| {
         // Fallback: use path as "hash" if file can't be read
         format!("path:{}", path_str)
     });
@@ -152,26 +223,55 @@ pub fn update_reputation(exe_path: &Path, is_anomaly: bool, is_alert: bool) -> R
         entry.record_anomaly();
     }
 
+    if !hash.starts_with("path:") {
+        super::prevalence::record_sighting(&hash, Some(&entry.exe_name), entry.signature.is_signed());
+    }
+
     // Store entry
     let mut db = REPUTATION_DB.write();
-    db.path_to_hash.insert(path_str, hash.clone());
 
     // Evict if too many entries
     if db.entries.len() >= MAX_ENTRIES {
         evict_old_entries(&mut db);
     }
 
-    db.entries.insert(hash, entry.clone());
+    db.path_to_hash.insert(path_str, hash.clone());
+    db.entries.insert(hash.clone(), entry.clone());
+    db.mark_dirty(&hash);
+    drop(db);
 
-    // Maybe save
-    let counter = UPDATE_COUNTER.fetch_add(1, Ordering::SeqCst);
+    maybe_flush();
+    entry
+}
 
-    if counter % SAVE_INTERVAL == 0 {
-        drop(db);
-        let _ = save();
+/// Verify lại chữ ký của các entry đang "signed" (sau khi abused signer list /
+/// trusted publisher allowlist đổi). Entry `Invalid` cũng được verify lại vì cert
+/// nội bộ vừa pin có thể chuyển thành Trusted. Trả về số entry đổi trạng thái.
+pub fn recheck_signatures() -> usize {
+    init();
+    let targets: Vec<(String, PathBuf)> = REPUTATION_DB.read().entries.iter()
+        .filter(|(_, e)| e.signature.is_signed() || matches!(e.signature, SignatureStatus::Invalid { .. }))
+        .map(|(hash, e)| (hash.clone(), e.exe_path.clone()))
+        .collect();
+
+    let mut changed = 0;
+    for (hash, path) in targets {
+        // Verify ngoài lock (PowerShell)
+        let status = signature::verify_signature(&path).status;
+        let mut db = REPUTATION_DB.write();
+        if let Some(entry) = db.entries.get_mut(&hash) {
+            if entry.signature != status {
+                entry.set_signature(status);
+                db.mark_dirty(&hash);
+                changed += 1;
+            }
+        }
     }
 
-    entry
+    if changed > 0 {
+        let _ = save();
+    }
+    changed
 }
 
 /// Whitelist một executable (trusted)
@@ -182,7 +282,11 @@ pub fn whitelist(exe_hash: &str) {
         entry.flags.is_whitelisted = true;
         entry.flags.is_blacklisted = false;
         entry.reputation_score = 1.0;
+        db.mark_dirty(exe_hash);
     }
+    drop(db);
+    // Quyết định của user - ghi ngay
+    let _ = save();
 }
 
 /// Blacklist một executable (untrusted)
@@ -193,7 +297,11 @@ pub fn blacklist(exe_hash: &str) {
         entry.flags.is_blacklisted = true;
         entry.flags.is_whitelisted = false;
         entry.reputation_score = 0.0;
+        db.mark_dirty(exe_hash);
     }
+    drop(db);
+    // Quyết định của user - ghi ngay
+    let _ = save();
 }
 
 /// Xóa whitelist/blacklist
@@ -205,7 +313,11 @@ pub fn clear_list(exe_hash: &str) {
         entry.flags.is_blacklisted = false;
         // Recalculate score
         entry.record_seen(); // This triggers recalculation
+        db.mark_dirty(exe_hash);
     }
+    drop(db);
+    // Quyết định của user - ghi ngay
+    let _ = save();
 }
 
 /// Kiểm tra executable có trusted không
@@ -274,54 +386,161 @@ pub fn get_reputation_status(exe_path: &Path) -> ProcessReputation {
 // PERSISTENCE
 // ============================================================================
 
-/// Save database to disk
+/// Flush nếu đủ số update hoặc thay đổi chưa ghi quá lâu
+fn maybe_flush() {
+    let counter = UPDATE_COUNTER.fetch_add(1, Ordering::SeqCst) + 1;
+    let now = Utc::now().timestamp();
+    let should_flush = REPUTATION_DB.read().needs_flush(counter, now);
+    if should_flush {
+        if let Err(e) = save() {
+            log::warn!("Failed to save reputation database: {}", e);
+        }
+    }
+}
+
+/// Ghi các entry đã đổi xuống SQLite (kèm decay định kỳ)
 pub fn save() -> Result<(), Box<dyn std::error::Error>> {
-    let db = REPUTATION_DB.read();
-    let path = get_db_path();
+    let (upserts, removed) = {
+        let mut db = REPUTATION_DB.write();
+        let now = Utc::now().timestamp();
+        if now - db.last_decay >= DECAY_INTERVAL_SECS {
+            db.apply_decay(now);
+        }
+        db.last_flush = now;
 
-    // Ensure directory exists
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+        let dirty = std::mem::take(&mut db.dirty);
+        let upserts: Vec<ReputationEntry> = dirty.iter()
+            .filter_map(|hash| db.entries.get(hash).cloned())
+            .collect();
+        (upserts, std::mem::take(&mut db.removed))
+    };
+
+    if upserts.is_empty() && removed.is_empty() {
+        return Ok(());
     }
 
-    let file = File::create(&path)?;
-    let writer = BufWriter::new(file);
+    let result: Result<(), Box<dyn std::error::Error>> = match CONNECTION.lock().as_mut() {
+        Some(conn) => write_entries(conn, &upserts, &removed).map_err(Into::into),
+        None => Err("Reputation database is not open".into()),
+    };
 
-    // Only save entries, path_to_hash can be rebuilt
-    serde_json::to_writer_pretty(writer, &db.entries)?;
+    if let Err(e) = result {
+        // Giữ lại để lần flush sau ghi tiếp
+        let mut db = REPUTATION_DB.write();
+        for entry in &upserts {
+            if db.entries.contains_key(&entry.exe_hash) {
+                db.dirty.insert(entry.exe_hash.clone());
+            }
+        }
+        db.removed.extend(removed);
+        return Err(e);
+    }
 
-    log::debug!("Saved {} reputation entries", db.entries.len());
+    log::debug!("Saved {} reputation entries ({} removed)", upserts.len(), removed.len());
     Ok(())
 }
 
-/// Load database from disk
-fn load_internal(db: &mut ReputationDatabase) -> Result<(), Box<dyn std::error::Error>> {
+fn open_connection() -> rusqlite::Result<Connection> {
     let path = get_db_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let conn = Connection::open(path)?;
+    init_schema(&conn)?;
+    Ok(conn)
+}
 
-    if !path.exists() {
-        return Ok(());
+fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS reputation (
+             exe_hash  TEXT PRIMARY KEY,
+             exe_path  TEXT NOT NULL,
+             last_seen INTEGER NOT NULL,
+             entry     TEXT NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS idx_reputation_last_seen ON reputation(last_seen);",
+    )
+}
+
+fn read_entries(conn: &Connection) -> rusqlite::Result<Vec<ReputationEntry>> {
+    let mut stmt = conn.prepare("SELECT entry FROM reputation")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+    let mut entries = Vec::new();
+    for json in rows {
+        match serde_json::from_str(&json?) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => log::warn!("Skipping corrupt reputation row: {}", e),
+        }
     }
+    Ok(entries)
+}
 
-    let file = File::open(&path)?;
-    let reader = BufReader::new(file);
+fn write_entries(
+    conn: &mut Connection,
+    upserts: &[ReputationEntry],
+    removed: &HashSet<String>,
+) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    {
+        let mut upsert = tx.prepare_cached(
+            "INSERT OR REPLACE INTO reputation (exe_hash, exe_path, last_seen, entry)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for entry in upserts {
+            let json = serde_json::to_string(entry)
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+            upsert.execute(params![
+                entry.exe_hash,
+                entry.exe_path.to_string_lossy().to_string(),
+                entry.last_seen,
+                json
+            ])?;
+        }
+
+        let mut delete = tx.prepare_cached("DELETE FROM reputation WHERE exe_hash = ?1")?;
+        for hash in removed {
+            delete.execute(params![hash])?;
+        }
+    }
+    tx.commit()
+}
 
-    db.entries = serde_json::from_reader(reader)?;
+/// Import file JSON cũ vào SQLite rồi đổi tên (chỉ chạy một lần)
+fn migrate_legacy_json(db: &mut ReputationDatabase) -> usize {
+    let path = get_data_dir().join(LEGACY_FILE_NAME);
+    let Ok(file) = File::open(&path) else { return 0 };
 
-    // Rebuild path_to_hash
-    for (hash, entry) in &db.entries {
-        let path_str = entry.exe_path.to_string_lossy().to_lowercase();
-        db.path_to_hash.insert(path_str, hash.clone());
+    let legacy: HashMap<String, ReputationEntry> = match serde_json::from_reader(BufReader::new(file)) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::warn!("Failed to read legacy reputation file: {}", e);
+            return 0;
+        }
+    };
+
+    let mut imported = 0;
+    for entry in legacy.into_values() {
+        if !db.entries.contains_key(&entry.exe_hash) {
+            db.mark_dirty(&entry.exe_hash);
+            db.index(entry);
+            imported += 1;
+        }
     }
 
-    Ok(())
+    let _ = fs::rename(&path, path.with_extension("json.migrated"));
+    imported
 }
 
-/// Get database file path
-fn get_db_path() -> PathBuf {
+fn get_data_dir() -> PathBuf {
     dirs::data_local_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join("OneShield")
-        .join(REPUTATION_FILE_NAME)
+}
+
+/// Get database file path
+fn get_db_path() -> PathBuf {
+    get_data_dir().join(REPUTATION_DB_NAME)
 }
 
 /// Evict old/unused entries
@@ -338,22 +557,12 @@ fn evict_old_entries(db: &mut ReputationDatabase) {
         if let Some(entry) = db.entries.remove(&hash) {
             let path_str = entry.exe_path.to_string_lossy().to_lowercase();
             db.path_to_hash.remove(&path_str);
+            db.dirty.remove(&hash);
+            db.removed.insert(hash);
         }
     }
 }
 
-// ============================================================================
-// UTILITIES
-// ============================================================================
-
-/// Compute SHA256 hash of file
-fn compute_file_hash(path: &Path) -> Result<String, std::io::Error> {
-    let mut file = File::open(path)?;
-    let mut hasher = Sha256::new();
-    std::io::copy(&mut file, &mut hasher)?;
-    Ok(format!("{:x}", hasher.finalize()))
-}
-
 // ============================================================================
 // STATISTICS
 // ============================================================================
@@ -448,14 +657,74 @@ pub fn get_most_anomalies(limit: usize) -> Vec<ReputationEntry> {
     entries
 }
 
+// ============================================================================
+// REPORT
+// ============================================================================
+
+/// Báo cáo reputation cho UI
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReputationReport {
+    pub generated_at: i64,
+    pub stats: ReputationStats,
+    /// Score cao nhất (không tính whitelist thủ công)
+    pub most_trusted: Vec<ReputationEntry>,
+    /// Score thấp nhất (không tính blacklist thủ công)
+    pub least_trusted: Vec<ReputationEntry>,
+    pub most_seen: Vec<ReputationEntry>,
+    pub most_anomalies: Vec<ReputationEntry>,
+    pub manually_listed: Vec<ReputationEntry>,
+    /// Entry lâu không thấy, score đang trôi về neutral
+    pub stale_count: usize,
+    /// Thay đổi chưa ghi xuống SQLite
+    pub pending_writes: usize,
+    pub db_path: String,
+}
+
+pub fn get_reputation_report(limit: usize) -> ReputationReport {
+    let stats = get_stats();
+    let now = Utc::now().timestamp();
+    let db = REPUTATION_DB.read();
+    let auto = |e: &ReputationEntry| !e.flags.is_whitelisted && !e.flags.is_blacklisted;
+
+    ReputationReport {
+        generated_at: now,
+        stats,
+        most_trusted: top_entries(&db, limit, auto, |a, b| b.reputation_score.total_cmp(&a.reputation_score)),
+        least_trusted: top_entries(&db, limit, auto, |a, b| a.reputation_score.total_cmp(&b.reputation_score)),
+        most_seen: top_entries(&db, limit, |_| true, |a, b| b.times_seen.cmp(&a.times_seen)),
+        most_anomalies: top_entries(&db, limit, |e| e.anomaly_count > 0, |a, b| b.anomaly_count.cmp(&a.anomaly_count)),
+        manually_listed: top_entries(&db, limit, |e| !auto(e), |a, b| b.last_seen.cmp(&a.last_seen)),
+        stale_count: db.entries.values().filter(|e| e.is_stale(now)).count(),
+        pending_writes: db.dirty.len() + db.removed.len(),
+        db_path: get_db_path().to_string_lossy().to_string(),
+    }
+}
+
+fn top_entries<F, C>(db: &ReputationDatabase, limit: usize, filter: F, cmp: C) -> Vec<ReputationEntry>
+where
+    F: Fn(&ReputationEntry) -> bool,
+    C: Fn(&ReputationEntry, &ReputationEntry) -> std::cmp::Ordering,
+{
+    let mut entries: Vec<&ReputationEntry> = db.entries.values().filter(|e| filter(e)).collect();
+    entries.sort_by(|a, b| cmp(a, b));
+    entries.into_iter().take(limit).cloned().collect()
+}
+
 /// Clear database
 pub fn clear() {
+    init();
     let mut db = REPUTATION_DB.write();
     db.entries.clear();
     db.path_to_hash.clear();
+    db.dirty.clear();
+    db.removed.clear();
+    drop(db);
 
-    // Delete file
-    let _ = fs::remove_file(get_db_path());
+    if let Some(conn) = CONNECTION.lock().as_ref() {
+        if let Err(e) = conn.execute("DELETE FROM reputation", []) {
+            log::warn!("Failed to clear reputation database: {}", e);
+        }
+    }
 
     log::info!("Reputation database cleared");
 }
@@ -500,6 +769,60 @@ mod tests {
         assert!(score_after_anomaly < score_after_clean);
     }
 
+    #[test]
+    fn test_decay() {
+        let day = 86_400;
+        let mut entry = ReputationEntry::new(
+            "decay_hash".to_string(),
+            "decay.exe".to_string(),
+            PathBuf::from("C:\\decay.exe"),
+        );
+        let start = entry.first_seen;
+        for _ in 0..4 {
+            entry.record_anomaly();
+        }
+
+        // Chưa hết một chu kỳ → không đổi
+        assert!(!entry.apply_decay(start + 10 * day));
+        assert_eq!(entry.anomaly_count, 4);
+
+        // 2 chu kỳ (60 ngày) → anomaly còn 1/4, score trôi về neutral vì stale
+        assert!(entry.apply_decay(start + 61 * day));
+        assert_eq!(entry.anomaly_count, 1);
+        assert!(entry.is_stale(start + 61 * day));
+        assert!((entry.reputation_score - 0.5).abs() < 0.2);
+
+        // Whitelist thủ công không bị decay
+        entry.flags.is_whitelisted = true;
+        entry.reputation_score = 1.0;
+        assert!(!entry.apply_decay(start + 365 * day));
+        assert_eq!(entry.reputation_score, 1.0);
+    }
+
+    #[test]
+    fn test_sqlite_roundtrip() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+
+        let mut entry = ReputationEntry::new(
+            "abc".to_string(),
+            "app.exe".to_string(),
+            PathBuf::from("C:\\app.exe"),
+        );
+        for _ in 0..9 {
+            entry.record_seen();
+        }
+        write_entries(&mut conn, &[entry.clone()], &HashSet::new()).unwrap();
+
+        let loaded = read_entries(&conn).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].times_seen, 10);
+
+        let removed: HashSet<String> = ["abc".to_string()].into_iter().collect();
+        write_entries(&mut conn, &[], &removed).unwrap();
+        assert!(read_entries(&conn).unwrap().is_empty());
+    }
+
     #[test]
     fn test_reputation_status() {
         // Clear first
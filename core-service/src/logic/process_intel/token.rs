@@ -0,0 +1,169 @@
+//! Process Token - User sở hữu, integrity level, elevation
+//!
+//! Đọc access token của process (OpenProcessToken) để biết process chạy dưới
+//! user nào, integrity level (Low / Medium / High / System) và có elevated không.
+//! Classifier dùng để xử lý tool unsigned chạy dưới SYSTEM nặng hơn nhiều so với
+//! cùng tool chạy dưới user thường.
+
+use serde::{Deserialize, Serialize};
+
+use super::types::IntegrityLevel;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessToken {
+    /// DOMAIN\user (None nếu không resolve được SID)
+    pub user: Option<String>,
+    pub integrity: IntegrityLevel,
+    /// UAC elevated token
+    pub elevated: bool,
+}
+
+impl ProcessToken {
+    /// Session admin: token elevated hoặc integrity từ High trở lên
+    pub fn is_admin_session(&self) -> bool {
+        self.elevated || self.integrity >= IntegrityLevel::High
+    }
+
+    /// Chạy dưới SYSTEM (LocalSystem service, hoặc protected process)
+    pub fn is_system(&self) -> bool {
+        self.integrity >= IntegrityLevel::System
+    }
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Đọc token của process. None nếu process đã exit hoặc không đủ quyền mở.
+pub fn query(pid: u32) -> Option<ProcessToken> {
+    platform::query(pid)
+}
+
+// ============================================================================
+// PLATFORM
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use std::ffi::c_void;
+    use windows::core::{PCWSTR, PWSTR};
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::Security::{
+        GetSidSubAuthority, GetSidSubAuthorityCount, GetTokenInformation, LookupAccountSidW,
+        TokenElevation, TokenIntegrityLevel, TokenUser, SID_NAME_USE, TOKEN_ELEVATION,
+        TOKEN_INFORMATION_CLASS, TOKEN_MANDATORY_LABEL, TOKEN_QUERY, TOKEN_USER,
+    };
+    use windows::Win32::System::Threading::{OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION};
+
+    pub fn query(pid: u32) -> Option<ProcessToken> {
+        let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }.ok()?;
+        let mut token = HANDLE::default();
+        let opened = unsafe { OpenProcessToken(process, TOKEN_QUERY, &mut token) };
+        unsafe { let _ = CloseHandle(process); }
+        opened.ok()?;
+
+        let integrity = token_info(token, TokenIntegrityLevel).map(|buf| {
+            let label = unsafe { &*(buf.as_ptr() as *const TOKEN_MANDATORY_LABEL) };
+            integrity_level(label)
+        });
+        let user = token_info(token, TokenUser).and_then(|buf| {
+            let info = unsafe { &*(buf.as_ptr() as *const TOKEN_USER) };
+            account_name(info)
+        });
+        let elevated = token_info(token, TokenElevation)
+            .map(|buf| unsafe { (*(buf.as_ptr() as *const TOKEN_ELEVATION)).TokenIsElevated != 0 })
+            .unwrap_or(false);
+
+        unsafe { let _ = CloseHandle(token); }
+
+        Some(ProcessToken {
+            user,
+            integrity: integrity?,
+            elevated,
+        })
+    }
+
+    /// GetTokenInformation: lần 1 lấy size, lần 2 đọc (buffer u64 để đủ alignment)
+    fn token_info(token: HANDLE, class: TOKEN_INFORMATION_CLASS) -> Option<Vec<u64>> {
+        let mut len = 0u32;
+        let _ = unsafe { GetTokenInformation(token, class, None, 0, &mut len) };
+        if len == 0 {
+            return None;
+        }
+        let mut buf = vec![0u64; (len as usize + 7) / 8];
+        unsafe {
+            GetTokenInformation(token, class, Some(buf.as_mut_ptr() as *mut c_void), len, &mut len)
+        }.ok()?;
+        Some(buf)
+    }
+
+    fn integrity_level(label: &TOKEN_MANDATORY_LABEL) -> IntegrityLevel {
+        let sid = label.Label.Sid;
+        let rid = unsafe {
+            let count = *GetSidSubAuthorityCount(sid);
+            *GetSidSubAuthority(sid, count.saturating_sub(1) as u32)
+        };
+        IntegrityLevel::from_rid(rid)
+    }
+
+    fn account_name(info: &TOKEN_USER) -> Option<String> {
+        let mut name = [0u16; 256];
+        let mut domain = [0u16; 256];
+        let mut name_len = name.len() as u32;
+        let mut domain_len = domain.len() as u32;
+        let mut sid_use = SID_NAME_USE::default();
+        unsafe {
+            LookupAccountSidW(
+                PCWSTR::null(),
+                info.User.Sid,
+                PWSTR(name.as_mut_ptr()),
+                &mut name_len,
+                PWSTR(domain.as_mut_ptr()),
+                &mut domain_len,
+                &mut sid_use,
+            )
+        }.ok()?;
+
+        let name = String::from_utf16_lossy(&name[..name_len as usize]);
+        let domain = String::from_utf16_lossy(&domain[..domain_len as usize]);
+        Some(if domain.is_empty() { name } else { format!("{}\\{}", domain, name) })
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::*;
+
+    pub fn query(_pid: u32) -> Option<ProcessToken> {
+        None
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integrity_from_rid() {
+        assert_eq!(IntegrityLevel::from_rid(0x0000), IntegrityLevel::Untrusted);
+        assert_eq!(IntegrityLevel::from_rid(0x1000), IntegrityLevel::Low);
+        assert_eq!(IntegrityLevel::from_rid(0x2000), IntegrityLevel::Medium);
+        assert_eq!(IntegrityLevel::from_rid(0x2100), IntegrityLevel::Medium);
+        assert_eq!(IntegrityLevel::from_rid(0x3000), IntegrityLevel::High);
+        assert_eq!(IntegrityLevel::from_rid(0x4000), IntegrityLevel::System);
+
+        let standard = ProcessToken { user: None, integrity: IntegrityLevel::Medium, elevated: false };
+        assert!(!standard.is_admin_session());
+        let service = ProcessToken { integrity: IntegrityLevel::System, ..standard };
+        assert!(service.is_admin_session() && service.is_system());
+    }
+}
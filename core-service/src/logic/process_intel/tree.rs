@@ -152,6 +152,9 @@ pub fn refresh_tree() {
     calculate_depths(&mut tree);
 
     tree.last_update = chrono::Utc::now().timestamp();
+
+    // Lưu genealogy để còn dựng ancestry sau khi parent exit
+    super::genealogy::observe(&tree.nodes);
 }
 
 /// Tính depths cho tất cả nodes
@@ -172,14 +175,18 @@ fn calculate_depths(tree: &mut ProcessTree) {
     }
 }
 
-/// Lấy chain từ pid đến root
+/// Lấy chain từ pid đến root (parent đã exit lấy từ genealogy)
 pub fn get_ancestry_chain(pid: u32) -> Vec<ProcessInfo> {
     let tree = PROCESS_TREE.read();
-    let mut chain = Vec::new();
+    let mut chain: Vec<ProcessInfo> = Vec::new();
     let mut current = Some(pid);
 
     while let Some(current_pid) = current {
         if let Some(node) = tree.nodes.get(&current_pid) {
+            // PID tái sử dụng: "parent" bắt đầu sau child → không phải parent thật
+            if chain.last().is_some_and(|child| node.info.start_time > child.start_time) {
+                break;
+            }
             chain.push(node.info.clone());
             current = node.info.parent_pid;
 
@@ -191,6 +198,24 @@ pub fn get_ancestry_chain(pid: u32) -> Vec<ProcessInfo> {
             break;
         }
     }
+    drop(tree);
+
+    // Nối phần ancestry của các parent đã exit
+    let historical = match chain.last() {
+        Some(last) if last.parent_pid.is_some() => super::genealogy::get_ancestry(last.pid, Some(last.start_time)),
+        Some(_) => Vec::new(),
+        None => super::genealogy::get_ancestry(pid, None),
+    };
+    let skip = usize::from(!chain.is_empty());
+    let mut historical: Vec<ProcessInfo> = historical.iter().skip(skip).map(|r| r.to_process_info()).collect();
+    for i in 0..historical.len() {
+        let parent_name = historical.get(i + 1).map(|p| p.name.clone());
+        historical[i].parent_name = parent_name;
+    }
+    if let (Some(last), Some(first)) = (chain.last_mut(), historical.first()) {
+        last.parent_name.get_or_insert_with(|| first.name.clone());
+    }
+    chain.extend(historical);
 
     chain
 }
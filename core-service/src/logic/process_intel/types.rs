@@ -25,6 +25,11 @@ pub enum SignatureStatus {
     Invalid {
         reason: String,
     },
+    /// Chữ ký hợp lệ về mặt crypto nhưng cert đã bị revoke / nằm trong abused signer list
+    Revoked {
+        publisher: String,
+        reason: String,
+    },
     /// Lỗi khi kiểm tra (file not found, etc.)
     Error {
         message: String,
@@ -46,9 +51,78 @@ impl SignatureStatus {
             SignatureStatus::SignedUntrusted { .. } => 2,
             SignatureStatus::Unsigned => 1,
             SignatureStatus::Invalid { .. } => 0,
+            SignatureStatus::Revoked { .. } => 0,
             SignatureStatus::Error { .. } => 0,
         }
     }
+
+    pub fn is_revoked(&self) -> bool {
+        matches!(self, SignatureStatus::Revoked { .. })
+    }
+}
+
+/// Signer bị lạm dụng (cert bị leak / dùng ký malware).
+/// Mọi field có giá trị phải khớp (AND); thumbprint / serial là hex không phân biệt hoa thường.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AbusedSigner {
+    #[serde(default)]
+    pub thumbprint: Option<String>,
+    #[serde(default)]
+    pub serial: Option<String>,
+    /// CN của publisher (exact, case-insensitive)
+    #[serde(default)]
+    pub publisher: Option<String>,
+    pub reason: String,
+}
+
+impl AbusedSigner {
+    pub fn is_valid(&self) -> bool {
+        self.thumbprint.is_some() || self.serial.is_some() || self.publisher.is_some()
+    }
+
+    pub fn matches(&self, thumbprint: &str, serial: &str, publisher: &str) -> bool {
+        if !self.is_valid() {
+            return false;
+        }
+        let hex_eq = |expected: &Option<String>, actual: &str| {
+            expected.as_ref().map(|e| normalize_hex(e) == normalize_hex(actual)).unwrap_or(true)
+        };
+        hex_eq(&self.thumbprint, thumbprint)
+            && hex_eq(&self.serial, serial)
+            && self.publisher.as_ref().map(|p| p.eq_ignore_ascii_case(publisher)).unwrap_or(true)
+    }
+}
+
+/// "43:bb 43..." → "43BB43..."
+pub fn normalize_hex(value: &str) -> String {
+    value.chars().filter(|c| c.is_ascii_hexdigit()).collect::<String>().to_uppercase()
+}
+
+// ============================================================================
+// TOKEN TYPES
+// ============================================================================
+
+/// Mandatory integrity level của process token (thấp → cao)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum IntegrityLevel {
+    Untrusted,
+    Low,
+    Medium,
+    High,
+    System,
+}
+
+impl IntegrityLevel {
+    /// RID cuối của mandatory label SID (S-1-16-xxxx)
+    pub fn from_rid(rid: u32) -> Self {
+        match rid {
+            r if r < 0x1000 => IntegrityLevel::Untrusted,
+            r if r < 0x2000 => IntegrityLevel::Low,
+            r if r < 0x3000 => IntegrityLevel::Medium, // gồm MediumPlus (0x2100)
+            r if r < 0x4000 => IntegrityLevel::High,
+            _ => IntegrityLevel::System,                // gồm Protected process (0x5000)
+        }
+    }
 }
 
 // ============================================================================
@@ -136,6 +210,15 @@ impl SpawnSeverity {
 // REPUTATION TYPES
 // ============================================================================
 
+/// Anomaly/alert count giảm một nửa sau mỗi chu kỳ này (evidence cũ nhẹ dần)
+const ANOMALY_HALF_LIFE_SECS: i64 = 30 * 86400;
+
+/// Không thấy executable quá số ngày này → score bắt đầu trôi về neutral
+const STALE_AFTER_DAYS: f32 = 30.0;
+
+/// Chu kỳ bán rã của độ lệch khỏi neutral khi entry stale
+const STALE_HALF_LIFE_DAYS: f32 = 30.0;
+
 /// Điểm reputation của một executable
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReputationEntry {
@@ -150,6 +233,9 @@ pub struct ReputationEntry {
     pub reputation_score: f32,      // 0.0 (bad) - 1.0 (trusted)
     pub signature: SignatureStatus,
     pub flags: ReputationFlags,
+    /// Lần cuối áp dụng decay cho anomaly/alert count (0 = tính từ first_seen)
+    #[serde(default)]
+    pub decayed_at: i64,
 }
 
 impl ReputationEntry {
@@ -167,6 +253,7 @@ impl ReputationEntry {
             reputation_score: 0.5, // Neutral by default
             signature: SignatureStatus::Unsigned,
             flags: ReputationFlags::default(),
+            decayed_at: now,
         }
     }
 
@@ -192,7 +279,10 @@ impl ReputationEntry {
 
     /// Tính lại reputation score
     fn recalculate_score(&mut self) {
-        let now = chrono::Utc::now().timestamp();
+        self.recalculate_score_at(chrono::Utc::now().timestamp());
+    }
+
+    fn recalculate_score_at(&mut self, now: i64) {
         let age_days = ((now - self.first_seen) as f32 / 86400.0).max(0.0);
 
         // Factors:
@@ -213,10 +303,53 @@ impl ReputationEntry {
             SignatureStatus::SignedUntrusted { .. } => 0.2,
             SignatureStatus::Unsigned => 0.0,
             SignatureStatus::Invalid { .. } => -0.2,
+            // Cert bị revoke / leak: tệ hơn unsigned, không được cộng điểm "signed"
+            SignatureStatus::Revoked { .. } => -0.3,
             SignatureStatus::Error { .. } => 0.0,
         };
 
-        self.reputation_score = (age_factor + clean_factor + signature_factor).clamp(0.0, 1.0);
+        let score = (age_factor + clean_factor + signature_factor).clamp(0.0, 1.0);
+
+        // 4. Staleness: lâu không thấy → trust (và distrust) trôi dần về neutral
+        let idle_days = (now - self.last_seen) as f32 / 86400.0 - STALE_AFTER_DAYS;
+        self.reputation_score = if idle_days > 0.0 {
+            0.5 + (score - 0.5) * 0.5f32.powf(idle_days / STALE_HALF_LIFE_DAYS)
+        } else {
+            score
+        };
+    }
+
+    /// Decay theo thời gian: anomaly/alert cũ giảm một nửa mỗi ANOMALY_HALF_LIFE_SECS,
+    /// entry stale trôi về neutral. Whitelist/blacklist thủ công không bị ảnh hưởng.
+    /// Trả về true nếu counters thay đổi (score luôn được tính lại, không cần persist).
+    pub fn apply_decay(&mut self, now: i64) -> bool {
+        if self.flags.is_whitelisted || self.flags.is_blacklisted {
+            return false;
+        }
+
+        let before = (self.anomaly_count, self.alert_count);
+        let base = if self.decayed_at > 0 { self.decayed_at } else { self.first_seen };
+        let halvings = (now - base) / ANOMALY_HALF_LIFE_SECS;
+        if halvings > 0 {
+            let shift = halvings.min(63) as u32;
+            self.anomaly_count >>= shift;
+            self.alert_count >>= shift;
+            self.decayed_at = base + halvings * ANOMALY_HALF_LIFE_SECS;
+        }
+        self.recalculate_score_at(now);
+
+        (self.anomaly_count, self.alert_count) != before
+    }
+
+    /// Entry không được thấy quá STALE_AFTER_DAYS
+    pub fn is_stale(&self, now: i64) -> bool {
+        (now - self.last_seen) as f32 / 86400.0 > STALE_AFTER_DAYS
+    }
+
+    /// Cập nhật chữ ký (re-verify) + tính lại score
+    pub fn set_signature(&mut self, signature: SignatureStatus) {
+        self.signature = signature;
+        self.recalculate_score();
     }
 
     /// Tuổi của entry (ngày)
@@ -280,6 +413,9 @@ pub struct LolbinInfo {
     pub risk_level: SpawnSeverity,
     pub mitre_techniques: &'static [&'static str],
     pub suspicious_parents: &'static [&'static str],
+    /// Argument patterns chỉ xuất hiện khi bị lạm dụng (so trên cmdline lowercase).
+    /// Bắt đầu bằng `-`/`/`: so từng token (kết thúc `:`/`=` thì so prefix), còn lại: substring.
+    pub abuse_indicators: &'static [&'static str],
 }
 
 // ============================================================================
@@ -303,10 +439,70 @@ pub const TRUSTED_PUBLISHERS: &[&str] = &[
     "Realtek Semiconductor Corp.",
 ];
 
-/// Kiểm tra publisher có trong whitelist không
+/// Kiểm tra publisher có trong whitelist không (built-in + allowlist user / cloud policy)
 pub fn is_publisher_trusted(publisher: &str) -> bool {
+    is_builtin_publisher(publisher) || super::signature::is_allowlisted_publisher(publisher, "")
+}
+
+/// Chỉ danh sách built-in (khớp substring)
+pub fn is_builtin_publisher(publisher: &str) -> bool {
     let publisher_lower = publisher.to_lowercase();
     TRUSTED_PUBLISHERS.iter().any(|&trusted| {
         publisher_lower.contains(&trusted.to_lowercase())
     })
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrustedPublisherSource {
+    Local,
+    Cloud,
+}
+
+fn default_publisher_source() -> TrustedPublisherSource {
+    TrustedPublisherSource::Local
+}
+
+/// Publisher tin cậy do user / cloud policy thêm (vd: cert ký nội bộ của tổ chức).
+/// Mọi field có giá trị phải khớp (AND). Entry có thumbprint còn được tin cả khi
+/// chain dẫn về root CA nội bộ mà máy không tin (`NotTrusted`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrustedPublisher {
+    /// CN của publisher (exact, case-insensitive)
+    #[serde(default)]
+    pub publisher: Option<String>,
+    #[serde(default)]
+    pub thumbprint: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default = "default_publisher_source")]
+    pub source: TrustedPublisherSource,
+}
+
+impl TrustedPublisher {
+    pub fn is_valid(&self) -> bool {
+        self.publisher.as_deref().map(|p| !p.trim().is_empty()).unwrap_or(false)
+            || self.thumbprint.as_deref().map(|t| !normalize_hex(t).is_empty()).unwrap_or(false)
+    }
+
+    /// Cert được pin theo thumbprint
+    pub fn is_pinned(&self) -> bool {
+        self.thumbprint.is_some()
+    }
+
+    /// `thumbprint` rỗng → chỉ entry không pin thumbprint mới khớp
+    pub fn matches(&self, publisher: &str, thumbprint: &str) -> bool {
+        if !self.is_valid() {
+            return false;
+        }
+        self.thumbprint.as_ref().map(|t| normalize_hex(t) == normalize_hex(thumbprint)).unwrap_or(true)
+            && self.publisher.as_ref().map(|p| p.trim().eq_ignore_ascii_case(publisher.trim())).unwrap_or(true)
+    }
+
+    /// Cùng publisher + thumbprint (bỏ qua comment / source)
+    pub fn same_identity(&self, other: &TrustedPublisher) -> bool {
+        let lower = |v: &Option<String>| v.as_deref().map(|s| s.trim().to_lowercase());
+        let hex = |v: &Option<String>| v.as_deref().map(normalize_hex);
+        lower(&self.publisher) == lower(&other.publisher) && hex(&self.thumbprint) == hex(&other.thumbprint)
+    }
+}
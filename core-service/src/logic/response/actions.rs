@@ -2,16 +2,19 @@
 //!
 //! Mục đích: Suspend, resume, kill processes
 //!
-//! Uses Windows API via PowerShell commands
+//! Suspend/resume go through NtSuspendProcess/NtResumeProcess (ntdll),
+//! kill and lookups still use taskkill/PowerShell commands
 
 use std::collections::HashMap;
 use std::process::Command;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::Instant;
 use parking_lot::RwLock;
 use once_cell::sync::Lazy;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
 use super::types::{ResponseAction, ActionResult, ActionError, ActionStatus};
+use crate::logic::config::SafetyConfig;
 
 // ============================================================================
 // STATE
@@ -25,6 +28,28 @@ static SUSPENDED_PROCESSES: Lazy<RwLock<HashMap<u32, SuspendedProcess>>> =
 
 const MAX_HISTORY: usize = 500;
 
+/// Số lần tối đa can thiệp 1 process trong 1 phút
+const MAX_ACTIONS_PER_MINUTE: u32 = 3;
+
+/// Giới hạn toàn máy trong 1 phút theo severity (alert không giới hạn)
+const GLOBAL_LIMIT_CONTAIN: u32 = 20; // suspend / block / quarantine
+const GLOBAL_LIMIT_KILL: u32 = 10;
+const GLOBAL_LIMIT_ISOLATE: u32 = 2;
+
+/// Actions đã được gate cho phép trong 60s gần nhất (rate limiting)
+static RECENT_EXECUTIONS: Lazy<RwLock<Vec<ExecutionStamp>>> =
+    Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Số action bị chặn bởi rate limit
+static RATE_LIMITED: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Debug, Clone, Copy)]
+struct ExecutionStamp {
+    at: DateTime<Utc>,
+    pid: Option<u32>,
+    severity: u8,
+}
+
 struct SuspendedProcess {
     pid: u32,
     name: String,
@@ -47,73 +72,29 @@ pub fn suspend_process(pid: u32) -> Result<ActionResult, ActionError> {
     // Get process name for logging
     let process_name = get_process_name(pid).unwrap_or_else(|| "Unknown".to_string());
 
-    // Use PowerShell to suspend (via debug API)
-    // Note: This requires elevated privileges
-    let ps_script = format!(
-        r#"
-        $process = Get-Process -Id {} -ErrorAction SilentlyContinue
-        if ($process) {{
-            # Suspend via NtSuspendProcess
-            $signature = @"
-            [DllImport("ntdll.dll", SetLastError = true)]
-            public static extern int NtSuspendProcess(IntPtr processHandle);
-"@
-            $ntdll = Add-Type -MemberDefinition $signature -Name 'NtDll' -Namespace 'Win32' -PassThru
-            $handle = $process.Handle
-            $result = $ntdll::NtSuspendProcess($handle)
-            if ($result -eq 0) {{
-                Write-Output "SUCCESS"
-            }} else {{
-                Write-Output "FAILED:$result"
-            }}
-        }} else {{
-            Write-Output "NOT_FOUND"
-        }}
-        "#,
-        pid
-    );
-
-    let output = Command::new("powershell")
-        .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", &ps_script])
-        .output();
+    // Suspend all threads at once (NtSuspendProcess / SIGSTOP)
+    nt_suspend_process(pid)?;
 
     let duration = start.elapsed().as_millis() as u64;
 
-    match output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-
-            if stdout.contains("SUCCESS") {
-                // Track suspended process
-                SUSPENDED_PROCESSES.write().insert(pid, SuspendedProcess {
-                    pid,
-                    name: process_name.clone(),
-                    suspended_at: Utc::now().timestamp(),
-                });
-
-                let result = ActionResult {
-                    action: ResponseAction::SuspendProcess { pid },
-                    status: ActionStatus::Success,
-                    message: format!("Suspended process {} ({})", pid, process_name),
-                    timestamp: Utc::now().timestamp(),
-                    duration_ms: duration,
-                };
+    // Track suspended process
+    SUSPENDED_PROCESSES.write().insert(pid, SuspendedProcess {
+        pid,
+        name: process_name.clone(),
+        suspended_at: Utc::now().timestamp(),
+    });
+
+    let result = ActionResult {
+        action: ResponseAction::SuspendProcess { pid },
+        status: ActionStatus::Success,
+        message: format!("Suspended process {} ({})", pid, process_name),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: duration,
+    };
 
-                record_action(result.clone());
-                log::info!("Suspended process {} ({})", pid, process_name);
-                Ok(result)
-            } else if stdout.contains("NOT_FOUND") {
-                Err(ActionError::ProcessNotFound { pid })
-            } else {
-                Err(ActionError::CommandFailed {
-                    command: "NtSuspendProcess".to_string(),
-                    exit_code: -1,
-                    stderr: stdout,
-                })
-            }
-        }
-        Err(e) => Err(ActionError::Other { message: e.to_string() }),
-    }
+    record_action(result.clone());
+    log::info!("Suspended process {} ({})", pid, process_name);
+    Ok(result)
 }
 
 /// Resume a suspended process
@@ -127,64 +108,24 @@ pub fn resume_process(pid: u32) -> Result<ActionResult, ActionError> {
         });
     }
 
-    let ps_script = format!(
-        r#"
-        $process = Get-Process -Id {} -ErrorAction SilentlyContinue
-        if ($process) {{
-            $signature = @"
-            [DllImport("ntdll.dll", SetLastError = true)]
-            public static extern int NtResumeProcess(IntPtr processHandle);
-"@
-            $ntdll = Add-Type -MemberDefinition $signature -Name 'NtDll2' -Namespace 'Win32' -PassThru
-            $handle = $process.Handle
-            $result = $ntdll::NtResumeProcess($handle)
-            if ($result -eq 0) {{
-                Write-Output "SUCCESS"
-            }} else {{
-                Write-Output "FAILED:$result"
-            }}
-        }} else {{
-            Write-Output "NOT_FOUND"
-        }}
-        "#,
-        pid
-    );
-
-    let output = Command::new("powershell")
-        .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", &ps_script])
-        .output();
+    nt_resume_process(pid)?;
 
     let duration = start.elapsed().as_millis() as u64;
 
-    match output {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-
-            if stdout.contains("SUCCESS") {
-                // Remove from tracked
-                SUSPENDED_PROCESSES.write().remove(&pid);
+    // Remove from tracked
+    SUSPENDED_PROCESSES.write().remove(&pid);
 
-                let result = ActionResult {
-                    action: ResponseAction::ResumeProcess { pid },
-                    status: ActionStatus::Success,
-                    message: format!("Resumed process {}", pid),
-                    timestamp: Utc::now().timestamp(),
-                    duration_ms: duration,
-                };
+    let result = ActionResult {
+        action: ResponseAction::ResumeProcess { pid },
+        status: ActionStatus::Success,
+        message: format!("Resumed process {}", pid),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: duration,
+    };
 
-                record_action(result.clone());
-                log::info!("Resumed process {}", pid);
-                Ok(result)
-            } else {
-                Err(ActionError::CommandFailed {
-                    command: "NtResumeProcess".to_string(),
-                    exit_code: -1,
-                    stderr: stdout,
-                })
-            }
-        }
-        Err(e) => Err(ActionError::Other { message: e.to_string() }),
-    }
+    record_action(result.clone());
+    log::info!("Resumed process {}", pid);
+    Ok(result)
 }
 
 /// Kill a process
@@ -240,22 +181,405 @@ pub fn kill_process(pid: u32, force: bool) -> Result<ActionResult, ActionError>
     }
 }
 
+/// Kill a process together with every descendant
+///
+/// Descendants come from a fresh `process_intel::tree` snapshot and are
+/// terminated bottom-up (leaves first) so a dropper cannot respawn payloads
+/// while its children are being killed. Each PID is re-checked against the
+/// start time in the snapshot right before it is killed - a node that exited
+/// and had its PID reused is skipped. Recorded as a single action.
+pub fn kill_process_tree(pid: u32) -> Result<ActionResult, ActionError> {
+    use crate::logic::process_intel::tree;
+
+    let start = Instant::now();
+
+    if !process_exists(pid) {
+        return Err(ActionError::ProcessNotFound { pid });
+    }
+
+    let process_name = get_process_name(pid).unwrap_or_else(|| "Unknown".to_string());
+
+    tree::refresh_tree();
+    let root_start = tree::get_process_info(pid).map(|p| p.start_time);
+    // get_descendants is pre-order (parent before child) - reversed it is bottom-up
+    let descendants = tree::get_descendants(pid);
+
+    let mut killed = Vec::new();
+    let mut failed = Vec::new();
+    let mut skipped = 0;
+
+    for child in descendants.iter().rev() {
+        if !is_same_process(child.pid, child.start_time) {
+            log::warn!("Kill tree: skipping {} ({}) - exited or PID reused since snapshot", child.pid, child.name);
+            skipped += 1;
+            continue;
+        }
+        match terminate(child.pid, true) {
+            Ok(()) => killed.push(child.pid),
+            Err(e) => {
+                log::warn!("Kill tree: failed to kill child {} ({}): {}", child.pid, child.name, e);
+                failed.push(child.pid);
+            }
+        }
+    }
+
+    // Root last
+    let root_result = match root_start {
+        Some(start) if is_same_process(pid, start) => terminate(pid, true),
+        _ => Err(ActionError::ProcessNotFound { pid }),
+    };
+    let duration = start.elapsed().as_millis() as u64;
+
+    for killed_pid in killed.iter().chain(std::iter::once(&pid)) {
+        SUSPENDED_PROCESSES.write().remove(killed_pid);
+    }
+
+    if let Err(e) = root_result {
+        if killed.is_empty() {
+            return Err(e);
+        }
+        failed.push(pid);
+    }
+
+    let status = if failed.is_empty() {
+        ActionStatus::Success
+    } else {
+        ActionStatus::PartialSuccess
+    };
+
+    let result = ActionResult {
+        action: ResponseAction::KillProcessTree { pid },
+        status,
+        message: format!(
+            "Killed process tree {} ({}): {} descendants killed, {} failed, {} skipped (exited or PID reused)",
+            pid, process_name, killed.len(), failed.len(), skipped
+        ),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: duration,
+    };
+
+    record_action(result.clone());
+    log::warn!("{}", result.message);
+    Ok(result)
+}
+
 /// Execute a response action
 pub fn execute_action(action: ResponseAction) -> Result<ActionResult, ActionError> {
     match action {
         ResponseAction::SuspendProcess { pid } => suspend_process(pid),
         ResponseAction::ResumeProcess { pid } => resume_process(pid),
         ResponseAction::KillProcess { pid, force } => kill_process(pid, force),
+        ResponseAction::KillProcessTree { pid } => kill_process_tree(pid),
         _ => Err(ActionError::InvalidAction {
             reason: format!("Action {:?} not handled by actions module", action.action_type()),
         }),
     }
 }
 
+// ============================================================================
+// RESPONSE GATE
+// ============================================================================
+
+/// Ai yêu cầu response action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// Detection tự động (Action Guard, playbook, ransomware profile, ...)
+    Automatic,
+    /// User / analyst (UI, approval, lệnh từ console)
+    Manual,
+}
+
+/// Kill-switch tại thời điểm quyết định
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Safety {
+    pub dry_run: bool,
+    pub auto_block: bool,
+}
+
+impl Safety {
+    pub fn current() -> Self {
+        Self {
+            dry_run: SafetyConfig::is_dry_run(),
+            auto_block: SafetyConfig::is_auto_block_enabled(),
+        }
+    }
+}
+
+/// Lý do gate không cho thực thi
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GateDenial {
+    DryRun,
+    AutoBlockDisabled,
+    RateLimited(String),
+}
+
+impl std::fmt::Display for GateDenial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GateDenial::DryRun => write!(f, "dry-run mode"),
+            GateDenial::AutoBlockDisabled => write!(f, "auto-block disabled"),
+            GateDenial::RateLimited(reason) => write!(f, "rate limited ({})", reason),
+        }
+    }
+}
+
+impl From<GateDenial> for ActionError {
+    fn from(denial: GateDenial) -> Self {
+        ActionError::NotPermitted { reason: denial.to_string() }
+    }
+}
+
+/// Kiểm tra kill-switch cho một action. Chỉ action can thiệp (severity > 1)
+/// do detection tự động yêu cầu mới bị chặn; alert, revert và action thủ công luôn qua.
+pub fn check_safety(safety: Safety, severity: u8, origin: Origin) -> Result<(), GateDenial> {
+    if severity <= 1 || origin == Origin::Manual {
+        return Ok(());
+    }
+    if safety.dry_run {
+        return Err(GateDenial::DryRun);
+    }
+    if !safety.auto_block {
+        return Err(GateDenial::AutoBlockDisabled);
+    }
+    Ok(())
+}
+
+/// Gate chung của response layer - mọi action phải qua đây ngay trước khi thực thi:
+/// kill-switch (chỉ action tự động) rồi rate limit (mọi origin)
+pub fn authorize(severity: u8, pid: Option<u32>, origin: Origin) -> Result<(), GateDenial> {
+    authorize_with(Safety::current(), severity, pid, origin)
+}
+
+/// `authorize` với kill-switch đã snapshot từ trước
+pub fn authorize_with(safety: Safety, severity: u8, pid: Option<u32>, origin: Origin) -> Result<(), GateDenial> {
+    check_safety(safety, severity, origin)?;
+    reserve_rate(severity, pid).map_err(|reason| {
+        RATE_LIMITED.fetch_add(1, Ordering::SeqCst);
+        GateDenial::RateLimited(reason)
+    })
+}
+
+/// Thực thi action sau khi qua gate
+pub fn execute_gated(action: ResponseAction, origin: Origin) -> Result<ActionResult, ActionError> {
+    if let Err(denial) = authorize(action.severity(), action.target_pid(), origin) {
+        log::info!("[{}] skipped {}", denial, action.description());
+        return Err(denial.into());
+    }
+    execute_action(action)
+}
+
+/// Nhóm rate limit theo severity và giới hạn toàn máy / phút (None = alert, không giới hạn)
+fn rate_bucket(severity: u8) -> Option<(&'static str, u32)> {
+    match severity {
+        0..=1 => None,
+        2..=3 => Some(("contain", GLOBAL_LIMIT_CONTAIN)),
+        4 => Some(("kill", GLOBAL_LIMIT_KILL)),
+        _ => Some(("isolate", GLOBAL_LIMIT_ISOLATE)),
+    }
+}
+
+/// Check rate limit và giữ chỗ trong cùng một write lock, nên hai response
+/// đồng thời không thể cùng lọt qua slot cuối. Slot tính theo lần thực thi
+/// (kể cả thất bại). Err = lý do bị chặn.
+fn reserve_rate(severity: u8, pid: Option<u32>) -> Result<(), String> {
+    let (bucket, limit) = match rate_bucket(severity) {
+        Some(b) => b,
+        None => return Ok(()),
+    };
+
+    let now = Utc::now();
+    let cutoff = now - chrono::Duration::seconds(60);
+    let mut recent = RECENT_EXECUTIONS.write();
+    recent.retain(|s| s.at > cutoff);
+
+    if let Some(pid) = pid {
+        let per_process = recent.iter()
+            .filter(|s| s.pid == Some(pid) && s.severity > 1)
+            .count() as u32;
+        if per_process >= MAX_ACTIONS_PER_MINUTE {
+            return Err(format!("PID {} already hit {} actions in the last minute", pid, per_process));
+        }
+    }
+
+    let global = recent.iter()
+        .filter(|s| rate_bucket(s.severity).map(|(b, _)| b) == Some(bucket))
+        .count() as u32;
+    if global >= limit {
+        return Err(format!("global {} limit reached ({} per minute)", bucket, limit));
+    }
+
+    recent.push(ExecutionStamp { at: now, pid, severity });
+    Ok(())
+}
+
+/// Counters rate limit (60s gần nhất) cho status
+pub fn rate_limit_status() -> serde_json::Value {
+    let cutoff = Utc::now() - chrono::Duration::seconds(60);
+    let recent = RECENT_EXECUTIONS.read();
+    let count = |bucket: Option<&str>| recent.iter()
+        .filter(|s| s.at > cutoff && rate_bucket(s.severity).map(|(b, _)| b) == bucket)
+        .count();
+
+    serde_json::json!({
+        "per_process_limit": MAX_ACTIONS_PER_MINUTE,
+        "last_minute": {
+            "alert": count(None),
+            "contain": count(Some("contain")),
+            "kill": count(Some("kill")),
+            "isolate": count(Some("isolate")),
+        },
+        "limits": {
+            "contain": GLOBAL_LIMIT_CONTAIN,
+            "kill": GLOBAL_LIMIT_KILL,
+            "isolate": GLOBAL_LIMIT_ISOLATE,
+        },
+        "rate_limited_total": RATE_LIMITED.load(Ordering::SeqCst),
+    })
+}
+
+pub(crate) fn reset_rate_limits() {
+    RECENT_EXECUTIONS.write().clear();
+    RATE_LIMITED.store(0, Ordering::SeqCst);
+}
+
+// ============================================================================
+// NATIVE SUSPEND / RESUME
+// ============================================================================
+
+/// Suspend all threads of a process.
+///
+/// Windows: `NtSuspendProcess` on a handle opened with `PROCESS_SUSPEND_RESUME`
+/// (requires the same or higher integrity level as the target).
+/// Other platforms: `SIGSTOP`.
+pub(crate) fn nt_suspend_process(pid: u32) -> Result<(), ActionError> {
+    #[cfg(windows)]
+    {
+        ntapi::suspend(pid)
+    }
+    #[cfg(not(windows))]
+    {
+        send_signal(pid, "-STOP")
+    }
+}
+
+/// Resume all threads of a process previously suspended with [`nt_suspend_process`].
+pub(crate) fn nt_resume_process(pid: u32) -> Result<(), ActionError> {
+    #[cfg(windows)]
+    {
+        ntapi::resume(pid)
+    }
+    #[cfg(not(windows))]
+    {
+        send_signal(pid, "-CONT")
+    }
+}
+
+#[cfg(windows)]
+mod ntapi {
+    use windows::Win32::Foundation::{CloseHandle, HANDLE, NTSTATUS};
+    use windows::Win32::System::Threading::{OpenProcess, PROCESS_SUSPEND_RESUME};
+
+    use super::ActionError;
+
+    // Undocumented but stable since NT 5.1 - not exposed by the windows crate
+    #[link(name = "ntdll")]
+    extern "system" {
+        fn NtSuspendProcess(process_handle: HANDLE) -> NTSTATUS;
+        fn NtResumeProcess(process_handle: HANDLE) -> NTSTATUS;
+    }
+
+    const STATUS_ACCESS_DENIED: i32 = 0xC0000022_u32 as i32;
+
+    pub fn suspend(pid: u32) -> Result<(), ActionError> {
+        call(pid, "NtSuspendProcess", |h| unsafe { NtSuspendProcess(h) })
+    }
+
+    pub fn resume(pid: u32) -> Result<(), ActionError> {
+        call(pid, "NtResumeProcess", |h| unsafe { NtResumeProcess(h) })
+    }
+
+    fn call(pid: u32, name: &str, f: impl FnOnce(HANDLE) -> NTSTATUS) -> Result<(), ActionError> {
+        let handle = unsafe { OpenProcess(PROCESS_SUSPEND_RESUME, false, pid) }
+            .map_err(|e| ActionError::AccessDenied {
+                reason: format!("OpenProcess({}) failed: {}", pid, e),
+            })?;
+
+        let status = f(handle);
+        unsafe {
+            let _ = CloseHandle(handle);
+        }
+
+        if status.is_ok() {
+            Ok(())
+        } else if status.0 == STATUS_ACCESS_DENIED {
+            Err(ActionError::AccessDenied {
+                reason: format!("{} denied for PID {}", name, pid),
+            })
+        } else {
+            Err(ActionError::CommandFailed {
+                command: name.to_string(),
+                exit_code: status.0,
+                stderr: format!("NTSTATUS 0x{:08X}", status.0),
+            })
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn send_signal(pid: u32, signal: &str) -> Result<(), ActionError> {
+    let output = Command::new("kill")
+        .args([signal, &pid.to_string()])
+        .output()
+        .map_err(|e| ActionError::Other { message: e.to_string() })?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(ActionError::CommandFailed {
+            command: format!("kill {}", signal),
+            exit_code: output.status.code().unwrap_or(-1),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+}
+
 // ============================================================================
 // UTILITIES
 // ============================================================================
 
+/// Terminate a single PID without bookkeeping (used by tree kill)
+fn terminate(pid: u32, force: bool) -> Result<(), ActionError> {
+    #[cfg(windows)]
+    let output = {
+        let pid_str = pid.to_string();
+        let mut args = vec!["/PID", pid_str.as_str()];
+        if force {
+            args.insert(0, "/F");
+        }
+        Command::new("taskkill").args(&args).output()
+    };
+
+    #[cfg(not(windows))]
+    let output = Command::new("kill")
+        .args([if force { "-9" } else { "-15" }, &pid.to_string()])
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => Err(ActionError::CommandFailed {
+            command: if cfg!(windows) { "taskkill" } else { "kill" }.to_string(),
+            exit_code: output.status.code().unwrap_or(-1),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        }),
+        Err(e) => Err(ActionError::Other { message: e.to_string() }),
+    }
+}
+
+/// PID still belongs to the process with this start time (not exited / reused)
+fn is_same_process(pid: u32, start_time: i64) -> bool {
+    super::expiry::process_start_time(pid) == Some(start_time as u64)
+}
+
 fn process_exists(pid: u32) -> bool {
     Command::new("powershell")
         .args([
@@ -283,7 +607,7 @@ fn get_process_name(pid: u32) -> Option<String> {
         })
 }
 
-fn record_action(result: ActionResult) {
+pub(crate) fn record_action(result: ActionResult) {
     let mut history = ACTION_HISTORY.write();
     history.push(result);
 
@@ -375,4 +699,37 @@ mod tests {
         assert!(action.description().contains("1234"));
         assert!(action.description().contains("Force"));
     }
+
+    #[test]
+    fn test_gate_blocks_automatic_only() {
+        let dry = Safety { dry_run: true, auto_block: true };
+        let off = Safety { dry_run: false, auto_block: false };
+        let kill = ResponseAction::KillProcess { pid: 1, force: true }.severity();
+
+        assert_eq!(check_safety(dry, kill, Origin::Automatic), Err(GateDenial::DryRun));
+        assert_eq!(check_safety(off, kill, Origin::Automatic), Err(GateDenial::AutoBlockDisabled));
+        assert!(check_safety(dry, kill, Origin::Manual).is_ok());
+        // Alert / revert không bị chặn
+        assert!(check_safety(dry, 1, Origin::Automatic).is_ok());
+        assert!(check_safety(off, ResponseAction::ReleaseHost.severity(), Origin::Automatic).is_ok());
+    }
+
+    #[test]
+    fn test_rate_limit_per_process() {
+        let pid = Some(987_650);
+        for _ in 0..MAX_ACTIONS_PER_MINUTE {
+            assert!(authorize(2, pid, Origin::Manual).is_ok());
+        }
+        assert!(matches!(authorize(4, pid, Origin::Manual), Err(GateDenial::RateLimited(_))));
+        // Alert không bị giới hạn
+        assert!(authorize(1, pid, Origin::Manual).is_ok());
+        RECENT_EXECUTIONS.write().retain(|s| s.pid != pid);
+    }
+
+    #[test]
+    fn test_kill_tree_action_type() {
+        let action = ResponseAction::KillProcessTree { pid: 42 };
+        assert_eq!(action.action_type(), "kill_process_tree");
+        assert!(action.description().contains("42"));
+    }
 }
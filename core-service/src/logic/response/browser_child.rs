@@ -0,0 +1,304 @@
+//! Browser-Child Containment (Phase 5)
+//!
+//! Mục đích: Xử lý threat được spawn từ browser mà không đụng tới browser session
+//!
+//! 1. Tìm browser tổ tiên gần nhất qua process tree
+//! 2. Kill nhánh con vi phạm (process con trực tiếp của browser + cây con của nó)
+//! 3. Quarantine file tải về (executable nằm trong thư mục user-writable)
+//! 4. Block hash: process mới có cùng hash bị kill ngay khi xuất hiện
+//!
+//! Nhẹ hơn nhiều so với `IsolateSession` - user vẫn giữ nguyên các tab đang mở.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::process_intel::{tree, reputation, hashing, ProcessInfo};
+use super::types::{ActionResult, ActionError, ActionStatus, ResponseAction};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const BROWSERS: &[&str] = &[
+    "chrome.exe", "msedge.exe", "firefox.exe", "brave.exe", "opera.exe",
+    "vivaldi.exe", "iexplore.exe",
+];
+
+/// Thư mục user-writable - executable ở đây coi là file tải về
+const DOWNLOAD_MARKERS: &[&str] = &["\\downloads\\", "\\appdata\\", "\\temp\\", "\\desktop\\", "/downloads/", "/tmp/"];
+
+const BLOCKED_HASHES_FILE: &str = "blocked_hashes.json";
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockedHash {
+    pub sha256: String,
+    pub file_name: String,
+    pub reason: String,
+    pub blocked_at: i64,
+}
+
+/// Kết quả containment
+#[derive(Debug, Clone, Serialize)]
+pub struct BrowserContainment {
+    pub browser_pid: u32,
+    pub browser_name: String,
+    /// Process con trực tiếp của browser đã bị kill (cùng cây con)
+    pub killed_pid: u32,
+    pub quarantine_id: Option<String>,
+    pub blocked_hash: Option<String>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static BLOCKED_HASHES: Lazy<RwLock<HashMap<String, BlockedHash>>> =
+    Lazy::new(|| RwLock::new(load_blocked()));
+
+/// PIDs đã kiểm tra hash (tránh hash lại mỗi lần quét)
+static SEEN_PIDS: Lazy<RwLock<HashSet<u32>>> = Lazy::new(|| RwLock::new(HashSet::new()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+pub fn is_browser(name: &str) -> bool {
+    let name = name.rsplit(['\\', '/']).next().unwrap_or(name).to_lowercase();
+    BROWSERS.contains(&name.as_str())
+}
+
+/// Tìm browser tổ tiên gần nhất của `pid`.
+/// Trả về (browser, process con trực tiếp của browser trên nhánh dẫn tới `pid`).
+pub fn find_browser_ancestor(pid: u32) -> Option<(ProcessInfo, ProcessInfo)> {
+    let chain = tree::get_ancestry_chain(pid);
+    // Bản thân target là browser → không phải browser-child
+    if chain.first().map(|p| is_browser(&p.name)).unwrap_or(true) {
+        return None;
+    }
+    let idx = chain.iter().position(|p| is_browser(&p.name))?;
+    Some((chain[idx].clone(), chain[idx - 1].clone()))
+}
+
+/// Containment cho threat spawn từ browser
+pub fn contain(pid: u32, reason: &str) -> Result<(ActionResult, BrowserContainment), ActionError> {
+    let start = Instant::now();
+
+    tree::refresh_tree();
+    let (browser, offender) = find_browser_ancestor(pid).ok_or_else(|| ActionError::InvalidAction {
+        reason: format!("PID {} was not spawned by a browser", pid),
+    })?;
+
+    log::warn!(
+        "Browser-child containment: {} (PID {}) spawned from {} (PID {})",
+        offender.name, offender.pid, browser.name, browser.pid
+    );
+
+    // 1. Kill nhánh vi phạm (không đụng browser)
+    super::actions::kill_process_tree(offender.pid)?;
+
+    // 2 + 3. Quarantine + block hash cho các executable tải về trong nhánh
+    let target = tree::get_process_info(pid).unwrap_or_else(|| offender.clone());
+    let mut quarantine_id = None;
+    let mut blocked_hash = None;
+    for exe in [&target, &offender].iter().filter_map(|p| p.exe_path.as_ref()) {
+        if !is_download_path(exe) || !exe.exists() {
+            continue;
+        }
+        if let Some(hash) = file_hash(exe) {
+            block_hash(&hash, exe, reason);
+            blocked_hash.get_or_insert(hash);
+        }
+        match super::file_quarantine::quarantine_file(exe, reason, None) {
+            Ok(entry) => {
+                quarantine_id.get_or_insert(entry.id);
+            }
+            Err(e) => log::warn!("Failed to quarantine {}: {}", exe.display(), e),
+        }
+    }
+
+    let containment = BrowserContainment {
+        browser_pid: browser.pid,
+        browser_name: browser.name.clone(),
+        killed_pid: offender.pid,
+        quarantine_id,
+        blocked_hash,
+    };
+
+    let result = ActionResult {
+        action: ResponseAction::ContainBrowserChild { pid },
+        status: ActionStatus::Success,
+        message: format!(
+            "Contained {} spawned by {} (browser left running){}{}",
+            offender.name,
+            browser.name,
+            if containment.quarantine_id.is_some() { ", download quarantined" } else { "" },
+            if containment.blocked_hash.is_some() { ", hash blocked" } else { "" },
+        ),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    };
+    super::actions::record_action(result.clone());
+
+    Ok((result, containment))
+}
+
+/// Thêm hash vào blocklist (và đánh dấu blacklist trong reputation DB)
+pub fn block_hash(sha256: &str, exe: &Path, reason: &str) {
+    let sha256 = sha256.to_lowercase();
+    let file_name = exe.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    reputation::update_reputation(exe, true, true);
+    reputation::blacklist(&sha256);
+
+    BLOCKED_HASHES.write().insert(sha256.clone(), BlockedHash {
+        sha256,
+        file_name,
+        reason: reason.to_string(),
+        blocked_at: Utc::now().timestamp(),
+    });
+    save_blocked();
+}
+
+/// Gỡ hash khỏi blocklist
+pub fn unblock_hash(sha256: &str) -> bool {
+    let sha256 = sha256.to_lowercase();
+    let removed = BLOCKED_HASHES.write().remove(&sha256).is_some();
+    if removed {
+        reputation::clear_list(&sha256);
+        save_blocked();
+    }
+    removed
+}
+
+pub fn get_blocked_hashes() -> Vec<BlockedHash> {
+    let mut list: Vec<_> = BLOCKED_HASHES.read().values().cloned().collect();
+    list.sort_by(|a, b| b.blocked_at.cmp(&a.blocked_at));
+    list
+}
+
+/// Kill mọi process mới có hash nằm trong blocklist. Gọi định kỳ từ analysis loop.
+///
+/// Đi qua Action Guard (whitelist, dry-run, auto-block, rate limit, ActionRecord).
+/// Trả về số process đã được xử lý (kill / chờ approval / simulated).
+pub fn enforce_hash_blocks() -> usize {
+    use crate::logic::action_guard::{self, ActionType};
+
+    if BLOCKED_HASHES.read().is_empty() {
+        return 0;
+    }
+
+    let mut system = sysinfo::System::new();
+    system.refresh_processes();
+
+    let mut handled = 0;
+    let mut alive = HashSet::new();
+    for (pid, process) in system.processes() {
+        let pid = pid.as_u32();
+        alive.insert(pid);
+        if !SEEN_PIDS.write().insert(pid) {
+            continue;
+        }
+        let hash = match process.exe().and_then(file_hash) {
+            Some(h) => h,
+            None => continue,
+        };
+        if BLOCKED_HASHES.read().contains_key(&hash) {
+            log::error!("Blocked hash executed: {} (PID {})", process.name(), pid);
+            match action_guard::execute_action(
+                ActionType::KillProcess,
+                Some(pid),
+                process.name(),
+                1.0,
+                vec!["BLOCKED_HASH".to_string(), format!("sha256:{}", hash)],
+                true,
+            ) {
+                Ok(result) => {
+                    log::warn!("Blocked hash {}: {}", hash, result.message);
+                    handled += 1;
+                }
+                Err(e) => log::warn!("Blocked hash {} on PID {} not enforced: {}", hash, pid, e),
+            }
+        }
+    }
+
+    SEEN_PIDS.write().retain(|pid| alive.contains(pid));
+    handled
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+fn is_download_path(path: &Path) -> bool {
+    let lower = path.to_string_lossy().to_lowercase();
+    DOWNLOAD_MARKERS.iter().any(|m| lower.contains(m))
+}
+
+fn file_hash(path: &Path) -> Option<String> {
+    hashing::sha256_file(path)
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn blocked_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(BLOCKED_HASHES_FILE)
+}
+
+fn load_blocked() -> HashMap<String, BlockedHash> {
+    fs::read_to_string(blocked_path())
+        .ok()
+        .and_then(|c| serde_json::from_str::<Vec<BlockedHash>>(&c).ok())
+        .map(|list| list.into_iter().map(|b| (b.sha256.clone(), b)).collect())
+        .unwrap_or_default()
+}
+
+fn save_blocked() {
+    let path = blocked_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let list: Vec<_> = BLOCKED_HASHES.read().values().cloned().collect();
+    if let Ok(json) = serde_json::to_string_pretty(&list) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_browser() {
+        assert!(is_browser("chrome.exe"));
+        assert!(is_browser("C:\\Program Files\\Mozilla Firefox\\FIREFOX.EXE"));
+        assert!(!is_browser("chromedriver.exe"));
+    }
+
+    #[test]
+    fn test_is_download_path() {
+        assert!(is_download_path(Path::new("C:\\Users\\bob\\Downloads\\setup.exe")));
+        assert!(is_download_path(Path::new("C:\\Users\\bob\\AppData\\Local\\Temp\\x.exe")));
+        assert!(!is_download_path(Path::new("C:\\Windows\\System32\\cmd.exe")));
+    }
+}
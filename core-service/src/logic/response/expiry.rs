@@ -0,0 +1,343 @@
+//! Time-limited Actions (Phase 5)
+//!
+//! Mục đích: Tự động revert suspend / network block / host isolation khi hết hạn
+//!
+//! Background scheduler kiểm tra mỗi `TICK_SECS`, revert các action đã hết hạn
+//! và ghi telemetry `ActionReverted`. Lịch được lưu ra disk vì netsh rules và
+//! host isolation vẫn tồn tại sau khi agent restart. Revert theo PID lưu kèm
+//! start time của process để không resume nhầm process đã tái sử dụng PID.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::action_guard::{self, ActionType};
+use crate::logic::telemetry::{self, SecurityEvent, ProcessInfo as TelemetryProcessInfo};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const TICK_SECS: u64 = 5;
+const SCHEDULE_FILE: &str = "scheduled_reverts.json";
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// What to undo when the action expires
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RevertKind {
+    ResumeProcess { pid: u32 },
+    UnblockNetwork { pid: u32 },
+    UnblockDestination { target: String },
+    ReleaseHost,
+    RestoreFile { quarantine_id: String },
+    RestoreDirectory { group_id: String },
+    RestorePersistence { snapshot_id: String },
+    UnblockUsb { block_id: String },
+}
+
+impl RevertKind {
+    /// Action type được revert (cho telemetry)
+    pub fn action_type(&self) -> Option<ActionType> {
+        match self {
+            RevertKind::ResumeProcess { .. } => Some(ActionType::SuspendProcess),
+            RevertKind::UnblockNetwork { .. } => Some(ActionType::BlockNetworkIO),
+            RevertKind::UnblockDestination { .. } => Some(ActionType::BlockDestination),
+            RevertKind::ReleaseHost => Some(ActionType::IsolateHost),
+            RevertKind::RestoreFile { .. } | RevertKind::RestoreDirectory { .. } => Some(ActionType::QuarantineFile),
+            RevertKind::RestorePersistence { .. } => Some(ActionType::RemovePersistence),
+            RevertKind::UnblockUsb { .. } => Some(ActionType::BlockUsb),
+        }
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        match self {
+            RevertKind::ResumeProcess { pid } | RevertKind::UnblockNetwork { pid } => Some(*pid),
+            _ => None,
+        }
+    }
+}
+
+/// A scheduled revert
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledRevert {
+    pub id: String,
+    pub kind: RevertKind,
+    pub target_name: String,
+    pub created_at: i64,
+    pub expires_at: i64,
+    /// Start time của process khi schedule (revert theo PID)
+    #[serde(default)]
+    pub process_start: Option<u64>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static SCHEDULED: Lazy<RwLock<Vec<ScheduledRevert>>> =
+    Lazy::new(|| RwLock::new(load_schedule()));
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Schedule a revert `duration_secs` from now. Replaces an existing schedule
+/// for the same target so re-running an action extends its expiry.
+pub fn schedule(kind: RevertKind, target_name: &str, duration_secs: u64) -> ScheduledRevert {
+    let now = Utc::now().timestamp();
+    let entry = ScheduledRevert {
+        id: uuid::Uuid::new_v4().to_string(),
+        kind: kind.clone(),
+        target_name: target_name.to_string(),
+        created_at: now,
+        expires_at: now + duration_secs as i64,
+        process_start: kind.pid().and_then(process_start_time),
+    };
+
+    {
+        let mut scheduled = SCHEDULED.write();
+        scheduled.retain(|s| s.kind != kind);
+        scheduled.push(entry.clone());
+    }
+    save_schedule();
+
+    log::info!("Scheduled auto-revert of {:?} in {}s", kind, duration_secs);
+    entry
+}
+
+/// Cancel a scheduled revert (action stays in place)
+pub fn cancel(id: &str) -> bool {
+    let removed = {
+        let mut scheduled = SCHEDULED.write();
+        let before = scheduled.len();
+        scheduled.retain(|s| s.id != id);
+        scheduled.len() != before
+    };
+    if removed {
+        save_schedule();
+    }
+    removed
+}
+
+/// Drop schedules targeting something that was reverted manually
+pub fn forget(kind: &RevertKind) {
+    let removed = {
+        let mut scheduled = SCHEDULED.write();
+        let before = scheduled.len();
+        scheduled.retain(|s| &s.kind != kind);
+        scheduled.len() != before
+    };
+    if removed {
+        save_schedule();
+    }
+}
+
+/// All pending schedules (soonest first)
+pub fn get_scheduled() -> Vec<ScheduledRevert> {
+    let mut list = SCHEDULED.read().clone();
+    list.sort_by_key(|s| s.expires_at);
+    list
+}
+
+/// Start the background scheduler (idempotent)
+pub fn start() {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(|| {
+        log::info!("Action expiry scheduler started ({} pending)", SCHEDULED.read().len());
+        loop {
+            std::thread::sleep(Duration::from_secs(TICK_SECS));
+            run_due(Utc::now().timestamp());
+        }
+    });
+}
+
+// ============================================================================
+// SCHEDULER
+// ============================================================================
+
+fn run_due(now: i64) {
+    let due = take_due(&mut SCHEDULED.write(), now);
+    if due.is_empty() {
+        return;
+    }
+
+    for entry in due {
+        let outcome = check_process_identity(&entry).and_then(|_| revert(&entry.kind));
+        let (success, message) = match &outcome {
+            Ok(msg) => (true, msg.clone()),
+            Err(e) => (false, e.clone()),
+        };
+
+        if success {
+            log::info!("Auto-reverted {:?}: {}", entry.kind, message);
+        } else {
+            log::warn!("Auto-revert of {:?} failed: {}", entry.kind, message);
+        }
+
+        telemetry::record(SecurityEvent::action_reverted(
+            TelemetryProcessInfo::new(entry.kind.pid().unwrap_or(0), &entry.target_name),
+            entry.kind.action_type(),
+            "expired",
+            success,
+        ));
+    }
+
+    save_schedule();
+}
+
+fn take_due(scheduled: &mut Vec<ScheduledRevert>, now: i64) -> Vec<ScheduledRevert> {
+    let (due, rest): (Vec<_>, Vec<_>) = scheduled.drain(..).partition(|s| s.expires_at <= now);
+    *scheduled = rest;
+    due
+}
+
+/// Process bị suspend phải còn là đúng process lúc schedule. Xóa rule network
+/// theo PID là dọn dẹp nên vẫn chạy khi process đã thoát.
+fn check_process_identity(entry: &ScheduledRevert) -> Result<(), String> {
+    let (RevertKind::ResumeProcess { pid }, Some(expected)) = (&entry.kind, entry.process_start) else {
+        return Ok(());
+    };
+
+    match process_start_time(*pid) {
+        Some(start) if start == expected => Ok(()),
+        Some(_) => Err(format!("PID {} đã được tái sử dụng bởi process khác, bỏ qua resume", pid)),
+        None => Err(format!("Process {} đã thoát", pid)),
+    }
+}
+
+pub(super) fn process_start_time(pid: u32) -> Option<u64> {
+    let mut system = sysinfo::System::new();
+    let spid = sysinfo::Pid::from_u32(pid);
+    if !system.refresh_process(spid) {
+        return None;
+    }
+    system.process(spid).map(|p| p.start_time())
+}
+
+/// Đảo ngược một action (dùng chung cho expiry và undo)
+pub fn revert(kind: &RevertKind) -> Result<String, String> {
+    match kind {
+        RevertKind::ResumeProcess { pid } => action_guard::resume_process(*pid)
+            .map(|r| r.message)
+            .map_err(|e| e.to_string()),
+        RevertKind::UnblockNetwork { pid } => action_guard::unblock_network_io(*pid)
+            .map(|r| r.message)
+            .map_err(|e| e.to_string()),
+        RevertKind::UnblockDestination { target } => super::network::unblock_destination(target)
+            .map(|r| r.message)
+            .map_err(|e| e.to_string()),
+        RevertKind::ReleaseHost => super::network::release_host()
+            .map(|r| r.message)
+            .map_err(|e| e.to_string()),
+        RevertKind::RestoreFile { quarantine_id } => super::file_quarantine::restore_file(quarantine_id)
+            .map(|path| format!("Restored {}", path.display()))
+            .map_err(|e| e.to_string()),
+        RevertKind::RestoreDirectory { group_id } => super::file_quarantine::restore_directory(group_id)
+            .map(|path| format!("Restored {}", path.display()))
+            .map_err(|e| e.to_string()),
+        RevertKind::RestorePersistence { snapshot_id } => super::registry::restore_persistence(snapshot_id)
+            .map(|r| r.message)
+            .map_err(|e| e.to_string()),
+        RevertKind::UnblockUsb { block_id } => super::usb::unblock_usb(block_id)
+            .map(|r| r.message)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn schedule_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(SCHEDULE_FILE)
+}
+
+fn load_schedule() -> Vec<ScheduledRevert> {
+    fs::read_to_string(schedule_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_schedule() {
+    let path = schedule_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*SCHEDULED.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, kind: RevertKind, expires_at: i64, process_start: Option<u64>) -> ScheduledRevert {
+        ScheduledRevert {
+            id: id.to_string(),
+            kind,
+            target_name: format!("{}.exe", id),
+            created_at: 0,
+            expires_at,
+            process_start,
+        }
+    }
+
+    #[test]
+    fn test_take_due_only_expired() {
+        let mut scheduled = vec![
+            entry("a", RevertKind::ResumeProcess { pid: 1 }, 100, None),
+            entry("b", RevertKind::UnblockNetwork { pid: 2 }, 200, None),
+        ];
+
+        let due = take_due(&mut scheduled, 150);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, "a");
+        assert_eq!(scheduled.len(), 1);
+        assert_eq!(scheduled[0].id, "b");
+    }
+
+    #[test]
+    fn test_resume_checks_process_identity() {
+        let pid = std::process::id();
+        let start = process_start_time(pid).expect("own process is running");
+
+        let same = entry("same", RevertKind::ResumeProcess { pid }, 0, Some(start));
+        assert!(check_process_identity(&same).is_ok());
+
+        let reused = entry("reused", RevertKind::ResumeProcess { pid }, 0, Some(start + 1));
+        assert!(check_process_identity(&reused).is_err());
+
+        // Schedule cũ (không có start time) và unblock network không bị chặn
+        assert!(check_process_identity(&entry("legacy", RevertKind::ResumeProcess { pid }, 0, None)).is_ok());
+        assert!(check_process_identity(&entry("net", RevertKind::UnblockNetwork { pid }, 0, Some(start + 1))).is_ok());
+    }
+
+    #[test]
+    fn test_revert_kind_serde() {
+        let json = serde_json::to_string(&RevertKind::UnblockNetwork { pid: 42 }).unwrap();
+        assert!(json.contains("\"kind\":\"unblock_network\""));
+    }
+}
@@ -1,11 +1,15 @@
 //! File Quarantine Module (Phase 5)
 //!
-//! Mục đích: Di chuyển files nghi ngờ vào quarantine folder
+//! Mục đích: Di chuyển files nghi ngờ vào quarantine vault
 //!
 //! Features:
-//! - Move files to secure quarantine location
-//! - Track metadata for restore
+//! - Encrypt files into an access-restricted vault (AES-256-GCM, per-file key)
+//! - Per-file keys wrapped by a vault master key
+//! - Track metadata (original path, hash, ACL, attributes) for restore
 //! - Secure deletion
+//! - Directory quarantine: cả cây thư mục trong 1 lần, restore atomic qua staging dir
+//!
+//! File trong vault không thể bị execute hay re-drop vì nội dung đã bị mã hóa.
 
 use std::collections::HashMap;
 use std::fs;
@@ -15,8 +19,11 @@ use once_cell::sync::Lazy;
 use chrono::Utc;
 use sha2::{Sha256, Digest};
 use uuid::Uuid;
+use aes_gcm::{Aes256Gcm, Key, Nonce, aead::{Aead, KeyInit}};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use rand::RngCore;
 
-use super::types::{QuarantineEntry, ActionResult, ActionError, ActionStatus, ResponseAction};
+use super::types::{QuarantineEntry, QuarantineGroup, ActionResult, ActionError, ActionStatus, ResponseAction};
 
 // ============================================================================
 // CONSTANTS
@@ -25,6 +32,13 @@ use super::types::{QuarantineEntry, ActionResult, ActionError, ActionStatus, Res
 const QUARANTINE_FOLDER: &str = "quarantine";
 const METADATA_FILE: &str = "quarantine_metadata.json";
 const MAX_QUARANTINE_SIZE_MB: u64 = 500;
+const MASTER_KEY_FILE: &str = "vault.key";
+const VAULT_EXTENSION: &str = "vault";
+const NONCE_LEN: usize = 12;
+const GROUPS_FILE: &str = "quarantine_groups.json";
+/// Giới hạn cho directory quarantine
+const MAX_DIR_FILES: usize = 1000;
+const MAX_DIR_DEPTH: usize = 16;
 
 // ============================================================================
 // STATE
@@ -39,23 +53,36 @@ static QUARANTINE_MANAGER: Lazy<RwLock<QuarantineManager>> =
 
 pub struct QuarantineManager {
     entries: HashMap<String, QuarantineEntry>,
+    groups: HashMap<String, QuarantineGroup>,
     quarantine_dir: PathBuf,
     total_size: u64,
+    master_key: Option<[u8; 32]>,
 }
 
 impl QuarantineManager {
     pub fn new() -> Self {
         let quarantine_dir = get_quarantine_dir();
 
-        // Create directory if not exists
+        // Create directory if not exists; ACL được áp lại mỗi lần start
         if !quarantine_dir.exists() {
             let _ = fs::create_dir_all(&quarantine_dir);
         }
+        restrict_vault_access(&quarantine_dir);
+
+        let master_key = match load_or_create_master_key(&quarantine_dir) {
+            Ok(key) => Some(key),
+            Err(e) => {
+                log::error!("Quarantine vault key unavailable: {}", e);
+                None
+            }
+        };
 
         let mut manager = Self {
             entries: HashMap::new(),
+            groups: HashMap::new(),
             quarantine_dir,
             total_size: 0,
+            master_key,
         };
 
         // Load existing entries
@@ -98,20 +125,42 @@ impl QuarantineManager {
             .map(|n| n.to_string_lossy().to_string())
             .unwrap_or_else(|| "unknown".to_string());
 
+        let master_key = self.master_key.ok_or_else(|| ActionError::Other {
+            message: "Quarantine vault key unavailable".to_string(),
+        })?;
+
+        // Capture attributes before the original is removed
+        let original_acl = read_acl(path);
+        let original_readonly = metadata.permissions().readonly();
+        let original_modified = metadata.modified().ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+
+        // Encrypt with a fresh per-file key
+        let plaintext = fs::read(path)
+            .map_err(|e| ActionError::Other { message: format!("Failed to read file: {}", e) })?;
+
+        let mut file_key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut file_key);
+        let (nonce, ciphertext) = seal(&file_key, &plaintext)?;
+        let wrapped_key = wrap_key(&master_key, &file_key)?;
+
         // Destination path (use ID to prevent name collisions)
-        let quarantine_path = self.quarantine_dir.join(format!("{}.quarantine", id));
-
-        // Move file to quarantine
-        fs::rename(path, &quarantine_path)
-            .or_else(|_| {
-                // If rename fails (cross-device), try copy + delete
-                fs::copy(path, &quarantine_path)
-                    .and_then(|_| fs::remove_file(path))
-            })
+        let quarantine_path = self.quarantine_dir.join(format!("{}.{}", id, VAULT_EXTENSION));
+
+        fs::write(&quarantine_path, &ciphertext)
             .map_err(|e| ActionError::Other {
-                message: format!("Failed to quarantine file: {}", e)
+                message: format!("Failed to write vault file: {}", e)
             })?;
 
+        // Remove original (overwrite first so it can't be carved back)
+        if let Err(e) = secure_remove(path, file_size) {
+            let _ = fs::remove_file(&quarantine_path);
+            return Err(ActionError::Other {
+                message: format!("Failed to quarantine file: {}", e),
+            });
+        }
+
         let entry = QuarantineEntry {
             id: id.clone(),
             original_path: path.to_path_buf(),
@@ -123,6 +172,14 @@ impl QuarantineManager {
             reason: reason.to_string(),
             source_incident: incident_id,
             can_restore: true,
+            encrypted: true,
+            wrapped_key: Some(wrapped_key),
+            nonce: Some(BASE64.encode(nonce)),
+            original_acl,
+            original_readonly,
+            original_modified,
+            group_id: None,
+            relative_path: None,
         };
 
         self.entries.insert(id.clone(), entry.clone());
@@ -171,18 +228,12 @@ impl QuarantineManager {
             entry.original_path.clone()
         };
 
-        // Move back
-        fs::rename(&entry.quarantine_path, &restore_path)
-            .or_else(|_| {
-                fs::copy(&entry.quarantine_path, &restore_path)
-                    .and_then(|_| fs::remove_file(&entry.quarantine_path))
-            })
-            .map_err(|e| ActionError::Other {
-                message: format!("Failed to restore file: {}", e),
-            })?;
+        self.write_restored(&entry, &restore_path)?;
+        let _ = fs::remove_file(&entry.quarantine_path);
 
         self.total_size = self.total_size.saturating_sub(entry.file_size);
         self.entries.remove(quarantine_id);
+        self.detach_from_group(&entry);
         self.save_metadata();
 
         log::info!("Restored file: {} -> {}", entry.quarantine_path.display(), restore_path.display());
@@ -190,6 +241,192 @@ impl QuarantineManager {
         Ok(restore_path)
     }
 
+    /// Entry được restore / delete riêng lẻ thì không còn thuộc group
+    fn detach_from_group(&mut self, entry: &QuarantineEntry) {
+        if let Some(group_id) = &entry.group_id {
+            if let Some(group) = self.groups.get_mut(group_id) {
+                group.entry_ids.retain(|id| id != &entry.id);
+                if group.entry_ids.is_empty() {
+                    self.groups.remove(group_id);
+                }
+            }
+        }
+    }
+
+    /// Ghi nội dung entry ra `restore_path` (vault file được giữ nguyên trừ legacy entry)
+    fn write_restored(&self, entry: &QuarantineEntry, restore_path: &Path) -> Result<(), ActionError> {
+        if entry.encrypted {
+            let plaintext = self.decrypt_entry(entry)?;
+
+            // Integrity check - vault file must decrypt to the original content
+            let mut hasher = Sha256::new();
+            hasher.update(&plaintext);
+            if format!("{:x}", hasher.finalize()) != entry.sha256 {
+                return Err(ActionError::Other {
+                    message: "Restored content hash mismatch".to_string(),
+                });
+            }
+
+            if let Some(parent) = restore_path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            fs::write(restore_path, &plaintext)
+                .map_err(|e| ActionError::Other {
+                    message: format!("Failed to restore file: {}", e),
+                })?;
+            restore_attributes(restore_path, entry);
+        } else {
+            // Legacy entry (plain move)
+            fs::rename(&entry.quarantine_path, restore_path)
+                .or_else(|_| {
+                    fs::copy(&entry.quarantine_path, restore_path)
+                        .and_then(|_| fs::remove_file(&entry.quarantine_path))
+                })
+                .map_err(|e| ActionError::Other {
+                    message: format!("Failed to restore file: {}", e),
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Quarantine cả cây thư mục. Nếu 1 file thất bại, các file đã quarantine
+    /// được restore lại (all-or-nothing).
+    pub fn quarantine_directory(&mut self, root: &Path, reason: &str, incident_id: Option<String>)
+        -> Result<QuarantineGroup, ActionError>
+    {
+        if !root.is_dir() {
+            return Err(ActionError::FileNotFound {
+                path: root.to_string_lossy().to_string(),
+            });
+        }
+
+        let mut files = Vec::new();
+        let mut directories = Vec::new();
+        collect_tree(root, root, 0, &mut files, &mut directories)?;
+
+        let total_size: u64 = files.iter()
+            .filter_map(|f| fs::metadata(f).ok())
+            .map(|m| m.len())
+            .sum();
+        if self.total_size + total_size > MAX_QUARANTINE_SIZE_MB * 1024 * 1024 {
+            return Err(ActionError::Other {
+                message: "Quarantine folder size limit reached".to_string(),
+            });
+        }
+
+        let group_id = Uuid::new_v4().to_string();
+        let mut entry_ids = Vec::new();
+
+        for file in &files {
+            match self.quarantine(file, reason, incident_id.clone()) {
+                Ok(entry) => {
+                    if let Some(e) = self.entries.get_mut(&entry.id) {
+                        e.group_id = Some(group_id.clone());
+                        e.relative_path = file.strip_prefix(root).ok().map(|p| p.to_path_buf());
+                    }
+                    entry_ids.push(entry.id);
+                }
+                Err(e) => {
+                    log::error!("Directory quarantine failed at {}: {} - rolling back", file.display(), e);
+                    for id in &entry_ids {
+                        if let Err(err) = self.restore(id) {
+                            log::error!("Rollback failed for {}: {}", id, err);
+                        }
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        // Xóa các thư mục đã rỗng (sâu nhất trước)
+        for dir in directories.iter().rev() {
+            let _ = fs::remove_dir(root.join(dir));
+        }
+        let _ = fs::remove_dir(root);
+
+        let group = QuarantineGroup {
+            id: group_id.clone(),
+            root: root.to_path_buf(),
+            reason: reason.to_string(),
+            entry_ids,
+            directories,
+            total_size,
+            quarantine_time: Utc::now().timestamp(),
+        };
+
+        self.groups.insert(group_id, group.clone());
+        self.save_metadata();
+
+        log::warn!("Quarantined directory {} ({} files)", root.display(), group.entry_ids.len());
+
+        Ok(group)
+    }
+
+    /// Restore cả cây thư mục: ghi ra staging dir rồi rename 1 lần.
+    /// Nếu root đã tồn tại lại, restore sang `<root>_restored`.
+    pub fn restore_directory(&mut self, group_id: &str) -> Result<PathBuf, ActionError> {
+        let group = self.groups.get(group_id)
+            .ok_or_else(|| ActionError::Other {
+                message: format!("Quarantine group not found: {}", group_id),
+            })?
+            .clone();
+
+        let entries: Vec<QuarantineEntry> = group.entry_ids.iter()
+            .map(|id| self.entries.get(id).cloned().ok_or_else(|| ActionError::Other {
+                message: format!("Quarantine entry missing from group: {}", id),
+            }))
+            .collect::<Result<_, _>>()?;
+
+        let target = if group.root.exists() {
+            let mut path = group.root.clone().into_os_string();
+            path.push("_restored");
+            PathBuf::from(path)
+        } else {
+            group.root.clone()
+        };
+        if target.exists() {
+            return Err(ActionError::Other {
+                message: format!("Restore target already exists: {}", target.display()),
+            });
+        }
+
+        let mut staging = target.clone().into_os_string();
+        staging.push(format!(".restoring-{}", &group.id[..8]));
+        let staging = PathBuf::from(staging);
+
+        let staged = (|| -> Result<(), ActionError> {
+            fs::create_dir_all(&staging).map_err(|e| ActionError::Other { message: e.to_string() })?;
+            for dir in &group.directories {
+                let _ = fs::create_dir_all(staging.join(dir));
+            }
+            for entry in &entries {
+                let relative = entry.relative_path.clone()
+                    .unwrap_or_else(|| PathBuf::from(&entry.file_name));
+                self.write_restored(entry, &staging.join(relative))?;
+            }
+            fs::rename(&staging, &target).map_err(|e| ActionError::Other {
+                message: format!("Failed to move restored tree into place: {}", e),
+            })
+        })();
+
+        if let Err(e) = staged {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+
+        for entry in &entries {
+            let _ = fs::remove_file(&entry.quarantine_path);
+            self.total_size = self.total_size.saturating_sub(entry.file_size);
+            self.entries.remove(&entry.id);
+        }
+        self.groups.remove(group_id);
+        self.save_metadata();
+
+        log::info!("Restored directory {} ({} files)", target.display(), entries.len());
+
+        Ok(target)
+    }
+
     /// Delete a quarantined file permanently
     pub fn delete(&mut self, quarantine_id: &str) -> Result<(), ActionError> {
         let entry = self.entries.get(quarantine_id)
@@ -199,19 +436,7 @@ impl QuarantineManager {
             .clone();
 
         if entry.quarantine_path.exists() {
-            // Secure delete - overwrite before delete
-            if let Ok(mut file) = fs::OpenOptions::new()
-                .write(true)
-                .open(&entry.quarantine_path)
-            {
-                use std::io::Write;
-                let zeros = vec![0u8; 4096];
-                for _ in 0..(entry.file_size / 4096 + 1) {
-                    let _ = file.write_all(&zeros);
-                }
-            }
-
-            fs::remove_file(&entry.quarantine_path)
+            secure_remove(&entry.quarantine_path, entry.file_size)
                 .map_err(|e| ActionError::Other {
                     message: format!("Failed to delete file: {}", e),
                 })?;
@@ -219,6 +444,7 @@ impl QuarantineManager {
 
         self.total_size = self.total_size.saturating_sub(entry.file_size);
         self.entries.remove(quarantine_id);
+        self.detach_from_group(&entry);
         self.save_metadata();
 
         log::info!("Deleted quarantined file: {}", entry.file_name);
@@ -226,6 +452,26 @@ impl QuarantineManager {
         Ok(())
     }
 
+    /// Decrypt a vault entry in memory
+    fn decrypt_entry(&self, entry: &QuarantineEntry) -> Result<Vec<u8>, ActionError> {
+        let master_key = self.master_key.ok_or_else(|| ActionError::Other {
+            message: "Quarantine vault key unavailable".to_string(),
+        })?;
+
+        let wrapped = entry.wrapped_key.as_deref().ok_or_else(|| ActionError::Other {
+            message: "Missing wrapped key".to_string(),
+        })?;
+        let nonce = entry.nonce.as_deref()
+            .and_then(|n| BASE64.decode(n).ok())
+            .ok_or_else(|| ActionError::Other { message: "Missing nonce".to_string() })?;
+
+        let file_key = unwrap_key(&master_key, wrapped)?;
+        let ciphertext = fs::read(&entry.quarantine_path)
+            .map_err(|e| ActionError::Other { message: e.to_string() })?;
+
+        unseal(&file_key, &nonce, &ciphertext)
+    }
+
     /// Get all entries
     pub fn list(&self) -> Vec<QuarantineEntry> {
         self.entries.values().cloned().collect()
@@ -236,6 +482,11 @@ impl QuarantineManager {
         self.entries.get(id).cloned()
     }
 
+    /// Get all directory groups
+    pub fn list_groups(&self) -> Vec<QuarantineGroup> {
+        self.groups.values().cloned().collect()
+    }
+
     /// Load metadata from disk
     fn load_metadata(&mut self) {
         let metadata_path = self.quarantine_dir.join(METADATA_FILE);
@@ -250,6 +501,14 @@ impl QuarantineManager {
                 }
             }
         }
+
+        if let Ok(content) = fs::read_to_string(self.quarantine_dir.join(GROUPS_FILE)) {
+            if let Ok(groups) = serde_json::from_str::<Vec<QuarantineGroup>>(&content) {
+                for group in groups {
+                    self.groups.insert(group.id.clone(), group);
+                }
+            }
+        }
     }
 
     /// Save metadata to disk
@@ -260,6 +519,11 @@ impl QuarantineManager {
         if let Ok(json) = serde_json::to_string_pretty(&entries) {
             let _ = fs::write(&metadata_path, json);
         }
+
+        let groups: Vec<_> = self.groups.values().collect();
+        if let Ok(json) = serde_json::to_string_pretty(&groups) {
+            let _ = fs::write(self.quarantine_dir.join(GROUPS_FILE), json);
+        }
     }
 
     /// Get stats
@@ -295,27 +559,247 @@ fn get_quarantine_dir() -> PathBuf {
         .join(QUARANTINE_FOLDER)
 }
 
-fn calculate_file_hash(path: &Path) -> Result<String, ActionError> {
-    use std::io::Read;
+/// AES-256-GCM encrypt, returns (nonce, ciphertext)
+fn seal(key: &[u8; 32], plaintext: &[u8]) -> Result<([u8; NONCE_LEN], Vec<u8>), ActionError> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
 
-    let mut file = fs::File::open(path)
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| ActionError::Other { message: "Vault encryption failed".to_string() })?;
+
+    Ok((nonce, ciphertext))
+}
+
+/// AES-256-GCM decrypt (fails if ciphertext was tampered with)
+fn unseal(key: &[u8; 32], nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, ActionError> {
+    if nonce.len() != NONCE_LEN {
+        return Err(ActionError::Other { message: "Invalid nonce".to_string() });
+    }
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| ActionError::Other { message: "Vault decryption failed".to_string() })
+}
+
+/// Wrap a per-file key with the master key -> base64(nonce || ciphertext)
+fn wrap_key(master: &[u8; 32], file_key: &[u8; 32]) -> Result<String, ActionError> {
+    let (nonce, ciphertext) = seal(master, file_key)?;
+    let mut blob = nonce.to_vec();
+    blob.extend_from_slice(&ciphertext);
+    Ok(BASE64.encode(blob))
+}
+
+fn unwrap_key(master: &[u8; 32], wrapped: &str) -> Result<[u8; 32], ActionError> {
+    let blob = BASE64.decode(wrapped)
         .map_err(|e| ActionError::Other { message: e.to_string() })?;
+    if blob.len() <= NONCE_LEN {
+        return Err(ActionError::Other { message: "Invalid wrapped key".to_string() });
+    }
 
-    let mut hasher = Sha256::new();
-    let mut buffer = [0u8; 8192];
+    let key = unseal(master, &blob[..NONCE_LEN], &blob[NONCE_LEN..])?;
+    key.try_into()
+        .map_err(|_| ActionError::Other { message: "Invalid file key length".to_string() })
+}
 
-    loop {
-        let bytes_read = file.read(&mut buffer)
-            .map_err(|e| ActionError::Other { message: e.to_string() })?;
+/// Vault key lưu dưới dạng DPAPI blob. Chỉ tạo key mới khi file chưa tồn tại:
+/// lỗi đọc khác (bị khóa, sai quyền) mà tạo lại sẽ làm mất toàn bộ file đã quarantine.
+fn load_or_create_master_key(dir: &Path) -> Result<[u8; 32], String> {
+    use crate::logic::identity::dpapi;
+
+    let path = dir.join(MASTER_KEY_FILE);
+
+    let key = match fs::read_to_string(&path) {
+        Ok(content) => {
+            let stored = BASE64.decode(content.trim()).map_err(|e| e.to_string())?;
+            match dpapi::unprotect(&stored) {
+                Ok(key) => key,
+                // Key cũ lưu plaintext → bọc DPAPI
+                Err(_) if stored.len() == 32 => {
+                    write_master_key(&path, &stored)?;
+                    stored
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let mut key = [0u8; 32];
+            rand::thread_rng().fill_bytes(&mut key);
+            write_master_key(&path, &key)?;
+            key.to_vec()
+        }
+        Err(e) => return Err(format!("Cannot read vault key: {}", e)),
+    };
+    restrict_vault_access(&path);
+
+    key.try_into().map_err(|_| "Invalid vault key length".to_string())
+}
 
-        if bytes_read == 0 {
-            break;
+fn write_master_key(path: &Path, key: &[u8]) -> Result<(), String> {
+    let blob = crate::logic::identity::dpapi::protect(key)?;
+    fs::write(path, BASE64.encode(blob)).map_err(|e| e.to_string())
+}
+
+/// Chỉ SYSTEM + Administrators được truy cập vault
+#[cfg(windows)]
+fn restrict_vault_access(path: &Path) {
+    let result = std::process::Command::new("icacls")
+        .arg(path)
+        .args([
+            "/inheritance:r",
+            "/grant:r", "*S-1-5-18:(OI)(CI)F",     // SYSTEM
+            "/grant:r", "*S-1-5-32-544:(OI)(CI)F", // Administrators
+        ])
+        .output();
+
+    if let Err(e) = result {
+        log::warn!("Failed to restrict vault ACL: {}", e);
+    }
+}
+
+#[cfg(not(windows))]
+fn restrict_vault_access(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = if path.is_dir() { 0o700 } else { 0o600 };
+    let _ = fs::set_permissions(path, fs::Permissions::from_mode(mode));
+}
+
+/// Read ACL as SDDL
+#[cfg(windows)]
+fn read_acl(path: &Path) -> Option<String> {
+    let output = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            &format!("(Get-Acl -LiteralPath '{}').Sddl", path.display().to_string().replace('\'', "''")),
+        ])
+        .output()
+        .ok()?;
+
+    let sddl = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if sddl.is_empty() { None } else { Some(sddl) }
+}
+
+#[cfg(not(windows))]
+fn read_acl(path: &Path) -> Option<String> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).ok().map(|m| format!("{:o}", m.permissions().mode() & 0o7777))
+}
+
+/// Restore ACL, read-only flag and modification time
+fn restore_attributes(path: &Path, entry: &QuarantineEntry) {
+    if let Some(modified) = entry.original_modified {
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(modified.max(0) as u64);
+        if let Ok(file) = fs::OpenOptions::new().write(true).open(path) {
+            let _ = file.set_modified(time);
         }
+    }
 
-        hasher.update(&buffer[..bytes_read]);
+    if let Some(acl) = &entry.original_acl {
+        apply_acl(path, acl);
     }
 
-    Ok(format!("{:x}", hasher.finalize()))
+    if entry.original_readonly {
+        if let Ok(metadata) = fs::metadata(path) {
+            let mut perms = metadata.permissions();
+            perms.set_readonly(true);
+            let _ = fs::set_permissions(path, perms);
+        }
+    }
+}
+
+#[cfg(windows)]
+fn apply_acl(path: &Path, sddl: &str) {
+    let script = format!(
+        "$a = Get-Acl -LiteralPath '{0}'; $a.SetSecurityDescriptorSddlForm('{1}'); Set-Acl -LiteralPath '{0}' -AclObject $a",
+        path.display().to_string().replace('\'', "''"),
+        sddl.replace('\'', "''"),
+    );
+    if let Err(e) = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command", &script])
+        .output()
+    {
+        log::warn!("Failed to restore ACL for {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(not(windows))]
+fn apply_acl(path: &Path, mode: &str) {
+    use std::os::unix::fs::PermissionsExt;
+    if let Ok(mode) = u32::from_str_radix(mode, 8) {
+        let _ = fs::set_permissions(path, fs::Permissions::from_mode(mode));
+    }
+}
+
+/// Thu thập files + sub-directories (relative) của cây thư mục
+fn collect_tree(
+    root: &Path,
+    dir: &Path,
+    depth: usize,
+    files: &mut Vec<PathBuf>,
+    directories: &mut Vec<PathBuf>,
+) -> Result<(), ActionError> {
+    if depth > MAX_DIR_DEPTH {
+        return Err(ActionError::InvalidAction {
+            reason: format!("Directory too deep: {}", dir.display()),
+        });
+    }
+
+    let entries = fs::read_dir(dir)
+        .map_err(|e| ActionError::Other { message: e.to_string() })?;
+    for entry in entries.flatten() {
+        let file_type = match entry.file_type() {
+            Ok(t) => t,
+            Err(_) => continue,
+        };
+        let path = entry.path();
+        // Không theo symlink / junction ra ngoài cây
+        if file_type.is_symlink() {
+            continue;
+        }
+        if file_type.is_dir() {
+            if let Ok(relative) = path.strip_prefix(root) {
+                directories.push(relative.to_path_buf());
+            }
+            collect_tree(root, &path, depth + 1, files, directories)?;
+        } else if file_type.is_file() {
+            files.push(path);
+            if files.len() > MAX_DIR_FILES {
+                return Err(ActionError::InvalidAction {
+                    reason: format!("Directory has more than {} files", MAX_DIR_FILES),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Overwrite with zeros then delete
+fn secure_remove(path: &Path, size: u64) -> std::io::Result<()> {
+    if let Ok(metadata) = fs::metadata(path) {
+        // Read-only files can't be opened for write
+        let mut perms = metadata.permissions();
+        if perms.readonly() {
+            perms.set_readonly(false);
+            let _ = fs::set_permissions(path, perms);
+        }
+    }
+
+    if let Ok(mut file) = fs::OpenOptions::new().write(true).open(path) {
+        use std::io::Write;
+        let zeros = vec![0u8; 4096];
+        for _ in 0..(size / 4096 + 1) {
+            let _ = file.write_all(&zeros);
+        }
+        let _ = file.sync_all();
+    }
+
+    fs::remove_file(path)
+}
+
+fn calculate_file_hash(path: &Path) -> Result<String, ActionError> {
+    crate::logic::process_intel::hashing::hash_file(path)
+        .map(|h| h.sha256)
+        .map_err(|message| ActionError::Other { message })
 }
 
 // ============================================================================
@@ -339,6 +823,23 @@ pub fn delete_quarantined(quarantine_id: &str) -> Result<(), ActionError> {
     QUARANTINE_MANAGER.write().delete(quarantine_id)
 }
 
+/// Quarantine a directory tree
+pub fn quarantine_directory(path: &Path, reason: &str, incident_id: Option<String>)
+    -> Result<QuarantineGroup, ActionError>
+{
+    QUARANTINE_MANAGER.write().quarantine_directory(path, reason, incident_id)
+}
+
+/// Restore a quarantined directory tree
+pub fn restore_directory(group_id: &str) -> Result<PathBuf, ActionError> {
+    QUARANTINE_MANAGER.write().restore_directory(group_id)
+}
+
+/// Get all quarantined directory groups
+pub fn get_quarantine_groups() -> Vec<QuarantineGroup> {
+    QUARANTINE_MANAGER.read().list_groups()
+}
+
 /// Get all quarantined files
 pub fn get_quarantine_list() -> Vec<QuarantineEntry> {
     QUARANTINE_MANAGER.read().list()
@@ -366,3 +867,49 @@ pub struct QuarantineStats {
     pub oldest_entry: Option<i64>,
     pub newest_entry: Option<i64>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_open_roundtrip() {
+        let key = [7u8; 32];
+        let (nonce, ciphertext) = seal(&key, b"MZ payload").unwrap();
+        assert_ne!(ciphertext.as_slice(), b"MZ payload");
+        assert_eq!(unseal(&key, &nonce, &ciphertext).unwrap(), b"MZ payload");
+    }
+
+    #[test]
+    fn test_tampered_ciphertext_rejected() {
+        let key = [7u8; 32];
+        let (nonce, mut ciphertext) = seal(&key, b"MZ payload").unwrap();
+        ciphertext[0] ^= 0xFF;
+        assert!(unseal(&key, &nonce, &ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_collect_tree() {
+        let root = std::env::temp_dir().join(format!("oneshield_qdir_{}", Uuid::new_v4()));
+        fs::create_dir_all(root.join("stage").join("empty")).unwrap();
+        fs::write(root.join("a.exe"), b"MZ").unwrap();
+        fs::write(root.join("stage").join("b.dll"), b"MZ").unwrap();
+
+        let mut files = Vec::new();
+        let mut directories = Vec::new();
+        collect_tree(&root, &root, 0, &mut files, &mut directories).unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(directories.contains(&PathBuf::from("stage").join("empty")));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_key_wrap_roundtrip() {
+        let master = [1u8; 32];
+        let file_key = [2u8; 32];
+        let wrapped = wrap_key(&master, &file_key).unwrap();
+        assert_eq!(unwrap_key(&master, &wrapped).unwrap(), file_key);
+        assert!(unwrap_key(&[3u8; 32], &wrapped).is_err());
+    }
+}
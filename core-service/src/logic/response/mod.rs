@@ -3,39 +3,65 @@
 //! Mục đích: Tự động phản ứng với threats
 //!
 //! # Components
-//! - `actions.rs`: Process actions (suspend, kill, quarantine)
+//! - `actions.rs`: Process actions (suspend, kill, quarantine) + response gate (dry-run / auto-block)
 //! - `network.rs`: Network isolation via Windows Firewall
+//! - `wfp.rs`: Per-process network blocking via Windows Filtering Platform
 //! - `file_quarantine.rs`: File quarantine management
+//! - `registry.rs`: Registry persistence rollback (snapshot + undo)
 //! - `webhook.rs`: Alert integration (Slack, Discord, Teams)
+//! - `playbook.rs`: Declarative remediation playbooks
+//! - `expiry.rs`: Time-limited actions with scheduled auto-revert
+//! - `ransomware.rs`: Ransomware profile (suspend tree, shadow-copy guard, recovery list)
+//! - `usb.rs`: USB storage blocking (PnP disable / removable storage policy)
+//! - `browser_child.rs`: Browser-spawned threat containment (kill child, quarantine download, block hash)
+//! - `verify.rs`: Post-action verification (process gone / suspended / network blocked)
 
 // Allow unused for now - will be fully integrated in future phases
 #![allow(unused)]
 
 pub mod actions;
 pub mod network;
+pub mod wfp;
 pub mod file_quarantine;
+pub mod registry;
 pub mod webhook;
+pub mod playbook;
+pub mod expiry;
+pub mod ransomware;
+pub mod usb;
+pub mod verify;
+pub mod browser_child;
 pub mod types;
 
 // Re-exports from types
 pub use types::{
     ResponseAction, ActionResult, ActionError, ActionStatus,
-    QuarantineEntry, WebhookConfig, WebhookPlatform, AlertPayload,
+    QuarantineEntry, QuarantineGroup, WebhookConfig, WebhookPlatform, AlertPayload,
 };
 
 // Re-exports from submodules
 pub use actions::{
-    suspend_process, resume_process, kill_process,
+    suspend_process, resume_process, kill_process, kill_process_tree,
     execute_action, get_action_history,
 };
 pub use network::{
     block_network, unblock_network, is_network_blocked,
-    get_blocked_processes,
+    get_blocked_processes, isolate_host, release_host, is_host_isolated,
+    get_isolation_status, HostIsolation,
+    block_destination, unblock_destination, get_blocked_destinations, BlockedDestination,
 };
 pub use file_quarantine::{
     quarantine_file, restore_file, delete_quarantined,
+    quarantine_directory, restore_directory, get_quarantine_groups,
     get_quarantine_list, QuarantineManager,
 };
+pub use registry::{
+    remediate_alert, remove_persistence, restore_persistence, get_snapshots,
+    RegistrySnapshot,
+};
+pub use playbook::{
+    Playbook, PlaybookStep, PlaybookContext, PlaybookRun, find_playbook, run_playbook,
+};
 pub use webhook::{
     send_alert, add_webhook, remove_webhook, test_webhook,
     get_webhooks, AlertManager,
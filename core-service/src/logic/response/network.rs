@@ -3,6 +3,14 @@
 //! Mục đích: Block/unblock network access via Windows Firewall
 //!
 //! Uses netsh advfirewall commands
+//!
+//! Destination block: hostname → sinkhole trong hosts file (không chặn nhầm site khác
+//! dùng chung IP CDN), chỉ IP → firewall rule `remoteip=`.
+//!
+//! Host isolation: chặn toàn bộ traffic (default policy block) và chỉ cho phép
+//! cloud server (kèm proxy của agent) + DNS resolver đang cấu hình để agent vẫn
+//! nhận được lệnh release.
+//! Firewall policy của từng profile được snapshot lúc isolate và khôi phục khi release.
 
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -19,6 +27,21 @@ use super::types::{ActionResult, ActionError, ActionStatus, ResponseAction};
 // ============================================================================
 
 const RULE_PREFIX: &str = "OneShield_Block_";
+const ISOLATE_RULE_PREFIX: &str = "OneShield_Isolate_";
+/// Dùng chung RULE_PREFIX để `cleanup_all_rules` gỡ luôn
+const DESTINATION_RULE_PREFIX: &str = "OneShield_Block_dst_";
+
+/// Firewall policy restored on release when no snapshot exists (Windows default)
+const DEFAULT_FIREWALL_POLICY: &str = "blockinbound,allowoutbound";
+const ISOLATED_FIREWALL_POLICY: &str = "blockinbound,blockoutbound";
+const FIREWALL_PROFILES: &[&str] = &["domainprofile", "privateprofile", "publicprofile"];
+
+const ISOLATION_STATE_FILE: &str = "isolation.json";
+
+/// Đánh dấu dòng sinkhole do agent thêm vào hosts file
+const HOSTS_MARKER: &str = "# OneShield_Block_dst";
+/// `rule_name` của destination chặn qua hosts file
+const HOSTS_RULE: &str = "hosts";
 
 // ============================================================================
 // STATE
@@ -34,6 +57,36 @@ struct BlockedProcess {
     blocked_at: i64,
 }
 
+/// Destination bị chặn (remote IP / hostname → rule)
+static BLOCKED_DESTINATIONS: Lazy<RwLock<HashMap<String, BlockedDestination>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BlockedDestination {
+    /// Remote IP hoặc hostname
+    pub ip: String,
+    pub rule_name: String,
+    pub reason: String,
+    pub blocked_at: i64,
+}
+
+/// Host isolation state - persisted so isolation survives agent restart
+static HOST_ISOLATION: Lazy<RwLock<Option<HostIsolation>>> =
+    Lazy::new(|| RwLock::new(load_isolation_state()));
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct HostIsolation {
+    pub isolated_at: i64,
+    pub allowed_ips: Vec<String>,
+    /// DNS resolvers còn được phép (port 53)
+    #[serde(default)]
+    pub dns_servers: Vec<String>,
+    pub rules: Vec<String>,
+    /// Firewall policy trước khi isolate (profile → policy)
+    #[serde(default)]
+    pub previous_policy: HashMap<String, String>,
+}
+
 // ============================================================================
 // NETWORK ACTIONS
 // ============================================================================
@@ -156,6 +209,423 @@ pub fn get_blocked_processes() -> Vec<(u32, String, i64)> {
         .collect()
 }
 
+// ============================================================================
+// DESTINATION BLOCKING
+// ============================================================================
+
+/// Chặn outbound tới 1 destination cho mọi process (malicious site / C2).
+/// Hostname → sinkhole trong hosts file, IP → firewall rule.
+pub fn block_destination(target: &str, reason: &str) -> Result<ActionResult, ActionError> {
+    let start = Instant::now();
+
+    let (target, rule_name) = match target.parse::<std::net::IpAddr>() {
+        Ok(ip) => {
+            let ip = ip.to_string();
+            let rule_name = format!("{}{}", DESTINATION_RULE_PREFIX, ip.replace(':', "_"));
+            (ip, rule_name)
+        }
+        Err(_) => {
+            let host = normalize_hostname(target).ok_or_else(|| ActionError::InvalidAction {
+                reason: format!("Invalid IP address or hostname: {}", target),
+            })?;
+            (host, HOSTS_RULE.to_string())
+        }
+    };
+
+    if BLOCKED_DESTINATIONS.read().contains_key(&target) {
+        return Err(ActionError::InvalidAction {
+            reason: format!("Destination {} is already blocked", target),
+        });
+    }
+
+    if rule_name == HOSTS_RULE {
+        edit_hosts_file(|content| add_sinkhole(content, &target))?;
+    } else {
+        run_netsh(&[
+            "advfirewall".to_string(),
+            "firewall".to_string(),
+            "add".to_string(),
+            "rule".to_string(),
+            format!("name={}", rule_name),
+            "dir=out".to_string(),
+            format!("remoteip={}", target),
+            "action=block".to_string(),
+            "enable=yes".to_string(),
+        ])?;
+    }
+
+    BLOCKED_DESTINATIONS.write().insert(target.clone(), BlockedDestination {
+        ip: target.clone(),
+        rule_name,
+        reason: reason.to_string(),
+        blocked_at: Utc::now().timestamp(),
+    });
+
+    log::warn!("Blocked outbound traffic to {} ({})", target, reason);
+    Ok(ActionResult {
+        action: ResponseAction::BlockDestination { ip: target.clone() },
+        status: ActionStatus::Success,
+        message: format!("Blocked outbound traffic to {}", target),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// Gỡ chặn destination (firewall rule hoặc dòng sinkhole)
+pub fn unblock_destination(target: &str) -> Result<ActionResult, ActionError> {
+    let start = Instant::now();
+
+    let is_ip = target.parse::<std::net::IpAddr>().is_ok();
+    let target = if is_ip { target.to_string() } else { normalize_hostname(target).unwrap_or_else(|| target.to_string()) };
+    let rule_name = BLOCKED_DESTINATIONS.read().get(&target)
+        .map(|b| b.rule_name.clone())
+        .unwrap_or_else(|| if is_ip {
+            format!("{}{}", DESTINATION_RULE_PREFIX, target.replace(':', "_"))
+        } else {
+            HOSTS_RULE.to_string()
+        });
+
+    if rule_name == HOSTS_RULE {
+        edit_hosts_file(|content| remove_sinkhole(content, Some(&target)))?;
+    } else {
+        delete_firewall_rule(&rule_name)?;
+    }
+    BLOCKED_DESTINATIONS.write().remove(&target);
+
+    log::info!("Unblocked outbound traffic to {}", target);
+    Ok(ActionResult {
+        action: ResponseAction::UnblockDestination { ip: target.clone() },
+        status: ActionStatus::Success,
+        message: format!("Unblocked outbound traffic to {}", target),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+pub fn is_destination_blocked(ip: &str) -> bool {
+    BLOCKED_DESTINATIONS.read().contains_key(ip)
+}
+
+pub fn get_blocked_destinations() -> Vec<BlockedDestination> {
+    BLOCKED_DESTINATIONS.read().values().cloned().collect()
+}
+
+// ============================================================================
+// HOST ISOLATION
+// ============================================================================
+
+/// Isolate the whole host: block all inbound/outbound traffic except
+/// the cloud server, the configured DNS resolvers and any extra
+/// `allowed_hosts` (hostnames or IPs).
+pub fn isolate_host(allowed_hosts: &[String]) -> Result<ActionResult, ActionError> {
+    let start = Instant::now();
+
+    if HOST_ISOLATION.read().is_some() {
+        return Err(ActionError::InvalidAction {
+            reason: "Host is already isolated".to_string(),
+        });
+    }
+
+    // Cloud server must stay reachable, otherwise we can never release remotely.
+    // Agent đi qua proxy thì proxy (và script PAC) cũng phải còn truy cập được
+    let cloud_url = crate::constants::get_cloud_url();
+    let proxies = crate::logic::cloud_sync::proxy::endpoints(&cloud_url);
+    let mut allowed_ips: Vec<String> = Vec::new();
+    for host in std::iter::once(&cloud_url).chain(&proxies) {
+        for ip in resolve_host_ips(host) {
+            if !allowed_ips.contains(&ip) {
+                allowed_ips.push(ip);
+            }
+        }
+    }
+    if allowed_ips.is_empty() {
+        return Err(ActionError::NetworkError {
+            message: format!("Cannot resolve cloud server {} - refusing to isolate", cloud_url),
+        });
+    }
+    for host in allowed_hosts {
+        for ip in resolve_host_ips(host) {
+            if !allowed_ips.contains(&ip) {
+                allowed_ips.push(ip);
+            }
+        }
+    }
+
+    // Snapshot policy hiện tại để release trả lại đúng cấu hình của máy
+    let previous_policy = snapshot_firewall_policy()?;
+
+    let remote_ips = allowed_ips.join(",");
+    let mut rules: Vec<(String, Vec<String>)> = vec![
+        (format!("{}Cloud_Out", ISOLATE_RULE_PREFIX), vec![
+            "dir=out".into(), "action=allow".into(), format!("remoteip={}", remote_ips),
+        ]),
+        (format!("{}Cloud_In", ISOLATE_RULE_PREFIX), vec![
+            "dir=in".into(), "action=allow".into(), format!("remoteip={}", remote_ips),
+        ]),
+    ];
+
+    // DNS chỉ tới resolver đang cấu hình, không mở port 53 ra mọi đích
+    let dns_servers = dns_resolvers();
+    if dns_servers.is_empty() {
+        log::warn!("No DNS resolver found - isolating without DNS access");
+    } else {
+        let resolvers = dns_servers.join(",");
+        for protocol in ["UDP", "TCP"] {
+            rules.push((format!("{}DNS_{}", ISOLATE_RULE_PREFIX, protocol), vec![
+                "dir=out".into(), "action=allow".into(), format!("protocol={}", protocol),
+                "remoteport=53".into(), format!("remoteip={}", resolvers),
+            ]));
+        }
+    }
+
+    let mut created = Vec::new();
+    for (name, params) in &rules {
+        let mut args = vec![
+            "advfirewall".to_string(), "firewall".to_string(), "add".to_string(), "rule".to_string(),
+            format!("name={}", name),
+        ];
+        args.extend(params.iter().cloned());
+        args.push("enable=yes".to_string());
+
+        if let Err(e) = run_netsh(&args) {
+            rollback_isolation_rules(&created);
+            return Err(e);
+        }
+        created.push(name.clone());
+    }
+
+    // Flip default policy to block everything not explicitly allowed
+    if let Err(e) = set_firewall_policy("allprofiles", ISOLATED_FIREWALL_POLICY) {
+        rollback_isolation_rules(&created);
+        return Err(e);
+    }
+
+    let message = format!("Host isolated (allowed: {}, DNS: {})", remote_ips, dns_servers.join(","));
+    let state = HostIsolation {
+        isolated_at: Utc::now().timestamp(),
+        allowed_ips: allowed_ips.clone(),
+        dns_servers,
+        rules: created,
+        previous_policy,
+    };
+    save_isolation_state(Some(&state));
+    *HOST_ISOLATION.write() = Some(state);
+
+    log::warn!("{}", message);
+
+    Ok(ActionResult {
+        action: ResponseAction::IsolateHost { allowed_hosts: allowed_hosts.to_vec() },
+        status: ActionStatus::Success,
+        message,
+        timestamp: Utc::now().timestamp(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// Lift host isolation: restore the firewall policy snapshot and remove allow rules
+pub fn release_host() -> Result<ActionResult, ActionError> {
+    let start = Instant::now();
+
+    let state = HOST_ISOLATION.read().clone();
+    let state = match state {
+        Some(s) => s,
+        None => {
+            return Err(ActionError::InvalidAction {
+                reason: "Host is not isolated".to_string(),
+            });
+        }
+    };
+
+    if state.previous_policy.is_empty() {
+        // State từ bản cũ không có snapshot
+        set_firewall_policy("allprofiles", DEFAULT_FIREWALL_POLICY)?;
+    } else {
+        for (profile, policy) in &state.previous_policy {
+            set_firewall_policy(profile, policy)?;
+        }
+    }
+    rollback_isolation_rules(&state.rules);
+
+    *HOST_ISOLATION.write() = None;
+    save_isolation_state(None);
+    super::expiry::forget(&super::expiry::RevertKind::ReleaseHost);
+
+    log::info!("Host isolation released");
+
+    Ok(ActionResult {
+        action: ResponseAction::ReleaseHost,
+        status: ActionStatus::Success,
+        message: "Host isolation released".to_string(),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// Check if host is currently isolated
+pub fn is_host_isolated() -> bool {
+    HOST_ISOLATION.read().is_some()
+}
+
+/// Get current isolation state (None if not isolated)
+pub fn get_isolation_status() -> Option<HostIsolation> {
+    HOST_ISOLATION.read().clone()
+}
+
+/// Resolve a URL / hostname / IP to IP strings
+fn resolve_host_ips(target: &str) -> Vec<String> {
+    use std::net::{IpAddr, ToSocketAddrs};
+
+    if let Ok(ip) = target.parse::<IpAddr>() {
+        return vec![ip.to_string()];
+    }
+
+    let (host, port) = match reqwest::Url::parse(target) {
+        Ok(url) if url.host_str().is_some() => (
+            url.host_str().unwrap_or_default().to_string(),
+            url.port_or_known_default().unwrap_or(443),
+        ),
+        _ => (target.to_string(), 443),
+    };
+
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return vec![ip.to_string()];
+    }
+
+    let mut ips: Vec<String> = Vec::new();
+    match (host, port).to_socket_addrs() {
+        Ok(addrs) => {
+            for addr in addrs {
+                let ip = addr.ip().to_string();
+                if !ips.contains(&ip) {
+                    ips.push(ip);
+                }
+            }
+        }
+        Err(e) => log::warn!("Failed to resolve {}: {}", host, e),
+    }
+    ips
+}
+
+fn rollback_isolation_rules(rules: &[String]) {
+    for name in rules {
+        if let Err(e) = delete_firewall_rule(name) {
+            log::warn!("Failed to delete isolation rule {}: {}", name, e);
+        }
+    }
+}
+
+fn set_firewall_policy(profile: &str, policy: &str) -> Result<(), ActionError> {
+    run_netsh(&[
+        "advfirewall".to_string(),
+        "set".to_string(),
+        profile.to_string(),
+        "firewallpolicy".to_string(),
+        policy.to_string(),
+    ])
+}
+
+/// Policy hiện tại của từng firewall profile
+fn snapshot_firewall_policy() -> Result<HashMap<String, String>, ActionError> {
+    let mut snapshot = HashMap::new();
+    for profile in FIREWALL_PROFILES {
+        let output = Command::new("netsh")
+            .args(["advfirewall", "show", profile, "firewallpolicy"])
+            .output()
+            .map_err(|e| ActionError::Other { message: e.to_string() })?;
+
+        let policy = parse_firewall_policy(&String::from_utf8_lossy(&output.stdout))
+            .ok_or_else(|| ActionError::Other {
+                message: format!("Cannot read firewall policy of {} - refusing to isolate", profile),
+            })?;
+        snapshot.insert(profile.to_string(), policy);
+    }
+    Ok(snapshot)
+}
+
+/// Lấy giá trị `<inbound>,<outbound>` từ output `netsh advfirewall show`.
+/// Nhãn bị localize nên chỉ dựa vào token policy.
+fn parse_firewall_policy(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .map(|token| token.to_lowercase())
+        .find(|token| {
+            token.split_once(',').is_some_and(|(inbound, outbound)| {
+                inbound.contains("inbound") || outbound.contains("outbound")
+            })
+        })
+}
+
+/// DNS resolver đang cấu hình trên máy
+fn dns_resolvers() -> Vec<String> {
+    #[cfg(windows)]
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "(Get-DnsClientServerAddress).ServerAddresses",
+        ])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string());
+
+    #[cfg(not(windows))]
+    let output = std::fs::read_to_string("/etc/resolv.conf");
+
+    match output {
+        Ok(output) => parse_resolvers(&output),
+        Err(e) => {
+            log::warn!("Failed to read DNS resolvers: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Một IP mỗi dòng (PowerShell) hoặc `nameserver <ip>` (resolv.conf)
+fn parse_resolvers(output: &str) -> Vec<String> {
+    let mut resolvers: Vec<String> = Vec::new();
+    for line in output.lines() {
+        let line = line.trim();
+        let candidate = line.strip_prefix("nameserver").unwrap_or(line).trim();
+        if let Ok(ip) = candidate.parse::<std::net::IpAddr>() {
+            let ip = ip.to_string();
+            if !resolvers.contains(&ip) {
+                resolvers.push(ip);
+            }
+        }
+    }
+    resolvers
+}
+
+fn isolation_state_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|d| d.join("OneShield").join(ISOLATION_STATE_FILE))
+}
+
+fn load_isolation_state() -> Option<HostIsolation> {
+    let path = isolation_state_path()?;
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save_isolation_state(state: Option<&HostIsolation>) {
+    let Some(path) = isolation_state_path() else { return };
+
+    match state {
+        Some(state) => {
+            if let Some(parent) = path.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            if let Ok(json) = serde_json::to_string_pretty(state) {
+                if let Err(e) = std::fs::write(&path, json) {
+                    log::error!("Failed to persist isolation state: {}", e);
+                }
+            }
+        }
+        None => {
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
+
 // ============================================================================
 // FIREWALL HELPERS
 // ============================================================================
@@ -220,6 +690,23 @@ fn delete_firewall_rule(name: &str) -> Result<(), ActionError> {
     }
 }
 
+fn run_netsh(args: &[String]) -> Result<(), ActionError> {
+    let output = Command::new("netsh")
+        .args(args)
+        .output()
+        .map_err(|e| ActionError::Other { message: e.to_string() })?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(ActionError::CommandFailed {
+            command: "netsh".to_string(),
+            exit_code: output.status.code().unwrap_or(-1),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+}
+
 fn get_exe_path_from_pid(pid: u32) -> Result<PathBuf, ActionError> {
     let output = Command::new("powershell")
         .args([
@@ -239,6 +726,72 @@ fn get_exe_path_from_pid(pid: u32) -> Result<PathBuf, ActionError> {
     }
 }
 
+// ============================================================================
+// HOSTS FILE SINKHOLE
+// ============================================================================
+
+/// Hostname hợp lệ (lowercase, bỏ dấu chấm cuối), None nếu không phải hostname
+fn normalize_hostname(target: &str) -> Option<String> {
+    let host = target.trim().trim_end_matches('.').to_lowercase();
+    let valid = host.len() <= 253
+        && host.contains('.')
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        });
+    valid.then_some(host)
+}
+
+fn hosts_path() -> PathBuf {
+    #[cfg(windows)]
+    {
+        let root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".to_string());
+        PathBuf::from(root).join(r"System32\drivers\etc\hosts")
+    }
+    #[cfg(not(windows))]
+    {
+        PathBuf::from("/etc/hosts")
+    }
+}
+
+/// Thêm dòng sinkhole (IPv4 + IPv6) cho `host`
+fn add_sinkhole(content: &str, host: &str) -> String {
+    let mut out = remove_sinkhole(content, Some(host));
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&format!("0.0.0.0 {} {}\n", host, HOSTS_MARKER));
+    out.push_str(&format!(":: {} {}\n", host, HOSTS_MARKER));
+    out
+}
+
+/// Gỡ dòng sinkhole của agent cho `host` (None = mọi dòng của agent)
+fn remove_sinkhole(content: &str, host: Option<&str>) -> String {
+    content.lines()
+        .filter(|line| {
+            let ours = line.trim_end().ends_with(HOSTS_MARKER);
+            let matches = host.map_or(true, |h| line.split_whitespace().nth(1) == Some(h));
+            !(ours && matches)
+        })
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+fn edit_hosts_file(edit: impl FnOnce(&str) -> String) -> Result<(), ActionError> {
+    let path = hosts_path();
+    let content = std::fs::read_to_string(&path).unwrap_or_default();
+    std::fs::write(&path, edit(&content))
+        .map_err(|e| ActionError::AccessDenied { reason: format!("Cannot write {}: {}", path.display(), e) })?;
+
+    // Bỏ kết quả DNS đã cache để sinkhole có hiệu lực ngay
+    #[cfg(windows)]
+    let _ = Command::new("ipconfig").arg("/flushdns").output();
+    Ok(())
+}
+
 // ============================================================================
 // CLEANUP
 // ============================================================================
@@ -260,6 +813,10 @@ pub fn cleanup_all_rules() -> Result<usize, ActionError> {
         Ok(_) => {
             let count = BLOCKED_PROCESSES.read().len();
             BLOCKED_PROCESSES.write().clear();
+            if let Err(e) = edit_hosts_file(|content| remove_sinkhole(content, None)) {
+                log::warn!("Failed to clean hosts file sinkholes: {}", e);
+            }
+            BLOCKED_DESTINATIONS.write().clear();
             log::info!("Cleaned up {} firewall rules", count);
             Ok(count)
         }
@@ -303,3 +860,50 @@ pub fn get_stats() -> NetworkStats {
         blocked_processes: blocked_info,
     }
 }
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hosts_sinkhole() {
+        assert_eq!(normalize_hostname("Login.Evil.Example."), Some("login.evil.example".to_string()));
+        assert_eq!(normalize_hostname("not a host"), None);
+        assert_eq!(normalize_hostname("localhost"), None);
+
+        let original = "127.0.0.1 localhost\n# comment";
+        let blocked = add_sinkhole(original, "evil.example");
+        assert!(blocked.contains("0.0.0.0 evil.example # OneShield_Block_dst"));
+        assert!(blocked.starts_with("127.0.0.1 localhost\n# comment\n"));
+        // Thêm lại không nhân đôi dòng
+        assert_eq!(add_sinkhole(&blocked, "evil.example"), blocked);
+
+        let both = add_sinkhole(&blocked, "bad.example");
+        let one = remove_sinkhole(&both, Some("evil.example"));
+        assert!(!one.contains("evil.example"));
+        assert!(one.contains("bad.example"));
+        assert_eq!(remove_sinkhole(&both, None), "127.0.0.1 localhost\n# comment\n");
+    }
+
+    #[test]
+    fn test_parse_firewall_policy() {
+        let output = "\nPublic Profile Settings: \n----------------------------------------------------------------------\nFirewall Policy                       BlockInbound,AllowOutbound\n\nOk.\n";
+        assert_eq!(parse_firewall_policy(output), Some("blockinbound,allowoutbound".to_string()));
+        assert_eq!(parse_firewall_policy("Richtlinie   BlockInboundAlways,BlockOutbound"), Some("blockinboundalways,blockoutbound".to_string()));
+        assert_eq!(parse_firewall_policy("Policy NotConfigured,AllowOutbound"), Some("notconfigured,allowoutbound".to_string()));
+        assert_eq!(parse_firewall_policy("Ok."), None);
+    }
+
+    #[test]
+    fn test_parse_resolvers() {
+        let powershell = "192.168.1.1\r\n8.8.8.8\r\nfec0:0:0:ffff::1\r\n192.168.1.1\r\n";
+        assert_eq!(parse_resolvers(powershell), vec!["192.168.1.1", "8.8.8.8", "fec0:0:0:ffff::1"]);
+
+        let resolv_conf = "# generated\nnameserver 127.0.0.53\noptions edns0\nsearch lan\n";
+        assert_eq!(parse_resolvers(resolv_conf), vec!["127.0.0.53"]);
+    }
+}
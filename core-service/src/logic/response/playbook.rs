@@ -0,0 +1,534 @@
+//! Remediation Playbooks (Phase 5)
+//!
+//! Mục đích: Chuỗi hành động khai báo (JSON / YAML) gắn với threat class / rule ID
+//!
+//! Ví dụ: suspend → quarantine exe → block network → collect triage.
+//! Mỗi bước được ghi vào action history (một lần); playbook custom đặt trong
+//! `%LOCALAPPDATA%/OneShield/playbooks/<id>.json|.yaml|.yml`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::actions::Origin;
+use super::types::{ActionResult, ActionError, ActionStatus, ResponseAction, AlertPayload, AlertSeverity};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const PLAYBOOK_FOLDER: &str = "playbooks";
+const PLAYBOOK_EXTENSIONS: &[&str] = &["json", "yaml", "yml"];
+const TRIAGE_FOLDER: &str = "triage";
+const MAX_RUNS: usize = 100;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Declarative playbook definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Playbook {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Threat classes that trigger this playbook (e.g. "Malicious")
+    #[serde(default)]
+    pub threat_classes: Vec<String>,
+    /// Rule IDs / tags that trigger this playbook
+    #[serde(default)]
+    pub rule_ids: Vec<String>,
+    /// Abort remaining steps when one fails
+    #[serde(default)]
+    pub stop_on_failure: bool,
+    pub steps: Vec<PlaybookStep>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// One playbook step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PlaybookStep {
+    SuspendProcess,
+    KillProcess {
+        #[serde(default)]
+        force: bool,
+    },
+    KillProcessTree,
+    QuarantineFile,
+    BlockNetwork,
+    IsolateHost,
+    CollectTriage,
+    SendAlert {
+        #[serde(default)]
+        webhook_id: Option<String>,
+    },
+}
+
+/// Target of a playbook run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlaybookContext {
+    pub pid: Option<u32>,
+    pub process_name: Option<String>,
+    pub exe_path: Option<PathBuf>,
+    pub threat_class: Option<String>,
+    pub rule_id: Option<String>,
+    pub incident_id: Option<String>,
+}
+
+/// Per-step outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepResult {
+    pub step: PlaybookStep,
+    pub success: bool,
+    pub message: String,
+    pub duration_ms: u64,
+}
+
+/// Result of a playbook run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaybookRun {
+    pub playbook_id: String,
+    pub context: PlaybookContext,
+    pub started_at: i64,
+    pub steps: Vec<StepResult>,
+    pub status: ActionStatus,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static PLAYBOOKS: Lazy<RwLock<HashMap<String, Playbook>>> =
+    Lazy::new(|| RwLock::new(load_playbooks()));
+
+static RUNS: Lazy<RwLock<Vec<PlaybookRun>>> =
+    Lazy::new(|| RwLock::new(Vec::new()));
+
+// ============================================================================
+// ENGINE
+// ============================================================================
+
+/// Find the first enabled playbook bound to a threat class or rule/tag
+pub fn find_playbook(threat_class: Option<&str>, tags: &[String]) -> Option<Playbook> {
+    let playbooks = PLAYBOOKS.read();
+    let mut candidates: Vec<_> = playbooks.values().filter(|p| p.enabled).collect();
+    candidates.sort_by(|a, b| a.id.cmp(&b.id));
+
+    candidates.into_iter().find(|p| {
+        let class_match = threat_class
+            .map(|c| p.threat_classes.iter().any(|t| t.eq_ignore_ascii_case(c)))
+            .unwrap_or(false);
+        let rule_match = tags.iter().any(|tag| p.rule_ids.iter().any(|r| r.eq_ignore_ascii_case(tag)));
+        class_match || rule_match
+    }).cloned()
+}
+
+/// Run a playbook by ID
+pub fn run_playbook(playbook_id: &str, context: PlaybookContext) -> Result<PlaybookRun, ActionError> {
+    let playbook = PLAYBOOKS.read().get(playbook_id).cloned()
+        .ok_or_else(|| ActionError::InvalidAction {
+            reason: format!("Playbook not found: {}", playbook_id),
+        })?;
+
+    Ok(execute(&playbook, context, Origin::Manual))
+}
+
+/// Execute all steps of a playbook. Playbook tự động (`Origin::Automatic`)
+/// đi qua response gate ở từng bước.
+pub fn execute(playbook: &Playbook, mut context: PlaybookContext, origin: Origin) -> PlaybookRun {
+    log::warn!("Running playbook '{}' ({} steps)", playbook.name, playbook.steps.len());
+
+    // Resolve exe path once - later steps (kill) may make it unavailable
+    if context.exe_path.is_none() {
+        if let Some(pid) = context.pid {
+            context.exe_path = crate::logic::process_intel::tree::get_process_info(pid)
+                .and_then(|p| p.exe_path);
+        }
+    }
+
+    let started_at = Utc::now().timestamp();
+    let mut steps = Vec::with_capacity(playbook.steps.len());
+
+    for step in &playbook.steps {
+        let start = Instant::now();
+        let outcome = execute_step(step, &context, origin);
+        let duration_ms = start.elapsed().as_millis() as u64;
+
+        let (success, message) = match &outcome {
+            Ok(result) => (result.status != ActionStatus::Failed, result.message.clone()),
+            Err(e) => (false, e.to_string()),
+        };
+
+        // Ghi từng bước vào action history - bước thành công của suspend / kill
+        // đã được chính action đó ghi
+        match outcome {
+            Ok(result) if !self_records(step) => super::actions::record_action(result),
+            Ok(_) => {}
+            Err(e) => super::actions::record_action(ActionResult {
+                action: step_action(step, &context),
+                status: ActionStatus::Failed,
+                message: format!("[{}] {}", playbook.id, e),
+                timestamp: Utc::now().timestamp(),
+                duration_ms,
+            }),
+        }
+
+        steps.push(StepResult { step: step.clone(), success, message, duration_ms });
+
+        if !success && playbook.stop_on_failure {
+            log::warn!("Playbook '{}' stopped at failed step {:?}", playbook.id, step);
+            break;
+        }
+    }
+
+    let ok = steps.iter().filter(|s| s.success).count();
+    let status = if ok == playbook.steps.len() {
+        ActionStatus::Success
+    } else if ok > 0 {
+        ActionStatus::PartialSuccess
+    } else {
+        ActionStatus::Failed
+    };
+
+    let run = PlaybookRun {
+        playbook_id: playbook.id.clone(),
+        context,
+        started_at,
+        steps,
+        status,
+    };
+
+    let mut runs = RUNS.write();
+    runs.push(run.clone());
+    if runs.len() > MAX_RUNS {
+        let excess = runs.len() - MAX_RUNS;
+        runs.drain(0..excess);
+    }
+
+    run
+}
+
+fn execute_step(step: &PlaybookStep, ctx: &PlaybookContext, origin: Origin) -> Result<ActionResult, ActionError> {
+    let pid = || ctx.pid.ok_or_else(|| ActionError::InvalidAction {
+        reason: "Step requires a target PID".to_string(),
+    });
+
+    super::actions::authorize(step_action(step, ctx).severity(), ctx.pid, origin)?;
+
+    match step {
+        PlaybookStep::SuspendProcess => super::actions::suspend_process(pid()?),
+        PlaybookStep::KillProcess { force } => super::actions::kill_process(pid()?, *force),
+        PlaybookStep::KillProcessTree => super::actions::kill_process_tree(pid()?),
+        PlaybookStep::BlockNetwork => super::network::block_network(pid()?, ctx.exe_path.clone()),
+        PlaybookStep::IsolateHost => super::network::isolate_host(&[]),
+        PlaybookStep::QuarantineFile => {
+            let start = Instant::now();
+            let path = ctx.exe_path.clone().ok_or_else(|| ActionError::InvalidAction {
+                reason: "No executable path to quarantine".to_string(),
+            })?;
+            let reason = format!("Playbook: {}", ctx.threat_class.as_deref().unwrap_or("manual"));
+            let entry = super::file_quarantine::quarantine_file(&path, &reason, ctx.incident_id.clone())?;
+            Ok(ActionResult {
+                action: ResponseAction::QuarantineFile { path },
+                status: ActionStatus::Success,
+                message: format!("Quarantined {} ({})", entry.file_name, entry.id),
+                timestamp: Utc::now().timestamp(),
+                duration_ms: start.elapsed().as_millis() as u64,
+            })
+        }
+        PlaybookStep::CollectTriage => collect_triage(pid()?),
+        PlaybookStep::SendAlert { webhook_id } => {
+            let start = Instant::now();
+            let mut payload = AlertPayload::new(
+                "One-Shield playbook executed",
+                &format!(
+                    "Threat {} on {} (PID {})",
+                    ctx.threat_class.as_deref().unwrap_or("-"),
+                    ctx.process_name.as_deref().unwrap_or("unknown"),
+                    ctx.pid.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string()),
+                ),
+                AlertSeverity::High,
+            );
+            payload.process_name = ctx.process_name.clone();
+            payload.process_pid = ctx.pid;
+            payload.incident_id = ctx.incident_id.clone();
+
+            let message = match webhook_id {
+                Some(id) => super::webhook::send_alert_to(id, &payload)?,
+                None => {
+                    let results = super::webhook::send_alert(&payload);
+                    format!("Alert sent to {} webhook(s)", results.iter().filter(|r| r.is_ok()).count())
+                }
+            };
+
+            Ok(ActionResult {
+                action: step_action(&PlaybookStep::SendAlert { webhook_id: webhook_id.clone() }, ctx),
+                status: ActionStatus::Success,
+                message,
+                timestamp: Utc::now().timestamp(),
+                duration_ms: start.elapsed().as_millis() as u64,
+            })
+        }
+    }
+}
+
+/// Step mà action bên dưới tự ghi vào action history khi thành công
+fn self_records(step: &PlaybookStep) -> bool {
+    matches!(
+        step,
+        PlaybookStep::SuspendProcess | PlaybookStep::KillProcess { .. } | PlaybookStep::KillProcessTree
+    )
+}
+
+/// ResponseAction tương ứng với step (dùng khi ghi history cho bước lỗi)
+fn step_action(step: &PlaybookStep, ctx: &PlaybookContext) -> ResponseAction {
+    let pid = ctx.pid.unwrap_or(0);
+    match step {
+        PlaybookStep::SuspendProcess => ResponseAction::SuspendProcess { pid },
+        PlaybookStep::KillProcess { force } => ResponseAction::KillProcess { pid, force: *force },
+        PlaybookStep::KillProcessTree => ResponseAction::KillProcessTree { pid },
+        PlaybookStep::BlockNetwork => ResponseAction::BlockNetwork { pid, exe_path: ctx.exe_path.clone() },
+        PlaybookStep::IsolateHost => ResponseAction::IsolateHost { allowed_hosts: vec![] },
+        PlaybookStep::QuarantineFile => ResponseAction::QuarantineFile {
+            path: ctx.exe_path.clone().unwrap_or_default(),
+        },
+        PlaybookStep::CollectTriage => ResponseAction::CollectTriage { pid },
+        PlaybookStep::SendAlert { webhook_id } => ResponseAction::SendAlert {
+            webhook_id: webhook_id.clone().unwrap_or_else(|| "all".to_string()),
+            message: "playbook".to_string(),
+        },
+    }
+}
+
+// ============================================================================
+// TRIAGE COLLECTION
+// ============================================================================
+
+/// Snapshot process context (ancestry, children, image) to a JSON file
+pub fn collect_triage(pid: u32) -> Result<ActionResult, ActionError> {
+    use crate::logic::process_intel::tree;
+
+    let start = Instant::now();
+    tree::refresh_tree();
+
+    let process = tree::get_process_info(pid).ok_or(ActionError::ProcessNotFound { pid })?;
+
+    let triage = serde_json::json!({
+        "collected_at": Utc::now().to_rfc3339(),
+        "hostname": hostname::get().ok().map(|h| h.to_string_lossy().to_string()),
+        "process": process,
+        "ancestry": tree::get_ancestry_chain(pid),
+        "descendants": tree::get_descendants(pid),
+        "network_blocked": super::network::is_network_blocked(pid),
+    });
+
+    let dir = data_dir().join(TRIAGE_FOLDER);
+    fs::create_dir_all(&dir).map_err(|e| ActionError::Other { message: e.to_string() })?;
+    let path = dir.join(format!("{}_{}.json", Utc::now().format("%Y%m%d_%H%M%S"), pid));
+
+    let json = serde_json::to_string_pretty(&triage)
+        .map_err(|e| ActionError::Other { message: e.to_string() })?;
+    fs::write(&path, json).map_err(|e| ActionError::Other { message: e.to_string() })?;
+
+    Ok(ActionResult {
+        action: ResponseAction::CollectTriage { pid },
+        status: ActionStatus::Success,
+        message: format!("Triage data saved to {}", path.display()),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+// ============================================================================
+// STORAGE
+// ============================================================================
+
+fn data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+}
+
+fn playbook_dir() -> PathBuf {
+    data_dir().join(PLAYBOOK_FOLDER)
+}
+
+/// Built-in playbooks + custom JSON / YAML files (custom overrides built-in by ID)
+fn load_playbooks() -> HashMap<String, Playbook> {
+    let mut map: HashMap<String, Playbook> = default_playbooks()
+        .into_iter()
+        .map(|p| (p.id.clone(), p))
+        .collect();
+
+    if let Ok(entries) = fs::read_dir(playbook_dir()) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let extension = extension(&path);
+            if !PLAYBOOK_EXTENSIONS.contains(&extension.as_str()) {
+                continue;
+            }
+            match fs::read_to_string(&path).map_err(|e| e.to_string())
+                .and_then(|c| parse_playbook(&extension, &c))
+            {
+                Ok(p) => {
+                    map.insert(p.id.clone(), p);
+                }
+                Err(e) => log::warn!("Invalid playbook {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    map
+}
+
+fn extension(path: &std::path::Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Parse playbook theo phần mở rộng file (`json`, còn lại là YAML)
+pub fn parse_playbook(extension: &str, content: &str) -> Result<Playbook, String> {
+    match extension {
+        "json" => serde_json::from_str(content).map_err(|e| format!("JSON parse error: {}", e)),
+        _ => serde_yaml::from_str(content).map_err(|e| format!("YAML parse error: {}", e)),
+    }
+}
+
+fn default_playbooks() -> Vec<Playbook> {
+    vec![Playbook {
+        id: "contain-malicious".to_string(),
+        name: "Contain malicious process".to_string(),
+        description: "Suspend, quarantine executable, block network, collect triage".to_string(),
+        enabled: false, // opt-in
+        threat_classes: vec!["Malicious".to_string()],
+        rule_ids: vec![],
+        stop_on_failure: false,
+        steps: vec![
+            PlaybookStep::SuspendProcess,
+            PlaybookStep::CollectTriage,
+            PlaybookStep::BlockNetwork,
+            PlaybookStep::KillProcess { force: true },
+            PlaybookStep::QuarantineFile,
+        ],
+    }]
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// List all playbooks
+pub fn get_playbooks() -> Vec<Playbook> {
+    let mut list: Vec<_> = PLAYBOOKS.read().values().cloned().collect();
+    list.sort_by(|a, b| a.id.cmp(&b.id));
+    list
+}
+
+/// Create or replace a playbook (persisted as JSON)
+pub fn save_playbook(playbook: Playbook) -> Result<(), ActionError> {
+    if playbook.id.is_empty() || playbook.id.contains(['/', '\\', '.']) {
+        return Err(ActionError::InvalidAction {
+            reason: format!("Invalid playbook id: '{}'", playbook.id),
+        });
+    }
+    if playbook.steps.is_empty() {
+        return Err(ActionError::InvalidAction {
+            reason: "Playbook has no steps".to_string(),
+        });
+    }
+
+    let dir = playbook_dir();
+    fs::create_dir_all(&dir).map_err(|e| ActionError::Other { message: e.to_string() })?;
+    let json = serde_json::to_string_pretty(&playbook)
+        .map_err(|e| ActionError::Other { message: e.to_string() })?;
+    fs::write(dir.join(format!("{}.json", playbook.id)), json)
+        .map_err(|e| ActionError::Other { message: e.to_string() })?;
+
+    PLAYBOOKS.write().insert(playbook.id.clone(), playbook);
+    Ok(())
+}
+
+/// Delete a custom playbook
+pub fn delete_playbook(playbook_id: &str) -> bool {
+    for ext in PLAYBOOK_EXTENSIONS {
+        let _ = fs::remove_file(playbook_dir().join(format!("{}.{}", playbook_id, ext)));
+    }
+    PLAYBOOKS.write().remove(playbook_id).is_some()
+}
+
+/// Recent playbook runs (newest last)
+pub fn get_runs(limit: usize) -> Vec<PlaybookRun> {
+    let runs = RUNS.read();
+    let start = runs.len().saturating_sub(limit);
+    runs[start..].to_vec()
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_playbook_json() {
+        let json = r#"{
+            "id": "ransomware",
+            "name": "Ransomware containment",
+            "rule_ids": ["RANSOM_001"],
+            "steps": [
+                {"action": "suspend_process"},
+                {"action": "kill_process", "force": true},
+                {"action": "send_alert", "webhook_id": "soc"}
+            ]
+        }"#;
+
+        let playbook: Playbook = serde_json::from_str(json).unwrap();
+        assert!(playbook.enabled);
+        assert_eq!(playbook.steps.len(), 3);
+        assert!(matches!(playbook.steps[1], PlaybookStep::KillProcess { force: true }));
+    }
+
+    #[test]
+    fn test_parse_playbook_yaml() {
+        let yaml = r#"
+id: ransomware
+name: Ransomware containment
+threat_classes: [Malicious]
+stop_on_failure: true
+steps:
+  - action: suspend_process
+  - action: kill_process_tree
+  - action: send_alert
+    webhook_id: soc
+"#;
+
+        let playbook = parse_playbook("yaml", yaml).unwrap();
+        assert!(playbook.enabled);
+        assert!(playbook.stop_on_failure);
+        assert_eq!(playbook.steps.len(), 3);
+        assert!(matches!(&playbook.steps[2], PlaybookStep::SendAlert { webhook_id: Some(id) } if id == "soc"));
+        assert!(parse_playbook("yml", "id: [").is_err());
+    }
+
+    #[test]
+    fn test_step_without_pid_fails() {
+        let result = execute_step(&PlaybookStep::SuspendProcess, &PlaybookContext::default(), Origin::Manual);
+        assert!(result.is_err());
+    }
+}
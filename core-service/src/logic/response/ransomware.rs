@@ -0,0 +1,291 @@
+//! Ransomware Response Profile (Phase 5)
+//!
+//! Mục đích: Phản ứng riêng khi tags cho thấy mã hóa / sửa file hàng loạt
+//!
+//! 1. Suspend cả process tree (root trước để không spawn thêm)
+//! 2. Shadow-copy guard: trong `GUARD_SECS` kill mọi vssadmin / wmic / wbadmin /
+//!    bcdedit / PowerShell xóa shadow copy hoặc tắt recovery
+//! 3. Snapshot danh sách file vừa bị sửa trong user folders vào incident
+//!    để phục vụ recovery triage
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::actions::Origin;
+use super::types::{ActionResult, ActionError, ActionStatus, ResponseAction};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Tags cho thấy hành vi mã hóa / sửa file hàng loạt
+const RANSOMWARE_TAGS: &[&str] = &[
+    "RANSOMWARE", "ENCRYPT_MASS", "MASS_FILE_MODIFICATION", "MASS_FILE_RENAME", "FILE_ENCRYPTION",
+];
+
+/// Shadow-copy guard hoạt động bao lâu sau khi phát hiện
+const GUARD_SECS: i64 = 600;
+const GUARD_POLL_MS: u64 = 500;
+
+/// File sửa trong khoảng này được đưa vào snapshot
+const RECENT_WINDOW_SECS: u64 = 15 * 60;
+const MAX_RECOVERY_FILES: usize = 500;
+const MAX_SCAN_DEPTH: usize = 6;
+
+/// Command line patterns xóa backup / tắt recovery
+const SHADOW_DELETE_PATTERNS: &[(&str, &str)] = &[
+    ("vssadmin", "delete shadows"),
+    ("vssadmin", "resize shadowstorage"),
+    ("wmic", "shadowcopy delete"),
+    ("wbadmin", "delete catalog"),
+    ("wbadmin", "delete systemstatebackup"),
+    ("bcdedit", "recoveryenabled no"),
+    ("powershell", "win32_shadowcopy"),
+];
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// Unix timestamp khi shadow-copy guard hết hạn (0 = tắt)
+static GUARD_UNTIL: AtomicI64 = AtomicI64::new(0);
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// File bị sửa gần đây (phục vụ recovery)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryFile {
+    pub path: String,
+    pub size: u64,
+    pub modified_at: i64,
+}
+
+/// Kết quả ransomware response
+#[derive(Debug, Clone, Serialize)]
+pub struct RansomwareResponse {
+    pub root_pid: u32,
+    pub suspended: Vec<u32>,
+    pub failed: Vec<u32>,
+    pub guard_until: i64,
+    pub recovery_files: usize,
+    pub incident_id: Option<String>,
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Tags có chỉ ra ransomware không
+pub fn is_ransomware(tags: &[String]) -> bool {
+    tags.iter().any(|t| {
+        let upper = t.to_uppercase();
+        RANSOMWARE_TAGS.iter().any(|r| upper.contains(r))
+    })
+}
+
+/// Chạy ransomware profile cho process `pid`
+pub fn respond(pid: u32, process_name: &str, tags: &[String]) -> Result<ActionResult, ActionError> {
+    use crate::logic::process_intel::tree;
+
+    let start = Instant::now();
+    log::error!("🔐 Ransomware profile triggered for {} (PID {})", process_name, pid);
+
+    // 1. Suspend tree - root trước rồi tới con (pre-order)
+    tree::refresh_tree();
+    let mut targets = vec![pid];
+    targets.extend(tree::get_descendants(pid).into_iter().map(|p| p.pid));
+
+    let mut suspended = Vec::new();
+    let mut failed = Vec::new();
+    for target in targets {
+        match super::actions::suspend_process(target) {
+            Ok(_) => suspended.push(target),
+            Err(e) => {
+                log::warn!("Ransomware profile: cannot suspend {}: {}", target, e);
+                failed.push(target);
+            }
+        }
+    }
+
+    // 2. Shadow-copy guard
+    let guard_until = arm_shadow_guard(GUARD_SECS);
+
+    // 3. Recovery snapshot
+    let files = recent_files(&user_folders(), RECENT_WINDOW_SECS, MAX_RECOVERY_FILES);
+    let incident_id = crate::logic::incident::attach_recovery_files(Some(pid), process_name, tags, files.clone());
+
+    let response = RansomwareResponse {
+        root_pid: pid,
+        suspended: suspended.clone(),
+        failed: failed.clone(),
+        guard_until,
+        recovery_files: files.len(),
+        incident_id: incident_id.map(|id| id.to_string()),
+    };
+    log::warn!("Ransomware response: {:?}", response);
+
+    if suspended.is_empty() {
+        return Err(ActionError::AccessDenied {
+            reason: format!("Could not suspend any process in tree of {}", pid),
+        });
+    }
+
+    let result = ActionResult {
+        action: ResponseAction::SuspendProcess { pid },
+        status: if failed.is_empty() { ActionStatus::Success } else { ActionStatus::PartialSuccess },
+        message: format!(
+            "Ransomware profile: suspended {} process(es), {} failed, shadow-copy guard {}s, {} recently modified files recorded",
+            suspended.len(), failed.len(), GUARD_SECS, files.len()
+        ),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    };
+    super::actions::record_action(result.clone());
+
+    Ok(result)
+}
+
+/// Bật shadow-copy guard trong `secs` giây (gia hạn nếu đang chạy).
+/// Trả về timestamp hết hạn.
+pub fn arm_shadow_guard(secs: i64) -> i64 {
+    let until = Utc::now().timestamp() + secs;
+    let previous = GUARD_UNTIL.swap(until, Ordering::SeqCst);
+
+    // Thread cũ vẫn đang chạy - chỉ cần gia hạn
+    if previous > Utc::now().timestamp() {
+        return until;
+    }
+
+    std::thread::spawn(|| {
+        log::warn!("Shadow-copy guard armed");
+        let mut system = sysinfo::System::new();
+        while Utc::now().timestamp() < GUARD_UNTIL.load(Ordering::SeqCst) {
+            system.refresh_processes();
+            for (pid, process) in system.processes() {
+                let cmdline = process.cmd().join(" ");
+                if is_shadow_delete(process.name(), &cmdline) {
+                    log::error!("Blocked shadow-copy tampering: {} ({})", cmdline, pid);
+                    let kill = ResponseAction::KillProcess { pid: pid.as_u32(), force: true };
+                    match super::actions::execute_gated(kill, Origin::Automatic) {
+                        Ok(_) | Err(ActionError::NotPermitted { .. }) => {}
+                        Err(e) => log::warn!("Failed to kill {}: {}", pid, e),
+                    }
+                }
+            }
+            std::thread::sleep(Duration::from_millis(GUARD_POLL_MS));
+        }
+        log::info!("Shadow-copy guard expired");
+    });
+
+    until
+}
+
+/// Guard đang hoạt động?
+pub fn is_guard_active() -> bool {
+    GUARD_UNTIL.load(Ordering::SeqCst) > Utc::now().timestamp()
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// Command line có xóa shadow copy / tắt recovery không
+fn is_shadow_delete(name: &str, cmdline: &str) -> bool {
+    let name = name.to_lowercase();
+    let cmdline = cmdline.to_lowercase();
+    SHADOW_DELETE_PATTERNS.iter().any(|(image, pattern)| {
+        (name.starts_with(image) || cmdline.contains(image)) && cmdline.contains(pattern)
+    })
+}
+
+fn user_folders() -> Vec<PathBuf> {
+    [dirs::desktop_dir(), dirs::document_dir(), dirs::picture_dir(), dirs::download_dir()]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// File bị sửa trong `window_secs` gần nhất (mới nhất trước)
+fn recent_files(roots: &[PathBuf], window_secs: u64, limit: usize) -> Vec<RecoveryFile> {
+    let cutoff = SystemTime::now() - Duration::from_secs(window_secs);
+    let mut files = Vec::new();
+    for root in roots {
+        walk(root, 0, cutoff, &mut files, limit * 4);
+    }
+    files.sort_by(|a, b| b.modified_at.cmp(&a.modified_at));
+    files.truncate(limit);
+    files
+}
+
+fn walk(dir: &Path, depth: usize, cutoff: SystemTime, out: &mut Vec<RecoveryFile>, cap: usize) {
+    if depth > MAX_SCAN_DEPTH || out.len() >= cap {
+        return;
+    }
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let meta = match entry.metadata() {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        if meta.is_dir() {
+            walk(&entry.path(), depth + 1, cutoff, out, cap);
+        } else if let Ok(modified) = meta.modified() {
+            if modified >= cutoff {
+                out.push(RecoveryFile {
+                    path: entry.path().to_string_lossy().to_string(),
+                    size: meta.len(),
+                    modified_at: chrono::DateTime::<Utc>::from(modified).timestamp(),
+                });
+            }
+        }
+        if out.len() >= cap {
+            return;
+        }
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_ransomware() {
+        assert!(is_ransomware(&["ENCRYPT_MASS".to_string()]));
+        assert!(is_ransomware(&["mass_file_modification".to_string()]));
+        assert!(!is_ransomware(&["RAPIDDISKACTIVITY".to_string()]));
+    }
+
+    #[test]
+    fn test_is_shadow_delete() {
+        assert!(is_shadow_delete("vssadmin.exe", "vssadmin.exe Delete Shadows /All /Quiet"));
+        assert!(is_shadow_delete("WMIC.exe", "wmic shadowcopy delete"));
+        assert!(is_shadow_delete("bcdedit.exe", "bcdedit /set {default} recoveryenabled No"));
+        assert!(!is_shadow_delete("vssadmin.exe", "vssadmin list shadows"));
+    }
+
+    #[test]
+    fn test_recent_files() {
+        let dir = std::env::temp_dir().join(format!("oneshield_rw_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub").join("a.docx.locked"), b"x").unwrap();
+
+        let files = recent_files(&[dir.clone()], 60, 10);
+        assert_eq!(files.len(), 1);
+        assert!(files[0].path.ends_with("a.docx.locked"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
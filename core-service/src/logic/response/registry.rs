@@ -0,0 +1,466 @@
+//! Registry Persistence Rollback Module (Phase 5)
+//!
+//! Mục đích: Gỡ persistence (Run key value, service key) mà `persistence.rs` phát hiện
+//!
+//! Trước khi xóa luôn snapshot lại (raw bytes + kiểu value, hoặc `reg export` cả key)
+//! để có thể undo nguyên vẹn (kể cả REG_MULTI_SZ / REG_BINARY).
+//!
+//! Detector gọi `remediate_alert` → Action Guard (`REMOVE_PERSISTENCE`): whitelist,
+//! Safety Config, approval (chỉ alert Critical được gỡ tự động), rate limit, undo.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Instant;
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::types::{ActionResult, ActionError, ActionStatus, ResponseAction};
+use crate::logic::action_guard::{self, ActionType};
+use crate::logic::behavioral_sigs::{PersistenceAlert, PersistenceMechanism, PersistenceSeverity};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const SNAPSHOT_FOLDER: &str = "registry_snapshots";
+const METADATA_FILE: &str = "snapshots.json";
+
+/// Hives tried (in order) when the alert location has no hive prefix
+const HIVES: &[&str] = &["HKLM", "HKCU"];
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static SNAPSHOTS: Lazy<RwLock<HashMap<String, RegistrySnapshot>>> =
+    Lazy::new(|| RwLock::new(load_snapshots()));
+
+/// Snapshot of a removed persistence entry
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RegistrySnapshot {
+    pub id: String,
+    /// Full key path including hive (e.g. `HKCU\Software\...\Run`)
+    pub key: String,
+    /// Value name - None means the whole key was removed (services)
+    pub value_name: Option<String>,
+    /// REG_SZ, REG_EXPAND_SZ, ... (hiển thị)
+    pub value_type: Option<String>,
+    /// Data dạng text (hiển thị)
+    pub value_data: Option<String>,
+    /// Kiểu value gốc (`REG_VALUE_TYPE`) - restore dùng cái này
+    #[serde(default)]
+    pub value_kind: Option<u32>,
+    /// Data gốc, nguyên byte
+    #[serde(default)]
+    pub value_raw: Option<Vec<u8>>,
+    /// `reg export` file for whole-key removals
+    pub export_file: Option<PathBuf>,
+    pub removed_at: i64,
+    pub restored: bool,
+}
+
+// ============================================================================
+// ACTIONS
+// ============================================================================
+
+/// Remediate a persistence alert qua Action Guard: snapshot + remove the offending
+/// value/key. Alert Critical được gỡ ngay, còn lại tạo pending action chờ approval.
+pub fn remediate_alert(alert: &PersistenceAlert) -> Result<action_guard::ActionResult, action_guard::ActionError> {
+    // COM hijack: xóa cả key CLSID của HKCU → Windows dùng lại entry HKLM
+    let whole_key = matches!(alert.mechanism, PersistenceMechanism::Service | PersistenceMechanism::ComHijack);
+    let value_name = if whole_key { None } else { alert.value_name.as_deref() };
+
+    action_guard::execute_action(
+        ActionType::RemovePersistence,
+        (alert.process_pid != 0).then_some(alert.process_pid),
+        &persistence_target(&alert.location, value_name),
+        1.0,
+        vec!["PERSISTENCE".to_string(), alert.mitre_technique.clone()],
+        alert.severity == PersistenceSeverity::Critical,
+    )
+}
+
+/// Target của action `REMOVE_PERSISTENCE`: `key [value]`, hoặc `key` khi xóa cả key
+pub fn persistence_target(location: &str, value_name: Option<&str>) -> String {
+    describe(location, value_name)
+}
+
+/// Ngược lại của `persistence_target` → (key, value name)
+pub fn parse_persistence_target(target: &str) -> (String, Option<String>) {
+    match target.strip_suffix(']').and_then(|t| t.rsplit_once(" [")) {
+        Some((key, value)) => (key.to_string(), Some(value.to_string())),
+        None => (target.to_string(), None),
+    }
+}
+
+/// Snapshot and delete a registry value (or the whole key if `value_name` is None)
+pub fn remove_persistence(location: &str, value_name: Option<&str>) -> Result<(ActionResult, RegistrySnapshot), ActionError> {
+    let start = Instant::now();
+
+    // Never delete a monitored root (e.g. the whole Services or Run key)
+    if value_name.is_none() {
+        let trimmed = location.trim_end_matches('\\').to_lowercase();
+        if crate::logic::behavioral_sigs::PERSISTENCE_KEYS.iter()
+            .any(|(root, _)| trimmed.ends_with(&root.to_lowercase()))
+        {
+            return Err(ActionError::InvalidAction {
+                reason: format!("Refusing to delete persistence root key {}", location),
+            });
+        }
+    }
+
+    let key = resolve_key(location, value_name).ok_or_else(|| ActionError::FileNotFound {
+        path: match value_name {
+            Some(v) => format!("{}\\{}", location, v),
+            None => location.to_string(),
+        },
+    })?;
+
+    let id = Uuid::new_v4().to_string();
+    let mut snapshot = RegistrySnapshot {
+        id: id.clone(),
+        key: key.clone(),
+        value_name: value_name.map(|s| s.to_string()),
+        value_type: None,
+        value_data: None,
+        value_kind: None,
+        value_raw: None,
+        export_file: None,
+        removed_at: Utc::now().timestamp(),
+        restored: false,
+    };
+
+    match value_name {
+        Some(name) => {
+            // Raw bytes là bản backup thật - không đọc được thì không xóa
+            let (kind, raw) = platform::read_value(&key, name).ok_or_else(|| ActionError::Other {
+                message: format!("Cannot back up {}", describe(&key, Some(name))),
+            })?;
+            snapshot.value_kind = Some(kind);
+            snapshot.value_raw = Some(raw);
+            if let Ok((value_type, value_data)) = query_value(&key, name) {
+                snapshot.value_type = Some(value_type);
+                snapshot.value_data = Some(value_data);
+            }
+            run_reg(&["delete", &key, "/v", name, "/f"])?;
+        }
+        None => {
+            let export_file = get_snapshot_dir().join(format!("{}.reg", id));
+            run_reg(&["export", &key, &export_file.to_string_lossy(), "/y"])?;
+            snapshot.export_file = Some(export_file);
+            run_reg(&["delete", &key, "/f"])?;
+        }
+    }
+
+    SNAPSHOTS.write().insert(id.clone(), snapshot.clone());
+    save_snapshots();
+
+    let result = ActionResult {
+        action: ResponseAction::RemovePersistence {
+            key: key.clone(),
+            value_name: value_name.map(|s| s.to_string()),
+        },
+        status: ActionStatus::Success,
+        message: format!("Removed persistence {} (snapshot {})", describe(&key, value_name), id),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    };
+
+    log::warn!("Removed persistence {} (snapshot {})", describe(&key, value_name), id);
+    super::actions::record_action(result.clone());
+
+    Ok((result, snapshot))
+}
+
+/// Undo a removal from its snapshot
+pub fn restore_persistence(snapshot_id: &str) -> Result<ActionResult, ActionError> {
+    let start = Instant::now();
+
+    let snapshot = SNAPSHOTS.read().get(snapshot_id).cloned()
+        .ok_or_else(|| ActionError::InvalidAction {
+            reason: format!("Snapshot not found: {}", snapshot_id),
+        })?;
+
+    if snapshot.restored {
+        return Err(ActionError::InvalidAction {
+            reason: format!("Snapshot {} already restored", snapshot_id),
+        });
+    }
+
+    match (&snapshot.value_name, &snapshot.export_file) {
+        (Some(name), _) if snapshot.value_kind.is_some() && snapshot.value_raw.is_some() => {
+            let kind = snapshot.value_kind.unwrap_or_default();
+            platform::write_value(&snapshot.key, name, kind, snapshot.value_raw.as_deref().unwrap_or_default())?;
+        }
+        (Some(name), _) => {
+            // Snapshot cũ (chỉ có text)
+            let value_type = snapshot.value_type.as_deref().unwrap_or("REG_SZ");
+            let value_data = snapshot.value_data.as_deref().unwrap_or("");
+            run_reg(&["add", &snapshot.key, "/v", name, "/t", value_type, "/d", value_data, "/f"])?;
+        }
+        (None, Some(file)) => {
+            run_reg(&["import", &file.to_string_lossy()])?;
+        }
+        (None, None) => {
+            return Err(ActionError::InvalidAction {
+                reason: "Snapshot has no data to restore".to_string(),
+            });
+        }
+    }
+
+    if let Some(s) = SNAPSHOTS.write().get_mut(snapshot_id) {
+        s.restored = true;
+    }
+    save_snapshots();
+
+    let result = ActionResult {
+        action: ResponseAction::RestorePersistence { snapshot_id: snapshot_id.to_string() },
+        status: ActionStatus::Success,
+        message: format!("Restored {}", describe(&snapshot.key, snapshot.value_name.as_deref())),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    };
+
+    log::info!("Restored persistence snapshot {}", snapshot_id);
+    super::actions::record_action(result.clone());
+
+    Ok(result)
+}
+
+/// Get all snapshots (newest first)
+pub fn get_snapshots() -> Vec<RegistrySnapshot> {
+    let mut list: Vec<_> = SNAPSHOTS.read().values().cloned().collect();
+    list.sort_by(|a, b| b.removed_at.cmp(&a.removed_at));
+    list
+}
+
+// ============================================================================
+// REG HELPERS
+// ============================================================================
+
+/// Find the hive that actually contains the key/value
+fn resolve_key(location: &str, value_name: Option<&str>) -> Option<String> {
+    let upper = location.to_uppercase();
+    let candidates: Vec<String> = if upper.starts_with("HK") {
+        vec![location.to_string()]
+    } else {
+        HIVES.iter().map(|h| format!("{}\\{}", h, location)).collect()
+    };
+
+    candidates.into_iter().find(|key| {
+        let mut args = vec!["query", key.as_str()];
+        if let Some(name) = value_name {
+            args.extend(["/v", name]);
+        }
+        run_reg(&args).is_ok()
+    })
+}
+
+/// Read type + data of a value via `reg query`
+fn query_value(key: &str, name: &str) -> Result<(String, String), ActionError> {
+    let output = reg_output(&["query", key, "/v", name])?;
+    parse_query_output(&output, name).ok_or_else(|| ActionError::Other {
+        message: format!("Cannot parse reg query output for {}", name),
+    })
+}
+
+/// Parse `    <name>    REG_SZ    <data>` line
+fn parse_query_output(output: &str, name: &str) -> Option<(String, String)> {
+    output.lines().find_map(|line| {
+        let line = line.trim_start();
+        let rest = line.strip_prefix(name)?;
+        let rest = rest.trim_start();
+        if !rest.starts_with("REG_") {
+            return None;
+        }
+        let (value_type, data) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        Some((value_type.to_string(), data.trim().to_string()))
+    })
+}
+
+fn run_reg(args: &[&str]) -> Result<(), ActionError> {
+    reg_output(args).map(|_| ())
+}
+
+fn reg_output(args: &[&str]) -> Result<String, ActionError> {
+    let output = Command::new("reg")
+        .args(args)
+        .output()
+        .map_err(|e| ActionError::Other { message: e.to_string() })?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(ActionError::CommandFailed {
+            command: format!("reg {}", args.first().unwrap_or(&"")),
+            exit_code: output.status.code().unwrap_or(-1),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+}
+
+fn describe(key: &str, value_name: Option<&str>) -> String {
+    match value_name {
+        Some(v) => format!("{} [{}]", key, v),
+        None => key.to_string(),
+    }
+}
+
+/// `HKCU\Software\...` / `HKEY_CURRENT_USER\Software\...` → (hive viết tắt, subkey)
+fn split_hive(key: &str) -> Option<(&'static str, &str)> {
+    let (hive, subkey) = key.split_once('\\').unwrap_or((key, ""));
+    let hive = match hive.to_uppercase().as_str() {
+        "HKLM" | "HKEY_LOCAL_MACHINE" => "HKLM",
+        "HKCU" | "HKEY_CURRENT_USER" => "HKCU",
+        "HKU" | "HKEY_USERS" => "HKU",
+        "HKCR" | "HKEY_CLASSES_ROOT" => "HKCR",
+        _ => return None,
+    };
+    Some((hive, subkey))
+}
+
+#[cfg(windows)]
+mod platform {
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::System::Registry::{
+        RegCloseKey, RegCreateKeyExW, RegOpenKeyExW, RegQueryValueExW, RegSetValueExW, HKEY,
+        HKEY_CLASSES_ROOT, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, HKEY_USERS, KEY_QUERY_VALUE,
+        KEY_SET_VALUE, REG_OPTION_NON_VOLATILE, REG_VALUE_TYPE,
+    };
+
+    use super::ActionError;
+
+    fn root(hive: &str) -> HKEY {
+        match hive {
+            "HKLM" => HKEY_LOCAL_MACHINE,
+            "HKU" => HKEY_USERS,
+            "HKCR" => HKEY_CLASSES_ROOT,
+            _ => HKEY_CURRENT_USER,
+        }
+    }
+
+    /// (kiểu value, raw bytes)
+    pub fn read_value(key: &str, name: &str) -> Option<(u32, Vec<u8>)> {
+        let (hive, subkey) = super::split_hive(key)?;
+        let mut hkey = HKEY::default();
+        unsafe { RegOpenKeyExW(root(hive), &HSTRING::from(subkey), 0, KEY_QUERY_VALUE, &mut hkey) }
+            .ok()
+            .ok()?;
+
+        let name = HSTRING::from(name);
+        let mut kind = REG_VALUE_TYPE::default();
+        let mut len = 0u32;
+        let mut value = None;
+        if unsafe { RegQueryValueExW(hkey, &name, None, Some(&mut kind), None, Some(&mut len)) }.is_ok() {
+            let mut data = vec![0u8; len as usize];
+            let status = unsafe {
+                RegQueryValueExW(hkey, &name, None, Some(&mut kind), Some(data.as_mut_ptr()), Some(&mut len))
+            };
+            if status.is_ok() {
+                data.truncate(len as usize);
+                value = Some((kind.0, data));
+            }
+        }
+        unsafe { let _ = RegCloseKey(hkey); }
+        value
+    }
+
+    pub fn write_value(key: &str, name: &str, kind: u32, data: &[u8]) -> Result<(), ActionError> {
+        let (hive, subkey) = super::split_hive(key).ok_or_else(|| ActionError::InvalidAction {
+            reason: format!("Unknown registry hive: {}", key),
+        })?;
+        let mut hkey = HKEY::default();
+        unsafe {
+            RegCreateKeyExW(
+                root(hive), &HSTRING::from(subkey), 0, PCWSTR::null(), REG_OPTION_NON_VOLATILE, KEY_SET_VALUE,
+                None, &mut hkey, None,
+            )
+        }
+        .ok()
+        .map_err(|e| ActionError::AccessDenied { reason: format!("Cannot open {}: {}", key, e) })?;
+
+        let status = unsafe { RegSetValueExW(hkey, &HSTRING::from(name), 0, REG_VALUE_TYPE(kind), Some(data)) };
+        unsafe { let _ = RegCloseKey(hkey); }
+        status.ok().map_err(|e| ActionError::AccessDenied { reason: format!("Cannot write {}: {}", name, e) })
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::ActionError;
+
+    pub fn read_value(_key: &str, _name: &str) -> Option<(u32, Vec<u8>)> {
+        None
+    }
+
+    pub fn write_value(_key: &str, _name: &str, _kind: u32, _data: &[u8]) -> Result<(), ActionError> {
+        Err(ActionError::InvalidAction { reason: "Registry not supported on this platform".to_string() })
+    }
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn get_snapshot_dir() -> PathBuf {
+    let dir = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(SNAPSHOT_FOLDER);
+    let _ = fs::create_dir_all(&dir);
+    dir
+}
+
+fn load_snapshots() -> HashMap<String, RegistrySnapshot> {
+    let path = get_snapshot_dir().join(METADATA_FILE);
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Vec<RegistrySnapshot>>(&content).ok())
+        .map(|list| list.into_iter().map(|s| (s.id.clone(), s)).collect())
+        .unwrap_or_default()
+}
+
+fn save_snapshots() {
+    let path = get_snapshot_dir().join(METADATA_FILE);
+    let list: Vec<_> = SNAPSHOTS.read().values().cloned().collect();
+    if let Ok(json) = serde_json::to_string_pretty(&list) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query_output() {
+        let output = "\r\nHKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Run\r\n    Updater    REG_SZ    C:\\Users\\a\\AppData\\Local\\Temp\\evil.exe -silent\r\n\r\n";
+        let (value_type, data) = parse_query_output(output, "Updater").unwrap();
+        assert_eq!(value_type, "REG_SZ");
+        assert_eq!(data, "C:\\Users\\a\\AppData\\Local\\Temp\\evil.exe -silent");
+    }
+
+    #[test]
+    fn test_parse_query_output_missing() {
+        assert!(parse_query_output("ERROR: not found", "Updater").is_none());
+    }
+
+    #[test]
+    fn test_persistence_target_roundtrip() {
+        let key = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+        let target = persistence_target(key, Some("Updater"));
+        assert_eq!(parse_persistence_target(&target), (key.to_string(), Some("Updater".to_string())));
+        assert_eq!(parse_persistence_target(key), (key.to_string(), None));
+
+        assert_eq!(split_hive(r"HKEY_LOCAL_MACHINE\SYSTEM\CurrentControlSet"), Some(("HKLM", r"SYSTEM\CurrentControlSet")));
+        assert_eq!(split_hive(r"Software\Foo"), None);
+    }
+}
@@ -19,21 +19,60 @@ pub enum ResponseAction {
     /// Kill a process
     KillProcess { pid: u32, force: bool },
 
+    /// Kill a process and all of its descendants (children first)
+    KillProcessTree { pid: u32 },
+
+    /// Kill a browser-spawned child, quarantine its download and block its hash
+    ContainBrowserChild { pid: u32 },
+
     /// Block network for a process
     BlockNetwork { pid: u32, exe_path: Option<PathBuf> },
 
     /// Unblock network for a process
     UnblockNetwork { pid: u32 },
 
+    /// Block outbound traffic to a remote IP (all processes)
+    BlockDestination { ip: String },
+
+    /// Lift a destination block
+    UnblockDestination { ip: String },
+
+    /// Isolate the whole host (only cloud server + DNS reachable)
+    IsolateHost { allowed_hosts: Vec<String> },
+
+    /// Lift host isolation
+    ReleaseHost,
+
+    /// Snapshot + remove a registry persistence value (or whole key)
+    RemovePersistence { key: String, value_name: Option<String> },
+
+    /// Undo a persistence removal from its snapshot
+    RestorePersistence { snapshot_id: String },
+
     /// Quarantine a file
     QuarantineFile { path: PathBuf },
 
     /// Restore a quarantined file
     RestoreFile { quarantine_id: String },
 
+    /// Quarantine a whole directory tree
+    QuarantineDirectory { path: PathBuf },
+
+    /// Restore a quarantined directory tree
+    RestoreDirectory { group_id: String },
+
     /// Delete a quarantined file permanently
     DeleteQuarantined { quarantine_id: String },
 
+    /// Disable a USB storage device (None = all removable storage)
+    BlockUsb { device_id: Option<String> },
+
+    /// Undo a USB block
+    UnblockUsb { block_id: String },
+
+    /// Snapshot process context for triage
+    CollectTriage { pid: u32 },
+
     /// Send webhook alert
     SendAlert { webhook_id: String, message: String },
 
@@ -47,16 +86,74 @@ impl ResponseAction {
             ResponseAction::SuspendProcess { .. } => "suspend_process",
             ResponseAction::ResumeProcess { .. } => "resume_process",
             ResponseAction::KillProcess { .. } => "kill_process",
+            ResponseAction::KillProcessTree { .. } => "kill_process_tree",
+            ResponseAction::ContainBrowserChild { .. } => "contain_browser_child",
             ResponseAction::BlockNetwork { .. } => "block_network",
             ResponseAction::UnblockNetwork { .. } => "unblock_network",
+            ResponseAction::BlockDestination { .. } => "block_destination",
+            ResponseAction::UnblockDestination { .. } => "unblock_destination",
+            ResponseAction::IsolateHost { .. } => "isolate_host",
+            ResponseAction::ReleaseHost => "release_host",
+            ResponseAction::RemovePersistence { .. } => "remove_persistence",
+            ResponseAction::RestorePersistence { .. } => "restore_persistence",
             ResponseAction::QuarantineFile { .. } => "quarantine_file",
             ResponseAction::RestoreFile { .. } => "restore_file",
+            ResponseAction::QuarantineDirectory { .. } => "quarantine_directory",
+            ResponseAction::RestoreDirectory { .. } => "restore_directory",
             ResponseAction::DeleteQuarantined { .. } => "delete_quarantined",
+            ResponseAction::BlockUsb { .. } => "block_usb",
+            ResponseAction::UnblockUsb { .. } => "unblock_usb",
+            ResponseAction::CollectTriage { .. } => "collect_triage",
             ResponseAction::SendAlert { .. } => "send_alert",
             ResponseAction::Custom { name, .. } => "custom",
         }
     }
 
+    /// Mức can thiệp, cùng thang với Action Guard
+    /// (0 = khôi phục, 1 = alert, 2-3 = contain, 4 = kill, 5 = isolate)
+    pub fn severity(&self) -> u8 {
+        match self {
+            ResponseAction::SuspendProcess { .. } => 2,
+            ResponseAction::BlockNetwork { .. }
+            | ResponseAction::BlockDestination { .. }
+            | ResponseAction::RemovePersistence { .. }
+            | ResponseAction::QuarantineFile { .. }
+            | ResponseAction::QuarantineDirectory { .. }
+            | ResponseAction::DeleteQuarantined { .. }
+            | ResponseAction::BlockUsb { .. } => 3,
+            ResponseAction::KillProcess { .. }
+            | ResponseAction::KillProcessTree { .. }
+            | ResponseAction::ContainBrowserChild { .. } => 4,
+            ResponseAction::IsolateHost { .. } => 5,
+            ResponseAction::CollectTriage { .. }
+            | ResponseAction::SendAlert { .. }
+            | ResponseAction::Custom { .. } => 1,
+            ResponseAction::ResumeProcess { .. }
+            | ResponseAction::UnblockNetwork { .. }
+            | ResponseAction::UnblockDestination { .. }
+            | ResponseAction::ReleaseHost
+            | ResponseAction::RestorePersistence { .. }
+            | ResponseAction::RestoreFile { .. }
+            | ResponseAction::RestoreDirectory { .. }
+            | ResponseAction::UnblockUsb { .. } => 0,
+        }
+    }
+
+    /// PID bị tác động (nếu action nhắm vào process)
+    pub fn target_pid(&self) -> Option<u32> {
+        match self {
+            ResponseAction::SuspendProcess { pid }
+            | ResponseAction::ResumeProcess { pid }
+            | ResponseAction::KillProcess { pid, .. }
+            | ResponseAction::KillProcessTree { pid }
+            | ResponseAction::ContainBrowserChild { pid }
+            | ResponseAction::BlockNetwork { pid, .. }
+            | ResponseAction::UnblockNetwork { pid }
+            | ResponseAction::CollectTriage { pid } => Some(*pid),
+            _ => None,
+        }
+    }
+
     pub fn description(&self) -> String {
         match self {
             ResponseAction::SuspendProcess { pid } => format!("Suspend process {}", pid),
@@ -68,11 +165,27 @@ impl ResponseAction {
                     format!("Kill process {}", pid)
                 }
             }
+            ResponseAction::KillProcessTree { pid } => format!("Kill process tree of {}", pid),
+            ResponseAction::ContainBrowserChild { pid } => format!("Contain browser child {}", pid),
             ResponseAction::BlockNetwork { pid, .. } => format!("Block network for PID {}", pid),
             ResponseAction::UnblockNetwork { pid } => format!("Unblock network for PID {}", pid),
+            ResponseAction::BlockDestination { ip } => format!("Block outbound traffic to {}", ip),
+            ResponseAction::UnblockDestination { ip } => format!("Unblock outbound traffic to {}", ip),
+            ResponseAction::IsolateHost { .. } => "Isolate host from network".to_string(),
+            ResponseAction::ReleaseHost => "Release host isolation".to_string(),
+            ResponseAction::RemovePersistence { key, .. } => format!("Remove persistence {}", key),
+            ResponseAction::RestorePersistence { snapshot_id } => format!("Restore persistence {}", snapshot_id),
             ResponseAction::QuarantineFile { path } => format!("Quarantine {}", path.display()),
             ResponseAction::RestoreFile { quarantine_id } => format!("Restore {}", quarantine_id),
+            ResponseAction::QuarantineDirectory { path } => format!("Quarantine directory {}", path.display()),
+            ResponseAction::RestoreDirectory { group_id } => format!("Restore directory {}", group_id),
             ResponseAction::DeleteQuarantined { quarantine_id } => format!("Delete {}", quarantine_id),
+            ResponseAction::BlockUsb { device_id } => match device_id {
+                Some(id) => format!("Block USB device {}", id),
+                None => "Block all removable storage".to_string(),
+            },
+            ResponseAction::UnblockUsb { block_id } => format!("Unblock USB {}", block_id),
+            ResponseAction::CollectTriage { pid } => format!("Collect triage for PID {}", pid),
             ResponseAction::SendAlert { webhook_id, .. } => format!("Send alert to {}", webhook_id),
             ResponseAction::Custom { name, .. } => format!("Custom: {}", name),
         }
@@ -126,6 +239,8 @@ pub enum ActionError {
     CommandFailed { command: String, exit_code: i32, stderr: String },
     /// Invalid action
     InvalidAction { reason: String },
+    /// Blocked by the response gate (dry-run, auto-block off, rate limit)
+    NotPermitted { reason: String },
     /// Other error
     Other { message: String },
 }
@@ -141,6 +256,7 @@ impl std::fmt::Display for ActionError {
                 write!(f, "Command '{}' failed ({}): {}", command, exit_code, stderr)
             }
             ActionError::InvalidAction { reason } => write!(f, "Invalid action: {}", reason),
+            ActionError::NotPermitted { reason } => write!(f, "Not permitted: {}", reason),
             ActionError::Other { message } => write!(f, "Error: {}", message),
         }
     }
@@ -165,6 +281,42 @@ pub struct QuarantineEntry {
     pub reason: String,
     pub source_incident: Option<String>,
     pub can_restore: bool,
+    /// File được mã hóa AES-256-GCM trong vault (false = legacy entry, plain move)
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Per-file key, wrapped bằng vault master key (base64: nonce || ciphertext)
+    #[serde(default)]
+    pub wrapped_key: Option<String>,
+    /// Nonce dùng để mã hóa nội dung file (base64)
+    #[serde(default)]
+    pub nonce: Option<String>,
+    /// Original ACL (SDDL) - restored on restore
+    #[serde(default)]
+    pub original_acl: Option<String>,
+    #[serde(default)]
+    pub original_readonly: bool,
+    /// Original modification time (unix seconds)
+    #[serde(default)]
+    pub original_modified: Option<i64>,
+    /// Directory quarantine group this file belongs to
+    #[serde(default)]
+    pub group_id: Option<String>,
+    /// Path relative to the group root
+    #[serde(default)]
+    pub relative_path: Option<PathBuf>,
+}
+
+/// A directory tree quarantined in one operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantineGroup {
+    pub id: String,
+    pub root: PathBuf,
+    pub reason: String,
+    pub entry_ids: Vec<String>,
+    /// Sub-directories (relative to root) so empty folders are restored too
+    pub directories: Vec<PathBuf>,
+    pub total_size: u64,
+    pub quarantine_time: i64,
 }
 
 // ============================================================================
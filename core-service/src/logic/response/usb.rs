@@ -0,0 +1,350 @@
+//! USB Storage Blocking Module (Phase 5)
+//!
+//! Mục đích: Chặn USB storage khi phát hiện exfiltration ra removable media
+//!
+//! - Một device cụ thể: disable qua PnP (`pnputil /disable-device`, SetupAPI)
+//! - Toàn bộ removable storage: registry policy
+//!   `HKLM\SOFTWARE\Policies\Microsoft\Windows\RemovableStorageDevices\Deny_All`
+//!
+//! Mỗi block được lưu ra disk (kèm giá trị policy cũ) để undo sau restart.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Instant;
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::types::{ActionResult, ActionError, ActionStatus, ResponseAction};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const POLICY_KEY: &str = r"HKLM\SOFTWARE\Policies\Microsoft\Windows\RemovableStorageDevices";
+const POLICY_VALUE: &str = "Deny_All";
+const BLOCKS_FILE: &str = "usb_blocks.json";
+
+/// Tags chỉ ra exfiltration ra removable media
+const EXFIL_TAGS: &[&str] = &["USB_EXFILTRATION", "REMOVABLE_MEDIA_EXFIL", "MASS_COPY_TO_REMOVABLE"];
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UsbTarget {
+    /// PnP instance ID (vd: `USBSTOR\DISK&VEN_...`)
+    Device { instance_id: String },
+    AllRemovable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsbBlock {
+    pub id: String,
+    pub target: UsbTarget,
+    /// Giá trị Deny_All trước khi block (None = chưa tồn tại)
+    pub previous_policy: Option<u32>,
+    pub blocked_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsbDevice {
+    pub instance_id: String,
+    pub friendly_name: String,
+    pub status: String,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static BLOCKS: Lazy<RwLock<HashMap<String, UsbBlock>>> = Lazy::new(|| RwLock::new(load_blocks()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Tags có chỉ ra exfiltration ra USB không
+pub fn is_removable_exfil(tags: &[String]) -> bool {
+    tags.iter().any(|t| {
+        let upper = t.to_uppercase();
+        EXFIL_TAGS.iter().any(|e| upper.contains(e))
+    })
+}
+
+/// Block một USB storage device, hoặc toàn bộ removable storage nếu `instance_id` = None
+pub fn block_usb(instance_id: Option<&str>) -> Result<(ActionResult, UsbBlock), ActionError> {
+    let start = Instant::now();
+
+    let target = match instance_id.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(id) => UsbTarget::Device { instance_id: id.to_string() },
+        None => UsbTarget::AllRemovable,
+    };
+
+    if let Some(existing) = BLOCKS.read().values().find(|b| b.target == target) {
+        return Err(ActionError::InvalidAction {
+            reason: format!("Already blocked ({})", existing.id),
+        });
+    }
+
+    let previous_policy = match &target {
+        UsbTarget::Device { instance_id } => {
+            platform::disable_device(instance_id)?;
+            None
+        }
+        UsbTarget::AllRemovable => {
+            let previous = platform::read_policy();
+            platform::write_policy(Some(1))?;
+            previous
+        }
+    };
+
+    let block = UsbBlock {
+        id: uuid::Uuid::new_v4().to_string(),
+        target: target.clone(),
+        previous_policy,
+        blocked_at: Utc::now().timestamp(),
+    };
+    BLOCKS.write().insert(block.id.clone(), block.clone());
+    save_blocks();
+
+    let result = ActionResult {
+        action: ResponseAction::BlockUsb { device_id: instance_id.map(|s| s.to_string()) },
+        status: ActionStatus::Success,
+        message: format!("Blocked {} ({})", describe(&target), block.id),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    };
+
+    log::warn!("{}", result.message);
+    super::actions::record_action(result.clone());
+    Ok((result, block))
+}
+
+/// Undo một USB block
+pub fn unblock_usb(block_id: &str) -> Result<ActionResult, ActionError> {
+    let start = Instant::now();
+
+    let block = BLOCKS.read().get(block_id).cloned().ok_or_else(|| ActionError::InvalidAction {
+        reason: format!("USB block not found: {}", block_id),
+    })?;
+
+    match &block.target {
+        UsbTarget::Device { instance_id } => platform::enable_device(instance_id)?,
+        UsbTarget::AllRemovable => platform::write_policy(block.previous_policy)?,
+    }
+
+    BLOCKS.write().remove(block_id);
+    save_blocks();
+
+    let result = ActionResult {
+        action: ResponseAction::UnblockUsb { block_id: block_id.to_string() },
+        status: ActionStatus::Success,
+        message: format!("Unblocked {}", describe(&block.target)),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    };
+
+    log::info!("{}", result.message);
+    super::actions::record_action(result.clone());
+    Ok(result)
+}
+
+/// Các USB block đang có hiệu lực
+pub fn get_blocks() -> Vec<UsbBlock> {
+    let mut list: Vec<_> = BLOCKS.read().values().cloned().collect();
+    list.sort_by(|a, b| b.blocked_at.cmp(&a.blocked_at));
+    list
+}
+
+/// Liệt kê USB storage devices đang cắm
+pub fn list_devices() -> Result<Vec<UsbDevice>, ActionError> {
+    platform::list_devices()
+}
+
+fn describe(target: &UsbTarget) -> String {
+    match target {
+        UsbTarget::Device { instance_id } => format!("USB device {}", instance_id),
+        UsbTarget::AllRemovable => "all removable storage".to_string(),
+    }
+}
+
+// ============================================================================
+// WINDOWS BACKEND
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+
+    pub fn disable_device(instance_id: &str) -> Result<(), ActionError> {
+        run("pnputil", &["/disable-device", instance_id])
+    }
+
+    pub fn enable_device(instance_id: &str) -> Result<(), ActionError> {
+        run("pnputil", &["/enable-device", instance_id])
+    }
+
+    pub fn read_policy() -> Option<u32> {
+        let output = Command::new("reg")
+            .args(["query", POLICY_KEY, "/v", POLICY_VALUE])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        // `    Deny_All    REG_DWORD    0x1`
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|l| l.split_whitespace().last()?.strip_prefix("0x").map(|v| v.to_string()))
+            .and_then(|v| u32::from_str_radix(&v, 16).ok())
+    }
+
+    pub fn write_policy(value: Option<u32>) -> Result<(), ActionError> {
+        match value {
+            Some(v) => run("reg", &[
+                "add", POLICY_KEY, "/v", POLICY_VALUE, "/t", "REG_DWORD", "/d", &v.to_string(), "/f",
+            ])?,
+            None => run("reg", &["delete", POLICY_KEY, "/v", POLICY_VALUE, "/f"])?,
+        }
+        // Áp dụng policy ngay
+        let _ = Command::new("gpupdate").args(["/target:computer", "/force"]).output();
+        Ok(())
+    }
+
+    pub fn list_devices() -> Result<Vec<UsbDevice>, ActionError> {
+        let script = "Get-PnpDevice -PresentOnly | Where-Object { $_.InstanceId -like 'USBSTOR*' } | \
+                      Select-Object InstanceId,FriendlyName,Status | ConvertTo-Json -Compress";
+        let output = Command::new("powershell")
+            .args(["-NoProfile", "-Command", script])
+            .output()
+            .map_err(|e| ActionError::Other { message: e.to_string() })?;
+
+        let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if text.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // ConvertTo-Json trả object khi chỉ có 1 device
+        let value: serde_json::Value = serde_json::from_str(&text)
+            .map_err(|e| ActionError::Other { message: e.to_string() })?;
+        let items = match value {
+            serde_json::Value::Array(items) => items,
+            other => vec![other],
+        };
+
+        Ok(items.iter().map(|d| UsbDevice {
+            instance_id: d["InstanceId"].as_str().unwrap_or_default().to_string(),
+            friendly_name: d["FriendlyName"].as_str().unwrap_or_default().to_string(),
+            status: d["Status"].as_str().unwrap_or_default().to_string(),
+        }).collect())
+    }
+
+    fn run(program: &str, args: &[&str]) -> Result<(), ActionError> {
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .map_err(|e| ActionError::Other { message: e.to_string() })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(ActionError::CommandFailed {
+                command: format!("{} {}", program, args.first().unwrap_or(&"")),
+                exit_code: output.status.code().unwrap_or(-1),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            })
+        }
+    }
+}
+
+// ============================================================================
+// FALLBACK (non-Windows)
+// ============================================================================
+
+#[cfg(not(windows))]
+mod platform {
+    use super::*;
+
+    fn unsupported() -> ActionError {
+        ActionError::InvalidAction {
+            reason: "USB blocking is only supported on Windows".to_string(),
+        }
+    }
+
+    pub fn disable_device(_instance_id: &str) -> Result<(), ActionError> {
+        Err(unsupported())
+    }
+
+    pub fn enable_device(_instance_id: &str) -> Result<(), ActionError> {
+        Err(unsupported())
+    }
+
+    pub fn read_policy() -> Option<u32> {
+        None
+    }
+
+    pub fn write_policy(_value: Option<u32>) -> Result<(), ActionError> {
+        Err(unsupported())
+    }
+
+    pub fn list_devices() -> Result<Vec<UsbDevice>, ActionError> {
+        Ok(Vec::new())
+    }
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn blocks_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(BLOCKS_FILE)
+}
+
+fn load_blocks() -> HashMap<String, UsbBlock> {
+    fs::read_to_string(blocks_path())
+        .ok()
+        .and_then(|c| serde_json::from_str::<Vec<UsbBlock>>(&c).ok())
+        .map(|list| list.into_iter().map(|b| (b.id.clone(), b)).collect())
+        .unwrap_or_default()
+}
+
+fn save_blocks() {
+    let path = blocks_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let list: Vec<_> = BLOCKS.read().values().cloned().collect();
+    if let Ok(json) = serde_json::to_string_pretty(&list) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_removable_exfil() {
+        assert!(is_removable_exfil(&["USB_EXFILTRATION".to_string()]));
+        assert!(!is_removable_exfil(&["NETWORK_SPIKE".to_string()]));
+    }
+
+    #[test]
+    fn test_usb_target_serde() {
+        let json = serde_json::to_string(&UsbTarget::AllRemovable).unwrap();
+        assert!(json.contains("all_removable"));
+    }
+}
@@ -0,0 +1,166 @@
+//! Enforcement Verification (Phase 5)
+//!
+//! Mục đích: Kiểm tra action đã thực sự có hiệu lực
+//!
+//! - Kill: PID không còn tồn tại (hoặc đã bị tái sử dụng bởi image khác)
+//! - Suspend: mọi thread đang ở trạng thái suspended
+//! - Block network: WFP filter / firewall rule vẫn còn
+//!
+//! Action Guard gọi sau mỗi action và retry / escalate nếu không đạt.
+
+use std::process::Command;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Action đang có hiệu lực
+    Enforced,
+    /// Action không có hiệu lực (kèm lý do)
+    NotEnforced(String),
+    /// Không kiểm tra được trên platform này
+    Unverifiable,
+}
+
+// ============================================================================
+// PROBES
+// ============================================================================
+
+/// Process `pid` còn sống với đúng image `name` không.
+/// PID bị tái sử dụng bởi process khác được coi như đã chết.
+pub fn process_alive(pid: u32, name: &str) -> bool {
+    let mut system = sysinfo::System::new();
+    let spid = sysinfo::Pid::from_u32(pid);
+    if !system.refresh_process(spid) {
+        return false;
+    }
+    match system.process(spid) {
+        Some(process) => name.is_empty() || same_image(process.name(), name),
+        None => false,
+    }
+}
+
+/// Kill đã có hiệu lực chưa
+pub fn verify_killed(pid: u32, name: &str) -> Outcome {
+    if process_alive(pid, name) {
+        Outcome::NotEnforced(format!("Process {} ({}) is still running", pid, name))
+    } else {
+        Outcome::Enforced
+    }
+}
+
+/// Suspend đã có hiệu lực chưa (process đã thoát cũng coi là đạt)
+pub fn verify_suspended(pid: u32, name: &str) -> Outcome {
+    if !process_alive(pid, name) {
+        return Outcome::Enforced;
+    }
+    match is_suspended(pid) {
+        Some(true) => Outcome::Enforced,
+        Some(false) => Outcome::NotEnforced(format!("Process {} has running threads", pid)),
+        None => Outcome::Unverifiable,
+    }
+}
+
+/// Block network đã có hiệu lực chưa
+pub fn verify_network_blocked(pid: u32, name: &str) -> Outcome {
+    if !process_alive(pid, name) {
+        return Outcome::Enforced;
+    }
+    match network_block_present(pid) {
+        Some(true) => Outcome::Enforced,
+        Some(false) => Outcome::NotEnforced(format!("No network block found for PID {}", pid)),
+        None => Outcome::Unverifiable,
+    }
+}
+
+fn same_image(actual: &str, expected: &str) -> bool {
+    let base = |s: &str| s.rsplit(['\\', '/']).next().unwrap_or(s).to_lowercase();
+    base(actual) == base(expected)
+}
+
+// ============================================================================
+// PLATFORM BACKENDS
+// ============================================================================
+
+#[cfg(windows)]
+fn is_suspended(pid: u32) -> Option<bool> {
+    // Đếm thread không ở trạng thái Suspended
+    let script = format!(
+        "((Get-Process -Id {}).Threads | Where-Object {{ $_.WaitReason -ne 'Suspended' }}).Count",
+        pid
+    );
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-Command", &script])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let running: u32 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(running == 0)
+}
+
+#[cfg(not(windows))]
+fn is_suspended(pid: u32) -> Option<bool> {
+    // /proc/<pid>/stat: "pid (comm) S ..." - state 'T' = stopped
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let state = stat.rsplit(')').next()?.split_whitespace().next()?;
+    Some(state == "T" || state == "t")
+}
+
+#[cfg(windows)]
+fn network_block_present(pid: u32) -> Option<bool> {
+    if super::wfp::is_blocked(pid) {
+        return Some(true);
+    }
+    let output = Command::new("netsh")
+        .args([
+            "advfirewall", "firewall", "show", "rule",
+            &format!("name=AISecurityBlock_{}", pid),
+        ])
+        .output()
+        .ok()?;
+    Some(output.status.success())
+}
+
+#[cfg(not(windows))]
+fn network_block_present(pid: u32) -> Option<bool> {
+    let output = Command::new("iptables")
+        .args([
+            "-C", "OUTPUT",
+            "-m", "owner", "--pid-owner", &pid.to_string(),
+            "-j", "DROP",
+        ])
+        .output()
+        .ok()?;
+    // exit 1 = rule không tồn tại, khác = không kiểm tra được (thiếu quyền...)
+    match output.status.code() {
+        Some(0) => Some(true),
+        Some(1) => Some(false),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_image() {
+        assert!(same_image("C:\\Temp\\Evil.exe", "evil.exe"));
+        assert!(!same_image("notepad.exe", "evil.exe"));
+    }
+
+    #[test]
+    fn test_current_process_alive() {
+        let pid = std::process::id();
+        assert!(process_alive(pid, ""));
+        assert_eq!(verify_killed(pid, ""), Outcome::NotEnforced(format!("Process {} () is still running", pid)));
+    }
+}
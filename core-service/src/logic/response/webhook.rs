@@ -258,6 +258,21 @@ impl AlertManager {
             }));
         }
 
+        // Pending action: analyst approve/reject qua cloud console (cần đăng nhập console)
+        if let Some(action_id) = payload.extra.get("action_id") {
+            let mut text = format!("*Action ID:* `{}`", action_id);
+            if let (Some(approve), Some(reject)) = (payload.extra.get("approve_url"), payload.extra.get("reject_url")) {
+                text.push_str(&format!(
+                    "\n*Approve:* `POST {}`\n*Reject:* `POST {}`\n_Cloud console API - requires an analyst console login_",
+                    approve, reject
+                ));
+            }
+            blocks.push(serde_json::json!({
+                "type": "context",
+                "elements": [{ "type": "mrkdwn", "text": text }]
+            }));
+        }
+
         serde_json::json!({
             "blocks": blocks,
             "attachments": [{
@@ -554,6 +569,19 @@ mod tests {
         assert!(formatted.contains("blocks"));
     }
 
+    #[test]
+    fn test_format_slack_pending_action() {
+        let manager = AlertManager::new();
+        let mut payload = AlertPayload::new("Approval required", "KILL_PROCESS", AlertSeverity::High);
+        payload.extra.insert("action_id".to_string(), "abc-123".to_string());
+        payload.extra.insert("approve_url".to_string(), "https://cloud/actions/abc-123/approve".to_string());
+        payload.extra.insert("reject_url".to_string(), "https://cloud/actions/abc-123/reject".to_string());
+
+        let formatted = manager.format_slack(&payload, false);
+        assert!(formatted.contains("abc-123"));
+        assert!(formatted.contains("requires an analyst console login"));
+    }
+
     #[test]
     fn test_format_discord() {
         let manager = AlertManager::new();
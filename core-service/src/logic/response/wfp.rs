@@ -0,0 +1,319 @@
+//! WFP Network Blocking (Phase 5)
+//!
+//! Mục đích: Block network theo PID qua Windows Filtering Platform
+//!
+//! Khác với netsh (match theo đường dẫn program do caller truyền vào), ở đây
+//! image path được lấy trực tiếp từ process đang chạy nên vẫn bắt được bản
+//! copy trong %TEMP%. Filter nằm trong dynamic session: tự biến mất khi agent
+//! thoát, và bị gỡ ngay khi process đích exit (watcher thread).
+//!
+//! Lưu ý: classic WFP filter không có condition theo PID, nên app ID của image
+//! được dùng - các instance khác của cùng binary cũng bị chặn cho tới khi gỡ.
+
+use std::collections::HashMap;
+use std::time::Instant;
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+
+use super::types::{ActionResult, ActionError, ActionStatus, ResponseAction};
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static WFP_BLOCKS: Lazy<RwLock<HashMap<u32, WfpBlock>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct WfpBlock {
+    pub pid: u32,
+    pub image_path: String,
+    pub filter_ids: Vec<u64>,
+    pub blocked_at: i64,
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Block all inbound/outbound connections of a running process
+pub fn block_pid(pid: u32) -> Result<ActionResult, ActionError> {
+    let start = Instant::now();
+
+    if WFP_BLOCKS.read().contains_key(&pid) {
+        return Err(ActionError::InvalidAction {
+            reason: format!("Process {} is already blocked", pid),
+        });
+    }
+
+    let (image_path, filter_ids) = platform::add_filters(pid)?;
+
+    WFP_BLOCKS.write().insert(pid, WfpBlock {
+        pid,
+        image_path: image_path.clone(),
+        filter_ids,
+        blocked_at: Utc::now().timestamp(),
+    });
+
+    // Auto cleanup khi process exit
+    platform::watch_exit(pid);
+
+    log::warn!("WFP blocked network for PID {} ({})", pid, image_path);
+
+    Ok(ActionResult {
+        action: ResponseAction::BlockNetwork {
+            pid,
+            exe_path: Some(image_path.clone().into()),
+        },
+        status: ActionStatus::Success,
+        message: format!("Blocked network for {} ({}) via WFP", pid, image_path),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// Remove WFP filters for a process
+pub fn unblock_pid(pid: u32) -> Result<ActionResult, ActionError> {
+    let start = Instant::now();
+
+    let block = WFP_BLOCKS.write().remove(&pid).ok_or_else(|| ActionError::InvalidAction {
+        reason: format!("Process {} is not blocked by WFP", pid),
+    })?;
+
+    platform::remove_filters(&block.filter_ids)?;
+
+    log::info!("WFP unblocked network for PID {}", pid);
+
+    Ok(ActionResult {
+        action: ResponseAction::UnblockNetwork { pid },
+        status: ActionStatus::Success,
+        message: format!("Unblocked network for PID {}", pid),
+        timestamp: Utc::now().timestamp(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// Check if a PID is blocked via WFP
+pub fn is_blocked(pid: u32) -> bool {
+    WFP_BLOCKS.read().contains_key(&pid)
+}
+
+/// Get all WFP blocks
+pub fn get_blocked() -> Vec<WfpBlock> {
+    WFP_BLOCKS.read().values().cloned().collect()
+}
+
+/// Called by the exit watcher - drop filters of a process that has terminated
+fn on_process_exit(pid: u32) {
+    if let Some(block) = WFP_BLOCKS.write().remove(&pid) {
+        match platform::remove_filters(&block.filter_ids) {
+            Ok(()) => log::info!("PID {} exited - removed {} WFP filters", pid, block.filter_ids.len()),
+            Err(e) => log::warn!("PID {} exited - failed to remove WFP filters: {}", pid, e),
+        }
+    }
+}
+
+// ============================================================================
+// WINDOWS BACKEND
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use once_cell::sync::Lazy;
+    use parking_lot::Mutex;
+    use windows::core::{PCWSTR, PWSTR, GUID};
+    use windows::Win32::Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0};
+    use windows::Win32::NetworkManagement::WindowsFilteringPlatform::*;
+    use windows::Win32::Security::PSECURITY_DESCRIPTOR;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, WaitForSingleObject, INFINITE,
+        PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SYNCHRONIZE,
+    };
+
+    use super::ActionError;
+
+    const RPC_C_AUTHN_DEFAULT: u32 = 0xFFFF_FFFF;
+
+    /// Engine handle (raw pointer as usize - HANDLE is not Send)
+    static ENGINE: Lazy<Mutex<Option<usize>>> = Lazy::new(|| Mutex::new(None));
+
+    fn engine() -> Result<HANDLE, ActionError> {
+        let mut guard = ENGINE.lock();
+        if let Some(raw) = *guard {
+            return Ok(HANDLE(raw as *mut _));
+        }
+
+        let mut name: Vec<u16> = "One-Shield WFP session\0".encode_utf16().collect();
+        let session = FWPM_SESSION0 {
+            displayData: FWPM_DISPLAY_DATA0 {
+                name: PWSTR(name.as_mut_ptr()),
+                description: PWSTR::null(),
+            },
+            // Dynamic: toàn bộ filter bị xóa khi engine handle đóng
+            flags: FWPM_SESSION_FLAG_DYNAMIC,
+            ..Default::default()
+        };
+
+        let mut handle = HANDLE::default();
+        let status = unsafe {
+            FwpmEngineOpen0(PCWSTR::null(), RPC_C_AUTHN_DEFAULT, None, Some(&session), &mut handle)
+        };
+        check(status, "FwpmEngineOpen0")?;
+
+        *guard = Some(handle.0 as usize);
+        Ok(handle)
+    }
+
+    pub fn add_filters(pid: u32) -> Result<(String, Vec<u64>), ActionError> {
+        let image_path = image_path_of(pid)?;
+        let engine = engine()?;
+
+        let wide: Vec<u16> = image_path.encode_utf16().chain(std::iter::once(0)).collect();
+        let mut app_id: *mut FWP_BYTE_BLOB = std::ptr::null_mut();
+        check(
+            unsafe { FwpmGetAppIdFromFileName0(PCWSTR(wide.as_ptr()), &mut app_id) },
+            "FwpmGetAppIdFromFileName0",
+        )?;
+
+        let layers = [
+            FWPM_LAYER_ALE_AUTH_CONNECT_V4,
+            FWPM_LAYER_ALE_AUTH_CONNECT_V6,
+            FWPM_LAYER_ALE_AUTH_RECV_ACCEPT_V4,
+            FWPM_LAYER_ALE_AUTH_RECV_ACCEPT_V6,
+        ];
+
+        let mut name: Vec<u16> = format!("OneShield_Block_{}\0", pid).encode_utf16().collect();
+        let mut ids = Vec::with_capacity(layers.len());
+
+        for layer in layers {
+            let mut condition = FWPM_FILTER_CONDITION0 {
+                fieldKey: FWPM_CONDITION_ALE_APP_ID,
+                matchType: FWP_MATCH_EQUAL,
+                conditionValue: FWP_CONDITION_VALUE0 {
+                    r#type: FWP_BYTE_BLOB_TYPE,
+                    Anonymous: FWP_CONDITION_VALUE0_0 { byteBlob: app_id },
+                },
+            };
+
+            let filter = FWPM_FILTER0 {
+                displayData: FWPM_DISPLAY_DATA0 {
+                    name: PWSTR(name.as_mut_ptr()),
+                    description: PWSTR::null(),
+                },
+                layerKey: layer,
+                subLayerKey: GUID::zeroed(),
+                weight: FWP_VALUE0 {
+                    r#type: FWP_UINT8,
+                    Anonymous: FWP_VALUE0_0 { uint8: 15 },
+                },
+                numFilterConditions: 1,
+                filterCondition: &mut condition,
+                action: FWPM_ACTION0 {
+                    r#type: FWP_ACTION_BLOCK,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+
+            let mut id = 0u64;
+            let status = unsafe {
+                FwpmFilterAdd0(engine, &filter, PSECURITY_DESCRIPTOR::default(), Some(&mut id))
+            };
+            if let Err(e) = check(status, "FwpmFilterAdd0") {
+                let _ = remove_filters(&ids);
+                unsafe { FwpmFreeMemory0(&mut app_id as *mut _ as *mut *mut _) };
+                return Err(e);
+            }
+            ids.push(id);
+        }
+
+        unsafe { FwpmFreeMemory0(&mut app_id as *mut _ as *mut *mut _) };
+        Ok((image_path, ids))
+    }
+
+    pub fn remove_filters(ids: &[u64]) -> Result<(), ActionError> {
+        let engine = engine()?;
+        let mut last_err = None;
+        for id in ids {
+            if let Err(e) = check(unsafe { FwpmFilterDeleteById0(engine, *id) }, "FwpmFilterDeleteById0") {
+                last_err = Some(e);
+            }
+        }
+        last_err.map_or(Ok(()), Err)
+    }
+
+    pub fn watch_exit(pid: u32) {
+        let handle = match unsafe { OpenProcess(PROCESS_SYNCHRONIZE, false, pid) } {
+            Ok(h) => h.0 as usize,
+            Err(e) => {
+                log::debug!("Cannot watch PID {} for exit: {}", pid, e);
+                return;
+            }
+        };
+
+        std::thread::spawn(move || {
+            let handle = HANDLE(handle as *mut _);
+            let result = unsafe { WaitForSingleObject(handle, INFINITE) };
+            unsafe {
+                let _ = CloseHandle(handle);
+            }
+            if result == WAIT_OBJECT_0 {
+                super::on_process_exit(pid);
+            }
+        });
+    }
+
+    fn image_path_of(pid: u32) -> Result<String, ActionError> {
+        let handle = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }
+            .map_err(|_| ActionError::ProcessNotFound { pid })?;
+
+        let mut buf = [0u16; 1024];
+        let mut len = buf.len() as u32;
+        let result = unsafe {
+            QueryFullProcessImageNameW(handle, PROCESS_NAME_WIN32, PWSTR(buf.as_mut_ptr()), &mut len)
+        };
+        unsafe {
+            let _ = CloseHandle(handle);
+        }
+
+        result.map_err(|e| ActionError::AccessDenied {
+            reason: format!("QueryFullProcessImageNameW({}) failed: {}", pid, e),
+        })?;
+
+        Ok(String::from_utf16_lossy(&buf[..len as usize]))
+    }
+
+    fn check(status: u32, api: &str) -> Result<(), ActionError> {
+        if status == 0 {
+            Ok(())
+        } else {
+            Err(ActionError::CommandFailed {
+                command: api.to_string(),
+                exit_code: status as i32,
+                stderr: format!("WFP error 0x{:08X}", status),
+            })
+        }
+    }
+}
+
+// ============================================================================
+// FALLBACK (non-Windows)
+// ============================================================================
+
+#[cfg(not(windows))]
+mod platform {
+    use super::ActionError;
+
+    pub fn add_filters(pid: u32) -> Result<(String, Vec<u64>), ActionError> {
+        Err(ActionError::InvalidAction {
+            reason: format!("WFP is not available on this platform (PID {})", pid),
+        })
+    }
+
+    pub fn remove_filters(_ids: &[u64]) -> Result<(), ActionError> {
+        Ok(())
+    }
+
+    pub fn watch_exit(_pid: u32) {}
+}
@@ -0,0 +1,686 @@
+//! Elasticsearch / OpenSearch Exporter - Ship telemetry + incident qua `_bulk` API
+//!
+//! Mục đích: Team tự vận hành ELK / OpenSearch nhận event + incident không cần cloud console
+//!
+//! - SecurityEvent → `<prefix>-events-YYYY.MM.DD` (action `create`, `_id` = event id
+//!   → gửi lại sau lỗi không tạo bản trùng, 409 tính là đã có)
+//! - Incident → `<prefix>-incidents-YYYY.MM.DD` theo ngày bắt đầu (action `index`,
+//!   `_id` = incident id → incident cập nhật ghi đè document cũ). Chỉ gửi incident
+//!   thay đổi từ lần gửi trước.
+//! - Index template cho 2 pattern (mapping ECS-style, string → keyword) được PUT khi bật
+//!   hoặc đổi config; `lifecycle_policy` gắn vào template (ILM với Elasticsearch,
+//!   ISM với OpenSearch) → index theo ngày rollover / xóa theo tuổi.
+//! - Event vào queue có giới hạn (đầy → bỏ event cũ nhất), worker flush định kỳ;
+//!   cluster lỗi → event giữ lại cho lần sau.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::logic::cloud_sync::managed_settings;
+use crate::logic::incident::{self, Incident};
+use super::event::SecurityEvent;
+use super::exporter;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CONFIG_FILE: &str = "elastic.json";
+const MAX_QUEUE: usize = 50_000;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Worker tick (flush thực tế theo `flush_interval_secs`)
+const TICK: Duration = Duration::from_secs(1);
+
+// ============================================================================
+// CONFIG
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClusterFlavor {
+    Elasticsearch,
+    Opensearch,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ElasticConfig {
+    pub enabled: bool,
+    /// Base URL của cluster ("https://es.example.local:9200")
+    pub url: String,
+    pub flavor: ClusterFlavor,
+    /// Basic auth
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Elasticsearch API key (base64 "id:key"), ưu tiên hơn basic auth
+    pub api_key: Option<String>,
+    /// Tiền tố index: `<prefix>-events-*`, `<prefix>-incidents-*`
+    pub index_prefix: String,
+    pub ship_incidents: bool,
+    /// Số document tối đa mỗi request `_bulk`
+    pub batch_size: usize,
+    pub flush_interval_secs: u64,
+    /// PUT index template khi bật / đổi config
+    pub install_templates: bool,
+    /// Tên ILM (Elasticsearch) / ISM (OpenSearch) policy đã tạo sẵn trên cluster
+    pub lifecycle_policy: Option<String>,
+}
+
+impl Default for ElasticConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            flavor: ClusterFlavor::Elasticsearch,
+            username: None,
+            password: None,
+            api_key: None,
+            index_prefix: "oneshield".to_string(),
+            ship_incidents: true,
+            batch_size: 500,
+            flush_interval_secs: 10,
+            install_templates: true,
+            lifecycle_policy: None,
+        }
+    }
+}
+
+impl ElasticConfig {
+    fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let url = self.url.trim();
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err("Elasticsearch url must start with http:// or https://".to_string());
+        }
+        let prefix = &self.index_prefix;
+        if prefix.is_empty()
+            || !prefix.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+            || prefix.starts_with(['-', '_'])
+        {
+            return Err("index_prefix must be lowercase letters, digits, '-' or '_'".to_string());
+        }
+        if self.batch_size == 0 || self.flush_interval_secs == 0 {
+            return Err("batch_size and flush_interval_secs must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+
+    fn base_url(&self) -> &str {
+        self.url.trim().trim_end_matches('/')
+    }
+
+    fn auth_header(&self) -> Option<String> {
+        if let Some(key) = self.api_key.as_deref().filter(|k| !k.trim().is_empty()) {
+            return Some(format!("ApiKey {}", key.trim()));
+        }
+        let user = self.username.as_deref().filter(|u| !u.is_empty())?;
+        let credentials = format!("{}:{}", user, self.password.as_deref().unwrap_or(""));
+        Some(format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(credentials)))
+    }
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ElasticStatus {
+    pub enabled: bool,
+    pub templates_installed: bool,
+    pub events_sent: u64,
+    pub incidents_sent: u64,
+    /// Document bị cluster từ chối (mapping lỗi, ...) - không gửi lại
+    pub rejected: u64,
+    /// Queue đầy → bỏ event cũ nhất
+    pub dropped: u64,
+    pub queued: usize,
+    pub last_flush: Option<i64>,
+    pub last_error: Option<String>,
+}
+
+struct ExportState {
+    queue: VecDeque<SecurityEvent>,
+    /// incident id → fingerprint lần gửi thành công gần nhất
+    shipped_incidents: HashMap<String, u64>,
+    /// Config generation đã cài template
+    templates_generation: Option<u64>,
+    last_flush_at: i64,
+}
+
+static CONFIG: Lazy<RwLock<ElasticConfig>> = Lazy::new(|| RwLock::new(load_config()));
+static CONFIG_GENERATION: AtomicU64 = AtomicU64::new(0);
+/// Config đang theo managed settings từ cloud → không sửa local được
+static MANAGED: AtomicBool = AtomicBool::new(false);
+static STATE: Lazy<Mutex<ExportState>> = Lazy::new(|| Mutex::new(ExportState {
+    queue: VecDeque::new(),
+    shipped_incidents: HashMap::new(),
+    templates_generation: None,
+    last_flush_at: 0,
+}));
+static STATUS: Lazy<Mutex<ElasticStatus>> = Lazy::new(|| Mutex::new(ElasticStatus::default()));
+/// Chỉ 1 flush tại một thời điểm (worker + `flush_now`)
+static FLUSHING: Mutex<()> = Mutex::new(());
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Đưa event vào queue export (no-op nếu tắt)
+pub fn publish(event: &SecurityEvent) {
+    if !CONFIG.read().enabled {
+        return;
+    }
+    let mut state = STATE.lock();
+    if state.queue.len() >= MAX_QUEUE {
+        state.queue.pop_front();
+        STATUS.lock().dropped += 1;
+    }
+    state.queue.push_back(event.clone());
+}
+
+/// Worker flush định kỳ (idempotent)
+pub fn start() {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(|| loop {
+        thread::sleep(TICK);
+        let config = CONFIG.read().clone();
+        if !config.enabled {
+            continue;
+        }
+        let now = Utc::now().timestamp();
+        if now - STATE.lock().last_flush_at < config.flush_interval_secs as i64 {
+            continue;
+        }
+        if let Err(e) = flush(&config) {
+            log::warn!("Elasticsearch export failed: {}", e);
+        }
+    });
+}
+
+/// Flush ngay (UI / test kết nối)
+pub fn flush_now() -> Result<(), String> {
+    let config = CONFIG.read().clone();
+    if !config.enabled {
+        return Err("Elasticsearch export is disabled".to_string());
+    }
+    flush(&config)
+}
+
+pub fn get_config() -> ElasticConfig {
+    CONFIG.read().clone()
+}
+
+pub fn set_config(config: ElasticConfig) -> Result<(), String> {
+    if MANAGED.load(Ordering::SeqCst) {
+        return Err("Elasticsearch settings are managed by your organization".to_string());
+    }
+    config.validate()?;
+    replace_config(config);
+    save_config();
+    Ok(())
+}
+
+/// Config local + override từ managed settings (chỉ kiểm tra, chưa áp dụng)
+pub fn managed_config(overrides: &serde_json::Value) -> Result<ElasticConfig, String> {
+    let config = managed_settings::merge_overrides(&load_config(), overrides)?;
+    config.validate()?;
+    Ok(config)
+}
+
+/// Áp config từ managed settings (không ghi ra disk). None = quay lại config local
+pub fn set_managed(config: Option<ElasticConfig>) {
+    match config {
+        Some(config) => {
+            MANAGED.store(true, Ordering::SeqCst);
+            replace_config(config);
+        }
+        None if MANAGED.swap(false, Ordering::SeqCst) => replace_config(load_config()),
+        None => {}
+    }
+}
+
+fn replace_config(config: ElasticConfig) {
+    let changed_target = {
+        let current = CONFIG.read();
+        current.url != config.url || current.index_prefix != config.index_prefix
+    };
+    if !config.enabled {
+        STATE.lock().queue.clear();
+    }
+    *CONFIG.write() = config;
+    CONFIG_GENERATION.fetch_add(1, Ordering::SeqCst);
+    if changed_target {
+        // Cluster / index khác → gửi lại incident đang mở
+        STATE.lock().shipped_incidents.clear();
+    }
+}
+
+pub fn get_status() -> ElasticStatus {
+    let mut status = STATUS.lock().clone();
+    status.enabled = CONFIG.read().enabled;
+    status.queued = STATE.lock().queue.len();
+    status
+}
+
+// ============================================================================
+// FLUSH
+// ============================================================================
+
+fn flush(config: &ElasticConfig) -> Result<(), String> {
+    let _guard = FLUSHING.lock();
+    STATE.lock().last_flush_at = Utc::now().timestamp();
+
+    let result = flush_inner(config);
+    let mut status = STATUS.lock();
+    status.last_flush = Some(Utc::now().timestamp());
+    status.last_error = result.as_ref().err().cloned();
+    result
+}
+
+fn flush_inner(config: &ElasticConfig) -> Result<(), String> {
+    let generation = CONFIG_GENERATION.load(Ordering::SeqCst);
+    if config.install_templates && STATE.lock().templates_generation != Some(generation) {
+        install_templates(config)?;
+        STATE.lock().templates_generation = Some(generation);
+        STATUS.lock().templates_installed = true;
+    }
+
+    // Events: lấy theo batch, lỗi transport → trả lại đầu queue
+    loop {
+        let batch: Vec<SecurityEvent> = {
+            let mut state = STATE.lock();
+            let n = state.queue.len().min(config.batch_size);
+            state.queue.drain(..n).collect()
+        };
+        if batch.is_empty() {
+            break;
+        }
+        let lines: Vec<(serde_json::Value, serde_json::Value)> = batch.iter()
+            .map(|e| event_action(config, e))
+            .collect();
+        match send_bulk(config, &lines) {
+            Ok(outcome) => {
+                let mut status = STATUS.lock();
+                status.events_sent += outcome.accepted as u64;
+                status.rejected += outcome.rejected.len() as u64;
+            }
+            Err(e) => {
+                let mut state = STATE.lock();
+                for event in batch.into_iter().rev() {
+                    state.queue.push_front(event);
+                }
+                state.queue.truncate(MAX_QUEUE);
+                return Err(e);
+            }
+        }
+    }
+
+    if config.ship_incidents {
+        ship_incidents(config)?;
+    }
+    Ok(())
+}
+
+fn ship_incidents(config: &ElasticConfig) -> Result<(), String> {
+    let changed: Vec<(String, u64, Incident)> = {
+        let state = STATE.lock();
+        incident::get_incidents()
+            .into_iter()
+            .filter_map(|inc| {
+                let id = inc.incident_id.to_string();
+                let fp = fingerprint(&inc);
+                (state.shipped_incidents.get(&id) != Some(&fp)).then_some((id, fp, inc))
+            })
+            .collect()
+    };
+
+    for chunk in changed.chunks(config.batch_size) {
+        let lines: Vec<(serde_json::Value, serde_json::Value)> = chunk.iter()
+            .map(|(_, _, inc)| incident_action(config, inc))
+            .collect();
+        let outcome = send_bulk(config, &lines)?;
+
+        let mut state = STATE.lock();
+        for (i, (id, fp, _)) in chunk.iter().enumerate() {
+            // Document bị từ chối vẫn đánh dấu: gửi lại y nguyên cũng bị từ chối
+            state.shipped_incidents.insert(id.clone(), *fp);
+            if !outcome.rejected.contains(&i) {
+                STATUS.lock().incidents_sent += 1;
+            }
+        }
+        STATUS.lock().rejected += outcome.rejected.len() as u64;
+    }
+
+    // Incident đã rời active list → bỏ fingerprint
+    let active: std::collections::HashSet<String> = incident::get_incidents()
+        .iter()
+        .map(|i| i.incident_id.to_string())
+        .collect();
+    STATE.lock().shipped_incidents.retain(|id, _| active.contains(id));
+    Ok(())
+}
+
+fn fingerprint(incident: &Incident) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(incident).unwrap_or_default().hash(&mut hasher);
+    hasher.finish()
+}
+
+// ============================================================================
+// DOCUMENTS
+// ============================================================================
+
+/// Index theo ngày (UTC): `<prefix>-<kind>-YYYY.MM.DD`
+pub fn daily_index(prefix: &str, kind: &str, at: &DateTime<Utc>) -> String {
+    format!("{}-{}-{}", prefix, kind, at.format("%Y.%m.%d"))
+}
+
+fn event_action(config: &ElasticConfig, event: &SecurityEvent) -> (serde_json::Value, serde_json::Value) {
+    let action = json!({ "create": {
+        "_index": daily_index(&config.index_prefix, "events", &event.timestamp),
+        "_id": event.id,
+    }});
+    (action, event_document(event))
+}
+
+/// Document ECS-style: field chung ở top-level, event gốc dưới `one_shield`
+pub fn event_document(event: &SecurityEvent) -> serde_json::Value {
+    json!({
+        "@timestamp": event.timestamp.to_rfc3339(),
+        "message": event.description,
+        "event": {
+            "id": event.id,
+            "kind": "event",
+            "action": event.event_type.as_str(),
+            "severity": exporter::siem_severity(event),
+        },
+        "host": { "name": exporter::local_hostname() },
+        "agent": { "type": "one-shield", "version": env!("CARGO_PKG_VERSION") },
+        "process": event.process.as_ref().map(|p| json!({
+            "name": p.name,
+            "pid": p.pid,
+            "executable": p.path,
+            "parent": { "pid": p.parent_pid },
+            "command_line": p.command_line,
+        })),
+        "one_shield": event,
+    })
+}
+
+fn incident_action(config: &ElasticConfig, incident: &Incident) -> (serde_json::Value, serde_json::Value) {
+    let action = json!({ "index": {
+        "_index": daily_index(&config.index_prefix, "incidents", &incident.started_at),
+        "_id": incident.incident_id.to_string(),
+    }});
+    let document = json!({
+        "@timestamp": incident.last_seen.to_rfc3339(),
+        "event": {
+            "id": incident.incident_id.to_string(),
+            "kind": "alert",
+            "start": incident.started_at.to_rfc3339(),
+            "end": incident.last_seen.to_rfc3339(),
+        },
+        "host": { "name": exporter::local_hostname() },
+        "agent": { "type": "one-shield", "version": env!("CARGO_PKG_VERSION") },
+        "threat": { "technique": { "id": incident.mitre_techniques } },
+        "one_shield": incident,
+    });
+    (action, document)
+}
+
+// ============================================================================
+// HTTP
+// ============================================================================
+
+struct BulkOutcome {
+    accepted: usize,
+    /// Vị trí item bị từ chối (trừ 409 của `create` = đã có)
+    rejected: Vec<usize>,
+}
+
+fn request(config: &ElasticConfig, method: &str, path: &str) -> ureq::Request {
+    let mut req = ureq::request(method, &format!("{}{}", config.base_url(), path))
+        .timeout(REQUEST_TIMEOUT);
+    if let Some(auth) = config.auth_header() {
+        req = req.set("Authorization", &auth);
+    }
+    req
+}
+
+fn http_error(e: ureq::Error) -> String {
+    match e {
+        ureq::Error::Status(code, response) => {
+            let body = response.into_string().unwrap_or_default();
+            format!("HTTP {}: {}", code, body.chars().take(300).collect::<String>())
+        }
+        e => e.to_string(),
+    }
+}
+
+fn send_bulk(config: &ElasticConfig, lines: &[(serde_json::Value, serde_json::Value)]) -> Result<BulkOutcome, String> {
+    let mut body = String::new();
+    for (action, document) in lines {
+        body.push_str(&action.to_string());
+        body.push('\n');
+        body.push_str(&document.to_string());
+        body.push('\n');
+    }
+
+    let text = request(config, "POST", "/_bulk")
+        .set("Content-Type", "application/x-ndjson")
+        .send_string(&body)
+        .map_err(http_error)?
+        .into_string()
+        .map_err(|e| e.to_string())?;
+    let response: serde_json::Value = serde_json::from_str(&text)
+        .map_err(|e| format!("Invalid _bulk response: {}", e))?;
+    Ok(parse_bulk_response(&response, lines.len()))
+}
+
+fn parse_bulk_response(response: &serde_json::Value, count: usize) -> BulkOutcome {
+    let mut rejected = Vec::new();
+    if response["errors"].as_bool().unwrap_or(false) {
+        for (i, item) in response["items"].as_array().into_iter().flatten().enumerate() {
+            let Some((op, result)) = item.as_object().and_then(|o| o.iter().next()) else { continue };
+            let status = result["status"].as_u64().unwrap_or(0);
+            let duplicate = op == "create" && status == 409;
+            if status >= 300 && !duplicate {
+                if rejected.len() < 3 {
+                    log::warn!("Elasticsearch rejected document: {}", result["error"]);
+                }
+                rejected.push(i);
+            }
+        }
+    }
+    BulkOutcome { accepted: count - rejected.len(), rejected }
+}
+
+fn install_templates(config: &ElasticConfig) -> Result<(), String> {
+    for (kind, mappings) in [("events", events_mappings()), ("incidents", incidents_mappings())] {
+        let name = format!("{}-{}", config.index_prefix, kind);
+        let body = index_template(config, kind, mappings);
+        request(config, "PUT", &format!("/_index_template/{}", name))
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string())
+            .map_err(|e| format!("Failed to install index template {}: {}", name, http_error(e)))?;
+    }
+    log::info!("Elasticsearch index templates installed ({})", config.index_prefix);
+    Ok(())
+}
+
+fn index_template(config: &ElasticConfig, kind: &str, mappings: serde_json::Value) -> serde_json::Value {
+    let mut settings = serde_json::Map::new();
+    if let Some(policy) = config.lifecycle_policy.as_deref().filter(|p| !p.is_empty()) {
+        let key = match config.flavor {
+            ClusterFlavor::Elasticsearch => "index.lifecycle.name",
+            ClusterFlavor::Opensearch => "plugins.index_state_management.policy_id",
+        };
+        settings.insert(key.to_string(), json!(policy));
+    }
+    json!({
+        "index_patterns": [format!("{}-{}-*", config.index_prefix, kind)],
+        "priority": 200,
+        "template": {
+            "settings": settings,
+            "mappings": mappings,
+        },
+        "_meta": { "managed_by": "one-shield" },
+    })
+}
+
+/// String → keyword (giữ cả field chưa khai báo của event / incident), text cho message
+fn dynamic_templates() -> serde_json::Value {
+    json!([{ "strings_as_keyword": {
+        "match_mapping_type": "string",
+        "mapping": { "type": "keyword", "ignore_above": 1024 },
+    }}])
+}
+
+fn events_mappings() -> serde_json::Value {
+    json!({
+        "dynamic_templates": dynamic_templates(),
+        "properties": {
+            "@timestamp": { "type": "date" },
+            "message": { "type": "text" },
+            "event": { "properties": {
+                "severity": { "type": "integer" },
+            }},
+            "process": { "properties": {
+                "pid": { "type": "long" },
+                "parent": { "properties": { "pid": { "type": "long" } } },
+                "command_line": { "type": "text" },
+            }},
+            "one_shield": { "properties": {
+                "timestamp": { "type": "date" },
+                "description": { "type": "text" },
+                "ai_context": { "properties": {
+                    "anomaly_score": { "type": "float" },
+                    "confidence": { "type": "float" },
+                    "baseline_deviation": { "type": "float" },
+                    "final_score": { "type": "float" },
+                }},
+                // Metadata khác nhau theo loại event → không index, tránh mapping conflict
+                "metadata": { "type": "object", "enabled": false },
+            }},
+        },
+    })
+}
+
+fn incidents_mappings() -> serde_json::Value {
+    json!({
+        "dynamic_templates": dynamic_templates(),
+        "properties": {
+            "@timestamp": { "type": "date" },
+            "event": { "properties": {
+                "start": { "type": "date" },
+                "end": { "type": "date" },
+            }},
+            "one_shield": { "properties": {
+                "started_at": { "type": "date" },
+                "last_seen": { "type": "date" },
+                // Chi tiết lồng nhau nhiều cấp: lưu trong _source, không index
+                "explanation": { "type": "object", "enabled": false },
+                "records": { "type": "object", "enabled": false },
+                "process_ancestry": { "type": "object", "enabled": false },
+                "execution_artifacts": { "type": "object", "enabled": false },
+                "script_excerpts": { "type": "object", "enabled": false },
+            }},
+        },
+    })
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn config_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(CONFIG_FILE)
+}
+
+fn load_config() -> ElasticConfig {
+    fs::read_to_string(config_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_config() {
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*CONFIG.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use super::super::event::EventType;
+
+    #[test]
+    fn test_bulk_documents() {
+        let config = ElasticConfig {
+            enabled: true,
+            url: "https://es.local:9200/".to_string(),
+            lifecycle_policy: Some("oneshield-30d".to_string()),
+            flavor: ClusterFlavor::Opensearch,
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.base_url(), "https://es.local:9200");
+        assert!(ElasticConfig { index_prefix: "OneShield".to_string(), ..config.clone() }.validate().is_err());
+
+        let at = Utc.with_ymd_and_hms(2026, 3, 7, 23, 59, 0).unwrap();
+        assert_eq!(daily_index("oneshield", "events", &at), "oneshield-events-2026.03.07");
+
+        let event = SecurityEvent::new(EventType::SystemStart, "Started");
+        let (action, doc) = event_action(&config, &event);
+        assert_eq!(action["create"]["_id"], event.id.as_str());
+        assert!(action["create"]["_index"].as_str().unwrap().starts_with("oneshield-events-"));
+        assert_eq!(doc["event"]["action"], "system_start");
+        assert_eq!(doc["one_shield"]["description"], "Started");
+
+        let template = index_template(&config, "events", events_mappings());
+        assert_eq!(template["index_patterns"][0], "oneshield-events-*");
+        assert_eq!(template["template"]["settings"]["plugins.index_state_management.policy_id"], "oneshield-30d");
+
+        let response = json!({
+            "errors": true,
+            "items": [
+                { "create": { "status": 201 } },
+                { "create": { "status": 409 } },
+                { "create": { "status": 400, "error": { "type": "mapper_parsing_exception" } } },
+            ],
+        });
+        let outcome = parse_bulk_response(&response, 3);
+        assert_eq!(outcome.accepted, 2);
+        assert_eq!(outcome.rejected, vec![2]);
+    }
+}
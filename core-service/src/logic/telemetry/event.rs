@@ -32,6 +32,8 @@ pub enum EventType {
     UserDenied,
     /// Action expired without user response
     ActionExpired,
+    /// Executed action was reverted (expiry or undo)
+    ActionReverted,
     /// User override - disagreed with AI
     UserOverride,
     /// Process was added to whitelist
@@ -46,6 +48,10 @@ pub enum EventType {
     ModelEvent,
     /// Baseline learned/updated
     BaselineEvent,
+    /// Analyst feedback on a behavioral rule match (true/false positive)
+    RuleFeedback,
+    /// Trusted code-signing publisher allowlist changed (local or cloud policy)
+    PublisherAllowlistChanged,
 }
 
 impl EventType {
@@ -58,6 +64,7 @@ impl EventType {
             EventType::UserApproved => "user_approved",
             EventType::UserDenied => "user_denied",
             EventType::ActionExpired => "action_expired",
+            EventType::ActionReverted => "action_reverted",
             EventType::UserOverride => "user_override",
             EventType::WhitelistAdded => "whitelist_added",
             EventType::WhitelistRemoved => "whitelist_removed",
@@ -65,6 +72,8 @@ impl EventType {
             EventType::SystemStop => "system_stop",
             EventType::ModelEvent => "model_event",
             EventType::BaselineEvent => "baseline_event",
+            EventType::RuleFeedback => "rule_feedback",
+            EventType::PublisherAllowlistChanged => "publisher_allowlist_changed",
         }
     }
 
@@ -72,10 +81,10 @@ impl EventType {
         match self {
             EventType::SystemStart | EventType::SystemStop => 0,
             EventType::ModelEvent | EventType::BaselineEvent => 1,
-            EventType::WhitelistAdded | EventType::WhitelistRemoved => 2,
+            EventType::WhitelistAdded | EventType::WhitelistRemoved | EventType::PublisherAllowlistChanged => 2,
             EventType::ThreatDetected | EventType::PolicyDecision => 3,
-            EventType::ActionCreated | EventType::ActionExpired => 4,
-            EventType::UserApproved | EventType::UserDenied => 5,
+            EventType::ActionCreated | EventType::ActionExpired | EventType::ActionReverted => 4,
+            EventType::UserApproved | EventType::UserDenied | EventType::RuleFeedback => 5,
             EventType::ActionExecuted | EventType::UserOverride => 6,
         }
     }
@@ -383,6 +392,56 @@ impl SecurityEvent {
         })
     }
 
+    /// Create action reverted event (`reason`: "expired", "undo", ...)
+    pub fn action_reverted(process: ProcessInfo, action: Option<ActionType>, reason: &str, success: bool) -> Self {
+        let mut event = Self::new(
+            EventType::ActionReverted,
+            &format!(
+                "Reverted {:?} for {} ({}, success: {})",
+                action, process.name, reason, success
+            ),
+        )
+        .with_process(process)
+        .with_metadata(serde_json::json!({
+            "reason": reason,
+            "success": success,
+        }));
+        if let Some(action) = action {
+            event = event.with_action(action);
+        }
+        event
+    }
+
+    /// Create rule feedback event (rule tuning)
+    pub fn rule_feedback(rule_id: &str, false_positive: bool, note: Option<&str>) -> Self {
+        Self::new(
+            EventType::RuleFeedback,
+            &format!(
+                "Rule {} marked as {}",
+                rule_id,
+                if false_positive { "false positive" } else { "true positive" }
+            ),
+        )
+        .with_metadata(serde_json::json!({
+            "rule_id": rule_id,
+            "false_positive": false_positive,
+            "note": note,
+        }))
+    }
+
+    /// Create publisher allowlist audit event (`change`: "added", "removed", "cloud_applied")
+    pub fn publisher_allowlist_changed(change: &str, source: &str, entries: serde_json::Value) -> Self {
+        Self::new(
+            EventType::PublisherAllowlistChanged,
+            &format!("Trusted publisher allowlist {} ({})", change, source),
+        )
+        .with_metadata(serde_json::json!({
+            "change": change,
+            "source": source,
+            "entries": entries,
+        }))
+    }
+
     /// Create system start event
     pub fn system_start(version: &str) -> Self {
         Self::new(
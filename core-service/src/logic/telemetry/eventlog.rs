@@ -0,0 +1,444 @@
+//! Windows Event Log - Ghi incident High/Critical + tamper vào Application log
+//!
+//! Mục đích: WEF/WEC subscription và SIEM agent (Splunk UF, Winlogbeat, NXLog...)
+//! đã thu Application log sẵn → không cần cấu hình thêm output nào.
+//!
+//! - Source "One-Shield" đăng ký dưới
+//!   `HKLM\SYSTEM\CurrentControlSet\Services\EventLog\Application` (cần quyền admin,
+//!   service chạy SYSTEM). Message file dùng `EventLogMessages.dll` của .NET (message
+//!   "%1" generic) → Event Viewer hiển thị nguyên text, không cần tự build .mc
+//! - Incident được poll từ incident manager: ghi lần đầu đạt High/Critical và ghi lại
+//!   khi escalate High → Critical
+//! - Tamper event (identity bị sửa / copy) ghi trực tiếp qua `write_tamper`
+//!
+//! | Event ID | Type    | Nội dung                         |
+//! |----------|---------|----------------------------------|
+//! | 1000     | Error   | Incident Critical                |
+//! | 1001     | Warning | Incident High                    |
+//! | 2000     | Error   | Tamper (identity / config agent) |
+//!
+//! Config local (`eventlog.json`), mặc định bật. Non-Windows: no-op.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::logic::incident::{self, Incident, Severity};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CONFIG_FILE: &str = "eventlog.json";
+pub const SOURCE_NAME: &str = "One-Shield";
+
+pub const EVENT_ID_INCIDENT_CRITICAL: u32 = 1000;
+pub const EVENT_ID_INCIDENT_HIGH: u32 = 1001;
+pub const EVENT_ID_TAMPER: u32 = 2000;
+
+/// Số explanation factor tối đa đưa vào message
+const MAX_FACTORS: usize = 5;
+
+// ============================================================================
+// CONFIG / STATUS
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventLogConfig {
+    pub enabled: bool,
+    /// Chỉ ghi incident Critical (High bỏ qua); tamper luôn ghi
+    pub critical_only: bool,
+}
+
+impl Default for EventLogConfig {
+    fn default() -> Self {
+        Self { enabled: true, critical_only: false }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EventLogStatus {
+    pub supported: bool,
+    pub enabled: bool,
+    /// Source đã có trong registry (đăng ký lúc init)
+    pub source_registered: bool,
+    pub incidents_written: u64,
+    pub tamper_written: u64,
+    pub failed: u64,
+    pub last_error: Option<String>,
+    pub last_written_at: Option<DateTime<Utc>>,
+}
+
+/// Mức severity đã ghi cho incident
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum WrittenLevel {
+    High,
+    Critical,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static CONFIG: Lazy<RwLock<EventLogConfig>> = Lazy::new(|| RwLock::new(load_config()));
+static STATUS: Lazy<Mutex<EventLogStatus>> = Lazy::new(|| Mutex::new(EventLogStatus::default()));
+/// incident_id → mức đã ghi (ghi lại khi escalate)
+static WRITTEN: Lazy<Mutex<HashMap<Uuid, WrittenLevel>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Đăng ký event source (gọi lúc startup)
+pub fn init() {
+    if !platform::SUPPORTED || !CONFIG.read().enabled {
+        return;
+    }
+    match platform::register_source() {
+        Ok(()) => {
+            STATUS.lock().source_registered = true;
+            log::info!("Event Log source '{}' registered", SOURCE_NAME);
+        }
+        Err(e) => {
+            log::warn!("Failed to register Event Log source: {}", e);
+            STATUS.lock().last_error = Some(e);
+        }
+    }
+}
+
+/// Ghi incident High/Critical chưa ghi (hoặc vừa escalate), trả về số event đã ghi
+pub fn sync_incidents() -> usize {
+    let config = CONFIG.read().clone();
+    if !platform::SUPPORTED || !config.enabled {
+        return 0;
+    }
+
+    let incidents = incident::get_incidents();
+    let mut written = WRITTEN.lock();
+    // Incident đã đóng / bị dọn khỏi manager → bỏ khỏi map
+    written.retain(|id, _| incidents.iter().any(|i| i.incident_id == *id));
+
+    let mut count = 0;
+    for inc in &incidents {
+        let Some(level) = level_of(&inc.severity) else { continue };
+        if config.critical_only && level != WrittenLevel::Critical {
+            continue;
+        }
+        if written.get(&inc.incident_id).map_or(false, |prev| *prev >= level) {
+            continue;
+        }
+
+        let (event_id, kind) = match level {
+            WrittenLevel::Critical => (EVENT_ID_INCIDENT_CRITICAL, platform::EntryKind::Error),
+            WrittenLevel::High => (EVENT_ID_INCIDENT_HIGH, platform::EntryKind::Warning),
+        };
+        if report(kind, event_id, &format_incident(inc)) {
+            written.insert(inc.incident_id, level);
+            STATUS.lock().incidents_written += 1;
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Ghi tamper event (identity / config agent bị sửa)
+pub fn write_tamper(tamper_type: &str, description: &str) {
+    if !platform::SUPPORTED || !CONFIG.read().enabled {
+        return;
+    }
+    if report(platform::EntryKind::Error, EVENT_ID_TAMPER, &format_tamper(tamper_type, description)) {
+        STATUS.lock().tamper_written += 1;
+    }
+}
+
+pub fn get_config() -> EventLogConfig {
+    CONFIG.read().clone()
+}
+
+pub fn set_config(config: EventLogConfig) -> Result<(), String> {
+    let enabling = config.enabled && !CONFIG.read().enabled;
+    *CONFIG.write() = config;
+    save_config();
+    if enabling && !STATUS.lock().source_registered {
+        init();
+    }
+    Ok(())
+}
+
+pub fn get_status() -> EventLogStatus {
+    let mut status = STATUS.lock().clone();
+    status.supported = platform::SUPPORTED;
+    status.enabled = CONFIG.read().enabled;
+    status
+}
+
+// ============================================================================
+// MESSAGE
+// ============================================================================
+
+fn level_of(severity: &Severity) -> Option<WrittenLevel> {
+    match severity {
+        Severity::Critical => Some(WrittenLevel::Critical),
+        Severity::High => Some(WrittenLevel::High),
+        Severity::Low | Severity::Medium => None,
+    }
+}
+
+fn format_incident(inc: &Incident) -> String {
+    let mut tags: Vec<&str> = Vec::new();
+    for tag in inc.records.iter().flat_map(|r| r.tags.iter()) {
+        if !tags.contains(&tag.as_str()) {
+            tags.push(tag);
+        }
+    }
+
+    let mut lines = vec![
+        format!("One-Shield incident ({:?})", inc.severity),
+        String::new(),
+        format!("Incident ID: {}", inc.incident_id),
+        format!("Severity: {:?}", inc.severity),
+        format!("Status: {:?}", inc.status),
+        format!("Started: {}", inc.started_at.to_rfc3339()),
+        format!("Last seen: {}", inc.last_seen.to_rfc3339()),
+        format!("Detections: {}", inc.records.len()),
+    ];
+    if !tags.is_empty() {
+        lines.push(format!("Tags: {}", tags.join(", ")));
+    }
+    if !inc.mitre_techniques.is_empty() {
+        lines.push(format!("MITRE ATT&CK: {}", inc.mitre_techniques.join(", ")));
+    }
+    if let Some(process) = inc.process_ancestry.first() {
+        lines.push(format!(
+            "Process: {} (PID {})",
+            process.exe_path.as_deref().unwrap_or(&process.image),
+            process.pid
+        ));
+    }
+    if let Some(explanation) = &inc.explanation {
+        let factors: Vec<String> = explanation.contributions.iter()
+            .take(MAX_FACTORS)
+            .map(|c| format!("{} ({:.2})", c.name, c.importance))
+            .collect();
+        if !factors.is_empty() {
+            lines.push(format!("Top factors: {}", factors.join(", ")));
+        }
+    }
+    lines.join("\r\n")
+}
+
+fn format_tamper(tamper_type: &str, description: &str) -> String {
+    [
+        "One-Shield agent tampering detected".to_string(),
+        String::new(),
+        format!("Type: {}", tamper_type),
+        format!("Description: {}", description),
+        "MITRE ATT&CK: T1562, T1562.001".to_string(),
+        "Action: agent self-healed, will re-register with cloud".to_string(),
+    ]
+    .join("\r\n")
+}
+
+fn report(kind: platform::EntryKind, event_id: u32, message: &str) -> bool {
+    match platform::report(kind, event_id, message) {
+        Ok(()) => {
+            STATUS.lock().last_written_at = Some(Utc::now());
+            true
+        }
+        Err(e) => {
+            log::warn!("Failed to write Event Log entry {}: {}", event_id, e);
+            let mut status = STATUS.lock();
+            status.failed += 1;
+            status.last_error = Some(e);
+            false
+        }
+    }
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn config_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(CONFIG_FILE)
+}
+
+fn load_config() -> EventLogConfig {
+    fs::read_to_string(config_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_config() {
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*CONFIG.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// PLATFORM
+// ============================================================================
+
+#[cfg(windows)]
+mod platform {
+    use windows::core::{w, HSTRING, PCWSTR};
+    use windows::Win32::Foundation::PSID;
+    use windows::Win32::System::EventLog::{
+        DeregisterEventSource, RegisterEventSourceW, ReportEventW,
+        EVENTLOG_ERROR_TYPE, EVENTLOG_WARNING_TYPE,
+    };
+    use windows::Win32::System::Registry::{
+        RegCloseKey, RegCreateKeyExW, RegSetValueExW, HKEY, HKEY_LOCAL_MACHINE,
+        KEY_SET_VALUE, REG_DWORD, REG_EXPAND_SZ, REG_OPTION_NON_VOLATILE,
+    };
+
+    pub const SUPPORTED: bool = true;
+
+    /// Message file generic của .NET ("%1") - có sẵn trên Windows 10+
+    const MESSAGE_FILE: &str = r"%SystemRoot%\Microsoft.NET\Framework64\v4.0.30319\EventLogMessages.dll";
+    /// EVENTLOG_ERROR_TYPE | EVENTLOG_WARNING_TYPE | EVENTLOG_INFORMATION_TYPE
+    const TYPES_SUPPORTED: u32 = 7;
+    /// ReportEvent giới hạn 31.839 ký tự mỗi string
+    const MAX_MESSAGE_CHARS: usize = 31_000;
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum EntryKind {
+        Error,
+        Warning,
+    }
+
+    pub fn register_source() -> Result<(), String> {
+        let subkey = HSTRING::from(format!(
+            r"SYSTEM\CurrentControlSet\Services\EventLog\Application\{}",
+            super::SOURCE_NAME
+        ));
+        let mut hkey = HKEY::default();
+        let status = unsafe {
+            RegCreateKeyExW(
+                HKEY_LOCAL_MACHINE,
+                &subkey,
+                0,
+                PCWSTR::null(),
+                REG_OPTION_NON_VOLATILE,
+                KEY_SET_VALUE,
+                None,
+                &mut hkey,
+                None,
+            )
+        };
+        if status.is_err() {
+            return Err(format!("RegCreateKeyExW failed: {:?}", status));
+        }
+
+        let message_file: Vec<u8> = MESSAGE_FILE.encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(|c| c.to_le_bytes())
+            .collect();
+        let types = TYPES_SUPPORTED.to_le_bytes();
+        let result = unsafe {
+            let a = RegSetValueExW(hkey, w!("EventMessageFile"), 0, REG_EXPAND_SZ, Some(&message_file));
+            let b = RegSetValueExW(hkey, w!("TypesSupported"), 0, REG_DWORD, Some(&types));
+            let _ = RegCloseKey(hkey);
+            (a, b)
+        };
+        if result.0.is_err() || result.1.is_err() {
+            return Err(format!("RegSetValueExW failed: {:?} / {:?}", result.0, result.1));
+        }
+        Ok(())
+    }
+
+    pub fn report(kind: EntryKind, event_id: u32, message: &str) -> Result<(), String> {
+        let truncated: String = message.chars().take(MAX_MESSAGE_CHARS).collect();
+        let text = HSTRING::from(truncated);
+        let strings = [PCWSTR(text.as_ptr())];
+        let event_type = match kind {
+            EntryKind::Error => EVENTLOG_ERROR_TYPE,
+            EntryKind::Warning => EVENTLOG_WARNING_TYPE,
+        };
+
+        unsafe {
+            let handle = RegisterEventSourceW(PCWSTR::null(), w!("One-Shield"))
+                .map_err(|e| format!("RegisterEventSourceW failed: {}", e))?;
+            let result = ReportEventW(
+                handle,
+                event_type,
+                0,
+                event_id,
+                PSID::default(),
+                1,
+                0,
+                Some(strings.as_ptr()),
+                None,
+            );
+            let _ = DeregisterEventSource(handle);
+            result.map_err(|e| format!("ReportEventW failed: {}", e))
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    pub const SUPPORTED: bool = false;
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum EntryKind {
+        Error,
+        Warning,
+    }
+
+    pub fn register_source() -> Result<(), String> {
+        Err("Windows Event Log is only available on Windows".to_string())
+    }
+
+    pub fn report(_kind: EntryKind, _event_id: u32, _message: &str) -> Result<(), String> {
+        Err("Windows Event Log is only available on Windows".to_string())
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::incident::DatasetRecordSummary;
+    use crate::logic::threat::ThreatClass;
+
+    #[test]
+    fn test_incident_message() {
+        let summary = DatasetRecordSummary {
+            ts: Utc::now(),
+            score: 1.0,
+            confidence: 1.0,
+            threat: ThreatClass::Malicious,
+            tags: vec!["BEACONING".to_string(), "geo:RU".to_string()],
+        };
+        let mut inc = Incident::new(summary, None);
+        inc.escalate(Severity::Critical);
+        inc.add_techniques(&["T1071.001"]);
+
+        assert_eq!(level_of(&inc.severity), Some(WrittenLevel::Critical));
+        assert_eq!(level_of(&Severity::Medium), None);
+        assert!(WrittenLevel::Critical > WrittenLevel::High);
+
+        let message = format_incident(&inc);
+        assert!(message.contains(&inc.incident_id.to_string()));
+        assert!(message.contains("Tags: BEACONING, geo:RU"));
+        assert!(message.contains("MITRE ATT&CK: T1071.001"));
+    }
+}
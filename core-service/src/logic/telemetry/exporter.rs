@@ -1,15 +1,44 @@
 //! Security Event Exporter
 //!
-//! Future: Export events to external systems (SIEM, analytics, cloud).
-//! For now, provides export utilities for local analysis.
+//! Export utilities for local analysis + SIEM line formats (CEF for ArcSight,
+//! LEEF for QRadar), chọn theo từng output sink (`EventFormat`).
+//!
+//! ## SecurityEvent → CEF / LEEF field mapping
+//!
+//! | SecurityEvent                 | CEF (header / extension)          | LEEF 1.0                |
+//! |-------------------------------|-----------------------------------|-------------------------|
+//! | `event_type`                  | Signature ID, `cat`               | EventID, `cat`          |
+//! | `description`                 | Name, `msg`                       | `msg`                   |
+//! | `severity` / `event_type`     | Severity (0-10)                   | `sev` (1-10)            |
+//! | `timestamp`                   | `rt` (epoch ms)                   | `devTime` + `devTimeFormat` |
+//! | `id`                          | `externalId`                      | `eventId`               |
+//! | `session_id`                  | `cs1` (`cs1Label=sessionId`)      | `sessionId`             |
+//! | `process.name`                | `dproc`                           | `resource`              |
+//! | `process.pid`                 | `dpid`                            | `pid`                   |
+//! | `process.path`                | `filePath`                        | `filePath`              |
+//! | `process.parent_pid`          | `cn1` (`cn1Label=parentPid`)      | `parentPid`             |
+//! | `threat_class`                | `cs2` (`cs2Label=threatClass`)    | `threatClass`           |
+//! | `decision`                    | `cs3` (`cs3Label=decision`)       | `decision`              |
+//! | `action`                      | `act`                             | `action`                |
+//! | `ai_context.anomaly_score`    | `cfp1` (`cfp1Label=anomalyScore`) | `anomalyScore`          |
+//! | `ai_context.confidence`       | `cfp2` (`cfp2Label=confidence`)   | `confidence`            |
+//! | `ai_context.tags`             | `cs4` (`cs4Label=tags`, `,`)      | `tags`                  |
+//! | local hostname                | `dvchost`                         | `identHostName`         |
+//!
+//! Field không có giá trị thì bỏ qua. Severity: Low 3, Medium 5, High 8, Critical 10;
+//! event không có severity → theo loại event (threat / override 5, action 3, system 1).
 
 use std::path::PathBuf;
 use std::io::Write;
-use chrono::{DateTime, Utc};
-use serde::Serialize;
+use chrono::{DateTime, SecondsFormat, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::policy::Severity;
 
 use super::event::{SecurityEvent, EventType};
 use super::recorder;
+use super::scrub::{self, ScrubTarget};
 
 // ============================================================================
 // EXPORT FORMATS
@@ -24,19 +53,47 @@ pub enum ExportFormat {
     Csv,
     /// Compact JSON array
     JsonArray,
+    /// CEF line per event (ArcSight)
+    Cef,
+    /// LEEF line per event (QRadar)
+    Leef,
+}
+
+/// Định dạng 1 event của output sink (syslog, ...)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventFormat {
+    #[default]
+    Json,
+    Cef,
+    Leef,
+}
+
+impl EventFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "json" => Ok(EventFormat::Json),
+            "cef" => Ok(EventFormat::Cef),
+            "leef" => Ok(EventFormat::Leef),
+            other => Err(format!("Unknown event format '{}' (expected json, cef or leef)", other)),
+        }
+    }
 }
 
 // ============================================================================
 // EXPORT FUNCTIONS
 // ============================================================================
 
-/// Export events from log file to different format
+/// Export events from log file to different format (ẩn danh PII nếu bật `scrub.file_exports`)
 pub fn export_file(
     source: &PathBuf,
     destination: &PathBuf,
     format: ExportFormat,
 ) -> std::io::Result<usize> {
-    let events = recorder::read_events(source)?;
+    let mut events = recorder::read_events(source)?;
+    if scrub::enabled(ScrubTarget::FileExport) {
+        events = events.iter().map(scrub::scrub_event).collect();
+    }
     export_events(&events, destination, format)
 }
 
@@ -62,6 +119,12 @@ pub fn export_events(
         ExportFormat::Csv => {
             export_csv(&mut file, events)?;
         }
+        ExportFormat::Cef | ExportFormat::Leef => {
+            let line_format = if matches!(format, ExportFormat::Cef) { EventFormat::Cef } else { EventFormat::Leef };
+            for event in events {
+                writeln!(file, "{}", format_event(event, line_format))?;
+            }
+        }
     }
 
     Ok(count)
@@ -111,6 +174,193 @@ fn export_csv(file: &mut std::fs::File, events: &[SecurityEvent]) -> std::io::Re
     Ok(())
 }
 
+// ============================================================================
+// SIEM FORMATS (CEF / LEEF)
+// ============================================================================
+
+const VENDOR: &str = "One-Shield";
+const PRODUCT: &str = "One-Shield Agent";
+
+/// 1 event → 1 dòng theo format của sink
+pub fn format_event(event: &SecurityEvent, format: EventFormat) -> String {
+    match format {
+        EventFormat::Json => event.to_jsonl(),
+        EventFormat::Cef => format_cef(event),
+        EventFormat::Leef => format_leef(event),
+    }
+}
+
+/// Severity 0-10 dùng chung cho CEF / LEEF
+pub fn siem_severity(event: &SecurityEvent) -> u8 {
+    if let Some(severity) = &event.severity {
+        return match severity {
+            Severity::Low => 3,
+            Severity::Medium => 5,
+            Severity::High => 8,
+            Severity::Critical => 10,
+        };
+    }
+    match event.event_type {
+        EventType::ThreatDetected | EventType::UserOverride => 5,
+        EventType::SystemStart
+        | EventType::SystemStop
+        | EventType::ModelEvent
+        | EventType::BaselineEvent => 1,
+        _ => 3,
+    }
+}
+
+/// `CEF:0|Vendor|Product|Version|SignatureID|Name|Severity|Extension`
+pub fn format_cef(event: &SecurityEvent) -> String {
+    let mut ext: Vec<(&str, String)> = vec![
+        ("rt", event.timestamp.timestamp_millis().to_string()),
+        ("externalId", event.id.clone()),
+        ("cat", event.event_type.as_str().to_string()),
+        ("dvchost", local_hostname()),
+        ("cs1Label", "sessionId".to_string()),
+        ("cs1", event.session_id.clone()),
+    ];
+    if let Some(process) = &event.process {
+        ext.push(("dproc", process.name.clone()));
+        if let Some(pid) = process.pid {
+            ext.push(("dpid", pid.to_string()));
+        }
+        if let Some(path) = &process.path {
+            ext.push(("filePath", path.clone()));
+        }
+        if let Some(ppid) = process.parent_pid {
+            ext.push(("cn1Label", "parentPid".to_string()));
+            ext.push(("cn1", ppid.to_string()));
+        }
+    }
+    if let Some(threat) = &event.threat_class {
+        ext.push(("cs2Label", "threatClass".to_string()));
+        ext.push(("cs2", format!("{:?}", threat)));
+    }
+    if let Some(decision) = &event.decision {
+        ext.push(("cs3Label", "decision".to_string()));
+        ext.push(("cs3", format!("{:?}", decision)));
+    }
+    if let Some(action) = &event.action {
+        ext.push(("act", format!("{:?}", action)));
+    }
+    if let Some(ai) = &event.ai_context {
+        ext.push(("cfp1Label", "anomalyScore".to_string()));
+        ext.push(("cfp1", format!("{:.4}", ai.anomaly_score)));
+        ext.push(("cfp2Label", "confidence".to_string()));
+        ext.push(("cfp2", format!("{:.4}", ai.confidence)));
+        if !ai.tags.is_empty() {
+            ext.push(("cs4Label", "tags".to_string()));
+            ext.push(("cs4", ai.tags.join(",")));
+        }
+    }
+    ext.push(("msg", event.description.clone()));
+
+    let extension: Vec<String> = ext.iter()
+        .map(|(key, value)| format!("{}={}", key, cef_escape_value(value)))
+        .collect();
+    format!(
+        "CEF:0|{}|{}|{}|{}|{}|{}|{}",
+        cef_escape_header(VENDOR),
+        cef_escape_header(PRODUCT),
+        cef_escape_header(env!("CARGO_PKG_VERSION")),
+        cef_escape_header(event.event_type.as_str()),
+        cef_escape_header(&event.description),
+        siem_severity(event),
+        extension.join(" ")
+    )
+}
+
+/// `LEEF:1.0|Vendor|Product|Version|EventID|` + attribute phân tách bằng tab
+pub fn format_leef(event: &SecurityEvent) -> String {
+    let mut attrs: Vec<(&str, String)> = vec![
+        ("cat", event.event_type.as_str().to_string()),
+        ("sev", siem_severity(event).max(1).to_string()),
+        ("devTime", event.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)),
+        ("devTimeFormat", "yyyy-MM-dd'T'HH:mm:ss.SSSX".to_string()),
+        ("eventId", event.id.clone()),
+        ("sessionId", event.session_id.clone()),
+        ("identHostName", local_hostname()),
+    ];
+    if let Some(process) = &event.process {
+        attrs.push(("resource", process.name.clone()));
+        if let Some(pid) = process.pid {
+            attrs.push(("pid", pid.to_string()));
+        }
+        if let Some(path) = &process.path {
+            attrs.push(("filePath", path.clone()));
+        }
+        if let Some(ppid) = process.parent_pid {
+            attrs.push(("parentPid", ppid.to_string()));
+        }
+    }
+    if let Some(threat) = &event.threat_class {
+        attrs.push(("threatClass", format!("{:?}", threat)));
+    }
+    if let Some(decision) = &event.decision {
+        attrs.push(("decision", format!("{:?}", decision)));
+    }
+    if let Some(action) = &event.action {
+        attrs.push(("action", format!("{:?}", action)));
+    }
+    if let Some(ai) = &event.ai_context {
+        attrs.push(("anomalyScore", format!("{:.4}", ai.anomaly_score)));
+        attrs.push(("confidence", format!("{:.4}", ai.confidence)));
+        if !ai.tags.is_empty() {
+            attrs.push(("tags", ai.tags.join(",")));
+        }
+    }
+    attrs.push(("msg", event.description.clone()));
+
+    let body: Vec<String> = attrs.iter()
+        .map(|(key, value)| format!("{}={}", key, leef_escape_value(value)))
+        .collect();
+    format!(
+        "LEEF:1.0|{}|{}|{}|{}|{}",
+        leef_escape_header(VENDOR),
+        leef_escape_header(PRODUCT),
+        leef_escape_header(env!("CARGO_PKG_VERSION")),
+        leef_escape_header(event.event_type.as_str()),
+        body.join("\t")
+    )
+}
+
+/// CEF header: escape `\` và `|`, không xuống dòng
+fn cef_escape_header(value: &str) -> String {
+    value.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\r', '\n'], " ")
+}
+
+/// CEF extension: escape `\` và `=`, xuống dòng → `\n`
+fn cef_escape_value(value: &str) -> String {
+    value.replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// LEEF header: escape `|`
+fn leef_escape_header(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// LEEF attribute: tab là delimiter, không được xuất hiện trong value
+fn leef_escape_value(value: &str) -> String {
+    value.replace(['\t', '\r', '\n'], " ")
+}
+
+/// Hostname máy (cache)
+pub fn local_hostname() -> String {
+    static HOSTNAME: Lazy<String> = Lazy::new(|| {
+        hostname::get()
+            .map(|h| h.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+    HOSTNAME.clone()
+}
+
 // ============================================================================
 // TRAINING DATA EXPORT
 // ============================================================================
@@ -161,6 +411,13 @@ pub fn export_training_data(
         }
     }
 
+    if scrub::enabled(ScrubTarget::FileExport) {
+        for record in &mut training_records {
+            record.process_name = scrub::scrub_text(&record.process_name);
+            record.tags = record.tags.iter().map(|t| scrub::scrub_text(t)).collect();
+        }
+    }
+
     // Export as JSONL
     let mut file = std::fs::File::create(destination)?;
     for record in &training_records {
@@ -312,6 +569,48 @@ mod tests {
         assert!(lines[0].starts_with("id,timestamp"));
     }
 
+    #[test]
+    fn test_cef_format() {
+        let mut event = SecurityEvent::policy_decision(
+            ProcessInfo::new(42, "evil|x.exe").with_path("C:\\Temp\\a=b.exe"),
+            ThreatClass::Malicious,
+            crate::logic::policy::Decision::AutoBlock,
+            Severity::High,
+        );
+        event.description = "Blocked evil|x.exe\nsecond line".to_string();
+
+        let line = format_cef(&event);
+        assert!(!line.contains('\n'));
+        let header: Vec<&str> = line.splitn(8, |c| c == '|').collect();
+        assert_eq!(header[0], "CEF:0");
+        assert_eq!(header[1], "One-Shield");
+        assert_eq!(header[4], "policy_decision");
+        assert!(line.contains("|Blocked evil\\|x.exe second line|8|"));
+        assert!(line.contains("filePath=C:\\\\Temp\\\\a\\=b.exe"));
+        assert!(line.contains("dpid=42"));
+        assert!(line.contains("cs2Label=threatClass cs2=Malicious"));
+        assert!(line.contains("msg=Blocked evil|x.exe\\nsecond line"));
+    }
+
+    #[test]
+    fn test_leef_format() {
+        let mut event = create_test_events().remove(0);
+        event.description = "tab\there".to_string();
+
+        let line = format_leef(&event);
+        assert!(line.starts_with("LEEF:1.0|One-Shield|One-Shield Agent|"));
+        let (_, body) = line.split_at(line.find("|threat_detected|").unwrap() + "|threat_detected|".len());
+        let attrs: Vec<&str> = body.split('\t').collect();
+        assert!(attrs.contains(&"cat=threat_detected"));
+        assert!(attrs.contains(&"sev=5"));
+        assert!(attrs.contains(&"pid=123"));
+        assert!(attrs.contains(&"anomalyScore=0.7500"));
+        assert!(attrs.contains(&"msg=tab here"));
+
+        assert_eq!(EventFormat::parse("LEEF"), Ok(EventFormat::Leef));
+        assert!(EventFormat::parse("xml").is_err());
+    }
+
     #[test]
     fn test_export_json_array() {
         let temp_dir = TempDir::new().unwrap();
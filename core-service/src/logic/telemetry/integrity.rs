@@ -0,0 +1,530 @@
+//! Log Integrity - Hash chain + signed checkpoint cho security logs
+//!
+//! Log của EDR phải phát hiện được việc bị sửa / xóa dòng:
+//! - Mỗi record được nối thêm `"chain":{"seq","prev","hash"}` vào cuối JSON,
+//!   `hash = SHA256("<seq>|<prev>|" + event JSON gốc)`. Chain nối tiếp qua rotate và
+//!   restart (record đầu file mới trỏ về hash cuối của file trước)
+//! - Checkpoint (mỗi `CHECKPOINT_EVERY` record / `CHECKPOINT_INTERVAL_SECS`, lúc rotate
+//!   và shutdown): dòng `{"checkpoint":{...}}` ký HMAC-SHA256 bằng key riêng của máy
+//!   (`log_chain.key`, bọc DPAPI) → sửa một dòng rồi tính lại toàn bộ hash phía sau
+//!   vẫn lộ ở checkpoint kế tiếp nếu không có key
+//! - Key bị xóa / thay trong khi log đã có chain → tamper event (checkpoint cũ không
+//!   còn kiểm chứng được, không được lặng lẽ tạo key mới)
+//! - `verify_log_integrity`: kiểm tra từng file (hash, prev, checkpoint) + liên kết giữa
+//!   các file + đuôi file đang ghi so với head trong memory
+//!
+//! File đầu tiên còn lại sau retention có `prev` không kiểm chứng được (file trước đã
+//! bị xóa hợp lệ) → không tính là lỗi. Record không có chain (log cũ) chỉ được đếm.
+
+use std::fs;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use once_cell::sync::Lazy;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const KEY_FILE: &str = "log_chain.key";
+/// `prev` của record đầu tiên trong chain
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+/// Checkpoint sau mỗi N record
+const CHECKPOINT_EVERY: u64 = 1000;
+/// ... hoặc sau N giây kể từ checkpoint trước (nếu có record mới)
+const CHECKPOINT_INTERVAL_SECS: i64 = 300;
+/// Marker chain ở cuối dòng record
+const CHAIN_MARKER: &str = ",\"chain\":";
+/// Issue tối đa trả về (log bị phá nặng → không trả về hàng nghìn dòng)
+const MAX_ISSUES: usize = 200;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainLink {
+    pub seq: u64,
+    pub prev: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub seq: u64,
+    /// Hash của record `seq`
+    pub head: String,
+    pub timestamp: DateTime<Utc>,
+    /// HMAC-SHA256("<seq>|<head>|<timestamp>") hex
+    pub signature: String,
+}
+
+/// Trạng thái key checkpoint lúc load
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyStatus {
+    Loaded,
+    /// Chưa có file key (lần chạy đầu, hoặc key đã bị xóa)
+    Created,
+    /// File key hỏng / không giải mã được → đã thay bằng key mới
+    Replaced,
+    /// Không đọc được file key → key tạm trong memory, không ghi đè file
+    Unreadable,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckpointLine {
+    checkpoint: Checkpoint,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// Nội dung record không khớp hash (dòng bị sửa)
+    HashMismatch,
+    /// `prev` / `seq` không nối với record trước (dòng bị xóa / chèn / đảo)
+    ChainBreak,
+    /// Chữ ký checkpoint sai
+    BadCheckpointSignature,
+    /// Checkpoint không khớp head của chain tại vị trí đó
+    CheckpointMismatch,
+    /// Record đầu file không nối với file trước (file / đuôi file bị xóa)
+    FileGap,
+    /// File đang ghi bị cắt đuôi so với head trong memory
+    TailTruncated,
+    /// Dòng không parse được
+    Malformed,
+    /// Record không có chain xen giữa record có chain
+    Unchained,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityIssue {
+    pub file: String,
+    /// Số dòng (1-based)
+    pub line: usize,
+    pub kind: IssueKind,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IntegrityReport {
+    pub valid: bool,
+    pub files_checked: usize,
+    pub records_checked: u64,
+    pub checkpoints_verified: u64,
+    /// Record ghi trước khi có hash chain
+    pub unchained_records: u64,
+    pub issues: Vec<IntegrityIssue>,
+    pub truncated_issues: bool,
+    pub checked_at: Option<DateTime<Utc>>,
+}
+
+// ============================================================================
+// CHAIN (writer side, thuộc Recorder)
+// ============================================================================
+
+/// Trạng thái chain của recorder
+pub struct Chain {
+    key: Vec<u8>,
+    seq: u64,
+    head: String,
+    since_checkpoint: u64,
+    last_checkpoint_at: DateTime<Utc>,
+}
+
+impl Chain {
+    pub fn new(key: Vec<u8>) -> Self {
+        Self {
+            key,
+            seq: 0,
+            head: GENESIS_HASH.to_string(),
+            since_checkpoint: 0,
+            last_checkpoint_at: Utc::now(),
+        }
+    }
+
+    /// Tiếp tục chain từ record cuối cùng trong thư mục log (restart)
+    pub fn resume(key: Vec<u8>, dir: &Path) -> Self {
+        let mut chain = Self::new(key);
+        let files = super::recorder::list_log_files(&dir.to_path_buf()).unwrap_or_default();
+        for file in files.iter().rev() {
+            if let Some(link) = last_link(file) {
+                chain.seq = link.seq;
+                chain.head = link.hash;
+                break;
+            }
+        }
+        chain
+    }
+
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    pub fn head(&self) -> &str {
+        &self.head
+    }
+
+    /// Nối chain vào event JSON → dòng ghi xuống file
+    pub fn link(&mut self, event_json: &str) -> String {
+        let seq = self.seq + 1;
+        let hash = record_hash(seq, &self.head, event_json);
+        let link = ChainLink { seq, prev: std::mem::replace(&mut self.head, hash.clone()), hash };
+        self.seq = seq;
+        self.since_checkpoint += 1;
+        append_chain(event_json, &link)
+    }
+
+    pub fn checkpoint_due(&self) -> bool {
+        self.since_checkpoint >= CHECKPOINT_EVERY
+            || (self.since_checkpoint > 0
+                && (Utc::now() - self.last_checkpoint_at).num_seconds() >= CHECKPOINT_INTERVAL_SECS)
+    }
+
+    /// Dòng checkpoint cho head hiện tại (None nếu chưa có record nào từ checkpoint trước)
+    pub fn checkpoint(&mut self) -> Option<String> {
+        if self.since_checkpoint == 0 {
+            return None;
+        }
+        let timestamp = Utc::now();
+        let checkpoint = Checkpoint {
+            seq: self.seq,
+            head: self.head.clone(),
+            timestamp,
+            signature: sign(&self.key, self.seq, &self.head, &timestamp),
+        };
+        self.since_checkpoint = 0;
+        self.last_checkpoint_at = timestamp;
+        Some(serde_json::json!({ "checkpoint": checkpoint }).to_string())
+    }
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+static KEY: Lazy<(Vec<u8>, KeyStatus)> = Lazy::new(load_or_create_key);
+
+/// Key HMAC cho checkpoint (tạo ngẫu nhiên lần đầu, lưu local)
+pub fn signing_key() -> Vec<u8> {
+    KEY.0.clone()
+}
+
+/// Gọi lúc recorder start với seq cuối của chain trong log: key bị mất / bị thay
+/// khi log đã có chain → tamper event
+pub fn check_signing_key(chained_seq: u64) {
+    let status = KEY.1;
+    if key_tampered(status, chained_seq) {
+        report_key_tamper(status, chained_seq);
+    }
+}
+
+/// Kiểm tra toàn bộ thư mục log của recorder
+pub fn verify_log_integrity() -> Result<IntegrityReport, String> {
+    let dir = super::recorder::log_dir().ok_or("Telemetry recorder not initialized")?;
+    let live = super::recorder::chain_head();
+    Ok(verify_dir(&dir, &signing_key(), live.as_ref().map(|(f, s, h)| (f.as_path(), *s, h.as_str()))))
+}
+
+// ============================================================================
+// VERIFY
+// ============================================================================
+
+/// `live` = (file đang ghi, seq, head) của recorder
+pub fn verify_dir(dir: &Path, key: &[u8], live: Option<(&Path, u64, &str)>) -> IntegrityReport {
+    let mut report = IntegrityReport { checked_at: Some(Utc::now()), ..Default::default() };
+    let files = super::recorder::list_log_files(&dir.to_path_buf()).unwrap_or_default();
+
+    // (seq, hash) của record cuối file trước
+    let mut previous: Option<(u64, String)> = None;
+    for file in &files {
+        report.files_checked += 1;
+        let name = file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let reader = match super::recorder::open_log_reader(file) {
+            Ok(reader) => reader,
+            Err(e) => {
+                push_issue(&mut report, &name, 0, IssueKind::Malformed, format!("cannot open: {}", e));
+                continue;
+            }
+        };
+
+        let mut last: Option<(u64, String)> = None;
+        for (index, line) in reader.lines().enumerate() {
+            let line_no = index + 1;
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    push_issue(&mut report, &name, line_no, IssueKind::Malformed, e.to_string());
+                    break;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if line.starts_with("{\"checkpoint\":") {
+                let Ok(CheckpointLine { checkpoint }) = serde_json::from_str::<CheckpointLine>(&line) else {
+                    push_issue(&mut report, &name, line_no, IssueKind::Malformed, "unreadable checkpoint".to_string());
+                    continue;
+                };
+                if !verify_signature(key, &checkpoint) {
+                    push_issue(&mut report, &name, line_no, IssueKind::BadCheckpointSignature,
+                        format!("checkpoint seq {} signature invalid", checkpoint.seq));
+                } else if last.as_ref() != Some(&(checkpoint.seq, checkpoint.head.clone())) {
+                    push_issue(&mut report, &name, line_no, IssueKind::CheckpointMismatch,
+                        format!("checkpoint seq {} does not match chain head", checkpoint.seq));
+                } else {
+                    report.checkpoints_verified += 1;
+                }
+                continue;
+            }
+
+            let Some((event_json, link)) = split_chain(&line) else {
+                if last.is_some() {
+                    push_issue(&mut report, &name, line_no, IssueKind::Unchained,
+                        "record without hash chain after chained records".to_string());
+                } else if serde_json::from_str::<serde_json::Value>(&line).is_ok() {
+                    report.unchained_records += 1;
+                } else {
+                    push_issue(&mut report, &name, line_no, IssueKind::Malformed, "invalid JSON".to_string());
+                }
+                continue;
+            };
+            report.records_checked += 1;
+
+            if record_hash(link.seq, &link.prev, &event_json) != link.hash {
+                push_issue(&mut report, &name, line_no, IssueKind::HashMismatch,
+                    format!("record seq {} content modified", link.seq));
+            }
+            match (&last, &previous) {
+                (Some((seq, hash)), _) => {
+                    if link.seq != seq + 1 || link.prev != *hash {
+                        push_issue(&mut report, &name, line_no, IssueKind::ChainBreak,
+                            format!("seq {} does not follow seq {} (records removed or reordered)", link.seq, seq));
+                    }
+                }
+                // Record đầu file: nối với file trước (nếu còn)
+                (None, Some((seq, hash))) => {
+                    if link.seq != seq + 1 || link.prev != *hash {
+                        push_issue(&mut report, &name, line_no, IssueKind::FileGap,
+                            format!("first record seq {} does not follow previous file (seq {})", link.seq, seq));
+                    }
+                }
+                (None, None) => {}
+            }
+            last = Some((link.seq, link.hash));
+        }
+
+        if let Some((live_file, seq, head)) = live {
+            if live_file == file.as_path() && last.as_ref() != Some(&(seq, head.to_string())) {
+                push_issue(&mut report, &name, 0, IssueKind::TailTruncated,
+                    format!("file does not end at in-memory head seq {}", seq));
+            }
+        }
+        if last.is_some() {
+            previous = last;
+        }
+    }
+
+    report.valid = report.issues.is_empty();
+    report
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+fn record_hash(seq: u64, prev: &str, event_json: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}|{}|", seq, prev).as_bytes());
+    hasher.update(event_json.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// `{...}` + link → `{...,"chain":{...}}`
+fn append_chain(event_json: &str, link: &ChainLink) -> String {
+    let body = event_json.strip_suffix('}').unwrap_or(event_json);
+    let link = serde_json::to_string(link).unwrap_or_default();
+    format!("{}{}{}}}", body, CHAIN_MARKER, link)
+}
+
+/// Tách dòng record → (event JSON gốc, link). Chain luôn là field cuối
+fn split_chain(line: &str) -> Option<(String, ChainLink)> {
+    let index = line.rfind(CHAIN_MARKER)?;
+    let link_json = line[index + CHAIN_MARKER.len()..].strip_suffix('}')?;
+    let link = serde_json::from_str::<ChainLink>(link_json).ok()?;
+    Some((format!("{}}}", &line[..index]), link))
+}
+
+fn last_link(file: &Path) -> Option<ChainLink> {
+    let reader = super::recorder::open_log_reader(file).ok()?;
+    reader.lines()
+        .map_while(Result::ok)
+        .filter_map(|line| split_chain(&line).map(|(_, link)| link))
+        .last()
+}
+
+fn sign(key: &[u8], seq: u64, head: &str, timestamp: &DateTime<Utc>) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take key of any size");
+    mac.update(format!("{}|{}|{}", seq, head, timestamp.to_rfc3339()).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn verify_signature(key: &[u8], checkpoint: &Checkpoint) -> bool {
+    let Ok(signature) = hex::decode(&checkpoint.signature) else { return false };
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take key of any size");
+    mac.update(format!("{}|{}|{}", checkpoint.seq, checkpoint.head, checkpoint.timestamp.to_rfc3339()).as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+fn push_issue(report: &mut IntegrityReport, file: &str, line: usize, kind: IssueKind, detail: String) {
+    if report.issues.len() >= MAX_ISSUES {
+        report.truncated_issues = true;
+        return;
+    }
+    report.issues.push(IntegrityIssue { file: file.to_string(), line, kind, detail });
+}
+
+fn load_or_create_key() -> (Vec<u8>, KeyStatus) {
+    use crate::logic::identity::dpapi;
+
+    let path = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(KEY_FILE);
+
+    let status = match fs::read_to_string(&path) {
+        Ok(content) => {
+            let stored = hex::decode(content.trim()).unwrap_or_default();
+            match dpapi::unprotect(&stored) {
+                Ok(key) if key.len() == 32 => return (key, KeyStatus::Loaded),
+                // Key cũ lưu plaintext → bọc DPAPI
+                Err(_) if stored.len() == 32 => {
+                    persist_key(&path, &stored);
+                    return (stored, KeyStatus::Loaded);
+                }
+                _ => KeyStatus::Replaced,
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => KeyStatus::Created,
+        Err(e) => {
+            log::error!("Cannot read log chain key: {}", e);
+            KeyStatus::Unreadable
+        }
+    };
+
+    let mut key = vec![0u8; 32];
+    rand::thread_rng().fill_bytes(&mut key);
+    if status != KeyStatus::Unreadable {
+        persist_key(&path, &key);
+    }
+    (key, status)
+}
+
+fn persist_key(path: &Path, key: &[u8]) {
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let result = crate::logic::identity::dpapi::protect(key)
+        .and_then(|blob| fs::write(path, hex::encode(blob)).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log::warn!("Failed to persist log chain key: {}", e);
+    }
+}
+
+fn key_tampered(status: KeyStatus, chained_seq: u64) -> bool {
+    match status {
+        KeyStatus::Created => chained_seq > 0,
+        KeyStatus::Replaced => true,
+        KeyStatus::Loaded | KeyStatus::Unreadable => false,
+    }
+}
+
+/// MITRE ATT&CK: T1070 - Indicator Removal
+fn report_key_tamper(status: KeyStatus, chained_seq: u64) {
+    let (tamper_type, cause) = match status {
+        KeyStatus::Created => ("log_key_missing", "was deleted"),
+        _ => ("log_key_invalid", "was modified or cannot be decrypted"),
+    };
+    let description = format!(
+        "Log chain key {} while {} chained records exist - earlier checkpoints can no longer be verified",
+        cause, chained_seq
+    );
+    log::error!("🚨 LOG TAMPERING DETECTED: {}", description);
+
+    crate::logic::cloud_sync::sync::queue_incident(
+        uuid::Uuid::new_v4(),
+        "high".to_string(),
+        format!("Log Tampering: {}", tamper_type),
+        Some(description.clone()),
+        Some(vec!["T1070".to_string()]),
+        Some("Defense Evasion".to_string()),
+        Some(1.0),
+    );
+    super::eventlog::write_tamper(tamper_type, &description);
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::event::{EventType, SecurityEvent};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_key_is_tamper_only_with_existing_chain() {
+        assert!(!key_tampered(KeyStatus::Created, 0));
+        assert!(key_tampered(KeyStatus::Created, 42));
+        assert!(key_tampered(KeyStatus::Replaced, 0));
+        assert!(!key_tampered(KeyStatus::Loaded, 42));
+        assert!(!key_tampered(KeyStatus::Unreadable, 42));
+    }
+
+    #[test]
+    fn test_detects_edit_and_delete() {
+        let temp_dir = TempDir::new().unwrap();
+        let key = b"test-key".to_vec();
+        let mut chain = Chain::new(key.clone());
+        let mut lines = Vec::new();
+        for i in 0..5 {
+            let event = SecurityEvent::new(EventType::ThreatDetected, &format!("Threat {}", i));
+            lines.push(chain.link(&event.to_jsonl()));
+        }
+        lines.push(chain.checkpoint().unwrap());
+        let file = temp_dir.path().join("security_2026_01_01_000000.jsonl");
+        let write = |lines: &[String]| fs::write(&file, lines.join("\n") + "\n").unwrap();
+
+        write(&lines);
+        let live = Some((file.as_path(), chain.seq(), chain.head()));
+        let report = verify_dir(temp_dir.path(), &key, live);
+        assert!(report.valid, "{:?}", report.issues);
+        assert_eq!((report.records_checked, report.checkpoints_verified), (5, 1));
+        // Record vẫn đọc được như SecurityEvent
+        assert_eq!(super::super::recorder::read_events(&file).unwrap().len(), 5);
+
+        // Sửa nội dung 1 dòng
+        let mut edited = lines.clone();
+        edited[1] = edited[1].replace("Threat 1", "Benign 1");
+        write(&edited);
+        let report = verify_dir(temp_dir.path(), &key, None);
+        assert!(report.issues.iter().any(|i| i.kind == IssueKind::HashMismatch && i.line == 2));
+
+        // Xóa 1 dòng
+        let mut deleted = lines.clone();
+        deleted.remove(2);
+        write(&deleted);
+        let report = verify_dir(temp_dir.path(), &key, None);
+        assert!(report.issues.iter().any(|i| i.kind == IssueKind::ChainBreak && i.line == 3));
+
+        // Checkpoint với key khác
+        let report = verify_dir(temp_dir.path(), b"other-key", None);
+        assert!(report.issues.iter().any(|i| i.kind == IssueKind::BadCheckpointSignature));
+    }
+}
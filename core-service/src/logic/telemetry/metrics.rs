@@ -0,0 +1,445 @@
+//! Prometheus Metrics - Endpoint `/metrics` (localhost) cho fleet observability
+//!
+//! Tùy chọn (mặc định tắt). Khi bật: HTTP server tối giản trên `127.0.0.1:<port>`,
+//! chỉ phục vụ `GET /metrics` theo text exposition format 0.0.4 → Prometheus /
+//! Grafana Agent / VictoriaMetrics scrape sức khỏe agent không cần cloud console.
+//!
+//! | Metric                                    | Type      | Nguồn                         |
+//! |-------------------------------------------|-----------|-------------------------------|
+//! | `oneshield_events_recorded_total`         | counter   | telemetry recorder            |
+//! | `oneshield_events_per_second`             | gauge     | tốc độ ghi event (~60s)       |
+//! | `oneshield_buffer_size{buffer}`           | gauge     | collector / model / exporters |
+//! | `oneshield_inference_duration_seconds`    | histogram | `model::inference::predict`   |
+//! | `oneshield_incidents_open{severity}`      | gauge     | incident manager              |
+//! | `oneshield_pending_actions`               | gauge     | action guard                  |
+//! | `oneshield_cloud_connected`               | gauge     | cloud sync                    |
+//! | `oneshield_cloud_sync_failures_total{op}` | counter   | heartbeat / incident / upload |
+//! | `oneshield_export_dropped_total{sink}`    | counter   | syslog / elastic / cloud      |
+//!
+//! Config local (`metrics.json`), đổi port → server bind lại.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::incident::{self, IncidentStatus, Severity};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CONFIG_FILE: &str = "metrics.json";
+const DEFAULT_PORT: u16 = 9464;
+/// Chu kỳ poll accept / lấy mẫu tốc độ event
+const TICK: Duration = Duration::from_millis(200);
+const RATE_SAMPLE_EVERY: Duration = Duration::from_secs(5);
+/// Cửa sổ tính events/sec (12 mẫu × 5s)
+const RATE_SAMPLES: usize = 12;
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Upper bound (giây) các bucket của histogram inference
+const INFERENCE_BUCKETS: [f64; 10] = [0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0];
+
+// ============================================================================
+// CONFIG / STATUS
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    /// Luôn bind 127.0.0.1
+    pub port: u16,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self { enabled: false, port: DEFAULT_PORT }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MetricsStatus {
+    pub enabled: bool,
+    pub listening: Option<String>,
+    pub scrapes: u64,
+    pub last_error: Option<String>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static CONFIG: Lazy<RwLock<MetricsConfig>> = Lazy::new(|| RwLock::new(load_config()));
+/// Tăng mỗi lần đổi config → server bind lại
+static CONFIG_GENERATION: AtomicU64 = AtomicU64::new(0);
+static STARTED: AtomicBool = AtomicBool::new(false);
+static STATUS: Lazy<Mutex<MetricsStatus>> = Lazy::new(|| Mutex::new(MetricsStatus::default()));
+
+static INFERENCE: Histogram = Histogram::new();
+static HEARTBEAT_FAILURES: AtomicU64 = AtomicU64::new(0);
+static INCIDENT_SYNC_FAILURES: AtomicU64 = AtomicU64::new(0);
+static TELEMETRY_UPLOAD_FAILURES: AtomicU64 = AtomicU64::new(0);
+/// (thời điểm, events_recorded) để tính events/sec
+static RATE_WINDOW: Lazy<Mutex<VecDeque<(Instant, u64)>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+// ============================================================================
+// HISTOGRAM
+// ============================================================================
+
+struct Histogram {
+    /// Số quan sát <= bucket (không cộng dồn, cộng dồn lúc render)
+    buckets: [AtomicU64; INFERENCE_BUCKETS.len()],
+    sum_us: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        Self {
+            buckets: [const { AtomicU64::new(0) }; INFERENCE_BUCKETS.len()],
+            sum_us: AtomicU64::new(0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    fn observe_us(&self, micros: u64) {
+        let secs = micros as f64 / 1_000_000.0;
+        if let Some(i) = INFERENCE_BUCKETS.iter().position(|b| secs <= *b) {
+            self.buckets[i].fetch_add(1, Ordering::Relaxed);
+        }
+        self.sum_us.fetch_add(micros, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        let mut cumulative = 0;
+        for (bound, bucket) in INFERENCE_BUCKETS.iter().zip(&self.buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative);
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, count);
+        let _ = writeln!(out, "{}_sum {}", name, self.sum_us.load(Ordering::Relaxed) as f64 / 1_000_000.0);
+        let _ = writeln!(out, "{}_count {}", name, count);
+    }
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Ghi nhận thời gian 1 lần inference (gọi từ `model::inference::predict`)
+pub fn observe_inference(micros: u64) {
+    INFERENCE.observe_us(micros);
+}
+
+/// (số lần inference, tổng thời gian µs) từ đầu session
+pub fn inference_totals() -> (u64, u64) {
+    (INFERENCE.count.load(Ordering::Relaxed), INFERENCE.sum_us.load(Ordering::Relaxed))
+}
+
+/// Ghi nhận heartbeat lên cloud thất bại
+pub fn inc_heartbeat_failure() {
+    HEARTBEAT_FAILURES.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Ghi nhận đồng bộ incident lên cloud thất bại
+pub fn inc_incident_sync_failure() {
+    INCIDENT_SYNC_FAILURES.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Ghi nhận upload telemetry lên cloud thất bại
+pub fn inc_telemetry_upload_failure() {
+    TELEMETRY_UPLOAD_FAILURES.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Khởi động server thread (no-op khi tắt, tự bind khi bật qua `set_config`)
+pub fn start() {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(serve_loop);
+}
+
+pub fn get_config() -> MetricsConfig {
+    CONFIG.read().clone()
+}
+
+pub fn set_config(config: MetricsConfig) -> Result<(), String> {
+    if config.port < 1024 {
+        return Err("port must be >= 1024".to_string());
+    }
+    *CONFIG.write() = config;
+    CONFIG_GENERATION.fetch_add(1, Ordering::SeqCst);
+    save_config();
+    Ok(())
+}
+
+pub fn get_status() -> MetricsStatus {
+    let mut status = STATUS.lock().clone();
+    status.enabled = CONFIG.read().enabled;
+    status
+}
+
+/// Text exposition của toàn bộ metric
+pub fn render() -> String {
+    let mut out = String::new();
+
+    let recorded = super::events_recorded();
+    counter(&mut out, "oneshield_events_recorded_total", "Security events recorded this session", &[(None, recorded as f64)]);
+    gauge(&mut out, "oneshield_events_per_second", "Security events recorded per second (about 60s window)",
+        &[(None, events_per_second(recorded))]);
+
+    let syslog = super::syslog::get_status();
+    let elastic = super::elastic::get_status();
+    let cloud_telemetry = crate::logic::cloud_sync::telemetry::get_status();
+    let (process_events, pending_summaries) = crate::logic::collector::buffer_sizes();
+    gauge(&mut out, "oneshield_buffer_size", "Items waiting in internal buffers / export queues", &[
+        (Some(("buffer", "process_events")), process_events as f64),
+        (Some(("buffer", "pending_summaries")), pending_summaries as f64),
+        (Some(("buffer", "model_sequence")), crate::logic::model::buffer::buffer_size() as f64),
+        (Some(("buffer", "cloud_incidents")), crate::logic::cloud_sync::sync::pending_incidents_count() as f64),
+        (Some(("buffer", "cloud_telemetry")), cloud_telemetry.queued_events as f64),
+        (Some(("buffer", "syslog")), syslog.queued as f64),
+        (Some(("buffer", "elasticsearch")), elastic.queued as f64),
+    ]);
+
+    INFERENCE.render(&mut out, "oneshield_inference_duration_seconds", "Anomaly model inference latency");
+
+    let incidents = incident::get_incidents();
+    let open = |severity: Severity| incidents.iter()
+        .filter(|i| i.status == IncidentStatus::Open && i.severity == severity)
+        .count() as f64;
+    gauge(&mut out, "oneshield_incidents_open", "Open incidents by severity", &[
+        (Some(("severity", "low")), open(Severity::Low)),
+        (Some(("severity", "medium")), open(Severity::Medium)),
+        (Some(("severity", "high")), open(Severity::High)),
+        (Some(("severity", "critical")), open(Severity::Critical)),
+    ]);
+    gauge(&mut out, "oneshield_pending_actions", "Response actions waiting for approval",
+        &[(None, crate::logic::action_guard::get_pending_actions().len() as f64)]);
+
+    let sync = crate::logic::cloud_sync::get_status();
+    gauge(&mut out, "oneshield_cloud_connected", "1 if the agent is connected to the cloud console",
+        &[(None, if sync.is_connected { 1.0 } else { 0.0 })]);
+    gauge(&mut out, "oneshield_cloud_consecutive_failures", "Consecutive failed cloud heartbeats",
+        &[(None, sync.consecutive_failures as f64)]);
+    counter(&mut out, "oneshield_cloud_sync_failures_total", "Failed cloud sync operations", &[
+        (Some(("op", "heartbeat")), HEARTBEAT_FAILURES.load(Ordering::Relaxed) as f64),
+        (Some(("op", "incident_sync")), INCIDENT_SYNC_FAILURES.load(Ordering::Relaxed) as f64),
+        (Some(("op", "telemetry_upload")), TELEMETRY_UPLOAD_FAILURES.load(Ordering::Relaxed) as f64),
+    ]);
+    counter(&mut out, "oneshield_export_dropped_total", "Events dropped by exporters (queue full)", &[
+        (Some(("sink", "syslog")), syslog.dropped as f64),
+        (Some(("sink", "elasticsearch")), elastic.dropped as f64),
+        (Some(("sink", "cloud")), cloud_telemetry.dropped as f64),
+    ]);
+
+    out
+}
+
+// ============================================================================
+// FORMAT
+// ============================================================================
+
+type Sample<'a> = (Option<(&'a str, &'a str)>, f64);
+
+fn counter(out: &mut String, name: &str, help: &str, samples: &[Sample]) {
+    family(out, name, help, "counter", samples);
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, samples: &[Sample]) {
+    family(out, name, help, "gauge", samples);
+}
+
+fn family(out: &mut String, name: &str, help: &str, kind: &str, samples: &[Sample]) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (label, value) in samples {
+        match label {
+            Some((key, val)) => {
+                let _ = writeln!(out, "{}{{{}=\"{}\"}} {}", name, key, escape_label(val), value);
+            }
+            None => {
+                let _ = writeln!(out, "{} {}", name, value);
+            }
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// events/sec trên cửa sổ mẫu (mẫu được lấy bởi server thread)
+fn events_per_second(current: u64) -> f64 {
+    let window = RATE_WINDOW.lock();
+    match window.front() {
+        Some((at, count)) => {
+            let secs = at.elapsed().as_secs_f64();
+            if secs < 1.0 { 0.0 } else { current.saturating_sub(*count) as f64 / secs }
+        }
+        None => 0.0,
+    }
+}
+
+fn sample_rate() {
+    let mut window = RATE_WINDOW.lock();
+    if window.back().map_or(true, |(at, _)| at.elapsed() >= RATE_SAMPLE_EVERY) {
+        window.push_back((Instant::now(), super::events_recorded()));
+        while window.len() > RATE_SAMPLES {
+            window.pop_front();
+        }
+    }
+}
+
+// ============================================================================
+// SERVER
+// ============================================================================
+
+fn serve_loop() {
+    let mut bound: Option<(u64, TcpListener)> = None;
+    loop {
+        thread::sleep(TICK);
+        sample_rate();
+
+        let config = CONFIG.read().clone();
+        let generation = CONFIG_GENERATION.load(Ordering::SeqCst);
+        if !config.enabled {
+            if bound.take().is_some() {
+                STATUS.lock().listening = None;
+                log::info!("Metrics endpoint stopped");
+            }
+            continue;
+        }
+        if bound.as_ref().map_or(true, |(g, _)| *g != generation) {
+            bound = None;
+            match bind(config.port) {
+                Ok(listener) => {
+                    let address = format!("127.0.0.1:{}", config.port);
+                    log::info!("Metrics endpoint listening on http://{}/metrics", address);
+                    let mut status = STATUS.lock();
+                    status.listening = Some(address);
+                    status.last_error = None;
+                    bound = Some((generation, listener));
+                }
+                Err(e) => {
+                    let mut status = STATUS.lock();
+                    if status.last_error.as_deref() != Some(e.as_str()) {
+                        log::warn!("Metrics endpoint: {}", e);
+                    }
+                    status.listening = None;
+                    status.last_error = Some(e);
+                    continue;
+                }
+            }
+        }
+
+        if let Some((_, listener)) = &bound {
+            while let Ok((stream, _)) = listener.accept() {
+                if let Err(e) = handle(stream) {
+                    log::debug!("Metrics request failed: {}", e);
+                }
+            }
+        }
+    }
+}
+
+fn bind(port: u16) -> Result<TcpListener, String> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .map_err(|e| format!("bind 127.0.0.1:{} failed: {}", port, e))?;
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    Ok(listener)
+}
+
+fn handle(mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut buf = [0u8; 2048];
+    let n = stream.read(&mut buf)?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let mut parts = request.lines().next().unwrap_or_default().split_whitespace();
+    let (method, path) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+
+    let (status, content_type, body) = if method == "GET" && (path == "/metrics" || path.starts_with("/metrics?")) {
+        STATUS.lock().scrapes += 1;
+        ("200 OK", "text/plain; version=0.0.4; charset=utf-8", render())
+    } else {
+        ("404 Not Found", "text/plain; charset=utf-8", "not found\n".to_string())
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, content_type, body.len(), body
+    );
+    stream.write_all(response.as_bytes())?;
+    stream.flush()
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn config_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(CONFIG_FILE)
+}
+
+fn load_config() -> MetricsConfig {
+    fs::read_to_string(config_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_config() {
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*CONFIG.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exposition_format() {
+        let histogram = Histogram::new();
+        histogram.observe_us(300);
+        histogram.observe_us(4_000);
+        histogram.observe_us(2_000_000);
+        let mut out = String::new();
+        histogram.render(&mut out, "latency_seconds", "Latency");
+        assert!(out.contains("# TYPE latency_seconds histogram"));
+        assert!(out.contains("latency_seconds_bucket{le=\"0.0005\"} 1\n"));
+        assert!(out.contains("latency_seconds_bucket{le=\"0.005\"} 2\n"));
+        assert!(out.contains("latency_seconds_bucket{le=\"1\"} 2\n"));
+        assert!(out.contains("latency_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(out.contains("latency_seconds_count 3\n"));
+
+        let mut out = String::new();
+        gauge(&mut out, "queue", "Queue", &[(Some(("name", "a\"b")), 2.0), (None, 1.5)]);
+        assert_eq!(out, "# HELP queue Queue\n# TYPE queue gauge\nqueue{name=\"a\\\"b\"} 2\nqueue 1.5\n");
+    }
+}
@@ -9,7 +9,17 @@
 //! ## Structure
 //! - `event.rs` - SecurityEvent struct (immutable, timestamped)
 //! - `recorder.rs` - Append-only JSONL writer (thread-safe)
-//! - `exporter.rs` - Export to formats (CSV, JSON) + training data
+//! - `retention.rs` - Size/time rotation policy, zstd compression, retention budget
+//! - `query.rs` - Filtered, paginated queries over on-disk (incl. rotated) logs
+//! - `metrics.rs` - Optional Prometheus `/metrics` endpoint on localhost (agent health)
+//! - `otel.rs` - OpenTelemetry spans for the detection pipeline + optional OTLP/HTTP exporter
+//! - `integrity.rs` - Per-record hash chain + signed checkpoints, `verify_log_integrity`
+//! - `exporter.rs` - Export to formats (CSV, JSON, CEF, LEEF) + training data
+//! - `rule_stats.rs` - Per-rule tuning stats (match rate, FP feedback, severity)
+//! - `syslog.rs` - Stream events to a syslog server (RFC 5424 over UDP/TCP/TLS)
+//! - `elastic.rs` - Ship events + incidents to Elasticsearch/OpenSearch (`_bulk`, daily indices)
+//! - `eventlog.rs` - Write High/Critical incidents + tamper events to the Windows Event Log
+//! - `scrub.rs` - Hash usernames / hostnames / profile paths in file exports + cloud upload (local salt)
 //!
 //! ## Usage
 //! ```ignore
@@ -30,7 +40,17 @@
 
 pub mod event;
 pub mod recorder;
+pub mod retention;
+pub mod integrity;
+pub mod query;
+pub mod metrics;
+pub mod otel;
 pub mod exporter;
+pub mod rule_stats;
+pub mod syslog;
+pub mod elastic;
+pub mod eventlog;
+pub mod scrub;
 
 // Re-export main types and functions
 pub use event::{
@@ -57,6 +77,8 @@ pub use recorder::{
 
 pub use exporter::{
     ExportFormat,
+    EventFormat,
+    format_event,
     export_file,
     export_events,
     export_training_data,
@@ -64,3 +86,13 @@ pub use exporter::{
     AnalyticsSummary,
     TrainingRecord,
 };
+
+pub use rule_stats::RuleTuningStats;
+pub use syslog::{SyslogConfig, SyslogProtocol, SyslogStatus};
+pub use retention::{RetentionConfig, RetentionStats};
+pub use integrity::{IntegrityReport, IntegrityIssue};
+pub use query::{EventQuery, EventQueryResult};
+pub use metrics::{MetricsConfig, MetricsStatus};
+pub use otel::{OtelConfig, OtelStatus};
+pub use elastic::{ElasticConfig, ElasticStatus};
+pub use eventlog::{EventLogConfig, EventLogStatus};
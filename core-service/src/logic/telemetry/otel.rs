@@ -0,0 +1,569 @@
+//! OpenTelemetry Tracing - Span cho detection pipeline + OTLP/HTTP exporter
+//!
+//! Mục đích: đo latency từng stage collector → features → baseline → AI → policy →
+//! action ngay trên máy production, xem trong Jaeger / Tempo / Honeycomb...
+//!
+//! - `span("stage")` trả về guard; span kết thúc khi guard drop. Parent lấy từ span
+//!   đang mở trên cùng thread (thread-local) → gọi lồng nhau tự thành cây
+//! - Root span quyết định sampling (`sample_ratio`), span con theo root
+//! - Tắt (mặc định) → `span()` chỉ đọc 1 AtomicBool, không cấp phát
+//! - Exporter: OTLP/HTTP JSON (`POST <endpoint>/v1/traces`), batch theo `batch_size` /
+//!   `flush_interval_secs`; queue có giới hạn (đầy → bỏ span cũ nhất)
+//! - Latency tổng hợp theo stage giữ local (`get_status().stages`) để xem nhanh từ UI
+//!
+//! Config local (`otel.json`), đổi qua `set_config`.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::logic::cloud_sync::managed_settings;
+use super::exporter;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CONFIG_FILE: &str = "otel.json";
+const MAX_QUEUE: usize = 20_000;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Worker tick (flush thực tế theo `flush_interval_secs` / `batch_size`)
+const TICK: Duration = Duration::from_millis(500);
+/// Instrumentation scope
+const SCOPE_NAME: &str = "oneshield.pipeline";
+
+/// SpanKind INTERNAL
+const SPAN_KIND_INTERNAL: u8 = 1;
+/// Status.code ERROR
+const STATUS_CODE_ERROR: u8 = 2;
+
+// ============================================================================
+// CONFIG / STATUS
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OtelConfig {
+    pub enabled: bool,
+    /// OTLP/HTTP base URL của collector ("http://localhost:4318")
+    pub endpoint: String,
+    /// Header thêm vào request (API key của backend SaaS, ...)
+    pub headers: BTreeMap<String, String>,
+    pub service_name: String,
+    /// Tỉ lệ trace được giữ 0.0..=1.0
+    pub sample_ratio: f64,
+    pub batch_size: usize,
+    pub flush_interval_secs: u64,
+}
+
+impl Default for OtelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://localhost:4318".to_string(),
+            headers: BTreeMap::new(),
+            service_name: "oneshield-agent".to_string(),
+            sample_ratio: 1.0,
+            batch_size: 512,
+            flush_interval_secs: 5,
+        }
+    }
+}
+
+impl OtelConfig {
+    fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let endpoint = self.endpoint.trim();
+        if !(endpoint.starts_with("http://") || endpoint.starts_with("https://")) {
+            return Err("OTLP endpoint must start with http:// or https://".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.sample_ratio) {
+            return Err("sample_ratio must be between 0 and 1".to_string());
+        }
+        if self.batch_size == 0 || self.flush_interval_secs == 0 {
+            return Err("batch_size and flush_interval_secs must be greater than zero".to_string());
+        }
+        if self.service_name.trim().is_empty() {
+            return Err("service_name is required".to_string());
+        }
+        Ok(())
+    }
+
+    fn traces_url(&self) -> String {
+        let base = self.endpoint.trim().trim_end_matches('/');
+        if base.ends_with("/v1/traces") {
+            base.to_string()
+        } else {
+            format!("{}/v1/traces", base)
+        }
+    }
+}
+
+/// Latency tổng hợp của 1 stage (span name)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StageLatency {
+    pub count: u64,
+    pub errors: u64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    #[serde(skip)]
+    total_ms: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OtelStatus {
+    pub enabled: bool,
+    pub spans_exported: u64,
+    /// Queue đầy → bỏ span cũ nhất
+    pub spans_dropped: u64,
+    pub queued: usize,
+    pub last_export: Option<i64>,
+    pub last_error: Option<String>,
+    pub stages: BTreeMap<String, StageLatency>,
+}
+
+// ============================================================================
+// SPAN
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum AttrValue {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl From<&str> for AttrValue {
+    fn from(v: &str) -> Self { AttrValue::Str(v.to_string()) }
+}
+impl From<String> for AttrValue {
+    fn from(v: String) -> Self { AttrValue::Str(v) }
+}
+impl From<i64> for AttrValue {
+    fn from(v: i64) -> Self { AttrValue::Int(v) }
+}
+impl From<u32> for AttrValue {
+    fn from(v: u32) -> Self { AttrValue::Int(v as i64) }
+}
+impl From<usize> for AttrValue {
+    fn from(v: usize) -> Self { AttrValue::Int(v as i64) }
+}
+impl From<f32> for AttrValue {
+    fn from(v: f32) -> Self { AttrValue::Float(v as f64) }
+}
+impl From<f64> for AttrValue {
+    fn from(v: f64) -> Self { AttrValue::Float(v) }
+}
+impl From<bool> for AttrValue {
+    fn from(v: bool) -> Self { AttrValue::Bool(v) }
+}
+
+/// Span đã kết thúc, chờ export
+#[derive(Debug, Clone)]
+pub struct SpanData {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    pub parent_span_id: Option<[u8; 8]>,
+    pub name: &'static str,
+    pub start_ns: u64,
+    pub end_ns: u64,
+    pub attributes: Vec<(&'static str, AttrValue)>,
+    pub error: Option<String>,
+}
+
+/// (trace_id, span_id) của span đang mở; None = trace không được sample
+type SpanContext = Option<([u8; 16], [u8; 8])>;
+
+thread_local! {
+    static CURRENT: RefCell<Vec<SpanContext>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Guard của span đang mở (kết thúc khi drop)
+pub struct SpanGuard {
+    /// Có push context lên stack của thread
+    pushed: bool,
+    data: Option<SpanData>,
+}
+
+impl SpanGuard {
+    pub fn set_attr(&mut self, key: &'static str, value: impl Into<AttrValue>) {
+        if let Some(data) = &mut self.data {
+            data.attributes.push((key, value.into()));
+        }
+    }
+
+    pub fn set_error(&mut self, message: impl Into<String>) {
+        if let Some(data) = &mut self.data {
+            data.error = Some(message.into());
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.data.is_some()
+    }
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        if self.pushed {
+            CURRENT.with(|stack| stack.borrow_mut().pop());
+        }
+        if let Some(mut data) = self.data.take() {
+            data.end_ns = now_ns().max(data.start_ns);
+            finish(data);
+        }
+    }
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// Mirror của `config.enabled` (fast path trong `span()`)
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STARTED: AtomicBool = AtomicBool::new(false);
+/// Config đang theo managed settings từ cloud → không sửa local được
+static MANAGED: AtomicBool = AtomicBool::new(false);
+static CONFIG: Lazy<RwLock<OtelConfig>> = Lazy::new(|| {
+    let config = load_config();
+    ENABLED.store(config.enabled, Ordering::SeqCst);
+    RwLock::new(config)
+});
+static QUEUE: Lazy<Mutex<VecDeque<SpanData>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+static STATUS: Lazy<Mutex<OtelStatus>> = Lazy::new(|| Mutex::new(OtelStatus::default()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Mở span `name` (con của span đang mở trên thread, hoặc root mới)
+pub fn span(name: &'static str) -> SpanGuard {
+    span_at(name, None)
+}
+
+/// Như `span`, với thời điểm bắt đầu cho trước (vd: thời gian chờ trong queue)
+pub fn span_at(name: &'static str, start: Option<SystemTime>) -> SpanGuard {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return SpanGuard { pushed: false, data: None };
+    }
+
+    let parent = CURRENT.with(|stack| stack.borrow().last().copied());
+    let context: SpanContext = match parent {
+        // Trace không được sample → span con cũng không
+        Some(None) => None,
+        Some(Some((trace_id, _))) => Some((trace_id, rand::thread_rng().gen())),
+        None => {
+            let ratio = CONFIG.read().sample_ratio;
+            let sampled = ratio >= 1.0 || rand::thread_rng().gen::<f64>() < ratio;
+            sampled.then(|| (rand::thread_rng().gen(), rand::thread_rng().gen()))
+        }
+    };
+    CURRENT.with(|stack| stack.borrow_mut().push(context));
+
+    let data = context.map(|(trace_id, span_id)| SpanData {
+        trace_id,
+        span_id,
+        parent_span_id: parent.flatten().map(|(_, id)| id),
+        name,
+        start_ns: start.map(system_time_ns).unwrap_or_else(now_ns),
+        end_ns: 0,
+        attributes: Vec::new(),
+        error: None,
+    });
+    SpanGuard { pushed: true, data }
+}
+
+/// Khởi động exporter thread
+pub fn start() {
+    Lazy::force(&CONFIG);
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(|| {
+        let mut last_flush = now_ns();
+        loop {
+            thread::sleep(TICK);
+            let config = CONFIG.read().clone();
+            if !config.enabled {
+                continue;
+            }
+            let due = now_ns().saturating_sub(last_flush) >= config.flush_interval_secs * 1_000_000_000;
+            if !due && QUEUE.lock().len() < config.batch_size {
+                continue;
+            }
+            last_flush = now_ns();
+            if let Err(e) = flush(&config) {
+                log::debug!("OTLP export failed: {}", e);
+            }
+        }
+    });
+}
+
+pub fn get_config() -> OtelConfig {
+    CONFIG.read().clone()
+}
+
+pub fn set_config(config: OtelConfig) -> Result<(), String> {
+    if MANAGED.load(Ordering::SeqCst) {
+        return Err("OpenTelemetry settings are managed by your organization".to_string());
+    }
+    config.validate()?;
+    replace_config(config);
+    save_config();
+    Ok(())
+}
+
+/// Config local + override từ managed settings (chỉ kiểm tra, chưa áp dụng)
+pub fn managed_config(overrides: &serde_json::Value) -> Result<OtelConfig, String> {
+    let config = managed_settings::merge_overrides(&load_config(), overrides)?;
+    config.validate()?;
+    Ok(config)
+}
+
+/// Áp config từ managed settings (không ghi ra disk). None = quay lại config local
+pub fn set_managed(config: Option<OtelConfig>) {
+    match config {
+        Some(config) => {
+            MANAGED.store(true, Ordering::SeqCst);
+            replace_config(config);
+        }
+        None if MANAGED.swap(false, Ordering::SeqCst) => replace_config(load_config()),
+        None => {}
+    }
+}
+
+fn replace_config(config: OtelConfig) {
+    ENABLED.store(config.enabled, Ordering::SeqCst);
+    if !config.enabled {
+        QUEUE.lock().clear();
+    }
+    *CONFIG.write() = config;
+}
+
+pub fn get_status() -> OtelStatus {
+    let mut status = STATUS.lock().clone();
+    status.enabled = CONFIG.read().enabled;
+    status.queued = QUEUE.lock().len();
+    status
+}
+
+/// Xóa latency tổng hợp (bắt đầu đo lại sau khi đổi cấu hình / deploy)
+pub fn reset_stage_stats() {
+    STATUS.lock().stages.clear();
+}
+
+// ============================================================================
+// EXPORT
+// ============================================================================
+
+fn finish(data: SpanData) {
+    {
+        let mut status = STATUS.lock();
+        let stage = status.stages.entry(data.name.to_string()).or_default();
+        let ms = (data.end_ns - data.start_ns) as f64 / 1_000_000.0;
+        stage.count += 1;
+        stage.total_ms += ms;
+        stage.avg_ms = stage.total_ms / stage.count as f64;
+        stage.max_ms = stage.max_ms.max(ms);
+        if data.error.is_some() {
+            stage.errors += 1;
+        }
+    }
+
+    let mut queue = QUEUE.lock();
+    if queue.len() >= MAX_QUEUE {
+        queue.pop_front();
+        STATUS.lock().spans_dropped += 1;
+    }
+    queue.push_back(data);
+}
+
+fn flush(config: &OtelConfig) -> Result<(), String> {
+    loop {
+        let batch: Vec<SpanData> = {
+            let mut queue = QUEUE.lock();
+            let n = queue.len().min(config.batch_size);
+            queue.drain(..n).collect()
+        };
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let body = build_payload(config, &batch).to_string();
+        let mut req = ureq::post(&config.traces_url())
+            .timeout(REQUEST_TIMEOUT)
+            .set("Content-Type", "application/json");
+        for (key, value) in &config.headers {
+            req = req.set(key, value);
+        }
+
+        match req.send_string(&body) {
+            Ok(_) => {
+                let mut status = STATUS.lock();
+                status.spans_exported += batch.len() as u64;
+                status.last_export = Some(chrono::Utc::now().timestamp());
+                status.last_error = None;
+            }
+            Err(e) => {
+                let message = match e {
+                    ureq::Error::Status(code, response) => {
+                        format!("HTTP {}: {}", code, response.into_string().unwrap_or_default())
+                    }
+                    other => other.to_string(),
+                };
+                // Collector lỗi → trả batch về đầu queue cho lần sau
+                let mut queue = QUEUE.lock();
+                for span in batch.into_iter().rev() {
+                    if queue.len() >= MAX_QUEUE {
+                        break;
+                    }
+                    queue.push_front(span);
+                }
+                drop(queue);
+                STATUS.lock().last_error = Some(message.clone());
+                return Err(message);
+            }
+        }
+    }
+}
+
+/// ExportTraceServiceRequest (OTLP JSON encoding: id dạng hex, int64 dạng string)
+fn build_payload(config: &OtelConfig, spans: &[SpanData]) -> serde_json::Value {
+    let spans: Vec<serde_json::Value> = spans.iter().map(|span| {
+        let mut value = json!({
+            "traceId": hex::encode(span.trace_id),
+            "spanId": hex::encode(span.span_id),
+            "name": span.name,
+            "kind": SPAN_KIND_INTERNAL,
+            "startTimeUnixNano": span.start_ns.to_string(),
+            "endTimeUnixNano": span.end_ns.to_string(),
+            "attributes": span.attributes.iter().map(|(k, v)| attribute(k, v)).collect::<Vec<_>>(),
+        });
+        if let Some(parent) = span.parent_span_id {
+            value["parentSpanId"] = json!(hex::encode(parent));
+        }
+        if let Some(error) = &span.error {
+            value["status"] = json!({ "code": STATUS_CODE_ERROR, "message": error });
+        }
+        value
+    }).collect();
+
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    attribute("service.name", &AttrValue::Str(config.service_name.clone())),
+                    attribute("service.version", &AttrValue::from(env!("CARGO_PKG_VERSION"))),
+                    attribute("host.name", &AttrValue::Str(exporter::local_hostname())),
+                ]
+            },
+            "scopeSpans": [{
+                "scope": { "name": SCOPE_NAME, "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }]
+        }]
+    })
+}
+
+fn attribute(key: &str, value: &AttrValue) -> serde_json::Value {
+    let value = match value {
+        AttrValue::Str(s) => json!({ "stringValue": s }),
+        AttrValue::Int(i) => json!({ "intValue": i.to_string() }),
+        AttrValue::Float(f) => json!({ "doubleValue": f }),
+        AttrValue::Bool(b) => json!({ "boolValue": b }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+fn now_ns() -> u64 {
+    system_time_ns(SystemTime::now())
+}
+
+fn system_time_ns(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0)
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn config_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(CONFIG_FILE)
+}
+
+fn load_config() -> OtelConfig {
+    fs::read_to_string(config_path())
+        .ok()
+        .and_then(|c| serde_json::from_str::<OtelConfig>(&c).ok())
+        .filter(|c| c.validate().is_ok())
+        .unwrap_or_default()
+}
+
+fn save_config() {
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*CONFIG.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_spans_and_payload() {
+        Lazy::force(&CONFIG);
+        ENABLED.store(true, Ordering::SeqCst);
+
+        let (root, child) = {
+            let mut root = span("pipeline.summary");
+            root.set_attr("summary.id", "s-1");
+            let child = {
+                let mut child = span("baseline.analyze");
+                child.set_attr("tags", 2usize);
+                child.set_error("boom");
+                child.data.clone().unwrap()
+            };
+            (root.data.clone().unwrap(), child)
+        };
+        ENABLED.store(CONFIG.read().enabled, Ordering::SeqCst);
+
+        assert_eq!(child.trace_id, root.trace_id);
+        assert_eq!(child.parent_span_id, Some(root.span_id));
+        assert_eq!(root.parent_span_id, None);
+        CURRENT.with(|stack| assert!(stack.borrow().is_empty()));
+
+        let payload = build_payload(&OtelConfig::default(), &[root.clone(), child.clone()]);
+        let spans = &payload["resourceSpans"][0]["scopeSpans"][0]["spans"];
+        assert_eq!(spans[0]["traceId"], hex::encode(root.trace_id));
+        assert_eq!(spans[0]["traceId"].as_str().unwrap().len(), 32);
+        assert!(spans[0].get("parentSpanId").is_none());
+        assert_eq!(spans[1]["parentSpanId"], hex::encode(root.span_id));
+        assert_eq!(spans[1]["attributes"][0]["value"]["intValue"], "2");
+        assert_eq!(spans[1]["status"]["code"], 2);
+        assert_eq!(payload["resourceSpans"][0]["resource"]["attributes"][0]["value"]["stringValue"], "oneshield-agent");
+    }
+}
@@ -0,0 +1,308 @@
+//! Event Query - Lọc + phân trang security events trực tiếp trên file log
+//!
+//! UI không phải load nguyên file để hiển thị view đã lọc:
+//! - Đọc stream từng dòng trên `.jsonl` và `.jsonl.zst` (đã rotate), mới nhất trước
+//! - File nằm ngoài khoảng thời gian (theo timestamp trong tên file) bị bỏ qua
+//! - Free-text lọc thô trên dòng trước khi parse JSON, sau đó khớp lại trên
+//!   description / process / metadata
+//! - Phân trang theo offset, dừng đọc khi đủ `limit + 1` kết quả (`has_more`)
+
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::logic::policy::Severity;
+use super::event::{EventType, SecurityEvent};
+use super::recorder;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct EventQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Rỗng = mọi loại
+    pub event_types: Vec<EventType>,
+    /// Chỉ event có severity >= giá trị này (event không có severity bị loại)
+    pub min_severity: Option<Severity>,
+    pub pid: Option<u32>,
+    /// Substring (không phân biệt hoa thường) của tên / path process
+    pub process_name: Option<String>,
+    /// Substring trong description, process, command line, metadata
+    pub text: Option<String>,
+    pub offset: usize,
+    /// Mặc định 100, tối đa 1000
+    pub limit: Option<usize>,
+    /// Cũ nhất trước (mặc định mới nhất trước)
+    pub oldest_first: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventQueryResult {
+    pub events: Vec<SecurityEvent>,
+    pub offset: usize,
+    pub limit: usize,
+    pub has_more: bool,
+    /// Offset cho trang kế tiếp (None nếu hết)
+    pub next_offset: Option<usize>,
+    pub files_scanned: usize,
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Query trên thư mục log của recorder
+pub fn query_security_events(query: &EventQuery) -> Result<EventQueryResult, String> {
+    let dir = recorder::log_dir().unwrap_or_else(recorder::default_log_dir);
+    query_dir(&dir, query)
+}
+
+pub fn query_dir(dir: &Path, query: &EventQuery) -> Result<EventQueryResult, String> {
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Err("'from' must be before 'to'".to_string());
+        }
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let wanted = query.offset + limit + 1;
+    let filter = Filter::new(query);
+
+    let files = recorder::list_log_files(&dir.to_path_buf()).map_err(|e| e.to_string())?;
+    let windows = file_windows(&files);
+
+    let mut matched: Vec<SecurityEvent> = Vec::new();
+    let mut files_scanned = 0;
+    let ordered: Box<dyn Iterator<Item = &(PathBuf, Option<DateTime<Utc>>, Option<DateTime<Utc>>)>> =
+        if query.oldest_first { Box::new(windows.iter()) } else { Box::new(windows.iter().rev()) };
+
+    for (path, start, end) in ordered {
+        if matched.len() >= wanted {
+            break;
+        }
+        // File mở lúc `start`, event cuối trước `end` (file kế tiếp mở)
+        if let (Some(to), Some(start)) = (query.to, start) {
+            if *start > to {
+                continue;
+            }
+        }
+        if let (Some(from), Some(end)) = (query.from, end) {
+            if *end < from {
+                continue;
+            }
+        }
+
+        files_scanned += 1;
+        let mut in_file = scan_file(path, &filter).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if !query.oldest_first {
+            in_file.reverse();
+        }
+        matched.extend(in_file);
+    }
+
+    let has_more = matched.len() > query.offset + limit;
+    let events: Vec<SecurityEvent> = matched.into_iter().skip(query.offset).take(limit).collect();
+    Ok(EventQueryResult {
+        events,
+        offset: query.offset,
+        limit,
+        has_more,
+        next_offset: has_more.then_some(query.offset + limit),
+        files_scanned,
+    })
+}
+
+// ============================================================================
+// FILTER
+// ============================================================================
+
+struct Filter<'a> {
+    query: &'a EventQuery,
+    process_name: Option<String>,
+    text: Option<String>,
+    min_rank: Option<u8>,
+}
+
+impl<'a> Filter<'a> {
+    fn new(query: &'a EventQuery) -> Self {
+        let lower = |s: &Option<String>| s.as_ref()
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty());
+        Self {
+            query,
+            process_name: lower(&query.process_name),
+            text: lower(&query.text),
+            min_rank: query.min_severity.map(severity_rank),
+        }
+    }
+
+    /// Lọc thô trên dòng (trước khi parse)
+    fn line_may_match(&self, line: &str) -> bool {
+        match &self.text {
+            Some(text) => line.to_lowercase().contains(text.as_str()),
+            None => true,
+        }
+    }
+
+    fn matches(&self, event: &SecurityEvent) -> bool {
+        let q = self.query;
+        if q.from.map_or(false, |from| event.timestamp < from) || q.to.map_or(false, |to| event.timestamp > to) {
+            return false;
+        }
+        if !q.event_types.is_empty() && !q.event_types.contains(&event.event_type) {
+            return false;
+        }
+        if let Some(min_rank) = self.min_rank {
+            if event.severity.map_or(true, |s| severity_rank(s) < min_rank) {
+                return false;
+            }
+        }
+        if let Some(pid) = q.pid {
+            if event.process.as_ref().and_then(|p| p.pid) != Some(pid) {
+                return false;
+            }
+        }
+        if let Some(name) = &self.process_name {
+            let Some(process) = &event.process else { return false };
+            let in_name = process.name.to_lowercase().contains(name.as_str());
+            let in_path = process.path.as_ref().map_or(false, |p| p.to_lowercase().contains(name.as_str()));
+            if !in_name && !in_path {
+                return false;
+            }
+        }
+        if let Some(text) = &self.text {
+            let mut haystack = event.description.to_lowercase();
+            if let Some(process) = &event.process {
+                haystack.push(' ');
+                haystack.push_str(&process.name.to_lowercase());
+                for extra in [&process.path, &process.command_line].into_iter().flatten() {
+                    haystack.push(' ');
+                    haystack.push_str(&extra.to_lowercase());
+                }
+            }
+            if let Some(metadata) = &event.metadata {
+                haystack.push(' ');
+                haystack.push_str(&metadata.to_string().to_lowercase());
+            }
+            if !haystack.contains(text.as_str()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn severity_rank(severity: Severity) -> u8 {
+    match severity {
+        Severity::Low => 0,
+        Severity::Medium => 1,
+        Severity::High => 2,
+        Severity::Critical => 3,
+    }
+}
+
+// ============================================================================
+// FILES
+// ============================================================================
+
+/// Event khớp filter trong 1 file, theo thứ tự ghi
+fn scan_file(path: &Path, filter: &Filter) -> std::io::Result<Vec<SecurityEvent>> {
+    let reader = recorder::open_log_reader(path)?;
+    let mut events = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.is_empty() || !filter.line_may_match(&line) {
+            continue;
+        }
+        if let Ok(event) = serde_json::from_str::<SecurityEvent>(&line) {
+            if filter.matches(&event) {
+                events.push(event);
+            }
+        }
+    }
+    Ok(events)
+}
+
+/// (file, thời điểm mở, thời điểm file kế tiếp mở) - theo thứ tự tên file
+fn file_windows(files: &[PathBuf]) -> Vec<(PathBuf, Option<DateTime<Utc>>, Option<DateTime<Utc>>)> {
+    let starts: Vec<Option<DateTime<Utc>>> = files.iter().map(|f| file_start_time(f)).collect();
+    files.iter()
+        .enumerate()
+        .map(|(i, f)| (f.clone(), starts[i], starts.get(i + 1).copied().flatten()))
+        .collect()
+}
+
+/// `security_YYYY_MM_DD_HHMMSS.jsonl[.zst]` → thời điểm mở file
+fn file_start_time(path: &Path) -> Option<DateTime<Utc>> {
+    let name = path.file_name()?.to_str()?;
+    let stamp = name.strip_prefix("security_")?.get(..17)?;
+    NaiveDateTime::parse_from_str(stamp, "%Y_%m_%d_%H%M%S")
+        .ok()
+        .map(|t| t.and_utc())
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::event::ProcessInfo;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_query_filters_and_pages() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut lines = Vec::new();
+        for i in 0..30 {
+            let mut event = SecurityEvent::new(EventType::ThreatDetected, &format!("Threat {}", i))
+                .with_process(ProcessInfo::new(100 + (i % 3), if i % 2 == 0 { "evil.exe" } else { "notepad.exe" }))
+                .with_severity(if i % 5 == 0 { Severity::Critical } else { Severity::Low });
+            event.timestamp = Utc::now() - chrono::Duration::seconds(100 - i as i64);
+            lines.push(event.to_jsonl());
+        }
+        lines.push(SecurityEvent::new(EventType::SystemStart, "Started").to_jsonl());
+        std::fs::write(temp_dir.path().join("security_2026_01_01_000000.jsonl"), lines.join("\n")).unwrap();
+
+        assert_eq!(file_start_time(Path::new("security_2026_01_01_000000.jsonl.zst")).unwrap().to_rfc3339(),
+            "2026-01-01T00:00:00+00:00");
+
+        let query = EventQuery {
+            event_types: vec![EventType::ThreatDetected],
+            process_name: Some("EVIL".to_string()),
+            limit: Some(4),
+            ..Default::default()
+        };
+        let page = query_dir(temp_dir.path(), &query).unwrap();
+        assert_eq!(page.events.len(), 4);
+        assert!(page.has_more);
+        assert_eq!(page.next_offset, Some(4));
+        // Mới nhất trước
+        assert_eq!(page.events[0].description, "Threat 28");
+
+        let last = query_dir(temp_dir.path(), &EventQuery { offset: 12, ..query.clone() }).unwrap();
+        assert_eq!(last.events.len(), 3);
+        assert!(!last.has_more);
+
+        let critical = EventQuery { min_severity: Some(Severity::High), pid: Some(100), ..Default::default() };
+        let result = query_dir(temp_dir.path(), &critical).unwrap();
+        // i % 5 == 0 && i % 3 == 0 → 0, 15
+        assert_eq!(result.events.len(), 2);
+
+        let text = EventQuery { text: Some("threat 7".to_string()), oldest_first: true, ..Default::default() };
+        let result = query_dir(temp_dir.path(), &text).unwrap();
+        assert_eq!(result.events.len(), 1);
+    }
+}
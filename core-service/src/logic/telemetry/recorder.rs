@@ -2,23 +2,24 @@
 //!
 //! Append-only JSONL writer for security events.
 //! Thread-safe, persistent, and crash-resistant.
+//! Rotation / compression / retention policy: see `retention.rs`.
+//! Hash chain + signed checkpoints (tamper evidence): see `integrity.rs`.
 
 use std::fs::{File, OpenOptions};
 use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use parking_lot::Mutex;
-use chrono::{Utc, Datelike, Timelike};
+use chrono::{DateTime, Utc, Datelike, Timelike};
 
 use super::event::SecurityEvent;
+use super::integrity::Chain;
+use super::retention;
 
 // ============================================================================
 // CONSTANTS
 // ============================================================================
 
-/// Maximum file size before rotation (50 MB)
-const MAX_FILE_SIZE: u64 = 50 * 1024 * 1024;
-
 /// Default log directory name
 const LOG_DIR: &str = "security_logs";
 
@@ -44,20 +45,26 @@ pub struct Recorder {
     writer: BufWriter<File>,
     current_file: PathBuf,
     current_size: u64,
+    opened_at: DateTime<Utc>,
     base_dir: PathBuf,
+    chain: Chain,
 }
 
 impl Recorder {
     /// Create a new recorder in the given directory
     pub fn new(base_dir: PathBuf) -> std::io::Result<Self> {
         std::fs::create_dir_all(&base_dir)?;
+        // Nối tiếp chain của session trước (trước khi mở file mới)
+        let chain = Chain::resume(super::integrity::signing_key(), &base_dir);
         let (file_path, file) = Self::open_new_file(&base_dir)?;
 
         Ok(Self {
             writer: BufWriter::new(file),
             current_file: file_path,
             current_size: 0,
+            opened_at: Utc::now(),
             base_dir,
+            chain,
         })
     }
 
@@ -88,17 +95,23 @@ impl Recorder {
     /// Record a security event
     pub fn record(&mut self, event: &SecurityEvent) -> std::io::Result<()> {
         let line = event.to_jsonl();
-        let bytes = line.as_bytes();
 
-        // Check if rotation needed
-        if self.current_size + bytes.len() as u64 > MAX_FILE_SIZE {
+        // Check if rotation needed (size or age)
+        let config = retention::get_config();
+        let too_big = self.current_size > 0
+            && self.current_size + line.len() as u64 > config.max_file_bytes();
+        let too_old = config.rotate_interval()
+            .map_or(false, |interval| Utc::now() - self.opened_at >= interval);
+        if too_big || too_old {
             self.rotate()?;
         }
 
-        // Write line + newline
-        self.writer.write_all(bytes)?;
-        self.writer.write_all(b"\n")?;
-        self.current_size += bytes.len() as u64 + 1;
+        // Write line + newline (chain link appended after rotation so it lands in the new file)
+        let line = self.chain.link(&line);
+        self.write_line(&line)?;
+        if self.chain.checkpoint_due() {
+            self.write_checkpoint()?;
+        }
 
         // Flush for durability
         self.writer.flush()?;
@@ -107,8 +120,25 @@ impl Recorder {
         Ok(())
     }
 
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.current_size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    /// Signed checkpoint of the chain head (no-op if nothing new since last one)
+    fn write_checkpoint(&mut self) -> std::io::Result<()> {
+        if let Some(line) = self.chain.checkpoint() {
+            self.write_line(&line)?;
+        }
+        Ok(())
+    }
+
     /// Rotate to a new file
     fn rotate(&mut self) -> std::io::Result<()> {
+        // Close the old file with a checkpoint
+        self.write_checkpoint()?;
         self.writer.flush()?;
 
         let (new_path, new_file) = Self::open_new_file(&self.base_dir)?;
@@ -117,6 +147,10 @@ impl Recorder {
         log::info!("Rotated from {:?} to {:?}", self.current_file, new_path);
         self.current_file = new_path;
         self.current_size = 0;
+        self.opened_at = Utc::now();
+
+        // Nén file cũ + áp retention (thread riêng, không giữ RECORDER lock)
+        retention::schedule_cleanup();
 
         Ok(())
     }
@@ -138,25 +172,37 @@ impl Recorder {
 
 /// Initialize the global recorder
 pub fn init(base_dir: Option<PathBuf>) -> std::io::Result<()> {
-    let dir = base_dir.unwrap_or_else(|| {
-        // Default: app data directory
-        dirs::data_local_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("ai-security")
-            .join(LOG_DIR)
-    });
-
-    let recorder = Recorder::new(dir)?;
+    let dir = base_dir.unwrap_or_else(default_log_dir);
+
+    let recorder = Recorder::new(dir.clone())?;
+    // Trước record đầu tiên của session: seq = record cuối của session trước
+    super::integrity::check_signing_key(recorder.chain.seq());
     *RECORDER.lock() = Some(recorder);
 
+    // File của session trước → nén + áp retention
+    retention::set_log_dir(&dir);
+    retention::schedule_cleanup();
+
     // Record system start
     record(SecurityEvent::system_start(env!("CARGO_PKG_VERSION")));
 
     Ok(())
 }
 
+/// Default log directory (app data directory)
+pub fn default_log_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ai-security")
+        .join(LOG_DIR)
+}
+
 /// Record a security event (global function)
 pub fn record(event: SecurityEvent) {
+    super::syslog::publish(&event);
+    super::elastic::publish(&event);
+    crate::logic::cloud_sync::telemetry::publish(&event);
+
     let mut guard = RECORDER.lock();
     if let Some(recorder) = guard.as_mut() {
         if let Err(e) = recorder.record(&event) {
@@ -178,12 +224,25 @@ pub fn current_log_file() -> Option<PathBuf> {
     RECORDER.lock().as_ref().map(|r| r.current_file().clone())
 }
 
+/// Get log directory of the running recorder
+pub fn log_dir() -> Option<PathBuf> {
+    RECORDER.lock().as_ref().map(|r| r.base_dir.clone())
+}
+
+/// Current file + chain head (seq, hash) - for integrity verification
+pub fn chain_head() -> Option<(PathBuf, u64, String)> {
+    RECORDER.lock().as_ref().map(|r| {
+        (r.current_file.clone(), r.chain.seq(), r.chain.head().to_string())
+    })
+}
+
 /// Flush and close the recorder
 pub fn shutdown() {
     let mut guard = RECORDER.lock();
     if let Some(mut recorder) = guard.take() {
         let uptime = 0; // TODO: Calculate actual uptime
         let _ = recorder.record(&SecurityEvent::system_stop(uptime));
+        let _ = recorder.write_checkpoint();
         let _ = recorder.writer.flush();
         log::info!("Security recorder shutdown. Total events: {}", events_recorded());
     }
@@ -193,12 +252,22 @@ pub fn shutdown() {
 // QUERY API (for reading logs)
 // ============================================================================
 
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
 
-/// Read all events from a log file
-pub fn read_events(file_path: &PathBuf) -> std::io::Result<Vec<SecurityEvent>> {
+/// Open a log file for line reading (`.jsonl` or rotated `.jsonl.zst`)
+pub fn open_log_reader(file_path: &Path) -> std::io::Result<BufReader<Box<dyn Read>>> {
     let file = File::open(file_path)?;
-    let reader = BufReader::new(file);
+    let source: Box<dyn Read> = if retention::is_compressed(file_path) {
+        Box::new(zstd::stream::read::Decoder::new(file)?)
+    } else {
+        Box::new(file)
+    };
+    Ok(BufReader::new(source))
+}
+
+/// Read all events from a log file (checkpoint lines are skipped)
+pub fn read_events(file_path: &PathBuf) -> std::io::Result<Vec<SecurityEvent>> {
+    let reader = open_log_reader(file_path)?;
     let mut events = Vec::new();
 
     for line in reader.lines() {
@@ -231,7 +300,7 @@ pub fn find_overrides(file_path: &PathBuf) -> std::io::Result<Vec<SecurityEvent>
     Ok(events.into_iter().filter(|e| e.is_override()).collect())
 }
 
-/// Get list of all log files in directory
+/// Get list of all log files in directory (including compressed ones)
 pub fn list_log_files(dir: &PathBuf) -> std::io::Result<Vec<PathBuf>> {
     let mut files = Vec::new();
 
@@ -239,7 +308,8 @@ pub fn list_log_files(dir: &PathBuf) -> std::io::Result<Vec<PathBuf>> {
         for entry in std::fs::read_dir(dir)? {
             let entry = entry?;
             let path = entry.path();
-            if path.extension().map_or(false, |e| e == "jsonl") {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            if name.ends_with(LOG_EXT) || name.ends_with(&format!("{}.{}", LOG_EXT, retention::COMPRESSED_EXT)) {
                 files.push(path);
             }
         }
@@ -260,6 +330,7 @@ pub struct RecorderStats {
     pub events_recorded: u64,
     pub current_file: Option<String>,
     pub session_id: String,
+    pub retention: retention::RetentionStats,
 }
 
 /// Get recorder statistics
@@ -268,6 +339,7 @@ pub fn stats() -> RecorderStats {
         events_recorded: events_recorded(),
         current_file: current_log_file().map(|p| p.to_string_lossy().to_string()),
         session_id: super::event::get_session_id(),
+        retention: retention::get_stats(),
     }
 }
 
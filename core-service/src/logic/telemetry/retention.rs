@@ -0,0 +1,378 @@
+//! Log Retention - Rotation policy, nén zstd và dọn log cũ cho security logs
+//!
+//! - Rotation: recorder mở file mới khi vượt `max_file_mb` hoặc file đã mở quá
+//!   `rotate_interval_hours`
+//! - File đã rotate → nén `<name>.jsonl.zst` (xóa bản gốc sau khi nén xong);
+//!   `recorder::read_events` đọc được cả hai dạng
+//! - Retention: xóa file cũ hơn `retention_days`, sau đó xóa file cũ nhất tới khi tổng
+//!   dung lượng thư mục <= `max_total_mb`. File đang ghi không bao giờ bị động tới
+//!
+//! Cleanup chạy nền lúc init và sau mỗi lần rotate. Config local (`log_retention.json`).
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CONFIG_FILE: &str = "log_retention.json";
+/// Extension của file đã nén (sau `.jsonl`)
+pub const COMPRESSED_EXT: &str = "zst";
+const MB: u64 = 1024 * 1024;
+
+// ============================================================================
+// CONFIG / STATS
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// Rotate khi file hiện tại vượt kích thước này
+    pub max_file_mb: u64,
+    /// Rotate theo thời gian (0 = chỉ theo kích thước)
+    pub rotate_interval_hours: u64,
+    /// Nén zstd file đã rotate
+    pub compress: bool,
+    /// zstd level 1..=19
+    pub compression_level: i32,
+    /// Xóa file cũ hơn N ngày (0 = không giới hạn)
+    pub retention_days: u64,
+    /// Tổng dung lượng tối đa của thư mục log (0 = không giới hạn)
+    pub max_total_mb: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_file_mb: 50,
+            rotate_interval_hours: 24,
+            compress: true,
+            compression_level: 3,
+            retention_days: 30,
+            max_total_mb: 1024,
+        }
+    }
+}
+
+impl RetentionConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=1024).contains(&self.max_file_mb) {
+            return Err("max_file_mb must be between 1 and 1024".to_string());
+        }
+        if !(1..=19).contains(&self.compression_level) {
+            return Err("compression_level must be between 1 and 19".to_string());
+        }
+        if self.max_total_mb != 0 && self.max_total_mb < self.max_file_mb {
+            return Err("max_total_mb must be at least max_file_mb".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn max_file_bytes(&self) -> u64 {
+        self.max_file_mb * MB
+    }
+
+    pub fn rotate_interval(&self) -> Option<chrono::Duration> {
+        (self.rotate_interval_hours > 0).then(|| chrono::Duration::hours(self.rotate_interval_hours as i64))
+    }
+}
+
+/// Thống kê cleanup (cộng dồn trong session) + dung lượng hiện tại
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetentionStats {
+    pub files_compressed: u64,
+    pub bytes_before_compression: u64,
+    pub bytes_after_compression: u64,
+    pub files_deleted: u64,
+    pub bytes_deleted: u64,
+    pub last_cleanup_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// Số file log trên disk (gồm file đang ghi)
+    pub disk_files: u64,
+    pub disk_bytes: u64,
+}
+
+/// Kết quả của một lần cleanup
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CleanupResult {
+    pub compressed: u64,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    pub deleted: u64,
+    pub bytes_deleted: u64,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static CONFIG: Lazy<RwLock<RetentionConfig>> = Lazy::new(|| RwLock::new(load_config()));
+static STATS: Lazy<Mutex<RetentionStats>> = Lazy::new(|| Mutex::new(RetentionStats::default()));
+/// Thư mục log (set bởi `recorder::init`)
+static LOG_DIR: Lazy<RwLock<Option<PathBuf>>> = Lazy::new(|| RwLock::new(None));
+/// Một cleanup tại một thời điểm
+static CLEANUP_LOCK: Mutex<()> = Mutex::new(());
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+pub fn get_config() -> RetentionConfig {
+    CONFIG.read().clone()
+}
+
+pub fn set_config(config: RetentionConfig) -> Result<(), String> {
+    config.validate()?;
+    *CONFIG.write() = config;
+    save_config();
+    schedule_cleanup();
+    Ok(())
+}
+
+pub fn get_stats() -> RetentionStats {
+    let mut stats = STATS.lock().clone();
+    if let Some(dir) = LOG_DIR.read().clone() {
+        for (_, size, _) in log_files(&dir) {
+            stats.disk_files += 1;
+            stats.disk_bytes += size;
+        }
+    }
+    stats
+}
+
+/// Gọi từ recorder lúc init
+pub(super) fn set_log_dir(dir: &Path) {
+    *LOG_DIR.write() = Some(dir.to_path_buf());
+}
+
+/// Chạy cleanup nền (nén file đã rotate + áp retention)
+pub fn schedule_cleanup() {
+    if LOG_DIR.read().is_none() {
+        return;
+    }
+    std::thread::spawn(|| {
+        if let Err(e) = run_cleanup() {
+            log::warn!("Telemetry log cleanup failed: {}", e);
+        }
+    });
+}
+
+/// Cleanup đồng bộ (command `run_telemetry_cleanup`)
+pub fn run_cleanup() -> Result<RetentionStats, String> {
+    let Some(dir) = LOG_DIR.read().clone() else {
+        return Err("Telemetry recorder not initialized".to_string());
+    };
+    let _guard = CLEANUP_LOCK.lock();
+    let current = super::recorder::current_log_file();
+    let config = get_config();
+
+    match cleanup(&dir, current.as_deref(), &config, SystemTime::now()) {
+        Ok(result) => {
+            if result.compressed > 0 || result.deleted > 0 {
+                log::info!(
+                    "Telemetry cleanup: compressed {} file(s) ({} → {} bytes), deleted {} file(s) ({} bytes)",
+                    result.compressed, result.bytes_before, result.bytes_after,
+                    result.deleted, result.bytes_deleted
+                );
+            }
+            let mut stats = STATS.lock();
+            stats.files_compressed += result.compressed;
+            stats.bytes_before_compression += result.bytes_before;
+            stats.bytes_after_compression += result.bytes_after;
+            stats.files_deleted += result.deleted;
+            stats.bytes_deleted += result.bytes_deleted;
+            stats.last_cleanup_at = Some(Utc::now());
+            stats.last_error = None;
+        }
+        Err(e) => {
+            STATS.lock().last_error = Some(e.to_string());
+            return Err(e.to_string());
+        }
+    }
+    Ok(get_stats())
+}
+
+// ============================================================================
+// CLEANUP
+// ============================================================================
+
+/// Xóa file quá hạn, nén file `.jsonl` đã rotate rồi áp budget tổng dung lượng
+fn cleanup(
+    dir: &Path,
+    current: Option<&Path>,
+    config: &RetentionConfig,
+    now: SystemTime,
+) -> std::io::Result<CleanupResult> {
+    let mut result = CleanupResult::default();
+    let is_current = |path: &Path| current.map_or(false, |c| c == path);
+
+    // Tuổi tính theo mtime (file nén giữ mtime của bản gốc)
+    if config.retention_days > 0 {
+        let max_age = Duration::from_secs(config.retention_days * 24 * 3600);
+        for (path, size, modified) in log_files(dir) {
+            let expired = now.duration_since(modified).map_or(false, |age| age > max_age);
+            if expired && !is_current(&path) && fs::remove_file(&path).is_ok() {
+                result.deleted += 1;
+                result.bytes_deleted += size;
+            }
+        }
+    }
+
+    if config.compress {
+        for (path, size, _) in log_files(dir) {
+            if is_current(&path) || is_compressed(&path) {
+                continue;
+            }
+            let compressed_size = compress_file(&path, config.compression_level)?;
+            result.compressed += 1;
+            result.bytes_before += size;
+            result.bytes_after += compressed_size;
+        }
+    }
+
+    // Cũ nhất trước (tên file chứa timestamp)
+    let files = log_files(dir);
+    if config.max_total_mb > 0 {
+        let budget = config.max_total_mb * MB;
+        let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+        for (path, size, _) in &files {
+            if total <= budget {
+                break;
+            }
+            if is_current(path) {
+                continue;
+            }
+            if fs::remove_file(path).is_ok() {
+                total -= size;
+                result.deleted += 1;
+                result.bytes_deleted += size;
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// `x.jsonl` → `x.jsonl.zst`, trả về kích thước sau nén
+fn compress_file(path: &Path, level: i32) -> std::io::Result<u64> {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(COMPRESSED_EXT);
+    let dest = PathBuf::from(name);
+    let tmp = dest.with_extension(format!("{}.tmp", COMPRESSED_EXT));
+
+    let modified = fs::metadata(path)?.modified()?;
+    {
+        let input = BufReader::new(File::open(path)?);
+        let mut output = BufWriter::new(File::create(&tmp)?);
+        zstd::stream::copy_encode(input, &mut output, level)?;
+        output.flush()?;
+        output.get_ref().set_modified(modified)?;
+    }
+    fs::rename(&tmp, &dest)?;
+    fs::remove_file(path)?;
+    Ok(fs::metadata(&dest)?.len())
+}
+
+pub(super) fn is_compressed(path: &Path) -> bool {
+    path.extension().map_or(false, |e| e == COMPRESSED_EXT)
+}
+
+/// File log trong thư mục (path, size, modified), sort theo tên
+fn log_files(dir: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+    let mut files: Vec<(PathBuf, u64, SystemTime)> = super::recorder::list_log_files(&dir.to_path_buf())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|path| {
+            let meta = fs::metadata(&path).ok()?;
+            Some((path, meta.len(), meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)))
+        })
+        .collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    files
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn config_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(CONFIG_FILE)
+}
+
+fn load_config() -> RetentionConfig {
+    fs::read_to_string(config_path())
+        .ok()
+        .and_then(|c| serde_json::from_str::<RetentionConfig>(&c).ok())
+        .filter(|c| c.validate().is_ok())
+        .unwrap_or_default()
+}
+
+fn save_config() {
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*CONFIG.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::event::{EventType, SecurityEvent};
+    use tempfile::TempDir;
+
+    fn write_log(dir: &Path, name: &str, events: usize, age_days: u64) -> PathBuf {
+        let path = dir.join(name);
+        let mut content = String::new();
+        for i in 0..events {
+            content.push_str(&SecurityEvent::new(EventType::ThreatDetected, &format!("Threat {}", i)).to_jsonl());
+            content.push('\n');
+        }
+        fs::write(&path, content).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age_days * 24 * 3600);
+        File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_compress_and_retention() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let expired = write_log(dir, "security_2026_01_01_000000.jsonl", 10, 40);
+        let rotated = write_log(dir, "security_2026_02_01_000000.jsonl", 200, 1);
+        let current = write_log(dir, "security_2026_02_02_000000.jsonl", 5, 0);
+
+        let config = RetentionConfig { max_total_mb: 0, ..Default::default() };
+        let result = cleanup(dir, Some(&current), &config, SystemTime::now()).unwrap();
+
+        assert_eq!(result.compressed, 1);
+        assert!(result.bytes_after < result.bytes_before);
+        assert_eq!(result.deleted, 1);
+        assert!(!expired.exists());
+        assert!(!rotated.exists());
+        assert!(current.exists());
+
+        // File nén đọc lại được
+        let compressed = dir.join("security_2026_02_01_000000.jsonl.zst");
+        let events = super::super::recorder::read_events(&compressed).unwrap();
+        assert_eq!(events.len(), 200);
+        assert_eq!(super::super::recorder::list_log_files(&dir.to_path_buf()).unwrap().len(), 2);
+    }
+}
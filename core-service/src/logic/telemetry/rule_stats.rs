@@ -0,0 +1,279 @@
+//! Rule Tuning Statistics
+//!
+//! Thống kê per-rule để tune behavioral rules:
+//! - Match rate (matches / ngày kể từ lần match đầu)
+//! - False-positive feedback từ analyst
+//! - Severity trung bình của incident mà rule tạo ra
+//!
+//! Lưu cạnh security logs (`rule_stats.json`), feedback còn được ghi
+//! thành `RuleFeedback` event trong telemetry.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::event::SecurityEvent;
+use crate::logic::incident::Severity;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const STATS_FILE: &str = "rule_stats.json";
+
+/// Cửa sổ tối thiểu khi tính match rate (tránh rule mới match 1 lần bị coi là ồn)
+const MIN_RATE_WINDOW_SECS: i64 = 3600;
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static STORE: Lazy<RwLock<RuleStatsStore>> = Lazy::new(|| RwLock::new(load()));
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// Counters lưu trên disk
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuleCounters {
+    pub matches: u64,
+    pub first_match_at: Option<i64>,
+    pub last_match_at: Option<i64>,
+    pub incidents: u64,
+    /// Tổng severity incident (Low=1 .. Critical=4)
+    pub incident_severity_sum: u64,
+    pub false_positives: u64,
+    pub true_positives: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuleStatsStore {
+    #[serde(default)]
+    pub rules: HashMap<String, RuleCounters>,
+}
+
+/// Thống kê tune rule (trả về UI / get_stats)
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleTuningStats {
+    pub rule_id: String,
+    pub matches: u64,
+    pub matches_per_day: f64,
+    pub incidents: u64,
+    /// 1.0 (Low) .. 4.0 (Critical), None nếu chưa tạo incident
+    pub mean_incident_severity: Option<f64>,
+    pub false_positives: u64,
+    pub true_positives: u64,
+    /// FP / (FP + TP), None nếu chưa có feedback
+    pub false_positive_rate: Option<f64>,
+    /// Càng cao càng ồn: match nhiều, FP nhiều, severity thấp
+    pub noise_score: f64,
+    pub last_match_at: Option<i64>,
+}
+
+fn severity_weight(severity: &Severity) -> u64 {
+    match severity {
+        Severity::Low => 1,
+        Severity::Medium => 2,
+        Severity::High => 3,
+        Severity::Critical => 4,
+    }
+}
+
+// ============================================================================
+// STORE
+// ============================================================================
+
+impl RuleStatsStore {
+    pub fn record_match(&mut self, rule_id: &str, now: i64) {
+        let c = self.rules.entry(rule_id.to_string()).or_default();
+        c.matches += 1;
+        c.first_match_at.get_or_insert(now);
+        c.last_match_at = Some(now);
+    }
+
+    pub fn record_incident(&mut self, rule_id: &str, severity: &Severity) {
+        let c = self.rules.entry(rule_id.to_string()).or_default();
+        c.incidents += 1;
+        c.incident_severity_sum += severity_weight(severity);
+    }
+
+    pub fn record_feedback(&mut self, rule_id: &str, false_positive: bool) {
+        let c = self.rules.entry(rule_id.to_string()).or_default();
+        if false_positive {
+            c.false_positives += 1;
+        } else {
+            c.true_positives += 1;
+        }
+    }
+
+    pub fn tuning_stats(&self, now: i64) -> Vec<RuleTuningStats> {
+        self.rules
+            .iter()
+            .map(|(rule_id, c)| compute(rule_id, c, now))
+            .collect()
+    }
+
+    /// Rules ồn nhất trước (noise_score giảm dần)
+    pub fn noisiest(&self, now: i64, limit: usize) -> Vec<RuleTuningStats> {
+        let mut stats: Vec<_> = self
+            .tuning_stats(now)
+            .into_iter()
+            .filter(|s| s.noise_score > 0.0)
+            .collect();
+        stats.sort_by(|a, b| b.noise_score.total_cmp(&a.noise_score).then_with(|| a.rule_id.cmp(&b.rule_id)));
+        stats.truncate(limit);
+        stats
+    }
+}
+
+fn compute(rule_id: &str, c: &RuleCounters, now: i64) -> RuleTuningStats {
+    let window_secs = c
+        .first_match_at
+        .map(|first| (now - first).max(MIN_RATE_WINDOW_SECS))
+        .unwrap_or(MIN_RATE_WINDOW_SECS);
+    let matches_per_day = c.matches as f64 * 86_400.0 / window_secs as f64;
+
+    let mean_incident_severity = (c.incidents > 0)
+        .then(|| c.incident_severity_sum as f64 / c.incidents as f64);
+
+    let feedback = c.false_positives + c.true_positives;
+    let false_positive_rate = (feedback > 0)
+        .then(|| c.false_positives as f64 / feedback as f64);
+
+    // FP xác nhận nhân tối đa 5x, rule chỉ tạo incident nhẹ bị coi là ồn hơn
+    let noise_score = matches_per_day
+        * (1.0 + 4.0 * false_positive_rate.unwrap_or(0.0))
+        / mean_incident_severity.unwrap_or(1.0);
+
+    RuleTuningStats {
+        rule_id: rule_id.to_string(),
+        matches: c.matches,
+        matches_per_day,
+        incidents: c.incidents,
+        mean_incident_severity,
+        false_positives: c.false_positives,
+        true_positives: c.true_positives,
+        false_positive_rate,
+        noise_score,
+        last_match_at: c.last_match_at,
+    }
+}
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Rule match được báo (sau dedup per process)
+pub fn record_match(rule_id: &str) {
+    STORE.write().record_match(rule_id, Utc::now().timestamp());
+    save();
+}
+
+/// Rule match tạo incident
+pub fn record_incident(rule_id: &str, severity: &Severity) {
+    STORE.write().record_incident(rule_id, severity);
+    save();
+}
+
+/// Analyst feedback (true/false positive)
+pub fn record_feedback(rule_id: &str, false_positive: bool, note: Option<&str>) {
+    STORE.write().record_feedback(rule_id, false_positive);
+    save();
+    super::record(SecurityEvent::rule_feedback(rule_id, false_positive, note));
+}
+
+/// Stats của tất cả rules đã từng match / có feedback
+pub fn get_all() -> Vec<RuleTuningStats> {
+    let mut stats = STORE.read().tuning_stats(Utc::now().timestamp());
+    stats.sort_by(|a, b| a.rule_id.cmp(&b.rule_id));
+    stats
+}
+
+/// Top rules ồn nhất
+pub fn get_noisiest(limit: usize) -> Vec<RuleTuningStats> {
+    STORE.read().noisiest(Utc::now().timestamp(), limit)
+}
+
+/// Xóa stats của rule (sau khi đã tune lại)
+pub fn reset(rule_id: &str) -> bool {
+    let removed = STORE.write().rules.remove(rule_id).is_some();
+    if removed {
+        save();
+    }
+    removed
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn stats_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ai-security")
+        .join(STATS_FILE)
+}
+
+fn load() -> RuleStatsStore {
+    fs::read_to_string(stats_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save() {
+    let path = stats_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*STORE.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noisiest_ranking() {
+        let mut store = RuleStatsStore::default();
+        let now = 1_700_000_000;
+        let day_ago = now - 86_400;
+
+        // NOISY: 10 match/ngày, một nửa là FP, incident Low
+        for _ in 0..10 {
+            store.record_match("NOISY", day_ago);
+        }
+        store.record_incident("NOISY", &Severity::Low);
+        store.record_feedback("NOISY", true);
+        store.record_feedback("NOISY", false);
+
+        // QUIET: 10 match/ngày, incident Critical, không FP
+        for _ in 0..10 {
+            store.record_match("QUIET", day_ago);
+        }
+        store.record_incident("QUIET", &Severity::Critical);
+        store.record_feedback("QUIET", false);
+
+        let ranked = store.noisiest(now, 10);
+        assert_eq!(ranked[0].rule_id, "NOISY");
+        assert_eq!(ranked[0].false_positive_rate, Some(0.5));
+        assert_eq!(ranked[0].mean_incident_severity, Some(1.0));
+        assert!((ranked[0].matches_per_day - 10.0).abs() < 1e-9);
+        assert_eq!(ranked[1].rule_id, "QUIET");
+
+        // Chỉ có feedback, chưa match → không lọt vào danh sách ồn
+        store.record_feedback("NEVER_MATCHED", true);
+        assert_eq!(store.noisiest(now, 10).len(), 2);
+    }
+}
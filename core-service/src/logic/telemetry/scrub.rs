@@ -0,0 +1,400 @@
+//! PII Scrubbing - Ẩn danh username / hostname / path trong profile user khi export
+//!
+//! Dữ liệu export (dataset training, file event, telemetry upload lên cloud) thường được
+//! chia sẻ cho team ML → không được lộ danh tính:
+//! - Hostname máy và username hiện tại (so khớp nguyên từ, không phân biệt hoa thường)
+//!   → `host_<hex>` / `user_<hex>`
+//! - Path trong profile user (`C:\Users\<name>\...`, `/home/<name>/...`, `/Users/<name>/...`):
+//!   tên user → `user_<hex>`, thư mục / file cá nhân → `h_<hex>` (giữ extension). Thư mục
+//!   chuẩn (AppData, Temp, Downloads, ...) giữ nguyên vì có giá trị cho model
+//!
+//! Token = HMAC-SHA256(salt, "<kind>|<giá trị lowercase>") → cùng giá trị luôn ra cùng
+//! token, khác máy ra khác token. Salt (`scrub_salt.key`) và bảng token → giá trị gốc
+//! (`scrub_map.json`) chỉ nằm local → chủ máy tra ngược được (`reveal`), người nhận export thì không.
+//!
+//! Áp dụng cho: file export (dataset, event, training data) và telemetry upload lên cloud.
+//! Syslog / Elasticsearch / Event Log là hạ tầng của chính tổ chức → giữ nguyên.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use super::event::SecurityEvent;
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CONFIG_FILE: &str = "scrub_config.json";
+const SALT_FILE: &str = "scrub_salt.key";
+const MAP_FILE: &str = "scrub_map.json";
+/// Độ dài hex của token (48 bit)
+const TOKEN_HEX_LEN: usize = 12;
+/// Tên quá ngắn (vd "pc", "a") dễ trùng từ thường → không thay
+const MIN_IDENTITY_LEN: usize = 3;
+
+/// Thư mục chuẩn trong profile - không phải dữ liệu cá nhân
+const KNOWN_FOLDERS: &[&str] = &[
+    "appdata", "local", "locallow", "roaming", "temp", "tmp", "desktop", "documents",
+    "downloads", "pictures", "music", "videos", "favorites", "contacts", "onedrive",
+    "microsoft", "windows", "programs", "startup", "start menu", "packages", "inetcache",
+    "recent", ".cache", ".config", ".local", ".ssh", "share", "bin", "library", "application support",
+];
+/// Profile hệ thống - không phải user cụ thể
+const SYSTEM_PROFILES: &[&str] = &["public", "default", "default user", "all users"];
+
+/// Path trong profile: prefix, tên user, phần còn lại (dừng ở dấu ngoặc / xuống dòng).
+/// `rest` cho phép khoảng trắng trong tên thư mục; phần sau khoảng trắng của component cuối
+/// được tách lại trong `split_path_tail` (thường là text mô tả, không thuộc path)
+static PROFILE_PATH_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)(?P<root>\b[a-z]:[\\/]+(?:users|documents and settings)[\\/]+|/home/|/Users/)(?P<user>[^\\/\s"'<>|:;,]+)(?P<rest>(?:[\\/]+[^\\/\s"'<>|:;,]+(?: [^\\/\s"'<>|:;,]+)*)*)"#)
+        .expect("valid profile path regex")
+});
+
+// ============================================================================
+// CONFIG
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScrubConfig {
+    /// File export: dataset, security events, training data
+    pub file_exports: bool,
+    /// Telemetry upload lên cloud console
+    pub cloud_upload: bool,
+}
+
+impl Default for ScrubConfig {
+    fn default() -> Self {
+        Self {
+            file_exports: true,
+            cloud_upload: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrubTarget {
+    FileExport,
+    CloudUpload,
+}
+
+// ============================================================================
+// SCRUBBER
+// ============================================================================
+
+/// Bộ thay thế với salt + danh tính local (tách khỏi global để test)
+pub(crate) struct Scrubber {
+    salt: Vec<u8>,
+    /// (kind, regex nguyên từ) cho hostname / username
+    identities: Vec<(&'static str, Regex)>,
+}
+
+impl Scrubber {
+    pub(crate) fn new(salt: Vec<u8>, hostnames: &[String], usernames: &[String]) -> Self {
+        let mut identities = Vec::new();
+        let mut add = |kind: &'static str, value: &String| {
+            let value = value.trim();
+            if value.chars().count() < MIN_IDENTITY_LEN {
+                return;
+            }
+            if let Ok(re) = Regex::new(&format!(r"(?i)\b{}\b", regex::escape(value))) {
+                identities.push((kind, re));
+            }
+        };
+        hostnames.iter().for_each(|h| add("host", h));
+        usernames.iter().for_each(|u| add("user", u));
+        Self { salt, identities }
+    }
+
+    /// Thay danh tính trong `text`, ghi token mới vào `map` (token → giá trị gốc)
+    pub(crate) fn scrub(&self, text: &str, map: &mut HashMap<String, String>) -> String {
+        let mut out = PROFILE_PATH_RE.replace_all(text, |caps: &Captures| {
+            let user = &caps["user"];
+            let user = if SYSTEM_PROFILES.contains(&user.to_lowercase().as_str()) {
+                user.to_string()
+            } else {
+                self.token("user", user, map)
+            };
+            let (path, tail) = split_path_tail(&caps["rest"]);
+            format!("{}{}{}{}", &caps["root"], user, self.scrub_rest(path, map), tail)
+        }).into_owned();
+
+        for (kind, re) in &self.identities {
+            if re.is_match(&out) {
+                out = re.replace_all(&out, |caps: &Captures| self.token(kind, &caps[0], map)).into_owned();
+            }
+        }
+        out
+    }
+
+    /// Phần path sau tên user: giữ thư mục chuẩn + extension, hash phần còn lại
+    fn scrub_rest(&self, rest: &str, map: &mut HashMap<String, String>) -> String {
+        let mut out = String::with_capacity(rest.len());
+        let mut component = String::new();
+        let flush = |component: &mut String, out: &mut String, map: &mut HashMap<String, String>| {
+            if component.is_empty() {
+                return;
+            }
+            if KNOWN_FOLDERS.contains(&component.to_lowercase().as_str()) {
+                out.push_str(component);
+            } else {
+                match component.rsplit_once('.') {
+                    Some((stem, ext)) if !stem.is_empty() && (1..=5).contains(&ext.len()) => {
+                        out.push_str(&self.token("h", stem, map));
+                        out.push('.');
+                        out.push_str(ext);
+                    }
+                    _ => out.push_str(&self.token("h", component, map)),
+                }
+            }
+            component.clear();
+        };
+        for c in rest.chars() {
+            if c == '\\' || c == '/' {
+                flush(&mut component, &mut out, map);
+                out.push(c);
+            } else {
+                component.push(c);
+            }
+        }
+        flush(&mut component, &mut out, map);
+        out
+    }
+
+    fn token(&self, kind: &str, value: &str, map: &mut HashMap<String, String>) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.salt).expect("HMAC can take key of any size");
+        mac.update(format!("{}|{}", kind, value.to_lowercase()).as_bytes());
+        let digest = hex::encode(mac.finalize().into_bytes());
+        let token = format!("{}_{}", kind, &digest[..TOKEN_HEX_LEN]);
+        map.entry(token.clone()).or_insert_with(|| value.to_string());
+        token
+    }
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static CONFIG: Lazy<RwLock<ScrubConfig>> = Lazy::new(|| RwLock::new(load_config()));
+static SCRUBBER: Lazy<Scrubber> = Lazy::new(|| {
+    Scrubber::new(load_or_create_salt(), &[super::exporter::local_hostname()], &local_usernames())
+});
+/// token → giá trị gốc (persist vào `scrub_map.json`)
+static MAP: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(load_map()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+pub fn get_config() -> ScrubConfig {
+    CONFIG.read().clone()
+}
+
+pub fn set_config(config: ScrubConfig) -> Result<(), String> {
+    *CONFIG.write() = config;
+    save_json(CONFIG_FILE, &*CONFIG.read());
+    Ok(())
+}
+
+/// Có scrub cho đích export này không
+pub fn enabled(target: ScrubTarget) -> bool {
+    let config = CONFIG.read();
+    match target {
+        ScrubTarget::FileExport => config.file_exports,
+        ScrubTarget::CloudUpload => config.cloud_upload,
+    }
+}
+
+/// Ẩn danh 1 chuỗi
+pub fn scrub_text(text: &str) -> String {
+    let mut map = MAP.lock();
+    let before = map.len();
+    let out = SCRUBBER.scrub(text, &mut map);
+    if map.len() != before {
+        save_json(MAP_FILE, &*map);
+    }
+    out
+}
+
+/// Bản sao event với mọi field text đã ẩn danh
+pub fn scrub_event(event: &SecurityEvent) -> SecurityEvent {
+    let mut map = MAP.lock();
+    let before = map.len();
+    let scrubbed = scrub_event_with(&SCRUBBER, event, &mut map);
+    if map.len() != before {
+        save_json(MAP_FILE, &*map);
+    }
+    scrubbed
+}
+
+/// Tra ngược token → giá trị gốc (chỉ trên máy đã tạo token)
+pub fn reveal(token: &str) -> Option<String> {
+    MAP.lock().get(token.trim()).cloned()
+}
+
+// ============================================================================
+// IMPLEMENTATION
+// ============================================================================
+
+/// Tách phần text sau khoảng trắng đầu tiên của component cuối: `\a b\x.exe was blocked`
+/// → (`\a b\x.exe`, ` was blocked`)
+fn split_path_tail(rest: &str) -> (&str, &str) {
+    let last_sep = rest.rfind(['\\', '/']).map(|i| i + 1).unwrap_or(0);
+    match rest[last_sep..].find(' ') {
+        Some(i) => rest.split_at(last_sep + i),
+        None => (rest, ""),
+    }
+}
+
+pub(crate) fn scrub_event_with(scrubber: &Scrubber, event: &SecurityEvent, map: &mut HashMap<String, String>) -> SecurityEvent {
+    let mut event = event.clone();
+    event.description = scrubber.scrub(&event.description, map);
+    if let Some(process) = event.process.as_mut() {
+        process.path = process.path.as_deref().map(|p| scrubber.scrub(p, map));
+        process.command_line = process.command_line.as_deref().map(|c| scrubber.scrub(c, map));
+    }
+    if let Some(ai) = event.ai_context.as_mut() {
+        ai.tags = ai.tags.iter().map(|t| scrubber.scrub(t, map)).collect();
+        ai.reasons = ai.reasons.iter().map(|r| scrubber.scrub(r, map)).collect();
+    }
+    if let Some(override_info) = event.user_override.as_mut() {
+        override_info.reason = override_info.reason.as_deref().map(|r| scrubber.scrub(r, map));
+    }
+    if let Some(metadata) = event.metadata.as_mut() {
+        scrub_json(scrubber, metadata, map);
+    }
+    event
+}
+
+fn scrub_json(scrubber: &Scrubber, value: &mut serde_json::Value, map: &mut HashMap<String, String>) {
+    match value {
+        serde_json::Value::String(s) => *s = scrubber.scrub(s, map),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|v| scrub_json(scrubber, v, map)),
+        serde_json::Value::Object(fields) => fields.values_mut().for_each(|v| scrub_json(scrubber, v, map)),
+        _ => {}
+    }
+}
+
+/// User đang đăng nhập (+ user của profile hiện tại nếu khác)
+fn local_usernames() -> Vec<String> {
+    let mut names: Vec<String> = ["USERNAME", "USER"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .collect();
+    if let Some(profile) = dirs::home_dir().and_then(|h| h.file_name().map(|n| n.to_string_lossy().to_string())) {
+        names.push(profile);
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn data_path(file: &str) -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(file)
+}
+
+fn load_config() -> ScrubConfig {
+    fs::read_to_string(data_path(CONFIG_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn load_map() -> HashMap<String, String> {
+    fs::read_to_string(data_path(MAP_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_json<T: Serialize>(file: &str, value: &T) {
+    let path = data_path(file);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(value) {
+        let _ = fs::write(path, json);
+    }
+}
+
+fn load_or_create_salt() -> Vec<u8> {
+    let path = data_path(SALT_FILE);
+    if let Some(salt) = fs::read_to_string(&path).ok().and_then(|k| hex::decode(k.trim()).ok()) {
+        if salt.len() == 32 {
+            return salt;
+        }
+    }
+
+    let mut salt = vec![0u8; 32];
+    rand::thread_rng().fill_bytes(&mut salt);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Err(e) = fs::write(&path, hex::encode(&salt)) {
+        log::warn!("Failed to persist scrub salt: {}", e);
+    }
+    salt
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::event::{EventType, ProcessInfo};
+
+    #[test]
+    fn test_scrub_identities_and_profile_paths() {
+        let scrubber = Scrubber::new(vec![7u8; 32], &["WORKSTATION-42".to_string()], &["alice".to_string()]);
+        let mut map = HashMap::new();
+
+        let path = r"C:\Users\Alice\Documents\Salary 2026\payroll.xlsx";
+        let out = scrubber.scrub(path, &mut map);
+        assert!(out.starts_with(r"C:\Users\user_"), "{}", out);
+        assert!(out.contains(r"\Documents\"));
+        assert!(out.ends_with(".xlsx"));
+        assert!(!out.to_lowercase().contains("alice") && !out.contains("payroll"));
+
+        // Cùng user ở chỗ khác → cùng token; tra ngược được
+        let text = scrubber.scrub("login by ALICE on workstation-42 from /home/alice/.ssh/id_rsa", &mut map);
+        let user_token = out.split('\\').nth(2).unwrap();
+        assert!(text.contains(user_token));
+        assert!(text.contains("host_") && !text.to_lowercase().contains("workstation"));
+        assert!(text.contains("/.ssh/h_"));
+        assert_eq!(map.get(user_token).map(|u| u.to_lowercase()), Some("alice".to_string()));
+
+        // Profile hệ thống + text không liên quan giữ nguyên
+        assert_eq!(scrubber.scrub(r"C:\Users\Public\Desktop", &mut map), r"C:\Users\Public\Desktop");
+        assert_eq!(scrubber.scrub("powershell.exe -enc AAAA", &mut map), "powershell.exe -enc AAAA");
+        assert!(scrubber.scrub("/home/alice/run.sh was blocked", &mut map).ends_with(".sh was blocked"));
+
+        // Salt khác → token khác
+        let other = Scrubber::new(vec![8u8; 32], &[], &[]);
+        assert_ne!(other.scrub(path, &mut HashMap::new()), out);
+
+        let mut event = SecurityEvent::new(EventType::ThreatDetected, r"Blocked C:\Users\alice\AppData\Local\Temp\x.exe");
+        event.process = Some(ProcessInfo::new(1, "x.exe").with_path(r"C:\Users\alice\AppData\Local\Temp\x.exe"));
+        event.metadata = Some(serde_json::json!({ "host": "WORKSTATION-42", "n": 3 }));
+        let scrubbed = scrub_event_with(&scrubber, &event, &mut map);
+        let json = serde_json::to_string(&scrubbed).unwrap().to_lowercase();
+        assert!(!json.contains("alice") && !json.contains("workstation"));
+        assert!(json.contains(r"\\appdata\\local\\temp\\"));
+    }
+}
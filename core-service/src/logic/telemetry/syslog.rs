@@ -0,0 +1,542 @@
+//! Syslog Exporter - Stream SecurityEvent tới syslog server (RFC 5424)
+//!
+//! Mục đích: Không dùng cloud console vẫn đưa được event vào log pipeline sẵn có
+//! (rsyslog, syslog-ng, Graylog, SIEM collector).
+//!
+//! - Transport: UDP (RFC 5426), TCP (RFC 6587 octet counting), TLS (RFC 5425)
+//! - PRI = facility (config) * 8 + severity (map từ `SecurityEvent`)
+//! - MSG = event theo `format` của sink: JSON (mặc định), CEF hoặc LEEF (`exporter`)
+//! - `publish()` không block recorder: event vào queue có giới hạn, worker thread
+//!   giữ connection, mất kết nối → reconnect với backoff (queue đầy → drop + đếm)
+//!
+//! Config local (`syslog.json`), đổi qua `set_config`.
+
+use std::fs;
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread;
+use std::time::Duration;
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::Lazy;
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::logic::cloud_sync::managed_settings;
+use crate::logic::policy::Severity;
+use super::event::{EventType, SecurityEvent};
+use super::exporter::{self, EventFormat};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const CONFIG_FILE: &str = "syslog.json";
+/// Event chờ gửi tối đa (server chậm / mất kết nối)
+const QUEUE_CAPACITY: usize = 10_000;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+const MIN_BACKOFF_SECS: u64 = 1;
+const MAX_BACKOFF_SECS: u64 = 60;
+/// APP-NAME (RFC 5424: tối đa 48 ký tự ASCII)
+const APP_NAME: &str = "OneShield";
+
+// ============================================================================
+// CONFIG
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogProtocol {
+    Udp,
+    Tcp,
+    Tls,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyslogConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub protocol: SyslogProtocol,
+    /// Tên facility: "kern", "user", "daemon", "auth", "authpriv", "security" (log audit),
+    /// "local0".."local7"
+    pub facility: String,
+    /// Định dạng MSG: json | cef | leef
+    pub format: EventFormat,
+    /// Chỉ gửi event có syslog severity <= giá trị này (0 emerg .. 7 debug)
+    pub max_severity: u8,
+    /// CA PEM để verify server (TLS), None = trust store của OS
+    pub tls_ca_file: Option<String>,
+    /// Bỏ verify certificate (chỉ dùng cho lab)
+    pub tls_accept_invalid_certs: bool,
+}
+
+impl Default for SyslogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: 514,
+            protocol: SyslogProtocol::Udp,
+            facility: "local4".to_string(),
+            format: EventFormat::Json,
+            max_severity: 6,
+            tls_ca_file: None,
+            tls_accept_invalid_certs: false,
+        }
+    }
+}
+
+impl SyslogConfig {
+    fn validate(&self) -> Result<(), String> {
+        facility_code(&self.facility)
+            .ok_or_else(|| format!("Unknown syslog facility '{}'", self.facility))?;
+        if self.max_severity > 7 {
+            return Err("Syslog max_severity must be between 0 and 7".to_string());
+        }
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.host.trim().is_empty() {
+            return Err("Syslog host is required".to_string());
+        }
+        if self.port == 0 {
+            return Err("Syslog port must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyslogStatus {
+    pub enabled: bool,
+    pub connected: bool,
+    pub sent: u64,
+    /// Queue đầy hoặc gửi lỗi sau reconnect
+    pub dropped: u64,
+    pub queued: u64,
+    pub last_error: Option<String>,
+    pub last_sent: Option<i64>,
+}
+
+static CONFIG: Lazy<RwLock<SyslogConfig>> = Lazy::new(|| RwLock::new(load_config()));
+/// Tăng mỗi lần đổi config → worker mở lại connection
+static CONFIG_GENERATION: AtomicU64 = AtomicU64::new(0);
+static SENDER: Lazy<Mutex<Option<SyncSender<SecurityEvent>>>> = Lazy::new(|| Mutex::new(None));
+static STATUS: Lazy<Mutex<SyslogStatus>> = Lazy::new(|| Mutex::new(SyslogStatus::default()));
+static QUEUED: AtomicU64 = AtomicU64::new(0);
+/// Config đang theo managed settings từ cloud → không sửa local được
+static MANAGED: AtomicBool = AtomicBool::new(false);
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Đưa event vào queue gửi syslog (no-op nếu tắt / dưới ngưỡng severity)
+pub fn publish(event: &SecurityEvent) {
+    {
+        let config = CONFIG.read();
+        if !config.enabled || syslog_severity(event) > config.max_severity {
+            return;
+        }
+    }
+    start_worker();
+
+    let sender = SENDER.lock().clone();
+    let Some(sender) = sender else { return };
+    // Tăng trước khi gửi: worker có thể nhận (và giảm) ngay sau try_send
+    QUEUED.fetch_add(1, Ordering::Relaxed);
+    if sender.try_send(event.clone()).is_err() {
+        QUEUED.fetch_sub(1, Ordering::Relaxed);
+        STATUS.lock().dropped += 1;
+    }
+}
+
+pub fn get_config() -> SyslogConfig {
+    CONFIG.read().clone()
+}
+
+pub fn set_config(config: SyslogConfig) -> Result<(), String> {
+    if MANAGED.load(Ordering::SeqCst) {
+        return Err("Syslog settings are managed by your organization".to_string());
+    }
+    check_config(&config)?;
+    replace_config(config);
+    save_config();
+    Ok(())
+}
+
+/// Config local + override từ managed settings (chỉ kiểm tra, chưa áp dụng)
+pub fn managed_config(overrides: &serde_json::Value) -> Result<SyslogConfig, String> {
+    let config = managed_settings::merge_overrides(&load_config(), overrides)?;
+    check_config(&config)?;
+    Ok(config)
+}
+
+/// Áp config từ managed settings (không ghi ra disk). None = quay lại config local
+pub fn set_managed(config: Option<SyslogConfig>) {
+    match config {
+        Some(config) => {
+            MANAGED.store(true, Ordering::SeqCst);
+            replace_config(config);
+        }
+        None if MANAGED.swap(false, Ordering::SeqCst) => replace_config(load_config()),
+        None => {}
+    }
+}
+
+fn check_config(config: &SyslogConfig) -> Result<(), String> {
+    config.validate()?;
+    if config.protocol == SyslogProtocol::Tls {
+        if let Some(path) = &config.tls_ca_file {
+            load_ca(path)?;
+        }
+    }
+    Ok(())
+}
+
+fn replace_config(config: SyslogConfig) {
+    *CONFIG.write() = config;
+    CONFIG_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+pub fn get_status() -> SyslogStatus {
+    let mut status = STATUS.lock().clone();
+    status.enabled = CONFIG.read().enabled;
+    status.queued = QUEUED.load(Ordering::Relaxed);
+    status
+}
+
+/// Gửi thử 1 message đồng bộ (kiểm tra host / port / TLS từ UI)
+pub fn send_test() -> Result<(), String> {
+    let config = CONFIG.read().clone();
+    config.validate()?;
+    if config.host.trim().is_empty() {
+        return Err("Syslog host is required".to_string());
+    }
+    let event = SecurityEvent::new(EventType::SystemStart, "One-Shield syslog test message");
+    let mut transport = Transport::connect(&config)?;
+    transport.send(&format_message(&event, &config))
+}
+
+// ============================================================================
+// WORKER
+// ============================================================================
+
+fn start_worker() {
+    let mut sender = SENDER.lock();
+    if sender.is_some() {
+        return;
+    }
+    let (tx, rx) = mpsc::sync_channel(QUEUE_CAPACITY);
+    *sender = Some(tx);
+    thread::spawn(move || worker_loop(rx));
+}
+
+fn worker_loop(rx: Receiver<SecurityEvent>) {
+    let mut transport: Option<Transport> = None;
+    let mut generation = CONFIG_GENERATION.load(Ordering::SeqCst);
+    let mut backoff = MIN_BACKOFF_SECS;
+
+    while let Ok(event) = rx.recv() {
+        QUEUED.fetch_sub(1, Ordering::Relaxed);
+        let config = CONFIG.read().clone();
+        if !config.enabled {
+            transport = None;
+            continue;
+        }
+        let current = CONFIG_GENERATION.load(Ordering::SeqCst);
+        if current != generation {
+            generation = current;
+            transport = None;
+            backoff = MIN_BACKOFF_SECS;
+        }
+
+        let message = format_message(&event, &config);
+        // Lần 1: connection hiện tại; lỗi → reconnect và thử lại 1 lần
+        let mut delivered = false;
+        for attempt in 0..2 {
+            if transport.is_none() {
+                match Transport::connect(&config) {
+                    Ok(t) => {
+                        transport = Some(t);
+                        backoff = MIN_BACKOFF_SECS;
+                        STATUS.lock().connected = true;
+                    }
+                    Err(e) => {
+                        set_error(e);
+                        // Chờ trước khi thử lại; event trong lúc này vẫn xếp hàng
+                        thread::sleep(Duration::from_secs(backoff));
+                        backoff = (backoff * 2).min(MAX_BACKOFF_SECS);
+                        continue;
+                    }
+                }
+            }
+            if let Some(t) = transport.as_mut() {
+                match t.send(&message) {
+                    Ok(()) => {
+                        delivered = true;
+                        break;
+                    }
+                    Err(e) => {
+                        transport = None;
+                        set_error(e);
+                        if attempt == 0 {
+                            log::warn!("Syslog connection lost, reconnecting");
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut status = STATUS.lock();
+        if delivered {
+            status.sent += 1;
+            status.last_sent = Some(Utc::now().timestamp());
+        } else {
+            status.dropped += 1;
+        }
+    }
+}
+
+fn set_error(error: String) {
+    let mut status = STATUS.lock();
+    status.connected = false;
+    status.last_error = Some(error);
+}
+
+// ============================================================================
+// TRANSPORT
+// ============================================================================
+
+enum Transport {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+    Tls(native_tls::TlsStream<TcpStream>),
+}
+
+impl Transport {
+    fn connect(config: &SyslogConfig) -> Result<Self, String> {
+        let host = config.host.trim();
+        let addr = (host, config.port)
+            .to_socket_addrs()
+            .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+            .next()
+            .ok_or_else(|| format!("No address for {}", host))?;
+
+        match config.protocol {
+            SyslogProtocol::Udp => {
+                let bind = if addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+                let socket = UdpSocket::bind(bind).map_err(|e| e.to_string())?;
+                socket.connect(addr).map_err(|e| format!("Syslog UDP connect failed: {}", e))?;
+                Ok(Transport::Udp(socket))
+            }
+            SyslogProtocol::Tcp => Ok(Transport::Tcp(tcp_connect(addr)?)),
+            SyslogProtocol::Tls => {
+                let mut builder = native_tls::TlsConnector::builder();
+                if let Some(path) = &config.tls_ca_file {
+                    builder.add_root_certificate(load_ca(path)?);
+                }
+                if config.tls_accept_invalid_certs {
+                    builder.danger_accept_invalid_certs(true);
+                }
+                let connector = builder.build().map_err(|e| e.to_string())?;
+                let stream = connector
+                    .connect(host, tcp_connect(addr)?)
+                    .map_err(|e| format!("Syslog TLS handshake failed: {}", e))?;
+                Ok(Transport::Tls(stream))
+            }
+        }
+    }
+
+    fn send(&mut self, message: &str) -> Result<(), String> {
+        let result = match self {
+            Transport::Udp(socket) => socket.send(message.as_bytes()).map(|_| ()),
+            // RFC 6587 / 5425: "MSG-LEN SP SYSLOG-MSG"
+            Transport::Tcp(stream) => write_framed(stream, message),
+            Transport::Tls(stream) => write_framed(stream, message),
+        };
+        result.map_err(|e| format!("Syslog send failed: {}", e))
+    }
+}
+
+fn tcp_connect(addr: std::net::SocketAddr) -> Result<TcpStream, String> {
+    let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+        .map_err(|e| format!("Syslog connect to {} failed: {}", addr, e))?;
+    let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+    let _ = stream.set_nodelay(true);
+    Ok(stream)
+}
+
+fn write_framed<W: Write>(writer: &mut W, message: &str) -> std::io::Result<()> {
+    writer.write_all(format!("{} {}", message.len(), message).as_bytes())?;
+    writer.flush()
+}
+
+fn load_ca(path: &str) -> Result<native_tls::Certificate, String> {
+    let pem = fs::read(path).map_err(|e| format!("Failed to read CA file {}: {}", path, e))?;
+    native_tls::Certificate::from_pem(&pem).map_err(|e| format!("Invalid CA file {}: {}", path, e))
+}
+
+// ============================================================================
+// FORMAT
+// ============================================================================
+
+/// Facility name → code (RFC 5424 section 6.2.1)
+pub fn facility_code(name: &str) -> Option<u8> {
+    let code = match name.trim().to_lowercase().as_str() {
+        "kern" => 0,
+        "user" => 1,
+        "mail" => 2,
+        "daemon" => 3,
+        "auth" => 4,
+        "syslog" => 5,
+        "authpriv" => 10,
+        "security" | "audit" => 13,
+        "local0" => 16,
+        "local1" => 17,
+        "local2" => 18,
+        "local3" => 19,
+        "local4" => 20,
+        "local5" => 21,
+        "local6" => 22,
+        "local7" => 23,
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Severity của event → syslog severity (2 crit, 3 err, 4 warning, 5 notice, 6 info)
+pub fn syslog_severity(event: &SecurityEvent) -> u8 {
+    if let Some(severity) = &event.severity {
+        return match severity {
+            Severity::Critical => 2,
+            Severity::High => 3,
+            Severity::Medium => 4,
+            Severity::Low => 5,
+        };
+    }
+    match event.event_type {
+        EventType::ThreatDetected | EventType::UserOverride => 4,
+        EventType::PolicyDecision
+        | EventType::ActionCreated
+        | EventType::ActionExecuted
+        | EventType::ActionReverted
+        | EventType::UserApproved
+        | EventType::UserDenied
+        | EventType::ActionExpired
+        | EventType::WhitelistAdded
+        | EventType::WhitelistRemoved
+        | EventType::PublisherAllowlistChanged => 5,
+        EventType::SystemStart
+        | EventType::SystemStop
+        | EventType::ModelEvent
+        | EventType::BaselineEvent
+        | EventType::RuleFeedback => 6,
+    }
+}
+
+/// `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID - MSG`
+pub fn format_message(event: &SecurityEvent, config: &SyslogConfig) -> String {
+    let facility = facility_code(&config.facility).unwrap_or(20);
+    let pri = facility as u32 * 8 + syslog_severity(event) as u32;
+    format!(
+        "<{}>1 {} {} {} {} {} - {}",
+        pri,
+        event.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+        header_field(&exporter::local_hostname(), 255),
+        APP_NAME,
+        std::process::id(),
+        header_field(event.event_type.as_str(), 32),
+        exporter::format_event(event, config.format)
+    )
+}
+
+/// Header field RFC 5424: PRINTUSASCII, không space, giới hạn độ dài, rỗng → "-"
+fn header_field(value: &str, max_len: usize) -> String {
+    let cleaned: String = value.chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max_len)
+        .collect();
+    if cleaned.is_empty() { "-".to_string() } else { cleaned }
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn config_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(CONFIG_FILE)
+}
+
+fn load_config() -> SyslogConfig {
+    fs::read_to_string(config_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_config() {
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*CONFIG.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::threat::ThreatClass;
+    use super::super::event::ProcessInfo;
+
+    #[test]
+    fn test_format_rfc5424() {
+        let config = SyslogConfig { facility: "local4".to_string(), ..Default::default() };
+        let event = SecurityEvent::policy_decision(
+            ProcessInfo::new(42, "evil.exe"),
+            ThreatClass::Malicious,
+            crate::logic::policy::Decision::AutoBlock,
+            Severity::Critical,
+        );
+        let message = format_message(&event, &config);
+        // local4 (20) * 8 + crit (2)
+        assert!(message.starts_with("<162>1 "));
+        let fields: Vec<&str> = message.splitn(8, ' ').collect();
+        assert_eq!(fields[3], APP_NAME);
+        assert_eq!(fields[5], "policy_decision");
+        assert_eq!(fields[6], "-");
+        assert!(serde_json::from_str::<SecurityEvent>(fields[7]).is_ok());
+
+        let cef = SyslogConfig { format: EventFormat::Cef, ..config.clone() };
+        assert!(format_message(&event, &cef).splitn(8, ' ').nth(7).unwrap().starts_with("CEF:0|"));
+
+        let start = SecurityEvent::system_start("1.0");
+        assert_eq!(syslog_severity(&start), 6);
+        assert_eq!(facility_code("AUTH"), Some(4));
+        assert_eq!(facility_code("local9"), None);
+        assert_eq!(header_field("my host", 255), "myhost");
+        assert_eq!(header_field("", 32), "-");
+
+        let mut framed = Vec::new();
+        write_framed(&mut framed, "<14>1 x").unwrap();
+        assert_eq!(framed, b"7 <14>1 x");
+    }
+}
@@ -68,6 +68,33 @@ pub fn classify_with_thresholds(
         reasons.push("Spike behavior detected".to_string());
     }
 
+    // Static PE analysis (packer, entropy, suspicious imports, ...)
+    if let Some(static_risk) = context.static_risk_score.filter(|s| *s >= 0.3) {
+        context_score += static_risk * 0.5;
+        reasons.push(format!("Static analysis risk: {:.2}", static_risk));
+    }
+
+    // Binary unsigned hiếm trong fleet (chỉ vài endpoint từng chạy)
+    if context.rare_in_fleet {
+        context_score += 0.3;
+        reasons.push("RareInFleet: unsigned binary seen on very few endpoints".to_string());
+    }
+
+    // Unsigned binary với quyền cao: SYSTEM nặng hơn nhiều so với user thường
+    if context.is_unsigned_system() {
+        context_score += 0.5;
+        reasons.push(format!(
+            "Unsigned binary running as SYSTEM{}",
+            context.user.as_deref().map(|u| format!(" ({})", u)).unwrap_or_default()
+        ));
+    } else if context.is_unsigned_elevated() {
+        context_score += 0.25;
+        reasons.push(format!(
+            "Unsigned binary running elevated{}",
+            context.user.as_deref().map(|u| format!(" ({})", u)).unwrap_or_default()
+        ));
+    }
+
     // Tags influence
     for tag in &context.tags {
         match tag.as_str() {
@@ -90,6 +117,9 @@ pub fn classify_with_thresholds(
     if context.is_new_process && !context.is_whitelisted {
         final_score *= thresholds.new_process_multiplier;
     }
+    if context.is_unsigned_system() && !context.is_whitelisted {
+        final_score *= thresholds.system_unsigned_multiplier;
+    }
 
     // Clamp to 0-1
     final_score = final_score.clamp(0.0, 1.0);
@@ -158,6 +188,7 @@ pub fn classify_simple(anomaly_score: f32) -> ThreatClass {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::logic::process_intel::IntegrityLevel;
 
     #[test]
     fn test_benign_classification() {
@@ -213,6 +244,47 @@ mod tests {
         assert_eq!(result.threat_class, ThreatClass::Suspicious);
     }
 
+    #[test]
+    fn test_rare_in_fleet_boosts_score() {
+        let anomaly = AnomalyScore {
+            score: 0.6,
+            confidence: 0.8,
+            method: "onnx".to_string(),
+        };
+        let baseline = BaselineDiff::default();
+
+        let common = classify(&anomaly, &baseline, &ThreatContext::default());
+        let rare = classify(&anomaly, &baseline, &ThreatContext::default().with_rare_in_fleet(true));
+        assert!(rare.score_breakdown.final_score > common.score_breakdown.final_score);
+        assert!(rare.reasons.iter().any(|r| r.starts_with("RareInFleet")));
+    }
+
+    #[test]
+    fn test_unsigned_system_more_severe_than_user() {
+        let anomaly = AnomalyScore {
+            score: 0.6,
+            confidence: 0.9,
+            method: "onnx".to_string(),
+        };
+        let baseline = BaselineDiff::default();
+        let unsigned = ThreatContext::default().with_unsigned(true);
+
+        let standard = classify(&anomaly, &baseline, &unsigned.clone()
+            .with_token(Some("CORP\\alice".to_string()), false, Some(IntegrityLevel::Medium)));
+        let elevated = classify(&anomaly, &baseline, &unsigned.clone()
+            .with_token(Some("CORP\\alice".to_string()), true, Some(IntegrityLevel::High)));
+        let system = classify(&anomaly, &baseline, &unsigned.clone()
+            .with_token(Some("NT AUTHORITY\\SYSTEM".to_string()), true, Some(IntegrityLevel::System)));
+        let signed_system = classify(&anomaly, &baseline, &ThreatContext::default()
+            .with_token(None, true, Some(IntegrityLevel::System)));
+
+        assert!(elevated.score_breakdown.final_score > standard.score_breakdown.final_score);
+        assert!(system.score_breakdown.final_score > elevated.score_breakdown.final_score);
+        assert!(system.reasons.iter().any(|r| r.starts_with("Unsigned binary running as SYSTEM")));
+        let signed_user = classify(&anomaly, &baseline, &ThreatContext::default());
+        assert_eq!(signed_system.score_breakdown.final_score, signed_user.score_breakdown.final_score);
+    }
+
     #[test]
     fn test_whitelisted_reduces_score() {
         let anomaly = AnomalyScore {
@@ -5,6 +5,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::logic::process_intel::IntegrityLevel;
+
 // ============================================================================
 // THREAT CONTEXT
 // ============================================================================
@@ -28,6 +30,24 @@ pub struct ThreatContext {
     pub process_name: Option<String>,
     /// Process ID
     pub pid: Option<u32>,
+    /// Static PE risk score (0.0 - 1.0) - có trước khi process có runtime history
+    #[serde(default)]
+    pub static_risk_score: Option<f32>,
+    /// RareInFleet: binary unsigned chỉ chạy trên vài endpoint trong org
+    #[serde(default)]
+    pub rare_in_fleet: bool,
+    /// User sở hữu process (DOMAIN\user)
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Token elevated / integrity High trở lên
+    #[serde(default)]
+    pub is_admin_session: bool,
+    /// Integrity level của process token (None = không đọc được)
+    #[serde(default)]
+    pub integrity_level: Option<IntegrityLevel>,
+    /// Binary không có chữ ký hợp lệ (unsigned / invalid / revoked)
+    #[serde(default)]
+    pub is_unsigned: bool,
 }
 
 impl ThreatContext {
@@ -71,10 +91,51 @@ impl ThreatContext {
         self
     }
 
+    /// Add static PE analysis score
+    pub fn with_static_risk(mut self, score: Option<f32>) -> Self {
+        self.static_risk_score = score;
+        self
+    }
+
+    /// Mark as rare in fleet (low prevalence, unsigned)
+    pub fn with_rare_in_fleet(mut self, rare: bool) -> Self {
+        self.rare_in_fleet = rare;
+        self
+    }
+
+    /// Add owning user, admin session and integrity level
+    pub fn with_token(mut self, user: Option<String>, is_admin_session: bool, integrity: Option<IntegrityLevel>) -> Self {
+        self.user = user;
+        self.is_admin_session = is_admin_session;
+        self.integrity_level = integrity;
+        self
+    }
+
+    /// Mark binary as unsigned (no valid signature)
+    pub fn with_unsigned(mut self, unsigned: bool) -> Self {
+        self.is_unsigned = unsigned;
+        self
+    }
+
+    /// Unsigned binary chạy dưới SYSTEM
+    pub fn is_unsigned_system(&self) -> bool {
+        self.is_unsigned && self.integrity_level >= Some(IntegrityLevel::System)
+    }
+
+    /// Unsigned binary chạy elevated (admin) nhưng không phải SYSTEM
+    pub fn is_unsigned_elevated(&self) -> bool {
+        self.is_unsigned
+            && !self.is_unsigned_system()
+            && (self.is_admin_session || self.integrity_level >= Some(IntegrityLevel::High))
+    }
+
     /// Check if context has suspicious indicators
     pub fn has_suspicious_indicators(&self) -> bool {
         self.is_new_process
+            || self.rare_in_fleet
+            || self.is_unsigned_system()
             || self.child_process_count > 5
+            || self.static_risk_score.map(|s| s >= 0.5).unwrap_or(false)
             || self.tags.iter().any(|t| {
                 t.contains("ANOMALY") || t.contains("BURST") || t.contains("CRYPTO")
             })
@@ -115,5 +176,13 @@ mod tests {
         let many_children = ThreatContext::default()
             .with_children(10);
         assert!(many_children.has_suspicious_indicators());
+
+        let packed = ThreatContext::default()
+            .with_static_risk(Some(0.7));
+        assert!(packed.has_suspicious_indicators());
+
+        let rare = ThreatContext::default()
+            .with_rare_in_fleet(true);
+        assert!(rare.has_suspicious_indicators());
     }
 }
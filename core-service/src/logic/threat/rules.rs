@@ -45,6 +45,9 @@ pub const NEW_PROCESS_MULTIPLIER: f32 = 1.1;
 /// Score reduction for whitelisted processes
 pub const WHITELIST_REDUCTION: f32 = 0.5;
 
+/// Score multiplier for unsigned binaries running as SYSTEM
+pub const SYSTEM_UNSIGNED_MULTIPLIER: f32 = 1.3;
+
 // ============================================================================
 // NETWORK THRESHOLDS
 // ============================================================================
@@ -69,6 +72,9 @@ pub struct ClassificationThresholds {
     pub spike_multiplier: f32,
     /// Multiplier for new process
     pub new_process_multiplier: f32,
+    /// Multiplier for unsigned binary running as SYSTEM
+    #[serde(default = "default_system_unsigned_multiplier")]
+    pub system_unsigned_multiplier: f32,
     /// Threshold for high network activity (bytes/min)
     pub high_network_threshold: u64,
 }
@@ -81,11 +87,16 @@ impl Default for ClassificationThresholds {
             malicious_confidence_min: MALICIOUS_CONFIDENCE_MIN,
             spike_multiplier: SPIKE_MULTIPLIER,
             new_process_multiplier: NEW_PROCESS_MULTIPLIER,
+            system_unsigned_multiplier: SYSTEM_UNSIGNED_MULTIPLIER,
             high_network_threshold: HIGH_NETWORK_THRESHOLD,
         }
     }
 }
 
+fn default_system_unsigned_multiplier() -> f32 {
+    SYSTEM_UNSIGNED_MULTIPLIER
+}
+
 impl ClassificationThresholds {
     /// High sensitivity - lower thresholds, more alerts
     pub fn high_sensitivity() -> Self {
@@ -0,0 +1,382 @@
+//! Action Guard Whitelist
+//!
+//! Mục đích: Danh sách process không bao giờ bị Action Guard can thiệp
+//!
+//! Entry types:
+//! - `Name`      - tên process (khớp chính xác, không phân biệt hoa thường)
+//! - `Path`      - đường dẫn đầy đủ của executable
+//! - `Sha256`    - hash của executable
+//! - `Publisher` - publisher Authenticode (chữ ký phải hợp lệ)
+//!
+//! Entries được lưu ra disk; entries từ cloud policy (`source = Cloud`) được
+//! thay thế toàn bộ mỗi lần sync, entries local không bị ảnh hưởng.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use parking_lot::RwLock;
+use once_cell::sync::Lazy;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::process_intel::{signature, types::SignatureStatus};
+
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+const WHITELIST_FILE: &str = "whitelist.json";
+
+/// System processes luôn được whitelist. Khi biết path, image phải nằm trong
+/// `%SystemRoot%\System32` (không tin tên file đặt ở chỗ khác)
+const SYSTEM_PROCESSES: &[&str] = &[
+    "system", "smss.exe", "csrss.exe", "wininit.exe", "winlogon.exe",
+    "services.exe", "lsass.exe", "svchost.exe", "dwm.exe",
+    "explorer.exe", "taskhostw.exe", "runtimebroker.exe",
+];
+
+/// System processes nằm trực tiếp trong `%SystemRoot%`
+const SYSTEM_ROOT_PROCESSES: &[&str] = &["explorer.exe"];
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WhitelistKind {
+    Name,
+    Path,
+    Sha256,
+    Publisher,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WhitelistSource {
+    Local,
+    Cloud,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    pub id: String,
+    pub kind: WhitelistKind,
+    /// Giá trị đã normalize (lowercase)
+    pub value: String,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default = "default_source")]
+    pub source: WhitelistSource,
+    pub added_at: i64,
+}
+
+fn default_source() -> WhitelistSource {
+    WhitelistSource::Local
+}
+
+impl WhitelistEntry {
+    pub fn new(kind: WhitelistKind, value: &str, comment: Option<String>, source: WhitelistSource) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            value: normalize(kind, value),
+            comment,
+            source,
+            added_at: Utc::now().timestamp(),
+        }
+    }
+}
+
+/// Process được kiểm tra
+pub struct Target<'a> {
+    pub name: &'a str,
+    pub exe_path: Option<&'a Path>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+static ENTRIES: Lazy<RwLock<Vec<WhitelistEntry>>> = Lazy::new(|| RwLock::new(load()));
+
+// ============================================================================
+// PUBLIC API
+// ============================================================================
+
+/// Thêm entry (bỏ qua nếu đã tồn tại). Trả về entry đang có hiệu lực.
+pub fn add(kind: WhitelistKind, value: &str, comment: Option<String>) -> Result<WhitelistEntry, String> {
+    validate(kind, value)?;
+    let entry = WhitelistEntry::new(kind, value, comment, WhitelistSource::Local);
+
+    {
+        let mut entries = ENTRIES.write();
+        if let Some(existing) = entries.iter().find(|e| e.kind == entry.kind && e.value == entry.value) {
+            return Ok(existing.clone());
+        }
+        entries.push(entry.clone());
+    }
+    save();
+
+    log::info!("Added to whitelist: {:?} {}", entry.kind, entry.value);
+    Ok(entry)
+}
+
+/// Xóa entry theo id hoặc value. Entry từ cloud chỉ bị xóa khi cloud sync lại.
+pub fn remove(id_or_value: &str) -> bool {
+    let needle = id_or_value.to_lowercase();
+    let removed = {
+        let mut entries = ENTRIES.write();
+        let before = entries.len();
+        entries.retain(|e| {
+            e.source == WhitelistSource::Cloud || (e.id != id_or_value && e.value != needle)
+        });
+        entries.len() != before
+    };
+    if removed {
+        save();
+    }
+    removed
+}
+
+/// Tất cả entries
+pub fn list() -> Vec<WhitelistEntry> {
+    ENTRIES.read().clone()
+}
+
+pub fn count() -> usize {
+    ENTRIES.read().len()
+}
+
+/// Kiểm tra process có được whitelist không
+pub fn is_whitelisted(target: &Target) -> bool {
+    let name = base_name(target.name);
+    if is_system_process(&name, target.exe_path) {
+        return true;
+    }
+
+    let entries = ENTRIES.read();
+    if entries.is_empty() {
+        return false;
+    }
+
+    if entries.iter().any(|e| e.kind == WhitelistKind::Name && e.value == name) {
+        return true;
+    }
+
+    let path = match target.exe_path {
+        Some(p) => p,
+        None => return false,
+    };
+
+    let path_norm = normalize(WhitelistKind::Path, &path.to_string_lossy());
+    if entries.iter().any(|e| e.kind == WhitelistKind::Path && e.value == path_norm) {
+        return true;
+    }
+
+    // Hash / publisher tốn kém hơn - chỉ tính khi có entry tương ứng
+    if entries.iter().any(|e| e.kind == WhitelistKind::Sha256) {
+        if let Some(hash) = file_hash(path) {
+            if entries.iter().any(|e| e.kind == WhitelistKind::Sha256 && e.value == hash) {
+                return true;
+            }
+        }
+    }
+
+    if entries.iter().any(|e| e.kind == WhitelistKind::Publisher) {
+        let publisher = match signature::verify_signature(path).status {
+            SignatureStatus::Trusted { publisher, .. } | SignatureStatus::SignedUntrusted { publisher } => {
+                Some(publisher.to_lowercase())
+            }
+            _ => None,
+        };
+        if let Some(publisher) = publisher {
+            if entries.iter().any(|e| e.kind == WhitelistKind::Publisher && e.value == publisher) {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+/// Import entries từ file JSON (`[WhitelistEntry]`). Trả về số entry mới.
+pub fn import(path: &Path) -> Result<usize, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let imported: Vec<WhitelistEntry> = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+
+    let mut added = 0;
+    {
+        let mut entries = ENTRIES.write();
+        for item in imported {
+            if validate(item.kind, &item.value).is_err() {
+                log::warn!("Skipping invalid whitelist entry: {:?} {}", item.kind, item.value);
+                continue;
+            }
+            let entry = WhitelistEntry::new(item.kind, &item.value, item.comment, WhitelistSource::Local);
+            if !entries.iter().any(|e| e.kind == entry.kind && e.value == entry.value) {
+                entries.push(entry);
+                added += 1;
+            }
+        }
+    }
+    save();
+
+    log::info!("Imported {} whitelist entries from {}", added, path.display());
+    Ok(added)
+}
+
+/// Export local entries ra file JSON
+pub fn export(path: &Path) -> Result<usize, String> {
+    let local: Vec<WhitelistEntry> = ENTRIES.read().iter()
+        .filter(|e| e.source == WhitelistSource::Local)
+        .cloned()
+        .collect();
+    let json = serde_json::to_string_pretty(&local).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| e.to_string())?;
+    Ok(local.len())
+}
+
+/// Thay toàn bộ entries từ cloud policy (`config.whitelist`)
+pub fn apply_cloud(items: &[serde_json::Value]) -> usize {
+    let cloud: Vec<WhitelistEntry> = items.iter()
+        .filter_map(|v| {
+            let kind: WhitelistKind = serde_json::from_value(v.get("kind")?.clone()).ok()?;
+            let value = v.get("value")?.as_str()?;
+            validate(kind, value).ok()?;
+            let comment = v.get("comment").and_then(|c| c.as_str()).map(|s| s.to_string());
+            Some(WhitelistEntry::new(kind, value, comment, WhitelistSource::Cloud))
+        })
+        .collect();
+    let count = cloud.len();
+
+    {
+        let mut entries = ENTRIES.write();
+        entries.retain(|e| e.source == WhitelistSource::Local);
+        entries.extend(cloud);
+    }
+    save();
+
+    log::info!("Applied {} whitelist entries from cloud policy", count);
+    count
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+fn normalize(kind: WhitelistKind, value: &str) -> String {
+    let value = value.trim();
+    match kind {
+        WhitelistKind::Name => base_name(value),
+        WhitelistKind::Path => value.replace('/', "\\").to_lowercase(),
+        WhitelistKind::Sha256 | WhitelistKind::Publisher => value.to_lowercase(),
+    }
+}
+
+fn validate(kind: WhitelistKind, value: &str) -> Result<(), String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("Whitelist value is empty".to_string());
+    }
+    if kind == WhitelistKind::Sha256
+        && (value.len() != 64 || !value.chars().all(|c| c.is_ascii_hexdigit()))
+    {
+        return Err(format!("Invalid SHA-256: {}", value));
+    }
+    Ok(())
+}
+
+/// Tên thuộc `SYSTEM_PROCESSES` và (nếu biết path) image nằm đúng thư mục hệ thống
+fn is_system_process(name: &str, exe_path: Option<&Path>) -> bool {
+    if !SYSTEM_PROCESSES.contains(&name) {
+        return false;
+    }
+    let path = match exe_path {
+        Some(p) => normalize(WhitelistKind::Path, &p.to_string_lossy()),
+        None => return true,
+    };
+    let dir = path.rsplit_once('\\').map(|(dir, _)| dir).unwrap_or("");
+
+    let root = std::env::var("SystemRoot").unwrap_or_else(|_| "C:\\Windows".to_string());
+    let expected = if SYSTEM_ROOT_PROCESSES.contains(&name) {
+        root
+    } else {
+        format!("{}\\System32", root)
+    };
+    dir == normalize(WhitelistKind::Path, &expected)
+}
+
+fn base_name(name: &str) -> String {
+    name.rsplit(['\\', '/']).next().unwrap_or(name).to_lowercase()
+}
+
+/// SHA-256 của file (qua hashing service, cache theo path + size + mtime)
+pub(crate) fn file_hash(path: &Path) -> Option<String> {
+    super::process_intel::hashing::sha256_file(path)
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+
+fn whitelist_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("OneShield")
+        .join(WHITELIST_FILE)
+}
+
+fn load() -> Vec<WhitelistEntry> {
+    fs::read_to_string(whitelist_path())
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save() {
+    let path = whitelist_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(&*ENTRIES.read()) {
+        let _ = fs::write(path, json);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_process_exact_match() {
+        assert!(is_whitelisted(&Target { name: "System", exe_path: None }));
+        assert!(is_whitelisted(&Target { name: "C:\\Windows\\System32\\lsass.exe", exe_path: None }));
+        // Không còn substring match
+        assert!(!is_whitelisted(&Target { name: "systemhack.exe", exe_path: None }));
+    }
+
+    #[test]
+    fn test_system_process_requires_system_dir() {
+        let root = std::env::var("SystemRoot").unwrap_or_else(|_| "C:\\Windows".to_string());
+        let genuine = PathBuf::from(format!("{}\\System32\\svchost.exe", root));
+        assert!(is_whitelisted(&Target { name: "svchost.exe", exe_path: Some(&genuine) }));
+
+        let impostor = PathBuf::from("C:\\Users\\Public\\svchost.exe");
+        assert!(!is_whitelisted(&Target { name: "svchost.exe", exe_path: Some(&impostor) }));
+
+        let explorer = PathBuf::from(format!("{}\\explorer.exe", root));
+        assert!(is_whitelisted(&Target { name: "explorer.exe", exe_path: Some(&explorer) }));
+    }
+
+    #[test]
+    fn test_normalize_and_validate() {
+        assert_eq!(normalize(WhitelistKind::Name, "C:\\Tools\\Foo.EXE"), "foo.exe");
+        assert_eq!(normalize(WhitelistKind::Path, "C:/Tools/Foo.exe"), "c:\\tools\\foo.exe");
+        assert!(validate(WhitelistKind::Sha256, "abc").is_err());
+        assert!(validate(WhitelistKind::Sha256, &"a".repeat(64)).is_ok());
+    }
+}
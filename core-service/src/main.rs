@@ -72,6 +72,10 @@ fn main() {
             log::info!("Event system initialized");
 
             // Initialize telemetry (security logging)
+            logic::telemetry::elastic::start();
+            logic::telemetry::eventlog::init();
+            logic::telemetry::metrics::start();
+            logic::telemetry::otel::start();
             if let Err(e) = logic::telemetry::init(None) {
                 log::warn!("Telemetry init failed: {} - events will not be recorded", e);
             } else {
@@ -81,6 +85,24 @@ fn main() {
             // Start Analysis Engine Loop (Bridges Collector -> Incident)
             logic::analysis_loop::start();
 
+            // Behavioral rules từ thư mục rules/ (hot-reload)
+            logic::behavioral_sigs::rules_dir::start();
+
+            // Persistence keys (Run / Services / IFEO) → persistence monitor
+            logic::behavioral_sigs::registry_watcher::start();
+
+            // Auto-revert time-limited response actions
+            logic::response::expiry::start();
+
+            // Ransomware canary files
+            logic::advanced_detection::canary::start();
+
+            // abuse.ch / ET threat feeds (store local + sync định kỳ)
+            logic::external_intel::threat_feed::start();
+            logic::external_intel::misp::start();
+            logic::external_intel::taxii::start();
+            logic::external_intel::otx::start();
+
             // Start Cloud Sync Loop (Phase 10)
             logic::cloud_sync::init();
             let sync_config = logic::cloud_sync::SyncConfig::default();
@@ -95,7 +117,9 @@ fn main() {
                     .build()
                     .expect("Failed to create tokio runtime for cloud sync");
 
-                rt.block_on(async {
+                // LocalSet: kênh lệnh WebSocket chạy song song trên cùng thread (spawn_local)
+                let local = tokio::task::LocalSet::new();
+                local.block_on(&rt, async {
                     logic::cloud_sync::start_sync_loop(sync_config).await;
                 });
             });
@@ -150,15 +174,108 @@ fn main() {
 
             // Action Guard Commands (Phase III)
             commands::get_action_guard_status,
+            commands::get_action_guard_config,
+            commands::set_action_guard_config,
+            commands::get_escalations,
             commands::get_pending_actions,
             commands::approve_action,
             commands::cancel_action,
+            commands::undo_action,
+            commands::contain_browser_child,
+            commands::get_blocked_hashes,
+            commands::unblock_hash,
             commands::get_action_history,
             commands::kill_process,
+            commands::kill_process_tree,
             commands::suspend_process,
+            commands::resume_process,
+            commands::block_process_network,
+            commands::unblock_process_network,
+            commands::get_scheduled_reverts,
+            commands::cancel_scheduled_revert,
+            commands::isolate_host,
+            commands::release_host,
+            commands::get_host_isolation_status,
+            commands::remove_persistence,
+            commands::restore_persistence,
+            commands::get_persistence_snapshots,
+            commands::get_persistence_alerts,
+            commands::get_uac_bypass_alerts,
+            commands::get_wmi_subscriptions,
+            commands::get_bits_abuse_alerts,
+            commands::get_kerberoast_alerts,
+            commands::list_behavioral_rules,
+            commands::create_behavioral_rule,
+            commands::update_behavioral_rule,
+            commands::delete_behavioral_rule,
+            commands::set_behavioral_rule_enabled,
+            commands::get_behavioral_rule_matches,
+            commands::get_behavioral_rule_stats,
+            commands::get_noisiest_rules,
+            commands::submit_rule_feedback,
+            commands::get_rule_files_status,
+            commands::reload_rule_files,
+            commands::test_rule,
+            commands::list_rule_suppressions,
+            commands::add_rule_suppression,
+            commands::remove_rule_suppression,
+            commands::get_rule_suppression_audit,
+            commands::get_network_collector_stats,
+            commands::get_url_reputation_alerts,
+            commands::get_url_reputation_config,
+            commands::set_url_reputation_config,
+            commands::get_geoip_status,
+            commands::import_geoip_database,
+            commands::set_geoip_config,
+            commands::lookup_geoip,
+            commands::get_geoip_alerts,
+            commands::get_blocked_destinations,
+            commands::unblock_destination,
+            commands::get_playbooks,
+            commands::save_playbook,
+            commands::delete_playbook,
+            commands::run_playbook,
+            commands::get_playbook_runs,
+            commands::set_dry_run_mode,
+            commands::get_dry_run_mode,
             commands::add_to_whitelist,
             commands::remove_from_whitelist,
             commands::get_whitelist,
+            commands::add_whitelist_entry,
+            commands::get_whitelist_entries,
+            commands::import_whitelist,
+            commands::export_whitelist,
+            commands::get_abused_signers,
+            commands::add_abused_signer,
+            commands::remove_abused_signer,
+            commands::get_trusted_publishers,
+            commands::add_trusted_publisher,
+            commands::remove_trusted_publisher,
+            commands::get_threat_feed_stats,
+            commands::sync_threat_feeds,
+            commands::get_misp_status,
+            commands::sync_misp,
+            commands::get_taxii_status,
+            commands::sync_taxii,
+            commands::get_otx_status,
+            commands::sync_otx,
+            commands::set_otx_api_key,
+            commands::import_iocs,
+            commands::list_local_ioc_sources,
+            commands::remove_local_iocs,
+            commands::import_intel_bundle,
+            commands::get_intel_bundle_status,
+            commands::get_intel_matches,
+            commands::get_mitre_coverage,
+            commands::get_mitre_dataset_info,
+            commands::update_mitre_dataset,
+            commands::get_vt_queue_status,
+            commands::set_vt_api_key,
+            commands::submit_vt_hash,
+            commands::get_execution_artifacts,
+            commands::set_execution_artifact_enrichment,
+            commands::get_process_ancestry,
+            commands::get_reputation_report,
 
             // ONNX AI Commands (Phase IV)
             commands::load_onnx_model,
@@ -183,16 +300,58 @@ fn main() {
 
             // Telemetry Commands (v0.6.1)
             commands::get_telemetry_stats,
+            commands::get_telemetry_retention_config,
+            commands::set_telemetry_retention_config,
+            commands::run_telemetry_cleanup,
+            commands::verify_log_integrity,
+            commands::get_syslog_config,
+            commands::set_syslog_config,
+            commands::get_syslog_status,
+            commands::test_syslog,
+            commands::get_elastic_config,
+            commands::set_elastic_config,
+            commands::get_elastic_status,
+            commands::flush_elastic_export,
+            commands::get_event_log_config,
+            commands::set_event_log_config,
+            commands::get_event_log_status,
+            commands::get_metrics_config,
+            commands::set_metrics_config,
+            commands::get_metrics_status,
+            commands::get_otel_config,
+            commands::set_otel_config,
+            commands::get_otel_status,
             commands::get_security_analytics,
             commands::get_security_log_files,
             commands::get_recent_security_events,
+            commands::query_security_events,
 
             // Engine Status (P2.1)
             commands::get_engine_status,
             commands::export_dataset,
+            commands::export_dataset_sampled,
+            commands::get_dataset_retention_config,
+            commands::set_dataset_retention_config,
+            commands::run_dataset_cleanup,
+            commands::get_dataset_report,
+            commands::get_scrub_config,
+            commands::set_scrub_config,
+            commands::reveal_scrubbed_value,
             commands::submit_user_feedback,
+            commands::list_label_candidates,
+            commands::relabel_dataset_record,
+            commands::bulk_label_dataset,
+            commands::get_labeling_progress,
             commands::get_incidents,
             commands::get_incident_detail,
+            commands::set_incident_verdict,
+            commands::add_incident_note,
+            commands::get_incident_kill_chain,
+            commands::list_incident_suppressions,
+            commands::add_incident_suppression,
+            commands::remove_incident_suppression,
+            commands::get_incident_suppression_audit,
+            commands::export_incident_report,
 
             // Enterprise Commands (Phase 7)
             enterprise::enterprise_login,
@@ -209,8 +368,15 @@ fn main() {
             enterprise::get_quarantined_files,
             enterprise::quarantine_file,
             enterprise::restore_quarantined_file,
+            enterprise::quarantine_directory,
+            enterprise::restore_quarantined_directory,
+            enterprise::get_quarantined_directories,
             enterprise::delete_quarantined_file,
             enterprise::get_quarantine_stats,
+            enterprise::get_usb_devices,
+            enterprise::get_usb_blocks,
+            enterprise::block_usb,
+            enterprise::unblock_usb,
             enterprise::get_webhooks,
             enterprise::add_webhook,
             enterprise::remove_webhook,
@@ -226,12 +392,23 @@ fn main() {
             advanced_detection::scan_script,
             advanced_detection::is_script_malicious,
             advanced_detection::get_amsi_stats,
+            advanced_detection::get_amsi_provider_status,
+            advanced_detection::get_script_block_status,
+            advanced_detection::scan_office_document,
+            advanced_detection::get_macro_alerts,
+            advanced_detection::get_evasion_alerts,
+            advanced_detection::scan_process_evasion,
+            advanced_detection::get_credential_theft_alerts,
+            advanced_detection::get_canary_status,
+            advanced_detection::replant_canaries,
             advanced_detection::analyze_process_injection,
             advanced_detection::get_injection_alerts,
             advanced_detection::get_injection_stats,
             advanced_detection::scan_memory,
             advanced_detection::scan_file_shellcode,
             advanced_detection::get_memory_stats,
+            advanced_detection::get_memory_scheduler_status,
+            advanced_detection::get_memory_scheduler_alerts,
             advanced_detection::get_threat_alerts,
             advanced_detection::get_advanced_detection_stats,
 
@@ -239,12 +416,26 @@ fn main() {
             advanced_detection::get_keylogger_alerts,
             advanced_detection::get_keylogger_stats,
             advanced_detection::check_process_keylogger,
+            advanced_detection::get_surveillance_alerts,
 
             // IAT Analysis Commands (Phase 9)
             advanced_detection::analyze_file_imports,
             advanced_detection::analyze_api_imports,
             advanced_detection::get_iat_stats,
             advanced_detection::clear_iat_cache,
+            advanced_detection::get_iat_profile,
+            advanced_detection::get_iat_tamper_alerts,
+            advanced_detection::analyze_pe_static,
+            advanced_detection::get_pe_static_result,
+            advanced_detection::emulate_shellcode_file,
+            advanced_detection::set_shellcode_emulation_enabled,
+            advanced_detection::get_shellcode_emulation_status,
+            advanced_detection::run_full_scan,
+            advanced_detection::cancel_full_scan,
+            advanced_detection::get_full_scan_status,
+            advanced_detection::get_full_scan_history,
+            advanced_detection::get_full_scan_config,
+            advanced_detection::set_full_scan_config,
 
             // Cloud Sync Commands (Phase 10)
             cloud_sync::get_cloud_sync_status,
@@ -253,6 +444,16 @@ fn main() {
             cloud_sync::update_cloud_sync_config,
             cloud_sync::queue_incident_for_sync,
             cloud_sync::get_pending_incidents_count,
+            cloud_sync::get_rule_pack_status,
+            cloud_sync::get_cloud_telemetry_config,
+            cloud_sync::set_cloud_telemetry_config,
+            cloud_sync::get_cloud_telemetry_status,
+            cloud_sync::get_dataset_upload_config,
+            cloud_sync::set_dataset_upload_config,
+            cloud_sync::get_dataset_upload_status,
+            cloud_sync::get_cloud_proxy_status,
+            cloud_sync::get_managed_settings_status,
+            cloud_sync::set_cloud_proxy_config,
 
             // Personal Auth Commands (Phase 13)
             cloud_sync::get_agent_mode,